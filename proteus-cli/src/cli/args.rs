@@ -1,11 +1,11 @@
 //! CLI argument definitions for `proteus-cli`.
 
-use clap::{Arg, ArgAction, Command};
+use clap::{Arg, ArgAction, ArgGroup, Command};
 
 fn with_input_arg(cmd: Command, required: bool) -> Command {
     cmd.arg(
         Arg::new("INPUT")
-            .help("Input .prot/.mka file, audio file, or directory of nested audio files")
+            .help("Input .prot/.mka file, audio file, directory of nested audio files, or - for standard input")
             .required(required)
             .index(1),
     )
@@ -52,6 +52,18 @@ fn build_bench_subcommand() -> Command {
         .subcommand(with_bench_common_args(
             Command::new("sweep").about("Run a sweep over multiple FFT sizes and exit"),
         ))
+        .subcommand(with_input_arg(
+            Command::new("container")
+                .about("Render a real .prot container offline and report per-stage timing")
+                .arg(
+                    Arg::new("bench-seconds")
+                        .long("bench-seconds")
+                        .value_name("SECONDS")
+                        .default_value("30.0")
+                        .help("Seconds of audio to render"),
+                ),
+            true,
+        ))
 }
 
 fn build_verify_subcommand() -> Command {
@@ -159,6 +171,105 @@ fn build_peaks_subcommand() -> Command {
         ))
 }
 
+fn build_export_batch_subcommand() -> Command {
+    with_input_arg(
+        Command::new("export-batch")
+            .about("Render seeds or combinations of a container to WAV files in parallel")
+            .arg(
+                Arg::new("out-dir")
+                    .long("out-dir")
+                    .value_name("DIR")
+                    .required(true)
+                    .help("Directory to write the rendered WAV files into"),
+            )
+            .arg(
+                Arg::new("seeds")
+                    .long("seeds")
+                    .value_name("LIST")
+                    .help("Comma-separated selection seeds to render"),
+            )
+            .arg(
+                Arg::new("variants")
+                    .long("variants")
+                    .value_name("COUNT")
+                    .help("Render COUNT unique combinations drawn at random"),
+            )
+            .arg(
+                Arg::new("all")
+                    .long("all")
+                    .action(ArgAction::SetTrue)
+                    .help("Render every combination of the container"),
+            )
+            .group(
+                ArgGroup::new("selection")
+                    .args(["seeds", "variants", "all"])
+                    .required(true),
+            )
+            .arg(
+                Arg::new("seed")
+                    .long("seed")
+                    .value_name("SEED")
+                    .default_value("0")
+                    .help("Seed used to draw --variants"),
+            )
+            .arg(
+                Arg::new("jobs")
+                    .long("jobs")
+                    .short('j')
+                    .value_name("COUNT")
+                    .help("Renders to run at once (default: available CPU cores)"),
+            )
+            .arg(
+                Arg::new("seconds")
+                    .long("seconds")
+                    .value_name("SECONDS")
+                    .help("Seconds to render per file (default: full length)"),
+            )
+            .arg(
+                Arg::new("format")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .value_parser(["f32", "s16"])
+                    .default_value("f32")
+                    .help("WAV sample format"),
+            )
+            .arg(
+                Arg::new("trim-silence")
+                    .long("trim-silence")
+                    .value_name("DBFS")
+                    .allow_negative_numbers(true)
+                    .help("Trim leading and trailing audio quieter than DBFS"),
+            )
+            .arg(
+                Arg::new("normalize-lufs")
+                    .long("normalize-lufs")
+                    .value_name("LUFS")
+                    .allow_negative_numbers(true)
+                    .help("Normalize each file to this integrated loudness"),
+            )
+            .arg(
+                Arg::new("normalize-peak")
+                    .long("normalize-peak")
+                    .value_name("DBFS")
+                    .allow_negative_numbers(true)
+                    .help("Normalize each file's peak to DBFS (a ceiling with --normalize-lufs)"),
+            )
+            .arg(
+                Arg::new("fade-in")
+                    .long("fade-in")
+                    .value_name("MS")
+                    .help("Fade-in length in milliseconds"),
+            )
+            .arg(
+                Arg::new("fade-out")
+                    .long("fade-out")
+                    .value_name("MS")
+                    .help("Fade-out length in milliseconds"),
+            ),
+        true,
+    )
+}
+
 fn build_init_subcommand() -> Command {
     Command::new("init")
         .about("Generate shuffle/effects JSON for a directory of nested audio files")
@@ -176,6 +287,23 @@ fn build_create_subcommand() -> Command {
         .subcommand(
             Command::new("effects-json").about("Print a default Vec<AudioEffect> JSON payload"),
         )
+        .subcommand(
+            Command::new("settings-schema")
+                .about("Print the JSON Schema for a play_settings.json version")
+                .arg(
+                    Arg::new("version")
+                        .long("version")
+                        .value_name("VERSION")
+                        .default_value("4")
+                        .help("Settings version: legacy, 1, 2, 3, or 4"),
+                )
+                .arg(
+                    Arg::new("out-dir")
+                        .long("out-dir")
+                        .value_name("DIR")
+                        .help("Write one schema file per version into DIR instead"),
+                ),
+        )
 }
 
 /// Build the CLI argument parser and command definitions.
@@ -217,6 +345,26 @@ pub fn build_cli() -> Command {
                 .default_value("20")
                 .help("Amount of audio (ms) to buffer before starting playback"),
         )
+        .arg(
+            Arg::new("output-buffer-frames")
+                .long("output-buffer-frames")
+                .value_name("FRAMES")
+                .value_parser(clap::value_parser!(u32))
+                .help("Open the output device in low-latency mode with this buffer size"),
+        )
+        .arg(
+            Arg::new("decode-threads")
+                .long("decode-threads")
+                .value_name("THREADS")
+                .value_parser(clap::value_parser!(usize))
+                .help("Decode threads kept alive across seeks and shuffles"),
+        )
+        .arg(
+            Arg::new("output")
+                .long("output")
+                .value_name("SPEC")
+                .help("Stream raw PCM instead of playing: pcm:[f32|s16:]<path|-> (- is stdout)"),
+        )
         .arg(
             Arg::new("start-sink-chunks")
                 .long("start-sink-chunks")
@@ -271,6 +419,12 @@ pub fn build_cli() -> Command {
                 .action(ArgAction::SetTrue)
                 .help("Read track durations metadata, then exit"),
         )
+        .arg(
+            Arg::new("validate")
+                .long("validate")
+                .action(ArgAction::SetTrue)
+                .help("Check the container for structural problems, then exit"),
+        )
         .arg(
             Arg::new("scan-durations")
                 .long("scan-durations")
@@ -303,7 +457,7 @@ pub fn build_cli() -> Command {
         .arg(Arg::new("debug").short('d').help("Show debug output"))
         .arg(
             Arg::new("INPUT")
-                .help("Input .prot/.mka file, audio file, or directory of nested audio files")
+                .help("Input .prot/.mka file, audio file, directory of nested audio files, or - for standard input")
                 .required(false)
                 .index(1),
         )
@@ -311,6 +465,7 @@ pub fn build_cli() -> Command {
         .subcommand(build_verify_subcommand())
         .subcommand(build_info_subcommand())
         .subcommand(build_peaks_subcommand())
+        .subcommand(build_export_batch_subcommand())
         .subcommand(build_init_subcommand())
         .subcommand(build_create_subcommand())
 }
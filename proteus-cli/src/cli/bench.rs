@@ -12,6 +12,7 @@ pub fn run_bench_subcommand(args: &ArgMatches) -> Result<i32> {
     match bench_cmd {
         "dsp" => run_single_bench(bench_args).map(|code| code.unwrap_or(0)),
         "sweep" => run_sweep_bench(bench_args).map(|code| code.unwrap_or(0)),
+        "container" => run_container_bench(bench_args).map(|code| code.unwrap_or(0)),
         _ => Ok(1),
     }
 }
@@ -134,6 +135,49 @@ fn run_sweep_bench(_args: &ArgMatches) -> Result<Option<i32>> {
     }
 }
 
+/// Render a real container offline and print per-stage timing.
+fn run_container_bench(_args: &ArgMatches) -> Result<Option<i32>> {
+    #[cfg(not(feature = "bench"))]
+    {
+        eprintln!("Benchmarking requires the `bench` feature.");
+        Ok(Some(1))
+    }
+    #[cfg(feature = "bench")]
+    {
+        let args = _args;
+        let input = args.get_one::<String>("INPUT").unwrap();
+        let seconds = args
+            .get_one::<String>("bench-seconds")
+            .unwrap()
+            .parse::<f64>()
+            .unwrap();
+
+        let result = match proteus_lib::diagnostics::bench::bench_container(input, seconds) {
+            Ok(result) => result,
+            Err(err) => {
+                eprintln!("{}", err);
+                return Ok(Some(1));
+            }
+        };
+        println!(
+            "Container bench ({}): audio {:.2}ms, wall {:.2}ms, rt {:.3}x",
+            input, result.audio_time_ms, result.wall_ms, result.rt_factor
+        );
+        println!("stage                | total_ms | rt_x");
+        for stage in [&result.decode, &result.mix]
+            .into_iter()
+            .chain(result.effects.iter())
+        {
+            println!(
+                "{:<20} | {:>8.2} | {:>5.3}",
+                stage.name, stage.total_ms, stage.rt_factor
+            );
+        }
+
+        Ok(Some(0))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::run_bench_subcommand;
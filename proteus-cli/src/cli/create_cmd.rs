@@ -1,6 +1,10 @@
 //! Create command handlers.
 
+use std::path::Path;
+
+use clap::ArgMatches;
 use log::error;
+use proteus_lib::container::play_settings::{json_schema, PlaySettingsVersion};
 
 use crate::project_files;
 
@@ -19,6 +23,47 @@ pub(crate) fn run_create_effects_json() -> i32 {
     }
 }
 
+/// Handle `create settings-schema`.
+pub(crate) fn run_create_settings_schema(args: &ArgMatches) -> i32 {
+    if let Some(dir) = args.get_one::<String>("out-dir") {
+        return match write_settings_schemas(Path::new(dir)) {
+            Ok(()) => 0,
+            Err(err) => {
+                error!("Failed to write schemas to {}: {}", dir, err);
+                -1
+            }
+        };
+    }
+    let requested = args
+        .get_one::<String>("version")
+        .map_or("4", String::as_str);
+    let Some(version) = PlaySettingsVersion::parse(requested) else {
+        error!("Unknown play_settings version: {}", requested);
+        return -1;
+    };
+    match serde_json::to_string_pretty(&json_schema(version)) {
+        Ok(json) => {
+            println!("{}", json);
+            0
+        }
+        Err(err) => {
+            error!("Failed to serialize schema: {}", err);
+            -1
+        }
+    }
+}
+
+fn write_settings_schemas(dir: &Path) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for version in PlaySettingsVersion::ALL {
+        let path = dir.join(format!("play_settings.{}.schema.json", version.label()));
+        let json = serde_json::to_string_pretty(&json_schema(version))?;
+        std::fs::write(&path, json + "\n")?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::run_create_effects_json;
@@ -28,4 +73,17 @@ mod tests {
         let code = run_create_effects_json();
         assert_eq!(code, 0);
     }
+
+    #[test]
+    fn settings_schemas_are_written_per_version() {
+        let dir = tempfile::tempdir().unwrap();
+        super::write_settings_schemas(dir.path()).unwrap();
+        let v4 = std::fs::read_to_string(dir.path().join("play_settings.v4.schema.json")).unwrap();
+        let schema: serde_json::Value = serde_json::from_str(&v4).unwrap();
+        assert_eq!(schema["required"][0], "encoder_version");
+        assert!(dir
+            .path()
+            .join("play_settings.legacy.schema.json")
+            .is_file());
+    }
 }
@@ -0,0 +1,162 @@
+//! Export-batch subcommand handler.
+
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use log::error;
+use proteus_lib::container::prot::{Combination, Prot};
+use proteus_lib::playback::pcm_output::PcmFormat;
+use proteus_lib::playback::render::{
+    export_batch, BatchEvent, BatchExportOptions, BatchJob, BatchVariant,
+};
+use proteus_lib::tools::progress::CancellationToken;
+
+/// Handle `export-batch`: render seeds or combinations of a container to WAV files.
+pub(crate) fn run_export_batch(args: &ArgMatches) -> i32 {
+    let input = args.get_one::<String>("INPUT").unwrap();
+    let out_dir = PathBuf::from(args.get_one::<String>("out-dir").unwrap());
+    let options = match parse_options(args) {
+        Ok(options) => options,
+        Err(err) => {
+            error!("{}", err);
+            return -1;
+        }
+    };
+    let prot = match Prot::try_new(input) {
+        Ok(prot) => prot,
+        Err(err) => {
+            error!("{}", err);
+            return -1;
+        }
+    };
+    let jobs = match build_jobs(args, &prot, input, &out_dir) {
+        Ok(jobs) => jobs,
+        Err(err) => {
+            error!("{}", err);
+            return -1;
+        }
+    };
+    if let Err(err) = std::fs::create_dir_all(&out_dir) {
+        error!("failed to create {}: {}", out_dir.display(), err);
+        return -1;
+    }
+
+    let total = jobs.len();
+    let mut done = 0;
+    let results = export_batch(&prot, &jobs, options, &CancellationToken::new(), |event| {
+        if let BatchEvent::Finished { job, result } = event {
+            done += 1;
+            let output = jobs[job].output.display();
+            match result {
+                Ok(checksum) => println!("[{}/{}] {} {}", done, total, output, checksum),
+                Err(err) => error!("[{}/{}] {}: {}", done, total, output, err),
+            }
+        }
+    });
+    if results.iter().all(Result::is_ok) {
+        0
+    } else {
+        -1
+    }
+}
+
+fn parse_options(args: &ArgMatches) -> Result<BatchExportOptions, String> {
+    let mut options = BatchExportOptions::default();
+    if let Some(value) = args.get_one::<String>("jobs") {
+        options.jobs = value
+            .parse::<usize>()
+            .ok()
+            .filter(|jobs| *jobs > 0)
+            .ok_or_else(|| format!("Invalid --jobs value '{}'", value))?;
+    }
+    if let Some(value) = args.get_one::<String>("seconds") {
+        options.seconds = Some(
+            value
+                .parse::<f64>()
+                .ok()
+                .filter(|seconds| seconds.is_finite() && *seconds > 0.0)
+                .ok_or_else(|| format!("Invalid --seconds value '{}'", value))?,
+        );
+    }
+    options.format = match args.get_one::<String>("format").map(String::as_str) {
+        Some("s16") => PcmFormat::S16Le,
+        _ => PcmFormat::F32Le,
+    };
+    let post = &mut options.render.post;
+    post.trim_silence_dbfs = parse_level(args, "trim-silence")?;
+    post.loudness_lufs = parse_level(args, "normalize-lufs")?;
+    post.peak_dbfs = parse_level(args, "normalize-peak")?;
+    post.fade_in_ms = parse_fade_ms(args, "fade-in")?;
+    post.fade_out_ms = parse_fade_ms(args, "fade-out")?;
+    Ok(options)
+}
+
+fn parse_level(args: &ArgMatches, name: &str) -> Result<Option<f32>, String> {
+    args.get_one::<String>(name)
+        .map(|value| {
+            value
+                .parse::<f32>()
+                .ok()
+                .filter(|level| level.is_finite())
+                .ok_or_else(|| format!("Invalid --{} value '{}'", name, value))
+        })
+        .transpose()
+}
+
+fn parse_fade_ms(args: &ArgMatches, name: &str) -> Result<f32, String> {
+    let Some(value) = args.get_one::<String>(name) else {
+        return Ok(0.0);
+    };
+    value
+        .parse::<f32>()
+        .ok()
+        .filter(|ms| ms.is_finite() && *ms >= 0.0)
+        .ok_or_else(|| format!("Invalid --{} value '{}'", name, value))
+}
+
+fn build_jobs(
+    args: &ArgMatches,
+    prot: &Prot,
+    input: &str,
+    out_dir: &Path,
+) -> Result<Vec<BatchJob>, String> {
+    let stem = Path::new(input)
+        .file_stem()
+        .map_or_else(|| "export".into(), |stem| stem.to_string_lossy());
+    let job = |variant: BatchVariant, label: String| BatchJob {
+        variant,
+        output: out_dir.join(format!("{}-{}.wav", stem, label)),
+    };
+
+    if let Some(seeds) = args.get_one::<String>("seeds") {
+        return seeds
+            .split(',')
+            .map(|seed| {
+                let seed = seed.trim();
+                seed.parse::<u64>()
+                    .map(|value| job(BatchVariant::Seed(value), format!("seed-{}", value)))
+                    .map_err(|_| format!("Invalid seed '{}' in --seeds", seed))
+            })
+            .collect();
+    }
+
+    let combinations = prot
+        .combinations()
+        .ok_or_else(|| "container has too many combinations to enumerate".to_string())?;
+    let variant = |combination: Combination| {
+        let label = format!("variant-{}", combination.index);
+        job(BatchVariant::Combination(combination), label)
+    };
+    if args.get_flag("all") {
+        return Ok(combinations.map(variant).collect());
+    }
+    let count = args.get_one::<String>("variants").unwrap();
+    let count = count
+        .parse::<usize>()
+        .map_err(|_| format!("Invalid --variants value '{}'", count))?;
+    let seed = args.get_one::<String>("seed").unwrap();
+    let seed = seed
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid --seed value '{}'", seed))?;
+    Ok(combinations.sample(count, seed).map(variant).collect())
+}
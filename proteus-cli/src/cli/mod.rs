@@ -4,7 +4,9 @@ pub mod args;
 pub mod bench;
 pub mod controls;
 mod create_cmd;
+mod export_cmd;
 mod info_cmd;
+mod output_spec;
 mod peaks_cmd;
 mod playback_runner;
 pub mod runner;
@@ -18,7 +20,15 @@ mod tests {
     #[test]
     fn cli_root_registers_expected_subcommands() {
         let command = build_cli();
-        for name in ["bench", "verify", "info", "peaks", "init", "create"] {
+        for name in [
+            "bench",
+            "verify",
+            "info",
+            "peaks",
+            "export-batch",
+            "init",
+            "create",
+        ] {
             assert!(
                 command.get_subcommands().any(|sub| sub.get_name() == name),
                 "missing subcommand: {name}"
@@ -0,0 +1,51 @@
+//! Parsing for the `--output` destination spec.
+
+use std::path::PathBuf;
+
+use proteus_lib::playback::pcm_output::{PcmBackend, PcmFormat, PcmTarget};
+
+/// Parse `pcm:[f32|s16:]<path|->` into a raw PCM backend.
+///
+/// `-` or `stdout` writes to standard output; any other value is opened as a
+/// file or named pipe. The sample format defaults to `f32`.
+pub(crate) fn parse_output_spec(spec: &str) -> Result<PcmBackend, String> {
+    let Some(rest) = spec.strip_prefix("pcm:") else {
+        return Err(format!(
+            "unsupported output '{}': expected pcm:[f32|s16:]<path|->",
+            spec
+        ));
+    };
+    let (format, target) = match rest.split_once(':') {
+        Some(("f32", target)) => (PcmFormat::F32Le, target),
+        Some(("s16", target)) => (PcmFormat::S16Le, target),
+        _ => (PcmFormat::F32Le, rest),
+    };
+    let target = match target {
+        "" => return Err(format!("output '{}' is missing a destination", spec)),
+        "-" | "stdout" => PcmTarget::Stdout,
+        path => PcmTarget::Path(PathBuf::from(path)),
+    };
+    Ok(PcmBackend::new(target, format))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_format_and_target() {
+        let backend = parse_output_spec("pcm:s16:/tmp/fifo").unwrap();
+        assert_eq!(backend.format(), PcmFormat::S16Le);
+        assert_eq!(
+            backend.target(),
+            &PcmTarget::Path(PathBuf::from("/tmp/fifo"))
+        );
+
+        let backend = parse_output_spec("pcm:-").unwrap();
+        assert_eq!(backend.format(), PcmFormat::F32Le);
+        assert_eq!(backend.target(), &PcmTarget::Stdout);
+
+        assert!(parse_output_spec("pcm:").is_err());
+        assert!(parse_output_spec("wav:out.wav").is_err());
+    }
+}
@@ -16,12 +16,17 @@ use crossterm::{
 };
 use log::error;
 use proteus_lib::{
-    container::prot::PathsTrack,
-    playback::player::{self, EndOfStreamAction, PlayerInitOptions},
+    container::validate::ValidationSeverity,
+    playback::{
+        engine::DecodePool,
+        pcm_output::PcmTarget,
+        player::{self, EndOfStreamAction, OutputMode, PlayerInitOptions, PlayerSource},
+    },
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 use symphonia::core::errors::Result;
 
+use super::output_spec::parse_output_spec;
 use super::{controls, ui};
 use crate::logging::LogLine;
 use crate::{logging, project_files};
@@ -29,6 +34,7 @@ use crate::{logging, project_files};
 struct SessionConfig {
     gain: f32,
     quiet: bool,
+    exit_on_finish: bool,
 }
 
 /// Handle default playback path.
@@ -46,6 +52,25 @@ pub(crate) fn run_playback(
     if let Some(code) = maybe_print_durations(args, &file_path) {
         return Ok(code);
     }
+    if args.get_flag("validate") {
+        return Ok(print_validation(&file_path));
+    }
+
+    let pcm_backend = match args
+        .get_one::<String>("output")
+        .map(|spec| parse_output_spec(spec))
+    {
+        Some(Ok(backend)) => Some(backend),
+        Some(Err(err)) => {
+            error!("{}", err);
+            return Ok(-1);
+        }
+        None => None,
+    };
+    // Stdout carries the audio stream, so the status UI must stay off it.
+    let pcm_to_stdout = pcm_backend
+        .as_ref()
+        .is_some_and(|backend| backend.target() == &PcmTarget::Stdout);
 
     let session = SessionConfig {
         gain: args
@@ -53,11 +78,27 @@ pub(crate) fn run_playback(
             .unwrap()
             .parse::<f32>()
             .unwrap(),
-        quiet: args.get_flag("quiet"),
+        quiet: args.get_flag("quiet") || pcm_to_stdout,
+        exit_on_finish: pcm_backend.is_some(),
     };
 
     let cli_player_options = PlayerInitOptions {
-        end_of_stream_action: EndOfStreamAction::Pause,
+        end_of_stream_action: if pcm_backend.is_some() {
+            EndOfStreamAction::Stop
+        } else {
+            EndOfStreamAction::Pause
+        },
+        output_mode: args.get_one::<u32>("output-buffer-frames").map_or(
+            OutputMode::Shared,
+            |frames| OutputMode::LowLatency {
+                buffer_frames: *frames,
+            },
+        ),
+        decode_threads: args
+            .get_one::<usize>("decode-threads")
+            .copied()
+            .unwrap_or_else(DecodePool::default_threads),
+        ..PlayerInitOptions::default()
     };
     let mut player = build_player_from_args(args, &file_path, cli_player_options)?;
 
@@ -72,6 +113,10 @@ pub(crate) fn run_playback(
         }
     }
 
+    if let Some(backend) = pcm_backend {
+        player.set_output_backend(Some(Arc::new(backend)));
+    }
+
     player.play();
     player.set_volume(session.gain / 100.0);
     Ok(run_playback_session(player, session, log_buffer))
@@ -83,7 +128,7 @@ fn maybe_print_durations(args: &ArgMatches, file_path: &str) -> Option<i32> {
         let durations = proteus_lib::container::info::get_durations_by_scan(file_path);
         let elapsed = start.elapsed();
         let mut items = durations.into_iter().collect::<Vec<_>>();
-        items.sort_by(|a, b| a.0.cmp(&b.0));
+        items.sort_by_key(|item| item.0);
         for (track_id, seconds) in items {
             println!("track {}: {:.3}s", track_id, seconds);
         }
@@ -95,7 +140,7 @@ fn maybe_print_durations(args: &ArgMatches, file_path: &str) -> Option<i32> {
         let durations = proteus_lib::container::info::get_durations(file_path);
         let elapsed = start.elapsed();
         let mut items = durations.into_iter().collect::<Vec<_>>();
-        items.sort_by(|a, b| a.0.cmp(&b.0));
+        items.sort_by_key(|item| item.0);
         for (track_id, seconds) in items {
             println!("track {}: {:.3}s", track_id, seconds);
         }
@@ -105,17 +150,31 @@ fn maybe_print_durations(args: &ArgMatches, file_path: &str) -> Option<i32> {
     None
 }
 
+fn print_validation(file_path: &str) -> i32 {
+    let report = proteus_lib::container::validate(file_path);
+    for issue in &report.issues {
+        let label = match issue.severity() {
+            ValidationSeverity::Error => "error",
+            ValidationSeverity::Warning => "warning",
+        };
+        println!("{}: {}", label, issue);
+    }
+    if report.is_valid() {
+        println!("{}: ok", report.path);
+        0
+    } else {
+        println!("{}: invalid", report.path);
+        1
+    }
+}
+
 fn build_player_from_args(
     args: &ArgMatches,
     file_path: &str,
     cli_player_options: PlayerInitOptions,
 ) -> Result<player::Player> {
     let input_path = Path::new(&file_path);
-    let is_container = file_path.ends_with(".prot") || file_path.ends_with(".mka");
-    let is_directory = input_path.is_dir();
-    let player = if is_container {
-        player::Player::new_with_options(file_path, cli_player_options)
-    } else if is_directory {
+    let player = if input_path.is_dir() {
         let config = project_files::load_directory_playback_config(input_path).map_err(|err| {
             error!("{}", err);
             symphonia::core::errors::Error::IoError(std::io::Error::other(err))
@@ -139,8 +198,16 @@ fn build_player_from_args(
         }
         player
     } else {
-        let track = PathsTrack::new_from_file_paths(vec![file_path.to_string()]);
-        player::Player::new_from_file_paths_with_options(vec![track], cli_player_options)
+        // Containers and plain audio files are told apart by content, so
+        // any extension is accepted here.
+        player::Player::try_from_source_with_options(
+            PlayerSource::ContainerPath(file_path.to_string()),
+            cli_player_options,
+        )
+        .map_err(|err| {
+            error!("{}", err);
+            symphonia::core::errors::Error::IoError(std::io::Error::other(err.to_string()))
+        })?
     };
     Ok(player)
 }
@@ -174,6 +241,9 @@ fn run_playback_session(
         if !controls::handle_key_event(&mut player) {
             break;
         }
+        if config.exit_on_finish && player.is_finished() {
+            break;
+        }
 
         sleep(Duration::from_millis(50));
     }
@@ -194,7 +264,14 @@ fn draw_status_frame(
     let time = player.get_time();
     let duration = player.get_duration();
     let playing = player.is_playing();
-    let effect_names = player.get_effect_names();
+    let effect_names = player
+        .get_effect_descriptors()
+        .into_iter()
+        .map(|effect| match effect.bypass {
+            Some(bypass) => format!("{} ({})", effect.name, bypass),
+            None => effect.name.to_string(),
+        })
+        .collect();
     #[cfg(feature = "output-meter")]
     let levels = player.get_levels();
     #[cfg(not(feature = "output-meter"))]
@@ -204,7 +281,7 @@ fn draw_status_frame(
     #[cfg(not(feature = "output-meter"))]
     let levels_db: Vec<f32> = Vec::new();
     #[cfg(feature = "debug")]
-    let dsp_metrics = player.get_dsp_metrics();
+    let metrics = player.get_metrics();
     #[cfg(feature = "debug")]
     let (thread_exists, state, audio_heard) = player.debug_playback_state();
     #[cfg(feature = "debug")]
@@ -220,41 +297,47 @@ fn draw_status_frame(
         #[cfg(feature = "debug")]
         sample_rate: player.audio_info().sample_rate,
         #[cfg(feature = "debug")]
-        overrun: dsp_metrics.overrun,
+        overrun: metrics.flag("mix.overrun").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        overrun_ms: dsp_metrics.overrun_ms,
+        overrun_ms: metrics.gauge("mix.overrun_ms").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        avg_overrun_ms: dsp_metrics.avg_overrun_ms,
+        avg_overrun_ms: metrics.gauge("mix.avg_overrun_ms").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        max_overrun_ms: dsp_metrics.max_overrun_ms,
+        max_overrun_ms: metrics.gauge("mix.max_overrun_ms").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        chain_ksps: dsp_metrics.chain_ksps,
+        chain_ksps: metrics.gauge("mix.chain_ksps").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        avg_chain_ksps: dsp_metrics.avg_chain_ksps,
+        avg_chain_ksps: metrics.gauge("mix.avg_chain_ksps").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        min_chain_ksps: dsp_metrics.min_chain_ksps,
+        min_chain_ksps: metrics.gauge("mix.min_chain_ksps").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        max_chain_ksps: dsp_metrics.max_chain_ksps,
+        max_chain_ksps: metrics.gauge("mix.max_chain_ksps").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        underrun_count: dsp_metrics.underrun_count,
+        underrun_count: metrics.count("output.underrun_count").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        underrun_active: dsp_metrics.underrun_active,
+        underrun_active: metrics.flag("output.underrun_active").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        pop_count: dsp_metrics.pop_count,
+        pop_count: metrics.count("output.pop_count").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        clip_count: dsp_metrics.clip_count,
+        clip_count: metrics.count("output.clip_count").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        nan_count: dsp_metrics.nan_count,
+        nan_count: metrics.count("output.nan_count").unwrap_or_default(),
         #[cfg(feature = "debug")]
-        late_append_count: dsp_metrics.late_append_count,
+        late_append_count: metrics
+            .count("output.late_append_count")
+            .unwrap_or_default(),
         #[cfg(feature = "debug")]
-        late_append_active: dsp_metrics.late_append_active,
+        late_append_active: metrics
+            .flag("output.late_append_active")
+            .unwrap_or_default(),
         #[cfg(feature = "debug")]
-        track_key_count: dsp_metrics.track_key_count,
+        track_key_count: metrics.count("mix.track_key_count").unwrap_or_default() as usize,
         #[cfg(feature = "debug")]
-        finished_track_count: dsp_metrics.finished_track_count,
+        finished_track_count: metrics
+            .count("mix.finished_track_count")
+            .unwrap_or_default() as usize,
         #[cfg(feature = "debug")]
-        prot_key_count: dsp_metrics.prot_key_count,
+        prot_key_count: metrics.count("mix.prot_key_count").unwrap_or_default() as usize,
         #[cfg(feature = "debug")]
         thread_exists,
         #[cfg(feature = "debug")]
@@ -13,7 +13,7 @@ use symphonia::core::errors::Result;
 use crate::logging::LogLine;
 use crate::{cli, project_files};
 
-use super::{create_cmd, info_cmd, peaks_cmd, playback_runner};
+use super::{create_cmd, export_cmd, info_cmd, peaks_cmd, playback_runner};
 
 /// Main CLI execution path: parse args, run subcommands, or start playback.
 pub fn run(args: &ArgMatches, log_buffer: Arc<Mutex<VecDeque<LogLine>>>) -> Result<i32> {
@@ -28,9 +28,13 @@ pub fn run(args: &ArgMatches, log_buffer: Arc<Mutex<VecDeque<LogLine>>>) -> Resu
                 info_cmd::run_info(file_path, print)
             }
             "peaks" => peaks_cmd::run_peaks(sub_args),
+            "export-batch" => export_cmd::run_export_batch(sub_args),
             "verify" => run_verify(sub_args)?,
             "create" => match sub_args.subcommand() {
                 Some(("effects-json", _)) => create_cmd::run_create_effects_json(),
+                Some(("settings-schema", schema_args)) => {
+                    create_cmd::run_create_settings_schema(schema_args)
+                }
                 _ => {
                     error!("Unknown create subcommand");
                     -1
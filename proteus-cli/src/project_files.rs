@@ -5,12 +5,9 @@ use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
 
+use proteus_lib::container::play_settings::{SelectionMode, TrackLoop};
 use proteus_lib::container::prot::PathsTrack;
-use proteus_lib::dsp::effects::{
-    AudioEffect, CompressorEffect, ConvolutionReverbEffect, DelayReverbEffect,
-    DiffusionReverbEffect, DistortionEffect, GainEffect, HighPassFilterEffect, LimiterEffect,
-    LowPassFilterEffect, MultibandEqEffect, PanEffect,
-};
+use proteus_lib::dsp::effects::{AudioEffect, DefaultEffectChain};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
@@ -61,6 +58,16 @@ pub struct JsonPathsTrack {
     pub selections_count: u32,
     #[serde(default)]
     pub shuffle_points: Vec<String>,
+    #[serde(default, skip_serializing_if = "SelectionMode::is_random")]
+    pub selection_mode: SelectionMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<Vec<f32>>,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub start_offset_ms: u64,
+    #[serde(default, rename = "loop", skip_serializing_if = "Option::is_none")]
+    pub looping: Option<TrackLoop>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -78,6 +85,10 @@ fn default_selections_count() -> u32 {
     1
 }
 
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
 pub fn load_directory_playback_config(root: &Path) -> ProjectFilesResult<DirectoryPlaybackConfig> {
     let shuffle_path = root.join("shuffle_schedule.json");
     let effects_path = root.join("effects_chain.json");
@@ -145,43 +156,11 @@ pub fn write_init_files(root: &Path) -> ProjectFilesResult<()> {
 }
 
 pub fn default_effects_chain_enabled() -> Vec<AudioEffect> {
-    vec![
-        AudioEffect::ConvolutionReverb(ConvolutionReverbEffect::default()),
-        AudioEffect::DiffusionReverb(DiffusionReverbEffect::default()),
-        AudioEffect::DelayReverb(DelayReverbEffect::default()),
-        AudioEffect::LowPassFilter(LowPassFilterEffect::default()),
-        AudioEffect::HighPassFilter(HighPassFilterEffect::default()),
-        AudioEffect::Distortion(DistortionEffect::default()),
-        AudioEffect::Gain(GainEffect::default()),
-        AudioEffect::Compressor(CompressorEffect::default()),
-        AudioEffect::Limiter(LimiterEffect::default()),
-        AudioEffect::MultibandEq(MultibandEqEffect::default()),
-        AudioEffect::Pan(PanEffect::default()),
-    ]
+    DefaultEffectChain::new().build()
 }
 
 pub fn default_effects_chain_disabled() -> Vec<AudioEffect> {
-    default_effects_chain_enabled()
-        .into_iter()
-        .map(disable_effect)
-        .collect()
-}
-
-fn disable_effect(mut effect: AudioEffect) -> AudioEffect {
-    match &mut effect {
-        AudioEffect::DelayReverb(e) => e.enabled = false,
-        AudioEffect::DiffusionReverb(e) => e.enabled = false,
-        AudioEffect::ConvolutionReverb(e) => e.enabled = false,
-        AudioEffect::LowPassFilter(e) => e.enabled = false,
-        AudioEffect::HighPassFilter(e) => e.enabled = false,
-        AudioEffect::Distortion(e) => e.enabled = false,
-        AudioEffect::Gain(e) => e.enabled = false,
-        AudioEffect::Compressor(e) => e.enabled = false,
-        AudioEffect::Limiter(e) => e.enabled = false,
-        AudioEffect::MultibandEq(e) => e.enabled = false,
-        AudioEffect::Pan(e) => e.enabled = false,
-    }
-    effect
+    DefaultEffectChain::new().enabled(false).build()
 }
 
 fn load_paths_tracks_json(root: &Path, path: &Path) -> ProjectFilesResult<Vec<PathsTrack>> {
@@ -218,6 +197,11 @@ fn load_paths_tracks_json(root: &Path, path: &Path) -> ProjectFilesResult<Vec<Pa
             pan: track.pan,
             selections_count: track.selections_count.max(1),
             shuffle_points: track.shuffle_points,
+            selection_mode: track.selection_mode,
+            transitions: track.transitions,
+            start_offset_ms: track.start_offset_ms,
+            looping: track.looping,
+            group: track.group,
         });
     }
 
@@ -244,6 +228,11 @@ fn discover_paths_tracks_json_from_directory(
             pan: 0.0,
             selections_count: 1,
             shuffle_points: Vec::new(),
+            selection_mode: SelectionMode::Random,
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         });
     }
     Ok(tracks)
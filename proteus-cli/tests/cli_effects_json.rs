@@ -9,10 +9,14 @@ fn create_effects_json_outputs_all_effects() {
         .success()
         .stdout(contains("ConvolutionReverbSettings"))
         .stdout(contains("DelayReverbSettings"))
+        .stdout(contains("DelayEchoSettings"))
         .stdout(contains("LowPassFilterSettings"))
         .stdout(contains("HighPassFilterSettings"))
         .stdout(contains("DistortionSettings"))
         .stdout(contains("CompressorSettings"))
         .stdout(contains("LimiterSettings"))
-        .stdout(contains("MultibandEqSettings"));
+        .stdout(contains("MultibandEqSettings"))
+        .stdout(contains("DynamicEqSettings"))
+        .stdout(contains("SaturationSettings"))
+        .stdout(contains("ShimmerReverbSettings"));
 }
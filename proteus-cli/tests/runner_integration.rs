@@ -24,3 +24,62 @@ fn peaks_json_missing_input_returns_failure() {
     let output = run_cli(&["peaks", "json", "/definitely/missing.audio"]);
     assert!(!output.status.success());
 }
+
+#[test]
+fn verify_decode_reads_standard_input() {
+    let bin = env!("CARGO_BIN_EXE_prot");
+    let input = std::fs::File::open(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_audio/test-16bit.wav"
+    ))
+    .expect("open test audio");
+    let output = Command::new(bin)
+        .args(["verify", "decode", "-"])
+        .stdin(input)
+        .output()
+        .expect("run prot CLI");
+    assert!(output.status.success());
+}
+
+#[test]
+fn export_batch_writes_one_wav_per_seed() {
+    let out_dir = tempfile::tempdir().expect("create temp dir");
+    let input = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_audio/demo_shuffle_points.prot"
+    );
+    let output = run_cli(&[
+        "export-batch",
+        input,
+        "--out-dir",
+        out_dir.path().to_str().unwrap(),
+        "--seeds",
+        "1,2",
+        "--seconds",
+        "0.2",
+        "--jobs",
+        "2",
+        "--normalize-peak",
+        "-1",
+        "--fade-out",
+        "50",
+    ]);
+    assert!(output.status.success());
+    for seed in [1, 2] {
+        let path = out_dir
+            .path()
+            .join(format!("demo_shuffle_points-seed-{}.wav", seed));
+        assert!(path.exists(), "missing {}", path.display());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for line in stdout.lines() {
+        let checksum = line.rsplit(' ').next().unwrap();
+        assert_eq!(checksum.len(), 16, "line without checksum: {}", line);
+    }
+}
+
+#[test]
+fn export_batch_requires_a_selection() {
+    let output = run_cli(&["export-batch", "song.prot", "--out-dir", "out"]);
+    assert!(!output.status.success());
+}
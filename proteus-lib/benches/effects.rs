@@ -0,0 +1,59 @@
+//! Criterion benchmarks for per-effect DSP cost.
+//!
+//! Run with `cargo bench -p proteus-lib --features bench --bench effects`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use proteus_lib::diagnostics::bench::kernels;
+
+/// Frames per processing block (about 21 ms at 48 kHz).
+const BLOCK_FRAMES: usize = 1024;
+const CONVOLUTION_IR_SECONDS: f32 = 1.0;
+const CONVOLUTION_FFT_SIZES: [usize; 4] = [1024, 4096, 16384, 24576];
+const EQ_BAND_COUNTS: [usize; 4] = [1, 4, 8, 16];
+
+fn convolution(c: &mut Criterion) {
+    let mut group = c.benchmark_group("convolution");
+    for fft_size in CONVOLUTION_FFT_SIZES {
+        let block = kernels::signal(fft_size / 2 / kernels::BENCH_CHANNELS);
+        let mut convolver = kernels::convolver(CONVOLUTION_IR_SECONDS, fft_size);
+        group.throughput(Throughput::Elements(block.len() as u64));
+        group.bench_function(BenchmarkId::from_parameter(fft_size), |b| {
+            b.iter(|| kernels::convolve(&mut convolver, &block))
+        });
+    }
+    group.finish();
+}
+
+fn multiband_eq(c: &mut Criterion) {
+    let mut group = c.benchmark_group("multiband_eq");
+    let context = kernels::context();
+    let block = kernels::signal(BLOCK_FRAMES);
+    let mut output = Vec::with_capacity(block.len());
+    group.throughput(Throughput::Elements(BLOCK_FRAMES as u64));
+    for bands in EQ_BAND_COUNTS {
+        let mut effect = kernels::multiband_eq(bands);
+        effect.warm_up(&context);
+        group.bench_function(BenchmarkId::from_parameter(bands), |b| {
+            b.iter(|| kernels::process_effect(&mut effect, &block, &context, &mut output))
+        });
+    }
+    group.finish();
+}
+
+fn compressor(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compressor");
+    let context = kernels::context();
+    let block = kernels::signal(BLOCK_FRAMES);
+    let mut output = Vec::with_capacity(block.len());
+    let mut effect = kernels::compressor();
+    effect.warm_up(&context);
+    group.throughput(Throughput::Elements(BLOCK_FRAMES as u64));
+    group.bench_function(BenchmarkId::from_parameter(BLOCK_FRAMES), |b| {
+        b.iter(|| kernels::process_effect(&mut effect, &block, &context, &mut output))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, convolution, multiband_eq, compressor);
+criterion_main!(benches);
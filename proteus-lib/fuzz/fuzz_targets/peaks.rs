@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use proteus_lib::fuzz::PeaksInput;
+
+fuzz_target!(|input: PeaksInput| {
+    proteus_lib::fuzz::peaks(&input);
+});
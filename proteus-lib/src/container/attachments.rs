@@ -0,0 +1,455 @@
+//! Bounds-checked reader for Matroska attachments.
+//!
+//! `.prot` files carry `play_settings.json` and impulse responses as Matroska
+//! attachments. This module walks the EBML element tree just far enough to
+//! collect them, validating every declared element size against its parent
+//! and the stream length before reading. Corrupted or truncated containers
+//! produce an [`AttachmentError`] instead of a panic or an unbounded
+//! allocation. [`append_attachment`] adds an attachment to an existing file.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::tools::progressive::{open_media, ProgressiveFile};
+
+mod append;
+
+pub use append::append_attachment;
+
+pub(crate) const ID_EBML_HEADER: u32 = 0x1A45_DFA3;
+pub(crate) const ID_SEGMENT: u32 = 0x1853_8067;
+pub(crate) const ID_CLUSTER: u32 = 0x1F43_B675;
+#[cfg(feature = "signing")]
+pub(crate) const ID_TRACKS: u32 = 0x1654_AE6B;
+const ID_ATTACHMENTS: u32 = 0x1941_A469;
+const ID_ATTACHED_FILE: u32 = 0x61A7;
+const ID_FILE_NAME: u32 = 0x466E;
+const ID_FILE_MIME_TYPE: u32 = 0x4660;
+const ID_FILE_DATA: u32 = 0x465C;
+const ID_FILE_UID: u32 = 0x46AE;
+
+/// Longest EBML element id, in bytes.
+const MAX_ID_BYTES: u32 = 4;
+/// Longest EBML element size, in bytes.
+const MAX_SIZE_BYTES: u32 = 8;
+
+/// A file attached to a Matroska container.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContainerAttachment {
+    /// Attachment file name as stored in the container.
+    pub name: String,
+    /// MIME type, or an empty string when the container omits it.
+    pub mime_type: String,
+    /// Raw attachment bytes.
+    pub data: Vec<u8>,
+}
+
+/// Failure while reading container attachments.
+#[derive(Debug)]
+pub enum AttachmentError {
+    /// Reading from the underlying stream failed (including truncation).
+    Io(std::io::Error),
+    /// The EBML structure is invalid or inconsistent with the stream length.
+    Malformed(String),
+    /// An attachment is larger than the caller's size limit.
+    TooLarge {
+        /// Declared attachment size, in bytes.
+        size: u64,
+        /// Size limit, in bytes.
+        limit: u64,
+    },
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read container: {}", err),
+            Self::Malformed(reason) => write!(f, "malformed container: {}", reason),
+            Self::TooLarge { size, limit } => {
+                write!(f, "attachment of {} bytes exceeds limit of {}", size, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {}
+
+impl From<std::io::Error> for AttachmentError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+/// Return `true` when the file at `path` starts with an EBML header.
+///
+/// This identifies Matroska files (`.prot`, `.mka`, `.mkv`, `.webm`) by
+/// content, whatever their extension. Unreadable files return `false`.
+pub fn is_matroska_path(path: impl AsRef<Path>) -> bool {
+    File::open(path).is_ok_and(|mut file| is_matroska(&mut file))
+}
+
+/// [`is_matroska_path`], reading through `download` when one is given.
+pub(crate) fn is_matroska_with_download(path: &str, download: Option<&ProgressiveFile>) -> bool {
+    open_media(path, download).is_ok_and(|mut source| is_matroska(&mut source))
+}
+
+/// Return `true` when `reader` starts with an EBML header.
+///
+/// Reads at most four bytes from the current position.
+pub fn is_matroska<R: Read>(reader: &mut R) -> bool {
+    let mut magic = [0_u8; MAX_ID_BYTES as usize];
+    reader.read_exact(&mut magic).is_ok() && u32::from_be_bytes(magic) == ID_EBML_HEADER
+}
+
+/// Read every attachment from the container at `path`.
+pub fn read_attachments_from_path(
+    path: impl AsRef<Path>,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    read_attachments_from_path_with_limit(path, u64::MAX)
+}
+
+/// Read every attachment from the container at `path`, refusing any larger
+/// than `max_bytes`.
+///
+/// # Errors
+///
+/// Returns [`AttachmentError::TooLarge`] before reading an oversized
+/// attachment's data.
+pub fn read_attachments_from_path_with_limit(
+    path: impl AsRef<Path>,
+    max_bytes: u64,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    read_attachments_with_limit(&mut BufReader::new(File::open(path)?), max_bytes)
+}
+
+/// [`read_attachments_from_path_with_limit`], reading through `download`
+/// when one is given.
+///
+/// While the download runs, only attachments ahead of the first cluster are
+/// read.
+pub(crate) fn read_attachments_with_download(
+    path: impl AsRef<Path>,
+    download: Option<&ProgressiveFile>,
+    max_bytes: u64,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    match download {
+        Some(download) if download.is_downloading() => {
+            read_attachments_until(&mut BufReader::new(download.open()?), max_bytes, true)
+        }
+        _ => read_attachments_from_path_with_limit(path, max_bytes),
+    }
+}
+
+/// Read every attachment from a Matroska stream.
+///
+/// Elements of unknown size (live-streamed clusters) cannot be skipped, so
+/// the walk stops at the first one and returns the attachments found so far.
+pub fn read_attachments<R: Read + Seek>(
+    reader: &mut R,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    read_attachments_with_limit(reader, u64::MAX)
+}
+
+/// [`read_attachments`] refusing any attachment larger than `max_bytes`.
+///
+/// # Errors
+///
+/// Returns [`AttachmentError::TooLarge`] before reading an oversized
+/// attachment's data.
+pub fn read_attachments_with_limit<R: Read + Seek>(
+    reader: &mut R,
+    max_bytes: u64,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    read_attachments_until(reader, max_bytes, false)
+}
+
+// With `leading_only`, the walk stops at the first cluster, so a partially
+// downloaded file is never read past the attachments muxers put up front.
+fn read_attachments_until<R: Read + Seek>(
+    reader: &mut R,
+    max_bytes: u64,
+    leading_only: bool,
+) -> Result<Vec<ContainerAttachment>, AttachmentError> {
+    let stream_end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut attachments = Vec::new();
+    let mut position = 0;
+    let mut saw_header = false;
+    while position < stream_end {
+        let element = read_element_header(reader, position, stream_end)?;
+        match element.id {
+            ID_EBML_HEADER => saw_header = true,
+            ID_SEGMENT if saw_header => {
+                let walk = SegmentWalk {
+                    stream_end,
+                    max_bytes,
+                    leading_only,
+                };
+                walk_segment(reader, &element, &walk, &mut attachments)?;
+            }
+            _ if !saw_header => {
+                return Err(malformed("stream does not start with an EBML header"));
+            }
+            _ => {}
+        }
+        let Some(end) = element.end else {
+            break;
+        };
+        position = end;
+        reader.seek(SeekFrom::Start(position))?;
+    }
+
+    if !saw_header {
+        return Err(malformed("stream does not start with an EBML header"));
+    }
+    Ok(attachments)
+}
+
+/// Bodies of every top-level segment element with `id`, in file order.
+///
+/// Elements larger than `max_bytes` fail with [`AttachmentError::TooLarge`].
+/// The walk stops at the first child of unknown size.
+#[cfg(feature = "signing")]
+pub(crate) fn read_segment_elements<R: Read + Seek>(
+    reader: &mut R,
+    id: u32,
+    max_bytes: u64,
+) -> Result<Vec<Vec<u8>>, AttachmentError> {
+    let stream_end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+    let header = read_element_header(reader, 0, stream_end)?;
+    if header.id != ID_EBML_HEADER {
+        return Err(malformed("stream does not start with an EBML header"));
+    }
+    let mut position = header
+        .end
+        .ok_or_else(|| malformed("EBML header has unknown size"))?;
+    let segment = loop {
+        if position >= stream_end {
+            return Ok(Vec::new());
+        }
+        reader.seek(SeekFrom::Start(position))?;
+        let element = read_element_header(reader, position, stream_end)?;
+        if element.id == ID_SEGMENT {
+            break element;
+        }
+        let Some(end) = element.end else {
+            return Ok(Vec::new());
+        };
+        position = end;
+    };
+
+    let segment_end = segment.end.unwrap_or(stream_end);
+    let mut bodies = Vec::new();
+    let mut position = segment.data_start;
+    while position < segment_end {
+        reader.seek(SeekFrom::Start(position))?;
+        let child = read_element_header(reader, position, segment_end)?;
+        let Some(child_end) = child.end else {
+            break;
+        };
+        if child.id == id {
+            let size = child_end - child.data_start;
+            if size > max_bytes {
+                return Err(AttachmentError::TooLarge {
+                    size,
+                    limit: max_bytes,
+                });
+            }
+            bodies.push(read_bytes(reader, &child, child_end)?);
+        }
+        position = child_end;
+    }
+    Ok(bodies)
+}
+
+/// Decoded EBML element header.
+pub(crate) struct ElementHeader {
+    pub(crate) id: u32,
+    /// Offset of the element's id.
+    start: u64,
+    /// Length of the size field, which ends at `data_start`.
+    size_len: u32,
+    pub(crate) data_start: u64,
+    /// End offset of the element, or `None` for an unknown-size element.
+    pub(crate) end: Option<u64>,
+}
+
+/// Bounds and stopping rule for one segment walk.
+struct SegmentWalk {
+    stream_end: u64,
+    max_bytes: u64,
+    leading_only: bool,
+}
+
+fn walk_segment<R: Read + Seek>(
+    reader: &mut R,
+    segment: &ElementHeader,
+    walk: &SegmentWalk,
+    attachments: &mut Vec<ContainerAttachment>,
+) -> Result<(), AttachmentError> {
+    let segment_end = segment.end.unwrap_or(walk.stream_end);
+    let mut position = segment.data_start;
+    while position < segment_end {
+        reader.seek(SeekFrom::Start(position))?;
+        let child = read_element_header(reader, position, segment_end)?;
+        let Some(child_end) = child.end else {
+            return Ok(());
+        };
+        if walk.leading_only && child.id == ID_CLUSTER {
+            return Ok(());
+        }
+        if child.id == ID_ATTACHMENTS {
+            read_attachment_list(reader, &child, child_end, walk.max_bytes, attachments)?;
+        }
+        position = child_end;
+    }
+    Ok(())
+}
+
+fn read_attachment_list<R: Read + Seek>(
+    reader: &mut R,
+    list: &ElementHeader,
+    list_end: u64,
+    max_bytes: u64,
+    attachments: &mut Vec<ContainerAttachment>,
+) -> Result<(), AttachmentError> {
+    let mut position = list.data_start;
+    while position < list_end {
+        reader.seek(SeekFrom::Start(position))?;
+        let child = read_element_header(reader, position, list_end)?;
+        let child_end = child
+            .end
+            .ok_or_else(|| malformed("attachment entry has unknown size"))?;
+        if child.id == ID_ATTACHED_FILE {
+            attachments.push(read_attached_file(reader, &child, child_end, max_bytes)?);
+        }
+        position = child_end;
+    }
+    Ok(())
+}
+
+fn read_attached_file<R: Read + Seek>(
+    reader: &mut R,
+    file: &ElementHeader,
+    file_end: u64,
+    max_bytes: u64,
+) -> Result<ContainerAttachment, AttachmentError> {
+    let mut attachment = ContainerAttachment {
+        name: String::new(),
+        mime_type: String::new(),
+        data: Vec::new(),
+    };
+    let mut position = file.data_start;
+    while position < file_end {
+        reader.seek(SeekFrom::Start(position))?;
+        let field = read_element_header(reader, position, file_end)?;
+        let field_end = field
+            .end
+            .ok_or_else(|| malformed("attachment field has unknown size"))?;
+        match field.id {
+            ID_FILE_NAME => attachment.name = read_string(reader, &field, field_end)?,
+            ID_FILE_MIME_TYPE => attachment.mime_type = read_string(reader, &field, field_end)?,
+            ID_FILE_DATA => {
+                let size = field_end - field.data_start;
+                if size > max_bytes {
+                    return Err(AttachmentError::TooLarge {
+                        size,
+                        limit: max_bytes,
+                    });
+                }
+                attachment.data = read_bytes(reader, &field, field_end)?;
+            }
+            _ => {}
+        }
+        position = field_end;
+    }
+    Ok(attachment)
+}
+
+fn read_bytes<R: Read>(
+    reader: &mut R,
+    element: &ElementHeader,
+    end: u64,
+) -> Result<Vec<u8>, AttachmentError> {
+    // `end` was validated against the stream length, so this allocation is
+    // bounded by the real input size.
+    let len = usize::try_from(end - element.data_start)
+        .map_err(|_| malformed("element does not fit in memory"))?;
+    let mut data = vec![0_u8; len];
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+fn read_string<R: Read>(
+    reader: &mut R,
+    element: &ElementHeader,
+    end: u64,
+) -> Result<String, AttachmentError> {
+    let bytes = read_bytes(reader, element, end)?;
+    let text = String::from_utf8_lossy(&bytes);
+    Ok(text.trim_end_matches('\0').to_string())
+}
+
+/// Read the element header at `position` and check it fits before `parent_end`.
+pub(crate) fn read_element_header<R: Read>(
+    reader: &mut R,
+    position: u64,
+    parent_end: u64,
+) -> Result<ElementHeader, AttachmentError> {
+    // Ids keep their length marker bits, so a 4-byte id always fits in `u32`.
+    let (id, id_len) = read_vint(reader, MAX_ID_BYTES)?;
+    let (raw_size, size_len) = read_vint(reader, MAX_SIZE_BYTES)?;
+    let data_start = position + u64::from(id_len) + u64::from(size_len);
+    if data_start > parent_end {
+        return Err(malformed("element header overruns its parent"));
+    }
+
+    let size_mask = (1_u64 << (7 * size_len)) - 1;
+    let size = raw_size & size_mask;
+    let end = if size == size_mask {
+        None
+    } else {
+        let end = data_start
+            .checked_add(size)
+            .filter(|end| *end <= parent_end)
+            .ok_or_else(|| malformed("element size overruns its parent"))?;
+        Some(end)
+    };
+    Ok(ElementHeader {
+        id: id as u32,
+        start: position,
+        size_len,
+        data_start,
+        end,
+    })
+}
+
+/// Read a raw EBML variable-length integer of at most `max_len` bytes.
+///
+/// Returns the value with its length marker still set, and its length.
+fn read_vint<R: Read>(reader: &mut R, max_len: u32) -> Result<(u64, u32), AttachmentError> {
+    let mut first = [0_u8; 1];
+    reader.read_exact(&mut first)?;
+    let len = first[0].leading_zeros() + 1;
+    if len > max_len {
+        return Err(malformed("invalid variable-length integer"));
+    }
+    let mut rest = [0_u8; MAX_SIZE_BYTES as usize];
+    let rest = &mut rest[..(len - 1) as usize];
+    reader.read_exact(rest)?;
+    let value = rest.iter().fold(u64::from(first[0]), |value, byte| {
+        (value << 8) | u64::from(*byte)
+    });
+    Ok((value, len))
+}
+
+fn malformed(reason: &str) -> AttachmentError {
+    AttachmentError::Malformed(reason.to_string())
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,222 @@
+//! In-place attachment writer for finished Matroska files.
+//!
+//! Matroska allows one attachments element per segment, so a new file joins
+//! the existing list instead of starting another one. When that list already
+//! ends the file it grows in place; otherwise its entries are copied into a
+//! fresh list at the end of the segment and the old element is overwritten
+//! with a zero-filled void element of the same length, so no other offset in
+//! the file changes.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use super::{
+    malformed, read_element_header, AttachmentError, ContainerAttachment, ElementHeader,
+    ID_ATTACHED_FILE, ID_ATTACHMENTS, ID_EBML_HEADER, ID_FILE_DATA, ID_FILE_MIME_TYPE,
+    ID_FILE_NAME, ID_FILE_UID, ID_SEGMENT, MAX_SIZE_BYTES,
+};
+
+const ID_VOID: u32 = 0xEC;
+
+/// Append `attachment` to the Matroska file at `path`, in place.
+///
+/// The attachment is added to the segment's attachments element, which is
+/// created at the end of the segment when the file has none; the segment
+/// size is patched to cover the new bytes. This is the builder-side
+/// counterpart of [`super::read_attachments`] for tools that add files to
+/// finished containers. Files written by earlier versions, which started a
+/// new attachments element per call, are merged back into one.
+///
+/// # Errors
+///
+/// Returns [`AttachmentError::Malformed`] when the file is not Matroska, the
+/// segment does not end the file, or its size field is too short for the new
+/// size, and [`AttachmentError::Io`] when reading or writing fails.
+pub fn append_attachment(
+    path: impl AsRef<Path>,
+    attachment: &ContainerAttachment,
+) -> Result<(), AttachmentError> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let stream_end = file.seek(SeekFrom::End(0))?;
+    let segment = find_segment(&mut file, stream_end)?;
+    if segment.end.is_some_and(|end| end != stream_end) {
+        return Err(malformed("segment does not end the file"));
+    }
+    let lists = attachment_lists(&mut file, &segment, stream_end)?;
+    let entry = encode_attached_file(attachment);
+
+    if let [list] = lists.as_slice() {
+        if list.end == Some(stream_end) {
+            let list_size = stream_end - list.data_start + entry.len() as u64;
+            if fits(list, list_size) {
+                let segment_size = segment_size_after(&segment, stream_end, entry.len())?;
+                file.seek(SeekFrom::End(0))?;
+                file.write_all(&entry)?;
+                patch_size(&mut file, list, list_size)?;
+                if let Some(size) = segment_size {
+                    patch_size(&mut file, &segment, size)?;
+                }
+                file.flush()?;
+                return Ok(());
+            }
+        }
+    }
+
+    let mut body = Vec::new();
+    for list in &lists {
+        let end = list
+            .end
+            .ok_or_else(|| malformed("attachments element has unknown size"))?;
+        let len = usize::try_from(end - list.data_start)
+            .map_err(|_| malformed("attachments element does not fit in memory"))?;
+        let start = body.len();
+        body.resize(start + len, 0);
+        file.seek(SeekFrom::Start(list.data_start))?;
+        file.read_exact(&mut body[start..])?;
+    }
+    body.extend(entry);
+    let bytes = encode_element(ID_ATTACHMENTS, &body);
+    let segment_size = segment_size_after(&segment, stream_end, bytes.len())?;
+
+    // Write the merged list before voiding the old ones, so an interrupted
+    // append leaves duplicates rather than losing attachments.
+    file.seek(SeekFrom::End(0))?;
+    file.write_all(&bytes)?;
+    if let Some(size) = segment_size {
+        patch_size(&mut file, &segment, size)?;
+    }
+    for list in &lists {
+        void_element(&mut file, list)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+fn find_segment(file: &mut File, stream_end: u64) -> Result<ElementHeader, AttachmentError> {
+    let mut position = 0;
+    loop {
+        if position >= stream_end {
+            return Err(malformed("no segment to append to"));
+        }
+        file.seek(SeekFrom::Start(position))?;
+        let element = read_element_header(file, position, stream_end)?;
+        if position == 0 && element.id != ID_EBML_HEADER {
+            return Err(malformed("stream does not start with an EBML header"));
+        }
+        if element.id == ID_SEGMENT {
+            return Ok(element);
+        }
+        position = element
+            .end
+            .ok_or_else(|| malformed("top-level element has unknown size"))?;
+    }
+}
+
+/// Every attachments element in `segment`, up to the first child of unknown
+/// size, after which readers cannot find attachments either.
+fn attachment_lists(
+    file: &mut File,
+    segment: &ElementHeader,
+    stream_end: u64,
+) -> Result<Vec<ElementHeader>, AttachmentError> {
+    let segment_end = segment.end.unwrap_or(stream_end);
+    let mut lists = Vec::new();
+    let mut position = segment.data_start;
+    while position < segment_end {
+        file.seek(SeekFrom::Start(position))?;
+        let child = read_element_header(file, position, segment_end)?;
+        let Some(child_end) = child.end else {
+            break;
+        };
+        if child.id == ID_ATTACHMENTS {
+            lists.push(child);
+        }
+        position = child_end;
+    }
+    Ok(lists)
+}
+
+/// New segment size once `added` bytes follow it, or `None` when the
+/// segment has unknown size and needs no patch.
+fn segment_size_after(
+    segment: &ElementHeader,
+    stream_end: u64,
+    added: usize,
+) -> Result<Option<u64>, AttachmentError> {
+    if segment.end.is_none() {
+        return Ok(None);
+    }
+    let size = stream_end - segment.data_start + added as u64;
+    if !fits(segment, size) {
+        return Err(malformed("segment size field is too short"));
+    }
+    Ok(Some(size))
+}
+
+/// Whether `size` fits the element's existing size field.
+fn fits(element: &ElementHeader, size: u64) -> bool {
+    // All-ones is reserved for "unknown size".
+    size < (1_u64 << (7 * element.size_len)) - 1
+}
+
+fn patch_size(file: &mut File, element: &ElementHeader, size: u64) -> Result<(), AttachmentError> {
+    file.seek(SeekFrom::Start(
+        element.data_start - u64::from(element.size_len),
+    ))?;
+    file.write_all(&encode_size(size, element.size_len))?;
+    Ok(())
+}
+
+/// Overwrite `element` with a zero-filled void element spanning the same
+/// bytes, so the superseded copy cannot be mistaken for live content.
+fn void_element(file: &mut File, element: &ElementHeader) -> Result<(), AttachmentError> {
+    const ZEROS: [u8; 8192] = [0; 8192];
+    let end = element
+        .end
+        .ok_or_else(|| malformed("attachments element has unknown size"))?;
+    let total = end - element.start;
+    // An attachments element has a four-byte id, so at least two bytes
+    // remain for the size field after the one-byte void id.
+    let size_len = (total - 1).min(u64::from(MAX_SIZE_BYTES)) as u32;
+    let mut remaining = total - 1 - u64::from(size_len);
+    let mut header = vec![ID_VOID as u8];
+    header.extend(encode_size(remaining, size_len));
+    file.seek(SeekFrom::Start(element.start))?;
+    file.write_all(&header)?;
+    while remaining > 0 {
+        let len = remaining.min(ZEROS.len() as u64) as usize;
+        file.write_all(&ZEROS[..len])?;
+        remaining -= len as u64;
+    }
+    Ok(())
+}
+
+/// Encode an attached-file entry for `attachment`.
+fn encode_attached_file(attachment: &ContainerAttachment) -> Vec<u8> {
+    let mut file = encode_element(ID_FILE_NAME, attachment.name.as_bytes());
+    file.extend(encode_element(
+        ID_FILE_MIME_TYPE,
+        attachment.mime_type.as_bytes(),
+    ));
+    file.extend(encode_element(ID_FILE_DATA, &attachment.data));
+    let uid = rand::random::<u64>().max(1);
+    file.extend(encode_element(ID_FILE_UID, &uid.to_be_bytes()));
+    encode_element(ID_ATTACHED_FILE, &file)
+}
+
+/// Encode one element with an eight-byte size field.
+fn encode_element(id: u32, body: &[u8]) -> Vec<u8> {
+    let id_bytes = id.to_be_bytes();
+    let id_start = id_bytes.iter().position(|byte| *byte != 0).unwrap_or(3);
+    let mut out = id_bytes[id_start..].to_vec();
+    out.extend(encode_size(body.len() as u64, MAX_SIZE_BYTES));
+    out.extend_from_slice(body);
+    out
+}
+
+/// Encode `size` as an EBML size field of `len` bytes.
+fn encode_size(size: u64, len: u32) -> Vec<u8> {
+    let marked = size | (1_u64 << (7 * len));
+    marked.to_be_bytes()[(8 - len) as usize..].to_vec()
+}
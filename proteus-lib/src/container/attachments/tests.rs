@@ -0,0 +1,194 @@
+use std::io::Cursor;
+use std::path::Path;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::*;
+
+fn element(id: &[u8], body: &[u8]) -> Vec<u8> {
+    let mut out = id.to_vec();
+    out.push(0x01);
+    out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+    out.extend_from_slice(body);
+    out
+}
+
+fn container(attachment_body: &[u8]) -> Vec<u8> {
+    let attachments = element(&[0x19, 0x41, 0xA4, 0x69], attachment_body);
+    let mut out = element(&[0x1A, 0x45, 0xDF, 0xA3], &[]);
+    out.extend(element(&[0x18, 0x53, 0x80, 0x67], &attachments));
+    out
+}
+
+/// Ids of the segment's children in `bytes`, in file order.
+fn segment_children(bytes: &[u8]) -> Vec<u32> {
+    let mut reader = Cursor::new(bytes);
+    let end = bytes.len() as u64;
+    let header = read_element_header(&mut reader, 0, end).unwrap();
+    let position = header.end.unwrap();
+    reader.set_position(position);
+    let segment = read_element_header(&mut reader, position, end).unwrap();
+    let mut ids = Vec::new();
+    let mut position = segment.data_start;
+    while position < segment.end.unwrap() {
+        reader.set_position(position);
+        let child = read_element_header(&mut reader, position, end).unwrap();
+        ids.push(child.id);
+        position = child.end.unwrap();
+    }
+    ids
+}
+
+fn temp_container(name: &str, bytes: &[u8]) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("proteus-append-{}-{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("container.mka");
+    std::fs::write(&path, bytes).unwrap();
+    path
+}
+
+fn attached_file(name: &str, data: &[u8]) -> Vec<u8> {
+    let mut body = element(&[0x46, 0x6E], name.as_bytes());
+    body.extend(element(&[0x46, 0x60], b"application/json"));
+    body.extend(element(&[0x46, 0x5C], data));
+    element(&[0x61, 0xA7], &body)
+}
+
+#[test]
+fn reads_attachment_fields_from_minimal_container() {
+    let bytes = container(&attached_file("play_settings.json", b"{}"));
+    let attachments = read_attachments(&mut Cursor::new(bytes)).unwrap();
+    assert_eq!(
+        attachments,
+        vec![ContainerAttachment {
+            name: "play_settings.json".to_string(),
+            mime_type: "application/json".to_string(),
+            data: b"{}".to_vec(),
+        }]
+    );
+}
+
+#[test]
+fn reads_play_settings_from_fixture() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_audio/demo_shuffle_points.prot");
+    let attachments = read_attachments_from_path(path).unwrap();
+    let settings = attachments
+        .iter()
+        .find(|attachment| attachment.name == "play_settings.json")
+        .expect("fixture carries play settings");
+    assert!(serde_json::from_slice::<serde_json::Value>(&settings.data).is_ok());
+}
+
+#[test]
+fn oversized_element_is_rejected_without_allocating() {
+    let mut bytes = container(&attached_file("ir.wav", &[0; 16]));
+    let data_size_at = bytes.len() - 16 - 7;
+    bytes[data_size_at..data_size_at + 7]
+        .copy_from_slice(&[0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]);
+    assert!(matches!(
+        read_attachments(&mut Cursor::new(bytes)),
+        Err(AttachmentError::Malformed(_))
+    ));
+}
+
+#[test]
+fn non_ebml_input_is_rejected() {
+    assert!(read_attachments(&mut Cursor::new(b"{\"not\":\"mkv\"}".to_vec())).is_err());
+    assert!(read_attachments(&mut Cursor::new(Vec::new())).is_err());
+}
+
+#[test]
+fn corrupted_fixture_never_panics() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_audio/demo_shuffle_points.prot");
+    let original = std::fs::read(path).unwrap();
+    let mut rng = StdRng::seed_from_u64(0xA77A_C4ED);
+    for round in 0..200 {
+        let mut bytes = original.clone();
+        for _ in 0..rng.gen_range(1..8) {
+            let index = rng.gen_range(0..bytes.len().min(4096));
+            bytes[index] = rng.gen();
+        }
+        if round % 3 == 0 {
+            bytes.truncate(rng.gen_range(0..bytes.len()));
+        }
+        let _ = read_attachments(&mut Cursor::new(bytes));
+    }
+}
+
+#[test]
+fn matroska_is_detected_by_content_not_extension() {
+    assert!(is_matroska(&mut Cursor::new(container(&[]))));
+    assert!(!is_matroska(&mut Cursor::new(b"RIFF\0\0\0\0WAVE".to_vec())));
+    assert!(!is_matroska(&mut Cursor::new(vec![0x1A, 0x45])));
+
+    let audio = Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_audio");
+    assert!(is_matroska_path(audio.join("demo_shuffle_points.prot")));
+    assert!(!is_matroska_path(audio.join("test-24bit.flac")));
+    assert!(!is_matroska_path(audio.join("missing.mka")));
+}
+
+#[test]
+fn attachments_over_the_size_limit_are_refused() {
+    let bytes = container(&attached_file("ir.wav", &[0; 16]));
+    assert!(read_attachments_with_limit(&mut Cursor::new(bytes.clone()), 16).is_ok());
+    assert!(matches!(
+        read_attachments_with_limit(&mut Cursor::new(bytes), 15),
+        Err(AttachmentError::TooLarge {
+            size: 16,
+            limit: 15
+        })
+    ));
+}
+
+#[test]
+fn appended_attachments_join_the_existing_element() {
+    let path = temp_container(
+        "join",
+        &container(&attached_file("play_settings.json", b"{}")),
+    );
+    let extra = ContainerAttachment {
+        name: "notes.txt".to_string(),
+        mime_type: "text/plain".to_string(),
+        data: b"hello".to_vec(),
+    };
+    append_attachment(&path, &extra).unwrap();
+    append_attachment(&path, &extra).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    let attachments = read_attachments_from_path(&path).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    assert_eq!(segment_children(&bytes), [ID_ATTACHMENTS]);
+    let names: Vec<&str> = attachments.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["play_settings.json", "notes.txt", "notes.txt"]);
+    assert_eq!(attachments[1], extra);
+}
+
+#[test]
+fn leading_attachments_are_rewritten_at_the_end() {
+    let mut segment = element(
+        &[0x19, 0x41, 0xA4, 0x69],
+        &attached_file("play_settings.json", b"{}"),
+    );
+    segment.extend(element(&[0x1F, 0x43, 0xB6, 0x75], &[0; 8]));
+    let mut bytes = element(&[0x1A, 0x45, 0xDF, 0xA3], &[]);
+    bytes.extend(element(&[0x18, 0x53, 0x80, 0x67], &segment));
+    let path = temp_container("rewrite", &bytes);
+
+    let extra = ContainerAttachment {
+        name: "notes.txt".to_string(),
+        mime_type: "text/plain".to_string(),
+        data: b"hello".to_vec(),
+    };
+    append_attachment(&path, &extra).unwrap();
+    let rewritten = std::fs::read(&path).unwrap();
+    let attachments = read_attachments_from_path(&path).unwrap();
+    std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+    assert_eq!(
+        segment_children(&rewritten),
+        [0xEC, ID_CLUSTER, ID_ATTACHMENTS]
+    );
+    let names: Vec<&str> = attachments.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, ["play_settings.json", "notes.txt"]);
+}
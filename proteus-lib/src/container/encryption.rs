@@ -0,0 +1,534 @@
+//! AES-256-GCM encrypted track payloads.
+//!
+//! An encrypted container lists its encrypted tracks in an
+//! [`ENCRYPTION_ATTACHMENT_NAME`] attachment. Every packet of a listed track
+//! is stored as a 12-byte nonce followed by the AES-256-GCM ciphertext and
+//! tag of the original packet. The track id and the packet's timestamp are
+//! the associated data, so packets cannot be swapped between tracks or
+//! reordered within one. Codec headers stay in the clear so decoders can be
+//! built before any key is known. Each track names a key id, and may carry
+//! its content key wrapped (AES-256-GCM encrypted) by the key the provider
+//! returns for that id.
+//!
+//! Players resolve keys once through a key provider
+//! ([`Player::set_key_provider`](crate::playback::player::Player::set_key_provider)),
+//! which stores them on the player's container. Decode workers and offline
+//! renders of that container receive the keys with the container path and
+//! decrypt packets as they are demuxed; other readers of the same file see
+//! the stored ciphertext. Muxers build encrypted containers with
+//! [`encrypt_packet`], [`wrap_key`], and [`EncryptionManifest`].
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use symphonia::core::errors::{Error as SymphoniaError, Result as SymphoniaResult};
+use symphonia::core::formats::{
+    Cue, FormatOptions, FormatReader, Packet, SeekMode, SeekTo, SeekedTo, Track,
+};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::Metadata;
+
+use crate::container::attachments::{
+    read_attachments_from_path_with_limit, AttachmentError, ContainerAttachment,
+};
+use crate::container::limits::ParseLimits;
+use crate::container::prot::TrackKeys;
+
+/// Name of the attachment listing encrypted tracks.
+pub const ENCRYPTION_ATTACHMENT_NAME: &str = "encryption.json";
+
+/// The only supported scheme.
+pub const SCHEME_AES_256_GCM: &str = "aes-256-gcm";
+
+const NONCE_BYTES: usize = 12;
+/// Associated data binding wrapped keys to their purpose.
+const KEY_WRAP_AAD: &[u8] = b"proteus-key-wrap";
+
+/// Encrypted tracks of a container, stored as [`ENCRYPTION_ATTACHMENT_NAME`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptionManifest {
+    /// Encryption scheme; must be [`SCHEME_AES_256_GCM`].
+    pub scheme: String,
+    /// Encrypted tracks; tracks not listed are in the clear.
+    pub tracks: Vec<EncryptedTrack>,
+}
+
+/// One encrypted track in an [`EncryptionManifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedTrack {
+    /// Container track id.
+    pub track_id: u32,
+    /// Id passed to the key provider.
+    pub key_id: String,
+    /// Hex content key wrapped by the provider's key (see [`wrap_key`]);
+    /// without it the provider's key decrypts the track directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wrapped_key: Option<String>,
+}
+
+impl EncryptionManifest {
+    /// Manifest for the [`SCHEME_AES_256_GCM`] scheme.
+    pub fn new(tracks: Vec<EncryptedTrack>) -> Self {
+        Self {
+            scheme: SCHEME_AES_256_GCM.to_string(),
+            tracks,
+        }
+    }
+
+    /// The manifest as an attachment ready to mux.
+    pub fn to_attachment(&self) -> ContainerAttachment {
+        ContainerAttachment {
+            name: ENCRYPTION_ATTACHMENT_NAME.to_string(),
+            mime_type: "application/json".to_string(),
+            data: serde_json::to_vec_pretty(self).unwrap_or_default(),
+        }
+    }
+
+    /// Resolve each track's content key through `provider`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`EncryptionError`] for an unsupported scheme, a key id the
+    /// provider does not know, or a wrapped key that does not unwrap.
+    pub fn resolve_keys(
+        &self,
+        provider: impl Fn(&str) -> Option<[u8; 32]>,
+    ) -> Result<HashMap<u32, [u8; 32]>, EncryptionError> {
+        if self.scheme != SCHEME_AES_256_GCM {
+            return Err(EncryptionError::UnsupportedScheme(self.scheme.clone()));
+        }
+        let mut keys = HashMap::with_capacity(self.tracks.len());
+        for track in &self.tracks {
+            let key = provider(&track.key_id)
+                .ok_or_else(|| EncryptionError::MissingKey(track.key_id.clone()))?;
+            let key = match &track.wrapped_key {
+                Some(wrapped) => unwrap_key(&key, wrapped)
+                    .ok_or_else(|| EncryptionError::KeyUnwrap(track.key_id.clone()))?,
+                None => key,
+            };
+            keys.insert(track.track_id, key);
+        }
+        Ok(keys)
+    }
+}
+
+/// Failure while resolving keys for an encrypted container.
+#[derive(Debug)]
+pub enum EncryptionError {
+    /// The container's attachments could not be read.
+    Attachments(AttachmentError),
+    /// The encryption manifest is not valid JSON of the expected shape.
+    Manifest(serde_json::Error),
+    /// The manifest names a scheme other than [`SCHEME_AES_256_GCM`].
+    UnsupportedScheme(String),
+    /// The key provider has no key for this id.
+    MissingKey(String),
+    /// The wrapped key for this id did not decrypt with the provider's key.
+    KeyUnwrap(String),
+    /// AES-256-GCM refused to encrypt a packet or key.
+    Encrypt(aes_gcm::Error),
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attachments(err) => write!(f, "{}", err),
+            Self::Manifest(err) => write!(f, "invalid {}: {}", ENCRYPTION_ATTACHMENT_NAME, err),
+            Self::UnsupportedScheme(scheme) => {
+                write!(f, "unsupported encryption scheme: {}", scheme)
+            }
+            Self::MissingKey(key_id) => write!(f, "no key for key id '{}'", key_id),
+            Self::KeyUnwrap(key_id) => write!(f, "failed to unwrap key for key id '{}'", key_id),
+            Self::Encrypt(err) => write!(f, "encryption failed: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for EncryptionError {}
+
+impl From<AttachmentError> for EncryptionError {
+    fn from(err: AttachmentError) -> Self {
+        Self::Attachments(err)
+    }
+}
+
+/// Read the encryption manifest of the container at `path`; `None` when no
+/// track is encrypted.
+///
+/// # Errors
+///
+/// Returns [`EncryptionError`] when the container or manifest cannot be read.
+pub fn read_encryption_manifest(
+    path: impl AsRef<Path>,
+) -> Result<Option<EncryptionManifest>, EncryptionError> {
+    read_encryption_manifest_with_limits(path, &ParseLimits::unlimited())
+}
+
+/// [`read_encryption_manifest`] refusing attachments larger than
+/// `limits.max_attachment_bytes`.
+///
+/// # Errors
+///
+/// Returns [`EncryptionError::Attachments`] with
+/// [`AttachmentError::TooLarge`] for an oversized attachment, plus the
+/// errors of [`read_encryption_manifest`].
+pub fn read_encryption_manifest_with_limits(
+    path: impl AsRef<Path>,
+    limits: &ParseLimits,
+) -> Result<Option<EncryptionManifest>, EncryptionError> {
+    read_attachments_from_path_with_limit(path, limits.max_attachment_bytes)?
+        .iter()
+        .find(|attachment| attachment.name == ENCRYPTION_ATTACHMENT_NAME)
+        .map(|attachment| serde_json::from_slice(&attachment.data))
+        .transpose()
+        .map_err(EncryptionError::Manifest)
+}
+
+/// Encrypt one packet of `track_id` for storage in a container.
+///
+/// `timestamp` is the packet's timestamp as the demuxer reports it, in the
+/// track's time base; for Matroska, the block's timestamp in segment
+/// timestamp-scale units.
+///
+/// # Errors
+///
+/// Returns [`EncryptionError::Encrypt`] when AES-256-GCM refuses the packet.
+pub fn encrypt_packet(
+    key: &[u8; 32],
+    track_id: u32,
+    timestamp: u64,
+    packet: &[u8],
+) -> Result<Vec<u8>, EncryptionError> {
+    seal(key, &packet_aad(track_id, timestamp), packet)
+}
+
+/// Decrypt one stored packet of `track_id` at `timestamp`; `None` when it
+/// does not authenticate, including when it was moved to another track or
+/// position.
+pub fn decrypt_packet(
+    key: &[u8; 32],
+    track_id: u32,
+    timestamp: u64,
+    stored: &[u8],
+) -> Option<Vec<u8>> {
+    open(key, &packet_aad(track_id, timestamp), stored)
+}
+
+/// Associated data binding a packet to its track and timestamp.
+fn packet_aad(track_id: u32, timestamp: u64) -> [u8; 12] {
+    let mut aad = [0_u8; 12];
+    aad[..4].copy_from_slice(&track_id.to_le_bytes());
+    aad[4..].copy_from_slice(&timestamp.to_le_bytes());
+    aad
+}
+
+/// Wrap `content_key` with `wrapping_key`, hex encoded for
+/// [`EncryptedTrack::wrapped_key`].
+///
+/// # Errors
+///
+/// Returns [`EncryptionError::Encrypt`] when AES-256-GCM refuses the key.
+pub fn wrap_key(
+    wrapping_key: &[u8; 32],
+    content_key: &[u8; 32],
+) -> Result<String, EncryptionError> {
+    Ok(seal(wrapping_key, KEY_WRAP_AAD, content_key)?
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+fn unwrap_key(wrapping_key: &[u8; 32], wrapped: &str) -> Option<[u8; 32]> {
+    if !wrapped.len().is_multiple_of(2) || !wrapped.is_ascii() {
+        return None;
+    }
+    let bytes = (0..wrapped.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&wrapped[at..at + 2], 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    open(wrapping_key, KEY_WRAP_AAD, &bytes)?.try_into().ok()
+}
+
+fn seal(key: &[u8; 32], aad: &[u8], msg: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let mut nonce = [0_u8; NONCE_BYTES];
+    rand::thread_rng().fill_bytes(&mut nonce);
+    let ciphertext = Aes256Gcm::new(key.into())
+        .encrypt(Nonce::from_slice(&nonce), Payload { msg, aad })
+        .map_err(EncryptionError::Encrypt)?;
+    let mut stored = nonce.to_vec();
+    stored.extend(ciphertext);
+    Ok(stored)
+}
+
+fn open(key: &[u8; 32], aad: &[u8], stored: &[u8]) -> Option<Vec<u8>> {
+    if stored.len() < NONCE_BYTES {
+        return None;
+    }
+    let (nonce, msg) = stored.split_at(NONCE_BYTES);
+    Aes256Gcm::new(key.into())
+        .decrypt(Nonce::from_slice(nonce), Payload { msg, aad })
+        .ok()
+}
+
+/// Wrap `format` so it decrypts the tracks that have a key in `keys`;
+/// returns it unchanged when `keys` is empty.
+pub(crate) fn decrypting_reader(
+    format: Box<dyn FormatReader>,
+    keys: &Arc<TrackKeys>,
+) -> Box<dyn FormatReader> {
+    if keys.is_empty() {
+        return format;
+    }
+    Box::new(DecryptingReader {
+        inner: format,
+        keys: keys.clone(),
+    })
+}
+
+/// Format reader that decrypts packets of keyed tracks as they are read.
+struct DecryptingReader {
+    inner: Box<dyn FormatReader>,
+    keys: Arc<TrackKeys>,
+}
+
+impl FormatReader for DecryptingReader {
+    fn try_new(_source: MediaSourceStream, _options: &FormatOptions) -> SymphoniaResult<Self> {
+        Err(SymphoniaError::Unsupported(
+            "decrypting readers wrap an opened reader",
+        ))
+    }
+
+    fn cues(&self) -> &[Cue] {
+        self.inner.cues()
+    }
+
+    fn metadata(&mut self) -> Metadata<'_> {
+        self.inner.metadata()
+    }
+
+    fn seek(&mut self, mode: SeekMode, to: SeekTo) -> SymphoniaResult<SeekedTo> {
+        self.inner.seek(mode, to)
+    }
+
+    fn tracks(&self) -> &[Track] {
+        self.inner.tracks()
+    }
+
+    fn next_packet(&mut self) -> SymphoniaResult<Packet> {
+        let mut packet = self.inner.next_packet()?;
+        if let Some(key) = self.keys.get(&packet.track_id()) {
+            packet.data = decrypt_packet(key, packet.track_id(), packet.ts(), &packet.data)
+                .ok_or(SymphoniaError::DecodeError(
+                    "encrypted packet failed to authenticate",
+                ))?
+                .into_boxed_slice();
+        }
+        Ok(packet)
+    }
+
+    fn into_inner(self: Box<Self>) -> MediaSourceStream {
+        self.inner.into_inner()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Seconds of the fixture muxed into test containers.
+    const SECONDS: u64 = 2;
+
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let start = id_bytes.iter().position(|byte| *byte != 0).unwrap();
+        let mut out = id_bytes[start..].to_vec();
+        out.push(0x01);
+        out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn uint(id: u32, value: u64) -> Vec<u8> {
+        element(id, &value.to_be_bytes())
+    }
+
+    /// FLAC frames of the first seconds of the test fixture.
+    pub(crate) struct FlacFrames {
+        sample_rate: u32,
+        channels: u64,
+        stream_info: Box<[u8]>,
+        /// `(timestamp, frame)` pairs.
+        pub(crate) frames: Vec<(u64, Vec<u8>)>,
+    }
+
+    pub(crate) fn fixture_frames() -> FlacFrames {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_audio/test-24bit.flac");
+        let mut format = crate::tools::decode::get_reader(path).unwrap();
+        let params = format.default_track().unwrap().codec_params.clone();
+        let sample_rate = params.sample_rate.unwrap();
+        let mut frames = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            if packet.ts() >= SECONDS * u64::from(sample_rate) {
+                break;
+            }
+            frames.push((packet.ts(), packet.data.to_vec()));
+        }
+        FlacFrames {
+            sample_rate,
+            channels: params.channels.unwrap().count() as u64,
+            stream_info: params.extra_data.unwrap(),
+            frames,
+        }
+    }
+
+    /// Write a one-track FLAC Matroska file whose frames pass through
+    /// `seal_packet` with their block timestamp, plus `attachments`.
+    pub(crate) fn write_flac_container(
+        path: &Path,
+        flac: &FlacFrames,
+        seal_packet: impl Fn(u64, &[u8]) -> Vec<u8>,
+        attachments: &[ContainerAttachment],
+    ) {
+        let header = [
+            uint(0x4286, 1),
+            uint(0x42F7, 1),
+            uint(0x42F2, 4),
+            uint(0x42F3, 8),
+            element(0x4282, b"matroska"),
+            uint(0x4287, 4),
+            uint(0x4285, 2),
+        ]
+        .concat();
+        let info = element(0x1549_A966, &uint(0x2A_D7B1, 1_000_000));
+        let audio = [
+            element(0xB5, &f64::from(flac.sample_rate).to_be_bytes()),
+            uint(0x9F, flac.channels),
+        ]
+        .concat();
+        let mut codec_private = b"fLaC".to_vec();
+        codec_private.extend_from_slice(&[0x80, 0, 0, flac.stream_info.len() as u8]);
+        codec_private.extend_from_slice(&flac.stream_info);
+        let track = [
+            uint(0xD7, 1),
+            uint(0x73C5, 1),
+            uint(0x83, 2),
+            element(0x86, b"A_FLAC"),
+            element(0x63A2, &codec_private),
+            element(0xE1, &audio),
+        ]
+        .concat();
+        let tracks = element(0x1654_AE6B, &element(0xAE, &track));
+        let mut cluster = uint(0xE7, 0);
+        for (ts, frame) in &flac.frames {
+            let timecode = (ts * 1000 / u64::from(flac.sample_rate)) as i16;
+            let mut block = vec![0x81];
+            block.extend_from_slice(&timecode.to_be_bytes());
+            block.push(0x80);
+            block.extend(seal_packet(timecode as u64, frame));
+            cluster.extend(element(0xA3, &block));
+        }
+        let mut attached = Vec::new();
+        for attachment in attachments {
+            let file = [
+                element(0x466E, attachment.name.as_bytes()),
+                element(0x4660, attachment.mime_type.as_bytes()),
+                element(0x465C, &attachment.data),
+                uint(0x46AE, 1 + attached.len() as u64),
+            ]
+            .concat();
+            attached.extend(element(0x61A7, &file));
+        }
+        let segment = [
+            info,
+            tracks,
+            element(0x1F43_B675, &cluster),
+            element(0x1941_A469, &attached),
+        ]
+        .concat();
+        let mut bytes = element(0x1A45_DFA3, &header);
+        bytes.extend(element(0x1853_8067, &segment));
+        std::fs::write(path, bytes).unwrap();
+    }
+
+    pub(crate) fn temp_path(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!(
+            "proteus_encryption_{}_{}.mka",
+            name,
+            std::process::id()
+        ))
+    }
+
+    fn read_packets(path: &str, keys: &Arc<TrackKeys>) -> Vec<Vec<u8>> {
+        let mut format = crate::tools::decode::get_container_reader(path, keys, None).unwrap();
+        let mut packets = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            packets.push(packet.data.to_vec());
+        }
+        packets
+    }
+
+    #[test]
+    fn packets_and_wrapped_keys_round_trip() {
+        let key = [7_u8; 32];
+        let stored = encrypt_packet(&key, 3, 960, b"pcm").unwrap();
+        assert_eq!(
+            decrypt_packet(&key, 3, 960, &stored).as_deref(),
+            Some(&b"pcm"[..])
+        );
+        assert_eq!(decrypt_packet(&key, 4, 960, &stored), None);
+        assert_eq!(decrypt_packet(&key, 3, 0, &stored), None);
+        assert_eq!(decrypt_packet(&[8; 32], 3, 960, &stored), None);
+
+        let content_key = [9_u8; 32];
+        let manifest = EncryptionManifest::new(vec![EncryptedTrack {
+            track_id: 1,
+            key_id: "label".to_string(),
+            wrapped_key: Some(wrap_key(&key, &content_key).unwrap()),
+        }]);
+        let keys = manifest
+            .resolve_keys(|id| (id == "label").then_some(key))
+            .unwrap();
+        assert_eq!(keys[&1], content_key);
+        assert!(matches!(
+            manifest.resolve_keys(|_| None),
+            Err(EncryptionError::MissingKey(_))
+        ));
+        assert!(matches!(
+            manifest.resolve_keys(|_| Some([1; 32])),
+            Err(EncryptionError::KeyUnwrap(_))
+        ));
+    }
+
+    #[test]
+    fn keyed_readers_decrypt_packets_as_they_are_read() {
+        let key = [5_u8; 32];
+        let path = temp_path("reader");
+        let flac = fixture_frames();
+        let manifest = EncryptionManifest::new(vec![EncryptedTrack {
+            track_id: 1,
+            key_id: "k".to_string(),
+            wrapped_key: None,
+        }]);
+        write_flac_container(
+            &path,
+            &flac,
+            |ts, pcm| encrypt_packet(&key, 1, ts, pcm).unwrap(),
+            &[manifest.to_attachment()],
+        );
+        let path_str = path.display().to_string();
+
+        let manifest = read_encryption_manifest(&path).unwrap().unwrap();
+        let plain: Vec<Vec<u8>> = flac.frames.iter().map(|(_, frame)| frame.clone()).collect();
+        assert_ne!(read_packets(&path_str, &Arc::default()), plain);
+
+        let keys = Arc::new(manifest.resolve_keys(|_| Some(key)).unwrap());
+        assert_eq!(read_packets(&path_str, &keys), plain);
+        std::fs::remove_file(path).unwrap();
+    }
+}
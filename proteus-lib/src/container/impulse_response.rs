@@ -0,0 +1,140 @@
+//! Impulse responses stored as container attachments.
+//!
+//! Convolution reverbs only decode impulse response bytes; reading them out
+//! of a `.prot`/`.mka` container happens here, before playback builds the
+//! [`EffectContext`](crate::dsp::effects::EffectContext).
+
+use std::fmt;
+use std::path::Path;
+
+use log::info;
+
+use crate::container::attachments::{read_attachments_with_download, AttachmentError};
+use crate::dsp::effects::convolution_reverb::impulse_response::{
+    load_impulse_response_from_bytes_with_tail, ImpulseResponse, ImpulseResponseError,
+};
+use crate::dsp::effects::convolution_reverb::ImpulseResponseAttachments;
+use crate::tools::progressive::ProgressiveFile;
+
+/// Failure loading an impulse response attachment.
+#[derive(Debug)]
+pub enum AttachedImpulseResponseError {
+    /// The container attachments could not be read.
+    Attachments(AttachmentError),
+    /// No attachment with the given name was found in the container.
+    NotFound(String),
+    /// The attachment did not decode as an impulse response.
+    Decode(ImpulseResponseError),
+}
+
+impl fmt::Display for AttachedImpulseResponseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attachments(err) => write!(f, "failed to read prot container: {}", err),
+            Self::NotFound(name) => {
+                write!(f, "impulse response attachment not found: {}", name)
+            }
+            Self::Decode(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for AttachedImpulseResponseError {}
+
+impl From<AttachmentError> for AttachedImpulseResponseError {
+    fn from(err: AttachmentError) -> Self {
+        Self::Attachments(err)
+    }
+}
+
+impl From<ImpulseResponseError> for AttachedImpulseResponseError {
+    fn from(err: ImpulseResponseError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+/// Load an impulse response attachment from a `.prot`/`.mka` container.
+pub fn load_impulse_response_from_prot_attachment(
+    prot_path: impl AsRef<Path>,
+    attachment_name: &str,
+) -> Result<ImpulseResponse, AttachedImpulseResponseError> {
+    load_impulse_response_from_prot_attachment_with_tail(prot_path, attachment_name, Some(-60.0))
+}
+
+/// Load a container attachment and optionally trim its tail in dB.
+pub fn load_impulse_response_from_prot_attachment_with_tail(
+    prot_path: impl AsRef<Path>,
+    attachment_name: &str,
+    tail_db: Option<f32>,
+) -> Result<ImpulseResponse, AttachedImpulseResponseError> {
+    load_impulse_response_from_prot_attachment_with_limit(
+        prot_path,
+        attachment_name,
+        tail_db,
+        u64::MAX,
+    )
+}
+
+/// [`load_impulse_response_from_prot_attachment_with_tail`] refusing
+/// attachments larger than `max_bytes`.
+pub fn load_impulse_response_from_prot_attachment_with_limit(
+    prot_path: impl AsRef<Path>,
+    attachment_name: &str,
+    tail_db: Option<f32>,
+    max_bytes: u64,
+) -> Result<ImpulseResponse, AttachedImpulseResponseError> {
+    let mut attachments = ImpulseResponseAttachments::default();
+    let names = [attachment_name.to_string()];
+    read_impulse_response_attachments(prot_path, None, &names, max_bytes, &mut attachments)?;
+    let bytes = attachments
+        .get(attachment_name)
+        .ok_or_else(|| AttachedImpulseResponseError::NotFound(attachment_name.to_string()))?;
+    Ok(load_impulse_response_from_bytes_with_tail(bytes, tail_db)?)
+}
+
+/// Read the attachments called `names` into `attachments`, through
+/// `download` while the container still arrives.
+///
+/// Names the container does not carry are left out.
+pub(crate) fn read_impulse_response_attachments(
+    prot_path: impl AsRef<Path>,
+    download: Option<&ProgressiveFile>,
+    names: &[String],
+    max_bytes: u64,
+    attachments: &mut ImpulseResponseAttachments,
+) -> Result<(), AttachmentError> {
+    for attachment in read_attachments_with_download(prot_path, download, max_bytes)? {
+        let name = attachment.name.trim_matches('"');
+        if names.iter().any(|wanted| wanted == name) {
+            info!("read impulse response attachment {}", name);
+            attachments.insert(name, attachment.data);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::demo_container_with_impulse_response;
+
+    #[test]
+    fn impulse_responses_load_from_container_attachments() {
+        let path = demo_container_with_impulse_response("impulse_response", "load");
+        let impulse_response =
+            load_impulse_response_from_prot_attachment(&path, "hall.wav").unwrap();
+        assert_eq!(impulse_response.sample_rate, 48_000);
+        assert_eq!(impulse_response.channel_count(), 1);
+        assert!(matches!(
+            load_impulse_response_from_prot_attachment(&path, "room.wav"),
+            Err(AttachedImpulseResponseError::NotFound(name)) if name == "room.wav"
+        ));
+        assert!(matches!(
+            load_impulse_response_from_prot_attachment_with_limit(&path, "hall.wav", None, 16),
+            Err(AttachedImpulseResponseError::Attachments(
+                AttachmentError::TooLarge { .. }
+            ))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
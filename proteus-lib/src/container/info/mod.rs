@@ -1,6 +1,7 @@
 //! Container metadata helpers and duration probing.
 
 mod aiff;
+mod scan;
 mod track_info;
 
 use std::{collections::HashMap, fs::File, path::Path};
@@ -11,14 +12,18 @@ use symphonia::core::{
     codecs::CodecParameters,
     errors::Error,
     formats::FormatOptions,
-    io::{MediaSource, MediaSourceStream, ReadOnlySource},
+    io::{MediaSource, MediaSourceStream},
     meta::MetadataOptions,
     probe::{Hint, ProbeResult},
-    units::TimeBase,
 };
 
+pub use scan::try_get_durations_by_scan_with_progress;
 use track_info::{gather_track_info, gather_track_info_from_file_paths};
 
+use crate::tools::progress::Progress;
+use crate::tools::progressive::ProgressiveFile;
+use crate::tools::stdin::{is_stdin_path, StdinSource};
+
 /// Error returned when combining metadata from audio files with incompatible formats.
 #[derive(Debug)]
 pub enum InfoError {
@@ -28,6 +33,8 @@ pub enum InfoError {
     ProbeFailed(String),
     /// No audio tracks were found in the media source.
     NoTracksFound,
+    /// The operation was cancelled through its [`CancellationToken`](crate::tools::progress::CancellationToken).
+    Cancelled,
 }
 
 impl std::fmt::Display for InfoError {
@@ -36,6 +43,7 @@ impl std::fmt::Display for InfoError {
             Self::IncompatibleTracks(msg) => write!(f, "incompatible tracks: {}", msg),
             Self::ProbeFailed(msg) => write!(f, "probe failed: {}", msg),
             Self::NoTracksFound => write!(f, "no tracks found in media source"),
+            Self::Cancelled => write!(f, "cancelled"),
         }
     }
 }
@@ -59,12 +67,31 @@ pub fn get_time_from_frames(codec_params: &CodecParameters) -> f64 {
 
 /// Probe a media file (or stdin `-`) and return the Symphonia probe result.
 pub fn get_probe_result_from_string(file_path: &str) -> Result<ProbeResult, Error> {
-    // If the path string is '-' then read from standard input.
-    if file_path == "-" {
-        let source = Box::new(ReadOnlySource::new(std::io::stdin())) as Box<dyn MediaSource>;
-        return probe_with_hint(source, None);
+    get_probe_result_with_download(file_path, None)
+}
+
+/// [`get_probe_result_from_string`], reading through `download` while the
+/// file is still arriving.
+pub(crate) fn get_probe_result_with_download(
+    file_path: &str,
+    download: Option<&ProgressiveFile>,
+) -> Result<ProbeResult, Error> {
+    if is_stdin_path(file_path) {
+        return probe_with_hint(Box::new(StdinSource::open()), None);
+    }
+    if let Some(download) = download.filter(|download| download.is_downloading()) {
+        return probe_with_hint(Box::new(download.open()?), None);
     }
 
+    probe_path_with(file_path, |file| Box::new(file))
+}
+
+// Probe `file_path` with extension hints, wrapping each opened file in a
+// media source built by `wrap`.
+fn probe_path_with(
+    file_path: &str,
+    wrap: impl Fn(File) -> Box<dyn MediaSource>,
+) -> Result<ProbeResult, Error> {
     let path = Path::new(file_path);
     let ext = path
         .extension()
@@ -94,8 +121,7 @@ pub fn get_probe_result_from_string(file_path: &str) -> Result<ProbeResult, Erro
             Ok(f) => f,
             Err(e) => return Err(Error::IoError(e)),
         };
-        let source = Box::new(file) as Box<dyn MediaSource>;
-        if let Ok(probed) = probe_with_hint(source, hint.as_deref()) {
+        if let Ok(probed) = probe_with_hint(wrap(file), hint.as_deref()) {
             return Ok(probed);
         }
     }
@@ -127,7 +153,14 @@ fn probe_with_hint(
 ///
 /// For container files, this may be approximate if metadata is inaccurate.
 pub fn get_durations(file_path: &str) -> HashMap<u32, f64> {
-    match try_get_durations(file_path) {
+    get_durations_with_download(file_path, None)
+}
+
+fn get_durations_with_download(
+    file_path: &str,
+    download: Option<&ProgressiveFile>,
+) -> HashMap<u32, f64> {
+    match try_get_durations_with_download(file_path, download) {
         Ok(durations) => durations,
         Err(err) => {
             warn!(
@@ -145,7 +178,14 @@ pub fn get_durations(file_path: &str) -> HashMap<u32, f64> {
 ///
 /// Returns [`InfoError`] when probing fails or no tracks are available.
 pub fn try_get_durations(file_path: &str) -> Result<HashMap<u32, f64>, InfoError> {
-    let mut probed = get_probe_result_from_string(file_path)
+    try_get_durations_with_download(file_path, None)
+}
+
+fn try_get_durations_with_download(
+    file_path: &str,
+    download: Option<&ProgressiveFile>,
+) -> Result<HashMap<u32, f64>, InfoError> {
+    let mut probed = get_probe_result_with_download(file_path, download)
         .map_err(|err| InfoError::ProbeFailed(err.to_string()))?;
 
     let mut durations: Vec<f64> = Vec::new();
@@ -218,42 +258,7 @@ pub fn get_durations_by_scan(file_path: &str) -> HashMap<u32, f64> {
 ///
 /// Returns [`InfoError`] when probing fails or when no tracks are present.
 pub fn try_get_durations_by_scan(file_path: &str) -> Result<HashMap<u32, f64>, InfoError> {
-    let mut probed = get_probe_result_from_string(file_path)
-        .map_err(|err| InfoError::ProbeFailed(err.to_string()))?;
-    if probed.format.tracks().is_empty() {
-        return Err(InfoError::NoTracksFound);
-    }
-    let mut max_ts: HashMap<u32, u64> = HashMap::new();
-    let mut time_bases: HashMap<u32, Option<TimeBase>> = HashMap::new();
-    let mut sample_rates: HashMap<u32, Option<u32>> = HashMap::new();
-
-    for track in probed.format.tracks().iter() {
-        max_ts.insert(track.id, 0);
-        time_bases.insert(track.id, track.codec_params.time_base);
-        sample_rates.insert(track.id, track.codec_params.sample_rate);
-    }
-
-    while let Ok(packet) = probed.format.next_packet() {
-        let entry = max_ts.entry(packet.track_id()).or_insert(0);
-        if packet.ts() > *entry {
-            *entry = packet.ts();
-        }
-    }
-
-    let mut duration_map: HashMap<u32, f64> = HashMap::new();
-    for (track_id, ts) in max_ts {
-        let seconds = if let Some(time_base) = time_bases.get(&track_id).copied().flatten() {
-            let time = time_base.calc_time(ts);
-            time.seconds as f64 + time.frac
-        } else if let Some(sample_rate) = sample_rates.get(&track_id).copied().flatten() {
-            ts as f64 / sample_rate as f64
-        } else {
-            0.0
-        };
-        duration_map.insert(track_id, seconds);
-    }
-
-    Ok(duration_map)
+    try_get_durations_by_scan_with_progress(file_path, &mut Progress::new())
 }
 
 /// Aggregate codec information for a track.
@@ -287,11 +292,25 @@ impl Info {
     ///
     /// Uses metadata-based duration probing first and falls back to a full
     /// packet scan only when metadata is missing or all-zero.
+    /// Standard input (`-`) is never scanned, since that would wait for the
+    /// whole stream; tracks whose header gives no length report `0.0`.
     pub fn new(file_path: String) -> Self {
-        let track_info = gather_track_info(&file_path);
+        Self::new_with_download(file_path, None)
+    }
+
+    /// [`Self::new`], reading through `download` while the file is still
+    /// arriving; like standard input, such a file is never scanned.
+    pub(crate) fn new_with_download(file_path: String, download: Option<&ProgressiveFile>) -> Self {
+        let track_info = gather_track_info(&file_path, download);
+        let downloading = download.is_some_and(ProgressiveFile::is_downloading);
+        let duration_map = if is_stdin_path(&file_path) || downloading {
+            get_durations_with_download(&file_path, download)
+        } else {
+            get_durations_best_effort(&file_path)
+        };
 
         Self {
-            duration_map: get_durations_best_effort(&file_path),
+            duration_map,
             file_paths: vec![file_path],
             channels: track_info.channel_count,
             sample_rate: track_info.sample_rate,
@@ -299,6 +318,39 @@ impl Info {
         }
     }
 
+    /// Build info for a single container file, reporting scan progress.
+    ///
+    /// Metadata durations are used when present. Otherwise the packet scan
+    /// reports progress and stops once `progress` is cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`InfoError::Cancelled`] when cancelled, or the scan error when
+    /// the file cannot be probed.
+    pub fn try_new_with_progress(
+        file_path: String,
+        progress: &mut Progress<'_>,
+    ) -> Result<Self, InfoError> {
+        progress.check().map_err(|_| InfoError::Cancelled)?;
+        let track_info = gather_track_info(&file_path, None);
+        let durations = get_durations(&file_path);
+        let duration_map = if !durations.is_empty() && durations.values().any(|value| *value > 0.0)
+        {
+            progress.report(1.0);
+            durations
+        } else {
+            try_get_durations_by_scan_with_progress(&file_path, progress)?
+        };
+
+        Ok(Self {
+            duration_map,
+            file_paths: vec![file_path],
+            channels: track_info.channel_count,
+            sample_rate: track_info.sample_rate,
+            bits_per_sample: track_info.bits_per_sample,
+        })
+    }
+
     /// Build info for a list of standalone files.
     ///
     /// Uses metadata when available and falls back to scanning.
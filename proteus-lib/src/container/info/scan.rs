@@ -0,0 +1,140 @@
+//! Packet-scan duration measurement with progress and cancellation.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use symphonia::core::io::MediaSource;
+use symphonia::core::units::TimeBase;
+
+use super::{probe_path_with, InfoError};
+use crate::tools::progress::Progress;
+
+/// Strict packet-scan duration mapping per track, reporting progress.
+///
+/// Progress is the fraction of the file read so far.
+///
+/// # Errors
+///
+/// Returns [`InfoError`] when probing fails, when no tracks are present, or
+/// [`InfoError::Cancelled`] once `progress` is cancelled.
+pub fn try_get_durations_by_scan_with_progress(
+    file_path: &str,
+    progress: &mut Progress<'_>,
+) -> Result<HashMap<u32, f64>, InfoError> {
+    let position = Arc::new(AtomicU64::new(0));
+    let file_len = std::fs::metadata(file_path).map_or(0, |meta| meta.len());
+    let mut probed = probe_path_with(file_path, |file| {
+        position.store(0, Ordering::Relaxed);
+        Box::new(TrackedFile {
+            file,
+            position: position.clone(),
+        })
+    })
+    .map_err(|err| InfoError::ProbeFailed(err.to_string()))?;
+    if probed.format.tracks().is_empty() {
+        return Err(InfoError::NoTracksFound);
+    }
+    let mut max_ts: HashMap<u32, u64> = HashMap::new();
+    let mut time_bases: HashMap<u32, Option<TimeBase>> = HashMap::new();
+    let mut sample_rates: HashMap<u32, Option<u32>> = HashMap::new();
+
+    for track in probed.format.tracks().iter() {
+        max_ts.insert(track.id, 0);
+        time_bases.insert(track.id, track.codec_params.time_base);
+        sample_rates.insert(track.id, track.codec_params.sample_rate);
+    }
+
+    while let Ok(packet) = probed.format.next_packet() {
+        if progress.is_cancelled() {
+            return Err(InfoError::Cancelled);
+        }
+        progress.report_ratio(position.load(Ordering::Relaxed), file_len);
+        let entry = max_ts.entry(packet.track_id()).or_insert(0);
+        if packet.ts() > *entry {
+            *entry = packet.ts();
+        }
+    }
+
+    let mut duration_map: HashMap<u32, f64> = HashMap::new();
+    for (track_id, ts) in max_ts {
+        let seconds = if let Some(time_base) = time_bases.get(&track_id).copied().flatten() {
+            let time = time_base.calc_time(ts);
+            time.seconds as f64 + time.frac
+        } else if let Some(sample_rate) = sample_rates.get(&track_id).copied().flatten() {
+            ts as f64 / sample_rate as f64
+        } else {
+            0.0
+        };
+        duration_map.insert(track_id, seconds);
+    }
+
+    progress.report(1.0);
+    Ok(duration_map)
+}
+
+/// File source that publishes its read position for progress reporting.
+struct TrackedFile {
+    file: File,
+    position: Arc<AtomicU64>,
+}
+
+impl Read for TrackedFile {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.file.read(buf)?;
+        self.position.fetch_add(read as u64, Ordering::Relaxed);
+        Ok(read)
+    }
+}
+
+impl Seek for TrackedFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.file.seek(pos)?;
+        self.position.store(position, Ordering::Relaxed);
+        Ok(position)
+    }
+}
+
+impl MediaSource for TrackedFile {
+    fn is_seekable(&self) -> bool {
+        self.file.is_seekable()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.file.byte_len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::progress::CancellationToken;
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_audio/demo_shuffle_points.prot"
+    );
+
+    #[test]
+    fn scan_reports_progress_up_to_completion() {
+        let mut fractions = Vec::new();
+        let durations = {
+            let mut progress = Progress::new().with_callback(|fraction| fractions.push(fraction));
+            try_get_durations_by_scan_with_progress(FIXTURE, &mut progress).unwrap()
+        };
+        assert!(!durations.is_empty());
+        assert_eq!(fractions.last(), Some(&1.0));
+        assert!(fractions.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+
+    #[test]
+    fn cancelled_scan_stops_with_an_error() {
+        let token = CancellationToken::new();
+        token.cancel();
+        let mut progress = Progress::new().with_cancellation(token);
+        let result = try_get_durations_by_scan_with_progress(FIXTURE, &mut progress);
+        assert!(matches!(result, Err(InfoError::Cancelled)));
+    }
+}
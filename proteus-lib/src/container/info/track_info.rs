@@ -11,7 +11,8 @@ use symphonia::core::{
 };
 
 use super::aiff::fallback_track_info;
-use super::{get_probe_result_from_string, InfoError, TrackInfo};
+use super::{get_probe_result_with_download, InfoError, TrackInfo};
+use crate::tools::progressive::ProgressiveFile;
 
 pub(super) fn get_track_info(track: &Track) -> TrackInfo {
     let codec_params = &track.codec_params;
@@ -54,8 +55,8 @@ fn bits_from_sample_format(sample_format: Option<SampleFormat>) -> u32 {
     }
 }
 
-fn bits_from_decode(file_path: &str) -> u32 {
-    let mut probed = match get_probe_result_from_string(file_path) {
+fn bits_from_decode(file_path: &str, download: Option<&ProgressiveFile>) -> u32 {
+    let mut probed = match get_probe_result_with_download(file_path, download) {
         Ok(probed) => probed,
         Err(_) => return 0,
     };
@@ -180,8 +181,8 @@ pub(super) fn reduce_track_infos(track_infos: Vec<TrackInfo>) -> Result<TrackInf
     }))
 }
 
-pub(super) fn gather_track_info(file_path: &str) -> TrackInfo {
-    let probed = match get_probe_result_from_string(file_path) {
+pub(super) fn gather_track_info(file_path: &str, download: Option<&ProgressiveFile>) -> TrackInfo {
+    let probed = match get_probe_result_with_download(file_path, download) {
         Ok(probed) => probed,
         Err(_) => return fallback_track_info(file_path),
     };
@@ -204,7 +205,7 @@ pub(super) fn gather_track_info(file_path: &str) -> TrackInfo {
         }
     };
     if info.bits_per_sample == 0 {
-        let decoded_bits = bits_from_decode(file_path);
+        let decoded_bits = bits_from_decode(file_path, download);
         if decoded_bits > 0 {
             info.bits_per_sample = decoded_bits;
         }
@@ -220,7 +221,7 @@ pub(super) fn gather_track_info_from_file_paths(file_paths: Vec<String>) -> Trac
 
     for file_path in file_paths {
         debug!("file path: {:?}", file_path);
-        let track_info = gather_track_info(&file_path);
+        let track_info = gather_track_info(&file_path, None);
         track_infos.push(track_info);
     }
 
@@ -0,0 +1,202 @@
+//! Parsing limits for containers from untrusted sources.
+//!
+//! A hostile `.prot` file can declare a multi-gigabyte attachment, thousands
+//! of tracks, or a `play_settings.json` that takes seconds to decode. Apps
+//! that load user uploads pass [`ParseLimits`] to
+//! [`Prot::try_new_with_limits`](crate::container::prot::Prot::try_new_with_limits),
+//! which checks each limit before doing the work it bounds and fails with a
+//! [`LimitExceeded`] instead.
+
+use std::fmt;
+
+use crate::container::play_settings::PlaySettingsFile;
+
+/// Upper bounds enforced while parsing a container.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Largest attachment read into memory, in bytes.
+    pub max_attachment_bytes: u64,
+    /// Most audio tracks in the container, and most tracks listed in
+    /// `play_settings.json`.
+    pub max_tracks: usize,
+    /// Largest `play_settings.json` decoded, in bytes.
+    pub max_play_settings_bytes: usize,
+    /// Most shuffle points across all tracks of `play_settings.json`.
+    pub max_shuffle_points: usize,
+}
+
+impl Default for ParseLimits {
+    /// Limits generous enough for any authored container: 64 MiB
+    /// attachments, 256 tracks, 1 MiB of play settings, and 10,000 shuffle
+    /// points.
+    fn default() -> Self {
+        Self {
+            max_attachment_bytes: 64 * 1024 * 1024,
+            max_tracks: 256,
+            max_play_settings_bytes: 1024 * 1024,
+            max_shuffle_points: 10_000,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// No limits; what the plain constructors use for trusted files.
+    pub fn unlimited() -> Self {
+        Self {
+            max_attachment_bytes: u64::MAX,
+            max_tracks: usize::MAX,
+            max_play_settings_bytes: usize::MAX,
+            max_shuffle_points: usize::MAX,
+        }
+    }
+
+    /// Check the number of audio tracks the container declares.
+    pub(crate) fn check_tracks(&self, count: usize) -> Result<(), LimitExceeded> {
+        if count > self.max_tracks {
+            return Err(LimitExceeded::Tracks {
+                count,
+                limit: self.max_tracks,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the size of a raw `play_settings.json` payload.
+    pub(crate) fn check_play_settings_size(&self, size: usize) -> Result<(), LimitExceeded> {
+        if size > self.max_play_settings_bytes {
+            return Err(LimitExceeded::PlaySettingsSize {
+                size,
+                limit: self.max_play_settings_bytes,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check the tracks and shuffle points of decoded play settings.
+    pub(crate) fn check_play_settings(
+        &self,
+        play_settings: &PlaySettingsFile,
+    ) -> Result<(), LimitExceeded> {
+        let (tracks, shuffle_points) = match play_settings {
+            PlaySettingsFile::Legacy(file) => (file.settings.inner().tracks.len(), 0),
+            _ => play_settings
+                .versioned_payload()
+                .map(|payload| {
+                    let shuffle_points = payload
+                        .tracks
+                        .iter()
+                        .map(|track| track.shuffle_points.len())
+                        .sum();
+                    (payload.tracks.len(), shuffle_points)
+                })
+                .unwrap_or_default(),
+        };
+        self.check_tracks(tracks)?;
+        if shuffle_points > self.max_shuffle_points {
+            return Err(LimitExceeded::ShufflePoints {
+                count: shuffle_points,
+                limit: self.max_shuffle_points,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A container exceeded one of its [`ParseLimits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LimitExceeded {
+    /// An attachment is larger than [`ParseLimits::max_attachment_bytes`].
+    AttachmentSize {
+        /// Declared attachment size, in bytes.
+        size: u64,
+        /// Configured limit, in bytes.
+        limit: u64,
+    },
+    /// More tracks than [`ParseLimits::max_tracks`].
+    Tracks {
+        /// Number of tracks found.
+        count: usize,
+        /// Configured limit.
+        limit: usize,
+    },
+    /// `play_settings.json` is larger than
+    /// [`ParseLimits::max_play_settings_bytes`].
+    PlaySettingsSize {
+        /// Payload size, in bytes.
+        size: usize,
+        /// Configured limit, in bytes.
+        limit: usize,
+    },
+    /// More shuffle points than [`ParseLimits::max_shuffle_points`].
+    ShufflePoints {
+        /// Number of shuffle points found.
+        count: usize,
+        /// Configured limit.
+        limit: usize,
+    },
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::AttachmentSize { size, limit } => {
+                write!(f, "attachment of {} bytes exceeds limit of {}", size, limit)
+            }
+            Self::Tracks { count, limit } => {
+                write!(f, "{} tracks exceed limit of {}", count, limit)
+            }
+            Self::PlaySettingsSize { size, limit } => write!(
+                f,
+                "play_settings.json of {} bytes exceeds limit of {}",
+                size, limit
+            ),
+            Self::ShufflePoints { count, limit } => {
+                write!(f, "{} shuffle points exceed limit of {}", count, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_settings_are_checked_for_tracks_and_shuffle_points() {
+        let play_settings: PlaySettingsFile = serde_json::from_str(
+            r#"{"encoder_version":"3","play_settings":{"tracks":[
+                {"level":1.0,"pan":0.0,"ids":[1],"name":"a","safe_name":"a",
+                 "shuffle_points":["0:10","0:20"]},
+                {"level":1.0,"pan":0.0,"ids":[2],"name":"b","safe_name":"b",
+                 "shuffle_points":["0:30"]}
+            ]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ParseLimits::default().check_play_settings(&play_settings),
+            Ok(())
+        );
+
+        let limits = ParseLimits {
+            max_shuffle_points: 2,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_play_settings(&play_settings),
+            Err(LimitExceeded::ShufflePoints { count: 3, limit: 2 })
+        );
+        let limits = ParseLimits {
+            max_tracks: 1,
+            ..ParseLimits::default()
+        };
+        assert_eq!(
+            limits.check_play_settings(&play_settings),
+            Err(LimitExceeded::Tracks { count: 2, limit: 1 })
+        );
+        assert!(ParseLimits::unlimited()
+            .check_play_settings_size(usize::MAX)
+            .is_ok());
+    }
+}
@@ -0,0 +1,196 @@
+//! Loudness tags for consistent playback level across containers.
+//!
+//! A [`LoudnessTag`] carries the gain that brings a container to the
+//! ReplayGain 2.0 reference of -18 LUFS. Tags are read from a `loudness`
+//! object in `play_settings.json` or, failing that, from standard
+//! `REPLAYGAIN_*` container tags. Authoring tools compute a tag from a render
+//! (see `playback::render::measure_loudness`) and write it back with
+//! [`LoudnessTag::write_to_play_settings`] or [`LoudnessTag::replaygain_tags`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::container::info::get_probe_result_with_download;
+use crate::tools::progressive::ProgressiveFile;
+
+/// Loudness target shared with ReplayGain 2.0, in LUFS.
+pub const REFERENCE_LUFS: f32 = -18.0;
+
+const TRACK_GAIN_KEY: &str = "REPLAYGAIN_TRACK_GAIN";
+const TRACK_PEAK_KEY: &str = "REPLAYGAIN_TRACK_PEAK";
+const ALBUM_GAIN_KEY: &str = "REPLAYGAIN_ALBUM_GAIN";
+const ALBUM_PEAK_KEY: &str = "REPLAYGAIN_ALBUM_PEAK";
+const REFERENCE_KEY: &str = "REPLAYGAIN_REFERENCE_LOUDNESS";
+
+/// Playback gain needed to reach [`REFERENCE_LUFS`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LoudnessTag {
+    /// Gain in dB to apply to the mix.
+    pub gain_db: f32,
+    /// Sample peak of the untrimmed mix (linear, 1.0 = full scale).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peak: Option<f32>,
+    /// Measured integrated loudness of the untrimmed mix, in LUFS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub integrated_lufs: Option<f32>,
+}
+
+impl LoudnessTag {
+    /// Build a tag from a measured integrated loudness and sample peak.
+    pub fn from_measurement(integrated_lufs: f32, peak: f32) -> Self {
+        Self {
+            gain_db: REFERENCE_LUFS - integrated_lufs,
+            peak: Some(peak),
+            integrated_lufs: Some(integrated_lufs),
+        }
+    }
+
+    /// Parse `REPLAYGAIN_*` tags, preferring track gain over album gain.
+    ///
+    /// Keys are matched case-insensitively. Returns `None` when no gain tag
+    /// parses.
+    pub fn from_replaygain_tags<'a>(
+        tags: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Option<Self> {
+        let mut found = [None; 4];
+        let keys = [
+            TRACK_GAIN_KEY,
+            TRACK_PEAK_KEY,
+            ALBUM_GAIN_KEY,
+            ALBUM_PEAK_KEY,
+        ];
+        for (key, value) in tags {
+            if let Some(index) = keys.iter().position(|k| key.eq_ignore_ascii_case(k)) {
+                found[index] = parse_number(value);
+            }
+        }
+        let [track_gain, track_peak, album_gain, album_peak] = found;
+        let (gain_db, peak) = match (track_gain, album_gain) {
+            (Some(gain), _) => (gain, track_peak),
+            (None, Some(gain)) => (gain, album_peak),
+            (None, None) => return None,
+        };
+        Some(Self {
+            gain_db,
+            peak,
+            integrated_lufs: None,
+        })
+    }
+
+    /// Gain to apply in dB, reduced so the tagged peak stays at or below full scale.
+    pub fn trim_db(&self) -> f32 {
+        if !self.gain_db.is_finite() {
+            return 0.0;
+        }
+        match self.peak.filter(|peak| peak.is_finite() && *peak > 0.0) {
+            Some(peak) => self.gain_db.min(-20.0 * peak.log10()),
+            None => self.gain_db,
+        }
+    }
+
+    /// Linear form of [`LoudnessTag::trim_db`].
+    pub fn trim_gain(&self) -> f32 {
+        10_f32.powf(self.trim_db() / 20.0)
+    }
+
+    /// Standard ReplayGain tag pairs for writing into container metadata.
+    pub fn replaygain_tags(&self) -> Vec<(String, String)> {
+        let mut tags = vec![(
+            TRACK_GAIN_KEY.to_string(),
+            format!("{:+.2} dB", self.gain_db),
+        )];
+        if let Some(peak) = self.peak {
+            tags.push((TRACK_PEAK_KEY.to_string(), format!("{:.6}", peak)));
+        }
+        tags.push((
+            REFERENCE_KEY.to_string(),
+            format!("{:.1} LUFS", REFERENCE_LUFS),
+        ));
+        tags
+    }
+
+    /// Store the tag as `loudness` in a parsed `play_settings.json` document.
+    ///
+    /// Settings nested under a `play_settings` key are updated in place.
+    /// Returns `false` when the document is not a JSON object.
+    pub fn write_to_play_settings(&self, play_settings: &mut serde_json::Value) -> bool {
+        let Some(root) = play_settings.as_object_mut() else {
+            return false;
+        };
+        let target = match root.get_mut("play_settings") {
+            Some(serde_json::Value::Object(nested)) => nested,
+            _ => root,
+        };
+        match serde_json::to_value(self) {
+            Ok(value) => {
+                target.insert("loudness".to_string(), value);
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+/// Read ReplayGain tags from a container's metadata, if present.
+pub fn read_replaygain_tags(file_path: &str) -> Option<LoudnessTag> {
+    read_replaygain_tags_with_download(file_path, None)
+}
+
+/// [`read_replaygain_tags`], probing through `download` when one is given.
+pub(crate) fn read_replaygain_tags_with_download(
+    file_path: &str,
+    download: Option<&ProgressiveFile>,
+) -> Option<LoudnessTag> {
+    let mut probed = get_probe_result_with_download(file_path, download).ok()?;
+    let revision = probed.format.metadata().current()?.clone();
+    let pairs: Vec<(String, String)> = revision
+        .tags()
+        .iter()
+        .map(|tag| (tag.key.clone(), tag.value.to_string()))
+        .collect();
+    LoudnessTag::from_replaygain_tags(pairs.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+}
+
+// Parse the leading number of values such as "-6.48 dB" or "0.988".
+fn parse_number(value: &str) -> Option<f32> {
+    let number = value
+        .trim()
+        .trim_end_matches(|c: char| c.is_ascii_alphabetic() || c.is_whitespace());
+    number.parse::<f32>().ok().filter(|value| value.is_finite())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaygain_tags_prefer_track_gain_and_limit_by_peak() {
+        let tag = LoudnessTag::from_replaygain_tags([
+            ("replaygain_album_gain", "-2.00 dB"),
+            ("REPLAYGAIN_TRACK_GAIN", "+4.50 dB"),
+            ("REPLAYGAIN_TRACK_PEAK", "0.891251"),
+        ])
+        .unwrap();
+        assert_eq!(tag.gain_db, 4.5);
+        assert!((tag.trim_db() - 1.0).abs() < 1e-3);
+        assert!(LoudnessTag::from_replaygain_tags([("TITLE", "x")]).is_none());
+    }
+
+    #[test]
+    fn measured_tag_round_trips_through_play_settings_and_tags() {
+        let tag = LoudnessTag::from_measurement(-12.0, 0.5);
+        assert_eq!(tag.gain_db, -6.0);
+        let mut settings = serde_json::json!({"play_settings": {"tracks": []}});
+        assert!(tag.write_to_play_settings(&mut settings));
+        let stored: LoudnessTag =
+            serde_json::from_value(settings["play_settings"]["loudness"].clone()).unwrap();
+        assert_eq!(stored, tag);
+
+        let tags = tag.replaygain_tags();
+        let parsed =
+            LoudnessTag::from_replaygain_tags(tags.iter().map(|(k, v)| (k.as_str(), v.as_str())))
+                .unwrap();
+        assert_eq!(parsed.gain_db, -6.0);
+        assert_eq!(parsed.peak, Some(0.5));
+    }
+}
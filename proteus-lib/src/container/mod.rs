@@ -1,6 +1,21 @@
 //! Container parsing and metadata for `.prot`/`.mka` files.
 
+pub mod attachments;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod impulse_response;
 pub mod info;
+pub mod limits;
+pub mod loudness;
 pub mod play_settings;
+pub mod presets;
 pub mod prot;
 pub(crate) mod prot_settings;
+#[cfg(feature = "signing")]
+pub mod signing;
+pub mod silence;
+pub mod validate;
+
+#[cfg(feature = "signing")]
+pub use signing::{verify_signature, verify_signature_with_limits};
+pub use validate::validate;
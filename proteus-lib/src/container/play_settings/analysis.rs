@@ -0,0 +1,219 @@
+//! Per-candidate tempo and key metadata used to keep shuffles in key.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::SettingsTrack;
+
+/// Pitch class of a key's tonic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PitchClass {
+    /// C.
+    C,
+    /// C sharp / D flat.
+    #[serde(rename = "C#", alias = "Db")]
+    CSharp,
+    /// D.
+    D,
+    /// D sharp / E flat.
+    #[serde(rename = "D#", alias = "Eb")]
+    DSharp,
+    /// E.
+    E,
+    /// F.
+    F,
+    /// F sharp / G flat.
+    #[serde(rename = "F#", alias = "Gb")]
+    FSharp,
+    /// G.
+    G,
+    /// G sharp / A flat.
+    #[serde(rename = "G#", alias = "Ab")]
+    GSharp,
+    /// A.
+    A,
+    /// A sharp / B flat.
+    #[serde(rename = "A#", alias = "Bb")]
+    ASharp,
+    /// B.
+    B,
+}
+
+impl PitchClass {
+    /// All pitch classes, ascending from C.
+    pub const ALL: [PitchClass; 12] = [
+        Self::C,
+        Self::CSharp,
+        Self::D,
+        Self::DSharp,
+        Self::E,
+        Self::F,
+        Self::FSharp,
+        Self::G,
+        Self::GSharp,
+        Self::A,
+        Self::ASharp,
+        Self::B,
+    ];
+
+    /// Semitones above C (`0..12`).
+    pub fn semitone(self) -> u8 {
+        self as u8
+    }
+
+    /// Pitch class `semitone` semitones above C, wrapping at the octave.
+    pub fn from_semitone(semitone: usize) -> Self {
+        Self::ALL[semitone % 12]
+    }
+
+    fn name(self) -> &'static str {
+        [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ][self as usize]
+    }
+}
+
+/// Major or minor mode of a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum KeyMode {
+    /// Major (Ionian) mode.
+    Major,
+    /// Natural minor (Aeolian) mode.
+    Minor,
+}
+
+/// Musical key of a candidate, e.g. `A minor`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct MusicalKey {
+    /// Tonic of the key.
+    pub tonic: PitchClass,
+    /// Mode of the key.
+    pub mode: KeyMode,
+}
+
+impl MusicalKey {
+    /// Position on the Camelot wheel (`1..=12`); relative keys share it and
+    /// neighbours are a fifth apart.
+    pub fn camelot_number(&self) -> u8 {
+        let fifths = (self.tonic.semitone() as usize * 7) % 12;
+        let offset = match self.mode {
+            KeyMode::Major => 7,
+            KeyMode::Minor => 4,
+        };
+        ((fifths + offset) % 12) as u8 + 1
+    }
+
+    /// Return `true` when the keys mix without clashing.
+    ///
+    /// Compatible keys are the same key, its relative major or minor, and the
+    /// keys a fifth up or down in the same mode.
+    pub fn is_compatible(&self, other: &MusicalKey) -> bool {
+        let distance = self.camelot_number().abs_diff(other.camelot_number());
+        let distance = distance.min(12 - distance);
+        distance == 0 || (distance == 1 && self.mode == other.mode)
+    }
+}
+
+impl fmt::Display for MusicalKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            KeyMode::Major => "major",
+            KeyMode::Minor => "minor",
+        };
+        write!(f, "{} {}", self.tonic.name(), mode)
+    }
+}
+
+/// Estimated tempo and key of one candidate id.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CandidateAnalysis {
+    /// Candidate id the analysis applies to.
+    pub id: u32,
+    /// Estimated tempo in beats per minute, when the stem has a clear pulse.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tempo_bpm: Option<f32>,
+    /// Estimated key, when the stem is pitched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<MusicalKey>,
+}
+
+/// Return the analysis entry for `id`, if one is declared.
+pub fn candidate_analysis(analysis: &[CandidateAnalysis], id: u32) -> Option<&CandidateAnalysis> {
+    analysis.iter().find(|entry| entry.id == id)
+}
+
+/// Pairs of ids on different tracks whose analyzed keys clash.
+///
+/// Ids without a key never clash. Each pair is listed once, smaller id first.
+pub fn key_clash_pairs(tracks: &[SettingsTrack]) -> Vec<[u32; 2]> {
+    let keyed: Vec<(usize, u32, MusicalKey)> = tracks
+        .iter()
+        .enumerate()
+        .flat_map(|(index, track)| {
+            track.candidate_analysis.iter().filter_map(move |entry| {
+                let key = entry.key?;
+                track
+                    .ids
+                    .contains(&entry.id)
+                    .then_some((index, entry.id, key))
+            })
+        })
+        .collect();
+    let mut pairs = Vec::new();
+    for (position, (track_a, id_a, key_a)) in keyed.iter().enumerate() {
+        for (track_b, id_b, key_b) in &keyed[position + 1..] {
+            if track_a != track_b && id_a != id_b && !key_a.is_compatible(key_b) {
+                pairs.push([*id_a.min(id_b), *id_a.max(id_b)]);
+            }
+        }
+    }
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(tonic: PitchClass, mode: KeyMode) -> MusicalKey {
+        MusicalKey { tonic, mode }
+    }
+
+    #[test]
+    fn camelot_neighbours_and_relatives_are_compatible() {
+        let c_major = key(PitchClass::C, KeyMode::Major);
+        let a_minor = key(PitchClass::A, KeyMode::Minor);
+        assert_eq!(c_major.camelot_number(), 8);
+        assert_eq!(a_minor.camelot_number(), 8);
+        assert_eq!(key(PitchClass::B, KeyMode::Major).camelot_number(), 1);
+        assert!(c_major.is_compatible(&a_minor));
+        assert!(c_major.is_compatible(&key(PitchClass::G, KeyMode::Major)));
+        assert!(c_major.is_compatible(&key(PitchClass::F, KeyMode::Major)));
+        assert!(!c_major.is_compatible(&key(PitchClass::E, KeyMode::Minor)));
+        assert!(!c_major.is_compatible(&key(PitchClass::FSharp, KeyMode::Major)));
+        assert_eq!(
+            key(PitchClass::FSharp, KeyMode::Minor).to_string(),
+            "F# minor"
+        );
+    }
+
+    #[test]
+    fn analysis_round_trips_with_sharp_and_flat_names() {
+        let entry: CandidateAnalysis = serde_json::from_value(serde_json::json!({
+            "id": 3, "tempo_bpm": 92.5, "key": {"tonic": "Bb", "mode": "minor"}
+        }))
+        .unwrap();
+        assert_eq!(entry.key, Some(key(PitchClass::ASharp, KeyMode::Minor)));
+        assert_eq!(
+            serde_json::to_value(entry).unwrap()["key"],
+            serde_json::json!({"tonic": "A#", "mode": "minor"})
+        );
+    }
+}
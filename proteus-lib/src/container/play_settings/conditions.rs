@@ -0,0 +1,81 @@
+//! Runtime-variable conditions for interactive selection.
+//!
+//! Hosts set named numeric variables at runtime (for example `intensity`).
+//! Tracks can gate their shuffle points and individual candidates on those
+//! variables, so the next shuffle boundary follows the host's state.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Runtime variable values set by the host, keyed by name.
+pub type RuntimeVariables = BTreeMap<String, f64>;
+
+/// Range test on a single runtime variable.
+///
+/// Bounds are inclusive; an omitted bound is open. A condition on a variable
+/// that has not been set never holds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct VariableCondition {
+    /// Name of the runtime variable to test.
+    pub variable: String,
+    /// Smallest accepted value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// Largest accepted value.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+}
+
+/// Conditions that must hold for one candidate id to be selectable.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CandidateCondition {
+    /// Candidate id the conditions apply to.
+    pub id: u32,
+    /// Conditions that must all hold.
+    pub when: Vec<VariableCondition>,
+}
+
+impl VariableCondition {
+    /// Return `true` when the variable is set and within bounds.
+    pub fn holds(&self, variables: &RuntimeVariables) -> bool {
+        let Some(value) = variables.get(&self.variable).copied() else {
+            return false;
+        };
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+}
+
+/// Return `true` when every condition holds (vacuously true when empty).
+pub fn conditions_hold(conditions: &[VariableCondition], variables: &RuntimeVariables) -> bool {
+    conditions
+        .iter()
+        .all(|condition| condition.holds(variables))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn condition(min: Option<f64>, max: Option<f64>) -> VariableCondition {
+        VariableCondition {
+            variable: "intensity".to_string(),
+            min,
+            max,
+        }
+    }
+
+    #[test]
+    fn bounds_are_inclusive_and_unset_variables_fail() {
+        let mut variables = RuntimeVariables::new();
+        assert!(!condition(None, None).holds(&variables));
+
+        variables.insert("intensity".to_string(), 0.5);
+        assert!(condition(Some(0.5), Some(0.5)).holds(&variables));
+        assert!(!condition(Some(0.6), None).holds(&variables));
+        assert!(!condition(None, Some(0.4)).holds(&variables));
+        assert!(conditions_hold(&[], &variables));
+    }
+}
@@ -0,0 +1,130 @@
+//! Effect entries of the `play_settings.json` DSP chain.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dsp::effects::AudioEffect;
+
+/// One entry in the `play_settings.json` DSP effect chain.
+///
+/// Recognized effect payloads decode into [`EffectSettings::Known`] so callers can
+/// work with [`AudioEffect`] directly. Unrecognized payloads are preserved as
+/// [`EffectSettings::Raw`] for forward compatibility and lossless round-tripping.
+#[derive(Debug, Clone)]
+pub enum EffectSettings {
+    /// A recognized effect entry with typed settings.
+    Known(Box<AudioEffect>),
+    /// An unrecognized or caller-provided raw effect payload.
+    Raw(serde_json::Value),
+}
+
+impl EffectSettings {
+    /// Return the typed effect when this entry is already known.
+    pub fn as_audio_effect(&self) -> Option<&AudioEffect> {
+        match self {
+            Self::Known(effect) => Some(effect.as_ref()),
+            Self::Raw(_) => None,
+        }
+    }
+
+    /// Decode this entry into a typed [`AudioEffect`].
+    pub fn decode_audio_effect(&self) -> serde_json::Result<AudioEffect> {
+        match self {
+            Self::Known(effect) => Ok(effect.as_ref().clone()),
+            Self::Raw(raw) => serde_json::from_value(raw.clone()),
+        }
+    }
+
+    /// Return the preserved raw JSON payload, if this entry is untyped.
+    pub fn as_raw_value(&self) -> Option<&serde_json::Value> {
+        match self {
+            Self::Known(_) => None,
+            Self::Raw(raw) => Some(raw),
+        }
+    }
+
+    pub(super) fn raw_wrapper_object(
+        &self,
+        key: &str,
+    ) -> Option<&serde_json::Map<String, serde_json::Value>> {
+        self.as_raw_value()?.as_object()?.get(key)?.as_object()
+    }
+}
+
+impl From<AudioEffect> for EffectSettings {
+    fn from(effect: AudioEffect) -> Self {
+        Self::Known(Box::new(effect))
+    }
+}
+
+impl From<serde_json::Value> for EffectSettings {
+    fn from(value: serde_json::Value) -> Self {
+        match serde_json::from_value::<AudioEffect>(value.clone()) {
+            Ok(effect) => Self::Known(Box::new(effect)),
+            Err(_) => Self::Raw(value),
+        }
+    }
+}
+
+// Editors validate against the known effects; raw entries are a decoder fallback.
+#[cfg(feature = "schema")]
+impl schemars::JsonSchema for EffectSettings {
+    fn inline_schema() -> bool {
+        true
+    }
+
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "EffectSettings".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        generator.subschema_for::<AudioEffect>()
+    }
+}
+
+impl Serialize for EffectSettings {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Known(effect) => effect.serialize(serializer),
+            Self::Raw(raw) => raw.serialize(serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for EffectSettings {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        Ok(Self::from(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn effect_settings_deserializes_known_effects_to_typed_variant() {
+        let effect: EffectSettings =
+            serde_json::from_str(r#"{"GainSettings":{"enabled":true,"gain":1.25}}"#).unwrap();
+
+        assert!(matches!(
+            effect.as_audio_effect(),
+            Some(AudioEffect::Gain(_))
+        ));
+    }
+
+    #[test]
+    fn effect_settings_preserves_unknown_effects_as_raw_json() {
+        let effect: EffectSettings =
+            serde_json::from_str(r#"{"CustomEffectSettings":{"enabled":true,"amount":0.5}}"#)
+                .unwrap();
+
+        let raw = effect.as_raw_value().unwrap();
+        assert_eq!(raw["CustomEffectSettings"]["amount"], 0.5);
+    }
+}
@@ -0,0 +1,301 @@
+//! Versioned `play_settings.json` documents and the values read from them.
+
+use log::{info, warn};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::dsp::effects::{AudioEffect, ConvolutionReverbSettings};
+
+use super::{
+    EffectSettings, PlaySettingsLegacyFile, PlaySettingsPayload, PlaySettingsV1File,
+    PlaySettingsV2File, PlaySettingsV3File, PlaySettingsV4File,
+};
+
+/// Versioned settings file representation.
+#[derive(Debug, Clone)]
+pub(crate) enum PlaySettingsFile {
+    /// Legacy (pre-versioned) settings format without an `encoder_version` field.
+    Legacy(PlaySettingsLegacyFile),
+    /// Version 1 settings format.
+    V1(PlaySettingsV1File),
+    /// Version 2 settings format.
+    V2(PlaySettingsV2File),
+    /// Version 3 settings format.
+    V3(PlaySettingsV3File),
+    /// Version 4 settings format with selection rules.
+    V4(PlaySettingsV4File),
+    /// Settings with an unrecognized `encoder_version`; raw JSON is preserved.
+    Unknown {
+        /// The raw JSON value preserved for round-trip serialization.
+        raw: serde_json::Value,
+    },
+}
+
+impl PlaySettingsFile {
+    /// Return normalized modern payload for V1-V4 settings.
+    pub(crate) fn versioned_payload(&self) -> Option<&PlaySettingsPayload> {
+        match self {
+            PlaySettingsFile::V1(file) => Some(file.settings.inner()),
+            PlaySettingsFile::V2(file) => Some(file.settings.inner()),
+            PlaySettingsFile::V3(file) => Some(file.settings.inner()),
+            PlaySettingsFile::V4(file) => Some(file.settings.inner()),
+            _ => None,
+        }
+    }
+
+    /// Return mutable normalized modern payload for V1-V4 settings.
+    pub(crate) fn versioned_payload_mut(&mut self) -> Option<&mut PlaySettingsPayload> {
+        match self {
+            PlaySettingsFile::V1(file) => Some(file.settings.inner_mut()),
+            PlaySettingsFile::V2(file) => Some(file.settings.inner_mut()),
+            PlaySettingsFile::V3(file) => Some(file.settings.inner_mut()),
+            PlaySettingsFile::V4(file) => Some(file.settings.inner_mut()),
+            _ => None,
+        }
+    }
+}
+
+/// Return raw effect entries for versioned settings files.
+pub(crate) fn effects(play_settings: &PlaySettingsFile) -> Option<&[EffectSettings]> {
+    play_settings
+        .versioned_payload()
+        .map(|payload| payload.effects.as_slice())
+}
+
+enum ConvolutionReverbSettingsView<'a> {
+    Typed(&'a ConvolutionReverbSettings),
+    Raw(&'a serde_json::Map<String, serde_json::Value>),
+}
+
+/// Return the first convolution-reverb effect payload object, if present.
+fn first_convolution_reverb_settings(
+    play_settings: &PlaySettingsFile,
+) -> Option<ConvolutionReverbSettingsView<'_>> {
+    let effects = effects(play_settings)?;
+    effects.iter().find_map(|effect| match effect {
+        EffectSettings::Known(boxed) => match boxed.as_ref() {
+            AudioEffect::ConvolutionReverb(effect) => {
+                Some(ConvolutionReverbSettingsView::Typed(effect.settings()))
+            }
+            _ => None,
+        },
+        _ => effect
+            .raw_wrapper_object("ConvolutionReverbSettings")
+            .map(ConvolutionReverbSettingsView::Raw),
+    })
+}
+
+/// Extract raw impulse-response setting text from play settings.
+pub(crate) fn extract_impulse_response_text(play_settings: &PlaySettingsFile) -> Option<String> {
+    match first_convolution_reverb_settings(play_settings)? {
+        ConvolutionReverbSettingsView::Typed(settings) => settings
+            .impulse_response
+            .as_deref()
+            .or(settings.impulse_response_attachment.as_deref())
+            .or(settings.impulse_response_path.as_deref())
+            .map(ToString::to_string),
+        ConvolutionReverbSettingsView::Raw(settings) => settings
+            .get("impulse_response")
+            .and_then(serde_json::Value::as_str)
+            .or_else(|| {
+                settings
+                    .get("impulse_response_attachment")
+                    .and_then(serde_json::Value::as_str)
+            })
+            .or_else(|| {
+                settings
+                    .get("impulse_response_path")
+                    .and_then(serde_json::Value::as_str)
+            })
+            .map(ToString::to_string),
+    }
+}
+
+/// Extract configured convolution-reverb tail dB from play settings.
+pub(crate) fn extract_impulse_response_tail_db(play_settings: &PlaySettingsFile) -> Option<f32> {
+    match first_convolution_reverb_settings(play_settings)? {
+        ConvolutionReverbSettingsView::Typed(settings) => settings
+            .impulse_response_tail_db
+            .or(settings.impulse_response_tail),
+        ConvolutionReverbSettingsView::Raw(settings) => settings
+            .get("impulse_response_tail_db")
+            .and_then(serde_json::Value::as_f64)
+            .map(|value| value as f32)
+            .or_else(|| {
+                settings
+                    .get("impulse_response_tail")
+                    .and_then(serde_json::Value::as_f64)
+                    .map(|value| value as f32)
+            }),
+    }
+}
+
+/// Normalized `encoder_version` of a settings document, if declared.
+pub(crate) fn encoder_version(document: &serde_json::Value) -> Option<String> {
+    document.get("encoder_version").and_then(|raw| match raw {
+        serde_json::Value::String(version) => Some(version.clone()),
+        serde_json::Value::Number(number) => number
+            .as_f64()
+            .map(|val| {
+                if (val - 1.0).abs() < f64::EPSILON {
+                    "1".to_string()
+                } else if (val - 2.0).abs() < f64::EPSILON {
+                    "2".to_string()
+                } else if (val - 3.0).abs() < f64::EPSILON {
+                    "3".to_string()
+                } else if (val - 4.0).abs() < f64::EPSILON {
+                    "4".to_string()
+                } else {
+                    number.to_string()
+                }
+            })
+            .or_else(|| Some(number.to_string())),
+        _ => None,
+    })
+}
+
+impl<'de> Deserialize<'de> for PlaySettingsFile {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let value = serde_json::Value::deserialize(deserializer)?;
+        let encoder_version = encoder_version(&value);
+
+        info!("encoder version: {:?}", encoder_version);
+
+        let parsed = match encoder_version.as_deref() {
+            None => serde_json::from_value::<PlaySettingsLegacyFile>(value.clone())
+                .map(PlaySettingsFile::Legacy),
+            Some("1") => serde_json::from_value::<PlaySettingsV1File>(value.clone())
+                .map(PlaySettingsFile::V1),
+            Some("2") => serde_json::from_value::<PlaySettingsV2File>(value.clone())
+                .map(PlaySettingsFile::V2),
+            Some("3") => serde_json::from_value::<PlaySettingsV3File>(value.clone())
+                .map(PlaySettingsFile::V3),
+            Some("4") => serde_json::from_value::<PlaySettingsV4File>(value.clone())
+                .map(PlaySettingsFile::V4),
+            Some(version) => {
+                warn!("unknown encoder version: {:?}", version);
+                return Ok(PlaySettingsFile::Unknown { raw: value });
+            }
+        };
+
+        parsed.or_else(|_| Ok(PlaySettingsFile::Unknown { raw: value }))
+    }
+}
+
+impl Serialize for PlaySettingsFile {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        fn with_version<T, S>(payload: &T, version: &str, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            T: Serialize,
+            S: Serializer,
+        {
+            let mut value = serde_json::to_value(payload).map_err(serde::ser::Error::custom)?;
+            match value {
+                serde_json::Value::Object(ref mut map) => {
+                    map.insert(
+                        "encoder_version".to_string(),
+                        serde_json::Value::String(version.to_string()),
+                    );
+                }
+                other => {
+                    let mut map = serde_json::Map::new();
+                    map.insert(
+                        "encoder_version".to_string(),
+                        serde_json::Value::String(version.to_string()),
+                    );
+                    map.insert("play_settings".to_string(), other);
+                    value = serde_json::Value::Object(map);
+                }
+            }
+            value.serialize(serializer)
+        }
+
+        match self {
+            PlaySettingsFile::Legacy(file) => file.serialize(serializer),
+            PlaySettingsFile::V1(file) => with_version(file, "1", serializer),
+            PlaySettingsFile::V2(file) => with_version(file, "2", serializer),
+            PlaySettingsFile::V3(file) => with_version(file, "3", serializer),
+            PlaySettingsFile::V4(file) => with_version(file, "4", serializer),
+            PlaySettingsFile::Unknown { raw, .. } => raw.serialize(serializer),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl PlaySettingsFile {
+        fn encoder_version(&self) -> Option<&str> {
+            match self {
+                PlaySettingsFile::Legacy(_) => None,
+                PlaySettingsFile::V1(_) => Some("1"),
+                PlaySettingsFile::V2(_) => Some("2"),
+                PlaySettingsFile::V3(_) => Some("3"),
+                PlaySettingsFile::V4(_) => Some("4"),
+                PlaySettingsFile::Unknown { raw } => {
+                    raw.get("encoder_version").and_then(|v| v.as_str())
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_versioned_settings_and_preserve_encoder_version_on_serialize() {
+        let parsed: PlaySettingsFile = serde_json::from_str(
+            r#"{
+                "encoder_version": "1",
+                "play_settings": { "effects": [], "tracks": [] }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.encoder_version(), Some("1"));
+        let serialized = serde_json::to_value(parsed).unwrap();
+        assert_eq!(serialized["encoder_version"], "1");
+    }
+
+    #[test]
+    fn deserialize_unknown_encoder_version_as_unknown_variant() {
+        let parsed: PlaySettingsFile =
+            serde_json::from_str(r#"{"encoder_version": "99", "play_settings": {}}"#).unwrap();
+        assert!(matches!(parsed, PlaySettingsFile::Unknown { .. }));
+        assert_eq!(parsed.encoder_version(), Some("99"));
+    }
+
+    #[test]
+    fn deserialize_v4_settings_with_selection_rules() {
+        let parsed: PlaySettingsFile = serde_json::from_str(
+            r#"{
+                "encoder_version": 4,
+                "play_settings": {
+                    "tracks": [{
+                        "level": 1.0, "pan": 0.0, "ids": [1, 2], "name": "A",
+                        "safe_name": "a", "weights": [3.0, 1.0]
+                    }],
+                    "selection_rules": {
+                        "groups": [{"name": "leads", "ids": [1, 3], "exactly": 1}],
+                        "never_together": [[2, 4]]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(parsed.encoder_version(), Some("4"));
+        let payload = parsed.versioned_payload().unwrap();
+        assert_eq!(payload.tracks[0].weights, vec![3.0, 1.0]);
+        assert_eq!(payload.selection_rules.groups[0].exactly, Some(1));
+        let serialized = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(serialized["encoder_version"], "4");
+        assert_eq!(
+            serialized["play_settings"]["selection_rules"]["never_together"][0][1],
+            4
+        );
+    }
+}
@@ -0,0 +1,22 @@
+//! VCA-style track groups.
+//!
+//! Tracks name the group they belong to; the payload may declare an initial
+//! gain per group. A group's gain multiplies the level of every member track
+//! in the mix, so a whole section can be ridden with one control.
+
+use serde::{Deserialize, Serialize};
+
+/// Initial gain of a named track group.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct TrackGroup {
+    /// Name referenced by each member track's `group`.
+    pub name: String,
+    /// Linear gain applied on top of member track levels (1.0 = unity).
+    #[serde(default = "default_group_level")]
+    pub level: f32,
+}
+
+fn default_group_level() -> f32 {
+    1.0
+}
@@ -0,0 +1,22 @@
+//! Per-candidate display metadata for mixer and now-playing UIs.
+
+use serde::{Deserialize, Serialize};
+
+/// Display label and artwork for one candidate id.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CandidateLabel {
+    /// Candidate id the label applies to.
+    pub id: u32,
+    /// Display label, e.g. `Take 3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    /// Name of a container attachment holding artwork for this candidate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork: Option<String>,
+}
+
+/// Return the label entry for `id`, if one is declared.
+pub fn candidate_label(labels: &[CandidateLabel], id: u32) -> Option<&CandidateLabel> {
+    labels.iter().find(|label| label.id == id)
+}
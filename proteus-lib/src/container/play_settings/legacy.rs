@@ -6,6 +6,7 @@ use super::PlaySettingsContainer;
 
 /// Top-level wrapper for legacy settings files.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub(crate) struct PlaySettingsLegacyFile {
     /// The legacy settings payload, which may be nested or flat.
     #[serde(flatten)]
@@ -14,6 +15,7 @@ pub(crate) struct PlaySettingsLegacyFile {
 
 /// Legacy settings payload (tracks only).
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub(crate) struct PlaySettingsLegacy {
     /// Per-track legacy settings read from the container.
     #[serde(default)]
@@ -22,6 +24,7 @@ pub(crate) struct PlaySettingsLegacy {
 
 /// Legacy per-track settings.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub(crate) struct PlaySettingsTrackLegacy {
     /// Index of the first take to use for this track.
     #[serde(rename = "startingIndex")]
@@ -0,0 +1,296 @@
+//! Authoring lints for `play_settings.json`.
+//!
+//! [`lint`] reports values that decode but are probably mistakes: deprecated
+//! aliases that still work but have a preferred spelling, and numbers outside
+//! the range the engine expects. Lints never affect decoding.
+
+use std::fmt;
+
+use serde_json::{Map, Value};
+
+use super::strict::split_payload;
+
+/// Deprecated effect names and their replacements.
+const EFFECT_ALIASES: &[(&str, &str)] = &[
+    ("BasicReverbSettings", "DelayReverbSettings"),
+    ("DeEsserSettings", "DynamicEqSettings"),
+];
+/// Deprecated convolution reverb fields and their replacements.
+const CONVOLUTION_ALIASES: &[(&str, &str)] = &[
+    ("impulse_response_attachment", "impulse_response"),
+    ("impulse_response_path", "impulse_response"),
+    ("impulse_response_tail", "impulse_response_tail_db"),
+];
+/// Effect fields holding a dry/wet ratio, under any accepted spelling.
+const MIX_FIELDS: &[&str] = &["mix", "dry_wet", "wet_dry"];
+
+/// Category of a [`LintWarning`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LintKind {
+    /// A deprecated spelling that still decodes.
+    DeprecatedAlias,
+    /// A value outside the range the engine expects.
+    OutOfRange,
+}
+
+/// One authoring problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintWarning {
+    /// Path to the value, e.g. `play_settings.tracks[0].pan`.
+    pub path: String,
+    /// Warning category.
+    pub kind: LintKind,
+    /// Human-readable explanation.
+    pub message: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Report deprecated aliases and out-of-range values in a settings document.
+///
+/// Accepts nested (`{"play_settings": {...}}`) and flat documents of any
+/// version. Values of the wrong type are left to strict decoding.
+pub fn lint(document: &Value) -> Vec<LintWarning> {
+    let mut lints = Lints::default();
+    let (prefix, payload) = split_payload(document);
+    let Some(payload) = payload.as_object() else {
+        return lints.warnings;
+    };
+
+    if payload.contains_key("bpm") {
+        lints.alias(format!("{}bpm", prefix), "tempo_bpm");
+    }
+    for key in ["tempo_bpm", "bpm"] {
+        lints.range(payload, key, prefix, "a positive tempo", |bpm| bpm > 0.0);
+    }
+    if let Some(loudness) = payload.get("loudness").and_then(Value::as_object) {
+        let path = format!("{}loudness.", prefix);
+        lints.range(loudness, "peak", &path, "a non-negative peak", |peak| {
+            peak >= 0.0
+        });
+    }
+    for (index, track) in objects(payload.get("tracks")) {
+        lint_track(&mut lints, track, &format!("{}tracks[{}].", prefix, index));
+    }
+    for (index, effect) in objects(payload.get("effects")) {
+        for (name, settings) in effect {
+            let path = format!("{}effects[{}].{}", prefix, index, name);
+            lint_effect(&mut lints, name, settings, &path);
+        }
+    }
+    lints.warnings
+}
+
+fn lint_track(lints: &mut Lints, track: &Map<String, Value>, path: &str) {
+    lints.range(track, "level", path, "a non-negative gain", |level| {
+        level >= 0.0
+    });
+    lints.range(track, "pan", path, "a pan between -1 and 1", |pan| {
+        (-1.0..=1.0).contains(&pan)
+    });
+    lints.range(track, "selections_count", path, "at least 1", |count| {
+        count >= 1.0
+    });
+    lints.range(track, "length", path, "at least 1", |length| length >= 1.0);
+    let id_count = track.get("ids").and_then(Value::as_array).map(Vec::len);
+    if let Some(weights) = track.get("weights").and_then(Value::as_array) {
+        for (index, weight) in weights.iter().enumerate() {
+            if weight.as_f64().is_some_and(|weight| weight < 0.0) {
+                lints.out_of_range(
+                    format!("{}weights[{}]", path, index),
+                    "a non-negative weight",
+                );
+            }
+        }
+        if id_count.is_some_and(|ids| weights.len() > ids) {
+            lints.warnings.push(LintWarning {
+                path: format!("{}weights", path),
+                kind: LintKind::OutOfRange,
+                message: "more weights than ids; extra weights are ignored".to_string(),
+            });
+        }
+    }
+    let per_candidate = [
+        (
+            "candidate_labels",
+            "label for an id not in ids; it is never shown",
+        ),
+        (
+            "candidate_trims",
+            "trim for an id not in ids; it is never applied",
+        ),
+        (
+            "candidate_analysis",
+            "analysis for an id not in ids; it is never used",
+        ),
+    ];
+    for (key, message) in per_candidate {
+        for (index, entry) in objects(track.get(key)) {
+            let listed = entry.get("id").is_some_and(|id| {
+                track
+                    .get("ids")
+                    .and_then(Value::as_array)
+                    .is_some_and(|ids| ids.contains(id))
+            });
+            if !listed {
+                lints.warnings.push(LintWarning {
+                    path: format!("{}{}[{}].id", path, key, index),
+                    kind: LintKind::OutOfRange,
+                    message: message.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn lint_effect(lints: &mut Lints, name: &str, settings: &Value, path: &str) {
+    if let Some((_, replacement)) = EFFECT_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        lints.alias(path.to_string(), replacement);
+    }
+    let Some(settings) = settings.as_object() else {
+        return;
+    };
+    if name == "ConvolutionReverbSettings" {
+        for (alias, replacement) in CONVOLUTION_ALIASES {
+            if settings.contains_key(*alias) {
+                lints.alias(format!("{}.{}", path, alias), replacement);
+            }
+        }
+    }
+    for field in MIX_FIELDS {
+        lints.range(
+            settings,
+            field,
+            &format!("{}.", path),
+            "a mix between 0 and 1",
+            |mix| (0.0..=1.0).contains(&mix),
+        );
+    }
+}
+
+// Enumerate the object entries of an optional JSON array.
+fn objects(value: Option<&Value>) -> impl Iterator<Item = (usize, &Map<String, Value>)> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .enumerate()
+        .filter_map(|(index, item)| Some((index, item.as_object()?)))
+}
+
+#[derive(Default)]
+struct Lints {
+    warnings: Vec<LintWarning>,
+}
+
+impl Lints {
+    fn alias(&mut self, path: String, replacement: &str) {
+        self.warnings.push(LintWarning {
+            path,
+            kind: LintKind::DeprecatedAlias,
+            message: format!("deprecated alias; use `{}`", replacement),
+        });
+    }
+
+    fn out_of_range(&mut self, path: String, expected: &str) {
+        self.warnings.push(LintWarning {
+            path,
+            kind: LintKind::OutOfRange,
+            message: format!("out of range; expected {}", expected),
+        });
+    }
+
+    // Flag a numeric `key` of `object` that fails `valid`; non-finite numbers
+    // always fail.
+    fn range(
+        &mut self,
+        object: &Map<String, Value>,
+        key: &str,
+        prefix: &str,
+        expected: &str,
+        valid: impl Fn(f64) -> bool,
+    ) {
+        let Some(value) = object.get(key).and_then(Value::as_f64) else {
+            return;
+        };
+        if !value.is_finite() || !valid(value) {
+            self.out_of_range(format!("{}{}", prefix, key), expected);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn lint_reports_aliases_and_out_of_range_values() {
+        let warnings = lint(&json!({
+            "encoder_version": 3,
+            "play_settings": {
+                "bpm": 120,
+                "tracks": [{
+                    "level": -1.0, "pan": 1.5, "ids": [1], "name": "a", "safe_name": "a",
+                    "weights": [1.0, -2.0], "candidate_labels": [{"id": 7, "label": "x"}],
+                    "candidate_trims": [{"id": 1, "start_ms": 40}, {"id": 8, "start_ms": 40}]
+                }],
+                "effects": [
+                    {"BasicReverbSettings": {"mix": 1.5}},
+                    {"ConvolutionReverbSettings": {"impulse_response_tail": -60.0}}
+                ]
+            }
+        }));
+        let found: Vec<(&str, LintKind)> = warnings
+            .iter()
+            .map(|warning| (warning.path.as_str(), warning.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("play_settings.bpm", LintKind::DeprecatedAlias),
+                ("play_settings.tracks[0].level", LintKind::OutOfRange),
+                ("play_settings.tracks[0].pan", LintKind::OutOfRange),
+                ("play_settings.tracks[0].weights[1]", LintKind::OutOfRange),
+                ("play_settings.tracks[0].weights", LintKind::OutOfRange),
+                (
+                    "play_settings.tracks[0].candidate_labels[0].id",
+                    LintKind::OutOfRange
+                ),
+                (
+                    "play_settings.tracks[0].candidate_trims[1].id",
+                    LintKind::OutOfRange
+                ),
+                (
+                    "play_settings.effects[0].BasicReverbSettings",
+                    LintKind::DeprecatedAlias
+                ),
+                (
+                    "play_settings.effects[0].BasicReverbSettings.mix",
+                    LintKind::OutOfRange
+                ),
+                (
+                    "play_settings.effects[1].ConvolutionReverbSettings.impulse_response_tail",
+                    LintKind::DeprecatedAlias
+                ),
+            ]
+        );
+        assert_eq!(
+            warnings[0].to_string(),
+            "play_settings.bpm: deprecated alias; use `tempo_bpm`"
+        );
+    }
+
+    #[test]
+    fn clean_settings_have_no_lints() {
+        let document = json!({"encoder_version": 4, "tempo_bpm": 96.0, "tracks": [
+            {"level": 0.8, "pan": -0.5, "ids": [1, 2], "name": "a", "safe_name": "a"}
+        ]});
+        assert!(lint(&document).is_empty());
+    }
+}
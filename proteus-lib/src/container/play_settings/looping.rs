@@ -0,0 +1,84 @@
+//! Per-track loop configuration.
+
+use serde::{Deserialize, Serialize};
+
+/// Default crossfade applied where a loop wraps back to its start.
+const DEFAULT_LOOP_CROSSFADE_MS: u64 = 20;
+
+/// How a track repeats: `true` loops the whole source, an object loops a
+/// region of it.
+///
+/// A looping track plays from the start of its source until the loop end,
+/// then keeps wrapping to the loop start until the song ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(untagged)]
+pub enum TrackLoop {
+    /// Loop the whole source when `true`; `false` disables looping.
+    Whole(bool),
+    /// Loop between two source positions.
+    Region(LoopRegion),
+}
+
+/// Source region repeated by a looping track.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct LoopRegion {
+    /// Loop start in source milliseconds.
+    #[serde(default)]
+    pub start_ms: u64,
+    /// Loop end in source milliseconds; the end of the source when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<u64>,
+    /// Crossfade length at each wrap, in milliseconds.
+    #[serde(default = "default_loop_crossfade_ms")]
+    pub crossfade_ms: u64,
+}
+
+impl Default for LoopRegion {
+    fn default() -> Self {
+        Self {
+            start_ms: 0,
+            end_ms: None,
+            crossfade_ms: DEFAULT_LOOP_CROSSFADE_MS,
+        }
+    }
+}
+
+impl TrackLoop {
+    /// Region to repeat, or `None` when looping is disabled.
+    pub fn region(&self) -> Option<LoopRegion> {
+        match self {
+            Self::Whole(true) => Some(LoopRegion::default()),
+            Self::Whole(false) => None,
+            Self::Region(region) => Some(*region),
+        }
+    }
+}
+
+fn default_loop_crossfade_ms() -> u64 {
+    DEFAULT_LOOP_CROSSFADE_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loop_accepts_a_flag_or_a_region() {
+        let whole: TrackLoop = serde_json::from_str("true").unwrap();
+        assert_eq!(whole.region(), Some(LoopRegion::default()));
+        let off: TrackLoop = serde_json::from_str("false").unwrap();
+        assert_eq!(off.region(), None);
+        let region: TrackLoop =
+            serde_json::from_str(r#"{"start_ms": 250, "end_ms": 900}"#).unwrap();
+        assert_eq!(
+            region.region(),
+            Some(LoopRegion {
+                start_ms: 250,
+                end_ms: Some(900),
+                crossfade_ms: DEFAULT_LOOP_CROSSFADE_MS,
+            })
+        );
+    }
+}
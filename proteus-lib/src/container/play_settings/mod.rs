@@ -1,96 +1,52 @@
 //! Serde models for `play_settings.json` with versioned decoding.
 
-use log::{info, warn};
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::borrow::Cow;
 
-use crate::dsp::effects::{AudioEffect, ConvolutionReverbSettings};
+use serde::{Deserialize, Serialize};
 
-pub(crate) mod legacy;
+use crate::container::loudness::LoudnessTag;
+use crate::dsp::pan::PanLaw;
 
+mod analysis;
+mod conditions;
+mod effect_settings;
+mod file;
+mod groups;
+mod labels;
+pub(crate) mod legacy;
+mod lint;
+mod looping;
+mod rules;
+#[cfg(feature = "schema")]
+mod schema;
+mod strict;
+mod trims;
+
+pub use analysis::{
+    candidate_analysis, key_clash_pairs, CandidateAnalysis, KeyMode, MusicalKey, PitchClass,
+};
+pub use conditions::{conditions_hold, CandidateCondition, RuntimeVariables, VariableCondition};
+pub use effect_settings::EffectSettings;
+pub(crate) use file::{
+    effects, encoder_version, extract_impulse_response_tail_db, extract_impulse_response_text,
+    PlaySettingsFile,
+};
+pub use groups::TrackGroup;
+pub use labels::{candidate_label, CandidateLabel};
 pub(crate) use legacy::{PlaySettingsLegacy, PlaySettingsLegacyFile};
-
-/// One entry in the `play_settings.json` DSP effect chain.
-///
-/// Recognized effect payloads decode into [`EffectSettings::Known`] so callers can
-/// work with [`AudioEffect`] directly. Unrecognized payloads are preserved as
-/// [`EffectSettings::Raw`] for forward compatibility and lossless round-tripping.
-#[derive(Debug, Clone)]
-pub enum EffectSettings {
-    /// A recognized effect entry with typed settings.
-    Known(Box<AudioEffect>),
-    /// An unrecognized or caller-provided raw effect payload.
-    Raw(serde_json::Value),
-}
-
-impl EffectSettings {
-    /// Return the typed effect when this entry is already known.
-    pub fn as_audio_effect(&self) -> Option<&AudioEffect> {
-        match self {
-            Self::Known(effect) => Some(effect.as_ref()),
-            Self::Raw(_) => None,
-        }
-    }
-
-    /// Decode this entry into a typed [`AudioEffect`].
-    pub fn decode_audio_effect(&self) -> serde_json::Result<AudioEffect> {
-        match self {
-            Self::Known(effect) => Ok(effect.as_ref().clone()),
-            Self::Raw(raw) => serde_json::from_value(raw.clone()),
-        }
-    }
-
-    /// Return the preserved raw JSON payload, if this entry is untyped.
-    pub fn as_raw_value(&self) -> Option<&serde_json::Value> {
-        match self {
-            Self::Known(_) => None,
-            Self::Raw(raw) => Some(raw),
-        }
-    }
-
-    fn raw_wrapper_object(&self, key: &str) -> Option<&serde_json::Map<String, serde_json::Value>> {
-        self.as_raw_value()?.as_object()?.get(key)?.as_object()
-    }
-}
-
-impl From<AudioEffect> for EffectSettings {
-    fn from(effect: AudioEffect) -> Self {
-        Self::Known(Box::new(effect))
-    }
-}
-
-impl From<serde_json::Value> for EffectSettings {
-    fn from(value: serde_json::Value) -> Self {
-        match serde_json::from_value::<AudioEffect>(value.clone()) {
-            Ok(effect) => Self::Known(Box::new(effect)),
-            Err(_) => Self::Raw(value),
-        }
-    }
-}
-
-impl Serialize for EffectSettings {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        match self {
-            Self::Known(effect) => effect.serialize(serializer),
-            Self::Raw(raw) => raw.serialize(serializer),
-        }
-    }
-}
-
-impl<'de> Deserialize<'de> for EffectSettings {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        Ok(Self::from(value))
-    }
-}
+pub use lint::{lint, LintKind, LintWarning};
+pub use looping::{LoopRegion, TrackLoop};
+pub use rules::{SelectionGroup, SelectionMode, SelectionRules};
+#[cfg(feature = "schema")]
+pub use schema::{json_schema, PlaySettingsVersion};
+pub(crate) use strict::decode_strict;
+pub use strict::{check_strict, ParseMode, PlaySettingsError};
+pub use trims::{candidate_trim, CandidateTrim};
+pub(crate) use trims::{settings_tracks, settings_tracks_mut, write_candidate_entries};
 
 /// Track-level configuration shared by newer settings versions.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct SettingsTrack {
     /// Playback volume level for this track (linear gain, 1.0 = unity).
     pub level: f32,
@@ -108,10 +64,63 @@ pub struct SettingsTrack {
     /// Named shuffle points at which the track may rotate to the next selection.
     #[serde(default)]
     pub shuffle_points: Vec<String>,
+    /// Relative selection weight for each entry in `ids` (version 4).
+    ///
+    /// Missing entries default to `1.0`; when empty, every id is equally likely.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub weights: Vec<f32>,
+    /// How the next candidate is picked at each shuffle point.
+    #[serde(default, skip_serializing_if = "SelectionMode::is_random")]
+    pub selection_mode: SelectionMode,
+    /// Markov transition weights: row `i` weights the pick that follows `ids[i]`.
+    ///
+    /// Rows are parallel to `ids`; missing entries count as `0.0`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transitions: Vec<Vec<f32>>,
+    /// Runtime conditions that must all hold for a shuffle point to reselect this track.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub shuffle_when: Vec<VariableCondition>,
+    /// Runtime conditions gating individual candidates.
+    ///
+    /// A candidate is eligible only while all its conditions hold. When no
+    /// candidate is eligible, every candidate is.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidate_when: Vec<CandidateCondition>,
+    /// Display labels and artwork references for individual candidates.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidate_labels: Vec<CandidateLabel>,
+    /// Source trims for candidates with leading or trailing silence.
+    ///
+    /// Written by [`detect_candidate_trims`](crate::container::silence::detect_candidate_trims)
+    /// so badly exported stems still line up.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidate_trims: Vec<CandidateTrim>,
+    /// Estimated tempo and key of individual candidates.
+    ///
+    /// Written by [`analyze_key_tempo`](crate::tools::key_tempo::analyze_key_tempo);
+    /// read by `selection_rules.avoid_key_clashes`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub candidate_analysis: Vec<CandidateAnalysis>,
+    /// Timeline position, in milliseconds, where this track's audio begins.
+    ///
+    /// The track is silent until then; a value of `0` starts it with the song.
+    #[serde(default, skip_serializing_if = "is_zero_ms")]
+    pub start_offset_ms: u64,
+    /// Repeat this track until the song ends; see [`TrackLoop`].
+    #[serde(default, rename = "loop", skip_serializing_if = "Option::is_none")]
+    pub looping: Option<TrackLoop>,
+    /// Name of the [`TrackGroup`] whose gain rides this track.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// 3D position for binaural rendering; replaces `pan` when present.
+    #[cfg(feature = "hrtf")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binaural: Option<crate::dsp::effects::BinauralPannerSettings>,
 }
 
 /// Shared payload used by versioned `play_settings.json` schemas.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct PlaySettingsPayload {
     /// DSP effect chain applied to the final mix, in processing order.
     #[serde(default)]
@@ -119,10 +128,80 @@ pub struct PlaySettingsPayload {
     /// Per-track volume, pan, and selection configuration.
     #[serde(default)]
     pub tracks: Vec<SettingsTrack>,
+    /// Song tempo in beats per minute, used by tempo-synced effects.
+    #[serde(default, alias = "bpm", skip_serializing_if = "Option::is_none")]
+    pub tempo_bpm: Option<f32>,
+    /// Constraints on which ids may be selected together (version 4).
+    #[serde(default, skip_serializing_if = "SelectionRules::is_empty")]
+    pub selection_rules: SelectionRules,
+    /// Named timeline regions that can be queued at runtime (version 4).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sections: Vec<SettingsSection>,
+    /// Loudness tag applied as an input trim; overrides container ReplayGain tags.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness: Option<LoudnessTag>,
+    /// Initial gains of the track groups referenced by `tracks[].group`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<TrackGroup>,
+    /// Taper used to pan tracks; [`PanLaw::Balance`] when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pan_law: Option<PanLaw>,
+    /// Effect parameter changes applied at timeline positions (version 4).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub effect_automation: Vec<EffectAutomationEvent>,
+}
+
+impl PlaySettingsPayload {
+    /// Selection rules with `avoid_key_clashes` expanded into
+    /// `never_together` pairs from the tracks' `candidate_analysis`.
+    pub fn effective_selection_rules(&self) -> Cow<'_, SelectionRules> {
+        if !self.selection_rules.avoid_key_clashes {
+            return Cow::Borrowed(&self.selection_rules);
+        }
+        let mut rules = self.selection_rules.clone();
+        for pair in key_clash_pairs(&self.tracks) {
+            if !rules.never_together.contains(&pair) {
+                rules.never_together.push(pair);
+            }
+        }
+        Cow::Owned(rules)
+    }
+}
+
+/// Effect parameter change applied at a timeline position.
+///
+/// Timestamps use the same format as `shuffle_points`, so a change can land
+/// exactly on a shuffle point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct EffectAutomationEvent {
+    /// Timestamp at which the change applies.
+    pub at: String,
+    /// Index of the target effect in `effects`.
+    pub effect: usize,
+    /// Parameter to change, e.g. `reverb_mix` or `low_pass_freq_hz`.
+    pub parameter: String,
+    /// New parameter value.
+    pub value: f32,
+}
+
+/// Named region of the timeline used for horizontal re-sequencing.
+///
+/// Timestamps use the same format as `shuffle_points`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SettingsSection {
+    /// Name passed to `Player::queue_section`.
+    pub name: String,
+    /// Section start timestamp.
+    pub start: String,
+    /// Section end timestamp (exclusive).
+    pub end: String,
 }
 
 /// Top-level wrapper shared by versioned settings files.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub(crate) struct VersionedPlaySettingsFile<T> {
     /// The settings payload, which may be nested under a `play_settings` key or flat.
     #[serde(flatten)]
@@ -141,13 +220,22 @@ pub(crate) type PlaySettingsV2File = VersionedPlaySettingsFile<PlaySettingsV2>;
 pub(crate) type PlaySettingsV3 = PlaySettingsPayload;
 /// Top-level wrapper for V3 settings files.
 pub(crate) type PlaySettingsV3File = VersionedPlaySettingsFile<PlaySettingsV3>;
+/// Version 4 settings payload, adding selection rules and candidate weights.
+pub(crate) type PlaySettingsV4 = PlaySettingsPayload;
+/// Top-level wrapper for V4 settings files.
+pub(crate) type PlaySettingsV4File = VersionedPlaySettingsFile<PlaySettingsV4>;
 
 fn default_selections_count() -> u32 {
     1
 }
 
+fn is_zero_ms(value: &u64) -> bool {
+    *value == 0
+}
+
 /// Wrapper allowing `play_settings` to be nested or flat.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(untagged)]
 pub(crate) enum PlaySettingsContainer<T> {
     /// Settings wrapped under a `play_settings` key in the JSON object.
@@ -177,226 +265,10 @@ impl<T> PlaySettingsContainer<T> {
     }
 }
 
-/// Versioned settings file representation.
-#[derive(Debug, Clone)]
-pub(crate) enum PlaySettingsFile {
-    /// Legacy (pre-versioned) settings format without an `encoder_version` field.
-    Legacy(PlaySettingsLegacyFile),
-    /// Version 1 settings format.
-    V1(PlaySettingsV1File),
-    /// Version 2 settings format.
-    V2(PlaySettingsV2File),
-    /// Version 3 settings format.
-    V3(PlaySettingsV3File),
-    /// Settings with an unrecognized `encoder_version`; raw JSON is preserved.
-    Unknown {
-        /// The raw JSON value preserved for round-trip serialization.
-        raw: serde_json::Value,
-    },
-}
-
-impl PlaySettingsFile {
-    /// Return normalized modern payload for V1/V2/V3 settings.
-    pub(crate) fn versioned_payload(&self) -> Option<&PlaySettingsPayload> {
-        match self {
-            PlaySettingsFile::V1(file) => Some(file.settings.inner()),
-            PlaySettingsFile::V2(file) => Some(file.settings.inner()),
-            PlaySettingsFile::V3(file) => Some(file.settings.inner()),
-            _ => None,
-        }
-    }
-
-    /// Return mutable normalized modern payload for V1/V2/V3 settings.
-    pub(crate) fn versioned_payload_mut(&mut self) -> Option<&mut PlaySettingsPayload> {
-        match self {
-            PlaySettingsFile::V1(file) => Some(file.settings.inner_mut()),
-            PlaySettingsFile::V2(file) => Some(file.settings.inner_mut()),
-            PlaySettingsFile::V3(file) => Some(file.settings.inner_mut()),
-            _ => None,
-        }
-    }
-}
-
-/// Return raw effect entries for versioned settings files.
-pub(crate) fn effects(play_settings: &PlaySettingsFile) -> Option<&[EffectSettings]> {
-    play_settings
-        .versioned_payload()
-        .map(|payload| payload.effects.as_slice())
-}
-
-enum ConvolutionReverbSettingsView<'a> {
-    Typed(&'a ConvolutionReverbSettings),
-    Raw(&'a serde_json::Map<String, serde_json::Value>),
-}
-
-/// Return the first convolution-reverb effect payload object, if present.
-fn first_convolution_reverb_settings(
-    play_settings: &PlaySettingsFile,
-) -> Option<ConvolutionReverbSettingsView<'_>> {
-    let effects = effects(play_settings)?;
-    effects.iter().find_map(|effect| match effect {
-        EffectSettings::Known(boxed) => match boxed.as_ref() {
-            AudioEffect::ConvolutionReverb(effect) => {
-                Some(ConvolutionReverbSettingsView::Typed(effect.settings()))
-            }
-            _ => None,
-        },
-        _ => effect
-            .raw_wrapper_object("ConvolutionReverbSettings")
-            .map(ConvolutionReverbSettingsView::Raw),
-    })
-}
-
-/// Extract raw impulse-response setting text from play settings.
-pub(crate) fn extract_impulse_response_text(play_settings: &PlaySettingsFile) -> Option<String> {
-    match first_convolution_reverb_settings(play_settings)? {
-        ConvolutionReverbSettingsView::Typed(settings) => settings
-            .impulse_response
-            .as_deref()
-            .or(settings.impulse_response_attachment.as_deref())
-            .or(settings.impulse_response_path.as_deref())
-            .map(ToString::to_string),
-        ConvolutionReverbSettingsView::Raw(settings) => settings
-            .get("impulse_response")
-            .and_then(serde_json::Value::as_str)
-            .or_else(|| {
-                settings
-                    .get("impulse_response_attachment")
-                    .and_then(serde_json::Value::as_str)
-            })
-            .or_else(|| {
-                settings
-                    .get("impulse_response_path")
-                    .and_then(serde_json::Value::as_str)
-            })
-            .map(ToString::to_string),
-    }
-}
-
-/// Extract configured convolution-reverb tail dB from play settings.
-pub(crate) fn extract_impulse_response_tail_db(play_settings: &PlaySettingsFile) -> Option<f32> {
-    match first_convolution_reverb_settings(play_settings)? {
-        ConvolutionReverbSettingsView::Typed(settings) => settings
-            .impulse_response_tail_db
-            .or(settings.impulse_response_tail),
-        ConvolutionReverbSettingsView::Raw(settings) => settings
-            .get("impulse_response_tail_db")
-            .and_then(serde_json::Value::as_f64)
-            .map(|value| value as f32)
-            .or_else(|| {
-                settings
-                    .get("impulse_response_tail")
-                    .and_then(serde_json::Value::as_f64)
-                    .map(|value| value as f32)
-            }),
-    }
-}
-
-impl<'de> Deserialize<'de> for PlaySettingsFile {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let value = serde_json::Value::deserialize(deserializer)?;
-        let encoder_version = value.get("encoder_version").and_then(|raw| match raw {
-            serde_json::Value::String(version) => Some(version.clone()),
-            serde_json::Value::Number(number) => number
-                .as_f64()
-                .map(|val| {
-                    if (val - 1.0).abs() < f64::EPSILON {
-                        "1".to_string()
-                    } else if (val - 2.0).abs() < f64::EPSILON {
-                        "2".to_string()
-                    } else if (val - 3.0).abs() < f64::EPSILON {
-                        "3".to_string()
-                    } else {
-                        number.to_string()
-                    }
-                })
-                .or_else(|| Some(number.to_string())),
-            _ => None,
-        });
-
-        info!("encoder version: {:?}", encoder_version);
-
-        let parsed = match encoder_version.as_deref() {
-            None => serde_json::from_value::<PlaySettingsLegacyFile>(value.clone())
-                .map(PlaySettingsFile::Legacy),
-            Some("1") => serde_json::from_value::<PlaySettingsV1File>(value.clone())
-                .map(PlaySettingsFile::V1),
-            Some("2") => serde_json::from_value::<PlaySettingsV2File>(value.clone())
-                .map(PlaySettingsFile::V2),
-            Some("3") => serde_json::from_value::<PlaySettingsV3File>(value.clone())
-                .map(PlaySettingsFile::V3),
-            Some(version) => {
-                warn!("unknown encoder version: {:?}", version);
-                return Ok(PlaySettingsFile::Unknown { raw: value });
-            }
-        };
-
-        parsed.or_else(|_| Ok(PlaySettingsFile::Unknown { raw: value }))
-    }
-}
-
-impl Serialize for PlaySettingsFile {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        fn with_version<T, S>(payload: &T, version: &str, serializer: S) -> Result<S::Ok, S::Error>
-        where
-            T: Serialize,
-            S: Serializer,
-        {
-            let mut value = serde_json::to_value(payload).map_err(serde::ser::Error::custom)?;
-            match value {
-                serde_json::Value::Object(ref mut map) => {
-                    map.insert(
-                        "encoder_version".to_string(),
-                        serde_json::Value::String(version.to_string()),
-                    );
-                }
-                other => {
-                    let mut map = serde_json::Map::new();
-                    map.insert(
-                        "encoder_version".to_string(),
-                        serde_json::Value::String(version.to_string()),
-                    );
-                    map.insert("play_settings".to_string(), other);
-                    value = serde_json::Value::Object(map);
-                }
-            }
-            value.serialize(serializer)
-        }
-
-        match self {
-            PlaySettingsFile::Legacy(file) => file.serialize(serializer),
-            PlaySettingsFile::V1(file) => with_version(file, "1", serializer),
-            PlaySettingsFile::V2(file) => with_version(file, "2", serializer),
-            PlaySettingsFile::V3(file) => with_version(file, "3", serializer),
-            PlaySettingsFile::Unknown { raw, .. } => raw.serialize(serializer),
-        }
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    impl PlaySettingsFile {
-        fn encoder_version(&self) -> Option<&str> {
-            match self {
-                PlaySettingsFile::Legacy(_) => None,
-                PlaySettingsFile::V1(_) => Some("1"),
-                PlaySettingsFile::V2(_) => Some("2"),
-                PlaySettingsFile::V3(_) => Some("3"),
-                PlaySettingsFile::Unknown { raw } => {
-                    raw.get("encoder_version").and_then(|v| v.as_str())
-                }
-            }
-        }
-    }
-
     #[test]
     fn container_inner_accessors_work_for_both_variants() {
         let mut nested = PlaySettingsContainer::Nested {
@@ -412,29 +284,6 @@ mod tests {
         assert_eq!(*flat.inner(), 4);
     }
 
-    #[test]
-    fn deserialize_versioned_settings_and_preserve_encoder_version_on_serialize() {
-        let parsed: PlaySettingsFile = serde_json::from_str(
-            r#"{
-                "encoder_version": "1",
-                "play_settings": { "effects": [], "tracks": [] }
-            }"#,
-        )
-        .unwrap();
-
-        assert_eq!(parsed.encoder_version(), Some("1"));
-        let serialized = serde_json::to_value(parsed).unwrap();
-        assert_eq!(serialized["encoder_version"], "1");
-    }
-
-    #[test]
-    fn deserialize_unknown_encoder_version_as_unknown_variant() {
-        let parsed: PlaySettingsFile =
-            serde_json::from_str(r#"{"encoder_version": "99", "play_settings": {}}"#).unwrap();
-        assert!(matches!(parsed, PlaySettingsFile::Unknown { .. }));
-        assert_eq!(parsed.encoder_version(), Some("99"));
-    }
-
     #[test]
     fn versioned_payload_defaults_to_empty_lists() {
         let v1: PlaySettingsV1 = serde_json::from_str("{}").unwrap();
@@ -447,23 +296,45 @@ mod tests {
     }
 
     #[test]
-    fn effect_settings_deserializes_known_effects_to_typed_variant() {
-        let effect: EffectSettings =
-            serde_json::from_str(r#"{"GainSettings":{"enabled":true,"gain":1.25}}"#).unwrap();
-
+    fn key_clash_avoidance_adds_never_together_pairs() {
+        let track = |ids: &str, analysis: &str| {
+            format!(
+                r#"{{"level": 1.0, "pan": 0.0, "ids": {ids}, "name": "t", "safe_name": "t",
+                    "candidate_analysis": {analysis}}}"#
+            )
+        };
+        let payload: PlaySettingsV4 = serde_json::from_str(&format!(
+            r#"{{"tracks": [{}, {}], "selection_rules": {{"never_together": [[1, 9]]}}}}"#,
+            track(
+                "[1, 2]",
+                r#"[{"id": 1, "key": {"tonic": "C", "mode": "major"}},
+                    {"id": 2, "key": {"tonic": "F#", "mode": "major"}}]"#
+            ),
+            track(
+                "[3]",
+                r#"[{"id": 3, "tempo_bpm": 120.0, "key": {"tonic": "A", "mode": "minor"}}]"#
+            ),
+        ))
+        .unwrap();
         assert!(matches!(
-            effect.as_audio_effect(),
-            Some(AudioEffect::Gain(_))
+            payload.effective_selection_rules(),
+            Cow::Borrowed(_)
         ));
+
+        let mut payload = payload;
+        payload.selection_rules.avoid_key_clashes = true;
+        assert_eq!(
+            payload.effective_selection_rules().never_together,
+            vec![[1, 9], [2, 3]]
+        );
     }
 
     #[test]
-    fn effect_settings_preserves_unknown_effects_as_raw_json() {
-        let effect: EffectSettings =
-            serde_json::from_str(r#"{"CustomEffectSettings":{"enabled":true,"amount":0.5}}"#)
-                .unwrap();
+    fn versioned_payload_reads_optional_tempo() {
+        let payload: PlaySettingsV3 = serde_json::from_str(r#"{"bpm":96.0}"#).unwrap();
+        assert_eq!(payload.tempo_bpm, Some(96.0));
 
-        let raw = effect.as_raw_value().unwrap();
-        assert_eq!(raw["CustomEffectSettings"]["amount"], 0.5);
+        let serialized = serde_json::to_value(&payload).unwrap();
+        assert_eq!(serialized["tempo_bpm"], 96.0);
     }
 }
@@ -0,0 +1,174 @@
+//! Selection modes and constraints introduced by `play_settings` version 4.
+//!
+//! Rules restrict which container track ids may be selected at the same time
+//! across all slots. They are evaluated against a complete selection, so the
+//! schedule builder can check every candidate selection it draws. Selection
+//! modes control how each slot picks its next candidate at a shuffle point.
+
+use serde::{Deserialize, Serialize};
+
+/// How a track picks its next candidate at each shuffle point.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionMode {
+    /// Independent draw every time, weighted by `weights` when present.
+    #[default]
+    Random,
+    /// Draw from the `transitions` row of the previously selected candidate.
+    ///
+    /// The first pick, and any pick whose row is missing or all zero, falls
+    /// back to a random draw.
+    Markov,
+    /// Step to the next candidate in `ids` order, wrapping at the end.
+    ///
+    /// The first pick is a random draw.
+    Sequential,
+}
+
+impl SelectionMode {
+    /// Return `true` for the default [`SelectionMode::Random`] mode.
+    pub fn is_random(&self) -> bool {
+        *self == Self::Random
+    }
+}
+
+/// Selection constraints applied whenever slots are (re)selected.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SelectionRules {
+    /// Named groups limiting how many of their ids may be active at once.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<SelectionGroup>,
+    /// Pairs of ids that must never be selected at the same time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub never_together: Vec<[u32; 2]>,
+    /// Keep ids whose analyzed keys clash from being selected together.
+    ///
+    /// Reads `candidate_analysis` on the tracks; see
+    /// [`MusicalKey::is_compatible`](super::MusicalKey::is_compatible).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub avoid_key_clashes: bool,
+}
+
+/// A named set of track ids with an activity constraint.
+///
+/// An id is active when any slot currently selects it. When both limits are
+/// set, `exactly` takes precedence.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct SelectionGroup {
+    /// Display name used in diagnostics.
+    #[serde(default)]
+    pub name: String,
+    /// Track ids belonging to the group.
+    pub ids: Vec<u32>,
+    /// At most this many group ids may be active at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_active: Option<u32>,
+    /// Exactly this many group ids must be active at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exactly: Option<u32>,
+}
+
+impl SelectionRules {
+    /// Return `true` when no constraint is configured.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty() && self.never_together.is_empty() && !self.avoid_key_clashes
+    }
+
+    /// Count how far `selection` is from satisfying every rule.
+    ///
+    /// Returns `0` when all rules hold. Group violations count the number of
+    /// ids over (or under) the limit; each co-selected exclusion pair counts
+    /// once.
+    pub fn violations(&self, selection: &[u32]) -> usize {
+        let group_violations: usize = self
+            .groups
+            .iter()
+            .map(|group| group.violations(selection))
+            .sum();
+        let pair_violations = self
+            .never_together
+            .iter()
+            .filter(|[a, b]| a != b && selection.contains(a) && selection.contains(b))
+            .count();
+        group_violations + pair_violations
+    }
+}
+
+impl SelectionGroup {
+    fn violations(&self, selection: &[u32]) -> usize {
+        let active = self.ids.iter().filter(|id| selection.contains(id)).count();
+        match (self.exactly, self.max_active) {
+            (Some(exactly), _) => active.abs_diff(exactly as usize),
+            (None, Some(max_active)) => active.saturating_sub(max_active as usize),
+            (None, None) => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(ids: Vec<u32>, max_active: Option<u32>, exactly: Option<u32>) -> SelectionGroup {
+        SelectionGroup {
+            name: "group".to_string(),
+            ids,
+            max_active,
+            exactly,
+        }
+    }
+
+    #[test]
+    fn group_limits_count_active_ids() {
+        let at_most_one = SelectionRules {
+            groups: vec![group(vec![1, 2, 3], Some(1), None)],
+            ..SelectionRules::default()
+        };
+        assert_eq!(at_most_one.violations(&[1, 4]), 0);
+        assert_eq!(at_most_one.violations(&[1, 2, 3]), 2);
+
+        let exactly_two = SelectionRules {
+            groups: vec![group(vec![1, 2, 3], Some(3), Some(2))],
+            ..SelectionRules::default()
+        };
+        assert_eq!(exactly_two.violations(&[1, 2]), 0);
+        assert_eq!(exactly_two.violations(&[4, 5]), 2);
+    }
+
+    #[test]
+    fn never_together_counts_co_selected_pairs() {
+        let rules = SelectionRules {
+            never_together: vec![[1, 2], [3, 3]],
+            ..SelectionRules::default()
+        };
+        assert_eq!(rules.violations(&[1, 3]), 0);
+        assert_eq!(rules.violations(&[2, 1]), 1);
+        assert!(SelectionRules::default().is_empty());
+    }
+
+    #[test]
+    fn selection_mode_uses_snake_case_names() {
+        let mode: SelectionMode = serde_json::from_str(r#""markov""#).unwrap();
+        assert_eq!(mode, SelectionMode::Markov);
+        assert_eq!(
+            serde_json::to_value(SelectionMode::Sequential).unwrap(),
+            "sequential"
+        );
+        assert!(SelectionMode::default().is_random());
+    }
+
+    #[test]
+    fn rules_round_trip_through_json() {
+        let rules: SelectionRules = serde_json::from_str(
+            r#"{"groups":[{"name":"leads","ids":[3,4],"max_active":1}],"never_together":[[5,6]]}"#,
+        )
+        .unwrap();
+        assert_eq!(rules.groups[0].max_active, Some(1));
+        assert_eq!(rules.never_together, vec![[5, 6]]);
+        let value = serde_json::to_value(&rules).unwrap();
+        assert!(value["groups"][0].get("exactly").is_none());
+    }
+}
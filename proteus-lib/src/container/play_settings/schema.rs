@@ -0,0 +1,159 @@
+//! JSON Schema export for `play_settings.json` (feature `schema`).
+//!
+//! Schemas are generated from the same serde models the decoder uses, so an
+//! editor that validates against them accepts what the strict decoder
+//! accepts. Versions 1 to 4 share one payload model and differ only in the
+//! declared `encoder_version`.
+
+use schemars::generate::SchemaSettings;
+use schemars::{JsonSchema, Schema};
+use serde_json::{json, Value};
+
+use super::{PlaySettingsLegacyFile, PlaySettingsPayload, VersionedPlaySettingsFile};
+
+/// A `play_settings.json` schema version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaySettingsVersion {
+    /// Unversioned settings without an `encoder_version`.
+    Legacy,
+    /// `encoder_version` 1.
+    V1,
+    /// `encoder_version` 2.
+    V2,
+    /// `encoder_version` 3.
+    V3,
+    /// `encoder_version` 4.
+    V4,
+}
+
+impl PlaySettingsVersion {
+    /// Every supported version, oldest first.
+    pub const ALL: [Self; 5] = [Self::Legacy, Self::V1, Self::V2, Self::V3, Self::V4];
+
+    /// The `encoder_version` value, or `None` for legacy settings.
+    pub fn encoder_version(self) -> Option<&'static str> {
+        match self {
+            Self::Legacy => None,
+            Self::V1 => Some("1"),
+            Self::V2 => Some("2"),
+            Self::V3 => Some("3"),
+            Self::V4 => Some("4"),
+        }
+    }
+
+    /// Parse `legacy`, or an `encoder_version` such as `4` or `v4`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("legacy") {
+            return Some(Self::Legacy);
+        }
+        let number = value.trim_start_matches(['v', 'V']);
+        Self::ALL
+            .into_iter()
+            .find(|version| version.encoder_version() == Some(number))
+    }
+
+    /// Short label, `legacy` or `v1` to `v4`.
+    pub fn label(self) -> String {
+        self.encoder_version()
+            .map_or_else(|| "legacy".to_string(), |version| format!("v{}", version))
+    }
+}
+
+/// Generate the JSON Schema (draft 2020-12) for one settings version.
+pub fn json_schema(version: PlaySettingsVersion) -> Value {
+    let mut schema = match version {
+        PlaySettingsVersion::Legacy => generate::<PlaySettingsLegacyFile>(),
+        _ => generate::<VersionedPlaySettingsFile<PlaySettingsPayload>>(),
+    };
+    let root = schema.ensure_object();
+    root.insert(
+        "title".to_string(),
+        json!(format!("Proteus play_settings.json ({})", version.label())),
+    );
+    match version.encoder_version() {
+        // The decoder accepts the version as a string or a number.
+        Some(encoder_version) => {
+            let number: u32 = encoder_version.parse().unwrap_or_default();
+            if let Some(properties) = root
+                .entry("properties")
+                .or_insert_with(|| json!({}))
+                .as_object_mut()
+            {
+                properties.insert(
+                    "encoder_version".to_string(),
+                    json!({ "enum": [encoder_version, number] }),
+                );
+            }
+            if let Some(required) = root
+                .entry("required")
+                .or_insert_with(|| json!([]))
+                .as_array_mut()
+            {
+                required.push(json!("encoder_version"));
+            }
+        }
+        None => {
+            root.insert(
+                "not".to_string(),
+                json!({ "required": ["encoder_version"] }),
+            );
+        }
+    }
+    schema.to_value()
+}
+
+fn generate<T: JsonSchema>() -> Schema {
+    SchemaSettings::draft2020_12()
+        .into_generator()
+        .into_root_schema_for::<T>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versioned_schema_describes_tracks_and_pins_encoder_version() {
+        let schema = json_schema(PlaySettingsVersion::V4);
+        assert_eq!(
+            schema["properties"]["encoder_version"]["enum"],
+            json!(["4", 4])
+        );
+        assert!(schema["required"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("encoder_version")));
+        let text = schema.to_string();
+        for expected in [
+            "safe_name",
+            "selection_rules",
+            "GainSettings",
+            "shuffle_points",
+        ] {
+            assert!(text.contains(expected), "schema lacks {}", expected);
+        }
+
+        let legacy = json_schema(PlaySettingsVersion::Legacy);
+        assert!(legacy.to_string().contains("startingIndex"));
+        assert_eq!(legacy["not"]["required"], json!(["encoder_version"]));
+    }
+
+    #[test]
+    fn versions_parse_from_labels() {
+        assert_eq!(
+            PlaySettingsVersion::parse("legacy"),
+            Some(PlaySettingsVersion::Legacy)
+        );
+        assert_eq!(
+            PlaySettingsVersion::parse("3"),
+            Some(PlaySettingsVersion::V3)
+        );
+        assert_eq!(
+            PlaySettingsVersion::parse("v4"),
+            Some(PlaySettingsVersion::V4)
+        );
+        assert_eq!(PlaySettingsVersion::parse("7"), None);
+        assert_eq!(PlaySettingsVersion::V2.label(), "v2");
+    }
+}
@@ -0,0 +1,267 @@
+//! Strict `play_settings.json` decoding with path-level errors.
+//!
+//! The lenient decoder keeps playback going: a payload that fails to decode
+//! becomes [`PlaySettingsFile::Unknown`], a broken nested payload falls back to
+//! an empty flat one, and unrecognized effects are kept as raw JSON. Strict
+//! mode rejects all of these and names the offending value instead.
+
+use std::fmt;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Map, Value};
+
+use super::legacy::PlaySettingsTrackLegacy;
+use super::{encoder_version, PlaySettingsFile, PlaySettingsPayload, SettingsTrack};
+use crate::dsp::effects::AudioEffect;
+
+/// How `play_settings.json` failures are handled while loading a container.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// Fall back to unversioned or default settings and log a warning.
+    #[default]
+    Lenient,
+    /// Fail with a [`PlaySettingsError`] naming the offending value.
+    Strict,
+}
+
+/// Strict-mode failure while decoding `play_settings.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaySettingsError {
+    /// Path to the offending value, e.g. `play_settings.tracks[2].pan`; empty
+    /// for the document itself.
+    pub path: String,
+    /// What is wrong, including the expected type where serde reports one.
+    pub message: String,
+    /// Declared `encoder_version`, or `None` for legacy settings.
+    pub version: Option<String>,
+}
+
+impl fmt::Display for PlaySettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "document"
+        } else {
+            &self.path
+        };
+        match &self.version {
+            Some(version) => write!(
+                f,
+                "{}: {} (encoder_version {})",
+                path, self.message, version
+            ),
+            None => write!(f, "{}: {} (legacy settings)", path, self.message),
+        }
+    }
+}
+
+impl std::error::Error for PlaySettingsError {}
+
+/// Check a parsed `play_settings.json` document in strict mode.
+///
+/// # Errors
+///
+/// Returns the first value that the lenient decoder would drop, default, or
+/// preserve as raw JSON.
+pub fn check_strict(document: &Value) -> Result<(), PlaySettingsError> {
+    decode_strict(document).map(|_| ())
+}
+
+/// Decode `document`, failing where the lenient decoder would fall back.
+pub(crate) fn decode_strict(document: &Value) -> Result<PlaySettingsFile, PlaySettingsError> {
+    let version = encoder_version(document);
+    let error = |path: String, message: String| PlaySettingsError {
+        path,
+        message,
+        version: version.clone(),
+    };
+    if !document.is_object() {
+        return Err(error(String::new(), "expected a JSON object".to_string()));
+    }
+    let (prefix, payload) = split_payload(document);
+    let Some(payload) = payload.as_object() else {
+        return Err(error(
+            prefix.trim_end_matches('.').to_string(),
+            "expected a JSON object".to_string(),
+        ));
+    };
+
+    let checked = match version.as_deref() {
+        None => check_fields::<super::PlaySettingsLegacy>(payload, prefix, |key, value| {
+            check_tracks::<PlaySettingsTrackLegacy>(key, value, prefix, &Map::new())
+        }),
+        Some("1" | "2" | "3" | "4") => {
+            check_fields::<PlaySettingsPayload>(payload, prefix, |key, value| match key {
+                "tracks" => check_tracks::<SettingsTrack>(key, value, prefix, &track_base()),
+                "effects" => check_effects(value, prefix),
+                _ => None,
+            })
+        }
+        Some(_) => Some((
+            "encoder_version".to_string(),
+            "unsupported encoder version".to_string(),
+        )),
+    };
+    if let Some((path, message)) = checked {
+        return Err(error(path, message));
+    }
+
+    match serde_json::from_value::<PlaySettingsFile>(document.clone()) {
+        Ok(PlaySettingsFile::Unknown { .. }) => Err(error(
+            String::new(),
+            "settings match no supported schema".to_string(),
+        )),
+        Ok(parsed) => Ok(parsed),
+        Err(err) => Err(error(String::new(), err.to_string())),
+    }
+}
+
+/// Split a document into its path prefix and payload, nested or flat.
+pub(super) fn split_payload(document: &Value) -> (&'static str, &Value) {
+    match document.get("play_settings") {
+        Some(nested) => ("play_settings.", nested),
+        None => ("", document),
+    }
+}
+
+// Decode each field of `object` on its own so a failure names its key. A
+// failing field is first handed to `descend` for a more precise location.
+fn check_fields<T: DeserializeOwned>(
+    object: &Map<String, Value>,
+    prefix: &str,
+    descend: impl Fn(&str, &Value) -> Option<(String, String)>,
+) -> Option<(String, String)> {
+    for (key, value) in object {
+        if let Some(found) = descend(key, value) {
+            return Some(found);
+        }
+        let single = Value::Object(Map::from_iter([(key.clone(), value.clone())]));
+        if let Err(err) = serde_json::from_value::<T>(single) {
+            return Some((format!("{}{}", prefix, key), err.to_string()));
+        }
+    }
+    serde_json::from_value::<T>(Value::Object(object.clone()))
+        .err()
+        .map(|err| (prefix.trim_end_matches('.').to_string(), err.to_string()))
+}
+
+fn check_tracks<T: DeserializeOwned>(
+    key: &str,
+    value: &Value,
+    prefix: &str,
+    base: &Map<String, Value>,
+) -> Option<(String, String)> {
+    if key != "tracks" {
+        return None;
+    }
+    value
+        .as_array()?
+        .iter()
+        .enumerate()
+        .find_map(|(index, track)| {
+            let path = format!("{}tracks[{}]", prefix, index);
+            let Some(track) = track.as_object() else {
+                return Some((path, "expected a JSON object".to_string()));
+            };
+            let field_error = track.iter().find_map(|(field, value)| {
+                let mut probe = base.clone();
+                probe.insert(field.clone(), value.clone());
+                serde_json::from_value::<T>(Value::Object(probe))
+                    .err()
+                    .map(|err| (format!("{}.{}", path, field), err.to_string()))
+            });
+            field_error.or_else(|| {
+                serde_json::from_value::<T>(Value::Object(track.clone()))
+                    .err()
+                    .map(|err| (path, err.to_string()))
+            })
+        })
+}
+
+// Smallest valid track, so single fields can be decoded in isolation.
+fn track_base() -> Map<String, Value> {
+    let base = json!({"level": 1.0, "pan": 0.0, "ids": [], "name": "", "safe_name": ""});
+    base.as_object().cloned().unwrap_or_default()
+}
+
+fn check_effects(value: &Value, prefix: &str) -> Option<(String, String)> {
+    value
+        .as_array()?
+        .iter()
+        .enumerate()
+        .find_map(|(index, effect)| {
+            let path = format!("{}effects[{}]", prefix, index);
+            let err = serde_json::from_value::<AudioEffect>(effect.clone()).err()?;
+            // Effect settings default every field, so each one decodes alone.
+            let field_error = effect
+                .as_object()
+                .filter(|wrapper| wrapper.len() == 1)
+                .and_then(|wrapper| wrapper.iter().next())
+                .and_then(|(name, settings)| {
+                    settings.as_object()?.iter().find_map(|(field, value)| {
+                        let probe = json!({ name.as_str(): { field.as_str(): value } });
+                        serde_json::from_value::<AudioEffect>(probe)
+                            .err()
+                            .map(|err| (format!("{}.{}.{}", path, name, field), err.to_string()))
+                    })
+                });
+            Some(field_error.unwrap_or((path, err.to_string())))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strict_error(document: Value) -> PlaySettingsError {
+        check_strict(&document).unwrap_err()
+    }
+
+    #[test]
+    fn strict_mode_names_the_offending_field() {
+        let err = strict_error(json!({
+            "encoder_version": 3,
+            "play_settings": {"tracks": [
+                {"level": 1.0, "pan": 0.0, "ids": [1], "name": "a", "safe_name": "a"},
+                {"level": 1.0, "pan": "left", "ids": [2], "name": "b", "safe_name": "b"}
+            ]}
+        }));
+        assert_eq!(err.path, "play_settings.tracks[1].pan");
+        assert!(err.message.contains("expected f32"), "{}", err.message);
+        assert_eq!(err.version.as_deref(), Some("3"));
+
+        let err = strict_error(json!({"encoder_version": "2", "play_settings": {"tracks": [
+            {"level": 1.0, "pan": 0.0, "ids": [1], "name": "a"}
+        ]}}));
+        assert_eq!(err.path, "play_settings.tracks[0]");
+        assert!(err.message.contains("safe_name"));
+
+        let err = strict_error(json!({"encoder_version": 2, "effects": [
+            {"GainSettings": {"gain": "loud"}}, {"WobbleSettings": {}}
+        ]}));
+        assert_eq!(err.path, "effects[0].GainSettings.gain");
+
+        let err = strict_error(json!({"encoder_version": 2, "effects": [{"WobbleSettings": {}}]}));
+        assert_eq!(err.path, "effects[0]");
+        assert!(err.message.contains("unknown variant"));
+    }
+
+    #[test]
+    fn strict_mode_rejects_unknown_versions_and_accepts_valid_settings() {
+        let err = strict_error(json!({"encoder_version": 9, "play_settings": {}}));
+        assert_eq!(err.path, "encoder_version");
+        assert_eq!(
+            err.to_string(),
+            "encoder_version: unsupported encoder version (encoder_version 9)"
+        );
+
+        let err = strict_error(json!({"tracks": [{"length": "two"}]}));
+        assert_eq!(err.path, "tracks[0].length");
+        assert_eq!(err.version, None);
+
+        let valid = json!({"encoder_version": 4, "play_settings": {
+            "tracks": [{"level": 0.5, "pan": 0.0, "ids": [1, 2], "name": "a", "safe_name": "a"}],
+            "effects": [{"GainSettings": {"enabled": true, "gain": 1.0}}]
+        }});
+        assert!(matches!(decode_strict(&valid), Ok(PlaySettingsFile::V4(_))));
+    }
+}
@@ -0,0 +1,89 @@
+//! Per-candidate source trims for stems exported with extra silence.
+
+use serde::{Deserialize, Serialize};
+
+/// Source region of one candidate id that holds its audio.
+///
+/// The engine skips the source before `start_ms`, so the first audible
+/// sample lands at the track's start offset, and stops decoding at `end_ms`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct CandidateTrim {
+    /// Candidate id the trim applies to.
+    pub id: u32,
+    /// Source position, in milliseconds, where playback starts.
+    #[serde(default)]
+    pub start_ms: u64,
+    /// Source position, in milliseconds, where playback ends; the end of the
+    /// source when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<u64>,
+}
+
+/// Return the trim entry for `id`, if one is declared.
+pub fn candidate_trim(trims: &[CandidateTrim], id: u32) -> Option<&CandidateTrim> {
+    trims.iter().find(|trim| trim.id == id)
+}
+
+/// Track objects of a parsed `play_settings.json` document, whether the
+/// settings are nested under a `play_settings` key or flat.
+pub(crate) fn settings_tracks(
+    play_settings: &serde_json::Value,
+) -> Option<&Vec<serde_json::Value>> {
+    let target = match play_settings.get("play_settings") {
+        Some(nested @ serde_json::Value::Object(_)) => nested,
+        _ => play_settings,
+    };
+    target.get("tracks")?.as_array()
+}
+
+/// Mutable form of [`settings_tracks`].
+pub(crate) fn settings_tracks_mut(
+    play_settings: &mut serde_json::Value,
+) -> Option<&mut Vec<serde_json::Value>> {
+    let nested = play_settings
+        .get("play_settings")
+        .is_some_and(serde_json::Value::is_object);
+    let target = if nested {
+        play_settings.get_mut("play_settings")?
+    } else {
+        play_settings
+    };
+    target.get_mut("tracks")?.as_array_mut()
+}
+
+/// Store `entries` under `key` on the tracks of a parsed
+/// `play_settings.json` document, giving each track the entries whose id it
+/// lists and removing `key` from tracks with none.
+///
+/// Returns `false` when the document has no `tracks` array.
+pub(crate) fn write_candidate_entries<T: Serialize>(
+    play_settings: &mut serde_json::Value,
+    key: &str,
+    entries: &[T],
+    id: impl Fn(&T) -> u32,
+) -> bool {
+    let Some(tracks) = settings_tracks_mut(play_settings) else {
+        return false;
+    };
+    for track in tracks
+        .iter_mut()
+        .filter_map(serde_json::Value::as_object_mut)
+    {
+        let ids: Vec<u64> = track
+            .get("ids")
+            .and_then(serde_json::Value::as_array)
+            .map(|ids| ids.iter().filter_map(serde_json::Value::as_u64).collect())
+            .unwrap_or_default();
+        let listed: Vec<&T> = entries
+            .iter()
+            .filter(|entry| ids.contains(&u64::from(id(entry))))
+            .collect();
+        if listed.is_empty() {
+            track.remove(key);
+        } else if let Ok(value) = serde_json::to_value(listed) {
+            track.insert(key.to_string(), value);
+        }
+    }
+    true
+}
@@ -0,0 +1,144 @@
+//! Effect presets bundled in a container as `presets/*.json` attachments.
+//!
+//! Each preset attachment holds a JSON array of effects in the same shape as
+//! the `effects` list of `play_settings.json`. The preset name is the
+//! attachment name without the `presets/` prefix and `.json` extension, so
+//! `presets/Cathedral.json` is the preset `Cathedral`.
+
+use std::path::Path;
+
+use log::warn;
+use serde::Serialize;
+
+use crate::container::attachments::{
+    read_attachments_from_path_with_limit, AttachmentError, ContainerAttachment,
+};
+use crate::container::limits::ParseLimits;
+use crate::container::play_settings::EffectSettings;
+use crate::dsp::effects::{normalize_legacy_effect_aliases, AudioEffect};
+
+/// Attachment name prefix that marks an effect preset.
+pub const PRESET_ATTACHMENT_PREFIX: &str = "presets/";
+
+/// A named effect chain embedded in a container.
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectPreset {
+    /// Preset name, taken from the attachment name.
+    pub name: String,
+    /// Effect chain the preset applies, in order.
+    pub effects: Vec<AudioEffect>,
+}
+
+/// Return the preset name an attachment carries, if it is a preset.
+pub fn preset_name(attachment_name: &str) -> Option<&str> {
+    let name = attachment_name
+        .trim_matches('"')
+        .strip_prefix(PRESET_ATTACHMENT_PREFIX)?
+        .strip_suffix(".json")?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Decode the effect presets among `attachments`, in attachment order.
+///
+/// Attachments that are not valid JSON arrays are skipped with a warning, as
+/// are individual entries that do not decode into a known effect. When two
+/// attachments share a name, the first wins.
+pub fn effect_presets(attachments: &[ContainerAttachment]) -> Vec<EffectPreset> {
+    let mut presets: Vec<EffectPreset> = Vec::new();
+    for attachment in attachments {
+        let Some(name) = preset_name(&attachment.name) else {
+            continue;
+        };
+        if presets.iter().any(|preset| preset.name == name) {
+            warn!("duplicate effect preset {:?}; keeping the first", name);
+            continue;
+        }
+        let entries: Vec<EffectSettings> = match serde_json::from_slice(&attachment.data) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("failed to parse effect preset {:?}: {}", name, err);
+                continue;
+            }
+        };
+        let mut effects = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match entry.decode_audio_effect() {
+                Ok(effect) => effects.push(effect),
+                Err(err) => warn!("failed to parse effect in preset {:?}: {}", name, err),
+            }
+        }
+        presets.push(EffectPreset {
+            name: name.to_string(),
+            effects: normalize_legacy_effect_aliases(effects),
+        });
+    }
+    presets
+}
+
+/// Read the effect presets bundled in the container at `path`.
+///
+/// # Errors
+///
+/// Returns an [`AttachmentError`] when the container cannot be read.
+pub fn read_effect_presets(path: impl AsRef<Path>) -> Result<Vec<EffectPreset>, AttachmentError> {
+    read_effect_presets_with_limits(path, &ParseLimits::unlimited())
+}
+
+/// [`read_effect_presets`] refusing attachments larger than
+/// `limits.max_attachment_bytes`.
+///
+/// # Errors
+///
+/// Returns [`AttachmentError::TooLarge`] for an oversized attachment, plus
+/// the errors of [`read_effect_presets`].
+pub fn read_effect_presets_with_limits(
+    path: impl AsRef<Path>,
+    limits: &ParseLimits,
+) -> Result<Vec<EffectPreset>, AttachmentError> {
+    Ok(effect_presets(&read_attachments_from_path_with_limit(
+        path,
+        limits.max_attachment_bytes,
+    )?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(name: &str, data: &str) -> ContainerAttachment {
+        ContainerAttachment {
+            name: name.to_string(),
+            mime_type: "application/json".to_string(),
+            data: data.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn preset_names_come_from_the_attachment_path() {
+        assert_eq!(preset_name("presets/Cathedral.json"), Some("Cathedral"));
+        assert_eq!(preset_name("\"presets/Dry Room.json\""), Some("Dry Room"));
+        assert_eq!(preset_name("presets/.json"), None);
+        assert_eq!(preset_name("play_settings.json"), None);
+        assert_eq!(preset_name("presets/notes.txt"), None);
+    }
+
+    #[test]
+    fn presets_decode_known_effects_and_skip_bad_attachments() {
+        let attachments = [
+            attachment("play_settings.json", "{}"),
+            attachment(
+                "presets/Warm.json",
+                r#"[{"GainSettings": {"enabled": true, "gain": 0.5}}, {"Unknown": {}}]"#,
+            ),
+            attachment("presets/Broken.json", "{not json"),
+            attachment("presets/Warm.json", "[]"),
+            attachment("presets/Empty.json", "[]"),
+        ];
+        let presets = effect_presets(&attachments);
+        let names: Vec<&str> = presets.iter().map(|preset| preset.name.as_str()).collect();
+        assert_eq!(names, ["Warm", "Empty"]);
+        assert_eq!(presets[0].effects.len(), 1);
+        assert!(matches!(presets[0].effects[0], AudioEffect::Gain(_)));
+        assert!(presets[1].effects.is_empty());
+    }
+}
@@ -1,11 +1,24 @@
 //! Accessors and view helpers for [`Prot`].
 
+use std::sync::Arc;
+
 use log::warn;
 
+use crate::container::attachments::{read_attachments_with_download, AttachmentError};
+use crate::container::limits::ParseLimits;
+use crate::container::loudness::LoudnessTag;
+use crate::container::presets::{effect_presets, EffectPreset};
 use crate::dsp::effects::convolution_reverb::ImpulseResponseSpec;
 use crate::dsp::effects::AudioEffect;
+use crate::dsp::pan::PanLaw;
+use crate::tools::progressive::ProgressiveFile;
+use crate::tools::stdin::is_stdin_path;
 
-use super::{Prot, ProtSource};
+use super::schedule::parse_timestamp_ms;
+use super::types::{CandidateInfo, EffectAutomationPoint, LogicalTrackInfo, TimelineSection};
+use super::TrackKeys;
+use super::{versioned_tracks, Prot, ProtSource};
+use crate::container::play_settings::{candidate_label, PlaySettingsFile};
 
 impl Prot {
     /// Return effects parsed from play_settings, if any.
@@ -13,6 +26,51 @@ impl Prot {
         self.effects.clone()
     }
 
+    /// Limits the container was loaded with; unlimited unless it came from
+    /// [`Prot::try_new_with_limits`].
+    pub fn parse_limits(&self) -> ParseLimits {
+        self.limits
+    }
+
+    /// Content keys for the container's encrypted tracks; empty until a
+    /// player resolves them.
+    pub(crate) fn track_keys(&self) -> Arc<TrackKeys> {
+        self.track_keys.clone()
+    }
+
+    /// Download the container still arrives through; `None` unless it came
+    /// from [`Prot::try_new_progressive`].
+    pub(crate) fn download(&self) -> Option<ProgressiveFile> {
+        self.download.clone()
+    }
+
+    /// Replace the content keys readers of this container decrypt with.
+    #[cfg(feature = "encryption")]
+    pub(crate) fn set_track_keys(&mut self, keys: TrackKeys) {
+        self.track_keys = Arc::new(keys);
+    }
+
+    /// Read the effect presets bundled as `presets/*.json` attachments.
+    ///
+    /// File-path sources have no attachments and return no presets.
+    /// Attachments are bounded by [`Self::parse_limits`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AttachmentError`] when the container cannot be read.
+    pub fn get_effect_presets(&self) -> Result<Vec<EffectPreset>, AttachmentError> {
+        match &self.source {
+            ProtSource::Container { file_path } => {
+                Ok(effect_presets(&read_attachments_with_download(
+                    file_path,
+                    self.download.as_ref(),
+                    self.limits.max_attachment_bytes,
+                )?))
+            }
+            ProtSource::Paths { .. } => Ok(Vec::new()),
+        }
+    }
+
     /// Get the convolution impulse response spec, if configured.
     pub fn get_impulse_response_spec(&self) -> Option<ImpulseResponseSpec> {
         self.impulse_response_spec.clone()
@@ -23,6 +81,219 @@ impl Prot {
         self.impulse_response_tail_db
     }
 
+    /// Get the song tempo declared in play_settings, if any.
+    pub fn get_tempo_bpm(&self) -> Option<f32> {
+        self.play_settings
+            .as_ref()?
+            .versioned_payload()?
+            .tempo_bpm
+            .filter(|bpm| bpm.is_finite() && *bpm > 0.0)
+    }
+
+    /// Get the pan law declared in play_settings, or the default balance law.
+    pub fn get_pan_law(&self) -> PanLaw {
+        self.play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+            .and_then(|payload| payload.pan_law)
+            .unwrap_or_default()
+    }
+
+    /// Get the loudness tag used for the input trim, if any.
+    ///
+    /// A `loudness` object in play_settings takes precedence over ReplayGain
+    /// tags in the container metadata.
+    pub fn get_loudness_tag(&self) -> Option<LoudnessTag> {
+        self.play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+            .and_then(|payload| payload.loudness)
+            .or(self.replaygain)
+    }
+
+    /// Return name, mix state, and current selection for each logical track.
+    ///
+    /// Tracks appear in the same order as the groups of
+    /// [`Prot::get_shuffle_schedule`]; `selection` comes from the schedule
+    /// entry active at `at_seconds`.
+    pub fn get_track_info(&self, at_seconds: f64) -> Vec<LogicalTrackInfo> {
+        let schedule = self.get_shuffle_schedule();
+        let groups = schedule
+            .iter()
+            .take_while(|(time, _)| *time <= at_seconds)
+            .last()
+            .or(schedule.first())
+            .map(|(_, groups)| groups.as_slice())
+            .unwrap_or(&[]);
+        let mut tracks = self.logical_track_info();
+        for (track, group) in tracks.iter_mut().zip(groups) {
+            track.selection = group.clone();
+        }
+        tracks
+    }
+
+    // Track metadata without selections, filtered like
+    // `logical_track_slot_spans` so indices line up with schedule groups.
+    fn logical_track_info(&self) -> Vec<LogicalTrackInfo> {
+        if let ProtSource::Paths { file_paths, .. } = &self.source {
+            return file_paths
+                .iter()
+                .filter(|track| !track.file_paths.is_empty() && track.selections_count > 0)
+                .enumerate()
+                .map(|(index, track)| LogicalTrackInfo {
+                    level: track.level,
+                    pan: track.pan,
+                    selections_count: track.selections_count,
+                    ..unnamed_track(index, track.file_paths.iter().map(path_candidate))
+                })
+                .collect();
+        }
+        let Some(play_settings) = self.play_settings.as_ref() else {
+            return Vec::new();
+        };
+        if let Some(tracks) = versioned_tracks(play_settings) {
+            return tracks
+                .iter()
+                .filter(|track| !track.ids.is_empty() && track.selections_count > 0)
+                .map(|track| LogicalTrackInfo {
+                    name: track.name.clone(),
+                    safe_name: track.safe_name.clone(),
+                    candidates: track.ids.len(),
+                    level: track.level,
+                    pan: track.pan,
+                    selections_count: track.selections_count,
+                    selection: Vec::new(),
+                    candidate_info: track
+                        .ids
+                        .iter()
+                        .map(|id| {
+                            let label = candidate_label(&track.candidate_labels, *id);
+                            CandidateInfo {
+                                source: id.to_string(),
+                                label: label.and_then(|label| label.label.clone()),
+                                artwork: label.and_then(|label| label.artwork.clone()),
+                            }
+                        })
+                        .collect(),
+                })
+                .collect();
+        }
+        let PlaySettingsFile::Legacy(file) = play_settings else {
+            return Vec::new();
+        };
+        // Legacy ids run from `starting_index + 1` for `length` entries.
+        file.settings
+            .inner()
+            .tracks
+            .iter()
+            .filter_map(|track| Some((track.starting_index?, track.length?)))
+            .enumerate()
+            .map(|(index, (start, length))| {
+                unnamed_track(
+                    index,
+                    (start + 1..=start + length).map(|id| CandidateInfo {
+                        source: id.to_string(),
+                        label: None,
+                        artwork: None,
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    /// Return the named timeline sections declared in play_settings.
+    ///
+    /// Sections with unparseable timestamps or a non-positive length are
+    /// skipped with a warning.
+    pub fn get_sections(&self) -> Vec<TimelineSection> {
+        let Some(payload) = self
+            .play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+        else {
+            return Vec::new();
+        };
+        payload
+            .sections
+            .iter()
+            .filter_map(|section| {
+                let start = parse_timestamp_ms(&section.start);
+                let end = parse_timestamp_ms(&section.end);
+                match (start, end) {
+                    (Some(start), Some(end)) if end > start => Some(TimelineSection {
+                        name: section.name.clone(),
+                        start_seconds: start as f64 / 1000.0,
+                        end_seconds: end as f64 / 1000.0,
+                    }),
+                    _ => {
+                        warn!("ignoring invalid section: {}", section.name);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// Return the effect automation declared in play_settings, in timeline
+    /// order.
+    ///
+    /// Changes with unparseable timestamps are skipped with a warning.
+    pub fn get_effect_automation(&self) -> Vec<EffectAutomationPoint> {
+        let Some(payload) = self
+            .play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+        else {
+            return Vec::new();
+        };
+        let mut points: Vec<EffectAutomationPoint> = payload
+            .effect_automation
+            .iter()
+            .filter_map(|event| match parse_timestamp_ms(&event.at) {
+                Some(at_ms) => Some(EffectAutomationPoint {
+                    at_seconds: at_ms as f64 / 1000.0,
+                    effect_index: event.effect,
+                    parameter: event.parameter.clone(),
+                    value: event.value,
+                }),
+                None => {
+                    warn!("ignoring effect automation at invalid time: {}", event.at);
+                    None
+                }
+            })
+            .collect();
+        points.sort_by(|a, b| a.at_seconds.total_cmp(&b.at_seconds));
+        points
+    }
+
+    /// Return per-slot binaural positions for tracks that declare one.
+    ///
+    /// Slots are expanded by `selections_count` in the same order as
+    /// [`Prot::get_track_mix_settings`].
+    #[cfg(feature = "hrtf")]
+    pub fn get_track_binaural_settings(
+        &self,
+    ) -> std::collections::HashMap<u16, crate::dsp::effects::BinauralPannerSettings> {
+        let mut settings = std::collections::HashMap::new();
+        let Some(tracks) = self
+            .play_settings
+            .as_ref()
+            .and_then(super::versioned_tracks)
+        else {
+            return settings;
+        };
+        let mut slot_index: u16 = 0;
+        for track in tracks {
+            for _ in 0..track.selections_count.max(1) {
+                if let Some(binaural) = &track.binaural {
+                    settings.insert(slot_index, binaural.clone());
+                }
+                slot_index = slot_index.saturating_add(1);
+            }
+        }
+        settings
+    }
+
     /// Return the container path if this is a `.prot`/`.mka` file.
     pub fn get_container_path(&self) -> Option<String> {
         match &self.source {
@@ -31,6 +302,12 @@ impl Prot {
         }
     }
 
+    /// Return `false` when the source is read from standard input, which
+    /// plays once from the start and cannot seek.
+    pub fn is_seekable(&self) -> bool {
+        !matches!(&self.source, ProtSource::Container { file_path } if is_stdin_path(file_path))
+    }
+
     /// Override the impulse response spec at runtime.
     pub fn set_impulse_response_spec(&mut self, spec: ImpulseResponseSpec) {
         self.impulse_response_spec = Some(spec);
@@ -150,3 +427,31 @@ impl Prot {
         }
     }
 }
+
+// Unity-gain, centered track named after its position.
+fn unnamed_track(
+    index: usize,
+    candidate_info: impl Iterator<Item = CandidateInfo>,
+) -> LogicalTrackInfo {
+    let candidate_info: Vec<CandidateInfo> = candidate_info.collect();
+    LogicalTrackInfo {
+        name: format!("Track {}", index + 1),
+        safe_name: format!("track_{}", index + 1),
+        candidates: candidate_info.len(),
+        level: 1.0,
+        pan: 0.0,
+        selections_count: 1,
+        selection: Vec::new(),
+        candidate_info,
+    }
+}
+
+fn path_candidate(path: &String) -> CandidateInfo {
+    CandidateInfo {
+        source: path.clone(),
+        label: std::path::Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned()),
+        artwork: None,
+    }
+}
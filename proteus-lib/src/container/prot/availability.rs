@@ -0,0 +1,175 @@
+//! Detection of missing and zero-length sources.
+//!
+//! A selected track id that the container does not have, or a file that
+//! decodes to nothing, never delivers audio, and the mixer would wait for it
+//! indefinitely. Sources are checked against the scanned durations: at
+//! selection time (to redraw another candidate) and when the runtime plan is
+//! built (so unplayable instances are left out instead of stalling the mix).
+
+use log::warn;
+
+use crate::container::info::Info;
+
+use super::types::{RuntimeInstanceMeta, ShuffleSource};
+use super::{Prot, ProtSource};
+
+/// What to do when a selected track is missing or has zero length.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MissingTrackPolicy {
+    /// Draw another playable candidate for the slot; the slot is skipped
+    /// when none of its candidates can play.
+    #[default]
+    Retry,
+    /// Keep the selection and leave the slot silent while it is selected.
+    Skip,
+    /// Like [`Self::Skip`], and also raise a diagnostics event when playback
+    /// starts with an unplayable selection.
+    Error,
+}
+
+/// Return `true` when the source at `index` has a known, non-zero duration.
+///
+/// When no source has a positive duration the scan itself is unreliable, so
+/// every source is treated as playable.
+pub(super) fn source_playable(info: &Info, index: u32) -> bool {
+    let scanned = info.duration_map.values().any(|duration| *duration > 0.0);
+    !scanned
+        || info
+            .get_duration(index)
+            .is_some_and(|duration| duration > 0.0)
+}
+
+/// Playability of `candidates`, or an empty list when all of them can play.
+pub(super) fn playable_mask(
+    info: &Info,
+    candidates: impl Iterator<Item = Option<u32>>,
+) -> Vec<bool> {
+    let mask: Vec<bool> = candidates
+        .map(|index| index.is_some_and(|index| source_playable(info, index)))
+        .collect();
+    if mask.iter().all(|playable| *playable) {
+        Vec::new()
+    } else {
+        mask
+    }
+}
+
+impl Prot {
+    /// Set how missing or zero-length selections are handled.
+    ///
+    /// Takes effect from the next [`Prot::refresh_tracks`] or playback start.
+    pub fn set_missing_track_policy(&mut self, policy: MissingTrackPolicy) {
+        self.missing_track_policy = policy;
+    }
+
+    /// Policy applied to missing or zero-length selections.
+    pub fn missing_track_policy(&self) -> MissingTrackPolicy {
+        self.missing_track_policy
+    }
+
+    /// Scheduled track ids or paths that cannot play, without duplicates.
+    pub fn unplayable_selections(&self) -> Vec<String> {
+        let mut missing: Vec<String> = Vec::new();
+        for source in self
+            .shuffle_schedule
+            .iter()
+            .flat_map(|entry| entry.sources.iter())
+        {
+            if self.source_is_playable(source) {
+                continue;
+            }
+            let name = match source {
+                ShuffleSource::TrackId(id) => id.to_string(),
+                ShuffleSource::FilePath(path) => path.clone(),
+            };
+            if !missing.contains(&name) {
+                missing.push(name);
+            }
+        }
+        missing
+    }
+
+    pub(super) fn source_is_playable(&self, source: &ShuffleSource) -> bool {
+        match (source, &self.source) {
+            (ShuffleSource::TrackId(id), _) => source_playable(&self.info, *id),
+            (
+                ShuffleSource::FilePath(path),
+                ProtSource::Paths {
+                    file_paths_dictionary,
+                    ..
+                },
+            ) => file_paths_dictionary
+                .iter()
+                .position(|candidate| candidate == path)
+                .is_some_and(|index| source_playable(&self.info, index as u32)),
+            (ShuffleSource::FilePath(_), ProtSource::Container { .. }) => false,
+        }
+    }
+
+    /// Drop plan instances whose source cannot play, logging each source once.
+    pub(super) fn retain_playable_instances(&self, instances: &mut Vec<RuntimeInstanceMeta>) {
+        let before = instances.len();
+        let mut reported: Vec<ShuffleSource> = Vec::new();
+        instances.retain(|instance| {
+            if self.source_is_playable(&instance.source_key) {
+                return true;
+            }
+            if !reported.contains(&instance.source_key) {
+                warn!(
+                    "skipping missing or zero-length source {:?} in slot {}",
+                    instance.source_key, instance.slot_index
+                );
+                reported.push(instance.source_key.clone());
+            }
+            false
+        });
+        if instances.len() == before {
+            return;
+        }
+        for (instance_id, instance) in instances.iter_mut().enumerate() {
+            instance.instance_id = instance_id;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn info(durations: &[(u32, f64)]) -> Info {
+        Info {
+            file_paths: Vec::new(),
+            duration_map: durations.iter().copied().collect::<HashMap<_, _>>(),
+            channels: 2,
+            sample_rate: 48_000,
+            bits_per_sample: 16,
+        }
+    }
+
+    #[test]
+    fn absent_and_zero_length_sources_are_unplayable() {
+        let info = info(&[(1, 10.0), (2, 0.0)]);
+        assert!(source_playable(&info, 1));
+        assert!(!source_playable(&info, 2));
+        assert!(!source_playable(&info, 3));
+    }
+
+    #[test]
+    fn failed_scan_treats_every_source_as_playable() {
+        let info = info(&[(1, 0.0)]);
+        assert!(source_playable(&info, 1));
+        assert!(source_playable(&info, 7));
+    }
+
+    #[test]
+    fn playable_mask_is_empty_when_every_candidate_plays() {
+        let info = info(&[(1, 10.0), (2, 5.0)]);
+        assert!(playable_mask(&info, [Some(1), Some(2)].into_iter()).is_empty());
+        assert_eq!(
+            playable_mask(&info, [Some(1), Some(9), None].into_iter()),
+            vec![true, false, false]
+        );
+    }
+}
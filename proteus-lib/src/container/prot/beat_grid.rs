@@ -0,0 +1,144 @@
+//! Quantizing shuffle points to a beat grid.
+//!
+//! With a grid set, each shuffle point moves to the nearest beat, so source
+//! switches land on the beat of an externally shared tempo. The stored
+//! schedule is left untouched; the grid applies when the schedule is read.
+
+use super::types::ShuffleScheduleEntry;
+use super::Prot;
+
+/// Regular beat grid laid over the timeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatGrid {
+    /// Timeline position of a bar line, in seconds.
+    pub origin_seconds: f64,
+    /// Beat length in seconds.
+    pub beat_seconds: f64,
+    /// Beats per bar.
+    pub beats_per_bar: f64,
+}
+
+impl BeatGrid {
+    /// Grid at `tempo_bpm` with a bar line at `origin_seconds`.
+    pub fn new(origin_seconds: f64, tempo_bpm: f64, beats_per_bar: f64) -> Self {
+        Self {
+            origin_seconds,
+            beat_seconds: 60.0 / tempo_bpm.max(f64::EPSILON),
+            beats_per_bar: beats_per_bar.max(1.0),
+        }
+    }
+
+    /// Beat closest to `seconds`.
+    pub fn nearest_beat(&self, seconds: f64) -> f64 {
+        let beats = ((seconds - self.origin_seconds) / self.beat_seconds).round();
+        self.origin_seconds + beats * self.beat_seconds
+    }
+
+    /// First beat at or after `seconds`.
+    pub fn next_beat(&self, seconds: f64) -> f64 {
+        // Tolerate rounding so a position on a beat stays there.
+        let beats = ((seconds - self.origin_seconds) / self.beat_seconds - 1e-9).ceil();
+        self.origin_seconds + beats * self.beat_seconds
+    }
+
+    /// First bar line at or after `seconds`.
+    pub fn next_bar(&self, seconds: f64) -> f64 {
+        let bar_seconds = self.beat_seconds * self.beats_per_bar;
+        // Tolerate rounding so a position on a bar line stays there.
+        let bars = ((seconds - self.origin_seconds) / bar_seconds - 1e-9).ceil();
+        self.origin_seconds + bars * bar_seconds
+    }
+}
+
+impl Prot {
+    /// Quantize shuffle points to `grid`, or restore them with `None`.
+    ///
+    /// Applies to engines started afterwards, so call it before playing or
+    /// seeking. The first schedule entry stays at the start.
+    pub fn set_beat_grid(&mut self, grid: Option<BeatGrid>) {
+        self.beat_grid = grid;
+    }
+
+    /// Grid shuffle points are quantized to, if any.
+    pub fn beat_grid(&self) -> Option<BeatGrid> {
+        self.beat_grid
+    }
+
+    /// Shuffle schedule with the beat grid applied.
+    pub(super) fn quantized_schedule(&self) -> Vec<ShuffleScheduleEntry> {
+        match self.beat_grid {
+            Some(grid) => quantize_schedule(&self.shuffle_schedule, &grid),
+            None => self.shuffle_schedule.clone(),
+        }
+    }
+}
+
+/// Move every entry after the first to its nearest beat.
+///
+/// Entries that land on or before the previous one replace it, so the later
+/// selection wins and times stay strictly increasing.
+fn quantize_schedule(
+    schedule: &[ShuffleScheduleEntry],
+    grid: &BeatGrid,
+) -> Vec<ShuffleScheduleEntry> {
+    let mut quantized: Vec<ShuffleScheduleEntry> = Vec::with_capacity(schedule.len());
+    for (index, entry) in schedule.iter().enumerate() {
+        let mut entry = entry.clone();
+        if index > 0 {
+            let snapped = grid.nearest_beat(entry.at_ms as f64 / 1000.0).max(0.0);
+            entry.at_ms = (snapped * 1000.0).round() as u64;
+        }
+        match quantized.last_mut() {
+            Some(previous) if index > 1 && entry.at_ms <= previous.at_ms => {
+                previous.sources = entry.sources;
+            }
+            Some(previous) if entry.at_ms <= previous.at_ms => {
+                // Never move a switch onto the opening entry.
+                entry.at_ms = previous.at_ms + 1;
+                quantized.push(entry);
+            }
+            _ => quantized.push(entry),
+        }
+    }
+    quantized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::types::ShuffleSource;
+    use super::*;
+
+    fn entry(at_ms: u64, id: u32) -> ShuffleScheduleEntry {
+        ShuffleScheduleEntry {
+            at_ms,
+            sources: vec![ShuffleSource::TrackId(id)],
+        }
+    }
+
+    #[test]
+    fn grid_finds_nearest_beat_and_next_bar() {
+        // 120 BPM in 4/4 with a bar line at 0.25 s.
+        let grid = BeatGrid::new(0.25, 120.0, 4.0);
+        assert!((grid.nearest_beat(1.1) - 1.25).abs() < 1e-9);
+        assert!((grid.next_bar(0.3) - 2.25).abs() < 1e-9);
+        assert!((grid.next_bar(2.25) - 2.25).abs() < 1e-9);
+        assert!((grid.next_beat(0.3) - 0.75).abs() < 1e-9);
+        assert!((grid.next_beat(0.75) - 0.75).abs() < 1e-9);
+    }
+
+    #[test]
+    fn shuffle_points_snap_to_beats_and_collisions_keep_the_later_selection() {
+        let grid = BeatGrid::new(0.0, 120.0, 4.0);
+        let schedule = vec![
+            entry(0, 1),
+            entry(1_180, 2),
+            entry(1_240, 3),
+            entry(3_100, 4),
+        ];
+        let quantized = quantize_schedule(&schedule, &grid);
+        assert_eq!(
+            quantized,
+            vec![entry(0, 1), entry(1_000, 3), entry(3_000, 4)]
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Enumeration of the distinct selections a container can play.
+//!
+//! Every slot draws one candidate at the start and again at each of its
+//! shuffle points, so a combination is one choice per draw. Combinations are
+//! numbered in mixed radix over those draws, which lets
+//! [`Combinations`] walk the whole space in order or jump straight to a
+//! sampled index without materializing anything else. Selection weights,
+//! rules, and the missing-track policy shape random draws only; they do not
+//! remove combinations from the space.
+
+use std::collections::{BTreeSet, HashSet};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::container::play_settings::PlaySettingsFile;
+
+use super::schedule::parse_shuffle_points;
+use super::{Prot, ProtError, ProtSource};
+
+/// One fixed selection of a container.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combination {
+    /// Position of this combination in [`Prot::combinations`] order.
+    pub index: u128,
+    /// `(time_seconds, sources)` entries with one track ID or file path per
+    /// slot, in slot order, at the start and at every shuffle point.
+    pub schedule: Vec<(f64, Vec<String>)>,
+}
+
+/// One draw of a slot: the slot picks a new candidate at `at_ms`.
+#[derive(Debug, Clone)]
+struct Draw {
+    slot: usize,
+    at_ms: u64,
+}
+
+/// Iterator over every distinct selection of a container, in index order.
+///
+/// Created by [`Prot::combinations`]. Besides iterating, combinations can be
+/// looked up by index with [`Self::get`] or sampled with [`Self::sample`].
+#[derive(Debug, Clone)]
+pub struct Combinations {
+    /// Candidate sources of each slot.
+    slots: Vec<Vec<String>>,
+    /// Draws in radix order, least significant first.
+    draws: Vec<Draw>,
+    /// Every draw time, ascending and starting at zero.
+    timestamps: Vec<u64>,
+    len: u128,
+    next: u128,
+}
+
+impl Combinations {
+    fn new(slots: Vec<(Vec<String>, Vec<u64>)>) -> Option<Self> {
+        let mut timestamps = BTreeSet::from([0]);
+        let mut draws = Vec::new();
+        let mut len: u128 = if slots.is_empty() { 0 } else { 1 };
+        for (slot, (candidates, points)) in slots.iter().enumerate() {
+            for at_ms in std::iter::once(0).chain(points.iter().copied().filter(|at| *at > 0)) {
+                timestamps.insert(at_ms);
+                draws.push(Draw { slot, at_ms });
+                len = len.checked_mul(candidates.len() as u128)?;
+            }
+        }
+        Some(Self {
+            slots: slots
+                .into_iter()
+                .map(|(candidates, _)| candidates)
+                .collect(),
+            draws,
+            timestamps: timestamps.into_iter().collect(),
+            len,
+            next: 0,
+        })
+    }
+
+    /// Total number of combinations, including any already iterated.
+    pub fn len(&self) -> u128 {
+        self.len
+    }
+
+    /// Return `true` when the container has nothing to select.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the combination at `index`, or `None` past the end.
+    pub fn get(&self, index: u128) -> Option<Combination> {
+        if index >= self.len {
+            return None;
+        }
+        let mut remaining = index;
+        let mut choices: Vec<Vec<(u64, usize)>> = vec![Vec::new(); self.slots.len()];
+        for draw in &self.draws {
+            let radix = self.slots[draw.slot].len() as u128;
+            choices[draw.slot].push((draw.at_ms, (remaining % radix) as usize));
+            remaining /= radix;
+        }
+        let schedule = self
+            .timestamps
+            .iter()
+            .map(|&at_ms| {
+                let sources = self
+                    .slots
+                    .iter()
+                    .zip(&choices)
+                    .map(|(candidates, slot_choices)| {
+                        let choice = slot_choices
+                            .iter()
+                            .take_while(|(draw_ms, _)| *draw_ms <= at_ms)
+                            .last()
+                            .map_or(0, |(_, choice)| *choice);
+                        candidates[choice].clone()
+                    })
+                    .collect();
+                (at_ms as f64 / 1000.0, sources)
+            })
+            .collect();
+        Some(Combination { index, schedule })
+    }
+
+    /// Draw up to `count` distinct combinations at random, reproducibly for `seed`.
+    ///
+    /// No combination is returned twice; when `count` is at least
+    /// [`Self::len`], every combination is returned in shuffled order.
+    pub fn sample(&self, count: usize, seed: u64) -> impl Iterator<Item = Combination> + '_ {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let count = (count as u128).min(self.len);
+        // Floyd's algorithm picks a uniform subset without touching the rest
+        // of the space, which may be far too large to list.
+        let mut picked = HashSet::new();
+        let mut indices = Vec::with_capacity(count as usize);
+        for upper in (self.len - count)..self.len {
+            let candidate = rng.gen_range(0..=upper);
+            let index = if picked.insert(candidate) {
+                candidate
+            } else {
+                picked.insert(upper);
+                upper
+            };
+            indices.push(index);
+        }
+        indices.shuffle(&mut rng);
+        indices.into_iter().filter_map(|index| self.get(index))
+    }
+}
+
+impl Iterator for Combinations {
+    type Item = Combination;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let combination = self.get(self.next)?;
+        self.next += 1;
+        Some(combination)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.next;
+        let remaining = usize::try_from(remaining).ok();
+        (remaining.unwrap_or(usize::MAX), remaining)
+    }
+}
+
+impl Prot {
+    /// Enumerate the distinct selections this container can play.
+    ///
+    /// For well-formed play settings the count matches
+    /// [`Self::count_possible_combinations`]. A container without play
+    /// settings has a single combination. Returns `None` when the count does
+    /// not fit in a `u128`.
+    pub fn combinations(&self) -> Option<Combinations> {
+        Combinations::new(self.combination_slots())
+    }
+
+    /// Play `combination` instead of a random selection.
+    ///
+    /// The shuffle schedule is replaced until the next refresh; use a clone
+    /// per variant to batch-render several combinations.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtError::UnknownSource`] when the combination names a
+    /// source that is not part of this container.
+    pub fn apply_combination(&mut self, combination: &Combination) -> Result<(), ProtError> {
+        self.set_fixed_schedule(&combination.schedule)
+            .map_err(ProtError::UnknownSource)
+    }
+
+    /// Candidate sources and shuffle points of every slot, in slot order.
+    fn combination_slots(&self) -> Vec<(Vec<String>, Vec<u64>)> {
+        let mut slots = Vec::new();
+        if let ProtSource::Paths { file_paths, .. } = &self.source {
+            for track in file_paths
+                .iter()
+                .filter(|track| !track.file_paths.is_empty())
+            {
+                let points = parse_shuffle_points(&track.shuffle_points);
+                for _ in 0..track.selections_count {
+                    slots.push((track.file_paths.clone(), points.clone()));
+                }
+            }
+            return slots;
+        }
+
+        match &self.play_settings {
+            Some(PlaySettingsFile::Legacy(file)) => {
+                for track in &file.settings.inner().tracks {
+                    let (Some(starting_index), Some(length)) = (track.starting_index, track.length)
+                    else {
+                        continue;
+                    };
+                    let ids = (starting_index + 1..starting_index + 1 + length)
+                        .map(|id| id.to_string())
+                        .collect::<Vec<_>>();
+                    if !ids.is_empty() {
+                        slots.push((ids, Vec::new()));
+                    }
+                }
+            }
+            Some(PlaySettingsFile::Unknown { .. }) => {}
+            Some(play_settings) => {
+                let tracks = super::versioned_tracks(play_settings).unwrap_or(&[]);
+                for track in tracks.iter().filter(|track| !track.ids.is_empty()) {
+                    let ids: Vec<String> = track.ids.iter().map(u32::to_string).collect();
+                    let points = parse_shuffle_points(&track.shuffle_points);
+                    for _ in 0..track.selections_count {
+                        slots.push((ids.clone(), points.clone()));
+                    }
+                }
+            }
+            None => {
+                if let Some(track_id) = self.info.duration_map.keys().min() {
+                    slots.push((vec![track_id.to_string()], Vec::new()));
+                }
+            }
+        }
+        slots
+    }
+}
@@ -0,0 +1,105 @@
+//! VCA-style track group gains.
+//!
+//! Each logical track may name a group. A group's gain starts at the level
+//! declared in the play settings (unity when undeclared) and can be changed at
+//! runtime; the mixer multiplies it into every member track's level.
+
+use super::helpers::sanitize_level;
+use super::{Prot, ProtSource};
+
+impl Prot {
+    /// Names of all track groups, declared or referenced by a track.
+    pub fn group_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .declared_groups()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        for name in self.track_group_names().into_iter().flatten() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// Current gain of the group `name`, or `None` when no such group exists.
+    pub fn group_level(&self, name: &str) -> Option<f32> {
+        if let Some(level) = self.group_levels.get(name) {
+            return Some(*level);
+        }
+        if let Some((_, level)) = self
+            .declared_groups()
+            .into_iter()
+            .find(|(group, _)| group == name)
+        {
+            return Some(level);
+        }
+        self.track_group_names()
+            .iter()
+            .any(|group| group.as_deref() == Some(name))
+            .then_some(1.0)
+    }
+
+    /// Set the gain of the group `name`.
+    ///
+    /// Returns `false` when no track group has that name.
+    pub fn set_group_level(&mut self, name: &str, level: f32) -> bool {
+        if self.group_level(name).is_none() {
+            return false;
+        }
+        self.group_levels
+            .insert(name.to_string(), sanitize_level(level));
+        true
+    }
+
+    /// Group gain of each logical track; `1.0` for ungrouped tracks.
+    pub fn track_group_gains(&self) -> Vec<f32> {
+        self.track_group_names()
+            .iter()
+            .map(|group| {
+                group
+                    .as_deref()
+                    .and_then(|name| self.group_level(name))
+                    .unwrap_or(1.0)
+            })
+            .collect()
+    }
+
+    fn declared_groups(&self) -> Vec<(String, f32)> {
+        self.play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+            .map(|payload| {
+                payload
+                    .groups
+                    .iter()
+                    .map(|group| (group.name.clone(), sanitize_level(group.level)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Group of each logical track, parallel to the runtime plan's tracks.
+    fn track_group_names(&self) -> Vec<Option<String>> {
+        if let ProtSource::Paths { file_paths, .. } = &self.source {
+            return file_paths
+                .iter()
+                .filter(|track| !track.file_paths.is_empty() && track.selections_count > 0)
+                .map(|track| track.group.clone())
+                .collect();
+        }
+        match self
+            .play_settings
+            .as_ref()
+            .and_then(super::versioned_tracks)
+        {
+            Some(tracks) => tracks
+                .iter()
+                .filter(|track| !track.ids.is_empty() && track.selections_count > 0)
+                .map(|track| track.group.clone())
+                .collect(),
+            None => vec![None; self.logical_track_slot_spans().len()],
+        }
+    }
+}
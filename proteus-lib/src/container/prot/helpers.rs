@@ -124,6 +124,9 @@ pub(super) fn build_segment_instance(
         }],
         selection_index: placement.selection_index,
         occurrence_index,
+        start_offset_ms: 0,
+        looping: None,
+        trim: Default::default(),
     })
 }
 
@@ -214,13 +217,14 @@ pub(super) fn collect_legacy_tracks(
     longest_duration: &mut f64,
     info: &Info,
     total_duration: &mut f64,
+    rng: &mut dyn RngCore,
 ) {
     for track in &settings.tracks {
         let (Some(starting_index), Some(length)) = (track.starting_index, track.length) else {
             continue;
         };
         let starting_index = starting_index + 1;
-        let index = rand::thread_rng().gen_range(starting_index..(starting_index + length));
+        let index = rng.gen_range(starting_index..(starting_index + length));
         if let Some(track_duration) = info.get_duration(index) {
             if track_duration > *longest_duration {
                 *longest_duration = track_duration;
@@ -283,7 +287,7 @@ fn checked_pow(base: u128, exp: u32) -> Option<u128> {
     Some(result)
 }
 
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 #[cfg(test)]
 mod tests {
@@ -319,6 +323,11 @@ mod tests {
             pan: 0.0,
             selections_count: 1,
             shuffle_points: vec!["0:15".to_string(), "0:45".to_string()],
+            selection_mode: Default::default(),
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         }];
         assert_eq!(count_paths_track_combinations(&tracks), Some(8));
     }
@@ -365,6 +374,19 @@ mod tests {
             safe_name: "Track".to_string(),
             selections_count,
             shuffle_points: shuffle_points.into_iter().map(|v| v.to_string()).collect(),
+            weights: Vec::new(),
+            selection_mode: Default::default(),
+            transitions: Vec::new(),
+            shuffle_when: Vec::new(),
+            candidate_when: Vec::new(),
+            candidate_labels: Vec::new(),
+            candidate_trims: Vec::new(),
+            candidate_analysis: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
+            #[cfg(feature = "hrtf")]
+            binaural: None,
         }
     }
 }
@@ -1,30 +1,55 @@
-//! Container model and play settings parsing for `.prot`/`.mka`.
+//! Container model and play settings parsing for `.prot`/`.mka` and single
+//! audio files.
 
 mod accessors;
+mod availability;
+mod beat_grid;
+mod combinations;
+mod groups;
 mod helpers;
 mod plan;
 mod schedule;
+mod selection;
+mod timing;
 pub mod types;
+mod variables;
 
+use std::collections::HashMap;
 use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::Arc;
 
 use log::{debug, error, info, warn};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
 
+use crate::container::attachments::is_matroska_with_download;
 use crate::container::info::*;
-use crate::container::play_settings::{PlaySettingsFile, SettingsTrack};
+use crate::container::limits::{LimitExceeded, ParseLimits};
+use crate::container::loudness::{read_replaygain_tags_with_download, LoudnessTag};
+use crate::container::play_settings::{
+    ParseMode, PlaySettingsError, PlaySettingsFile, RuntimeVariables, SettingsTrack,
+};
 use crate::container::prot_settings::{
     derive_runtime_settings, try_load_play_settings_from_container, PlaySettingsLoadError,
 };
 use crate::dsp::effects::convolution_reverb::ImpulseResponseSpec;
 use crate::dsp::effects::AudioEffect;
+use crate::tools::progressive::ProgressiveFile;
 
-pub use types::PathsTrack;
+pub use availability::MissingTrackPolicy;
+pub use beat_grid::BeatGrid;
+pub use combinations::{Combination, Combinations};
 pub(crate) use types::{
-    ActiveWindow, RuntimeInstanceMeta, RuntimeInstancePlan, ShuffleScheduleEntry, ShuffleSource,
+    ActiveWindow, InstanceLoop, RuntimeInstanceMeta, RuntimeInstancePlan, ShuffleScheduleEntry,
+    ShuffleSource, SourceTrim,
+};
+pub use types::{
+    CandidateInfo, EffectAutomationPoint, LogicalTrackInfo, PathsTrack, TimelineSection,
 };
 
 use helpers::*;
 use schedule::*;
+use selection::SelectionContext;
 
 /// Parsed `.prot` container with resolved tracks and playback metadata.
 #[derive(Debug, Clone)]
@@ -40,8 +65,19 @@ pub struct Prot {
     pub(crate) impulse_response_spec: Option<ImpulseResponseSpec>,
     pub(crate) impulse_response_tail_db: Option<f32>,
     pub(crate) effects: Option<Vec<AudioEffect>>,
+    pub(crate) variables: RuntimeVariables,
+    pub(crate) replaygain: Option<LoudnessTag>,
+    pub(crate) missing_track_policy: MissingTrackPolicy,
+    pub(crate) group_levels: HashMap<String, f32>,
+    pub(crate) beat_grid: Option<BeatGrid>,
+    pub(crate) limits: ParseLimits,
+    pub(crate) track_keys: Arc<TrackKeys>,
+    pub(crate) download: Option<ProgressiveFile>,
 }
 
+/// Content keys of a container's encrypted tracks, by track id.
+pub(crate) type TrackKeys = HashMap<u32, [u8; 32]>;
+
 #[derive(Debug, Clone)]
 pub(crate) enum ProtSource {
     Container {
@@ -58,12 +94,21 @@ pub(crate) enum ProtSource {
 pub enum ProtError {
     /// The container or file-path set could not be initialized; message contains details.
     Initialization(String),
+    /// `play_settings.json` was rejected in [`ParseMode::Strict`].
+    InvalidPlaySettings(PlaySettingsError),
+    /// A fixed selection names a source that is not a track of this container.
+    UnknownSource(String),
+    /// The container exceeded one of the [`ParseLimits`] it was loaded with.
+    LimitExceeded(LimitExceeded),
 }
 
 impl std::fmt::Display for ProtError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Initialization(msg) => write!(f, "prot initialization failed: {}", msg),
+            Self::InvalidPlaySettings(err) => write!(f, "invalid play_settings.json: {}", err),
+            Self::UnknownSource(source) => write!(f, "source not in container: {}", source),
+            Self::LimitExceeded(err) => write!(f, "container exceeds parsing limits: {}", err),
         }
     }
 }
@@ -72,6 +117,10 @@ impl std::error::Error for ProtError {}
 
 impl Prot {
     /// Load a single container file and resolve tracks.
+    ///
+    /// The format is probed from the file's content, so any single audio
+    /// file Symphonia can decode (WAV, FLAC, MP3, Ogg, AIFF, plain Matroska)
+    /// loads as a one-track container with no play settings.
     pub fn new(file_path: &str) -> Self {
         Self::try_new(file_path).unwrap_or_else(|err| panic!("Prot::new failed: {}", err))
     }
@@ -82,18 +131,108 @@ impl Prot {
     ///
     /// Returns [`ProtError`] when parsing or initialization panics.
     pub fn try_new(file_path: &str) -> Result<Self, ProtError> {
-        catch_unwind(AssertUnwindSafe(|| Self::build_from_path(file_path))).map_err(|panic| {
-            let panic_msg = panic
-                .downcast_ref::<&str>()
-                .map(|msg| (*msg).to_string())
-                .or_else(|| panic.downcast_ref::<String>().cloned())
-                .unwrap_or_else(|| "unknown panic".to_string());
-            ProtError::Initialization(panic_msg)
+        Self::try_new_with_mode(file_path, ParseMode::Lenient)
+    }
+
+    /// Fallible constructor that decodes `play_settings.json` in `mode`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtError::InvalidPlaySettings`] when `mode` is
+    /// [`ParseMode::Strict`] and the settings cannot be read or decoded, and
+    /// [`ProtError::Initialization`] when initialization panics.
+    pub fn try_new_with_mode(file_path: &str, mode: ParseMode) -> Result<Self, ProtError> {
+        Self::try_new_with_limits(file_path, mode, &ParseLimits::unlimited())
+    }
+
+    /// Fallible constructor for containers from untrusted sources.
+    ///
+    /// Works like [`Self::try_new_with_mode`], but refuses containers that
+    /// exceed `limits`. Track counts are checked from the container header
+    /// before durations are probed, and attachment and `play_settings.json`
+    /// sizes before they are read or decoded, so a hostile file fails fast
+    /// instead of exhausting memory or time. The container keeps `limits`
+    /// (see [`Self::parse_limits`]) and applies them to the attachments it
+    /// reads later, such as presets, one-shots, and impulse responses.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtError::LimitExceeded`] when a limit is exceeded, in
+    /// either parse mode, plus the errors of [`Self::try_new_with_mode`].
+    pub fn try_new_with_limits(
+        file_path: &str,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<Self, ProtError> {
+        catch_initialization_panic(|| Self::build_from_path(file_path, None, Some(mode), limits))
+    }
+
+    /// Fallible constructor for a container that is still downloading.
+    ///
+    /// Works like [`Self::try_new_with_limits`] on the bytes of `file` that
+    /// have arrived, which must include the header, tracks, and leading
+    /// attachments; [`ProgressiveFile::wait_until_ready`] waits for them.
+    /// Durations come from the header alone. The container keeps `file`, so
+    /// players and renders built on it read through the download as later
+    /// bytes arrive.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of [`Self::try_new_with_limits`].
+    pub fn try_new_progressive(
+        file: &ProgressiveFile,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<Self, ProtError> {
+        catch_initialization_panic(|| {
+            Self::build_from_path(file.path(), Some(file), Some(mode), limits)
         })
     }
 
-    fn build_from_path(file_path: &str) -> Self {
-        let info = Info::new(file_path.to_string());
+    /// Load one audio file as a one-track container, skipping play settings.
+    ///
+    /// No `play_settings.json` is read, even from a `.prot` file, so there
+    /// are no shuffle points, container effects, or selection rules: the
+    /// file's first audio track plays as is. This is the express path behind
+    /// [`crate::playback::player::Player::new_single_file`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ProtError::Initialization`] when the file is not a supported
+    /// audio file or initialization panics.
+    pub fn try_new_single_file(file_path: &str) -> Result<Self, ProtError> {
+        catch_initialization_panic(|| {
+            Self::build_from_path(file_path, None, None, &ParseLimits::unlimited())
+        })
+    }
+
+    // `mode` is `None` for single files, whose play settings are never read.
+    fn build_from_path(
+        file_path: &str,
+        download: Option<&ProgressiveFile>,
+        mode: Option<ParseMode>,
+        limits: &ParseLimits,
+    ) -> Result<Self, ProtError> {
+        let container = is_matroska_with_download(file_path, download);
+        // Non-container files are probed to reject unsupported audio, and
+        // limited loads to count tracks; one probe serves both. Probing
+        // reads only the header; duration probing may scan.
+        if !container || limits.max_tracks < usize::MAX {
+            let probed = get_probe_result_with_download(file_path, download).map_err(|err| {
+                ProtError::Initialization(if container {
+                    format!("failed to probe {}: {}", file_path, err)
+                } else {
+                    format!(
+                        "{} is neither a container nor a supported audio file: {}",
+                        file_path, err
+                    )
+                })
+            })?;
+            limits
+                .check_tracks(probed.format.tracks().len())
+                .map_err(ProtError::LimitExceeded)?;
+        }
+        let info = Info::new_with_download(file_path.to_string(), download);
 
         debug!("prot info: {:?}", info);
 
@@ -110,12 +249,23 @@ impl Prot {
             impulse_response_spec: None,
             impulse_response_tail_db: None,
             effects: None,
+            variables: RuntimeVariables::new(),
+            replaygain: None,
+            missing_track_policy: MissingTrackPolicy::default(),
+            group_levels: HashMap::new(),
+            beat_grid: None,
+            limits: *limits,
+            track_keys: Arc::default(),
+            download: download.cloned(),
         };
 
-        this.load_play_settings();
+        if let Some(mode) = mode {
+            this.load_play_settings(mode, limits)?;
+        }
+        this.replaygain = read_replaygain_tags_with_download(file_path, download);
         this.refresh_tracks();
 
-        this
+        Ok(this)
     }
 
     /// Build a container from multiple standalone file path sets.
@@ -145,6 +295,14 @@ impl Prot {
             impulse_response_spec: None,
             impulse_response_tail_db: None,
             effects: None,
+            variables: RuntimeVariables::new(),
+            replaygain: None,
+            missing_track_policy: MissingTrackPolicy::default(),
+            group_levels: HashMap::new(),
+            beat_grid: None,
+            limits: ParseLimits::unlimited(),
+            track_keys: Arc::default(),
+            download: None,
         };
 
         this.refresh_tracks();
@@ -163,6 +321,18 @@ impl Prot {
 
     /// Rebuild the active track list (e.g., after shuffle).
     pub fn refresh_tracks(&mut self) {
+        self.refresh_tracks_with_rng(&mut rand::thread_rng());
+    }
+
+    /// Rebuild the active track list with a seeded random source.
+    ///
+    /// The same seed always resolves the same selection and shuffle schedule,
+    /// which makes renders reproducible.
+    pub fn refresh_tracks_with_seed(&mut self, seed: u64) {
+        self.refresh_tracks_with_rng(&mut StdRng::seed_from_u64(seed));
+    }
+
+    fn refresh_tracks_with_rng(&mut self, rng: &mut dyn RngCore) {
         self.track_ids = None;
         self.track_paths = None;
         self.shuffle_schedule.clear();
@@ -173,14 +343,20 @@ impl Prot {
             file_paths_dictionary,
         } = &self.source
         {
-            let (schedule, longest_duration) =
-                build_paths_shuffle_schedule(file_paths, &self.info, file_paths_dictionary);
+            let (schedule, longest_duration) = build_paths_shuffle_schedule(
+                file_paths,
+                &self.info,
+                file_paths_dictionary,
+                self.missing_track_policy,
+                rng,
+            );
             self.shuffle_schedule = schedule;
             self.duration = longest_duration;
 
             if let Some(entry) = self.shuffle_schedule.first() {
                 self.track_paths = Some(sources_to_track_paths(&entry.sources));
             }
+            self.apply_timeline_duration();
 
             return;
         }
@@ -196,6 +372,7 @@ impl Prot {
                         &mut longest_duration,
                         &self.info,
                         &mut self.duration,
+                        rng,
                     );
                     self.track_ids = Some(track_index_array.clone());
                     self.shuffle_schedule = vec![ShuffleScheduleEntry {
@@ -210,38 +387,150 @@ impl Prot {
                     error!("unknown file format");
                 }
                 _ => {
-                    if let Some(tracks) = versioned_tracks(play_settings) {
+                    if let Some(payload) = play_settings.versioned_payload() {
+                        let rules = payload.effective_selection_rules();
+                        let context = SelectionContext {
+                            rules: &rules,
+                            variables: &self.variables,
+                            missing: self.missing_track_policy,
+                        };
                         let (schedule, longest_duration) =
-                            build_id_shuffle_schedule(tracks, &self.info);
+                            build_id_shuffle_schedule(&payload.tracks, context, &self.info, rng);
                         self.shuffle_schedule = schedule;
                         self.duration = longest_duration;
                     }
                 }
             },
             None => {
-                warn!("no play_settings.json found; no tracks resolved");
+                // Plain audio files and Matroska files without play settings
+                // play as a one-track container.
+                if let Some(track_id) = self.info.duration_map.keys().min().copied() {
+                    info!("no play_settings.json found; playing track {}", track_id);
+                    self.shuffle_schedule = vec![ShuffleScheduleEntry {
+                        at_ms: 0,
+                        sources: vec![ShuffleSource::TrackId(track_id)],
+                    }];
+                    self.duration = longest_schedule_duration(&self.info, &self.shuffle_schedule);
+                } else {
+                    warn!("no play_settings.json found; no tracks resolved");
+                }
             }
         }
 
         if let Some(entry) = self.shuffle_schedule.first() {
             self.track_ids = Some(sources_to_track_ids(&entry.sources));
         }
+        self.apply_timeline_duration();
     }
 
-    fn load_play_settings(&mut self) {
+    /// Replace the shuffle schedule with fixed selections.
+    ///
+    /// Each entry is `(time_seconds, sources)` with one track ID or file path
+    /// per slot, in slot order. No randomness is involved, so playback
+    /// follows the given selections exactly.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first source that names no track of this container; the
+    /// current schedule is left unchanged.
+    pub(crate) fn set_fixed_schedule(
+        &mut self,
+        entries: &[(f64, Vec<String>)],
+    ) -> Result<(), String> {
+        let mut schedule = Vec::with_capacity(entries.len());
+        for (at_seconds, sources) in entries {
+            let sources = sources
+                .iter()
+                .map(|source| self.schedule_source(source).ok_or_else(|| source.clone()))
+                .collect::<Result<Vec<_>, _>>()?;
+            schedule.push(ShuffleScheduleEntry {
+                at_ms: seconds_to_ms(*at_seconds),
+                sources,
+            });
+        }
+        schedule.sort_by_key(|entry| entry.at_ms);
+
+        let first = schedule.first().map(|entry| entry.sources.as_slice());
+        match &self.source {
+            ProtSource::Container { .. } => {
+                self.duration = longest_schedule_duration(&self.info, &schedule);
+                self.track_ids = first.map(sources_to_track_ids);
+            }
+            ProtSource::Paths { .. } => {
+                self.track_paths = first.map(sources_to_track_paths);
+            }
+        }
+        self.shuffle_schedule = schedule;
+        self.apply_timeline_duration();
+        Ok(())
+    }
+
+    fn schedule_source(&self, source: &str) -> Option<ShuffleSource> {
+        match &self.source {
+            ProtSource::Container { .. } => source
+                .parse::<u32>()
+                .ok()
+                .filter(|track_id| self.info.get_duration(*track_id).is_some())
+                .map(ShuffleSource::TrackId),
+            ProtSource::Paths {
+                file_paths_dictionary,
+                ..
+            } => file_paths_dictionary
+                .iter()
+                .any(|path| path == source)
+                .then(|| ShuffleSource::FilePath(source.to_string())),
+        }
+    }
+
+    // Strict mode turns every failure except a missing attachment into an
+    // error; exceeded limits are errors in either mode.
+    fn load_play_settings(
+        &mut self,
+        mode: ParseMode,
+        limits: &ParseLimits,
+    ) -> Result<(), ProtError> {
         let ProtSource::Container { file_path } = &self.source else {
-            return;
+            return Ok(());
         };
+        if !is_matroska_with_download(file_path, self.download.as_ref()) {
+            debug!(
+                "{} is not a Matroska file; skipping play_settings",
+                file_path
+            );
+            return Ok(());
+        }
 
-        let play_settings = match try_load_play_settings_from_container(file_path) {
+        let play_settings = match try_load_play_settings_from_container(
+            file_path,
+            self.download.as_ref(),
+            mode,
+            limits,
+        ) {
             Ok(play_settings) => play_settings,
-            Err(PlaySettingsLoadError::MissingAttachment) => return,
+            Err(PlaySettingsLoadError::MissingAttachment) => return Ok(()),
+            Err(PlaySettingsLoadError::Invalid(err)) => {
+                return Err(ProtError::InvalidPlaySettings(err))
+            }
+            Err(PlaySettingsLoadError::LimitExceeded(err)) => {
+                return Err(ProtError::LimitExceeded(err))
+            }
+            Err(err) if mode == ParseMode::Strict => {
+                return Err(ProtError::InvalidPlaySettings(PlaySettingsError {
+                    path: String::new(),
+                    message: err.to_string(),
+                    version: None,
+                }))
+            }
             Err(err) => {
                 warn!("unable to load play_settings.json: {}", err);
-                return;
+                return Ok(());
             }
         };
 
+        limits
+            .check_play_settings(&play_settings)
+            .map_err(ProtError::LimitExceeded)?;
+
         let runtime = derive_runtime_settings(&play_settings);
         self.impulse_response_spec = runtime.impulse_response_spec;
         self.impulse_response_tail_db = runtime.impulse_response_tail_db;
@@ -256,9 +545,25 @@ impl Prot {
         }
 
         self.play_settings = Some(play_settings);
+        Ok(())
     }
 }
 
+// Run `build`, turning a panic during initialization into
+// `ProtError::Initialization`.
+fn catch_initialization_panic(
+    build: impl FnOnce() -> Result<Prot, ProtError>,
+) -> Result<Prot, ProtError> {
+    catch_unwind(AssertUnwindSafe(build)).map_err(|panic| {
+        let panic_msg = panic
+            .downcast_ref::<&str>()
+            .map(|msg| (*msg).to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        ProtError::Initialization(panic_msg)
+    })?
+}
+
 fn versioned_tracks(play_settings: &PlaySettingsFile) -> Option<&[SettingsTrack]> {
     play_settings
         .versioned_payload()
@@ -27,7 +27,7 @@ impl Prot {
             return vec![(0.0, group_ids_by_slot_spans(&current, &slot_spans))];
         }
 
-        self.shuffle_schedule
+        self.quantized_schedule()
             .iter()
             .map(|entry| {
                 let ids: Vec<String> = entry
@@ -70,7 +70,10 @@ impl Prot {
             build_slot_layout(slot_count, &self.logical_track_slot_spans());
         normalize_schedule_sources(&mut schedule, slot_count);
         let event_boundaries_ms = collect_event_boundaries(&schedule, start_ms);
-        let instances = collect_runtime_instances(&schedule, &slot_layout, slot_count, start_ms);
+        let mut instances =
+            collect_runtime_instances(&schedule, &slot_layout, slot_count, start_ms);
+        self.retain_playable_instances(&mut instances);
+        self.apply_track_timing(&mut instances, start_ms);
 
         RuntimeInstancePlan {
             logical_track_count,
@@ -156,7 +159,10 @@ impl Prot {
             PlaySettingsFile::Legacy(file) => {
                 count_legacy_track_combinations(file.settings.inner())
             }
-            PlaySettingsFile::V1(_) | PlaySettingsFile::V2(_) | PlaySettingsFile::V3(_) => {
+            PlaySettingsFile::V1(_)
+            | PlaySettingsFile::V2(_)
+            | PlaySettingsFile::V3(_)
+            | PlaySettingsFile::V4(_) => {
                 count_settings_track_combinations(versioned_tracks(play_settings).unwrap_or(&[]))
             }
             PlaySettingsFile::Unknown { .. } => None,
@@ -214,7 +220,7 @@ impl Prot {
 
 fn runtime_schedule(prot: &Prot) -> Vec<ShuffleScheduleEntry> {
     if !prot.shuffle_schedule.is_empty() {
-        return prot.shuffle_schedule.clone();
+        return prot.quantized_schedule();
     }
 
     let fallback_sources: Vec<ShuffleSource> = if let Some(track_ids) = &prot.track_ids {
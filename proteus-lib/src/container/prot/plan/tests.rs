@@ -4,7 +4,9 @@ use crate::container::info::Info;
 use crate::container::play_settings::PlaySettingsFile;
 use crate::container::play_settings::SettingsTrack;
 use crate::container::prot::types::PathsTrack;
-use crate::container::prot::{Prot, ProtSource, ShuffleScheduleEntry, ShuffleSource};
+use crate::container::prot::{
+    MissingTrackPolicy, Prot, ProtSource, ShuffleScheduleEntry, ShuffleSource,
+};
 
 fn test_info() -> Info {
     Info {
@@ -31,6 +33,14 @@ fn prot_from_paths(file_paths: Vec<PathsTrack>, dictionary: Vec<String>) -> Prot
         impulse_response_spec: None,
         impulse_response_tail_db: None,
         effects: None,
+        variables: Default::default(),
+        replaygain: None,
+        missing_track_policy: Default::default(),
+        group_levels: Default::default(),
+        beat_grid: None,
+        limits: crate::container::limits::ParseLimits::unlimited(),
+        track_keys: std::sync::Arc::default(),
+        download: None,
     }
 }
 
@@ -43,6 +53,11 @@ fn get_track_mix_settings_repeats_by_selections_count_for_paths_tracks() {
             pan: -0.3,
             selections_count: 2,
             shuffle_points: vec![],
+            selection_mode: Default::default(),
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         }],
         vec!["a.wav".to_string()],
     );
@@ -61,6 +76,11 @@ fn set_slot_mix_settings_updates_paths_track() {
             pan: 0.0,
             selections_count: 2,
             shuffle_points: vec![],
+            selection_mode: Default::default(),
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         }],
         vec!["a.wav".to_string()],
     );
@@ -80,6 +100,13 @@ fn get_track_mix_settings_includes_v3_tracks() {
     let play_settings = PlaySettingsFile::V3(PlaySettingsV3File {
         settings: PlaySettingsContainer::Flat(PlaySettingsV3 {
             effects: Vec::new(),
+            tempo_bpm: None,
+            selection_rules: Default::default(),
+            sections: Vec::new(),
+            effect_automation: Vec::new(),
+            loudness: None,
+            groups: Vec::new(),
+            pan_law: None,
             tracks: vec![SettingsTrack {
                 level: 0.25,
                 pan: 0.2,
@@ -88,6 +115,19 @@ fn get_track_mix_settings_includes_v3_tracks() {
                 safe_name: "track".to_string(),
                 selections_count: 2,
                 shuffle_points: vec![],
+                weights: Vec::new(),
+                selection_mode: Default::default(),
+                transitions: Vec::new(),
+                shuffle_when: Vec::new(),
+                candidate_when: Vec::new(),
+                candidate_labels: Vec::new(),
+                candidate_trims: Vec::new(),
+                candidate_analysis: Vec::new(),
+                start_offset_ms: 0,
+                looping: None,
+                group: None,
+                #[cfg(feature = "hrtf")]
+                binaural: None,
             }],
         }),
     });
@@ -105,6 +145,14 @@ fn get_track_mix_settings_includes_v3_tracks() {
         impulse_response_spec: None,
         impulse_response_tail_db: None,
         effects: None,
+        variables: Default::default(),
+        replaygain: None,
+        missing_track_policy: Default::default(),
+        group_levels: Default::default(),
+        beat_grid: None,
+        limits: crate::container::limits::ParseLimits::unlimited(),
+        track_keys: std::sync::Arc::default(),
+        download: None,
     };
 
     let settings = prot.get_track_mix_settings();
@@ -122,6 +170,11 @@ fn linked_slot_indices_returns_all_slots_for_same_track() {
                 pan: 0.0,
                 selections_count: 2,
                 shuffle_points: vec![],
+                selection_mode: Default::default(),
+                transitions: Vec::new(),
+                start_offset_ms: 0,
+                looping: None,
+                group: None,
             },
             PathsTrack {
                 file_paths: vec!["b.wav".to_string()],
@@ -129,6 +182,11 @@ fn linked_slot_indices_returns_all_slots_for_same_track() {
                 pan: 0.0,
                 selections_count: 1,
                 shuffle_points: vec![],
+                selection_mode: Default::default(),
+                transitions: Vec::new(),
+                start_offset_ms: 0,
+                looping: None,
+                group: None,
             },
         ],
         vec!["a.wav".to_string(), "b.wav".to_string()],
@@ -150,6 +208,11 @@ fn get_shuffle_schedule_groups_by_paths_track_selections_count() {
                 pan: 0.0,
                 selections_count: 2,
                 shuffle_points: vec![],
+                selection_mode: Default::default(),
+                transitions: Vec::new(),
+                start_offset_ms: 0,
+                looping: None,
+                group: None,
             },
             PathsTrack {
                 file_paths: vec!["c.wav".to_string()],
@@ -157,6 +220,11 @@ fn get_shuffle_schedule_groups_by_paths_track_selections_count() {
                 pan: 0.0,
                 selections_count: 1,
                 shuffle_points: vec![],
+                selection_mode: Default::default(),
+                transitions: Vec::new(),
+                start_offset_ms: 0,
+                looping: None,
+                group: None,
             },
         ],
         vec![
@@ -184,3 +252,169 @@ fn get_shuffle_schedule_groups_by_paths_track_selections_count() {
         ]
     );
 }
+
+fn prot_with_ids(ids: &str, durations: &[(u32, f64)], policy: MissingTrackPolicy) -> Prot {
+    let play_settings: PlaySettingsFile = serde_json::from_str(&format!(
+        r#"{{
+            "encoder_version": "4",
+            "play_settings": {{
+                "tracks": [
+                    {{"level": 1.0, "pan": 0.0, "ids": {ids}, "name": "A", "safe_name": "a",
+                     "shuffle_points": ["0:05", "0:10"]}}
+                ]
+            }}
+        }}"#
+    ))
+    .unwrap();
+    let mut prot = prot_from_paths(Vec::new(), Vec::new());
+    prot.source = ProtSource::Container {
+        file_path: "dummy.prot".to_string(),
+    };
+    prot.info.duration_map = durations.iter().copied().collect();
+    prot.play_settings = Some(play_settings);
+    prot.set_missing_track_policy(policy);
+    prot
+}
+
+#[test]
+fn retry_policy_never_selects_missing_or_empty_ids() {
+    // Id 2 is zero-length and id 4 is absent from the container.
+    let durations = [(1, 12.0), (2, 0.0), (3, 12.0)];
+    let mut prot = prot_with_ids("[1, 2, 4]", &durations, MissingTrackPolicy::Retry);
+    for seed in 0..20 {
+        prot.refresh_tracks_with_seed(seed);
+        assert_eq!(prot.shuffle_schedule.len(), 3);
+        assert!(prot.unplayable_selections().is_empty(), "seed {seed}");
+    }
+}
+
+#[test]
+fn skip_policy_keeps_the_selection_but_leaves_it_out_of_the_plan() {
+    let durations = [(1, 12.0), (2, 0.0)];
+    let mut prot = prot_with_ids("[2]", &durations, MissingTrackPolicy::Skip);
+    prot.refresh_tracks_with_seed(1);
+    assert_eq!(prot.unplayable_selections(), vec!["2".to_string()]);
+    assert!(prot.build_runtime_instance_plan(0.0).instances.is_empty());
+}
+
+#[test]
+fn retry_policy_skips_unplayable_paths() {
+    let mut track = PathsTrack::new_from_file_paths(vec!["a.wav".into(), "b.wav".into()]);
+    track.shuffle_points = vec!["0:05".to_string(), "0:10".to_string()];
+    let mut prot = prot_from_paths(vec![track], vec!["a.wav".into(), "b.wav".into()]);
+    prot.info.duration_map = HashMap::from([(0, 12.0), (1, 0.0)]);
+    for seed in 0..20 {
+        prot.refresh_tracks_with_seed(seed);
+        let plan = prot.build_runtime_instance_plan(0.0);
+        assert!(plan
+            .instances
+            .iter()
+            .all(|instance| instance.source_key == ShuffleSource::FilePath("a.wav".into())));
+    }
+}
+
+#[test]
+fn start_offsets_reach_plan_instances_and_extend_duration() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1], "name": "A", "safe_name": "a"},
+                    {"level": 1.0, "pan": 0.0, "ids": [2], "name": "B", "safe_name": "b",
+                     "start_offset_ms": 2500}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_paths(Vec::new(), Vec::new());
+    prot.source = ProtSource::Container {
+        file_path: "dummy.prot".to_string(),
+    };
+    prot.info.duration_map = HashMap::from([(1, 10.0), (2, 10.0)]);
+    prot.play_settings = Some(play_settings);
+    prot.refresh_tracks_with_seed(1);
+
+    assert_eq!(prot.track_start_offsets_ms(), vec![0, 2500]);
+    assert_eq!(*prot.get_duration(), 12.5);
+    let offsets: Vec<u64> = prot
+        .build_runtime_instance_plan(1.0)
+        .instances
+        .iter()
+        .map(|instance| instance.start_offset_ms)
+        .collect();
+    assert_eq!(offsets, vec![0, 2500]);
+}
+
+#[test]
+fn candidate_trims_reach_plan_instances_and_shorten_duration() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1], "name": "A", "safe_name": "a",
+                     "candidate_trims": [{"id": 1, "start_ms": 500, "end_ms": 9000}]},
+                    {"level": 1.0, "pan": 0.0, "ids": [2], "name": "B", "safe_name": "b",
+                     "candidate_trims": [{"id": 2, "start_ms": 1500}]}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_paths(Vec::new(), Vec::new());
+    prot.source = ProtSource::Container {
+        file_path: "dummy.prot".to_string(),
+    };
+    prot.info.duration_map = HashMap::from([(1, 10.0), (2, 10.0)]);
+    prot.play_settings = Some(play_settings);
+    prot.refresh_tracks_with_seed(1);
+
+    assert_eq!(*prot.get_duration(), 8.5);
+    let trims: Vec<_> = prot
+        .build_runtime_instance_plan(0.0)
+        .instances
+        .iter()
+        .map(|instance| (instance.trim.start_ms, instance.trim.end_ms))
+        .collect();
+    assert_eq!(trims, vec![(500, Some(9000)), (1500, None)]);
+}
+
+#[test]
+fn looping_tracks_contribute_one_pass_and_loop_until_the_song_ends() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1], "name": "A", "safe_name": "a"},
+                    {"level": 1.0, "pan": 0.0, "ids": [2], "name": "B", "safe_name": "b",
+                     "loop": true},
+                    {"level": 1.0, "pan": 0.0, "ids": [3], "name": "C", "safe_name": "c",
+                     "loop": {"start_ms": 1000, "end_ms": 4000}}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_paths(Vec::new(), Vec::new());
+    prot.source = ProtSource::Container {
+        file_path: "dummy.prot".to_string(),
+    };
+    prot.info.duration_map = HashMap::from([(1, 10.0), (2, 3.0), (3, 30.0)]);
+    prot.play_settings = Some(play_settings);
+    prot.refresh_tracks_with_seed(1);
+
+    assert_eq!(*prot.get_duration(), 10.0);
+    let loops: Vec<_> = prot
+        .build_runtime_instance_plan(2.0)
+        .instances
+        .iter()
+        .map(|instance| instance.looping.map(|l| (l.start_ms, l.end_ms, l.until_ms)))
+        .collect();
+    assert_eq!(
+        loops,
+        vec![None, Some((0, 3000, 8000)), Some((1000, 4000, 8000))]
+    );
+}
@@ -3,24 +3,34 @@
 use std::collections::{BTreeSet, HashSet};
 
 use log::warn;
-use rand::Rng;
+use rand::RngCore;
 
 use crate::container::info::Info;
 use crate::container::play_settings::SettingsTrack;
 
+use super::availability::{playable_mask, MissingTrackPolicy};
+use super::helpers::sources_to_track_ids;
+use super::selection::{
+    reselect_slots, restrict_to_playable, select_initial, CandidateChooser, SelectionContext,
+    SlotCandidates,
+};
 use super::types::{PathsTrack, ShuffleScheduleEntry, ShuffleSource};
 
-pub(super) fn build_id_shuffle_schedule(
-    tracks: &[SettingsTrack],
-    info: &Info,
-) -> (Vec<ShuffleScheduleEntry>, f64) {
-    let mut shuffle_timestamps = BTreeSet::new();
-    let mut slot_candidates: Vec<Vec<u32>> = Vec::new();
-    let mut slot_points: Vec<HashSet<u64>> = Vec::new();
-    let mut current_ids: Vec<u32> = Vec::new();
-    let mut longest_duration = 0.0_f64;
-    shuffle_timestamps.insert(0);
+/// Slots, their shuffle points and every shuffle timestamp of an id schedule.
+struct IdScheduleLayout {
+    slots: Vec<SlotCandidates>,
+    slot_points: Vec<HashSet<u64>>,
+    timestamps: BTreeSet<u64>,
+}
 
+/// Lay out the slots of `tracks`; with `retry_from`, draws skip candidates
+/// that cannot play according to its durations.
+fn id_schedule_layout(tracks: &[SettingsTrack], retry_from: Option<&Info>) -> IdScheduleLayout {
+    let mut layout = IdScheduleLayout {
+        slots: Vec::new(),
+        slot_points: Vec::new(),
+        timestamps: BTreeSet::from([0]),
+    };
     for track in tracks {
         if track.ids.is_empty() {
             continue;
@@ -30,72 +40,141 @@ pub(super) fn build_id_shuffle_schedule(
             continue;
         }
         let points = parse_shuffle_points(&track.shuffle_points);
-        for point in &points {
-            shuffle_timestamps.insert(*point);
-        }
+        layout.timestamps.extend(points.iter().copied());
         let point_set: HashSet<u64> = points.into_iter().collect();
+        let mut candidates = SlotCandidates::from_track(track);
+        if let Some(info) = retry_from {
+            let playable = playable_mask(info, candidates.ids().iter().copied().map(Some));
+            candidates = candidates.with_playable(playable);
+        }
         for _ in 0..selections {
-            slot_candidates.push(track.ids.clone());
-            slot_points.push(point_set.clone());
-            let choice = random_id(&track.ids);
-            if let Some(duration) = info.get_duration(choice) {
-                longest_duration = longest_duration.max(duration);
-            }
-            current_ids.push(choice);
+            layout.slots.push(candidates.clone());
+            layout.slot_points.push(point_set.clone());
         }
     }
+    layout
+}
 
-    let mut schedule = Vec::new();
-    if current_ids.is_empty() {
-        return (schedule, longest_duration);
+pub(super) fn build_id_shuffle_schedule(
+    tracks: &[SettingsTrack],
+    context: SelectionContext<'_>,
+    info: &Info,
+    rng: &mut dyn RngCore,
+) -> (Vec<ShuffleScheduleEntry>, f64) {
+    let layout = id_schedule_layout(tracks, retry_source(context.missing, info));
+    if layout.slots.is_empty() {
+        return (Vec::new(), 0.0);
     }
 
-    schedule.push(ShuffleScheduleEntry {
-        at_ms: 0,
-        sources: current_ids
-            .iter()
-            .copied()
-            .map(ShuffleSource::TrackId)
-            .collect(),
-    });
+    let current_ids = select_initial(&layout.slots, context, rng);
+    let mut schedule = vec![id_schedule_entry(0, &current_ids)];
+    extend_id_schedule(&layout, &mut schedule, current_ids, context, rng);
+    let longest_duration = longest_schedule_duration(info, &schedule);
+    (schedule, longest_duration)
+}
 
-    for timestamp in shuffle_timestamps.into_iter().filter(|point| *point > 0) {
-        for slot_index in 0..current_ids.len() {
-            if slot_points[slot_index].contains(&timestamp) {
-                current_ids[slot_index] = random_id(&slot_candidates[slot_index]);
-                if let Some(duration) = info.get_duration(current_ids[slot_index]) {
-                    longest_duration = longest_duration.max(duration);
-                }
-            }
-        }
-        schedule.push(ShuffleScheduleEntry {
-            at_ms: timestamp,
-            sources: current_ids
-                .iter()
-                .copied()
-                .map(ShuffleSource::TrackId)
-                .collect(),
-        });
-    }
+/// Redraw every entry after `after_ms`, keeping earlier entries unchanged.
+///
+/// Falls back to a full build when `existing` does not match the layout of
+/// `tracks`.
+pub(super) fn rebuild_id_shuffle_schedule_after(
+    existing: &[ShuffleScheduleEntry],
+    after_ms: u64,
+    tracks: &[SettingsTrack],
+    context: SelectionContext<'_>,
+    info: &Info,
+    rng: &mut dyn RngCore,
+) -> (Vec<ShuffleScheduleEntry>, f64) {
+    let layout = id_schedule_layout(tracks, retry_source(context.missing, info));
+    let mut schedule: Vec<ShuffleScheduleEntry> = existing
+        .iter()
+        .take_while(|entry| entry.at_ms <= after_ms)
+        .cloned()
+        .collect();
+    let current_ids = schedule
+        .last()
+        .map(|entry| sources_to_track_ids(&entry.sources))
+        .filter(|ids| !ids.is_empty() && ids.len() == layout.slots.len());
+    let Some(current_ids) = current_ids else {
+        return build_id_shuffle_schedule(tracks, context, info, rng);
+    };
 
+    extend_id_schedule(&layout, &mut schedule, current_ids, context, rng);
+    let longest_duration = longest_schedule_duration(info, &schedule);
     (schedule, longest_duration)
 }
 
+/// Append an entry for every layout timestamp after the last one in `schedule`.
+fn extend_id_schedule(
+    layout: &IdScheduleLayout,
+    schedule: &mut Vec<ShuffleScheduleEntry>,
+    mut current_ids: Vec<u32>,
+    context: SelectionContext<'_>,
+    rng: &mut dyn RngCore,
+) {
+    let after_ms = schedule.last().map_or(0, |entry| entry.at_ms);
+    for &timestamp in layout.timestamps.range(after_ms + 1..) {
+        let reshuffled: Vec<usize> = (0..current_ids.len())
+            .filter(|slot_index| {
+                layout.slot_points[*slot_index].contains(&timestamp)
+                    && layout.slots[*slot_index].shuffles(context.variables)
+            })
+            .collect();
+        reselect_slots(&layout.slots, &mut current_ids, &reshuffled, context, rng);
+        schedule.push(id_schedule_entry(timestamp, &current_ids));
+    }
+}
+
+/// Return `true` when any gate in `tracks` reads `variable`.
+pub(super) fn id_schedule_uses_variable(tracks: &[SettingsTrack], variable: &str) -> bool {
+    id_schedule_layout(tracks, None)
+        .slots
+        .iter()
+        .any(|slot| slot.uses_variable(variable))
+}
+
+fn retry_source(policy: MissingTrackPolicy, info: &Info) -> Option<&Info> {
+    (policy == MissingTrackPolicy::Retry).then_some(info)
+}
+
+fn id_schedule_entry(at_ms: u64, ids: &[u32]) -> ShuffleScheduleEntry {
+    ShuffleScheduleEntry {
+        at_ms,
+        sources: ids.iter().copied().map(ShuffleSource::TrackId).collect(),
+    }
+}
+
+pub(super) fn longest_schedule_duration(info: &Info, schedule: &[ShuffleScheduleEntry]) -> f64 {
+    schedule
+        .iter()
+        .flat_map(|entry| sources_to_track_ids(&entry.sources))
+        .filter_map(|id| info.get_duration(id))
+        .fold(0.0, f64::max)
+}
+
 struct ScheduleBuildState<'a> {
     shuffle_timestamps: &'a mut BTreeSet<u64>,
     slot_candidates: &'a mut Vec<Vec<String>>,
+    slot_choosers: &'a mut Vec<CandidateChooser>,
     slot_points: &'a mut Vec<HashSet<u64>>,
+    slot_playable: &'a mut Vec<Vec<bool>>,
     current_paths: &'a mut Vec<String>,
+    retry_missing: bool,
+    rng: &'a mut dyn RngCore,
 }
 
 pub(super) fn build_paths_shuffle_schedule(
     tracks: &[PathsTrack],
     info: &Info,
     dictionary: &[String],
+    missing: MissingTrackPolicy,
+    rng: &mut dyn RngCore,
 ) -> (Vec<ShuffleScheduleEntry>, f64) {
     let mut shuffle_timestamps = BTreeSet::new();
     let mut slot_candidates: Vec<Vec<String>> = Vec::new();
+    let mut slot_choosers: Vec<CandidateChooser> = Vec::new();
     let mut slot_points: Vec<HashSet<u64>> = Vec::new();
+    let mut slot_playable: Vec<Vec<bool>> = Vec::new();
     let mut current_paths: Vec<String> = Vec::new();
     let mut longest_duration = 0.0_f64;
     let dictionary_lookup: std::collections::HashMap<&str, u32> = dictionary
@@ -109,8 +188,12 @@ pub(super) fn build_paths_shuffle_schedule(
         let mut state = ScheduleBuildState {
             shuffle_timestamps: &mut shuffle_timestamps,
             slot_candidates: &mut slot_candidates,
+            slot_choosers: &mut slot_choosers,
             slot_points: &mut slot_points,
+            slot_playable: &mut slot_playable,
             current_paths: &mut current_paths,
+            retry_missing: missing == MissingTrackPolicy::Retry,
+            rng: &mut *rng,
         };
         longest_duration = append_path_track_slots(
             track,
@@ -138,7 +221,13 @@ pub(super) fn build_paths_shuffle_schedule(
     for timestamp in shuffle_timestamps.into_iter().filter(|point| *point > 0) {
         for slot_index in 0..current_paths.len() {
             if slot_points[slot_index].contains(&timestamp) {
-                current_paths[slot_index] = random_path(&slot_candidates[slot_index]);
+                let candidates = &slot_candidates[slot_index];
+                let previous = candidates
+                    .iter()
+                    .position(|path| *path == current_paths[slot_index]);
+                let eligible = restrict_to_playable(None, &slot_playable[slot_index]);
+                let next = slot_choosers[slot_index].pick_index(previous, eligible.as_deref(), rng);
+                current_paths[slot_index] = candidates[next].clone();
                 if let Some(index) = dictionary_lookup
                     .get(current_paths[slot_index].as_str())
                     .copied()
@@ -183,10 +272,31 @@ fn append_path_track_slots(
         state.shuffle_timestamps.insert(*point);
     }
     let point_set: HashSet<u64> = points.into_iter().collect();
+    let chooser = CandidateChooser::new(
+        track.file_paths.len(),
+        track.selection_mode,
+        &[],
+        &track.transitions,
+    );
+    let playable = if state.retry_missing {
+        playable_mask(
+            info,
+            track
+                .file_paths
+                .iter()
+                .map(|path| dictionary_lookup.get(path.as_str()).copied()),
+        )
+    } else {
+        Vec::new()
+    };
+    let eligible = restrict_to_playable(None, &playable);
     for _ in 0..selections {
         state.slot_candidates.push(track.file_paths.clone());
         state.slot_points.push(point_set.clone());
-        let choice = random_path(&track.file_paths);
+        state.slot_playable.push(playable.clone());
+        let choice =
+            track.file_paths[chooser.pick_index(None, eligible.as_deref(), state.rng)].clone();
+        state.slot_choosers.push(chooser.clone());
         longest_duration =
             update_longest_duration_for_path(info, dictionary_lookup, &choice, longest_duration);
         state.current_paths.push(choice);
@@ -222,7 +332,7 @@ pub(super) fn parse_shuffle_points(points: &[String]) -> Vec<u64> {
     parsed
 }
 
-fn parse_timestamp_ms(value: &str) -> Option<u64> {
+pub(super) fn parse_timestamp_ms(value: &str) -> Option<u64> {
     let parts: Vec<&str> = value.trim().split(':').collect();
     if parts.len() > 3 {
         return None;
@@ -258,16 +368,6 @@ pub(super) fn seconds_to_ms(seconds: f64) -> u64 {
     (seconds * 1000.0).round() as u64
 }
 
-pub(super) fn random_id(ids: &[u32]) -> u32 {
-    let random_index = rand::thread_rng().gen_range(0..ids.len());
-    ids[random_index]
-}
-
-pub(super) fn random_path(paths: &[String]) -> String {
-    let random_index = rand::thread_rng().gen_range(0..paths.len());
-    paths[random_index].clone()
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
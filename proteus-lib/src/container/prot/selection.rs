@@ -0,0 +1,340 @@
+//! Weighted, rule-constrained slot selection for shuffle schedules.
+
+use log::warn;
+use rand::{Rng, RngCore};
+
+use super::availability::MissingTrackPolicy;
+use crate::container::play_settings::{
+    conditions_hold, RuntimeVariables, SelectionMode, SelectionRules, SettingsTrack,
+    VariableCondition,
+};
+
+/// Draws to attempt before settling for the closest selection found.
+const MAX_SELECTION_ATTEMPTS: usize = 256;
+
+/// Picks candidate indices for one slot according to its selection mode.
+#[derive(Debug, Clone)]
+pub(super) struct CandidateChooser {
+    len: usize,
+    mode: SelectionMode,
+    /// Per-candidate weights, or `None` for a uniform choice.
+    weights: Option<Vec<f64>>,
+    /// Markov rows; `None` rows fall back to the base draw.
+    transitions: Vec<Option<Vec<f64>>>,
+}
+
+impl CandidateChooser {
+    /// Build a chooser over `len` candidates.
+    ///
+    /// Missing base weights count as `1.0`; missing transition entries count
+    /// as `0.0`. Negative and non-finite values are treated as `0.0`.
+    pub(super) fn new(
+        len: usize,
+        mode: SelectionMode,
+        weights: &[f32],
+        transitions: &[Vec<f32>],
+    ) -> Self {
+        let base: Vec<f64> = (0..len)
+            .map(|index| sanitize_weight(weights.get(index).copied().unwrap_or(1.0)))
+            .collect();
+        let uniform = weights.is_empty()
+            || base.iter().all(|weight| *weight == base[0])
+            || base.iter().sum::<f64>() <= 0.0;
+        let transitions = if mode == SelectionMode::Markov {
+            transitions
+                .iter()
+                .take(len)
+                .map(|row| {
+                    let row: Vec<f64> = (0..len)
+                        .map(|index| sanitize_weight(row.get(index).copied().unwrap_or(0.0)))
+                        .collect();
+                    (row.iter().sum::<f64>() > 0.0).then_some(row)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        Self {
+            len,
+            mode,
+            weights: (!uniform).then_some(base),
+            transitions,
+        }
+    }
+
+    /// Pick the next candidate index given the previously selected one.
+    ///
+    /// When `eligible` is set, only indices marked `true` are picked; it must
+    /// mark at least one index.
+    pub(super) fn pick_index(
+        &self,
+        previous: Option<usize>,
+        eligible: Option<&[bool]>,
+        rng: &mut dyn RngCore,
+    ) -> usize {
+        match (self.mode, previous) {
+            (SelectionMode::Sequential, Some(previous)) => (1..=self.len)
+                .map(|step| (previous + step) % self.len)
+                .find(|index| eligible.is_none_or(|eligible| eligible[*index]))
+                .unwrap_or((previous + 1) % self.len),
+            (SelectionMode::Markov, Some(previous)) => {
+                let row = self.transitions.get(previous).and_then(Option::as_ref);
+                match row.and_then(|row| masked(row, eligible)) {
+                    Some(row) => weighted_index(&row, rng),
+                    None => self.pick_base(eligible, rng),
+                }
+            }
+            _ => self.pick_base(eligible, rng),
+        }
+    }
+
+    fn pick_base(&self, eligible: Option<&[bool]>, rng: &mut dyn RngCore) -> usize {
+        let Some(eligible) = eligible else {
+            return match self.weights.as_ref() {
+                Some(weights) => weighted_index(weights, rng),
+                None => rng.gen_range(0..self.len),
+            };
+        };
+        let uniform = vec![1.0; self.len];
+        let weights = self.weights.as_deref().unwrap_or(&uniform);
+        let weights = masked(weights, Some(eligible)).or_else(|| masked(&uniform, Some(eligible)));
+        weights.map_or(0, |weights| weighted_index(&weights, rng))
+    }
+}
+
+/// Zero the weights of ineligible indices; `None` when nothing is left.
+fn masked(weights: &[f64], eligible: Option<&[bool]>) -> Option<Vec<f64>> {
+    let weights: Vec<f64> = match eligible {
+        Some(eligible) => weights
+            .iter()
+            .zip(eligible)
+            .map(|(weight, eligible)| if *eligible { *weight } else { 0.0 })
+            .collect(),
+        None => weights.to_vec(),
+    };
+    (weights.iter().sum::<f64>() > 0.0).then_some(weights)
+}
+
+fn sanitize_weight(weight: f32) -> f64 {
+    if weight.is_finite() && weight > 0.0 {
+        f64::from(weight)
+    } else {
+        0.0
+    }
+}
+
+/// Draw an index proportionally to `weights`, which must sum above zero.
+fn weighted_index(weights: &[f64], rng: &mut dyn RngCore) -> usize {
+    let mut target = rng.gen::<f64>() * weights.iter().sum::<f64>();
+    for (index, weight) in weights.iter().enumerate() {
+        if target < *weight {
+            return index;
+        }
+        target -= weight;
+    }
+    // Rounding can leave `target` just past the last bucket.
+    weights
+        .iter()
+        .rposition(|weight| *weight > 0.0)
+        .unwrap_or(0)
+}
+
+/// Selection rules and runtime variables shared by every draw.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SelectionContext<'a> {
+    pub rules: &'a SelectionRules,
+    pub variables: &'a RuntimeVariables,
+    pub missing: MissingTrackPolicy,
+}
+
+/// Candidate ids, their chooser and runtime gates for one slot.
+#[derive(Debug, Clone)]
+pub(super) struct SlotCandidates {
+    ids: Vec<u32>,
+    chooser: CandidateChooser,
+    /// Per-candidate conditions parallel to `ids`; empty when ungated.
+    conditions: Vec<Vec<VariableCondition>>,
+    shuffle_when: Vec<VariableCondition>,
+    /// Per-candidate playability parallel to `ids`; empty when all can play.
+    playable: Vec<bool>,
+}
+
+impl SlotCandidates {
+    /// Build the candidate list for one slot of `track`.
+    pub(super) fn from_track(track: &SettingsTrack) -> Self {
+        Self {
+            ids: track.ids.clone(),
+            chooser: CandidateChooser::new(
+                track.ids.len(),
+                track.selection_mode,
+                &track.weights,
+                &track.transitions,
+            ),
+            conditions: if track.candidate_when.is_empty() {
+                Vec::new()
+            } else {
+                track
+                    .ids
+                    .iter()
+                    .map(|id| {
+                        track
+                            .candidate_when
+                            .iter()
+                            .filter(|candidate| candidate.id == *id)
+                            .flat_map(|candidate| candidate.when.iter().cloned())
+                            .collect()
+                    })
+                    .collect()
+            },
+            shuffle_when: track.shuffle_when.clone(),
+            playable: Vec::new(),
+        }
+    }
+
+    /// Restrict draws to the candidates marked playable in `playable`.
+    pub(super) fn with_playable(mut self, playable: Vec<bool>) -> Self {
+        self.playable = playable;
+        self
+    }
+
+    /// Candidate ids in declaration order.
+    pub(super) fn ids(&self) -> &[u32] {
+        &self.ids
+    }
+
+    /// Return `true` when this slot's shuffle points are active.
+    pub(super) fn shuffles(&self, variables: &RuntimeVariables) -> bool {
+        conditions_hold(&self.shuffle_when, variables)
+    }
+
+    /// Return `true` when any gate on this slot reads `variable`.
+    pub(super) fn uses_variable(&self, variable: &str) -> bool {
+        self.shuffle_when
+            .iter()
+            .chain(self.conditions.iter().flatten())
+            .any(|condition| condition.variable == variable)
+    }
+
+    fn pick(
+        &self,
+        previous: Option<u32>,
+        variables: &RuntimeVariables,
+        rng: &mut dyn RngCore,
+    ) -> u32 {
+        let previous =
+            previous.and_then(|id| self.ids.iter().position(|candidate| *candidate == id));
+        let eligible: Option<Vec<bool>> = (!self.conditions.is_empty())
+            .then(|| {
+                self.conditions
+                    .iter()
+                    .map(|conditions| conditions_hold(conditions, variables))
+                    .collect::<Vec<bool>>()
+            })
+            .filter(|eligible| eligible.contains(&true));
+        let eligible = restrict_to_playable(eligible, &self.playable);
+        self.ids[self.chooser.pick_index(previous, eligible.as_deref(), rng)]
+    }
+}
+
+/// Narrow `eligible` to playable candidates, unless that leaves none.
+pub(super) fn restrict_to_playable(
+    eligible: Option<Vec<bool>>,
+    playable: &[bool],
+) -> Option<Vec<bool>> {
+    if playable.is_empty() {
+        return eligible;
+    }
+    let restricted: Vec<bool> = playable
+        .iter()
+        .enumerate()
+        .map(|(index, playable)| {
+            *playable && eligible.as_ref().is_none_or(|eligible| eligible[index])
+        })
+        .collect();
+    if restricted.contains(&true) {
+        Some(restricted)
+    } else {
+        eligible
+    }
+}
+
+/// Draw the first selection for every slot.
+pub(super) fn select_initial(
+    slots: &[SlotCandidates],
+    context: SelectionContext<'_>,
+    rng: &mut dyn RngCore,
+) -> Vec<u32> {
+    let mut current = vec![0_u32; slots.len()];
+    let all_slots: Vec<usize> = (0..slots.len()).collect();
+    select(slots, &mut current, &all_slots, None, context, rng);
+    current
+}
+
+/// Redraw the slots listed in `reselect`, keeping all other slots fixed.
+///
+/// Draws are repeated until `rules` hold for the whole selection. If no
+/// draw satisfies them within [`MAX_SELECTION_ATTEMPTS`], the draw with the
+/// fewest violations is kept and a warning is logged.
+pub(super) fn reselect_slots(
+    slots: &[SlotCandidates],
+    current: &mut [u32],
+    reselect: &[usize],
+    context: SelectionContext<'_>,
+    rng: &mut dyn RngCore,
+) {
+    let previous = current.to_vec();
+    select(slots, current, reselect, Some(&previous), context, rng);
+}
+
+fn select(
+    slots: &[SlotCandidates],
+    current: &mut [u32],
+    reselect: &[usize],
+    previous: Option<&[u32]>,
+    context: SelectionContext<'_>,
+    rng: &mut dyn RngCore,
+) {
+    let rules = context.rules;
+    draw(slots, current, reselect, previous, context.variables, rng);
+    if rules.is_empty() {
+        return;
+    }
+
+    let mut best = current.to_vec();
+    let mut best_violations = rules.violations(current);
+    for _ in 1..MAX_SELECTION_ATTEMPTS {
+        if best_violations == 0 {
+            break;
+        }
+        draw(slots, current, reselect, previous, context.variables, rng);
+        let violations = rules.violations(current);
+        if violations < best_violations {
+            best.copy_from_slice(current);
+            best_violations = violations;
+        }
+    }
+    current.copy_from_slice(&best);
+    if best_violations > 0 {
+        warn!(
+            "selection rules not satisfiable after {} attempts; keeping closest selection ({} violations)",
+            MAX_SELECTION_ATTEMPTS, best_violations
+        );
+    }
+}
+
+fn draw(
+    slots: &[SlotCandidates],
+    current: &mut [u32],
+    reselect: &[usize],
+    previous: Option<&[u32]>,
+    variables: &RuntimeVariables,
+    rng: &mut dyn RngCore,
+) {
+    for &slot_index in reselect {
+        let previous_id = previous.map(|ids| ids[slot_index]);
+        current[slot_index] = slots[slot_index].pick(previous_id, variables, rng);
+    }
+}
+
+#[cfg(test)]
+mod tests;
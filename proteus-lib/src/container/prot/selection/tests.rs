@@ -0,0 +1,170 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use super::*;
+use crate::container::play_settings::{CandidateCondition, SelectionGroup};
+
+fn context<'a>(rules: &'a SelectionRules, variables: &'a RuntimeVariables) -> SelectionContext<'a> {
+    SelectionContext {
+        rules,
+        variables,
+        missing: MissingTrackPolicy::Retry,
+    }
+}
+
+fn track(ids: Vec<u32>, weights: Vec<f32>) -> SettingsTrack {
+    SettingsTrack {
+        level: 1.0,
+        pan: 0.0,
+        ids,
+        name: "Track".to_string(),
+        safe_name: "track".to_string(),
+        selections_count: 1,
+        shuffle_points: Vec::new(),
+        weights,
+        selection_mode: SelectionMode::Random,
+        transitions: Vec::new(),
+        shuffle_when: Vec::new(),
+        candidate_when: Vec::new(),
+        candidate_labels: Vec::new(),
+        candidate_trims: Vec::new(),
+        candidate_analysis: Vec::new(),
+        start_offset_ms: 0,
+        looping: None,
+        group: None,
+        #[cfg(feature = "hrtf")]
+        binaural: None,
+    }
+}
+
+#[test]
+fn weighted_pick_skips_zero_weight_candidates() {
+    let slot = SlotCandidates::from_track(&track(vec![1, 2, 3], vec![0.0, 5.0, 0.0]));
+    let mut rng = StdRng::seed_from_u64(1);
+    assert!((0..100).all(|_| slot.pick(None, &RuntimeVariables::new(), &mut rng) == 2));
+}
+
+#[test]
+fn initial_selection_honors_groups_and_exclusions() {
+    let slots = vec![
+        SlotCandidates::from_track(&track(vec![1, 2], Vec::new())),
+        SlotCandidates::from_track(&track(vec![3, 4], Vec::new())),
+        SlotCandidates::from_track(&track(vec![5, 6], Vec::new())),
+    ];
+    let rules = SelectionRules {
+        groups: vec![SelectionGroup {
+            name: "leads".to_string(),
+            ids: vec![1, 3, 5],
+            max_active: None,
+            exactly: Some(1),
+        }],
+        never_together: vec![[2, 4]],
+        avoid_key_clashes: false,
+    };
+    let variables = RuntimeVariables::new();
+    let mut rng = StdRng::seed_from_u64(9);
+    for _ in 0..50 {
+        let current = select_initial(&slots, context(&rules, &variables), &mut rng);
+        assert_eq!(rules.violations(&current), 0, "{current:?}");
+    }
+}
+
+#[test]
+fn reselect_keeps_fixed_slots() {
+    let slots = vec![
+        SlotCandidates::from_track(&track(vec![1, 2], Vec::new())),
+        SlotCandidates::from_track(&track(vec![3, 4], Vec::new())),
+    ];
+    let rules = SelectionRules {
+        never_together: vec![[1, 3]],
+        ..SelectionRules::default()
+    };
+    let variables = RuntimeVariables::new();
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..20 {
+        let mut current = vec![1, 0];
+        reselect_slots(
+            &slots,
+            &mut current,
+            &[1],
+            context(&rules, &variables),
+            &mut rng,
+        );
+        assert_eq!(current, vec![1, 4]);
+    }
+}
+
+#[test]
+fn markov_follows_transition_rows() {
+    let mut markov = track(vec![10, 20, 30], Vec::new());
+    markov.selection_mode = SelectionMode::Markov;
+    // 10 -> 20 -> 30 -> 10, with an unusable row for 30 falling back to random.
+    markov.transitions = vec![vec![0.0, 1.0], vec![0.0, 0.0, 1.0]];
+    let slot = SlotCandidates::from_track(&markov);
+    let variables = RuntimeVariables::new();
+    let mut rng = StdRng::seed_from_u64(5);
+    for _ in 0..20 {
+        assert_eq!(slot.pick(Some(10), &variables, &mut rng), 20);
+        assert_eq!(slot.pick(Some(20), &variables, &mut rng), 30);
+    }
+    let fallback: std::collections::HashSet<u32> = (0..100)
+        .map(|_| slot.pick(Some(30), &variables, &mut rng))
+        .collect();
+    assert_eq!(fallback.len(), 3);
+}
+
+#[test]
+fn sequential_cycles_through_candidates() {
+    let mut sequential = track(vec![1, 2, 3], Vec::new());
+    sequential.selection_mode = SelectionMode::Sequential;
+    let slots = vec![SlotCandidates::from_track(&sequential)];
+    let rules = SelectionRules::default();
+    let variables = RuntimeVariables::new();
+    let mut rng = StdRng::seed_from_u64(2);
+    let mut current = vec![3];
+    let mut seen = Vec::new();
+    for _ in 0..4 {
+        reselect_slots(
+            &slots,
+            &mut current,
+            &[0],
+            context(&rules, &variables),
+            &mut rng,
+        );
+        seen.push(current[0]);
+    }
+    assert_eq!(seen, vec![1, 2, 3, 1]);
+}
+
+#[test]
+fn candidate_conditions_follow_runtime_variables() {
+    let mut gated = track(vec![1, 2, 3], Vec::new());
+    gated.candidate_when = vec![
+        CandidateCondition {
+            id: 3,
+            when: vec![VariableCondition {
+                variable: "intensity".to_string(),
+                min: Some(0.5),
+                max: None,
+            }],
+        },
+        CandidateCondition {
+            id: 1,
+            when: vec![VariableCondition {
+                variable: "intensity".to_string(),
+                min: None,
+                max: Some(0.5),
+            }],
+        },
+    ];
+    let slot = SlotCandidates::from_track(&gated);
+    let mut variables = RuntimeVariables::new();
+    let mut rng = StdRng::seed_from_u64(4);
+
+    variables.insert("intensity".to_string(), 0.9);
+    assert!((0..100).all(|_| slot.pick(None, &variables, &mut rng) != 1));
+    variables.insert("intensity".to_string(), 0.1);
+    assert!((0..100).all(|_| slot.pick(None, &variables, &mut rng) != 3));
+    assert!(slot.uses_variable("intensity"));
+    assert!(!slot.uses_variable("tempo"));
+}
@@ -0,0 +1,108 @@
+//! Enumerating, sampling, and applying selection combinations.
+
+use super::*;
+
+fn two_track_settings() -> PlaySettingsFile {
+    serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1, 2], "name": "A", "safe_name": "a",
+                     "shuffle_points": ["0:05"]},
+                    {"level": 1.0, "pan": 0.0, "ids": [3, 4, 5], "name": "B", "safe_name": "b",
+                     "weights": [1.0, 0.0, 1.0]}
+                ]
+            }
+        }"#,
+    )
+    .unwrap()
+}
+
+#[test]
+fn combinations_enumerate_every_counted_selection_once() {
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(two_track_settings());
+
+    let combinations = prot.combinations().unwrap();
+    assert_eq!(Some(combinations.len()), prot.count_possible_combinations());
+    assert_eq!(combinations.len(), 12);
+
+    let all: Vec<Combination> = combinations.collect();
+    assert_eq!(all.len(), 12);
+    let distinct: std::collections::HashSet<String> = all
+        .iter()
+        .map(|combination| format!("{:?}", combination.schedule))
+        .collect();
+    assert_eq!(distinct.len(), 12);
+    for (index, combination) in all.iter().enumerate() {
+        assert_eq!(combination.index, index as u128);
+        let times: Vec<f64> = combination.schedule.iter().map(|(at, _)| *at).collect();
+        assert_eq!(times, vec![0.0, 5.0]);
+        assert_eq!(combination.schedule[0].1.len(), 2);
+        // Track B has no shuffle points, so it keeps its first choice.
+        assert_eq!(combination.schedule[0].1[1], combination.schedule[1].1[1]);
+    }
+}
+
+#[test]
+fn sampled_combinations_are_distinct_and_reproducible() {
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(two_track_settings());
+    let combinations = prot.combinations().unwrap();
+
+    let indices = |count, seed| -> Vec<u128> {
+        combinations
+            .sample(count, seed)
+            .map(|combination| combination.index)
+            .collect()
+    };
+    let sampled = indices(5, 3);
+    assert_eq!(sampled.len(), 5);
+    assert_eq!(sampled, indices(5, 3));
+    let unique: std::collections::HashSet<u128> = sampled.iter().copied().collect();
+    assert_eq!(unique.len(), 5);
+
+    let mut everything = indices(100, 9);
+    everything.sort_unstable();
+    assert_eq!(everything, (0..12).collect::<Vec<u128>>());
+}
+
+#[test]
+fn applying_a_combination_fixes_the_schedule() {
+    let mut prot = prot_from_container("demo.prot");
+    prot.info.duration_map = (1..=5).map(|id| (id, 10.0)).collect();
+    prot.play_settings = Some(two_track_settings());
+    let combination = prot.combinations().unwrap().get(7).unwrap();
+
+    prot.apply_combination(&combination).unwrap();
+    let applied: Vec<Vec<String>> = prot
+        .get_shuffle_schedule()
+        .into_iter()
+        .map(|(_, tracks)| tracks.concat())
+        .collect();
+    let expected: Vec<Vec<String>> = combination
+        .schedule
+        .iter()
+        .map(|(_, sources)| sources.clone())
+        .collect();
+    assert_eq!(applied, expected);
+
+    let foreign = Combination {
+        index: 0,
+        schedule: vec![(0.0, vec!["1".to_string(), "99".to_string()])],
+    };
+    assert!(matches!(
+        prot.apply_combination(&foreign),
+        Err(ProtError::UnknownSource(source)) if source == "99"
+    ));
+}
+
+#[test]
+fn containers_without_play_settings_have_one_combination() {
+    let mut prot = prot_from_container("song.wav");
+    prot.info.duration_map = HashMap::from([(3, 1.0), (1, 1.0)]);
+    let all: Vec<Combination> = prot.combinations().unwrap().collect();
+    assert_eq!(all.len(), 1);
+    assert_eq!(all[0].schedule, vec![(0.0, vec!["1".to_string()])]);
+}
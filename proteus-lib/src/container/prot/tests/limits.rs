@@ -0,0 +1,92 @@
+//! Loading single files and enforcing [`ParseLimits`].
+
+use super::*;
+use crate::container::attachments::AttachmentError;
+
+#[test]
+fn single_audio_files_load_as_one_track_containers() {
+    let audio = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../test_audio");
+    for name in [
+        "test-16bit.wav",
+        "test-24bit.flac",
+        "test-32bit.mp3",
+        "test-32bit.ogg",
+    ] {
+        let path = audio.join(name).display().to_string();
+        let prot = Prot::try_new_with_mode(&path, ParseMode::Strict)
+            .unwrap_or_else(|err| panic!("{name} should load: {err}"));
+        assert_eq!(prot.get_shuffle_schedule().len(), 1, "{name}");
+        assert_eq!(prot.track_ids.as_ref().map(Vec::len), Some(1), "{name}");
+        assert!(*prot.get_duration() > 0.0, "{name}");
+
+        let rendered = crate::playback::render::render_selection_to_pcm(&prot, 1, 0.25);
+        assert!(
+            rendered.samples.iter().any(|sample| sample.abs() > 1.0e-4),
+            "{name} should render audio"
+        );
+    }
+}
+
+#[test]
+fn non_audio_files_fail_to_load_without_panicking() {
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+    assert!(Prot::try_new(&path.display().to_string()).is_err());
+}
+
+#[test]
+fn parse_limits_refuse_oversized_containers_in_either_mode() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_audio/demo_shuffle_points.prot"
+    );
+    let defaults = ParseLimits::default();
+    assert!(Prot::try_new_with_limits(path, ParseMode::Strict, &defaults).is_ok());
+
+    let cases = [
+        ParseLimits {
+            max_tracks: 1,
+            ..defaults
+        },
+        ParseLimits {
+            max_attachment_bytes: 16,
+            ..defaults
+        },
+        ParseLimits {
+            max_play_settings_bytes: 16,
+            ..defaults
+        },
+        ParseLimits {
+            max_shuffle_points: 0,
+            ..defaults
+        },
+    ];
+    for limits in cases {
+        for mode in [ParseMode::Lenient, ParseMode::Strict] {
+            assert!(
+                matches!(
+                    Prot::try_new_with_limits(path, mode, &limits),
+                    Err(ProtError::LimitExceeded(_))
+                ),
+                "{limits:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn later_attachment_reads_keep_the_load_limits() {
+    let path = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/../test_audio/demo_shuffle_points.prot"
+    );
+    let mut prot =
+        Prot::try_new_with_limits(path, ParseMode::Lenient, &ParseLimits::default()).unwrap();
+    assert_eq!(prot.parse_limits(), ParseLimits::default());
+    assert!(prot.get_effect_presets().is_ok());
+
+    prot.limits.max_attachment_bytes = 16;
+    assert!(matches!(
+        prot.get_effect_presets(),
+        Err(AttachmentError::TooLarge { .. })
+    ));
+}
@@ -0,0 +1,440 @@
+use std::collections::HashMap;
+
+use super::*;
+use crate::container::info::Info;
+use crate::container::play_settings::SettingsTrack;
+
+mod combinations;
+mod limits;
+
+fn test_info() -> Info {
+    Info {
+        file_paths: Vec::new(),
+        duration_map: HashMap::new(),
+        channels: 2,
+        sample_rate: 48_000,
+        bits_per_sample: 16,
+    }
+}
+
+fn prot_from_container(file_path: &str) -> Prot {
+    Prot {
+        info: test_info(),
+        source: ProtSource::Container {
+            file_path: file_path.to_string(),
+        },
+        track_ids: None,
+        track_paths: None,
+        duration: 0.0,
+        shuffle_schedule: Vec::new(),
+        play_settings: None,
+        impulse_response_spec: None,
+        impulse_response_tail_db: None,
+        effects: None,
+        variables: Default::default(),
+        replaygain: None,
+        missing_track_policy: Default::default(),
+        group_levels: Default::default(),
+        beat_grid: None,
+        limits: crate::container::limits::ParseLimits::unlimited(),
+        track_keys: std::sync::Arc::default(),
+        download: None,
+    }
+}
+
+#[test]
+fn build_runtime_instance_plan_keeps_duplicate_instances() {
+    let mut prot = prot_from_container("demo.prot");
+    prot.shuffle_schedule = vec![
+        ShuffleScheduleEntry {
+            at_ms: 0,
+            sources: vec![
+                ShuffleSource::TrackId(1),
+                ShuffleSource::TrackId(2),
+                ShuffleSource::TrackId(3),
+            ],
+        },
+        ShuffleScheduleEntry {
+            at_ms: 14_604,
+            sources: vec![
+                ShuffleSource::TrackId(2),
+                ShuffleSource::TrackId(2),
+                ShuffleSource::TrackId(2),
+            ],
+        },
+    ];
+    prot.play_settings = Some(PlaySettingsFile::V1(
+        crate::container::play_settings::PlaySettingsV1File {
+            settings: crate::container::play_settings::PlaySettingsContainer::Flat(
+                crate::container::play_settings::PlaySettingsV1 {
+                    effects: Vec::new(),
+                    tempo_bpm: None,
+                    selection_rules: Default::default(),
+                    sections: Vec::new(),
+                    effect_automation: Vec::new(),
+                    loudness: None,
+                    groups: Vec::new(),
+                    pan_law: None,
+                    tracks: vec![
+                        SettingsTrack {
+                            level: 1.0,
+                            pan: 0.0,
+                            ids: vec![1, 2],
+                            name: "A".to_string(),
+                            safe_name: "a".to_string(),
+                            selections_count: 2,
+                            shuffle_points: vec!["0:14.604".to_string()],
+                            weights: Vec::new(),
+                            selection_mode: Default::default(),
+                            transitions: Vec::new(),
+                            shuffle_when: Vec::new(),
+                            candidate_when: Vec::new(),
+                            candidate_labels: Vec::new(),
+                            candidate_trims: Vec::new(),
+                            candidate_analysis: Vec::new(),
+                            start_offset_ms: 0,
+                            looping: None,
+                            group: None,
+                            #[cfg(feature = "hrtf")]
+                            binaural: None,
+                        },
+                        SettingsTrack {
+                            level: 1.0,
+                            pan: 0.0,
+                            ids: vec![2, 3],
+                            name: "B".to_string(),
+                            safe_name: "b".to_string(),
+                            selections_count: 1,
+                            shuffle_points: vec!["0:14.604".to_string()],
+                            weights: Vec::new(),
+                            selection_mode: Default::default(),
+                            transitions: Vec::new(),
+                            shuffle_when: Vec::new(),
+                            candidate_when: Vec::new(),
+                            candidate_labels: Vec::new(),
+                            candidate_trims: Vec::new(),
+                            candidate_analysis: Vec::new(),
+                            start_offset_ms: 0,
+                            looping: None,
+                            group: None,
+                            #[cfg(feature = "hrtf")]
+                            binaural: None,
+                        },
+                    ],
+                },
+            ),
+        },
+    ));
+
+    let plan = prot.build_runtime_instance_plan(0.0);
+    assert_eq!(plan.logical_track_count, 2);
+    assert_eq!(plan.instances.len(), 5);
+
+    let logical0 = plan
+        .instances
+        .iter()
+        .filter(|instance| instance.logical_track_index == 0)
+        .count();
+    let logical1 = plan
+        .instances
+        .iter()
+        .filter(|instance| instance.logical_track_index == 1)
+        .count();
+    assert_eq!(logical0, 3);
+    assert_eq!(logical1, 2);
+}
+
+#[test]
+fn build_runtime_instance_plan_clips_windows_to_start_time() {
+    let mut prot = prot_from_container("demo.prot");
+    prot.track_ids = Some(vec![1]);
+    prot.shuffle_schedule = vec![
+        ShuffleScheduleEntry {
+            at_ms: 0,
+            sources: vec![ShuffleSource::TrackId(1)],
+        },
+        ShuffleScheduleEntry {
+            at_ms: 10_000,
+            sources: vec![ShuffleSource::TrackId(2)],
+        },
+    ];
+
+    let plan = prot.build_runtime_instance_plan(5.0);
+    assert_eq!(plan.event_boundaries_ms, vec![5_000]);
+    assert_eq!(plan.instances.len(), 2);
+    assert_eq!(plan.instances[0].active_windows[0].start_ms, 0);
+    assert_eq!(plan.instances[0].active_windows[0].end_ms, Some(5_000));
+    assert_eq!(plan.instances[1].active_windows[0].start_ms, 5_000);
+}
+
+#[test]
+fn refresh_tracks_with_seed_is_reproducible() {
+    let file_paths: Vec<String> = (0..16).map(|i| format!("take_{i}.wav")).collect();
+    let mut track = PathsTrack::new_from_file_paths(file_paths.clone());
+    track.selections_count = 2;
+    track.shuffle_points = vec!["0:10".to_string(), "0:20".to_string()];
+    let mut prot = prot_from_container("demo.prot");
+    prot.source = ProtSource::Paths {
+        file_paths: vec![track],
+        file_paths_dictionary: file_paths,
+    };
+
+    prot.refresh_tracks_with_seed(7);
+    let first = prot.get_shuffle_schedule();
+    prot.refresh_tracks_with_seed(7);
+    assert_eq!(prot.get_shuffle_schedule(), first);
+    assert_eq!(first.len(), 3);
+}
+
+#[test]
+fn refresh_tracks_honors_v4_selection_rules_at_every_shuffle_point() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1, 2, 3], "name": "A", "safe_name": "a",
+                     "shuffle_points": ["0:05", "0:10", "0:15"]},
+                    {"level": 1.0, "pan": 0.0, "ids": [4, 5, 6], "name": "B", "safe_name": "b",
+                     "shuffle_points": ["0:10"], "weights": [1.0, 1.0, 4.0]}
+                ],
+                "selection_rules": {
+                    "groups": [{"name": "leads", "ids": [1, 4], "exactly": 1}],
+                    "never_together": [[3, 6]]
+                }
+            }
+        }"#,
+    )
+    .unwrap();
+    let rules = play_settings
+        .versioned_payload()
+        .unwrap()
+        .selection_rules
+        .clone();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    for seed in 0..20 {
+        prot.refresh_tracks_with_seed(seed);
+        assert_eq!(prot.shuffle_schedule.len(), 4);
+        for entry in &prot.shuffle_schedule {
+            let ids = sources_to_track_ids(&entry.sources);
+            assert_eq!(rules.violations(&ids), 0, "seed {seed}: {ids:?}");
+        }
+    }
+}
+
+#[test]
+fn refresh_tracks_follows_markov_transitions_across_shuffle_points() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1, 2, 3], "name": "A", "safe_name": "a",
+                     "shuffle_points": ["0:05", "0:10", "0:15"],
+                     "selection_mode": "markov",
+                     "transitions": [[0, 1, 0], [0, 0, 1], [1, 0, 0]]}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    for seed in 0..10 {
+        prot.refresh_tracks_with_seed(seed);
+        let ids: Vec<u32> = prot
+            .shuffle_schedule
+            .iter()
+            .map(|entry| sources_to_track_ids(&entry.sources)[0])
+            .collect();
+        for pair in ids.windows(2) {
+            assert_eq!(pair[1], pair[0] % 3 + 1, "seed {seed}: {ids:?}");
+        }
+    }
+}
+
+#[test]
+fn runtime_variables_gate_shuffle_points_and_reschedule_only_the_future() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1, 2, 3], "name": "A", "safe_name": "a",
+                     "shuffle_points": ["0:05", "0:10", "0:15"],
+                     "shuffle_when": [{"variable": "intensity", "min": 0.5}],
+                     "candidate_when": [{"id": 3, "when": [{"variable": "intensity", "min": 0.5}]}]}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    prot.refresh_tracks_with_seed(7);
+    let first = sources_to_track_ids(&prot.shuffle_schedule[0].sources);
+    assert_ne!(first, vec![3]);
+    for entry in &prot.shuffle_schedule {
+        assert_eq!(sources_to_track_ids(&entry.sources), first);
+    }
+
+    assert!(!prot.set_variable("tempo", 1.0));
+    assert!(prot.set_variable("intensity", 0.9));
+    let mut changed = false;
+    for _ in 0..20 {
+        assert!(prot.reschedule_after(7.0));
+        assert_eq!(prot.shuffle_schedule.len(), 4);
+        for entry in &prot.shuffle_schedule[..2] {
+            assert_eq!(sources_to_track_ids(&entry.sources), first);
+        }
+        changed |= prot.shuffle_schedule[2..]
+            .iter()
+            .any(|entry| sources_to_track_ids(&entry.sources) != first);
+    }
+    assert!(changed);
+}
+
+#[test]
+fn get_sections_parses_timestamps_and_skips_invalid_ranges() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [],
+                "sections": [
+                    {"name": "intro", "start": "0", "end": "0:08"},
+                    {"name": "chorus", "start": "0:08", "end": "0:16.5"},
+                    {"name": "broken", "start": "0:20", "end": "0:10"}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    let sections = prot.get_sections();
+    assert_eq!(sections.len(), 2);
+    assert_eq!(sections[1].name, "chorus");
+    assert_eq!(sections[1].start_seconds, 8.0);
+    assert_eq!(sections[1].end_seconds, 16.5);
+    assert!(sections[0].contains(0.0));
+    assert!(!sections[0].contains(8.0));
+}
+
+#[test]
+fn get_effect_automation_sorts_by_time_and_skips_invalid_timestamps() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [],
+                "effect_automation": [
+                    {"at": "2:00", "effect": 1, "parameter": "reverb_mix", "value": 0.4},
+                    {"at": "later", "effect": 0, "parameter": "gain", "value": 0.5},
+                    {"at": "0:30.5", "effect": 0, "parameter": "gain", "value": 0.8}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    let points = prot.get_effect_automation();
+    assert_eq!(points.len(), 2);
+    assert_eq!(points[0].at_seconds, 30.5);
+    assert_eq!(points[0].parameter, "gain");
+    assert_eq!(points[1].at_seconds, 120.0);
+    assert_eq!(points[1].effect_index, 1);
+    assert_eq!(points[1].value, 0.4);
+}
+
+#[test]
+fn get_track_info_reports_names_mix_and_active_selection() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 0.5, "pan": -0.25, "ids": [1, 2, 3], "name": "Drums", "safe_name": "drums",
+                     "candidate_labels": [{"id": 3, "label": "Take 3", "artwork": "drums3.png"}]},
+                    {"level": 1.0, "pan": 0.0, "ids": [], "name": "Empty", "safe_name": "empty"},
+                    {"level": 0.8, "pan": 0.5, "ids": [4, 5], "name": "Bass", "safe_name": "bass"}
+                ]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+    prot.shuffle_schedule = vec![
+        ShuffleScheduleEntry {
+            at_ms: 0,
+            sources: vec![ShuffleSource::TrackId(1), ShuffleSource::TrackId(4)],
+        },
+        ShuffleScheduleEntry {
+            at_ms: 10_000,
+            sources: vec![ShuffleSource::TrackId(3), ShuffleSource::TrackId(5)],
+        },
+    ];
+    assert!(prot.set_slot_mix_settings(0, 0.2, 0.1));
+
+    let info = prot.get_track_info(4.0);
+    assert_eq!(info.len(), 2);
+    assert_eq!(info[0].name, "Drums");
+    assert_eq!(info[0].safe_name, "drums");
+    assert_eq!(info[0].candidates, 3);
+    assert_eq!((info[0].level, info[0].pan), (0.2, 0.1));
+    assert_eq!(info[0].selections_count, 1);
+    assert_eq!(info[0].selection, vec!["1".to_string()]);
+    assert_eq!(info[1].name, "Bass");
+    assert_eq!((info[1].level, info[1].pan), (0.8, 0.5));
+    assert_eq!(info[1].selection, vec!["4".to_string()]);
+    assert_eq!(info[0].candidate_info[0].label, None);
+    assert_eq!(
+        info[0].candidate_info[2],
+        CandidateInfo {
+            source: "3".to_string(),
+            label: Some("Take 3".to_string()),
+            artwork: Some("drums3.png".to_string()),
+        }
+    );
+
+    let later = prot.get_track_info(12.0);
+    assert_eq!(later[0].selection, vec!["3".to_string()]);
+    assert_eq!(later[1].selection, vec!["5".to_string()]);
+}
+
+#[test]
+fn group_levels_ride_every_member_track() {
+    let play_settings: PlaySettingsFile = serde_json::from_str(
+        r#"{
+            "encoder_version": "4",
+            "play_settings": {
+                "tracks": [
+                    {"level": 1.0, "pan": 0.0, "ids": [1], "name": "Kick", "safe_name": "kick", "group": "perc"},
+                    {"level": 1.0, "pan": 0.0, "ids": [2], "name": "Bass", "safe_name": "bass"},
+                    {"level": 1.0, "pan": 0.0, "ids": [3], "name": "Hats", "safe_name": "hats", "group": "perc"},
+                    {"level": 1.0, "pan": 0.0, "ids": [4], "name": "Pad", "safe_name": "pad", "group": "keys"}
+                ],
+                "groups": [{"name": "perc", "level": 0.5}]
+            }
+        }"#,
+    )
+    .unwrap();
+    let mut prot = prot_from_container("demo.prot");
+    prot.play_settings = Some(play_settings);
+
+    assert_eq!(prot.group_names(), vec!["perc", "keys"]);
+    assert_eq!(prot.track_group_gains(), vec![0.5, 1.0, 0.5, 1.0]);
+    assert!(prot.set_group_level("keys", 0.25));
+    assert!(prot.set_group_level("perc", 1.5));
+    assert!(!prot.set_group_level("strings", 0.0));
+    assert_eq!(prot.group_level("keys"), Some(0.25));
+    assert_eq!(prot.track_group_gains(), vec![1.5, 1.0, 1.5, 0.25]);
+}
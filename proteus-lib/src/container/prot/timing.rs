@@ -0,0 +1,167 @@
+//! Per-track start offsets and loops.
+//!
+//! A track with `start_offset_ms` plays its first sample that far into the
+//! timeline and is silent before. A looping track repeats a region of its
+//! source until the song ends. Both are copied onto runtime plan instances so
+//! decode workers can delay and repeat their sources, and both change how far
+//! a track reaches into the timeline: an offset pushes the end out, and a loop
+//! contributes a single pass up to its loop end rather than running forever.
+//! Candidate trims are copied the same way, per source: a trimmed candidate
+//! plays its source from the trim start and stops at the trim end.
+
+use crate::container::play_settings::{candidate_trim, CandidateTrim, LoopRegion};
+
+use super::helpers::build_slot_layout;
+use super::schedule::seconds_to_ms;
+use super::types::{InstanceLoop, RuntimeInstanceMeta, ShuffleSource, SourceTrim};
+use super::{versioned_tracks, Prot, ProtSource};
+
+/// Offset, loop, and candidate trims configured on one logical track.
+#[derive(Debug, Clone, Default)]
+struct TrackTiming {
+    start_offset_ms: u64,
+    looping: Option<LoopRegion>,
+    trims: Vec<CandidateTrim>,
+}
+
+impl TrackTiming {
+    /// Trim of `source`, or the whole source when it has none.
+    fn source_trim(&self, source: &ShuffleSource) -> SourceTrim {
+        let ShuffleSource::TrackId(id) = source else {
+            return SourceTrim::default();
+        };
+        candidate_trim(&self.trims, *id).map_or_else(SourceTrim::default, |trim| SourceTrim {
+            start_ms: trim.start_ms,
+            end_ms: trim.end_ms.filter(|end_ms| *end_ms > trim.start_ms),
+        })
+    }
+}
+
+impl Prot {
+    /// Start offset, in milliseconds, of each logical track.
+    ///
+    /// Parallel to the logical tracks of the runtime plan; legacy settings
+    /// have no offsets and report `0` for every track.
+    pub fn track_start_offsets_ms(&self) -> Vec<u64> {
+        self.track_timings()
+            .iter()
+            .map(|timing| timing.start_offset_ms)
+            .collect()
+    }
+
+    fn track_timings(&self) -> Vec<TrackTiming> {
+        if let ProtSource::Paths { file_paths, .. } = &self.source {
+            return file_paths
+                .iter()
+                .filter(|track| !track.file_paths.is_empty() && track.selections_count > 0)
+                .map(|track| TrackTiming {
+                    start_offset_ms: track.start_offset_ms,
+                    looping: track.looping.and_then(|looping| looping.region()),
+                    trims: Vec::new(),
+                })
+                .collect();
+        }
+        match self.play_settings.as_ref().and_then(versioned_tracks) {
+            Some(tracks) => tracks
+                .iter()
+                .filter(|track| !track.ids.is_empty() && track.selections_count > 0)
+                .map(|track| TrackTiming {
+                    start_offset_ms: track.start_offset_ms,
+                    looping: track.looping.and_then(|looping| looping.region()),
+                    trims: track.candidate_trims.clone(),
+                })
+                .collect(),
+            None => vec![TrackTiming::default(); self.logical_track_slot_spans().len()],
+        }
+    }
+
+    /// Copy each track's start offset and loop, and each source's trim, onto
+    /// its plan instances.
+    ///
+    /// `start_ms` is the run start, used to place the song end that loops
+    /// stop at.
+    pub(super) fn apply_track_timing(&self, instances: &mut [RuntimeInstanceMeta], start_ms: u64) {
+        let timings = self.track_timings();
+        // Round up so a loop never stops short of the song end.
+        let until_ms = ((self.duration.max(0.0) * 1000.0).ceil() as u64).saturating_sub(start_ms);
+        for instance in instances {
+            let timing = timings
+                .get(instance.logical_track_index)
+                .cloned()
+                .unwrap_or_default();
+            instance.start_offset_ms = timing.start_offset_ms;
+            instance.trim = timing.source_trim(&instance.source_key);
+            instance.looping = timing.looping.and_then(|region| {
+                let source_ms = seconds_to_ms(self.source_duration(&instance.source_key)?);
+                let end_ms = region.end_ms.unwrap_or(source_ms).min(source_ms);
+                (end_ms > region.start_ms && until_ms > 0).then_some(InstanceLoop {
+                    start_ms: region.start_ms,
+                    end_ms,
+                    crossfade_ms: region.crossfade_ms,
+                    until_ms,
+                })
+            });
+        }
+    }
+
+    /// Recompute `duration` from the scheduled sources when any track has an
+    /// offset, a loop, or candidate trims.
+    pub(super) fn apply_timeline_duration(&mut self) {
+        let timings = self.track_timings();
+        if timings.iter().all(|timing| {
+            timing.start_offset_ms == 0 && timing.looping.is_none() && timing.trims.is_empty()
+        }) {
+            return;
+        }
+        let slot_count = self
+            .shuffle_schedule
+            .iter()
+            .map(|entry| entry.sources.len())
+            .max()
+            .unwrap_or(0);
+        let (slot_layout, _) = build_slot_layout(slot_count, &self.logical_track_slot_spans());
+        let untimed = TrackTiming::default();
+        let mut end = 0.0_f64;
+        for entry in &self.shuffle_schedule {
+            for (slot_index, source) in entry.sources.iter().enumerate() {
+                let timing = slot_layout
+                    .get(slot_index)
+                    .and_then(|(track_index, _)| timings.get(*track_index))
+                    .unwrap_or(&untimed);
+                let Some(duration) = self.source_duration(source) else {
+                    continue;
+                };
+                let trim = timing.source_trim(source);
+                let source_end = match (timing.looping, trim.end_ms) {
+                    (Some(region), _) => region
+                        .end_ms
+                        .map_or(duration, |end_ms| duration.min(end_ms as f64 / 1000.0)),
+                    (None, Some(end_ms)) => duration.min(end_ms as f64 / 1000.0),
+                    (None, None) => duration,
+                };
+                let played = (source_end - trim.start_ms as f64 / 1000.0).max(0.0);
+                end = end.max(timing.start_offset_ms as f64 / 1000.0 + played);
+            }
+        }
+        if end > 0.0 {
+            self.duration = end;
+        }
+    }
+
+    fn source_duration(&self, source: &ShuffleSource) -> Option<f64> {
+        match (source, &self.source) {
+            (ShuffleSource::TrackId(id), _) => self.info.get_duration(*id),
+            (
+                ShuffleSource::FilePath(path),
+                ProtSource::Paths {
+                    file_paths_dictionary,
+                    ..
+                },
+            ) => file_paths_dictionary
+                .iter()
+                .position(|candidate| candidate == path)
+                .and_then(|index| self.info.get_duration(index as u32)),
+            (ShuffleSource::FilePath(_), ProtSource::Container { .. }) => None,
+        }
+    }
+}
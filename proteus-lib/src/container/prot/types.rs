@@ -1,12 +1,14 @@
 //! Shared types for the prot module.
 
+use crate::container::play_settings::{SelectionMode, TrackLoop};
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub(crate) enum ShuffleSource {
     TrackId(u32),
     FilePath(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct ShuffleScheduleEntry {
     pub at_ms: u64,
     pub sources: Vec<ShuffleSource>,
@@ -29,6 +31,35 @@ pub(crate) struct RuntimeInstanceMeta {
     pub active_windows: Vec<ActiveWindow>,
     pub selection_index: usize,
     pub occurrence_index: usize,
+    /// Start offset of the owning track; the source's first sample plays
+    /// this many milliseconds into the timeline.
+    pub start_offset_ms: u64,
+    /// Loop of the owning track, resolved against the source duration.
+    pub looping: Option<InstanceLoop>,
+    /// Source region holding the candidate's audio.
+    pub trim: SourceTrim,
+}
+
+/// Source region one candidate plays, from its `candidate_trims` entry.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct SourceTrim {
+    /// Source position, in milliseconds, that plays at the track start.
+    pub start_ms: u64,
+    /// Source position, in milliseconds, where decoding stops.
+    pub end_ms: Option<u64>,
+}
+
+/// Resolved loop for one instance's source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct InstanceLoop {
+    /// Loop start in source milliseconds.
+    pub start_ms: u64,
+    /// Loop end in source milliseconds.
+    pub end_ms: u64,
+    /// Crossfade length at each wrap.
+    pub crossfade_ms: u64,
+    /// Song end relative to the run start; looping stops there.
+    pub until_ms: u64,
 }
 
 /// Expanded runtime plan used by schedule-driven routing/mixing.
@@ -52,6 +83,16 @@ pub struct PathsTrack {
     pub selections_count: u32,
     /// Timestamps where this track is reshuffled.
     pub shuffle_points: Vec<String>,
+    /// How the next path is picked at each shuffle point.
+    pub selection_mode: SelectionMode,
+    /// Markov transition weights, parallel to `file_paths`.
+    pub transitions: Vec<Vec<f32>>,
+    /// Timeline position, in milliseconds, where this track's audio begins.
+    pub start_offset_ms: u64,
+    /// Repeat this track until the song ends.
+    pub looping: Option<TrackLoop>,
+    /// Track group whose gain rides this track.
+    pub group: Option<String>,
 }
 
 impl PathsTrack {
@@ -63,10 +104,78 @@ impl PathsTrack {
             pan: 0.0,
             selections_count: 1,
             shuffle_points: Vec::new(),
+            selection_mode: SelectionMode::Random,
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         }
     }
 }
 
+/// Named timeline region resolved from play_settings `sections`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineSection {
+    /// Section name.
+    pub name: String,
+    /// Start position in seconds.
+    pub start_seconds: f64,
+    /// End position in seconds (exclusive).
+    pub end_seconds: f64,
+}
+
+impl TimelineSection {
+    /// Return `true` when `seconds` falls inside this section.
+    pub fn contains(&self, seconds: f64) -> bool {
+        seconds >= self.start_seconds && seconds < self.end_seconds
+    }
+}
+
+/// Effect parameter change resolved from play_settings `effect_automation`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EffectAutomationPoint {
+    /// Timeline position in seconds.
+    pub at_seconds: f64,
+    /// Index of the target effect in the container's chain.
+    pub effect_index: usize,
+    /// Parameter name as written in play_settings.
+    pub parameter: String,
+    /// New parameter value.
+    pub value: f32,
+}
+
+/// Display metadata and current state for one logical track.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogicalTrackInfo {
+    /// Display name; `Track N` when the source does not declare one.
+    pub name: String,
+    /// Filesystem/identifier-safe name.
+    pub safe_name: String,
+    /// Number of candidate ids or paths the track picks from.
+    pub candidates: usize,
+    /// Current track gain scalar.
+    pub level: f32,
+    /// Current track pan position.
+    pub pan: f32,
+    /// Number of simultaneous selections.
+    pub selections_count: u32,
+    /// Ids or paths selected for this track at the queried time.
+    pub selection: Vec<String>,
+    /// Display metadata for every candidate, in declaration order.
+    pub candidate_info: Vec<CandidateInfo>,
+}
+
+/// Display metadata for one candidate id or path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CandidateInfo {
+    /// Candidate id or path, in the same form as schedule selections.
+    pub source: String,
+    /// Display label; the file stem for path candidates without one.
+    pub label: Option<String>,
+    /// Name of a container attachment holding artwork, if any.
+    pub artwork: Option<String>,
+}
+
 /// Slot identity within the schedule layout.
 pub(super) struct SlotPlacement {
     pub slot_index: usize,
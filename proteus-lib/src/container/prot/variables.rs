@@ -0,0 +1,73 @@
+//! Runtime variables that gate shuffle points and candidates.
+
+use crate::container::play_settings::RuntimeVariables;
+
+use super::helpers::sources_to_track_ids;
+use super::schedule::{
+    id_schedule_uses_variable, rebuild_id_shuffle_schedule_after, seconds_to_ms,
+};
+use super::selection::SelectionContext;
+use super::Prot;
+
+impl Prot {
+    /// Set a runtime variable read by `shuffle_when` and `candidate_when` conditions.
+    ///
+    /// Only stores the value; call [`Prot::reschedule_after`] (or refresh the
+    /// tracks) to apply it to the schedule. Returns `true` when any condition
+    /// in the current play settings reads `name`.
+    pub fn set_variable(&mut self, name: &str, value: f64) -> bool {
+        self.variables.insert(name.to_string(), value);
+        self.play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+            .is_some_and(|payload| id_schedule_uses_variable(&payload.tracks, name))
+    }
+
+    /// Return the current value of a runtime variable.
+    pub fn get_variable(&self, name: &str) -> Option<f64> {
+        self.variables.get(name).copied()
+    }
+
+    /// Return every runtime variable set so far.
+    pub fn variables(&self) -> &RuntimeVariables {
+        &self.variables
+    }
+
+    /// Redraw the shuffle schedule after `seconds` using the current variables.
+    ///
+    /// Entries at or before `seconds` are kept, so the selection that is
+    /// currently playing does not change; the next shuffle boundary and every
+    /// later one are drawn again. Returns `false` for sources without
+    /// versioned play settings, which have no conditions to apply.
+    pub fn reschedule_after(&mut self, seconds: f64) -> bool {
+        let Some(payload) = self
+            .play_settings
+            .as_ref()
+            .and_then(|play_settings| play_settings.versioned_payload())
+        else {
+            return false;
+        };
+        let rules = payload.effective_selection_rules();
+        let context = SelectionContext {
+            rules: &rules,
+            variables: &self.variables,
+            missing: self.missing_track_policy,
+        };
+        let (schedule, longest_duration) = rebuild_id_shuffle_schedule_after(
+            &self.shuffle_schedule,
+            seconds_to_ms(seconds),
+            &payload.tracks,
+            context,
+            &self.info,
+            &mut rand::thread_rng(),
+        );
+        self.shuffle_schedule = schedule;
+        self.duration = longest_duration;
+        self.track_ids = self
+            .shuffle_schedule
+            .first()
+            .map(|entry| sources_to_track_ids(&entry.sources));
+        self.apply_timeline_duration();
+        true
+    }
+}
@@ -1,11 +1,13 @@
 //! Play-settings loading and effect-derivation helpers for `Prot`.
 
 use log::{info, warn};
-use matroska::Matroska;
 
-use crate::container::play_settings::{self, PlaySettingsFile};
+use crate::container::attachments::{read_attachments_with_download, AttachmentError};
+use crate::container::limits::{LimitExceeded, ParseLimits};
+use crate::container::play_settings::{self, ParseMode, PlaySettingsError, PlaySettingsFile};
 use crate::dsp::effects::convolution_reverb::{parse_impulse_response_string, ImpulseResponseSpec};
 use crate::dsp::effects::{normalize_legacy_effect_aliases, AudioEffect};
+use crate::tools::progressive::ProgressiveFile;
 
 /// Runtime settings extracted from parsed play-settings payloads.
 #[derive(Debug, Clone, Default)]
@@ -21,23 +23,26 @@ pub(crate) struct ProtRuntimeSettings {
 /// Failure modes while loading `play_settings.json` from a container file.
 #[derive(Debug)]
 pub(crate) enum PlaySettingsLoadError {
-    /// Failed to open the container file for reading.
-    OpenFile(std::io::Error),
-    /// Failed to parse the file as a Matroska container.
-    OpenMatroska(matroska::Error),
+    /// Failed to read attachments from the container file.
+    ReadAttachments(AttachmentError),
     /// Failed to deserialize the `play_settings.json` attachment as JSON.
     ParseJson(serde_json::Error),
     /// The `play_settings.json` attachment was not present in the container.
     MissingAttachment,
+    /// Strict decoding rejected the settings.
+    Invalid(PlaySettingsError),
+    /// The container exceeded a parsing limit.
+    LimitExceeded(LimitExceeded),
 }
 
 impl std::fmt::Display for PlaySettingsLoadError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::OpenFile(err) => write!(f, "failed to open container file: {}", err),
-            Self::OpenMatroska(err) => write!(f, "failed to read matroska container: {}", err),
+            Self::ReadAttachments(err) => write!(f, "failed to read container: {}", err),
             Self::ParseJson(err) => write!(f, "failed to parse play_settings.json: {}", err),
             Self::MissingAttachment => write!(f, "play_settings.json attachment not found"),
+            Self::Invalid(err) => write!(f, "invalid play_settings.json: {}", err),
+            Self::LimitExceeded(err) => write!(f, "container exceeds parsing limits: {}", err),
         }
     }
 }
@@ -45,20 +50,56 @@ impl std::fmt::Display for PlaySettingsLoadError {
 impl std::error::Error for PlaySettingsLoadError {}
 
 /// Fallible play-settings loader with typed error variants.
+///
+/// Attachment and payload sizes are checked against `limits` before they
+/// are read or decoded. While `download` runs, only the attachments that
+/// precede the clusters are searched.
 pub(crate) fn try_load_play_settings_from_container(
     file_path: &str,
+    download: Option<&ProgressiveFile>,
+    mode: ParseMode,
+    limits: &ParseLimits,
 ) -> Result<PlaySettingsFile, PlaySettingsLoadError> {
-    let file = std::fs::File::open(file_path).map_err(PlaySettingsLoadError::OpenFile)?;
-    let mka: Matroska = Matroska::open(file).map_err(PlaySettingsLoadError::OpenMatroska)?;
+    let attachments = read_attachments_with_download(
+        file_path,
+        download,
+        limits.max_attachment_bytes,
+    )
+    .map_err(|err| match err {
+        AttachmentError::TooLarge { size, limit } => {
+            PlaySettingsLoadError::LimitExceeded(LimitExceeded::AttachmentSize { size, limit })
+        }
+        err => PlaySettingsLoadError::ReadAttachments(err),
+    })?;
 
-    let attachment = mka
-        .attachments
+    let attachment = attachments
         .iter()
         .find(|attachment| attachment.name == "play_settings.json")
         .ok_or(PlaySettingsLoadError::MissingAttachment)?;
+    limits
+        .check_play_settings_size(attachment.data.len())
+        .map_err(PlaySettingsLoadError::LimitExceeded)?;
+
+    match mode {
+        ParseMode::Lenient => {
+            parse_play_settings(&attachment.data).map_err(PlaySettingsLoadError::ParseJson)
+        }
+        ParseMode::Strict => parse_play_settings_strict(&attachment.data),
+    }
+}
+
+/// Decode a raw `play_settings.json` payload.
+pub(crate) fn parse_play_settings(bytes: &[u8]) -> Result<PlaySettingsFile, serde_json::Error> {
+    serde_json::from_slice::<PlaySettingsFile>(bytes)
+}
 
-    serde_json::from_slice::<PlaySettingsFile>(&attachment.data)
-        .map_err(PlaySettingsLoadError::ParseJson)
+/// Decode a raw `play_settings.json` payload in strict mode.
+pub(crate) fn parse_play_settings_strict(
+    bytes: &[u8],
+) -> Result<PlaySettingsFile, PlaySettingsLoadError> {
+    let document: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(PlaySettingsLoadError::ParseJson)?;
+    play_settings::decode_strict(&document).map_err(PlaySettingsLoadError::Invalid)
 }
 
 /// Derive runtime effect state from a parsed play-settings file.
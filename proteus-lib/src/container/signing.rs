@@ -0,0 +1,329 @@
+//! Ed25519 signatures over container content.
+//!
+//! A signed container carries a [`SIGNATURE_ATTACHMENT_NAME`] attachment
+//! holding a 64-byte Ed25519 signature. The signed message is a SHA-256
+//! digest of the segment's tracks element (every track entry, including its
+//! codec settings and codec private data), then every audio packet (track
+//! id, timestamp, and data, in demux order), then every other attachment
+//! (name, MIME type, and data), which covers `play_settings.json`, impulse
+//! responses, and presets. Container-level layout such as cues or tags is
+//! not covered, so remuxing that keeps the track entries and content
+//! unchanged keeps a signature valid.
+//!
+//! Distributors sign with [`sign_container`], or embed
+//! [`signature_attachment`] when muxing themselves; players check with
+//! [`verify_signature`]. Several signatures may be present, for example one
+//! per distributor; verification succeeds when any matches the key.
+
+use std::fmt;
+use std::path::Path;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::container::attachments::{
+    append_attachment, read_attachments_from_path_with_limit, read_segment_elements,
+    AttachmentError, ContainerAttachment, ID_TRACKS,
+};
+use crate::container::info::get_probe_result_from_string;
+use crate::container::limits::ParseLimits;
+
+/// Name of the attachment holding a container signature.
+pub const SIGNATURE_ATTACHMENT_NAME: &str = "signature.ed25519";
+
+/// Domain separator so the digest cannot be confused with other SHA-256
+/// messages signed by the same key.
+const DIGEST_DOMAIN: &[u8] = b"proteus-container-signature-v1";
+
+/// Failure while signing or verifying a container.
+#[derive(Debug)]
+pub enum SignatureError {
+    /// The container's attachments could not be read or written.
+    Attachments(AttachmentError),
+    /// The container's audio could not be demuxed.
+    Decode(SymphoniaError),
+    /// The public key is not a valid Ed25519 point.
+    InvalidKey,
+    /// The container has no signature attachment.
+    Unsigned,
+    /// No signature matches the key, so the content or signer differs.
+    Mismatch,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Attachments(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "failed to read audio packets: {}", err),
+            Self::InvalidKey => write!(f, "invalid Ed25519 public key"),
+            Self::Unsigned => write!(f, "container is not signed"),
+            Self::Mismatch => write!(f, "signature does not match container content"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl From<AttachmentError> for SignatureError {
+    fn from(err: AttachmentError) -> Self {
+        Self::Attachments(err)
+    }
+}
+
+/// Generate a random Ed25519 secret key.
+pub fn generate_secret_key() -> [u8; 32] {
+    let mut secret_key = [0_u8; 32];
+    rand::thread_rng().fill_bytes(&mut secret_key);
+    secret_key
+}
+
+/// Public key matching `secret_key`, for distribution to players.
+pub fn public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    SigningKey::from_bytes(secret_key)
+        .verifying_key()
+        .to_bytes()
+}
+
+/// SHA-256 digest of the signed content of the container at `path`.
+///
+/// # Errors
+///
+/// Returns [`SignatureError`] when the container cannot be read or demuxed.
+pub fn content_digest(path: impl AsRef<Path>) -> Result<[u8; 32], SignatureError> {
+    content_digest_with_limits(path, &ParseLimits::unlimited())
+}
+
+/// [`content_digest`] refusing attachments larger than
+/// `limits.max_attachment_bytes`.
+///
+/// # Errors
+///
+/// Returns [`SignatureError::Attachments`] with
+/// [`AttachmentError::TooLarge`] for an oversized attachment, plus the
+/// errors of [`content_digest`].
+pub fn content_digest_with_limits(
+    path: impl AsRef<Path>,
+    limits: &ParseLimits,
+) -> Result<[u8; 32], SignatureError> {
+    let attachments = read_attachments_from_path_with_limit(&path, limits.max_attachment_bytes)?;
+    content_digest_with(path.as_ref(), &attachments, limits)
+}
+
+/// Build the signature attachment for the container at `path`.
+///
+/// Muxers that write their own attachments embed the result as is.
+///
+/// # Errors
+///
+/// Returns [`SignatureError`] when the container cannot be read or demuxed.
+pub fn signature_attachment(
+    path: impl AsRef<Path>,
+    secret_key: &[u8; 32],
+) -> Result<ContainerAttachment, SignatureError> {
+    let digest = content_digest(path)?;
+    let signature = SigningKey::from_bytes(secret_key).sign(&digest);
+    Ok(ContainerAttachment {
+        name: SIGNATURE_ATTACHMENT_NAME.to_string(),
+        mime_type: "application/octet-stream".to_string(),
+        data: signature.to_bytes().to_vec(),
+    })
+}
+
+/// Sign the container at `path` in place.
+///
+/// Signatures already present are kept and do not enter the digest, so
+/// signing again with another key adds a second signature.
+///
+/// # Errors
+///
+/// Returns [`SignatureError`] when the container cannot be read, demuxed, or
+/// appended to.
+pub fn sign_container(path: impl AsRef<Path>, secret_key: &[u8; 32]) -> Result<(), SignatureError> {
+    let attachment = signature_attachment(&path, secret_key)?;
+    append_attachment(path, &attachment)?;
+    Ok(())
+}
+
+/// Check that the container at `path` is signed by `public_key` and
+/// unchanged since.
+///
+/// # Errors
+///
+/// Returns [`SignatureError::Unsigned`] when there is no signature,
+/// [`SignatureError::Mismatch`] when none matches the key and content, and
+/// [`SignatureError::InvalidKey`] for a malformed key.
+pub fn verify_signature(
+    path: impl AsRef<Path>,
+    public_key: &[u8; 32],
+) -> Result<(), SignatureError> {
+    verify_signature_with_limits(path, public_key, &ParseLimits::unlimited())
+}
+
+/// [`verify_signature`] for containers from untrusted sources, refusing
+/// attachments larger than `limits.max_attachment_bytes`.
+///
+/// # Errors
+///
+/// Returns [`SignatureError::Attachments`] with
+/// [`AttachmentError::TooLarge`] for an oversized attachment, plus the
+/// errors of [`verify_signature`].
+pub fn verify_signature_with_limits(
+    path: impl AsRef<Path>,
+    public_key: &[u8; 32],
+    limits: &ParseLimits,
+) -> Result<(), SignatureError> {
+    let key = VerifyingKey::from_bytes(public_key).map_err(|_| SignatureError::InvalidKey)?;
+    let attachments = read_attachments_from_path_with_limit(&path, limits.max_attachment_bytes)?;
+    let signatures: Vec<Signature> = attachments
+        .iter()
+        .filter(|attachment| attachment.name == SIGNATURE_ATTACHMENT_NAME)
+        .filter_map(|attachment| Signature::from_slice(&attachment.data).ok())
+        .collect();
+    if signatures.is_empty() {
+        return Err(SignatureError::Unsigned);
+    }
+    let digest = content_digest_with(path.as_ref(), &attachments, limits)?;
+    if signatures
+        .iter()
+        .any(|signature| key.verify_strict(&digest, signature).is_ok())
+    {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+fn content_digest_with(
+    path: &Path,
+    attachments: &[ContainerAttachment],
+    limits: &ParseLimits,
+) -> Result<[u8; 32], SignatureError> {
+    let mut hasher = Sha256::new();
+    hasher.update(DIGEST_DOMAIN);
+
+    // Track entries decide how packets decode, so swapping a codec's private
+    // data or a track's channel layout must break the signature too.
+    let mut file =
+        std::io::BufReader::new(std::fs::File::open(path).map_err(AttachmentError::from)?);
+    for tracks in read_segment_elements(&mut file, ID_TRACKS, limits.max_attachment_bytes)? {
+        hash_field(&mut hasher, &tracks);
+    }
+
+    let path = path.to_string_lossy();
+    let mut probed = get_probe_result_from_string(&path).map_err(SignatureError::Decode)?;
+    loop {
+        match probed.format.next_packet() {
+            Ok(packet) => {
+                hasher.update(packet.track_id().to_le_bytes());
+                hasher.update(packet.ts().to_le_bytes());
+                hash_field(&mut hasher, &packet.data);
+            }
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(err) => return Err(SignatureError::Decode(err)),
+        }
+    }
+
+    for attachment in attachments
+        .iter()
+        .filter(|attachment| attachment.name != SIGNATURE_ATTACHMENT_NAME)
+    {
+        hash_field(&mut hasher, attachment.name.as_bytes());
+        hash_field(&mut hasher, attachment.mime_type.as_bytes());
+        hash_field(&mut hasher, &attachment.data);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// Hash a length-prefixed field, so field boundaries are part of the digest.
+fn hash_field(hasher: &mut Sha256, bytes: &[u8]) {
+    hasher.update((bytes.len() as u64).to_le_bytes());
+    hasher.update(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::demo_container_copy;
+
+    #[test]
+    fn signed_containers_verify_until_tampered_with() {
+        let path = demo_container_copy("signing", "signed");
+        let secret_key = generate_secret_key();
+        let public = public_key(&secret_key);
+        assert!(matches!(
+            verify_signature(&path, &public),
+            Err(SignatureError::Unsigned)
+        ));
+
+        sign_container(&path, &secret_key).unwrap();
+        verify_signature(&path, &public).unwrap();
+        assert!(matches!(
+            verify_signature(&path, &public_key(&generate_secret_key())),
+            Err(SignatureError::Mismatch)
+        ));
+        // The signed container still loads and plays.
+        let prot = crate::container::prot::Prot::try_new(&path).unwrap();
+        assert!(!prot.get_shuffle_schedule().is_empty());
+
+        // Change one byte of play_settings.json without breaking its layout.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let at = bytes
+            .windows(17)
+            .position(|window| window == b"\"encoder_version\"")
+            .unwrap();
+        bytes[at + 1] = b'E';
+        std::fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            verify_signature(&path, &public),
+            Err(SignatureError::Mismatch)
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn track_entries_are_covered_by_the_signature() {
+        let path = demo_container_copy("signing", "tracks");
+        let secret_key = generate_secret_key();
+        sign_container(&path, &secret_key).unwrap();
+
+        // Flip one bit of the first TrackUID, which leaves demuxing intact.
+        let mut bytes = std::fs::read(&path).unwrap();
+        let tracks = read_segment_elements(&mut std::io::Cursor::new(&bytes), ID_TRACKS, u64::MAX)
+            .unwrap()
+            .remove(0);
+        let start = bytes
+            .windows(tracks.len())
+            .position(|window| window == tracks)
+            .unwrap();
+        let uid = tracks
+            .windows(3)
+            .position(|window| window == [0x73, 0xC5, 0x88])
+            .unwrap();
+        bytes[start + uid + 10] ^= 1;
+        std::fs::write(&path, bytes).unwrap();
+        assert!(matches!(
+            verify_signature(&path, &public_key(&secret_key)),
+            Err(SignatureError::Mismatch)
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn second_signatures_leave_the_digest_unchanged() {
+        let path = demo_container_copy("signing", "cosigned");
+        let before = content_digest(&path).unwrap();
+        let (first, second) = (generate_secret_key(), generate_secret_key());
+        sign_container(&path, &first).unwrap();
+        sign_container(&path, &second).unwrap();
+        assert_eq!(content_digest(&path).unwrap(), before);
+        verify_signature(&path, &public_key(&first)).unwrap();
+        verify_signature(&path, &public_key(&second)).unwrap();
+        std::fs::remove_file(&path).unwrap();
+    }
+}
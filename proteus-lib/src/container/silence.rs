@@ -0,0 +1,338 @@
+//! Leading and trailing silence detection for candidate stems.
+//!
+//! Stems exported from a DAW sometimes carry silence the other stems do not,
+//! which throws them out of alignment. [`detect_candidate_trims`] finds where
+//! the audio of each track in a container actually starts and ends, and
+//! [`write_candidate_trims`] records the result as `candidate_trims` in
+//! `play_settings.json`, which the engine honours when it decodes the
+//! candidate.
+//!
+//! Levels are measured as the peak of 10 ms blocks across all channels. A
+//! stretch only counts as audio once it stays above the threshold for the
+//! hold time, so clicks and noise bursts in the silence are trimmed too.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::audio::decode::for_each_channel_sample;
+use crate::container::play_settings::{write_candidate_entries, CandidateTrim};
+use crate::tools::decode::{get_reader, DecoderOpenError};
+
+/// Blocks per second used for level measurement (10 ms blocks).
+const BLOCKS_PER_SECOND: u32 = 100;
+
+/// Threshold and hold used to tell audio from silence.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SilenceDetection {
+    /// Level in dBFS at or above which a block counts as audible.
+    pub threshold_dbfs: f32,
+    /// Minimum length, in milliseconds, audio must stay above the threshold.
+    pub hold_ms: u32,
+}
+
+impl Default for SilenceDetection {
+    fn default() -> Self {
+        Self {
+            threshold_dbfs: -60.0,
+            hold_ms: 30,
+        }
+    }
+}
+
+/// Error returned when a file cannot be scanned for silence.
+#[derive(Debug)]
+pub enum SilenceScanError {
+    /// The file could not be opened or has no decodable audio track.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the file.
+    Decode(SymphoniaError),
+}
+
+impl fmt::Display for SilenceScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SilenceScanError {}
+
+/// Block peak levels of one track.
+struct TrackLevels {
+    decoder: Box<dyn Decoder>,
+    sample_rate: u32,
+    block_frames: usize,
+    block_fill: usize,
+    block_peak: f32,
+    peaks: Vec<f32>,
+    frames: u64,
+}
+
+impl TrackLevels {
+    fn push_frame_peaks(&mut self, frame_peaks: &[f32]) {
+        for peak in frame_peaks {
+            self.block_peak = self.block_peak.max(*peak);
+            self.block_fill += 1;
+            if self.block_fill == self.block_frames {
+                self.peaks.push(self.block_peak);
+                self.block_fill = 0;
+                self.block_peak = 0.0;
+            }
+        }
+        self.frames += frame_peaks.len() as u64;
+    }
+
+    fn finish(mut self, detection: SilenceDetection) -> Option<(u64, Option<u64>)> {
+        if self.block_fill > 0 {
+            self.peaks.push(self.block_peak);
+        }
+        let threshold = 10.0_f32.powf(detection.threshold_dbfs / 20.0);
+        let hold_blocks = (detection.hold_ms as usize * BLOCKS_PER_SECOND as usize)
+            .div_ceil(1000)
+            .max(1);
+        let (start_block, end_block) = audible_span(&self.peaks, threshold, hold_blocks)?;
+
+        let rate = u64::from(self.sample_rate.max(1));
+        let block_frames = self.block_frames as u64;
+        let start_ms = start_block as u64 * block_frames * 1000 / rate;
+        let end_frame = end_block as u64 * block_frames;
+        let end_ms = (end_frame < self.frames).then(|| (end_frame * 1000).div_ceil(rate));
+        Some((start_ms, end_ms))
+    }
+}
+
+/// First and one-past-last block of the runs of audible blocks lasting at
+/// least `hold_blocks`, or `None` when there is no such run.
+fn audible_span(peaks: &[f32], threshold: f32, hold_blocks: usize) -> Option<(usize, usize)> {
+    let mut span: Option<(usize, usize)> = None;
+    let mut run_start = None;
+    for index in 0..=peaks.len() {
+        let audible = peaks.get(index).is_some_and(|peak| *peak >= threshold);
+        match (audible, run_start) {
+            (true, None) => run_start = Some(index),
+            (false, Some(start)) => {
+                if index - start >= hold_blocks {
+                    span = Some((span.map_or(start, |(first, _)| first), index));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    span
+}
+
+/// Scan every audio track of `file_path` and return a trim for each track
+/// with leading or trailing silence.
+///
+/// Trim ids are container track ids. Tracks that are silent throughout, or
+/// that start and end with audio, get no entry.
+///
+/// # Errors
+///
+/// Returns [`SilenceScanError`] when the file cannot be opened or decoded.
+pub fn detect_candidate_trims(
+    file_path: &str,
+    detection: SilenceDetection,
+) -> Result<Vec<CandidateTrim>, SilenceScanError> {
+    let mut format = get_reader(file_path).map_err(SilenceScanError::Open)?;
+    let mut tracks: HashMap<u32, TrackLevels> = HashMap::new();
+    for track in format.tracks() {
+        if track.codec_params.codec == CODEC_TYPE_NULL {
+            continue;
+        }
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| SilenceScanError::Open(DecoderOpenError::UnsupportedCodec(err)))?;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(48_000);
+        tracks.insert(
+            track.id,
+            TrackLevels {
+                decoder,
+                sample_rate,
+                block_frames: (sample_rate / BLOCKS_PER_SECOND).max(1) as usize,
+                block_fill: 0,
+                block_peak: 0.0,
+                peaks: Vec::new(),
+                frames: 0,
+            },
+        );
+    }
+    if tracks.is_empty() {
+        return Err(SilenceScanError::Open(
+            DecoderOpenError::NoSupportedAudioTrack,
+        ));
+    }
+
+    let mut frame_peaks: Vec<f32> = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(err) => return Err(SilenceScanError::Decode(err)),
+        };
+        let Some(levels) = tracks.get_mut(&packet.track_id()) else {
+            continue;
+        };
+        let decoded = levels
+            .decoder
+            .decode(&packet)
+            .map_err(SilenceScanError::Decode)?;
+        frame_peaks.clear();
+        frame_peaks.resize(decoded.frames(), 0.0);
+        for channel in 0..decoded.spec().channels.count() {
+            let mut frame = 0;
+            for_each_channel_sample(&decoded, channel, |sample| {
+                if let Some(peak) = frame_peaks.get_mut(frame) {
+                    *peak = peak.max(sample.abs());
+                }
+                frame += 1;
+            });
+        }
+        levels.push_frame_peaks(&frame_peaks);
+    }
+
+    let mut trims: Vec<CandidateTrim> = tracks
+        .into_iter()
+        .filter_map(|(id, levels)| {
+            let (start_ms, end_ms) = levels.finish(detection)?;
+            (start_ms > 0 || end_ms.is_some()).then_some(CandidateTrim {
+                id,
+                start_ms,
+                end_ms,
+            })
+        })
+        .collect();
+    trims.sort_by_key(|trim| trim.id);
+    Ok(trims)
+}
+
+/// Store `trims` as `candidate_trims` on the tracks of a parsed
+/// `play_settings.json` document.
+///
+/// Each track receives the trims for the ids it lists, replacing any it
+/// had; tracks with none lose the key. Settings nested under a
+/// `play_settings` key are updated in place. Returns `false` when the
+/// document has no `tracks` array.
+pub fn write_candidate_trims(
+    play_settings: &mut serde_json::Value,
+    trims: &[CandidateTrim],
+) -> bool {
+    write_candidate_entries(play_settings, "candidate_trims", trims, |trim| trim.id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::pcm_output::PcmFormat;
+    use crate::playback::render::RenderedPcm;
+
+    const RATE: u32 = 48_000;
+
+    fn write_stem(name: &str, segments: &[(f32, f64)]) -> std::path::PathBuf {
+        let mut samples = Vec::new();
+        for (amplitude, seconds) in segments {
+            let frames = (seconds * f64::from(RATE)) as usize;
+            for frame in 0..frames {
+                let value =
+                    amplitude * (frame as f32 * 440.0 * std::f32::consts::TAU / RATE as f32).sin();
+                samples.extend([value, value]);
+            }
+        }
+        let path = std::env::temp_dir().join(format!(
+            "proteus-silence-{}-{}.wav",
+            name,
+            std::process::id()
+        ));
+        RenderedPcm {
+            samples,
+            sample_rate: RATE,
+            channels: 2,
+        }
+        .write_wav(&path, PcmFormat::F32Le)
+        .unwrap();
+        path
+    }
+
+    fn detect(path: &std::path::Path) -> Vec<CandidateTrim> {
+        let trims = detect_candidate_trims(&path.to_string_lossy(), SilenceDetection::default());
+        let _ = std::fs::remove_file(path);
+        trims.unwrap()
+    }
+
+    #[test]
+    fn leading_and_trailing_silence_is_found() {
+        let trims = detect(&write_stem(
+            "padded",
+            &[(0.0, 0.25), (0.5, 0.5), (0.0, 0.25)],
+        ));
+        assert_eq!(trims.len(), 1);
+        assert_eq!(trims[0].start_ms, 250);
+        assert_eq!(trims[0].end_ms, Some(750));
+    }
+
+    #[test]
+    fn clicks_shorter_than_the_hold_are_trimmed() {
+        let trims = detect(&write_stem(
+            "click",
+            &[(0.0, 0.1), (0.5, 0.01), (0.0, 0.1), (0.5, 0.5)],
+        ));
+        assert_eq!(trims[0].start_ms, 210);
+        assert_eq!(trims[0].end_ms, None);
+    }
+
+    #[test]
+    fn tight_and_silent_stems_get_no_trim() {
+        assert!(detect(&write_stem("tight", &[(0.5, 0.5)])).is_empty());
+        assert!(detect(&write_stem("silent", &[(0.0, 0.5)])).is_empty());
+    }
+
+    #[test]
+    fn trims_are_written_to_the_tracks_listing_their_ids() {
+        let mut settings = serde_json::json!({
+            "play_settings": {
+                "tracks": [
+                    {"ids": [1, 2], "candidate_trims": [{"id": 9, "start_ms": 1}]},
+                    {"ids": [3]}
+                ]
+            }
+        });
+        let trims = [
+            CandidateTrim {
+                id: 2,
+                start_ms: 120,
+                end_ms: None,
+            },
+            CandidateTrim {
+                id: 3,
+                start_ms: 0,
+                end_ms: Some(9_000),
+            },
+        ];
+        assert!(write_candidate_trims(&mut settings, &trims));
+        let tracks = &settings["play_settings"]["tracks"];
+        assert_eq!(
+            tracks[0]["candidate_trims"],
+            serde_json::json!([{"id": 2, "start_ms": 120}])
+        );
+        assert_eq!(
+            tracks[1]["candidate_trims"],
+            serde_json::json!([{"id": 3, "start_ms": 0, "end_ms": 9000}])
+        );
+
+        assert!(write_candidate_trims(&mut settings, &[]));
+        assert!(settings["play_settings"]["tracks"][0]
+            .get("candidate_trims")
+            .is_none());
+        assert!(!write_candidate_trims(&mut serde_json::json!({}), &trims));
+    }
+}
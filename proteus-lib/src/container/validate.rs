@@ -0,0 +1,431 @@
+//! Structural validation of `.prot`/`.mka` containers.
+//!
+//! [`validate`] checks a container without building a player and collects
+//! every problem it finds into a [`ValidationReport`]: unreadable files,
+//! missing or unparseable `play_settings.json`, track ids that name no stream,
+//! suspicious attachment names, streams no decoder supports, and duration
+//! metadata that disagrees with the packets. Errors make playback fail or play
+//! the wrong audio; warnings flag containers that play but are likely broken.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::path::Path;
+
+use serde::Serialize;
+use symphonia::core::codecs::DecoderOptions;
+
+use crate::container::attachments::{read_attachments_from_path, ContainerAttachment};
+use crate::container::info::{
+    get_probe_result_from_string, try_get_durations, try_get_durations_by_scan,
+};
+use crate::container::play_settings::PlaySettingsFile;
+use crate::container::presets::PRESET_ATTACHMENT_PREFIX;
+use crate::container::prot_settings::{
+    parse_play_settings, parse_play_settings_strict, PlaySettingsLoadError,
+};
+
+/// Allowed difference between tagged and scanned durations, in seconds.
+const DURATION_TOLERANCE_S: f64 = 0.1;
+const PLAY_SETTINGS_NAME: &str = "play_settings.json";
+
+/// How serious a [`ValidationIssue`] is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidationSeverity {
+    /// Playback fails or plays the wrong audio.
+    Error,
+    /// Playback works but the container is likely damaged.
+    Warning,
+}
+
+/// One problem found in a container.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ValidationIssue {
+    /// The file could not be opened or probed as media.
+    Unreadable {
+        /// Probe or I/O failure.
+        reason: String,
+    },
+    /// The attachment list could not be read.
+    UnreadableAttachments {
+        /// Attachment parser failure.
+        reason: String,
+    },
+    /// The container has no `play_settings.json` attachment.
+    MissingPlaySettings,
+    /// `play_settings.json` is not valid settings JSON or has an unknown version.
+    InvalidPlaySettings {
+        /// Parse failure or unsupported version.
+        reason: String,
+    },
+    /// A settings track references a stream id the container does not have.
+    UnknownTrackId {
+        /// Name of the settings track, or its index for legacy settings.
+        track: String,
+        /// Referenced stream id.
+        track_id: u32,
+    },
+    /// An attachment name is empty, duplicated, or not a plain file name.
+    BadAttachmentName {
+        /// Attachment name as stored.
+        name: String,
+        /// Why the name is rejected.
+        reason: String,
+    },
+    /// No available decoder supports the stream's codec.
+    UnsupportedCodec {
+        /// Stream id.
+        track_id: u32,
+        /// Codec identifier reported by the demuxer.
+        codec: String,
+    },
+    /// Duration metadata disagrees with the last packet timestamp.
+    DurationMismatch {
+        /// Stream id.
+        track_id: u32,
+        /// Duration from tags or frame counts, in seconds.
+        metadata_s: f64,
+        /// Duration found by scanning packets, in seconds.
+        scanned_s: f64,
+    },
+}
+
+impl ValidationIssue {
+    /// Severity of this issue.
+    pub fn severity(&self) -> ValidationSeverity {
+        match self {
+            Self::BadAttachmentName { .. } | Self::DurationMismatch { .. } => {
+                ValidationSeverity::Warning
+            }
+            _ => ValidationSeverity::Error,
+        }
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable { reason } => write!(f, "unreadable container: {}", reason),
+            Self::UnreadableAttachments { reason } => {
+                write!(f, "unreadable attachments: {}", reason)
+            }
+            Self::MissingPlaySettings => write!(f, "missing {}", PLAY_SETTINGS_NAME),
+            Self::InvalidPlaySettings { reason } => {
+                write!(f, "invalid {}: {}", PLAY_SETTINGS_NAME, reason)
+            }
+            Self::UnknownTrackId { track, track_id } => write!(
+                f,
+                "track '{}' references missing stream id {}",
+                track, track_id
+            ),
+            Self::BadAttachmentName { name, reason } => {
+                write!(f, "attachment '{}': {}", name.escape_debug(), reason)
+            }
+            Self::UnsupportedCodec { track_id, codec } => {
+                write!(f, "stream {} uses unsupported codec {}", track_id, codec)
+            }
+            Self::DurationMismatch {
+                track_id,
+                metadata_s,
+                scanned_s,
+            } => write!(
+                f,
+                "stream {} duration is tagged {:.3}s but scans to {:.3}s",
+                track_id, metadata_s, scanned_s
+            ),
+        }
+    }
+}
+
+/// Every issue found in one container.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationReport {
+    /// Path that was validated.
+    pub path: String,
+    /// Issues in check order.
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    /// Return `true` when no issue is an error.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Issues with [`ValidationSeverity::Error`].
+    pub fn errors(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(ValidationSeverity::Error)
+    }
+
+    /// Issues with [`ValidationSeverity::Warning`].
+    pub fn warnings(&self) -> impl Iterator<Item = &ValidationIssue> {
+        self.with_severity(ValidationSeverity::Warning)
+    }
+
+    fn with_severity(
+        &self,
+        severity: ValidationSeverity,
+    ) -> impl Iterator<Item = &ValidationIssue> {
+        self.issues
+            .iter()
+            .filter(move |issue| issue.severity() == severity)
+    }
+}
+
+/// Validate the container at `path`.
+///
+/// Never panics on malformed input; every failure is reported as an issue.
+/// Durations are checked with a full packet scan, so this reads the whole file.
+pub fn validate(path: impl AsRef<Path>) -> ValidationReport {
+    let path = path.as_ref().display().to_string();
+    let mut issues = Vec::new();
+
+    let stream_ids = match check_streams(&path, &mut issues) {
+        Some(ids) => ids,
+        None => return ValidationReport { path, issues },
+    };
+    check_durations(&path, &mut issues);
+
+    match read_attachments_from_path(&path) {
+        Ok(attachments) => {
+            issues.extend(attachment_name_issues(&attachments));
+            match attachments
+                .iter()
+                .find(|attachment| attachment.name == PLAY_SETTINGS_NAME)
+            {
+                Some(attachment) => {
+                    issues.extend(play_settings_issues(&attachment.data, &stream_ids))
+                }
+                None => issues.push(ValidationIssue::MissingPlaySettings),
+            }
+        }
+        Err(err) => issues.push(ValidationIssue::UnreadableAttachments {
+            reason: err.to_string(),
+        }),
+    }
+
+    ValidationReport { path, issues }
+}
+
+// Probe the file and check every stream has a decoder; returns the stream ids.
+fn check_streams(path: &str, issues: &mut Vec<ValidationIssue>) -> Option<HashSet<u32>> {
+    let probed = match get_probe_result_from_string(path) {
+        Ok(probed) => probed,
+        Err(err) => {
+            issues.push(ValidationIssue::Unreadable {
+                reason: err.to_string(),
+            });
+            return None;
+        }
+    };
+    let codecs = symphonia::default::get_codecs();
+    let options = DecoderOptions::default();
+    let mut ids = HashSet::new();
+    for track in probed.format.tracks() {
+        ids.insert(track.id);
+        if codecs.make(&track.codec_params, &options).is_err() {
+            issues.push(ValidationIssue::UnsupportedCodec {
+                track_id: track.id,
+                codec: track.codec_params.codec.to_string(),
+            });
+        }
+    }
+    Some(ids)
+}
+
+fn check_durations(path: &str, issues: &mut Vec<ValidationIssue>) {
+    let (Ok(metadata), Ok(scanned)) = (try_get_durations(path), try_get_durations_by_scan(path))
+    else {
+        return;
+    };
+    issues.extend(duration_issues(&metadata, &scanned));
+}
+
+// Streams without duration metadata are skipped: playback scans those anyway.
+fn duration_issues(
+    metadata: &HashMap<u32, f64>,
+    scanned: &HashMap<u32, f64>,
+) -> Vec<ValidationIssue> {
+    let mut ids: Vec<u32> = metadata.keys().copied().collect();
+    ids.sort_unstable();
+    ids.into_iter()
+        .filter_map(|track_id| {
+            let metadata_s = metadata[&track_id];
+            let scanned_s = *scanned.get(&track_id)?;
+            (metadata_s > 0.0 && (metadata_s - scanned_s).abs() > DURATION_TOLERANCE_S).then_some(
+                ValidationIssue::DurationMismatch {
+                    track_id,
+                    metadata_s,
+                    scanned_s,
+                },
+            )
+        })
+        .collect()
+}
+
+fn attachment_name_issues(attachments: &[ContainerAttachment]) -> Vec<ValidationIssue> {
+    let mut seen = HashSet::new();
+    attachments
+        .iter()
+        .filter_map(|attachment| {
+            let name = &attachment.name;
+            // Effect presets live under a `presets/` folder by convention.
+            let file_name = name.strip_prefix(PRESET_ATTACHMENT_PREFIX).unwrap_or(name);
+            let reason = if file_name.trim().is_empty() {
+                "name is empty"
+            } else if file_name.contains(['/', '\\']) {
+                "name contains a path separator"
+            } else if name.chars().any(char::is_control) {
+                "name contains control characters"
+            } else if name != name.trim() {
+                "name has leading or trailing whitespace"
+            } else if !seen.insert(name.as_str()) {
+                "name is used by more than one attachment"
+            } else {
+                return None;
+            };
+            Some(ValidationIssue::BadAttachmentName {
+                name: name.clone(),
+                reason: reason.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn play_settings_issues(bytes: &[u8], stream_ids: &HashSet<u32>) -> Vec<ValidationIssue> {
+    let settings = match parse_play_settings(bytes) {
+        Ok(settings) => settings,
+        Err(err) => {
+            return vec![ValidationIssue::InvalidPlaySettings {
+                reason: err.to_string(),
+            }]
+        }
+    };
+    let referenced: Vec<(String, BTreeSet<u32>)> = match &settings {
+        PlaySettingsFile::Legacy(file) => file
+            .settings
+            .inner()
+            .tracks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, track)| {
+                let first = track.starting_index? + 1;
+                Some((index.to_string(), (first..first + track.length?).collect()))
+            })
+            .collect(),
+        PlaySettingsFile::Unknown { .. } => {
+            // Strict decoding explains what the lenient decoder gave up on.
+            let reason = match parse_play_settings_strict(bytes) {
+                Err(PlaySettingsLoadError::Invalid(err)) => err.to_string(),
+                Err(err) => err.to_string(),
+                Ok(_) => "settings match no supported encoder_version".to_string(),
+            };
+            return vec![ValidationIssue::InvalidPlaySettings { reason }];
+        }
+        _ => settings
+            .versioned_payload()
+            .map(|payload| {
+                payload
+                    .tracks
+                    .iter()
+                    .map(|track| (track.name.clone(), track.ids.iter().copied().collect()))
+                    .collect()
+            })
+            .unwrap_or_default(),
+    };
+    referenced
+        .into_iter()
+        .flat_map(|(track, ids)| {
+            ids.into_iter()
+                .filter(|id| !stream_ids.contains(id))
+                .map(move |track_id| ValidationIssue::UnknownTrackId {
+                    track: track.clone(),
+                    track_id,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(name: &str) -> ContainerAttachment {
+        ContainerAttachment {
+            name: name.to_string(),
+            mime_type: String::new(),
+            data: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn fixture_validates_and_missing_file_is_unreadable() {
+        let fixture = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio/demo_shuffle_points_effects.prot");
+        let report = validate(&fixture);
+        assert!(report.is_valid(), "{:?}", report.issues);
+
+        let report = validate("/nonexistent/missing.prot");
+        assert!(!report.is_valid());
+        assert!(matches!(
+            report.issues.as_slice(),
+            [ValidationIssue::Unreadable { .. }]
+        ));
+    }
+
+    #[test]
+    fn settings_attachments_and_durations_are_checked() {
+        let settings = br#"{"encoder_version": 2, "play_settings": {"tracks": [
+            {"level": 1.0, "pan": 0.0, "ids": [1, 7], "name": "Drums", "safe_name": "drums"}
+        ]}}"#;
+        let ids: HashSet<u32> = [1, 2].into_iter().collect();
+        assert_eq!(
+            play_settings_issues(settings, &ids),
+            vec![ValidationIssue::UnknownTrackId {
+                track: "Drums".to_string(),
+                track_id: 7,
+            }]
+        );
+        assert!(matches!(
+            play_settings_issues(b"{not json", &ids).as_slice(),
+            [ValidationIssue::InvalidPlaySettings { .. }]
+        ));
+        let broken = br#"{"encoder_version": 2, "tracks": [{"level": "x"}]}"#;
+        assert_eq!(
+            play_settings_issues(broken, &ids)[0].to_string(),
+            "invalid play_settings.json: tracks[0].level: \
+             invalid type: string \"x\", expected f32 (encoder_version 2)"
+        );
+
+        let names = [
+            "ir.wav",
+            "../ir.wav",
+            "",
+            "ir.wav",
+            "ok.json",
+            "presets/Warm.json",
+            "presets/a/b.json",
+        ]
+        .map(attachment);
+        let flagged: Vec<String> = attachment_name_issues(&names)
+            .into_iter()
+            .map(|issue| issue.to_string())
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                "attachment '../ir.wav': name contains a path separator",
+                "attachment '': name is empty",
+                "attachment 'ir.wav': name is used by more than one attachment",
+                "attachment 'presets/a/b.json': name contains a path separator",
+            ]
+        );
+
+        let metadata = HashMap::from([(1, 10.0), (2, 0.0), (3, 5.0)]);
+        let scanned = HashMap::from([(1, 12.0), (2, 4.0), (3, 5.05)]);
+        let issues = duration_issues(&metadata, &scanned);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity(), ValidationSeverity::Warning);
+    }
+}
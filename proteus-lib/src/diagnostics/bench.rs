@@ -1,8 +1,23 @@
-//! Synthetic DSP benchmarks for convolution performance.
+//! DSP benchmarks: synthetic convolution runs and real-container profiling.
+//!
+//! [`bench_convolver`] measures the convolver on random buffers and
+//! [`bench_convolution_paths`] compares its CPU and GPU paths, while
+//! [`bench_container`] renders an actual `.prot` through the playback
+//! decode/mix/effects pipeline offline and reports where the time went.
+//! [`kernels`] exposes per-effect processing for the Criterion benches.
+
+pub mod kernels;
+
+use std::time::{Duration, Instant};
 
 use rand::Rng;
 
+use crate::container::prot::{Prot, ProtError};
 use crate::dsp::effects::convolution_reverb::convolution::Convolver;
+use crate::playback::engine::{render_offline, DecodeThreading, PlaybackBufferSettings};
+
+/// Start buffer used for offline container benchmarks (matches the player default).
+const CONTAINER_BENCH_START_BUFFER_MS: f32 = 20.0;
 
 /// Configuration parameters for a convolution benchmark run.
 #[derive(Debug, Clone, Copy)]
@@ -25,8 +40,48 @@ pub struct DspBenchResult {
     pub ir_segments: usize,
 }
 
+/// Convolution path measured by [`bench_convolution_paths`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvolutionPath {
+    Cpu,
+    /// Whole frames on the GPU (feature `gpu`): FFTs and partition sums.
+    Gpu,
+}
+
 /// Run a benchmark for a single FFT size.
 pub fn bench_convolver(config: DspBenchConfig) -> DspBenchResult {
+    let (input, ir) = random_buffers(&config);
+    let mut convolver = Convolver::new(&ir, config.fft_size);
+    time_convolver(&mut convolver, &input, ir.len(), &config)
+}
+
+/// Run the same random input and IR through each available convolution path.
+///
+/// The CPU path is always measured. The GPU path is added when the `gpu`
+/// feature is enabled and an adapter could be opened; the first timed
+/// iteration includes uploading the IR spectra.
+pub fn bench_convolution_paths(config: DspBenchConfig) -> Vec<(ConvolutionPath, DspBenchResult)> {
+    let (input, ir) = random_buffers(&config);
+    let mut results = Vec::new();
+    let mut cpu = Convolver::new(&ir, config.fft_size);
+    results.push((
+        ConvolutionPath::Cpu,
+        time_convolver(&mut cpu, &input, ir.len(), &config),
+    ));
+
+    #[cfg(feature = "gpu")]
+    if crate::dsp::effects::convolution_reverb::gpu_available() {
+        let mut gpu = Convolver::new(&ir, config.fft_size);
+        gpu.set_gpu(true);
+        results.push((
+            ConvolutionPath::Gpu,
+            time_convolver(&mut gpu, &input, ir.len(), &config),
+        ));
+    }
+    results
+}
+
+fn random_buffers(config: &DspBenchConfig) -> (Vec<f32>, Vec<f32>) {
     let input_len = (config.sample_rate as f32 * config.input_seconds).max(1.0) as usize;
     let ir_len = (config.sample_rate as f32 * config.ir_seconds).max(1.0) as usize;
 
@@ -37,13 +92,21 @@ pub fn bench_convolver(config: DspBenchConfig) -> DspBenchResult {
     let ir: Vec<f32> = (0..ir_len)
         .map(|_| rng.gen_range(-1.0_f32..1.0_f32))
         .collect();
+    (input, ir)
+}
 
-    let mut convolver = Convolver::new(&ir, config.fft_size);
+fn time_convolver(
+    convolver: &mut Convolver,
+    input: &[f32],
+    ir_len: usize,
+    config: &DspBenchConfig,
+) -> DspBenchResult {
+    let input_len = input.len();
     let mut times: Vec<f64> = Vec::with_capacity(config.iterations.max(1));
 
     for _ in 0..config.iterations.max(1) {
         let start = std::time::Instant::now();
-        let _ = convolver.process(&input);
+        let _ = convolver.process(input);
         let elapsed = start.elapsed().as_secs_f64() * 1000.0;
         times.push(elapsed);
     }
@@ -82,6 +145,97 @@ pub fn bench_convolver_sweep(
     results
 }
 
+/// Time spent in one pipeline stage of a container benchmark.
+#[derive(Debug, Clone)]
+pub struct StageTiming {
+    pub name: String,
+    pub total_ms: f64,
+    pub rt_factor: f64,
+}
+
+/// Per-stage timing results from [`bench_container`].
+#[derive(Debug, Clone)]
+pub struct ContainerBenchResult {
+    pub audio_time_ms: f64,
+    pub wall_ms: f64,
+    pub rt_factor: f64,
+    /// Time the pipeline spent waiting on decode workers.
+    pub decode: StageTiming,
+    /// Packet routing and track mixing.
+    pub mix: StageTiming,
+    /// One entry per effect in the container's chain, in order.
+    pub effects: Vec<StageTiming>,
+}
+
+/// Render up to `seconds` of a real container offline and time each stage.
+///
+/// The container's own effect chain is used. Decoding runs on the regular
+/// decode worker threads, so `decode` measures time the mix stalled waiting
+/// for packets rather than total decoder CPU.
+///
+/// # Errors
+///
+/// Returns [`ProtError`] when the container cannot be loaded or has no
+/// decodable audio.
+pub fn bench_container(path: &str, seconds: f64) -> Result<ContainerBenchResult, ProtError> {
+    let prot = Prot::try_new(path)?;
+    if prot.info.sample_rate == 0 || prot.info.channels == 0 {
+        return Err(ProtError::Initialization(format!(
+            "no decodable audio in {}",
+            path
+        )));
+    }
+    let effects = prot.get_effects().unwrap_or_default();
+    let names: Vec<String> = effects
+        .iter()
+        .map(|effect| effect.display_name().to_string())
+        .collect();
+    let sample_rate = f64::from(prot.info.sample_rate);
+    let channels = prot.info.channels as usize;
+
+    let start = Instant::now();
+    let stats = render_offline(
+        prot,
+        effects,
+        PlaybackBufferSettings::new(CONTAINER_BENCH_START_BUFFER_MS),
+        0.0,
+        seconds,
+        DecodeThreading::Workers.into(),
+        |_| {},
+    );
+    let wall_ms = start.elapsed().as_secs_f64() * 1000.0;
+    let audio_time_ms = (stats.rendered_samples / channels) as f64 / sample_rate * 1000.0;
+    let stage = |name: &str, elapsed: Duration| {
+        let total_ms = elapsed.as_secs_f64() * 1000.0;
+        StageTiming {
+            name: name.to_string(),
+            total_ms,
+            rt_factor: rt_factor(total_ms, audio_time_ms),
+        }
+    };
+
+    Ok(ContainerBenchResult {
+        audio_time_ms,
+        wall_ms,
+        rt_factor: rt_factor(wall_ms, audio_time_ms),
+        decode: stage("decode", stats.timings.decode),
+        mix: stage("mix", stats.timings.mix),
+        effects: names
+            .iter()
+            .zip(stats.timings.effects)
+            .map(|(name, elapsed)| stage(name, elapsed))
+            .collect(),
+    })
+}
+
+fn rt_factor(elapsed_ms: f64, audio_time_ms: f64) -> f64 {
+    if audio_time_ms > 0.0 {
+        elapsed_ms / audio_time_ms
+    } else {
+        0.0
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,4 +273,40 @@ mod tests {
         assert_eq!(sweep[1].0, 128);
         assert_eq!(sweep[2].0, 256);
     }
+
+    #[test]
+    fn bench_convolution_paths_always_measures_the_cpu() {
+        let config = DspBenchConfig {
+            sample_rate: 48_000,
+            input_seconds: 0.01,
+            ir_seconds: 0.05,
+            fft_size: 256,
+            iterations: 1,
+        };
+
+        let results = bench_convolution_paths(config);
+        assert_eq!(results[0].0, ConvolutionPath::Cpu);
+        assert!(results.len() <= 2);
+        for (_, result) in &results {
+            assert!(result.avg_ms >= 0.0);
+            assert_eq!(result.ir_segments, results[0].1.ir_segments);
+        }
+    }
+
+    #[test]
+    fn bench_container_reports_stage_timings_for_fixture() {
+        let fixture = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio/demo_shuffle_points_effects.prot");
+        let result = bench_container(&fixture.display().to_string(), 0.5).unwrap();
+        assert!((result.audio_time_ms - 500.0).abs() < 1.0);
+        assert!(result.wall_ms > 0.0);
+        assert_eq!(result.decode.name, "decode");
+        assert!(result.mix.total_ms > 0.0);
+        assert!(!result.effects.is_empty());
+    }
+
+    #[test]
+    fn bench_container_rejects_missing_file() {
+        assert!(bench_container("/nonexistent/missing.prot", 0.1).is_err());
+    }
 }
@@ -0,0 +1,124 @@
+//! Effect processing kernels for Criterion benchmarks.
+//!
+//! Each kernel is a plain function over caller-owned state and buffers so the
+//! benches in `proteus-lib/benches` measure only the DSP work: fixtures are
+//! built once up front and the timed closure calls one kernel per iteration.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::dsp::effects::convolution_reverb::convolution::Convolver;
+use crate::dsp::effects::{
+    AudioEffect, CompressorEffect, EffectContext, EqPointSettings, MultibandEqEffect,
+    MultibandEqSettings,
+};
+
+/// Sample rate used by every benchmark fixture.
+pub const BENCH_SAMPLE_RATE: u32 = 48_000;
+/// Interleaved channel count used by every benchmark fixture.
+pub const BENCH_CHANNELS: usize = 2;
+
+const SIGNAL_SEED: u64 = 0x5eed_0001;
+const EQ_LOWEST_HZ: f32 = 40.0;
+const EQ_HIGHEST_HZ: f32 = 16_000.0;
+const EQ_BAND_Q: f32 = 1.0;
+const EQ_BAND_GAIN_DB: f32 = 3.0;
+
+/// Deterministic noise buffer of `frames` interleaved stereo frames.
+pub fn signal(frames: usize) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(SIGNAL_SEED);
+    (0..frames * BENCH_CHANNELS)
+        .map(|_| rng.gen_range(-0.5_f32..0.5_f32))
+        .collect()
+}
+
+/// Effect context matching the benchmark fixtures.
+pub fn context() -> EffectContext {
+    EffectContext::new(BENCH_SAMPLE_RATE, BENCH_CHANNELS, None, None, -60.0)
+        .expect("benchmark sample rate and channels are valid")
+}
+
+/// Convolver over a deterministic impulse response of `ir_seconds`.
+pub fn convolver(ir_seconds: f32, fft_size: usize) -> Convolver {
+    let ir_len = (BENCH_SAMPLE_RATE as f32 * ir_seconds).max(1.0) as usize;
+    let mut rng = StdRng::seed_from_u64(SIGNAL_SEED ^ ir_len as u64);
+    let ir: Vec<f32> = (0..ir_len)
+        .map(|i| rng.gen_range(-1.0_f32..1.0_f32) * (-(i as f32) / ir_len as f32 * 6.0).exp())
+        .collect();
+    Convolver::new(&ir, fft_size)
+}
+
+/// Convolve one mono block.
+pub fn convolve(convolver: &mut Convolver, input: &[f32]) -> Vec<f32> {
+    convolver.process(input)
+}
+
+/// Enabled multiband EQ with `bands` peaking bands spread log-evenly across the spectrum.
+pub fn multiband_eq(bands: usize) -> AudioEffect {
+    let ratio = EQ_HIGHEST_HZ / EQ_LOWEST_HZ;
+    let points = (0..bands)
+        .map(|band| {
+            let position = if bands > 1 {
+                band as f32 / (bands - 1) as f32
+            } else {
+                0.5
+            };
+            let freq_hz = EQ_LOWEST_HZ * ratio.powf(position);
+            EqPointSettings::new(freq_hz as u32, EQ_BAND_Q, EQ_BAND_GAIN_DB)
+        })
+        .collect();
+    let mut effect = MultibandEqEffect::default();
+    effect.enabled = true;
+    effect.settings = MultibandEqSettings::new(points, None, None);
+    AudioEffect::MultibandEq(effect)
+}
+
+/// Enabled compressor with default settings.
+pub fn compressor() -> AudioEffect {
+    let mut effect = CompressorEffect::default();
+    effect.enabled = true;
+    AudioEffect::Compressor(effect)
+}
+
+/// Process one interleaved block through `effect` into `output`.
+///
+/// `output` is cleared first; reuse it across iterations to keep allocation
+/// out of the measurement.
+pub fn process_effect(
+    effect: &mut AudioEffect,
+    input: &[f32],
+    context: &EffectContext,
+    output: &mut Vec<f32>,
+) {
+    output.clear();
+    effect.process_into(input, output, context, false);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernels_preserve_block_length() {
+        let input = signal(512);
+        let context = context();
+        let mut output = Vec::new();
+        for mut effect in [multiband_eq(8), compressor()] {
+            effect.warm_up(&context);
+            process_effect(&mut effect, &input, &context, &mut output);
+            assert_eq!(output.len(), input.len());
+        }
+        assert_eq!(signal(4), signal(4));
+    }
+
+    #[test]
+    fn multiband_eq_spreads_requested_band_count() {
+        let AudioEffect::MultibandEq(effect) = multiband_eq(4) else {
+            panic!("expected multiband eq");
+        };
+        let freqs: Vec<u32> = effect.settings.points.iter().map(|p| p.freq_hz).collect();
+        assert_eq!(freqs.len(), 4);
+        assert_eq!(freqs[0], EQ_LOWEST_HZ as u32);
+        assert!(freqs.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
@@ -0,0 +1,302 @@
+//! Recent `log` events captured per player for display in host apps.
+//!
+//! The library reports recoverable problems — an impulse response that failed
+//! to load, a track that was skipped — through the `log` facade, which a GUI
+//! host usually routes to a file or nowhere. Installing [`CaptureLogger`] (or
+//! calling [`capture`] from the host's own logger) also copies each event into
+//! a bounded buffer owned by every live player, readable through
+//! [`crate::playback::player::Player::get_recent_events`].
+//!
+//! Events raised on a player's own threads, or while it loads its container,
+//! go only to that player. Events from anywhere else go to every player.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+
+use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
+use serde::Serialize;
+
+use crate::diagnostics::reporter::unix_time_ms;
+
+/// Events kept per player before the oldest are dropped.
+pub const RECENT_EVENT_CAPACITY: usize = 256;
+
+/// Least severe level kept in player buffers.
+const CAPTURE_LEVEL: Level = Level::Info;
+
+/// Severity of a captured event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventLevel {
+    Error,
+    Warn,
+    Info,
+}
+
+/// One captured log event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LogEvent {
+    /// Wall-clock time in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub level: EventLevel,
+    /// Module path of the code that logged it, e.g.
+    /// `proteus_lib::dsp::effects::convolution_reverb::ir_loader`.
+    pub target: String,
+    pub message: String,
+}
+
+type EventBuffer = Arc<Mutex<VecDeque<LogEvent>>>;
+
+struct Subscriber {
+    session_id: String,
+    buffer: Weak<Mutex<VecDeque<LogEvent>>>,
+}
+
+static SUBSCRIBERS: Mutex<Vec<Subscriber>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static CURRENT_SESSION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// `log` backend that captures events for players and forwards every record
+/// to an optional inner logger.
+pub struct CaptureLogger {
+    inner: Option<Box<dyn Log>>,
+}
+
+impl CaptureLogger {
+    /// Wrap `inner`, or capture only when `None`.
+    pub fn new(inner: Option<Box<dyn Log>>) -> Self {
+        Self { inner }
+    }
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= CAPTURE_LEVEL
+            || self
+                .inner
+                .as_ref()
+                .is_some_and(|inner| inner.enabled(metadata))
+    }
+
+    fn log(&self, record: &Record) {
+        capture(record);
+        if let Some(inner) = &self.inner {
+            if inner.enabled(record.metadata()) {
+                inner.log(record);
+            }
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(inner) = &self.inner {
+            inner.flush();
+        }
+    }
+}
+
+/// Install a [`CaptureLogger`] around `inner` as the process-wide logger.
+///
+/// `max_level` is what `inner` should see; the capture itself needs at least
+/// `Info`, so the global maximum is raised to that if lower.
+///
+/// # Errors
+///
+/// Returns [`SetLoggerError`] when a logger is already installed; hosts with
+/// their own logger can call [`capture`] from it instead.
+pub fn install(inner: Option<Box<dyn Log>>, max_level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_logger(Box::leak(Box::new(CaptureLogger::new(inner))))?;
+    log::set_max_level(max_level.max(CAPTURE_LEVEL.to_level_filter()));
+    Ok(())
+}
+
+/// Copy `record` into the buffers of the players it belongs to.
+///
+/// Records below `Info` are ignored.
+pub fn capture(record: &Record) {
+    let level = match record.level() {
+        Level::Error => EventLevel::Error,
+        Level::Warn => EventLevel::Warn,
+        Level::Info => EventLevel::Info,
+        Level::Debug | Level::Trace => return,
+    };
+    let event = LogEvent {
+        timestamp_ms: unix_time_ms(),
+        level,
+        target: record.target().to_string(),
+        message: record.args().to_string(),
+    };
+    let session = current_session();
+
+    let mut subscribers = lock(&SUBSCRIBERS);
+    subscribers.retain(|subscriber| subscriber.buffer.strong_count() > 0);
+    for subscriber in subscribers.iter() {
+        if session
+            .as_deref()
+            .is_some_and(|session| session != subscriber.session_id)
+        {
+            continue;
+        }
+        if let Some(buffer) = subscriber.buffer.upgrade() {
+            push_event(&buffer, event.clone());
+        }
+    }
+}
+
+fn push_event(buffer: &EventBuffer, event: LogEvent) {
+    let mut events = lock(buffer);
+    if events.len() >= RECENT_EVENT_CAPACITY {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+/// A player's captured events; clones share one buffer.
+#[derive(Debug, Clone)]
+pub(crate) struct LogCapture {
+    session_id: String,
+    buffer: EventBuffer,
+}
+
+impl LogCapture {
+    /// Start receiving events for `session_id`.
+    pub(crate) fn register(session_id: &str) -> Self {
+        let buffer: EventBuffer = Arc::new(Mutex::new(VecDeque::new()));
+        lock(&SUBSCRIBERS).push(Subscriber {
+            session_id: session_id.to_string(),
+            buffer: Arc::downgrade(&buffer),
+        });
+        Self {
+            session_id: session_id.to_string(),
+            buffer,
+        }
+    }
+
+    /// Captured events, oldest first.
+    pub(crate) fn recent(&self) -> Vec<LogEvent> {
+        lock(&self.buffer).iter().cloned().collect()
+    }
+
+    /// Attribute events logged on this thread to this capture until the
+    /// returned guard drops.
+    pub(crate) fn enter(&self) -> SessionScope {
+        SessionScope::enter(Some(self.session_id.clone()))
+    }
+}
+
+// The playback lock helpers log on poison, which would re-enter `capture`
+// while a lock here is held, so poison is recovered silently instead.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Session that events on this thread are attributed to, if any.
+pub(crate) fn current_session() -> Option<String> {
+    CURRENT_SESSION.with(|current| current.borrow().clone())
+}
+
+/// Restores the thread's previous session when dropped.
+pub(crate) struct SessionScope {
+    previous: Option<String>,
+}
+
+impl SessionScope {
+    /// Attribute this thread's events to `session`; `None` broadcasts them.
+    ///
+    /// Used to carry a spawning thread's [`current_session`] into the
+    /// threads it starts.
+    pub(crate) fn enter(session: Option<String>) -> Self {
+        let previous = CURRENT_SESSION.with(|current| current.replace(session));
+        Self { previous }
+    }
+}
+
+impl Drop for SessionScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_SESSION.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(level: Level, message: &str) {
+        capture(
+            &Record::builder()
+                .level(level)
+                .target("proteus_lib::test")
+                .args(format_args!("{}", message))
+                .build(),
+        );
+    }
+
+    fn messages(capture: &LogCapture) -> Vec<String> {
+        capture
+            .recent()
+            .into_iter()
+            .map(|event| event.message)
+            .collect()
+    }
+
+    #[test]
+    fn scoped_events_reach_only_their_session() {
+        let first = LogCapture::register("log-capture-first");
+        let second = LogCapture::register("log-capture-second");
+        {
+            let _scope = first.enter();
+            record(Level::Warn, "ir failed to load");
+        }
+        let event = first
+            .recent()
+            .into_iter()
+            .find(|event| event.message == "ir failed to load")
+            .unwrap();
+        assert_eq!(event.level, EventLevel::Warn);
+        assert_eq!(event.target, "proteus_lib::test");
+        assert!(!messages(&second).contains(&"ir failed to load".to_string()));
+    }
+
+    #[test]
+    fn unscoped_events_reach_every_session_and_debug_is_dropped() {
+        let first = LogCapture::register("log-capture-broadcast-a");
+        let second = LogCapture::register("log-capture-broadcast-b");
+        let _scope = SessionScope::enter(None);
+        record(Level::Info, "track skipped");
+        record(Level::Debug, "noise");
+        assert!(messages(&first).contains(&"track skipped".to_string()));
+        assert!(messages(&second).contains(&"track skipped".to_string()));
+        assert!(!messages(&first).contains(&"noise".to_string()));
+    }
+
+    #[test]
+    fn buffer_keeps_the_most_recent_events() {
+        let capture = LogCapture::register("log-capture-bounded");
+        let _scope = capture.enter();
+        for index in 0..RECENT_EVENT_CAPACITY + 5 {
+            record(Level::Error, &index.to_string());
+        }
+        let messages = messages(&capture);
+        assert_eq!(messages.len(), RECENT_EVENT_CAPACITY);
+        assert_eq!(
+            messages.last(),
+            Some(&(RECENT_EVENT_CAPACITY + 4).to_string())
+        );
+        assert!(!messages.contains(&"0".to_string()));
+    }
+
+    #[test]
+    fn scope_restores_the_previous_session() {
+        let outer = SessionScope::enter(Some("outer".to_string()));
+        {
+            let _inner = SessionScope::enter(Some("inner".to_string()));
+            assert_eq!(current_session().as_deref(), Some("inner"));
+        }
+        assert_eq!(current_session().as_deref(), Some("outer"));
+        drop(outer);
+        assert_eq!(current_session(), None);
+    }
+}
@@ -0,0 +1,250 @@
+//! Namespaced metrics snapshots.
+//!
+//! [`MetricsRegistry`] flattens the playback engine's measurements into
+//! dotted names grouped by stage, so UIs and exporters query one snapshot
+//! instead of several structs:
+//! - `mix.*`: mix-loop deadline overruns, DSP throughput, and track counts.
+//! - `output.*`: underruns, sample faults, and the queues feeding the sink.
+//! - `effects.convolution.*`: threading and timing of the first enabled
+//!   convolution reverb; absent when the chain has none.
+//!
+//! The registry serializes as one flat JSON object keyed by those names.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::dsp::effects::convolution_reverb::ReverbMetrics;
+use crate::playback::engine::DspChainMetrics;
+
+/// One metric reading.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MetricValue {
+    /// A condition that is currently on or off.
+    Flag(bool),
+    /// A monotonically increasing event count or an item count.
+    Count(u64),
+    /// A measured quantity such as a duration or a rate.
+    Gauge(f64),
+}
+
+impl From<bool> for MetricValue {
+    fn from(value: bool) -> Self {
+        Self::Flag(value)
+    }
+}
+
+impl From<u64> for MetricValue {
+    fn from(value: u64) -> Self {
+        Self::Count(value)
+    }
+}
+
+impl From<usize> for MetricValue {
+    fn from(value: usize) -> Self {
+        Self::Count(value as u64)
+    }
+}
+
+impl From<f64> for MetricValue {
+    fn from(value: f64) -> Self {
+        Self::Gauge(value)
+    }
+}
+
+/// Snapshot of named metrics, ordered by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct MetricsRegistry {
+    values: BTreeMap<String, MetricValue>,
+}
+
+impl MetricsRegistry {
+    /// Create an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `name` to `value`, replacing any previous reading.
+    pub fn record(&mut self, name: impl Into<String>, value: impl Into<MetricValue>) {
+        self.values.insert(name.into(), value.into());
+    }
+
+    /// Reading for `name`, if recorded.
+    pub fn get(&self, name: &str) -> Option<MetricValue> {
+        self.values.get(name).copied()
+    }
+
+    /// Reading for `name` when it is a [`MetricValue::Flag`].
+    pub fn flag(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            MetricValue::Flag(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Reading for `name` when it is a [`MetricValue::Count`].
+    pub fn count(&self, name: &str) -> Option<u64> {
+        match self.get(name)? {
+            MetricValue::Count(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Reading for `name` when it is a [`MetricValue::Gauge`].
+    pub fn gauge(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            MetricValue::Gauge(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Readings whose names start with `namespace` followed by a dot, e.g.
+    /// `"output"` or `"effects.convolution"`.
+    pub fn namespace<'a>(
+        &'a self,
+        namespace: &'a str,
+    ) -> impl Iterator<Item = (&'a str, MetricValue)> + 'a {
+        self.iter().filter(move |(name, _)| {
+            name.strip_prefix(namespace)
+                .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    /// All readings in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, MetricValue)> {
+        self.values
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value))
+    }
+
+    /// Number of recorded metrics.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether nothing has been recorded.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Serialize every reading as one flat JSON object.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    fn record_reverb(&mut self, metrics: &ReverbMetrics) {
+        self.record("effects.convolution.worker_threads", metrics.worker_threads);
+        self.record(
+            "effects.convolution.channel_workers",
+            metrics.channel_workers,
+        );
+        self.record(
+            "effects.convolution.partition_workers",
+            metrics.partition_workers,
+        );
+        self.record("effects.convolution.partitions", metrics.partitions);
+        self.record("effects.convolution.last_block_ms", metrics.last_block_ms);
+        self.record("effects.convolution.gpu", metrics.gpu);
+    }
+}
+
+impl From<&DspChainMetrics> for MetricsRegistry {
+    fn from(metrics: &DspChainMetrics) -> Self {
+        let mut registry = Self::new();
+        registry.record("mix.overrun", metrics.overrun);
+        registry.record("mix.overrun_ms", metrics.overrun_ms);
+        registry.record("mix.avg_overrun_ms", metrics.avg_overrun_ms);
+        registry.record("mix.max_overrun_ms", metrics.max_overrun_ms);
+        registry.record("mix.chain_ksps", metrics.chain_ksps);
+        registry.record("mix.avg_chain_ksps", metrics.avg_chain_ksps);
+        registry.record("mix.min_chain_ksps", metrics.min_chain_ksps);
+        registry.record("mix.max_chain_ksps", metrics.max_chain_ksps);
+        registry.record("mix.track_key_count", metrics.track_key_count);
+        registry.record("mix.finished_track_count", metrics.finished_track_count);
+        registry.record("mix.prot_key_count", metrics.prot_key_count);
+        registry.record("mix.degradation_level", metrics.degradation_level);
+
+        registry.record("output.underrun_count", metrics.underrun_count);
+        registry.record("output.underrun_active", metrics.underrun_active);
+        registry.record("output.pop_count", metrics.pop_count);
+        registry.record("output.clip_count", metrics.clip_count);
+        registry.record("output.nan_count", metrics.nan_count);
+        registry.record("output.late_append_count", metrics.late_append_count);
+        registry.record("output.late_append_active", metrics.late_append_active);
+        registry.record("output.queued_sink_ms", metrics.queued_sink_ms);
+        registry.record("output.chunk_ms", metrics.output_chunk_ms);
+        registry.record("output.queue_ms", metrics.output_queue_ms);
+        registry.record("output.queue_capacity_ms", metrics.output_queue_capacity_ms);
+
+        if let Some(reverb) = &metrics.reverb {
+            registry.record_reverb(reverb);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chain_metrics_land_in_their_namespaces() {
+        let metrics = DspChainMetrics {
+            underrun_count: 3,
+            chain_ksps: 512.0,
+            overrun: true,
+            ..DspChainMetrics::default()
+        };
+        let registry = MetricsRegistry::from(&metrics);
+        assert_eq!(registry.count("output.underrun_count"), Some(3));
+        assert_eq!(registry.gauge("mix.chain_ksps"), Some(512.0));
+        assert_eq!(registry.flag("mix.overrun"), Some(true));
+        assert_eq!(registry.gauge("output.underrun_count"), None);
+        assert_eq!(registry.namespace("effects.convolution").count(), 0);
+        assert!(registry
+            .namespace("output")
+            .all(|(name, _)| name.starts_with("output.")));
+    }
+
+    #[test]
+    fn reverb_metrics_appear_when_present() {
+        let metrics = DspChainMetrics {
+            reverb: Some(ReverbMetrics {
+                worker_threads: 4,
+                partitions: 12,
+                ..ReverbMetrics::default()
+            }),
+            ..DspChainMetrics::default()
+        };
+        let registry = MetricsRegistry::from(&metrics);
+        assert_eq!(
+            registry.count("effects.convolution.worker_threads"),
+            Some(4)
+        );
+        assert_eq!(registry.namespace("effects.convolution").count(), 6);
+        assert_eq!(registry.namespace("effects").count(), 6);
+    }
+
+    #[test]
+    fn namespace_matches_whole_segments() {
+        let mut registry = MetricsRegistry::new();
+        registry.record("mix.overrun", false);
+        registry.record("mixer.other", 1.0);
+        let names: Vec<&str> = registry.namespace("mix").map(|(name, _)| name).collect();
+        assert_eq!(names, ["mix.overrun"]);
+    }
+
+    #[test]
+    fn json_export_is_one_flat_object() {
+        let mut registry = MetricsRegistry::new();
+        registry.record("output.pop_count", 2_u64);
+        registry.record("mix.overrun", false);
+        registry.record("mix.overrun_ms", 1.5);
+        assert_eq!(
+            registry.to_json().unwrap(),
+            r#"{"mix.overrun":false,"mix.overrun_ms":1.5,"output.pop_count":2}"#
+        );
+    }
+}
@@ -2,4 +2,8 @@
 
 #[cfg(feature = "bench")]
 pub mod bench;
+pub mod log_capture;
+pub mod metrics;
 pub mod reporter;
+pub mod session;
+pub mod watchdog;
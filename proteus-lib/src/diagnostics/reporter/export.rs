@@ -0,0 +1,230 @@
+//! Structured diagnostics records and exporters.
+//!
+//! A [`DiagnosticsRecord`] bundles a playback [`Report`], the current
+//! [`MetricsRegistry`] snapshot, and any pending watchdog events with a session ID and
+//! container identifier so field reports can be correlated. Records serialize
+//! to JSON and can be shipped through any [`DiagnosticsExporter`]:
+//! [`RollingFileExporter`] keeps a bounded set of JSON-lines files on disk, and
+//! `OtlpExporter` (behind the `otlp` feature) posts them to an OTLP/HTTP
+//! collector as log records.
+
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::Serialize;
+
+use super::Report;
+use crate::diagnostics::metrics::MetricsRegistry;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
+
+const ROLLING_LOG_FILE_NAME: &str = "proteus-diagnostics.jsonl";
+
+/// One structured diagnostics snapshot.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsRecord {
+    /// Identifier shared by every record from one player instance.
+    pub session_id: String,
+    /// Container path, or the joined source file paths for file-list playback.
+    pub container_id: Option<String>,
+    /// Wall-clock capture time in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    /// Playback state at capture time.
+    pub report: Report,
+    /// Namespaced engine metrics at capture time.
+    pub metrics: MetricsRegistry,
+    /// Watchdog events raised since the previous export.
+    pub events: Vec<DiagnosticsEvent>,
+}
+
+impl DiagnosticsRecord {
+    /// Serialize the record as a single-line JSON object.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+}
+
+/// Errors that can occur while exporting diagnostics.
+#[derive(Debug)]
+pub enum ExportError {
+    /// Writing or rotating the on-disk log failed.
+    Io(std::io::Error),
+    /// The record could not be serialized.
+    Serialize(serde_json::Error),
+    /// The remote collector rejected the record or could not be reached.
+    Transport(String),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to write diagnostics: {}", err),
+            Self::Serialize(err) => write!(f, "failed to serialize diagnostics: {}", err),
+            Self::Transport(message) => write!(f, "failed to send diagnostics: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for ExportError {}
+
+impl From<std::io::Error> for ExportError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ExportError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Serialize(err)
+    }
+}
+
+/// Destination for diagnostics records.
+pub trait DiagnosticsExporter: Send {
+    /// Export one record.
+    fn export(&mut self, record: &DiagnosticsRecord) -> Result<(), ExportError>;
+}
+
+/// JSON-lines exporter that rotates files once they exceed a size budget.
+///
+/// The active file is `proteus-diagnostics.jsonl` inside `directory`; rotated
+/// files get a numeric suffix (`.1` is the newest) and at most `max_files`
+/// rotated files are kept.
+#[derive(Debug, Clone)]
+pub struct RollingFileExporter {
+    directory: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RollingFileExporter {
+    /// Create an exporter writing into `directory`.
+    pub fn new(directory: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> Self {
+        Self {
+            directory: directory.into(),
+            max_bytes: max_bytes.max(1),
+            max_files,
+        }
+    }
+
+    /// Path of the file currently being written.
+    pub fn active_path(&self) -> PathBuf {
+        self.directory.join(ROLLING_LOG_FILE_NAME)
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        self.directory
+            .join(format!("{}.{}", ROLLING_LOG_FILE_NAME, index))
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            return fs::remove_file(self.active_path());
+        }
+        let oldest = self.rotated_path(self.max_files);
+        if oldest.exists() {
+            fs::remove_file(&oldest)?;
+        }
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(self.active_path(), self.rotated_path(1))
+    }
+}
+
+impl DiagnosticsExporter for RollingFileExporter {
+    fn export(&mut self, record: &DiagnosticsRecord) -> Result<(), ExportError> {
+        let line = record.to_json()?;
+        fs::create_dir_all(&self.directory)?;
+        let active = self.active_path();
+        let current_len = fs::metadata(&active).map(|meta| meta.len()).unwrap_or(0);
+        if current_len > 0 && current_len + line.len() as u64 + 1 > self.max_bytes {
+            self.rotate()?;
+        }
+        let mut file = open_append(&active)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+fn open_append(path: &Path) -> std::io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Generate a random session identifier (32 hex characters).
+pub fn new_session_id() -> String {
+    let value: u128 = rand::random();
+    format!("{:032x}", value)
+}
+
+/// Current wall-clock time in milliseconds since the Unix epoch.
+pub(crate) fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::engine::DspChainMetrics;
+
+    fn record(time: f64) -> DiagnosticsRecord {
+        DiagnosticsRecord {
+            session_id: "session".to_string(),
+            container_id: Some("song.prot".to_string()),
+            timestamp_ms: 1,
+            report: Report {
+                time,
+                volume: 1.0,
+                duration: 10.0,
+                playing: true,
+                #[cfg(feature = "link")]
+                link: None,
+            },
+            metrics: MetricsRegistry::from(&DspChainMetrics::default()),
+            events: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn record_serializes_session_container_and_metrics() {
+        let json: serde_json::Value =
+            serde_json::from_str(&record(1.5).to_json().unwrap()).unwrap();
+        assert_eq!(json["session_id"], "session");
+        assert_eq!(json["container_id"], "song.prot");
+        assert_eq!(json["report"]["time"], 1.5);
+        assert_eq!(json["metrics"]["output.underrun_count"], 0);
+    }
+
+    #[test]
+    fn rolling_file_exporter_rotates_and_caps_files() {
+        let directory = std::env::temp_dir().join(format!("proteus_diag_{}", new_session_id()));
+        let line_len = record(0.0).to_json().unwrap().len() as u64 + 1;
+        let mut exporter = RollingFileExporter::new(&directory, line_len * 2, 2);
+        for step in 0..7 {
+            exporter.export(&record(step as f64)).unwrap();
+        }
+
+        let active = fs::read_to_string(exporter.active_path()).unwrap();
+        assert_eq!(active.lines().count(), 1);
+        assert!(exporter.rotated_path(1).exists());
+        assert!(exporter.rotated_path(2).exists());
+        assert!(!exporter.rotated_path(3).exists());
+        let _ = fs::remove_dir_all(&directory);
+    }
+
+    #[test]
+    fn session_ids_are_unique_hex() {
+        let first = new_session_id();
+        assert_eq!(first.len(), 32);
+        assert!(first.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(first, new_session_id());
+    }
+}
@@ -1,4 +1,18 @@
 //! Periodic playback state reporter for UI updates.
+//!
+//! Reports can also be bundled with DSP metrics into structured
+//! [`DiagnosticsRecord`]s and exported as JSON (see the `export` module).
+
+mod export;
+#[cfg(feature = "otlp")]
+mod otlp;
+
+pub(crate) use export::unix_time_ms;
+pub use export::{
+    new_session_id, DiagnosticsExporter, DiagnosticsRecord, ExportError, RollingFileExporter,
+};
+#[cfg(feature = "otlp")]
+pub use otlp::OtlpExporter;
 
 use std::{
     sync::{
@@ -9,10 +23,12 @@ use std::{
     time::Duration,
 };
 
+use serde::Serialize;
+
 use crate::playback::player::PlayerState;
 
 /// Snapshot of playback state sent to UI consumers.
-#[derive(Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Report {
     /// Current playback position in seconds.
     pub time: f64,
@@ -22,6 +38,10 @@ pub struct Report {
     pub duration: f64,
     /// Whether playback is currently in the `Playing` state.
     pub playing: bool,
+    /// Tempo and phase of the Link session the player follows, if any.
+    #[cfg(feature = "link")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<crate::playback::link::LinkReport>,
 }
 
 /// Background reporter that polls the [`crate::playback::player::Player`] state at fixed
@@ -36,6 +56,8 @@ pub struct Reporter {
     interval: Duration,
     finish: Arc<AtomicBool>,
     thread_handle: Arc<Mutex<Option<JoinHandle<()>>>>,
+    #[cfg(feature = "link")]
+    link: Arc<Mutex<Option<crate::playback::link::SharedLink>>>,
 }
 
 impl Reporter {
@@ -57,15 +79,38 @@ impl Reporter {
             interval,
             finish: Arc::new(AtomicBool::new(false)),
             thread_handle: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "link")]
+            link: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Include the state of the Link session held by `link` in reports.
+    #[cfg(feature = "link")]
+    pub fn with_link(
+        mut self,
+        link: Arc<Mutex<Option<crate::playback::link::SharedLink>>>,
+    ) -> Self {
+        self.link = link;
+        self
+    }
+
+    #[cfg(feature = "link")]
+    fn link_report(&self) -> Option<crate::playback::link::LinkReport> {
+        self.link
+            .lock()
+            .unwrap_or_else(|_| panic!("link lock poisoned — a thread panicked while holding it"))
+            .as_deref()
+            .map(crate::playback::link::LinkReport::capture)
+    }
+
     fn run(&self) {
         let mut last_report = Report {
             time: 0.0,
             volume: 0.0,
             duration: 0.0,
             playing: false,
+            #[cfg(feature = "link")]
+            link: None,
         };
 
         loop {
@@ -82,6 +127,8 @@ impl Reporter {
                 playing: *self.state.lock().unwrap_or_else(|_| {
                     panic!("state lock poisoned — a thread panicked while holding it")
                 }) == PlayerState::Playing,
+                #[cfg(feature = "link")]
+                link: self.link_report(),
             };
 
             if report != last_report {
@@ -0,0 +1,127 @@
+//! OTLP/HTTP log exporter for diagnostics records (`otlp` feature).
+//!
+//! Each record is sent as one OpenTelemetry log record using the OTLP JSON
+//! encoding. The serialized record is the log body; the session and container
+//! identifiers are also attached as attributes so collectors can index them.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use super::export::{DiagnosticsExporter, DiagnosticsRecord, ExportError};
+
+const LOGS_PATH: &str = "/v1/logs";
+const DEFAULT_SERVICE_NAME: &str = "proteus";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// OTLP severity number for `WARN`.
+const SEVERITY_WARN: u8 = 13;
+/// OTLP severity number for `INFO`.
+const SEVERITY_INFO: u8 = 9;
+
+/// Exporter that posts records to an OTLP/HTTP collector.
+#[derive(Debug, Clone)]
+pub struct OtlpExporter {
+    url: String,
+    service_name: String,
+    agent: ureq::Agent,
+}
+
+impl OtlpExporter {
+    /// Create an exporter for a collector base URL such as `http://localhost:4318`.
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            url: format!("{}{}", endpoint.trim_end_matches('/'), LOGS_PATH),
+            service_name: DEFAULT_SERVICE_NAME.to_string(),
+            agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build(),
+        }
+    }
+
+    /// Override the `service.name` resource attribute (defaults to `proteus`).
+    pub fn with_service_name(mut self, service_name: impl Into<String>) -> Self {
+        self.service_name = service_name.into();
+        self
+    }
+}
+
+impl DiagnosticsExporter for OtlpExporter {
+    fn export(&mut self, record: &DiagnosticsRecord) -> Result<(), ExportError> {
+        let payload = logs_payload(&self.service_name, record)?;
+        self.agent
+            .post(&self.url)
+            .set("Content-Type", "application/json")
+            .send_string(&payload.to_string())
+            .map_err(|err| ExportError::Transport(err.to_string()))?;
+        Ok(())
+    }
+}
+
+fn logs_payload(service_name: &str, record: &DiagnosticsRecord) -> Result<Value, ExportError> {
+    let time_unix_nano = (record.timestamp_ms as u128 * 1_000_000).to_string();
+    let (severity_number, severity_text) = if record.events.is_empty() {
+        (SEVERITY_INFO, "INFO")
+    } else {
+        (SEVERITY_WARN, "WARN")
+    };
+    let mut attributes = vec![string_attribute("session.id", &record.session_id)];
+    if let Some(container_id) = record.container_id.as_deref() {
+        attributes.push(string_attribute("container.id", container_id));
+    }
+    Ok(json!({
+        "resourceLogs": [{
+            "resource": {
+                "attributes": [string_attribute("service.name", service_name)]
+            },
+            "scopeLogs": [{
+                "scope": { "name": "proteus_lib::diagnostics" },
+                "logRecords": [{
+                    "timeUnixNano": time_unix_nano,
+                    "severityNumber": severity_number,
+                    "severityText": severity_text,
+                    "body": { "stringValue": record.to_json()? },
+                    "attributes": attributes
+                }]
+            }]
+        }]
+    }))
+}
+
+fn string_attribute(key: &str, value: &str) -> Value {
+    json!({ "key": key, "value": { "stringValue": value } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::metrics::MetricsRegistry;
+    use crate::diagnostics::reporter::Report;
+
+    #[test]
+    fn logs_payload_carries_session_and_container_attributes() {
+        let record = DiagnosticsRecord {
+            session_id: "abc".to_string(),
+            container_id: Some("song.prot".to_string()),
+            timestamp_ms: 2,
+            report: Report {
+                time: 0.0,
+                volume: 1.0,
+                duration: 1.0,
+                playing: false,
+                #[cfg(feature = "link")]
+                link: None,
+            },
+            metrics: MetricsRegistry::new(),
+            events: Vec::new(),
+        };
+        let payload = logs_payload("test", &record).unwrap();
+        let log = &payload["resourceLogs"][0]["scopeLogs"][0]["logRecords"][0];
+        assert_eq!(log["timeUnixNano"], "2000000");
+        assert_eq!(log["attributes"][0]["value"]["stringValue"], "abc");
+        assert_eq!(log["attributes"][1]["value"]["stringValue"], "song.prot");
+    }
+
+    #[test]
+    fn exporter_builds_logs_url() {
+        let exporter = OtlpExporter::new("http://localhost:4318/");
+        assert_eq!(exporter.url, "http://localhost:4318/v1/logs");
+    }
+}
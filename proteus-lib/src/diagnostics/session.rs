@@ -0,0 +1,150 @@
+//! Per-session play statistics.
+//!
+//! A [`PlaySession`] accumulates what a listener actually heard during one
+//! player's lifetime: output time, reshuffles, which candidates were chosen
+//! and for how long they played, and output underruns. Content authors can
+//! serialize it to JSON to learn which stems get heard.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// Grouped shuffle schedule as returned by `Player::get_shuffle_schedule`.
+pub(crate) type ScheduleSnapshot = Vec<(f64, Vec<Vec<String>>)>;
+
+/// Statistics collected over one playback session.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlaySession {
+    /// Identifier shared with diagnostics records from the same player.
+    pub session_id: String,
+    /// Seconds of audio delivered to the output.
+    pub play_time_s: f64,
+    /// Number of times selections were redrawn (shuffles and variable changes).
+    pub shuffle_count: u64,
+    /// Number of shuffle-point boundaries crossed during playback.
+    pub shuffle_points_heard: u64,
+    /// How many schedule segments selected each candidate, keyed by track ID or path.
+    pub selections: BTreeMap<String, u64>,
+    /// Seconds each candidate was audible, keyed by track ID or path.
+    pub heard_s: BTreeMap<String, f64>,
+    /// Number of times the output queue ran dry while playing.
+    pub underrun_count: u64,
+    #[serde(skip)]
+    schedule: ScheduleSnapshot,
+    #[serde(skip)]
+    segment: Option<usize>,
+}
+
+impl PlaySession {
+    /// Create an empty session with the given identifier.
+    pub fn new(session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Serialize the statistics as a single-line JSON object.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Record a newly resolved schedule and tally its selections.
+    ///
+    /// `reshuffle` is `false` for the initial resolution and `true` when an
+    /// existing schedule was redrawn.
+    pub(crate) fn record_schedule(&mut self, schedule: ScheduleSnapshot, reshuffle: bool) {
+        if reshuffle {
+            self.shuffle_count += 1;
+        }
+        for key in schedule
+            .iter()
+            .flat_map(|(_, groups)| groups.iter().flatten())
+        {
+            *self.selections.entry(key.clone()).or_default() += 1;
+        }
+        self.schedule = schedule;
+        self.segment = None;
+    }
+
+    /// Record `seconds` of output delivered while the timeline was at `position_s`.
+    pub(crate) fn record_output(&mut self, position_s: f64, seconds: f64) {
+        self.play_time_s += seconds;
+        let Some(segment) = self
+            .schedule
+            .iter()
+            .rposition(|(start_s, _)| *start_s <= position_s)
+        else {
+            return;
+        };
+        if self.segment.is_some_and(|previous| segment == previous + 1) {
+            self.shuffle_points_heard += 1;
+        }
+        self.segment = Some(segment);
+        for key in self.schedule[segment].1.iter().flatten() {
+            *self.heard_s.entry(key.clone()).or_default() += seconds;
+        }
+    }
+
+    /// Record that the output queue ran dry while playing.
+    pub(crate) fn record_underrun(&mut self) {
+        self.underrun_count += 1;
+    }
+
+    /// Clear all counters, keeping the session identifier and current schedule.
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            session_id: std::mem::take(&mut self.session_id),
+            schedule: std::mem::take(&mut self.schedule),
+            ..Self::default()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlaySession;
+
+    fn schedule() -> Vec<(f64, Vec<Vec<String>>)> {
+        vec![
+            (0.0, vec![vec!["a".to_string()], vec!["c".to_string()]]),
+            (10.0, vec![vec!["b".to_string()], vec!["c".to_string()]]),
+        ]
+    }
+
+    #[test]
+    fn session_tallies_selections_and_heard_time() {
+        let mut session = PlaySession::new("s1");
+        session.record_schedule(schedule(), false);
+        session.record_output(1.0, 2.0);
+        session.record_output(11.0, 1.0);
+        session.record_underrun();
+
+        assert_eq!(session.shuffle_count, 0);
+        assert_eq!(session.shuffle_points_heard, 1);
+        assert_eq!(session.selections["c"], 2);
+        assert_eq!(session.heard_s["a"], 2.0);
+        assert_eq!(session.heard_s["c"], 3.0);
+        assert_eq!(session.play_time_s, 3.0);
+        assert_eq!(session.underrun_count, 1);
+
+        let json: serde_json::Value = serde_json::from_str(&session.to_json().unwrap()).unwrap();
+        assert_eq!(json["session_id"], "s1");
+        assert!(json.get("schedule").is_none());
+    }
+
+    #[test]
+    fn reshuffle_counts_and_reset_keeps_identity() {
+        let mut session = PlaySession::new("s2");
+        session.record_schedule(schedule(), false);
+        session.record_schedule(schedule(), true);
+        assert_eq!(session.shuffle_count, 1);
+        assert_eq!(session.selections["a"], 2);
+
+        session.reset();
+        assert_eq!(session.session_id, "s2");
+        assert!(session.selections.is_empty());
+        session.record_output(0.0, 1.0);
+        assert_eq!(session.heard_s["a"], 1.0);
+    }
+}
@@ -0,0 +1,332 @@
+//! Output-stage watchdog for "no audio but time advancing" reports.
+//!
+//! The mix thread feeds every chunk it sends (before and after the effect
+//! chain) into a [`PlaybackWatchdog`], along with idle periods spent waiting
+//! for decoded input. When output stays silent, repeats the same buffer, or
+//! stops arriving for longer than the configured threshold, a
+//! [`DiagnosticsEvent`] is raised naming the stage most likely at fault.
+//!
+//! The mix thread only produces chunks while the sink is consuming them, so
+//! the checks effectively run only while the player is `Playing`.
+
+use std::time::Instant;
+
+use serde::Serialize;
+
+/// Peak level below which a chunk counts as silent (about -100 dBFS).
+const SILENCE_PEAK: f32 = 1.0e-5;
+/// Minimum number of consecutive identical chunks before reporting a repeat.
+const REPEATED_BUFFER_MIN_CHUNKS: usize = 4;
+
+/// Pipeline stage suspected of causing a watchdog condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaybackStage {
+    /// Decoders stopped delivering audio, or delivered only silence/repeats.
+    Decoder,
+    /// The effect chain turned a live input into silence or a frozen buffer.
+    Effects,
+    /// Output chunks reached the sink too late to keep it fed.
+    Sink,
+}
+
+/// Condition detected by the playback watchdog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchdogCondition {
+    /// Output has been silent for longer than the threshold.
+    SustainedSilence,
+    /// Output repeated the same non-silent buffer for longer than the threshold.
+    RepeatedBuffers,
+    /// The mix thread waited for decoded input for longer than the threshold.
+    InputStalled,
+    /// Chunks were appended to the sink late for longer than the threshold.
+    OutputLate,
+    /// Playback started with a selected source that is missing or has zero
+    /// length (raised under [`MissingTrackPolicy::Error`]).
+    ///
+    /// [`MissingTrackPolicy::Error`]: crate::container::prot::MissingTrackPolicy::Error
+    MissingSource,
+    /// The overload policy applied a degradation step after the effect chain
+    /// overran for the reported duration.
+    ///
+    /// See [`OverloadPolicy`](crate::playback::engine::OverloadPolicy).
+    QualityDegraded,
+    /// The overload policy undid a degradation step after the reported
+    /// duration of headroom.
+    QualityRestored,
+}
+
+/// Diagnostics event raised by the playback watchdog.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct DiagnosticsEvent {
+    /// What was observed.
+    pub condition: WatchdogCondition,
+    /// Stage most likely responsible.
+    pub suspected_stage: PlaybackStage,
+    /// How long the condition had persisted when reported, in milliseconds.
+    pub duration_ms: f64,
+}
+
+/// Stateful detector fed from the mix thread output stage.
+///
+/// Each condition is reported once per episode and re-armed when it clears.
+#[derive(Debug)]
+pub(crate) struct PlaybackWatchdog {
+    threshold_s: f64,
+    silent_s: f64,
+    silent_input_live: bool,
+    silence_reported: bool,
+    repeat_s: f64,
+    repeat_chunks: usize,
+    repeat_input_changed: bool,
+    repeat_reported: bool,
+    last_input: Vec<f32>,
+    last_output: Vec<f32>,
+    late_s: f64,
+    late_reported: bool,
+    idle_since: Option<Instant>,
+    idle_reported: bool,
+}
+
+impl PlaybackWatchdog {
+    /// Create a watchdog that reports conditions lasting longer than `threshold_ms`.
+    ///
+    /// A threshold of `0` disables all checks.
+    pub(crate) fn new(threshold_ms: f32) -> Self {
+        Self {
+            threshold_s: threshold_seconds(threshold_ms),
+            silent_s: 0.0,
+            silent_input_live: false,
+            silence_reported: false,
+            repeat_s: 0.0,
+            repeat_chunks: 0,
+            repeat_input_changed: false,
+            repeat_reported: false,
+            last_input: Vec::new(),
+            last_output: Vec::new(),
+            late_s: 0.0,
+            late_reported: false,
+            idle_since: None,
+            idle_reported: false,
+        }
+    }
+
+    /// Update the reporting threshold.
+    pub(crate) fn set_threshold_ms(&mut self, threshold_ms: f32) {
+        self.threshold_s = threshold_seconds(threshold_ms);
+    }
+
+    /// Observe one chunk leaving the output stage.
+    ///
+    /// `input` is the pre-effects mix and `output` the post-effects buffer sent
+    /// to the sink; `audio_s` is the chunk duration and `output_late` whether
+    /// the sink currently reports late appends.
+    pub(crate) fn observe_chunk(
+        &mut self,
+        input: &[f32],
+        output: &[f32],
+        audio_s: f64,
+        output_late: bool,
+    ) -> Vec<DiagnosticsEvent> {
+        self.idle_since = None;
+        self.idle_reported = false;
+        if self.threshold_s <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        let input_live = !is_silent(input);
+        let output_silent = is_silent(output);
+        if output_silent {
+            self.silent_s += audio_s;
+            self.silent_input_live |= input_live;
+            if !self.silence_reported && self.silent_s >= self.threshold_s {
+                self.silence_reported = true;
+                events.push(self.event(
+                    WatchdogCondition::SustainedSilence,
+                    if self.silent_input_live {
+                        PlaybackStage::Effects
+                    } else {
+                        PlaybackStage::Decoder
+                    },
+                    self.silent_s,
+                ));
+            }
+        } else {
+            self.silent_s = 0.0;
+            self.silent_input_live = false;
+            self.silence_reported = false;
+        }
+
+        if !output_silent && output == self.last_output.as_slice() {
+            self.repeat_s += audio_s;
+            self.repeat_chunks += 1;
+            self.repeat_input_changed |= input != self.last_input.as_slice();
+            if !self.repeat_reported
+                && self.repeat_chunks >= REPEATED_BUFFER_MIN_CHUNKS
+                && self.repeat_s >= self.threshold_s
+            {
+                self.repeat_reported = true;
+                events.push(self.event(
+                    WatchdogCondition::RepeatedBuffers,
+                    if self.repeat_input_changed {
+                        PlaybackStage::Effects
+                    } else {
+                        PlaybackStage::Decoder
+                    },
+                    self.repeat_s,
+                ));
+            }
+        } else {
+            self.repeat_s = 0.0;
+            self.repeat_chunks = 0;
+            self.repeat_input_changed = false;
+            self.repeat_reported = false;
+        }
+        self.last_input.clear();
+        self.last_input.extend_from_slice(input);
+        self.last_output.clear();
+        self.last_output.extend_from_slice(output);
+
+        if output_late {
+            self.late_s += audio_s;
+            if !self.late_reported && self.late_s >= self.threshold_s {
+                self.late_reported = true;
+                events.push(self.event(
+                    WatchdogCondition::OutputLate,
+                    PlaybackStage::Sink,
+                    self.late_s,
+                ));
+            }
+        } else {
+            self.late_s = 0.0;
+            self.late_reported = false;
+        }
+
+        events
+    }
+
+    /// Observe the mix thread waiting for decoded input at `now`.
+    pub(crate) fn observe_idle(&mut self, now: Instant) -> Option<DiagnosticsEvent> {
+        let since = *self.idle_since.get_or_insert(now);
+        if self.threshold_s <= 0.0 || self.idle_reported {
+            return None;
+        }
+        let idle_s = now.saturating_duration_since(since).as_secs_f64();
+        if idle_s < self.threshold_s {
+            return None;
+        }
+        self.idle_reported = true;
+        Some(self.event(
+            WatchdogCondition::InputStalled,
+            PlaybackStage::Decoder,
+            idle_s,
+        ))
+    }
+
+    fn event(
+        &self,
+        condition: WatchdogCondition,
+        suspected_stage: PlaybackStage,
+        duration_s: f64,
+    ) -> DiagnosticsEvent {
+        DiagnosticsEvent {
+            condition,
+            suspected_stage,
+            duration_ms: duration_s * 1000.0,
+        }
+    }
+}
+
+fn threshold_seconds(threshold_ms: f32) -> f64 {
+    if threshold_ms.is_finite() {
+        f64::from(threshold_ms.max(0.0)) / 1000.0
+    } else {
+        0.0
+    }
+}
+
+fn is_silent(samples: &[f32]) -> bool {
+    samples.iter().all(|sample| sample.abs() < SILENCE_PEAK)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn tone(len: usize, offset: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| ((i + offset) as f32 * 0.1).sin() * 0.5)
+            .collect()
+    }
+
+    #[test]
+    fn silence_with_live_input_blames_effects_once() {
+        let mut watchdog = PlaybackWatchdog::new(1_000.0);
+        let silent = vec![0.0; 64];
+        let mut events = Vec::new();
+        for step in 0..8 {
+            events.extend(watchdog.observe_chunk(&tone(64, step * 64), &silent, 0.25, false));
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].condition, WatchdogCondition::SustainedSilence);
+        assert_eq!(events[0].suspected_stage, PlaybackStage::Effects);
+    }
+
+    #[test]
+    fn repeated_output_with_repeated_input_blames_decoder() {
+        let mut watchdog = PlaybackWatchdog::new(500.0);
+        let chunk = tone(64, 0);
+        let mut events = Vec::new();
+        for _ in 0..8 {
+            events.extend(watchdog.observe_chunk(&chunk, &chunk, 0.25, false));
+        }
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].condition, WatchdogCondition::RepeatedBuffers);
+        assert_eq!(events[0].suspected_stage, PlaybackStage::Decoder);
+    }
+
+    #[test]
+    fn idle_input_reports_stall_and_rearms_after_chunk() {
+        let mut watchdog = PlaybackWatchdog::new(100.0);
+        let start = Instant::now();
+        assert!(watchdog.observe_idle(start).is_none());
+        let event = watchdog
+            .observe_idle(start + Duration::from_millis(150))
+            .expect("stall event");
+        assert_eq!(event.condition, WatchdogCondition::InputStalled);
+        assert!(watchdog
+            .observe_idle(start + Duration::from_millis(300))
+            .is_none());
+
+        watchdog.observe_chunk(&tone(64, 0), &tone(64, 0), 0.01, false);
+        let later = start + Duration::from_secs(1);
+        assert!(watchdog.observe_idle(later).is_none());
+        assert!(watchdog
+            .observe_idle(later + Duration::from_millis(150))
+            .is_some());
+    }
+
+    #[test]
+    fn late_output_blames_sink_and_zero_threshold_disables() {
+        let mut watchdog = PlaybackWatchdog::new(200.0);
+        let events: Vec<_> = (0..4)
+            .flat_map(|step| {
+                watchdog.observe_chunk(&tone(64, step * 64), &tone(64, step * 64), 0.1, true)
+            })
+            .collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].suspected_stage, PlaybackStage::Sink);
+
+        let mut disabled = PlaybackWatchdog::new(0.0);
+        let silent = vec![0.0; 64];
+        for _ in 0..100 {
+            assert!(disabled
+                .observe_chunk(&silent, &silent, 1.0, true)
+                .is_empty());
+        }
+    }
+}
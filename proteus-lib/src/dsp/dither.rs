@@ -0,0 +1,176 @@
+//! TPDF dither and optional noise shaping for reduced bit-depth output.
+//!
+//! The pipeline runs in `f32`, but 16-bit devices and integer exports
+//! truncate to a coarser grid, which turns low-level detail into correlated
+//! distortion. [`Ditherer`] adds triangular (TPDF) noise of one LSB before
+//! quantizing to the target bit depth, and can feed the quantization error
+//! back through a shaping filter to push the noise toward high frequencies.
+//! Output samples stay `f32` but land exactly on the target grid.
+
+/// Noise-shaping filter applied to the quantization error.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoiseShaping {
+    /// Plain TPDF dither with a flat noise spectrum.
+    #[default]
+    None,
+    /// First-order error feedback (+6 dB/octave high-pass noise).
+    FirstOrder,
+    /// Second-order error feedback (+12 dB/octave high-pass noise).
+    SecondOrder,
+}
+
+impl NoiseShaping {
+    fn coefficients(self) -> [f32; 2] {
+        match self {
+            Self::None => [0.0, 0.0],
+            Self::FirstOrder => [1.0, 0.0],
+            Self::SecondOrder => [2.0, -1.0],
+        }
+    }
+}
+
+/// Dither configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DitherSettings {
+    /// Target bit depth, clamped to `2..=24` (the `f32` mantissa width).
+    pub bit_depth: u32,
+    /// Noise-shaping filter.
+    pub shaping: NoiseShaping,
+}
+
+impl DitherSettings {
+    /// TPDF dither to `bit_depth` bits without noise shaping.
+    pub fn new(bit_depth: u32) -> Self {
+        Self {
+            bit_depth,
+            shaping: NoiseShaping::None,
+        }
+    }
+
+    /// Return these settings with `shaping` applied.
+    pub fn with_shaping(mut self, shaping: NoiseShaping) -> Self {
+        self.shaping = shaping;
+        self
+    }
+}
+
+impl Default for DitherSettings {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+/// Stateful dither processor for one interleaved stream.
+#[derive(Debug, Clone)]
+pub struct Ditherer {
+    settings: DitherSettings,
+    scale: f32,
+    errors: Vec<[f32; 2]>,
+    rng: u32,
+}
+
+impl Ditherer {
+    /// Seed used by [`Ditherer::new`]; a fixed seed keeps renders reproducible.
+    pub const DEFAULT_SEED: u32 = 0x9E37_79B9;
+
+    /// Create a ditherer for `channels` interleaved channels.
+    pub fn new(settings: DitherSettings, channels: usize) -> Self {
+        Self::with_seed(settings, channels, Self::DEFAULT_SEED)
+    }
+
+    /// Create a ditherer with an explicit noise seed.
+    pub fn with_seed(settings: DitherSettings, channels: usize, seed: u32) -> Self {
+        let bit_depth = settings.bit_depth.clamp(2, 24);
+        Self {
+            settings,
+            scale: 2.0_f32.powi(bit_depth as i32 - 1),
+            errors: vec![[0.0; 2]; channels.max(1)],
+            rng: seed.max(1),
+        }
+    }
+
+    /// Settings this ditherer was created with.
+    pub fn settings(&self) -> DitherSettings {
+        self.settings
+    }
+
+    /// Dither and quantize interleaved `samples` in place.
+    pub fn process(&mut self, samples: &mut [f32]) {
+        let channels = self.errors.len();
+        let [c1, c2] = self.settings.shaping.coefficients();
+        let max = (self.scale - 1.0) / self.scale;
+        for frame in samples.chunks_mut(channels) {
+            for (sample, error) in frame.iter_mut().zip(&mut self.errors) {
+                let target = *sample * self.scale - (c1 * error[0] + c2 * error[1]);
+                let noise = next_uniform(&mut self.rng) + next_uniform(&mut self.rng);
+                let rounded = (target + noise).round();
+                // Feed back the unclipped error so full-scale input cannot
+                // wind up the shaping filter.
+                error[1] = error[0];
+                error[0] = rounded - target;
+                *sample =
+                    (rounded.clamp(-self.scale, self.scale - 1.0) / self.scale).clamp(-1.0, max);
+            }
+        }
+    }
+}
+
+/// Uniform noise in `[-0.5, 0.5)` LSB from a xorshift generator.
+fn next_uniform(state: &mut u32) -> f32 {
+    let mut x = *state;
+    x ^= x << 13;
+    x ^= x >> 17;
+    x ^= x << 5;
+    *state = x;
+    (x >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_lands_on_target_grid() {
+        let mut ditherer = Ditherer::new(DitherSettings::new(16), 2);
+        let mut samples: Vec<f32> = (0..256).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+        ditherer.process(&mut samples);
+        for sample in samples {
+            let scaled = sample * 32768.0;
+            assert_eq!(scaled, scaled.round());
+        }
+    }
+
+    #[test]
+    fn dither_noise_is_bounded_and_unbiased() {
+        for shaping in [NoiseShaping::None, NoiseShaping::FirstOrder] {
+            let settings = DitherSettings::new(8).with_shaping(shaping);
+            let mut ditherer = Ditherer::new(settings, 1);
+            let input = vec![0.25_f32; 512];
+            let mut output = input.clone();
+            ditherer.process(&mut output);
+            let lsb = 1.0 / 128.0;
+            let limit = if shaping == NoiseShaping::None {
+                1.5
+            } else {
+                3.0
+            };
+            assert!(output
+                .iter()
+                .zip(&input)
+                .all(|(out, inp)| (out - inp).abs() <= limit * lsb));
+            let mean = output.iter().sum::<f32>() / output.len() as f32;
+            assert!((mean - 0.25).abs() < lsb);
+        }
+    }
+
+    #[test]
+    fn same_seed_is_reproducible_and_full_scale_clamps() {
+        let settings = DitherSettings::new(16).with_shaping(NoiseShaping::SecondOrder);
+        let mut first = vec![1.5_f32, -1.5, 0.1, -0.1];
+        let mut second = first.clone();
+        Ditherer::new(settings, 2).process(&mut first);
+        Ditherer::new(settings, 2).process(&mut second);
+        assert_eq!(first, second);
+        assert!(first[0] < 1.0 && first[1] >= -1.0);
+    }
+}
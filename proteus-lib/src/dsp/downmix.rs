@@ -0,0 +1,115 @@
+//! Fold-down previews of the output mix.
+//!
+//! A downmix keeps the channel count of the stream so it can be switched on
+//! and off mid-playback, but replaces the content with what a smaller
+//! playback system would hear: the mono sum on the front pair, or a
+//! surround mix folded to the front pair. Remaining channels are silenced.
+//!
+//! Surround channels are folded with the ITU-R BS.775 Lo/Ro coefficients
+//! (centre and surrounds at −3 dB, LFE dropped) and scaled so full-scale
+//! in-phase input cannot clip. The mono sum averages left and right, so
+//! centred material keeps its level while hard-panned material drops 6 dB.
+
+use std::f32::consts::FRAC_1_SQRT_2;
+
+/// Output fold-down applied before metering and the device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DownmixMode {
+    /// Pass the mix through unchanged.
+    #[default]
+    Off,
+    /// Sum to mono on the front pair, e.g. to check mono compatibility.
+    Mono,
+    /// Fold surround channels into the front pair; stereo passes through.
+    Stereo,
+}
+
+/// Apply `mode` in place to interleaved `samples` with `channels` channels.
+pub fn downmix_in_place(samples: &mut [f32], channels: usize, mode: DownmixMode) {
+    if mode == DownmixMode::Off || channels < 2 {
+        return;
+    }
+    if mode == DownmixMode::Stereo && channels == 2 {
+        return;
+    }
+    let coefficients = stereo_fold(channels);
+    let scale = fold_scale(&coefficients);
+    for frame in samples.chunks_exact_mut(channels) {
+        let (mut left, mut right) = (0.0_f32, 0.0_f32);
+        for (sample, (to_left, to_right)) in frame.iter().zip(&coefficients) {
+            left += sample * to_left;
+            right += sample * to_right;
+        }
+        left *= scale;
+        right *= scale;
+        if mode == DownmixMode::Mono {
+            let mono = 0.5 * (left + right);
+            (left, right) = (mono, mono);
+        }
+        frame.fill(0.0);
+        frame[0] = left;
+        frame[1] = right;
+    }
+}
+
+/// Left/right contribution of each input channel, in WAVE channel order.
+fn stereo_fold(channels: usize) -> Vec<(f32, f32)> {
+    const L: (f32, f32) = (1.0, 0.0);
+    const R: (f32, f32) = (0.0, 1.0);
+    const C: (f32, f32) = (FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+    const LFE: (f32, f32) = (0.0, 0.0);
+    const LS: (f32, f32) = (FRAC_1_SQRT_2, 0.0);
+    const RS: (f32, f32) = (0.0, FRAC_1_SQRT_2);
+    match channels {
+        2 => vec![L, R],
+        3 => vec![L, R, C],
+        4 => vec![L, R, LS, RS],
+        5 => vec![L, R, C, LS, RS],
+        6 => vec![L, R, C, LFE, LS, RS],
+        8 => vec![L, R, C, LFE, LS, RS, LS, RS],
+        _ => (0..channels)
+            .map(|channel| if channel % 2 == 0 { L } else { R })
+            .collect(),
+    }
+}
+
+/// Gain keeping the louder folded side at or below full scale.
+fn fold_scale(coefficients: &[(f32, f32)]) -> f32 {
+    let left: f32 = coefficients.iter().map(|(to_left, _)| to_left).sum();
+    let right: f32 = coefficients.iter().map(|(_, to_right)| to_right).sum();
+    1.0 / left.max(right).max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mono_keeps_centred_material_and_cancels_inverted_material() {
+        let mut samples = vec![0.5, 0.5, 0.8, -0.8, 1.0, 0.0];
+        downmix_in_place(&mut samples, 2, DownmixMode::Mono);
+        assert_eq!(samples, vec![0.5, 0.5, 0.0, 0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn stereo_leaves_stereo_untouched() {
+        let mut samples = vec![0.25, -0.5];
+        downmix_in_place(&mut samples, 2, DownmixMode::Stereo);
+        assert_eq!(samples, vec![0.25, -0.5]);
+    }
+
+    #[test]
+    fn surround_folds_to_the_front_pair_without_clipping() {
+        let mut samples = vec![1.0; 6];
+        downmix_in_place(&mut samples, 6, DownmixMode::Stereo);
+        assert!((samples[0] - 1.0).abs() < 1e-6);
+        assert!((samples[1] - 1.0).abs() < 1e-6);
+        assert_eq!(&samples[2..], &[0.0; 4]);
+
+        let mut centre_only = vec![0.0, 0.0, 1.0, 1.0, 0.0, 0.0];
+        downmix_in_place(&mut centre_only, 6, DownmixMode::Stereo);
+        assert!((centre_only[0] - centre_only[1]).abs() < 1e-6);
+        assert!(centre_only[0] > 0.0 && centre_only[0] < FRAC_1_SQRT_2);
+        assert_eq!(centre_only[3], 0.0);
+    }
+}
@@ -5,12 +5,14 @@ use serde::{Deserialize, Serialize};
 
 use super::core::smoother::ParamSmoother;
 use super::EffectContext;
+use crate::dsp::precision::{narrow, widen, DspFloat};
 
 const DEFAULT_DURATION_MS: u64 = 100;
 const MAX_AMPLITUDE: f32 = 0.8;
 
 /// Serializable settings for the legacy delay-based reverb effect.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DelayReverbSettings {
     /// Length of the feedback delay line in milliseconds.
@@ -44,6 +46,7 @@ impl Default for DelayReverbSettings {
 
 /// Delay reverb effect (feedback delay + mix).
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DelayReverbEffect {
     /// Whether the effect is active; when `false` samples pass through unmodified.
@@ -199,12 +202,11 @@ impl DelayReverbEffect {
     }
 
     fn mix_target(&self) -> f32 {
-        let target = if self.mix > 0.0 {
+        if self.mix > 0.0 {
             self.mix.clamp(0.0, MAX_AMPLITUDE)
         } else {
             self.settings.amplitude()
-        };
-        target
+        }
     }
 
     fn update_mix_smoother(&mut self, context: &EffectContext) {
@@ -237,7 +239,7 @@ impl DelayReverbEffect {
 #[derive(Clone)]
 struct DelayReverbState {
     delay_samples: usize,
-    delay_line: Vec<f32>,
+    delay_line: Vec<DspFloat>,
     write_pos: usize,
 }
 
@@ -263,10 +265,12 @@ impl DelayReverbState {
         }
 
         let delay_len = self.delay_line.len();
+        let amplitude = widen(amplitude);
         for &sample in samples {
+            let sample = widen(sample);
             let delayed = self.delay_line[self.write_pos];
             let output = sample + (delayed * amplitude);
-            out.push(output);
+            out.push(narrow(output));
 
             // Feedback delay for smoother tails.
             self.delay_line[self.write_pos] = sample + (delayed * amplitude);
@@ -292,11 +296,12 @@ impl DelayReverbState {
         let delay_len = self.delay_line.len();
         let channels = channels.max(1);
         for frame in samples.chunks(channels) {
-            let frame_amplitude = amplitude.next();
+            let frame_amplitude = widen(amplitude.next());
             for &sample in frame {
+                let sample = widen(sample);
                 let delayed = self.delay_line[self.write_pos];
                 let output = sample + (delayed * frame_amplitude);
-                out.push(output);
+                out.push(narrow(output));
 
                 self.delay_line[self.write_pos] = sample + (delayed * frame_amplitude);
                 self.write_pos += 1;
@@ -314,10 +319,11 @@ impl DelayReverbState {
 
         let delay_len = self.delay_line.len();
         let mut out = Vec::with_capacity(delay_len);
+        let amplitude = widen(amplitude);
         for _ in 0..delay_len {
             let delayed = self.delay_line[self.write_pos];
             let output = delayed * amplitude;
-            out.push(output);
+            out.push(narrow(output));
 
             // Feed silence to decay the tail.
             self.delay_line[self.write_pos] = delayed * amplitude;
@@ -0,0 +1,314 @@
+//! Head-related impulse response (HRIR) sources for the binaural panner.
+//!
+//! Two sources are supported:
+//! - An embedded structural model (spherical head with a single pinna echo,
+//!   after Brown & Duda) that synthesizes an HRIR for any direction without
+//!   shipping measurement data.
+//! - A user-provided measurement set loaded from JSON. SOFA files can be
+//!   converted to this layout offline; the nearest measured direction is used.
+
+use std::f32::consts::PI;
+use std::fmt;
+use std::path::Path;
+
+use serde::Deserialize;
+
+/// Spherical head radius in metres.
+const HEAD_RADIUS_M: f32 = 0.0875;
+/// Speed of sound in metres per second.
+const SPEED_OF_SOUND_M_S: f32 = 343.0;
+/// Head-shadow coefficient at the most shadowed incidence angle.
+const SHADOW_ALPHA_MIN: f32 = 0.1;
+/// Incidence angle (degrees) at which the head shadow is deepest.
+const SHADOW_THETA_MIN_DEG: f32 = 150.0;
+/// Pinna echo gain relative to the direct path.
+const PINNA_ECHO_GAIN: f32 = 0.5;
+/// Pinna echo delay model constants in samples at 44.1 kHz.
+const PINNA_ECHO_A: f32 = 5.0;
+const PINNA_ECHO_B: f32 = 2.0;
+const PINNA_REFERENCE_RATE: f32 = 44_100.0;
+/// Embedded HRIR length at 48 kHz; scaled with the sample rate.
+const EMBEDDED_HRIR_TAPS_48K: usize = 128;
+const MIN_HRIR_TAPS: usize = 32;
+const MAX_HRIR_TAPS: usize = 1_024;
+
+/// Errors that can occur while loading a user HRIR set.
+#[derive(Debug)]
+pub enum HrirError {
+    /// The HRIR file could not be read.
+    Io(std::io::Error),
+    /// The HRIR file is not valid JSON in the expected layout.
+    Parse(serde_json::Error),
+    /// The HRIR set contained no usable measurements.
+    Empty,
+}
+
+impl fmt::Display for HrirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "failed to read hrir set: {}", err),
+            Self::Parse(err) => write!(f, "failed to parse hrir set: {}", err),
+            Self::Empty => write!(f, "hrir set contains no usable measurements"),
+        }
+    }
+}
+
+impl std::error::Error for HrirError {}
+
+impl From<std::io::Error> for HrirError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for HrirError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Parse(err)
+    }
+}
+
+/// Left/right impulse responses for one direction, at the runtime sample rate.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct HrirPair {
+    pub(super) left: Vec<f32>,
+    pub(super) right: Vec<f32>,
+}
+
+/// One measured direction in a user HRIR set.
+#[derive(Debug, Clone, Deserialize)]
+struct HrirMeasurement {
+    azimuth_deg: f32,
+    elevation_deg: f32,
+    left: Vec<f32>,
+    right: Vec<f32>,
+}
+
+/// A user-provided HRIR measurement set.
+///
+/// Expected JSON layout:
+///
+/// ```json
+/// {"sample_rate": 48000, "measurements": [
+///   {"azimuth_deg": 0.0, "elevation_deg": 0.0, "left": [...], "right": [...]}
+/// ]}
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub(super) struct HrirSet {
+    sample_rate: u32,
+    measurements: Vec<HrirMeasurement>,
+}
+
+impl HrirSet {
+    /// Load and validate an HRIR set from a JSON file.
+    pub(super) fn load(path: &Path) -> Result<Self, HrirError> {
+        let bytes = std::fs::read(path)?;
+        let mut set: HrirSet = serde_json::from_slice(&bytes)?;
+        set.measurements.retain(|m| {
+            m.azimuth_deg.is_finite()
+                && m.elevation_deg.is_finite()
+                && !m.left.is_empty()
+                && !m.right.is_empty()
+        });
+        if set.measurements.is_empty() || set.sample_rate == 0 {
+            return Err(HrirError::Empty);
+        }
+        Ok(set)
+    }
+
+    /// Return the HRIR nearest to the requested direction, resampled to `sample_rate`.
+    pub(super) fn nearest(
+        &self,
+        azimuth_deg: f32,
+        elevation_deg: f32,
+        sample_rate: u32,
+    ) -> HrirPair {
+        let target = direction(azimuth_deg, elevation_deg);
+        let nearest = self
+            .measurements
+            .iter()
+            .max_by(|a, b| {
+                let da = dot(direction(a.azimuth_deg, a.elevation_deg), target);
+                let db = dot(direction(b.azimuth_deg, b.elevation_deg), target);
+                da.total_cmp(&db)
+            })
+            .expect("hrir set is validated to be non-empty");
+        HrirPair {
+            left: resample_linear(&nearest.left, self.sample_rate, sample_rate),
+            right: resample_linear(&nearest.right, self.sample_rate, sample_rate),
+        }
+    }
+}
+
+/// Synthesize an HRIR pair from the embedded spherical-head model.
+pub(super) fn embedded_hrir(azimuth_deg: f32, elevation_deg: f32, sample_rate: u32) -> HrirPair {
+    let taps = (EMBEDDED_HRIR_TAPS_48K as f32 * sample_rate as f32 / 48_000.0).ceil() as usize;
+    let taps = taps.clamp(MIN_HRIR_TAPS, MAX_HRIR_TAPS);
+    let [x, _, _] = direction(azimuth_deg, elevation_deg);
+    let pinna_delay = pinna_delay_samples(azimuth_deg, elevation_deg, sample_rate);
+    HrirPair {
+        left: ear_response(-x, pinna_delay, sample_rate, taps),
+        right: ear_response(x, pinna_delay, sample_rate, taps),
+    }
+}
+
+/// Build one ear's response given the cosine of the source/ear-axis angle.
+fn ear_response(cos_incidence: f32, pinna_delay: f32, sample_rate: u32, taps: usize) -> Vec<f32> {
+    let theta = cos_incidence.clamp(-1.0, 1.0).acos();
+    let head_delay = woodworth_delay_s(theta) * sample_rate as f32;
+
+    let mut impulse = vec![0.0_f32; taps];
+    add_fractional_impulse(&mut impulse, head_delay, 1.0);
+    add_fractional_impulse(&mut impulse, head_delay + pinna_delay, PINNA_ECHO_GAIN);
+    apply_head_shadow(&mut impulse, theta, sample_rate);
+
+    let norm = 1.0 / (1.0 + PINNA_ECHO_GAIN);
+    impulse.iter_mut().for_each(|sample| *sample *= norm);
+    impulse
+}
+
+/// Per-ear arrival delay, offset so the nearest ear arrives at zero.
+fn woodworth_delay_s(theta: f32) -> f32 {
+    let base = HEAD_RADIUS_M / SPEED_OF_SOUND_M_S;
+    if theta < PI / 2.0 {
+        base * (1.0 - theta.cos())
+    } else {
+        base * (1.0 + theta - PI / 2.0)
+    }
+}
+
+/// Apply the one-pole/one-zero head-shadow filter in place.
+fn apply_head_shadow(samples: &mut [f32], theta: f32, sample_rate: u32) {
+    let theta_deg = theta.to_degrees();
+    let alpha = (1.0 + SHADOW_ALPHA_MIN / 2.0)
+        + (1.0 - SHADOW_ALPHA_MIN / 2.0)
+            * (theta_deg / SHADOW_THETA_MIN_DEG * 180.0)
+                .to_radians()
+                .cos();
+    let beta = 2.0 * SPEED_OF_SOUND_M_S / HEAD_RADIUS_M;
+    let k = 2.0 * sample_rate as f32;
+    let b0 = (alpha * k + beta) / (k + beta);
+    let b1 = (beta - alpha * k) / (k + beta);
+    let a1 = (beta - k) / (k + beta);
+    let (mut x1, mut y1) = (0.0_f32, 0.0_f32);
+    for sample in samples {
+        let x = *sample;
+        let y = b0 * x + b1 * x1 - a1 * y1;
+        x1 = x;
+        y1 = y;
+        *sample = y;
+    }
+}
+
+fn pinna_delay_samples(azimuth_deg: f32, elevation_deg: f32, sample_rate: u32) -> f32 {
+    let delay = PINNA_ECHO_A
+        * (azimuth_deg.to_radians() / 2.0).cos()
+        * (90.0 - elevation_deg).to_radians().sin()
+        + PINNA_ECHO_B;
+    delay.abs() * sample_rate as f32 / PINNA_REFERENCE_RATE
+}
+
+fn add_fractional_impulse(buffer: &mut [f32], delay: f32, gain: f32) {
+    let whole = delay.floor().max(0.0) as usize;
+    let frac = delay - delay.floor();
+    if let Some(tap) = buffer.get_mut(whole) {
+        *tap += gain * (1.0 - frac);
+    }
+    if let Some(tap) = buffer.get_mut(whole + 1) {
+        *tap += gain * frac;
+    }
+}
+
+/// Unit vector for a direction: x = right, y = front, z = up.
+fn direction(azimuth_deg: f32, elevation_deg: f32) -> [f32; 3] {
+    let (az, el) = (azimuth_deg.to_radians(), elevation_deg.to_radians());
+    [el.cos() * az.sin(), el.cos() * az.cos(), el.sin()]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn resample_linear(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.len() < 2 {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f32 / to_rate as f32;
+    let len = ((samples.len() as f32 / ratio).ceil() as usize).max(1);
+    // Preserve the broadband level of the response across rate changes.
+    let gain = ratio.min(1.0);
+    (0..len)
+        .map(|index| {
+            let position = index as f32 * ratio;
+            let base = position.floor() as usize;
+            let frac = position - base as f32;
+            let a = samples.get(base).copied().unwrap_or(0.0);
+            let b = samples.get(base + 1).copied().unwrap_or(0.0);
+            (a + (b - a) * frac) * gain
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum()
+    }
+
+    fn first_peak(samples: &[f32]) -> usize {
+        samples
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    }
+
+    #[test]
+    fn embedded_hrir_right_source_is_louder_and_earlier_on_right() {
+        let pair = embedded_hrir(90.0, 0.0, 48_000);
+        assert!(energy(&pair.right) > energy(&pair.left));
+        assert!(first_peak(&pair.right) < first_peak(&pair.left));
+    }
+
+    #[test]
+    fn embedded_hrir_front_source_is_symmetric() {
+        let pair = embedded_hrir(0.0, 0.0, 48_000);
+        for (l, r) in pair.left.iter().zip(&pair.right) {
+            assert!((l - r).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn hrir_set_picks_nearest_measurement() {
+        let set = HrirSet {
+            sample_rate: 48_000,
+            measurements: vec![
+                HrirMeasurement {
+                    azimuth_deg: -90.0,
+                    elevation_deg: 0.0,
+                    left: vec![1.0],
+                    right: vec![0.1],
+                },
+                HrirMeasurement {
+                    azimuth_deg: 90.0,
+                    elevation_deg: 0.0,
+                    left: vec![0.1],
+                    right: vec![1.0],
+                },
+            ],
+        };
+        let pair = set.nearest(70.0, 10.0, 48_000);
+        assert_eq!(pair.right, vec![1.0]);
+    }
+
+    #[test]
+    fn hrir_set_load_rejects_empty_set() {
+        let path = std::env::temp_dir().join("proteus_hrir_empty_test.json");
+        std::fs::write(&path, r#"{"sample_rate":48000,"measurements":[]}"#).unwrap();
+        let result = HrirSet::load(&path);
+        let _ = std::fs::remove_file(&path);
+        assert!(matches!(result, Err(HrirError::Empty)));
+    }
+}
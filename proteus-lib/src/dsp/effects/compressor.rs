@@ -3,6 +3,7 @@
 use serde::{Deserialize, Serialize};
 
 use super::core::level::deserialize_db_gain;
+use super::core::smoother::ParamSmoother;
 use super::EffectContext;
 use crate::dsp::guardrails::{
     sanitize_channels, sanitize_finite, sanitize_finite_max, sanitize_finite_min,
@@ -16,6 +17,7 @@ const DEFAULT_MAKEUP_DB: f32 = 0.0;
 
 /// Serialized configuration for compressor parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct CompressorSettings {
     /// Signal level above which compression is applied, in dBFS.
@@ -24,6 +26,10 @@ pub struct CompressorSettings {
         alias = "threshold_db",
         deserialize_with = "deserialize_db_gain"
     )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub threshold_db: f32,
     /// Compression ratio; e.g. 4.0 means a 4:1 ratio above the threshold.
     pub ratio: f32,
@@ -40,6 +46,10 @@ pub struct CompressorSettings {
         alias = "makeup_gain_db",
         deserialize_with = "deserialize_db_gain"
     )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub makeup_gain_db: f32,
 }
 
@@ -76,6 +86,7 @@ impl Default for CompressorSettings {
 
 /// Configured compressor effect with runtime state.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct CompressorEffect {
     /// Whether the compressor is active; when `false` samples pass through unmodified.
@@ -115,16 +126,7 @@ impl super::core::DspEffect for CompressorEffect {
         let mut output = Vec::with_capacity(samples.len());
 
         for frame in samples.chunks(channels) {
-            let mut peak = 0.0_f32;
-            for &sample in frame {
-                peak = peak.max(sample.abs());
-            }
-
-            let level_db = rodio::math::linear_to_db(peak);
-            let target_gain_db = compute_gain_db(level_db, state.threshold_db, state.ratio);
-            state.update_gain(target_gain_db);
-            let gain = rodio::math::db_to_linear(state.current_gain_db + state.makeup_gain_db);
-
+            let gain = state.next_frame_gain(frame);
             for &sample in frame {
                 output.push(sample * gain);
             }
@@ -154,14 +156,7 @@ impl super::core::DspEffect for CompressorEffect {
         }
         let channels = state.channels;
         for frame in input.chunks(channels) {
-            let mut peak = 0.0_f32;
-            for &sample in frame {
-                peak = peak.max(sample.abs());
-            }
-            let level_db = rodio::math::linear_to_db(peak);
-            let target_gain_db = compute_gain_db(level_db, state.threshold_db, state.ratio);
-            state.update_gain(target_gain_db);
-            let gain = rodio::math::db_to_linear(state.current_gain_db + state.makeup_gain_db);
+            let gain = state.next_frame_gain(frame);
             for &sample in frame {
                 output.push(sample * gain);
             }
@@ -194,6 +189,7 @@ impl CompressorEffect {
             attack_ms,
             release_ms,
             makeup_gain_db,
+            ramp_samples: context.parameter_ramp_samples(),
         };
         if let Some(state) = self.state.as_mut() {
             if state.matches_structure(&params) {
@@ -215,17 +211,18 @@ struct CompressorParams {
     attack_ms: f32,
     release_ms: f32,
     makeup_gain_db: f32,
+    ramp_samples: usize,
 }
 
 #[derive(Clone, Debug)]
 struct CompressorState {
     sample_rate: u32,
     channels: usize,
-    threshold_db: f32,
-    ratio: f32,
+    threshold_db: ParamSmoother,
+    ratio: ParamSmoother,
     attack_coeff: f32,
     release_coeff: f32,
-    makeup_gain_db: f32,
+    makeup_gain_db: ParamSmoother,
     current_gain_db: f32,
 }
 
@@ -236,11 +233,11 @@ impl CompressorState {
         Self {
             sample_rate: params.sample_rate,
             channels: params.channels,
-            threshold_db: params.threshold_db,
-            ratio: params.ratio,
+            threshold_db: ParamSmoother::new(params.threshold_db),
+            ratio: ParamSmoother::new(params.ratio),
             attack_coeff,
             release_coeff,
-            makeup_gain_db: params.makeup_gain_db,
+            makeup_gain_db: ParamSmoother::new(params.makeup_gain_db),
             current_gain_db: 0.0,
         }
     }
@@ -249,12 +246,34 @@ impl CompressorState {
         self.sample_rate == params.sample_rate && self.channels == params.channels
     }
 
+    /// Retarget threshold, ratio and makeup; they ramp over `ramp_samples`
+    /// frames so a change mid-stream does not step the output gain.
     fn update_parameters(&mut self, params: &CompressorParams) {
-        self.threshold_db = params.threshold_db;
-        self.ratio = params.ratio;
+        for (smoother, target) in [
+            (&mut self.threshold_db, params.threshold_db),
+            (&mut self.ratio, params.ratio),
+            (&mut self.makeup_gain_db, params.makeup_gain_db),
+        ] {
+            if (smoother.target() - target).abs() > f32::EPSILON {
+                smoother.set_target(target, params.ramp_samples);
+            }
+        }
         self.attack_coeff = time_to_coeff(params.attack_ms, params.sample_rate);
         self.release_coeff = time_to_coeff(params.release_ms, params.sample_rate);
-        self.makeup_gain_db = params.makeup_gain_db;
+    }
+
+    /// Advance the detector and parameter ramps by one frame and return the
+    /// linear gain to apply to it.
+    fn next_frame_gain(&mut self, frame: &[f32]) -> f32 {
+        let peak = frame
+            .iter()
+            .fold(0.0_f32, |peak, sample| peak.max(sample.abs()));
+        let level_db = rodio::math::linear_to_db(peak);
+        let threshold_db = self.threshold_db.next();
+        let ratio = self.ratio.next();
+        let target_gain_db = compute_gain_db(level_db, threshold_db, ratio);
+        self.update_gain(target_gain_db);
+        rodio::math::db_to_linear(self.current_gain_db + self.makeup_gain_db.next())
     }
 
     fn update_gain(&mut self, target_gain_db: f32) {
@@ -271,7 +290,8 @@ impl CompressorState {
     }
 }
 
-fn compute_gain_db(level_db: f32, threshold_db: f32, ratio: f32) -> f32 {
+/// Static gain computer: dB of reduction for a detected level above `threshold_db`.
+pub(super) fn compute_gain_db(level_db: f32, threshold_db: f32, ratio: f32) -> f32 {
     if level_db <= threshold_db {
         0.0
     } else {
@@ -280,7 +300,8 @@ fn compute_gain_db(level_db: f32, threshold_db: f32, ratio: f32) -> f32 {
     }
 }
 
-fn time_to_coeff(time_ms: f32, sample_rate: u32) -> f32 {
+/// One-pole smoothing coefficient for an attack/release time in milliseconds.
+pub(super) fn time_to_coeff(time_ms: f32, sample_rate: u32) -> f32 {
     if time_ms <= 0.0 || !time_ms.is_finite() {
         return 0.0;
     }
@@ -311,8 +332,10 @@ mod tests {
 
     #[test]
     fn compressor_applies_gain_reduction() {
-        let mut effect = CompressorEffect::default();
-        effect.enabled = true;
+        let mut effect = CompressorEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.threshold_db = -6.0;
         effect.settings.ratio = 2.0;
         effect.settings.attack_ms = 0.0;
@@ -366,8 +389,10 @@ mod tests {
 
     #[test]
     fn threshold_change_preserves_gain_envelope() {
-        let mut effect = CompressorEffect::default();
-        effect.enabled = true;
+        let mut effect = CompressorEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.threshold_db = -24.0;
         effect.settings.ratio = 4.0;
         effect.settings.attack_ms = 0.0;
@@ -402,8 +427,10 @@ mod tests {
 
     #[test]
     fn attack_and_release_changes_recompute_coefficients_without_gain_jump() {
-        let mut effect = CompressorEffect::default();
-        effect.enabled = true;
+        let mut effect = CompressorEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.threshold_db = -18.0;
         effect.settings.ratio = 4.0;
         effect.settings.attack_ms = 10.0;
@@ -432,4 +459,27 @@ mod tests {
         assert!((state.attack_coeff - attack_before).abs() > 1e-6);
         assert!((state.release_coeff - release_before).abs() > 1e-6);
     }
+
+    #[test]
+    fn makeup_change_ramps_instead_of_stepping() {
+        let mut effect = CompressorEffect {
+            enabled: true,
+            ..Default::default()
+        };
+        effect.settings.threshold_db = 0.0;
+        effect.settings.makeup_gain_db = 0.0;
+
+        let mut context = context(1);
+        context.set_parameter_ramp_ms(1.0);
+        let quiet = vec![0.1_f32; 96];
+        let _ = effect.process(&quiet, &context, false);
+
+        effect.settings.makeup_gain_db = 12.0;
+        let output = effect.process(&quiet, &context, false);
+
+        let target = 0.1 * rodio::math::db_to_linear(12.0);
+        assert!(output[0] < target * 0.5);
+        assert!(output.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert!(approx_eq(*output.last().unwrap(), target, 1e-4));
+    }
 }
@@ -0,0 +1,126 @@
+//! Conformance checks run over every [`AudioEffect`] variant.
+//!
+//! These pin the block contract documented on `DspEffect::process`: a
+//! non-draining call returns exactly as many samples as it was given, and
+//! anything an effect still holds comes out only when drained.
+
+use super::{AudioEffect, EffectContext};
+
+const CHANNELS: usize = 2;
+
+/// Chunk sizes covering empty, single-frame, odd and larger-than-block input.
+const CHUNK_FRAMES: [usize; 6] = [0, 1, 7, 64, 1_000, 4_800];
+
+/// Every variant, enabled with its default settings.
+fn enabled_effects() -> Vec<AudioEffect> {
+    let names = [
+        "DelayReverbSettings",
+        "DelayEchoSettings",
+        "DiffusionReverbSettings",
+        "ShimmerReverbSettings",
+        "ConvolutionReverbSettings",
+        "LowPassFilterSettings",
+        "HighPassFilterSettings",
+        "DistortionSettings",
+        "SaturationSettings",
+        "GainSettings",
+        "CompressorSettings",
+        "LimiterSettings",
+        "MultibandEqSettings",
+        "DynamicEqSettings",
+        "PanSettings",
+    ];
+    names
+        .iter()
+        .map(|name| {
+            let json = format!(r#"{{"{}":{{"enabled":true}}}}"#, name);
+            serde_json::from_str(&json).unwrap_or_else(|err| panic!("{}: {}", name, err))
+        })
+        .collect()
+}
+
+fn context() -> EffectContext {
+    EffectContext::new(48_000, CHANNELS, None, None, -60.0).unwrap()
+}
+
+fn signal(frames: usize, offset: usize) -> Vec<f32> {
+    (0..frames * CHANNELS)
+        .map(|index| {
+            let phase = (offset + index / CHANNELS) as f32 * 0.05;
+            0.5 * phase.sin()
+        })
+        .collect()
+}
+
+#[test]
+fn every_variant_returns_one_sample_per_input_sample() {
+    let context = context();
+    for mut effect in enabled_effects() {
+        let mut offset = 0;
+        for frames in CHUNK_FRAMES {
+            let input = signal(frames, offset);
+            offset += frames;
+            let output = effect.process(&input, &context, false);
+            assert_eq!(
+                output.len(),
+                input.len(),
+                "{} returned the wrong length for {} frames",
+                effect.display_name(),
+                frames
+            );
+        }
+    }
+}
+
+#[test]
+fn process_into_appends_exactly_the_input_length() {
+    let context = context();
+    for mut effect in enabled_effects() {
+        let mut output = vec![1.0; 3];
+        let mut offset = 0;
+        for frames in CHUNK_FRAMES {
+            let input = signal(frames, offset);
+            offset += frames;
+            let before = output.len();
+            effect.process_into(&input, &mut output, &context, false);
+            assert_eq!(
+                output.len() - before,
+                input.len(),
+                "{} appended the wrong length for {} frames",
+                effect.display_name(),
+                frames
+            );
+        }
+        assert_eq!(&output[..3], &[1.0; 3], "{}", effect.display_name());
+    }
+}
+
+#[test]
+fn drained_tail_is_whole_frames_and_finite() {
+    let context = context();
+    for mut effect in enabled_effects() {
+        let _ = effect.process(&signal(4_800, 0), &context, false);
+        let tail = effect.process(&[], &context, true);
+        assert_eq!(tail.len() % CHANNELS, 0, "{}", effect.display_name());
+        assert!(
+            tail.iter().all(|sample| sample.is_finite()),
+            "{}",
+            effect.display_name()
+        );
+    }
+}
+
+#[test]
+fn every_variant_is_covered() {
+    let covered: Vec<&str> = enabled_effects()
+        .iter()
+        .map(AudioEffect::display_name)
+        .collect();
+    let mut unique = covered.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), covered.len());
+    // Keep in step with `define_audio_effects!`; a new variant must be added
+    // to `enabled_effects` above.
+    assert_eq!(covered.len(), 15);
+}
@@ -1,380 +1,390 @@
 //! Convolution engine used by reverb and offline processing.
 //!
-//! Two implementations are provided:
-//! - `real_fft` (default / feature `real-fft`): real FFT using `realfft`.
-//! - `complex_fft` (feature `complex-fft`): full complex FFT using `rustfft`.
+//! Transforms come from the build's [`crate::dsp::fft`] backend, so the
+//! convolver works on half spectra with `real-fft` and full spectra with
+//! `complex-fft` without knowing which.
 //!
-//! Note: when both features are enabled, `real_fft` is used.
-
-#[cfg(all(feature = "complex-fft", not(feature = "real-fft")))]
-mod complex_fft {
-    use std::collections::VecDeque;
-    use std::sync::Arc;
-
-    use rustfft::{num_complex::Complex, Fft, FftPlanner};
-
-    // Taken from https://github.com/BordenJardine/reverb_vst
-
-    /// Overlap-add convolver based on complex FFTs.
-    #[derive(Clone)]
-    pub struct Convolver {
-        pub fft_size: usize,
-        ir_segments: Vec<Vec<Complex<f32>>>,
-        previous_frame_q: VecDeque<Vec<Complex<f32>>>,
-        pub previous_tail: Vec<f32>,
-        pending_output: Vec<f32>,
-        fft_processor: Arc<dyn Fft<f32>>,
-        ifft_processor: Arc<dyn Fft<f32>>,
-    }
-
-    impl Convolver {
-        /// Create a new convolver for a single-channel impulse response.
-        pub fn new(ir_signal: &[f32], fft_size: usize) -> Self {
-            let mut planner = FftPlanner::<f32>::new();
-            let fft_processor = planner.plan_fft_forward(fft_size);
-            let ifft_processor = planner.plan_fft_inverse(fft_size);
-
-            let ir_segments = segment_buffer(ir_signal, fft_size, &fft_processor);
-            let segment_count = ir_segments.len();
-            Self {
-                fft_size,
-                ir_segments,
-                fft_processor,
-                ifft_processor,
-                previous_frame_q: init_previous_frame_q(segment_count, fft_size),
-                previous_tail: init_previous_tail(fft_size / 2),
-                pending_output: Vec::new(),
-            }
-        }
-
-        /// Process a block of input samples and return the convolved output.
-        ///
-        /// The output length matches the input length. Internal tails are
-        /// preserved between calls.
-        pub fn process(&mut self, input_buffer: &[f32]) -> Vec<f32> {
-            let io_len = input_buffer.len();
-            let segment_size = self.fft_size / 2;
-            let input_segments = segment_buffer(input_buffer, self.fft_size, &self.fft_processor);
-
-            let mut output: Vec<f32> = Vec::with_capacity(io_len);
-            let norm = self.fft_size as f32;
-
-            if !self.pending_output.is_empty() {
-                let take = io_len.min(self.pending_output.len());
-                output.extend_from_slice(&self.pending_output[..take]);
-                self.pending_output.drain(0..take);
-            }
+//! Partition spectra are accumulated through [`accumulate_partitions`], which
+//! can spread the partitions of a long impulse response over the jobs of a
+//! [`ConvolutionPool`]. With the `gpu` feature a block may instead run
+//! entirely on the GPU, which hands the history back when the CPU takes over.
+
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::sync::Arc;
+
+use log::error;
+
+#[cfg(feature = "gpu")]
+use super::gpu::{GpuBlock, GpuSlot};
+use super::pool::ConvolutionPool;
+use crate::dsp::fft::{plan_real, Complex, RealFft};
+use crate::dsp::precision::{narrow, widen, DspFloat};
+
+/// Fewest partitions worth handing to a worker thread of their own.
+const MIN_PARTITIONS_PER_WORKER: usize = 4;
+
+/// Number of workers [`accumulate_partitions`] will actually use for
+/// `partitions` partitions when asked for `requested`.
+pub(crate) fn effective_partition_workers(partitions: usize, requested: usize) -> usize {
+    requested.min(partitions / MIN_PARTITIONS_PER_WORKER).max(1)
+}
 
-            for segment in input_segments {
-                self.previous_frame_q.push_front(segment);
-                self.previous_frame_q.pop_back();
+/// Partition history of a convolver, newest frame first.
+type FrameQueue = VecDeque<Vec<Complex<DspFloat>>>;
 
-                let mut convolved = self.convolve_frame();
-                self.ifft_processor.process(&mut convolved);
+/// Contiguous range of partitions summed by one pool job.
+struct PartitionJob {
+    frames: Arc<FrameQueue>,
+    ir_segments: Arc<Vec<Vec<Complex<DspFloat>>>>,
+    range: Range<usize>,
+    spectrum_len: usize,
+}
 
-                let mut time_domain: Vec<f32> = Vec::with_capacity(self.fft_size);
-                for sample in convolved {
-                    time_domain.push(sample.re / norm);
-                }
+impl PartitionJob {
+    fn sum(&mut self) -> Vec<Complex<DspFloat>> {
+        sum_partitions(
+            &self.frames,
+            &self.ir_segments,
+            self.range.clone(),
+            self.spectrum_len,
+        )
+    }
+}
 
-                for i in 0..segment_size {
-                    if let Some(sample) = time_domain.get_mut(i) {
-                        *sample += self.previous_tail[i];
-                    }
-                }
+/// Sum `frames[i] * ir_segments[i]` over `range` into a new spectrum.
+fn sum_partitions(
+    frames: &FrameQueue,
+    ir_segments: &[Vec<Complex<DspFloat>>],
+    range: Range<usize>,
+    spectrum_len: usize,
+) -> Vec<Complex<DspFloat>> {
+    let mut acc = vec![Complex { re: 0.0, im: 0.0 }; spectrum_len];
+    for index in range {
+        multiply_accumulate(&mut acc, &frames[index], &ir_segments[index]);
+    }
+    acc
+}
 
-                self.previous_tail = time_domain[segment_size..self.fft_size].to_vec();
-                let remaining = io_len.saturating_sub(output.len());
-                if remaining == 0 {
-                    self.pending_output
-                        .extend_from_slice(&time_domain[0..segment_size]);
-                    continue;
-                }
-                if remaining >= segment_size {
-                    output.extend_from_slice(&time_domain[0..segment_size]);
-                } else {
-                    output.extend_from_slice(&time_domain[0..remaining]);
-                    self.pending_output
-                        .extend_from_slice(&time_domain[remaining..segment_size]);
+/// Sum `frames[i] * ir_segments[i]` over every partition into a new spectrum.
+///
+/// With a pool and more than one worker the partitions are split into
+/// contiguous ranges, each summed as its own pool job, and the partial
+/// spectra are added back in range order, so a given worker count always
+/// produces the same output. A range whose job failed is summed inline.
+fn accumulate_partitions(
+    frames: &Arc<FrameQueue>,
+    ir_segments: &Arc<Vec<Vec<Complex<DspFloat>>>>,
+    spectrum_len: usize,
+    pool: Option<&ConvolutionPool>,
+    workers: usize,
+) -> Vec<Complex<DspFloat>> {
+    let partitions = ir_segments.len().min(frames.len());
+    let workers = effective_partition_workers(partitions, workers);
+    let Some(pool) = pool.filter(|_| workers > 1) else {
+        return sum_partitions(frames, ir_segments, 0..partitions, spectrum_len);
+    };
+
+    let per_worker = partitions.div_ceil(workers);
+    let jobs = (0..partitions)
+        .step_by(per_worker)
+        .map(|start| PartitionJob {
+            frames: frames.clone(),
+            ir_segments: ir_segments.clone(),
+            range: start..(start + per_worker).min(partitions),
+            spectrum_len,
+        })
+        .collect();
+
+    let mut acc: Option<Vec<Complex<DspFloat>>> = None;
+    for finished in pool.run_batch(jobs, PartitionJob::sum) {
+        let mut job = finished.job;
+        let partial = finished.output.unwrap_or_else(|| {
+            error!(
+                "convolution worker failed on partitions {:?}; summing them inline",
+                job.range
+            );
+            job.sum()
+        });
+        match acc.as_mut() {
+            None => acc = Some(partial),
+            Some(acc) => {
+                for (acc_sample, sample) in acc.iter_mut().zip(partial) {
+                    *acc_sample += sample;
                 }
             }
-
-            output
         }
+    }
+    acc.unwrap_or_default()
+}
 
-        /// Reset internal FFT history and tail buffers.
-        pub fn clear_state(&mut self) {
-            for frame in &mut self.previous_frame_q {
-                for sample in frame.iter_mut() {
-                    sample.re = 0.0;
-                    sample.im = 0.0;
-                }
-            }
-            self.previous_tail.fill(0.0);
-            self.pending_output.clear();
-        }
+/// In-place complex multiply-accumulate: `acc += lhs * rhs`.
+fn multiply_accumulate(
+    acc: &mut [Complex<DspFloat>],
+    lhs: &[Complex<DspFloat>],
+    rhs: &[Complex<DspFloat>],
+) {
+    for ((acc_sample, lhs_sample), rhs_sample) in acc.iter_mut().zip(lhs).zip(rhs) {
+        let re = (lhs_sample.re * rhs_sample.re) - (lhs_sample.im * rhs_sample.im);
+        let im = (lhs_sample.im * rhs_sample.re) + (lhs_sample.re * rhs_sample.im);
+        acc_sample.re += re;
+        acc_sample.im += im;
+    }
+}
 
-        fn convolve_frame(&mut self) -> Vec<Complex<f32>> {
-            let mut convolved = vec![Complex { re: 0.0, im: 0.0 }; self.fft_size];
+/// Overlap-add convolver over uniformly partitioned FFT spectra.
+#[derive(Clone)]
+pub struct Convolver {
+    /// FFT size used for each overlap-add block, in samples.
+    pub fft_size: usize,
+    ir_segments: Arc<Vec<Vec<Complex<DspFloat>>>>,
+    previous_frame_q: Arc<FrameQueue>,
+    /// Overlap tail from the previous block, added to the current output.
+    pub previous_tail: Vec<DspFloat>,
+    pending_output: Vec<f32>,
+    fft: Arc<dyn RealFft>,
+    #[cfg(feature = "gpu")]
+    gpu: GpuSlot,
+}
 
-            for i in 0..self.ir_segments.len() {
-                multiply_accumulate(
-                    &mut convolved,
-                    &self.previous_frame_q[i],
-                    &self.ir_segments[i],
-                );
-            }
-            convolved
+impl Convolver {
+    /// Create a new convolver for a single-channel impulse response.
+    pub fn new(ir_signal: &[f32], fft_size: usize) -> Self {
+        let fft = plan_real(fft_size);
+        let ir_segments = Arc::new(segment_buffer(ir_signal, fft.as_ref()));
+        let previous_frame_q =
+            Arc::new(init_previous_frame_q(ir_segments.len(), fft.spectrum_len()));
+        Self {
+            fft_size,
+            ir_segments,
+            previous_frame_q,
+            previous_tail: vec![0.0; fft_size / 2],
+            pending_output: Vec::new(),
+            fft,
+            #[cfg(feature = "gpu")]
+            gpu: GpuSlot::default(),
         }
     }
 
-    /// In-place complex multiply-accumulate: `acc += lhs * rhs`.
-    fn multiply_accumulate(acc: &mut [Complex<f32>], lhs: &[Complex<f32>], rhs: &[Complex<f32>]) {
-        for ((acc_sample, lhs_sample), rhs_sample) in acc.iter_mut().zip(lhs).zip(rhs) {
-            let re = (lhs_sample.re * rhs_sample.re) - (lhs_sample.im * rhs_sample.im);
-            let im = (lhs_sample.im * rhs_sample.re) + (lhs_sample.re * rhs_sample.im);
-            acc_sample.re += re;
-            acc_sample.im += im;
-        }
+    /// Process a block of input samples and return the convolved output.
+    ///
+    /// The output length matches the input length. Internal tails are
+    /// preserved between calls.
+    pub fn process(&mut self, input_buffer: &[f32]) -> Vec<f32> {
+        self.process_with_pool(input_buffer, None, 1)
     }
 
-    /// Initialize a zeroed overlap-add tail of the given size.
-    fn init_previous_tail(size: usize) -> Vec<f32> {
-        let mut tail = Vec::new();
-        for _ in 0..size {
-            tail.push(0.0);
+    /// Like [`Self::process`], spreading the partition sums over up to
+    /// `workers` jobs on `pool`.
+    pub(crate) fn process_with_pool(
+        &mut self,
+        input_buffer: &[f32],
+        pool: Option<&ConvolutionPool>,
+        workers: usize,
+    ) -> Vec<f32> {
+        let io_len = input_buffer.len();
+        let segment_size = self.fft_size / 2;
+        let segments = split_segments(input_buffer, segment_size);
+        let frames = self.convolve_segments(&segments, pool, workers);
+
+        let mut output: Vec<f32> = Vec::with_capacity(io_len);
+        let norm = self.fft_size as DspFloat;
+
+        if !self.pending_output.is_empty() {
+            let take = io_len.min(self.pending_output.len());
+            output.extend_from_slice(&self.pending_output[..take]);
+            self.pending_output.drain(0..take);
         }
-        tail
-    }
 
-    /// Segment a time-domain buffer into FFT-sized spectra.
-    fn segment_buffer(
-        buffer: &[f32],
-        fft_size: usize,
-        fft_processor: &Arc<dyn Fft<f32>>,
-    ) -> Vec<Vec<Complex<f32>>> {
-        let mut segments = Vec::new();
-        let segment_size = fft_size / 2;
-
-        let mut index = 0;
-        while index < buffer.len() {
-            let mut new_segment: Vec<Complex<f32>> = Vec::new();
-            for i in index..index + segment_size {
-                match buffer.get(i) {
-                    Some(sample) => new_segment.push(Complex {
-                        re: *sample,
-                        im: 0.0,
-                    }),
-                    None => continue,
-                }
-            }
-            while new_segment.len() < fft_size {
-                new_segment.push(Complex { re: 0.0, im: 0.0 });
+        for mut time_domain in frames {
+            for sample in &mut time_domain {
+                *sample /= norm;
             }
-            fft_processor.process(&mut new_segment);
-            segments.push(new_segment);
-            index += segment_size;
-        }
 
-        segments
-    }
+            for (sample, tail) in time_domain
+                .iter_mut()
+                .take(segment_size)
+                .zip(self.previous_tail.iter().take(segment_size))
+            {
+                *sample += *tail;
+            }
 
-    /// Build a queue of empty spectrum frames for overlap-add history.
-    fn init_previous_frame_q(segment_count: usize, fft_size: usize) -> VecDeque<Vec<Complex<f32>>> {
-        let mut q = VecDeque::new();
-        for _ in 0..segment_count {
-            let mut empty = Vec::new();
-            for _ in 0..fft_size {
-                empty.push(Complex { re: 0.0, im: 0.0 });
+            self.previous_tail = time_domain[segment_size..self.fft_size].to_vec();
+            let remaining = io_len.saturating_sub(output.len());
+            if remaining == 0 {
+                self.pending_output.extend(
+                    time_domain[0..segment_size]
+                        .iter()
+                        .map(|&sample| narrow(sample)),
+                );
+                continue;
+            }
+            if remaining >= segment_size {
+                output.extend(
+                    time_domain[0..segment_size]
+                        .iter()
+                        .map(|&sample| narrow(sample)),
+                );
+            } else {
+                output.extend(
+                    time_domain[0..remaining]
+                        .iter()
+                        .map(|&sample| narrow(sample)),
+                );
+                self.pending_output.extend(
+                    time_domain[remaining..segment_size]
+                        .iter()
+                        .map(|&sample| narrow(sample)),
+                );
             }
-            q.push_back(empty);
         }
-        q
+
+        output
     }
-}
 
-#[cfg(feature = "real-fft")]
-mod real_fft {
-    use std::collections::VecDeque;
-    use std::sync::Arc;
-
-    use log::error;
-    use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
-
-    /// Overlap-add convolver based on real FFTs.
-    #[derive(Clone)]
-    pub struct Convolver {
-        /// FFT size used for each overlap-add block, in samples.
-        pub fft_size: usize,
-        ir_segments: Vec<Vec<Complex<f32>>>,
-        previous_frame_q: VecDeque<Vec<Complex<f32>>>,
-        /// Overlap tail from the previous block, added to the current output.
-        pub previous_tail: Vec<f32>,
-        pending_output: Vec<f32>,
-        r2c: Arc<dyn RealToComplex<f32>>,
-        c2r: Arc<dyn ComplexToReal<f32>>,
+    /// Number of uniform partitions the impulse response is split into.
+    pub fn partitions(&self) -> usize {
+        self.ir_segments.len()
     }
 
-    impl Convolver {
-        /// Create a new convolver for a single-channel impulse response.
-        pub fn new(ir_signal: &[f32], fft_size: usize) -> Self {
-            let mut planner = RealFftPlanner::<f32>::new();
-            let r2c = planner.plan_fft_forward(fft_size);
-            let c2r = planner.plan_fft_inverse(fft_size);
-            let spectrum_len = (fft_size / 2) + 1;
-
-            let ir_segments = segment_buffer(ir_signal, fft_size, &r2c, spectrum_len);
-            let segment_count = ir_segments.len();
-            Self {
-                fft_size,
-                ir_segments,
-                r2c,
-                c2r,
-                previous_frame_q: init_previous_frame_q(segment_count, spectrum_len),
-                previous_tail: init_previous_tail(fft_size / 2),
-                pending_output: Vec::new(),
-            }
+    /// Run whole frames on the GPU when `enabled`, falling back to the CPU
+    /// if no adapter is available.
+    #[cfg(feature = "gpu")]
+    pub fn set_gpu(&mut self, enabled: bool) {
+        if let Some(history) = self.gpu.set_enabled(enabled) {
+            self.restore_history(&history);
         }
+    }
 
-        /// Process a block of input samples and return the convolved output.
-        ///
-        /// The output length matches the input length. Internal tails are
-        /// preserved between calls.
-        pub fn process(&mut self, input_buffer: &[f32]) -> Vec<f32> {
-            let io_len = input_buffer.len();
-            let segment_size = self.fft_size / 2;
-            let spectrum_len = self.ir_segments.first().map(|seg| seg.len()).unwrap_or(0);
-            let input_segments =
-                segment_buffer(input_buffer, self.fft_size, &self.r2c, spectrum_len);
-
-            let mut output: Vec<f32> = Vec::with_capacity(io_len);
-            let norm = self.fft_size as f32;
-            let spectrum_len = self.ir_segments.first().map(|seg| seg.len()).unwrap_or(0);
-
-            if !self.pending_output.is_empty() {
-                let take = io_len.min(self.pending_output.len());
-                output.extend_from_slice(&self.pending_output[..take]);
-                self.pending_output.drain(0..take);
-            }
-
-            for segment in input_segments {
-                self.previous_frame_q.push_front(segment);
-                self.previous_frame_q.pop_back();
-
-                let mut convolved = vec![Complex { re: 0.0, im: 0.0 }; spectrum_len];
-                for (prev, ir) in self.previous_frame_q.iter().zip(self.ir_segments.iter()) {
-                    multiply_accumulate(&mut convolved, prev, ir);
-                }
-
-                let mut time_domain = vec![0.0_f32; self.fft_size];
-                if let Err(err) = self.c2r.process(&mut convolved, &mut time_domain) {
-                    error!("real IFFT failed in convolver process: {}", err);
-                    continue;
-                }
-
-                for sample in &mut time_domain {
-                    *sample /= norm;
-                }
-
-                for (sample, tail) in time_domain
-                    .iter_mut()
-                    .take(segment_size)
-                    .zip(self.previous_tail.iter().take(segment_size))
-                {
-                    *sample += *tail;
-                }
+    /// Whether the last block ran on the GPU.
+    #[cfg(feature = "gpu")]
+    pub fn gpu_active(&self) -> bool {
+        self.gpu.active()
+    }
 
-                self.previous_tail = time_domain[segment_size..self.fft_size].to_vec();
-                let remaining = io_len.saturating_sub(output.len());
-                if remaining == 0 {
-                    self.pending_output
-                        .extend_from_slice(&time_domain[0..segment_size]);
-                    continue;
-                }
-                if remaining >= segment_size {
-                    output.extend_from_slice(&time_domain[0..segment_size]);
-                } else {
-                    output.extend_from_slice(&time_domain[0..remaining]);
-                    self.pending_output
-                        .extend_from_slice(&time_domain[remaining..segment_size]);
-                }
+    /// Reset internal FFT history and tail buffers.
+    pub fn clear_state(&mut self) {
+        for frame in Arc::make_mut(&mut self.previous_frame_q) {
+            for sample in frame.iter_mut() {
+                sample.re = 0.0;
+                sample.im = 0.0;
             }
+        }
+        self.previous_tail.fill(0.0);
+        self.pending_output.clear();
+        #[cfg(feature = "gpu")]
+        self.gpu.reset();
+    }
 
-            output
+    /// Convolve each segment into an unnormalized time-domain frame,
+    /// skipping segments whose transforms fail.
+    fn convolve_segments(
+        &mut self,
+        segments: &[Vec<DspFloat>],
+        pool: Option<&ConvolutionPool>,
+        workers: usize,
+    ) -> Vec<Vec<DspFloat>> {
+        #[cfg(feature = "gpu")]
+        match self.gpu.process(
+            segments,
+            &self.previous_frame_q,
+            &self.ir_segments,
+            self.fft.as_ref(),
+        ) {
+            GpuBlock::Done(frames) => return frames,
+            GpuBlock::Cpu(Some(history)) => self.restore_history(&history),
+            GpuBlock::Cpu(None) => {}
         }
 
-        /// Reset internal FFT history and tail buffers.
-        pub fn clear_state(&mut self) {
-            for frame in &mut self.previous_frame_q {
-                for sample in frame.iter_mut() {
-                    sample.re = 0.0;
-                    sample.im = 0.0;
-                }
+        let spectrum_len = self.fft.spectrum_len();
+        let mut frames = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let Some(spectrum) = forward_segment(segment, self.fft.as_ref()) else {
+                continue;
+            };
+            // Pool jobs drop their clones before a batch returns, so this
+            // only copies the history after the convolver itself was cloned.
+            let history = Arc::make_mut(&mut self.previous_frame_q);
+            history.push_front(spectrum);
+            history.pop_back();
+
+            let mut convolved = accumulate_partitions(
+                &self.previous_frame_q,
+                &self.ir_segments,
+                spectrum_len,
+                pool,
+                workers,
+            );
+            let mut time_domain = vec![0.0 as DspFloat; self.fft_size];
+            if let Err(err) = self.fft.inverse(&mut convolved, &mut time_domain) {
+                error!("inverse FFT failed in convolver process: {}", err);
+                continue;
             }
-            self.previous_tail.fill(0.0);
-            self.pending_output.clear();
+            frames.push(time_domain);
         }
+        frames
     }
 
-    fn multiply_accumulate(acc: &mut [Complex<f32>], lhs: &[Complex<f32>], rhs: &[Complex<f32>]) {
-        for ((acc_sample, lhs_sample), rhs_sample) in acc.iter_mut().zip(lhs).zip(rhs) {
-            let re = (lhs_sample.re * rhs_sample.re) - (lhs_sample.im * rhs_sample.im);
-            let im = (lhs_sample.im * rhs_sample.re) + (lhs_sample.re * rhs_sample.im);
-            acc_sample.re += re;
-            acc_sample.im += im;
+    /// Rebuild the spectrum history from time-domain segments, newest first,
+    /// after the GPU hands the convolver back to the CPU.
+    #[cfg(feature = "gpu")]
+    fn restore_history(&mut self, segments: &VecDeque<Vec<DspFloat>>) {
+        let fft = self.fft.clone();
+        for (frame, segment) in Arc::make_mut(&mut self.previous_frame_q)
+            .iter_mut()
+            .zip(segments)
+        {
+            if let Some(spectrum) = forward_segment(segment, fft.as_ref()) {
+                *frame = spectrum;
+            }
         }
     }
+}
 
-    fn init_previous_tail(size: usize) -> Vec<f32> {
-        vec![0.0; size]
-    }
-
-    fn segment_buffer(
-        buffer: &[f32],
-        fft_size: usize,
-        r2c: &Arc<dyn RealToComplex<f32>>,
-        spectrum_len: usize,
-    ) -> Vec<Vec<Complex<f32>>> {
-        let mut segments = Vec::new();
-        let segment_size = fft_size / 2;
-
-        let mut index = 0;
-        while index < buffer.len() {
-            let mut time_domain = vec![0.0_f32; fft_size];
-            for (offset, sample) in buffer.iter().skip(index).take(segment_size).enumerate() {
-                time_domain[offset] = *sample;
-            }
-
-            let mut spectrum = vec![Complex { re: 0.0, im: 0.0 }; spectrum_len];
-            if let Err(err) = r2c.process(&mut time_domain, &mut spectrum) {
-                error!("real FFT failed while segmenting buffer: {}", err);
-                index += segment_size;
-                continue;
-            }
-            segments.push(spectrum);
-            index += segment_size;
-        }
+/// Split a time-domain buffer into half-FFT-size blocks, zero-padding the
+/// last one.
+fn split_segments(buffer: &[f32], segment_size: usize) -> Vec<Vec<DspFloat>> {
+    buffer
+        .chunks(segment_size.max(1))
+        .map(|chunk| {
+            let mut segment: Vec<DspFloat> = chunk.iter().map(|&sample| widen(sample)).collect();
+            segment.resize(segment_size, 0.0);
+            segment
+        })
+        .collect()
+}
 
-        segments
+/// Zero-pad a half-FFT-size block to the FFT size and transform it.
+fn forward_segment(segment: &[DspFloat], fft: &dyn RealFft) -> Option<Vec<Complex<DspFloat>>> {
+    let mut time_domain = vec![0.0 as DspFloat; fft.fft_len()];
+    for (slot, sample) in time_domain.iter_mut().zip(segment) {
+        *slot = *sample;
     }
-
-    fn init_previous_frame_q(
-        segment_count: usize,
-        spectrum_len: usize,
-    ) -> VecDeque<Vec<Complex<f32>>> {
-        let mut q = VecDeque::new();
-        for _ in 0..segment_count {
-            q.push_back(vec![Complex { re: 0.0, im: 0.0 }; spectrum_len]);
-        }
-        q
+    let mut spectrum = vec![Complex { re: 0.0, im: 0.0 }; fft.spectrum_len()];
+    if let Err(err) = fft.forward(&mut time_domain, &mut spectrum) {
+        error!("FFT failed while segmenting buffer: {}", err);
+        return None;
     }
+    Some(spectrum)
 }
 
-#[cfg(all(not(feature = "real-fft"), feature = "complex-fft"))]
-pub use complex_fft::Convolver;
+/// Split a time-domain buffer into half-FFT-size blocks, zero-pad each to
+/// the FFT size and transform it.
+fn segment_buffer(buffer: &[f32], fft: &dyn RealFft) -> Vec<Vec<Complex<DspFloat>>> {
+    split_segments(buffer, fft.fft_len() / 2)
+        .iter()
+        .filter_map(|segment| forward_segment(segment, fft))
+        .collect()
+}
 
-#[cfg(feature = "real-fft")]
-pub use real_fft::Convolver;
+/// Build a queue of empty spectrum frames for overlap-add history.
+fn init_previous_frame_q(segment_count: usize, spectrum_len: usize) -> FrameQueue {
+    let mut q = VecDeque::new();
+    for _ in 0..segment_count {
+        q.push_back(vec![Complex { re: 0.0, im: 0.0 }; spectrum_len]);
+    }
+    q
+}
 
 #[cfg(test)]
 mod tests {
@@ -398,4 +408,48 @@ mod tests {
             .iter()
             .all(|sample| sample.abs() < 1e-9));
     }
+
+    /// Passes on machines without an adapter too, via the CPU fallback.
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn gpu_preference_matches_the_cpu_path() {
+        let ir: Vec<f32> = (0..2_000).map(|i| (i as f32 * 0.37).sin() * 0.5).collect();
+        let input: Vec<f32> = (0..1_500).map(|i| (i as f32 * 0.11).cos()).collect();
+        let mut cpu = Convolver::new(&ir, 256);
+        let mut gpu = Convolver::new(&ir, 256);
+        gpu.set_gpu(true);
+
+        for chunk in input.chunks(500) {
+            let expected = cpu.process(chunk);
+            let output = gpu.process(chunk);
+            assert_eq!(output.len(), expected.len());
+            for (out, exp) in output.iter().zip(&expected) {
+                assert!((out - exp).abs() < 1e-3, "{out} vs {exp}");
+            }
+        }
+        assert_eq!(gpu.gpu_active(), super::super::gpu::available());
+    }
+
+    /// The CPU picks up from the GPU's input history without a seam.
+    #[cfg(feature = "gpu")]
+    #[test]
+    fn disabling_gpu_mid_stream_continues_the_cpu_output() {
+        let ir: Vec<f32> = (0..2_000).map(|i| (i as f32 * 0.21).cos() * 0.5).collect();
+        let input: Vec<f32> = (0..2_000).map(|i| (i as f32 * 0.07).sin()).collect();
+        let mut cpu = Convolver::new(&ir, 256);
+        let mut switched = Convolver::new(&ir, 256);
+        switched.set_gpu(true);
+
+        for (index, chunk) in input.chunks(400).enumerate() {
+            if index == 2 {
+                switched.set_gpu(false);
+            }
+            let expected = cpu.process(chunk);
+            let output = switched.process(chunk);
+            for (out, exp) in output.iter().zip(&expected) {
+                assert!((out - exp).abs() < 1e-3, "{out} vs {exp}");
+            }
+        }
+        assert!(!switched.gpu_active());
+    }
 }
@@ -0,0 +1,479 @@
+//! Experimental wgpu offload of partitioned convolution.
+//!
+//! For an impulse response split into `P` partitions, every block costs two
+//! FFTs and `P` complex spectrum multiplies. [`GpuPartitions`] keeps the IR
+//! spectra and a ring of recent input spectra resident on the GPU and runs
+//! the whole frame there: the forward FFT of the new input segment, the
+//! multiply-accumulate over every partition, and the inverse FFT. Only the
+//! time-domain segment goes up and the time-domain frame comes back; the CPU
+//! keeps the overlap-add.
+//!
+//! A block's frames are submitted back to back and read back together, with
+//! a bounded wait of [`FRAME_DEADLINE`] per frame instead of blocking the
+//! caller until the device drains. A block that misses its deadline is
+//! convolved on the CPU and the convolver stays there until its state is
+//! next cleared. While the GPU holds the input spectra, the slot keeps the
+//! matching time-domain segments so the CPU history can be rebuilt whenever
+//! it takes over.
+//!
+//! The device is opened once per process. When no adapter is available, a
+//! buffer exceeds the device limits, or a readback fails, the convolver logs
+//! the reason once and keeps running on the CPU path. The shaders work in
+//! `f32` regardless of the `f64-dsp` feature.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, OnceLock};
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+use log::{info, warn};
+
+use crate::dsp::fft::{Complex, RealFft};
+use crate::dsp::precision::{narrow, DspFloat};
+use partitions::{GpuError, GpuPartitions};
+
+mod partitions;
+
+/// Partitions × channels at which an unset `gpu` preference turns the
+/// offload on.
+pub(crate) const GPU_AUTO_PARTITIONS: usize = 128;
+
+/// GPU time allowed per frame before a block falls back to the CPU.
+///
+/// A frame carries half an FFT of audio, about 85 ms at the default size and
+/// 48 kHz, so a late GPU still leaves the CPU path time to finish the block.
+const FRAME_DEADLINE: Duration = Duration::from_millis(5);
+
+const WORKGROUP_SIZE: u32 = 64;
+/// Bytes in one complex bin on the GPU (two `f32`).
+const BIN_BYTES: u64 = 8;
+
+const SHADER: &str = r#"
+struct Frame {
+    fft_len: u32,
+    partitions: u32,
+    head: u32,
+    pad: u32,
+}
+
+struct Stage {
+    span: u32,
+    inverse: u32,
+    pad0: u32,
+    pad1: u32,
+}
+
+@group(0) @binding(0) var<uniform> frame: Frame;
+@group(0) @binding(1) var<uniform> stage: Stage;
+@group(0) @binding(2) var<storage, read> ir: array<vec2<f32>>;
+@group(0) @binding(3) var<storage, read> history: array<vec2<f32>>;
+@group(0) @binding(4) var<storage, read> segment: array<f32>;
+@group(0) @binding(5) var<storage, read> src: array<vec2<f32>>;
+@group(0) @binding(6) var<storage, read_write> dst: array<vec2<f32>>;
+
+const TAU: f32 = 6.283185307179586;
+
+// Zero-pad the input segment into a complex signal.
+@compute @workgroup_size(64)
+fn load(@builtin(global_invocation_id) id: vec3<u32>) {
+    let index = id.x;
+    if (index >= frame.fft_len) {
+        return;
+    }
+    var sample = 0.0;
+    if (index < frame.fft_len / 2u) {
+        sample = segment[index];
+    }
+    dst[index] = vec2<f32>(sample, 0.0);
+}
+
+// One radix-2 Stockham pass merging sub-transforms of length `span`.
+@compute @workgroup_size(64)
+fn butterfly(@builtin(global_invocation_id) id: vec3<u32>) {
+    let pair = id.x;
+    let half_len = frame.fft_len / 2u;
+    if (pair >= half_len) {
+        return;
+    }
+    let k = pair % stage.span;
+    var direction = -1.0;
+    if (stage.inverse != 0u) {
+        direction = 1.0;
+    }
+    let angle = direction * TAU * f32(k) / f32(2u * stage.span);
+    let w = vec2<f32>(cos(angle), sin(angle));
+    let a = src[pair];
+    let b = src[pair + half_len];
+    let wb = vec2<f32>(b.x * w.x - b.y * w.y, b.x * w.y + b.y * w.x);
+    let first = (pair - k) * 2u + k;
+    dst[first] = a + wb;
+    dst[first + stage.span] = a - wb;
+}
+
+// Sum history[p] * ir[p] over every partition, one invocation per bin.
+@compute @workgroup_size(64)
+fn multiply_accumulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let bin = id.x;
+    if (bin >= frame.fft_len) {
+        return;
+    }
+    var sum = vec2<f32>(0.0, 0.0);
+    for (var p = 0u; p < frame.partitions; p = p + 1u) {
+        let slot = (frame.head + p) % frame.partitions;
+        let x = history[slot * frame.fft_len + bin];
+        let h = ir[p * frame.fft_len + bin];
+        sum += vec2<f32>(x.x * h.x - x.y * h.y, x.y * h.x + x.x * h.y);
+    }
+    dst[bin] = sum;
+}
+"#;
+
+struct GpuContext {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    layout: wgpu::BindGroupLayout,
+    load: wgpu::ComputePipeline,
+    butterfly: wgpu::ComputePipeline,
+    multiply_accumulate: wgpu::ComputePipeline,
+}
+
+/// Whether a GPU adapter could be opened for the convolution offload.
+pub fn available() -> bool {
+    context().is_some()
+}
+
+fn context() -> Option<&'static GpuContext> {
+    static CONTEXT: OnceLock<Option<GpuContext>> = OnceLock::new();
+    CONTEXT.get_or_init(open_context).as_ref()
+}
+
+fn open_context() -> Option<GpuContext> {
+    let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::new_without_display_handle());
+    let adapter = match block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::HighPerformance,
+        ..Default::default()
+    })) {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            warn!("gpu convolution unavailable, using CPU: {}", err);
+            return None;
+        }
+    };
+    let (device, queue) = match block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+        label: Some("proteus convolution"),
+        ..Default::default()
+    })) {
+        Ok(pair) => pair,
+        Err(err) => {
+            warn!("gpu convolution device request failed, using CPU: {}", err);
+            return None;
+        }
+    };
+    // Errors are collected through scopes; anything that escapes one is
+    // logged rather than left to wgpu's default handler, which panics.
+    device.on_uncaptured_error(Arc::new(|err| {
+        warn!("gpu convolution device error: {}", err);
+    }));
+    let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+    let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("partitioned convolution"),
+        source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+    });
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("partitioned convolution"),
+        entries: &[
+            buffer_entry(0, wgpu::BufferBindingType::Uniform, false),
+            buffer_entry(1, wgpu::BufferBindingType::Uniform, true),
+            buffer_entry(
+                2,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+            ),
+            buffer_entry(
+                3,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+            ),
+            buffer_entry(
+                4,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+            ),
+            buffer_entry(
+                5,
+                wgpu::BufferBindingType::Storage { read_only: true },
+                false,
+            ),
+            buffer_entry(
+                6,
+                wgpu::BufferBindingType::Storage { read_only: false },
+                false,
+            ),
+        ],
+    });
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("partitioned convolution"),
+        bind_group_layouts: &[Some(&layout)],
+        immediate_size: 0,
+    });
+    let pipeline = |entry_point: &str| {
+        device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some(entry_point),
+            layout: Some(&pipeline_layout),
+            module: &module,
+            entry_point: Some(entry_point),
+            compilation_options: Default::default(),
+            cache: None,
+        })
+    };
+    let load = pipeline("load");
+    let butterfly = pipeline("butterfly");
+    let multiply_accumulate = pipeline("multiply_accumulate");
+    if let Some(err) = block_on(scope.pop()) {
+        warn!("gpu convolution shaders rejected, using CPU: {}", err);
+        return None;
+    }
+    info!("gpu convolution using {}", adapter.get_info().name);
+    Some(GpuContext {
+        device,
+        queue,
+        layout,
+        load,
+        butterfly,
+        multiply_accumulate,
+    })
+}
+
+fn buffer_entry(
+    binding: u32,
+    ty: wgpu::BufferBindingType,
+    has_dynamic_offset: bool,
+) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::COMPUTE,
+        ty: wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset,
+            min_binding_size: None,
+        },
+        count: None,
+    }
+}
+
+/// Outcome of offering a block to a [`GpuSlot`].
+pub(crate) enum GpuBlock {
+    /// Unnormalized time-domain frames, one per input segment.
+    Done(Vec<Vec<DspFloat>>),
+    /// Convolve the block on the CPU, first rebuilding the CPU history from
+    /// these time-domain segments (newest first) when given.
+    Cpu(Option<VecDeque<Vec<DspFloat>>>),
+}
+
+/// Per-convolver GPU offload with lazy setup and CPU fallback.
+///
+/// Cloning yields a slot with the same preference and input history but no
+/// GPU buffers, so a cloned convolver never shares device state with the
+/// original.
+#[derive(Default)]
+pub(crate) struct GpuSlot {
+    enabled: bool,
+    failed: bool,
+    late: bool,
+    active: bool,
+    state: Option<GpuPartitions>,
+    /// Time-domain input segments, newest first, while the GPU holds the
+    /// only current input spectra; `None` while the CPU history is current.
+    history: Option<VecDeque<Vec<DspFloat>>>,
+}
+
+impl Clone for GpuSlot {
+    fn clone(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            failed: self.failed,
+            late: self.late,
+            history: self.history.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+impl GpuSlot {
+    /// Turn the offload on or off; turning it off frees the GPU buffers and
+    /// returns the history the CPU must rebuild, if the GPU held it.
+    pub(crate) fn set_enabled(&mut self, enabled: bool) -> Option<VecDeque<Vec<DspFloat>>> {
+        self.enabled = enabled;
+        if enabled {
+            return None;
+        }
+        self.state = None;
+        self.active = false;
+        self.history.take()
+    }
+
+    /// Whether the last block ran on the GPU.
+    pub(crate) fn active(&self) -> bool {
+        self.active
+    }
+
+    /// Drop GPU-side state after the caller cleared its own history, and
+    /// give the GPU another chance after a missed deadline.
+    pub(crate) fn reset(&mut self) {
+        self.state = None;
+        self.history = None;
+        self.late = false;
+    }
+
+    /// Convolve `segments` (half-FFT-size time-domain blocks) on the GPU.
+    ///
+    /// `frames` is the CPU spectrum history, used to seed the GPU the first
+    /// time the offload runs.
+    pub(crate) fn process(
+        &mut self,
+        segments: &[Vec<DspFloat>],
+        frames: &VecDeque<Vec<Complex<DspFloat>>>,
+        ir_segments: &[Vec<Complex<DspFloat>>],
+        fft: &dyn RealFft,
+    ) -> GpuBlock {
+        self.active = false;
+        if !self.enabled || self.failed || self.late || ir_segments.is_empty() {
+            return GpuBlock::Cpu(self.history.take());
+        }
+        if self.state.is_none() {
+            let spectra: Vec<Vec<Complex<DspFloat>>> = match &self.history {
+                Some(history) => history
+                    .iter()
+                    .map(|segment| forward(segment, fft))
+                    .collect(),
+                None => frames.iter().cloned().collect(),
+            };
+            if self.history.is_none() {
+                self.history = Some(time_domain_history(frames, fft));
+            }
+            self.state = GpuPartitions::new(ir_segments, &spectra, fft.fft_len());
+            if self.state.is_none() {
+                warn!("gpu convolution setup failed, falling back to CPU");
+                self.failed = true;
+                return GpuBlock::Cpu(self.history.take());
+            }
+        }
+        let Some(state) = self.state.as_mut() else {
+            return GpuBlock::Cpu(self.history.take());
+        };
+        match state.process(segments) {
+            Ok(output) => {
+                if let Some(history) = self.history.as_mut() {
+                    for segment in segments {
+                        history.push_front(segment.clone());
+                    }
+                    history.truncate(ir_segments.len());
+                }
+                self.active = true;
+                GpuBlock::Done(output)
+            }
+            Err(GpuError::Late) => {
+                warn!("gpu convolution missed its deadline, using CPU until the next reset");
+                self.late = true;
+                self.state = None;
+                GpuBlock::Cpu(self.history.take())
+            }
+            Err(GpuError::Failed) => {
+                warn!("gpu convolution failed, falling back to CPU");
+                self.failed = true;
+                self.state = None;
+                GpuBlock::Cpu(self.history.take())
+            }
+        }
+    }
+}
+
+/// Forward-transform a half-FFT-size segment, zero-padded to the FFT size.
+fn forward(segment: &[DspFloat], fft: &dyn RealFft) -> Vec<Complex<DspFloat>> {
+    let mut time_domain = vec![0.0 as DspFloat; fft.fft_len()];
+    for (slot, sample) in time_domain.iter_mut().zip(segment) {
+        *slot = *sample;
+    }
+    let mut spectrum = vec![Complex { re: 0.0, im: 0.0 }; fft.spectrum_len()];
+    if let Err(err) = fft.forward(&mut time_domain, &mut spectrum) {
+        warn!("FFT failed while seeding gpu convolution history: {}", err);
+    }
+    spectrum
+}
+
+/// Recover the time-domain input segments behind CPU history spectra.
+fn time_domain_history(
+    frames: &VecDeque<Vec<Complex<DspFloat>>>,
+    fft: &dyn RealFft,
+) -> VecDeque<Vec<DspFloat>> {
+    let fft_len = fft.fft_len();
+    let norm = fft_len as DspFloat;
+    frames
+        .iter()
+        .map(|frame| {
+            let mut spectrum = frame.clone();
+            let mut time_domain = vec![0.0 as DspFloat; fft_len];
+            if let Err(err) = fft.inverse(&mut spectrum, &mut time_domain) {
+                warn!(
+                    "inverse FFT failed while saving convolution history: {}",
+                    err
+                );
+            }
+            time_domain.truncate(fft_len / 2);
+            for sample in &mut time_domain {
+                *sample /= norm;
+            }
+            time_domain
+        })
+        .collect()
+}
+
+/// Pack spectra as consecutive little-endian `f32` pairs of `fft_len` bins,
+/// expanding half spectra by Hermitian symmetry.
+fn spectra_bytes<'a>(
+    spectra: impl Iterator<Item = &'a [Complex<DspFloat>]>,
+    fft_len: usize,
+) -> Vec<u8> {
+    let zero = Complex { re: 0.0, im: 0.0 };
+    spectra
+        .flat_map(|spectrum| {
+            (0..fft_len).map(move |bin| match spectrum.get(bin) {
+                Some(value) => *value,
+                None => spectrum.get(fft_len - bin).map_or(zero, |mirror| Complex {
+                    re: mirror.re,
+                    im: -mirror.im,
+                }),
+            })
+        })
+        .flat_map(|bin| {
+            let mut bytes = [0u8; BIN_BYTES as usize];
+            bytes[..4].copy_from_slice(&narrow(bin.re).to_le_bytes());
+            bytes[4..].copy_from_slice(&narrow(bin.im).to_le_bytes());
+            bytes
+        })
+        .collect()
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// Drive one of wgpu's setup futures to completion on this thread.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        thread::park();
+    }
+}
@@ -0,0 +1,368 @@
+//! Device buffers and per-frame command encoding for the GPU convolver.
+
+use std::sync::mpsc;
+
+use log::warn;
+use wgpu::util::DeviceExt;
+
+use super::{
+    block_on, context, spectra_bytes, GpuContext, BIN_BYTES, FRAME_DEADLINE, WORKGROUP_SIZE,
+};
+use crate::dsp::fft::Complex;
+use crate::dsp::precision::{narrow, widen, DspFloat};
+
+/// Bytes in one `Stage` or `Frame` uniform.
+const UNIFORM_BYTES: u64 = 16;
+
+/// Why a block could not be taken from the GPU.
+pub(super) enum GpuError {
+    /// The readback did not arrive within the block's deadline.
+    Late,
+    /// The device rejected the work or the readback.
+    Failed,
+}
+
+/// GPU-resident IR spectra, input ring, FFT scratch, and readback buffers.
+pub(super) struct GpuPartitions {
+    frame_params: wgpu::Buffer,
+    segment: wgpu::Buffer,
+    history: wgpu::Buffer,
+    work: [wgpu::Buffer; 2],
+    readback: Option<wgpu::Buffer>,
+    readback_frames: usize,
+    /// `bind_groups[i]` reads `work[i]` and writes the other work buffer.
+    bind_groups: [wgpu::BindGroup; 2],
+    stage_stride: u32,
+    stages: u32,
+    partitions: usize,
+    fft_len: usize,
+    head: usize,
+}
+
+impl GpuPartitions {
+    /// Upload `ir_segments` and the current input `history` spectra.
+    ///
+    /// Returns `None` when no device is open, the FFT size is not a power of
+    /// two, or a table exceeds the device's storage limits.
+    pub(super) fn new(
+        ir_segments: &[Vec<Complex<DspFloat>>],
+        history: &[Vec<Complex<DspFloat>>],
+        fft_len: usize,
+    ) -> Option<Self> {
+        let context = context()?;
+        if fft_len < 2 || !fft_len.is_power_of_two() {
+            warn!(
+                "gpu convolution needs a power-of-two FFT size, got {}",
+                fft_len
+            );
+            return None;
+        }
+        let partitions = ir_segments.len();
+        let frame_bytes = fft_len as u64 * BIN_BYTES;
+        let table_bytes = partitions as u64 * frame_bytes;
+        let limits = context.device.limits();
+        if table_bytes > limits.max_storage_buffer_binding_size {
+            warn!(
+                "gpu convolution needs {} bytes per table, device allows {}",
+                table_bytes, limits.max_storage_buffer_binding_size
+            );
+            return None;
+        }
+
+        let device = &context.device;
+        let scope = device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let ir = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("convolution ir spectra"),
+            contents: &spectra_bytes(ir_segments.iter().map(Vec::as_slice), fft_len),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let empty: &[Complex<DspFloat>] = &[];
+        let history = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("convolution input history"),
+            contents: &spectra_bytes(
+                history
+                    .iter()
+                    .map(Vec::as_slice)
+                    .chain(std::iter::repeat(empty))
+                    .take(partitions),
+                fft_len,
+            ),
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let stages = fft_len.trailing_zeros();
+        let stage_stride = limits
+            .min_uniform_buffer_offset_alignment
+            .max(UNIFORM_BYTES as u32);
+        let mut stage_bytes = vec![0u8; (2 * stages * stage_stride) as usize];
+        for inverse in 0..2_u32 {
+            for stage in 0..stages {
+                let offset = ((inverse * stages + stage) * stage_stride) as usize;
+                stage_bytes[offset..offset + 16].copy_from_slice(&uniform_bytes([
+                    1 << stage,
+                    inverse,
+                    0,
+                    0,
+                ]));
+            }
+        }
+        let stage_params = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("convolution fft stages"),
+            contents: &stage_bytes,
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let frame_params = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("convolution frame params"),
+            size: UNIFORM_BYTES,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let segment = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("convolution input segment"),
+            size: (fft_len / 2) as u64 * 4,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let work = [0, 1].map(|_| {
+            device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("convolution fft scratch"),
+                size: frame_bytes,
+                usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+                mapped_at_creation: false,
+            })
+        });
+        let bind_group = |src: &wgpu::Buffer, dst: &wgpu::Buffer| {
+            device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("convolution bindings"),
+                layout: &context.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: frame_params.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &stage_params,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(UNIFORM_BYTES),
+                        }),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: ir.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 3,
+                        resource: history.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 4,
+                        resource: segment.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 5,
+                        resource: src.as_entire_binding(),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 6,
+                        resource: dst.as_entire_binding(),
+                    },
+                ],
+            })
+        };
+        let bind_groups = [
+            bind_group(&work[0], &work[1]),
+            bind_group(&work[1], &work[0]),
+        ];
+        if let Some(err) = block_on(scope.pop()) {
+            warn!("gpu convolution buffers rejected: {}", err);
+            return None;
+        }
+
+        Some(Self {
+            frame_params,
+            segment,
+            history,
+            work,
+            readback: None,
+            readback_frames: 0,
+            bind_groups,
+            stage_stride,
+            stages,
+            partitions,
+            fft_len,
+            head: 0,
+        })
+    }
+
+    /// Convolve each segment in order and return the unnormalized
+    /// time-domain frames.
+    ///
+    /// Every frame is submitted before any readback is awaited, and the
+    /// wait is bounded by [`FRAME_DEADLINE`] per frame.
+    pub(super) fn process(
+        &mut self,
+        segments: &[Vec<DspFloat>],
+    ) -> Result<Vec<Vec<DspFloat>>, GpuError> {
+        let context = context().ok_or(GpuError::Failed)?;
+        if segments.is_empty() {
+            return Ok(Vec::new());
+        }
+        let frame_bytes = self.fft_len as u64 * BIN_BYTES;
+        let scope = context
+            .device
+            .push_error_scope(wgpu::ErrorFilter::Validation);
+        let readback = self.readback_for(context, segments.len());
+
+        let mut last_submission = None;
+        for (index, segment) in segments.iter().enumerate() {
+            self.head = (self.head + self.partitions - 1) % self.partitions;
+            let samples: Vec<u8> = segment
+                .iter()
+                .take(self.fft_len / 2)
+                .flat_map(|sample| narrow(*sample).to_le_bytes())
+                .collect();
+            context.queue.write_buffer(&self.segment, 0, &samples);
+            context.queue.write_buffer(
+                &self.frame_params,
+                0,
+                &uniform_bytes([self.fft_len, self.partitions, self.head, 0].map(|v| v as u32)),
+            );
+            let mut encoder =
+                context
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("convolution frame"),
+                    });
+            self.encode_frame(context, &mut encoder, &readback, index as u64 * frame_bytes);
+            last_submission = Some(context.queue.submit([encoder.finish()]));
+        }
+
+        let slice = readback.slice(..segments.len() as u64 * frame_bytes);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        match context.device.poll(wgpu::PollType::Wait {
+            submission_index: last_submission,
+            timeout: Some(FRAME_DEADLINE * segments.len() as u32),
+        }) {
+            Ok(_) => {}
+            Err(wgpu::PollError::Timeout) => return Err(GpuError::Late),
+            Err(_) => return Err(GpuError::Failed),
+        }
+        if let Some(err) = block_on(scope.pop()) {
+            warn!("gpu convolution frame rejected: {}", err);
+            return Err(GpuError::Failed);
+        }
+        match receiver.try_recv() {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => return Err(GpuError::Failed),
+            Err(_) => return Err(GpuError::Late),
+        }
+        let frames = {
+            let bytes = slice.get_mapped_range();
+            bytes
+                .chunks_exact(frame_bytes as usize)
+                .map(|frame| {
+                    frame
+                        .chunks_exact(BIN_BYTES as usize)
+                        .map(|bin| widen(f32::from_le_bytes([bin[0], bin[1], bin[2], bin[3]])))
+                        .collect()
+                })
+                .collect()
+        };
+        readback.unmap();
+        Ok(frames)
+    }
+
+    /// Readback buffer holding at least `frames` frames, grown on demand.
+    fn readback_for(&mut self, context: &GpuContext, frames: usize) -> wgpu::Buffer {
+        if let Some(readback) = self
+            .readback
+            .as_ref()
+            .filter(|_| self.readback_frames >= frames)
+        {
+            return readback.clone();
+        }
+        let readback = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("convolution readback"),
+            size: frames as u64 * self.fft_len as u64 * BIN_BYTES,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        self.readback = Some(readback.clone());
+        self.readback_frames = frames;
+        readback
+    }
+
+    /// Forward FFT of the segment into the history ring, multiply-accumulate
+    /// into scratch, inverse FFT, and copy the frame to `readback_offset`.
+    fn encode_frame(
+        &self,
+        context: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        readback: &wgpu::Buffer,
+        readback_offset: u64,
+    ) {
+        let frame_bytes = self.fft_len as u64 * BIN_BYTES;
+        let spectrum = self.encode_transform(context, encoder, &context.load, false);
+        encoder.copy_buffer_to_buffer(
+            &self.work[spectrum],
+            0,
+            &self.history,
+            self.head as u64 * frame_bytes,
+            frame_bytes,
+        );
+        let output = self.encode_transform(context, encoder, &context.multiply_accumulate, true);
+        encoder.copy_buffer_to_buffer(
+            &self.work[output],
+            0,
+            readback,
+            readback_offset,
+            frame_bytes,
+        );
+    }
+
+    /// Run `fill` into `work[0]`, then every FFT stage; return the index of
+    /// the work buffer holding the result.
+    fn encode_transform(
+        &self,
+        context: &GpuContext,
+        encoder: &mut wgpu::CommandEncoder,
+        fill: &wgpu::ComputePipeline,
+        inverse: bool,
+    ) -> usize {
+        let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("convolution transform"),
+            timestamp_writes: None,
+        });
+        pass.set_pipeline(fill);
+        pass.set_bind_group(0, &self.bind_groups[1], &[0]);
+        pass.dispatch_workgroups(workgroups(self.fft_len), 1, 1);
+
+        pass.set_pipeline(&context.butterfly);
+        let first_stage = if inverse { self.stages } else { 0 };
+        let mut current = 0;
+        for stage in 0..self.stages {
+            let offset = (first_stage + stage) * self.stage_stride;
+            pass.set_bind_group(0, &self.bind_groups[current], &[offset]);
+            pass.dispatch_workgroups(workgroups(self.fft_len / 2), 1, 1);
+            current ^= 1;
+        }
+        current
+    }
+}
+
+fn workgroups(invocations: usize) -> u32 {
+    (invocations as u32).div_ceil(WORKGROUP_SIZE)
+}
+
+fn uniform_bytes(values: [u32; 4]) -> [u8; UNIFORM_BYTES as usize] {
+    let mut bytes = [0u8; UNIFORM_BYTES as usize];
+    for (chunk, value) in bytes.chunks_exact_mut(4).zip(values) {
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
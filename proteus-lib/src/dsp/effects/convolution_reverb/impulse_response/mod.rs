@@ -1,13 +1,23 @@
 //! Load and normalize impulse responses for convolution reverb.
+//!
+//! Offline preparation of IR files (resampling, channel folding, trimming,
+//! and validation) lives in `tools` and is re-exported here.
+
+mod tools;
 
 use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Cursor, Read, Seek};
 use std::path::Path;
 
-use log::{info, warn};
-use matroska::Matroska;
+use log::warn;
 use rodio::{Decoder, Source};
+use serde::Serialize;
+
+pub use tools::{
+    downmix_impulse_response, resample_impulse_response, trim_impulse_response,
+    validate_impulse_response, ImpulseResponseIssue,
+};
 
 /// Decoded impulse response audio data.
 ///
@@ -42,6 +52,142 @@ impl ImpulseResponse {
         let channel_index = index % self.channels.len();
         &self.channels[channel_index]
     }
+
+    /// Return the length of the longest channel, in frames.
+    pub fn frames(&self) -> usize {
+        self.channels.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    /// Measure the characteristics hosts display for a loaded IR.
+    pub fn inspect(&self) -> ImpulseResponseInfo {
+        ImpulseResponseInfo {
+            sample_rate: self.sample_rate,
+            channels: self.channel_count(),
+            length_seconds: self.frames() as f32 / self.sample_rate.max(1) as f32,
+            rt60_seconds: estimate_rt60(self),
+            direct_to_reverberant_db: direct_to_reverberant_db(self),
+        }
+    }
+}
+
+/// Summary of an impulse response returned by [`ImpulseResponse::inspect`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ImpulseResponseInfo {
+    /// Sample rate of the impulse response, in Hz.
+    pub sample_rate: u32,
+    /// Number of channels.
+    pub channels: usize,
+    /// Length of the longest channel, in seconds.
+    pub length_seconds: f32,
+    /// Estimated time for the tail to decay by 60 dB, in seconds.
+    pub rt60_seconds: Option<f32>,
+    /// Energy up to shortly after the peak over the energy after it, in dB.
+    pub direct_to_reverberant_db: Option<f32>,
+}
+
+/// Window after the peak still counted as direct sound, in seconds.
+const DIRECT_WINDOW_SECONDS: f32 = 0.0025;
+
+/// Estimate the RT60 of `ir` in seconds, averaged across channels.
+///
+/// Each channel's Schroeder energy decay curve is fitted between -5 and
+/// -25 dB (T20) and extrapolated to 60 dB of decay. Channels whose decay
+/// never reaches -25 dB fall back to the -5 to -15 dB range (T10). Returns
+/// `None` when no channel decays far enough to measure.
+pub fn estimate_rt60(ir: &ImpulseResponse) -> Option<f32> {
+    let rate = ir.sample_rate as f64;
+    if rate <= 0.0 {
+        return None;
+    }
+    let estimates: Vec<f64> = ir
+        .channels
+        .iter()
+        .filter_map(|channel| {
+            let decay = energy_decay_db(channel);
+            decay_slope(&decay, -25.0)
+                .or_else(|| decay_slope(&decay, -15.0))
+                .map(|db_per_sample| -60.0 / (db_per_sample * rate))
+        })
+        .collect();
+    if estimates.is_empty() {
+        return None;
+    }
+    Some((estimates.iter().sum::<f64>() / estimates.len() as f64) as f32)
+}
+
+/// Estimate the direct-to-reverberant ratio of `ir` in dB, averaged across
+/// channels.
+///
+/// Direct sound is everything up to 2.5 ms after a channel's peak; the rest
+/// is reverberation. Returns `None` when no channel has energy on both
+/// sides of that split.
+pub fn direct_to_reverberant_db(ir: &ImpulseResponse) -> Option<f32> {
+    let window = (ir.sample_rate as f32 * DIRECT_WINDOW_SECONDS).round() as usize;
+    let ratios: Vec<f64> = ir
+        .channels
+        .iter()
+        .filter_map(|channel| {
+            let peak = channel
+                .iter()
+                .enumerate()
+                .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))?
+                .0;
+            let split = (peak + window + 1).min(channel.len());
+            let energy = |samples: &[f32]| -> f64 {
+                samples.iter().map(|s| f64::from(*s) * f64::from(*s)).sum()
+            };
+            let (direct, reverberant) = (energy(&channel[..split]), energy(&channel[split..]));
+            (direct > 0.0 && reverberant > 0.0).then(|| 10.0 * (direct / reverberant).log10())
+        })
+        .collect();
+    if ratios.is_empty() {
+        return None;
+    }
+    Some((ratios.iter().sum::<f64>() / ratios.len() as f64) as f32)
+}
+
+/// Schroeder backward-integrated energy of `channel`, in dB relative to its
+/// total energy.
+fn energy_decay_db(channel: &[f32]) -> Vec<f64> {
+    let mut remaining = 0.0_f64;
+    let mut curve: Vec<f64> = channel
+        .iter()
+        .rev()
+        .map(|sample| {
+            remaining += f64::from(*sample) * f64::from(*sample);
+            remaining
+        })
+        .collect();
+    curve.reverse();
+    let total = curve.first().copied().unwrap_or(0.0);
+    if total <= 0.0 {
+        return Vec::new();
+    }
+    curve
+        .iter()
+        .map(|energy| 10.0 * (energy / total).max(f64::MIN_POSITIVE).log10())
+        .collect()
+}
+
+/// Least-squares slope, in dB per sample, of `decay` between where it
+/// first falls to -5 dB and where it first falls to `end_db`.
+fn decay_slope(decay: &[f64], end_db: f64) -> Option<f64> {
+    let start = decay.iter().position(|db| *db <= -5.0)?;
+    let end = start + decay[start..].iter().position(|db| *db <= end_db)?;
+    if end <= start + 1 {
+        return None;
+    }
+    let count = (end - start + 1) as f64;
+    let mean_x = (start + end) as f64 / 2.0;
+    let mean_y = decay[start..=end].iter().sum::<f64>() / count;
+    let (mut covariance, mut variance) = (0.0, 0.0);
+    for (index, db) in decay.iter().enumerate().take(end + 1).skip(start) {
+        let dx = index as f64 - mean_x;
+        covariance += dx * (db - mean_y);
+        variance += dx * dx;
+    }
+    let slope = covariance / variance;
+    (slope < 0.0).then_some(slope)
 }
 
 /// Errors that can occur while loading or decoding impulse responses.
@@ -49,8 +195,6 @@ impl ImpulseResponse {
 pub enum ImpulseResponseError {
     /// An I/O error occurred while reading the IR file.
     Io(std::io::Error),
-    /// A Matroska container parsing error occurred while reading the IR attachment.
-    Matroska(matroska::Error),
     /// The rodio decoder failed to decode the IR audio data.
     Decode(rodio::decoder::DecoderError),
     /// No attachment with the given name was found in the container.
@@ -63,7 +207,6 @@ impl fmt::Display for ImpulseResponseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Self::Io(err) => write!(f, "failed to read impulse response: {}", err),
-            Self::Matroska(err) => write!(f, "failed to read prot container: {}", err),
             Self::Decode(err) => write!(f, "failed to decode impulse response: {}", err),
             Self::AttachmentNotFound(name) => {
                 write!(f, "impulse response attachment not found: {}", name)
@@ -87,12 +230,6 @@ impl From<rodio::decoder::DecoderError> for ImpulseResponseError {
     }
 }
 
-impl From<matroska::Error> for ImpulseResponseError {
-    fn from(err: matroska::Error) -> Self {
-        Self::Matroska(err)
-    }
-}
-
 /// Load an impulse response from a file path.
 ///
 /// # Example
@@ -117,6 +254,18 @@ pub fn load_impulse_response_from_file_with_tail(
     decode_impulse_response(BufReader::new(file), tail_db)
 }
 
+/// Load an impulse response from disk exactly as stored, without peak or
+/// energy normalization or tail trimming.
+///
+/// Use this to inspect or convert IR files; the reverb itself loads through
+/// the normalizing loaders.
+pub fn load_impulse_response_from_file_raw(
+    path: impl AsRef<Path>,
+) -> Result<ImpulseResponse, ImpulseResponseError> {
+    let file = File::open(path)?;
+    decode_impulse_response_raw(BufReader::new(file))
+}
+
 /// Load an impulse response from in-memory audio bytes.
 pub fn load_impulse_response_from_bytes(
     bytes: &[u8],
@@ -132,38 +281,19 @@ pub fn load_impulse_response_from_bytes_with_tail(
     decode_impulse_response(BufReader::new(Cursor::new(bytes.to_vec())), tail_db)
 }
 
-/// Load an impulse response attachment from a `.prot`/`.mka` container.
-pub fn load_impulse_response_from_prot_attachment(
-    prot_path: impl AsRef<Path>,
-    attachment_name: &str,
-) -> Result<ImpulseResponse, ImpulseResponseError> {
-    load_impulse_response_from_prot_attachment_with_tail(prot_path, attachment_name, Some(-60.0))
-}
-
-/// Load a container attachment and optionally trim its tail in dB.
-pub fn load_impulse_response_from_prot_attachment_with_tail(
-    prot_path: impl AsRef<Path>,
-    attachment_name: &str,
-    tail_db: Option<f32>,
-) -> Result<ImpulseResponse, ImpulseResponseError> {
-    let file = File::open(prot_path)?;
-    let mka: Matroska = Matroska::open(file)?;
-
-    let attachment = mka
-        .attachments
-        .iter()
-        .find(|attachment| attachment.name.trim_matches('"') == attachment_name)
-        .ok_or_else(|| ImpulseResponseError::AttachmentNotFound(attachment_name.to_string()))?;
-
-    info!("loading impulse bytes response from {}", attachment.name);
-
-    load_impulse_response_from_bytes_with_tail(&attachment.data, tail_db)
-}
-
 fn decode_impulse_response<R>(
     reader: R,
     tail_db: Option<f32>,
 ) -> Result<ImpulseResponse, ImpulseResponseError>
+where
+    R: Read + Seek + Send + Sync + 'static,
+{
+    let mut impulse_response = decode_impulse_response_raw(reader)?;
+    normalize_impulse_response_channels(&mut impulse_response.channels, tail_db, true);
+    Ok(impulse_response)
+}
+
+fn decode_impulse_response_raw<R>(reader: R) -> Result<ImpulseResponse, ImpulseResponseError>
 where
     R: Read + Seek + Send + Sync + 'static,
 {
@@ -180,8 +310,6 @@ where
         channel_samples[index % channels].push(sample);
     }
 
-    normalize_impulse_response_channels(&mut channel_samples, tail_db, true);
-
     if channel_samples.iter().any(|channel| channel.is_empty()) {
         warn!("impulse response includes empty channels; results may be silent");
     }
@@ -328,6 +456,55 @@ mod tests {
         assert!((max - 1.0) < 1e-6);
     }
 
+    /// Exponentially decaying noise reaching -60 dB after `rt60` seconds.
+    fn decaying_noise(rate: u32, rt60: f32, seconds: f32) -> Vec<f32> {
+        let mut seed = 0x2545_f491_u32;
+        (0..(rate as f32 * seconds) as usize)
+            .map(|index| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                let noise = (seed >> 8) as f32 / (1 << 24) as f32 * 2.0 - 1.0;
+                let t = index as f32 / rate as f32;
+                noise * 10.0_f32.powf(-3.0 * t / rt60)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn estimate_rt60_matches_synthetic_decay() {
+        let ir = ImpulseResponse {
+            sample_rate: 8_000,
+            channels: vec![
+                decaying_noise(8_000, 0.5, 1.5),
+                decaying_noise(8_000, 0.7, 1.5),
+            ],
+        };
+        let rt60 = estimate_rt60(&ir).unwrap();
+        assert!((rt60 - 0.6).abs() < 0.05, "{rt60}");
+
+        let silent = ImpulseResponse {
+            sample_rate: 8_000,
+            channels: vec![vec![0.0; 64]],
+        };
+        assert_eq!(estimate_rt60(&silent), None);
+    }
+
+    #[test]
+    fn inspect_reports_direct_to_reverberant_ratio() {
+        let mut channel = vec![0.0_f32; 8_000];
+        channel[10] = 1.0;
+        // 100 samples of 0.01 after the direct window: a hundredth of the
+        // direct energy.
+        channel[1_000..1_100].fill(0.01);
+        let ir = ImpulseResponse {
+            sample_rate: 8_000,
+            channels: vec![channel],
+        };
+        let info = ir.inspect();
+        assert_eq!(info.channels, 1);
+        assert_eq!(info.length_seconds, 1.0);
+        assert!((info.direct_to_reverberant_db.unwrap() - 20.0).abs() < 1e-3);
+    }
+
     #[test]
     fn normalize_impulse_response_channels_trims_tail_when_requested() {
         let mut channels = vec![vec![1.0_f32, 0.2, 0.01, 0.0001, 0.00001]];
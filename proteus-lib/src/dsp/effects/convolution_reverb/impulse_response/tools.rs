@@ -0,0 +1,293 @@
+//! Offline impulse response preparation used by the IR tooling commands:
+//! resampling, channel folding, trimming, and validation of unnormalized
+//! IRs before they are attached to a container.
+
+use std::fmt;
+
+use crate::dsp::resample::{ResampleQuality, Resampler};
+
+use super::ImpulseResponse;
+
+/// Fade applied where [`trim_impulse_response`] cuts a tail short, in
+/// seconds.
+const TRIM_FADE_SECONDS: f32 = 0.005;
+/// Leading silence, in seconds, beyond which an IR is flagged.
+const LEADING_SILENCE_WARN_SECONDS: f32 = 0.05;
+/// Length, in seconds, beyond which an IR is flagged as expensive.
+const LONG_IR_WARN_SECONDS: f32 = 10.0;
+/// Channel mean, relative to the peak, beyond which DC offset is flagged.
+const DC_OFFSET_WARN_RATIO: f32 = 0.01;
+
+/// Resample every channel of `ir` to `sample_rate` with a windowed-sinc
+/// converter.
+pub fn resample_impulse_response(ir: &ImpulseResponse, sample_rate: u32) -> ImpulseResponse {
+    if sample_rate == 0 || sample_rate == ir.sample_rate {
+        return ir.clone();
+    }
+    let channels = ir
+        .channels
+        .iter()
+        .map(|channel| {
+            let frames = (channel.len() as u64 * u64::from(sample_rate))
+                .div_ceil(u64::from(ir.sample_rate.max(1))) as usize;
+            let mut resampler =
+                Resampler::new(ResampleQuality::SincBest, ir.sample_rate, sample_rate, 1);
+            let mut resampled = Vec::with_capacity(frames);
+            resampler.process(channel, &mut resampled);
+            // Zero look-ahead flushes the final frames.
+            resampler.process(&vec![0.0; resampler.latency_frames() + 1], &mut resampled);
+            resampled.resize(frames, 0.0);
+            resampled
+        })
+        .collect();
+    ImpulseResponse {
+        sample_rate,
+        channels,
+    }
+}
+
+/// Fold `ir` to `channels` channels.
+///
+/// Output channel `i` averages every source channel whose index is `i`
+/// modulo `channels`, so stereo folds to mono as `(L + R) / 2`. Asking for
+/// more channels than the IR has repeats them as the reverb does.
+pub fn downmix_impulse_response(ir: &ImpulseResponse, channels: usize) -> ImpulseResponse {
+    if channels == 0 || channels == ir.channel_count() {
+        return ir.clone();
+    }
+    let frames = ir.frames();
+    let channels = (0..channels)
+        .map(|output| {
+            if output >= ir.channel_count() {
+                return ir.channel_for_output(output).to_vec();
+            }
+            let sources: Vec<&Vec<f32>> =
+                ir.channels.iter().skip(output).step_by(channels).collect();
+            let mut folded = vec![0.0_f32; frames];
+            for source in &sources {
+                for (sum, sample) in folded.iter_mut().zip(source.iter()) {
+                    *sum += sample / sources.len() as f32;
+                }
+            }
+            folded
+        })
+        .collect();
+    ImpulseResponse {
+        sample_rate: ir.sample_rate,
+        channels,
+    }
+}
+
+/// Cut `start_seconds` from the front of `ir`, then shorten it to
+/// `max_seconds` and to the last sample within `tail_db` of the peak.
+///
+/// A tail that is cut short gets a 5 ms fade-out so the cut does not click.
+pub fn trim_impulse_response(
+    ir: &ImpulseResponse,
+    start_seconds: f32,
+    max_seconds: Option<f32>,
+    tail_db: Option<f32>,
+) -> ImpulseResponse {
+    let rate = ir.sample_rate as f32;
+    let start = (start_seconds.max(0.0) * rate) as usize;
+    let mut channels: Vec<Vec<f32>> = ir
+        .channels
+        .iter()
+        .map(|channel| channel[start.min(channel.len())..].to_vec())
+        .collect();
+
+    let frames = channels.iter().map(Vec::len).max().unwrap_or(0);
+    let mut keep = max_seconds.map_or(frames, |seconds| {
+        ((seconds.max(0.0) * rate) as usize).min(frames)
+    });
+    if let Some(tail_db) = tail_db.filter(|db| db.is_finite()) {
+        let peak = channels
+            .iter()
+            .flatten()
+            .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+        let threshold = peak * 10.0_f32.powf(tail_db / 20.0);
+        let last = channels
+            .iter()
+            .filter_map(|channel| channel.iter().rposition(|s| s.abs() >= threshold))
+            .max()
+            .map_or(0, |last| last + 1);
+        keep = keep.min(last);
+    }
+
+    if keep < frames {
+        let fade = ((TRIM_FADE_SECONDS * rate) as usize).min(keep);
+        for channel in &mut channels {
+            channel.truncate(keep);
+            let len = channel.len();
+            for (offset, sample) in channel[len.saturating_sub(fade)..].iter_mut().enumerate() {
+                *sample *= 1.0 - (offset + 1) as f32 / fade as f32;
+            }
+        }
+    }
+    ImpulseResponse {
+        sample_rate: ir.sample_rate,
+        channels,
+    }
+}
+
+/// Problem found by [`validate_impulse_response`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImpulseResponseIssue {
+    /// The IR has no channels or no nonzero samples.
+    Silent,
+    /// The channel at this index has no samples.
+    EmptyChannel(usize),
+    /// This many samples are at or beyond full scale.
+    Clipped(usize),
+    /// The channel's mean is far from zero, relative to the peak.
+    DcOffset {
+        /// Channel index.
+        channel: usize,
+        /// Mean sample value.
+        offset: f32,
+    },
+    /// Seconds before the first sample within 60 dB of the peak.
+    LeadingSilence(f32),
+    /// Length in seconds, long enough to make convolution expensive.
+    Long(f32),
+}
+
+impl fmt::Display for ImpulseResponseIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Silent => write!(f, "impulse response is silent"),
+            Self::EmptyChannel(channel) => write!(f, "channel {} is empty", channel),
+            Self::Clipped(samples) => write!(f, "{} samples are clipped", samples),
+            Self::DcOffset { channel, offset } => {
+                write!(f, "channel {} has a DC offset of {:.4}", channel, offset)
+            }
+            Self::LeadingSilence(seconds) => {
+                write!(f, "{:.0} ms of silence before the onset", seconds * 1000.0)
+            }
+            Self::Long(seconds) => write!(f, "{:.1} s long; convolution will be costly", seconds),
+        }
+    }
+}
+
+/// Check an unnormalized IR for problems worth fixing before it is attached
+/// to a container.
+pub fn validate_impulse_response(ir: &ImpulseResponse) -> Vec<ImpulseResponseIssue> {
+    let mut issues = Vec::new();
+    let peak = ir
+        .channels
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+    if peak <= 0.0 {
+        return vec![ImpulseResponseIssue::Silent];
+    }
+    let rate = ir.sample_rate.max(1) as f32;
+
+    for (index, channel) in ir.channels.iter().enumerate() {
+        if channel.is_empty() {
+            issues.push(ImpulseResponseIssue::EmptyChannel(index));
+        }
+    }
+    let clipped = ir
+        .channels
+        .iter()
+        .flatten()
+        .filter(|sample| sample.abs() >= 1.0)
+        .count();
+    if clipped > 0 {
+        issues.push(ImpulseResponseIssue::Clipped(clipped));
+    }
+    for (channel, samples) in ir.channels.iter().enumerate() {
+        if samples.is_empty() {
+            continue;
+        }
+        let offset = samples.iter().sum::<f32>() / samples.len() as f32;
+        if offset.abs() > peak * DC_OFFSET_WARN_RATIO {
+            issues.push(ImpulseResponseIssue::DcOffset { channel, offset });
+        }
+    }
+    let onset = ir
+        .channels
+        .iter()
+        .filter_map(|channel| channel.iter().position(|s| s.abs() >= peak * 1.0e-3))
+        .min()
+        .unwrap_or(0);
+    if onset as f32 / rate > LEADING_SILENCE_WARN_SECONDS {
+        issues.push(ImpulseResponseIssue::LeadingSilence(onset as f32 / rate));
+    }
+    let seconds = ir.frames() as f32 / rate;
+    if seconds > LONG_IR_WARN_SECONDS {
+        issues.push(ImpulseResponseIssue::Long(seconds));
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_and_downmix_keep_the_response_shape() {
+        let ir = ImpulseResponse {
+            sample_rate: 24_000,
+            channels: vec![vec![0.5; 2_400], vec![0.25; 1_200]],
+        };
+        let resampled = resample_impulse_response(&ir, 48_000);
+        assert_eq!(resampled.sample_rate, 48_000);
+        assert_eq!(resampled.channels[0].len(), 4_800);
+        assert!((resampled.channels[0][2_400] - 0.5).abs() < 1e-3);
+
+        let mono = downmix_impulse_response(&ir, 1);
+        assert_eq!(mono.channel_count(), 1);
+        assert_eq!(mono.channels[0][0], 0.375);
+        assert_eq!(mono.channels[0][2_000], 0.25);
+        assert_eq!(
+            downmix_impulse_response(&mono, 2).channels[1],
+            mono.channels[0]
+        );
+    }
+
+    #[test]
+    fn trim_cuts_the_start_and_fades_a_shortened_tail() {
+        let ir = ImpulseResponse {
+            sample_rate: 1_000,
+            channels: vec![(0..1_000).map(|i| 1.0 - i as f32 / 1_000.0).collect()],
+        };
+        let trimmed = trim_impulse_response(&ir, 0.1, Some(0.5), None);
+        let channel = &trimmed.channels[0];
+        assert_eq!(channel.len(), 500);
+        assert_eq!(channel[0], 0.9);
+        assert_eq!(channel[499], 0.0);
+
+        // -20 dB of the 1.0 peak is 0.1, reached at sample 900.
+        let trimmed = trim_impulse_response(&ir, 0.0, None, Some(-20.0));
+        assert_eq!(trimmed.channels[0].len(), 901);
+    }
+
+    #[test]
+    fn validate_flags_clipping_offset_and_pre_delay() {
+        let mut channel = vec![0.0_f32; 48_000];
+        channel[4_800] = 1.0;
+        channel[4_801..].fill(0.05);
+        let ir = ImpulseResponse {
+            sample_rate: 48_000,
+            channels: vec![channel, Vec::new()],
+        };
+        let issues = validate_impulse_response(&ir);
+        assert!(issues.contains(&ImpulseResponseIssue::EmptyChannel(1)));
+        assert!(issues.contains(&ImpulseResponseIssue::Clipped(1)));
+        assert!(issues.contains(&ImpulseResponseIssue::LeadingSilence(0.1)));
+        assert!(issues
+            .iter()
+            .any(|issue| matches!(issue, ImpulseResponseIssue::DcOffset { channel: 0, .. })));
+
+        let silent = ImpulseResponse {
+            sample_rate: 48_000,
+            channels: vec![vec![0.0; 16]],
+        };
+        assert_eq!(
+            validate_impulse_response(&silent),
+            vec![ImpulseResponseIssue::Silent]
+        );
+    }
+}
@@ -8,8 +8,10 @@ use std::sync::{Arc, Mutex, OnceLock};
 use log::warn;
 
 use super::impulse_response;
+use super::ir_processing::ImpulseResponseProcessing;
 use super::reverb;
-use super::spec::ImpulseResponseSpec;
+use super::spec::{ImpulseResponseAttachments, ImpulseResponseSpec};
+use super::ResolvedConfig;
 
 type ImpulseResponseCacheMap =
     HashMap<ImpulseResponseCacheKey, Arc<impulse_response::ImpulseResponse>>;
@@ -57,21 +59,24 @@ struct ImpulseResponseCacheKey {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct ReverbKernelCacheKey {
     channels: usize,
+    fft_size: usize,
     impulse_response: ImpulseResponseCacheKey,
+    processing: Option<String>,
 }
 
 pub(super) fn build_reverb_with_impulse_response(
-    channels: usize,
+    config: &ResolvedConfig,
+    attachments: &ImpulseResponseAttachments,
     dry_wet: f32,
-    impulse_spec: Option<ImpulseResponseSpec>,
-    container_path: Option<&str>,
-    tail_db: f32,
 ) -> Option<reverb::Reverb> {
-    let impulse_spec = impulse_spec?;
+    let impulse_spec = config.impulse_spec.clone()?;
+    let container_path = config.container_path.as_deref();
+    let (channels, tail_db, fft_size) = (config.channels, config.tail_db, config.fft_size);
+    let processing = &config.processing;
 
     use self::impulse_response::{
-        load_impulse_response_from_file_with_tail,
-        load_impulse_response_from_prot_attachment_with_tail, ImpulseResponseError,
+        load_impulse_response_from_bytes_with_tail, load_impulse_response_from_file_with_tail,
+        ImpulseResponseError,
     };
 
     #[derive(Debug)]
@@ -101,82 +106,66 @@ pub(super) fn build_reverb_with_impulse_response(
 
     impl std::error::Error for ReverbLoadError {}
 
-    let result = match impulse_spec {
-        ImpulseResponseSpec::Attachment(name) => container_path
+    let load_attachment = |container_path: &str, name: String| {
+        let cache_key = ImpulseResponseCacheKey {
+            source: ImpulseResponseCacheSource::Attachment {
+                container_path: container_path.to_string(),
+                attachment_name: name.clone(),
+            },
+            tail_db_bits: tail_db.to_bits(),
+        };
+        load_cached_impulse_response(cache_key.clone(), || {
+            let bytes = attachments
+                .get(&name)
+                .ok_or_else(|| ImpulseResponseError::AttachmentNotFound(name.clone()))
+                .map_err(ReverbLoadError::AttachmentLoad)?;
+            load_impulse_response_from_bytes_with_tail(bytes, Some(tail_db))
+                .map_err(ReverbLoadError::AttachmentLoad)
+        })
+        .map(|impulse_response| (cache_key, impulse_response))
+    };
+
+    let result = match impulse_spec.attachment_name(container_path) {
+        Some(name) => container_path
             .ok_or(ReverbLoadError::MissingContainerPath)
-            .and_then(|path| {
-                let cache_key = ImpulseResponseCacheKey {
-                    source: ImpulseResponseCacheSource::Attachment {
-                        container_path: path.to_string(),
-                        attachment_name: name.clone(),
-                    },
-                    tail_db_bits: tail_db.to_bits(),
-                };
-                let impulse_response = load_cached_impulse_response(cache_key.clone(), || {
-                    load_impulse_response_from_prot_attachment_with_tail(path, &name, Some(tail_db))
-                        .map_err(ReverbLoadError::AttachmentLoad)
-                })?;
-                Ok((cache_key, impulse_response))
-            }),
-        ImpulseResponseSpec::FilePath(path) => {
-            let resolved_path = resolve_impulse_response_path(container_path, &path);
-            if resolved_path.exists() {
-                let cache_key = ImpulseResponseCacheKey {
-                    source: ImpulseResponseCacheSource::FilePath {
-                        path: resolved_path.to_string_lossy().into_owned(),
-                    },
-                    tail_db_bits: tail_db.to_bits(),
-                };
-                load_cached_impulse_response(cache_key.clone(), || {
-                    load_impulse_response_from_file_with_tail(&resolved_path, Some(tail_db))
-                        .map_err(ReverbLoadError::FileLoad)
-                })
-                .map(|impulse_response| (cache_key, impulse_response))
-            } else {
-                match container_path {
-                    Some(container_path) => {
-                        let fallback_name = Path::new(&path)
-                            .file_name()
-                            .and_then(|name| name.to_str())
-                            .map(|name| name.to_string());
-                        if let Some(fallback_name) = fallback_name {
-                            let cache_key = ImpulseResponseCacheKey {
-                                source: ImpulseResponseCacheSource::Attachment {
-                                    container_path: container_path.to_string(),
-                                    attachment_name: fallback_name.clone(),
-                                },
-                                tail_db_bits: tail_db.to_bits(),
-                            };
-                            load_cached_impulse_response(cache_key.clone(), || {
-                                load_impulse_response_from_prot_attachment_with_tail(
-                                    container_path,
-                                    &fallback_name,
-                                    Some(tail_db),
-                                )
-                                .map_err(ReverbLoadError::AttachmentLoad)
-                            })
-                            .map(|impulse_response| (cache_key, impulse_response))
-                        } else {
-                            Err(ReverbLoadError::PathNotFound(resolved_path))
-                        }
-                    }
-                    None => Err(ReverbLoadError::PathNotFound(resolved_path)),
+            .and_then(|path| load_attachment(path, name)),
+        None => match impulse_spec {
+            ImpulseResponseSpec::Attachment(_) => Err(ReverbLoadError::MissingContainerPath),
+            ImpulseResponseSpec::FilePath(path) => {
+                let resolved_path = resolve_impulse_response_path(container_path, &path);
+                if resolved_path.exists() {
+                    let cache_key = ImpulseResponseCacheKey {
+                        source: ImpulseResponseCacheSource::FilePath {
+                            path: resolved_path.to_string_lossy().into_owned(),
+                        },
+                        tail_db_bits: tail_db.to_bits(),
+                    };
+                    load_cached_impulse_response(cache_key.clone(), || {
+                        load_impulse_response_from_file_with_tail(&resolved_path, Some(tail_db))
+                            .map_err(ReverbLoadError::FileLoad)
+                    })
+                    .map(|impulse_response| (cache_key, impulse_response))
+                } else {
+                    Err(ReverbLoadError::PathNotFound(resolved_path))
                 }
             }
-        }
+        },
     };
 
     match result {
         Ok((impulse_response_cache_key, impulse_response)) => {
             let kernel_cache_key = ReverbKernelCacheKey {
                 channels,
+                fft_size,
                 impulse_response: impulse_response_cache_key,
+                processing: processing.cache_key(),
             };
             Some(build_cached_reverb(
                 kernel_cache_key,
                 channels,
                 dry_wet,
                 &impulse_response,
+                processing,
             ))
         }
         Err(err) => {
@@ -194,6 +183,7 @@ fn build_cached_reverb(
     channels: usize,
     dry_wet: f32,
     impulse_response: &impulse_response::ImpulseResponse,
+    processing: &ImpulseResponseProcessing,
 ) -> reverb::Reverb {
     use super::DEFAULT_DRY_WET;
 
@@ -212,8 +202,19 @@ fn build_cached_reverb(
         return reverb;
     }
 
-    let mut template =
-        reverb::Reverb::new_with_impulse_response(channels, DEFAULT_DRY_WET, impulse_response);
+    let processed;
+    let impulse_response = if processing.is_identity() {
+        impulse_response
+    } else {
+        processed = processing.apply(impulse_response);
+        &processed
+    };
+    let mut template = reverb::Reverb::with_fft_size(
+        channels,
+        DEFAULT_DRY_WET,
+        impulse_response,
+        cache_key.fft_size,
+    );
     template.clear_state();
     let template = Arc::new(template);
 
@@ -260,6 +261,21 @@ where
     Ok(cached)
 }
 
+/// Whether loading `impulse_spec` fails before any audio is read: an
+/// attachment with no container to read it from, or a file path that does
+/// not exist and has no container to fall back to.
+pub(super) fn impulse_response_unreachable(
+    impulse_spec: &ImpulseResponseSpec,
+    container_path: Option<&str>,
+) -> bool {
+    match impulse_spec {
+        ImpulseResponseSpec::Attachment(_) => container_path.is_none(),
+        ImpulseResponseSpec::FilePath(path) => {
+            container_path.is_none() && !resolve_impulse_response_path(None, path).exists()
+        }
+    }
+}
+
 pub(super) fn resolve_impulse_response_path(container_path: Option<&str>, path: &str) -> PathBuf {
     let path = Path::new(path);
     if path.is_absolute() {
@@ -277,7 +293,10 @@ pub(super) fn resolve_impulse_response_path(container_path: Option<&str>, path:
 
 #[cfg(test)]
 mod tests {
-    use super::{clear_global_caches, resolve_impulse_response_path};
+    use super::{
+        clear_global_caches, impulse_response_unreachable, resolve_impulse_response_path,
+        ImpulseResponseSpec,
+    };
     use std::path::PathBuf;
 
     #[test]
@@ -286,6 +305,18 @@ mod tests {
         assert_eq!(resolved, PathBuf::from("/tmp/project/ir/hall.wav"));
     }
 
+    #[test]
+    fn unreachable_specs_are_detected_without_loading() {
+        let missing = ImpulseResponseSpec::FilePath("/nonexistent/ir/hall.wav".to_string());
+        assert!(impulse_response_unreachable(&missing, None));
+        assert!(!impulse_response_unreachable(
+            &missing,
+            Some("/tmp/project/song.prot")
+        ));
+        let attachment = ImpulseResponseSpec::Attachment("hall.wav".to_string());
+        assert!(impulse_response_unreachable(&attachment, None));
+    }
+
     #[test]
     fn clear_global_caches_is_idempotent() {
         clear_global_caches();
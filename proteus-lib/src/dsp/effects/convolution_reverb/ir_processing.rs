@@ -0,0 +1,164 @@
+//! Load-time EQ, pre-delay trim, and level applied to impulse responses.
+
+use super::impulse_response::ImpulseResponse;
+use crate::dsp::effects::core::DspEffect;
+use crate::dsp::effects::multiband_eq::{MultibandEqEffect, MultibandEqSettings};
+use crate::dsp::effects::EffectContext;
+
+/// Level relative to the IR peak below which leading samples count as
+/// pre-delay (-60 dB).
+const ONSET_THRESHOLD: f32 = 1.0e-3;
+
+/// Processing applied to an impulse response before it is partitioned.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct ImpulseResponseProcessing {
+    pub eq: Option<MultibandEqSettings>,
+    pub pre_delay_trim_ms: f32,
+    pub gain_db: f32,
+}
+
+impl ImpulseResponseProcessing {
+    /// Build processing from settings values, ignoring non-finite ones.
+    pub fn new(
+        eq: Option<MultibandEqSettings>,
+        pre_delay_trim_ms: Option<f32>,
+        gain_db: Option<f32>,
+    ) -> Self {
+        let finite = |value: Option<f32>| value.filter(|value| value.is_finite()).unwrap_or(0.0);
+        Self {
+            eq,
+            pre_delay_trim_ms: finite(pre_delay_trim_ms).max(0.0),
+            gain_db: finite(gain_db),
+        }
+    }
+
+    /// Whether applying the processing would leave the IR unchanged.
+    pub fn is_identity(&self) -> bool {
+        self.eq.is_none() && self.pre_delay_trim_ms == 0.0 && self.gain_db == 0.0
+    }
+
+    /// Key telling processed kernels apart in the reverb cache; `None` for
+    /// the unprocessed IR.
+    pub fn cache_key(&self) -> Option<String> {
+        (!self.is_identity()).then(|| format!("{:?}", self))
+    }
+
+    /// Return a copy of `ir` with its pre-delay trimmed, then equalized, then
+    /// scaled.
+    ///
+    /// The trim stops at the first sample within 60 dB of the peak, so it
+    /// never cuts into the direct sound.
+    pub fn apply(&self, ir: &ImpulseResponse) -> ImpulseResponse {
+        let trim = self.trim_frames(ir);
+        let gain = 10.0_f32.powf(self.gain_db / 20.0);
+        let channels = ir
+            .channels
+            .iter()
+            .map(|channel| {
+                let mut samples = channel[trim.min(channel.len())..].to_vec();
+                if let Some(eq) = &self.eq {
+                    samples = equalize(eq, ir.sample_rate, &samples);
+                }
+                if gain != 1.0 {
+                    samples.iter_mut().for_each(|sample| *sample *= gain);
+                }
+                samples
+            })
+            .collect();
+        ImpulseResponse {
+            sample_rate: ir.sample_rate,
+            channels,
+        }
+    }
+
+    fn trim_frames(&self, ir: &ImpulseResponse) -> usize {
+        let requested = (self.pre_delay_trim_ms / 1000.0 * ir.sample_rate as f32) as usize;
+        if requested == 0 {
+            return 0;
+        }
+        let peak = ir
+            .channels
+            .iter()
+            .flatten()
+            .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+        let threshold = peak * ONSET_THRESHOLD;
+        let onset = ir
+            .channels
+            .iter()
+            .filter_map(|channel| channel.iter().position(|sample| sample.abs() >= threshold))
+            .min()
+            .unwrap_or(0);
+        requested.min(onset)
+    }
+}
+
+/// Run one IR channel through a multiband EQ at the IR's sample rate.
+fn equalize(eq: &MultibandEqSettings, sample_rate: u32, samples: &[f32]) -> Vec<f32> {
+    let Ok(context) = EffectContext::new(sample_rate, 1, None, None, -60.0) else {
+        return samples.to_vec();
+    };
+    let mut effect = MultibandEqEffect::default();
+    effect.enabled = true;
+    effect.settings = eq.clone();
+    effect.process(samples, &context, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::effects::multiband_eq::HighEdgeFilterSettings;
+
+    fn impulse_at(index: usize) -> ImpulseResponse {
+        let mut channel = vec![0.0_f32; 800];
+        channel[index] = 1.0;
+        channel[index + 100] = 0.25;
+        ImpulseResponse {
+            sample_rate: 8_000,
+            channels: vec![channel],
+        }
+    }
+
+    #[test]
+    fn pre_delay_trim_stops_at_the_direct_sound() {
+        let ir = impulse_at(80);
+        let processing = ImpulseResponseProcessing::new(None, Some(5.0), None);
+        assert_eq!(processing.apply(&ir).channels[0][40], 1.0);
+
+        let processing = ImpulseResponseProcessing::new(None, Some(50.0), None);
+        let trimmed = processing.apply(&ir);
+        assert_eq!(trimmed.channels[0][0], 1.0);
+        assert_eq!(trimmed.channels[0].len(), 720);
+    }
+
+    #[test]
+    fn gain_scales_and_eq_darkens_the_response() {
+        let ir = impulse_at(0);
+        let processing = ImpulseResponseProcessing::new(None, None, Some(-6.0));
+        assert!((processing.apply(&ir).channels[0][100] - 0.125).abs() < 0.01);
+
+        let eq = MultibandEqSettings::new(
+            Vec::new(),
+            None,
+            Some(HighEdgeFilterSettings::LowPass {
+                freq_hz: 500,
+                q: 0.707,
+            }),
+        );
+        let darkened = ImpulseResponseProcessing::new(Some(eq), None, None).apply(&ir);
+        let channel = &darkened.channels[0];
+        assert!(channel[0] < 0.5);
+        // A low-pass keeps the DC gain of the direct impulse.
+        let direct: f32 = channel[..100].iter().sum();
+        assert!((direct - 1.0).abs() < 0.05, "{direct}");
+    }
+
+    #[test]
+    fn identity_processing_has_no_cache_key() {
+        let identity = ImpulseResponseProcessing::new(None, Some(f32::NAN), Some(0.0));
+        assert!(identity.is_identity());
+        assert_eq!(identity.cache_key(), None);
+        assert!(ImpulseResponseProcessing::new(None, None, Some(3.0))
+            .cache_key()
+            .is_some());
+    }
+}
@@ -1,63 +1,50 @@
 //! Convolution reverb effect wrapper for the DSP chain.
 //!
 //! Impulse response loading, caching, and reverb kernel construction live in
-//! `ir_loader`. The effect struct, its `DspEffect` impl, and the runtime
-//! buffering state are defined here.
+//! `ir_loader`, and the load-time IR EQ, trim, and level in `ir_processing`.
+//! The serialized settings are in `settings` and the runtime buffering state
+//! in `state`; the effect struct and its `DspEffect` impl are defined here.
 
-use log::info;
 use serde::{Deserialize, Serialize};
 
 use super::core::smoother::ParamSmoother;
-use super::EffectContext;
+use super::{EffectBypass, EffectContext};
+use ir_processing::ImpulseResponseProcessing;
+use state::ConvolutionReverbState;
 
 pub mod convolution;
+#[cfg(feature = "gpu")]
+mod gpu;
 pub mod impulse_response;
 mod ir_loader;
+mod ir_processing;
+mod pool;
 pub mod reverb;
+mod settings;
 mod spec;
+mod state;
+
+#[cfg(feature = "gpu")]
+pub use gpu::available as gpu_available;
+pub use reverb::ReverbMetrics;
+pub use settings::ConvolutionReverbSettings;
 
 pub use ir_loader::clear_global_caches;
-pub use spec::{parse_impulse_response_string, ImpulseResponseSpec};
+pub use spec::{parse_impulse_response_string, ImpulseResponseAttachments, ImpulseResponseSpec};
 
 pub(crate) const DEFAULT_DRY_WET: f32 = 0.000001;
-const DEFAULT_TAIL_DB: f32 = -60.0;
+/// Tail level impulse responses are truncated at while shortened under load.
+const SHORTENED_TAIL_DB: f32 = -30.0;
 pub(crate) const REVERB_BATCH_BLOCKS: usize = 2;
-const DRAIN_MAX_BLOCKS: usize = 128;
-const DRAIN_SILENCE_EPSILON: f32 = 1.0e-6;
-const DRAIN_SILENT_BLOCKS_TO_STOP: usize = 2;
 
 /// Preferred processing batch size in interleaved samples for the reverb.
 pub fn preferred_batch_samples(channels: usize) -> usize {
     reverb::preferred_batch_samples(channels)
 }
 
-/// Serialized configuration for convolution reverb impulse response selection.
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
-#[serde(default)]
-pub struct ConvolutionReverbSettings {
-    /// Inline IR identifier or attachment name (primary field, checked first).
-    pub impulse_response: Option<String>,
-    /// Name of the IR embedded as a Matroska attachment (legacy alias).
-    pub impulse_response_attachment: Option<String>,
-    /// Filesystem path to an external IR audio file (legacy alias).
-    pub impulse_response_path: Option<String>,
-    /// dB level below peak at which the IR tail is considered silent and truncated.
-    pub impulse_response_tail_db: Option<f32>,
-    /// Legacy alias for `impulse_response_tail_db`.
-    pub impulse_response_tail: Option<f32>,
-}
-
-impl ConvolutionReverbSettings {
-    /// Resolve a tail trim value, falling back to the default.
-    pub fn tail_db_or_default(&self) -> f32 {
-        self.impulse_response_tail_db
-            .or(self.impulse_response_tail)
-            .unwrap_or(DEFAULT_TAIL_DB)
-    }
-}
-
 /// Configured convolution reverb effect with runtime state.
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct ConvolutionReverbEffect {
     /// Whether the effect is active; when `false` samples pass through unmodified.
@@ -74,6 +61,10 @@ pub struct ConvolutionReverbEffect {
     resolved_config: Option<ResolvedConfig>,
     #[serde(skip)]
     dry_wet_smoother: Option<ParamSmoother>,
+    #[serde(skip)]
+    shorten_tail: bool,
+    #[serde(skip)]
+    larger_blocks: bool,
 }
 
 impl std::fmt::Debug for ConvolutionReverbEffect {
@@ -95,6 +86,8 @@ impl Default for ConvolutionReverbEffect {
             state: None,
             resolved_config: None,
             dry_wet_smoother: None,
+            shorten_tail: false,
+            larger_blocks: false,
         }
     }
 }
@@ -137,6 +130,11 @@ impl crate::dsp::effects::core::DspEffect for ConvolutionReverbEffect {
             output.extend_from_slice(input);
             return;
         };
+        state
+            .reverb
+            .set_worker_threads(self.settings.worker_threads_or_default());
+        #[cfg(feature = "gpu")]
+        state.reverb.set_gpu(self.settings.gpu);
 
         if mix_settled {
             state.reverb.set_dry_wet(current_mix);
@@ -183,6 +181,52 @@ impl ConvolutionReverbEffect {
         &mut self.settings
     }
 
+    /// Threading and timing of the last processed block, once the reverb
+    /// has been built.
+    pub fn metrics(&self) -> Option<ReverbMetrics> {
+        self.state.as_ref().map(|state| state.reverb.metrics())
+    }
+
+    /// Reduce processing cost while playback is overloaded.
+    ///
+    /// `shorten_tail` truncates the impulse response at no lower than -30 dB
+    /// and `larger_blocks` convolves in blocks twice the usual size. Either
+    /// change rebuilds the reverb on the next processed chunk, so its current
+    /// tail is cut. Neither is serialized.
+    pub fn set_overload_degradation(&mut self, shorten_tail: bool, larger_blocks: bool) {
+        self.shorten_tail = shorten_tail;
+        self.larger_blocks = larger_blocks;
+    }
+
+    /// Container attachment the impulse response loads from in `context`,
+    /// if any.
+    pub fn impulse_response_attachment(&self, context: &EffectContext) -> Option<String> {
+        self.resolve_impulse_spec(context)?
+            .attachment_name(context.container_path())
+    }
+
+    /// Why the enabled effect passes audio through untouched in `context`,
+    /// if it does.
+    ///
+    /// A load failure is known once this instance has tried the impulse
+    /// response; before that only a missing container or file is detected.
+    pub fn bypass(&self, context: &EffectContext) -> Option<EffectBypass> {
+        if !self.enabled {
+            return None;
+        }
+        let config = self.resolve_config(context);
+        let Some(spec) = config.impulse_spec.as_ref() else {
+            return Some(EffectBypass::NoImpulseResponse);
+        };
+        let load_failed = self.resolved_config.as_ref() == Some(&config) && self.state.is_none();
+        if load_failed
+            || ir_loader::impulse_response_unreachable(spec, config.container_path.as_deref())
+        {
+            return Some(EffectBypass::ImpulseResponseUnavailable);
+        }
+        None
+    }
+
     fn update_dry_wet_smoother(&mut self, context: &EffectContext) {
         let target = self.dry_wet.clamp(0.0, 1.0);
         let smoother = self
@@ -201,11 +245,9 @@ impl ConvolutionReverbEffect {
 
         let start = std::time::Instant::now();
         let reverb = ir_loader::build_reverb_with_impulse_response(
-            config.channels,
+            &config,
+            context.impulse_response_attachments(),
             self.dry_wet,
-            config.impulse_spec.clone(),
-            config.container_path.as_deref(),
-            config.tail_db,
         );
         let elapsed_ms = start.elapsed().as_secs_f64() * 1000.0;
         log::info!(
@@ -219,9 +261,8 @@ impl ConvolutionReverbEffect {
         self.resolved_config = Some(config);
     }
 
-    fn resolve_config(&self, context: &EffectContext) -> ResolvedConfig {
-        let impulse_spec = self
-            .settings
+    fn resolve_impulse_spec(&self, context: &EffectContext) -> Option<ImpulseResponseSpec> {
+        self.settings
             .impulse_response
             .as_deref()
             .and_then(parse_impulse_response_string)
@@ -237,19 +278,38 @@ impl ConvolutionReverbEffect {
                     .as_deref()
                     .and_then(parse_impulse_response_string)
             })
-            .or_else(|| context.impulse_response_spec().cloned());
+            .or_else(|| context.impulse_response_spec().cloned())
+    }
 
+    fn resolve_config(&self, context: &EffectContext) -> ResolvedConfig {
+        let impulse_spec = self.resolve_impulse_spec(context);
         let tail_db = self
             .settings
             .impulse_response_tail_db
             .or(self.settings.impulse_response_tail)
             .unwrap_or(context.impulse_response_tail_db());
+        let tail_db = if self.shorten_tail {
+            tail_db.max(SHORTENED_TAIL_DB)
+        } else {
+            tail_db
+        };
+        let fft_size = if self.larger_blocks {
+            reverb::FFT_SIZE * 2
+        } else {
+            reverb::FFT_SIZE
+        };
 
         ResolvedConfig {
             channels: context.channels(),
             container_path: context.container_path().map(String::from),
             impulse_spec,
             tail_db,
+            fft_size,
+            processing: ImpulseResponseProcessing::new(
+                self.settings.impulse_response_eq.clone(),
+                self.settings.impulse_response_pre_delay_trim_ms,
+                self.settings.impulse_response_gain_db,
+            ),
         }
     }
 }
@@ -260,202 +320,59 @@ struct ResolvedConfig {
     container_path: Option<String>,
     impulse_spec: Option<ImpulseResponseSpec>,
     tail_db: f32,
-}
-
-#[derive(Clone)]
-struct ConvolutionReverbState {
-    reverb: reverb::Reverb,
-    input_buffer: Vec<f32>,
-    output_buffer: Vec<f32>,
-    block_in: Vec<f32>,
-    block_out: Vec<f32>,
-    block_samples: usize,
-    tail_drained: bool,
-}
-
-impl ConvolutionReverbState {
-    fn new(mut reverb: reverb::Reverb) -> Self {
-        info!("using convolution reverb");
-        let block_samples = reverb.block_size_samples();
-        reverb.set_dry_wet(DEFAULT_DRY_WET);
-        Self {
-            reverb,
-            input_buffer: Vec::new(),
-            output_buffer: Vec::new(),
-            block_in: Vec::new(),
-            block_out: Vec::new(),
-            block_samples,
-            tail_drained: false,
-        }
-    }
-
-    fn reset(&mut self) {
-        self.reverb.clear_state();
-        self.input_buffer.clear();
-        self.output_buffer.clear();
-        self.block_in.clear();
-        self.block_out.clear();
-        self.block_samples = self.reverb.block_size_samples();
-        self.tail_drained = false;
-    }
-
-    fn process_into(
-        &mut self,
-        samples: &[f32],
-        drain: bool,
-        out: &mut Vec<f32>,
-        dry_wet_smoother: Option<&mut ParamSmoother>,
-    ) {
-        if samples.is_empty() {
-            if !drain {
-                return;
-            }
-            if self.tail_drained {
-                return;
-            }
-
-            if !self.output_buffer.is_empty() {
-                out.extend(self.output_buffer.drain(..));
-            }
-            out.extend(self.drain_tail_blocks());
-            self.tail_drained = true;
-            return;
-        }
-
-        self.tail_drained = false;
-
-        if self.block_samples == 0 {
-            if let Some(smoother) = dry_wet_smoother {
-                self.reverb
-                    .process_into_with_smoother(samples, &mut self.block_out, smoother);
-            } else {
-                self.reverb.process_into(samples, &mut self.block_out);
-            }
-            out.extend_from_slice(&self.block_out);
-            return;
-        }
-
-        self.input_buffer.extend_from_slice(samples);
-        let batch_samples = self.block_samples * REVERB_BATCH_BLOCKS;
-        let should_flush = drain && !self.input_buffer.is_empty();
-        let mut dry_wet_smoother = dry_wet_smoother;
-        while self.input_buffer.len() >= batch_samples || should_flush {
-            let take = if self.input_buffer.len() >= batch_samples {
-                batch_samples
-            } else {
-                self.input_buffer.len()
-            };
-            self.block_in.clear();
-            self.block_in.extend(self.input_buffer.drain(0..take));
-            if let Some(smoother) = dry_wet_smoother.as_deref_mut() {
-                self.reverb.process_into_with_smoother(
-                    &self.block_in,
-                    &mut self.block_out,
-                    smoother,
-                );
-            } else {
-                self.reverb
-                    .process_into(&self.block_in, &mut self.block_out);
-            }
-            self.output_buffer.extend_from_slice(&self.block_out);
-            if take < batch_samples {
-                break;
-            }
-        }
-
-        // Keep output continuous for small chunks (e.g. around shuffle boundaries).
-        // If batch processing did not yield enough samples yet, process the pending
-        // input immediately instead of emitting silence.
-        while self.output_buffer.len() < samples.len() && !self.input_buffer.is_empty() {
-            let take = self.input_buffer.len().min(batch_samples.max(1));
-            self.block_in.clear();
-            self.block_in.extend(self.input_buffer.drain(0..take));
-            if let Some(smoother) = dry_wet_smoother.as_deref_mut() {
-                self.reverb.process_into_with_smoother(
-                    &self.block_in,
-                    &mut self.block_out,
-                    smoother,
-                );
-            } else {
-                self.reverb
-                    .process_into(&self.block_in, &mut self.block_out);
-            }
-            self.output_buffer.extend_from_slice(&self.block_out);
-        }
-
-        let chunk_len = samples.len();
-        if self.output_buffer.len() < chunk_len {
-            let out_len = self.output_buffer.len();
-            out.extend(self.output_buffer.drain(..));
-            if out_len < chunk_len {
-                out.extend_from_slice(&samples[out_len..chunk_len]);
-            }
-            self.output_buffer.clear();
-            return;
-        }
-
-        out.extend(self.output_buffer.drain(0..chunk_len));
-    }
-
-    fn drain_tail_blocks(&mut self) -> Vec<f32> {
-        if self.block_samples == 0 {
-            return Vec::new();
-        }
-
-        let mut drained = Vec::new();
-        let mut trailing_silent_blocks = 0usize;
-        let silence = vec![0.0_f32; self.block_samples.max(1)];
-
-        for _ in 0..DRAIN_MAX_BLOCKS {
-            self.reverb.process_into(&silence, &mut self.block_out);
-            if self.block_out.is_empty() {
-                break;
-            }
-
-            let max_abs = self
-                .block_out
-                .iter()
-                .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
-
-            if max_abs > DRAIN_SILENCE_EPSILON {
-                trailing_silent_blocks = 0;
-            } else {
-                trailing_silent_blocks = trailing_silent_blocks.saturating_add(1);
-            }
-
-            drained.extend_from_slice(&self.block_out);
-
-            if trailing_silent_blocks >= DRAIN_SILENT_BLOCKS_TO_STOP {
-                break;
-            }
-        }
-
-        drained
-    }
+    fft_size: usize,
+    processing: ImpulseResponseProcessing,
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        reverb::Reverb, ConvolutionReverbEffect, ConvolutionReverbSettings, ConvolutionReverbState,
-        EffectContext, ResolvedConfig,
+        ir_processing::ImpulseResponseProcessing,
+        reverb::{Reverb, FFT_SIZE},
+        ConvolutionReverbEffect, ConvolutionReverbSettings, ConvolutionReverbState, EffectContext,
+        ResolvedConfig,
     };
     use crate::dsp::effects::core::DspEffect;
 
     #[test]
-    fn tail_db_or_default_prefers_explicit_values() {
-        let settings = ConvolutionReverbSettings {
-            impulse_response_tail_db: Some(-24.0),
-            impulse_response_tail: Some(-30.0),
-            ..Default::default()
-        };
-        assert_eq!(settings.tail_db_or_default(), -24.0);
+    fn overload_degradation_shortens_tail_and_enlarges_blocks() {
+        let mut effect = ConvolutionReverbEffect::new(0.5);
+        let context = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
+        let config = effect.resolve_config(&context);
+        assert_eq!((config.tail_db, config.fft_size), (-60.0, FFT_SIZE));
+
+        effect.set_overload_degradation(true, true);
+        let config = effect.resolve_config(&context);
+        assert_eq!((config.tail_db, config.fft_size), (-30.0, FFT_SIZE * 2));
+
+        // A tail already shorter than the degraded one is kept.
+        effect.settings.impulse_response_tail_db = Some(-20.0);
+        assert_eq!(effect.resolve_config(&context).tail_db, -20.0);
+    }
+
+    #[test]
+    fn impulse_response_processing_changes_rebuild_the_reverb() {
+        let mut effect: ConvolutionReverbEffect = serde_json::from_str(
+            r#"{"impulse_response_gain_db": -6.0, "impulse_response_pre_delay_trim_ms": 10.0}"#,
+        )
+        .unwrap();
+        let context = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
+        let config = effect.resolve_config(&context);
+        assert_eq!(
+            config.processing,
+            ImpulseResponseProcessing::new(None, Some(10.0), Some(-6.0))
+        );
+
+        effect.settings.impulse_response_gain_db = Some(-3.0);
+        assert_ne!(effect.resolve_config(&context), config);
     }
 
     #[test]
     fn convolution_effect_passthrough_when_disabled() {
-        let mut effect = ConvolutionReverbEffect::default();
-        effect.enabled = false;
+        let mut effect = ConvolutionReverbEffect {
+            enabled: false,
+            ..Default::default()
+        };
         let input = vec![0.2_f32, -0.2, 0.1, -0.1];
         let context = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
         let output = effect.process(&input, &context, false);
@@ -472,6 +389,8 @@ mod tests {
             container_path: None,
             impulse_spec: None,
             tail_db: -60.0,
+            fft_size: FFT_SIZE,
+            processing: ImpulseResponseProcessing::default(),
         });
 
         let mut context = EffectContext::new(8_000, 1, None, None, -60.0).unwrap();
@@ -488,4 +407,37 @@ mod tests {
         assert!(smoother.current() > 0.2);
         assert!(smoother.current() < 0.8);
     }
+
+    #[test]
+    fn worker_threads_setting_reaches_the_reverb_metrics() {
+        let mut effect: ConvolutionReverbEffect =
+            serde_json::from_str(r#"{"enabled": true, "dry_wet": 0.5, "worker_threads": 2}"#)
+                .unwrap();
+        assert!(effect.metrics().is_none());
+        effect.state = Some(ConvolutionReverbState::new(Reverb::new(2, 0.5)));
+        effect.resolved_config = Some(ResolvedConfig {
+            channels: 2,
+            container_path: None,
+            impulse_spec: None,
+            tail_db: -60.0,
+            fft_size: FFT_SIZE,
+            processing: ImpulseResponseProcessing::default(),
+        });
+
+        let context = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
+        let _ = effect.process(&[0.5_f32; 8], &context, false);
+        let metrics = effect.metrics().expect("reverb should be built");
+        assert_eq!(metrics.worker_threads, 2);
+        assert_eq!(metrics.channel_workers, 2);
+
+        let auto = ConvolutionReverbSettings {
+            worker_threads: Some(0),
+            ..Default::default()
+        };
+        assert!(auto.worker_threads_or_default() >= 1);
+        assert_eq!(
+            ConvolutionReverbSettings::default().worker_threads_or_default(),
+            1
+        );
+    }
 }
@@ -0,0 +1,291 @@
+//! Long-lived worker threads for convolution reverb blocks.
+//!
+//! Convolving a block on several threads used to spawn scoped threads for
+//! every block, putting thread creation on the mix thread every few
+//! milliseconds. A [`ConvolutionPool`] starts its threads once, when a
+//! reverb's worker budget is set, and parks them between blocks.
+//!
+//! Work is submitted as a batch of jobs whose results come back in
+//! submission order, so sums over the results are deterministic. The
+//! submitting thread runs queued jobs of its own batch while it waits, which
+//! keeps nested batches (a channel job splitting its partition sums) moving
+//! however many pool threads are busy. A job that panics is reported as
+//! failed instead of unwinding into the mix thread, and the caller redoes
+//! that work inline.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+
+use log::warn;
+
+/// Shared pool of convolution worker threads.
+///
+/// Clones share the same threads. The threads exit once every clone has
+/// been dropped.
+#[derive(Clone)]
+pub(crate) struct ConvolutionPool {
+    owner: Arc<PoolOwner>,
+}
+
+struct PoolOwner {
+    shared: Arc<PoolShared>,
+    threads: usize,
+}
+
+struct PoolShared {
+    queue: Mutex<PoolQueue>,
+    work_ready: Condvar,
+}
+
+#[derive(Default)]
+struct PoolQueue {
+    tickets: VecDeque<Arc<dyn Ticket>>,
+    shutdown: bool,
+}
+
+/// One queued job of a batch; running it takes whichever job is still
+/// pending, or nothing if the submitter already ran them all.
+trait Ticket: Send + Sync {
+    fn run_one(&self);
+}
+
+/// A finished job: the job itself, handed back so its owned state survives,
+/// and its output, `None` when the job panicked.
+pub(crate) struct Finished<T, R> {
+    pub job: T,
+    pub output: Option<R>,
+}
+
+struct Batch<T, R> {
+    state: Mutex<BatchState<T, R>>,
+    done: Condvar,
+    run: fn(&mut T) -> R,
+}
+
+struct BatchState<T, R> {
+    pending: VecDeque<(usize, T)>,
+    finished: Vec<Option<Finished<T, R>>>,
+    remaining: usize,
+}
+
+impl ConvolutionPool {
+    /// Start a pool with `threads` worker threads.
+    ///
+    /// Threads that fail to start are logged and skipped; with none at all
+    /// every batch runs on the submitting thread.
+    pub(crate) fn new(threads: usize) -> Self {
+        let shared = Arc::new(PoolShared {
+            queue: Mutex::new(PoolQueue::default()),
+            work_ready: Condvar::new(),
+        });
+        let mut started = 0;
+        for index in 0..threads {
+            let worker_shared = shared.clone();
+            match thread::Builder::new()
+                .name(format!("proteus-convolution-{}", index))
+                .spawn(move || run_pool_thread(&worker_shared))
+            {
+                Ok(_) => started += 1,
+                Err(err) => warn!("failed to start convolution pool thread: {}", err),
+            }
+        }
+        Self {
+            owner: Arc::new(PoolOwner {
+                shared,
+                threads: started,
+            }),
+        }
+    }
+
+    /// Number of worker threads the pool runs.
+    pub(crate) fn threads(&self) -> usize {
+        self.owner.threads
+    }
+
+    /// Run `run` over every job and return them in submission order.
+    ///
+    /// Blocks until every job has finished; the calling thread runs pending
+    /// jobs itself while it waits.
+    pub(crate) fn run_batch<T, R>(&self, jobs: Vec<T>, run: fn(&mut T) -> R) -> Vec<Finished<T, R>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+    {
+        let count = jobs.len();
+        let batch = Arc::new(Batch {
+            state: Mutex::new(BatchState {
+                pending: jobs.into_iter().enumerate().collect(),
+                finished: (0..count).map(|_| None).collect(),
+                remaining: count,
+            }),
+            done: Condvar::new(),
+            run,
+        });
+        // The submitter takes one job itself, so one ticket fewer suffices.
+        let tickets = count.saturating_sub(1).min(self.threads());
+        if tickets > 0 {
+            let shared = &self.owner.shared;
+            let mut queue = lock_queue(shared);
+            for _ in 0..tickets {
+                queue.tickets.push_back(batch.clone());
+            }
+            drop(queue);
+            shared.work_ready.notify_all();
+        }
+        batch.help_until_done()
+    }
+}
+
+impl fmt::Debug for ConvolutionPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConvolutionPool")
+            .field("threads", &self.threads())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PoolOwner {
+    fn drop(&mut self) {
+        lock_queue(&self.shared).shutdown = true;
+        self.shared.work_ready.notify_all();
+    }
+}
+
+impl<T: Send, R: Send> Batch<T, R> {
+    /// Take the next pending job, run it outside the lock, and record it.
+    ///
+    /// Returns `false` when no job was pending.
+    fn run_next(&self) -> bool {
+        let Some((index, mut job)) = self.lock_state().pending.pop_front() else {
+            return false;
+        };
+        let output = panic::catch_unwind(AssertUnwindSafe(|| (self.run)(&mut job))).ok();
+        let mut state = self.lock_state();
+        state.finished[index] = Some(Finished { job, output });
+        state.remaining -= 1;
+        if state.remaining == 0 {
+            self.done.notify_all();
+        }
+        true
+    }
+
+    fn help_until_done(&self) -> Vec<Finished<T, R>> {
+        while self.run_next() {}
+        let mut state = self.lock_state();
+        while state.remaining > 0 {
+            state = self
+                .done
+                .wait(state)
+                .unwrap_or_else(|poisoned| recover("convolution batch", poisoned));
+        }
+        state.finished.drain(..).flatten().collect()
+    }
+
+    /// Recoverable poison policy, see [`recover`].
+    fn lock_state(&self) -> MutexGuard<'_, BatchState<T, R>> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| recover("convolution batch", poisoned))
+    }
+}
+
+impl<T: Send, R: Send> Ticket for Batch<T, R> {
+    fn run_one(&self) {
+        self.run_next();
+    }
+}
+
+fn run_pool_thread(shared: &PoolShared) {
+    loop {
+        let ticket = {
+            let mut queue = lock_queue(shared);
+            loop {
+                if let Some(ticket) = queue.tickets.pop_front() {
+                    break ticket;
+                }
+                if queue.shutdown {
+                    return;
+                }
+                queue = shared
+                    .work_ready
+                    .wait(queue)
+                    .unwrap_or_else(|poisoned| recover("convolution pool", poisoned));
+            }
+        };
+        ticket.run_one();
+    }
+}
+
+/// Recoverable poison policy: jobs and tickets run outside their locks, so
+/// batch results and the pool queue are never left half-updated.
+fn recover<G>(lock: &str, poisoned: PoisonError<G>) -> G {
+    warn!(
+        "{} lock poisoned; recovering because work runs outside the lock",
+        lock
+    );
+    poisoned.into_inner()
+}
+
+/// Recoverable poison policy, see [`recover`].
+fn lock_queue(shared: &PoolShared) -> MutexGuard<'_, PoolQueue> {
+    shared
+        .queue
+        .lock()
+        .unwrap_or_else(|poisoned| recover("convolution pool", poisoned))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConvolutionPool;
+
+    fn square(value: &mut u64) -> u64 {
+        *value * *value
+    }
+
+    #[test]
+    fn batches_return_results_in_submission_order() {
+        let pool = ConvolutionPool::new(3);
+        assert_eq!(pool.threads(), 3);
+        for _ in 0..20 {
+            let finished = pool.run_batch((0..10_u64).collect(), square);
+            let outputs: Vec<u64> = finished.into_iter().filter_map(|f| f.output).collect();
+            assert_eq!(outputs, (0..10_u64).map(|v| v * v).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn nested_batches_finish_on_a_single_thread_pool() {
+        fn outer(pool: &mut (ConvolutionPool, u64)) -> u64 {
+            pool.0
+                .run_batch(vec![pool.1, pool.1 + 1], square)
+                .into_iter()
+                .filter_map(|f| f.output)
+                .sum()
+        }
+        let pool = ConvolutionPool::new(1);
+        let jobs = (0..4).map(|v| (pool.clone(), v)).collect();
+        let sums: Vec<u64> = pool
+            .run_batch(jobs, outer)
+            .into_iter()
+            .filter_map(|f| f.output)
+            .collect();
+        assert_eq!(sums, vec![1, 5, 13, 25]);
+    }
+
+    #[test]
+    fn panicking_jobs_hand_back_their_state_without_output() {
+        fn fail_on_odd(value: &mut u64) -> u64 {
+            assert!(value.is_multiple_of(2), "odd job");
+            *value
+        }
+        let pool = ConvolutionPool::new(2);
+        let finished = pool.run_batch(vec![2_u64, 3, 4], fail_on_odd);
+        let jobs: Vec<u64> = finished.iter().map(|f| f.job).collect();
+        let outputs: Vec<Option<u64>> = finished.into_iter().map(|f| f.output).collect();
+        assert_eq!(jobs, vec![2, 3, 4]);
+        assert_eq!(outputs, vec![Some(2), None, Some(4)]);
+    }
+}
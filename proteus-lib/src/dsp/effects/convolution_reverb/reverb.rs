@@ -2,19 +2,31 @@
 //!
 //! This module exposes a simple per-channel reverb that mixes dry and wet
 //! signals and reuses internal scratch buffers to reduce allocations.
+//!
+//! With a worker thread budget above one, the reverb starts a long-lived
+//! [`ConvolutionPool`]; each block convolves its channels as pool jobs and
+//! splits each channel's FFT partitions across whatever budget is left.
+//! Every block joins all of its jobs in channel order before mixing, so
+//! output never depends on thread timing. If a job fails, the channel is
+//! convolved again inline and the reverb drops back to a single thread.
 
 use std::time::Instant;
 
-use log::debug;
+use log::{debug, error};
+use serde::Serialize;
 
-use super::convolution::Convolver;
+use super::convolution::{effective_partition_workers, Convolver};
 use super::impulse_response::ImpulseResponse;
+use super::pool::ConvolutionPool;
 use crate::dsp::effects::core::smoother::ParamSmoother;
 
 const IDENTITY_IMPULSE_RESPONSE: &[f32] = &[1.0];
 
 // Power-of-two FFT size; increasing improves frequency resolution at the cost of latency.
-const FFT_SIZE: usize = 8192;
+pub(super) const FFT_SIZE: usize = 8192;
+
+/// Upper bound on the worker thread budget of one reverb.
+pub const MAX_WORKER_THREADS: usize = 16;
 
 /// Preferred processing batch size in interleaved samples.
 pub fn preferred_batch_samples(channels: usize) -> usize {
@@ -26,6 +38,23 @@ pub fn preferred_batch_samples(channels: usize) -> usize {
     block_samples * super::REVERB_BATCH_BLOCKS
 }
 
+/// Threading and timing of the most recent [`Reverb`] block.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ReverbMetrics {
+    /// Configured worker thread budget.
+    pub worker_threads: usize,
+    /// Threads the channels were spread across.
+    pub channel_workers: usize,
+    /// Threads each channel's partition sums were spread across.
+    pub partition_workers: usize,
+    /// Uniform FFT partitions per channel impulse response.
+    pub partitions: usize,
+    /// Wall time spent convolving the last block, in milliseconds.
+    pub last_block_ms: f64,
+    /// Whether the last block's partition sums ran on the GPU.
+    pub gpu: bool,
+}
+
 /// Stateful convolution reverb processor.
 ///
 /// The processor keeps a `Convolver` per output channel and maintains
@@ -34,6 +63,9 @@ pub fn preferred_batch_samples(channels: usize) -> usize {
 pub struct Reverb {
     channels: usize,
     dry_wet: f32,
+    worker_threads: usize,
+    pool: Option<ConvolutionPool>,
+    metrics: ReverbMetrics,
     convolvers: Vec<Convolver>,
     scratch_dry: Vec<Vec<f32>>,
     scratch_wet: Vec<Vec<f32>>,
@@ -50,14 +82,7 @@ impl Reverb {
             convolvers.push(Convolver::new(IDENTITY_IMPULSE_RESPONSE, FFT_SIZE));
         }
 
-        Self {
-            channels,
-            dry_wet,
-            convolvers,
-            scratch_dry: Vec::new(),
-            scratch_wet: Vec::new(),
-            scratch_mixed: Vec::new(),
-        }
+        Self::with_convolvers(channels, dry_wet, convolvers)
     }
 
     /// Create a reverb with a custom impulse response.
@@ -68,21 +93,89 @@ impl Reverb {
         channels: usize,
         dry_wet: f32,
         impulse_response: &ImpulseResponse,
+    ) -> Self {
+        Self::with_fft_size(channels, dry_wet, impulse_response, FFT_SIZE)
+    }
+
+    /// [`Reverb::new_with_impulse_response`] with a power-of-two FFT size
+    /// other than the default; larger sizes mean fewer, longer partitions.
+    pub(super) fn with_fft_size(
+        channels: usize,
+        dry_wet: f32,
+        impulse_response: &ImpulseResponse,
+        fft_size: usize,
     ) -> Self {
         let mut convolvers = Vec::with_capacity(channels);
         for channel_index in 0..channels {
             let ir_channel = impulse_response.channel_for_output(channel_index);
-            convolvers.push(Convolver::new(ir_channel, FFT_SIZE));
+            convolvers.push(Convolver::new(ir_channel, fft_size));
         }
 
-        Self {
+        Self::with_convolvers(channels, dry_wet, convolvers)
+    }
+
+    fn with_convolvers(channels: usize, dry_wet: f32, convolvers: Vec<Convolver>) -> Self {
+        let mut reverb = Self {
             channels,
             dry_wet,
+            worker_threads: 1,
+            pool: None,
+            metrics: ReverbMetrics::default(),
             convolvers,
             scratch_dry: Vec::new(),
             scratch_wet: Vec::new(),
             scratch_mixed: Vec::new(),
+        };
+        reverb.set_worker_threads(1);
+        reverb
+    }
+
+    /// Set the worker thread budget, clamped to `1..=MAX_WORKER_THREADS`.
+    ///
+    /// `1` convolves every channel inline on the calling thread. Larger
+    /// budgets start `threads - 1` pool threads, since the calling thread
+    /// convolves alongside them.
+    pub fn set_worker_threads(&mut self, threads: usize) {
+        self.worker_threads = threads.clamp(1, MAX_WORKER_THREADS);
+        let pool_threads = self.worker_threads - 1;
+        if self.pool.as_ref().map_or(0, ConvolutionPool::threads) != pool_threads {
+            self.pool = (pool_threads > 0).then(|| ConvolutionPool::new(pool_threads));
         }
+        let (channel_workers, partition_workers) = self.worker_split();
+        let partitions = self.convolvers.first().map_or(0, Convolver::partitions);
+        self.metrics.worker_threads = self.worker_threads;
+        self.metrics.channel_workers = channel_workers;
+        self.metrics.partition_workers = effective_partition_workers(partitions, partition_workers);
+        self.metrics.partitions = partitions;
+    }
+
+    /// Offload partition sums to the GPU: `Some(true)` always, `Some(false)`
+    /// never, and `None` once partitions × channels reaches
+    /// [`GPU_AUTO_PARTITIONS`](super::gpu::GPU_AUTO_PARTITIONS).
+    ///
+    /// Convolvers fall back to the CPU when no adapter can be opened.
+    #[cfg(feature = "gpu")]
+    pub fn set_gpu(&mut self, preference: Option<bool>) {
+        let partitions = self.convolvers.first().map_or(0, Convolver::partitions);
+        let enabled =
+            preference.unwrap_or(partitions * self.channels >= super::gpu::GPU_AUTO_PARTITIONS);
+        for convolver in &mut self.convolvers {
+            convolver.set_gpu(enabled);
+        }
+    }
+
+    /// Threading and timing of the most recent block.
+    pub fn metrics(&self) -> ReverbMetrics {
+        self.metrics
+    }
+
+    /// Split the thread budget into `(channel_workers, partition_workers)`.
+    fn worker_split(&self) -> (usize, usize) {
+        let channel_workers = self.worker_threads.min(self.channels).max(1);
+        (
+            channel_workers,
+            (self.worker_threads / channel_workers).max(1),
+        )
     }
 
     /// Process an interleaved input buffer and return the mixed output.
@@ -111,13 +204,29 @@ impl Reverb {
         segment_size * self.channels
     }
 
-    fn process_channel(convolver: &mut Convolver, channel: &[f32], index: usize) -> Vec<f32> {
+    /// Return how many [`Reverb::block_size_samples`] blocks of silence it
+    /// takes to flush the whole impulse response tail once input stops.
+    pub fn tail_blocks(&self) -> usize {
+        self.convolvers
+            .iter()
+            .map(Convolver::partitions)
+            .max()
+            .map_or(0, |partitions| partitions + 1)
+    }
+
+    fn process_channel(
+        convolver: &mut Convolver,
+        channel: &[f32],
+        index: usize,
+        pool: Option<&ConvolutionPool>,
+        workers: usize,
+    ) -> Vec<f32> {
         let start = Instant::now();
 
         debug!("convolver fft size: {:?}", convolver.fft_size);
         debug!("channel length: {:?}", channel.len());
 
-        let processed = convolver.process(channel);
+        let processed = convolver.process_with_pool(channel, pool, workers);
         let end = Instant::now();
         debug!(
             "time taken to process channel #{}: {:?}",
@@ -155,11 +264,10 @@ impl Reverb {
             return;
         }
 
-        let frames = if self.channels > 0 {
-            input_buffer.len() / self.channels
-        } else {
-            0
-        };
+        let frames = input_buffer
+            .len()
+            .checked_div(self.channels)
+            .unwrap_or_default();
 
         if self.scratch_dry.len() != self.channels {
             self.scratch_dry = vec![Vec::new(); self.channels];
@@ -181,12 +289,7 @@ impl Reverb {
             }
         }
 
-        let channels = self.channels;
-        for ch in 0..channels {
-            let input = &self.scratch_dry[ch];
-            let processed = Self::process_channel(&mut self.convolvers[ch], input, ch);
-            self.scratch_wet[ch] = processed;
-        }
+        self.convolve_channels();
 
         let total_samples = frames * self.channels;
         if self.scratch_mixed.len() != total_samples {
@@ -232,6 +335,79 @@ impl Reverb {
         out.extend_from_slice(&self.scratch_mixed);
     }
 
+    /// Convolve each deinterleaved dry channel into its wet scratch buffer.
+    fn convolve_channels(&mut self) {
+        let start = Instant::now();
+        let (channel_workers, partition_workers) = self.worker_split();
+        match self.pool.clone() {
+            Some(pool) if channel_workers > 1 => self.convolve_on_pool(&pool, partition_workers),
+            pool => {
+                for (ch, (convolver, (input, wet))) in self
+                    .convolvers
+                    .iter_mut()
+                    .zip(self.scratch_dry.iter().zip(self.scratch_wet.iter_mut()))
+                    .enumerate()
+                {
+                    *wet = Self::process_channel(
+                        convolver,
+                        input,
+                        ch,
+                        pool.as_ref(),
+                        partition_workers,
+                    );
+                }
+            }
+        }
+        self.metrics.last_block_ms = start.elapsed().as_secs_f64() * 1000.0;
+        #[cfg(feature = "gpu")]
+        {
+            self.metrics.gpu = self.convolvers.iter().any(Convolver::gpu_active);
+        }
+    }
+
+    /// Convolve every channel as its own job on `pool`.
+    ///
+    /// Convolvers and dry inputs move into the jobs and come back in channel
+    /// order. A channel whose job failed has its convolver reset and is
+    /// convolved again inline, and later blocks run single-threaded.
+    fn convolve_on_pool(&mut self, pool: &ConvolutionPool, partition_workers: usize) {
+        let jobs: Vec<ChannelJob> = std::mem::take(&mut self.convolvers)
+            .into_iter()
+            .zip(self.scratch_dry.iter_mut().map(std::mem::take))
+            .enumerate()
+            .map(|(index, (convolver, input))| ChannelJob {
+                convolver,
+                input,
+                index,
+                pool: pool.clone(),
+                partition_workers,
+            })
+            .collect();
+
+        let mut failed = false;
+        for (finished, wet) in pool
+            .run_batch(jobs, ChannelJob::run)
+            .into_iter()
+            .zip(self.scratch_wet.iter_mut())
+        {
+            let mut job = finished.job;
+            *wet = finished.output.unwrap_or_else(|| {
+                error!(
+                    "convolution worker failed on channel {}; convolving it inline",
+                    job.index
+                );
+                failed = true;
+                job.convolver.clear_state();
+                Self::process_channel(&mut job.convolver, &job.input, job.index, None, 1)
+            });
+            self.convolvers.push(job.convolver);
+            self.scratch_dry[job.index] = job.input;
+        }
+        if failed {
+            self.set_worker_threads(1);
+        }
+    }
+
     /// Update the dry/wet mix, clamped to `[0.0, 1.0]`.
     pub fn set_dry_wet(&mut self, dry_wet: f32) {
         self.dry_wet = dry_wet.clamp(0.0, 1.0);
@@ -257,6 +433,27 @@ impl Reverb {
     }
 }
 
+/// One channel's block, moved onto a pool thread and handed back afterwards.
+struct ChannelJob {
+    convolver: Convolver,
+    input: Vec<f32>,
+    index: usize,
+    pool: ConvolutionPool,
+    partition_workers: usize,
+}
+
+impl ChannelJob {
+    fn run(&mut self) -> Vec<f32> {
+        Reverb::process_channel(
+            &mut self.convolver,
+            &self.input,
+            self.index,
+            Some(&self.pool),
+            self.partition_workers,
+        )
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,6 +472,64 @@ mod tests {
         assert_eq!(output, input);
     }
 
+    fn long_ir_reverb(threads: usize) -> Reverb {
+        let ir: Vec<f32> = (0..48_000)
+            .map(|index| (-(index as f32) / 8_000.0).exp() * ((index * 7 % 13) as f32 - 6.0) / 6.0)
+            .collect();
+        let ir = ImpulseResponse {
+            sample_rate: 48_000,
+            channels: vec![ir.clone(), ir],
+        };
+        let mut reverb = Reverb::new_with_impulse_response(2, 0.5, &ir);
+        reverb.set_worker_threads(threads);
+        reverb
+    }
+
+    #[test]
+    fn worker_threads_match_single_threaded_output() {
+        let input: Vec<f32> = (0..16_384)
+            .map(|index| ((index as f32) * 0.013).sin() * 0.5)
+            .collect();
+        let expected = long_ir_reverb(1).process(&input);
+
+        for threads in [2, 4] {
+            let mut reverb = long_ir_reverb(threads);
+            let output = reverb.process(&input);
+            assert_eq!(output.len(), expected.len());
+            let error = output
+                .iter()
+                .zip(&expected)
+                .fold(0.0_f32, |acc, (a, b)| acc.max((a - b).abs()));
+            assert!(error < 1e-4, "{threads} threads error {error}");
+            // Same thread count, same result.
+            assert_eq!(long_ir_reverb(threads).process(&input), output);
+        }
+    }
+
+    #[test]
+    fn metrics_report_the_worker_split() {
+        let reverb = long_ir_reverb(4);
+        let metrics = reverb.metrics();
+        assert_eq!(metrics.worker_threads, 4);
+        assert_eq!(metrics.channel_workers, 2);
+        assert_eq!(metrics.partition_workers, 2);
+        assert!(metrics.partitions >= 8);
+
+        let mut reverb = long_ir_reverb(64);
+        assert_eq!(reverb.metrics().worker_threads, MAX_WORKER_THREADS);
+        reverb.set_worker_threads(0);
+        assert_eq!(reverb.metrics().worker_threads, 1);
+        assert_eq!(reverb.metrics().partition_workers, 1);
+    }
+
+    #[test]
+    fn worker_budget_sizes_the_pool_and_one_thread_drops_it() {
+        let mut reverb = long_ir_reverb(4);
+        assert_eq!(reverb.pool.as_ref().map(|pool| pool.threads()), Some(3));
+        reverb.set_worker_threads(1);
+        assert!(reverb.pool.is_none());
+    }
+
     #[test]
     fn reverb_with_custom_ir_processes_interleaved_input() {
         let ir = ImpulseResponse {
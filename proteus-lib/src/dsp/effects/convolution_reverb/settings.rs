@@ -0,0 +1,74 @@
+//! Serialized impulse response selection and tuning for the convolution
+//! reverb.
+
+use serde::{Deserialize, Serialize};
+
+use crate::dsp::effects::multiband_eq::MultibandEqSettings;
+
+const DEFAULT_TAIL_DB: f32 = -60.0;
+
+/// Serialized configuration for convolution reverb impulse response selection.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ConvolutionReverbSettings {
+    /// Inline IR identifier or attachment name (primary field, checked first).
+    pub impulse_response: Option<String>,
+    /// Name of the IR embedded as a Matroska attachment (legacy alias).
+    pub impulse_response_attachment: Option<String>,
+    /// Filesystem path to an external IR audio file (legacy alias).
+    pub impulse_response_path: Option<String>,
+    /// dB level below peak at which the IR tail is considered silent and truncated.
+    pub impulse_response_tail_db: Option<f32>,
+    /// Legacy alias for `impulse_response_tail_db`.
+    pub impulse_response_tail: Option<f32>,
+    /// EQ applied to the impulse response when it is loaded, e.g. a high
+    /// shelf cut to darken the reverb.
+    pub impulse_response_eq: Option<MultibandEqSettings>,
+    /// Milliseconds of pre-delay removed from the start of the impulse
+    /// response; the trim never cuts into the direct sound.
+    pub impulse_response_pre_delay_trim_ms: Option<f32>,
+    /// Level applied to the impulse response when it is loaded, in dB.
+    pub impulse_response_gain_db: Option<f32>,
+    /// Worker threads used to convolve channels and IR partitions; unset or
+    /// `1` processes inline, `0` uses the available parallelism.
+    pub worker_threads: Option<usize>,
+    /// Offload the partition sums to the GPU with the experimental `gpu`
+    /// feature: `true` always, `false` never, unset only for very long IRs
+    /// or many channels. Ignored without the feature.
+    pub gpu: Option<bool>,
+}
+
+impl ConvolutionReverbSettings {
+    /// Resolve a tail trim value, falling back to the default.
+    pub fn tail_db_or_default(&self) -> f32 {
+        self.impulse_response_tail_db
+            .or(self.impulse_response_tail)
+            .unwrap_or(DEFAULT_TAIL_DB)
+    }
+
+    /// Resolve the worker thread budget, expanding `0` to the available
+    /// parallelism.
+    pub fn worker_threads_or_default(&self) -> usize {
+        match self.worker_threads {
+            None => 1,
+            Some(0) => std::thread::available_parallelism().map_or(1, usize::from),
+            Some(threads) => threads,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConvolutionReverbSettings;
+
+    #[test]
+    fn tail_db_or_default_prefers_explicit_values() {
+        let settings = ConvolutionReverbSettings {
+            impulse_response_tail_db: Some(-24.0),
+            impulse_response_tail: Some(-30.0),
+            ..Default::default()
+        };
+        assert_eq!(settings.tail_db_or_default(), -24.0);
+    }
+}
@@ -1,5 +1,12 @@
 //! Impulse response specification parsing helpers.
 
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::ir_loader::resolve_impulse_response_path;
+
 /// Location of an impulse response used for convolution reverb.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ImpulseResponseSpec {
@@ -9,6 +16,60 @@ pub enum ImpulseResponseSpec {
     FilePath(String),
 }
 
+impl ImpulseResponseSpec {
+    /// Name of the container attachment this spec loads, if any.
+    ///
+    /// A file path that does not exist falls back to the container
+    /// attachment carrying its file name.
+    pub fn attachment_name(&self, container_path: Option<&str>) -> Option<String> {
+        container_path?;
+        match self {
+            Self::Attachment(name) => Some(name.clone()),
+            Self::FilePath(path) => {
+                if resolve_impulse_response_path(container_path, path).exists() {
+                    return None;
+                }
+                Path::new(path)
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .map(str::to_string)
+            }
+        }
+    }
+}
+
+/// Impulse response attachments read from the loaded container, by name.
+///
+/// The playback layer reads the container; convolution reverbs only decode
+/// these bytes.
+#[derive(Clone, Default)]
+pub struct ImpulseResponseAttachments {
+    attachments: HashMap<String, Arc<[u8]>>,
+}
+
+impl ImpulseResponseAttachments {
+    /// Add the bytes of the attachment `name`, replacing earlier bytes.
+    pub fn insert(&mut self, name: impl Into<String>, bytes: impl Into<Arc<[u8]>>) {
+        self.attachments.insert(name.into(), bytes.into());
+    }
+
+    /// Bytes of the attachment `name`, if read.
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.attachments.get(name).map(|bytes| &bytes[..])
+    }
+
+    /// Return `true` when the attachment `name` was read.
+    pub fn contains(&self, name: &str) -> bool {
+        self.attachments.contains_key(name)
+    }
+}
+
+impl fmt::Debug for ImpulseResponseAttachments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.attachments.keys()).finish()
+    }
+}
+
 /// Parse an impulse response spec string into a concrete location.
 ///
 /// Supported prefixes:
@@ -47,4 +108,21 @@ mod tests {
             Some(ImpulseResponseSpec::FilePath("plain.wav".to_string()))
         );
     }
+
+    #[test]
+    fn missing_file_paths_fall_back_to_attachments_of_the_container() {
+        let container = Some("/nonexistent/project/song.prot");
+        assert_eq!(
+            ImpulseResponseSpec::Attachment("hall.wav".to_string()).attachment_name(container),
+            Some("hall.wav".to_string())
+        );
+        assert_eq!(
+            ImpulseResponseSpec::FilePath("ir/room.wav".to_string()).attachment_name(container),
+            Some("room.wav".to_string())
+        );
+        assert_eq!(
+            ImpulseResponseSpec::Attachment("hall.wav".to_string()).attachment_name(None),
+            None
+        );
+    }
 }
@@ -0,0 +1,217 @@
+//! Runtime buffering for a built convolution reverb.
+//!
+//! The reverb convolves fixed batches of blocks; this state collects input
+//! chunks into those batches, hands out output matching each chunk's length,
+//! and drains the tail when the stream ends.
+
+use log::info;
+
+use super::reverb::Reverb;
+use super::{DEFAULT_DRY_WET, REVERB_BATCH_BLOCKS};
+use crate::dsp::effects::core::smoother::ParamSmoother;
+
+/// Blocks drained past the IR length, covering output still queued in the
+/// convolvers.
+const DRAIN_EXTRA_BLOCKS: usize = 2;
+const DRAIN_SILENCE_EPSILON: f32 = 1.0e-6;
+const DRAIN_SILENT_BLOCKS_TO_STOP: usize = 2;
+
+#[derive(Clone)]
+pub(super) struct ConvolutionReverbState {
+    pub(super) reverb: Reverb,
+    input_buffer: Vec<f32>,
+    output_buffer: Vec<f32>,
+    block_in: Vec<f32>,
+    block_out: Vec<f32>,
+    block_samples: usize,
+    tail_drained: bool,
+}
+
+impl ConvolutionReverbState {
+    pub(super) fn new(mut reverb: Reverb) -> Self {
+        info!("using convolution reverb");
+        let block_samples = reverb.block_size_samples();
+        reverb.set_dry_wet(DEFAULT_DRY_WET);
+        Self {
+            reverb,
+            input_buffer: Vec::new(),
+            output_buffer: Vec::new(),
+            block_in: Vec::new(),
+            block_out: Vec::new(),
+            block_samples,
+            tail_drained: false,
+        }
+    }
+
+    pub(super) fn reset(&mut self) {
+        self.reverb.clear_state();
+        self.input_buffer.clear();
+        self.output_buffer.clear();
+        self.block_in.clear();
+        self.block_out.clear();
+        self.block_samples = self.reverb.block_size_samples();
+        self.tail_drained = false;
+    }
+
+    pub(super) fn process_into(
+        &mut self,
+        samples: &[f32],
+        drain: bool,
+        out: &mut Vec<f32>,
+        dry_wet_smoother: Option<&mut ParamSmoother>,
+    ) {
+        if samples.is_empty() {
+            if !drain {
+                return;
+            }
+            if self.tail_drained {
+                return;
+            }
+
+            if !self.output_buffer.is_empty() {
+                out.append(&mut self.output_buffer);
+            }
+            out.extend(self.drain_tail_blocks());
+            self.tail_drained = true;
+            return;
+        }
+
+        self.tail_drained = false;
+
+        if self.block_samples == 0 {
+            if let Some(smoother) = dry_wet_smoother {
+                self.reverb
+                    .process_into_with_smoother(samples, &mut self.block_out, smoother);
+            } else {
+                self.reverb.process_into(samples, &mut self.block_out);
+            }
+            out.extend_from_slice(&self.block_out);
+            return;
+        }
+
+        self.input_buffer.extend_from_slice(samples);
+        let batch_samples = self.block_samples * REVERB_BATCH_BLOCKS;
+        let should_flush = drain && !self.input_buffer.is_empty();
+        let mut dry_wet_smoother = dry_wet_smoother;
+        while self.input_buffer.len() >= batch_samples || should_flush {
+            let take = if self.input_buffer.len() >= batch_samples {
+                batch_samples
+            } else {
+                self.input_buffer.len()
+            };
+            self.block_in.clear();
+            self.block_in.extend(self.input_buffer.drain(0..take));
+            if let Some(smoother) = dry_wet_smoother.as_deref_mut() {
+                self.reverb.process_into_with_smoother(
+                    &self.block_in,
+                    &mut self.block_out,
+                    smoother,
+                );
+            } else {
+                self.reverb
+                    .process_into(&self.block_in, &mut self.block_out);
+            }
+            self.output_buffer.extend_from_slice(&self.block_out);
+            if take < batch_samples {
+                break;
+            }
+        }
+
+        // Keep output continuous for small chunks (e.g. around shuffle boundaries).
+        // If batch processing did not yield enough samples yet, process the pending
+        // input immediately instead of emitting silence.
+        while self.output_buffer.len() < samples.len() && !self.input_buffer.is_empty() {
+            let take = self.input_buffer.len().min(batch_samples.max(1));
+            self.block_in.clear();
+            self.block_in.extend(self.input_buffer.drain(0..take));
+            if let Some(smoother) = dry_wet_smoother.as_deref_mut() {
+                self.reverb.process_into_with_smoother(
+                    &self.block_in,
+                    &mut self.block_out,
+                    smoother,
+                );
+            } else {
+                self.reverb
+                    .process_into(&self.block_in, &mut self.block_out);
+            }
+            self.output_buffer.extend_from_slice(&self.block_out);
+        }
+
+        let chunk_len = samples.len();
+        if self.output_buffer.len() < chunk_len {
+            let out_len = self.output_buffer.len();
+            out.append(&mut self.output_buffer);
+            if out_len < chunk_len {
+                out.extend_from_slice(&samples[out_len..chunk_len]);
+            }
+            self.output_buffer.clear();
+            return;
+        }
+
+        out.extend(self.output_buffer.drain(0..chunk_len));
+    }
+
+    fn drain_tail_blocks(&mut self) -> Vec<f32> {
+        if self.block_samples == 0 {
+            return Vec::new();
+        }
+
+        let mut drained = Vec::new();
+        let mut trailing_silent_blocks = 0usize;
+        let silence = vec![0.0_f32; self.block_samples.max(1)];
+
+        for _ in 0..self.reverb.tail_blocks() + DRAIN_EXTRA_BLOCKS {
+            self.reverb.process_into(&silence, &mut self.block_out);
+            if self.block_out.is_empty() {
+                break;
+            }
+
+            let max_abs = self
+                .block_out
+                .iter()
+                .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+
+            if max_abs > DRAIN_SILENCE_EPSILON {
+                trailing_silent_blocks = 0;
+            } else {
+                trailing_silent_blocks = trailing_silent_blocks.saturating_add(1);
+            }
+
+            drained.extend_from_slice(&self.block_out);
+
+            if trailing_silent_blocks >= DRAIN_SILENT_BLOCKS_TO_STOP {
+                break;
+            }
+        }
+
+        drained
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ConvolutionReverbState;
+    use crate::dsp::effects::convolution_reverb::{
+        impulse_response::ImpulseResponse, reverb::Reverb,
+    };
+
+    #[test]
+    fn drain_covers_impulse_responses_longer_than_the_old_fixed_cap() {
+        // 300 partitions of 128 frames, well past 128 drain blocks.
+        let ir = ImpulseResponse {
+            sample_rate: 48_000,
+            channels: vec![vec![0.5; 300 * 128]],
+        };
+        let mut state = ConvolutionReverbState::new(Reverb::with_fft_size(1, 1.0, &ir, 256));
+        state.reverb.set_dry_wet(1.0);
+        assert_eq!(state.reverb.tail_blocks(), 301);
+
+        let mut impulse = vec![0.0_f32; 256];
+        impulse[0] = 1.0;
+        let mut out = Vec::new();
+        state.process_into(&impulse, false, &mut out, None);
+        state.process_into(&[], true, &mut out, None);
+        let audible = out.iter().filter(|sample| sample.abs() > 0.25).count();
+        assert_eq!(audible, ir.frames());
+    }
+}
@@ -1,13 +1,17 @@
 //! Internal biquad filter helpers derived from rodio BLT filters.
 
-use std::f32::consts::PI;
+use std::f64::consts::PI;
 
 use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_clamped, sanitize_freq};
+use crate::dsp::precision::{from_f64, narrow, widen, DspFloat};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub(crate) enum BiquadKind {
     LowPass,
     HighPass,
+    /// Constant 0 dB peak-gain band-pass centred on `freq`.
+    BandPass,
 }
 
 /// Number of samples over which biquad coefficients are ramped by default.
@@ -15,11 +19,11 @@ const DEFAULT_COEFF_RAMP_SAMPLES: usize = 240;
 
 #[derive(Clone, Copy, Debug)]
 pub(super) struct BiquadCoefficients {
-    pub(super) b0: f32,
-    pub(super) b1: f32,
-    pub(super) b2: f32,
-    pub(super) a1: f32,
-    pub(super) a2: f32,
+    pub(super) b0: DspFloat,
+    pub(super) b1: DspFloat,
+    pub(super) b2: DspFloat,
+    pub(super) a1: DspFloat,
+    pub(super) a2: DspFloat,
 }
 
 #[derive(Clone, Debug)]
@@ -33,19 +37,19 @@ pub(crate) struct BiquadState {
     target_coeffs: Option<BiquadCoefficients>,
     coeff_deltas: Option<CoefficientDeltas>,
     ramp_remaining: usize,
-    x_n1: Vec<f32>,
-    x_n2: Vec<f32>,
-    y_n1: Vec<f32>,
-    y_n2: Vec<f32>,
+    x_n1: Vec<DspFloat>,
+    x_n2: Vec<DspFloat>,
+    y_n1: Vec<DspFloat>,
+    y_n2: Vec<DspFloat>,
 }
 
 #[derive(Clone, Copy, Debug)]
 struct CoefficientDeltas {
-    d_b0: f32,
-    d_b1: f32,
-    d_b2: f32,
-    d_a1: f32,
-    d_a2: f32,
+    d_b0: DspFloat,
+    d_b1: DspFloat,
+    d_b2: DspFloat,
+    d_a1: DspFloat,
+    d_a2: DspFloat,
 }
 
 impl BiquadState {
@@ -131,7 +135,7 @@ impl BiquadState {
             ramp_samples
         };
 
-        let inv = 1.0 / ramp as f32;
+        let inv = 1.0 / ramp as DspFloat;
         self.target_coeffs = Some(target);
         self.coeff_deltas = Some(CoefficientDeltas {
             d_b0: (target.b0 - self.coeffs.b0) * inv,
@@ -174,6 +178,7 @@ impl BiquadState {
         let ramping = self.ramp_remaining > 0;
         for (idx, &sample) in samples.iter().enumerate() {
             let ch = idx % channels;
+            let sample = widen(sample);
             let result = self.coeffs.b0 * sample
                 + self.coeffs.b1 * self.x_n1[ch]
                 + self.coeffs.b2 * self.x_n2[ch]
@@ -185,7 +190,7 @@ impl BiquadState {
             self.y_n2[ch] = self.y_n1[ch];
             self.y_n1[ch] = result;
 
-            out.push(result);
+            out.push(narrow(result));
 
             if ramping && ch == channels - 1 {
                 self.advance_coefficients();
@@ -207,6 +212,7 @@ impl BiquadState {
         let ramping = self.ramp_remaining > 0;
         for (idx, &sample) in samples.iter().enumerate() {
             let ch = idx % channels;
+            let sample = widen(sample);
             let result = self.coeffs.b0 * sample
                 + self.coeffs.b1 * self.x_n1[ch]
                 + self.coeffs.b2 * self.x_n2[ch]
@@ -218,7 +224,7 @@ impl BiquadState {
             self.y_n2[ch] = self.y_n1[ch];
             self.y_n1[ch] = result;
 
-            output.push(result);
+            output.push(narrow(result));
 
             if ramping && ch == channels - 1 {
                 self.advance_coefficients();
@@ -238,9 +244,9 @@ impl BiquadState {
 }
 
 fn coefficients(kind: BiquadKind, sample_rate: u32, freq: u32, q: f32) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
 
     match kind {
         BiquadKind::LowPass => {
@@ -252,11 +258,11 @@ fn coefficients(kind: BiquadKind, sample_rate: u32, freq: u32, q: f32) -> Biquad
             let a2 = 1.0 - alpha;
 
             BiquadCoefficients {
-                b0: b0 / a0,
-                b1: b1 / a0,
-                b2: b2 / a0,
-                a1: a1 / a0,
-                a2: a2 / a0,
+                b0: from_f64(b0 / a0),
+                b1: from_f64(b1 / a0),
+                b2: from_f64(b2 / a0),
+                a1: from_f64(a1 / a0),
+                a2: from_f64(a2 / a0),
             }
         }
         BiquadKind::HighPass => {
@@ -268,11 +274,24 @@ fn coefficients(kind: BiquadKind, sample_rate: u32, freq: u32, q: f32) -> Biquad
             let a2 = 1.0 - alpha;
 
             BiquadCoefficients {
-                b0: b0 / a0,
-                b1: b1 / a0,
-                b2: b2 / a0,
-                a1: a1 / a0,
-                a2: a2 / a0,
+                b0: from_f64(b0 / a0),
+                b1: from_f64(b1 / a0),
+                b2: from_f64(b2 / a0),
+                a1: from_f64(a1 / a0),
+                a2: from_f64(a2 / a0),
+            }
+        }
+        BiquadKind::BandPass => {
+            let a0 = 1.0 + alpha;
+            let a1 = -2.0 * cos_w0;
+            let a2 = 1.0 - alpha;
+
+            BiquadCoefficients {
+                b0: from_f64(alpha / a0),
+                b1: from_f64(0.0),
+                b2: from_f64(-alpha / a0),
+                a1: from_f64(a1 / a0),
+                a2: from_f64(a2 / a0),
             }
         }
     }
@@ -290,6 +309,27 @@ mod tests {
         assert_eq!(output.len(), input.len());
     }
 
+    #[test]
+    fn band_pass_attenuates_dc() {
+        let mut state = BiquadState::new(BiquadKind::BandPass, 48_000, 1, 6_000, 1.0);
+        let output = state.process(&[1.0_f32; 4_800]);
+        assert!(output.last().unwrap().abs() < 1e-3);
+    }
+
+    #[test]
+    fn low_cutoff_low_pass_settles_to_unity_dc_gain() {
+        // Poles this close to z = 1 are where single-precision state drifts.
+        let mut state = BiquadState::new(BiquadKind::LowPass, 48_000, 1, 20, 0.707);
+        let output = state.process(&[0.5_f32; 96_000]);
+        let error = (output.last().unwrap() - 0.5).abs();
+        let tolerance = if cfg!(feature = "f64-dsp") {
+            1e-6
+        } else {
+            1e-2
+        };
+        assert!(error < tolerance, "dc error {error}");
+    }
+
     #[test]
     fn biquad_matches_uses_sanitized_values() {
         let state = BiquadState::new(BiquadKind::HighPass, 48_000, 1, 200_000, f32::NAN);
@@ -115,6 +115,15 @@ fn parse_number(value: &str) -> Option<f32> {
     trimmed.parse::<f32>().ok()
 }
 
+/// JSON Schema for gain fields, which take a number or a string like `"6db"`.
+#[cfg(feature = "schema")]
+pub(crate) fn gain_schema(_: &mut schemars::SchemaGenerator) -> schemars::Schema {
+    schemars::json_schema!({
+        "type": ["number", "string"],
+        "description": "Number, or a string with a dB suffix such as \"-6db\""
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -10,6 +10,14 @@ use super::EffectContext;
 ///
 /// This trait unifies the processing interface so the `AudioEffect` enum can
 /// dispatch generically rather than repeating match arms for every variant.
+///
+/// # Block contract
+/// A call with `drain == false` returns exactly `samples.len()` samples.
+/// Effects that buffer internally (block convolution, delay lines) absorb
+/// their latency and keep anything still ringing as a tail, which is only
+/// emitted by a draining call with empty input. The mix runner relies on
+/// this to line chains up sample for sample; `AudioEffect` checks it with a
+/// debug assertion.
 pub(crate) trait DspEffect {
     /// Process interleaved samples through the effect.
     ///
@@ -19,7 +27,7 @@ pub(crate) trait DspEffect {
     /// - `drain`: When true, flush any buffered tail data.
     ///
     /// # Returns
-    /// Processed interleaved samples.
+    /// Processed interleaved samples: as many as `samples` unless draining.
     fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32>;
 
     /// Process interleaved samples through the effect, appending output to `output`.
@@ -48,6 +56,14 @@ pub(crate) trait DspEffect {
     /// The default implementation is a no-op. Override for effects that
     /// require eager initialization before the first `process` call.
     fn warm_up(&mut self, _context: &EffectContext) {}
+
+    /// Delay the effect adds to its output with the current settings, in
+    /// frames.
+    ///
+    /// The default implementation reports none.
+    fn latency_frames(&self) -> usize {
+        0
+    }
 }
 
 #[cfg(test)]
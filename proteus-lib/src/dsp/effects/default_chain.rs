@@ -0,0 +1,112 @@
+//! Effect chain hosts fall back to when a container brings none.
+
+use super::{
+    AudioEffect, CompressorEffect, ConvolutionReverbEffect, DelayEchoEffect, DelayReverbEffect,
+    DiffusionReverbEffect, DistortionEffect, DynamicEqEffect, GainEffect, HighPassFilterEffect,
+    LimiterEffect, LowPassFilterEffect, MultibandEqEffect, PanEffect, SaturationEffect,
+    ShimmerReverbEffect,
+};
+
+/// Builder for the default effect chain.
+///
+/// The chain holds one instance of every effect at its default settings:
+/// reverbs and delays, filters, drive, dynamics, EQ, and pan last. Hosts that
+/// offer an effect rack when a container has no `effects` list should start
+/// from this chain, so every frontend shows the same rack.
+///
+/// # Example
+/// ```
+/// use proteus_lib::dsp::effects::DefaultEffectChain;
+///
+/// let rack = DefaultEffectChain::new().enabled(false).without("Pan").build();
+/// assert!(rack.iter().all(|effect| !effect.is_enabled()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct DefaultEffectChain {
+    enabled: bool,
+    excluded: Vec<String>,
+}
+
+impl Default for DefaultEffectChain {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DefaultEffectChain {
+    /// Start from the full chain with every effect enabled.
+    pub fn new() -> Self {
+        Self {
+            enabled: true,
+            excluded: Vec::new(),
+        }
+    }
+
+    /// Whether effects start enabled; disabled effects pass audio through
+    /// until a host turns them on.
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Leave out the effect with this [`AudioEffect::display_name`].
+    pub fn without(mut self, name: &str) -> Self {
+        self.excluded.push(name.to_string());
+        self
+    }
+
+    /// Build the chain.
+    pub fn build(&self) -> Vec<AudioEffect> {
+        [
+            AudioEffect::ConvolutionReverb(ConvolutionReverbEffect::default()),
+            AudioEffect::DiffusionReverb(DiffusionReverbEffect::default()),
+            AudioEffect::ShimmerReverb(ShimmerReverbEffect::default()),
+            AudioEffect::DelayReverb(DelayReverbEffect::default()),
+            AudioEffect::DelayEcho(DelayEchoEffect::default()),
+            AudioEffect::LowPassFilter(LowPassFilterEffect::default()),
+            AudioEffect::HighPassFilter(HighPassFilterEffect::default()),
+            AudioEffect::Distortion(DistortionEffect::default()),
+            AudioEffect::Gain(GainEffect::default()),
+            AudioEffect::Compressor(CompressorEffect::default()),
+            AudioEffect::Limiter(LimiterEffect::default()),
+            AudioEffect::MultibandEq(MultibandEqEffect::default()),
+            AudioEffect::DynamicEq(DynamicEqEffect::default()),
+            AudioEffect::Saturation(SaturationEffect::default()),
+            AudioEffect::Pan(PanEffect::default()),
+        ]
+        .into_iter()
+        .filter(|effect| {
+            !self
+                .excluded
+                .iter()
+                .any(|name| name == effect.display_name())
+        })
+        .map(|mut effect| {
+            effect.set_enabled(self.enabled);
+            effect
+        })
+        .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_chain_lists_every_effect_once() {
+        let chain = DefaultEffectChain::new().build();
+        assert_eq!(chain.len(), 15);
+        assert_eq!(chain[0].display_name(), "ConvolutionReverb");
+        assert_eq!(chain[14].display_name(), "Pan");
+        assert!(chain.iter().all(AudioEffect::is_enabled));
+
+        let trimmed = DefaultEffectChain::new()
+            .enabled(false)
+            .without("ConvolutionReverb")
+            .without("Pan")
+            .build();
+        assert_eq!(trimmed.len(), 13);
+        assert!(trimmed.iter().all(|effect| !effect.is_enabled()));
+    }
+}
@@ -0,0 +1,401 @@
+//! Feedback delay/echo effect with damping, ping-pong, and tempo sync.
+//!
+//! Unlike the delay reverb, which folds repeats into a dense tail, this effect
+//! produces discrete, musically timed echoes with an explicit dry/wet blend.
+
+use serde::{Deserialize, Serialize};
+
+use super::core::smoother::ParamSmoother;
+use super::EffectContext;
+use crate::dsp::guardrails::{sanitize_finite, sanitize_finite_clamped};
+use crate::dsp::precision::{narrow, widen, DspFloat};
+
+const DEFAULT_TIME_MS: f32 = 375.0;
+const DEFAULT_FEEDBACK: f32 = 0.35;
+const DEFAULT_DAMPING: f32 = 0.2;
+const DEFAULT_MIX: f32 = 0.3;
+const MAX_TIME_MS: f32 = 4000.0;
+const MAX_FEEDBACK: f32 = 0.95;
+const MS_PER_MINUTE: f32 = 60_000.0;
+
+/// Serializable settings for the delay/echo effect.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DelayEchoSettings {
+    /// Echo spacing in milliseconds; ignored when tempo sync resolves a tempo.
+    #[serde(alias = "delay_ms")]
+    pub time_ms: f32,
+    /// Portion of each echo fed back into the delay line; clamped to `[0.0, 0.95]`.
+    pub feedback: f32,
+    /// High-frequency damping in the feedback path (`0.0` = bright, `1.0` = dark).
+    pub damping: f32,
+    /// Alternate repeats between the left and right channels (stereo only).
+    pub ping_pong: bool,
+    /// Echo spacing in beats (e.g. `0.5` for an eighth note) when tempo-synced.
+    ///
+    /// Resolved against the play-settings tempo; falls back to `time_ms` when
+    /// the container does not declare a tempo.
+    pub tempo_sync_beats: Option<f32>,
+}
+
+impl DelayEchoSettings {
+    /// Create delay/echo settings.
+    pub fn new(time_ms: f32, feedback: f32, damping: f32, ping_pong: bool) -> Self {
+        Self {
+            time_ms,
+            feedback,
+            damping,
+            ping_pong,
+            tempo_sync_beats: None,
+        }
+    }
+
+    /// Resolve the effective echo time in milliseconds.
+    ///
+    /// # Arguments
+    ///
+    /// * `tempo_bpm` - Optional tempo used when `tempo_sync_beats` is set.
+    ///
+    /// # Returns
+    ///
+    /// Echo time clamped to `[0.0, MAX_TIME_MS]`.
+    pub fn resolved_time_ms(&self, tempo_bpm: Option<f32>) -> f32 {
+        let synced = self
+            .tempo_sync_beats
+            .zip(tempo_bpm)
+            .filter(|(beats, bpm)| {
+                beats.is_finite() && bpm.is_finite() && *beats > 0.0 && *bpm > 0.0
+            })
+            .map(|(beats, bpm)| beats * MS_PER_MINUTE / bpm);
+        let time_ms = synced.unwrap_or_else(|| sanitize_finite(self.time_ms, DEFAULT_TIME_MS));
+        time_ms.clamp(0.0, MAX_TIME_MS)
+    }
+
+    fn feedback(&self) -> f32 {
+        sanitize_finite_clamped(self.feedback, DEFAULT_FEEDBACK, 0.0, MAX_FEEDBACK)
+    }
+
+    fn damping(&self) -> f32 {
+        sanitize_finite_clamped(self.damping, DEFAULT_DAMPING, 0.0, 1.0)
+    }
+}
+
+impl Default for DelayEchoSettings {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIME_MS, DEFAULT_FEEDBACK, DEFAULT_DAMPING, false)
+    }
+}
+
+/// Tempo-syncable feedback delay producing discrete echoes.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DelayEchoEffect {
+    /// Whether the effect is active; when `false` samples pass through unmodified.
+    pub enabled: bool,
+    /// Dry/wet mix ratio (0.0 = fully dry, 1.0 = fully wet).
+    #[serde(alias = "dry_wet", alias = "wet_dry")]
+    pub mix: f32,
+    /// Echo timing, feedback, damping, and stereo behaviour.
+    #[serde(flatten)]
+    pub settings: DelayEchoSettings,
+    #[serde(skip)]
+    state: Option<DelayEchoState>,
+}
+
+impl Default for DelayEchoEffect {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mix: DEFAULT_MIX,
+            settings: DelayEchoSettings::default(),
+            state: None,
+        }
+    }
+}
+
+impl std::fmt::Debug for DelayEchoEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DelayEchoEffect")
+            .field("enabled", &self.enabled)
+            .field("mix", &self.mix)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl super::core::DspEffect for DelayEchoEffect {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        drain: bool,
+    ) {
+        if !self.enabled {
+            output.extend_from_slice(input);
+            return;
+        }
+
+        self.ensure_state(context);
+        let targets = self.targets(context);
+        let ping_pong = self.settings.ping_pong;
+        let Some(state) = self.state.as_mut() else {
+            output.extend_from_slice(input);
+            return;
+        };
+        state.update_targets(&targets, context.parameter_ramp_samples());
+
+        if input.is_empty() {
+            if drain {
+                state.drain_tail(ping_pong, output);
+            }
+            return;
+        }
+        state.process_samples(input, ping_pong, output);
+    }
+
+    fn reset_state(&mut self) {
+        self.state = None;
+    }
+
+    fn warm_up(&mut self, context: &EffectContext) {
+        self.ensure_state(context);
+    }
+}
+
+impl DelayEchoEffect {
+    /// Create a new delay/echo effect with the given dry/wet mix.
+    pub fn new(mix: f32) -> Self {
+        Self {
+            mix: mix.clamp(0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    fn targets(&self, context: &EffectContext) -> EchoTargets {
+        let time_ms = self.settings.resolved_time_ms(context.tempo_bpm());
+        EchoTargets {
+            mix: sanitize_finite_clamped(self.mix, DEFAULT_MIX, 0.0, 1.0),
+            feedback: self.settings.feedback(),
+            damping: self.settings.damping(),
+            delay_frames: time_ms * context.sample_rate() as f32 / 1000.0,
+        }
+    }
+
+    fn ensure_state(&mut self, context: &EffectContext) {
+        let channels = context.channels().max(1);
+        let capacity = max_delay_frames(context.sample_rate());
+        let needs_reset = self
+            .state
+            .as_ref()
+            .is_none_or(|state| state.channels != channels || state.capacity_frames != capacity);
+        if needs_reset {
+            let targets = self.targets(context);
+            self.state = Some(DelayEchoState::new(channels, capacity, &targets));
+        }
+    }
+}
+
+struct EchoTargets {
+    mix: f32,
+    feedback: f32,
+    damping: f32,
+    delay_frames: f32,
+}
+
+#[derive(Clone)]
+struct DelayEchoState {
+    channels: usize,
+    capacity_frames: usize,
+    delay_line: Vec<DspFloat>,
+    damping_state: Vec<DspFloat>,
+    write_frame: usize,
+    damping: f32,
+    mix: ParamSmoother,
+    feedback: ParamSmoother,
+    delay_frames: ParamSmoother,
+}
+
+impl DelayEchoState {
+    fn new(channels: usize, capacity_frames: usize, targets: &EchoTargets) -> Self {
+        Self {
+            channels,
+            capacity_frames,
+            delay_line: vec![0.0; capacity_frames * channels],
+            damping_state: vec![0.0; channels],
+            write_frame: 0,
+            damping: targets.damping,
+            mix: ParamSmoother::new(targets.mix),
+            feedback: ParamSmoother::new(targets.feedback),
+            delay_frames: ParamSmoother::new(clamp_delay(targets.delay_frames, capacity_frames)),
+        }
+    }
+
+    fn update_targets(&mut self, targets: &EchoTargets, ramp: usize) {
+        self.damping = targets.damping;
+        let delay = clamp_delay(targets.delay_frames, self.capacity_frames);
+        for (smoother, target) in [
+            (&mut self.mix, targets.mix),
+            (&mut self.feedback, targets.feedback),
+            (&mut self.delay_frames, delay),
+        ] {
+            if (smoother.target() - target).abs() > f32::EPSILON {
+                smoother.set_target(target, ramp);
+            }
+        }
+    }
+
+    fn process_samples(&mut self, samples: &[f32], ping_pong: bool, out: &mut Vec<f32>) {
+        let channels = self.channels;
+        let ping_pong = ping_pong && channels >= 2;
+        for frame in samples.chunks(channels) {
+            let mix = self.mix.next();
+            let feedback = widen(self.feedback.next());
+            let delay = self.delay_frames.next();
+            let base = self.write_frame * channels;
+
+            for (channel, &dry) in frame.iter().enumerate() {
+                let wet = narrow(self.read_delayed(channel, delay));
+                out.push(dry * (1.0 - mix) + wet * mix);
+            }
+
+            let mono_in = if ping_pong {
+                widen(frame.iter().take(2).sum::<f32>() * 0.5)
+            } else {
+                0.0
+            };
+            for channel in 0..channels {
+                let delayed = self.read_delayed(channel, delay);
+                self.damp(channel, delayed);
+            }
+            for channel in 0..channels {
+                let input = widen(frame.get(channel).copied().unwrap_or(0.0));
+                self.delay_line[base + channel] = if ping_pong && channel < 2 {
+                    // Repeats cross between L/R; only the left line takes fresh input.
+                    let cross = self.damping_state[1 - channel];
+                    let fresh = if channel == 0 { mono_in } else { 0.0 };
+                    fresh + cross * feedback
+                } else {
+                    input + self.damping_state[channel] * feedback
+                };
+            }
+            self.write_frame = (self.write_frame + 1) % self.capacity_frames;
+        }
+    }
+
+    fn drain_tail(&mut self, ping_pong: bool, out: &mut Vec<f32>) {
+        let frames = self.delay_frames.target().ceil().max(1.0) as usize;
+        let silence = vec![0.0_f32; frames * self.channels];
+        self.process_samples(&silence, ping_pong, out);
+    }
+
+    fn read_delayed(&self, channel: usize, delay_frames: f32) -> DspFloat {
+        let capacity = self.capacity_frames;
+        let whole = delay_frames.floor() as usize;
+        let frac = widen(delay_frames - whole as f32);
+        let index = |offset: usize| {
+            let frame = (self.write_frame + capacity - offset % capacity) % capacity;
+            self.delay_line[frame * self.channels + channel]
+        };
+        let near = index(whole.max(1));
+        let far = index(whole.max(1) + 1);
+        near + (far - near) * frac
+    }
+
+    /// One-pole low-pass on the feedback path; the result is kept in `damping_state`.
+    fn damp(&mut self, channel: usize, sample: DspFloat) {
+        let state = &mut self.damping_state[channel];
+        *state = sample + widen(self.damping) * (*state - sample);
+    }
+}
+
+fn max_delay_frames(sample_rate: u32) -> usize {
+    ((MAX_TIME_MS / 1000.0) * sample_rate as f32).ceil() as usize + 2
+}
+
+fn clamp_delay(delay_frames: f32, capacity_frames: usize) -> f32 {
+    delay_frames.clamp(1.0, capacity_frames.saturating_sub(2).max(1) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::core::DspEffect;
+    use super::*;
+
+    fn context(sample_rate: u32, channels: usize) -> EffectContext {
+        EffectContext::new(sample_rate, channels, None, None, -60.0).unwrap()
+    }
+
+    fn impulse(frames: usize, channels: usize) -> Vec<f32> {
+        let mut samples = vec![0.0_f32; frames * channels];
+        samples[..channels].fill(1.0);
+        samples
+    }
+
+    #[test]
+    fn delay_echo_disabled_passthrough() {
+        let mut effect = DelayEchoEffect {
+            enabled: false,
+            ..Default::default()
+        };
+        let input = vec![0.25_f32, -0.25, 0.5, -0.5];
+        let output = effect.process(&input, &context(48_000, 2), false);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn delay_echo_repeats_impulse_at_configured_time() {
+        let mut effect = DelayEchoEffect::new(1.0);
+        effect.settings = DelayEchoSettings::new(10.0, 0.5, 0.0, false);
+        let output = effect.process(&impulse(40, 1), &context(1_000, 1), false);
+        assert!(output[0].abs() < 1e-6);
+        assert!((output[10] - 1.0).abs() < 1e-6);
+        assert!((output[20] - 0.5).abs() < 1e-6);
+        assert!((output[30] - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn delay_echo_tempo_sync_overrides_time_ms() {
+        let mut settings = DelayEchoSettings::new(10.0, 0.0, 0.0, false);
+        settings.tempo_sync_beats = Some(0.5);
+        assert!((settings.resolved_time_ms(Some(120.0)) - 250.0).abs() < 1e-3);
+        assert!((settings.resolved_time_ms(None) - 10.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn delay_echo_ping_pong_alternates_channels() {
+        let mut effect = DelayEchoEffect::new(1.0);
+        effect.settings = DelayEchoSettings::new(10.0, 0.5, 0.0, true);
+        let output = effect.process(&impulse(30, 2), &context(1_000, 2), false);
+        assert!(output[20] > 0.5 && output[21].abs() < 1e-6);
+        assert!(output[40].abs() < 1e-6 && output[41] > 0.1);
+    }
+
+    #[test]
+    fn delay_echo_drain_emits_remaining_repeats() {
+        let mut effect = DelayEchoEffect::new(1.0);
+        effect.settings = DelayEchoSettings::new(10.0, 0.5, 0.0, false);
+        let ctx = context(1_000, 1);
+        let _ = effect.process(&impulse(5, 1), &ctx, false);
+        let tail = effect.process(&[], &ctx, true);
+        assert_eq!(tail.len(), 10);
+        assert!(tail.iter().any(|sample| sample.abs() > 0.9));
+    }
+
+    #[test]
+    fn delay_echo_deserializes_aliases() {
+        let json = r#"{"enabled":true,"dry_wet":0.4,"delay_ms":250.0,"ping_pong":true}"#;
+        let effect: DelayEchoEffect = serde_json::from_str(json).expect("deserialize echo");
+        assert!((effect.mix - 0.4).abs() < 1e-6);
+        assert!((effect.settings.time_ms - 250.0).abs() < 1e-6);
+        assert!(effect.settings.ping_pong);
+    }
+}
@@ -0,0 +1,146 @@
+//! Structured descriptions of configured effects for hosts and UIs.
+//!
+//! [`EffectDescriptor`] reports what an [`AudioEffect`] in a chain is set to
+//! and whether it is actually changing the audio. An enabled effect can still
+//! pass audio through untouched — a convolution reverb whose impulse response
+//! is missing, for example — and [`EffectBypass`] names that case so a host can
+//! explain it instead of leaving users to think their settings are ignored.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use super::{AudioEffect, EffectContext};
+
+/// Why an enabled effect is passing audio through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EffectBypass {
+    /// Neither the effect settings nor the container name an impulse response.
+    NoImpulseResponse,
+    /// The configured impulse response could not be loaded.
+    ImpulseResponseUnavailable,
+}
+
+impl std::fmt::Display for EffectBypass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoImpulseResponse => write!(f, "no impulse response configured"),
+            Self::ImpulseResponseUnavailable => write!(f, "impulse response failed to load"),
+        }
+    }
+}
+
+/// Snapshot of one effect in a chain.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectDescriptor {
+    /// Serialized effect type, e.g. `"ConvolutionReverbSettings"`.
+    pub kind: String,
+    /// Display label, matching [`AudioEffect::display_name`].
+    pub name: &'static str,
+    pub enabled: bool,
+    /// Serialized settings other than `enabled`, keyed by field name.
+    pub parameters: BTreeMap<String, Value>,
+    /// Delay the effect adds to its output, in frames.
+    pub latency_frames: usize,
+    /// Set when the effect is enabled but skipping itself.
+    pub bypass: Option<EffectBypass>,
+}
+
+impl AudioEffect {
+    /// Describe this effect as it would run in `context`.
+    pub fn descriptor(&self, context: &EffectContext) -> EffectDescriptor {
+        let (kind, mut parameters) = match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map
+                .into_iter()
+                .next()
+                .map(|(kind, settings)| match settings {
+                    Value::Object(fields) => (kind, fields.into_iter().collect()),
+                    _ => (kind, BTreeMap::new()),
+                })
+                .unwrap_or_default(),
+            _ => Default::default(),
+        };
+        let enabled = parameters
+            .remove("enabled")
+            .and_then(|value| value.as_bool())
+            .unwrap_or(false);
+        let bypass = self
+            .as_convolution_reverb()
+            .and_then(|effect| effect.bypass(context));
+        EffectDescriptor {
+            kind,
+            name: self.display_name(),
+            enabled,
+            parameters,
+            latency_frames: self.latency_frames(),
+            bypass,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::effects::{
+        ConvolutionReverbEffect, ConvolutionReverbSettings, GainEffect, LimiterEffect,
+    };
+
+    fn context() -> EffectContext {
+        EffectContext::new(48_000, 2, None, None, -60.0).unwrap()
+    }
+
+    #[test]
+    fn descriptor_lists_settings_without_enabled() {
+        let mut gain = GainEffect::default();
+        gain.enabled = true;
+        let effect = AudioEffect::Gain(gain);
+        let descriptor = effect.descriptor(&context());
+        assert_eq!(descriptor.kind, "GainSettings");
+        assert_eq!(descriptor.name, "Gain");
+        assert!(descriptor.enabled);
+        assert!(!descriptor.parameters.is_empty());
+        assert!(!descriptor.parameters.contains_key("enabled"));
+        assert_eq!(descriptor.latency_frames, 0);
+        assert_eq!(descriptor.bypass, None);
+    }
+
+    #[test]
+    fn oversampled_effects_report_their_latency() {
+        let mut limiter: LimiterEffect =
+            serde_json::from_str(r#"{"enabled": true, "oversampling": 4}"#).unwrap();
+        let effect = AudioEffect::Limiter(limiter.clone());
+        assert!(effect.descriptor(&context()).latency_frames > 0);
+
+        limiter.enabled = false;
+        let effect = AudioEffect::Limiter(limiter);
+        assert_eq!(effect.descriptor(&context()).latency_frames, 0);
+    }
+
+    #[test]
+    fn convolution_reverb_without_an_impulse_response_is_bypassed() {
+        let effect = AudioEffect::ConvolutionReverb(ConvolutionReverbEffect::new(0.5));
+        let descriptor = effect.descriptor(&context());
+        assert!(descriptor.enabled);
+        assert_eq!(descriptor.bypass, Some(EffectBypass::NoImpulseResponse));
+    }
+
+    #[test]
+    fn convolution_reverb_with_a_missing_file_is_bypassed() {
+        let mut reverb = ConvolutionReverbEffect::new(0.5);
+        *reverb.settings_mut() = ConvolutionReverbSettings {
+            impulse_response_path: Some("/nonexistent/ir/hall.wav".to_string()),
+            ..ConvolutionReverbSettings::default()
+        };
+        let effect = AudioEffect::ConvolutionReverb(reverb.clone());
+        assert_eq!(
+            effect.descriptor(&context()).bypass,
+            Some(EffectBypass::ImpulseResponseUnavailable)
+        );
+
+        reverb.enabled = false;
+        let effect = AudioEffect::ConvolutionReverb(reverb);
+        assert_eq!(effect.descriptor(&context()).bypass, None);
+    }
+}
@@ -17,7 +17,9 @@
 //! - Use lower `mix` for insert use on full mixes; higher `mix` works better on sends/auxes.
 //!
 //! DSP primitives (`DelayLine`, `CombFilter`, `AllpassFilter`, etc.) and the
-//! runtime state struct live in the private `primitives` module.
+//! runtime state struct live in the private `primitives` module. The
+//! [`ShimmerReverbEffect`] reuses the same state with a pitch-shifted
+//! feedback loop and lives in the private `shimmer` module.
 
 use serde::{Deserialize, Serialize};
 
@@ -25,9 +27,12 @@ use super::core::smoother::ParamSmoother;
 use super::EffectContext;
 use crate::dsp::guardrails::sanitize_channels;
 
+mod pitch_shift;
 mod primitives;
+mod shimmer;
 
 use primitives::{delay_samples, DiffusionReverbState};
+pub use shimmer::{ShimmerReverbEffect, ShimmerReverbSettings};
 
 const DEFAULT_PRE_DELAY_MS: u64 = 12;
 const DEFAULT_ROOM_SIZE_MS: u64 = 48;
@@ -44,6 +49,7 @@ const OUTPUT_ALLPASS_TUNING_MULTIPLIERS: [f32; 3] = [0.28, 0.41, 0.57];
 
 /// Serialized configuration for the diffusion reverb.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DiffusionReverbSettings {
     /// Pre-delay time in milliseconds.
@@ -128,6 +134,7 @@ impl Default for DiffusionReverbSettings {
 /// Internally this maintains one decorrelated reverb lane per output channel to
 /// avoid left/right cross-coupled ringing when processing interleaved buffers.
 #[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DiffusionReverbEffect {
     /// Whether the effect is active; when `false` samples pass through unmodified.
@@ -0,0 +1,99 @@
+//! Delay-line pitch shifter used in the shimmer feedback path.
+//!
+//! Two read taps sweep through a short delay buffer at a rate set by the
+//! pitch ratio, half a window apart. Each tap is weighted by a raised-cosine
+//! window so the jump when a tap wraps around is crossfaded out. The result is
+//! grainy on dry material but smooth once smeared by the reverb.
+
+use std::f32::consts::PI;
+
+/// Length of the sweeping window in milliseconds.
+const WINDOW_MS: f32 = 60.0;
+
+#[derive(Clone, Debug)]
+/// Mono granular pitch shifter with a fixed-length window.
+pub(super) struct PitchShifter {
+    buffer: Vec<f32>,
+    write: usize,
+    window: f32,
+    phase: f32,
+}
+
+impl PitchShifter {
+    /// Create a pitch shifter for the given sample rate.
+    pub(super) fn new(sample_rate: u32) -> Self {
+        let window = (sample_rate as f32 * WINDOW_MS / 1000.0).max(4.0);
+        Self {
+            buffer: vec![0.0; window as usize + 2],
+            write: 0,
+            window,
+            phase: 0.0,
+        }
+    }
+
+    /// Clear the buffer and restart the tap sweep.
+    pub(super) fn reset(&mut self) {
+        self.buffer.fill(0.0);
+        self.write = 0;
+        self.phase = 0.0;
+    }
+
+    /// Push one sample and return the pitch-shifted output.
+    ///
+    /// `ratio` is the playback speed (`2.0` = one octave up, `0.5` = one
+    /// octave down).
+    pub(super) fn process(&mut self, input: f32, ratio: f32) -> f32 {
+        self.buffer[self.write] = input;
+
+        let first = self.read_tap(self.phase);
+        let second = self.read_tap((self.phase + 0.5).fract());
+        let first_gain = (PI * self.phase).sin().powi(2);
+        let output = first * first_gain + second * (1.0 - first_gain);
+
+        self.phase = (self.phase + (1.0 - ratio) / self.window).rem_euclid(1.0);
+        self.write = (self.write + 1) % self.buffer.len();
+        output
+    }
+
+    fn read_tap(&self, phase: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = phase * self.window;
+        let whole = delay.floor();
+        let frac = delay - whole;
+        let index = (self.write + len - whole as usize % len) % len;
+        let previous = (index + len - 1) % len;
+        self.buffer[index] * (1.0 - frac) + self.buffer[previous] * frac
+    }
+}
+
+/// Convert a shift in semitones to a playback ratio.
+pub(super) fn semitones_to_ratio(semitones: f32) -> f32 {
+    2.0_f32.powf(semitones / 12.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zero_crossings(samples: &[f32]) -> usize {
+        samples
+            .windows(2)
+            .filter(|pair| pair[0] <= 0.0 && pair[1] > 0.0)
+            .count()
+    }
+
+    #[test]
+    fn pitch_shifter_octave_up_doubles_frequency() {
+        let sample_rate = 48_000;
+        let mut shifter = PitchShifter::new(sample_rate);
+        let input: Vec<f32> = (0..sample_rate as usize)
+            .map(|i| (2.0 * PI * 220.0 * i as f32 / sample_rate as f32).sin())
+            .collect();
+        let output: Vec<f32> = input
+            .iter()
+            .map(|&sample| shifter.process(sample, semitones_to_ratio(12.0)))
+            .collect();
+        let ratio = zero_crossings(&output) as f32 / zero_crossings(&input) as f32;
+        assert!((ratio - 2.0).abs() < 0.1, "ratio {ratio}");
+    }
+}
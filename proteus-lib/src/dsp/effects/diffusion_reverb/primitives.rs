@@ -5,6 +5,7 @@
 
 use crate::dsp::effects::core::smoother::ParamSmoother;
 use crate::dsp::guardrails::sanitize_channels;
+use crate::dsp::precision::{narrow, widen, DspFloat};
 
 // Upper bound for synthetic silence-fed tail flushing.
 const DRAIN_MAX_TAIL_MULTIPLIER: usize = 64;
@@ -126,6 +127,36 @@ impl DiffusionReverbState {
         }
     }
 
+    /// Process one interleaved frame with an extra signal injected into the comb bank.
+    ///
+    /// `injection` is added after the input diffusers, and `comb_out` receives
+    /// the averaged comb-bank output before the output diffusers. Taking the
+    /// feedback tap here keeps the loop gain bounded by the comb decay, which
+    /// the shimmer reverb relies on for stability.
+    pub(super) fn process_frame_with_injection(
+        &mut self,
+        frame: &[f32],
+        injection: &[f32],
+        wet: &mut [f32],
+        comb_out: &mut [f32],
+        settings: &super::DiffusionReverbSettings,
+    ) {
+        let (decay, damping) = (settings.decay(), settings.damping());
+        let (input_diffusion, output_diffusion) = diffusion_coeffs(settings.diffusion());
+        for (channel, lane) in self.lanes.iter_mut().enumerate() {
+            let (lane_wet, lane_comb) = lane.process_sample_with_injection(
+                frame.get(channel).copied().unwrap_or(0.0),
+                injection[channel],
+                decay,
+                damping,
+                input_diffusion,
+                output_diffusion,
+            );
+            wet[channel] = lane_wet;
+            comb_out[channel] = lane_comb;
+        }
+    }
+
     /// Drain the buffered reverb tail by feeding silence through all lanes.
     pub(super) fn drain_tail(&mut self, decay: f32, damping: f32, diffusion: f32) -> Vec<f32> {
         let (input_diffusion, output_diffusion) = diffusion_coeffs(diffusion);
@@ -231,24 +262,50 @@ impl ReverbLane {
         input_diffusion: f32,
         output_diffusion: f32,
     ) -> f32 {
+        self.process_sample_with_injection(
+            input,
+            0.0,
+            decay,
+            damping,
+            input_diffusion,
+            output_diffusion,
+        )
+        .0
+    }
+
+    /// Process one mono sample, adding `injection` ahead of the comb bank.
+    ///
+    /// Returns `(wet, comb_out)` where `comb_out` is the averaged comb-bank
+    /// output before the output diffusers and tone filter.
+    fn process_sample_with_injection(
+        &mut self,
+        input: f32,
+        injection: f32,
+        decay: f32,
+        damping: f32,
+        input_diffusion: f32,
+        output_diffusion: f32,
+    ) -> (f32, f32) {
         let mut x = self.pre_delay.process(input);
         for allpass in &mut self.input_allpass {
             x = allpass.process(x, input_diffusion);
         }
+        x += injection;
 
         let mut comb_sum = 0.0;
         for comb in &mut self.combs {
             comb_sum += comb.process(x, decay, damping);
         }
 
-        let mut wet = comb_sum / self.combs.len() as f32;
+        let comb_out = comb_sum / self.combs.len() as f32;
+        let mut wet = comb_out;
         for allpass in &mut self.output_allpass {
             wet = allpass.process(wet, output_diffusion);
         }
 
         // Soften high-frequency ringing in the late tail.
         let tone_smoothing = (0.55 + damping * 0.35).clamp(0.2, 0.95);
-        self.wet_tone.process(wet, tone_smoothing)
+        (self.wet_tone.process(wet, tone_smoothing), comb_out)
     }
 }
 
@@ -291,9 +348,9 @@ impl DelayLine {
 ///
 /// The internal lowpass reduces high-frequency build-up in the feedback loop.
 struct CombFilter {
-    buffer: Vec<f32>,
+    buffer: Vec<DspFloat>,
     index: usize,
-    lowpass: f32,
+    lowpass: DspFloat,
 }
 
 impl CombFilter {
@@ -320,22 +377,23 @@ impl CombFilter {
     /// - `feedback`: Feedback gain controlling decay time.
     /// - `damping`: One-pole lowpass smoothing in the feedback path.
     fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let damping = widen(damping);
         let delayed = self.buffer[self.index];
         self.lowpass = delayed * (1.0 - damping) + self.lowpass * damping;
         let output = self.lowpass;
-        self.buffer[self.index] = input + output * feedback;
+        self.buffer[self.index] = widen(input) + output * widen(feedback);
         self.index += 1;
         if self.index >= self.buffer.len() {
             self.index = 0;
         }
-        output
+        narrow(output)
     }
 }
 
 #[derive(Clone)]
 /// Standard feedback allpass diffuser.
 struct AllpassFilter {
-    buffer: Vec<f32>,
+    buffer: Vec<DspFloat>,
     index: usize,
 }
 
@@ -356,6 +414,7 @@ impl AllpassFilter {
 
     /// Process one sample through the allpass diffuser.
     fn process(&mut self, input: f32, feedback: f32) -> f32 {
+        let (input, feedback) = (widen(input), widen(feedback));
         let delayed = self.buffer[self.index];
         let output = delayed - feedback * input;
         self.buffer[self.index] = input + delayed * feedback;
@@ -363,7 +422,7 @@ impl AllpassFilter {
         if self.index >= self.buffer.len() {
             self.index = 0;
         }
-        output
+        narrow(output)
     }
 }
 
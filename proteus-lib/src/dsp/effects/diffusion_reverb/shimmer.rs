@@ -0,0 +1,383 @@
+//! Shimmer reverb: the diffusion reverb with a pitch-shifted feedback loop.
+//!
+//! Every frame the comb-bank output of the reverb is pitch shifted (an
+//! octave up by default) and fed back ahead of the comb bank. Each pass
+//! through the loop climbs another interval, producing the bright, rising
+//! tails common in ambient production.
+//!
+//! The loop deliberately bypasses the input and output diffusers: the comb
+//! bank's peak gain is `1 / (1 - decay)`, so scaling the feedback by
+//! `1 - decay` keeps the loop gain below unity for any `feedback < 1.0`.
+
+use serde::{Deserialize, Serialize};
+
+use super::super::core::smoother::ParamSmoother;
+use super::super::EffectContext;
+use super::pitch_shift::{semitones_to_ratio, PitchShifter};
+use super::primitives::{delay_samples, DiffusionReverbState};
+use super::{DiffusionReverbSettings, Tuning};
+use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_clamped};
+
+const DEFAULT_MIX: f32 = 0.35;
+const DEFAULT_SHIFT_SEMITONES: f32 = 12.0;
+const DEFAULT_FEEDBACK: f32 = 0.7;
+const MAX_SHIFT_SEMITONES: f32 = 24.0;
+const MAX_FEEDBACK: f32 = 0.95;
+// Upper bound for silence-fed tail flushing, in multiples of the longest delay.
+const DRAIN_MAX_TAIL_MULTIPLIER: usize = 128;
+const DRAIN_SILENCE_EPSILON: f32 = 1.0e-6;
+const DRAIN_SILENT_FRAMES_TO_STOP: usize = 128;
+
+/// Serialized configuration for the shimmer reverb.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ShimmerReverbSettings {
+    /// Pitch shift applied on each pass through the feedback loop, in semitones.
+    #[serde(alias = "shift", alias = "pitch_semitones")]
+    pub shift_semitones: f32,
+    /// Amount of shifted signal returned to the reverb input (`0.0..0.95`).
+    pub feedback: f32,
+    /// Underlying diffusion reverb parameters, using the same defaults.
+    #[serde(flatten)]
+    pub reverb: DiffusionReverbSettings,
+}
+
+impl ShimmerReverbSettings {
+    /// Create shimmer settings on top of the given reverb settings.
+    pub fn new(shift_semitones: f32, feedback: f32, reverb: DiffusionReverbSettings) -> Self {
+        Self {
+            shift_semitones,
+            feedback,
+            reverb,
+        }
+    }
+
+    fn ratio(&self) -> f32 {
+        let semitones = sanitize_finite_clamped(
+            self.shift_semitones,
+            DEFAULT_SHIFT_SEMITONES,
+            -MAX_SHIFT_SEMITONES,
+            MAX_SHIFT_SEMITONES,
+        );
+        semitones_to_ratio(semitones)
+    }
+
+    fn loop_gain(&self) -> f32 {
+        let feedback = sanitize_finite_clamped(self.feedback, DEFAULT_FEEDBACK, 0.0, MAX_FEEDBACK);
+        feedback * (1.0 - self.reverb.decay())
+    }
+}
+
+impl Default for ShimmerReverbSettings {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_SHIFT_SEMITONES,
+            DEFAULT_FEEDBACK,
+            DiffusionReverbSettings::default(),
+        )
+    }
+}
+
+/// Diffusion reverb with pitch-shifted feedback for octave-up shimmer tails.
+#[derive(Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct ShimmerReverbEffect {
+    /// Whether the effect is active; when `false` samples pass through unmodified.
+    pub enabled: bool,
+    /// Dry/wet mix ratio (0.0 = fully dry, 1.0 = fully wet).
+    #[serde(alias = "dry_wet", alias = "wet_dry")]
+    pub mix: f32,
+    /// Shift, feedback, and reverb parameters.
+    #[serde(flatten)]
+    pub settings: ShimmerReverbSettings,
+    #[serde(skip)]
+    state: Option<ShimmerState>,
+    #[serde(skip)]
+    tail_drained: bool,
+}
+
+impl Default for ShimmerReverbEffect {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            mix: DEFAULT_MIX,
+            settings: ShimmerReverbSettings::default(),
+            state: None,
+            tail_drained: false,
+        }
+    }
+}
+
+impl std::fmt::Debug for ShimmerReverbEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShimmerReverbEffect")
+            .field("enabled", &self.enabled)
+            .field("mix", &self.mix)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl crate::dsp::effects::core::DspEffect for ShimmerReverbEffect {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        drain: bool,
+    ) {
+        if !self.enabled {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.ensure_state(context);
+        let Some(state) = self.state.as_mut() else {
+            output.extend_from_slice(input);
+            return;
+        };
+
+        if input.is_empty() {
+            if drain && !self.tail_drained {
+                self.tail_drained = true;
+                state.drain_tail(&self.settings, output);
+            }
+            return;
+        }
+        self.tail_drained = false;
+        state.process(input, &self.settings, output);
+    }
+
+    fn reset_state(&mut self) {
+        if let Some(state) = self.state.as_mut() {
+            state.reset();
+        }
+        self.state = None;
+        self.tail_drained = false;
+    }
+
+    fn warm_up(&mut self, context: &EffectContext) {
+        self.ensure_state(context);
+    }
+}
+
+impl ShimmerReverbEffect {
+    /// Create a shimmer reverb with default settings and the given mix.
+    pub fn new(mix: f32) -> Self {
+        Self {
+            mix: mix.clamp(0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    fn ensure_state(&mut self, context: &EffectContext) {
+        let sample_rate = context.sample_rate();
+        let tuning = Tuning::new(
+            delay_samples(sample_rate, self.settings.reverb.pre_delay_ms),
+            delay_samples(sample_rate, self.settings.reverb.room_size_ms),
+        );
+        let channels = sanitize_channels(context.channels());
+        let mix = sanitize_finite_clamped(self.mix, DEFAULT_MIX, 0.0, 1.0);
+        let loop_gain = self.settings.loop_gain();
+        let ratio = self.settings.ratio();
+        let ramp = context.parameter_ramp_samples();
+
+        if let Some(state) = self.state.as_mut() {
+            if state.reverb.tuning == tuning
+                && state.reverb.channels == channels
+                && state.sample_rate == sample_rate
+            {
+                for (smoother, target) in [
+                    (&mut state.mix, mix),
+                    (&mut state.loop_gain, loop_gain),
+                    (&mut state.ratio, ratio),
+                ] {
+                    if (smoother.target() - target).abs() > f32::EPSILON {
+                        smoother.set_target(target, ramp);
+                    }
+                }
+                return;
+            }
+        }
+
+        // Delay-line topology depends on timing and channel count, so rebuild
+        // rather than mutate when those change.
+        self.state = Some(ShimmerState {
+            sample_rate,
+            reverb: DiffusionReverbState::new(tuning, channels),
+            shifters: vec![PitchShifter::new(sample_rate); channels],
+            feedback: vec![0.0; channels],
+            injection: vec![0.0; channels],
+            frame_wet: vec![0.0; channels],
+            frame_comb: vec![0.0; channels],
+            mix: ParamSmoother::new(mix),
+            loop_gain: ParamSmoother::new(loop_gain),
+            ratio: ParamSmoother::new(ratio),
+        });
+    }
+}
+
+#[derive(Clone)]
+struct ShimmerState {
+    sample_rate: u32,
+    reverb: DiffusionReverbState,
+    shifters: Vec<PitchShifter>,
+    feedback: Vec<f32>,
+    injection: Vec<f32>,
+    frame_wet: Vec<f32>,
+    frame_comb: Vec<f32>,
+    mix: ParamSmoother,
+    loop_gain: ParamSmoother,
+    ratio: ParamSmoother,
+}
+
+impl ShimmerState {
+    fn process(&mut self, input: &[f32], settings: &ShimmerReverbSettings, out: &mut Vec<f32>) {
+        let channels = self.reverb.channels;
+        for frame in input.chunks(channels) {
+            let mix = self.mix.next();
+            self.run_frame(frame, settings);
+            for (&dry, &wet) in frame.iter().zip(&self.frame_wet) {
+                out.push(dry * (1.0 - mix) + wet * mix);
+            }
+        }
+    }
+
+    fn drain_tail(&mut self, settings: &ShimmerReverbSettings, out: &mut Vec<f32>) {
+        let channels = self.reverb.channels;
+        let silence = vec![0.0; channels];
+        let max_tail_frames = self
+            .reverb
+            .tuning
+            .max_delay
+            .saturating_mul(DRAIN_MAX_TAIL_MULTIPLIER)
+            .max(1);
+        let mut trailing_silent_frames = 0usize;
+        for _ in 0..max_tail_frames {
+            let frame_start = out.len();
+            self.run_frame(&silence, settings);
+            let mix = self.mix.next();
+            let mut max_abs = 0.0_f32;
+            for &wet in &self.frame_wet {
+                max_abs = max_abs.max(wet.abs());
+                out.push(wet * mix);
+            }
+            if max_abs <= DRAIN_SILENCE_EPSILON {
+                trailing_silent_frames += 1;
+            } else {
+                trailing_silent_frames = 0;
+            }
+            if trailing_silent_frames >= DRAIN_SILENT_FRAMES_TO_STOP {
+                out.truncate(frame_start);
+                break;
+            }
+        }
+    }
+
+    /// Run one frame through the reverb and the pitch-shifted feedback loop.
+    fn run_frame(&mut self, frame: &[f32], settings: &ShimmerReverbSettings) {
+        let loop_gain = self.loop_gain.next();
+        let ratio = self.ratio.next();
+        for (injection, &feedback) in self.injection.iter_mut().zip(&self.feedback) {
+            *injection = feedback * loop_gain;
+        }
+        self.reverb.process_frame_with_injection(
+            frame,
+            &self.injection,
+            &mut self.frame_wet,
+            &mut self.frame_comb,
+            &settings.reverb,
+        );
+        for ((shifter, feedback), &comb) in self
+            .shifters
+            .iter_mut()
+            .zip(self.feedback.iter_mut())
+            .zip(&self.frame_comb)
+        {
+            *feedback = shifter.process(comb, ratio);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.reverb.reset();
+        for shifter in &mut self.shifters {
+            shifter.reset();
+        }
+        self.feedback.fill(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::effects::core::DspEffect;
+
+    fn context() -> EffectContext {
+        EffectContext::new(48_000, 2, None, None, -60.0).unwrap()
+    }
+
+    #[test]
+    fn shimmer_reverb_disabled_passthrough() {
+        let mut effect = ShimmerReverbEffect {
+            enabled: false,
+            ..Default::default()
+        };
+        let input = vec![0.1_f32, -0.1, 0.2, -0.2];
+        assert_eq!(effect.process(&input, &context(), false), input);
+    }
+
+    #[test]
+    fn shimmer_reverb_preserves_length_and_produces_tail() {
+        let mut effect = ShimmerReverbEffect::new(1.0);
+        let mut input = vec![0.0_f32; 9_600];
+        input[0] = 1.0;
+        input[1] = 1.0;
+        let output = effect.process(&input, &context(), false);
+        assert_eq!(output.len(), input.len());
+        assert!(output[4_800..].iter().any(|sample| sample.abs() > 1.0e-4));
+    }
+
+    #[test]
+    fn shimmer_reverb_decays_at_max_feedback() {
+        let mut effect = ShimmerReverbEffect::new(1.0);
+        effect.settings.feedback = 10.0;
+        effect.settings.reverb.decay = 0.9;
+        let mut input = vec![0.0_f32; 4 * 96_000];
+        input[..400].fill(0.5);
+        let output = effect.process(&input, &context(), false);
+        let peak = |samples: &[f32]| samples.iter().fold(0.0_f32, |acc, s| acc.max(s.abs()));
+        let first_second = peak(&output[..96_000]);
+        let last_second = peak(&output[3 * 96_000..]);
+        assert!(output.iter().all(|sample| sample.is_finite()));
+        assert!(
+            last_second < first_second * 0.5,
+            "first {first_second} last {last_second}"
+        );
+    }
+
+    #[test]
+    fn shimmer_reverb_drain_emits_tail_once() {
+        let mut effect = ShimmerReverbEffect::new(0.5);
+        let _ = effect.process(&[0.5_f32; 512], &context(), false);
+        let tail = effect.process(&[], &context(), true);
+        assert!(!tail.is_empty());
+        assert!(effect.process(&[], &context(), true).is_empty());
+    }
+
+    #[test]
+    fn shimmer_reverb_deserializes_flattened_reverb_settings() {
+        let json = r#"{"enabled":true,"mix":0.4,"shift":7.0,"feedback":0.6,
+            "room_size_ms":100,"decay":0.8}"#;
+        let effect: ShimmerReverbEffect = serde_json::from_str(json).expect("deserialize shimmer");
+        assert_eq!(effect.settings.shift_semitones, 7.0);
+        assert_eq!(effect.settings.reverb.room_size_ms, 100);
+        assert_eq!(effect.settings.reverb.pre_delay_ms, 12);
+    }
+}
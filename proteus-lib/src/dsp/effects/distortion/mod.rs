@@ -1,31 +1,56 @@
-//! Distortion effect based on rodio's distortion source.
+//! Distortion effects: plain hard clipping and oversampled saturation.
+//!
+//! [`DistortionEffect`] is based on rodio's distortion source. The
+//! [`SaturationEffect`] lives in the private `saturation` module. Both can run
+//! their nonlinear stage through the shared [`Oversampled`] wrapper.
 
 use serde::{Deserialize, Serialize};
 
 use super::core::level::deserialize_linear_gain;
 use super::core::smoother::ParamSmoother;
+use super::oversampled::{oversampling_factor, oversampling_latency_frames, Oversampled};
 use super::EffectContext;
 use crate::dsp::guardrails::sanitize_finite;
 
+mod saturation;
+
+pub use saturation::{SaturationCurve, SaturationEffect, SaturationSettings};
+
 const DEFAULT_GAIN: f32 = 1.0;
 const DEFAULT_THRESHOLD: f32 = 1.0;
+const DEFAULT_OVERSAMPLING: u32 = 1;
 
 /// Serialized configuration for distortion parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DistortionSettings {
     /// Pre-distortion gain multiplier applied before hard clipping.
     #[serde(deserialize_with = "deserialize_linear_gain")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub gain: f32,
     /// Clipping threshold; samples with absolute value above this are hard-clipped.
     #[serde(deserialize_with = "deserialize_linear_gain")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub threshold: f32,
+    /// Oversampling factor; `1` (off), `2`, or `4` (other values round to the nearest).
+    pub oversampling: u32,
 }
 
 impl DistortionSettings {
     /// Create a distortion settings payload.
     pub fn new(gain: f32, threshold: f32) -> Self {
-        Self { gain, threshold }
+        Self {
+            gain,
+            threshold,
+            oversampling: DEFAULT_OVERSAMPLING,
+        }
     }
 }
 
@@ -34,12 +59,14 @@ impl Default for DistortionSettings {
         Self {
             gain: DEFAULT_GAIN,
             threshold: DEFAULT_THRESHOLD,
+            oversampling: DEFAULT_OVERSAMPLING,
         }
     }
 }
 
 /// Configured distortion effect.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct DistortionEffect {
     /// Whether the distortion is active; when `false` samples pass through unmodified.
@@ -48,9 +75,7 @@ pub struct DistortionEffect {
     #[serde(flatten)]
     pub settings: DistortionSettings,
     #[serde(skip)]
-    gain_smoother: Option<ParamSmoother>,
-    #[serde(skip)]
-    threshold_smoother: Option<ParamSmoother>,
+    stage: Option<Oversampled<HardClipper>>,
 }
 
 impl std::fmt::Debug for DistortionEffect {
@@ -63,36 +88,59 @@ impl std::fmt::Debug for DistortionEffect {
 }
 
 impl super::core::DspEffect for DistortionEffect {
-    fn process(&mut self, samples: &[f32], context: &EffectContext, _drain: bool) -> Vec<f32> {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        drain: bool,
+    ) {
         if !self.enabled {
-            return samples.to_vec();
-        }
-        if samples.is_empty() {
-            return Vec::new();
+            output.extend_from_slice(input);
+            return;
         }
 
-        self.update_smoothers(context);
-        let gs = self.gain_smoother.as_mut().unwrap();
-        let ts = self.threshold_smoother.as_mut().unwrap();
-        let channels = context.channels().max(1);
+        let factor = oversampling_factor(self.settings.oversampling);
+        let stage = self
+            .stage
+            .get_or_insert_with(|| Oversampled::new(HardClipper::default(), factor));
+        stage.set_factor(factor);
+        stage.inner_mut().settings = self.settings.clone();
+        stage.process_into(input, output, context, drain);
+    }
 
-        let mut out = Vec::with_capacity(samples.len());
-        if gs.is_settled() && ts.is_settled() {
-            let gain = gs.current();
-            let threshold = ts.current();
-            for &sample in samples {
-                out.push((sample * gain).clamp(-threshold, threshold));
-            }
+    fn reset_state(&mut self) {
+        self.stage = None;
+    }
+
+    fn latency_frames(&self) -> usize {
+        if self.enabled {
+            oversampling_latency_frames(self.settings.oversampling)
         } else {
-            for frame in samples.chunks(channels) {
-                let gain = gs.next();
-                let threshold = ts.next();
-                for &sample in frame {
-                    out.push((sample * gain).clamp(-threshold, threshold));
-                }
-            }
+            0
         }
-        out
+    }
+}
+
+/// Smoothed gain and hard clip, run at whatever rate the wrapper chooses.
+#[derive(Clone, Default)]
+struct HardClipper {
+    settings: DistortionSettings,
+    gain_smoother: Option<ParamSmoother>,
+    threshold_smoother: Option<ParamSmoother>,
+}
+
+impl super::core::DspEffect for HardClipper {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
     }
 
     fn process_into(
@@ -102,11 +150,6 @@ impl super::core::DspEffect for DistortionEffect {
         context: &EffectContext,
         _drain: bool,
     ) {
-        if !self.enabled {
-            output.extend_from_slice(input);
-            return;
-        }
-
         self.update_smoothers(context);
         let gs = self.gain_smoother.as_mut().unwrap();
         let ts = self.threshold_smoother.as_mut().unwrap();
@@ -135,7 +178,7 @@ impl super::core::DspEffect for DistortionEffect {
     }
 }
 
-impl DistortionEffect {
+impl HardClipper {
     fn update_smoothers(&mut self, context: &EffectContext) {
         let target_gain = sanitize_finite(self.settings.gain, DEFAULT_GAIN);
         let target_threshold = sanitize_threshold(self.settings.threshold);
@@ -157,6 +200,16 @@ impl DistortionEffect {
     }
 }
 
+fn sanitize_threshold(threshold: f32) -> f32 {
+    let value = sanitize_finite(threshold, DEFAULT_THRESHOLD);
+    let t = value.abs();
+    if t <= f32::EPSILON {
+        DEFAULT_THRESHOLD
+    } else {
+        t
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::super::core::DspEffect;
@@ -176,8 +229,10 @@ mod tests {
 
     #[test]
     fn distortion_clamps_output() {
-        let mut effect = DistortionEffect::default();
-        effect.enabled = true;
+        let mut effect = DistortionEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.gain = 2.0;
         effect.settings.threshold = 0.5;
         let samples = vec![0.4_f32, -0.4, 0.6, -0.6];
@@ -195,15 +250,44 @@ mod tests {
         let effect: DistortionEffect = serde_json::from_str(json).expect("deserialize distortion");
         assert!(effect.settings.gain > 1.0);
         assert!(effect.settings.threshold > 0.0);
+        assert_eq!(effect.settings.oversampling, 1);
     }
-}
 
-fn sanitize_threshold(threshold: f32) -> f32 {
-    let value = sanitize_finite(threshold, DEFAULT_THRESHOLD);
-    let t = value.abs();
-    if t <= f32::EPSILON {
-        DEFAULT_THRESHOLD
-    } else {
-        t
+    /// Magnitude of `freq_hz` in `samples` at 48 kHz.
+    fn tone_level(samples: &[f32], freq_hz: f32) -> f32 {
+        let (mut re, mut im) = (0.0_f32, 0.0_f32);
+        for (index, sample) in samples.iter().enumerate() {
+            let phase = 2.0 * std::f32::consts::PI * freq_hz * index as f32 / 48_000.0;
+            re += sample * phase.cos();
+            im += sample * phase.sin();
+        }
+        (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn oversampling_suppresses_folded_harmonics() {
+        let context = EffectContext::new(48_000, 1, None, None, -60.0).unwrap();
+        let input: Vec<f32> = (0..9_600)
+            .map(|i| (2.0 * std::f32::consts::PI * 14_000.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let mut alias_levels = Vec::new();
+        for oversampling in [1, 4] {
+            let mut effect = DistortionEffect {
+                enabled: true,
+                settings: DistortionSettings {
+                    oversampling,
+                    ..DistortionSettings::new(4.0, 0.5)
+                },
+                ..Default::default()
+            };
+            let output = effect.process(&input, &context, false);
+            assert_eq!(output.len(), input.len());
+            // The third harmonic (42 kHz) folds to 6 kHz at the host rate.
+            alias_levels.push(tone_level(&output[4_800..], 6_000.0));
+        }
+        assert!(
+            alias_levels[1] < alias_levels[0] * 0.1,
+            "aliases {alias_levels:?}"
+        );
     }
 }
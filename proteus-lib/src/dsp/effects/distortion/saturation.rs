@@ -0,0 +1,455 @@
+//! Oversampled saturation with selectable transfer curves.
+//!
+//! Where [`super::DistortionEffect`] hard-clips at a fixed threshold, this
+//! effect drives the signal into a smooth curve at 2x or 4x the host rate so
+//! the generated harmonics fold back far less. Automatic gain compensation
+//! keeps low-level material at roughly unity gain as `drive` increases.
+
+use std::collections::VecDeque;
+
+use serde::{Deserialize, Serialize};
+
+use super::super::core::biquad::{BiquadKind, BiquadState};
+use super::super::core::level::deserialize_linear_gain;
+use super::super::core::smoother::ParamSmoother;
+use super::super::core::DspEffect;
+use super::super::oversampled::{oversampling_factor, oversampling_latency_frames, Oversampled};
+use super::super::EffectContext;
+use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_clamped};
+
+const DEFAULT_DRIVE: f32 = 2.0;
+const DEFAULT_OVERSAMPLING: u32 = 2;
+const DEFAULT_MIX: f32 = 1.0;
+const MIN_DRIVE: f32 = 0.01;
+const MAX_DRIVE: f32 = 100.0;
+/// Input level at which automatic gain compensation targets unity gain.
+const AUTO_GAIN_REFERENCE: f32 = 0.25;
+/// Bias applied to the tube curve to generate even harmonics.
+const TUBE_BIAS: f32 = 0.2;
+/// Input level at which the soft-clip curve reaches full scale.
+const SOFT_CLIP_KNEE: f32 = 1.5;
+/// Corner frequency of the DC blocker that follows the asymmetric curves.
+const DC_BLOCK_HZ: u32 = 10;
+const DC_BLOCK_Q: f32 = 0.707;
+
+/// Transfer curve applied by [`SaturationEffect`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum SaturationCurve {
+    /// Symmetric `tanh` curve with gradual compression of peaks.
+    #[default]
+    Tape,
+    /// Biased `tanh` curve that adds even-order harmonics.
+    Tube,
+    /// Cubic soft clipper that reaches full scale at a fixed knee.
+    #[serde(alias = "softclip")]
+    SoftClip,
+}
+
+impl SaturationCurve {
+    /// Apply the transfer curve to a single sample.
+    pub fn shape(self, x: f32) -> f32 {
+        match self {
+            SaturationCurve::Tape => x.tanh(),
+            SaturationCurve::Tube => (x + TUBE_BIAS).tanh() - TUBE_BIAS.tanh(),
+            SaturationCurve::SoftClip => {
+                let x = x.clamp(-SOFT_CLIP_KNEE, SOFT_CLIP_KNEE);
+                x - 4.0 * x * x * x / 27.0
+            }
+        }
+    }
+
+    /// Make-up gain that restores unity gain at [`AUTO_GAIN_REFERENCE`].
+    fn compensation(self, drive: f32) -> f32 {
+        let shaped = self.shape(drive * AUTO_GAIN_REFERENCE).abs();
+        if shaped <= f32::EPSILON {
+            1.0
+        } else {
+            AUTO_GAIN_REFERENCE / shaped
+        }
+    }
+}
+
+/// Serialized configuration for saturation parameters.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SaturationSettings {
+    /// Gain applied before the curve; accepts linear values or dB strings.
+    #[serde(deserialize_with = "deserialize_linear_gain")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
+    pub drive: f32,
+    /// Transfer curve used for waveshaping.
+    pub curve: SaturationCurve,
+    /// Oversampling factor; `1`, `2`, or `4` (other values round to the nearest).
+    pub oversampling: u32,
+    /// Scale the output so low-level material stays near unity gain.
+    pub auto_gain: bool,
+    /// Dry/wet mix ratio (0.0 = fully dry, 1.0 = fully saturated).
+    #[serde(alias = "dry_wet", alias = "wet_dry")]
+    pub mix: f32,
+}
+
+impl SaturationSettings {
+    /// Create a saturation settings payload.
+    pub fn new(drive: f32, curve: SaturationCurve, oversampling: u32) -> Self {
+        Self {
+            drive,
+            curve,
+            oversampling,
+            auto_gain: true,
+            mix: DEFAULT_MIX,
+        }
+    }
+
+    fn drive(&self) -> f32 {
+        sanitize_finite_clamped(self.drive, DEFAULT_DRIVE, MIN_DRIVE, MAX_DRIVE)
+    }
+
+    fn mix(&self) -> f32 {
+        sanitize_finite_clamped(self.mix, DEFAULT_MIX, 0.0, 1.0)
+    }
+}
+
+impl Default for SaturationSettings {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_DRIVE,
+            SaturationCurve::default(),
+            DEFAULT_OVERSAMPLING,
+        )
+    }
+}
+
+/// Configured saturation effect.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct SaturationEffect {
+    /// Whether the saturation is active; when `false` samples pass through unmodified.
+    pub enabled: bool,
+    /// Drive, curve, oversampling, and mix parameters.
+    #[serde(flatten)]
+    pub settings: SaturationSettings,
+    #[serde(skip)]
+    state: Option<SaturationState>,
+}
+
+impl std::fmt::Debug for SaturationEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SaturationEffect")
+            .field("enabled", &self.enabled)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl DspEffect for SaturationEffect {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        _drain: bool,
+    ) {
+        if !self.enabled {
+            output.extend_from_slice(input);
+            return;
+        }
+        if input.is_empty() {
+            return;
+        }
+
+        self.ensure_state(context);
+        let settings = &self.settings;
+        let Some(state) = self.state.as_mut() else {
+            output.extend_from_slice(input);
+            return;
+        };
+        state.process(input, output, settings, context);
+    }
+
+    fn reset_state(&mut self) {
+        if let Some(state) = self.state.as_mut() {
+            state.reset();
+        }
+        self.state = None;
+    }
+
+    fn warm_up(&mut self, context: &EffectContext) {
+        self.ensure_state(context);
+    }
+
+    fn latency_frames(&self) -> usize {
+        if self.enabled {
+            oversampling_latency_frames(self.settings.oversampling)
+        } else {
+            0
+        }
+    }
+}
+
+impl SaturationEffect {
+    fn ensure_state(&mut self, context: &EffectContext) {
+        let channels = sanitize_channels(context.channels());
+        let sample_rate = context.sample_rate();
+        let factor = oversampling_factor(self.settings.oversampling);
+        let ramp = context.parameter_ramp_samples();
+        let mix = self.settings.mix();
+
+        if let Some(state) = self.state.as_mut() {
+            if state.sample_rate == sample_rate
+                && state.channels == channels
+                && state.shaper.factor() == factor
+            {
+                if (state.mix.target() - mix).abs() > f32::EPSILON {
+                    state.mix.set_target(mix, ramp);
+                }
+                return;
+            }
+        }
+
+        self.state = Some(SaturationState::new(sample_rate, channels, factor, mix));
+    }
+}
+
+/// Drive and transfer curve, run at whatever rate the wrapper chooses.
+#[derive(Clone, Debug, Default)]
+struct Shaper {
+    curve: SaturationCurve,
+    auto_gain: bool,
+    drive: f32,
+    smoother: Option<ParamSmoother>,
+    makeup: (f32, f32),
+}
+
+impl Shaper {
+    fn set(&mut self, settings: &SaturationSettings) {
+        self.curve = settings.curve;
+        self.auto_gain = settings.auto_gain;
+        self.drive = settings.drive();
+        self.makeup.0 = f32::NAN;
+    }
+
+    /// Auto-gain make-up for `drive`, cached while the drive holds still.
+    fn makeup(&mut self, drive: f32) -> f32 {
+        if !self.auto_gain {
+            return 1.0;
+        }
+        if self.makeup.0 != drive {
+            self.makeup = (drive, self.curve.compensation(drive));
+        }
+        self.makeup.1
+    }
+}
+
+impl DspEffect for Shaper {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        _drain: bool,
+    ) {
+        let target = self.drive;
+        let smoother = self
+            .smoother
+            .get_or_insert_with(|| ParamSmoother::new(target));
+        if (smoother.target() - target).abs() > f32::EPSILON {
+            smoother.set_target(target, context.parameter_ramp_samples());
+        }
+        let curve = self.curve;
+        for frame in input.chunks(context.channels().max(1)) {
+            let drive = self.smoother.as_mut().map_or(target, ParamSmoother::next);
+            let makeup = self.makeup(drive);
+            output.extend(
+                frame
+                    .iter()
+                    .map(|&sample| curve.shape(sample * drive) * makeup),
+            );
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.smoother = None;
+    }
+}
+
+#[derive(Clone)]
+struct SaturationState {
+    sample_rate: u32,
+    channels: usize,
+    shaper: Oversampled<Shaper>,
+    dc_blocker: BiquadState,
+    mix: ParamSmoother,
+    /// Dry input delayed by the oversampler's latency so the mix lines up.
+    dry_delay: VecDeque<f32>,
+    shaped: Vec<f32>,
+    blocked: Vec<f32>,
+}
+
+impl SaturationState {
+    fn new(sample_rate: u32, channels: usize, factor: usize, mix: f32) -> Self {
+        let shaper = Oversampled::new(Shaper::default(), factor);
+        let latency = shaper.latency_frames() * channels;
+        Self {
+            sample_rate,
+            channels,
+            shaper,
+            dc_blocker: BiquadState::new(
+                BiquadKind::HighPass,
+                sample_rate,
+                channels,
+                DC_BLOCK_HZ,
+                DC_BLOCK_Q,
+            ),
+            mix: ParamSmoother::new(mix),
+            dry_delay: VecDeque::from(vec![0.0; latency]),
+            shaped: Vec::new(),
+            blocked: Vec::new(),
+        }
+    }
+
+    fn process(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        settings: &SaturationSettings,
+        context: &EffectContext,
+    ) {
+        self.shaper.inner_mut().set(settings);
+        self.shaped.clear();
+        self.shaper
+            .process_into(input, &mut self.shaped, context, false);
+
+        let wet = if settings.curve == SaturationCurve::Tube {
+            self.blocked.clear();
+            self.dc_blocker
+                .process_into(&self.shaped, &mut self.blocked);
+            &self.blocked
+        } else {
+            &self.shaped
+        };
+
+        for (dry_frame, wet_frame) in input.chunks(self.channels).zip(wet.chunks(self.channels)) {
+            let mix = self.mix.next();
+            for (&dry, &wet) in dry_frame.iter().zip(wet_frame) {
+                let dry = if self.dry_delay.is_empty() {
+                    dry
+                } else {
+                    self.dry_delay.push_back(dry);
+                    self.dry_delay.pop_front().unwrap_or(0.0)
+                };
+                output.push(dry * (1.0 - mix) + wet * mix);
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.shaper.reset_state();
+        self.dc_blocker.reset();
+        self.dry_delay.iter_mut().for_each(|sample| *sample = 0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::super::core::DspEffect;
+    use super::*;
+
+    fn context() -> EffectContext {
+        EffectContext::new(48_000, 1, None, None, -60.0).unwrap()
+    }
+
+    fn sine(freq_hz: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|index| {
+                let phase = 2.0 * std::f32::consts::PI * freq_hz * index as f32 / 48_000.0;
+                amplitude * phase.sin()
+            })
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0_f32, |acc, s| acc.max(s.abs()))
+    }
+
+    #[test]
+    fn saturation_disabled_passthrough() {
+        let mut effect = SaturationEffect::default();
+        let samples = vec![0.25_f32, -0.25, 0.5, -0.5];
+        let output = effect.process(&samples, &context(), false);
+        assert_eq!(output, samples);
+    }
+
+    #[test]
+    fn saturation_curves_are_bounded() {
+        for curve in [
+            SaturationCurve::Tape,
+            SaturationCurve::Tube,
+            SaturationCurve::SoftClip,
+        ] {
+            for x in [-100.0_f32, -1.0, 0.0, 1.0, 100.0] {
+                assert!(curve.shape(x).abs() <= 1.5, "{curve:?} at {x}");
+            }
+            assert!(curve.shape(0.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn saturation_auto_gain_keeps_quiet_signal_near_unity() {
+        for oversampling in [1, 2, 4] {
+            let mut effect = SaturationEffect {
+                enabled: true,
+                settings: SaturationSettings::new(8.0, SaturationCurve::Tape, oversampling),
+                ..Default::default()
+            };
+            let input = sine(200.0, AUTO_GAIN_REFERENCE, 9_600);
+            let output = effect.process(&input, &context(), false);
+            assert_eq!(output.len(), input.len());
+            let ratio = peak(&output[4_800..]) / AUTO_GAIN_REFERENCE;
+            assert!((0.8..1.2).contains(&ratio), "{oversampling}x ratio {ratio}");
+        }
+    }
+
+    #[test]
+    fn saturation_compresses_loud_peaks() {
+        let mut effect = SaturationEffect {
+            enabled: true,
+            settings: SaturationSettings {
+                auto_gain: false,
+                ..SaturationSettings::new(4.0, SaturationCurve::SoftClip, 4)
+            },
+            ..Default::default()
+        };
+        let input = sine(200.0, 1.0, 9_600);
+        let output = effect.process(&input, &context(), false);
+        assert!(peak(&output[4_800..]) < 1.1);
+    }
+
+    #[test]
+    fn saturation_deserializes_curve_and_db_drive() {
+        let json = r#"{"enabled":true,"drive":"12db","curve":"soft_clip","oversampling":4}"#;
+        let effect: SaturationEffect = serde_json::from_str(json).expect("deserialize saturation");
+        assert_eq!(effect.settings.curve, SaturationCurve::SoftClip);
+        assert_eq!(oversampling_factor(effect.settings.oversampling), 4);
+        assert!(effect.settings.drive > 3.9);
+        assert!(effect.settings.auto_gain);
+    }
+}
@@ -0,0 +1,385 @@
+//! Dynamic EQ effect: band-limited detection driving per-band gain reduction.
+//!
+//! Each band isolates its region with a band-pass biquad, follows the band
+//! level with a compressor-style envelope, and subtracts the excess from the
+//! signal. With a single high band this behaves as a de-esser.
+
+use serde::{Deserialize, Serialize};
+
+use super::compressor::{compute_gain_db, time_to_coeff};
+use super::core::biquad::{BiquadKind, BiquadState};
+use super::core::level::deserialize_db_gain;
+use super::EffectContext;
+use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_max, sanitize_finite_min};
+
+const DEFAULT_FREQ_HZ: u32 = 6_500;
+const DEFAULT_Q: f32 = 1.5;
+const DEFAULT_THRESHOLD_DB: f32 = -30.0;
+const DEFAULT_RATIO: f32 = 4.0;
+const DEFAULT_ATTACK_MS: f32 = 1.0;
+const DEFAULT_RELEASE_MS: f32 = 60.0;
+const DEFAULT_MAX_REDUCTION_DB: f32 = 12.0;
+
+/// Serialized configuration for one dynamic EQ band.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DynamicEqBandSettings {
+    /// Center frequency of the detection and reduction band in Hz.
+    #[serde(alias = "frequency_hz", alias = "freq")]
+    pub freq_hz: u32,
+    /// Band-pass Q; higher values narrow the affected region.
+    pub q: f32,
+    /// Band level above which reduction starts, in dBFS.
+    #[serde(alias = "threshold", deserialize_with = "deserialize_db_gain")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
+    pub threshold_db: f32,
+    /// Reduction ratio applied to the band level above the threshold.
+    pub ratio: f32,
+    /// Time for the band gain to react to an overshoot, in milliseconds.
+    #[serde(alias = "attack")]
+    pub attack_ms: f32,
+    /// Time for the band gain to recover, in milliseconds.
+    #[serde(alias = "release")]
+    pub release_ms: f32,
+    /// Upper bound on band attenuation, in dB (positive value).
+    #[serde(alias = "range_db")]
+    pub max_reduction_db: f32,
+}
+
+impl DynamicEqBandSettings {
+    /// Create a dynamic EQ band.
+    pub fn new(freq_hz: u32, q: f32, threshold_db: f32, ratio: f32) -> Self {
+        Self {
+            freq_hz,
+            q,
+            threshold_db,
+            ratio,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for DynamicEqBandSettings {
+    fn default() -> Self {
+        Self {
+            freq_hz: DEFAULT_FREQ_HZ,
+            q: DEFAULT_Q,
+            threshold_db: DEFAULT_THRESHOLD_DB,
+            ratio: DEFAULT_RATIO,
+            attack_ms: DEFAULT_ATTACK_MS,
+            release_ms: DEFAULT_RELEASE_MS,
+            max_reduction_db: DEFAULT_MAX_REDUCTION_DB,
+        }
+    }
+}
+
+/// Serialized configuration for the dynamic EQ band list.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DynamicEqSettings {
+    /// Bands processed in series; defaults to a single de-essing band.
+    pub bands: Vec<DynamicEqBandSettings>,
+}
+
+impl DynamicEqSettings {
+    /// Create dynamic EQ settings from a band list.
+    pub fn new(bands: Vec<DynamicEqBandSettings>) -> Self {
+        Self { bands }
+    }
+}
+
+impl Default for DynamicEqSettings {
+    fn default() -> Self {
+        Self {
+            bands: vec![DynamicEqBandSettings::default()],
+        }
+    }
+}
+
+/// Configured dynamic EQ (de-esser) effect.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(default)]
+pub struct DynamicEqEffect {
+    /// Whether the effect is active; when `false` samples pass through unmodified.
+    pub enabled: bool,
+    /// Band list with detection and reduction parameters.
+    #[serde(flatten)]
+    pub settings: DynamicEqSettings,
+    #[serde(skip)]
+    state: Option<DynamicEqState>,
+}
+
+impl std::fmt::Debug for DynamicEqEffect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DynamicEqEffect")
+            .field("enabled", &self.enabled)
+            .field("settings", &self.settings)
+            .finish()
+    }
+}
+
+impl super::core::DspEffect for DynamicEqEffect {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        _drain: bool,
+    ) {
+        if !self.enabled || input.is_empty() {
+            output.extend_from_slice(input);
+            return;
+        }
+        self.ensure_state(context);
+        let Some(state) = self.state.as_mut() else {
+            output.extend_from_slice(input);
+            return;
+        };
+
+        let start = output.len();
+        output.extend_from_slice(input);
+        for band in state.bands.iter_mut() {
+            band.process_in_place(&mut output[start..], state.channels, &mut state.scratch);
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.state = None;
+    }
+
+    fn warm_up(&mut self, context: &EffectContext) {
+        self.ensure_state(context);
+    }
+}
+
+impl DynamicEqEffect {
+    fn ensure_state(&mut self, context: &EffectContext) {
+        let channels = sanitize_channels(context.channels());
+        let sample_rate = context.sample_rate();
+        if let Some(state) = self.state.as_mut() {
+            if state.matches_structure(sample_rate, channels, self.settings.bands.len()) {
+                let ramp = context.parameter_ramp_samples();
+                for (band, settings) in state.bands.iter_mut().zip(&self.settings.bands) {
+                    band.update_parameters(&BandParams::from_settings(settings), ramp);
+                }
+                return;
+            }
+        }
+        let params: Vec<BandParams> = self
+            .settings
+            .bands
+            .iter()
+            .map(BandParams::from_settings)
+            .collect();
+        self.state = Some(DynamicEqState::new(sample_rate, channels, &params));
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct BandParams {
+    freq_hz: u32,
+    q: f32,
+    threshold_db: f32,
+    ratio: f32,
+    attack_ms: f32,
+    release_ms: f32,
+    max_reduction_db: f32,
+}
+
+impl BandParams {
+    fn from_settings(settings: &DynamicEqBandSettings) -> Self {
+        Self {
+            freq_hz: settings.freq_hz,
+            q: settings.q,
+            threshold_db: sanitize_finite_max(settings.threshold_db, DEFAULT_THRESHOLD_DB, 0.0),
+            ratio: sanitize_finite_min(settings.ratio, DEFAULT_RATIO, 1.0),
+            attack_ms: sanitize_finite_min(settings.attack_ms, DEFAULT_ATTACK_MS, 0.0),
+            release_ms: sanitize_finite_min(settings.release_ms, DEFAULT_RELEASE_MS, 0.0),
+            max_reduction_db: sanitize_finite_min(
+                settings.max_reduction_db,
+                DEFAULT_MAX_REDUCTION_DB,
+                0.0,
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DynamicEqState {
+    sample_rate: u32,
+    channels: usize,
+    bands: Vec<DynamicBand>,
+    scratch: Vec<f32>,
+}
+
+impl DynamicEqState {
+    fn new(sample_rate: u32, channels: usize, params: &[BandParams]) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            bands: params
+                .iter()
+                .map(|params| DynamicBand::new(sample_rate, channels, params))
+                .collect(),
+            scratch: Vec::new(),
+        }
+    }
+
+    fn matches_structure(&self, sample_rate: u32, channels: usize, band_count: usize) -> bool {
+        self.sample_rate == sample_rate
+            && self.channels == channels
+            && self.bands.len() == band_count
+    }
+}
+
+#[derive(Clone, Debug)]
+struct DynamicBand {
+    sample_rate: u32,
+    filter: BiquadState,
+    threshold_db: f32,
+    ratio: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    max_reduction_db: f32,
+    current_gain_db: f32,
+}
+
+impl DynamicBand {
+    fn new(sample_rate: u32, channels: usize, params: &BandParams) -> Self {
+        let filter = BiquadState::new(
+            BiquadKind::BandPass,
+            sample_rate,
+            channels,
+            params.freq_hz,
+            params.q,
+        );
+        let mut band = Self {
+            sample_rate,
+            filter,
+            threshold_db: 0.0,
+            ratio: 1.0,
+            attack_coeff: 0.0,
+            release_coeff: 0.0,
+            max_reduction_db: 0.0,
+            current_gain_db: 0.0,
+        };
+        band.set_dynamics(params);
+        band
+    }
+
+    fn update_parameters(&mut self, params: &BandParams, ramp_samples: usize) {
+        self.filter
+            .update_coefficients(params.freq_hz, params.q, ramp_samples);
+        self.set_dynamics(params);
+    }
+
+    fn set_dynamics(&mut self, params: &BandParams) {
+        self.threshold_db = params.threshold_db;
+        self.ratio = params.ratio;
+        self.attack_coeff = time_to_coeff(params.attack_ms, self.sample_rate);
+        self.release_coeff = time_to_coeff(params.release_ms, self.sample_rate);
+        self.max_reduction_db = params.max_reduction_db;
+    }
+
+    /// Attenuate this band in place: `out = x + (g - 1) * band(x)`.
+    fn process_in_place(&mut self, samples: &mut [f32], channels: usize, scratch: &mut Vec<f32>) {
+        scratch.clear();
+        self.filter.process_into(samples, scratch);
+
+        for (frame, band) in samples.chunks_mut(channels).zip(scratch.chunks(channels)) {
+            let peak = band.iter().fold(0.0_f32, |acc, s| acc.max(s.abs()));
+            let level_db = rodio::math::linear_to_db(peak);
+            let target_gain_db = compute_gain_db(level_db, self.threshold_db, self.ratio)
+                .max(-self.max_reduction_db);
+            let coeff = if target_gain_db < self.current_gain_db {
+                self.attack_coeff
+            } else {
+                self.release_coeff
+            };
+            self.current_gain_db = coeff * self.current_gain_db + (1.0 - coeff) * target_gain_db;
+            let band_gain = rodio::math::db_to_linear(self.current_gain_db) - 1.0;
+            for (sample, band_sample) in frame.iter_mut().zip(band) {
+                *sample += band_gain * band_sample;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::core::DspEffect;
+    use super::*;
+
+    fn context() -> EffectContext {
+        EffectContext::new(48_000, 1, None, None, -60.0).unwrap()
+    }
+
+    fn sine(freq_hz: f32, amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|index| {
+                let phase = 2.0 * std::f32::consts::PI * freq_hz * index as f32 / 48_000.0;
+                amplitude * phase.sin()
+            })
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn dynamic_eq_disabled_passthrough() {
+        let mut effect = DynamicEqEffect::default();
+        let input = vec![0.25_f32, -0.25, 0.5, -0.5];
+        let output = effect.process(&input, &context(), false);
+        assert_eq!(output, input);
+    }
+
+    #[test]
+    fn dynamic_eq_reduces_loud_sibilance_band() {
+        let mut effect = DynamicEqEffect {
+            enabled: true,
+            ..Default::default()
+        };
+        let input = sine(6_500.0, 0.8, 9_600);
+        let output = effect.process(&input, &context(), false);
+        assert_eq!(output.len(), input.len());
+        assert!(rms(&output[4_800..]) < rms(&input[4_800..]) * 0.7);
+    }
+
+    #[test]
+    fn dynamic_eq_leaves_out_of_band_content_mostly_untouched() {
+        let mut effect = DynamicEqEffect {
+            enabled: true,
+            ..Default::default()
+        };
+        let input = sine(200.0, 0.8, 9_600);
+        let output = effect.process(&input, &context(), false);
+        let ratio = rms(&output[4_800..]) / rms(&input[4_800..]);
+        assert!(ratio > 0.95, "ratio was {}", ratio);
+    }
+
+    #[test]
+    fn dynamic_eq_deserializes_band_list() {
+        let json = r#"{"enabled":true,"bands":[
+            {"freq_hz":5000,"q":2.0,"threshold_db":"-24db","ratio":3.0,"range_db":6.0}
+        ]}"#;
+        let effect: DynamicEqEffect = serde_json::from_str(json).expect("deserialize dynamic eq");
+        assert_eq!(effect.settings.bands.len(), 1);
+        assert_eq!(effect.settings.bands[0].freq_hz, 5_000);
+        assert!((effect.settings.bands[0].threshold_db + 24.0).abs() < 1e-6);
+        assert!((effect.settings.bands[0].max_reduction_db - 6.0).abs() < 1e-6);
+    }
+}
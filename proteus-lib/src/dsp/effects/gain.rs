@@ -11,10 +11,15 @@ const DEFAULT_GAIN: f32 = 1.0;
 
 /// Serialized configuration for gain parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct GainSettings {
     /// Linear amplitude multiplier applied to every sample (1.0 = unity gain).
     #[serde(deserialize_with = "deserialize_linear_gain")]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub gain: f32,
 }
 
@@ -33,6 +38,7 @@ impl Default for GainSettings {
 
 /// Configured gain effect.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct GainEffect {
     /// Whether the gain effect is active; when `false` samples pass through unmodified.
@@ -151,8 +157,10 @@ mod tests {
 
     #[test]
     fn gain_scales_samples() {
-        let mut effect = GainEffect::default();
-        effect.enabled = true;
+        let mut effect = GainEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.gain = 2.0;
         let samples = vec![0.25_f32, -0.25, 0.5, -0.5];
         let output = effect.process(&samples, &context(), false);
@@ -177,8 +185,10 @@ mod tests {
 
     #[test]
     fn gain_sweep_stays_continuous_on_sine_wave() {
-        let mut effect = GainEffect::default();
-        effect.enabled = true;
+        let mut effect = GainEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.gain = 0.8;
 
         let mut context = EffectContext::new(48_000, 1, None, None, -60.0).unwrap();
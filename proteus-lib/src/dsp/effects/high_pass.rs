@@ -10,6 +10,7 @@ const DEFAULT_Q: f32 = 0.5;
 
 /// Serialized configuration for high-pass filter parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct HighPassFilterSettings {
     /// Cutoff frequency in Hz; energy below this frequency is attenuated.
@@ -38,6 +39,7 @@ impl Default for HighPassFilterSettings {
 
 /// Configured high-pass filter effect with runtime state.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct HighPassFilterEffect {
     /// Whether the filter is active; when `false` samples pass through unmodified.
@@ -145,8 +147,10 @@ mod tests {
 
     #[test]
     fn high_pass_enabled_changes_signal() {
-        let mut effect = HighPassFilterEffect::default();
-        effect.enabled = true;
+        let mut effect = HighPassFilterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.freq_hz = 2000;
         let samples = vec![1.0_f32, -1.0, 1.0, -1.0, 1.0, -1.0];
         let output = effect.process(&samples, &context(), false);
@@ -157,8 +161,10 @@ mod tests {
 
     #[test]
     fn high_pass_q_change_stays_continuous() {
-        let mut effect = HighPassFilterEffect::default();
-        effect.enabled = true;
+        let mut effect = HighPassFilterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.freq_hz = 1_200;
         effect.settings.q = 0.6;
 
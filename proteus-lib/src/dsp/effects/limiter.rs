@@ -7,6 +7,7 @@ use rodio::source::{Limit, LimitSettings, SeekError, Source};
 use serde::{Deserialize, Serialize};
 
 use super::core::level::deserialize_db_gain;
+use super::oversampled::{oversampling_factor, oversampling_latency_frames, Oversampled};
 use super::EffectContext;
 use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_max, sanitize_finite_min};
 
@@ -14,9 +15,11 @@ const DEFAULT_THRESHOLD_DB: f32 = -1.0;
 const DEFAULT_KNEE_WIDTH_DB: f32 = 4.0;
 const DEFAULT_ATTACK_MS: f32 = 5.0;
 const DEFAULT_RELEASE_MS: f32 = 100.0;
+const DEFAULT_OVERSAMPLING: u32 = 1;
 
 /// Serialized configuration for limiter parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct LimiterSettings {
     /// Signal level above which limiting is applied, in dBFS.
@@ -25,6 +28,10 @@ pub struct LimiterSettings {
         alias = "threshold_db",
         deserialize_with = "deserialize_db_gain"
     )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub threshold_db: f32,
     /// Width of the soft-knee transition zone around the threshold, in dB.
     #[serde(
@@ -32,6 +39,10 @@ pub struct LimiterSettings {
         alias = "knee_width_db",
         deserialize_with = "deserialize_db_gain"
     )]
+    #[cfg_attr(
+        feature = "schema",
+        schemars(schema_with = "crate::dsp::effects::core::level::gain_schema")
+    )]
     pub knee_width_db: f32,
     /// Time for gain reduction to reach full limiting after a transient, in milliseconds.
     #[serde(alias = "attack_ms", alias = "attack")]
@@ -39,6 +50,9 @@ pub struct LimiterSettings {
     /// Time for gain to recover after the signal falls below the threshold, in milliseconds.
     #[serde(alias = "release_ms", alias = "release")]
     pub release_ms: f32,
+    /// Oversampling factor; `1` (off), `2`, or `4` (other values round to the nearest).
+    /// Higher factors catch inter-sample peaks at the cost of CPU.
+    pub oversampling: u32,
 }
 
 impl LimiterSettings {
@@ -49,6 +63,7 @@ impl LimiterSettings {
             knee_width_db,
             attack_ms,
             release_ms,
+            oversampling: DEFAULT_OVERSAMPLING,
         }
     }
 }
@@ -60,12 +75,14 @@ impl Default for LimiterSettings {
             knee_width_db: DEFAULT_KNEE_WIDTH_DB,
             attack_ms: DEFAULT_ATTACK_MS,
             release_ms: DEFAULT_RELEASE_MS,
+            oversampling: DEFAULT_OVERSAMPLING,
         }
     }
 }
 
 /// Configured limiter effect with runtime state.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct LimiterEffect {
     /// Whether the limiter is active; when `false` samples pass through unmodified.
@@ -74,7 +91,7 @@ pub struct LimiterEffect {
     #[serde(flatten)]
     pub settings: LimiterSettings,
     #[serde(skip)]
-    state: Option<LimiterState>,
+    stage: Option<Oversampled<LimiterCore>>,
 }
 
 impl std::fmt::Debug for LimiterEffect {
@@ -87,21 +104,61 @@ impl std::fmt::Debug for LimiterEffect {
 }
 
 impl super::core::DspEffect for LimiterEffect {
-    fn process(&mut self, samples: &[f32], context: &EffectContext, _drain: bool) -> Vec<f32> {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        drain: bool,
+    ) {
         if !self.enabled {
-            return samples.to_vec();
+            output.extend_from_slice(input);
+            return;
         }
 
-        self.ensure_state(context);
-        let Some(state) = self.state.as_mut() else {
-            return samples.to_vec();
-        };
+        let factor = oversampling_factor(self.settings.oversampling);
+        let stage = self
+            .stage
+            .get_or_insert_with(|| Oversampled::new(LimiterCore::default(), factor));
+        stage.set_factor(factor);
+        stage.inner_mut().settings = sanitize_settings(&self.settings);
+        stage.process_into(input, output, context, drain);
+    }
 
-        if samples.is_empty() {
-            return Vec::new();
+    fn reset_state(&mut self) {
+        if let Some(stage) = self.stage.as_mut() {
+            stage.reset_state();
         }
+        self.stage = None;
+    }
 
-        state.process(samples)
+    fn latency_frames(&self) -> usize {
+        if self.enabled {
+            oversampling_latency_frames(self.settings.oversampling)
+        } else {
+            0
+        }
+    }
+}
+
+/// Limiter running at whatever rate the oversampling wrapper chooses.
+#[derive(Clone, Default)]
+struct LimiterCore {
+    settings: LimiterSettings,
+    state: Option<LimiterState>,
+}
+
+impl super::core::DspEffect for LimiterCore {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
     }
 
     fn process_into(
@@ -111,10 +168,6 @@ impl super::core::DspEffect for LimiterEffect {
         context: &EffectContext,
         _drain: bool,
     ) {
-        if !self.enabled {
-            output.extend_from_slice(input);
-            return;
-        }
         self.ensure_state(context);
         let Some(state) = self.state.as_mut() else {
             output.extend_from_slice(input);
@@ -134,19 +187,22 @@ impl super::core::DspEffect for LimiterEffect {
     }
 }
 
-impl LimiterEffect {
+impl LimiterCore {
     fn ensure_state(&mut self, context: &EffectContext) {
-        let settings = sanitize_settings(&self.settings);
         let channels = sanitize_channels(context.channels());
 
         let needs_reset = self
             .state
             .as_ref()
-            .map(|state| !state.matches(context.sample_rate(), channels, &settings))
+            .map(|state| !state.matches(context.sample_rate(), channels, &self.settings))
             .unwrap_or(true);
 
         if needs_reset {
-            self.state = Some(LimiterState::new(context.sample_rate(), channels, settings));
+            self.state = Some(LimiterState::new(
+                context.sample_rate(),
+                channels,
+                self.settings.clone(),
+            ));
         }
     }
 }
@@ -180,23 +236,6 @@ impl LimiterState {
             && (self.settings.release_ms - settings.release_ms).abs() < f32::EPSILON
     }
 
-    fn process(&mut self, samples: &[f32]) -> Vec<f32> {
-        {
-            let inner = self.limiter.inner_mut();
-            inner.push_samples(samples);
-        }
-
-        let mut output = Vec::with_capacity(samples.len());
-        for _ in 0..samples.len() {
-            if let Some(sample) = self.limiter.next() {
-                output.push(sample);
-            } else {
-                break;
-            }
-        }
-        output
-    }
-
     fn process_into(&mut self, samples: &[f32], output: &mut Vec<f32>) {
         {
             let inner = self.limiter.inner_mut();
@@ -289,6 +328,7 @@ fn sanitize_settings(settings: &LimiterSettings) -> LimiterSettings {
         knee_width_db: sanitize_finite_min(settings.knee_width_db, DEFAULT_KNEE_WIDTH_DB, 0.1),
         attack_ms: sanitize_finite_min(settings.attack_ms, DEFAULT_ATTACK_MS, 0.0),
         release_ms: sanitize_finite_min(settings.release_ms, DEFAULT_RELEASE_MS, 0.0),
+        oversampling: settings.oversampling,
     }
 }
 
@@ -315,8 +355,10 @@ mod tests {
 
     #[test]
     fn limiter_reduces_hot_signal() {
-        let mut effect = LimiterEffect::default();
-        effect.enabled = true;
+        let mut effect = LimiterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.threshold_db = -12.0;
         effect.settings.knee_width_db = 0.5;
         effect.settings.attack_ms = 0.0;
@@ -331,8 +373,10 @@ mod tests {
 
     #[test]
     fn limiter_split_matches_single_pass() {
-        let mut settings = LimiterEffect::default();
-        settings.enabled = true;
+        let mut settings = LimiterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         settings.settings.threshold_db = -6.0;
         settings.settings.knee_width_db = 1.0;
         settings.settings.attack_ms = 0.0;
@@ -381,4 +425,24 @@ mod tests {
         let err = serde_json::from_str::<LimiterEffect>(json).expect_err("invalid limiter");
         assert!(err.to_string().contains("invalid gain value"));
     }
+
+    #[test]
+    fn oversampled_limiter_holds_a_hot_signal_down() {
+        let mut effect = LimiterEffect {
+            enabled: true,
+            ..Default::default()
+        };
+        effect.settings.threshold_db = -6.0;
+        effect.settings.oversampling = 4;
+
+        let samples: Vec<f32> = (0..9_600)
+            .map(|i| (2.0 * std::f32::consts::PI * 440.0 * i as f32 / 48_000.0).sin())
+            .collect();
+        let output = effect.process(&samples, &context(1), false);
+        assert_eq!(output.len(), samples.len());
+        let peak = output[4_800..]
+            .iter()
+            .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+        assert!(peak < 0.8, "peak {peak}");
+    }
 }
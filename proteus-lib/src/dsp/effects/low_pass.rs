@@ -10,6 +10,7 @@ const DEFAULT_Q: f32 = 0.5;
 
 /// Serialized configuration for low-pass filter parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct LowPassFilterSettings {
     /// Cutoff frequency in Hz; energy above this frequency is attenuated.
@@ -38,6 +39,7 @@ impl Default for LowPassFilterSettings {
 
 /// Configured low-pass filter effect with runtime state.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct LowPassFilterEffect {
     /// Whether the filter is active; when `false` samples pass through unmodified.
@@ -148,8 +150,10 @@ mod tests {
 
     #[test]
     fn low_pass_enabled_changes_signal() {
-        let mut effect = LowPassFilterEffect::default();
-        effect.enabled = true;
+        let mut effect = LowPassFilterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.freq_hz = 200;
         let samples = vec![1.0_f32, -1.0, 1.0, -1.0, 1.0, -1.0];
         let output = effect.process(&samples, &context(), false);
@@ -160,8 +164,10 @@ mod tests {
 
     #[test]
     fn low_pass_reset_restores_passthrough_when_disabled() {
-        let mut effect = LowPassFilterEffect::default();
-        effect.enabled = true;
+        let mut effect = LowPassFilterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.freq_hz = 350;
         let samples = vec![0.3_f32, -0.4, 0.9, -0.8];
         let _ = effect.process(&samples, &context(), false);
@@ -174,8 +180,10 @@ mod tests {
 
     #[test]
     fn low_pass_cutoff_change_stays_continuous() {
-        let mut effect = LowPassFilterEffect::default();
-        effect.enabled = true;
+        let mut effect = LowPassFilterEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.freq_hz = 300;
 
         let mut context = context();
@@ -10,27 +10,46 @@
 
 use serde::{Deserialize, Serialize};
 
-use crate::dsp::effects::convolution_reverb::ImpulseResponseSpec;
+use crate::dsp::effects::convolution_reverb::{ImpulseResponseAttachments, ImpulseResponseSpec};
 use crate::dsp::effects::core::smoother;
 
 pub mod basic_reverb;
+#[cfg(feature = "hrtf")]
+pub mod binaural_panner;
 pub mod compressor;
+#[cfg(test)]
+mod conformance;
 pub mod convolution_reverb;
 mod core;
+pub mod default_chain;
+pub mod delay_echo;
+pub mod descriptor;
 pub mod diffusion_reverb;
 pub mod distortion;
+pub mod dynamic_eq;
 pub mod gain;
 pub mod high_pass;
 pub mod limiter;
 pub mod low_pass;
 pub mod multiband_eq;
+mod oversampled;
 pub mod pan;
 
 pub use basic_reverb::{DelayReverbEffect, DelayReverbSettings};
+#[cfg(feature = "hrtf")]
+pub use binaural_panner::{BinauralPannerEffect, BinauralPannerSettings, HrirError};
 pub use compressor::{CompressorEffect, CompressorSettings};
 pub use convolution_reverb::{ConvolutionReverbEffect, ConvolutionReverbSettings};
-pub use diffusion_reverb::{DiffusionReverbEffect, DiffusionReverbSettings};
-pub use distortion::{DistortionEffect, DistortionSettings};
+pub use default_chain::DefaultEffectChain;
+pub use delay_echo::{DelayEchoEffect, DelayEchoSettings};
+pub use descriptor::{EffectBypass, EffectDescriptor};
+pub use diffusion_reverb::{
+    DiffusionReverbEffect, DiffusionReverbSettings, ShimmerReverbEffect, ShimmerReverbSettings,
+};
+pub use distortion::{
+    DistortionEffect, DistortionSettings, SaturationCurve, SaturationEffect, SaturationSettings,
+};
+pub use dynamic_eq::{DynamicEqBandSettings, DynamicEqEffect, DynamicEqSettings};
 pub use gain::{GainEffect, GainSettings};
 pub use high_pass::{HighPassFilterEffect, HighPassFilterSettings};
 pub use limiter::{LimiterEffect, LimiterSettings};
@@ -70,6 +89,8 @@ pub struct EffectContext {
     impulse_response_spec: Option<ImpulseResponseSpec>,
     impulse_response_tail_db: f32,
     parameter_ramp_samples: usize,
+    tempo_bpm: Option<f32>,
+    impulse_response_attachments: ImpulseResponseAttachments,
 }
 
 impl EffectContext {
@@ -109,6 +130,8 @@ impl EffectContext {
                 smoother::DEFAULT_PARAMETER_RAMP_MS,
                 sample_rate,
             ),
+            tempo_bpm: None,
+            impulse_response_attachments: ImpulseResponseAttachments::default(),
         })
     }
 
@@ -146,6 +169,38 @@ impl EffectContext {
     pub fn set_parameter_ramp_ms(&mut self, ms: f32) {
         self.parameter_ramp_samples = smoother::ramp_samples(ms.max(0.0), self.sample_rate);
     }
+
+    /// Copy of this context at `factor` times the sample rate, with the
+    /// parameter ramp scaled to keep its duration.
+    pub(crate) fn oversampled(&self, factor: usize) -> Self {
+        let factor = factor.max(1);
+        Self {
+            sample_rate: self.sample_rate.saturating_mul(factor as u32),
+            parameter_ramp_samples: self.parameter_ramp_samples * factor,
+            ..self.clone()
+        }
+    }
+
+    /// Song tempo in beats per minute used by tempo-synced effects, if known.
+    pub fn tempo_bpm(&self) -> Option<f32> {
+        self.tempo_bpm
+    }
+
+    /// Set the song tempo; non-finite or non-positive values clear it.
+    pub fn set_tempo_bpm(&mut self, tempo_bpm: Option<f32>) {
+        self.tempo_bpm = tempo_bpm.filter(|bpm| bpm.is_finite() && *bpm > 0.0);
+    }
+
+    /// Impulse response attachments read from the container for
+    /// convolution reverbs.
+    pub fn impulse_response_attachments(&self) -> &ImpulseResponseAttachments {
+        &self.impulse_response_attachments
+    }
+
+    /// Replace the impulse response attachments convolution reverbs decode.
+    pub fn set_impulse_response_attachments(&mut self, attachments: ImpulseResponseAttachments) {
+        self.impulse_response_attachments = attachments;
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -162,11 +217,14 @@ macro_rules! define_audio_effects {
     ) => {
         /// Configured audio effect that can process interleaved samples.
         #[derive(Debug, Clone, Serialize, Deserialize)]
+        #[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
         pub enum AudioEffect {
             $(
-                /// Effect variant wrapping a [`
-                #[doc = stringify!($effect_ty)]
-                /// `] configuration and runtime state.
+                #[doc = concat!(
+                    "Effect variant wrapping a [`",
+                    stringify!($effect_ty),
+                    "`] configuration and runtime state."
+                )]
                 #[serde(rename = $serde_name)]
                 $( $( #[serde(alias = $serde_alias)] )* )?
                 $variant($effect_ty),
@@ -186,6 +244,21 @@ macro_rules! define_audio_effects {
                 }
             }
 
+            /// Whether the effect processes audio; disabled effects pass it
+            /// through.
+            pub fn is_enabled(&self) -> bool {
+                match self {
+                    $( AudioEffect::$variant(effect) => effect.enabled, )*
+                }
+            }
+
+            /// Enable or bypass the effect.
+            pub fn set_enabled(&mut self, enabled: bool) {
+                match self {
+                    $( AudioEffect::$variant(effect) => effect.enabled = enabled, )*
+                }
+            }
+
             /// Return a mutable reference to the inner effect as a trait object.
             fn as_dsp_effect(&mut self) -> &mut dyn core::DspEffect {
                 match self {
@@ -193,6 +266,13 @@ macro_rules! define_audio_effects {
                 }
             }
 
+            /// Return a shared reference to the inner effect as a trait object.
+            fn as_dsp_effect_ref(&self) -> &dyn core::DspEffect {
+                match self {
+                    $( AudioEffect::$variant(effect) => effect, )*
+                }
+            }
+
             /// Process the provided samples through the effect.
             ///
             /// # Arguments
@@ -201,14 +281,24 @@ macro_rules! define_audio_effects {
             /// - `drain`: When true, flush any buffered tail data.
             ///
             /// # Returns
-            /// Processed interleaved samples.
+            /// Processed interleaved samples. Unless `drain` is set, exactly as
+            /// many as `samples`; buffered output is held back as a tail that a
+            /// draining call with empty input flushes.
             pub fn process(
                 &mut self,
                 samples: &[f32],
                 context: &EffectContext,
                 drain: bool,
             ) -> Vec<f32> {
-                self.as_dsp_effect().process(samples, context, drain)
+                let output = self.as_dsp_effect().process(samples, context, drain);
+                debug_assert!(
+                    drain || output.len() == samples.len(),
+                    "{} returned {} samples for {} input samples",
+                    self.display_name(),
+                    output.len(),
+                    samples.len()
+                );
+                output
             }
 
             /// Process the provided samples through the effect, appending output to `output`.
@@ -226,7 +316,15 @@ macro_rules! define_audio_effects {
                 context: &EffectContext,
                 drain: bool,
             ) {
-                self.as_dsp_effect().process_into(input, output, context, drain)
+                let start = output.len();
+                self.as_dsp_effect().process_into(input, output, context, drain);
+                debug_assert!(
+                    drain || output.len() - start == input.len(),
+                    "{} appended {} samples for {} input samples",
+                    self.display_name(),
+                    output.len() - start,
+                    input.len()
+                );
             }
 
             /// Reset any internal state maintained by the effect.
@@ -238,6 +336,12 @@ macro_rules! define_audio_effects {
             pub fn warm_up(&mut self, context: &EffectContext) {
                 self.as_dsp_effect().warm_up(context);
             }
+
+            /// Delay the effect adds to its output with the current settings,
+            /// in frames.
+            pub fn latency_frames(&self) -> usize {
+                self.as_dsp_effect_ref().latency_frames()
+            }
         }
     };
 }
@@ -245,15 +349,19 @@ macro_rules! define_audio_effects {
 define_audio_effects! {
     effects {
         DelayReverb(DelayReverbEffect, "DelayReverbSettings", aliases = ["BasicReverbSettings"]),
+        DelayEcho(DelayEchoEffect, "DelayEchoSettings"),
         DiffusionReverb(DiffusionReverbEffect, "DiffusionReverbSettings"),
+        ShimmerReverb(ShimmerReverbEffect, "ShimmerReverbSettings"),
         ConvolutionReverb(ConvolutionReverbEffect, "ConvolutionReverbSettings"),
         LowPassFilter(LowPassFilterEffect, "LowPassFilterSettings"),
         HighPassFilter(HighPassFilterEffect, "HighPassFilterSettings"),
         Distortion(DistortionEffect, "DistortionSettings"),
+        Saturation(SaturationEffect, "SaturationSettings"),
         Gain(GainEffect, "GainSettings"),
         Compressor(CompressorEffect, "CompressorSettings"),
         Limiter(LimiterEffect, "LimiterSettings"),
         MultibandEq(MultibandEqEffect, "MultibandEqSettings"),
+        DynamicEq(DynamicEqEffect, "DynamicEqSettings", aliases = ["DeEsserSettings"]),
         Pan(PanEffect, "PanSettings"),
     }
 }
@@ -326,15 +434,19 @@ mod tests {
     fn audio_effect_serde_roundtrip_variants() {
         let effects = vec![
             AudioEffect::DelayReverb(DelayReverbEffect::default()),
+            AudioEffect::DelayEcho(DelayEchoEffect::default()),
             AudioEffect::DiffusionReverb(DiffusionReverbEffect::default()),
+            AudioEffect::ShimmerReverb(ShimmerReverbEffect::default()),
             AudioEffect::ConvolutionReverb(ConvolutionReverbEffect::default()),
             AudioEffect::LowPassFilter(LowPassFilterEffect::default()),
             AudioEffect::HighPassFilter(HighPassFilterEffect::default()),
             AudioEffect::Distortion(DistortionEffect::default()),
+            AudioEffect::Saturation(SaturationEffect::default()),
             AudioEffect::Gain(GainEffect::default()),
             AudioEffect::Compressor(CompressorEffect::default()),
             AudioEffect::Limiter(LimiterEffect::default()),
             AudioEffect::MultibandEq(MultibandEqEffect::default()),
+            AudioEffect::DynamicEq(DynamicEqEffect::default()),
             AudioEffect::Pan(PanEffect::default()),
         ];
 
@@ -350,10 +462,15 @@ mod tests {
             {"ConvolutionReverbSettings":{"enabled":true,"wet_dry":0.25}},
             {"DelayReverbSettings":{"enabled":true,"dry_wet":0.5}},
             {"BasicReverbSettings":{"enabled":true,"dry_wet":0.5}},
+            {"DelayEchoSettings":{"enabled":true,"dry_wet":0.3,"time_ms":250.0,
+                "feedback":0.4,"damping":0.2,"ping_pong":true,"tempo_sync_beats":0.5}},
             {"DiffusionReverbSettings":{"enabled":true,"dry_wet":0.35}},
+            {"ShimmerReverbSettings":{"enabled":true,"dry_wet":0.4,"shift":12.0,"feedback":0.6}},
             {"LowPassFilterSettings":{"enabled":true,"freq":800,"bandwidth":0.7}},
             {"HighPassFilterSettings":{"enabled":true,"frequency_hz":1200,"q":0.9}},
             {"DistortionSettings":{"enabled":true,"gain":2.0,"threshold":0.4}},
+            {"SaturationSettings":{"enabled":true,"drive":"6db","curve":"tube",
+                "oversampling":4,"auto_gain":true,"mix":0.8}},
             {"GainSettings":{"enabled":true,"gain":1.25}},
             {"CompressorSettings":{"enabled":true,"threshold":-12.0,"ratio":2.0,
                 "attack":5.0,"release":50.0,"makeup_db":3.0}},
@@ -369,12 +486,16 @@ mod tests {
                 "low_edge":{"type":"high_pass","freq_hz":60,"q":0.7},
                 "high_edge":{"type":"high_shelf","freq_hz":10000,"q":0.8,"gain_db":1.5}
             }},
+            {"DynamicEqSettings":{"enabled":true,"bands":[
+                {"freq_hz":6500,"q":1.5,"threshold_db":-30.0,"ratio":4.0}
+            ]}},
+            {"DeEsserSettings":{"enabled":true}},
             {"PanSettings":{"enabled":true,"pan":-0.3}}
         ]
         "#;
 
         let decoded: Vec<AudioEffect> = serde_json::from_str(json).expect("deserialize effects");
-        assert_eq!(decoded.len(), 12);
+        assert_eq!(decoded.len(), 17);
     }
 
     #[test]
@@ -424,6 +545,16 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn effect_context_tempo_rejects_invalid_values() {
+        let mut ctx = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
+        assert!(ctx.tempo_bpm().is_none());
+        ctx.set_tempo_bpm(Some(128.0));
+        assert_eq!(ctx.tempo_bpm(), Some(128.0));
+        ctx.set_tempo_bpm(Some(-1.0));
+        assert!(ctx.tempo_bpm().is_none());
+    }
+
     #[test]
     fn effect_context_clone_preserves_validity() {
         let ctx = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
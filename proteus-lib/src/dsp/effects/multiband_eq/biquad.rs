@@ -1,6 +1,8 @@
 //! Biquad filter primitives and runtime EQ state for the multiband EQ.
 
-use std::f32::consts::PI;
+use std::f64::consts::PI;
+
+use crate::dsp::precision::{from_f64, DspFloat};
 
 #[derive(Clone, Copy, Debug)]
 pub(super) struct EqPointParams {
@@ -219,20 +221,20 @@ const DEFAULT_COEFF_RAMP_SAMPLES: usize = 240;
 
 #[derive(Clone, Copy, Debug)]
 struct BiquadCoefficients {
-    b0: f32,
-    b1: f32,
-    b2: f32,
-    a1: f32,
-    a2: f32,
+    b0: DspFloat,
+    b1: DspFloat,
+    b2: DspFloat,
+    a1: DspFloat,
+    a2: DspFloat,
 }
 
 #[derive(Clone, Copy, Debug)]
 struct CoefficientDeltas {
-    d_b0: f32,
-    d_b1: f32,
-    d_b2: f32,
-    d_a1: f32,
-    d_a2: f32,
+    d_b0: DspFloat,
+    d_b1: DspFloat,
+    d_b2: DspFloat,
+    d_a1: DspFloat,
+    d_a2: DspFloat,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -252,10 +254,10 @@ pub(super) struct Biquad {
     target_coeffs: Option<BiquadCoefficients>,
     coeff_deltas: Option<CoefficientDeltas>,
     ramp_remaining: usize,
-    x_n1: Vec<f32>,
-    x_n2: Vec<f32>,
-    y_n1: Vec<f32>,
-    y_n2: Vec<f32>,
+    x_n1: Vec<DspFloat>,
+    x_n2: Vec<DspFloat>,
+    y_n1: Vec<DspFloat>,
+    y_n2: Vec<DspFloat>,
 }
 
 impl Biquad {
@@ -284,7 +286,7 @@ impl Biquad {
         } else {
             ramp_samples
         };
-        let inv = 1.0 / ramp as f32;
+        let inv = 1.0 / ramp as DspFloat;
         self.target_coeffs = Some(target);
         self.coeff_deltas = Some(CoefficientDeltas {
             d_b0: (target.b0 - self.coeffs.b0) * inv,
@@ -316,7 +318,7 @@ impl Biquad {
         }
     }
 
-    pub(super) fn process_sample(&mut self, channel: usize, sample: f32) -> f32 {
+    pub(super) fn process_sample(&mut self, channel: usize, sample: DspFloat) -> DspFloat {
         let y = self.coeffs.b0 * sample
             + self.coeffs.b1 * self.x_n1[channel]
             + self.coeffs.b2 * self.x_n2[channel]
@@ -375,10 +377,10 @@ fn peaking_coefficients(
     q: f32,
     gain_db: f32,
 ) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq_hz as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq_hz) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
-    let amplitude = 10.0_f32.powf(gain_db / 40.0);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
+    let amplitude = 10.0_f64.powf(f64::from(gain_db) / 40.0);
 
     let b0 = 1.0 + alpha * amplitude;
     let b1 = -2.0 * cos_w0;
@@ -391,9 +393,9 @@ fn peaking_coefficients(
 }
 
 fn low_pass_coefficients(sample_rate: u32, freq_hz: u32, q: f32) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq_hz as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq_hz) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
 
     let b1 = 1.0 - cos_w0;
     let b0 = b1 / 2.0;
@@ -406,9 +408,9 @@ fn low_pass_coefficients(sample_rate: u32, freq_hz: u32, q: f32) -> BiquadCoeffi
 }
 
 fn high_pass_coefficients(sample_rate: u32, freq_hz: u32, q: f32) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq_hz as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq_hz) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
 
     let b0 = (1.0 + cos_w0) / 2.0;
     let b1 = -1.0 - cos_w0;
@@ -426,10 +428,10 @@ fn low_shelf_coefficients(
     q: f32,
     gain_db: f32,
 ) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq_hz as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq_hz) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
-    let amplitude = 10.0_f32.powf(gain_db / 40.0);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
+    let amplitude = 10.0_f64.powf(f64::from(gain_db) / 40.0);
     let sqrt_amplitude = amplitude.sqrt();
 
     let b0 =
@@ -450,10 +452,10 @@ fn high_shelf_coefficients(
     q: f32,
     gain_db: f32,
 ) -> BiquadCoefficients {
-    let w0 = 2.0 * PI * freq_hz as f32 / sample_rate as f32;
+    let w0 = 2.0 * PI * f64::from(freq_hz) / f64::from(sample_rate);
     let cos_w0 = w0.cos();
-    let alpha = w0.sin() / (2.0 * q);
-    let amplitude = 10.0_f32.powf(gain_db / 40.0);
+    let alpha = w0.sin() / (2.0 * f64::from(q));
+    let amplitude = 10.0_f64.powf(f64::from(gain_db) / 40.0);
     let sqrt_amplitude = amplitude.sqrt();
 
     let b0 =
@@ -469,19 +471,19 @@ fn high_shelf_coefficients(
 }
 
 fn normalized_coefficients(
-    b0: f32,
-    b1: f32,
-    b2: f32,
-    a0: f32,
-    a1: f32,
-    a2: f32,
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a0: f64,
+    a1: f64,
+    a2: f64,
 ) -> BiquadCoefficients {
     BiquadCoefficients {
-        b0: b0 / a0,
-        b1: b1 / a0,
-        b2: b2 / a0,
-        a1: a1 / a0,
-        a2: a2 / a0,
+        b0: from_f64(b0 / a0),
+        b1: from_f64(b1 / a0),
+        b2: from_f64(b2 / a0),
+        a1: from_f64(a1 / a0),
+        a2: from_f64(a2 / a0),
     }
 }
 
@@ -10,6 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use super::EffectContext;
 use crate::dsp::guardrails::{sanitize_channels, sanitize_finite_clamped, sanitize_freq};
+use crate::dsp::precision::{narrow, widen};
 
 mod biquad;
 
@@ -26,7 +27,8 @@ const MIN_GAIN_DB: f32 = -24.0;
 const MAX_GAIN_DB: f32 = 24.0;
 
 /// Serialized configuration for a single parametric EQ point.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct EqPointSettings {
     /// Center frequency of the parametric band, in Hz.
@@ -62,7 +64,8 @@ impl Default for EqPointSettings {
 ///
 /// `HighPass` removes low-end energy below the cutoff.
 /// `LowShelf` boosts/cuts the low-end around the center frequency.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum LowEdgeFilterSettings {
     /// High-pass filter that attenuates frequencies below `freq_hz`.
@@ -87,7 +90,8 @@ pub enum LowEdgeFilterSettings {
 ///
 /// `LowPass` removes high-end energy above the cutoff.
 /// `HighShelf` boosts/cuts the high-end around the center frequency.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(tag = "type", rename_all = "snake_case")]
 pub enum HighEdgeFilterSettings {
     /// Low-pass filter that attenuates frequencies above `freq_hz`.
@@ -109,7 +113,8 @@ pub enum HighEdgeFilterSettings {
 }
 
 /// Serialized configuration for multiband EQ.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct MultibandEqSettings {
     /// Parametric EQ bands applied in order from low to high frequency.
@@ -152,6 +157,7 @@ impl Default for MultibandEqSettings {
 
 /// Configured multiband EQ effect with runtime state.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct MultibandEqEffect {
     /// Whether the EQ effect is active; when `false` samples pass through unmodified.
@@ -192,7 +198,7 @@ impl super::core::DspEffect for MultibandEqEffect {
 
         for (idx, &sample) in samples.iter().enumerate() {
             let ch = idx % channels;
-            let mut y = sample;
+            let mut y = widen(sample);
 
             if let Some(filter) = state.low_edge.as_mut() {
                 y = filter.process_sample(ch, y);
@@ -206,7 +212,7 @@ impl super::core::DspEffect for MultibandEqEffect {
                 y = filter.process_sample(ch, y);
             }
 
-            output.push(y);
+            output.push(narrow(y));
         }
 
         output
@@ -234,7 +240,7 @@ impl super::core::DspEffect for MultibandEqEffect {
         let channels = state.channels;
         for (idx, &sample) in input.iter().enumerate() {
             let ch = idx % channels;
-            let mut y = sample;
+            let mut y = widen(sample);
             if let Some(filter) = state.low_edge.as_mut() {
                 y = filter.process_sample(ch, y);
             }
@@ -244,7 +250,7 @@ impl super::core::DspEffect for MultibandEqEffect {
             if let Some(filter) = state.high_edge.as_mut() {
                 y = filter.process_sample(ch, y);
             }
-            output.push(y);
+            output.push(narrow(y));
         }
     }
 
@@ -372,8 +378,10 @@ mod tests {
 
     #[test]
     fn multiband_eq_points_and_edges_change_signal() {
-        let mut effect = MultibandEqEffect::default();
-        effect.enabled = true;
+        let mut effect = MultibandEqEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.points = vec![
             EqPointSettings::new(120, 0.8, 6.0),
             EqPointSettings::new(1_000, 1.2, -4.0),
@@ -428,8 +436,10 @@ mod tests {
 
     #[test]
     fn multiband_eq_band_change_stays_continuous() {
-        let mut effect = MultibandEqEffect::default();
-        effect.enabled = true;
+        let mut effect = MultibandEqEffect {
+            enabled: true,
+            ..Default::default()
+        };
 
         let mut context = context();
         context.set_parameter_ramp_ms(5.0);
@@ -453,8 +463,10 @@ mod tests {
 
     #[test]
     fn multiband_eq_fast_adjustments_remain_stable() {
-        let mut effect = MultibandEqEffect::default();
-        effect.enabled = true;
+        let mut effect = MultibandEqEffect {
+            enabled: true,
+            ..Default::default()
+        };
 
         let mut context = context();
         context.set_parameter_ramp_ms(0.5);
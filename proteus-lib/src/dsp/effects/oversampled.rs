@@ -0,0 +1,387 @@
+//! Integer-factor oversampling around any effect.
+//!
+//! [`Oversampled`] runs its inner effect at 2x or 4x the host rate so the
+//! harmonics a nonlinear stage generates above the host Nyquist are filtered
+//! out instead of folding back as aliases. Interpolation and decimation share
+//! one linear-phase windowed-sinc low-pass split into polyphase branches, so
+//! only the taps that meet non-zero samples are evaluated on the way up and
+//! only the kept samples are computed on the way down. The pair delays the
+//! signal by [`Oversampled::latency_frames`] host frames; effects that blend
+//! the result with their dry input delay the dry path to match.
+
+use std::collections::VecDeque;
+
+use super::core::DspEffect;
+use super::EffectContext;
+use crate::dsp::guardrails::sanitize_channels;
+
+/// Filter taps per polyphase branch; also the round-trip latency in host frames.
+const TAPS_PER_PHASE: usize = 32;
+/// Passband edge of the shared low-pass as a fraction of the host sample rate.
+const CUTOFF_RATIO: f32 = 0.45;
+
+/// Map a serialized `oversampling` setting to a factor of `1`, `2`, or `4`.
+///
+/// `0` and `1` disable oversampling; values above `2` round up to `4`.
+pub(crate) fn oversampling_factor(setting: u32) -> usize {
+    match setting {
+        0 | 1 => 1,
+        2 => 2,
+        _ => 4,
+    }
+}
+
+/// Delay added by oversampling at a serialized `oversampling` setting, in
+/// host frames.
+pub(crate) fn oversampling_latency_frames(setting: u32) -> usize {
+    latency_for_factor(oversampling_factor(setting))
+}
+
+fn latency_for_factor(factor: usize) -> usize {
+    if factor > 1 {
+        TAPS_PER_PHASE
+    } else {
+        0
+    }
+}
+
+/// Runs `E` at `factor` times the host sample rate.
+///
+/// With a factor of `1` the wrapper is transparent and adds no latency.
+#[derive(Clone)]
+pub(crate) struct Oversampled<E> {
+    inner: E,
+    factor: usize,
+    stage: Option<ResampleStage>,
+}
+
+impl<E: DspEffect> Oversampled<E> {
+    /// Wrap `inner`, running it at `factor`x the host rate.
+    pub(crate) fn new(inner: E, factor: usize) -> Self {
+        Self {
+            inner,
+            factor: factor.max(1),
+            stage: None,
+        }
+    }
+
+    /// The wrapped effect.
+    #[cfg(test)]
+    pub(crate) fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Mutable access to the wrapped effect, e.g. to push new settings.
+    pub(crate) fn inner_mut(&mut self) -> &mut E {
+        &mut self.inner
+    }
+
+    /// Oversampling factor in use.
+    pub(crate) fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Change the oversampling factor, clearing filter and inner state when it
+    /// differs from the current one.
+    pub(crate) fn set_factor(&mut self, factor: usize) {
+        let factor = factor.max(1);
+        if factor != self.factor {
+            self.factor = factor;
+            self.stage = None;
+            self.inner.reset_state();
+        }
+    }
+
+    /// Delay added by the resampling filters, in host frames.
+    pub(crate) fn latency_frames(&self) -> usize {
+        latency_for_factor(self.factor)
+    }
+
+    fn ensure_stage(&mut self, context: &EffectContext) -> &mut ResampleStage {
+        let channels = sanitize_channels(context.channels());
+        let stale = self.stage.as_ref().is_none_or(|stage| {
+            stage.host_rate != context.sample_rate() || stage.channels != channels
+        });
+        if stale {
+            if self.stage.is_some() {
+                self.inner.reset_state();
+            }
+            self.stage = Some(ResampleStage::new(self.factor, context, channels));
+        }
+        self.stage
+            .as_mut()
+            .expect("resample stage was just initialized")
+    }
+}
+
+impl<E: DspEffect> DspEffect for Oversampled<E> {
+    fn process(&mut self, samples: &[f32], context: &EffectContext, drain: bool) -> Vec<f32> {
+        let mut output = Vec::with_capacity(samples.len());
+        self.process_into(samples, &mut output, context, drain);
+        output
+    }
+
+    fn process_into(
+        &mut self,
+        input: &[f32],
+        output: &mut Vec<f32>,
+        context: &EffectContext,
+        drain: bool,
+    ) {
+        if self.factor == 1 {
+            self.inner.process_into(input, output, context, drain);
+            return;
+        }
+
+        let latency = self.latency_frames();
+        self.ensure_stage(context);
+        let Self { inner, stage, .. } = self;
+        let stage = stage.as_mut().expect("resample stage must be initialized");
+
+        if !input.is_empty() {
+            stage.drained = false;
+            stage.run(inner, input, output);
+        }
+        if drain && !stage.drained {
+            stage.drained = true;
+            // Push the filters' own delay out, then whatever the inner effect
+            // still holds.
+            let silence = vec![0.0; latency * stage.channels];
+            stage.run(inner, &silence, output);
+            stage.high.clear();
+            inner.process_into(&[], &mut stage.high, &stage.context, true);
+            let frame = stage.channels * stage.factor;
+            let padded = stage.high.len().div_ceil(frame) * frame;
+            stage.high.resize(padded, 0.0);
+            stage.decimate(output);
+        }
+    }
+
+    fn reset_state(&mut self) {
+        self.inner.reset_state();
+        self.stage = None;
+    }
+
+    fn warm_up(&mut self, context: &EffectContext) {
+        if self.factor == 1 {
+            self.inner.warm_up(context);
+            return;
+        }
+        self.ensure_stage(context);
+        let Self { inner, stage, .. } = self;
+        if let Some(stage) = stage.as_ref() {
+            inner.warm_up(&stage.context);
+        }
+    }
+}
+
+/// Filter state and scratch buffers for one host rate and channel count.
+#[derive(Clone, Debug)]
+struct ResampleStage {
+    host_rate: u32,
+    channels: usize,
+    factor: usize,
+    context: EffectContext,
+    kernel: Vec<f32>,
+    up_history: Vec<VecDeque<f32>>,
+    down_history: Vec<VecDeque<f32>>,
+    up: Vec<f32>,
+    high: Vec<f32>,
+    drained: bool,
+}
+
+impl ResampleStage {
+    fn new(factor: usize, context: &EffectContext, channels: usize) -> Self {
+        let kernel = lowpass_kernel(factor);
+        let up_len = kernel.len().div_ceil(factor);
+        Self {
+            host_rate: context.sample_rate(),
+            channels,
+            factor,
+            context: context.oversampled(factor),
+            up_history: vec![VecDeque::from(vec![0.0; up_len]); channels],
+            down_history: vec![VecDeque::from(vec![0.0; kernel.len()]); channels],
+            kernel,
+            up: Vec::new(),
+            high: Vec::new(),
+            drained: false,
+        }
+    }
+
+    /// Interpolate `input`, run `inner` on it, and append decimated output.
+    fn run<E: DspEffect>(&mut self, inner: &mut E, input: &[f32], output: &mut Vec<f32>) {
+        self.interpolate(input);
+        self.high.clear();
+        inner.process_into(&self.up, &mut self.high, &self.context, false);
+        self.decimate(output);
+    }
+
+    /// Fill `up` with `input` at `factor`x the rate.
+    fn interpolate(&mut self, input: &[f32]) {
+        let factor = self.factor;
+        let gain = factor as f32;
+        self.up.clear();
+        self.up.reserve(input.len() * factor);
+        for frame in input.chunks(self.channels) {
+            for (history, &sample) in self.up_history.iter_mut().zip(frame) {
+                history.pop_back();
+                history.push_front(sample);
+            }
+            for phase in 0..factor {
+                for history in &self.up_history[..frame.len()] {
+                    let acc: f32 = self.kernel[phase..]
+                        .iter()
+                        .step_by(factor)
+                        .zip(history)
+                        .map(|(tap, sample)| tap * sample)
+                        .sum();
+                    self.up.push(acc * gain);
+                }
+            }
+        }
+    }
+
+    /// Low-pass `high` and keep every `factor`-th frame.
+    fn decimate(&mut self, output: &mut Vec<f32>) {
+        let channels = self.channels;
+        for block in self.high.chunks(channels * self.factor) {
+            // Evaluate on each block's first frame so the delay is a whole
+            // number of host frames.
+            for (index, frame) in block.chunks(channels).enumerate() {
+                for (history, &sample) in self.down_history.iter_mut().zip(frame) {
+                    history.pop_back();
+                    history.push_front(sample);
+                }
+                if index > 0 {
+                    continue;
+                }
+                for history in &self.down_history {
+                    let acc: f32 = self
+                        .kernel
+                        .iter()
+                        .zip(history)
+                        .map(|(tap, sample)| tap * sample)
+                        .sum();
+                    output.push(acc);
+                }
+            }
+        }
+    }
+}
+
+/// Blackman-windowed sinc low-pass at `factor`x the host rate, unity DC gain.
+fn lowpass_kernel(factor: usize) -> Vec<f32> {
+    let len = factor * TAPS_PER_PHASE + 1;
+    let center = (len - 1) as f64 / 2.0;
+    let cutoff = f64::from(CUTOFF_RATIO) / factor as f64;
+    let span = (len - 1) as f64;
+    let mut kernel: Vec<f64> = (0..len)
+        .map(|index| {
+            let n = index as f64;
+            let x = n - center;
+            let sinc = if x == 0.0 {
+                2.0 * cutoff
+            } else {
+                (2.0 * std::f64::consts::PI * cutoff * x).sin() / (std::f64::consts::PI * x)
+            };
+            let phase = 2.0 * std::f64::consts::PI * n / span;
+            let window = 0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos();
+            sinc * window
+        })
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    for tap in &mut kernel {
+        *tap /= sum;
+    }
+    kernel.into_iter().map(|tap| tap as f32).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pass-through inner effect that records the rate it ran at.
+    #[derive(Clone, Default)]
+    struct Probe {
+        sample_rate: u32,
+    }
+
+    impl DspEffect for Probe {
+        fn process(&mut self, samples: &[f32], context: &EffectContext, _drain: bool) -> Vec<f32> {
+            self.sample_rate = context.sample_rate();
+            samples.to_vec()
+        }
+
+        fn reset_state(&mut self) {}
+    }
+
+    fn sine(freq_hz: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .map(|i| (2.0 * std::f32::consts::PI * freq_hz * i as f32 / 48_000.0).sin())
+            .collect()
+    }
+
+    fn context() -> EffectContext {
+        EffectContext::new(48_000, 1, None, None, -60.0).unwrap()
+    }
+
+    #[test]
+    fn factor_maps_serialized_settings() {
+        assert_eq!(oversampling_factor(0), 1);
+        assert_eq!(oversampling_factor(1), 1);
+        assert_eq!(oversampling_factor(2), 2);
+        assert_eq!(oversampling_factor(3), 4);
+        assert_eq!(oversampling_factor(8), 4);
+        assert_eq!(oversampling_latency_frames(1), 0);
+        assert_eq!(oversampling_latency_frames(4), TAPS_PER_PHASE);
+    }
+
+    #[test]
+    fn passband_survives_with_the_declared_latency() {
+        for factor in [2, 4] {
+            let mut wrapped = Oversampled::new(Probe::default(), factor);
+            let input = sine(1_000.0, 4_800);
+            let output = wrapped.process(&input, &context(), false);
+            assert_eq!(output.len(), input.len());
+            assert_eq!(wrapped.inner().sample_rate, 48_000 * factor as u32);
+
+            let latency = wrapped.latency_frames();
+            let error = output[latency + 1_000..]
+                .iter()
+                .zip(&input[1_000..])
+                .fold(0.0_f32, |acc, (out, dry)| acc.max((out - dry).abs()));
+            assert!(error < 0.01, "{factor}x error {error}");
+        }
+    }
+
+    #[test]
+    fn split_chunks_match_a_single_pass() {
+        let input = sine(440.0, 2_000);
+        let mut whole = Oversampled::new(Probe::default(), 4);
+        let expected = whole.process(&input, &context(), false);
+
+        let mut split = Oversampled::new(Probe::default(), 4);
+        let mut output = split.process(&input[..333], &context(), false);
+        output.extend(split.process(&input[333..], &context(), false));
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn drain_flushes_the_filter_delay_once() {
+        let mut wrapped = Oversampled::new(Probe::default(), 2);
+        let _ = wrapped.process(&sine(1_000.0, 480), &context(), false);
+        let tail = wrapped.process(&[], &context(), true);
+        assert_eq!(tail.len(), wrapped.latency_frames());
+        assert!(tail.iter().any(|sample| sample.abs() > 0.1));
+        assert!(wrapped.process(&[], &context(), true).is_empty());
+    }
+
+    #[test]
+    fn unit_factor_is_transparent() {
+        let mut wrapped = Oversampled::new(Probe::default(), 1);
+        let input = sine(1_000.0, 64);
+        assert_eq!(wrapped.process(&input, &context(), false), input);
+        assert_eq!(wrapped.latency_frames(), 0);
+        assert_eq!(wrapped.inner().sample_rate, 48_000);
+    }
+}
@@ -10,6 +10,7 @@ const DEFAULT_PAN: f32 = 0.0;
 
 /// Serialized configuration for pan parameters.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct PanSettings {
     /// Stereo pan position in the range `[-1.0, 1.0]`.
@@ -36,6 +37,7 @@ impl Default for PanSettings {
 
 /// Configured pan effect.
 #[derive(Clone, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 #[serde(default)]
 pub struct PanEffect {
     /// Whether the pan effect is active; when `false` samples pass through unmodified.
@@ -171,8 +173,10 @@ mod tests {
 
     #[test]
     fn pan_hard_left_mutes_right_lane() {
-        let mut effect = PanEffect::default();
-        effect.enabled = true;
+        let mut effect = PanEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.pan = -1.0;
         let samples = vec![1.0_f32, 1.0, 0.5, 0.5];
         let output = effect.process(&samples, &stereo_context(), false);
@@ -181,8 +185,10 @@ mod tests {
 
     #[test]
     fn pan_hard_right_mutes_left_lane() {
-        let mut effect = PanEffect::default();
-        effect.enabled = true;
+        let mut effect = PanEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.pan = 1.0;
         let samples = vec![1.0_f32, 1.0, 0.5, 0.5];
         let output = effect.process(&samples, &stereo_context(), false);
@@ -195,8 +201,10 @@ mod tests {
 
     #[test]
     fn pan_non_stereo_passthrough() {
-        let mut effect = PanEffect::default();
-        effect.enabled = true;
+        let mut effect = PanEffect {
+            enabled: true,
+            ..Default::default()
+        };
         effect.settings.pan = 0.75;
         let samples = vec![0.1_f32, -0.2, 0.3, -0.4];
         let output = effect.process(&samples, &mono_context(), false);
@@ -205,8 +213,10 @@ mod tests {
 
     #[test]
     fn pan_changes_ramp_over_multiple_frames() {
-        let mut effect = PanEffect::default();
-        effect.enabled = true;
+        let mut effect = PanEffect {
+            enabled: true,
+            ..Default::default()
+        };
 
         let mut context = stereo_context();
         context.set_parameter_ramp_ms(0.5);
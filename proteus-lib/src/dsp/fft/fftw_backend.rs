@@ -0,0 +1,131 @@
+//! FFTW half-spectrum transforms, selected by the `fftw` feature.
+//!
+//! FFTW executes a plan only on buffers with the alignment it was planned
+//! with, so each plan owns aligned scratch buffers and callers' slices are
+//! copied through them. Plans are not `Sync`, so each sits behind a mutex
+//! held for the length of one transform; every convolver plans its own.
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use fftw::array::AlignedVec;
+use fftw::plan::{C2RPlan, R2CPlan};
+use fftw::types::Flag;
+use log::error;
+
+use super::{Complex, FftBackend, FftError, RealFft};
+use crate::dsp::precision::DspFloat;
+
+#[cfg(not(feature = "f64-dsp"))]
+type ForwardPlan = fftw::plan::R2CPlan32;
+#[cfg(not(feature = "f64-dsp"))]
+type InversePlan = fftw::plan::C2RPlan32;
+#[cfg(feature = "f64-dsp")]
+type ForwardPlan = fftw::plan::R2CPlan64;
+#[cfg(feature = "f64-dsp")]
+type InversePlan = fftw::plan::C2RPlan64;
+
+/// FFTW half-spectrum transforms.
+pub struct FftwBackend;
+
+impl FftBackend for FftwBackend {
+    const NAME: &'static str = "fftw";
+
+    fn plan(len: usize) -> Arc<dyn RealFft> {
+        let plans = match FftwPlans::new(len) {
+            Ok(plans) => Some(plans),
+            Err(err) => {
+                error!("fftw could not plan length {}: {}", len, err);
+                None
+            }
+        };
+        Arc::new(FftwPlan {
+            len,
+            plans: Mutex::new(plans),
+        })
+    }
+}
+
+/// Forward and inverse plans with the aligned buffers they were made for.
+struct FftwPlans {
+    forward: ForwardPlan,
+    inverse: InversePlan,
+    real: AlignedVec<DspFloat>,
+    spectrum: AlignedVec<Complex<DspFloat>>,
+}
+
+impl FftwPlans {
+    fn new(len: usize) -> fftw::error::Result<Self> {
+        Ok(Self {
+            forward: ForwardPlan::aligned(&[len], Flag::ESTIMATE)?,
+            inverse: InversePlan::aligned(&[len], Flag::ESTIMATE)?,
+            real: AlignedVec::new(len),
+            spectrum: AlignedVec::new(len / 2 + 1),
+        })
+    }
+}
+
+struct FftwPlan {
+    len: usize,
+    /// `None` when FFTW could not plan this length; every call then fails.
+    plans: Mutex<Option<FftwPlans>>,
+}
+
+impl FftwPlan {
+    /// Recoverable poison policy: the scratch buffers are overwritten by
+    /// every call, so a panic mid-transform leaves nothing to repair.
+    fn lock_plans(&self) -> MutexGuard<'_, Option<FftwPlans>> {
+        self.plans.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn run(
+        &self,
+        transform: impl FnOnce(&mut FftwPlans) -> fftw::error::Result<()>,
+    ) -> Result<(), FftError> {
+        let mut plans = self.lock_plans();
+        let plans = plans.as_mut().ok_or(FftError::unplanned(self.len))?;
+        transform(plans).map_err(|err| {
+            error!("fftw transform of length {} failed: {}", self.len, err);
+            FftError::unplanned(self.len)
+        })
+    }
+}
+
+impl RealFft for FftwPlan {
+    fn fft_len(&self) -> usize {
+        self.len
+    }
+
+    fn spectrum_len(&self) -> usize {
+        self.len / 2 + 1
+    }
+
+    fn forward(
+        &self,
+        input: &mut [DspFloat],
+        spectrum: &mut [Complex<DspFloat>],
+    ) -> Result<(), FftError> {
+        FftError::check(self.fft_len(), input.len())?;
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        self.run(|plans| {
+            plans.real.copy_from_slice(input);
+            plans.forward.r2c(&mut plans.real, &mut plans.spectrum)?;
+            spectrum.copy_from_slice(&plans.spectrum);
+            Ok(())
+        })
+    }
+
+    fn inverse(
+        &self,
+        spectrum: &mut [Complex<DspFloat>],
+        output: &mut [DspFloat],
+    ) -> Result<(), FftError> {
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        FftError::check(self.fft_len(), output.len())?;
+        self.run(|plans| {
+            plans.spectrum.copy_from_slice(spectrum);
+            plans.inverse.c2r(&mut plans.spectrum, &mut plans.real)?;
+            output.copy_from_slice(&plans.real);
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,364 @@
+//! FFT backends selected at build time.
+//!
+//! Spectral code plans transforms with [`plan_real`] and drives them through
+//! the [`RealFft`] trait, so it never names an FFT crate. The backend behind
+//! [`DefaultBackend`] is chosen by feature:
+//! - `real-fft` (default): `realfft` half-spectrum transforms.
+//! - `complex-fft`: full-length `rustfft` complex transforms of real signals.
+//! - `fftw`: FFTW half-spectrum transforms, built from source by `fftw-src`.
+//!
+//! `fftw` wins over both, and `real-fft` over `complex-fft`. Another library
+//! — IPP on server builds, say — plugs in the same way: implement
+//! [`FftBackend`] behind its own feature and select it in [`DefaultBackend`];
+//! callers are unchanged.
+
+use std::fmt;
+use std::sync::Arc;
+
+#[cfg(all(feature = "complex-fft", not(feature = "real-fft")))]
+pub use rustfft::num_complex::Complex;
+
+#[cfg(feature = "real-fft")]
+pub use realfft::num_complex::Complex;
+
+#[cfg(all(
+    feature = "fftw",
+    not(any(feature = "real-fft", feature = "complex-fft"))
+))]
+pub use num_complex::Complex;
+
+use crate::dsp::precision::DspFloat;
+
+#[cfg(feature = "fftw")]
+mod fftw_backend;
+#[cfg(feature = "fftw")]
+pub use fftw_backend::FftwBackend;
+
+/// Backend used by [`plan_real`].
+#[cfg(feature = "fftw")]
+pub type DefaultBackend = FftwBackend;
+/// Backend used by [`plan_real`].
+#[cfg(all(feature = "real-fft", not(feature = "fftw")))]
+pub type DefaultBackend = RealFftBackend;
+/// Backend used by [`plan_real`].
+#[cfg(all(
+    feature = "complex-fft",
+    not(any(feature = "real-fft", feature = "fftw"))
+))]
+pub type DefaultBackend = ComplexFftBackend;
+
+/// A planned forward/inverse transform pair of one real signal length.
+///
+/// Neither direction normalizes; a forward/inverse round trip scales by
+/// [`RealFft::fft_len`].
+pub trait RealFft: Send + Sync {
+    /// Time-domain length in samples.
+    fn fft_len(&self) -> usize;
+
+    /// Bins in a spectrum produced by [`RealFft::forward`].
+    fn spectrum_len(&self) -> usize;
+
+    /// Transform `input` (`fft_len` samples) into `spectrum` (`spectrum_len`
+    /// bins). `input` may be used as scratch space.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FftError`] when a buffer has the wrong length.
+    fn forward(
+        &self,
+        input: &mut [DspFloat],
+        spectrum: &mut [Complex<DspFloat>],
+    ) -> Result<(), FftError>;
+
+    /// Transform `spectrum` back into `output` (`fft_len` samples). `spectrum`
+    /// may be used as scratch space.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`FftError`] when a buffer has the wrong length.
+    fn inverse(
+        &self,
+        spectrum: &mut [Complex<DspFloat>],
+        output: &mut [DspFloat],
+    ) -> Result<(), FftError>;
+}
+
+/// A source of [`RealFft`] plans.
+pub trait FftBackend {
+    /// Short name reported in diagnostics.
+    const NAME: &'static str;
+
+    /// Plan a transform pair for signals of `len` samples.
+    fn plan(len: usize) -> Arc<dyn RealFft>;
+}
+
+/// Plan a transform pair of `len` samples on the build's [`DefaultBackend`].
+pub fn plan_real(len: usize) -> Arc<dyn RealFft> {
+    DefaultBackend::plan(len)
+}
+
+/// Name of the build's [`DefaultBackend`].
+pub fn backend_name() -> &'static str {
+    DefaultBackend::NAME
+}
+
+/// Error returned when a transform is given mismatched buffers or its
+/// backend could not plan it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FftError {
+    kind: FftErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FftErrorKind {
+    Length {
+        expected: usize,
+        actual: usize,
+    },
+    #[cfg(feature = "fftw")]
+    Unplanned {
+        len: usize,
+    },
+}
+
+impl FftError {
+    fn check(expected: usize, actual: usize) -> Result<(), Self> {
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(Self::length(expected, actual))
+        }
+    }
+
+    fn length(expected: usize, actual: usize) -> Self {
+        Self {
+            kind: FftErrorKind::Length { expected, actual },
+        }
+    }
+
+    #[cfg(feature = "fftw")]
+    fn unplanned(len: usize) -> Self {
+        Self {
+            kind: FftErrorKind::Unplanned { len },
+        }
+    }
+}
+
+impl fmt::Display for FftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            FftErrorKind::Length { expected, actual } => write!(
+                f,
+                "fft buffer length {} does not match the planned {}",
+                actual, expected
+            ),
+            #[cfg(feature = "fftw")]
+            FftErrorKind::Unplanned { len } => {
+                write!(f, "no fft plan could be made for length {}", len)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FftError {}
+
+/// `realfft` half-spectrum transforms.
+#[cfg(feature = "real-fft")]
+pub struct RealFftBackend;
+
+#[cfg(feature = "real-fft")]
+impl FftBackend for RealFftBackend {
+    const NAME: &'static str = "realfft";
+
+    fn plan(len: usize) -> Arc<dyn RealFft> {
+        let mut planner = realfft::RealFftPlanner::<DspFloat>::new();
+        Arc::new(RealFftPlan {
+            r2c: planner.plan_fft_forward(len),
+            c2r: planner.plan_fft_inverse(len),
+        })
+    }
+}
+
+#[cfg(feature = "real-fft")]
+struct RealFftPlan {
+    r2c: Arc<dyn realfft::RealToComplex<DspFloat>>,
+    c2r: Arc<dyn realfft::ComplexToReal<DspFloat>>,
+}
+
+#[cfg(feature = "real-fft")]
+impl RealFft for RealFftPlan {
+    fn fft_len(&self) -> usize {
+        self.r2c.len()
+    }
+
+    fn spectrum_len(&self) -> usize {
+        self.r2c.len() / 2 + 1
+    }
+
+    fn forward(
+        &self,
+        input: &mut [DspFloat],
+        spectrum: &mut [Complex<DspFloat>],
+    ) -> Result<(), FftError> {
+        FftError::check(self.fft_len(), input.len())?;
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        // realfft only fails on buffer lengths, which were checked above.
+        self.r2c
+            .process(input, spectrum)
+            .map_err(|_| FftError::length(self.fft_len(), input.len()))
+    }
+
+    fn inverse(
+        &self,
+        spectrum: &mut [Complex<DspFloat>],
+        output: &mut [DspFloat],
+    ) -> Result<(), FftError> {
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        FftError::check(self.fft_len(), output.len())?;
+        self.c2r
+            .process(spectrum, output)
+            .map_err(|_| FftError::length(self.fft_len(), output.len()))
+    }
+}
+
+/// `rustfft` complex transforms applied to real signals.
+#[cfg(feature = "complex-fft")]
+pub struct ComplexFftBackend;
+
+#[cfg(feature = "complex-fft")]
+impl FftBackend for ComplexFftBackend {
+    const NAME: &'static str = "rustfft";
+
+    fn plan(len: usize) -> Arc<dyn RealFft> {
+        let mut planner = rustfft::FftPlanner::<DspFloat>::new();
+        Arc::new(ComplexFftPlan {
+            forward: planner.plan_fft_forward(len),
+            inverse: planner.plan_fft_inverse(len),
+        })
+    }
+}
+
+#[cfg(feature = "complex-fft")]
+struct ComplexFftPlan {
+    forward: Arc<dyn rustfft::Fft<DspFloat>>,
+    inverse: Arc<dyn rustfft::Fft<DspFloat>>,
+}
+
+#[cfg(feature = "complex-fft")]
+impl RealFft for ComplexFftPlan {
+    fn fft_len(&self) -> usize {
+        self.forward.len()
+    }
+
+    fn spectrum_len(&self) -> usize {
+        self.forward.len()
+    }
+
+    fn forward(
+        &self,
+        input: &mut [DspFloat],
+        spectrum: &mut [Complex<DspFloat>],
+    ) -> Result<(), FftError> {
+        FftError::check(self.fft_len(), input.len())?;
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        for (bin, &sample) in spectrum.iter_mut().zip(input.iter()) {
+            *bin = Complex {
+                re: sample,
+                im: 0.0,
+            };
+        }
+        self.forward.process(spectrum);
+        Ok(())
+    }
+
+    fn inverse(
+        &self,
+        spectrum: &mut [Complex<DspFloat>],
+        output: &mut [DspFloat],
+    ) -> Result<(), FftError> {
+        FftError::check(self.spectrum_len(), spectrum.len())?;
+        FftError::check(self.fft_len(), output.len())?;
+        self.inverse.process(spectrum);
+        for (sample, bin) in output.iter_mut().zip(spectrum.iter()) {
+            *sample = bin.re;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(fft: &dyn RealFft) {
+        let signal: Vec<DspFloat> = (0..fft.fft_len())
+            .map(|i| (i as DspFloat * 0.3).sin())
+            .collect();
+        let mut input = signal.clone();
+        let mut spectrum = vec![Complex { re: 0.0, im: 0.0 }; fft.spectrum_len()];
+        fft.forward(&mut input, &mut spectrum).unwrap();
+        let mut output = vec![0.0; fft.fft_len()];
+        fft.inverse(&mut spectrum, &mut output).unwrap();
+        let scale = fft.fft_len() as DspFloat;
+        for (out, expected) in output.iter().zip(&signal) {
+            assert!((out / scale - expected).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn default_backend_round_trips_a_signal() {
+        round_trip(plan_real(64).as_ref());
+    }
+
+    #[cfg(all(feature = "real-fft", feature = "complex-fft"))]
+    #[test]
+    fn backends_agree_on_shared_bins() {
+        let real = RealFftBackend::plan(32);
+        let complex = ComplexFftBackend::plan(32);
+        round_trip(complex.as_ref());
+
+        let signal: Vec<DspFloat> = (0..32).map(|i| (i as DspFloat * 0.7).cos()).collect();
+        let mut real_spectrum = vec![Complex { re: 0.0, im: 0.0 }; real.spectrum_len()];
+        let mut complex_spectrum = vec![Complex { re: 0.0, im: 0.0 }; complex.spectrum_len()];
+        real.forward(&mut signal.clone(), &mut real_spectrum)
+            .unwrap();
+        complex
+            .forward(&mut signal.clone(), &mut complex_spectrum)
+            .unwrap();
+        for (a, b) in real_spectrum.iter().zip(&complex_spectrum) {
+            assert!((a - b).norm() < 1e-4);
+        }
+    }
+
+    #[cfg(all(feature = "fftw", feature = "real-fft"))]
+    #[test]
+    fn fftw_matches_realfft() {
+        let fftw = FftwBackend::plan(64);
+        let real = RealFftBackend::plan(64);
+        round_trip(fftw.as_ref());
+
+        let signal: Vec<DspFloat> = (0..64).map(|i| (i as DspFloat * 0.41).sin()).collect();
+        let mut fftw_spectrum = vec![Complex { re: 0.0, im: 0.0 }; fftw.spectrum_len()];
+        let mut real_spectrum = vec![Complex { re: 0.0, im: 0.0 }; real.spectrum_len()];
+        fftw.forward(&mut signal.clone(), &mut fftw_spectrum)
+            .unwrap();
+        real.forward(&mut signal.clone(), &mut real_spectrum)
+            .unwrap();
+        for (a, b) in fftw_spectrum.iter().zip(&real_spectrum) {
+            assert!((a - b).norm() < 1e-3);
+        }
+        assert_eq!(backend_name(), "fftw");
+    }
+
+    #[test]
+    fn mismatched_buffers_are_rejected() {
+        let fft = plan_real(16);
+        let mut input = vec![0.0; 8];
+        let mut spectrum = vec![Complex { re: 0.0, im: 0.0 }; fft.spectrum_len()];
+        let err = fft.forward(&mut input, &mut spectrum).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "fft buffer length 8 does not match the planned 16"
+        );
+    }
+}
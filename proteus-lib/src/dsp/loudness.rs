@@ -0,0 +1,205 @@
+//! Integrated loudness measurement (ITU-R BS.1770-4).
+//!
+//! [`LoudnessMeter`] K-weights interleaved input, collects 400 ms blocks on a
+//! 100 ms hop, and reports gated integrated loudness in LUFS along with the
+//! sample peak. Channels are weighted equally except in 5.1 layouts
+//! (L R C LFE Ls Rs), where the LFE is excluded and the surrounds get +1.5 dB.
+
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+
+/// Blocks quieter than this never count toward integrated loudness.
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+/// Blocks this far below the ungated mean are dropped in the second pass.
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Sub-blocks (100 ms) per gating block (400 ms).
+const SUB_BLOCKS_PER_BLOCK: usize = 4;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+// Stage 1 high shelf and stage 2 high-pass of the K-weighting filter,
+// derived for any sample rate (coefficients match the 48 kHz reference).
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = sample_rate.max(1) as f64;
+
+    let (f0, gain_db, q) = (
+        1_681.974_450_955_533,
+        3.999_843_853_973_347,
+        0.707_175_236_955_419_6,
+    );
+    let k = (PI * f0 / rate).tan();
+    let vh = 10_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+    [shelf, high_pass]
+}
+
+fn channel_weight(channel: usize, channels: usize) -> f64 {
+    match (channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn energy_to_lufs(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Streaming BS.1770 integrated loudness and sample-peak meter.
+#[derive(Debug, Clone)]
+pub struct LoudnessMeter {
+    channels: usize,
+    filters: Vec<[Biquad; 2]>,
+    weights: Vec<f64>,
+    sub_block_frames: usize,
+    sub_block_energy: f64,
+    sub_block_filled: usize,
+    recent: VecDeque<f64>,
+    blocks: Vec<f64>,
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    /// Create a meter for interleaved audio at `sample_rate` with `channels` channels.
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        Self {
+            channels,
+            filters: vec![k_weighting(sample_rate); channels],
+            weights: (0..channels)
+                .map(|channel| channel_weight(channel, channels))
+                .collect(),
+            sub_block_frames: (sample_rate.max(10) / 10) as usize,
+            sub_block_energy: 0.0,
+            sub_block_filled: 0,
+            recent: VecDeque::with_capacity(SUB_BLOCKS_PER_BLOCK),
+            blocks: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    /// Feed interleaved samples; trailing partial frames are ignored.
+    pub fn process(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut energy = 0.0;
+            for (channel, sample) in frame.iter().enumerate() {
+                self.peak = self.peak.max(sample.abs());
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let weighted = high_pass.process(shelf.process(*sample as f64));
+                energy += self.weights[channel] * weighted * weighted;
+            }
+            self.sub_block_energy += energy;
+            self.sub_block_filled += 1;
+            if self.sub_block_filled == self.sub_block_frames {
+                self.push_sub_block();
+            }
+        }
+    }
+
+    fn push_sub_block(&mut self) {
+        if self.recent.len() == SUB_BLOCKS_PER_BLOCK {
+            self.recent.pop_front();
+        }
+        self.recent
+            .push_back(self.sub_block_energy / self.sub_block_frames as f64);
+        self.sub_block_energy = 0.0;
+        self.sub_block_filled = 0;
+        if self.recent.len() == SUB_BLOCKS_PER_BLOCK {
+            let block = self.recent.iter().sum::<f64>() / SUB_BLOCKS_PER_BLOCK as f64;
+            self.blocks.push(block);
+        }
+    }
+
+    /// Gated integrated loudness in LUFS, or `None` until a block clears the
+    /// absolute gate.
+    pub fn integrated_lufs(&self) -> Option<f32> {
+        let gated_mean = |threshold: f64| {
+            let (sum, count) = self
+                .blocks
+                .iter()
+                .filter(|energy| energy_to_lufs(**energy) > threshold)
+                .fold((0.0, 0_usize), |(sum, count), energy| {
+                    (sum + energy, count + 1)
+                });
+            (count > 0).then(|| sum / count as f64)
+        };
+        let ungated = gated_mean(ABSOLUTE_GATE_LUFS)?;
+        let relative_gate = energy_to_lufs(ungated) + RELATIVE_GATE_LU;
+        let integrated = gated_mean(relative_gate.max(ABSOLUTE_GATE_LUFS))?;
+        Some(energy_to_lufs(integrated) as f32)
+    }
+
+    /// Largest absolute sample value seen (linear).
+    pub fn sample_peak(&self) -> f32 {
+        self.peak
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(rate: u32, channels: usize, amplitude: f32, seconds: f32) -> Vec<f32> {
+        let frames = (rate as f32 * seconds) as usize;
+        (0..frames)
+            .flat_map(|n| {
+                let value = amplitude * (2.0 * PI * 997.0 * n as f64 / rate as f64).sin() as f32;
+                std::iter::repeat_n(value, channels)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn full_scale_sine_reads_reference_loudness() {
+        let mut mono = LoudnessMeter::new(48_000, 1);
+        mono.process(&sine(48_000, 1, 1.0, 3.0));
+        let lufs = mono.integrated_lufs().unwrap();
+        assert!((lufs + 3.01).abs() < 0.05, "mono {lufs}");
+
+        let mut stereo = LoudnessMeter::new(44_100, 2);
+        stereo.process(&sine(44_100, 2, 0.5, 3.0));
+        let lufs = stereo.integrated_lufs().unwrap();
+        assert!((lufs + 6.02).abs() < 0.05, "stereo {lufs}");
+        assert!((stereo.sample_peak() - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn silence_is_gated_out() {
+        let mut meter = LoudnessMeter::new(48_000, 2);
+        meter.process(&vec![0.0; 48_000 * 2]);
+        assert_eq!(meter.integrated_lufs(), None);
+    }
+}
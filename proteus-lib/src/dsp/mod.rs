@@ -1,5 +1,12 @@
 //! DSP components: effects, mixing, and reverb utilities.
 
+pub mod dither;
+pub mod downmix;
 pub mod effects;
+pub mod fft;
 pub mod guardrails;
+pub mod loudness;
+pub mod pan;
+pub mod precision;
+pub mod resample;
 pub mod utils;
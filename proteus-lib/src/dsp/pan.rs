@@ -0,0 +1,122 @@
+//! Stereo pan laws.
+//!
+//! A pan law decides how much of a track reaches each front channel as it
+//! moves across the stereo field, and therefore how loud it sounds at the
+//! centre compared to a hard pan.
+
+use std::f32::consts::{FRAC_PI_2, SQRT_2};
+
+use serde::{Deserialize, Serialize};
+
+/// Gain taper applied when panning a track between the left and right
+/// channels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum PanLaw {
+    /// Linear balance: the centre leaves both channels at unity and panning
+    /// only attenuates the opposite channel. Hard pans sound quieter than
+    /// the centre.
+    #[default]
+    #[serde(rename = "balance")]
+    Balance,
+    /// Sine/cosine taper with the centre at −3 dB; constant acoustic power
+    /// across the pan range.
+    #[serde(rename = "-3db")]
+    Minus3Db,
+    /// Compromise between the −3 dB and −6 dB laws, with the centre at
+    /// −4.5 dB.
+    #[serde(rename = "-4.5db")]
+    Minus4Point5Db,
+    /// Linear crossfade with the centre at −6 dB; constant amplitude, suited
+    /// to mono-compatible mixes.
+    #[serde(rename = "-6db")]
+    Minus6Db,
+    /// Constant power normalised so the centre stays at unity; hard pans are
+    /// 3 dB louder than with [`PanLaw::Minus3Db`].
+    #[serde(rename = "equal_power")]
+    EqualPower,
+}
+
+impl PanLaw {
+    /// Left and right gains for `pan` in `-1.0..=1.0` (full left to full right).
+    ///
+    /// Out-of-range values are clamped; non-finite values pan to the centre.
+    pub fn gains(self, pan: f32) -> (f32, f32) {
+        let pan = if pan.is_finite() {
+            pan.clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        let position = (pan + 1.0) * 0.5;
+        let angle = position * FRAC_PI_2;
+        match self {
+            Self::Balance => ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0)),
+            Self::Minus3Db => (angle.cos(), angle.sin()),
+            Self::Minus4Point5Db => (
+                ((1.0 - position) * angle.cos()).sqrt(),
+                (position * angle.sin()).sqrt(),
+            ),
+            Self::Minus6Db => (1.0 - position, position),
+            Self::EqualPower => (SQRT_2 * angle.cos(), SQRT_2 * angle.sin()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn db(gain: f32) -> f32 {
+        20.0 * gain.log10()
+    }
+
+    #[test]
+    fn centre_attenuation_matches_each_law() {
+        let cases = [
+            (PanLaw::Balance, 0.0),
+            (PanLaw::Minus3Db, -3.01),
+            (PanLaw::Minus4Point5Db, -4.52),
+            (PanLaw::Minus6Db, -6.02),
+            (PanLaw::EqualPower, 0.0),
+        ];
+        for (law, expected_db) in cases {
+            let (left, right) = law.gains(0.0);
+            assert!((left - right).abs() < 1e-6, "{:?}", law);
+            assert!((db(left) - expected_db).abs() < 0.01, "{:?}", law);
+        }
+    }
+
+    #[test]
+    fn hard_pans_silence_the_opposite_channel() {
+        for law in [
+            PanLaw::Balance,
+            PanLaw::Minus3Db,
+            PanLaw::Minus4Point5Db,
+            PanLaw::Minus6Db,
+            PanLaw::EqualPower,
+        ] {
+            let (left, right) = law.gains(-1.0);
+            assert!(left > 0.99 && right.abs() < 1e-6, "{:?}", law);
+            let (left, right) = law.gains(1.0);
+            assert!(right > 0.99 && left.abs() < 1e-6, "{:?}", law);
+        }
+    }
+
+    #[test]
+    fn constant_power_laws_keep_power_across_the_range() {
+        for pan in [-0.8_f32, -0.3, 0.0, 0.45, 0.9] {
+            let (left, right) = PanLaw::Minus3Db.gains(pan);
+            assert!((left * left + right * right - 1.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn laws_round_trip_through_json() {
+        let law: PanLaw = serde_json::from_str("\"-4.5db\"").unwrap();
+        assert_eq!(law, PanLaw::Minus4Point5Db);
+        assert_eq!(
+            serde_json::to_string(&PanLaw::EqualPower).unwrap(),
+            "\"equal_power\""
+        );
+    }
+}
@@ -0,0 +1,79 @@
+//! Working precision of recursive DSP state and the mix bus.
+//!
+//! Samples travel between effects as `f32`, but state that feeds back into
+//! itself — biquad histories, reverb and echo feedback lines, convolution
+//! overlap-add accumulators, and the bus the tracks are summed into — can
+//! drift audibly at single precision over long tails and high feedback. Those
+//! paths store [`DspFloat`], which is `f64` when the `f64-dsp` feature is
+//! enabled and `f32` otherwise, and round back to `f32` only where a sample
+//! leaves them.
+
+/// Floating-point type used for accumulating DSP state.
+#[cfg(feature = "f64-dsp")]
+pub type DspFloat = f64;
+/// Floating-point type used for accumulating DSP state.
+#[cfg(not(feature = "f64-dsp"))]
+pub type DspFloat = f32;
+
+/// Lift an `f32` sample into working precision.
+#[cfg(feature = "f64-dsp")]
+#[inline]
+pub(crate) fn widen(sample: f32) -> DspFloat {
+    f64::from(sample)
+}
+
+/// Lift an `f32` sample into working precision.
+#[cfg(not(feature = "f64-dsp"))]
+#[inline]
+pub(crate) fn widen(sample: f32) -> DspFloat {
+    sample
+}
+
+/// Round a working-precision value back to an `f32` sample.
+#[cfg(feature = "f64-dsp")]
+#[inline]
+pub(crate) fn narrow(value: DspFloat) -> f32 {
+    value as f32
+}
+
+/// Round a working-precision value back to an `f32` sample.
+#[cfg(not(feature = "f64-dsp"))]
+#[inline]
+pub(crate) fn narrow(value: DspFloat) -> f32 {
+    value
+}
+
+/// Convert a value computed in `f64` (e.g. a filter coefficient) to working
+/// precision.
+#[cfg(feature = "f64-dsp")]
+#[inline]
+pub(crate) fn from_f64(value: f64) -> DspFloat {
+    value
+}
+
+/// Convert a value computed in `f64` (e.g. a filter coefficient) to working
+/// precision.
+#[cfg(not(feature = "f64-dsp"))]
+#[inline]
+pub(crate) fn from_f64(value: f64) -> DspFloat {
+    value as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_preserves_f32_samples() {
+        for sample in [0.0_f32, 1.0, -0.25, 1.0e-7, f32::MAX] {
+            assert_eq!(narrow(widen(sample)), sample);
+        }
+    }
+
+    #[test]
+    fn working_precision_matches_the_feature() {
+        let bits = std::mem::size_of::<DspFloat>() * 8;
+        assert_eq!(bits, if cfg!(feature = "f64-dsp") { 64 } else { 32 });
+        assert_eq!(narrow(from_f64(0.5)), 0.5);
+    }
+}
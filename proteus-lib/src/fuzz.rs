@@ -0,0 +1,96 @@
+//! Fuzzing entry points for container and peaks parsing (feature `fuzz`).
+//!
+//! Each function takes raw or [`arbitrary`]-generated input, runs one parser
+//! end to end, and discards the result. Malformed input must surface as an
+//! error inside the parser; any panic is a bug. The cargo-fuzz targets in
+//! `proteus-lib/fuzz` are thin wrappers around these functions.
+
+use std::io::Cursor;
+
+use arbitrary::Arbitrary;
+
+use crate::container::attachments::read_attachments;
+use crate::container::prot_settings::{derive_runtime_settings, parse_play_settings};
+use crate::peaks::{get_peaks_from_reader, GetPeaksOptions};
+
+/// Name of the play-settings attachment inside `.prot` containers.
+const PLAY_SETTINGS_ATTACHMENT: &str = "play_settings.json";
+
+/// Decode a `play_settings.json` payload and derive its runtime settings.
+pub fn play_settings(data: &[u8]) {
+    if let Ok(play_settings) = parse_play_settings(data) {
+        let _ = derive_runtime_settings(&play_settings);
+    }
+}
+
+/// Walk the attachments of a Matroska stream and decode any play settings found.
+pub fn container_attachments(data: &[u8]) {
+    let Ok(attachments) = read_attachments(&mut Cursor::new(data)) else {
+        return;
+    };
+    for attachment in attachments {
+        if attachment.name == PLAY_SETTINGS_ATTACHMENT {
+            play_settings(&attachment.data);
+        }
+    }
+}
+
+/// Peaks file bytes plus the query options to read them with.
+///
+/// Counts are narrow integers so generated queries stay within sizes a real
+/// caller would request.
+#[derive(Debug, Clone, Arbitrary)]
+pub struct PeaksInput {
+    /// Raw peaks file bytes.
+    pub data: Vec<u8>,
+    /// Query start in seconds.
+    pub start_seconds: Option<f64>,
+    /// Query end in seconds.
+    pub end_seconds: Option<f64>,
+    /// Requested number of output peaks.
+    pub target_peaks: Option<u16>,
+    /// Requested channel count.
+    pub channels: Option<u8>,
+}
+
+/// Read a peaks file from memory with the given query options.
+pub fn peaks(input: &PeaksInput) {
+    let options = GetPeaksOptions {
+        start_seconds: input.start_seconds,
+        end_seconds: input.end_seconds,
+        target_peaks: input.target_peaks.map(usize::from),
+        channels: input.channels.map(usize::from),
+    };
+    let _ = get_peaks_from_reader(&mut Cursor::new(&input.data), options);
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::Unstructured;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn entry_points_survive_random_input() {
+        let mut rng = StdRng::seed_from_u64(0xF022);
+        for _ in 0..500 {
+            let len = rng.gen_range(0..512);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            play_settings(&bytes);
+            container_attachments(&bytes);
+            if let Ok(input) = PeaksInput::arbitrary(&mut Unstructured::new(&bytes)) {
+                peaks(&input);
+            }
+        }
+    }
+
+    #[test]
+    fn play_settings_accepts_truncated_json() {
+        let json = br#"{"encoder_version":"3","play_settings":{"effects":[],"tracks":[]}}"#;
+        for end in 0..json.len() {
+            play_settings(&json[..end]);
+        }
+    }
+}
@@ -8,16 +8,25 @@
 //! - `playback`: real-time mixing engine and a high-level [`playback::player::Player`].
 //! - `dsp`: convolution and impulse response utilities for reverb.
 //! - `diagnostics`: optional benchmarks and metrics reporting.
+//! - `fuzz`: parser entry points for fuzzing (feature `fuzz`).
+
+// wgpu's handle types nest deeply enough that auto-trait checks on types
+// holding effects overflow the default limit.
+#![cfg_attr(feature = "gpu", recursion_limit = "256")]
 
 pub mod audio;
 pub mod container;
 pub mod diagnostics;
 pub mod dsp;
+#[cfg(feature = "fuzz")]
+pub mod fuzz;
 pub(crate) mod logging;
 pub mod peaks;
 pub mod playback;
 #[cfg(test)]
 mod test_data;
+#[cfg(test)]
+mod test_fixtures;
 pub mod tools;
 mod track;
 
@@ -9,6 +9,8 @@ pub enum PeaksError {
     Decode(String),
     /// The peaks file header or data did not match the expected binary format.
     InvalidFormat(String),
+    /// The operation was cancelled through its [`CancellationToken`](crate::tools::progress::CancellationToken).
+    Cancelled,
 }
 
 impl Display for PeaksError {
@@ -17,6 +19,7 @@ impl Display for PeaksError {
             Self::Io(err) => write!(f, "io error: {}", err),
             Self::Decode(err) => write!(f, "decode error: {}", err),
             Self::InvalidFormat(err) => write!(f, "invalid peaks format: {}", err),
+            Self::Cancelled => write!(f, "cancelled"),
         }
     }
 }
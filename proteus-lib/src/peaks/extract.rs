@@ -4,6 +4,7 @@ use symphonia::core::errors::Error;
 
 use crate::audio::decode::for_each_channel_sample;
 use crate::tools::decode::open_file;
+use crate::tools::progress::Progress;
 
 use super::{PeakWindow, PeaksData, PeaksError};
 
@@ -59,6 +60,7 @@ impl ChannelAccumulator {
 pub(super) fn extract_peaks_from_audio(
     file_path: &str,
     limited: bool,
+    progress: &mut Progress<'_>,
 ) -> Result<PeaksData, PeaksError> {
     let (mut decoder, mut format) =
         open_file(file_path).map_err(|err| PeaksError::Decode(err.to_string()))?;
@@ -86,6 +88,7 @@ pub(super) fn extract_peaks_from_audio(
     };
 
     let track_id = track.id;
+    let total_frames = track.codec_params.n_frames.unwrap_or(0);
     let mut accumulators = (0..channels)
         .map(|_| ChannelAccumulator::new())
         .collect::<Vec<_>>();
@@ -105,6 +108,10 @@ pub(super) fn extract_peaks_from_audio(
         if packet.track_id() != track_id {
             continue;
         }
+        if progress.is_cancelled() {
+            return Err(PeaksError::Cancelled);
+        }
+        progress.report_ratio(packet.ts(), total_frames);
 
         match decoder.decode(&packet) {
             Ok(decoded) => {
@@ -133,6 +140,7 @@ pub(super) fn extract_peaks_from_audio(
         })
         .collect();
 
+    progress.report(1.0);
     Ok(PeaksData {
         sample_rate,
         window_size: window_size as u32,
@@ -75,6 +75,17 @@ pub(super) fn read_peaks_by_indices<R: Read + Seek>(
         .data_offset
         .checked_add(start_peak.saturating_mul(bytes_per_peak))
         .ok_or_else(|| PeaksError::InvalidFormat("computed start offset overflow".to_string()))?;
+    let end_offset = end_peak
+        .checked_mul(bytes_per_peak)
+        .and_then(|len| header.data_offset.checked_add(len))
+        .ok_or_else(|| PeaksError::InvalidFormat("computed end offset overflow".to_string()))?;
+    // Check the header against the real stream length before sizing buffers
+    // from it, so a corrupted peak count cannot trigger a huge allocation.
+    if end_offset > reader.seek(SeekFrom::End(0))? {
+        return Err(PeaksError::InvalidFormat(
+            "peak data extends past end of file".to_string(),
+        ));
+    }
     reader.seek(SeekFrom::Start(start_offset))?;
 
     let mut channel_data = vec![Vec::with_capacity(samples_len); channels];
@@ -12,7 +12,7 @@ mod query;
 mod resample;
 
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Read, Seek};
 
 use super::{GetPeaksOptions, PeaksData, PeaksError};
 
@@ -26,6 +26,13 @@ pub(super) use io::write_peaks_file;
 pub(super) fn read_peaks_with_options(
     path: &str,
     options: &GetPeaksOptions,
+) -> Result<PeaksData, PeaksError> {
+    read_peaks_from_reader(&mut BufReader::new(File::open(path)?), options)
+}
+
+pub(super) fn read_peaks_from_reader<R: Read + Seek>(
+    reader: &mut R,
+    options: &GetPeaksOptions,
 ) -> Result<PeaksData, PeaksError> {
     if options.target_peaks == Some(0) {
         return Err(PeaksError::InvalidFormat(
@@ -39,13 +46,12 @@ pub(super) fn read_peaks_with_options(
         ));
     }
 
-    let mut reader = BufReader::new(File::open(path)?);
-    let header = read_header(&mut reader)?;
+    let header = read_header(reader)?;
     let (requested_start_sample, requested_end_sample) =
         compute_requested_sample_range(&header, options.start_seconds, options.end_seconds)?;
     let (start_peak, end_peak) =
         compute_peak_range(&header, requested_start_sample, requested_end_sample);
-    let mut peaks = read_peaks_by_indices(&mut reader, &header, start_peak, end_peak)?;
+    let mut peaks = read_peaks_by_indices(reader, &header, start_peak, end_peak)?;
 
     if let Some(requested_channels) = options.channels {
         peaks
@@ -1,4 +1,4 @@
-use super::super::{GetPeaksOptions, PeakWindow, PeaksData};
+use super::super::{GetPeaksOptions, PeakWindow, PeaksData, PeaksError};
 use super::io::write_peaks_file;
 use super::read_peaks_with_options;
 
@@ -245,3 +245,26 @@ fn zero_pads_when_requested_range_is_beyond_audio() {
 
     let _ = std::fs::remove_file(path);
 }
+
+#[test]
+fn rejects_peak_count_past_end_of_stream() {
+    use super::header::{write_header, Header, HEADER_SIZE};
+    use super::read_peaks_from_reader;
+
+    let mut bytes = Vec::new();
+    let header = Header {
+        channels: 2,
+        sample_rate: 48_000,
+        window_size: 512,
+        peak_count: u64::MAX / 64,
+        data_offset: HEADER_SIZE,
+    };
+    write_header(&mut bytes, &header).expect("header");
+    bytes.extend_from_slice(&[0; 32]);
+
+    let result = read_peaks_from_reader(
+        &mut std::io::Cursor::new(bytes),
+        &GetPeaksOptions::default(),
+    );
+    assert!(matches!(result, Err(PeaksError::InvalidFormat(_))));
+}
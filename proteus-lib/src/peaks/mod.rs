@@ -4,6 +4,10 @@ mod error;
 mod extract;
 mod format;
 
+use std::io::{Read, Seek};
+
+use crate::tools::progress::Progress;
+
 pub use error::PeaksError;
 
 /// A single peak window with maximum and minimum sample amplitude.
@@ -54,7 +58,20 @@ pub struct GetPeaksOptions {
 /// # Errors
 /// Returns an error if audio decode fails or if writing the peaks file fails.
 pub fn write_peaks(input_audio_file: &str, output_peaks_file: &str) -> Result<(), PeaksError> {
-    let peaks = extract::extract_peaks_from_audio(input_audio_file, false)?;
+    write_peaks_with_progress(input_audio_file, output_peaks_file, &mut Progress::new())
+}
+
+/// Like [`write_peaks`], reporting decode progress and honoring cancellation.
+///
+/// # Errors
+/// Returns [`PeaksError::Cancelled`] when cancelled before the file is
+/// written, otherwise the same errors as [`write_peaks`].
+pub fn write_peaks_with_progress(
+    input_audio_file: &str,
+    output_peaks_file: &str,
+    progress: &mut Progress<'_>,
+) -> Result<(), PeaksError> {
+    let peaks = extract::extract_peaks_from_audio(input_audio_file, false, progress)?;
     format::write_peaks_file(output_peaks_file, &peaks)
 }
 
@@ -73,6 +90,20 @@ pub fn get_peaks(peaks_file: &str, options: GetPeaksOptions) -> Result<PeaksData
     format::read_peaks_with_options(peaks_file, &options)
 }
 
+/// Read peaks from any seekable source holding binary peaks data.
+///
+/// Behaves like [`get_peaks`] for in-memory buffers or other non-file
+/// sources. Malformed input is reported as an error and never panics.
+///
+/// # Errors
+/// Returns an error if reading fails or the data has an invalid peaks format.
+pub fn get_peaks_from_reader<R: Read + Seek>(
+    reader: &mut R,
+    options: GetPeaksOptions,
+) -> Result<PeaksData, PeaksError> {
+    format::read_peaks_from_reader(reader, &options)
+}
+
 /// Read all channels and all peaks from a binary peaks file.
 ///
 /// # Arguments
@@ -126,7 +157,23 @@ pub fn get_peaks_in_range(
 /// # Errors
 /// Returns an error if decoding fails.
 pub fn extract_peaks_from_audio(file_path: &str, limited: bool) -> Result<PeaksData, PeaksError> {
-    extract::extract_peaks_from_audio(file_path, limited)
+    extract::extract_peaks_from_audio(file_path, limited, &mut Progress::new())
+}
+
+/// Like [`extract_peaks_from_audio`], reporting decode progress.
+///
+/// Progress is the decoded fraction of the first track; files without a
+/// frame count report only completion.
+///
+/// # Errors
+/// Returns [`PeaksError::Cancelled`] once `progress` is cancelled, otherwise
+/// the same errors as [`extract_peaks_from_audio`].
+pub fn extract_peaks_from_audio_with_progress(
+    file_path: &str,
+    limited: bool,
+    progress: &mut Progress<'_>,
+) -> Result<PeaksData, PeaksError> {
+    extract::extract_peaks_from_audio(file_path, limited, progress)
 }
 
 #[cfg(test)]
@@ -139,6 +186,24 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn peak_extraction_reports_progress_and_cancels() {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_audio/test-16bit.wav");
+        let mut fractions = Vec::new();
+        {
+            let mut progress = Progress::new().with_callback(|fraction| fractions.push(fraction));
+            extract_peaks_from_audio_with_progress(path, true, &mut progress).unwrap();
+        }
+        assert!(fractions.len() > 2);
+        assert_eq!(fractions.last(), Some(&1.0));
+
+        let token = crate::tools::progress::CancellationToken::new();
+        token.cancel();
+        let mut progress = Progress::new().with_cancellation(token);
+        let result = extract_peaks_from_audio_with_progress(path, true, &mut progress);
+        assert!(matches!(result, Err(PeaksError::Cancelled)));
+    }
+
     #[test]
     fn get_all_peaks_uses_default_options() {
         let result = get_all_peaks("/definitely/missing.peaks");
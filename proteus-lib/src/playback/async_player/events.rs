@@ -0,0 +1,142 @@
+//! Player state changes delivered as an async [`Stream`].
+
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+use std::time::Duration;
+
+use futures_core::Stream;
+use log::warn;
+
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::player::{Player, PlayerState};
+
+/// Change observed on a watched [`Player`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerEvent {
+    /// Transport state changed; the first event carries the initial state.
+    StateChanged(PlayerState),
+    /// Playback position advanced or jumped, in seconds.
+    Position(f64),
+    /// The playback thread exited after running.
+    Finished,
+}
+
+#[derive(Default)]
+struct EventQueue {
+    events: VecDeque<PlayerEvent>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl EventQueue {
+    fn push(&mut self, event: PlayerEvent) {
+        // A slow consumer only needs the latest position.
+        if let (PlayerEvent::Position(seconds), Some(PlayerEvent::Position(last))) =
+            (event, self.events.back_mut())
+        {
+            *last = seconds;
+            return;
+        }
+        self.events.push_back(event);
+    }
+}
+
+/// Stream of [`PlayerEvent`]s sampled from a player on a watcher thread.
+///
+/// The watcher holds a player handle, so the player keeps running until the
+/// stream is dropped. The stream never ends on its own.
+pub struct PlayerEvents {
+    queue: Arc<Mutex<EventQueue>>,
+}
+
+impl PlayerEvents {
+    /// Start sampling `player` every `interval`.
+    pub(super) fn watch(player: Player, interval: Duration) -> Self {
+        let queue = Arc::new(Mutex::new(EventQueue::default()));
+        let shared = queue.clone();
+        let spawned = thread::Builder::new()
+            .name("proteus-player-events".to_string())
+            .spawn(move || run_watcher(&player, &shared, interval));
+        if let Err(err) = spawned {
+            warn!("failed to spawn player event watcher: {}", err);
+        }
+        Self { queue }
+    }
+}
+
+impl Stream for PlayerEvents {
+    type Item = PlayerEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<PlayerEvent>> {
+        let mut queue = lock_queue(&self.queue);
+        match queue.events.pop_front() {
+            Some(event) => Poll::Ready(Some(event)),
+            None => {
+                queue.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+impl Drop for PlayerEvents {
+    fn drop(&mut self) {
+        lock_queue(&self.queue).closed = true;
+    }
+}
+
+fn run_watcher(player: &Player, queue: &Mutex<EventQueue>, interval: Duration) {
+    let mut last_state = None;
+    let mut last_position = None;
+    let mut was_running = false;
+    loop {
+        let state = player.get_state();
+        let position = player.get_time();
+        let running = !player.is_finished();
+
+        let mut events = Vec::new();
+        if last_state != Some(state) {
+            events.push(PlayerEvent::StateChanged(state));
+            last_state = Some(state);
+        }
+        if last_position != Some(position) {
+            events.push(PlayerEvent::Position(position));
+            last_position = Some(position);
+        }
+        if was_running && !running {
+            events.push(PlayerEvent::Finished);
+        }
+        was_running = running;
+
+        let waker = {
+            let mut queue = lock_queue(queue);
+            if queue.closed {
+                return;
+            }
+            for event in events.iter().copied() {
+                queue.push(event);
+            }
+            if events.is_empty() {
+                None
+            } else {
+                queue.waker.take()
+            }
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+        thread::sleep(interval);
+    }
+}
+
+/// Recoverable poison policy: queued events are disposable notifications.
+fn lock_queue(queue: &Mutex<EventQueue>) -> MutexGuard<'_, EventQueue> {
+    lock_recoverable(
+        queue,
+        "player event queue",
+        "queued events are disposable notifications",
+    )
+}
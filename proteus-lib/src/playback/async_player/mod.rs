@@ -0,0 +1,182 @@
+//! Async facade over [`Player`] (feature `async`).
+//!
+//! [`Player`] transport calls block while the runtime restarts or waits for
+//! audio. [`AsyncPlayer`] runs those calls on helper threads and returns
+//! futures that any executor can await, and exposes player state as a
+//! [`futures_core::Stream`] of [`PlayerEvent`]s. Non-blocking calls such as
+//! `pause` or `set_volume` go through [`AsyncPlayer::player`].
+
+mod events;
+mod task;
+
+use std::time::Duration;
+
+pub use events::{PlayerEvent, PlayerEvents};
+pub use task::BlockingTask;
+
+use crate::playback::player::Player;
+
+/// Async wrapper around a shared [`Player`] handle.
+///
+/// Cloning yields another handle to the same player.
+#[derive(Clone)]
+pub struct AsyncPlayer {
+    player: Player,
+}
+
+impl AsyncPlayer {
+    /// Wrap `player`.
+    pub fn new(player: Player) -> Self {
+        Self { player }
+    }
+
+    /// Borrow the wrapped player for non-blocking calls.
+    pub fn player(&self) -> &Player {
+        &self.player
+    }
+
+    /// Return the wrapped player.
+    pub fn into_inner(self) -> Player {
+        self.player
+    }
+
+    /// Start playback from the current position; resolves once audio starts.
+    pub fn play(&self) -> BlockingTask<()> {
+        let mut player = self.player.clone();
+        BlockingTask::spawn("proteus-async-play", move || player.play())
+    }
+
+    /// Start playback from `ts` seconds; resolves once audio starts.
+    pub fn play_at(&self, ts: f64) -> BlockingTask<()> {
+        let mut player = self.player.clone();
+        BlockingTask::spawn("proteus-async-play", move || player.play_at(ts))
+    }
+
+    /// Seek to `ts` seconds; resolves once the runtime has restarted.
+    pub fn seek(&self, ts: f64) -> BlockingTask<()> {
+        let mut player = self.player.clone();
+        BlockingTask::spawn("proteus-async-seek", move || player.seek(ts))
+    }
+
+    /// Stop playback; resolves once the playback thread has exited.
+    pub fn stop(&self) -> BlockingTask<()> {
+        let player = self.player.clone();
+        BlockingTask::spawn("proteus-async-stop", move || player.stop())
+    }
+
+    /// Resolve once no playback thread is running.
+    pub fn wait_until_finished(&self) -> BlockingTask<()> {
+        let player = self.player.clone();
+        BlockingTask::spawn("proteus-async-wait", move || player.sleep_until_end())
+    }
+
+    /// Stream state, position, and end-of-playback changes.
+    ///
+    /// The player is sampled every `interval`; position updates coalesce
+    /// while the consumer lags.
+    pub fn events(&self, interval: Duration) -> PlayerEvents {
+        PlayerEvents::watch(self.player.clone(), interval)
+    }
+}
+
+impl From<Player> for AsyncPlayer {
+    fn from(player: Player) -> Self {
+        Self::new(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::Future;
+    use std::pin::{pin, Pin};
+    use std::sync::Arc;
+    use std::task::{Context, Poll, Wake, Waker};
+    use std::thread::{self, Thread};
+
+    use futures_core::Stream;
+
+    use super::*;
+    use crate::container::prot::PathsTrack;
+    use crate::playback::player::{PlayerInitOptions, PlayerSource, PlayerState};
+    use crate::test_fixtures::DEMO_CONTAINER;
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut context = Context::from_waker(&waker);
+        let mut future = pin!(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    fn next_event(events: &mut PlayerEvents) -> PlayerEvent {
+        block_on(std::future::poll_fn(|cx| {
+            Pin::new(&mut *events).poll_next(cx)
+        }))
+        .expect("event stream does not end")
+    }
+
+    fn idle_player() -> AsyncPlayer {
+        let source = PlayerSource::FilePaths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        Player::try_from_source_with_options(source, PlayerInitOptions::default())
+            .unwrap()
+            .into()
+    }
+
+    #[test]
+    fn blocking_task_resolves_with_the_helper_thread_result() {
+        let task = BlockingTask::spawn("test", || {
+            thread::sleep(Duration::from_millis(10));
+            7
+        });
+        assert_eq!(block_on(task), 7);
+    }
+
+    #[test]
+    fn blocking_task_resumes_panics_in_the_awaiting_task() {
+        let task = BlockingTask::spawn("test", || -> u8 { panic!("boom") });
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| block_on(task)));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn idle_player_reports_initial_state_and_is_finished() {
+        let player = idle_player();
+        block_on(player.wait_until_finished());
+
+        let mut events = player.events(Duration::from_millis(5));
+        assert_eq!(
+            next_event(&mut events),
+            PlayerEvent::StateChanged(PlayerState::Stopped)
+        );
+        assert_eq!(next_event(&mut events), PlayerEvent::Position(0.0));
+    }
+
+    #[test]
+    fn seek_resolves_and_reports_the_new_position() {
+        let player = AsyncPlayer::new(Player::new(DEMO_CONTAINER));
+        let mut events = player.events(Duration::from_millis(5));
+        assert_eq!(
+            next_event(&mut events),
+            PlayerEvent::StateChanged(PlayerState::Stopped)
+        );
+        assert_eq!(next_event(&mut events), PlayerEvent::Position(0.0));
+
+        block_on(player.seek(2.0));
+        assert_eq!(next_event(&mut events), PlayerEvent::Position(2.0));
+        block_on(player.stop());
+    }
+}
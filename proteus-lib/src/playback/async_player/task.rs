@@ -0,0 +1,83 @@
+//! Blocking player calls run on helper threads and awaited as futures.
+
+use std::future::Future;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+use crate::playback::mutex_policy::lock_recoverable;
+
+type TaskResult<T> = thread::Result<T>;
+
+struct TaskState<T> {
+    result: Option<TaskResult<T>>,
+    waker: Option<Waker>,
+}
+
+/// Future resolving once a blocking call on a helper thread returns.
+///
+/// Works with any executor: the helper thread wakes the awaiting task
+/// directly. A panic in the blocking call is resumed in the awaiting task.
+pub struct BlockingTask<T> {
+    state: Arc<Mutex<TaskState<T>>>,
+}
+
+impl<T: Send + 'static> BlockingTask<T> {
+    /// Run `work` on a new thread named `name`.
+    pub(super) fn spawn(name: &str, work: impl FnOnce() -> T + Send + 'static) -> Self {
+        let state = Arc::new(Mutex::new(TaskState {
+            result: None,
+            waker: None,
+        }));
+        let shared = state.clone();
+        let complete = move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(work));
+            let waker = {
+                let mut state = lock_task(&shared);
+                state.result = Some(result);
+                state.waker.take()
+            };
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        };
+        if let Err(err) = thread::Builder::new()
+            .name(name.to_string())
+            .spawn(complete)
+        {
+            let message = format!("failed to spawn {} thread: {}", name, err);
+            lock_task(&state).result = Some(Err(Box::new(message)));
+        }
+        Self { state }
+    }
+}
+
+impl<T> Future for BlockingTask<T> {
+    type Output = T;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<T> {
+        let mut state = lock_task(&self.state);
+        match state.result.take() {
+            Some(Ok(value)) => Poll::Ready(value),
+            Some(Err(payload)) => {
+                drop(state);
+                panic::resume_unwind(payload)
+            }
+            None => {
+                state.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// Recoverable poison policy: the slot only hands one result to the awaiting task.
+fn lock_task<T>(state: &Mutex<TaskState<T>>) -> MutexGuard<'_, TaskState<T>> {
+    lock_recoverable(
+        state,
+        "async player task",
+        "the task slot only hands one result to the awaiting task",
+    )
+}
@@ -0,0 +1,199 @@
+//! Synchronization of playback to an external clock.
+//!
+//! A player synced to a [`ClockSource`] compares its position with the
+//! clock's on every appended chunk and nudges its output rate (varispeed)
+//! to close the gap, like a tape machine chasing timecode. Corrections are
+//! limited to [`MAX_VARISPEED`], small enough to stay inaudible; a gap
+//! beyond [`RELOCATE_THRESHOLD_SECONDS`] is closed by seeking instead.
+
+mod timecode;
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+pub use timecode::{FrameRate, MtcDecoder, Timecode, TimecodeInput};
+
+/// Largest rate deviation applied while chasing the clock (0.5%).
+pub const MAX_VARISPEED: f64 = 0.005;
+/// Offset, in seconds, beyond which the player relocates instead of chasing.
+pub const RELOCATE_THRESHOLD_SECONDS: f64 = 0.5;
+/// Offset, in seconds, within which the player counts as locked.
+const LOCK_WINDOW_SECONDS: f64 = 0.01;
+/// Rate correction per second of offset.
+const CHASE_GAIN: f64 = 0.1;
+/// Smoothing applied to offset readings, which jitter with the clock's
+/// update granularity.
+const OFFSET_SMOOTHING: f64 = 0.2;
+
+/// Sample counter advanced by the host, e.g. from its own audio callback.
+///
+/// Clones share the same counter.
+#[derive(Debug, Clone)]
+pub struct SampleClock {
+    frames: Arc<AtomicU64>,
+    sample_rate: u32,
+}
+
+impl SampleClock {
+    /// Create a clock counting frames at `sample_rate`, starting at zero.
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            frames: Arc::new(AtomicU64::new(0)),
+            sample_rate: sample_rate.max(1),
+        }
+    }
+
+    /// Advance the clock by `frames`.
+    pub fn advance(&self, frames: u64) {
+        self.frames.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// Set the clock to an absolute frame position.
+    pub fn set_frames(&self, frames: u64) {
+        self.frames.store(frames, Ordering::Relaxed);
+    }
+
+    /// Current clock position in seconds.
+    pub fn seconds(&self) -> f64 {
+        self.frames.load(Ordering::Relaxed) as f64 / f64::from(self.sample_rate)
+    }
+}
+
+/// External timeline a player can be slaved to.
+#[derive(Debug, Clone)]
+pub enum ClockSource {
+    /// Host-provided sample clock.
+    Samples(SampleClock),
+    /// SMPTE or MIDI timecode.
+    Timecode(TimecodeInput),
+}
+
+impl ClockSource {
+    /// Current clock position in seconds, or `None` while the clock is not
+    /// running.
+    pub fn seconds(&self) -> Option<f64> {
+        match self {
+            Self::Samples(clock) => Some(clock.seconds()),
+            Self::Timecode(input) => input.seconds(),
+        }
+    }
+}
+
+/// Snapshot of an active clock sync.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClockSyncStatus {
+    /// Smoothed clock position minus player position, in seconds; positive
+    /// when the player is behind.
+    pub offset_seconds: f64,
+    /// Output rate applied to the player, `1.0` at nominal speed.
+    pub rate: f64,
+    /// `true` while the offset is within the lock window.
+    pub locked: bool,
+    /// `true` while the clock is readable.
+    pub clock_running: bool,
+}
+
+/// Varispeed controller chasing a [`ClockSource`].
+#[derive(Debug, Clone)]
+pub(crate) struct ClockSync {
+    source: ClockSource,
+    offset: Option<f64>,
+    rate: f64,
+}
+
+impl ClockSync {
+    pub(crate) fn new(source: ClockSource) -> Self {
+        Self {
+            source,
+            offset: None,
+            rate: 1.0,
+        }
+    }
+
+    /// Clock being chased.
+    pub(crate) fn source(&self) -> &ClockSource {
+        &self.source
+    }
+
+    /// Compare the clock with the player's `position` and return the output
+    /// rate to apply next.
+    ///
+    /// The rate returns to nominal while the clock is stopped or the gap is
+    /// too large to chase.
+    pub(crate) fn update(&mut self, position: f64) -> f64 {
+        let Some(clock) = self.source.seconds() else {
+            self.offset = None;
+            self.rate = 1.0;
+            return self.rate;
+        };
+        let raw = clock - position;
+        let offset = match self.offset {
+            Some(previous) if (raw - previous).abs() < RELOCATE_THRESHOLD_SECONDS => {
+                previous + (raw - previous) * OFFSET_SMOOTHING
+            }
+            _ => raw,
+        };
+        self.offset = Some(offset);
+        self.rate = if offset.abs() > RELOCATE_THRESHOLD_SECONDS {
+            1.0
+        } else {
+            1.0 + (offset * CHASE_GAIN).clamp(-MAX_VARISPEED, MAX_VARISPEED)
+        };
+        self.rate
+    }
+
+    /// Forget the smoothed offset, e.g. after the player relocates.
+    pub(crate) fn reset(&mut self) {
+        self.offset = None;
+        self.rate = 1.0;
+    }
+
+    pub(crate) fn status(&self) -> ClockSyncStatus {
+        let offset_seconds = self.offset.unwrap_or(0.0);
+        ClockSyncStatus {
+            offset_seconds,
+            rate: self.rate,
+            locked: self.offset.is_some() && offset_seconds.abs() <= LOCK_WINDOW_SECONDS,
+            clock_running: self.offset.is_some(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn controller_speeds_up_a_lagging_player_and_locks() {
+        let clock = SampleClock::new(1000);
+        let mut sync = ClockSync::new(ClockSource::Samples(clock.clone()));
+        // The player starts 100 ms behind and advances 20 ms per chunk at
+        // the rate the controller asks for.
+        let mut position = -0.1;
+        let mut first_rate = None;
+        for _ in 0..5_000 {
+            clock.advance(20);
+            let rate = sync.update(position);
+            first_rate.get_or_insert(rate);
+            position += 0.02 * rate;
+        }
+        assert!(first_rate.unwrap() > 1.0);
+        assert!(first_rate.unwrap() <= 1.0 + MAX_VARISPEED);
+        let status = sync.status();
+        assert!(status.locked, "{:?}", status);
+        assert!((status.rate - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn large_offsets_and_stopped_clocks_play_at_nominal_rate() {
+        let clock = SampleClock::new(48_000);
+        clock.set_frames(48_000 * 10);
+        let mut sync = ClockSync::new(ClockSource::Samples(clock));
+        assert_eq!(sync.update(0.0), 1.0);
+        assert!(!sync.status().locked);
+
+        let mut stopped = ClockSync::new(ClockSource::Timecode(TimecodeInput::new()));
+        assert_eq!(stopped.update(0.0), 1.0);
+        assert!(!stopped.status().clock_running);
+    }
+}
@@ -0,0 +1,276 @@
+//! SMPTE timecode and MIDI timecode (MTC) decoding.
+
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::playback::mutex_policy::lock_recoverable;
+
+/// Longest a timecode reading is extrapolated before the clock counts as
+/// stopped, in seconds.
+const TIMECODE_FREEWHEEL_SECONDS: f64 = 0.5;
+
+/// SMPTE frame rate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FrameRate {
+    /// 24 frames per second (film).
+    Fps24,
+    /// 25 frames per second (PAL).
+    Fps25,
+    /// 29.97 frames per second, drop-frame (NTSC).
+    Fps2997Drop,
+    /// 30 frames per second, non-drop.
+    Fps30,
+}
+
+impl FrameRate {
+    /// Nominal frames counted per timecode second.
+    pub fn nominal_fps(self) -> u32 {
+        match self {
+            Self::Fps24 => 24,
+            Self::Fps25 => 25,
+            Self::Fps2997Drop | Self::Fps30 => 30,
+        }
+    }
+
+    /// Real frames per second.
+    pub fn fps(self) -> f64 {
+        match self {
+            Self::Fps2997Drop => 30_000.0 / 1001.0,
+            other => f64::from(other.nominal_fps()),
+        }
+    }
+
+    /// Rate encoded by the two rate bits of MTC messages.
+    fn from_mtc_bits(bits: u8) -> Self {
+        match bits & 0x03 {
+            0 => Self::Fps24,
+            1 => Self::Fps25,
+            2 => Self::Fps2997Drop,
+            _ => Self::Fps30,
+        }
+    }
+}
+
+/// SMPTE timecode position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timecode {
+    /// Hours, 0-23.
+    pub hours: u8,
+    /// Minutes, 0-59.
+    pub minutes: u8,
+    /// Seconds, 0-59.
+    pub seconds: u8,
+    /// Frames within the second.
+    pub frames: u8,
+    /// Frame rate the frames are counted at.
+    pub rate: FrameRate,
+}
+
+impl Timecode {
+    /// Timecode position in seconds of real time.
+    ///
+    /// Drop-frame timecode skips frame numbers 0 and 1 at the start of every
+    /// minute except each tenth, so labels stay close to wall-clock time.
+    pub fn to_seconds(&self) -> f64 {
+        let fps = u64::from(self.rate.nominal_fps());
+        let total_minutes = u64::from(self.hours) * 60 + u64::from(self.minutes);
+        let mut frame =
+            (total_minutes * 60 + u64::from(self.seconds)) * fps + u64::from(self.frames);
+        if self.rate == FrameRate::Fps2997Drop {
+            frame -= 2 * (total_minutes - total_minutes / 10);
+        }
+        frame as f64 / self.rate.fps()
+    }
+}
+
+/// Latest timecode reading, written by the host and read by the player.
+///
+/// Feed it from an LTC reader, a video timeline, or an [`MtcDecoder`]. Each
+/// reading is stamped on arrival and extrapolated between frames, so the
+/// player sees a continuous position rather than one that steps once per
+/// frame. Clones share the same reading.
+#[derive(Debug, Clone, Default)]
+pub struct TimecodeInput {
+    latest: Arc<Mutex<Option<(Timecode, Instant)>>>,
+}
+
+impl TimecodeInput {
+    /// Create an input with no reading yet.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the timecode just received.
+    pub fn set(&self, timecode: Timecode) {
+        *self.lock_latest() = Some((timecode, Instant::now()));
+    }
+
+    /// Forget the last reading, e.g. when the timecode source stops.
+    pub fn clear(&self) {
+        *self.lock_latest() = None;
+    }
+
+    /// Extrapolated position in seconds, or `None` when no recent reading
+    /// exists.
+    pub fn seconds(&self) -> Option<f64> {
+        let (timecode, received) = (*self.lock_latest())?;
+        let elapsed = received.elapsed().as_secs_f64();
+        (elapsed <= TIMECODE_FREEWHEEL_SECONDS).then(|| timecode.to_seconds() + elapsed)
+    }
+
+    fn lock_latest(&self) -> std::sync::MutexGuard<'_, Option<(Timecode, Instant)>> {
+        lock_recoverable(
+            &self.latest,
+            "timecode input",
+            "the latest reading is replaced wholesale",
+        )
+    }
+}
+
+/// Assembles MIDI timecode messages into [`Timecode`] positions.
+///
+/// Pass the data byte of every quarter-frame message (`0xF1 dd`) to
+/// [`MtcDecoder::push_quarter_frame`], and full-frame SysEx messages to
+/// [`MtcDecoder::push_full_frame`].
+#[derive(Debug, Clone, Default)]
+pub struct MtcDecoder {
+    pieces: [u8; 8],
+    received: u8,
+}
+
+impl MtcDecoder {
+    /// Create a decoder waiting for its first full sequence.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add one quarter-frame data byte.
+    ///
+    /// Returns the position once all eight pieces have arrived. The pieces
+    /// describe the frame current when the sequence started, two frames
+    /// before the last piece, so the result is advanced by two frames.
+    pub fn push_quarter_frame(&mut self, data: u8) -> Option<Timecode> {
+        let piece = usize::from((data >> 4) & 0x07);
+        if piece == 0 {
+            self.received = 0;
+        }
+        self.pieces[piece] = data & 0x0F;
+        self.received |= 1 << piece;
+        if piece != 7 || self.received != 0xFF {
+            return None;
+        }
+        let p = &self.pieces;
+        let timecode = Timecode {
+            frames: p[0] | ((p[1] & 0x01) << 4),
+            seconds: p[2] | ((p[3] & 0x03) << 4),
+            minutes: p[4] | ((p[5] & 0x03) << 4),
+            hours: p[6] | ((p[7] & 0x01) << 4),
+            rate: FrameRate::from_mtc_bits(p[7] >> 1),
+        };
+        Some(advance_frames(timecode, 2))
+    }
+
+    /// Decode a full-frame message (`F0 7F <dev> 01 01 hh mm ss ff F7`),
+    /// sent when the source locates.
+    pub fn push_full_frame(&mut self, message: &[u8]) -> Option<Timecode> {
+        let [0xF0, 0x7F, _, 0x01, 0x01, hh, mm, ss, ff, 0xF7] = *message else {
+            return None;
+        };
+        self.received = 0;
+        Some(Timecode {
+            hours: hh & 0x1F,
+            minutes: mm & 0x3F,
+            seconds: ss & 0x3F,
+            frames: ff & 0x1F,
+            rate: FrameRate::from_mtc_bits(hh >> 5),
+        })
+    }
+}
+
+/// Advance `timecode` by `count` frames, skipping dropped frame numbers.
+fn advance_frames(mut timecode: Timecode, count: u8) -> Timecode {
+    let fps = timecode.rate.nominal_fps() as u8;
+    for _ in 0..count {
+        timecode.frames += 1;
+        if timecode.frames < fps {
+            continue;
+        }
+        timecode.frames = 0;
+        timecode.seconds += 1;
+        if timecode.seconds == 60 {
+            timecode.seconds = 0;
+            timecode.minutes += 1;
+            if timecode.minutes == 60 {
+                timecode.minutes = 0;
+                timecode.hours = (timecode.hours + 1) % 24;
+            }
+            if timecode.rate == FrameRate::Fps2997Drop && !timecode.minutes.is_multiple_of(10) {
+                timecode.frames = 2;
+            }
+        }
+    }
+    timecode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timecode(hours: u8, minutes: u8, seconds: u8, frames: u8, rate: FrameRate) -> Timecode {
+        Timecode {
+            hours,
+            minutes,
+            seconds,
+            frames,
+            rate,
+        }
+    }
+
+    #[test]
+    fn timecode_converts_to_seconds() {
+        let pal = timecode(1, 2, 3, 12, FrameRate::Fps25);
+        assert!((pal.to_seconds() - 3723.48).abs() < 1e-9);
+        // Drop-frame labels track wall-clock time: ten minutes is 17982 frames.
+        let ten_minutes = timecode(0, 10, 0, 0, FrameRate::Fps2997Drop);
+        assert!((ten_minutes.to_seconds() - 17_982.0 * 1001.0 / 30_000.0).abs() < 1e-9);
+        let one_minute = timecode(0, 1, 0, 2, FrameRate::Fps2997Drop);
+        assert!((one_minute.to_seconds() - 1800.0 * 1001.0 / 30_000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn quarter_frames_assemble_into_a_timecode() {
+        let mut decoder = MtcDecoder::new();
+        // 01:02:03:20 at 25 fps.
+        let pieces = [0x04, 0x11, 0x23, 0x30, 0x42, 0x50, 0x61, 0x72];
+        let decoded: Vec<_> = pieces
+            .iter()
+            .filter_map(|data| decoder.push_quarter_frame(*data))
+            .collect();
+        assert_eq!(decoded, vec![timecode(1, 2, 3, 22, FrameRate::Fps25)]);
+    }
+
+    #[test]
+    fn full_frame_message_locates() {
+        let mut decoder = MtcDecoder::new();
+        let message = [0xF0, 0x7F, 0x7F, 0x01, 0x01, 0x60 | 10, 30, 15, 29, 0xF7];
+        assert_eq!(
+            decoder.push_full_frame(&message),
+            Some(timecode(10, 30, 15, 29, FrameRate::Fps30))
+        );
+        assert_eq!(decoder.push_full_frame(&message[..9]), None);
+    }
+
+    #[test]
+    fn drop_frame_advance_skips_dropped_labels() {
+        let last = timecode(0, 0, 59, 29, FrameRate::Fps2997Drop);
+        assert_eq!(
+            advance_frames(last, 1),
+            timecode(0, 1, 0, 2, FrameRate::Fps2997Drop)
+        );
+        let tenth = timecode(0, 9, 59, 29, FrameRate::Fps2997Drop);
+        assert_eq!(
+            advance_frames(tenth, 1),
+            timecode(0, 10, 0, 0, FrameRate::Fps2997Drop)
+        );
+    }
+}
@@ -0,0 +1,312 @@
+//! Persistent threads that run decode workers across playback runs.
+//!
+//! Every seek or shuffle restarts the mix runtime, which used to spawn fresh
+//! OS threads for each decode source. A [`DecodePool`] keeps a fixed number
+//! of threads parked between runs so restarts reuse them instead.
+//!
+//! Decode jobs run until their source ends and block while the mixer has no
+//! room, so a queued job could starve behind them. When every pool thread is
+//! busy, extra jobs get a temporary thread that exits when the job returns.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+
+use log::warn;
+
+use crate::diagnostics::log_capture::{current_session, SessionScope};
+use crate::playback::mutex_policy::{lock_recoverable, wait_recoverable};
+
+/// Upper bound for the default pool size.
+const MAX_DEFAULT_THREADS: usize = 4;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Shared pool of decode threads.
+///
+/// Clones share the same threads. The threads exit once every clone has
+/// been dropped and their current jobs have returned.
+#[derive(Clone)]
+pub struct DecodePool {
+    owner: Arc<PoolOwner>,
+}
+
+struct PoolOwner {
+    shared: Arc<PoolShared>,
+}
+
+struct PoolShared {
+    threads: usize,
+    state: Mutex<PoolState>,
+    work_ready: Condvar,
+}
+
+#[derive(Default)]
+struct PoolState {
+    queue: VecDeque<Job>,
+    spawned: usize,
+    idle: usize,
+    shutdown: bool,
+}
+
+impl DecodePool {
+    /// Create a pool that keeps up to `threads` decode threads alive.
+    ///
+    /// Threads start lazily on first use. A size of `0` disables reuse and
+    /// runs every job on its own temporary thread.
+    pub fn new(threads: usize) -> Self {
+        Self {
+            owner: Arc::new(PoolOwner {
+                shared: Arc::new(PoolShared {
+                    threads,
+                    state: Mutex::new(PoolState::default()),
+                    work_ready: Condvar::new(),
+                }),
+            }),
+        }
+    }
+
+    /// Default pool size: available parallelism, capped at four threads.
+    pub fn default_threads() -> usize {
+        thread::available_parallelism()
+            .map_or(1, |threads| threads.get())
+            .min(MAX_DEFAULT_THREADS)
+    }
+
+    /// Number of threads the pool keeps alive between jobs.
+    pub fn threads(&self) -> usize {
+        self.owner.shared.threads
+    }
+
+    /// Number of pool threads started so far.
+    pub fn spawned_threads(&self) -> usize {
+        lock_state(&self.owner.shared).spawned
+    }
+
+    /// Run `job` on an idle pool thread, a new pool thread, or a temporary
+    /// thread when the pool is saturated.
+    pub(crate) fn submit(&self, job: impl FnOnce() + Send + 'static) -> DecodeJobHandle {
+        let handle = DecodeJobHandle::default();
+        let completion = handle.clone();
+        let session = current_session();
+        let job: Job = Box::new(move || {
+            let _scope = SessionScope::enter(session);
+            let result = panic::catch_unwind(AssertUnwindSafe(job));
+            completion.complete(result.is_err());
+        });
+
+        let shared = &self.owner.shared;
+        let mut state = lock_state(shared);
+        if state.idle > state.queue.len() {
+            state.queue.push_back(job);
+            drop(state);
+            shared.work_ready.notify_one();
+            return handle;
+        }
+        if state.spawned < shared.threads {
+            state.queue.push_back(job);
+            let index = state.spawned;
+            let worker_shared = shared.clone();
+            let spawned = thread::Builder::new()
+                .name(format!("proteus-decode-{}", index))
+                .spawn(move || run_pool_thread(&worker_shared));
+            match spawned {
+                Ok(_) => {
+                    state.spawned += 1;
+                    return handle;
+                }
+                Err(err) => {
+                    warn!("failed to start decode pool thread: {}", err);
+                    let job = state.queue.pop_back().expect("job was just queued");
+                    drop(state);
+                    run_temporary(job);
+                    return handle;
+                }
+            }
+        }
+        drop(state);
+        run_temporary(job);
+        handle
+    }
+}
+
+impl Default for DecodePool {
+    fn default() -> Self {
+        Self::new(Self::default_threads())
+    }
+}
+
+impl fmt::Debug for DecodePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecodePool")
+            .field("threads", &self.threads())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for PoolOwner {
+    fn drop(&mut self) {
+        lock_state(&self.shared).shutdown = true;
+        self.shared.work_ready.notify_all();
+    }
+}
+
+fn run_pool_thread(shared: &PoolShared) {
+    loop {
+        let job = {
+            let mut state = lock_state(shared);
+            loop {
+                if let Some(job) = state.queue.pop_front() {
+                    break job;
+                }
+                if state.shutdown {
+                    state.spawned -= 1;
+                    return;
+                }
+                state.idle += 1;
+                state = wait_recoverable(
+                    &shared.work_ready,
+                    state,
+                    "decode pool state",
+                    "jobs run outside the lock, so the queue and counters stay consistent",
+                );
+                state.idle -= 1;
+            }
+        };
+        job();
+    }
+}
+
+fn run_temporary(job: Job) {
+    // If even a temporary thread cannot start, run inline so the job's
+    // completion handle is still signalled.
+    let slot = Arc::new(Mutex::new(Some(job)));
+    let spawned_slot = slot.clone();
+    let spawned = thread::Builder::new()
+        .name("proteus-decode-extra".to_string())
+        .spawn(move || {
+            if let Some(job) = take_job(&spawned_slot) {
+                job();
+            }
+        });
+    if let Err(err) = spawned {
+        warn!("failed to start decode thread, decoding inline: {}", err);
+        if let Some(job) = take_job(&slot) {
+            job();
+        }
+    }
+}
+
+/// Recoverable poison policy: the slot only hands one job to one thread.
+fn take_job(slot: &Mutex<Option<Job>>) -> Option<Job> {
+    lock_recoverable(
+        slot,
+        "decode job slot",
+        "the slot only hands one job to one thread",
+    )
+    .take()
+}
+
+/// Recoverable poison policy: jobs run outside the lock, so the queue and
+/// counters are never left half-updated.
+fn lock_state(shared: &PoolShared) -> MutexGuard<'_, PoolState> {
+    lock_recoverable(
+        &shared.state,
+        "decode pool state",
+        "jobs run outside the lock, so the queue and counters stay consistent",
+    )
+}
+
+/// Completion handle for a job submitted to a [`DecodePool`].
+#[derive(Clone, Default)]
+pub(crate) struct DecodeJobHandle {
+    done: Arc<(Mutex<Option<bool>>, Condvar)>,
+}
+
+impl DecodeJobHandle {
+    fn complete(&self, panicked: bool) {
+        let (result, finished) = &*self.done;
+        *lock_result(result) = Some(panicked);
+        finished.notify_all();
+    }
+
+    /// Block until the job has returned; `Err` when it panicked.
+    pub(crate) fn join(self) -> Result<(), ()> {
+        let (result, finished) = &*self.done;
+        let mut result = lock_result(result);
+        loop {
+            match *result {
+                Some(false) => return Ok(()),
+                Some(true) => return Err(()),
+                None => {
+                    result = wait_recoverable(
+                        finished,
+                        result,
+                        "decode job result",
+                        "the result is written once when the job returns",
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Recoverable poison policy: the result is written once when the job returns.
+fn lock_result(result: &Mutex<Option<bool>>) -> MutexGuard<'_, Option<bool>> {
+    lock_recoverable(
+        result,
+        "decode job result",
+        "the result is written once when the job returns",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn sequential_jobs_reuse_one_pool_thread() {
+        let pool = DecodePool::new(2);
+        let mut names = Vec::new();
+        for _ in 0..3 {
+            let (tx, rx) = mpsc::channel();
+            pool.submit(move || {
+                tx.send(thread::current().name().map(str::to_string))
+                    .unwrap();
+            })
+            .join()
+            .unwrap();
+            names.push(rx.recv().unwrap());
+            // Let the thread park again before the next submission.
+            while lock_state(&pool.owner.shared).idle == 0 {
+                thread::yield_now();
+            }
+        }
+        assert_eq!(pool.spawned_threads(), 1);
+        assert!(names.iter().all(|name| name == &names[0]));
+    }
+
+    #[test]
+    fn saturated_pool_runs_extra_jobs_concurrently() {
+        let pool = DecodePool::new(1);
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        let blocking = pool.submit(move || {
+            let _ = release_rx.recv();
+        });
+        // The pool is busy, so this job must not wait behind the first one.
+        pool.submit(|| {}).join().unwrap();
+        release_tx.send(()).unwrap();
+        blocking.join().unwrap();
+        assert_eq!(pool.spawned_threads(), 1);
+    }
+
+    #[test]
+    fn panicking_job_reports_an_error_and_keeps_the_thread() {
+        let pool = DecodePool::new(1);
+        assert!(pool.submit(|| panic!("decode failure")).join().is_err());
+        assert!(pool.submit(|| {}).join().is_ok());
+    }
+}
@@ -0,0 +1,148 @@
+//! Live capture input mixed alongside the container tracks.
+//!
+//! The capture callback pushes frames, already converted to the engine
+//! channel layout and sample rate, into a bounded [`LiveInputBus`]. The mix
+//! thread pulls one chunk's worth per cycle, applies the bus level/pan and
+//! optional effects, and sums the result into the track mix before the main
+//! effect chain, exactly where a container stem would land.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::dsp::effects::AudioEffect;
+use crate::dsp::pan::PanLaw;
+
+use super::compute_track_channel_gains;
+
+/// Shared slot holding the live input bus while capture is running.
+pub type SharedLiveInput = Arc<Mutex<Option<LiveInputBus>>>;
+
+/// Bounded FIFO of captured frames plus the live track's mix settings.
+#[derive(Debug)]
+pub struct LiveInputBus {
+    samples: VecDeque<f32>,
+    capacity: usize,
+    channels: usize,
+    level: f32,
+    pan: f32,
+    pan_law: PanLaw,
+    gains: Vec<f32>,
+    pending_effects: Option<Vec<AudioEffect>>,
+    duck_threshold: Option<f32>,
+    dropped_samples: u64,
+}
+
+impl LiveInputBus {
+    /// Create a bus holding at most `capacity_frames` frames of `channels`.
+    pub(crate) fn new(
+        channels: usize,
+        capacity_frames: usize,
+        level: f32,
+        pan: f32,
+        pan_law: PanLaw,
+    ) -> Self {
+        let channels = channels.max(1);
+        Self {
+            samples: VecDeque::with_capacity(capacity_frames * channels),
+            capacity: capacity_frames.max(1) * channels,
+            channels,
+            level,
+            pan,
+            pan_law,
+            gains: compute_track_channel_gains(level, pan, channels, pan_law),
+            pending_effects: None,
+            duck_threshold: None,
+            dropped_samples: 0,
+        }
+    }
+
+    /// Append captured interleaved samples, dropping the oldest on overflow.
+    ///
+    /// Dropping old audio keeps monitoring latency bounded when the mix
+    /// thread is not pulling (for example while playback is paused).
+    pub(crate) fn push_captured(&mut self, samples: &[f32]) {
+        self.samples.extend(samples.iter().copied());
+        let overflow = self.samples.len().saturating_sub(self.capacity);
+        if overflow > 0 {
+            let overflow = overflow.div_ceil(self.channels) * self.channels;
+            self.samples.drain(..overflow.min(self.samples.len()));
+            self.dropped_samples += overflow as u64;
+        }
+    }
+
+    /// Update level and pan using the same law as container track slots.
+    pub(crate) fn set_mix(&mut self, level: f32, pan: f32) {
+        self.level = level;
+        self.pan = pan;
+        self.gains = compute_track_channel_gains(level, pan, self.channels, self.pan_law);
+    }
+
+    /// Switch the pan law, keeping the current level and pan.
+    pub(crate) fn set_pan_law(&mut self, pan_law: PanLaw) {
+        self.pan_law = pan_law;
+        self.set_mix(self.level, self.pan);
+    }
+
+    /// Replace the live input effect chain on the next mix cycle.
+    pub(crate) fn set_effects(&mut self, effects: Vec<AudioEffect>) {
+        self.pending_effects = Some(effects);
+    }
+
+    /// Key main-mix ducking while the processed input peaks at or above `threshold` (linear).
+    pub(crate) fn set_duck_threshold(&mut self, threshold: Option<f32>) {
+        self.duck_threshold = threshold;
+    }
+
+    /// Take a newly requested effect chain, if any.
+    pub(crate) fn take_pending_effects(&mut self) -> Option<Vec<AudioEffect>> {
+        self.pending_effects.take()
+    }
+
+    /// Linear ducking threshold, if the live input keys ducking.
+    pub(crate) fn duck_threshold(&self) -> Option<f32> {
+        self.duck_threshold
+    }
+
+    /// Number of captured samples dropped because the bus was full.
+    pub(crate) fn dropped_samples(&self) -> u64 {
+        self.dropped_samples
+    }
+
+    /// Fill `output` with gain-applied captured samples, zero-padding on underrun.
+    pub(crate) fn take_into(&mut self, output: &mut [f32]) {
+        let channels = self.channels;
+        for (index, out) in output.iter_mut().enumerate() {
+            *out = self
+                .samples
+                .pop_front()
+                .map_or(0.0, |sample| sample * self.gains[index % channels]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LiveInputBus;
+    use crate::dsp::pan::PanLaw;
+
+    #[test]
+    fn bus_drops_oldest_frames_on_overflow() {
+        let mut bus = LiveInputBus::new(2, 2, 1.0, 0.0, PanLaw::Balance);
+        bus.push_captured(&[1.0, 1.0, 2.0, 2.0, 3.0, 3.0]);
+        assert_eq!(bus.dropped_samples(), 2);
+
+        let mut output = vec![0.0_f32; 6];
+        bus.take_into(&mut output);
+        assert_eq!(output, vec![2.0, 2.0, 3.0, 3.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn bus_applies_level_and_pan() {
+        let mut bus = LiveInputBus::new(2, 8, 1.0, 0.0, PanLaw::Balance);
+        bus.set_mix(0.5, -1.0);
+        bus.push_captured(&[1.0, 1.0]);
+        let mut output = vec![0.0_f32; 2];
+        bus.take_into(&mut output);
+        assert_eq!(output, vec![0.5, 0.0]);
+    }
+}
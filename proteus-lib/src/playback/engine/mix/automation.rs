@@ -0,0 +1,127 @@
+//! Effect automation scheduled at sample positions within a mix run.
+
+use log::warn;
+
+use crate::container::prot::EffectAutomationPoint;
+
+use super::types::EffectParameter;
+
+/// One parameter change at an interleaved-sample position.
+#[derive(Debug, Clone)]
+struct ScheduledChange {
+    at_sample: usize,
+    effect_index: usize,
+    parameter: EffectParameter,
+}
+
+/// Container effect automation resolved against the start of a mix run.
+///
+/// Positions count interleaved samples from the run start, matching the
+/// mix loop's `running_count`. Changes at or before the start are due at
+/// position zero, so a run started mid-timeline picks up earlier changes.
+#[derive(Debug, Clone, Default)]
+pub(super) struct EffectAutomation {
+    changes: Vec<ScheduledChange>,
+    next: usize,
+}
+
+impl EffectAutomation {
+    /// Resolve `points` for a run starting at `start_time` seconds.
+    ///
+    /// Unknown parameter names are skipped with a warning.
+    pub(super) fn new(
+        points: &[EffectAutomationPoint],
+        start_time: f64,
+        sample_rate: u32,
+        channels: usize,
+    ) -> Self {
+        let mut changes: Vec<ScheduledChange> = points
+            .iter()
+            .filter_map(|point| {
+                let Some(parameter) = EffectParameter::from_name(&point.parameter, point.value)
+                else {
+                    warn!(
+                        "ignoring effect automation for unknown parameter: {}",
+                        point.parameter
+                    );
+                    return None;
+                };
+                let offset_s = (point.at_seconds - start_time).max(0.0);
+                let frames = (offset_s * f64::from(sample_rate)).round() as usize;
+                Some(ScheduledChange {
+                    at_sample: frames * channels.max(1),
+                    effect_index: point.effect_index,
+                    parameter,
+                })
+            })
+            .collect();
+        changes.sort_by_key(|change| change.at_sample);
+        Self { changes, next: 0 }
+    }
+
+    /// Position of the next change strictly inside `(start, end)`, if any.
+    ///
+    /// The mix loop splits the chunk spanning `start..end` there.
+    pub(super) fn next_split(&self, start: usize, end: usize) -> Option<usize> {
+        self.changes[self.next..]
+            .iter()
+            .map(|change| change.at_sample)
+            .find(|&at| at > start)
+            .filter(|&at| at < end)
+    }
+
+    /// Take every change due at or before `position`, in order.
+    pub(super) fn take_due(&mut self, position: usize) -> Vec<(usize, EffectParameter)> {
+        let due = self.changes[self.next..]
+            .iter()
+            .take_while(|change| change.at_sample <= position)
+            .map(|change| (change.effect_index, change.parameter.clone()))
+            .collect::<Vec<_>>();
+        self.next += due.len();
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(at_seconds: f64, parameter: &str, value: f32) -> EffectAutomationPoint {
+        EffectAutomationPoint {
+            at_seconds,
+            effect_index: 0,
+            parameter: parameter.to_string(),
+            value,
+        }
+    }
+
+    #[test]
+    fn changes_resolve_to_frame_aligned_positions_from_the_run_start() {
+        let points = [point(12.5, "gain", 0.5), point(10.0, "reverb_mix", 0.4)];
+        let mut automation = EffectAutomation::new(&points, 10.0, 1_000, 2);
+
+        let due = automation.take_due(0);
+        assert_eq!(due.len(), 1);
+        assert!(matches!(due[0].1, EffectParameter::ReverbMix(mix) if mix == 0.4));
+        assert_eq!(automation.next_split(0, 4_000), None);
+        assert_eq!(automation.next_split(0, 6_000), Some(5_000));
+        assert!(automation.take_due(4_998).is_empty());
+        assert_eq!(automation.take_due(5_000).len(), 1);
+        assert_eq!(automation.next_split(5_000, 10_000), None);
+    }
+
+    #[test]
+    fn earlier_changes_are_due_at_the_start_and_unknown_names_are_dropped() {
+        let points = [
+            point(1.0, "gain", 0.2),
+            point(2.0, "wobble", 1.0),
+            point(3.0, "gain", 0.7),
+        ];
+        let mut automation = EffectAutomation::new(&points, 5.0, 48_000, 2);
+
+        let due = automation.take_due(0);
+        assert_eq!(due.len(), 2);
+        assert!(matches!(due[1].1, EffectParameter::Gain(gain) if gain == 0.7));
+        assert!(automation.take_due(usize::MAX).is_empty());
+    }
+}
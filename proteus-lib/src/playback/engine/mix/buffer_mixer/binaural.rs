@@ -0,0 +1,58 @@
+//! Per-track binaural rendering for [`BufferMixer`] (`hrtf` feature).
+//!
+//! Tracks that declare a 3D position are rendered through their own
+//! [`BinauralPannerEffect`] before the equal-weight sum, replacing the
+//! stereo pan law for those tracks.
+
+use std::collections::HashMap;
+
+use super::BufferMixer;
+use crate::dsp::effects::{BinauralPannerEffect, BinauralPannerSettings, EffectContext};
+use crate::dsp::pan::PanLaw;
+use crate::playback::engine::mix::track_stage::apply_track_gain_pan;
+
+impl BufferMixer {
+    /// Attach binaural panners to the logical tracks owning the given slots.
+    pub(crate) fn set_track_binaural_by_slot(
+        &mut self,
+        settings_by_slot: &HashMap<u16, BinauralPannerSettings>,
+        context: &EffectContext,
+    ) {
+        let mut panners: Vec<Option<BinauralPannerEffect>> = vec![None; self.track_instances.len()];
+        for (&slot_index, settings) in settings_by_slot {
+            let logical = self
+                .slot_to_logical
+                .get(slot_index as usize)
+                .copied()
+                .flatten();
+            if let Some(panner) = logical.and_then(|index| panners.get_mut(index)) {
+                panner.get_or_insert_with(|| BinauralPannerEffect::new(settings.clone()));
+            }
+        }
+        self.track_binaural = panners;
+        self.binaural_context = Some(context.clone());
+    }
+
+    /// Render a logical track binaurally if it has a panner attached.
+    ///
+    /// Returns `false` when the track has no 3D position, leaving the buffer
+    /// untouched so the caller can apply the regular gain/pan stage.
+    pub(super) fn apply_track_binaural(
+        &mut self,
+        track_index: usize,
+        track_buffer: &mut Vec<f32>,
+        level: f32,
+    ) -> bool {
+        let (Some(Some(panner)), Some(context)) = (
+            self.track_binaural.get_mut(track_index),
+            self.binaural_context.as_ref(),
+        ) else {
+            return false;
+        };
+        apply_track_gain_pan(track_buffer, level, 0.0, self.channels, PanLaw::Balance);
+        let mut rendered = Vec::with_capacity(track_buffer.len());
+        panner.render_into(track_buffer, &mut rendered, context);
+        *track_buffer = rendered;
+        true
+    }
+}
@@ -101,7 +101,23 @@ impl BufferMixer {
                 .get(track_index)
                 .copied()
                 .unwrap_or((1.0, 0.0));
-            apply_track_gain_pan(&mut track_buffer, level, pan, self.channels);
+            let level = level
+                * self
+                    .track_group_gains
+                    .get(track_index)
+                    .copied()
+                    .unwrap_or(1.0);
+            #[cfg(feature = "hrtf")]
+            let spatialized = self.apply_track_binaural(track_index, &mut track_buffer, level);
+            #[cfg(not(feature = "hrtf"))]
+            let spatialized = false;
+            if !spatialized {
+                let pan_law = self.effective_pan_law();
+                apply_track_gain_pan(&mut track_buffer, level, pan, self.channels, pan_law);
+            }
+            if let Some(levels) = self.track_levels.get_mut(track_index) {
+                levels.measure_into(&track_buffer, self.channels);
+            }
             logical_tracks.push(track_buffer);
         }
 
@@ -2,19 +2,23 @@
 
 mod aligned_buffer;
 mod backpressure;
+#[cfg(feature = "hrtf")]
+mod binaural;
 mod diagnostics;
 mod mixing;
 mod packet_router;
 mod routing_helpers;
 mod routing_time;
 
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::container::prot::{RuntimeInstanceMeta, RuntimeInstancePlan};
+use crate::container::prot::{InstanceLoop, RuntimeInstanceMeta, RuntimeInstancePlan, SourceTrim};
 use crate::dsp::guardrails::{sanitize_channels, sanitize_sample_rate};
+use crate::dsp::pan::PanLaw;
 #[cfg(feature = "buffer-map")]
 use crate::logging::clear_logfile;
+use crate::playback::track_meter::TrackLevels;
 
 use aligned_buffer::AlignedSampleBuffer;
 pub(crate) use backpressure::DecodeBackpressure;
@@ -23,6 +27,9 @@ pub(crate) use routing_helpers::FillState;
 pub(crate) use routing_helpers::{RouteDecision, SourceKey};
 use routing_time::instance_fully_past_window;
 
+/// Crossfade applied where a slot switches source at a shuffle point.
+pub(crate) const SHUFFLE_CROSSFADE_MS: usize = 2;
+
 #[derive(Debug)]
 pub(super) struct BufferInstance {
     pub(super) meta: RuntimeInstanceMeta,
@@ -61,10 +68,28 @@ pub(crate) struct BufferMixer {
     pub(super) instances: Vec<BufferInstance>,
     pub(super) track_instances: Vec<Vec<usize>>,
     pub(super) track_mix_settings: Vec<(f32, f32)>,
+    pub(super) track_group_gains: Vec<f32>,
+    pan_law: PanLaw,
+    pan_law_override: Option<PanLaw>,
     slot_to_logical: Vec<Option<usize>>,
     pub(super) decode_backpressure: Arc<DecodeBackpressure>,
     pub(super) crossfade_ms: usize,
     pub(super) pop_warning: Vec<usize>,
+    pub(super) track_levels: Vec<TrackLevels>,
+    /// Levels reported for slots without a logical track.
+    silent_levels: TrackLevels,
+    #[cfg(feature = "hrtf")]
+    track_binaural: Vec<Option<crate::dsp::effects::BinauralPannerEffect>>,
+    #[cfg(feature = "hrtf")]
+    binaural_context: Option<crate::dsp::effects::EffectContext>,
+}
+
+/// Delayed start, loop, and trim of one source, merged across its instances.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub(crate) struct SourceTiming {
+    pub(crate) start_offset_ms: u64,
+    pub(crate) looping: Option<InstanceLoop>,
+    pub(crate) trim: SourceTrim,
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -111,19 +136,31 @@ impl BufferMixer {
         }
 
         let decode_backpressure = Arc::new(DecodeBackpressure::from_instances(&instances));
+        let channels = sanitize_channels(channels);
+        let track_levels = vec![TrackLevels::silent(channels); track_instances.len()];
+        let track_group_gains = vec![1.0; track_instances.len()];
 
         Self {
             sample_rate: sanitize_sample_rate(sample_rate),
-            channels: sanitize_channels(channels),
+            channels,
             mix_chunk_samples: mix_chunk_samples.max(1),
             consumed_samples: 0,
             instances,
             track_instances,
             track_mix_settings,
+            track_group_gains,
+            pan_law: PanLaw::default(),
+            pan_law_override: None,
             slot_to_logical,
             decode_backpressure,
-            crossfade_ms: 2,
+            crossfade_ms: SHUFFLE_CROSSFADE_MS,
             pop_warning: Vec::new(),
+            track_levels,
+            silent_levels: TrackLevels::silent(channels),
+            #[cfg(feature = "hrtf")]
+            track_binaural: Vec::new(),
+            #[cfg(feature = "hrtf")]
+            binaural_context: None,
         }
     }
 
@@ -174,6 +211,18 @@ impl BufferMixer {
             .all(|track_index| self.track_ready_with_min_samples(track_index, min_samples))
     }
 
+    /// True when a source starts or stops within `[start, end)`, in
+    /// interleaved samples from the start of the run.
+    pub(crate) fn has_window_edge_within(&self, start: usize, end: usize) -> bool {
+        routing_time::window_edge_within(
+            &self.instances,
+            start,
+            end,
+            self.sample_rate,
+            self.channels,
+        )
+    }
+
     /// True when all logical tracks are finished.
     pub(crate) fn mix_finished(&self) -> bool {
         (0..self.track_instances.len()).all(|track_index| self.track_finished(track_index))
@@ -188,6 +237,30 @@ impl BufferMixer {
         set.into_iter().collect()
     }
 
+    /// Start offset, loop, and trim of each source that begins late,
+    /// repeats, or is trimmed.
+    ///
+    /// A source shared by tracks with different offsets takes the largest,
+    /// and loops when any of them loops.
+    pub(crate) fn source_timings(&self) -> HashMap<SourceKey, SourceTiming> {
+        let mut timings: HashMap<SourceKey, SourceTiming> = HashMap::new();
+        for instance in self.instances.iter() {
+            if instance.meta.start_offset_ms == 0
+                && instance.meta.looping.is_none()
+                && instance.meta.trim == SourceTrim::default()
+            {
+                continue;
+            }
+            let timing = timings
+                .entry(SourceKey::from(&instance.meta.source_key))
+                .or_default();
+            timing.start_offset_ms = timing.start_offset_ms.max(instance.meta.start_offset_ms);
+            timing.looping = timing.looping.or(instance.meta.looping);
+            timing.trim = instance.meta.trim;
+        }
+        timings
+    }
+
     /// Number of concrete instances in the mixer.
     pub(crate) fn instance_count(&self) -> usize {
         self.instances.len()
@@ -225,6 +298,51 @@ impl BufferMixer {
         }
     }
 
+    /// Replace the group gain of each logical track.
+    ///
+    /// Gains multiply the per-track level; tracks past the end of `gains`
+    /// play at unity.
+    pub(crate) fn set_track_group_gains(&mut self, gains: &[f32]) {
+        for (index, gain) in self.track_group_gains.iter_mut().enumerate() {
+            *gain = gains.get(index).copied().unwrap_or(1.0).max(0.0);
+        }
+    }
+
+    /// Set the pan law declared by the container's play settings.
+    pub(crate) fn set_pan_law(&mut self, pan_law: PanLaw) {
+        self.pan_law = pan_law;
+    }
+
+    /// Override the container's pan law at runtime (`None` restores it).
+    pub(crate) fn set_pan_law_override(&mut self, pan_law: Option<PanLaw>) {
+        self.pan_law_override = pan_law;
+    }
+
+    /// Pan law applied to track slots.
+    pub(super) fn effective_pan_law(&self) -> PanLaw {
+        self.pan_law_override.unwrap_or(self.pan_law)
+    }
+
+    /// Most recent pre-sum levels for each slot, in slot order.
+    ///
+    /// Slots that share a logical track report the same levels; slots with
+    /// no track report silence.
+    pub(crate) fn track_levels_by_slot(&self) -> impl Iterator<Item = &TrackLevels> + '_ {
+        self.slot_to_logical.iter().map(|logical| {
+            logical
+                .and_then(|index| self.track_levels.get(index))
+                .unwrap_or(&self.silent_levels)
+        })
+    }
+
+    /// Copy [`Self::track_levels_by_slot`] into `out`, reusing its buffers.
+    pub(crate) fn copy_track_levels_by_slot(&self, out: &mut Vec<TrackLevels>) {
+        out.resize_with(self.slot_to_logical.len(), TrackLevels::default);
+        for (slot, levels) in out.iter_mut().zip(self.track_levels_by_slot()) {
+            slot.clone_from(levels);
+        }
+    }
+
     /// Shared backpressure handle used by decode workers to block until source buffers have room.
     pub(crate) fn decode_backpressure(&self) -> Arc<DecodeBackpressure> {
         Arc::clone(&self.decode_backpressure)
@@ -39,6 +39,25 @@ pub(super) fn samples_to_ms(samples: usize, sample_rate: u32, channels: usize) -
     ((frames as f64 / sanitize_sample_rate(sample_rate) as f64) * 1000.0).round() as u64
 }
 
+/// Return true when any instance window starts or ends within `[start, end)`.
+///
+/// Offsets are interleaved samples from the start of the run; an edge at
+/// sample zero is ignored because every run starts there.
+pub(super) fn window_edge_within(
+    instances: &[BufferInstance],
+    start: usize,
+    end: usize,
+    sample_rate: u32,
+    channels: usize,
+) -> bool {
+    instances
+        .iter()
+        .flat_map(|instance| instance.meta.active_windows.iter())
+        .flat_map(|window| std::iter::once(window.start_ms).chain(window.end_ms))
+        .map(|ms| ms_to_samples(ms, sample_rate, channels))
+        .any(|edge| edge > 0 && edge >= start && edge < end)
+}
+
 /// Convert milliseconds to an interleaved sample count for the output format.
 fn ms_to_samples(ms: u64, sample_rate: u32, channels: usize) -> usize {
     let frames = ((ms as f64 / 1000.0) * sample_rate as f64).round() as usize;
@@ -75,6 +94,9 @@ mod tests {
                 active_windows: vec![ActiveWindow { start_ms, end_ms }],
                 selection_index: 0,
                 occurrence_index: 0,
+                start_offset_ms: 0,
+                looping: None,
+                trim: Default::default(),
             },
             buffer: super::super::AlignedSampleBuffer::with_capacity(16),
             buffer_capacity_samples: 16,
@@ -93,6 +115,22 @@ mod tests {
         assert!(instance_past_window_ts(&instance, &0.5));
     }
 
+    #[test]
+    fn window_edge_within_finds_starts_and_ends() {
+        let instances = [instance_with_window(500, Some(1000))];
+        // 500 ms at 1 kHz stereo is sample 1000; 1000 ms is sample 2000.
+        assert!(window_edge_within(&instances, 900, 1001, 1000, 2));
+        assert!(window_edge_within(&instances, 2000, 2100, 1000, 2));
+        assert!(!window_edge_within(&instances, 1001, 2000, 1000, 2));
+        assert!(!window_edge_within(
+            &[instance_with_window(0, None)],
+            0,
+            10,
+            1000,
+            2
+        ));
+    }
+
     #[test]
     fn samples_to_ms_converts_interleaved_counts() {
         assert_eq!(samples_to_ms(96_000, 48_000, 2), 1000);
@@ -20,6 +20,9 @@ fn simple_plan() -> RuntimeInstancePlan {
                 }],
                 selection_index: 0,
                 occurrence_index: 0,
+                start_offset_ms: 0,
+                looping: None,
+                trim: Default::default(),
             },
             RuntimeInstanceMeta {
                 instance_id: 1,
@@ -32,6 +35,9 @@ fn simple_plan() -> RuntimeInstancePlan {
                 }],
                 selection_index: 0,
                 occurrence_index: 0,
+                start_offset_ms: 0,
+                looping: None,
+                trim: Default::default(),
             },
         ],
         event_boundaries_ms: vec![0],
@@ -118,6 +124,9 @@ fn route_packet_zero_fills_when_packet_is_before_window_start() {
             }],
             selection_index: 0,
             occurrence_index: 0,
+            start_offset_ms: 0,
+            looping: None,
+            trim: Default::default(),
         }],
         event_boundaries_ms: vec![0, 1000],
     };
@@ -131,3 +140,33 @@ fn route_packet_zero_fills_when_packet_is_before_window_start() {
     let mixed = mixer.take_samples().expect("zero-filled samples");
     assert_eq!(mixed, vec![0.0, 0.0, 0.0, 0.0]);
 }
+
+#[test]
+/// Verifies per-slot levels are measured on each track before summing.
+fn take_samples_records_pre_sum_track_levels() {
+    let mut mixer = BufferMixer::new(simple_plan(), 48_000, 2, 16, Vec::new(), 4);
+
+    mixer.route_packet(&[1.0, 1.0, -1.0, -1.0], SourceKey::TrackId(1), 0.0);
+    mixer.route_packet(&[0.5, 0.0, 0.5, 0.0], SourceKey::TrackId(2), 0.0);
+    mixer.take_samples().expect("mixed samples");
+
+    let mut levels = Vec::new();
+    mixer.copy_track_levels_by_slot(&mut levels);
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0].peak, vec![1.0, 1.0]);
+    assert_eq!(levels[1].peak, vec![0.5, 0.0]);
+    assert!((levels[1].rms[0] - 0.5).abs() < 1e-6);
+}
+
+#[test]
+/// Verifies group gains multiply each track's own level in the mix.
+fn group_gains_scale_member_tracks() {
+    let mut mixer = BufferMixer::new(simple_plan(), 48_000, 2, 16, Vec::new(), 4);
+    mixer.set_track_group_gains(&[0.0]);
+
+    mixer.route_packet(&[1.0, 1.0, 1.0, 1.0], SourceKey::TrackId(1), 0.0);
+    mixer.route_packet(&[0.5, 0.5, 0.5, 0.5], SourceKey::TrackId(2), 0.0);
+
+    let mixed = mixer.take_samples().expect("mixed samples");
+    assert_eq!(mixed, vec![0.25, 0.25, 0.25, 0.25]);
+}
@@ -0,0 +1,139 @@
+//! Output-stage blend of the unprocessed mix with the effect-chain output.
+
+use std::collections::VecDeque;
+
+/// Mixes the chain's input back into its output for parallel processing.
+///
+/// The dry path is delayed by the chain's reported latency so it lines up
+/// with the processed signal instead of comb-filtering against it.
+#[derive(Debug, Clone)]
+pub(super) struct ChainMixer {
+    mix: f32,
+    dry_delay: VecDeque<f32>,
+}
+
+impl ChainMixer {
+    pub(super) fn new() -> Self {
+        Self {
+            mix: 1.0,
+            dry_delay: VecDeque::new(),
+        }
+    }
+
+    /// Blend interleaved `dry` input into the matching chain output `wet`.
+    ///
+    /// `target` is the processed share in `[0.0, 1.0]`; the blend moves to it
+    /// linearly over `ramp_frames`. `latency_frames` is the chain's delay.
+    pub(super) fn process(
+        &mut self,
+        target: f32,
+        dry: &[f32],
+        wet: &mut [f32],
+        channels: usize,
+        latency_frames: usize,
+        ramp_frames: usize,
+    ) {
+        self.blend(
+            target,
+            dry.iter().copied(),
+            wet,
+            channels,
+            latency_frames,
+            ramp_frames,
+        );
+    }
+
+    /// Blend a draining chain tail, flushing whatever dry input is still
+    /// delayed.
+    pub(super) fn process_tail(
+        &mut self,
+        target: f32,
+        wet: &mut [f32],
+        channels: usize,
+        latency_frames: usize,
+        ramp_frames: usize,
+    ) {
+        self.blend(
+            target,
+            std::iter::repeat(0.0),
+            wet,
+            channels,
+            latency_frames,
+            ramp_frames,
+        );
+    }
+
+    fn blend(
+        &mut self,
+        target: f32,
+        mut dry: impl Iterator<Item = f32>,
+        wet: &mut [f32],
+        channels: usize,
+        latency_frames: usize,
+        ramp_frames: usize,
+    ) {
+        let target = target.clamp(0.0, 1.0);
+        if self.mix >= 1.0 && target >= 1.0 {
+            self.dry_delay.clear();
+            return;
+        }
+        let channels = channels.max(1);
+        self.dry_delay.resize(latency_frames * channels, 0.0);
+        let step = 1.0 / ramp_frames.max(1) as f32;
+
+        for frame in wet.chunks_mut(channels) {
+            self.mix = if self.mix < target {
+                (self.mix + step).min(target)
+            } else {
+                (self.mix - step).max(target)
+            };
+            for sample in frame {
+                self.dry_delay.push_back(dry.next().unwrap_or(0.0));
+                let delayed = self.dry_delay.pop_front().unwrap_or(0.0);
+                *sample = delayed + (*sample - delayed) * self.mix;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fully_processed_mix_leaves_the_chain_output_alone() {
+        let mut mixer = ChainMixer::new();
+        let mut wet = vec![0.5_f32; 8];
+        mixer.process(1.0, &[1.0; 8], &mut wet, 2, 4, 0);
+        assert_eq!(wet, vec![0.5; 8]);
+    }
+
+    #[test]
+    fn settled_mix_blends_dry_and_wet() {
+        let mut mixer = ChainMixer::new();
+        let mut wet = vec![0.0_f32; 8];
+        mixer.process(0.25, &[1.0; 8], &mut wet, 2, 0, 0);
+        assert!(wet.iter().all(|sample| (sample - 0.75).abs() < 1e-6));
+    }
+
+    #[test]
+    fn dry_path_is_delayed_by_the_chain_latency() {
+        let mut mixer = ChainMixer::new();
+        let dry = [1.0_f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let mut wet = vec![0.0_f32; 6];
+        mixer.process(0.0, &dry, &mut wet, 1, 2, 0);
+        assert_eq!(wet, vec![0.0, 0.0, 1.0, 2.0, 3.0, 4.0]);
+
+        let mut tail = vec![0.0_f32; 3];
+        mixer.process_tail(0.0, &mut tail, 1, 2, 0);
+        assert_eq!(tail, vec![5.0, 6.0, 0.0]);
+    }
+
+    #[test]
+    fn mix_changes_ramp_per_frame() {
+        let mut mixer = ChainMixer::new();
+        let mut wet = vec![0.0_f32; 4];
+        mixer.process(0.0, &[1.0; 4], &mut wet, 1, 0, 4);
+        assert_eq!(wet, vec![0.25, 0.5, 0.75, 1.0]);
+    }
+}
@@ -1,5 +1,7 @@
 //! Decoded packet events produced by source decoders and consumed by the mix router.
 
+use std::sync::mpsc;
+
 use super::buffer_mixer::SourceKey;
 
 /// One decoded packet emitted by a decode worker.
@@ -26,8 +28,22 @@ pub(crate) enum DecodeWorkerEvent {
         recoverable: bool,
         message: String,
     },
-    /// Shared stream exhausted (for example, container demux EOF).
-    StreamExhausted,
+    /// Shared stream exhausted (for example, container demux EOF); finishes
+    /// every source read from that stream.
+    StreamExhausted { sources: Vec<SourceKey> },
+}
+
+/// Destination for decode worker events.
+pub(crate) trait DecodeEventSink {
+    /// Deliver one event. Returns `false` when the consumer is gone and
+    /// decoding should stop.
+    fn send_event(&self, event: DecodeWorkerEvent) -> bool;
+}
+
+impl DecodeEventSink for mpsc::SyncSender<DecodeWorkerEvent> {
+    fn send_event(&self, event: DecodeWorkerEvent) -> bool {
+        self.send(event).is_ok()
+    }
 }
 
 #[cfg(test)]
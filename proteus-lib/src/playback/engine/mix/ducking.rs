@@ -0,0 +1,122 @@
+//! Output-stage ducking of the main mix while overlays play.
+
+use super::super::state::DuckingSettings;
+
+/// Gain envelope follower that ducks the main mix while keyed.
+#[derive(Debug, Clone)]
+pub(super) struct Ducker {
+    gain: f32,
+    hold_frames_left: usize,
+}
+
+impl Ducker {
+    pub(super) fn new() -> Self {
+        Self {
+            gain: 1.0,
+            hold_frames_left: 0,
+        }
+    }
+
+    /// Apply the ducking envelope to interleaved `samples` in place.
+    ///
+    /// `keyed` marks that an overlay is audible during this chunk. The gain
+    /// moves toward the duck depth with the attack time while keyed, holds
+    /// for `hold_ms` after the key drops, then recovers with the release time.
+    pub(super) fn process(
+        &mut self,
+        settings: &DuckingSettings,
+        keyed: bool,
+        samples: &mut [f32],
+        channels: usize,
+        sample_rate: u32,
+    ) {
+        if !settings.enabled {
+            self.gain = 1.0;
+            self.hold_frames_left = 0;
+            return;
+        }
+        if !keyed && self.hold_frames_left == 0 && self.gain >= 1.0 {
+            return;
+        }
+        let channels = channels.max(1);
+        let frames_per_ms = sample_rate as f32 / 1000.0;
+        let duck_gain = 10.0_f32.powf(-settings.depth_db.abs() / 20.0);
+        let attack = smoothing_coefficient(settings.attack_ms, frames_per_ms);
+        let release = smoothing_coefficient(settings.release_ms, frames_per_ms);
+        let hold_frames = (settings.hold_ms.max(0.0) * frames_per_ms) as usize;
+
+        for frame in samples.chunks_mut(channels) {
+            let target = if keyed {
+                self.hold_frames_left = hold_frames;
+                duck_gain
+            } else if self.hold_frames_left > 0 {
+                self.hold_frames_left -= 1;
+                duck_gain
+            } else {
+                1.0
+            };
+            let coefficient = if target < self.gain { attack } else { release };
+            self.gain = target + (self.gain - target) * coefficient;
+            if (self.gain - 1.0).abs() < 1.0e-5 {
+                self.gain = 1.0;
+            }
+            for sample in frame {
+                *sample *= self.gain;
+            }
+        }
+    }
+}
+
+/// One-pole coefficient for a time constant of `ms` milliseconds.
+fn smoothing_coefficient(ms: f32, frames_per_ms: f32) -> f32 {
+    let frames = ms.max(0.0) * frames_per_ms;
+    if frames < 1.0 {
+        return 0.0;
+    }
+    (-1.0 / frames).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> DuckingSettings {
+        DuckingSettings {
+            enabled: true,
+            depth_db: -12.0,
+            attack_ms: 0.0,
+            hold_ms: 1.0,
+            release_ms: 0.0,
+        }
+    }
+
+    #[test]
+    fn keyed_chunks_are_attenuated_by_depth() {
+        let mut ducker = Ducker::new();
+        let mut samples = vec![1.0_f32; 8];
+        ducker.process(&settings(), true, &mut samples, 2, 1000);
+        let expected = 10.0_f32.powf(-12.0 / 20.0);
+        assert!(samples.iter().all(|s| (s - expected).abs() < 1e-6));
+    }
+
+    #[test]
+    fn gain_holds_then_recovers_after_key_drops() {
+        let mut ducker = Ducker::new();
+        let mut samples = vec![1.0_f32; 2];
+        ducker.process(&settings(), true, &mut samples, 2, 1000);
+
+        let mut samples = vec![1.0_f32; 4];
+        ducker.process(&settings(), false, &mut samples, 2, 1000);
+        assert!(samples[0] < 0.5);
+        assert_eq!(samples[2], 1.0);
+    }
+
+    #[test]
+    fn disabled_ducking_leaves_samples_untouched() {
+        let mut ducker = Ducker::new();
+        let mut samples = vec![0.5_f32; 4];
+        let disabled = DuckingSettings::default();
+        ducker.process(&disabled, true, &mut samples, 2, 48_000);
+        assert_eq!(samples, vec![0.5; 4]);
+    }
+}
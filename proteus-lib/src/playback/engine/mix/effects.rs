@@ -1,7 +1,48 @@
 //! DSP effect-chain helpers for the mix runtime.
 
+use log::warn;
+
+use crate::container::impulse_response::read_impulse_response_attachments;
+use crate::container::prot::Prot;
 use crate::dsp::effects::{AudioEffect, EffectContext};
 
+/// Read the impulse response attachments `effects` load from `prot` into
+/// `context`, waiting for them while the container still downloads.
+///
+/// Attachments already in `context` are not read again.
+pub(super) fn read_effect_attachments(
+    prot: &Prot,
+    effects: &[AudioEffect],
+    context: &mut EffectContext,
+) {
+    let names: Vec<String> = effects
+        .iter()
+        .filter_map(AudioEffect::as_convolution_reverb)
+        .filter_map(|effect| effect.impulse_response_attachment(context))
+        .filter(|name| !context.impulse_response_attachments().contains(name))
+        .collect();
+    let Some(container_path) = prot.get_container_path() else {
+        return;
+    };
+    if names.is_empty() {
+        return;
+    }
+    let mut attachments = context.impulse_response_attachments().clone();
+    match read_impulse_response_attachments(
+        &container_path,
+        prot.download().as_ref(),
+        &names,
+        prot.parse_limits().max_attachment_bytes,
+        &mut attachments,
+    ) {
+        Ok(()) => context.set_impulse_response_attachments(attachments),
+        Err(err) => warn!(
+            "failed to read impulse response attachments from {}: {}",
+            container_path, err
+        ),
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct EffectEnableFade {
     current_mix: f32,
@@ -85,6 +126,30 @@ pub(super) fn run_effect_chain(
     scratch_a: &mut Vec<f32>,
     scratch_b: &mut Vec<f32>,
     enable_fades: Option<&mut [Option<EffectEnableFade>]>,
+) {
+    run_effect_chain_observed(
+        effects,
+        input,
+        context,
+        drain,
+        (scratch_a, scratch_b),
+        enable_fades,
+        None,
+    );
+}
+
+/// Per-effect callback receiving the effect index, the effect, and its output.
+pub(super) type EffectObserver<'a> = &'a mut dyn FnMut(usize, &AudioEffect, &[f32]);
+
+/// [`run_effect_chain`] that also reports each effect's output to `observer`.
+pub(super) fn run_effect_chain_observed(
+    effects: &mut [AudioEffect],
+    input: &[f32],
+    context: &EffectContext,
+    drain: bool,
+    (scratch_a, scratch_b): (&mut Vec<f32>, &mut Vec<f32>),
+    enable_fades: Option<&mut [Option<EffectEnableFade>]>,
+    mut observer: Option<EffectObserver<'_>>,
 ) {
     scratch_a.clear();
     scratch_a.extend_from_slice(input);
@@ -102,9 +167,12 @@ pub(super) fn run_effect_chain(
                     if !fade.target_enabled() {
                         effect.reset_state();
                     }
-                    set_audio_effect_enabled(effect, fade.target_enabled());
+                    effect.set_enabled(fade.target_enabled());
                     fades[index] = None;
                 }
+                if let Some(observer) = observer.as_mut() {
+                    observer(index, effect, scratch_a);
+                }
                 continue;
             }
         }
@@ -112,10 +180,17 @@ pub(super) fn run_effect_chain(
         scratch_b.clear();
         effect.process_into(scratch_a, scratch_b, context, drain);
         std::mem::swap(scratch_a, scratch_b);
+        if let Some(observer) = observer.as_mut() {
+            observer(index, effect, scratch_a);
+        }
     }
     // scratch_a holds the final processed output.
 }
 
+/// Blend an effect's output into its input while it fades in or out.
+///
+/// Outside a drain both sides are the same length; a drained tail has no
+/// dry counterpart, so the shorter side is padded with silence.
 fn crossfade_enabled_output(
     dry: &mut Vec<f32>,
     wet: &[f32],
@@ -130,50 +205,19 @@ fn crossfade_enabled_output(
         let wet_mix = fade.next_mix();
         let dry_mix = 1.0 - wet_mix;
         let frame_end = (frame_start + channels).min(total_len);
-        for sample_index in frame_start..frame_end {
+        for (offset, sample) in dry[frame_start..frame_end].iter_mut().enumerate() {
+            let sample_index = frame_start + offset;
             let dry_sample = if sample_index < original_dry_len {
-                dry[sample_index]
+                *sample
             } else {
                 0.0
             };
             let wet_sample = wet.get(sample_index).copied().unwrap_or(0.0);
-            dry[sample_index] = (dry_sample * dry_mix) + (wet_sample * wet_mix);
+            *sample = (dry_sample * dry_mix) + (wet_sample * wet_mix);
         }
     }
 }
 
-fn set_audio_effect_enabled(effect: &mut AudioEffect, enabled: bool) {
-    match effect {
-        AudioEffect::Gain(effect) => effect.enabled = enabled,
-        AudioEffect::Pan(effect) => effect.enabled = enabled,
-        AudioEffect::Distortion(effect) => effect.enabled = enabled,
-        AudioEffect::DelayReverb(effect) => effect.enabled = enabled,
-        AudioEffect::DiffusionReverb(effect) => effect.enabled = enabled,
-        AudioEffect::ConvolutionReverb(effect) => effect.enabled = enabled,
-        AudioEffect::LowPassFilter(effect) => effect.enabled = enabled,
-        AudioEffect::HighPassFilter(effect) => effect.enabled = enabled,
-        AudioEffect::Compressor(effect) => effect.enabled = enabled,
-        AudioEffect::Limiter(effect) => effect.enabled = enabled,
-        AudioEffect::MultibandEq(effect) => effect.enabled = enabled,
-    }
-}
-
-pub(super) fn audio_effect_enabled(effect: &AudioEffect) -> bool {
-    match effect {
-        AudioEffect::Gain(effect) => effect.enabled,
-        AudioEffect::Pan(effect) => effect.enabled,
-        AudioEffect::Distortion(effect) => effect.enabled,
-        AudioEffect::DelayReverb(effect) => effect.enabled,
-        AudioEffect::DiffusionReverb(effect) => effect.enabled,
-        AudioEffect::ConvolutionReverb(effect) => effect.enabled,
-        AudioEffect::LowPassFilter(effect) => effect.enabled,
-        AudioEffect::HighPassFilter(effect) => effect.enabled,
-        AudioEffect::Compressor(effect) => effect.enabled,
-        AudioEffect::Limiter(effect) => effect.enabled,
-        AudioEffect::MultibandEq(effect) => effect.enabled,
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -277,7 +321,66 @@ mod tests {
             prev = frame[0];
         }
         assert!(largest_delta < 0.01);
-        assert!(!audio_effect_enabled(&effects[0]));
+        assert!(!effects[0].is_enabled());
         assert!(enable_fades[0].is_none());
     }
+
+    #[test]
+    fn effect_attachments_are_read_before_the_reverb_loads() {
+        let path = crate::test_fixtures::demo_container_with_impulse_response("mix_effects", "ir");
+        let prot = Prot::try_new(&path).unwrap();
+        let reverb: crate::dsp::effects::ConvolutionReverbEffect =
+            serde_json::from_str(r#"{"enabled": true, "impulse_response": "attachment:hall.wav"}"#)
+                .unwrap();
+        let mut effects = [AudioEffect::ConvolutionReverb(reverb)];
+        let mut context = EffectContext::new(48_000, 2, Some(path.clone()), None, -60.0).unwrap();
+
+        read_effect_attachments(&prot, &effects, &mut context);
+        std::fs::remove_file(&path).unwrap();
+        assert!(context.impulse_response_attachments().contains("hall.wav"));
+
+        effects[0].warm_up(&context);
+        let reverb = effects[0].as_convolution_reverb().unwrap();
+        assert!(reverb.metrics().is_some());
+    }
+
+    #[test]
+    fn effect_attachments_are_read_through_a_download() {
+        use crate::container::limits::ParseLimits;
+        use crate::container::play_settings::ParseMode;
+        use crate::tools::progressive::{BufferingState, ProgressiveDownload};
+
+        let source =
+            crate::test_fixtures::demo_container_with_impulse_response("mix_effects", "src");
+        let bytes = std::fs::read(&source).unwrap();
+        std::fs::remove_file(&source).unwrap();
+        let path = crate::test_fixtures::temp_container_path("mix_effects", "download");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        let mut chunks = bytes.chunks(32 * 1024);
+        while download.state() == BufferingState::Buffering {
+            download.append(chunks.next().unwrap()).unwrap();
+        }
+        let prot = Prot::try_new_progressive(
+            &download.file(),
+            ParseMode::Lenient,
+            &ParseLimits::unlimited(),
+        )
+        .unwrap();
+        let reverb: crate::dsp::effects::ConvolutionReverbEffect =
+            serde_json::from_str(r#"{"enabled": true, "impulse_response": "attachment:hall.wav"}"#)
+                .unwrap();
+        let effects = [AudioEffect::ConvolutionReverb(reverb)];
+        let mut context = EffectContext::new(48_000, 2, Some(path.clone()), None, -60.0).unwrap();
+
+        // The appended attachment follows the clusters, so it arrives last.
+        read_effect_attachments(&prot, &effects, &mut context);
+        assert!(!context.impulse_response_attachments().contains("hall.wav"));
+
+        for chunk in chunks {
+            download.append(chunk).unwrap();
+        }
+        read_effect_attachments(&prot, &effects, &mut context);
+        assert!(context.impulse_response_attachments().contains("hall.wav"));
+        std::fs::remove_file(path).unwrap();
+    }
 }
@@ -3,21 +3,34 @@
 //! This module exposes the public API used by `PlayerEngine` and delegates
 //! implementation details to focused submodules:
 //! - `types`: argument and transition structs.
+//! - `automation`: container effect automation scheduled at sample positions.
 //! - `effects`: effect-chain processing helpers.
 //! - `debug`: debug-only naming helpers.
-//! - `runner`: long-running mix loop and public entrypoint wrapper.
+//! - `runner`: long-running mix loop, public entrypoint wrapper, and offline renderer.
 //! - `track_stage` / `output_stage`: staged helpers used by the runner.
+//! - `ducking`: main-mix ducking while one-shots play.
+//! - `chain_mix`: parallel blend of the dry mix with the effect-chain output.
+//! - `overload`: hysteresis for degrading quality while the chain overruns.
+//! - `output_queue`: millisecond-bounded queue feeding the playback thread.
 
+mod automation;
 mod buffer_mixer;
+mod chain_mix;
 mod cover_map;
 mod debug;
 mod decoder_events;
+mod ducking;
 mod effects;
+mod output_queue;
 mod output_stage;
+mod overload;
 mod runner;
 mod track_stage;
 mod types;
 
+pub(crate) use buffer_mixer::SHUFFLE_CROSSFADE_MS;
+pub use output_queue::OutputReceiver;
+pub(crate) use runner::offline::{render_offline, DecodeThreading, OfflineRun};
 pub use runner::spawn_mix_thread;
 pub use types::{EffectParameter, EffectSettingsCommand, MixThreadArgs};
 
@@ -0,0 +1,236 @@
+//! Bounded output queue between the mix thread and the playback thread.
+//!
+//! The queue is sized in milliseconds of audio rather than in chunks, so how
+//! far the mix thread may run ahead of the sink no longer depends on how its
+//! output happens to be sliced. A larger capacity buys underrun margin at the
+//! cost of control latency; a smaller one does the opposite.
+//!
+//! A chunk is always admitted into an empty queue, so chunks longer than the
+//! capacity still make progress one at a time.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{RecvError, SendError, TryRecvError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+
+use rodio::buffer::SamplesBuffer;
+
+use crate::playback::mutex_policy::{lock_recoverable, wait_recoverable};
+
+type Chunk = (SamplesBuffer, f64);
+
+/// Create a queue that holds up to `capacity_ms` of mixed audio.
+pub(crate) fn output_queue(capacity_ms: f32) -> (OutputSender, OutputReceiver) {
+    let shared = Arc::new(QueueShared {
+        capacity_ms: f64::from(capacity_ms.max(0.0)),
+        state: Mutex::new(QueueState {
+            chunks: VecDeque::new(),
+            queued_ms: 0.0,
+            sender_alive: true,
+            receiver_alive: true,
+        }),
+        changed: Condvar::new(),
+    });
+    (
+        OutputSender {
+            shared: shared.clone(),
+        },
+        OutputReceiver { shared },
+    )
+}
+
+struct QueueShared {
+    capacity_ms: f64,
+    state: Mutex<QueueState>,
+    changed: Condvar,
+}
+
+struct QueueState {
+    chunks: VecDeque<Chunk>,
+    queued_ms: f64,
+    sender_alive: bool,
+    receiver_alive: bool,
+}
+
+impl QueueShared {
+    /// Recoverable poison policy: chunks are pushed and popped whole, so the
+    /// queue and its duration total never disagree.
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        lock_recoverable(
+            &self.state,
+            "mix output queue",
+            "chunks are pushed and popped whole, so the queue stays consistent",
+        )
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, QueueState>) -> MutexGuard<'a, QueueState> {
+        wait_recoverable(
+            &self.changed,
+            state,
+            "mix output queue",
+            "chunks are pushed and popped whole, so the queue stays consistent",
+        )
+    }
+}
+
+/// Mix-thread side of the output queue.
+pub(crate) struct OutputSender {
+    shared: Arc<QueueShared>,
+}
+
+impl OutputSender {
+    /// Queue `chunk`, blocking while the queue is at capacity.
+    ///
+    /// Fails once the receiver has been dropped.
+    pub(crate) fn send(&self, chunk: Chunk) -> Result<(), SendError<Chunk>> {
+        let chunk_ms = chunk.1 * 1000.0;
+        let mut state = self.shared.lock();
+        loop {
+            if !state.receiver_alive {
+                return Err(SendError(chunk));
+            }
+            if state.chunks.is_empty() || state.queued_ms + chunk_ms <= self.shared.capacity_ms {
+                break;
+            }
+            state = self.shared.wait(state);
+        }
+        state.queued_ms += chunk_ms;
+        state.chunks.push_back(chunk);
+        drop(state);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+
+    /// Milliseconds of audio queued and not yet received.
+    pub(crate) fn queued_ms(&self) -> f64 {
+        self.shared.lock().queued_ms
+    }
+
+    /// Configured queue capacity in milliseconds.
+    pub(crate) fn capacity_ms(&self) -> f64 {
+        self.shared.capacity_ms
+    }
+}
+
+impl Drop for OutputSender {
+    fn drop(&mut self) {
+        self.shared.lock().sender_alive = false;
+        self.shared.changed.notify_all();
+    }
+}
+
+/// Receiving end of a playback engine's mixed output.
+///
+/// Yields `(buffer, duration_seconds)` chunks until the mix thread finishes.
+pub struct OutputReceiver {
+    shared: Arc<QueueShared>,
+}
+
+impl OutputReceiver {
+    /// Block until the next chunk arrives; `Err` once the mix thread has
+    /// finished and the queue is empty.
+    pub fn recv(&self) -> Result<Chunk, RecvError> {
+        let mut state = self.shared.lock();
+        loop {
+            if let Some(chunk) = pop_chunk(&mut state) {
+                drop(state);
+                self.shared.changed.notify_all();
+                return Ok(chunk);
+            }
+            if !state.sender_alive {
+                return Err(RecvError);
+            }
+            state = self.shared.wait(state);
+        }
+    }
+
+    /// Take the next chunk if one is queued, without blocking.
+    pub fn try_recv(&self) -> Result<Chunk, TryRecvError> {
+        let mut state = self.shared.lock();
+        match pop_chunk(&mut state) {
+            Some(chunk) => {
+                drop(state);
+                self.shared.changed.notify_all();
+                Ok(chunk)
+            }
+            None if state.sender_alive => Err(TryRecvError::Empty),
+            None => Err(TryRecvError::Disconnected),
+        }
+    }
+}
+
+impl Iterator for OutputReceiver {
+    type Item = Chunk;
+
+    fn next(&mut self) -> Option<Chunk> {
+        self.recv().ok()
+    }
+}
+
+impl Drop for OutputReceiver {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.receiver_alive = false;
+        state.chunks.clear();
+        state.queued_ms = 0.0;
+        drop(state);
+        self.shared.changed.notify_all();
+    }
+}
+
+fn pop_chunk(state: &mut QueueState) -> Option<Chunk> {
+    let chunk = state.chunks.pop_front()?;
+    state.queued_ms = (state.queued_ms - chunk.1 * 1000.0).max(0.0);
+    Some(chunk)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    fn chunk_ms(ms: f64) -> Chunk {
+        (
+            SamplesBuffer::new(1, 1000, vec![0.0; ms as usize]),
+            ms / 1000.0,
+        )
+    }
+
+    #[test]
+    fn sender_blocks_once_the_millisecond_budget_is_used() {
+        let (tx, rx) = output_queue(20.0);
+        tx.send(chunk_ms(10.0)).unwrap();
+        tx.send(chunk_ms(10.0)).unwrap();
+        assert_eq!(tx.queued_ms(), 20.0);
+
+        let sender = thread::spawn(move || {
+            tx.send(chunk_ms(10.0)).unwrap();
+            tx
+        });
+        thread::sleep(Duration::from_millis(20));
+        assert!(!sender.is_finished());
+        rx.recv().unwrap();
+        let tx = sender.join().unwrap();
+        assert_eq!(tx.queued_ms(), 20.0);
+    }
+
+    #[test]
+    fn oversized_chunk_is_admitted_into_an_empty_queue() {
+        let (tx, rx) = output_queue(5.0);
+        tx.send(chunk_ms(50.0)).unwrap();
+        assert_eq!(rx.recv().unwrap().1, 0.05);
+    }
+
+    #[test]
+    fn dropping_either_end_disconnects_the_other() {
+        let (tx, rx) = output_queue(50.0);
+        tx.send(chunk_ms(10.0)).unwrap();
+        drop(tx);
+        assert!(rx.recv().is_ok());
+        assert!(rx.recv().is_err());
+
+        let (tx, rx) = output_queue(50.0);
+        drop(rx);
+        assert!(tx.send(chunk_ms(10.0)).is_err());
+    }
+}
@@ -1,24 +1,32 @@
 //! Output-stage DSP helpers for the mix runtime.
 
 use rodio::buffer::SamplesBuffer;
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use super::output_queue::OutputSender;
 
 /// Send produced samples over the mix thread output channel.
 pub(super) enum SendStatus {
     Sent,
     Empty,
     Disconnected,
+    /// A stop or seek was requested before every slice was sent.
+    Aborted,
 }
 
 /// Send produced samples over the mix thread output channel.
 ///
 /// When `output_slice_samples` is `Some(n)`, the buffer is sliced into chunks
 /// of at most `n` samples (frame-aligned) and each slice is sent individually.
-/// The bounded `sync_channel(1)` between mix and worker threads naturally
-/// gates each send, providing per-slice backpressure. When `None`, the entire
+/// The millisecond-bounded output queue gates each send, providing
+/// per-slice backpressure. When `None`, the entire
 /// buffer is sent as a single chunk (the default behavior).
+///
+/// `abort` is checked before each slice so a stop request drops the rest of
+/// the batch instead of queueing it behind the request.
 pub(super) fn send_samples(
-    sender: &mpsc::SyncSender<(SamplesBuffer, f64)>,
+    sender: &OutputSender,
+    abort: &AtomicBool,
     input_channels: u16,
     sample_rate: u32,
     samples: &[f32],
@@ -33,6 +41,9 @@ pub(super) fn send_samples(
         .max(input_channels as usize);
 
     for chunk in samples.chunks(max_chunk) {
+        if abort.load(Ordering::SeqCst) {
+            return SendStatus::Aborted;
+        }
         let length_in_seconds = chunk.len() as f64 / sample_rate as f64 / input_channels as f64;
         let samples_buffer = SamplesBuffer::new(input_channels, sample_rate, chunk.to_vec());
 
@@ -46,29 +57,30 @@ pub(super) fn send_samples(
 
 #[cfg(test)]
 mod tests {
+    use super::super::output_queue::output_queue;
     use super::*;
 
     #[test]
     fn send_samples_returns_empty_for_empty_buffers() {
-        let (tx, _rx) = mpsc::sync_channel(1);
-        let status = send_samples(&tx, 2, 48_000, &[], None);
+        let (tx, _rx) = output_queue(1000.0);
+        let status = send_samples(&tx, &AtomicBool::new(false), 2, 48_000, &[], None);
         assert!(matches!(status, SendStatus::Empty));
     }
 
     #[test]
     fn send_samples_returns_disconnected_when_receiver_is_gone() {
-        let (tx, rx) = mpsc::sync_channel(1);
+        let (tx, rx) = output_queue(1000.0);
         drop(rx);
-        let status = send_samples(&tx, 2, 48_000, &[0.1, -0.1], None);
+        let status = send_samples(&tx, &AtomicBool::new(false), 2, 48_000, &[0.1, -0.1], None);
         assert!(matches!(status, SendStatus::Disconnected));
     }
 
     #[test]
     fn send_samples_sliced_produces_multiple_chunks() {
-        let (tx, rx) = mpsc::sync_channel(16);
+        let (tx, rx) = output_queue(1000.0);
         // 8 samples, stereo, slice into groups of 4 (2 frames each)
         let samples = [0.1, -0.1, 0.2, -0.2, 0.3, -0.3, 0.4, -0.4];
-        let status = send_samples(&tx, 2, 48_000, &samples, Some(4));
+        let status = send_samples(&tx, &AtomicBool::new(false), 2, 48_000, &samples, Some(4));
         assert!(matches!(status, SendStatus::Sent));
 
         let (_chunk1, dur1) = rx.recv().unwrap();
@@ -83,12 +95,21 @@ mod tests {
 
     #[test]
     fn send_samples_none_slice_sends_single_chunk() {
-        let (tx, rx) = mpsc::sync_channel(16);
+        let (tx, rx) = output_queue(1000.0);
         let samples = [0.1, -0.1, 0.2, -0.2];
-        let status = send_samples(&tx, 2, 48_000, &samples, None);
+        let status = send_samples(&tx, &AtomicBool::new(false), 2, 48_000, &samples, None);
         assert!(matches!(status, SendStatus::Sent));
 
         let (_chunk, _dur) = rx.recv().unwrap();
         assert!(rx.try_recv().is_err());
     }
+
+    #[test]
+    fn send_samples_stops_slicing_once_aborted() {
+        let (tx, rx) = output_queue(1000.0);
+        let abort = AtomicBool::new(true);
+        let status = send_samples(&tx, &abort, 2, 48_000, &[0.1, -0.1, 0.2, -0.2], Some(2));
+        assert!(matches!(status, SendStatus::Aborted));
+        assert!(rx.try_recv().is_err());
+    }
 }
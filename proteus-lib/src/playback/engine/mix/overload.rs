@@ -0,0 +1,167 @@
+//! Hysteresis for stepping quality down and back up under sustained load.
+
+use super::super::state::{DegradationStep, OverloadPolicy};
+
+/// A step taken by [`OverloadGovernor::observe`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct QualityChange {
+    pub(super) step: DegradationStep,
+    /// `true` when `step` was applied, `false` when it was undone.
+    pub(super) degraded: bool,
+    /// How long the triggering condition lasted, in milliseconds.
+    pub(super) after_ms: f64,
+}
+
+/// Tracks how long the chain has been overloaded or idle and decides when
+/// to change the degradation level.
+#[derive(Debug, Clone, Default)]
+pub(super) struct OverloadGovernor {
+    level: usize,
+    overloaded_s: f64,
+    headroom_s: f64,
+}
+
+impl OverloadGovernor {
+    pub(super) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of steps currently applied.
+    pub(super) fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Return to full quality without reporting a change, e.g. when the
+    /// chain it was degrading has been replaced.
+    pub(super) fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Feed the load of one chunk lasting `audio_s` seconds.
+    ///
+    /// Returns the step to apply or undo once a condition has lasted long
+    /// enough. The timers restart after every change, so consecutive steps are
+    /// spaced by at least the policy's hold time.
+    pub(super) fn observe(
+        &mut self,
+        policy: &OverloadPolicy,
+        load: f64,
+        audio_s: f64,
+    ) -> Option<QualityChange> {
+        if !policy.enabled {
+            self.overloaded_s = 0.0;
+            self.headroom_s = 0.0;
+            return self.undo(0.0);
+        }
+        if load > policy.degrade_load {
+            self.overloaded_s += audio_s;
+            self.headroom_s = 0.0;
+        } else if load < policy.recover_load {
+            self.headroom_s += audio_s;
+            self.overloaded_s = 0.0;
+        } else {
+            self.overloaded_s = 0.0;
+            self.headroom_s = 0.0;
+        }
+
+        if self.overloaded_s * 1000.0 >= f64::from(policy.degrade_after_ms.max(0.0)) {
+            let step = *DegradationStep::ALL.get(self.level)?;
+            let after_ms = self.overloaded_s * 1000.0;
+            self.level += 1;
+            self.overloaded_s = 0.0;
+            return Some(QualityChange {
+                step,
+                degraded: true,
+                after_ms,
+            });
+        }
+        if self.headroom_s * 1000.0 >= f64::from(policy.recover_after_ms.max(0.0)) {
+            let after_ms = self.headroom_s * 1000.0;
+            self.headroom_s = 0.0;
+            return self.undo(after_ms);
+        }
+        None
+    }
+
+    fn undo(&mut self, after_ms: f64) -> Option<QualityChange> {
+        self.level = self.level.checked_sub(1)?;
+        Some(QualityChange {
+            step: DegradationStep::ALL[self.level],
+            degraded: false,
+            after_ms,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> OverloadPolicy {
+        OverloadPolicy {
+            enabled: true,
+            degrade_after_ms: 100.0,
+            recover_after_ms: 200.0,
+            ..OverloadPolicy::default()
+        }
+    }
+
+    #[test]
+    fn sustained_overload_steps_down_in_order() {
+        let mut governor = OverloadGovernor::new();
+        assert_eq!(governor.observe(&policy(), 1.5, 0.05), None);
+        let change = governor.observe(&policy(), 1.5, 0.05).unwrap();
+        assert_eq!(change.step, DegradationStep::ShortenReverbTail);
+        assert!(change.degraded);
+        assert!((change.after_ms - 100.0).abs() < 1e-9);
+
+        let steps: Vec<DegradationStep> = (0..10)
+            .filter_map(|_| governor.observe(&policy(), 1.5, 0.05))
+            .map(|change| change.step)
+            .collect();
+        assert_eq!(
+            steps,
+            [
+                DegradationStep::LargerConvolutionBlocks,
+                DegradationStep::BypassHeaviestEffect
+            ]
+        );
+        assert_eq!(governor.level(), 3);
+    }
+
+    #[test]
+    fn recovery_needs_sustained_headroom() {
+        let mut governor = OverloadGovernor::new();
+        governor.observe(&policy(), 1.5, 0.1).unwrap();
+        // Load between the thresholds neither degrades nor recovers.
+        for _ in 0..10 {
+            assert_eq!(governor.observe(&policy(), 0.7, 0.1), None);
+        }
+        assert_eq!(governor.observe(&policy(), 0.2, 0.1), None);
+        assert_eq!(governor.observe(&policy(), 1.5, 0.05), None);
+        assert_eq!(governor.observe(&policy(), 0.2, 0.1), None);
+        let change = governor.observe(&policy(), 0.2, 0.1).unwrap();
+        assert_eq!(change.step, DegradationStep::ShortenReverbTail);
+        assert!(!change.degraded);
+        assert_eq!(governor.level(), 0);
+    }
+
+    #[test]
+    fn disabling_the_policy_unwinds_every_step() {
+        let mut governor = OverloadGovernor::new();
+        governor.observe(&policy(), 1.5, 0.1).unwrap();
+        governor.observe(&policy(), 1.5, 0.1).unwrap();
+        let disabled = OverloadPolicy::default();
+        let undone: Vec<DegradationStep> = (0..3)
+            .filter_map(|_| governor.observe(&disabled, 1.5, 0.1))
+            .map(|change| change.step)
+            .collect();
+        assert_eq!(
+            undone,
+            [
+                DegradationStep::LargerConvolutionBlocks,
+                DegradationStep::ShortenReverbTail
+            ]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! Adaptive output slicing around transport events.
+//!
+//! DSP runs in large batches so convolution stays efficient, and every
+//! queued batch delays whatever the listener does next. Shortly after a run
+//! starts (playback start or seek), approaching a source boundary, and once
+//! the mix has finished, batches are sent in fine slices instead. Elsewhere
+//! the configured `output_slice_ms` (or the full batch) is kept, so
+//! mid-stream throughput is unchanged.
+
+use super::state::MixLoopState;
+
+/// Slice duration used near transport events.
+const FINE_SLICE_MS: f32 = 10.0;
+
+/// How far after a run start, or ahead of a boundary, fine slicing applies.
+const APPROACH_MS: f32 = 120.0;
+
+/// Interleaved-sample span of the chunk being sent, relative to the run start.
+#[derive(Debug, Clone, Copy)]
+struct ChunkSpan {
+    start: usize,
+    end: usize,
+}
+
+/// Slice size, in interleaved samples, for the chunk just processed.
+///
+/// `chunk_len` is the length of that chunk; the mix loop has already
+/// counted it in `running_count`.
+pub(super) fn output_slice_samples(state: &MixLoopState, chunk_len: usize) -> Option<usize> {
+    let (base_ms, adaptive) = {
+        let settings = state.lock_buffer_settings_recoverable();
+        (settings.output_slice_ms, settings.adaptive_chunking)
+    };
+    let sample_rate = state.audio_info.sample_rate;
+    let channels = state.audio_info.channels.max(1) as usize;
+    let span = ChunkSpan {
+        start: state.running_count.saturating_sub(chunk_len),
+        end: state.running_count,
+    };
+    let approach = ms_to_samples(APPROACH_MS, sample_rate, channels);
+    let near = adaptive
+        && near_transport_event(span, approach, state.buffer_mixer.mix_finished(), |s, e| {
+            state.buffer_mixer.has_window_edge_within(s, e)
+        });
+    slice_ms(base_ms, near).map(|ms| ms_to_samples(ms, sample_rate, channels).max(channels))
+}
+
+/// True when the chunk is close enough to a transport event to slice finely.
+fn near_transport_event(
+    span: ChunkSpan,
+    approach: usize,
+    mix_finished: bool,
+    has_edge_within: impl Fn(usize, usize) -> bool,
+) -> bool {
+    mix_finished || span.start < approach || has_edge_within(span.start, span.end + approach)
+}
+
+/// Pick the slice duration: the finer of the configured slice and
+/// [`FINE_SLICE_MS`] near events, the configured slice elsewhere.
+fn slice_ms(base_ms: Option<f32>, near_event: bool) -> Option<f32> {
+    if !near_event {
+        return base_ms;
+    }
+    Some(base_ms.map_or(FINE_SLICE_MS, |ms| ms.min(FINE_SLICE_MS)))
+}
+
+fn ms_to_samples(ms: f32, sample_rate: u32, channels: usize) -> usize {
+    let frames = (sample_rate as f32 * ms / 1000.0).ceil() as usize;
+    frames * channels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APPROACH: usize = 1000;
+
+    fn span(start: usize, end: usize) -> ChunkSpan {
+        ChunkSpan { start, end }
+    }
+
+    #[test]
+    fn chunks_right_after_a_run_start_are_sliced_finely() {
+        assert!(near_transport_event(
+            span(0, 500),
+            APPROACH,
+            false,
+            |_, _| false
+        ));
+        assert!(!near_transport_event(
+            span(4000, 4500),
+            APPROACH,
+            false,
+            |_, _| false
+        ));
+    }
+
+    #[test]
+    fn boundary_ahead_of_the_chunk_triggers_fine_slices() {
+        let boundary = 5200;
+        let has_edge = |s: usize, e: usize| boundary >= s && boundary < e;
+        assert!(near_transport_event(
+            span(4000, 4500),
+            APPROACH,
+            false,
+            has_edge
+        ));
+        assert!(!near_transport_event(
+            span(2000, 2500),
+            APPROACH,
+            false,
+            has_edge
+        ));
+    }
+
+    #[test]
+    fn finished_mix_is_always_sliced_finely() {
+        assert!(near_transport_event(
+            span(9000, 9500),
+            APPROACH,
+            true,
+            |_, _| false
+        ));
+    }
+
+    #[test]
+    fn slice_ms_keeps_the_base_away_from_events() {
+        assert_eq!(slice_ms(None, false), None);
+        assert_eq!(slice_ms(Some(30.0), false), Some(30.0));
+        assert_eq!(slice_ms(None, true), Some(FINE_SLICE_MS));
+        assert_eq!(slice_ms(Some(30.0), true), Some(FINE_SLICE_MS));
+        assert_eq!(slice_ms(Some(5.0), true), Some(5.0));
+    }
+}
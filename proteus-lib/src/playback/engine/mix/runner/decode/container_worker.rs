@@ -1,94 +1,187 @@
 //! Container demux decode worker (single demuxer feeding multiple track decoders).
 
-use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
 use std::sync::atomic::Ordering;
-use std::sync::mpsc;
 use std::sync::Arc;
-use std::thread;
-use std::thread::JoinHandle;
 use std::time::Instant;
 
-use log::{error, warn};
+use log::{debug, error, warn};
 use symphonia::core::codecs::{Decoder, DecoderOptions};
 use symphonia::core::errors::Error;
-use symphonia::core::formats::{SeekMode, SeekTo};
+use symphonia::core::formats::{FormatReader, SeekMode, SeekTo};
 use symphonia::core::units::{Time, TimeBase};
 
+use crate::container::prot::TrackKeys;
+use crate::playback::engine::decode_pool::{DecodeJobHandle, DecodePool};
+use crate::playback::engine::reader_cache::{ContainerReader, ReaderCache};
+use crate::tools::progressive::ProgressiveFile;
+use crate::tools::stdin::is_stdin_path;
+
 use super::super::super::buffer_mixer::{DecodeBackpressure, SourceKey};
-use super::super::super::decoder_events::DecodeWorkerEvent;
-use super::{decode_and_forward_packet, packet_ts_seconds, ForwardInfra, StartupLog};
+use super::super::super::decoder_events::{DecodeEventSink, DecodeWorkerEvent};
+use super::lead_in::forward_lead_in;
+use super::looping::{decode_looping_source, LoopReader};
+use super::{
+    decode_and_forward_packet, packet_ts_seconds, DecodeStart, DecodeWorkerLinks, ForwardInfra,
+    StartupLog,
+};
+
+/// Container file a worker decodes, with the reader cache of the player that
+/// owns the worker, the content keys of its encrypted tracks, and the
+/// download it still arrives through, if any.
+#[derive(Debug, Clone)]
+pub(crate) struct ContainerSource {
+    pub path: String,
+    pub readers: ReaderCache,
+    pub track_keys: Arc<TrackKeys>,
+    pub download: Option<ProgressiveFile>,
+}
 
-/// Spawn a single demux decode worker that services multiple container track ids.
+/// Run a single demux decode worker that services multiple container track ids
+/// on `pool`.
 pub(crate) fn spawn_container_decode_worker(
-    file_path: String,
+    pool: &DecodePool,
+    container: ContainerSource,
     track_ids: Vec<u32>,
-    start_time: f64,
+    start: DecodeStart,
     channels: u8,
-    sender: mpsc::SyncSender<DecodeWorkerEvent>,
-    abort: Arc<std::sync::atomic::AtomicBool>,
-    decode_backpressure: Arc<DecodeBackpressure>,
-) -> JoinHandle<()> {
-    thread::spawn(move || {
+    links: DecodeWorkerLinks,
+) -> DecodeJobHandle {
+    pool.submit(move || {
         run_container_decode_worker(
-            file_path,
-            track_ids,
-            start_time,
+            &container,
+            &track_ids,
+            start,
             channels,
-            sender,
-            abort,
-            decode_backpressure,
+            &links.sender,
+            &links.abort,
+            &links.decode_backpressure,
         )
     })
 }
 
-fn run_container_decode_worker(
-    file_path: String,
-    track_ids: Vec<u32>,
-    start_time: f64,
+/// Decode the requested container tracks on the calling thread until the
+/// stream ends, the sink rejects an event, or `abort` is set.
+///
+/// The reader and decoders come from the container's reader cache when a
+/// previous worker left them there, and are returned to it unless reading
+/// failed.
+pub(crate) fn run_container_decode_worker(
+    container: &ContainerSource,
+    track_ids: &[u32],
+    start: DecodeStart,
     channels: u8,
-    sender: mpsc::SyncSender<DecodeWorkerEvent>,
-    abort: Arc<std::sync::atomic::AtomicBool>,
-    decode_backpressure: Arc<DecodeBackpressure>,
+    sender: &dyn DecodeEventSink,
+    abort: &std::sync::atomic::AtomicBool,
+    decode_backpressure: &DecodeBackpressure,
 ) {
     let startup_trace = Instant::now();
-    let wanted: HashSet<u32> = track_ids.iter().copied().collect();
-    let Some(mut format) = open_container_reader(&file_path, &track_ids, &sender) else {
+    let file_path = container.path.as_str();
+    let wanted: BTreeSet<u32> = track_ids.iter().copied().collect();
+    let reused = reuse_container_reader(container, start.seek_seconds, &wanted);
+    let seeked = reused.is_some();
+    let Some(reader) = reused.or_else(|| open_container_reader(container, track_ids, sender))
+    else {
         return;
     };
-    let Some((mut decoders, time_bases, sample_rates)) =
-        initialize_container_decoders(format.as_ref(), &wanted, &sender)
+    let ContainerReader {
+        mut format,
+        mut decoders,
+    } = reader;
+    let Some((time_bases, sample_rates)) =
+        initialize_container_decoders(format.as_ref(), &wanted, &mut decoders, sender)
     else {
-        finish_container_sources(&wanted, &sender);
+        finish_container_sources(&wanted, sender);
         return;
     };
 
-    seek_container_reader(format.as_mut(), start_time, &file_path, &sender, &decoders);
+    // Standard input cannot seek and is already at its start.
+    if !seeked && !is_stdin_path(file_path) {
+        if let Err(err) = seek_container_reader(format.as_mut(), start.seek_seconds, &decoders) {
+            report_seek_failure(file_path, &decoders, &err, sender);
+        }
+    }
     let infra = ForwardInfra {
         worker_label: "container",
-        sender: &sender,
-        decode_backpressure: decode_backpressure.as_ref(),
-        abort: abort.as_ref(),
+        sender,
+        decode_backpressure,
+        abort,
         startup_trace,
     };
-    decode_container_packets(
+    let reusable = decode_container_packets(
         format.as_mut(),
         &mut decoders,
         &time_bases,
         &sample_rates,
-        start_time,
+        start,
         channels,
         infra,
     );
-    finish_container_sources(&wanted, &sender);
+    if reusable && !is_stdin_path(file_path) {
+        container
+            .readers
+            .checkin(file_path, ContainerReader { format, decoders });
+    }
+    finish_container_sources(&wanted, sender);
+}
+
+/// Check out a cached reader for `container` and seek it in place to
+/// `start_time` on the lowest wanted track.
+///
+/// Returns `None` (dropping the reader) when nothing is cached or the
+/// in-place seek fails, so the caller falls back to a fresh open.
+fn reuse_container_reader(
+    container: &ContainerSource,
+    start_time: f64,
+    wanted: &BTreeSet<u32>,
+) -> Option<ContainerReader> {
+    let file_path = container.path.as_str();
+    let mut reader = container.readers.checkout(file_path)?;
+    let seek_track = wanted
+        .iter()
+        .copied()
+        .find(|track_id| reader.format.tracks().iter().any(|t| t.id == *track_id))?;
+    let time = Time::new(start_time.floor() as u64, start_time.fract());
+    match reader.format.seek(
+        SeekMode::Coarse,
+        SeekTo::Time {
+            time,
+            track_id: Some(seek_track),
+        },
+    ) {
+        Ok(_) => {
+            debug!(
+                "container worker reusing cached reader: source={}",
+                file_path
+            );
+            Some(reader)
+        }
+        Err(err) => {
+            debug!(
+                "cached container reader seek failed, re-opening: source={} err={}",
+                file_path, err
+            );
+            None
+        }
+    }
 }
 
 fn open_container_reader(
-    file_path: &str,
+    container: &ContainerSource,
     track_ids: &[u32],
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
-) -> Option<Box<dyn symphonia::core::formats::FormatReader>> {
-    match crate::tools::decode::get_reader(file_path) {
-        Ok(format) => Some(format),
+    sender: &dyn DecodeEventSink,
+) -> Option<ContainerReader> {
+    let file_path = container.path.as_str();
+    match crate::tools::decode::get_container_reader(
+        file_path,
+        &container.track_keys,
+        container.download.as_ref(),
+    ) {
+        Ok(format) => Some(ContainerReader {
+            format,
+            decoders: HashMap::new(),
+        }),
         Err(err) => {
             error!(
                 "container worker open failed: source={} err={}",
@@ -96,49 +189,53 @@ fn open_container_reader(
             );
             for track_id in track_ids {
                 let source_key = SourceKey::TrackId(*track_id);
-                let _ = sender.send(DecodeWorkerEvent::SourceError {
+                let _ = sender.send_event(DecodeWorkerEvent::SourceError {
                     source_key: source_key.clone(),
                     recoverable: false,
                     message: err.to_string(),
                 });
-                let _ = sender.send(DecodeWorkerEvent::SourceFinished { source_key });
+                let _ = sender.send_event(DecodeWorkerEvent::SourceFinished { source_key });
             }
             None
         }
     }
 }
 
-type ContainerDecoderMaps = (
-    HashMap<u32, Box<dyn Decoder>>,
-    HashMap<u32, Option<TimeBase>>,
-    HashMap<u32, Option<u32>>,
-);
+type ContainerTimingMaps = (HashMap<u32, Option<TimeBase>>, HashMap<u32, Option<u32>>);
 
+/// Keep decoders for the `wanted` tracks, reusing any already in `decoders`.
 fn initialize_container_decoders(
-    format: &dyn symphonia::core::formats::FormatReader,
-    wanted: &HashSet<u32>,
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
-) -> Option<ContainerDecoderMaps> {
-    let mut decoders = HashMap::new();
+    format: &dyn FormatReader,
+    wanted: &BTreeSet<u32>,
+    decoders: &mut HashMap<u32, Box<dyn Decoder>>,
+    sender: &dyn DecodeEventSink,
+) -> Option<ContainerTimingMaps> {
     let mut time_bases = HashMap::new();
     let mut sample_rates = HashMap::new();
 
+    decoders.retain(|track_id, _| wanted.contains(track_id));
     for track_id in wanted.iter().copied() {
         let Some(track) = format.tracks().iter().find(|track| track.id == track_id) else {
+            decoders.remove(&track_id);
             continue;
         };
-        let dec_opts: DecoderOptions = Default::default();
-        if let Ok(decoder) = symphonia::default::get_codecs().make(&track.codec_params, &dec_opts) {
-            decoders.insert(track_id, decoder);
-            time_bases.insert(track_id, track.codec_params.time_base);
-            sample_rates.insert(track_id, track.codec_params.sample_rate);
+        if let Entry::Vacant(slot) = decoders.entry(track_id) {
+            let dec_opts: DecoderOptions = Default::default();
+            match symphonia::default::get_codecs().make(&track.codec_params, &dec_opts) {
+                Ok(decoder) => {
+                    slot.insert(decoder);
+                }
+                Err(_) => continue,
+            }
         }
+        time_bases.insert(track_id, track.codec_params.time_base);
+        sample_rates.insert(track_id, track.codec_params.sample_rate);
     }
 
     if decoders.is_empty() {
         for track_id in wanted {
             let source_key = SourceKey::TrackId(*track_id);
-            let _ = sender.send(DecodeWorkerEvent::SourceError {
+            let _ = sender.send_event(DecodeWorkerEvent::SourceError {
                 source_key: source_key.clone(),
                 recoverable: false,
                 message: "no decoders initialized for requested tracks".to_string(),
@@ -146,55 +243,107 @@ fn initialize_container_decoders(
         }
         None
     } else {
-        Some((decoders, time_bases, sample_rates))
+        Some((time_bases, sample_rates))
     }
 }
 
 fn seek_container_reader(
-    format: &mut dyn symphonia::core::formats::FormatReader,
+    format: &mut dyn FormatReader,
     start_time: f64,
-    file_path: &str,
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
     decoders: &HashMap<u32, Box<dyn Decoder>>,
-) {
-    let Some(first_track_id) = decoders.keys().next().copied() else {
-        return;
+) -> Result<(), Error> {
+    // Seek on the lowest track id so the resume point never depends on map order.
+    let Some(first_track_id) = decoders.keys().min().copied() else {
+        return Ok(());
     };
     let seconds = start_time.floor() as u64;
     let frac_of_second = start_time.fract();
     let time = Time::new(seconds, frac_of_second);
-    if let Err(err) = format.seek(
-        SeekMode::Coarse,
-        SeekTo::Time {
-            time,
-            track_id: Some(first_track_id),
-        },
-    ) {
-        warn!(
-            "container decode seek failed, falling back to stream start: source={} track_id={} err={}",
-            file_path, first_track_id, err
-        );
-        let _ = sender.send(DecodeWorkerEvent::SourceError {
-            source_key: SourceKey::TrackId(first_track_id),
-            recoverable: true,
-            message: format!("seek failed; continuing from stream start: {}", err),
-        });
-    }
+    format
+        .seek(
+            SeekMode::Coarse,
+            SeekTo::Time {
+                time,
+                track_id: Some(first_track_id),
+            },
+        )
+        .map(|_| ())
+}
+
+fn report_seek_failure(
+    file_path: &str,
+    decoders: &HashMap<u32, Box<dyn Decoder>>,
+    err: &Error,
+    sender: &dyn DecodeEventSink,
+) {
+    let Some(first_track_id) = decoders.keys().min().copied() else {
+        return;
+    };
+    warn!(
+        "container decode seek failed, falling back to stream start: source={} track_id={} err={}",
+        file_path, first_track_id, err
+    );
+    let _ = sender.send_event(DecodeWorkerEvent::SourceError {
+        source_key: SourceKey::TrackId(first_track_id),
+        recoverable: true,
+        message: format!("seek failed; continuing from stream start: {}", err),
+    });
 }
 
+/// Returns `false` when the reader hit a read error and must not be reused.
 fn decode_container_packets(
-    format: &mut dyn symphonia::core::formats::FormatReader,
+    format: &mut dyn FormatReader,
     decoders: &mut HashMap<u32, Box<dyn Decoder>>,
     time_bases: &HashMap<u32, Option<TimeBase>>,
     sample_rates: &HashMap<u32, Option<u32>>,
-    start_time: f64,
+    start: DecodeStart,
     channels: u8,
     infra: ForwardInfra<'_>,
-) {
+) -> bool {
     let mut log = StartupLog {
         logged_first_ready: false,
         logged_first_send: false,
     };
+    if start.lead_in_seconds > 0.0 {
+        let mut track_ids: Vec<u32> = decoders.keys().copied().collect();
+        track_ids.sort_unstable();
+        let sources: Vec<SourceKey> = track_ids.into_iter().map(SourceKey::TrackId).collect();
+        let sample_rate = sample_rates.values().find_map(|rate| *rate);
+        if !forward_lead_in(
+            &sources,
+            start.lead_in_seconds,
+            sample_rate.unwrap_or(48_000),
+            &infra,
+            &mut log,
+        ) {
+            return true;
+        }
+    }
+    if let Some(looping) = start.looping {
+        if let Some((&track_id, decoder)) = decoders.iter_mut().next() {
+            let reader = LoopReader {
+                decoder,
+                format,
+                track_id,
+                time_base: time_bases.get(&track_id).copied().flatten(),
+                sample_rate: sample_rates
+                    .get(&track_id)
+                    .copied()
+                    .flatten()
+                    .unwrap_or(48_000),
+            };
+            let source_key = SourceKey::TrackId(track_id);
+            return decode_looping_source(
+                reader,
+                start,
+                looping,
+                channels,
+                &source_key,
+                &infra,
+                &mut log,
+            );
+        }
+    }
     loop {
         if infra.abort.load(Ordering::Relaxed) {
             break;
@@ -203,19 +352,21 @@ fn decode_container_packets(
         let packet = match format.next_packet() {
             Ok(packet) => packet,
             Err(Error::IoError(ref err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => {
-                let _ = infra.sender.send(DecodeWorkerEvent::StreamExhausted);
+                let _ = infra.sender.send_event(DecodeWorkerEvent::StreamExhausted {
+                    sources: decoders.keys().copied().map(SourceKey::TrackId).collect(),
+                });
                 break;
             }
             Err(err) => {
                 warn!("container decode packet-read error: err={}", err);
                 for (track_id, _) in decoders.iter() {
-                    let _ = infra.sender.send(DecodeWorkerEvent::SourceError {
+                    let _ = infra.sender.send_event(DecodeWorkerEvent::SourceError {
                         source_key: SourceKey::TrackId(*track_id),
                         recoverable: false,
                         message: format!("packet-read failed: {}", err),
                     });
                 }
-                break;
+                return false;
             }
         };
         let track_id = packet.track_id();
@@ -228,8 +379,16 @@ fn decode_container_packets(
             packet.ts(),
             time_bases.get(&track_id).copied().flatten(),
             sample_rates.get(&track_id).copied().flatten(),
-            start_time,
-        );
+            start.seek_seconds,
+        ) + start.lead_in_seconds;
+        // Every track of a worker shares one trim, so the first past its end
+        // ends the group like end of stream.
+        if start.is_past_end(packet_ts) {
+            let _ = infra.sender.send_event(DecodeWorkerEvent::StreamExhausted {
+                sources: decoders.keys().copied().map(SourceKey::TrackId).collect(),
+            });
+            break;
+        }
         if !decode_and_forward_packet(
             decoder,
             &packet,
@@ -242,11 +401,12 @@ fn decode_container_packets(
             break;
         }
     }
+    true
 }
 
-fn finish_container_sources(wanted: &HashSet<u32>, sender: &mpsc::SyncSender<DecodeWorkerEvent>) {
+fn finish_container_sources(wanted: &BTreeSet<u32>, sender: &dyn DecodeEventSink) {
     for track_id in wanted {
-        let _ = sender.send(DecodeWorkerEvent::SourceFinished {
+        let _ = sender.send_event(DecodeWorkerEvent::SourceFinished {
             source_key: SourceKey::TrackId(*track_id),
         });
     }
@@ -267,6 +427,37 @@ mod tests {
         let _ = spawn_container_decode_worker;
     }
 
+    #[test]
+    fn stopped_worker_leaves_its_reader_for_the_next_seek() {
+        use std::sync::atomic::AtomicBool;
+
+        use super::super::super::super::buffer_mixer::DecodeBackpressure;
+        use super::{run_container_decode_worker, ContainerSource, DecodeStart};
+        use crate::playback::engine::ReaderCache;
+        use crate::test_fixtures::demo_container_copy;
+
+        let path = demo_container_copy("container_worker", "reuse");
+        let container = ContainerSource {
+            path: path.clone(),
+            readers: ReaderCache::default(),
+            track_keys: Default::default(),
+            download: None,
+        };
+
+        let (sender, _receiver) = mpsc::sync_channel::<DecodeWorkerEvent>(64);
+        let abort = AtomicBool::new(true);
+        let backpressure = DecodeBackpressure::default();
+        let start = DecodeStart::new(0.0, &Default::default());
+        run_container_decode_worker(&container, &[1], start, 2, &sender, &abort, &backpressure);
+        let reader = container
+            .readers
+            .checkout(&path)
+            .expect("reader was cached");
+        assert!(reader.decoders.contains_key(&1));
+
+        std::fs::remove_file(path).unwrap();
+    }
+
     /// Returns true when the error is an EOF (UnexpectedEof), mirroring the
     /// match pattern used in `decode_container_packets`.
     fn is_eof(err: &Error) -> bool {
@@ -308,7 +499,9 @@ mod tests {
 
         // Simulate the EOF branch: StreamExhausted, no SourceError
         if is_eof(&err) {
-            let _ = sender.send(DecodeWorkerEvent::StreamExhausted);
+            let _ = sender.send(DecodeWorkerEvent::StreamExhausted {
+                sources: Vec::new(),
+            });
         } else {
             for track_id in &track_ids {
                 let _ = sender.send(DecodeWorkerEvent::SourceError {
@@ -322,7 +515,10 @@ mod tests {
 
         let events: Vec<_> = receiver.try_iter().collect();
         assert_eq!(events.len(), 1);
-        assert!(matches!(events[0], DecodeWorkerEvent::StreamExhausted));
+        assert!(matches!(
+            events[0],
+            DecodeWorkerEvent::StreamExhausted { .. }
+        ));
     }
 
     #[test]
@@ -336,7 +532,9 @@ mod tests {
 
         // Simulate the real-error branch: per-track SourceError, no StreamExhausted
         if is_eof(&err) {
-            let _ = sender.send(DecodeWorkerEvent::StreamExhausted);
+            let _ = sender.send(DecodeWorkerEvent::StreamExhausted {
+                sources: Vec::new(),
+            });
         } else {
             for track_id in &track_ids {
                 let _ = sender.send(DecodeWorkerEvent::SourceError {
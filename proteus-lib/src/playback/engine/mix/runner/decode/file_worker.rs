@@ -1,10 +1,6 @@
 //! Standalone-file decode worker.
 
 use std::sync::atomic::Ordering;
-use std::sync::mpsc;
-use std::sync::Arc;
-use std::thread;
-use std::thread::JoinHandle;
 use std::time::Instant;
 
 use log::{debug, warn};
@@ -13,92 +9,98 @@ use symphonia::core::errors::Error;
 use symphonia::core::formats::{SeekMode, SeekTo};
 use symphonia::core::units::Time;
 
+use crate::playback::engine::decode_pool::{DecodeJobHandle, DecodePool};
 use crate::tools::decode::open_file;
 
 use super::super::super::buffer_mixer::{DecodeBackpressure, SourceKey};
-use super::super::super::decoder_events::DecodeWorkerEvent;
-use super::{decode_and_forward_packet, packet_ts_seconds, ForwardInfra, StartupLog};
+use super::super::super::decoder_events::{DecodeEventSink, DecodeWorkerEvent};
+use super::lead_in::forward_lead_in;
+use super::looping::{decode_looping_source, LoopReader};
+use super::{
+    decode_and_forward_packet, packet_ts_seconds, DecodeStart, DecodeWorkerLinks, ForwardInfra,
+    StartupLog,
+};
 
-/// Spawn a decode worker for one standalone audio file source.
+/// Run a decode worker for one standalone audio file source on `pool`.
 pub(crate) fn spawn_file_decode_worker(
+    pool: &DecodePool,
     file_path: String,
-    start_time: f64,
+    start: DecodeStart,
     channels: u8,
-    sender: mpsc::SyncSender<DecodeWorkerEvent>,
-    abort: Arc<std::sync::atomic::AtomicBool>,
-    decode_backpressure: Arc<DecodeBackpressure>,
-) -> JoinHandle<()> {
-    thread::spawn(move || {
+    links: DecodeWorkerLinks,
+) -> DecodeJobHandle {
+    pool.submit(move || {
         run_file_decode_worker(
-            file_path,
-            start_time,
+            &file_path,
+            start,
             channels,
-            sender,
-            abort,
-            decode_backpressure,
+            &links.sender,
+            &links.abort,
+            &links.decode_backpressure,
         )
     })
 }
 
-fn run_file_decode_worker(
-    file_path: String,
-    start_time: f64,
+/// Decode one standalone file on the calling thread until the stream ends,
+/// the sink rejects an event, or `abort` is set.
+pub(crate) fn run_file_decode_worker(
+    file_path: &str,
+    start: DecodeStart,
     channels: u8,
-    sender: mpsc::SyncSender<DecodeWorkerEvent>,
-    abort: Arc<std::sync::atomic::AtomicBool>,
-    decode_backpressure: Arc<DecodeBackpressure>,
+    sender: &dyn DecodeEventSink,
+    abort: &std::sync::atomic::AtomicBool,
+    decode_backpressure: &DecodeBackpressure,
 ) {
     let startup_trace = Instant::now();
-    let source_key = SourceKey::FilePath(file_path.clone());
-    let Some((mut decoder, mut format)) = open_file_decoder(&file_path, &source_key, &sender)
-    else {
+    let source_key = SourceKey::FilePath(file_path.to_string());
+    let Some((mut decoder, mut format)) = open_file_decoder(file_path, &source_key, sender) else {
         return;
     };
-    let Some(track) = select_decodable_track(format.as_ref(), &source_key, &sender) else {
+    let Some(track) = select_decodable_track(format.as_ref(), &source_key, sender) else {
         return;
     };
     seek_file_reader(
         format.as_mut(),
-        start_time,
-        &file_path,
+        start.seek_seconds,
+        file_path,
         track.id,
         &source_key,
-        &sender,
+        sender,
     );
     let infra = ForwardInfra {
         worker_label: "file",
-        sender: &sender,
-        decode_backpressure: decode_backpressure.as_ref(),
-        abort: abort.as_ref(),
+        sender,
+        decode_backpressure,
+        abort,
         startup_trace,
     };
     decode_file_packets(
         &mut decoder,
         format.as_mut(),
         &track,
-        start_time,
+        start,
         channels,
         &source_key,
         infra,
     );
-    let _ = sender.send(DecodeWorkerEvent::SourceFinished { source_key });
+    let _ = sender.send_event(DecodeWorkerEvent::SourceFinished { source_key });
 }
 
 fn open_file_decoder(
     file_path: &str,
     source_key: &SourceKey,
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
+    sender: &dyn DecodeEventSink,
 ) -> Option<crate::tools::decode::OpenedDecoder> {
     match open_file(file_path) {
         Ok(opened) => Some(opened),
         Err(err) => {
             debug!("file worker open failed: source={} err={}", file_path, err);
-            let _ = sender.send(DecodeWorkerEvent::SourceError {
+            let _ = sender.send_event(DecodeWorkerEvent::SourceError {
                 source_key: source_key.clone(),
                 recoverable: false,
                 message: err.to_string(),
             });
-            let _ = sender.send(DecodeWorkerEvent::SourceFinished {
+            let _ = sender.send_event(DecodeWorkerEvent::SourceFinished {
                 source_key: source_key.clone(),
             });
             None
@@ -109,7 +111,7 @@ fn open_file_decoder(
 fn select_decodable_track(
     format: &dyn symphonia::core::formats::FormatReader,
     source_key: &SourceKey,
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
+    sender: &dyn DecodeEventSink,
 ) -> Option<symphonia::core::formats::Track> {
     let track = format
         .tracks()
@@ -117,12 +119,12 @@ fn select_decodable_track(
         .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
         .cloned();
     if track.is_none() {
-        let _ = sender.send(DecodeWorkerEvent::SourceError {
+        let _ = sender.send_event(DecodeWorkerEvent::SourceError {
             source_key: source_key.clone(),
             recoverable: false,
             message: "no decodable audio track".to_string(),
         });
-        let _ = sender.send(DecodeWorkerEvent::SourceFinished {
+        let _ = sender.send_event(DecodeWorkerEvent::SourceFinished {
             source_key: source_key.clone(),
         });
     }
@@ -135,7 +137,7 @@ fn seek_file_reader(
     file_path: &str,
     track_id: u32,
     source_key: &SourceKey,
-    sender: &mpsc::SyncSender<DecodeWorkerEvent>,
+    sender: &dyn DecodeEventSink,
 ) {
     let seconds = start_time.floor() as u64;
     let frac_of_second = start_time.fract();
@@ -151,7 +153,7 @@ fn seek_file_reader(
             "file decode seek failed, falling back to stream start: source={} err={}",
             file_path, err
         );
-        let _ = sender.send(DecodeWorkerEvent::SourceError {
+        let _ = sender.send_event(DecodeWorkerEvent::SourceError {
             source_key: source_key.clone(),
             recoverable: true,
             message: format!("seek failed; continuing from stream start: {}", err),
@@ -163,7 +165,7 @@ fn decode_file_packets(
     decoder: &mut Box<dyn symphonia::core::codecs::Decoder>,
     format: &mut dyn symphonia::core::formats::FormatReader,
     track: &symphonia::core::formats::Track,
-    start_time: f64,
+    start: DecodeStart,
     channels: u8,
     source_key: &SourceKey,
     infra: ForwardInfra<'_>,
@@ -174,6 +176,30 @@ fn decode_file_packets(
     };
     let time_base = track.codec_params.time_base;
     let sample_rate = track.codec_params.sample_rate;
+    if start.lead_in_seconds > 0.0
+        && !forward_lead_in(
+            std::slice::from_ref(source_key),
+            start.lead_in_seconds,
+            sample_rate.unwrap_or(48_000),
+            &infra,
+            &mut log,
+        )
+    {
+        return;
+    }
+    if let Some(looping) = start.looping {
+        let reader = LoopReader {
+            decoder,
+            format,
+            track_id: track.id,
+            time_base,
+            sample_rate: sample_rate.unwrap_or(48_000),
+        };
+        decode_looping_source(
+            reader, start, looping, channels, source_key, &infra, &mut log,
+        );
+        return;
+    }
     loop {
         if infra.abort.load(Ordering::Relaxed) {
             break;
@@ -189,7 +215,7 @@ fn decode_file_packets(
                     "file decode packet-read error: source={:?} err={}",
                     source_key, err
                 );
-                let _ = infra.sender.send(DecodeWorkerEvent::SourceError {
+                let _ = infra.sender.send_event(DecodeWorkerEvent::SourceError {
                     source_key: source_key.clone(),
                     recoverable: false,
                     message: format!("packet-read failed: {}", err),
@@ -201,7 +227,11 @@ fn decode_file_packets(
             continue;
         }
 
-        let packet_ts = packet_ts_seconds(packet.ts(), time_base, sample_rate, start_time);
+        let packet_ts = packet_ts_seconds(packet.ts(), time_base, sample_rate, start.seek_seconds)
+            + start.lead_in_seconds;
+        if start.is_past_end(packet_ts) {
+            break;
+        }
         if !decode_and_forward_packet(
             decoder, &packet, channels, source_key, &infra, &mut log, packet_ts,
         ) {
@@ -0,0 +1,247 @@
+//! Delayed source starts.
+//!
+//! A track with a start offset plays its first sample that far into the
+//! timeline. Its decode worker seeks to the matching source position and,
+//! when the run starts before the offset, first sends silence up to it, so
+//! the mixer sees an ordinary gap-free stream and needs no special case.
+//! A trimmed source is read from its trim start instead of its first sample
+//! and stops at its trim end.
+
+use std::collections::HashMap;
+
+use super::super::super::buffer_mixer::{SourceKey, SourceTiming};
+use super::looping::SourceLoop;
+use super::{forward_decoded_packet, ForwardInfra, StartupLog};
+
+/// Frames per silence packet sent before a delayed source starts.
+const LEAD_IN_PACKET_FRAMES: usize = 1024;
+
+/// Where a decode worker starts reading, and how long it stays silent first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct DecodeStart {
+    /// Source position to seek to, in seconds.
+    pub seek_seconds: f64,
+    /// Silence sent before the first decoded packet, in seconds.
+    pub lead_in_seconds: f64,
+    /// Loop the source repeats, if any.
+    pub looping: Option<SourceLoop>,
+    /// Source position, in seconds, where a non-looping source stops.
+    pub end_seconds: Option<f64>,
+}
+
+impl DecodeStart {
+    /// Start for a source with `timing` in a run starting at `run_start`
+    /// seconds.
+    pub(crate) fn new(run_start: f64, timing: &SourceTiming) -> Self {
+        let elapsed = run_start - timing.start_offset_ms as f64 / 1000.0;
+        let source_elapsed = elapsed.max(0.0) + timing.trim.start_ms as f64 / 1000.0;
+        let looping = timing.looping.as_ref().map(SourceLoop::from_instance);
+        let seek_seconds = match looping {
+            Some(looping) if source_elapsed > 0.0 => looping.source_position(source_elapsed),
+            _ => source_elapsed,
+        };
+        Self {
+            seek_seconds,
+            lead_in_seconds: (-elapsed).max(0.0),
+            looping,
+            end_seconds: looping
+                .is_none()
+                .then(|| timing.trim.end_ms.map(|end_ms| end_ms as f64 / 1000.0))
+                .flatten(),
+        }
+    }
+
+    /// Return `true` once a packet at `packet_ts` (seconds since the worker
+    /// started sending) lies past the source's trim end.
+    pub(crate) fn is_past_end(&self, packet_ts: f64) -> bool {
+        self.end_seconds.is_some_and(|end_seconds| {
+            packet_ts - self.lead_in_seconds + self.seek_seconds >= end_seconds
+        })
+    }
+}
+
+/// Group container track ids into demuxer workers, smallest offset first.
+///
+/// Tracks with different offsets need different seek positions, and a
+/// looping track seeks on its own, so each such group gets its own demuxer.
+pub(crate) fn container_track_groups(
+    track_ids: impl IntoIterator<Item = u32>,
+    timings: &HashMap<SourceKey, SourceTiming>,
+) -> Vec<(SourceTiming, Vec<u32>)> {
+    let mut track_ids: Vec<u32> = track_ids.into_iter().collect();
+    track_ids.sort_unstable();
+    let mut groups: Vec<(SourceTiming, Vec<u32>)> = Vec::new();
+    for track_id in track_ids {
+        let timing = timings
+            .get(&SourceKey::TrackId(track_id))
+            .copied()
+            .unwrap_or_default();
+        let shared = timing.looping.is_none().then(|| {
+            groups
+                .iter_mut()
+                .find(|(group, _)| group.looping.is_none() && *group == timing)
+        });
+        match shared.flatten() {
+            Some((_, ids)) => ids.push(track_id),
+            None => groups.push((timing, vec![track_id])),
+        }
+    }
+    groups.sort_by_key(|(timing, _)| timing.start_offset_ms);
+    groups
+}
+
+/// Send `lead_in_seconds` of silence for each of `sources`, interleaving
+/// packets so no source runs ahead of the others under backpressure.
+///
+/// Returns `false` when the worker should stop.
+pub(super) fn forward_lead_in(
+    sources: &[SourceKey],
+    lead_in_seconds: f64,
+    sample_rate: u32,
+    infra: &ForwardInfra<'_>,
+    log: &mut StartupLog,
+) -> bool {
+    let total_frames = (lead_in_seconds * sample_rate as f64).round() as usize;
+    let mut sent_frames = 0usize;
+    while sent_frames < total_frames {
+        let frames = LEAD_IN_PACKET_FRAMES.min(total_frames - sent_frames);
+        let packet_ts = sent_frames as f64 / sample_rate.max(1) as f64;
+        for source in sources {
+            let silence = vec![0.0; frames * 2];
+            if !forward_decoded_packet(source.clone(), packet_ts, silence, infra, log) {
+                return false;
+            }
+        }
+        sent_frames += frames;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::mpsc;
+    use std::time::Instant;
+
+    use super::super::super::super::buffer_mixer::DecodeBackpressure;
+    use super::super::super::super::decoder_events::DecodeWorkerEvent;
+    use super::*;
+
+    fn offset(start_offset_ms: u64) -> SourceTiming {
+        SourceTiming {
+            start_offset_ms,
+            ..SourceTiming::default()
+        }
+    }
+
+    #[test]
+    fn offset_ahead_of_the_run_becomes_lead_in() {
+        assert_eq!(
+            DecodeStart::new(1.0, &offset(3_000)),
+            DecodeStart {
+                seek_seconds: 0.0,
+                lead_in_seconds: 2.0,
+                looping: None,
+                end_seconds: None,
+            }
+        );
+        assert_eq!(
+            DecodeStart::new(5.0, &offset(3_000)),
+            DecodeStart {
+                seek_seconds: 2.0,
+                lead_in_seconds: 0.0,
+                looping: None,
+                end_seconds: None,
+            }
+        );
+    }
+
+    #[test]
+    fn trimmed_sources_read_from_the_trim_start_and_stop_at_its_end() {
+        let timing = SourceTiming {
+            start_offset_ms: 1_000,
+            trim: crate::container::prot::SourceTrim {
+                start_ms: 250,
+                end_ms: Some(4_000),
+            },
+            ..SourceTiming::default()
+        };
+        let ahead = DecodeStart::new(0.5, &timing);
+        assert_eq!(ahead.seek_seconds, 0.25);
+        assert_eq!(ahead.lead_in_seconds, 0.5);
+        let inside = DecodeStart::new(2.0, &timing);
+        assert_eq!(inside.seek_seconds, 1.25);
+        assert_eq!(inside.end_seconds, Some(4.0));
+        assert!(!inside.is_past_end(2.7));
+        assert!(inside.is_past_end(2.75));
+    }
+
+    #[test]
+    fn container_tracks_are_grouped_by_offset_and_loop() {
+        let looping = SourceTiming {
+            start_offset_ms: 0,
+            looping: Some(crate::container::prot::InstanceLoop {
+                start_ms: 0,
+                end_ms: 1_000,
+                crossfade_ms: 10,
+                until_ms: 5_000,
+            }),
+            ..SourceTiming::default()
+        };
+        let timings = HashMap::from([
+            (SourceKey::TrackId(3), offset(500)),
+            (SourceKey::TrackId(1), offset(500)),
+            (SourceKey::TrackId(4), looping),
+            (SourceKey::TrackId(5), looping),
+        ]);
+        let groups = container_track_groups([5, 3, 2, 1, 4], &timings);
+        assert_eq!(
+            groups,
+            vec![
+                (offset(0), vec![2]),
+                (looping, vec![4]),
+                (looping, vec![5]),
+                (offset(500), vec![1, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn lead_in_interleaves_silence_across_sources() {
+        let (sender, receiver) = mpsc::sync_channel::<DecodeWorkerEvent>(16);
+        let abort = AtomicBool::new(false);
+        let backpressure = DecodeBackpressure::default();
+        let infra = ForwardInfra {
+            worker_label: "test",
+            sender: &sender,
+            decode_backpressure: &backpressure,
+            abort: &abort,
+            startup_trace: Instant::now(),
+        };
+        let mut log = StartupLog {
+            logged_first_ready: false,
+            logged_first_send: false,
+        };
+        let sources = [SourceKey::TrackId(1), SourceKey::TrackId(2)];
+        assert!(forward_lead_in(&sources, 0.05, 48_000, &infra, &mut log));
+        drop(sender);
+
+        let packets: Vec<_> = receiver
+            .try_iter()
+            .map(|event| match event {
+                DecodeWorkerEvent::Packet(packet) => packet,
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(packets.len(), 6);
+        assert_eq!(packets[0].source_key, SourceKey::TrackId(1));
+        assert_eq!(packets[1].source_key, SourceKey::TrackId(2));
+        let frames: usize = packets
+            .iter()
+            .filter(|packet| packet.source_key == SourceKey::TrackId(1))
+            .map(|packet| packet.samples.len() / 2)
+            .sum();
+        assert_eq!(frames, 2_400);
+        assert!(packets.iter().all(|p| p.samples.iter().all(|s| *s == 0.0)));
+    }
+}
@@ -0,0 +1,338 @@
+//! Looping sources.
+//!
+//! A looping source plays from its seek position to the loop end, then the
+//! worker seeks back to the loop start and keeps going until the song ends.
+//! The last `crossfade` of each pass is held back and faded into the first
+//! `crossfade` of the next one, so every wrap after the first pass shortens
+//! the loop period by the crossfade length.
+
+use std::f32::consts::FRAC_PI_2;
+use std::sync::atomic::Ordering;
+
+use log::warn;
+use symphonia::core::codecs::Decoder;
+use symphonia::core::errors::Error;
+use symphonia::core::formats::{FormatReader, SeekMode, SeekTo};
+use symphonia::core::units::{Time, TimeBase};
+
+use crate::container::prot::InstanceLoop;
+
+use super::super::super::buffer_mixer::SourceKey;
+use super::super::super::decoder_events::{DecodeEventSink, DecodeWorkerEvent};
+use super::{
+    forward_decoded_packet, interleaved_samples, packet_ts_seconds, DecodeStart, ForwardInfra,
+    StartupLog,
+};
+
+/// Loop of a decode worker's source, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct SourceLoop {
+    /// Source position each wrap returns to.
+    pub start_seconds: f64,
+    /// Source position where a pass ends.
+    pub end_seconds: f64,
+    /// Crossfade length at each wrap, at most half the loop length.
+    pub crossfade_seconds: f64,
+    /// Timeline position, relative to the run start, where the source stops.
+    pub until_seconds: f64,
+}
+
+impl SourceLoop {
+    pub(crate) fn from_instance(looping: &InstanceLoop) -> Self {
+        let start_seconds = looping.start_ms as f64 / 1000.0;
+        let end_seconds = looping.end_ms as f64 / 1000.0;
+        let crossfade_seconds =
+            (looping.crossfade_ms as f64 / 1000.0).min((end_seconds - start_seconds) / 2.0);
+        Self {
+            start_seconds,
+            end_seconds,
+            crossfade_seconds: crossfade_seconds.max(0.0),
+            until_seconds: looping.until_ms as f64 / 1000.0,
+        }
+    }
+
+    /// Source position heard `elapsed` seconds after the source's first
+    /// sample.
+    ///
+    /// A position inside a wrap crossfade resolves to the incoming head.
+    pub(crate) fn source_position(&self, elapsed: f64) -> f64 {
+        let first_wrap = self.end_seconds - self.crossfade_seconds;
+        if elapsed < first_wrap {
+            return elapsed;
+        }
+        let period = self.end_seconds - self.start_seconds - self.crossfade_seconds;
+        if period <= 0.0 {
+            return self.start_seconds;
+        }
+        self.start_seconds + (elapsed - first_wrap) % period
+    }
+}
+
+/// Crossfades the end of each pass into the start of the next.
+struct LoopJoiner {
+    crossfade_samples: usize,
+    held: Vec<f32>,
+    tail: Vec<f32>,
+    tail_pos: usize,
+}
+
+impl LoopJoiner {
+    fn new(crossfade_frames: usize) -> Self {
+        Self {
+            crossfade_samples: crossfade_frames * 2,
+            held: Vec::new(),
+            tail: Vec::new(),
+            tail_pos: 0,
+        }
+    }
+
+    /// Add decoded samples of the current pass and return those ready to send.
+    fn push(&mut self, samples: &[f32]) -> Vec<f32> {
+        let start = self.held.len();
+        self.held.extend_from_slice(samples);
+        let fade_frames = (self.tail.len() / 2).max(1) as f32;
+        for sample in self.held[start..].iter_mut() {
+            let Some(tail) = self.tail.get(self.tail_pos) else {
+                break;
+            };
+            let t = ((self.tail_pos / 2) as f32 + 0.5) / fade_frames * FRAC_PI_2;
+            *sample = *sample * t.sin() + tail * t.cos();
+            self.tail_pos += 1;
+        }
+        let ready = self.held.len().saturating_sub(self.crossfade_samples);
+        self.held.drain(..ready).collect()
+    }
+
+    /// End the current pass; its withheld end fades into the next pass.
+    fn wrap(&mut self) {
+        self.tail = std::mem::take(&mut self.held);
+        self.tail_pos = 0;
+    }
+
+    /// Return everything still withheld once the source stops.
+    fn finish(&mut self) -> Vec<f32> {
+        std::mem::take(&mut self.held)
+    }
+}
+
+/// Reader state of a looping source.
+pub(super) struct LoopReader<'a> {
+    pub decoder: &'a mut Box<dyn Decoder>,
+    pub format: &'a mut dyn FormatReader,
+    pub track_id: u32,
+    pub time_base: Option<TimeBase>,
+    pub sample_rate: u32,
+}
+
+/// Tracks how much of the timeline a looping source has covered.
+struct LoopOutput<'a, 'b> {
+    source_key: &'a SourceKey,
+    infra: &'a ForwardInfra<'b>,
+    lead_in_seconds: f64,
+    sample_rate: f64,
+    sent_frames: usize,
+    until_frames: usize,
+}
+
+impl LoopOutput<'_, '_> {
+    /// Send `samples`, trimmed to the song end. Returns `false` once the
+    /// source should stop.
+    fn send(&mut self, mut samples: Vec<f32>, log: &mut StartupLog) -> bool {
+        let room = self.until_frames.saturating_sub(self.sent_frames);
+        samples.truncate(room * 2);
+        if !samples.is_empty() {
+            let packet_ts = self.lead_in_seconds + self.sent_frames as f64 / self.sample_rate;
+            self.sent_frames += samples.len() / 2;
+            if !forward_decoded_packet(self.source_key.clone(), packet_ts, samples, self.infra, log)
+            {
+                return false;
+            }
+        }
+        self.sent_frames < self.until_frames
+    }
+}
+
+/// Decode a looping source until the song end, a stop, or a read failure.
+///
+/// The reader must already be positioned at `start.seek_seconds`. Returns
+/// `false` when a read error makes the reader unfit for reuse.
+pub(super) fn decode_looping_source(
+    reader: LoopReader<'_>,
+    start: DecodeStart,
+    looping: SourceLoop,
+    channels: u8,
+    source_key: &SourceKey,
+    infra: &ForwardInfra<'_>,
+    log: &mut StartupLog,
+) -> bool {
+    let LoopReader {
+        decoder,
+        format,
+        track_id,
+        time_base,
+        sample_rate: rate,
+    } = reader;
+    let sample_rate = f64::from(rate.max(1));
+    let mut joiner = LoopJoiner::new((looping.crossfade_seconds * sample_rate).round() as usize);
+    let mut output = LoopOutput {
+        source_key,
+        infra,
+        lead_in_seconds: start.lead_in_seconds,
+        sample_rate,
+        sent_frames: 0,
+        until_frames: ((looping.until_seconds - start.lead_in_seconds).max(0.0) * sample_rate)
+            .round() as usize,
+    };
+    let mut pass_start = start.seek_seconds;
+    let mut pass_frames = 0usize;
+    while !infra.abort.load(Ordering::Relaxed) {
+        let packet = match format.next_packet() {
+            Ok(packet) => Some(packet),
+            Err(Error::IoError(ref err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            Err(err) => {
+                report_error(
+                    infra.sender,
+                    source_key,
+                    false,
+                    format!("packet-read failed: {}", err),
+                );
+                return false;
+            }
+        };
+        let pass_ended = match packet {
+            None => true,
+            Some(packet) if packet.track_id() != track_id => continue,
+            Some(packet) => {
+                let samples = match decoder.decode(&packet) {
+                    Ok(decoded) => interleaved_samples(decoded, channels),
+                    Err(Error::DecodeError(err)) => {
+                        report_error(infra.sender, source_key, true, err.to_string());
+                        continue;
+                    }
+                    Err(err) => {
+                        report_error(infra.sender, source_key, false, err.to_string());
+                        return true;
+                    }
+                };
+                let packet_start = packet_ts_seconds(packet.ts(), time_base, Some(rate), 0.0);
+                let frames = samples.len() / 2;
+                let first = frame_at(pass_start - packet_start, sample_rate).min(frames);
+                let last = frame_at(looping.end_seconds - packet_start, sample_rate).min(frames);
+                if last > first {
+                    pass_frames += last - first;
+                    let ready = joiner.push(&samples[first * 2..last * 2]);
+                    if !output.send(ready, log) {
+                        return true;
+                    }
+                }
+                last < frames
+            }
+        };
+        if !pass_ended {
+            continue;
+        }
+        if pass_frames == 0 || !seek_loop_start(format, track_id, looping, source_key) {
+            break;
+        }
+        decoder.reset();
+        joiner.wrap();
+        pass_start = looping.start_seconds;
+        pass_frames = 0;
+    }
+    output.send(joiner.finish(), log);
+    true
+}
+
+/// Seek back to the loop start; a failure ends the source.
+fn seek_loop_start(
+    format: &mut dyn FormatReader,
+    track_id: u32,
+    looping: SourceLoop,
+    source_key: &SourceKey,
+) -> bool {
+    let start = looping.start_seconds;
+    let time = Time::new(start.floor() as u64, start.fract());
+    match format.seek(
+        SeekMode::Accurate,
+        SeekTo::Time {
+            time,
+            track_id: Some(track_id),
+        },
+    ) {
+        Ok(_) => true,
+        Err(err) => {
+            warn!(
+                "loop seek failed, ending source: source={:?} err={}",
+                source_key, err
+            );
+            false
+        }
+    }
+}
+
+fn frame_at(seconds: f64, sample_rate: f64) -> usize {
+    (seconds * sample_rate).round().max(0.0) as usize
+}
+
+fn report_error(
+    sender: &dyn DecodeEventSink,
+    source_key: &SourceKey,
+    recoverable: bool,
+    message: String,
+) {
+    let _ = sender.send_event(DecodeWorkerEvent::SourceError {
+        source_key: source_key.clone(),
+        recoverable,
+        message,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_loop(start: f64, end: f64, crossfade: f64) -> SourceLoop {
+        SourceLoop {
+            start_seconds: start,
+            end_seconds: end,
+            crossfade_seconds: crossfade,
+            until_seconds: 60.0,
+        }
+    }
+
+    #[test]
+    fn crossfade_is_capped_at_half_the_loop() {
+        let looping = SourceLoop::from_instance(&InstanceLoop {
+            start_ms: 1_000,
+            end_ms: 1_100,
+            crossfade_ms: 500,
+            until_ms: 10_000,
+        });
+        assert!((looping.crossfade_seconds - 0.05).abs() < 1e-9);
+    }
+
+    #[test]
+    fn source_position_wraps_with_a_crossfade_shortened_period() {
+        let looping = source_loop(1.0, 4.0, 0.5);
+        assert_eq!(looping.source_position(2.0), 2.0);
+        // The first wrap starts at 3.5s; each period afterwards lasts 2.5s.
+        assert_eq!(looping.source_position(3.5), 1.0);
+        assert_eq!(looping.source_position(4.0), 1.5);
+        assert_eq!(looping.source_position(6.0), 1.0);
+    }
+
+    #[test]
+    fn joiner_holds_back_the_crossfade_and_blends_it_into_the_next_pass() {
+        let mut joiner = LoopJoiner::new(2);
+        let first = joiner.push(&[1.0; 12]);
+        assert_eq!(first.len(), 8);
+        joiner.wrap();
+        let second = joiner.push(&[0.0; 12]);
+        assert_eq!(second.len(), 8);
+        // The tail fades out over the head's first two frames.
+        assert!(second[0] > second[2] && second[2] > 0.0);
+        assert_eq!(second[0], second[1]);
+        assert_eq!(&second[4..], &[0.0; 4]);
+        assert_eq!(joiner.finish().len(), 4);
+    }
+}
@@ -8,35 +8,54 @@
 //!   exhaustion the worker sends a single `SourceFinished` event.
 //! - **Container worker** (`container_worker`): multiple sources share one
 //!   demuxer. On stream exhaustion the worker sends `StreamExhausted`
-//!   (which finishes all sources at once) followed by per-source
+//!   (which finishes all of its sources at once) followed by per-source
 //!   `SourceFinished` events.
+//!
+//! Sources whose track starts late or loops are handled by `lead_in` and
+//! `looping`: each worker is given a [`DecodeStart`] instead of a bare seek
+//! time.
 
 mod container_worker;
 mod file_worker;
+mod lead_in;
+mod looping;
 
-use std::thread::JoinHandle;
+use std::sync::atomic::AtomicBool;
+use std::sync::{mpsc, Arc};
 
 use log::{debug, info, warn};
 
 use crate::dsp::guardrails::sanitize_channels;
+use crate::playback::engine::decode_pool::DecodeJobHandle;
 use symphonia::core::audio::AudioBufferRef;
 use symphonia::core::codecs::Decoder;
 use symphonia::core::errors::Error;
 use symphonia::core::formats::Packet;
 use symphonia::core::units::TimeBase;
 
-use super::super::buffer_mixer::SourceKey;
-use super::super::decoder_events::{DecodeWorkerEvent, DecodedPacket};
+use super::super::buffer_mixer::{DecodeBackpressure, SourceKey};
+use super::super::decoder_events::{DecodeEventSink, DecodeWorkerEvent, DecodedPacket};
+
+pub(super) use container_worker::{
+    run_container_decode_worker, spawn_container_decode_worker, ContainerSource,
+};
+pub(super) use file_worker::{run_file_decode_worker, spawn_file_decode_worker};
+pub(crate) use lead_in::{container_track_groups, DecodeStart};
 
-pub(super) use container_worker::spawn_container_decode_worker;
-pub(super) use file_worker::spawn_file_decode_worker;
+/// Channels shared by every decode worker of one mix run.
+#[derive(Clone)]
+pub(crate) struct DecodeWorkerLinks {
+    pub sender: mpsc::SyncSender<DecodeWorkerEvent>,
+    pub abort: Arc<AtomicBool>,
+    pub decode_backpressure: Arc<DecodeBackpressure>,
+}
 
 /// Shared decode-worker context passed to `forward_decoded_packet`.
 pub(super) struct ForwardInfra<'a> {
     pub worker_label: &'a str,
-    pub sender: &'a std::sync::mpsc::SyncSender<super::super::decoder_events::DecodeWorkerEvent>,
-    pub decode_backpressure: &'a super::super::buffer_mixer::DecodeBackpressure,
-    pub abort: &'a std::sync::atomic::AtomicBool,
+    pub sender: &'a dyn DecodeEventSink,
+    pub decode_backpressure: &'a DecodeBackpressure,
+    pub abort: &'a AtomicBool,
     pub startup_trace: std::time::Instant,
 }
 
@@ -46,15 +65,18 @@ pub(super) struct StartupLog {
     pub logged_first_send: bool,
 }
 
-/// Ensures decode workers are joined during mix-thread teardown.
+/// Ensures decode workers have returned during mix-thread teardown.
+///
+/// Workers run on pooled threads, so joining waits for the job rather than
+/// the thread.
 #[derive(Default)]
 pub(super) struct DecodeWorkerJoinGuard {
-    workers: Vec<JoinHandle<()>>,
+    workers: Vec<DecodeJobHandle>,
 }
 
 impl DecodeWorkerJoinGuard {
     /// Register a decode worker to be joined during teardown.
-    pub(super) fn push(&mut self, handle: JoinHandle<()>) {
+    pub(super) fn push(&mut self, handle: DecodeJobHandle) {
         self.workers.push(handle);
     }
 }
@@ -164,14 +186,13 @@ fn forward_decoded_packet(
         packet_ts,
         samples.len()
     );
-    if infra
+    if !infra
         .sender
-        .send(DecodeWorkerEvent::Packet(DecodedPacket {
+        .send_event(DecodeWorkerEvent::Packet(DecodedPacket {
             source_key: source_key.clone(),
             packet_ts,
             samples,
         }))
-        .is_err()
     {
         return false;
     }
@@ -218,7 +239,7 @@ pub(super) fn decode_and_forward_packet(
             forward_decoded_packet(source_key.clone(), packet_ts, samples, infra, log)
         }
         Err(Error::DecodeError(err)) => {
-            let _ = infra.sender.send(DecodeWorkerEvent::SourceError {
+            let _ = infra.sender.send_event(DecodeWorkerEvent::SourceError {
                 source_key: source_key.clone(),
                 recoverable: true,
                 message: err.to_string(),
@@ -226,7 +247,7 @@ pub(super) fn decode_and_forward_packet(
             true
         }
         Err(err) => {
-            let _ = infra.sender.send(DecodeWorkerEvent::SourceError {
+            let _ = infra.sender.send_event(DecodeWorkerEvent::SourceError {
                 source_key: source_key.clone(),
                 recoverable: false,
                 message: err.to_string(),
@@ -239,8 +260,8 @@ pub(super) fn decode_and_forward_packet(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::sync::{Arc, Mutex};
-    use std::thread;
+    use crate::playback::engine::DecodePool;
+    use std::sync::Mutex;
     use symphonia::core::units::TimeBase;
 
     #[test]
@@ -259,7 +280,9 @@ mod tests {
     fn decode_worker_join_guard_joins_registered_threads() {
         let joined = Arc::new(Mutex::new(0_u32));
         let joined_clone = joined.clone();
-        let handle = thread::spawn(move || {
+        let pool = DecodePool::new(1);
+        let handle = pool.submit(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
             *joined_clone.lock().unwrap() += 1;
         });
 
@@ -286,7 +309,7 @@ mod tests {
         assert!(matches!(container_key, SourceKey::TrackId(_)));
 
         // Verify the shared function symbol is linked (compile-time parity check).
-        let _shared_fn: fn(
+        type SharedDecodeFn = fn(
             &mut Box<dyn Decoder>,
             &Packet,
             u8,
@@ -294,7 +317,8 @@ mod tests {
             &ForwardInfra<'_>,
             &mut StartupLog,
             f64,
-        ) -> bool = decode_and_forward_packet;
+        ) -> bool;
+        let _shared_fn: SharedDecodeFn = decode_and_forward_packet;
     }
 
     #[test]
@@ -0,0 +1,155 @@
+//! Per-effect processing time on the mix thread.
+
+use std::time::Instant;
+
+use crate::dsp::effects::AudioEffect;
+use crate::playback::engine::EffectTiming;
+
+/// Weight of the newest chunk in each rolling average.
+const TIMING_ALPHA: f64 = 0.1;
+
+/// Splits one chain run into per-effect laps.
+///
+/// [`EffectTimer::start`] marks the chain's start; each [`EffectTimer::lap`]
+/// charges the time since the previous mark to one effect.
+#[derive(Debug)]
+pub(super) struct EffectTimer {
+    timings: Vec<EffectTiming>,
+    mark: Instant,
+    chunk_ms: f64,
+}
+
+impl EffectTimer {
+    pub(super) fn new() -> Self {
+        Self {
+            timings: Vec::new(),
+            mark: Instant::now(),
+            chunk_ms: 0.0,
+        }
+    }
+
+    /// Begin timing a chunk of `chunk_seconds` of audio through `effects`.
+    ///
+    /// History restarts when the chain's effects differ from the last run.
+    pub(super) fn start(&mut self, effects: &[AudioEffect], chunk_seconds: f64) {
+        let unchanged = self.timings.len() == effects.len()
+            && self
+                .timings
+                .iter()
+                .zip(effects)
+                .all(|(timing, effect)| timing.name == effect.display_name());
+        if !unchanged {
+            self.timings = effects
+                .iter()
+                .enumerate()
+                .map(|(index, effect)| EffectTiming {
+                    index,
+                    name: effect.display_name(),
+                    ..EffectTiming::default()
+                })
+                .collect();
+        }
+        self.chunk_ms = chunk_seconds * 1000.0;
+        self.mark = Instant::now();
+    }
+
+    /// Charge the time since the last mark to effect `index`.
+    pub(super) fn lap(&mut self, index: usize) {
+        let elapsed_ms = self.mark.elapsed().as_secs_f64() * 1000.0;
+        self.record(index, elapsed_ms);
+        self.mark = Instant::now();
+    }
+
+    /// Move the mark to now so bookkeeping between effects is not charged to
+    /// the next one.
+    pub(super) fn resume(&mut self) {
+        self.mark = Instant::now();
+    }
+
+    fn record(&mut self, index: usize, elapsed_ms: f64) {
+        let Some(timing) = self.timings.get_mut(index) else {
+            return;
+        };
+        timing.avg_ms = if timing.max_ms == 0.0 {
+            elapsed_ms
+        } else {
+            timing.avg_ms + (elapsed_ms - timing.avg_ms) * TIMING_ALPHA
+        };
+        timing.last_ms = elapsed_ms;
+        timing.max_ms = timing.max_ms.max(elapsed_ms);
+        timing.avg_load = if self.chunk_ms > 0.0 {
+            timing.avg_ms / self.chunk_ms
+        } else {
+            0.0
+        };
+    }
+
+    /// Duration of the audio in the last timed chunk, in seconds.
+    pub(super) fn chunk_seconds(&self) -> f64 {
+        self.chunk_ms / 1000.0
+    }
+
+    /// Time the whole chain took for the last chunk as a share of the audio
+    /// it covered (above `1.0` the chain cannot keep up).
+    pub(super) fn chain_load(&self) -> f64 {
+        if self.chunk_ms <= 0.0 {
+            return 0.0;
+        }
+        self.timings
+            .iter()
+            .map(|timing| timing.last_ms)
+            .sum::<f64>()
+            / self.chunk_ms
+    }
+
+    /// Timings for the current chain, in chain order.
+    pub(super) fn timings(&self) -> &[EffectTiming] {
+        &self.timings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::effects::{GainEffect, LimiterEffect};
+
+    fn chain() -> Vec<AudioEffect> {
+        vec![
+            AudioEffect::Gain(GainEffect::default()),
+            AudioEffect::Limiter(LimiterEffect::default()),
+        ]
+    }
+
+    #[test]
+    fn laps_build_rolling_average_and_max() {
+        let mut timer = EffectTimer::new();
+        timer.start(&chain(), 0.01);
+        timer.record(0, 2.0);
+        timer.record(0, 4.0);
+        timer.record(1, 1.0);
+        let timings = timer.timings();
+        assert_eq!(timings[0].name, "Gain");
+        assert_eq!(timings[0].last_ms, 4.0);
+        assert!((timings[0].avg_ms - 2.2).abs() < 1e-9);
+        assert_eq!(timings[0].max_ms, 4.0);
+        assert!((timings[0].avg_load - 0.22).abs() < 1e-9);
+        assert_eq!(timings[1].index, 1);
+        assert_eq!(timings[1].avg_ms, 1.0);
+        assert!((timer.chain_load() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn history_restarts_when_the_chain_changes() {
+        let mut timer = EffectTimer::new();
+        timer.start(&chain(), 0.01);
+        timer.record(0, 3.0);
+        timer.start(&chain(), 0.01);
+        assert_eq!(timer.timings()[0].max_ms, 3.0);
+
+        let mut reordered = chain();
+        reordered.reverse();
+        timer.start(&reordered, 0.01);
+        assert_eq!(timer.timings()[0].name, "Limiter");
+        assert_eq!(timer.timings()[0].max_ms, 0.0);
+    }
+}
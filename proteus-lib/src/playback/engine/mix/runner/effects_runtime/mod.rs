@@ -0,0 +1,376 @@
+//! Effect-chain processing, draining, and runtime update helpers.
+//!
+//! The mix thread owns a local copy of the effect chain (`local_effects`) and
+//! runs all DSP processing on it without holding the shared effects mutex.
+//! Control-path settings changes arrive through a lightweight command queue
+//! that is drained at chunk boundaries.
+
+mod parameters;
+mod tail;
+mod updates;
+
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use log::{debug, info, warn};
+
+use crate::dsp::dither::Ditherer;
+use crate::playback::mutex_policy::lock_recoverable;
+
+use super::super::super::one_shot::mix_one_shots;
+use super::super::effects::run_effect_chain;
+use super::super::output_stage;
+use super::chunking;
+use super::gain_staging;
+use super::live_input::mix_live_input;
+use super::overload;
+use super::state::MixLoopState;
+use super::watchdog;
+
+pub(super) use parameters::apply_effect_parameter;
+pub(super) use tail::drain_effect_tail;
+pub(super) use updates::{apply_effect_runtime_updates, schedule_effect_enable_fade};
+
+pub(super) fn process_and_send_samples(
+    mut samples: Vec<f32>,
+    state: &mut MixLoopState,
+    startup_trace: Instant,
+) -> bool {
+    state.running_count += samples.len();
+    debug!("processed {} samples so far", state.running_count);
+    if samples.len() < state.convolution_batch_samples {
+        warn!(
+            "Only processing {} samples! (Convolution wants {})",
+            samples.len(),
+            state.convolution_batch_samples
+        );
+    }
+    #[cfg(feature = "debug")]
+    let audio_time_ms = if state.audio_info.channels > 0 && state.audio_info.sample_rate > 0 {
+        (samples.len() as f64
+            / state.audio_info.channels as f64
+            / state.audio_info.sample_rate as f64)
+            * 1000.0
+    } else {
+        0.0
+    };
+    #[cfg(feature = "debug")]
+    let dsp_start = Instant::now();
+    gain_staging::apply_loudness_trim(state, &mut samples);
+    mix_live_input(state, &mut samples);
+    gain_staging::record_inputs(state, &samples);
+    process_automated_effects(samples.as_slice(), state);
+    apply_chain_mix(Some(samples.as_slice()), state);
+    if discard_preroll(state) {
+        return true;
+    }
+    mix_overlays(state);
+    gain_staging::record_master(state);
+    apply_output_dither(state);
+    watchdog::observe_output_chunk(state, &samples);
+    #[cfg(feature = "debug")]
+    update_debug_metrics(
+        state,
+        dsp_start,
+        audio_time_ms,
+        state.effect_scratch_a.len(),
+    );
+    let slice_samples = chunking::output_slice_samples(state, samples.len());
+    match output_stage::send_samples(
+        &state.sender,
+        &state.abort,
+        state.audio_info.channels as u16,
+        state.audio_info.sample_rate,
+        &state.effect_scratch_a,
+        slice_samples,
+    ) {
+        output_stage::SendStatus::Sent => {
+            if !state.logged_first_output_send {
+                state.logged_first_output_send = true;
+                info!(
+                    "mix startup trace: first output chunk sent at {}ms (processed_samples={})",
+                    startup_trace.elapsed().as_millis(),
+                    samples.len()
+                );
+            }
+            state.buffer_notify.notify_all();
+        }
+        output_stage::SendStatus::Empty => {}
+        output_stage::SendStatus::Aborted => return false,
+        output_stage::SendStatus::Disconnected => {
+            state.abort.store(true, Ordering::SeqCst);
+            return false;
+        }
+    }
+    let mut metrics = state.lock_dsp_metrics_recoverable();
+    metrics.track_key_count = state.buffer_mixer.instance_count();
+    metrics.prot_key_count = state.buffer_mixer.logical_track_count();
+    metrics.finished_track_count = state.buffer_mixer.finished_instance_count();
+    metrics.output_queue_ms = state.sender.queued_ms();
+    metrics.output_queue_capacity_ms = state.sender.capacity_ms();
+    metrics.reverb = state
+        .local_effects
+        .iter()
+        .filter_map(|effect| effect.as_convolution_reverb())
+        .find(|reverb| reverb.enabled)
+        .and_then(|reverb| reverb.metrics());
+    true
+}
+
+/// Drop processed pre-roll from the front of the chunk.
+///
+/// Returns `true` when nothing audible is left to send.
+fn discard_preroll(state: &mut MixLoopState) -> bool {
+    if state.preroll_samples_left == 0 {
+        return false;
+    }
+    let discard = state.preroll_samples_left.min(state.effect_scratch_a.len());
+    state.effect_scratch_a.drain(..discard);
+    state.preroll_samples_left -= discard;
+    state.effect_scratch_a.is_empty()
+}
+
+/// Blend the chain input (`None` while draining) back into the processed
+/// mix at the configured chain mix.
+fn apply_chain_mix(dry: Option<&[f32]>, state: &mut MixLoopState) {
+    let target = state.lock_buffer_settings_recoverable().chain_mix;
+    let channels = state.audio_info.channels as usize;
+    let latency_frames = state
+        .local_effects
+        .iter()
+        .map(|effect| effect.latency_frames())
+        .sum();
+    let ramp_frames = state.effect_context.parameter_ramp_samples();
+    match dry {
+        Some(dry) => state.chain_mixer.process(
+            target,
+            dry,
+            &mut state.effect_scratch_a,
+            channels,
+            latency_frames,
+            ramp_frames,
+        ),
+        None => state.chain_mixer.process_tail(
+            target,
+            &mut state.effect_scratch_a,
+            channels,
+            latency_frames,
+            ramp_frames,
+        ),
+    }
+}
+
+/// Duck the processed main mix while overlays play, mix one-shots in, then
+/// apply volume automation to the result.
+fn mix_overlays(state: &mut MixLoopState) {
+    let one_shots_playing = !state.lock_one_shots_recoverable().is_empty();
+    let keyed = one_shots_playing || state.live_input_runtime.keyed;
+    let ducking = state.lock_buffer_settings_recoverable().ducking;
+    state.ducker.process(
+        &ducking,
+        keyed,
+        &mut state.effect_scratch_a,
+        state.audio_info.channels as usize,
+        state.audio_info.sample_rate,
+    );
+    if one_shots_playing {
+        mix_one_shots(&state.one_shots, &mut state.effect_scratch_a);
+    }
+    let channels = state.audio_info.channels as usize;
+    let sample_rate = state.audio_info.sample_rate;
+    let mut ramp = lock_recoverable(
+        &state.volume_ramp,
+        "mix runtime volume ramp",
+        "volume automation is a scalar control ramp",
+    );
+    ramp.process(&mut state.effect_scratch_a, channels, sample_rate);
+}
+
+/// Dither the final output to the configured bit depth, if enabled.
+fn apply_output_dither(state: &mut MixLoopState) {
+    let Some(settings) = state.lock_buffer_settings_recoverable().output_dither else {
+        state.output_ditherer = None;
+        return;
+    };
+    let channels = state.audio_info.channels as usize;
+    let ditherer = match state.output_ditherer.as_mut() {
+        Some(ditherer) if ditherer.settings() == settings => ditherer,
+        _ => state
+            .output_ditherer
+            .insert(Ditherer::new(settings, channels)),
+    };
+    ditherer.process(&mut state.effect_scratch_a);
+}
+
+/// Run the chunk through the effect chain, splitting it wherever a container
+/// automation change lands so each change takes effect on its exact sample.
+fn process_automated_effects(samples: &[f32], state: &mut MixLoopState) {
+    let chunk_start = state.running_count - samples.len();
+    let chunk_end = state.running_count;
+    apply_due_automation(state, chunk_start);
+    let Some(first_split) = state.automation.next_split(chunk_start, chunk_end) else {
+        process_effects(samples, state);
+        return;
+    };
+    // Automation is sparse, so collecting the segments in a fresh buffer is
+    // acceptable for the few chunks that contain a change.
+    let mut processed = Vec::with_capacity(samples.len());
+    let mut offset = 0;
+    let mut split = Some(first_split);
+    while let Some(at) = split {
+        let end = at - chunk_start;
+        process_effects(&samples[offset..end], state);
+        processed.extend_from_slice(&state.effect_scratch_a);
+        offset = end;
+        apply_due_automation(state, at);
+        split = state.automation.next_split(at, chunk_end);
+    }
+    process_effects(&samples[offset..], state);
+    processed.extend_from_slice(&state.effect_scratch_a);
+    state.effect_scratch_a = processed;
+}
+
+/// Apply automation changes due at or before `position` to the local chain,
+/// any in-flight transition target, and the shared chain for control reads.
+fn apply_due_automation(state: &mut MixLoopState, position: usize) {
+    let due = state.automation.take_due(position);
+    if due.is_empty() {
+        return;
+    }
+    let mut shared = lock_recoverable(
+        &state.effects,
+        "mix runtime effects",
+        "the effect chain is hot-swappable runtime state",
+    );
+    for (effect_index, parameter) in due {
+        if let Some(effect) = shared.get_mut(effect_index) {
+            apply_effect_parameter(effect, parameter.clone());
+        }
+        if let Some(effect) = state
+            .active_inline_transition
+            .as_mut()
+            .and_then(|transition| transition.new_effects.get_mut(effect_index))
+        {
+            apply_effect_parameter(effect, parameter.clone());
+        }
+        if let Some(effect) = state.local_effects.get_mut(effect_index) {
+            apply_effect_parameter(effect, parameter);
+        }
+    }
+}
+
+fn process_effects(samples: &[f32], state: &mut MixLoopState) {
+    if let Some(transition) = state.active_inline_transition.as_mut() {
+        // Run old effects chain; result ends up in scratch_a.
+        run_effect_chain(
+            &mut transition.old_effects,
+            samples,
+            &state.effect_context,
+            false,
+            &mut state.effect_scratch_a,
+            &mut state.effect_scratch_b,
+            None,
+        );
+        // During a transition we need both outputs simultaneously, so we save
+        // old_out in a temporary Vec. Transitions are non-steady-state so this
+        // single allocation per chunk is acceptable.
+        let old_out: Vec<f32> = state.effect_scratch_a.clone();
+
+        // Run new effects chain; result ends up in scratch_a.
+        run_effect_chain(
+            &mut transition.new_effects,
+            samples,
+            &state.effect_context,
+            false,
+            &mut state.effect_scratch_a,
+            &mut state.effect_scratch_b,
+            None,
+        );
+
+        // Both chains honour the block contract, so their outputs line up.
+        debug_assert_eq!(old_out.len(), state.effect_scratch_a.len());
+        state.effect_scratch_b.clear();
+        state.effect_scratch_b.reserve(old_out.len());
+        let mix = if transition.total_samples == 0 {
+            1.0
+        } else {
+            let done = transition
+                .total_samples
+                .saturating_sub(transition.remaining_samples);
+            (done as f32 / transition.total_samples as f32).clamp(0.0, 1.0)
+        };
+        for (o, n) in old_out.iter().zip(state.effect_scratch_a.iter()) {
+            state.effect_scratch_b.push((o * (1.0 - mix)) + (n * mix));
+        }
+        std::mem::swap(&mut state.effect_scratch_a, &mut state.effect_scratch_b);
+
+        transition.remaining_samples = transition
+            .remaining_samples
+            .saturating_sub(samples.len().max(1));
+    } else {
+        // DSP runs on the mix-thread-owned local chain — no mutex held.
+        gain_staging::run_metered_chain(state, samples);
+        overload::observe_chain_load(state);
+    }
+
+    // Finalize transition: adopt new effects as the local chain and sync shared.
+    if state
+        .active_inline_transition
+        .as_ref()
+        .is_some_and(|transition| transition.remaining_samples == 0)
+    {
+        if let Some(transition) = state.active_inline_transition.take() {
+            let completed = transition.new_effects;
+            *state.lock_effects_recoverable() = completed.clone();
+            state.local_effects = completed;
+            state.effect_enable_fades = vec![None; state.local_effects.len()];
+            overload::chain_replaced(state);
+        }
+    }
+}
+
+#[cfg(feature = "debug")]
+fn update_debug_metrics(
+    state: &mut MixLoopState,
+    dsp_start: Instant,
+    audio_time_ms: f64,
+    processed_len: usize,
+) {
+    let dsp_time_ms = dsp_start.elapsed().as_secs_f64() * 1000.0;
+    let overrun_ms = (dsp_time_ms - audio_time_ms).max(0.0);
+    let chain_ksps = if dsp_time_ms > 0.0 {
+        (processed_len as f64 / (dsp_time_ms / 1000.0)) / 1000.0
+    } else {
+        0.0
+    };
+    state.avg_overrun_ms = if state.avg_overrun_ms == 0.0 {
+        overrun_ms
+    } else {
+        (state.avg_overrun_ms * (1.0 - state.alpha)) + (overrun_ms * state.alpha)
+    };
+    state.avg_chain_ksps = if state.avg_chain_ksps == 0.0 {
+        chain_ksps
+    } else {
+        (state.avg_chain_ksps * (1.0 - state.alpha)) + (chain_ksps * state.alpha)
+    };
+    if overrun_ms > 0.0 {
+        state.max_overrun_ms = state.max_overrun_ms.max(overrun_ms);
+    }
+    if chain_ksps > 0.0 {
+        state.min_chain_ksps = state.min_chain_ksps.min(chain_ksps);
+        state.max_chain_ksps = state.max_chain_ksps.max(chain_ksps);
+    }
+    let mut metrics = state.lock_dsp_metrics_recoverable();
+    metrics.overrun = dsp_time_ms > audio_time_ms;
+    metrics.overrun_ms = overrun_ms;
+    metrics.avg_overrun_ms = state.avg_overrun_ms;
+    metrics.max_overrun_ms = state.max_overrun_ms;
+    metrics.chain_ksps = chain_ksps;
+    metrics.avg_chain_ksps = state.avg_chain_ksps;
+    metrics.min_chain_ksps = if state.min_chain_ksps.is_finite() {
+        state.min_chain_ksps
+    } else {
+        0.0
+    };
+    metrics.max_chain_ksps = state.max_chain_ksps;
+}
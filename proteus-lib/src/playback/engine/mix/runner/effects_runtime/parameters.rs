@@ -0,0 +1,107 @@
+//! Parameter changes applied to individual effects in the local chain.
+
+use super::super::super::types::EffectParameter;
+
+pub(crate) fn apply_effect_parameter(
+    effect: &mut crate::dsp::effects::AudioEffect,
+    param: EffectParameter,
+) {
+    use crate::dsp::effects::AudioEffect;
+    match param {
+        EffectParameter::Gain(v) => {
+            if let AudioEffect::Gain(e) = effect {
+                e.settings.gain = v;
+            }
+        }
+        EffectParameter::Pan(v) => {
+            if let AudioEffect::Pan(e) = effect {
+                e.settings.pan = v;
+            }
+        }
+        EffectParameter::ReverbMix(v) => {
+            let clamped = v.clamp(0.0, 1.0);
+            match effect {
+                AudioEffect::ConvolutionReverb(e) => e.dry_wet = clamped,
+                AudioEffect::DelayReverb(e) => e.mix = clamped,
+                AudioEffect::DiffusionReverb(e) => e.mix = clamped,
+                AudioEffect::ShimmerReverb(e) => e.mix = clamped,
+                _ => {}
+            }
+        }
+        EffectParameter::DistortionGain(v) => {
+            if let AudioEffect::Distortion(e) = effect {
+                e.settings.gain = v;
+            }
+        }
+        EffectParameter::DistortionThreshold(v) => {
+            if let AudioEffect::Distortion(e) = effect {
+                e.settings.threshold = v;
+            }
+        }
+        EffectParameter::LowPassFreqHz(v) => {
+            if let AudioEffect::LowPassFilter(e) = effect {
+                e.settings.freq_hz = v;
+            }
+        }
+        EffectParameter::LowPassQ(v) => {
+            if let AudioEffect::LowPassFilter(e) = effect {
+                e.settings.q = v;
+            }
+        }
+        EffectParameter::HighPassFreqHz(v) => {
+            if let AudioEffect::HighPassFilter(e) = effect {
+                e.settings.freq_hz = v;
+            }
+        }
+        EffectParameter::HighPassQ(v) => {
+            if let AudioEffect::HighPassFilter(e) = effect {
+                e.settings.q = v;
+            }
+        }
+        EffectParameter::CompressorThresholdDb(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.threshold_db = v;
+            }
+        }
+        EffectParameter::CompressorRatio(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.ratio = v;
+            }
+        }
+        EffectParameter::CompressorAttackMs(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.attack_ms = v;
+            }
+        }
+        EffectParameter::CompressorReleaseMs(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.release_ms = v;
+            }
+        }
+        EffectParameter::CompressorMakeupDb(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.makeup_gain_db = v;
+            }
+        }
+        EffectParameter::LimiterThresholdDb(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.threshold_db = v;
+            }
+        }
+        EffectParameter::LimiterKneeWidthDb(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.knee_width_db = v;
+            }
+        }
+        EffectParameter::LimiterAttackMs(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.attack_ms = v;
+            }
+        }
+        EffectParameter::LimiterReleaseMs(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.release_ms = v;
+            }
+        }
+    }
+}
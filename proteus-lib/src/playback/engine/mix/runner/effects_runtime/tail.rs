@@ -0,0 +1,118 @@
+//! Effect tail draining once every source has finished.
+
+use std::sync::atomic::Ordering;
+
+use log::{info, warn};
+
+#[cfg(feature = "debug")]
+use crate::logging::pivot_buffer_trace::pivot_buffer;
+
+use super::super::super::effects::run_effect_chain;
+use super::super::super::output_stage;
+use super::super::chunking;
+use super::super::loop_body::{
+    DRAIN_SILENCE_EPSILON, DRAIN_SILENT_PASSES_TO_STOP, MAX_EFFECT_DRAIN_PASSES,
+};
+use super::super::state::MixLoopState;
+use super::{apply_chain_mix, apply_output_dither, mix_overlays};
+
+pub(crate) fn drain_effect_tail(state: &mut MixLoopState) -> bool {
+    #[cfg(feature = "debug")]
+    let _ = pivot_buffer();
+    info!("mix finished in runner");
+    state.effect_drain_passes = state.effect_drain_passes.saturating_add(1);
+    if state.effect_drain_passes > MAX_EFFECT_DRAIN_PASSES {
+        warn!(
+            "effect drain stopped after {} passes to avoid infinite tail generation",
+            MAX_EFFECT_DRAIN_PASSES
+        );
+        return false;
+    }
+
+    drain_effect_chains(state);
+    apply_chain_mix(None, state);
+    mix_overlays(state);
+    apply_output_dither(state);
+
+    if state.effect_scratch_a.is_empty() {
+        return false;
+    }
+
+    let max_abs = state
+        .effect_scratch_a
+        .iter()
+        .fold(0.0_f32, |acc, s| acc.max(s.abs()));
+    if max_abs <= DRAIN_SILENCE_EPSILON {
+        state.effect_drain_silent_passes = state.effect_drain_silent_passes.saturating_add(1);
+    } else {
+        state.effect_drain_silent_passes = 0;
+    }
+    if state.effect_drain_silent_passes >= DRAIN_SILENT_PASSES_TO_STOP {
+        info!("effect drain stopped after consecutive silent drain passes");
+        return false;
+    }
+
+    let slice_samples = chunking::output_slice_samples(state, 0);
+    match output_stage::send_samples(
+        &state.sender,
+        &state.abort,
+        state.audio_info.channels as u16,
+        state.audio_info.sample_rate,
+        &state.effect_scratch_a,
+        slice_samples,
+    ) {
+        output_stage::SendStatus::Sent => true,
+        output_stage::SendStatus::Empty | output_stage::SendStatus::Aborted => false,
+        output_stage::SendStatus::Disconnected => {
+            state.abort.store(true, Ordering::SeqCst);
+            false
+        }
+    }
+}
+
+fn drain_effect_chains(state: &mut MixLoopState) {
+    if let Some(transition) = state.active_inline_transition.as_mut() {
+        run_effect_chain(
+            &mut transition.old_effects,
+            &[],
+            &state.effect_context,
+            true,
+            &mut state.effect_scratch_a,
+            &mut state.effect_scratch_b,
+            None,
+        );
+        let old_out: Vec<f32> = state.effect_scratch_a.clone();
+
+        run_effect_chain(
+            &mut transition.new_effects,
+            &[],
+            &state.effect_context,
+            true,
+            &mut state.effect_scratch_a,
+            &mut state.effect_scratch_b,
+            None,
+        );
+
+        let len = old_out.len().max(state.effect_scratch_a.len());
+        state.effect_scratch_b.clear();
+        for i in 0..len {
+            state.effect_scratch_b.push(
+                (old_out.get(i).copied().unwrap_or(0.0)
+                    + state.effect_scratch_a.get(i).copied().unwrap_or(0.0))
+                    * 0.5,
+            );
+        }
+        std::mem::swap(&mut state.effect_scratch_a, &mut state.effect_scratch_b);
+    } else {
+        // Drain runs on the local chain — no mutex held.
+        run_effect_chain(
+            &mut state.local_effects,
+            &[],
+            &state.effect_context,
+            true,
+            &mut state.effect_scratch_a,
+            &mut state.effect_scratch_b,
+            Some(&mut state.effect_enable_fades),
+        );
+    }
+}
@@ -0,0 +1,241 @@
+//! Chain swaps, resets, and settings commands from the control path.
+
+use std::sync::atomic::Ordering;
+
+use crate::dsp::effects::{AudioEffect, EffectContext};
+
+use super::super::super::effects::{read_effect_attachments, EffectEnableFade};
+use super::super::super::types::EffectSettingsCommand;
+use super::super::overload;
+use super::super::state::MixLoopState;
+use super::apply_effect_parameter;
+
+pub(crate) fn apply_effect_runtime_updates(state: &mut MixLoopState) {
+    sync_effect_context_from_buffer_settings(state);
+
+    // Drain incremental settings commands from the control path.
+    drain_effect_settings_commands(state);
+
+    let current_reset = state.effects_reset.load(Ordering::SeqCst);
+    if current_reset != state.last_effects_reset {
+        // Full reset: clone the new chain from shared into local.
+        let refreshed_effects = state.lock_effects_recoverable().clone();
+        state.local_effects = refreshed_effects;
+        for effect in state.local_effects.iter_mut() {
+            effect.reset_state();
+        }
+        state.effect_enable_fades = vec![None; state.local_effects.len()];
+        state.active_inline_transition = None;
+        state.lock_inline_effects_update_recoverable().take();
+        state.effect_context = rebuild_effect_context(
+            &state.prot,
+            &state.buffer_settings,
+            &state.local_effects,
+            &state.effect_context,
+        );
+        state.last_effects_reset = current_reset;
+        overload::chain_replaced(state);
+    }
+
+    let pending_update = {
+        let mut pending = state.lock_inline_effects_update_recoverable();
+        pending.take()
+    };
+    if let Some(update) = pending_update {
+        read_effect_attachments(
+            &lock_prot(&state.prot),
+            &update.effects,
+            &mut state.effect_context,
+        );
+        let transition_samples = ((update.transition_ms / 1000.0)
+            * state.audio_info.sample_rate.max(1) as f32)
+            .round() as usize
+            * state.audio_info.channels.max(1) as usize;
+        if transition_samples == 0 {
+            // Instant replacement: adopt new chain as local, sync shared.
+            state.local_effects = update.effects;
+            for effect in state.local_effects.iter_mut() {
+                effect.warm_up(&state.effect_context);
+            }
+            *state.lock_effects_recoverable() = state.local_effects.clone();
+            state.effect_enable_fades = vec![None; state.local_effects.len()];
+            state.active_inline_transition = None;
+            overload::chain_replaced(state);
+        } else {
+            // Crossfade transition: snapshot local chain as old, warm up new.
+            let old_effects = state.local_effects.clone();
+            let mut new_effects = update.effects;
+            for effect in new_effects.iter_mut() {
+                effect.warm_up(&state.effect_context);
+            }
+            state.active_inline_transition = Some(
+                crate::playback::engine::mix::types::ActiveInlineTransition {
+                    old_effects,
+                    new_effects,
+                    total_samples: transition_samples,
+                    remaining_samples: transition_samples,
+                },
+            );
+        }
+    }
+}
+
+/// Drain queued effect settings commands and apply them to the local chain.
+fn drain_effect_settings_commands(state: &mut MixLoopState) {
+    let commands = {
+        let mut pending = state.lock_effect_settings_commands_recoverable();
+        if pending.is_empty() {
+            return;
+        }
+        std::mem::take(&mut *pending)
+    };
+    for command in commands {
+        match command {
+            EffectSettingsCommand::SetReverbEnabled(enabled) => {
+                let mut indices = Vec::new();
+                for (index, effect) in state.local_effects.iter().enumerate() {
+                    if effect.as_convolution_reverb().is_some()
+                        || effect.as_delay_reverb().is_some()
+                        || effect.as_diffusion_reverb().is_some()
+                    {
+                        indices.push(index);
+                    }
+                }
+                for index in indices {
+                    schedule_effect_enable_fade(state, index, enabled);
+                }
+            }
+            EffectSettingsCommand::SetReverbMix(dry_wet) => {
+                let clamped = dry_wet.clamp(0.0, 1.0);
+                for effect in state.local_effects.iter_mut() {
+                    if let Some(e) = effect.as_convolution_reverb_mut() {
+                        e.dry_wet = clamped;
+                    }
+                    if let Some(e) = effect.as_delay_reverb_mut() {
+                        e.mix = clamped;
+                    }
+                    if let Some(e) = effect.as_diffusion_reverb_mut() {
+                        e.mix = clamped;
+                    }
+                }
+            }
+            EffectSettingsCommand::SetEffectParameter {
+                effect_index,
+                parameter,
+            } => {
+                if let Some(effect) = state.local_effects.get_mut(effect_index) {
+                    apply_effect_parameter(effect, parameter);
+                }
+            }
+            EffectSettingsCommand::SetEffectEnabled {
+                effect_index,
+                enabled,
+            } => {
+                schedule_effect_enable_fade(state, effect_index, enabled);
+            }
+        }
+    }
+}
+
+pub(crate) fn schedule_effect_enable_fade(
+    state: &mut MixLoopState,
+    effect_index: usize,
+    enabled: bool,
+) {
+    let Some(effect) = state.local_effects.get_mut(effect_index) else {
+        return;
+    };
+
+    let current_mix = state
+        .effect_enable_fades
+        .get(effect_index)
+        .and_then(Option::as_ref)
+        .map_or_else(
+            || {
+                if effect.is_enabled() {
+                    1.0
+                } else {
+                    0.0
+                }
+            },
+            EffectEnableFade::current_mix,
+        );
+    let target_mix = if enabled { 1.0 } else { 0.0 };
+    if (current_mix - target_mix).abs() < f32::EPSILON {
+        if !enabled {
+            effect.reset_state();
+        }
+        effect.set_enabled(enabled);
+        if let Some(slot) = state.effect_enable_fades.get_mut(effect_index) {
+            *slot = None;
+        }
+        return;
+    }
+
+    if enabled && !effect.is_enabled() && current_mix <= f32::EPSILON {
+        effect.reset_state();
+        effect.set_enabled(true);
+    }
+
+    let ramp_frames = state.effect_context.parameter_ramp_samples();
+    if ramp_frames == 0 {
+        if !enabled {
+            effect.reset_state();
+        }
+        effect.set_enabled(enabled);
+        state.effect_enable_fades[effect_index] = None;
+        return;
+    }
+
+    state.effect_enable_fades[effect_index] =
+        Some(EffectEnableFade::new(current_mix, enabled, ramp_frames));
+}
+
+fn rebuild_effect_context(
+    prot_locked: &std::sync::Arc<std::sync::Mutex<crate::container::prot::Prot>>,
+    buffer_settings: &std::sync::Arc<
+        std::sync::Mutex<crate::playback::engine::PlaybackBufferSettings>,
+    >,
+    effects: &[AudioEffect],
+    previous: &EffectContext,
+) -> EffectContext {
+    let prot = lock_prot(prot_locked);
+    let parameter_ramp_ms = crate::playback::mutex_policy::lock_recoverable(
+        buffer_settings,
+        "mix runtime buffer settings",
+        "buffer settings are runtime configuration snapshots",
+    )
+    .parameter_ramp_ms;
+    let mut context = EffectContext::new(
+        prot.info.sample_rate,
+        prot.info.channels as usize,
+        prot.get_container_path(),
+        prot.get_impulse_response_spec(),
+        prot.get_impulse_response_tail_db().unwrap_or(-60.0),
+    )
+    .expect("prot info must have valid sample rate and channel count");
+    context.set_parameter_ramp_ms(parameter_ramp_ms);
+    context.set_tempo_bpm(prot.get_tempo_bpm());
+    if context.container_path() == previous.container_path() {
+        context.set_impulse_response_attachments(previous.impulse_response_attachments().clone());
+    }
+    read_effect_attachments(&prot, effects, &mut context);
+    context
+}
+
+fn lock_prot(
+    prot: &std::sync::Arc<std::sync::Mutex<crate::container::prot::Prot>>,
+) -> std::sync::MutexGuard<'_, crate::container::prot::Prot> {
+    crate::playback::mutex_policy::lock_invariant(
+        prot,
+        "mix runtime prot",
+        "effect context rebuilds require coherent container metadata",
+    )
+}
+
+fn sync_effect_context_from_buffer_settings(state: &mut MixLoopState) {
+    let parameter_ramp_ms = state.lock_buffer_settings_recoverable().parameter_ramp_ms;
+    state
+        .effect_context
+        .set_parameter_ramp_ms(parameter_ramp_ms);
+}
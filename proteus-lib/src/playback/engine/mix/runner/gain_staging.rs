@@ -0,0 +1,92 @@
+//! Mix-thread side of gain staging diagnostics.
+
+use std::sync::MutexGuard;
+
+use crate::dsp::effects::AudioEffect;
+use crate::playback::gain_staging::{GainStage, GainStagingRecorder, SharedGainStaging};
+use crate::playback::mutex_policy::lock_recoverable;
+
+use super::super::effects::run_effect_chain_observed;
+use super::state::MixLoopState;
+
+fn lock_gain_staging(gain_staging: &SharedGainStaging) -> MutexGuard<'_, GainStagingRecorder> {
+    lock_recoverable(
+        gain_staging,
+        "mix runtime gain staging",
+        "gain staging history is disposable diagnostics",
+    )
+}
+
+fn chunk_seconds(state: &MixLoopState, samples: usize) -> (usize, f64) {
+    let channels = state.audio_info.channels.max(1) as usize;
+    let frames = samples / channels;
+    (
+        frames,
+        frames as f64 / state.audio_info.sample_rate.max(1) as f64,
+    )
+}
+
+/// Scale the track mix by the container's loudness trim, if enabled.
+pub(super) fn apply_loudness_trim(state: &MixLoopState, samples: &mut [f32]) {
+    if state.loudness_gain == 1.0 || !state.lock_buffer_settings_recoverable().loudness_trim {
+        return;
+    }
+    for sample in samples.iter_mut() {
+        *sample *= state.loudness_gain;
+    }
+}
+
+/// Record per-track and summed levels for one chunk, if enabled.
+pub(super) fn record_inputs(state: &MixLoopState, samples: &[f32]) {
+    let mut recorder = lock_gain_staging(&state.gain_staging);
+    if !recorder.is_enabled() {
+        return;
+    }
+    let (frames, seconds) = chunk_seconds(state, samples.len());
+    recorder.record_tracks(state.buffer_mixer.track_levels_by_slot(), frames, seconds);
+    recorder.record(GainStage::Sum, samples, seconds);
+}
+
+/// Record master output levels for the processed chunk, if enabled.
+pub(super) fn record_master(state: &MixLoopState) {
+    let mut recorder = lock_gain_staging(&state.gain_staging);
+    if !recorder.is_enabled() {
+        return;
+    }
+    let (_, seconds) = chunk_seconds(state, state.effect_scratch_a.len());
+    recorder.record(GainStage::Master, &state.effect_scratch_a, seconds);
+}
+
+/// Run the steady-state effect chain, timing each effect and recording its
+/// output level when gain staging is enabled.
+pub(super) fn run_metered_chain(state: &mut MixLoopState, samples: &[f32]) {
+    let (_, seconds) = chunk_seconds(state, samples.len());
+    let mut recorder = lock_gain_staging(&state.gain_staging);
+    let metered = recorder.is_enabled();
+    let timer = &mut state.effect_timer;
+    timer.start(&state.local_effects, seconds);
+    let mut observer = |index: usize, effect: &AudioEffect, output: &[f32]| {
+        timer.lap(index);
+        if metered {
+            let stage = GainStage::Effect {
+                index,
+                name: effect.display_name(),
+            };
+            recorder.record(stage, output, seconds);
+            timer.resume();
+        }
+    };
+    run_effect_chain_observed(
+        &mut state.local_effects,
+        samples,
+        &state.effect_context,
+        false,
+        (&mut state.effect_scratch_a, &mut state.effect_scratch_b),
+        Some(&mut state.effect_enable_fades),
+        Some(&mut observer),
+    );
+    drop(recorder);
+    let mut timings = state.lock_effect_timings_recoverable();
+    timings.clear();
+    timings.extend_from_slice(state.effect_timer.timings());
+}
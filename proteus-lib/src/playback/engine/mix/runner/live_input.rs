@@ -0,0 +1,57 @@
+//! Mix-thread side of the live capture input.
+
+use crate::dsp::effects::AudioEffect;
+use crate::playback::mutex_policy::lock_recoverable;
+
+use super::super::effects::run_effect_chain;
+use super::state::MixLoopState;
+
+/// Mix-thread-owned processing state for the live input track.
+#[derive(Default)]
+pub(super) struct LiveInputRuntime {
+    effects: Vec<AudioEffect>,
+    capture: Vec<f32>,
+    scratch_a: Vec<f32>,
+    scratch_b: Vec<f32>,
+    /// Whether the last processed chunk should key main-mix ducking.
+    pub(super) keyed: bool,
+}
+
+/// Sum one chunk of live input into the pre-effects track mix.
+pub(super) fn mix_live_input(state: &mut MixLoopState, samples: &mut [f32]) {
+    let runtime = &mut state.live_input_runtime;
+    runtime.keyed = false;
+    let duck_threshold = {
+        let mut bus = lock_recoverable(
+            &state.live_input,
+            "mix runtime live input",
+            "captured live input is disposable streaming audio",
+        );
+        let Some(bus) = bus.as_mut() else {
+            runtime.effects.clear();
+            return;
+        };
+        if let Some(effects) = bus.take_pending_effects() {
+            runtime.effects = effects;
+        }
+        runtime.capture.resize(samples.len(), 0.0);
+        bus.take_into(&mut runtime.capture);
+        bus.duck_threshold()
+    };
+
+    run_effect_chain(
+        &mut runtime.effects,
+        &runtime.capture,
+        &state.effect_context,
+        false,
+        &mut runtime.scratch_a,
+        &mut runtime.scratch_b,
+        None,
+    );
+    let mut peak = 0.0_f32;
+    for (out, sample) in samples.iter_mut().zip(&runtime.scratch_a) {
+        *out += *sample;
+        peak = peak.max(sample.abs());
+    }
+    runtime.keyed = duck_threshold.is_some_and(|threshold| peak >= threshold);
+}
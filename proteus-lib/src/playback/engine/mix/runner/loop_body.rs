@@ -11,6 +11,7 @@ use super::super::buffer_mixer::BufferMixer;
 use super::super::decoder_events::DecodeWorkerEvent;
 use super::effects_runtime;
 use super::state::MixLoopState;
+use super::watchdog;
 
 pub(super) const MAX_EFFECT_DRAIN_PASSES: usize = 1024;
 pub(super) const DRAIN_SILENCE_EPSILON: f32 = 1.0e-6;
@@ -29,6 +30,9 @@ pub(super) fn run_mix_loop(state: &mut MixLoopState, startup_trace: Instant) {
             &mut state.logged_first_packet_route,
         );
         apply_inline_track_mix_updates(&state.inline_track_mix_updates, &mut state.buffer_mixer);
+        apply_inline_group_gains(&state.inline_group_gains, &mut state.buffer_mixer);
+        let pan_law = state.lock_buffer_settings_recoverable().pan_law;
+        state.buffer_mixer.set_pan_law_override(pan_law);
         effects_runtime::apply_effect_runtime_updates(state);
         if !state.started {
             if state
@@ -58,6 +62,7 @@ pub(super) fn run_mix_loop(state: &mut MixLoopState, startup_trace: Instant) {
                 break;
             }
         } else {
+            watchdog::observe_input_wait(state);
             thread::sleep(Duration::from_millis(2));
         }
     }
@@ -69,6 +74,9 @@ fn take_next_samples(state: &mut MixLoopState, startup_trace: Instant) -> Option
         return Some(state.pending_mix_samples.pop_chunk(batch));
     }
     if let Some(samples) = state.buffer_mixer.take_samples() {
+        state
+            .buffer_mixer
+            .copy_track_levels_by_slot(&mut state.lock_track_levels_recoverable());
         if !state.logged_first_take_samples {
             state.logged_first_take_samples = true;
             info!(
@@ -133,44 +141,53 @@ pub(super) fn drain_decode_events(
             );
             *logged_first_packet_drain = true;
         }
-        match event {
-            DecodeWorkerEvent::Packet(packet) => {
-                if !*logged_first_packet_route {
-                    info!(
+        if let DecodeWorkerEvent::Packet(packet) = &event {
+            if !*logged_first_packet_route {
+                info!(
                         "mix startup trace: first packet route start at {}ms (source={:?} ts={:.6} samples={})",
                         startup_trace.elapsed().as_millis(),
                         packet.source_key,
                         packet.packet_ts,
                         packet.samples.len()
                     );
-                    *logged_first_packet_route = true;
-                }
-                let _decision =
-                    buffer_mixer.route_packet(&packet.samples, packet.source_key, packet.packet_ts);
+                *logged_first_packet_route = true;
             }
-            DecodeWorkerEvent::SourceFinished { source_key } => {
+        }
+        route_decode_event(buffer_mixer, event);
+    }
+}
+
+/// Apply one decode worker event to the buffer mixer.
+pub(super) fn route_decode_event(buffer_mixer: &mut BufferMixer, event: DecodeWorkerEvent) {
+    match event {
+        DecodeWorkerEvent::Packet(packet) => {
+            let _decision =
+                buffer_mixer.route_packet(&packet.samples, packet.source_key, packet.packet_ts);
+        }
+        DecodeWorkerEvent::SourceFinished { source_key } => {
+            buffer_mixer.signal_finish(&source_key);
+        }
+        DecodeWorkerEvent::SourceError {
+            source_key,
+            recoverable,
+            message,
+        } => {
+            if recoverable {
+                warn!(
+                    "decode worker recoverable error: source={:?} {}",
+                    source_key, message
+                );
+            } else {
+                warn!(
+                    "decode worker terminal error: source={:?} {}",
+                    source_key, message
+                );
                 buffer_mixer.signal_finish(&source_key);
             }
-            DecodeWorkerEvent::SourceError {
-                source_key,
-                recoverable,
-                message,
-            } => {
-                if recoverable {
-                    warn!(
-                        "decode worker recoverable error: source={:?} {}",
-                        source_key, message
-                    );
-                } else {
-                    warn!(
-                        "decode worker terminal error: source={:?} {}",
-                        source_key, message
-                    );
-                    buffer_mixer.signal_finish(&source_key);
-                }
-            }
-            DecodeWorkerEvent::StreamExhausted => {
-                buffer_mixer.signal_finish_all();
+        }
+        DecodeWorkerEvent::StreamExhausted { sources } => {
+            for source_key in &sources {
+                buffer_mixer.signal_finish(source_key);
             }
         }
     }
@@ -194,6 +211,22 @@ pub(super) fn apply_inline_track_mix_updates(
     }
 }
 
+/// Apply the latest pending group gains to the buffer mixer.
+pub(super) fn apply_inline_group_gains(
+    inline_group_gains: &Arc<Mutex<Option<Vec<f32>>>>,
+    buffer_mixer: &mut BufferMixer,
+) {
+    let gains = crate::playback::mutex_policy::lock_recoverable(
+        inline_group_gains,
+        "mix runtime inline group gains",
+        "pending group gains are a disposable snapshot",
+    )
+    .take();
+    if let Some(gains) = gains {
+        buffer_mixer.set_track_group_gains(&gains);
+    }
+}
+
 /// Apply effect resets and inline effect transitions for the current loop iteration.
 #[cfg(test)]
 mod tests;
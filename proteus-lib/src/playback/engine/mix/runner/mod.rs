@@ -1,25 +1,43 @@
 //! Core mix-thread runtime loop implementation.
 
+mod chunking;
 mod decode;
+mod effect_timing;
 mod effects_runtime;
+mod gain_staging;
+mod live_input;
 mod loop_body;
+pub(crate) mod offline;
+mod overload;
 mod startup;
 mod state;
+mod watchdog;
 
-use rodio::buffer::SamplesBuffer;
-use std::sync::mpsc;
 use std::thread;
 use std::thread::JoinHandle;
 use std::time::Instant;
 
+use crate::diagnostics::log_capture::{current_session, SessionScope};
+use crate::playback::mutex_policy::lock_recoverable;
+
+use super::output_queue::{output_queue, OutputReceiver};
 use super::MixThreadArgs;
 
 /// Spawn the mixing thread and return a receiver of mixed audio buffers.
-pub fn spawn_mix_thread(
-    args: MixThreadArgs,
-) -> (mpsc::Receiver<(SamplesBuffer, f64)>, JoinHandle<()>) {
-    let (sender, receiver) = mpsc::sync_channel::<(SamplesBuffer, f64)>(1);
+///
+/// The receiver holds up to `output_queue_ms` of audio from the buffer
+/// settings in effect when the thread starts.
+pub fn spawn_mix_thread(args: MixThreadArgs) -> (OutputReceiver, JoinHandle<()>) {
+    let queue_ms = lock_recoverable(
+        &args.buffer_settings,
+        "mix output queue settings",
+        "buffer settings are runtime configuration snapshots",
+    )
+    .output_queue_ms;
+    let (sender, receiver) = output_queue(queue_ms);
+    let session = current_session();
     let handle = thread::spawn(move || {
+        let _scope = SessionScope::enter(session);
         let startup_trace = Instant::now();
         let Some(mut state) = startup::setup_mix_state(args, sender, startup_trace) else {
             return;
@@ -38,8 +56,8 @@ mod tests {
 
     #[test]
     fn drain_constants_are_positive() {
-        assert!(MAX_EFFECT_DRAIN_PASSES > 0);
-        assert!(DRAIN_SILENT_PASSES_TO_STOP > 0);
-        assert!(DRAIN_SILENCE_EPSILON > 0.0);
+        const { assert!(MAX_EFFECT_DRAIN_PASSES > 0) };
+        const { assert!(DRAIN_SILENT_PASSES_TO_STOP > 0) };
+        const { assert!(DRAIN_SILENCE_EPSILON > 0.0) };
     }
 }
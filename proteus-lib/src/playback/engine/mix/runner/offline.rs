@@ -0,0 +1,518 @@
+//! Offline (faster than real time) rendering through the mix-thread pipeline.
+//!
+//! Uses the same startup planning, buffer mixer, and effect chain as live
+//! playback, but pulls chunks as fast as they can be produced instead of
+//! pacing them against a sink. Each stage is timed so the cost of decoding,
+//! mixing, and every effect can be measured on real containers.
+//!
+//! Sources are decoded either by the regular decode worker threads or inline
+//! on the calling thread. Inline decoding fills the whole render window before
+//! mixing, so chunk boundaries never depend on thread scheduling and the
+//! output is bit-identical across runs.
+
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::container::prot::Prot;
+use crate::dsp::effects::{AudioEffect, EffectContext};
+use crate::playback::engine::premix::PremixBuffer;
+use crate::playback::engine::{DecodePool, PlaybackBufferSettings, ReaderCache};
+use crate::playback::mutex_policy::lock_recoverable;
+
+use super::super::automation::EffectAutomation;
+use super::super::buffer_mixer::{BufferMixer, DecodeBackpressure, SourceKey};
+use super::super::decoder_events::{DecodeEventSink, DecodeWorkerEvent};
+use super::decode::{
+    container_track_groups, run_container_decode_worker, run_file_decode_worker, ContainerSource,
+    DecodeStart,
+};
+use super::effects_runtime::apply_effect_parameter;
+use super::loop_body::route_decode_event;
+use super::startup::{
+    compute_mix_buffer_sizes, prepare_buffer_mixer, prepare_runtime_startup,
+    spawn_mix_decode_workers, SpawnDecodeArgs,
+};
+
+/// How long to block on the decode channel before re-checking mixer state.
+const DECODE_WAIT_TIMEOUT: Duration = Duration::from_millis(50);
+/// Extra audio decoded past the render window in inline mode so interleaved
+/// container tracks are all covered before decoding stops.
+const INLINE_DECODE_MARGIN_SECONDS: f64 = 1.0;
+
+/// How an offline render decodes its sources.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DecodeThreading {
+    /// Regular decode worker threads with backpressure, as in live playback.
+    Workers,
+    /// Decode the render window on the calling thread before mixing.
+    Inline,
+}
+
+/// Decode mode and optional cancellation flag for an offline render.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OfflineRun<'a> {
+    /// How sources are decoded.
+    pub(crate) threading: DecodeThreading,
+    /// Set from another thread to stop the render early.
+    pub(crate) cancel: Option<&'a AtomicBool>,
+}
+
+impl OfflineRun<'_> {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.is_some_and(|flag| flag.load(Ordering::Acquire))
+    }
+}
+
+impl From<DecodeThreading> for OfflineRun<'_> {
+    fn from(threading: DecodeThreading) -> Self {
+        Self {
+            threading,
+            cancel: None,
+        }
+    }
+}
+
+/// Wall-clock time spent in each pipeline stage during an offline render.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OfflineStageTimings {
+    /// Time spent decoding inline, or blocked waiting for decode workers.
+    pub(crate) decode: Duration,
+    /// Time spent routing packets and mixing track buffers.
+    pub(crate) mix: Duration,
+    /// Time spent in each effect, in chain order.
+    pub(crate) effects: Vec<Duration>,
+}
+
+/// Summary of an offline render.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct OfflineRenderStats {
+    /// Interleaved samples delivered to the chunk callback.
+    pub(crate) rendered_samples: usize,
+    /// Per-stage timings.
+    pub(crate) timings: OfflineStageTimings,
+    /// The render stopped early because `OfflineRun::cancel` was set.
+    pub(crate) cancelled: bool,
+}
+
+/// Render up to `max_seconds` of `prot` from `start_time`, passing each
+/// post-effects chunk to `on_chunk`.
+///
+/// Effects are warmed up before rendering and are not drained afterwards, so
+/// the output covers exactly the requested window (or less if the mix ends
+/// or the render is cancelled).
+pub(crate) fn render_offline(
+    prot: Prot,
+    effects: Vec<AudioEffect>,
+    buffer_settings: PlaybackBufferSettings,
+    start_time: f64,
+    max_seconds: f64,
+    run: OfflineRun<'_>,
+    mut on_chunk: impl FnMut(&[f32]),
+) -> OfflineRenderStats {
+    let audio_info = prot.info.clone();
+    let channels = audio_info.channels.max(1) as usize;
+    let max_seconds = max_seconds.max(0.0);
+    let max_samples = (max_seconds * f64::from(audio_info.sample_rate)) as usize * channels;
+    let mut stats = OfflineRenderStats {
+        rendered_samples: 0,
+        timings: OfflineStageTimings {
+            effects: vec![Duration::ZERO; effects.len()],
+            ..OfflineStageTimings::default()
+        },
+        cancelled: false,
+    };
+
+    let loudness_gain = if buffer_settings.loudness_trim {
+        prot.get_loudness_tag().map_or(1.0, |tag| tag.trim_gain())
+    } else {
+        1.0
+    };
+    let mut automation = EffectAutomation::new(
+        &prot.get_effect_automation(),
+        start_time,
+        audio_info.sample_rate,
+        channels,
+    );
+    let prot = Arc::new(Mutex::new(prot));
+    let buffer_settings = Arc::new(Mutex::new(buffer_settings));
+    let effects = Arc::new(Mutex::new(effects));
+    let startup = prepare_runtime_startup(&prot, &buffer_settings, &effects, start_time);
+    if startup.is_empty() || max_samples == 0 {
+        return stats;
+    }
+    let sizes = compute_mix_buffer_sizes(&audio_info, &buffer_settings, &effects);
+    let mut effects = lock_recoverable(
+        &effects,
+        "offline render effects",
+        "the effect chain is owned by this render",
+    );
+    let inline_limit_seconds = max_seconds + INLINE_DECODE_MARGIN_SECONDS;
+    let min_track_buffer_samples = match run.threading {
+        DecodeThreading::Workers => 0,
+        DecodeThreading::Inline => {
+            (inline_limit_seconds * f64::from(audio_info.sample_rate)).ceil() as usize * channels
+        }
+    };
+    let prepared = prepare_buffer_mixer(startup, &audio_info, &sizes, min_track_buffer_samples);
+    let mut buffer_mixer = prepared.buffer_mixer;
+    let effect_context = prepared.effect_context;
+    let decode_channels = audio_info.channels as u8;
+
+    let abort = Arc::new(AtomicBool::new(false));
+    let decode_backpressure = buffer_mixer.decode_backpressure();
+    let workers = match run.threading {
+        DecodeThreading::Workers => Some(spawn_mix_decode_workers(
+            &DecodePool::default(),
+            &buffer_mixer,
+            SpawnDecodeArgs {
+                container_path: prepared.container_path,
+                track_keys: prepared.track_keys,
+                download: prepared.download,
+                readers: ReaderCache::default(),
+                start_time,
+                channels: decode_channels,
+                startup_gate_samples: sizes.start_samples.max(sizes.min_mix_samples),
+            },
+            &decode_backpressure,
+            &abort,
+            Instant::now(),
+        )),
+        DecodeThreading::Inline => {
+            let decode_start = Instant::now();
+            let events = decode_inline(
+                &buffer_mixer,
+                prepared.container_path.map(|path| ContainerSource {
+                    path,
+                    readers: ReaderCache::default(),
+                    track_keys: prepared.track_keys,
+                    download: prepared.download,
+                }),
+                start_time,
+                decode_channels,
+                inline_limit_seconds,
+                run.cancel,
+            );
+            stats.timings.decode += decode_start.elapsed();
+            let route_start = Instant::now();
+            for event in events {
+                route_decode_event(&mut buffer_mixer, event);
+            }
+            stats.timings.mix += route_start.elapsed();
+            None
+        }
+    };
+    for effect in effects.iter_mut() {
+        effect.warm_up(&effect_context);
+    }
+
+    let mut chain = TimedEffectChain {
+        effects: &mut effects,
+        context: &effect_context,
+        scratch_a: Vec::new(),
+        scratch_b: Vec::new(),
+    };
+    let batch = sizes.convolution_batch_samples;
+    let mut pending = PremixBuffer::new();
+    let mut processed_samples = 0;
+    while stats.rendered_samples < max_samples {
+        if run.is_cancelled() {
+            stats.cancelled = true;
+            break;
+        }
+        let mix_start = Instant::now();
+        let mixed = buffer_mixer.take_samples();
+        stats.timings.mix += mix_start.elapsed();
+        let chunk = match mixed {
+            Some(samples) if batch == 0 => Some(samples),
+            Some(samples) => {
+                pending.push_interleaved(&samples);
+                (pending.len() >= batch).then(|| pending.pop_chunk(batch))
+            }
+            None if buffer_mixer.mix_finished() => {
+                if pending.is_empty() {
+                    break;
+                }
+                let remaining = pending.len();
+                pending.push_interleaved(&vec![0.0; batch.saturating_sub(remaining)]);
+                Some(pending.pop_chunk(batch))
+            }
+            None => {
+                let Some((packet_rx, _)) = workers.as_ref() else {
+                    // Inline mode routed everything up front; nothing more will arrive.
+                    break;
+                };
+                wait_for_decode(packet_rx, &mut buffer_mixer, &mut stats.timings);
+                None
+            }
+        };
+        let Some(mut chunk) = chunk else {
+            continue;
+        };
+        if loudness_gain != 1.0 {
+            chunk.iter_mut().for_each(|sample| *sample *= loudness_gain);
+        }
+        let output = chain.process_automated(
+            &chunk,
+            processed_samples,
+            &mut automation,
+            &mut stats.timings.effects,
+        );
+        processed_samples += chunk.len();
+        let take = output.len().min(max_samples - stats.rendered_samples);
+        on_chunk(&output[..take]);
+        stats.rendered_samples += take;
+    }
+
+    abort.store(true, Ordering::SeqCst);
+    decode_backpressure.shutdown();
+    if let Some((packet_rx, decode_workers)) = workers {
+        drop(packet_rx);
+        drop(decode_workers);
+    }
+    stats
+}
+
+/// Block for the next decode worker event and route everything queued.
+fn wait_for_decode(
+    packet_rx: &mpsc::Receiver<DecodeWorkerEvent>,
+    buffer_mixer: &mut BufferMixer,
+    timings: &mut OfflineStageTimings,
+) {
+    let wait_start = Instant::now();
+    let event = packet_rx.recv_timeout(DECODE_WAIT_TIMEOUT);
+    timings.decode += wait_start.elapsed();
+    match event {
+        Ok(event) => {
+            let route_start = Instant::now();
+            route_decode_event(buffer_mixer, event);
+            while let Ok(event) = packet_rx.try_recv() {
+                route_decode_event(buffer_mixer, event);
+            }
+            timings.mix += route_start.elapsed();
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => {}
+        Err(mpsc::RecvTimeoutError::Disconnected) => buffer_mixer.signal_finish_all(),
+    }
+}
+
+/// Collects inline decode events up to a timeline limit.
+struct InlineEventSink<'a> {
+    limit_seconds: f64,
+    cancel: &'a AtomicBool,
+    events: RefCell<Vec<DecodeWorkerEvent>>,
+}
+
+impl DecodeEventSink for InlineEventSink<'_> {
+    fn send_event(&self, event: DecodeWorkerEvent) -> bool {
+        if self.cancel.load(Ordering::Acquire) {
+            return false;
+        }
+        if let DecodeWorkerEvent::Packet(packet) = &event {
+            if packet.packet_ts > self.limit_seconds {
+                return false;
+            }
+        }
+        self.events.borrow_mut().push(event);
+        true
+    }
+}
+
+/// Decode every source of `buffer_mixer` on the calling thread, in a stable
+/// order, until `limit_seconds` past `start_time` or until `cancel` is set.
+fn decode_inline(
+    buffer_mixer: &BufferMixer,
+    container: Option<ContainerSource>,
+    start_time: f64,
+    channels: u8,
+    limit_seconds: f64,
+    cancel: Option<&AtomicBool>,
+) -> Vec<DecodeWorkerEvent> {
+    let never = AtomicBool::new(false);
+    let abort = cancel.unwrap_or(&never);
+    let sink = InlineEventSink {
+        limit_seconds,
+        cancel: abort,
+        events: RefCell::new(Vec::new()),
+    };
+    // Inline decoding never waits for room: the mixer buffers cover the window.
+    let unbounded = DecodeBackpressure::default();
+    let mut track_ids = Vec::new();
+    let mut file_paths = Vec::new();
+    for source in buffer_mixer.sources() {
+        match source {
+            SourceKey::TrackId(id) => track_ids.push(id),
+            SourceKey::FilePath(path) => file_paths.push(path),
+        }
+    }
+    file_paths.sort();
+    let timings = buffer_mixer.source_timings();
+
+    if let Some(container) = container {
+        for (timing, track_ids) in container_track_groups(track_ids, &timings) {
+            let start = DecodeStart::new(start_time, &timing);
+            run_container_decode_worker(
+                &container, &track_ids, start, channels, &sink, abort, &unbounded,
+            );
+        }
+    }
+    for path in file_paths {
+        let timing = timings
+            .get(&SourceKey::FilePath(path.clone()))
+            .copied()
+            .unwrap_or_default();
+        let start = DecodeStart::new(start_time, &timing);
+        run_file_decode_worker(&path, start, channels, &sink, abort, &unbounded);
+    }
+    sink.events.into_inner()
+}
+
+/// Effect chain runner that times each effect individually.
+struct TimedEffectChain<'a> {
+    effects: &'a mut [AudioEffect],
+    context: &'a EffectContext,
+    scratch_a: Vec<f32>,
+    scratch_b: Vec<f32>,
+}
+
+impl TimedEffectChain<'_> {
+    fn process(&mut self, input: &[f32], timings: &mut [Duration]) -> &[f32] {
+        self.scratch_a.clear();
+        self.scratch_a.extend_from_slice(input);
+        for (effect, elapsed) in self.effects.iter_mut().zip(timings.iter_mut()) {
+            self.scratch_b.clear();
+            let start = Instant::now();
+            effect.process_into(&self.scratch_a, &mut self.scratch_b, self.context, false);
+            *elapsed += start.elapsed();
+            std::mem::swap(&mut self.scratch_a, &mut self.scratch_b);
+        }
+        &self.scratch_a
+    }
+
+    /// Like [`Self::process`], applying each `automation` change on its exact
+    /// sample. `start` is the chunk's offset from the start of the render.
+    fn process_automated(
+        &mut self,
+        input: &[f32],
+        start: usize,
+        automation: &mut EffectAutomation,
+        timings: &mut [Duration],
+    ) -> &[f32] {
+        self.apply_automation(automation, start);
+        let end = start + input.len();
+        let mut split = automation.next_split(start, end);
+        if split.is_none() {
+            return self.process(input, timings);
+        }
+        let mut output = Vec::with_capacity(input.len());
+        let mut offset = 0;
+        while let Some(at) = split {
+            self.process(&input[offset..at - start], timings);
+            output.extend_from_slice(&self.scratch_a);
+            offset = at - start;
+            self.apply_automation(automation, at);
+            split = automation.next_split(at, end);
+        }
+        self.process(&input[offset..], timings);
+        output.extend_from_slice(&self.scratch_a);
+        self.scratch_a = output;
+        &self.scratch_a
+    }
+
+    fn apply_automation(&mut self, automation: &mut EffectAutomation, position: usize) {
+        for (effect_index, parameter) in automation.take_due(position) {
+            if let Some(effect) = self.effects.get_mut(effect_index) {
+                apply_effect_parameter(effect, parameter);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::engine::mix::decoder_events::DecodedPacket;
+
+    #[test]
+    fn timed_effect_chain_records_one_timing_per_effect() {
+        use crate::dsp::effects::GainEffect;
+
+        let mut gain = GainEffect::default();
+        gain.enabled = true;
+        gain.settings.gain = 2.0;
+        let mut effects = vec![AudioEffect::Gain(gain.clone()), AudioEffect::Gain(gain)];
+        let context = EffectContext::new(48_000, 2, None, None, -60.0).unwrap();
+        let mut chain = TimedEffectChain {
+            effects: &mut effects,
+            context: &context,
+            scratch_a: Vec::new(),
+            scratch_b: Vec::new(),
+        };
+        let mut timings = vec![Duration::ZERO; 2];
+        let output = chain.process(&[0.25, -0.25], &mut timings).to_vec();
+        assert_eq!(output, vec![1.0, -1.0]);
+        assert_eq!(timings.len(), 2);
+    }
+
+    #[test]
+    fn automated_chain_applies_changes_on_their_exact_sample() {
+        use crate::container::prot::EffectAutomationPoint;
+        use crate::dsp::effects::GainEffect;
+
+        let mut gain = GainEffect::default();
+        gain.enabled = true;
+        gain.settings.gain = 1.0;
+        let mut effects = vec![AudioEffect::Gain(gain)];
+        let mut context = EffectContext::new(1_000, 1, None, None, -60.0).unwrap();
+        context.set_parameter_ramp_ms(0.0);
+        let mut chain = TimedEffectChain {
+            effects: &mut effects,
+            context: &context,
+            scratch_a: Vec::new(),
+            scratch_b: Vec::new(),
+        };
+        let point = EffectAutomationPoint {
+            at_seconds: 0.006,
+            effect_index: 0,
+            parameter: "gain".to_string(),
+            value: 2.0,
+        };
+        let mut automation = EffectAutomation::new(&[point], 0.0, 1_000, 1);
+        let mut timings = vec![Duration::ZERO; 1];
+        let first = chain
+            .process_automated(&[0.5; 4], 0, &mut automation, &mut timings)
+            .to_vec();
+        let second = chain
+            .process_automated(&[0.5; 4], 4, &mut automation, &mut timings)
+            .to_vec();
+        assert_eq!(first, vec![0.5; 4]);
+        assert_eq!(second, vec![0.5, 0.5, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn inline_sink_rejects_packets_past_limit() {
+        let cancel = AtomicBool::new(false);
+        let sink = InlineEventSink {
+            limit_seconds: 1.0,
+            cancel: &cancel,
+            events: RefCell::new(Vec::new()),
+        };
+        let packet = |packet_ts| {
+            DecodeWorkerEvent::Packet(DecodedPacket {
+                source_key: SourceKey::TrackId(1),
+                packet_ts,
+                samples: vec![0.0; 2],
+            })
+        };
+        assert!(sink.send_event(packet(0.5)));
+        assert!(!sink.send_event(packet(1.5)));
+        assert!(sink.send_event(DecodeWorkerEvent::StreamExhausted {
+            sources: Vec::new()
+        }));
+        assert_eq!(sink.events.borrow().len(), 2);
+        cancel.store(true, Ordering::Release);
+        assert!(!sink.send_event(packet(0.5)));
+        assert_eq!(sink.events.borrow().len(), 2);
+    }
+}
@@ -0,0 +1,106 @@
+//! Mix-thread side of the overload policy.
+//!
+//! Degradation only touches the mix thread's local chain: reverb quality
+//! flags are runtime-only and the bypassed effect is faded out without
+//! changing the shared chain, so hosts keep seeing the settings they set.
+
+use log::info;
+
+use crate::diagnostics::watchdog::{DiagnosticsEvent, PlaybackStage, WatchdogCondition};
+use crate::dsp::effects::AudioEffect;
+use crate::playback::engine::DegradationStep;
+
+use super::super::overload::QualityChange;
+use super::effects_runtime::schedule_effect_enable_fade;
+use super::state::MixLoopState;
+use super::watchdog::publish_events;
+
+/// Feed the steady-state chain's load for the chunk just processed to the
+/// overload governor, applying any step it takes.
+pub(super) fn observe_chain_load(state: &mut MixLoopState) {
+    let policy = state.lock_buffer_settings_recoverable().overload;
+    let load = state.effect_timer.chain_load();
+    let audio_s = state.effect_timer.chunk_seconds();
+    let Some(change) = state.overload_governor.observe(&policy, load, audio_s) else {
+        return;
+    };
+    apply_change(state, change);
+    info!(
+        "overload policy: {} {:?} after {:.0}ms (chain load {:.2})",
+        if change.degraded { "applied" } else { "undid" },
+        change.step,
+        change.after_ms,
+        load
+    );
+    let condition = if change.degraded {
+        WatchdogCondition::QualityDegraded
+    } else {
+        WatchdogCondition::QualityRestored
+    };
+    publish_events(
+        state,
+        vec![DiagnosticsEvent {
+            condition,
+            suspected_stage: PlaybackStage::Effects,
+            duration_ms: change.after_ms,
+        }],
+    );
+    state.lock_dsp_metrics_recoverable().degradation_level = state.overload_governor.level();
+}
+
+/// Forget any degradation after the local chain was replaced; the new chain
+/// starts at full quality.
+pub(super) fn chain_replaced(state: &mut MixLoopState) {
+    state.overload_bypassed = None;
+    if state.overload_governor.level() == 0 {
+        return;
+    }
+    info!("overload policy: effect chain replaced, back to full quality");
+    state.overload_governor.reset();
+    state.lock_dsp_metrics_recoverable().degradation_level = 0;
+}
+
+fn apply_change(state: &mut MixLoopState, change: QualityChange) {
+    match change.step {
+        DegradationStep::ShortenReverbTail | DegradationStep::LargerConvolutionBlocks => {
+            let level = state.overload_governor.level();
+            for reverb in state
+                .local_effects
+                .iter_mut()
+                .filter_map(|effect| effect.as_convolution_reverb_mut())
+            {
+                reverb.set_overload_degradation(level >= 1, level >= 2);
+            }
+        }
+        DegradationStep::BypassHeaviestEffect if change.degraded => {
+            let heaviest = state
+                .effect_timer
+                .timings()
+                .iter()
+                .filter(|timing| {
+                    state
+                        .local_effects
+                        .get(timing.index)
+                        .is_some_and(AudioEffect::is_enabled)
+                })
+                .max_by(|a, b| a.avg_ms.total_cmp(&b.avg_ms))
+                .map(|timing| timing.index);
+            if let Some(index) = heaviest {
+                schedule_effect_enable_fade(state, index, false);
+            }
+            state.overload_bypassed = heaviest;
+        }
+        DegradationStep::BypassHeaviestEffect => {
+            let Some(index) = state.overload_bypassed.take() else {
+                return;
+            };
+            // Restore what the host last set, in case it toggled the effect
+            // while it was bypassed.
+            let enabled = state
+                .lock_effects_recoverable()
+                .get(index)
+                .is_some_and(AudioEffect::is_enabled);
+            schedule_effect_enable_fade(state, index, enabled);
+        }
+    }
+}
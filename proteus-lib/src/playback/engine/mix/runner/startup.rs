@@ -5,105 +5,163 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc};
 use std::time::Instant;
 
-use log::info;
-use rodio::buffer::SamplesBuffer;
+use log::{info, warn};
 
+use crate::container::prot::{MissingTrackPolicy, TrackKeys};
+use crate::diagnostics::watchdog::{DiagnosticsEvent, PlaybackStage, WatchdogCondition};
 use crate::dsp::effects::{convolution_reverb, AudioEffect, EffectContext};
+use crate::dsp::pan::PanLaw;
+use crate::playback::engine::{DecodePool, ReaderCache};
 use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
+use crate::tools::progressive::ProgressiveFile;
 
-use super::super::buffer_mixer::{BufferMixer, DecodeBackpressure, SourceKey};
+use super::super::buffer_mixer::{BufferMixer, DecodeBackpressure, SourceKey, SourceTiming};
 use super::super::decoder_events::DecodeWorkerEvent;
+use super::super::effects::read_effect_attachments;
+use super::super::output_queue::OutputSender;
 use super::super::types::MixThreadArgs;
 use super::decode::{
-    spawn_container_decode_worker, spawn_file_decode_worker, DecodeWorkerJoinGuard,
+    container_track_groups, spawn_container_decode_worker, spawn_file_decode_worker,
+    ContainerSource, DecodeStart, DecodeWorkerJoinGuard, DecodeWorkerLinks,
 };
 use super::state::{MixBufferSizes, MixDecodeHandle, MixLoopState};
 
-struct SpawnDecodeArgs {
-    container_path: Option<String>,
-    start_time: f64,
-    channels: u8,
-    startup_gate_samples: usize,
+pub(super) struct SpawnDecodeArgs {
+    pub container_path: Option<String>,
+    pub track_keys: Arc<TrackKeys>,
+    pub download: Option<ProgressiveFile>,
+    pub readers: ReaderCache,
+    pub start_time: f64,
+    pub channels: u8,
+    pub startup_gate_samples: usize,
 }
 
 struct DecodeSources {
-    container_path: Option<String>,
+    container: Option<ContainerSource>,
     track_ids: HashSet<u32>,
     file_paths: HashSet<String>,
+    timings: HashMap<SourceKey, SourceTiming>,
     start_time: f64,
     channels: u8,
 }
 
 pub(super) fn setup_mix_state(
-    args: MixThreadArgs,
-    sender: mpsc::SyncSender<(SamplesBuffer, f64)>,
+    mut args: MixThreadArgs,
+    sender: OutputSender,
     startup_trace: Instant,
 ) -> Option<MixLoopState> {
     info!("mix startup trace: thread start");
 
-    let startup = prepare_runtime_startup(&args.prot, &args.buffer_settings, args.start_time);
+    let preroll_ms = lock_recoverable(
+        &args.buffer_settings,
+        "mix startup buffer settings",
+        "buffer settings are runtime configuration snapshots",
+    )
+    .seek_preroll_ms;
+    let (start_time, preroll_samples) = preroll_start(
+        args.start_time,
+        preroll_ms,
+        args.audio_info.sample_rate,
+        args.audio_info.channels as usize,
+    );
+    if preroll_samples > 0 {
+        info!(
+            "mix startup trace: pre-rolling from {:.3}s to warm effects for {:.3}s",
+            start_time, args.start_time
+        );
+    }
+    args.start_time = start_time;
+
+    let startup = prepare_runtime_startup(
+        &args.prot,
+        &args.buffer_settings,
+        &args.effects,
+        args.start_time,
+    );
     info!(
         "mix startup trace: runtime plan built in {}ms (instances={})",
         startup_trace.elapsed().as_millis(),
         startup.instance_plan.instances.len()
     );
-    if startup.instance_plan.instances.is_empty() {
+    report_missing_sources(&startup, &args.diagnostics_events);
+    if startup.is_empty() {
         args.abort.store(true, Ordering::SeqCst);
         return None;
     }
 
     let sizes = compute_mix_buffer_sizes(&args.audio_info, &args.buffer_settings, &args.effects);
-    let track_mix_by_logical = build_track_mix_map(
-        &startup.instance_plan.instances,
-        &startup.track_mix_settings_by_slot,
-    );
-    let track_buffer_size = ((args.audio_info.sample_rate as usize * 10)
-        * args.audio_info.channels.max(1) as usize)
-        .max(sizes.start_samples * 2);
-    let buffer_mixer = BufferMixer::new(
-        startup.instance_plan,
-        args.audio_info.sample_rate,
-        args.audio_info.channels.max(1) as usize,
-        track_buffer_size,
-        track_mix_by_logical,
-        sizes.min_mix_samples,
-    );
+    let prepared = prepare_buffer_mixer(startup, &args.audio_info, &sizes, 0);
     info!(
         "mix startup trace: buffer_mixer ready in {}ms (track_buffer_size={} min_mix_samples={} start_samples={})",
         startup_trace.elapsed().as_millis(),
-        track_buffer_size,
+        prepared.track_buffer_size,
         sizes.min_mix_samples,
         sizes.start_samples
     );
 
     let spawn_args = SpawnDecodeArgs {
-        container_path: startup.container_path,
+        container_path: prepared.container_path,
+        track_keys: prepared.track_keys,
+        download: prepared.download,
+        readers: args.reader_cache.clone(),
         start_time: args.start_time,
         channels: args.audio_info.channels as u8,
         startup_gate_samples: sizes.start_samples.max(sizes.min_mix_samples),
     };
 
-    Some(finalize_mix_startup(
+    let mut state = finalize_mix_startup(
         args,
         sender,
-        buffer_mixer,
-        startup.effect_context,
+        prepared.buffer_mixer,
+        prepared.effect_context,
         sizes,
         spawn_args,
         startup_trace,
-    ))
+    );
+    state.preroll_samples_left = preroll_samples;
+    Some(state)
 }
 
-struct RuntimeStartup {
+/// Move `start_time` back by up to `preroll_ms`, whole frames at a time.
+///
+/// Returns the start to decode from and the interleaved samples before the
+/// requested position, which are processed but not played.
+fn preroll_start(
+    start_time: f64,
+    preroll_ms: f32,
+    sample_rate: u32,
+    channels: usize,
+) -> (f64, usize) {
+    if start_time <= 0.0 || preroll_ms <= 0.0 || sample_rate == 0 {
+        return (start_time, 0);
+    }
+    let preroll_s = (f64::from(preroll_ms) / 1000.0).min(start_time);
+    let frames = (preroll_s * f64::from(sample_rate)).floor();
+    (
+        start_time - frames / f64::from(sample_rate),
+        frames as usize * channels.max(1),
+    )
+}
+
+pub(super) struct RuntimeStartup {
     instance_plan: crate::container::prot::RuntimeInstancePlan,
     container_path: Option<String>,
+    track_keys: Arc<TrackKeys>,
+    download: Option<ProgressiveFile>,
     effect_context: EffectContext,
     track_mix_settings_by_slot: HashMap<u16, (f32, f32)>,
+    track_group_gains: Vec<f32>,
+    pan_law: PanLaw,
+    #[cfg(feature = "hrtf")]
+    track_binaural_by_slot: HashMap<u16, crate::dsp::effects::BinauralPannerSettings>,
+    /// Unplayable selections to report under [`MissingTrackPolicy::Error`].
+    missing_sources: Vec<String>,
 }
 
-fn prepare_runtime_startup(
+pub(super) fn prepare_runtime_startup(
     prot: &Arc<std::sync::Mutex<crate::container::prot::Prot>>,
     buffer_settings: &Arc<std::sync::Mutex<crate::playback::engine::PlaybackBufferSettings>>,
+    effects: &Arc<std::sync::Mutex<Vec<AudioEffect>>>,
     start_time: f64,
 ) -> RuntimeStartup {
     let p = lock_invariant(
@@ -126,17 +184,119 @@ fn prepare_runtime_startup(
     )
     .expect("prot info must have valid sample rate and channel count");
     effect_context.set_parameter_ramp_ms(parameter_ramp_ms);
+    effect_context.set_tempo_bpm(p.get_tempo_bpm());
+    read_effect_attachments(
+        &p,
+        &lock_recoverable(
+            effects,
+            "mix startup effects",
+            "the effect chain is hot-swappable runtime state",
+        ),
+        &mut effect_context,
+    );
     RuntimeStartup {
         instance_plan: p.build_runtime_instance_plan(start_time),
         container_path: p.get_container_path(),
+        track_keys: p.track_keys(),
+        download: p.download(),
         effect_context,
         track_mix_settings_by_slot: p.get_track_mix_settings(),
+        track_group_gains: p.track_group_gains(),
+        pan_law: p.get_pan_law(),
+        #[cfg(feature = "hrtf")]
+        track_binaural_by_slot: p.get_track_binaural_settings(),
+        missing_sources: if p.missing_track_policy() == MissingTrackPolicy::Error {
+            p.unplayable_selections()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// Raise a diagnostics event when the plan left out unplayable selections.
+fn report_missing_sources(
+    startup: &RuntimeStartup,
+    diagnostics_events: &Arc<std::sync::Mutex<Vec<DiagnosticsEvent>>>,
+) {
+    if startup.missing_sources.is_empty() {
+        return;
+    }
+    warn!(
+        "playback started with missing or zero-length sources: {}",
+        startup.missing_sources.join(", ")
+    );
+    lock_recoverable(
+        diagnostics_events,
+        "mix startup diagnostics events",
+        "pending diagnostics are append-only notifications",
+    )
+    .push(DiagnosticsEvent {
+        condition: WatchdogCondition::MissingSource,
+        suspected_stage: PlaybackStage::Decoder,
+        duration_ms: 0.0,
+    });
+}
+
+impl RuntimeStartup {
+    /// Return `true` when the plan has no instances to decode.
+    pub(super) fn is_empty(&self) -> bool {
+        self.instance_plan.instances.is_empty()
+    }
+}
+
+/// Buffer mixer and effect context built from a runtime startup plan.
+pub(super) struct PreparedMixer {
+    pub buffer_mixer: BufferMixer,
+    pub effect_context: EffectContext,
+    pub container_path: Option<String>,
+    pub track_keys: Arc<TrackKeys>,
+    pub download: Option<ProgressiveFile>,
+    pub track_buffer_size: usize,
+}
+
+/// Build the buffer mixer for a startup plan.
+///
+/// Per-instance buffers hold at least `min_track_buffer_samples`.
+pub(super) fn prepare_buffer_mixer(
+    startup: RuntimeStartup,
+    audio_info: &crate::container::info::Info,
+    sizes: &MixBufferSizes,
+    min_track_buffer_samples: usize,
+) -> PreparedMixer {
+    let track_mix_by_logical = build_track_mix_map(
+        &startup.instance_plan.instances,
+        &startup.track_mix_settings_by_slot,
+    );
+    let track_buffer_size = ((audio_info.sample_rate as usize * 10)
+        * audio_info.channels.max(1) as usize)
+        .max(sizes.start_samples * 2)
+        .max(min_track_buffer_samples);
+    let mut buffer_mixer = BufferMixer::new(
+        startup.instance_plan,
+        audio_info.sample_rate,
+        audio_info.channels.max(1) as usize,
+        track_buffer_size,
+        track_mix_by_logical,
+        sizes.min_mix_samples,
+    );
+    buffer_mixer.set_track_group_gains(&startup.track_group_gains);
+    buffer_mixer.set_pan_law(startup.pan_law);
+    #[cfg(feature = "hrtf")]
+    buffer_mixer
+        .set_track_binaural_by_slot(&startup.track_binaural_by_slot, &startup.effect_context);
+    PreparedMixer {
+        buffer_mixer,
+        effect_context: startup.effect_context,
+        container_path: startup.container_path,
+        track_keys: startup.track_keys,
+        download: startup.download,
+        track_buffer_size,
     }
 }
 
 fn finalize_mix_startup(
     args: MixThreadArgs,
-    sender: mpsc::SyncSender<(SamplesBuffer, f64)>,
+    sender: OutputSender,
     buffer_mixer: BufferMixer,
     effect_context: EffectContext,
     sizes: MixBufferSizes,
@@ -145,6 +305,7 @@ fn finalize_mix_startup(
 ) -> MixLoopState {
     let decode_backpressure = buffer_mixer.decode_backpressure();
     let (packet_rx, decode_workers) = spawn_mix_decode_workers(
+        &args.decode_pool,
         &buffer_mixer,
         spawn_args,
         &decode_backpressure,
@@ -174,7 +335,7 @@ fn finalize_mix_startup(
     )
 }
 
-fn compute_mix_buffer_sizes(
+pub(super) fn compute_mix_buffer_sizes(
     audio_info: &crate::container::info::Info,
     buffer_settings: &Arc<std::sync::Mutex<crate::playback::engine::PlaybackBufferSettings>>,
     effects: &Arc<std::sync::Mutex<Vec<AudioEffect>>>,
@@ -262,7 +423,8 @@ fn warm_up_effects(
     );
 }
 
-fn spawn_mix_decode_workers(
+pub(super) fn spawn_mix_decode_workers(
+    pool: &DecodePool,
     buffer_mixer: &BufferMixer,
     spawn_args: SpawnDecodeArgs,
     decode_backpressure: &Arc<DecodeBackpressure>,
@@ -279,20 +441,24 @@ fn spawn_mix_decode_workers(
         spawn_args.startup_gate_samples,
     );
     let sources_bundle = DecodeSources {
-        container_path: spawn_args.container_path,
+        container: spawn_args.container_path.map(|path| ContainerSource {
+            path,
+            readers: spawn_args.readers,
+            track_keys: spawn_args.track_keys,
+            download: spawn_args.download,
+        }),
         track_ids,
         file_paths,
+        timings: buffer_mixer.source_timings(),
         start_time: spawn_args.start_time,
         channels: spawn_args.channels,
     };
-    spawn_decode_workers(
-        &mut decode_workers,
-        packet_tx.clone(),
-        sources_bundle,
-        abort,
-        decode_backpressure,
-    );
-    drop(packet_tx);
+    let links = DecodeWorkerLinks {
+        sender: packet_tx,
+        abort: abort.clone(),
+        decode_backpressure: decode_backpressure.clone(),
+    };
+    spawn_decode_workers(pool, &mut decode_workers, links, sources_bundle);
     log_decode_worker_counts(&sources, startup_trace);
     (packet_rx, decode_workers)
 }
@@ -324,33 +490,35 @@ fn maybe_enable_startup_priority(
 }
 
 fn spawn_decode_workers(
+    pool: &DecodePool,
     decode_workers: &mut DecodeWorkerJoinGuard,
-    packet_tx: mpsc::SyncSender<DecodeWorkerEvent>,
+    links: DecodeWorkerLinks,
     sources: DecodeSources,
-    abort: &Arc<AtomicBool>,
-    decode_backpressure: &Arc<DecodeBackpressure>,
 ) {
-    if !sources.track_ids.is_empty() {
-        if let Some(path) = sources.container_path {
+    if let Some(container) = sources.container {
+        for (timing, track_ids) in container_track_groups(sources.track_ids, &sources.timings) {
             decode_workers.push(spawn_container_decode_worker(
-                path,
-                sources.track_ids.into_iter().collect(),
-                sources.start_time,
+                pool,
+                container.clone(),
+                track_ids,
+                DecodeStart::new(sources.start_time, &timing),
                 sources.channels,
-                packet_tx.clone(),
-                abort.clone(),
-                decode_backpressure.clone(),
+                links.clone(),
             ));
         }
     }
     for path in sources.file_paths {
+        let timing = sources
+            .timings
+            .get(&SourceKey::FilePath(path.clone()))
+            .copied()
+            .unwrap_or_default();
         decode_workers.push(spawn_file_decode_worker(
+            pool,
             path,
-            sources.start_time,
+            DecodeStart::new(sources.start_time, &timing),
             sources.channels,
-            packet_tx.clone(),
-            abort.clone(),
-            decode_backpressure.clone(),
+            links.clone(),
         ));
     }
 }
@@ -368,3 +536,24 @@ fn log_decode_worker_counts(sources: &[SourceKey], startup_trace: Instant) {
         file_count
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::preroll_start;
+
+    #[test]
+    fn preroll_moves_the_start_back_by_whole_frames() {
+        let (start, samples) = preroll_start(10.0, 500.0, 48_000, 2);
+        assert!((start - 9.5).abs() < 1e-9);
+        assert_eq!(samples, 48_000);
+    }
+
+    #[test]
+    fn preroll_stops_at_the_beginning() {
+        let (start, samples) = preroll_start(0.25, 500.0, 1_000, 1);
+        assert_eq!(start, 0.0);
+        assert_eq!(samples, 250);
+        assert_eq!(preroll_start(0.0, 500.0, 48_000, 2), (0.0, 0));
+        assert_eq!(preroll_start(3.0, 0.0, 48_000, 2), (3.0, 0));
+    }
+}
@@ -3,22 +3,32 @@
 use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
 
-use rodio::buffer::SamplesBuffer;
-
 use crate::container::info::Info;
 use crate::container::prot::Prot;
+use crate::diagnostics::watchdog::{DiagnosticsEvent, PlaybackWatchdog};
+use crate::dsp::dither::Ditherer;
 use crate::dsp::effects::{AudioEffect, EffectContext};
 use crate::playback::engine::premix::PremixBuffer;
 use crate::playback::engine::{
-    DspChainMetrics, InlineEffectsUpdate, InlineTrackMixUpdate, PlaybackBufferSettings,
+    DspChainMetrics, EffectTiming, InlineEffectsUpdate, InlineTrackMixUpdate, OneShotVoice,
+    PlaybackBufferSettings, SharedLiveInput, SharedVolumeRamp,
 };
-use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::gain_staging::SharedGainStaging;
+use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
+use crate::playback::track_meter::TrackLevels;
 
+use super::super::automation::EffectAutomation;
 use super::super::buffer_mixer::{BufferMixer, DecodeBackpressure};
+use super::super::chain_mix::ChainMixer;
 use super::super::decoder_events::DecodeWorkerEvent;
+use super::super::ducking::Ducker;
 use super::super::effects::EffectEnableFade;
+use super::super::output_queue::OutputSender;
+use super::super::overload::OverloadGovernor;
 use super::super::types::{ActiveInlineTransition, EffectSettingsCommand, MixThreadArgs};
 use super::decode::DecodeWorkerJoinGuard;
+use super::effect_timing::EffectTimer;
+use super::live_input::LiveInputRuntime;
 
 /// Precomputed mixing buffer sizes.
 pub(super) struct MixBufferSizes {
@@ -42,13 +52,35 @@ pub(super) struct MixLoopState {
     pub(super) effects: Arc<Mutex<Vec<AudioEffect>>>,
     pub(super) local_effects: Vec<AudioEffect>,
     pub(super) effect_settings_commands: Arc<Mutex<Vec<EffectSettingsCommand>>>,
+    /// Container effect automation still to apply during this run.
+    pub(super) automation: EffectAutomation,
+    pub(super) one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    pub(super) chain_mixer: ChainMixer,
+    pub(super) ducker: Ducker,
+    pub(super) output_ditherer: Option<Ditherer>,
+    pub(super) live_input: SharedLiveInput,
+    pub(super) live_input_runtime: LiveInputRuntime,
+    pub(super) volume_ramp: SharedVolumeRamp,
+    pub(super) gain_staging: SharedGainStaging,
+    pub(super) loudness_gain: f32,
     pub(super) effect_context: EffectContext,
-    pub(super) sender: mpsc::SyncSender<(SamplesBuffer, f64)>,
+    pub(super) sender: OutputSender,
     pub(super) buffer_notify: Arc<Condvar>,
     pub(super) audio_info: Info,
     pub(super) buffer_settings: Arc<Mutex<PlaybackBufferSettings>>,
     pub(super) dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    pub(super) track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    pub(super) effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    pub(super) effect_timer: EffectTimer,
+    pub(super) overload_governor: OverloadGovernor,
+    /// Effect faded out by the overload policy, if any.
+    pub(super) overload_bypassed: Option<usize>,
+    /// Processed samples still to discard before output is audible.
+    pub(super) preroll_samples_left: usize,
+    pub(super) diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
+    pub(super) watchdog: PlaybackWatchdog,
     pub(super) inline_track_mix_updates: Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    pub(super) inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     pub(super) inline_effects_update: Arc<Mutex<Option<InlineEffectsUpdate>>>,
     pub(super) effects_reset: Arc<AtomicU64>,
     pub(super) prot: Arc<Mutex<Prot>>,
@@ -89,7 +121,7 @@ pub(super) struct MixLoopState {
 impl MixLoopState {
     pub(super) fn new(
         args: MixThreadArgs,
-        sender: mpsc::SyncSender<(SamplesBuffer, f64)>,
+        sender: OutputSender,
         buffer_mixer: BufferMixer,
         effect_context: EffectContext,
         sizes: MixBufferSizes,
@@ -104,6 +136,30 @@ impl MixLoopState {
         )
         .clone();
         let effect_count = local_effects.len();
+        let (loudness_gain, automation) = {
+            let prot = lock_invariant(
+                &args.prot,
+                "mix runtime prot",
+                "loudness tags and automation are read-only container metadata",
+            );
+            (
+                prot.get_loudness_tag().map_or(1.0, |tag| tag.trim_gain()),
+                EffectAutomation::new(
+                    &prot.get_effect_automation(),
+                    args.start_time,
+                    args.audio_info.sample_rate,
+                    args.audio_info.channels as usize,
+                ),
+            )
+        };
+        let watchdog = PlaybackWatchdog::new(
+            lock_recoverable(
+                &args.buffer_settings,
+                "mix runtime buffer settings",
+                "buffer settings are runtime configuration snapshots",
+            )
+            .silence_watchdog_ms,
+        );
         Self {
             abort: args.abort,
             packet_rx: decode_handle.packet_rx,
@@ -112,13 +168,32 @@ impl MixLoopState {
             effects: args.effects,
             local_effects,
             effect_settings_commands: args.effect_settings_commands,
+            automation,
+            one_shots: args.one_shots,
+            chain_mixer: ChainMixer::new(),
+            ducker: Ducker::new(),
+            output_ditherer: None,
+            live_input: args.live_input,
+            live_input_runtime: LiveInputRuntime::default(),
+            volume_ramp: args.volume_ramp,
+            gain_staging: args.gain_staging,
+            loudness_gain,
             effect_context,
             sender,
             buffer_notify: args.buffer_notify,
             audio_info: args.audio_info,
             buffer_settings: args.buffer_settings,
             dsp_metrics: args.dsp_metrics,
+            track_levels: args.track_levels,
+            effect_timings: args.effect_timings,
+            effect_timer: EffectTimer::new(),
+            overload_governor: OverloadGovernor::new(),
+            overload_bypassed: None,
+            preroll_samples_left: 0,
+            diagnostics_events: args.diagnostics_events,
+            watchdog,
             inline_track_mix_updates: args.inline_track_mix_updates,
+            inline_group_gains: args.inline_group_gains,
             inline_effects_update: args.inline_effects_update,
             effects_reset: args.effects_reset,
             prot: args.prot,
@@ -175,6 +250,35 @@ impl MixLoopState {
         )
     }
 
+    /// Recoverable poison policy: track levels are derived telemetry.
+    pub(super) fn lock_track_levels_recoverable(&self) -> MutexGuard<'_, Vec<TrackLevels>> {
+        lock_recoverable(
+            &self.track_levels,
+            "mix runtime track levels",
+            "track levels are derived telemetry that can be rebuilt",
+        )
+    }
+
+    /// Recoverable poison policy: effect timings are derived telemetry.
+    pub(super) fn lock_effect_timings_recoverable(&self) -> MutexGuard<'_, Vec<EffectTiming>> {
+        lock_recoverable(
+            &self.effect_timings,
+            "mix runtime effect timings",
+            "effect timings are derived telemetry that can be rebuilt",
+        )
+    }
+
+    /// Recoverable poison policy: diagnostics events are a disposable queue.
+    pub(super) fn lock_diagnostics_events_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<DiagnosticsEvent>> {
+        lock_recoverable(
+            &self.diagnostics_events,
+            "mix runtime diagnostics events",
+            "diagnostics events are a disposable queue",
+        )
+    }
+
     /// Recoverable poison policy: buffer settings are runtime configuration snapshots.
     pub(super) fn lock_buffer_settings_recoverable(
         &self,
@@ -186,6 +290,15 @@ impl MixLoopState {
         )
     }
 
+    /// Recoverable poison policy: queued one-shots are disposable transient voices.
+    pub(super) fn lock_one_shots_recoverable(&self) -> MutexGuard<'_, Vec<OneShotVoice>> {
+        lock_recoverable(
+            &self.one_shots,
+            "mix runtime one-shots",
+            "queued one-shots are disposable transient voices",
+        )
+    }
+
     /// Recoverable poison policy: pending inline effect updates are a disposable queue.
     pub(super) fn lock_inline_effects_update_recoverable(
         &self,
@@ -0,0 +1,49 @@
+//! Playback watchdog hooks for the mix-thread output stage.
+
+use std::time::Instant;
+
+use log::warn;
+
+use crate::diagnostics::watchdog::DiagnosticsEvent;
+
+use super::state::MixLoopState;
+
+/// Maximum number of undrained diagnostics events kept for the control path.
+const MAX_PENDING_DIAGNOSTICS_EVENTS: usize = 64;
+
+/// Feed a processed chunk (pre- and post-effects) to the watchdog.
+pub(super) fn observe_output_chunk(state: &mut MixLoopState, input: &[f32]) {
+    let threshold_ms = state.lock_buffer_settings_recoverable().silence_watchdog_ms;
+    state.watchdog.set_threshold_ms(threshold_ms);
+    let channels = state.audio_info.channels.max(1) as f64;
+    let sample_rate = state.audio_info.sample_rate.max(1) as f64;
+    let audio_s = state.effect_scratch_a.len() as f64 / channels / sample_rate;
+    let output_late = state.lock_dsp_metrics_recoverable().late_append_active;
+    let events = state
+        .watchdog
+        .observe_chunk(input, &state.effect_scratch_a, audio_s, output_late);
+    publish_events(state, events);
+}
+
+/// Record that the mix thread is waiting for decoded input.
+pub(super) fn observe_input_wait(state: &mut MixLoopState) {
+    if let Some(event) = state.watchdog.observe_idle(Instant::now()) {
+        publish_events(state, vec![event]);
+    }
+}
+
+pub(super) fn publish_events(state: &MixLoopState, events: Vec<DiagnosticsEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let mut pending = state.lock_diagnostics_events_recoverable();
+    for event in events {
+        warn!(
+            "playback watchdog: {:?} for {:.0}ms (suspected stage: {:?})",
+            event.condition, event.duration_ms, event.suspected_stage
+        );
+        pending.push(event);
+    }
+    let overflow = pending.len().saturating_sub(MAX_PENDING_DIAGNOSTICS_EVENTS);
+    pending.drain(..overflow);
+}
@@ -1,11 +1,19 @@
 //! Logical-track mixing helpers.
 
-use crate::dsp::guardrails::{sanitize_finite_clamped, sanitize_finite_min};
+use crate::dsp::guardrails::sanitize_finite_min;
+use crate::dsp::pan::PanLaw;
+use crate::dsp::precision::{narrow, widen, DspFloat};
 
 /// Apply per-track gain/pan in-place to interleaved samples.
 ///
-/// Stereo-only panning is supported for now.
-pub(crate) fn apply_track_gain_pan(samples: &mut [f32], level: f32, pan: f32, channels: usize) {
+/// Stereo-only panning is supported for now; `pan_law` sets the taper.
+pub(crate) fn apply_track_gain_pan(
+    samples: &mut [f32],
+    level: f32,
+    pan: f32,
+    channels: usize,
+    pan_law: PanLaw,
+) {
     let level = sanitize_finite_min(level, 1.0, 0.0);
     if channels <= 1 {
         for sample in samples.iter_mut() {
@@ -14,10 +22,7 @@ pub(crate) fn apply_track_gain_pan(samples: &mut [f32], level: f32, pan: f32, ch
         return;
     }
 
-    let pan = sanitize_finite_clamped(pan, 0.0, -1.0, 1.0);
-
-    let left = if pan > 0.0 { 1.0 - pan } else { 1.0 };
-    let right = if pan < 0.0 { 1.0 + pan } else { 1.0 };
+    let (left, right) = pan_law.gains(pan);
 
     for (sample_index, sample) in samples.iter_mut().enumerate() {
         let lane_gain = match sample_index % channels {
@@ -30,6 +35,8 @@ pub(crate) fn apply_track_gain_pan(samples: &mut [f32], level: f32, pan: f32, ch
 }
 
 /// Combine logical-track buffers into one output buffer with equal weighting.
+///
+/// The bus is summed at working precision and rounded to `f32` once.
 pub(crate) fn combine_tracks_equal_weight(track_buffers: &[Vec<f32>]) -> Vec<f32> {
     let Some(first) = track_buffers.first() else {
         return Vec::new();
@@ -39,14 +46,14 @@ pub(crate) fn combine_tracks_equal_weight(track_buffers: &[Vec<f32>]) -> Vec<f32
         return Vec::new();
     }
 
-    let weight = 1.0_f32 / track_buffers.len() as f32;
-    let mut out = vec![0.0_f32; len];
+    let weight = 1.0 / track_buffers.len() as DspFloat;
+    let mut bus: Vec<DspFloat> = vec![0.0; len];
     for buffer in track_buffers {
         for (sample_index, sample) in buffer.iter().copied().enumerate().take(len) {
-            out[sample_index] += sample * weight;
+            bus[sample_index] += widen(sample) * weight;
         }
     }
-    out
+    bus.into_iter().map(narrow).collect()
 }
 
 #[cfg(test)]
@@ -57,7 +64,7 @@ mod tests {
     /// Verifies full-left pan mutes the right lane for stereo samples.
     fn apply_track_gain_pan_handles_stereo() {
         let mut samples = vec![1.0_f32, 1.0, 0.5, 0.5];
-        apply_track_gain_pan(&mut samples, 1.0, -1.0, 2);
+        apply_track_gain_pan(&mut samples, 1.0, -1.0, 2, PanLaw::Balance);
         assert_eq!(samples, vec![1.0_f32, 0.0, 0.5, 0.0]);
     }
 
@@ -74,7 +81,7 @@ mod tests {
     #[test]
     fn apply_track_gain_pan_clamps_invalid_inputs() {
         let mut samples = vec![1.0_f32, 1.0, 1.0, 1.0];
-        apply_track_gain_pan(&mut samples, f32::NAN, 2.0, 2);
+        apply_track_gain_pan(&mut samples, f32::NAN, 2.0, 2, PanLaw::Balance);
         assert_eq!(samples, vec![0.0_f32, 1.0, 0.0, 1.0]);
     }
 }
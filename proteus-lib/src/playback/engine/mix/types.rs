@@ -4,10 +4,17 @@ use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{Arc, Mutex};
 
 use crate::container::prot::Prot;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
 use crate::dsp::effects::AudioEffect;
+use crate::playback::gain_staging::SharedGainStaging;
+use crate::playback::track_meter::TrackLevels;
 
-use super::super::state::{DspChainMetrics, PlaybackBufferSettings};
-use super::super::{InlineEffectsUpdate, InlineTrackMixUpdate};
+use super::super::decode_pool::DecodePool;
+use super::super::reader_cache::ReaderCache;
+use super::super::state::{DspChainMetrics, EffectTiming, PlaybackBufferSettings};
+use super::super::{
+    InlineEffectsUpdate, InlineTrackMixUpdate, OneShotVoice, SharedLiveInput, SharedVolumeRamp,
+};
 
 /// Incremental effect settings change pushed from the control path.
 ///
@@ -76,6 +83,38 @@ pub enum EffectParameter {
     LimiterReleaseMs(f32),
 }
 
+impl EffectParameter {
+    /// Build a parameter from its snake_case name, e.g. `reverb_mix` or
+    /// `low_pass_freq_hz`, as used by play_settings effect automation.
+    ///
+    /// Frequencies are rounded to whole hertz. Returns `None` for unknown
+    /// names.
+    pub fn from_name(name: &str, value: f32) -> Option<Self> {
+        let hz = || value.max(0.0).round() as u32;
+        Some(match name {
+            "gain" => Self::Gain(value),
+            "pan" => Self::Pan(value),
+            "reverb_mix" => Self::ReverbMix(value),
+            "distortion_gain" => Self::DistortionGain(value),
+            "distortion_threshold" => Self::DistortionThreshold(value),
+            "low_pass_freq_hz" => Self::LowPassFreqHz(hz()),
+            "low_pass_q" => Self::LowPassQ(value),
+            "high_pass_freq_hz" => Self::HighPassFreqHz(hz()),
+            "high_pass_q" => Self::HighPassQ(value),
+            "compressor_threshold_db" => Self::CompressorThresholdDb(value),
+            "compressor_ratio" => Self::CompressorRatio(value),
+            "compressor_attack_ms" => Self::CompressorAttackMs(value),
+            "compressor_release_ms" => Self::CompressorReleaseMs(value),
+            "compressor_makeup_db" => Self::CompressorMakeupDb(value),
+            "limiter_threshold_db" => Self::LimiterThresholdDb(value),
+            "limiter_knee_width_db" => Self::LimiterKneeWidthDb(value),
+            "limiter_attack_ms" => Self::LimiterAttackMs(value),
+            "limiter_release_ms" => Self::LimiterReleaseMs(value),
+            _ => return None,
+        })
+    }
+}
+
 /// Arguments required to spawn the mixing thread.
 pub struct MixThreadArgs {
     pub audio_info: crate::container::info::Info,
@@ -83,6 +122,7 @@ pub struct MixThreadArgs {
     pub effects_reset: Arc<AtomicU64>,
     pub inline_effects_update: Arc<Mutex<Option<InlineEffectsUpdate>>>,
     pub inline_track_mix_updates: Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    pub inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     pub finished_tracks: Arc<Mutex<Vec<u16>>>,
     pub prot: Arc<Mutex<Prot>>,
     pub abort: Arc<AtomicBool>,
@@ -90,7 +130,16 @@ pub struct MixThreadArgs {
     pub buffer_settings: Arc<Mutex<PlaybackBufferSettings>>,
     pub effects: Arc<Mutex<Vec<AudioEffect>>>,
     pub dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    pub track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    pub effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    pub diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
     pub effect_settings_commands: Arc<Mutex<Vec<EffectSettingsCommand>>>,
+    pub one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    pub live_input: SharedLiveInput,
+    pub volume_ramp: SharedVolumeRamp,
+    pub gain_staging: SharedGainStaging,
+    pub decode_pool: DecodePool,
+    pub reader_cache: ReaderCache,
 }
 
 /// Active in-progress inline effect transition state.
@@ -104,7 +153,20 @@ pub(super) struct ActiveInlineTransition {
 
 #[cfg(test)]
 mod tests {
-    use super::ActiveInlineTransition;
+    use super::{ActiveInlineTransition, EffectParameter};
+
+    #[test]
+    fn effect_parameters_parse_from_names() {
+        assert!(matches!(
+            EffectParameter::from_name("reverb_mix", 0.4),
+            Some(EffectParameter::ReverbMix(mix)) if mix == 0.4
+        ));
+        assert!(matches!(
+            EffectParameter::from_name("low_pass_freq_hz", 1199.6),
+            Some(EffectParameter::LowPassFreqHz(1200))
+        ));
+        assert!(EffectParameter::from_name("wobble", 1.0).is_none());
+    }
 
     #[test]
     fn active_inline_transition_tracks_sample_budget() {
@@ -3,7 +3,7 @@
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicU64;
-use std::sync::{mpsc::Receiver, Arc, Condvar, Mutex, MutexGuard};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
 use std::thread::JoinHandle;
 
 use rodio::buffer::SamplesBuffer;
@@ -12,16 +12,38 @@ use log::warn;
 
 use crate::audio::buffer::{init_buffer_map, TrackBuffer};
 use crate::container::prot::Prot;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
+use crate::dsp::pan::PanLaw;
+use crate::playback::gain_staging::SharedGainStaging;
 use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
+use crate::playback::track_meter::TrackLevels;
 
+mod decode_pool;
+mod live_input;
 mod mix;
+mod one_shot;
 pub(crate) mod premix;
+pub(crate) mod reader_cache;
+mod source;
 mod state;
+mod volume_ramp;
 
-pub use state::{DspChainMetrics, PlaybackBufferSettings};
+pub use decode_pool::DecodePool;
+pub use reader_cache::ReaderCache;
+pub use state::{
+    DegradationStep, DspChainMetrics, DuckingSettings, EffectTiming, OverloadPolicy,
+    PlaybackBufferSettings,
+};
 
-pub use mix::{EffectParameter, EffectSettingsCommand};
+pub use live_input::{LiveInputBus, SharedLiveInput};
+pub use mix::{EffectParameter, EffectSettingsCommand, OutputReceiver};
+pub use one_shot::{OneShotError, OneShotSource, OneShotVoice};
+pub use source::EngineSource;
+pub use volume_ramp::{SharedVolumeRamp, VolumeCurve, VolumeRamp};
 
+pub(crate) use one_shot::OneShotLayout;
+
+pub(crate) use mix::{render_offline, DecodeThreading, OfflineRun, SHUFFLE_CROSSFADE_MS};
 use mix::{spawn_mix_thread, MixThreadArgs};
 
 /// Request to update the active effects chain inline during playback.
@@ -66,14 +88,64 @@ pub struct PlayerEngineConfig {
     pub effects: Arc<Mutex<Vec<crate::dsp::effects::AudioEffect>>>,
     /// Shared structure into which the engine writes live DSP performance metrics.
     pub dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    /// Shared per-slot track levels written by the mix thread.
+    pub track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    /// Shared per-effect processing times written by the mix thread.
+    pub effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    /// Shared queue into which the playback watchdog pushes diagnostics events.
+    pub diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
     /// Monotonic counter incremented each time the effect chain should be reset.
     pub effects_reset: Arc<AtomicU64>,
     /// Pending inline effects-chain swap to apply on the next mix cycle.
     pub inline_effects_update: Arc<Mutex<Option<InlineEffectsUpdate>>>,
     /// Pending per-track mix updates to apply on the next mix cycle.
     pub inline_track_mix_updates: Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    /// Latest group gain of each logical track, applied on the next mix cycle.
+    pub inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     /// Command queue for incremental effect settings changes from the control path.
     pub effect_settings_commands: Arc<Mutex<Vec<EffectSettingsCommand>>>,
+    /// Transient one-shot voices mixed on top of the output.
+    pub one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    /// Live capture input mixed as an extra track while capture runs.
+    pub live_input: SharedLiveInput,
+    /// Volume automation applied to the mixed output.
+    pub volume_ramp: SharedVolumeRamp,
+    /// Per-stage level history recorded while gain staging diagnostics run.
+    pub gain_staging: SharedGainStaging,
+    /// Threads that run decode workers, reused across engine restarts.
+    pub decode_pool: DecodePool,
+    /// Container readers kept open across engine restarts.
+    pub reader_cache: ReaderCache,
+}
+
+impl Default for PlayerEngineConfig {
+    /// Standalone configuration with fresh shared state, for hosts that run
+    /// the engine without a [`crate::playback::player::Player`].
+    fn default() -> Self {
+        Self {
+            abort_option: None,
+            start_time: 0.0,
+            buffer_settings: Arc::new(Mutex::new(PlaybackBufferSettings::new(20.0))),
+            effects: Arc::new(Mutex::new(Vec::new())),
+            dsp_metrics: Arc::new(Mutex::new(DspChainMetrics::default())),
+            track_levels: Arc::new(Mutex::new(Vec::new())),
+            effect_timings: Arc::new(Mutex::new(Vec::new())),
+            diagnostics_events: Arc::new(Mutex::new(Vec::new())),
+            effects_reset: Arc::new(AtomicU64::new(0)),
+            inline_effects_update: Arc::new(Mutex::new(None)),
+            inline_track_mix_updates: Arc::new(Mutex::new(Vec::new())),
+            inline_group_gains: Arc::new(Mutex::new(None)),
+            effect_settings_commands: Arc::new(Mutex::new(Vec::new())),
+            one_shots: Arc::new(Mutex::new(Vec::new())),
+            live_input: Arc::new(Mutex::new(None)),
+            volume_ramp: Arc::new(Mutex::new(VolumeRamp::default())),
+            gain_staging: Arc::new(Mutex::new(
+                crate::playback::gain_staging::GainStagingRecorder::default(),
+            )),
+            decode_pool: DecodePool::default(),
+            reader_cache: ReaderCache::default(),
+        }
+    }
 }
 
 /// Internal playback engine used by the high-level
@@ -91,11 +163,21 @@ pub struct PlayerEngine {
     effects_reset: Arc<AtomicU64>,
     inline_effects_update: Arc<Mutex<Option<InlineEffectsUpdate>>>,
     inline_track_mix_updates: Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     prot: Arc<Mutex<Prot>>,
     buffer_settings: Arc<Mutex<PlaybackBufferSettings>>,
     effects: Arc<Mutex<Vec<crate::dsp::effects::AudioEffect>>>,
     dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
     effect_settings_commands: Arc<Mutex<Vec<EffectSettingsCommand>>>,
+    one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    live_input: SharedLiveInput,
+    volume_ramp: SharedVolumeRamp,
+    gain_staging: SharedGainStaging,
+    decode_pool: DecodePool,
+    reader_cache: ReaderCache,
     mix_thread_handle: Option<JoinHandle<()>>,
 }
 
@@ -108,10 +190,20 @@ impl PlayerEngine {
             buffer_settings,
             effects,
             dsp_metrics,
+            track_levels,
+            effect_timings,
+            diagnostics_events,
             effects_reset,
             inline_effects_update,
             inline_track_mix_updates,
+            inline_group_gains,
             effect_settings_commands,
+            one_shots,
+            live_input,
+            volume_ramp,
+            gain_staging,
+            decode_pool,
+            reader_cache,
         } = config;
         let buffer_map = init_buffer_map();
         let buffer_notify = Arc::new(Condvar::new());
@@ -147,12 +239,22 @@ impl PlayerEngine {
             effects_reset,
             inline_effects_update,
             inline_track_mix_updates,
+            inline_group_gains,
             abort,
             prot,
             buffer_settings,
             effects,
             dsp_metrics,
+            track_levels,
+            effect_timings,
+            diagnostics_events,
             effect_settings_commands,
+            one_shots,
+            live_input,
+            volume_ramp,
+            gain_staging,
+            decode_pool,
+            reader_cache,
             mix_thread_handle: None,
         }
     }
@@ -177,7 +279,7 @@ impl PlayerEngine {
     }
 
     /// Start mixing and return a receiver for `(buffer, duration)` chunks.
-    pub fn start_receiver(&mut self) -> Receiver<(SamplesBuffer, f64)> {
+    pub fn start_receiver(&mut self) -> OutputReceiver {
         let prot = self.lock_prot_invariant();
         let keys = prot.get_keys();
         drop(prot);
@@ -185,7 +287,7 @@ impl PlayerEngine {
         self.spawn_mix_receiver()
     }
 
-    fn spawn_mix_receiver(&mut self) -> Receiver<(SamplesBuffer, f64)> {
+    fn spawn_mix_receiver(&mut self) -> OutputReceiver {
         let prot = self.lock_prot_invariant();
         let audio_info = prot.info.clone();
         drop(prot);
@@ -196,6 +298,7 @@ impl PlayerEngine {
             effects_reset: self.effects_reset.clone(),
             inline_effects_update: self.inline_effects_update.clone(),
             inline_track_mix_updates: self.inline_track_mix_updates.clone(),
+            inline_group_gains: self.inline_group_gains.clone(),
             finished_tracks: self.finished_tracks.clone(),
             prot: self.prot.clone(),
             abort: self.abort.clone(),
@@ -203,7 +306,16 @@ impl PlayerEngine {
             buffer_settings: self.buffer_settings.clone(),
             effects: self.effects.clone(),
             dsp_metrics: self.dsp_metrics.clone(),
+            track_levels: self.track_levels.clone(),
+            effect_timings: self.effect_timings.clone(),
+            diagnostics_events: self.diagnostics_events.clone(),
             effect_settings_commands: self.effect_settings_commands.clone(),
+            one_shots: self.one_shots.clone(),
+            live_input: self.live_input.clone(),
+            volume_ramp: self.volume_ramp.clone(),
+            gain_staging: self.gain_staging.clone(),
+            decode_pool: self.decode_pool.clone(),
+            reader_cache: self.reader_cache.clone(),
         });
         self.mix_thread_handle = Some(handle);
         receiver
@@ -229,7 +341,11 @@ impl PlayerEngine {
         let sample_rate = prot.info.sample_rate;
         let channels = prot.info.channels as usize;
         let track_mix_settings = prot.get_track_mix_settings();
-        let start_buffer_ms = self.lock_buffer_settings_recoverable().start_buffer_ms;
+        let (start_buffer_ms, pan_law) = {
+            let settings = self.lock_buffer_settings_recoverable();
+            (settings.start_buffer_ms, settings.pan_law)
+        };
+        let pan_law = pan_law.unwrap_or_else(|| prot.get_pan_law());
         drop(prot);
         let start_samples = ((sample_rate as f32 * start_buffer_ms) / 1000.0) as usize * channels;
         let buffer_size = (sample_rate as usize * 10).max(start_samples * 2);
@@ -253,7 +369,7 @@ impl PlayerEngine {
                 .get(&track_key)
                 .copied()
                 .unwrap_or((1.0, 0.0));
-            let gains = compute_track_channel_gains(level, pan, channels);
+            let gains = compute_track_channel_gains(level, pan, channels, pan_law);
             self.lock_track_channel_gains_recoverable()
                 .insert(track_key, gains);
         }
@@ -349,15 +465,18 @@ impl Drop for PlayerEngine {
     }
 }
 
-pub(crate) fn compute_track_channel_gains(level: f32, pan: f32, channels: usize) -> Vec<f32> {
+pub(crate) fn compute_track_channel_gains(
+    level: f32,
+    pan: f32,
+    channels: usize,
+    pan_law: PanLaw,
+) -> Vec<f32> {
     let level = level.max(0.0);
     if channels <= 1 {
         return vec![level];
     }
 
-    let pan = pan.clamp(-1.0, 1.0);
-    let left = if pan > 0.0 { 1.0 - pan } else { 1.0 };
-    let right = if pan < 0.0 { 1.0 + pan } else { 1.0 };
+    let (left, right) = pan_law.gains(pan);
 
     let mut gains = vec![level; channels];
     gains[0] = level * left;
@@ -368,10 +487,11 @@ pub(crate) fn compute_track_channel_gains(level: f32, pan: f32, channels: usize)
 #[cfg(test)]
 mod tests {
     use super::compute_track_channel_gains;
+    use crate::dsp::pan::PanLaw;
 
     #[test]
     fn channel_gains_apply_level_and_pan() {
-        let gains = compute_track_channel_gains(0.5, 0.5, 2);
+        let gains = compute_track_channel_gains(0.5, 0.5, 2, PanLaw::Balance);
         assert_eq!(gains.len(), 2);
         assert!((gains[0] - 0.25).abs() < 1e-6);
         assert!((gains[1] - 0.5).abs() < 1e-6);
@@ -379,7 +499,13 @@ mod tests {
 
     #[test]
     fn mono_gain_uses_level_only() {
-        let gains = compute_track_channel_gains(0.8, -1.0, 1);
+        let gains = compute_track_channel_gains(0.8, -1.0, 1, PanLaw::Balance);
         assert_eq!(gains, vec![0.8]);
     }
+
+    #[test]
+    fn channel_gains_follow_the_pan_law() {
+        let gains = compute_track_channel_gains(1.0, 0.0, 2, PanLaw::Minus6Db);
+        assert_eq!(gains, vec![0.5, 0.5]);
+    }
 }
@@ -0,0 +1,288 @@
+//! Transient one-shot voices mixed on top of the engine output.
+//!
+//! One-shots (UI sounds, stingers) are decoded up front on the calling
+//! thread, converted to the engine channel layout and sample rate, and queued
+//! in a shared slot list. The mix thread adds queued voices to each output
+//! chunk after the effect chain and drops them once they have played out, so
+//! the main track buffers are never touched.
+
+use std::fmt;
+use std::fs::File;
+use std::io::{BufReader, Cursor, Read, Seek};
+use std::sync::Mutex;
+
+use rodio::{Decoder, Source};
+
+use crate::container::attachments::{read_attachments_with_download, AttachmentError};
+use crate::dsp::pan::PanLaw;
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::tools::progressive::ProgressiveFile;
+
+use super::compute_track_channel_gains;
+
+/// Audio played by [`crate::playback::player::Player::play_one_shot`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OneShotSource {
+    /// Audio file on disk.
+    File(String),
+    /// Attachment of the loaded `.prot` container, by file name.
+    Attachment(String),
+}
+
+/// Errors that can occur while preparing a one-shot.
+#[derive(Debug)]
+pub enum OneShotError {
+    /// No playback is running to mix the one-shot into.
+    NotPlaying,
+    /// An attachment was requested but the player has no container loaded.
+    NoContainer,
+    /// No attachment with the given name was found in the container.
+    AttachmentNotFound(String),
+    /// Reading container attachments failed.
+    Attachments(AttachmentError),
+    /// An I/O error occurred while opening the file.
+    Io(std::io::Error),
+    /// The rodio decoder failed to decode the audio data.
+    Decode(rodio::decoder::DecoderError),
+}
+
+impl fmt::Display for OneShotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotPlaying => write!(f, "no playback running for one-shot"),
+            Self::NoContainer => write!(f, "one-shot attachment requires a loaded container"),
+            Self::AttachmentNotFound(name) => write!(f, "one-shot attachment not found: {}", name),
+            Self::Attachments(err) => write!(f, "failed to read prot container: {}", err),
+            Self::Io(err) => write!(f, "failed to read one-shot: {}", err),
+            Self::Decode(err) => write!(f, "failed to decode one-shot: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for OneShotError {}
+
+impl From<std::io::Error> for OneShotError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<rodio::decoder::DecoderError> for OneShotError {
+    fn from(err: rodio::decoder::DecoderError) -> Self {
+        Self::Decode(err)
+    }
+}
+
+impl From<AttachmentError> for OneShotError {
+    fn from(err: AttachmentError) -> Self {
+        Self::Attachments(err)
+    }
+}
+
+/// Decoded one-shot queued for mixing, already in engine layout.
+#[derive(Debug, Clone)]
+pub struct OneShotVoice {
+    samples: Vec<f32>,
+    position: usize,
+}
+
+/// Output layout the one-shot is converted to.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct OneShotLayout {
+    pub(crate) channels: usize,
+    pub(crate) sample_rate: u32,
+    pub(crate) pan_law: PanLaw,
+}
+
+impl OneShotVoice {
+    /// Decode `source` and convert it to `layout` with `gain` and `pan` applied.
+    ///
+    /// `container_path` resolves [`OneShotSource::Attachment`] sources,
+    /// read through `download` while the container is still arriving;
+    /// attachments larger than `max_attachment_bytes` are refused.
+    pub(crate) fn load(
+        source: &OneShotSource,
+        container_path: Option<&str>,
+        download: Option<&ProgressiveFile>,
+        max_attachment_bytes: u64,
+        gain: f32,
+        pan: f32,
+        layout: OneShotLayout,
+    ) -> Result<Self, OneShotError> {
+        match source {
+            OneShotSource::File(path) => {
+                Self::decode(BufReader::new(File::open(path)?), gain, pan, layout)
+            }
+            OneShotSource::Attachment(name) => {
+                let container_path = container_path.ok_or(OneShotError::NoContainer)?;
+                let attachment =
+                    read_attachments_with_download(container_path, download, max_attachment_bytes)?
+                        .into_iter()
+                        .find(|attachment| attachment.name.trim_matches('"') == name)
+                        .ok_or_else(|| OneShotError::AttachmentNotFound(name.clone()))?;
+                Self::decode(Cursor::new(attachment.data), gain, pan, layout)
+            }
+        }
+    }
+
+    fn decode<R>(
+        reader: R,
+        gain: f32,
+        pan: f32,
+        layout: OneShotLayout,
+    ) -> Result<Self, OneShotError>
+    where
+        R: Read + Seek + Send + Sync + 'static,
+    {
+        let decoder = Decoder::new(reader)?;
+        let source_channels = usize::from(decoder.channels().max(1));
+        let source_rate = decoder.sample_rate();
+        let samples: Vec<f32> = decoder.collect();
+        Ok(Self::from_interleaved(
+            &samples,
+            source_channels,
+            source_rate,
+            gain,
+            pan,
+            layout,
+        ))
+    }
+
+    /// Build a voice from interleaved samples in any channel count and rate.
+    pub(crate) fn from_interleaved(
+        samples: &[f32],
+        source_channels: usize,
+        source_rate: u32,
+        gain: f32,
+        pan: f32,
+        layout: OneShotLayout,
+    ) -> Self {
+        let mapped = map_channels(samples, source_channels, gain, pan, layout);
+        Self {
+            samples: resample_linear(&mapped, layout.channels, source_rate, layout.sample_rate),
+            position: 0,
+        }
+    }
+
+    /// Add the next samples of this voice to `output`.
+    ///
+    /// Returns `true` once the voice has played out.
+    fn mix_into(&mut self, output: &mut [f32]) -> bool {
+        let remaining = &self.samples[self.position..];
+        let count = remaining.len().min(output.len());
+        for (out, sample) in output.iter_mut().zip(&remaining[..count]) {
+            *out += *sample;
+        }
+        self.position += count;
+        self.position >= self.samples.len()
+    }
+}
+
+/// Mix every queued voice into `output` and drop finished voices.
+pub(crate) fn mix_one_shots(voices: &Mutex<Vec<OneShotVoice>>, output: &mut [f32]) {
+    let mut voices = lock_recoverable(
+        voices,
+        "mix runtime one-shots",
+        "queued one-shots are disposable transient voices",
+    );
+    if voices.is_empty() {
+        return;
+    }
+    voices.retain_mut(|voice| !voice.mix_into(output));
+}
+
+/// Map interleaved source frames onto `channels` outputs with level and pan.
+///
+/// Mono sources feed both front channels; the first two source channels are
+/// panned like a track slot and further channels pass through by index.
+fn map_channels(
+    samples: &[f32],
+    source_channels: usize,
+    gain: f32,
+    pan: f32,
+    layout: OneShotLayout,
+) -> Vec<f32> {
+    let channels = layout.channels;
+    let gains = compute_track_channel_gains(gain, pan, channels, layout.pan_law);
+    let frames = samples.len() / source_channels;
+    let mut mapped = Vec::with_capacity(frames * channels);
+    for frame in samples.chunks_exact(source_channels) {
+        if channels == 1 {
+            let sum: f32 = frame.iter().sum();
+            mapped.push(sum / source_channels as f32 * gains[0]);
+            continue;
+        }
+        for (channel, channel_gain) in gains.iter().enumerate() {
+            let sample = match channel {
+                0 => frame[0],
+                1 => frame[1.min(source_channels - 1)],
+                _ => frame.get(channel).copied().unwrap_or(0.0),
+            };
+            mapped.push(sample * channel_gain);
+        }
+    }
+    mapped
+}
+
+/// Linearly resample interleaved `samples` from `from_rate` to `to_rate`.
+fn resample_linear(samples: &[f32], channels: usize, from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let frames = samples.len() / channels;
+    let out_frames = (frames as u64 * u64::from(to_rate) / u64::from(from_rate)) as usize;
+    let step = f64::from(from_rate) / f64::from(to_rate);
+    let mut resampled = Vec::with_capacity(out_frames * channels);
+    for out_frame in 0..out_frames {
+        let position = out_frame as f64 * step;
+        let index = position as usize;
+        let next = (index + 1).min(frames - 1);
+        let fraction = (position - index as f64) as f32;
+        for channel in 0..channels {
+            let a = samples[index * channels + channel];
+            let b = samples[next * channels + channel];
+            resampled.push(a + (b - a) * fraction);
+        }
+    }
+    resampled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEREO_48K: OneShotLayout = OneShotLayout {
+        channels: 2,
+        sample_rate: 48_000,
+        pan_law: PanLaw::Balance,
+    };
+
+    #[test]
+    fn mono_source_is_panned_across_stereo_output() {
+        let voice = OneShotVoice::from_interleaved(&[1.0, 1.0], 1, 48_000, 0.5, 1.0, STEREO_48K);
+        assert_eq!(voice.samples, vec![0.0, 0.5, 0.0, 0.5]);
+    }
+
+    #[test]
+    fn voices_mix_additively_and_drop_when_finished() {
+        let voices = Mutex::new(vec![OneShotVoice::from_interleaved(
+            &[0.25; 6], 2, 48_000, 1.0, 0.0, STEREO_48K,
+        )]);
+        let mut output = vec![0.5_f32; 4];
+        mix_one_shots(&voices, &mut output);
+        assert_eq!(output, vec![0.75; 4]);
+        assert_eq!(voices.lock().unwrap().len(), 1);
+
+        let mut output = vec![0.0_f32; 4];
+        mix_one_shots(&voices, &mut output);
+        assert_eq!(output, vec![0.25, 0.25, 0.0, 0.0]);
+        assert!(voices.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn resampling_scales_frame_count() {
+        let samples = vec![0.0_f32; 2 * 24_000];
+        let resampled = resample_linear(&samples, 2, 24_000, 48_000);
+        assert_eq!(resampled.len(), 2 * 48_000);
+    }
+}
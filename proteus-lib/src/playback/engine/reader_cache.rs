@@ -0,0 +1,174 @@
+//! Container readers and decoders kept open across seeks.
+//!
+//! Re-probing a large container and rebuilding its track decoders dominates
+//! seek latency. When a container worker stops, it checks its reader and
+//! decoders into its player's [`ReaderCache`]; the next worker for the same
+//! file checks them out and seeks in place instead. Entries are keyed by
+//! path, length, and modification time so a rewritten file is always
+//! re-opened.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::SystemTime;
+
+use symphonia::core::codecs::Decoder;
+use symphonia::core::formats::FormatReader;
+
+use crate::playback::mutex_policy::lock_recoverable;
+
+/// Containers kept open at once; each holds a file handle and decoder state.
+const MAX_CACHED_READERS: usize = 4;
+
+/// Open container readers shared by the engines of one player.
+///
+/// Clones share the same entries, and the readers close once every clone
+/// has been dropped. Each player owns its own cache, so readers never leak
+/// between players that open the same file.
+#[derive(Clone, Default)]
+pub struct ReaderCache {
+    readers: Arc<Mutex<Vec<CachedReader>>>,
+}
+
+/// Open container reader with the decoders built for its tracks.
+pub(crate) struct ContainerReader {
+    pub format: Box<dyn FormatReader>,
+    pub decoders: HashMap<u32, Box<dyn Decoder>>,
+}
+
+struct CachedReader {
+    key: ReaderKey,
+    reader: ContainerReader,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ReaderKey {
+    path: String,
+    len: u64,
+    modified: Option<SystemTime>,
+}
+
+impl ReaderKey {
+    fn for_path(path: &str) -> Option<Self> {
+        let meta = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path: path.to_string(),
+            len: meta.len(),
+            modified: meta.modified().ok(),
+        })
+    }
+}
+
+impl ReaderCache {
+    /// Take the cached reader for `path`, if one matches the file on disk.
+    ///
+    /// Decoders are reset; the reader is left wherever the last worker
+    /// stopped and must be seeked before use.
+    pub(crate) fn checkout(&self, path: &str) -> Option<ContainerReader> {
+        let mut readers = self.lock_readers();
+        let index = readers.iter().position(|cached| cached.key.path == path)?;
+        let cached = readers.remove(index);
+        drop(readers);
+        if Some(cached.key) != ReaderKey::for_path(path) {
+            return None;
+        }
+        let mut reader = cached.reader;
+        for decoder in reader.decoders.values_mut() {
+            decoder.reset();
+        }
+        Some(reader)
+    }
+
+    /// Keep `reader` open for the next worker on `path`.
+    pub(crate) fn checkin(&self, path: &str, reader: ContainerReader) {
+        let Some(key) = ReaderKey::for_path(path) else {
+            return;
+        };
+        let mut readers = self.lock_readers();
+        readers.retain(|cached| cached.key.path != path);
+        if readers.len() >= MAX_CACHED_READERS {
+            readers.remove(0);
+        }
+        readers.push(CachedReader { key, reader });
+    }
+
+    /// Drop the cached reader for `path`, so the next worker re-opens it.
+    #[cfg(feature = "encryption")]
+    pub(crate) fn evict(&self, path: &str) {
+        self.lock_readers().retain(|cached| cached.key.path != path);
+    }
+
+    /// Number of readers currently kept open.
+    pub fn len(&self) -> usize {
+        self.lock_readers().len()
+    }
+
+    /// Return `true` when no reader is kept open.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Recoverable poison policy: cached readers are disposable and re-opened on a miss.
+    fn lock_readers(&self) -> MutexGuard<'_, Vec<CachedReader>> {
+        lock_recoverable(
+            &self.readers,
+            "container reader cache",
+            "cached readers are disposable and re-opened on a miss",
+        )
+    }
+}
+
+impl fmt::Debug for ReaderCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReaderCache")
+            .field("readers", &self.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_fixtures::demo_container_copy;
+
+    fn open(path: &str) -> ContainerReader {
+        ContainerReader {
+            format: crate::tools::decode::get_reader(path).unwrap(),
+            decoders: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn checked_in_reader_is_returned_once() {
+        let path = demo_container_copy("reader_cache", "hit");
+        let cache = ReaderCache::default();
+        assert!(cache.checkout(&path).is_none());
+        cache.checkin(&path, open(&path));
+        assert!(cache.checkout(&path).is_some());
+        assert!(cache.checkout(&path).is_none());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn rewritten_file_is_not_served_from_the_cache() {
+        let path = demo_container_copy("reader_cache", "stale");
+        let cache = ReaderCache::default();
+        cache.checkin(&path, open(&path));
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes.truncate(bytes.len() - 1);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(cache.checkout(&path).is_none());
+        assert!(cache.is_empty());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn caches_are_not_shared_between_players() {
+        let path = demo_container_copy("reader_cache", "scoped");
+        let first = ReaderCache::default();
+        first.checkin(&path, open(&path));
+        assert!(ReaderCache::default().checkout(&path).is_none());
+        assert!(first.clone().checkout(&path).is_some());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+//! Pull-based access to the engine's mixed output.
+//!
+//! A host application that runs its own audio callback can take mixed
+//! samples from an [`EngineSource`] instead of letting the player open and
+//! own an output device. The mix thread keeps running ahead into the bounded
+//! output queue; the source hands its chunks out in whatever sizes the host
+//! asks for.
+
+use std::time::Duration;
+
+use rodio::source::SeekError;
+use rodio::Source;
+
+use super::{OutputReceiver, PlayerEngine};
+
+/// Mixed engine output, pulled by the caller.
+///
+/// Implements rodio's [`Source`] so it can be appended to a rodio sink or
+/// mixer, and offers [`EngineSource::next_chunk`] and
+/// [`EngineSource::try_next_chunk`] for hosts that fill their own buffers.
+/// Dropping the source stops the mix thread.
+pub struct EngineSource {
+    // Declared before `engine` so the queue closes before the engine joins
+    // its mix thread on drop.
+    receiver: OutputReceiver,
+    engine: PlayerEngine,
+    pending: Vec<f32>,
+    position: usize,
+    channels: u16,
+    sample_rate: u32,
+    finished: bool,
+}
+
+impl PlayerEngine {
+    /// Start mixing and return the output as a pull-based [`EngineSource`].
+    pub fn into_source(mut self) -> EngineSource {
+        let receiver = self.start_receiver();
+        let (channels, sample_rate) = {
+            let prot = self.lock_prot_invariant();
+            (prot.info.channels.max(1) as u16, prot.info.sample_rate)
+        };
+        EngineSource {
+            receiver,
+            engine: self,
+            pending: Vec::new(),
+            position: 0,
+            channels,
+            sample_rate,
+            finished: false,
+        }
+    }
+}
+
+impl EngineSource {
+    /// Fill `out` with interleaved mixed samples, blocking until they are
+    /// ready.
+    ///
+    /// Returns the number of samples written, which is less than
+    /// `out.len()` only once the mix has ended.
+    pub fn next_chunk(&mut self, out: &mut [f32]) -> usize {
+        self.fill(out, true)
+    }
+
+    /// Fill `out` with whatever mixed samples are ready, without blocking.
+    ///
+    /// Intended for real-time callbacks: samples that are not ready yet are
+    /// written as silence. Returns the number of mixed samples written.
+    pub fn try_next_chunk(&mut self, out: &mut [f32]) -> usize {
+        let written = self.fill(out, false);
+        out[written..].fill(0.0);
+        written
+    }
+
+    /// `true` once the mix has ended and every sample has been pulled.
+    pub fn is_finished(&self) -> bool {
+        self.finished && self.position >= self.pending.len()
+    }
+
+    /// Engine producing this source's samples.
+    pub fn engine(&self) -> &PlayerEngine {
+        &self.engine
+    }
+
+    fn fill(&mut self, out: &mut [f32], block: bool) -> usize {
+        let mut written = 0;
+        while written < out.len() {
+            if self.position >= self.pending.len() && !self.refill(block) {
+                break;
+            }
+            let available = &self.pending[self.position..];
+            let count = available.len().min(out.len() - written);
+            out[written..written + count].copy_from_slice(&available[..count]);
+            self.position += count;
+            written += count;
+        }
+        written
+    }
+
+    /// Load the next chunk; `false` when none is ready or the mix has ended.
+    fn refill(&mut self, block: bool) -> bool {
+        if self.finished {
+            return false;
+        }
+        let chunk = if block {
+            self.receiver.recv().ok()
+        } else {
+            match self.receiver.try_recv() {
+                Ok(chunk) => Some(chunk),
+                Err(std::sync::mpsc::TryRecvError::Empty) => return false,
+                Err(std::sync::mpsc::TryRecvError::Disconnected) => None,
+            }
+        };
+        let Some((buffer, _)) = chunk else {
+            self.finished = true;
+            return false;
+        };
+        self.pending.clear();
+        self.pending.extend(buffer);
+        self.position = 0;
+        true
+    }
+}
+
+impl Iterator for EngineSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.pending.len() && !self.refill(true) {
+            return None;
+        }
+        let sample = self.pending[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+}
+
+impl Source for EngineSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    fn try_seek(&mut self, _pos: Duration) -> Result<(), SeekError> {
+        Err(SeekError::NotSupported {
+            underlying_source: "EngineSource",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use crate::container::prot::{PathsTrack, Prot};
+
+    use super::super::PlayerEngineConfig;
+    use super::*;
+
+    fn fixture_source() -> EngineSource {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_audio/test-16bit.wav");
+        let prot =
+            Prot::new_from_file_paths(vec![PathsTrack::new_from_file_paths(
+                vec![path.to_string()],
+            )]);
+        PlayerEngine::new(Arc::new(Mutex::new(prot)), PlayerEngineConfig::default()).into_source()
+    }
+
+    #[test]
+    fn source_pulls_mixed_samples_in_requested_sizes() {
+        let mut source = fixture_source();
+        assert!(source.channels() >= 1);
+        assert!(source.sample_rate() > 0);
+
+        let mut block = vec![0.0; 1000];
+        assert_eq!(source.next_chunk(&mut block), block.len());
+        assert_eq!(source.next_chunk(&mut block[..7]), 7);
+        assert_eq!(source.by_ref().take(10_000).count(), 10_000);
+        assert!(!source.is_finished());
+    }
+
+    #[test]
+    fn try_next_chunk_pads_with_silence() {
+        let mut source = fixture_source();
+        let mut block = vec![1.0; 256];
+        let written = source.try_next_chunk(&mut block);
+        assert!(block[written..].iter().all(|sample| *sample == 0.0));
+    }
+}
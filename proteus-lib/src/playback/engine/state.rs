@@ -1,5 +1,13 @@
 //! Shared playback state and metrics structures.
 
+use serde::Serialize;
+
+use crate::dsp::dither::DitherSettings;
+use crate::dsp::downmix::DownmixMode;
+use crate::dsp::effects::convolution_reverb::ReverbMetrics;
+use crate::dsp::pan::PanLaw;
+use crate::dsp::resample::ResampleQuality;
+
 /// Buffering configuration for the playback engine.
 #[derive(Debug, Clone, Copy)]
 pub struct PlaybackBufferSettings {
@@ -19,8 +27,29 @@ pub struct PlaybackBufferSettings {
     pub seek_fade_out_ms: f32,
     /// Duration of the fade-in applied after a seek operation, in milliseconds.
     pub seek_fade_in_ms: f32,
+    /// Audio (ms) before a non-zero start position that is run through the
+    /// effect chain and discarded, so reverb tails are already ringing when
+    /// playback becomes audible after a seek (0 disables).
+    pub seek_preroll_ms: f32,
     /// Crossfade duration (ms) used when switching inline effects mid-playback.
     pub inline_effects_transition_ms: f32,
+    /// Crossfade duration (ms) used when jumping to a queued section.
+    pub section_crossfade_ms: f32,
+    /// Crossfade duration (ms) used when a deferred shuffle hands playback to
+    /// the new selection at a chunk or beat boundary.
+    pub shuffle_crossfade_ms: f32,
+    /// Share of the effect-chain output in the main mix, in `[0.0, 1.0]`; the
+    /// rest is the chain's input, delayed to match the chain's latency.
+    /// `1.0` (the default) is fully processed.
+    pub chain_mix: f32,
+    /// Ducking applied to the main mix while one-shots or keyed live input play.
+    pub ducking: DuckingSettings,
+    /// Automatic quality reduction while the effect chain overruns.
+    pub overload: OverloadPolicy,
+    /// Dither applied to the final output before it reaches the sink (`None` = off).
+    pub output_dither: Option<DitherSettings>,
+    /// Sample-rate conversion used when the device rate differs from the container rate.
+    pub output_resampler: ResampleQuality,
     /// Threshold in milliseconds above which a late-append event is logged.
     pub append_jitter_log_ms: f32,
     /// When `true`, logs a message each time an effect boundary is crossed.
@@ -43,6 +72,29 @@ pub struct PlaybackBufferSettings {
     /// budget finer control. Disabled by default to avoid extra overhead in
     /// stability-first playback modes.
     pub output_slice_ms: Option<f32>,
+    /// Send output in fine slices near run starts, source boundaries, and
+    /// the end of the mix so seeks and stops take effect sooner.
+    ///
+    /// Away from those points output keeps `output_slice_ms` (or full
+    /// batches), so steady-state throughput is unchanged.
+    pub adaptive_chunking: bool,
+    /// Milliseconds of mixed audio the mix thread may queue ahead of the
+    /// playback thread.
+    ///
+    /// Larger values add underrun margin when DSP load is uneven; smaller
+    /// values keep control latency down. Read when a playback run starts, so
+    /// changes apply from the next start or seek.
+    pub output_queue_ms: f32,
+    /// How long (ms) output may stay silent, frozen, stalled, or late before
+    /// the playback watchdog raises a diagnostics event (0 disables).
+    pub silence_watchdog_ms: f32,
+    /// Apply the container's loudness tag as an input trim before effects.
+    pub loudness_trim: bool,
+    /// Pan law used for track slots, overriding the container's `pan_law`
+    /// (`None` = use the container's law).
+    pub pan_law: Option<PanLaw>,
+    /// Fold-down applied at the output stage, before metering.
+    pub downmix: DownmixMode,
 }
 
 impl PlaybackBufferSettings {
@@ -57,12 +109,26 @@ impl PlaybackBufferSettings {
             startup_fade_ms: 150.0,
             seek_fade_out_ms: 30.0,
             seek_fade_in_ms: 80.0,
+            seek_preroll_ms: 0.0,
             inline_effects_transition_ms: 25.0,
+            section_crossfade_ms: 250.0,
+            shuffle_crossfade_ms: 250.0,
+            chain_mix: 1.0,
+            ducking: DuckingSettings::default(),
+            overload: OverloadPolicy::default(),
+            output_dither: None,
+            output_resampler: ResampleQuality::Linear,
             append_jitter_log_ms: 0.0,
             effect_boundary_log: false,
             parameter_ramp_ms: 5.0,
             max_sink_latency_ms: None,
             output_slice_ms: None,
+            adaptive_chunking: true,
+            output_queue_ms: 50.0,
+            silence_watchdog_ms: 3000.0,
+            loudness_trim: true,
+            pan_law: None,
+            downmix: DownmixMode::Off,
         }
     }
 
@@ -83,18 +149,145 @@ impl PlaybackBufferSettings {
             startup_fade_ms: 80.0,
             seek_fade_out_ms: 20.0,
             seek_fade_in_ms: 50.0,
+            seek_preroll_ms: 0.0,
             inline_effects_transition_ms: 15.0,
+            section_crossfade_ms: 250.0,
+            shuffle_crossfade_ms: 250.0,
+            chain_mix: 1.0,
+            ducking: DuckingSettings::default(),
+            overload: OverloadPolicy::default(),
+            output_dither: None,
+            output_resampler: ResampleQuality::Linear,
             append_jitter_log_ms: 0.0,
             effect_boundary_log: false,
             parameter_ramp_ms: 5.0,
             max_sink_latency_ms: Some(60.0),
             output_slice_ms: Some(30.0),
+            adaptive_chunking: true,
+            output_queue_ms: 30.0,
+            silence_watchdog_ms: 3000.0,
+            loudness_trim: true,
+            pan_law: None,
+            downmix: DownmixMode::Off,
+        }
+    }
+
+    /// Size sink buffering around a device buffer of `device_latency_ms`.
+    ///
+    /// Used for low-latency output: output is sliced to about two device
+    /// periods and the sink queue is capped at four, so control latency
+    /// follows the negotiated device buffer instead of fixed defaults.
+    pub fn fit_output_latency(&mut self, device_latency_ms: f32) {
+        let period_ms = device_latency_ms.max(1.0);
+        self.output_slice_ms = Some((period_ms * 2.0).clamp(5.0, 30.0));
+        self.max_sink_latency_ms = Some((period_ms * 4.0).max(20.0));
+    }
+}
+
+/// Automatic attenuation of the main mix while an overlay plays.
+///
+/// The main mix is ducked by `depth_db` while any one-shot is audible (or
+/// while live input that keys ducking is above its threshold), and recovers
+/// after the last one ends plus `hold_ms`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DuckingSettings {
+    /// Whether ducking is applied at all.
+    pub enabled: bool,
+    /// Attenuation in dB while ducked (sign is ignored).
+    pub depth_db: f32,
+    /// Time constant (ms) for moving into the duck.
+    pub attack_ms: f32,
+    /// Time (ms) the duck is held after the overlay ends.
+    pub hold_ms: f32,
+    /// Time constant (ms) for recovering to full level.
+    pub release_ms: f32,
+}
+
+impl DuckingSettings {
+    /// Create enabled ducking settings.
+    pub fn new(depth_db: f32, attack_ms: f32, hold_ms: f32, release_ms: f32) -> Self {
+        Self {
+            enabled: true,
+            depth_db,
+            attack_ms: attack_ms.max(0.0),
+            hold_ms: hold_ms.max(0.0),
+            release_ms: release_ms.max(0.0),
+        }
+    }
+}
+
+impl Default for DuckingSettings {
+    /// Disabled, with -9 dB depth and 10/150/300 ms attack/hold/release.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            depth_db: -9.0,
+            attack_ms: 10.0,
+            hold_ms: 150.0,
+            release_ms: 300.0,
+        }
+    }
+}
+
+/// Automatic quality reduction when the effect chain cannot keep up.
+///
+/// Load is the effect chain's processing time divided by the duration of
+/// the audio it processed. After load stays above `degrade_load` for
+/// `degrade_after_ms`, the next [`DegradationStep`] is applied; after it
+/// stays below `recover_load` for `recover_after_ms`, the most recent step is
+/// undone. Each change raises a diagnostics event.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OverloadPolicy {
+    /// Whether quality is degraded at all.
+    pub enabled: bool,
+    /// Load above which the chain counts as overloaded.
+    pub degrade_load: f64,
+    /// Load below which the chain counts as having headroom again.
+    pub recover_load: f64,
+    /// Time (ms) of sustained overload before stepping quality down.
+    pub degrade_after_ms: f32,
+    /// Time (ms) of sustained headroom before stepping quality back up.
+    pub recover_after_ms: f32,
+}
+
+impl Default for OverloadPolicy {
+    /// Disabled, degrading above 0.9 load for 500 ms and recovering below 0.5
+    /// load for 10 s.
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            degrade_load: 0.9,
+            recover_load: 0.5,
+            degrade_after_ms: 500.0,
+            recover_after_ms: 10_000.0,
         }
     }
 }
 
+/// Quality reduction applied under an [`OverloadPolicy`], in the order the
+/// steps are taken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DegradationStep {
+    /// Truncate convolution reverb impulse responses at a higher tail level.
+    ShortenReverbTail,
+    /// Convolve in larger blocks, trading latency for fewer partitions.
+    LargerConvolutionBlocks,
+    /// Bypass the effect with the highest average processing time.
+    BypassHeaviestEffect,
+}
+
+impl DegradationStep {
+    /// Every step, in the order they are applied.
+    pub const ALL: [Self; 3] = [
+        Self::ShortenReverbTail,
+        Self::LargerConvolutionBlocks,
+        Self::BypassHeaviestEffect,
+    ];
+}
+
 /// Aggregated DSP chain performance metrics used by debug UI.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize)]
 pub struct DspChainMetrics {
     /// Whether the last mix cycle exceeded its deadline.
     pub overrun: bool,
@@ -140,6 +333,37 @@ pub struct DspChainMetrics {
     pub queued_sink_ms: f64,
     /// Duration of the most recently appended output chunk, in milliseconds.
     pub output_chunk_ms: f64,
+    /// Mixed audio waiting between the mix and playback threads, in
+    /// milliseconds, sampled after each send.
+    pub output_queue_ms: f64,
+    /// Capacity of the queue between the mix and playback threads, in milliseconds.
+    pub output_queue_capacity_ms: f64,
+    /// Number of [`DegradationStep`]s currently applied by the overload
+    /// policy (0 = full quality).
+    pub degradation_level: usize,
+    /// Threading and timing of the first enabled convolution reverb in the
+    /// chain, if any.
+    pub reverb: Option<ReverbMetrics>,
+}
+
+/// Processing time of one effect in the chain, measured on the mix thread.
+///
+/// Averages and maxima restart whenever the chain's effects change.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct EffectTiming {
+    /// Position of the effect in the chain.
+    pub index: usize,
+    /// Display label of the effect, as in `AudioEffect::display_name`.
+    pub name: &'static str,
+    /// Time spent in the effect for the most recent chunk, in milliseconds.
+    pub last_ms: f64,
+    /// Rolling average time per chunk, in milliseconds.
+    pub avg_ms: f64,
+    /// Largest time for a single chunk, in milliseconds.
+    pub max_ms: f64,
+    /// Rolling average time as a share of the audio each chunk covers
+    /// (`1.0` = this effect alone uses the whole real-time budget).
+    pub avg_load: f64,
 }
 
 #[cfg(test)]
@@ -165,6 +389,8 @@ mod tests {
         assert!(!settings.effect_boundary_log);
         assert!(settings.max_sink_latency_ms.is_none());
         assert!(settings.output_slice_ms.is_none());
+        assert!(settings.adaptive_chunking);
+        assert_eq!(settings.output_queue_ms, 50.0);
     }
 
     #[test]
@@ -198,5 +424,6 @@ mod tests {
         assert!(!metrics.late_append_active);
         assert_eq!(metrics.queued_sink_ms, 0.0);
         assert_eq!(metrics.output_chunk_ms, 0.0);
+        assert_eq!(metrics.output_queue_ms, 0.0);
     }
 }
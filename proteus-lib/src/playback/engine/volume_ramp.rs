@@ -0,0 +1,197 @@
+//! Sample-accurate volume automation applied on the mix thread.
+//!
+//! The control path records a fade request in a shared [`VolumeRamp`]; the
+//! mix thread picks it up at the next chunk boundary and evaluates the curve
+//! per frame, so gain changes never step between chunks.
+
+use std::f32::consts::FRAC_PI_2;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Shared volume automation state for one player.
+pub type SharedVolumeRamp = Arc<Mutex<VolumeRamp>>;
+
+/// Gain below which the exponential curve treats the level as silence (-80 dB).
+const EXPONENTIAL_FLOOR: f32 = 1.0e-4;
+
+/// Shape of a volume fade.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VolumeCurve {
+    /// Gain changes linearly with time.
+    #[default]
+    Linear,
+    /// Gain changes linearly in decibels, which sounds even to the ear.
+    Exponential,
+    /// Quarter-sine shape that keeps perceived power steady in crossfades.
+    EqualPower,
+}
+
+impl VolumeCurve {
+    /// Gain at progress `t` in `[0, 1]` of a fade from `start` to `target`.
+    pub fn value(&self, start: f32, target: f32, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        if t >= 1.0 {
+            return target;
+        }
+        match self {
+            Self::Linear => start + (target - start) * t,
+            Self::Exponential => {
+                let from = start.max(EXPONENTIAL_FLOOR);
+                let to = target.max(EXPONENTIAL_FLOOR);
+                from * (to / from).powf(t)
+            }
+            Self::EqualPower => {
+                if target >= start {
+                    start + (target - start) * (t * FRAC_PI_2).sin()
+                } else {
+                    target + (start - target) * (t * FRAC_PI_2).cos()
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum RampRequest {
+    Fade {
+        target: f32,
+        duration: Duration,
+        curve: VolumeCurve,
+    },
+    Cancel,
+}
+
+/// Automation gain applied to the mixed output before it reaches the sink.
+#[derive(Debug, Clone)]
+pub struct VolumeRamp {
+    gain: f32,
+    start: f32,
+    target: f32,
+    curve: VolumeCurve,
+    total_frames: u64,
+    elapsed_frames: u64,
+    pending: Option<RampRequest>,
+}
+
+impl Default for VolumeRamp {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            start: 1.0,
+            target: 1.0,
+            curve: VolumeCurve::Linear,
+            total_frames: 0,
+            elapsed_frames: 0,
+            pending: None,
+        }
+    }
+}
+
+impl VolumeRamp {
+    /// Queue a fade to `target` over `duration`, starting at the current gain.
+    pub(crate) fn fade_to(&mut self, target: f32, duration: Duration, curve: VolumeCurve) {
+        self.pending = Some(RampRequest::Fade {
+            target: target.max(0.0),
+            duration,
+            curve,
+        });
+    }
+
+    /// Stop any running or queued fade, holding the current gain.
+    pub(crate) fn cancel(&mut self) {
+        self.pending = Some(RampRequest::Cancel);
+    }
+
+    /// Current automation gain.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Return `true` while a fade is queued or running.
+    pub fn is_fading(&self) -> bool {
+        matches!(self.pending, Some(RampRequest::Fade { .. }))
+            || self.elapsed_frames < self.total_frames
+    }
+
+    /// Apply the automation gain to one interleaved chunk.
+    pub(crate) fn process(&mut self, samples: &mut [f32], channels: usize, sample_rate: u32) {
+        match self.pending.take() {
+            Some(RampRequest::Fade {
+                target,
+                duration,
+                curve,
+            }) => {
+                self.start = self.gain;
+                self.target = target;
+                self.curve = curve;
+                self.total_frames = (duration.as_secs_f64() * sample_rate as f64).round() as u64;
+                self.elapsed_frames = 0;
+                if self.total_frames == 0 {
+                    self.gain = target;
+                }
+            }
+            Some(RampRequest::Cancel) => self.total_frames = self.elapsed_frames,
+            None => {}
+        }
+
+        let channels = channels.max(1);
+        if self.elapsed_frames >= self.total_frames {
+            if (self.gain - 1.0).abs() > f32::EPSILON {
+                samples.iter_mut().for_each(|sample| *sample *= self.gain);
+            }
+            return;
+        }
+        for frame in samples.chunks_mut(channels) {
+            if self.elapsed_frames < self.total_frames {
+                self.elapsed_frames += 1;
+                let t = self.elapsed_frames as f32 / self.total_frames as f32;
+                self.gain = self.curve.value(self.start, self.target, t);
+            }
+            frame.iter_mut().for_each(|sample| *sample *= self.gain);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn curves_hit_endpoints() {
+        for curve in [
+            VolumeCurve::Linear,
+            VolumeCurve::Exponential,
+            VolumeCurve::EqualPower,
+        ] {
+            assert!((curve.value(1.0, 0.25, 0.0) - 1.0).abs() < 1e-6);
+            assert_eq!(curve.value(1.0, 0.0, 1.0), 0.0);
+        }
+        assert!((VolumeCurve::Exponential.value(1.0, 0.01, 0.5) - 0.1).abs() < 1e-5);
+    }
+
+    #[test]
+    fn ramp_spans_chunks_and_holds_target() {
+        let mut ramp = VolumeRamp::default();
+        ramp.fade_to(0.0, Duration::from_millis(4), VolumeCurve::Linear);
+        let mut first = vec![1.0_f32; 4];
+        ramp.process(&mut first, 1, 1000);
+        assert_eq!(first, vec![0.75, 0.5, 0.25, 0.0]);
+        assert!(!ramp.is_fading());
+
+        let mut second = vec![1.0_f32; 2];
+        ramp.process(&mut second, 1, 1000);
+        assert_eq!(second, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn cancel_holds_current_gain() {
+        let mut ramp = VolumeRamp::default();
+        ramp.fade_to(0.0, Duration::from_millis(4), VolumeCurve::Linear);
+        let mut chunk = vec![1.0_f32; 4];
+        ramp.process(&mut chunk[..2], 2, 1000);
+        ramp.cancel();
+        ramp.process(&mut chunk[2..], 2, 1000);
+        assert_eq!(chunk, vec![0.75, 0.75, 0.75, 0.75]);
+        assert!((ramp.gain() - 0.75).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,268 @@
+//! Per-stage level history for debugging gain staging.
+//!
+//! While enabled, the mix thread records peak and RMS levels at each stage of
+//! the pipeline: every logical track after gain/pan, the summed mix, the
+//! output of each effect, and the master output after overlays and volume
+//! automation. Levels are kept for a rolling window so a report shows where
+//! clipping or excessive attenuation was introduced over the last few seconds.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::playback::track_meter::TrackLevels;
+
+/// Shared gain staging recorder for one player.
+pub type SharedGainStaging = Arc<Mutex<GainStagingRecorder>>;
+
+/// Floor used when converting silent levels to decibels.
+const SILENCE_DB: f32 = -120.0;
+/// Tolerance for accumulated chunk durations when trimming the window.
+const WINDOW_EPSILON_S: f64 = 1.0e-9;
+
+/// One measurement point in the playback pipeline, in signal-flow order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GainStage {
+    /// A logical track after decode and track gain/pan, before summing.
+    Track(usize),
+    /// All tracks (and live input) summed, before the effect chain.
+    Sum,
+    /// Output of one effect in the chain.
+    Effect {
+        /// Position of the effect in the chain.
+        index: usize,
+        /// Display name of the effect.
+        name: &'static str,
+    },
+    /// Final output after overlays and volume automation.
+    Master,
+}
+
+/// Levels measured at one stage over the report window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageLevels {
+    /// Stage these levels were measured at.
+    pub stage: GainStage,
+    /// Highest absolute sample value (linear, 1.0 = full scale).
+    pub peak: f32,
+    /// RMS level across all channels (linear, 1.0 = full scale).
+    pub rms: f32,
+}
+
+impl StageLevels {
+    /// Peak level in dBFS.
+    pub fn peak_db(&self) -> f32 {
+        to_db(self.peak)
+    }
+
+    /// RMS level in dBFS.
+    pub fn rms_db(&self) -> f32 {
+        to_db(self.rms)
+    }
+
+    /// Return `true` when the stage exceeded full scale.
+    pub fn is_clipping(&self) -> bool {
+        self.peak > 1.0
+    }
+}
+
+/// Snapshot of stage levels over the recent window.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GainStagingReport {
+    /// Seconds of audio covered by the report.
+    pub window_s: f64,
+    /// Levels per stage, in signal-flow order.
+    pub stages: Vec<StageLevels>,
+}
+
+impl GainStagingReport {
+    /// First stage, in signal-flow order, whose peak exceeded full scale.
+    pub fn first_clipping_stage(&self) -> Option<&StageLevels> {
+        self.stages.iter().find(|levels| levels.is_clipping())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ChunkLevels {
+    peak: f32,
+    sum_squares: f64,
+    samples: u64,
+    seconds: f64,
+}
+
+#[derive(Debug, Default)]
+struct StageHistory {
+    chunks: VecDeque<ChunkLevels>,
+    seconds: f64,
+}
+
+/// Rolling per-stage level history written by the mix thread.
+#[derive(Debug, Default)]
+pub struct GainStagingRecorder {
+    window_s: Option<f64>,
+    stages: BTreeMap<GainStage, StageHistory>,
+}
+
+impl GainStagingRecorder {
+    /// Enable recording over `window`, or disable it with `None`.
+    ///
+    /// Changing the window discards recorded history.
+    pub(crate) fn set_window(&mut self, window: Option<Duration>) {
+        self.window_s = window.map(|window| window.as_secs_f64());
+        self.stages.clear();
+    }
+
+    /// Return `true` while stages are being recorded.
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.window_s.is_some()
+    }
+
+    /// Record one chunk of interleaved samples at `stage`.
+    pub(crate) fn record(&mut self, stage: GainStage, samples: &[f32], seconds: f64) {
+        let mut peak = 0.0_f32;
+        let mut sum_squares = 0.0_f64;
+        for sample in samples.iter().filter(|sample| sample.is_finite()) {
+            peak = peak.max(sample.abs());
+            sum_squares += f64::from(*sample) * f64::from(*sample);
+        }
+        self.push(
+            stage,
+            ChunkLevels {
+                peak,
+                sum_squares,
+                samples: samples.len() as u64,
+                seconds,
+            },
+        );
+    }
+
+    /// Record per-track levels already measured by the mixer.
+    pub(crate) fn record_tracks<'a>(
+        &mut self,
+        tracks: impl IntoIterator<Item = &'a TrackLevels>,
+        frames: usize,
+        seconds: f64,
+    ) {
+        for (index, levels) in tracks.into_iter().enumerate() {
+            let peak = levels.peak.iter().copied().fold(0.0_f32, f32::max);
+            let sum_squares = levels
+                .rms
+                .iter()
+                .map(|rms| f64::from(*rms) * f64::from(*rms) * frames as f64)
+                .sum();
+            let chunk = ChunkLevels {
+                peak,
+                sum_squares,
+                samples: (frames * levels.rms.len()) as u64,
+                seconds,
+            };
+            self.push(GainStage::Track(index), chunk);
+        }
+    }
+
+    /// Summarize the recorded window.
+    pub(crate) fn report(&self) -> GainStagingReport {
+        let stages = self
+            .stages
+            .iter()
+            .map(|(stage, history)| {
+                let peak = history.chunks.iter().map(|c| c.peak).fold(0.0, f32::max);
+                let sum_squares: f64 = history.chunks.iter().map(|c| c.sum_squares).sum();
+                let samples: u64 = history.chunks.iter().map(|c| c.samples).sum();
+                StageLevels {
+                    stage: *stage,
+                    peak,
+                    rms: (sum_squares / samples.max(1) as f64).sqrt() as f32,
+                }
+            })
+            .collect();
+        GainStagingReport {
+            window_s: self
+                .stages
+                .values()
+                .map(|history| history.seconds)
+                .fold(0.0, f64::max),
+            stages,
+        }
+    }
+
+    fn push(&mut self, stage: GainStage, chunk: ChunkLevels) {
+        let Some(window_s) = self.window_s else {
+            return;
+        };
+        let history = self.stages.entry(stage).or_default();
+        history.seconds += chunk.seconds;
+        history.chunks.push_back(chunk);
+        // Drop old chunks only while the rest still covers the window.
+        while let Some(oldest) = history.chunks.front().copied() {
+            if history.seconds - oldest.seconds < window_s - WINDOW_EPSILON_S {
+                break;
+            }
+            history.chunks.pop_front();
+            history.seconds -= oldest.seconds;
+        }
+    }
+}
+
+fn to_db(level: f32) -> f32 {
+    if level <= 0.0 {
+        SILENCE_DB
+    } else {
+        (20.0 * level.log10()).max(SILENCE_DB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_orders_stages_and_flags_first_clip() {
+        let mut recorder = GainStagingRecorder::default();
+        recorder.set_window(Some(Duration::from_secs(1)));
+        recorder.record(GainStage::Master, &[1.5, -1.5], 0.1);
+        recorder.record(
+            GainStage::Effect {
+                index: 0,
+                name: "Gain",
+            },
+            &[1.2, 0.0],
+            0.1,
+        );
+        recorder.record(GainStage::Sum, &[0.5, -0.5], 0.1);
+
+        let report = recorder.report();
+        let stages: Vec<GainStage> = report.stages.iter().map(|levels| levels.stage).collect();
+        assert_eq!(stages[0], GainStage::Sum);
+        assert_eq!(stages[2], GainStage::Master);
+        assert!((report.stages[0].rms - 0.5).abs() < 1e-6);
+        assert_eq!(
+            report.first_clipping_stage().map(|levels| levels.stage),
+            Some(GainStage::Effect {
+                index: 0,
+                name: "Gain"
+            })
+        );
+    }
+
+    #[test]
+    fn history_is_trimmed_to_window() {
+        let mut recorder = GainStagingRecorder::default();
+        recorder.set_window(Some(Duration::from_millis(200)));
+        recorder.record(GainStage::Sum, &[1.0], 0.1);
+        for _ in 0..3 {
+            recorder.record(GainStage::Sum, &[0.25], 0.1);
+        }
+        let report = recorder.report();
+        assert!((report.window_s - 0.2).abs() < 1e-9);
+        assert_eq!(report.stages[0].peak, 0.25);
+        assert!((report.stages[0].peak_db() + 12.041).abs() < 1e-2);
+    }
+
+    #[test]
+    fn disabled_recorder_keeps_nothing() {
+        let mut recorder = GainStagingRecorder::default();
+        recorder.record(GainStage::Sum, &[1.0], 0.1);
+        assert!(recorder.report().stages.is_empty());
+    }
+}
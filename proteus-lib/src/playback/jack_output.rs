@@ -0,0 +1,306 @@
+//! JACK output backend (feature `jack`).
+//!
+//! [`JackBackend`] registers one JACK client with an audio output port per
+//! channel, so proteus output can be routed anywhere in a JACK session graph.
+//! PipeWire sessions are reached through PipeWire's JACK compatibility layer.
+//! Install it with `Player::set_output_backend`.
+//!
+//! Mixed chunks are queued under a mutex that the realtime process callback
+//! only ever `try_lock`s; a contended period is filled with silence rather
+//! than blocking the JACK thread. Content at a different rate than the JACK
+//! server is converted with the in-crate sinc resampler on append.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use jack::{
+    AsyncClient, AudioOut, Client, ClientOptions, ClosureProcessHandler, Control, Port, PortFlags,
+    ProcessScope,
+};
+use log::warn;
+
+use crate::dsp::resample::{ResampleQuality, Resampler};
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::output_sink::{OutputBackend, OutputBackendError, OutputChunk, OutputSink};
+
+type ProcessFn = Box<dyn FnMut(&Client, &ProcessScope) -> Control + Send>;
+
+/// Output backend that plays through a JACK client.
+#[derive(Debug, Clone)]
+pub struct JackBackend {
+    client_name: String,
+    port_names: Vec<String>,
+    auto_connect: bool,
+}
+
+impl JackBackend {
+    /// Create a backend registering a JACK client named `client_name`.
+    ///
+    /// Ports are named `out_1`, `out_2`, ... and connected to the system's
+    /// physical playback ports in order.
+    pub fn new(client_name: impl Into<String>) -> Self {
+        Self {
+            client_name: client_name.into(),
+            port_names: Vec::new(),
+            auto_connect: true,
+        }
+    }
+
+    /// Name the output port of each channel, in channel order.
+    ///
+    /// Channels without a name fall back to `out_<n>`.
+    pub fn with_port_names(mut self, names: Vec<String>) -> Self {
+        self.port_names = names;
+        self
+    }
+
+    /// Connect ports to the physical playback ports when the sink opens.
+    ///
+    /// Disable to leave routing to a session manager.
+    pub fn with_auto_connect(mut self, auto_connect: bool) -> Self {
+        self.auto_connect = auto_connect;
+        self
+    }
+
+    fn port_name(&self, channel: usize) -> String {
+        self.port_names
+            .get(channel)
+            .filter(|name| !name.is_empty())
+            .cloned()
+            .unwrap_or_else(|| format!("out_{}", channel + 1))
+    }
+}
+
+impl OutputBackend for JackBackend {
+    fn open_sink(
+        &self,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Box<dyn OutputSink>, OutputBackendError> {
+        if channels == 0 {
+            return Err(OutputBackendError::UnsupportedFormat {
+                channels,
+                sample_rate,
+            });
+        }
+        let (client, _status) = Client::new(&self.client_name, ClientOptions::NO_START_SERVER)
+            .map_err(|err| OutputBackendError::Unavailable(err.to_string()))?;
+        let server_rate = client.sample_rate() as u32;
+        let ports = (0..channels as usize)
+            .map(|channel| {
+                client
+                    .register_port(&self.port_name(channel), AudioOut)
+                    .map_err(|err| OutputBackendError::Other(err.to_string()))
+            })
+            .collect::<Result<Vec<Port<AudioOut>>, _>>()?;
+        let port_names: Vec<String> = ports.iter().filter_map(|port| port.name().ok()).collect();
+
+        let queue = Arc::new(Mutex::new(JackQueue::new(channels as usize)));
+        let process: ProcessFn = Box::new(render_callback(queue.clone(), ports));
+        let active = client
+            .activate_async((), ClosureProcessHandler::new(process))
+            .map_err(|err| OutputBackendError::Other(err.to_string()))?;
+        if self.auto_connect {
+            connect_to_playback(active.as_client(), &port_names);
+        }
+
+        let resampler = (sample_rate != server_rate).then(|| {
+            Resampler::new(
+                ResampleQuality::SincMedium,
+                sample_rate,
+                server_rate,
+                channels as usize,
+            )
+        });
+        Ok(Box::new(JackSink {
+            queue,
+            resampler: Mutex::new(resampler),
+            server_rate,
+            _client: active,
+        }))
+    }
+}
+
+// Connect our ports to the physical playback ports, one to one.
+fn connect_to_playback(client: &Client, port_names: &[String]) {
+    let playback = client.ports(None, None, PortFlags::IS_INPUT | PortFlags::IS_PHYSICAL);
+    for (source, destination) in port_names.iter().zip(&playback) {
+        if let Err(err) = client.connect_ports_by_name(source, destination) {
+            warn!("failed to connect {} to {}: {}", source, destination, err);
+        }
+    }
+}
+
+// Build the realtime callback that drains the queue into the port buffers.
+fn render_callback(
+    queue: Arc<Mutex<JackQueue>>,
+    mut ports: Vec<Port<AudioOut>>,
+) -> impl FnMut(&Client, &ProcessScope) -> Control + Send {
+    move |_client, scope| {
+        let mut buffers: Vec<&mut [f32]> = ports
+            .iter_mut()
+            .map(|port| port.as_mut_slice(scope))
+            .collect();
+        match queue.try_lock() {
+            Ok(mut queue) => queue.render(&mut buffers),
+            Err(_) => buffers.iter_mut().for_each(|buffer| buffer.fill(0.0)),
+        }
+        Control::Continue
+    }
+}
+
+// Chunk queue shared between the sink handle and the process callback.
+struct JackQueue {
+    channels: usize,
+    chunks: VecDeque<Vec<f32>>,
+    offset: usize,
+    paused: bool,
+    volume: f32,
+    frames_played: u64,
+}
+
+impl JackQueue {
+    fn new(channels: usize) -> Self {
+        Self {
+            channels,
+            chunks: VecDeque::new(),
+            offset: 0,
+            paused: false,
+            volume: 1.0,
+            frames_played: 0,
+        }
+    }
+
+    // Fill one period of per-channel buffers, deinterleaving queued chunks.
+    fn render(&mut self, buffers: &mut [&mut [f32]]) {
+        let frames = buffers.first().map_or(0, |buffer| buffer.len());
+        for frame in 0..frames {
+            let sample_frame = if self.paused { None } else { self.next_frame() };
+            for (channel, buffer) in buffers.iter_mut().enumerate() {
+                buffer[frame] =
+                    sample_frame.map_or(0.0, |start| self.chunks[0][start + channel] * self.volume);
+            }
+            if sample_frame.is_some() {
+                self.offset += self.channels;
+                self.frames_played += 1;
+            }
+        }
+    }
+
+    // Index of the next frame in the front chunk, dropping exhausted chunks.
+    fn next_frame(&mut self) -> Option<usize> {
+        while let Some(front) = self.chunks.front() {
+            if self.offset + self.channels <= front.len() {
+                return Some(self.offset);
+            }
+            self.chunks.pop_front();
+            self.offset = 0;
+        }
+        None
+    }
+}
+
+struct JackSink {
+    queue: Arc<Mutex<JackQueue>>,
+    resampler: Mutex<Option<Resampler>>,
+    server_rate: u32,
+    _client: AsyncClient<(), ClosureProcessHandler<ProcessFn>>,
+}
+
+impl JackSink {
+    /// Recoverable poison policy: queued output is disposable playback state.
+    fn lock_queue(&self) -> MutexGuard<'_, JackQueue> {
+        lock_recoverable(
+            &self.queue,
+            "jack output queue",
+            "queued output is disposable playback state",
+        )
+    }
+}
+
+impl OutputSink for JackSink {
+    fn append(&self, chunk: OutputChunk) {
+        let samples = match lock_recoverable(
+            &self.resampler,
+            "jack output resampler",
+            "resampler history only affects the next few frames",
+        )
+        .as_mut()
+        {
+            Some(resampler) => {
+                let mut output = Vec::with_capacity(chunk.samples.len());
+                resampler.process(&chunk.samples, &mut output);
+                output
+            }
+            None => chunk.samples,
+        };
+        self.lock_queue().chunks.push_back(samples);
+    }
+
+    fn play(&self) {
+        self.lock_queue().paused = false;
+    }
+
+    fn pause(&self) {
+        self.lock_queue().paused = true;
+    }
+
+    fn is_paused(&self) -> bool {
+        self.lock_queue().paused
+    }
+
+    fn volume(&self) -> f32 {
+        self.lock_queue().volume
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.lock_queue().volume = volume;
+    }
+
+    fn len(&self) -> usize {
+        self.lock_queue().chunks.len()
+    }
+
+    fn clear(&self) {
+        let mut queue = self.lock_queue();
+        queue.chunks.clear();
+        queue.offset = 0;
+        queue.frames_played = 0;
+    }
+
+    fn stop(&self) {
+        self.clear();
+        self.pause();
+    }
+
+    fn position(&self) -> Duration {
+        let frames = self.lock_queue().frames_played;
+        Duration::from_secs_f64(frames as f64 / self.server_rate.max(1) as f64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn queue_deinterleaves_across_chunks_and_pads_silence() {
+        let mut queue = JackQueue::new(2);
+        queue.chunks.push_back(vec![0.1, 0.2]);
+        queue.chunks.push_back(vec![0.3, 0.4]);
+        let mut left = [1.0_f32; 3];
+        let mut right = [1.0_f32; 3];
+        queue.render(&mut [&mut left, &mut right]);
+        assert_eq!(left, [0.1, 0.3, 0.0]);
+        assert_eq!(right, [0.2, 0.4, 0.0]);
+        assert_eq!(queue.frames_played, 2);
+    }
+
+    #[test]
+    fn unnamed_channels_use_default_port_names() {
+        let backend = JackBackend::new("proteus").with_port_names(vec!["main_l".to_string()]);
+        assert_eq!(backend.port_name(0), "main_l");
+        assert_eq!(backend.port_name(1), "out_2");
+    }
+}
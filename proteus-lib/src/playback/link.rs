@@ -0,0 +1,96 @@
+//! Ableton Link tempo and phase sync (feature `link`).
+//!
+//! The Link SDK is GPL-licensed C++, so proteus does not link it directly.
+//! Hosts wrap their Link session (for example a `rusty_link` session state
+//! captured per call) in a [`LinkTimeline`] and install it with
+//! `Player::set_link`. While installed, engines started by play, seek, or a
+//! section jump quantize shuffle points to the shared beat grid, queued
+//! section changes wait for the next Link bar line, and reports carry the
+//! session tempo and phase.
+
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::container::prot::BeatGrid;
+
+/// Shared handle to a host's Link session.
+pub type SharedLink = Arc<dyn LinkTimeline>;
+
+/// View of a Link session's shared timeline.
+pub trait LinkTimeline: Send + Sync {
+    /// Session tempo in beats per minute.
+    fn tempo(&self) -> f64;
+    /// Beat position on the shared timeline at the time of the call, for
+    /// the session's quantum.
+    fn beat(&self) -> f64;
+    /// Beats per bar used for phase alignment.
+    fn quantum(&self) -> f64 {
+        4.0
+    }
+}
+
+/// Link session state included in playback reports.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct LinkReport {
+    /// Session tempo in beats per minute.
+    pub tempo: f64,
+    /// Beat position on the shared timeline.
+    pub beat: f64,
+    /// Position within the current bar, in beats (`0.0..quantum`).
+    pub phase: f64,
+    /// Beats per bar.
+    pub quantum: f64,
+}
+
+impl LinkReport {
+    /// Read the current state of `link`.
+    pub fn capture(link: &dyn LinkTimeline) -> Self {
+        let beat = link.beat();
+        let quantum = link.quantum().max(1.0);
+        Self {
+            tempo: link.tempo(),
+            beat,
+            phase: beat.rem_euclid(quantum),
+            quantum,
+        }
+    }
+}
+
+/// Beat grid that puts the Link bar lines under the audio heard now.
+///
+/// `position` is the timeline position currently reaching the listener.
+pub(crate) fn link_beat_grid(link: &dyn LinkTimeline, position: f64) -> BeatGrid {
+    let report = LinkReport::capture(link);
+    let grid = BeatGrid::new(0.0, report.tempo, report.quantum);
+    BeatGrid {
+        origin_seconds: position - report.phase * grid.beat_seconds,
+        ..grid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FixedLink;
+
+    impl LinkTimeline for FixedLink {
+        fn tempo(&self) -> f64 {
+            120.0
+        }
+
+        fn beat(&self) -> f64 {
+            9.5
+        }
+    }
+
+    #[test]
+    fn grid_places_the_next_bar_after_the_remaining_beats() {
+        let report = LinkReport::capture(&FixedLink);
+        assert_eq!(report.phase, 1.5);
+        // 1.5 beats into a 4-beat bar at 120 BPM: the next bar is 1.25 s away.
+        let grid = link_beat_grid(&FixedLink, 10.0);
+        assert!((grid.next_bar(10.0) - 11.25).abs() < 1e-9);
+    }
+}
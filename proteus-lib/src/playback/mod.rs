@@ -1,6 +1,23 @@
 //! Playback engine and high-level player API.
 
+#[cfg(feature = "async")]
+pub mod async_player;
+pub mod clock_sync;
 pub mod engine;
+pub mod gain_staging;
+#[cfg(feature = "jack")]
+pub mod jack_output;
+#[cfg(feature = "link")]
+pub mod link;
 pub(crate) mod mutex_policy;
 pub mod output_meter;
+pub mod output_sink;
+pub mod pcm_output;
 pub mod player;
+pub mod realization;
+pub mod render;
+pub mod server;
+#[cfg(feature = "streaming")]
+pub mod streaming;
+pub mod track_meter;
+pub mod transition;
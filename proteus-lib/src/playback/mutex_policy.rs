@@ -37,7 +37,6 @@ pub(crate) fn lock_recoverable<'a, T>(
     recover_poison(mutex.lock(), label, rationale)
 }
 
-#[allow(dead_code)]
 pub(crate) fn wait_recoverable<'a, T>(
     condvar: &Condvar,
     guard: MutexGuard<'a, T>,
@@ -1,4 +1,22 @@
 //! Output meter for tracking playback levels.
+//!
+//! Alongside peak/average levels the meter tracks stereo correlation between
+//! the first two channels and flags channels that are phase-inverted relative
+//! to channel 0, so cancellation between summed stems can be spotted.
+
+/// Correlation below which a channel is reported as phase-inverted against channel 0.
+pub const PHASE_INVERSION_THRESHOLD: f32 = -0.5;
+
+/// Stereo correlation and phase inversion reading for one meter frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CorrelationReading {
+    /// Correlation between channels 0 and 1, from `-1.0` (out of phase) to
+    /// `1.0` (identical). `0.0` for mono, silent, or uncorrelated signals.
+    pub correlation: f32,
+    /// Per-channel flag set when the channel correlates with channel 0 below
+    /// [`PHASE_INVERSION_THRESHOLD`]. Channel 0 is the reference and is never flagged.
+    pub phase_inverted: Vec<bool>,
+}
 
 #[cfg(feature = "output-meter")]
 mod enabled {
@@ -7,15 +25,58 @@ mod enabled {
     use rodio::buffer::SamplesBuffer;
     use rodio::Source;
 
+    use super::{CorrelationReading, PHASE_INVERSION_THRESHOLD};
     use crate::dsp::guardrails::{sanitize_channels, sanitize_sample_rate};
 
     #[derive(Debug)]
     struct Frame {
         peak: Vec<f32>,
         avg: Vec<f32>,
+        correlation: CorrelationReading,
         len_samples: usize,
     }
 
+    /// Per-frame accumulators for correlation against channel 0.
+    struct CorrelationSums {
+        energy: Vec<f32>,
+        cross: Vec<f32>,
+    }
+
+    impl CorrelationSums {
+        fn new(channels: usize) -> Self {
+            Self {
+                energy: vec![0.0; channels],
+                cross: vec![0.0; channels],
+            }
+        }
+
+        fn clear(&mut self) {
+            self.energy.fill(0.0);
+            self.cross.fill(0.0);
+        }
+
+        fn reading(&self) -> CorrelationReading {
+            let coefficient = |ch: usize| {
+                let denom = (self.energy[0] * self.energy[ch]).sqrt();
+                if denom > f32::EPSILON {
+                    (self.cross[ch] / denom).clamp(-1.0, 1.0)
+                } else {
+                    0.0
+                }
+            };
+            CorrelationReading {
+                correlation: if self.energy.len() > 1 {
+                    coefficient(1)
+                } else {
+                    0.0
+                },
+                phase_inverted: (0..self.energy.len())
+                    .map(|ch| ch > 0 && coefficient(ch) < PHASE_INVERSION_THRESHOLD)
+                    .collect(),
+            }
+        }
+    }
+
     #[derive(Debug)]
     pub struct OutputMeter {
         sample_rate: u32,
@@ -26,6 +87,7 @@ mod enabled {
         current_frame_remaining: usize,
         levels: Vec<f32>,
         averages: Vec<f32>,
+        correlation: CorrelationReading,
         queue: VecDeque<Frame>,
     }
 
@@ -43,6 +105,7 @@ mod enabled {
                 current_frame_remaining: 0,
                 levels: vec![0.0; channels],
                 averages: vec![0.0; channels],
+                correlation: silent_correlation(channels),
                 queue: VecDeque::new(),
             }
         }
@@ -53,6 +116,7 @@ mod enabled {
             self.current_frame_remaining = 0;
             self.levels.fill(0.0);
             self.averages.fill(0.0);
+            self.correlation = silent_correlation(self.channels);
         }
 
         pub fn set_refresh_hz(&mut self, refresh_hz: f32) {
@@ -73,6 +137,7 @@ mod enabled {
                 self.channels = channels;
                 self.levels = vec![0.0; channels];
                 self.averages = vec![0.0; channels];
+                self.correlation = silent_correlation(channels);
             }
             if sample_rate != self.sample_rate {
                 self.sample_rate = sample_rate;
@@ -85,10 +150,17 @@ mod enabled {
             let mut peak = vec![0.0_f32; channels];
             let mut sum = vec![0.0_f32; channels];
             let mut count = vec![0_usize; channels];
+            let mut correlation = CorrelationSums::new(channels);
+            let mut reference = 0.0_f32;
             let mut in_frame = 0_usize;
 
             for (idx, sample) in buffer.clone().enumerate() {
                 let ch = idx % channels;
+                if ch == 0 {
+                    reference = sample;
+                }
+                correlation.energy[ch] += sample * sample;
+                correlation.cross[ch] += sample * reference;
                 let value = sample.abs();
                 if value > peak[ch] {
                     peak[ch] = value;
@@ -98,18 +170,24 @@ mod enabled {
                 in_frame += 1;
 
                 if in_frame >= frame_len_samples {
-                    self.queue
-                        .push_back(finalize_frame(&peak, &sum, &count, in_frame));
+                    self.queue.push_back(finalize_frame(
+                        &peak,
+                        &sum,
+                        &count,
+                        &correlation,
+                        in_frame,
+                    ));
                     peak.fill(0.0);
                     sum.fill(0.0);
                     count.fill(0);
+                    correlation.clear();
                     in_frame = 0;
                 }
             }
 
             if in_frame > 0 {
                 self.queue
-                    .push_back(finalize_frame(&peak, &sum, &count, in_frame));
+                    .push_back(finalize_frame(&peak, &sum, &count, &correlation, in_frame));
             }
         }
 
@@ -130,6 +208,7 @@ mod enabled {
                     };
                     self.levels = frame.peak;
                     self.averages = frame.avg;
+                    self.correlation = frame.correlation;
                     self.current_frame_remaining = frame.len_samples;
                 }
 
@@ -146,13 +225,31 @@ mod enabled {
         pub fn averages(&self) -> Vec<f32> {
             self.averages.clone()
         }
+
+        /// Correlation and phase inversion flags for the current frame.
+        pub fn correlation(&self) -> CorrelationReading {
+            self.correlation.clone()
+        }
+    }
+
+    fn silent_correlation(channels: usize) -> CorrelationReading {
+        CorrelationReading {
+            correlation: 0.0,
+            phase_inverted: vec![false; channels],
+        }
     }
 
     fn frame_samples_per_channel(sample_rate: u32, refresh_hz: f32) -> usize {
         ((sample_rate as f32 / refresh_hz).round() as usize).max(1)
     }
 
-    fn finalize_frame(peak: &[f32], sum: &[f32], count: &[usize], len_samples: usize) -> Frame {
+    fn finalize_frame(
+        peak: &[f32],
+        sum: &[f32],
+        count: &[usize],
+        correlation: &CorrelationSums,
+        len_samples: usize,
+    ) -> Frame {
         let mut avg = Vec::with_capacity(sum.len());
         for (idx, value) in sum.iter().enumerate() {
             let denom = count[idx].max(1) as f32;
@@ -161,6 +258,7 @@ mod enabled {
         Frame {
             peak: peak.to_vec(),
             avg,
+            correlation: correlation.reading(),
             len_samples,
         }
     }
@@ -170,6 +268,8 @@ mod enabled {
 mod disabled {
     use rodio::buffer::SamplesBuffer;
 
+    use super::CorrelationReading;
+
     /// No-op output level meter used when the `output-meter` feature is disabled.
     #[derive(Debug)]
     pub struct OutputMeter {
@@ -205,6 +305,14 @@ mod disabled {
         pub fn averages(&self) -> Vec<f32> {
             vec![0.0; self.channels]
         }
+
+        /// Returns zero correlation with no channels flagged as inverted.
+        pub fn correlation(&self) -> CorrelationReading {
+            CorrelationReading {
+                correlation: 0.0,
+                phase_inverted: vec![false; self.channels],
+            }
+        }
     }
 }
 
@@ -241,11 +349,52 @@ mod tests {
         assert!(avg[1] > 0.0);
     }
 
+    #[cfg(feature = "output-meter")]
+    #[test]
+    fn output_meter_detects_inverted_channel() {
+        use rodio::buffer::SamplesBuffer;
+
+        let mut meter = OutputMeter::new(2, 10, 1.0);
+        let samples: Vec<f32> = (0..10)
+            .flat_map(|i| {
+                let value = if i % 2 == 0 { 0.5 } else { -0.25 };
+                [value, -value]
+            })
+            .collect();
+        meter.push_samples(&SamplesBuffer::new(2, 10, samples));
+        meter.advance(1.0);
+
+        let reading = meter.correlation();
+        assert!((reading.correlation + 1.0).abs() < 1e-6);
+        assert_eq!(reading.phase_inverted, vec![false, true]);
+    }
+
+    #[cfg(feature = "output-meter")]
+    #[test]
+    fn output_meter_identical_channels_are_fully_correlated() {
+        use rodio::buffer::SamplesBuffer;
+
+        let mut meter = OutputMeter::new(2, 10, 1.0);
+        let samples: Vec<f32> = (0..10)
+            .flat_map(|i| {
+                let value = (i as f32 * 0.7).sin();
+                [value, value]
+            })
+            .collect();
+        meter.push_samples(&SamplesBuffer::new(2, 10, samples));
+        meter.advance(1.0);
+
+        let reading = meter.correlation();
+        assert!((reading.correlation - 1.0).abs() < 1e-6);
+        assert_eq!(reading.phase_inverted, vec![false, false]);
+    }
+
     #[cfg(not(feature = "output-meter"))]
     #[test]
     fn output_meter_disabled_returns_zeroes() {
         let meter = OutputMeter::new(2, 48_000, 10.0);
         assert_eq!(meter.levels(), vec![0.0, 0.0]);
         assert_eq!(meter.averages(), vec![0.0, 0.0]);
+        assert_eq!(meter.correlation().phase_inverted, vec![false, false]);
     }
 }
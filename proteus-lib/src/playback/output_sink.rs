@@ -0,0 +1,214 @@
+//! Output sink abstraction for the playback worker.
+//!
+//! The playback worker hands mixed audio to an [`OutputSink`] and drives
+//! transport through it (pause, volume, queue depth) without knowing which
+//! audio API sits behind it. A rodio [`Sink`] connected to the default device
+//! is the built-in implementation; other outputs plug in through an
+//! [`OutputBackend`] installed with `Player::set_output_backend`.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use rodio::buffer::SamplesBuffer;
+use rodio::{Sink, Source};
+
+/// Shared, replaceable output sink for one player.
+pub type SharedOutputSink = Arc<Mutex<Box<dyn OutputSink>>>;
+
+/// One chunk of interleaved output audio.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OutputChunk {
+    /// Interleaved `f32` samples.
+    pub samples: Vec<f32>,
+    /// Number of interleaved channels.
+    pub channels: u16,
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+}
+
+impl OutputChunk {
+    /// Create a chunk from interleaved samples.
+    pub fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> Self {
+        Self {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Number of frames in the chunk.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels.max(1) as usize
+    }
+
+    /// Chunk duration in seconds.
+    pub fn duration_s(&self) -> f64 {
+        self.frames() as f64 / self.sample_rate.max(1) as f64
+    }
+}
+
+impl From<SamplesBuffer> for OutputChunk {
+    fn from(buffer: SamplesBuffer) -> Self {
+        let channels = buffer.channels();
+        let sample_rate = buffer.sample_rate();
+        Self::new(buffer.collect(), channels, sample_rate)
+    }
+}
+
+/// Destination the playback worker appends mixed audio to.
+///
+/// Implementations queue appended chunks and play them in order. Methods take
+/// `&self` because the sink is shared between the control thread and the
+/// playback worker; implementations use interior mutability.
+pub trait OutputSink: Send {
+    /// Queue `chunk` after any chunks already queued.
+    fn append(&self, chunk: OutputChunk);
+    /// Start or resume playback of queued audio.
+    fn play(&self);
+    /// Pause playback, keeping queued audio.
+    fn pause(&self);
+    /// Return `true` while playback is paused.
+    fn is_paused(&self) -> bool;
+    /// Current linear output gain.
+    fn volume(&self) -> f32;
+    /// Set the linear output gain.
+    fn set_volume(&self, volume: f32);
+    /// Number of queued chunks, including the one currently playing.
+    fn len(&self) -> usize;
+    /// Return `true` when no audio is queued.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Drop all queued audio.
+    fn clear(&self);
+    /// Stop playback and drop queued audio; the sink may not be reused.
+    fn stop(&self);
+    /// Playback position since the sink was created or last cleared.
+    fn position(&self) -> Duration;
+}
+
+impl OutputSink for Sink {
+    fn append(&self, chunk: OutputChunk) {
+        Sink::append(
+            self,
+            SamplesBuffer::new(chunk.channels, chunk.sample_rate, chunk.samples),
+        );
+    }
+
+    fn play(&self) {
+        Sink::play(self);
+    }
+
+    fn pause(&self) {
+        Sink::pause(self);
+    }
+
+    fn is_paused(&self) -> bool {
+        Sink::is_paused(self)
+    }
+
+    fn volume(&self) -> f32 {
+        Sink::volume(self)
+    }
+
+    fn set_volume(&self, volume: f32) {
+        Sink::set_volume(self, volume);
+    }
+
+    fn len(&self) -> usize {
+        Sink::len(self)
+    }
+
+    fn is_empty(&self) -> bool {
+        Sink::empty(self)
+    }
+
+    fn clear(&self) {
+        Sink::clear(self);
+    }
+
+    fn stop(&self) {
+        Sink::stop(self);
+    }
+
+    fn position(&self) -> Duration {
+        Sink::get_pos(self)
+    }
+}
+
+/// Errors reported by an [`OutputBackend`] when opening a sink.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputBackendError {
+    /// The backend's server or device is not reachable.
+    Unavailable(String),
+    /// The backend cannot play the requested format.
+    UnsupportedFormat {
+        /// Requested channel count.
+        channels: u16,
+        /// Requested sample rate.
+        sample_rate: u32,
+    },
+    /// Any other backend failure.
+    Other(String),
+}
+
+impl fmt::Display for OutputBackendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable(msg) => write!(f, "output backend unavailable: {}", msg),
+            Self::UnsupportedFormat {
+                channels,
+                sample_rate,
+            } => write!(
+                f,
+                "output backend cannot play {} channels at {} Hz",
+                channels, sample_rate
+            ),
+            Self::Other(msg) => write!(f, "output backend error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for OutputBackendError {}
+
+/// Factory for sinks that replace the default rodio device output.
+///
+/// The player opens one sink per playback run, in the content's channel
+/// count and sample rate.
+pub trait OutputBackend: Send + Sync {
+    /// Open a sink for `channels` interleaved channels at `sample_rate`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutputBackendError`] when the output cannot be opened.
+    fn open_sink(
+        &self,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Box<dyn OutputSink>, OutputBackendError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunk_reports_frames_and_duration() {
+        let chunk = OutputChunk::new(vec![0.0; 960], 2, 48_000);
+        assert_eq!(chunk.frames(), 480);
+        assert!((chunk.duration_s() - 0.01).abs() < 1e-12);
+    }
+
+    #[test]
+    fn rodio_sink_queues_chunks_through_trait() {
+        let (sink, _queue) = Sink::new();
+        let sink: Box<dyn OutputSink> = Box::new(sink);
+        sink.pause();
+        sink.append(OutputChunk::new(vec![0.0; 64], 2, 48_000));
+        assert_eq!(sink.len(), 1);
+        assert!(sink.is_paused());
+        sink.set_volume(0.5);
+        assert_eq!(sink.volume(), 0.5);
+    }
+}
@@ -0,0 +1,402 @@
+//! Raw PCM output backend for streaming pipelines.
+//!
+//! [`PcmBackend`] writes the mixed output as headerless interleaved PCM to
+//! stdout or a file path (typically a named pipe), so another process such
+//! as ffmpeg or an icecast source client can consume it. A writer thread
+//! paces output to real time at the configured rate, and the destination is
+//! opened once and kept across seeks so readers see one continuous stream.
+
+use std::collections::VecDeque;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::error;
+
+use crate::dsp::resample::{ResampleQuality, Resampler};
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::output_sink::{OutputBackend, OutputBackendError, OutputChunk, OutputSink};
+
+/// Longest the writer thread sleeps before re-checking for shutdown.
+const IDLE_WAIT: Duration = Duration::from_millis(50);
+
+/// Sample encoding written by [`PcmBackend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PcmFormat {
+    /// 32-bit little-endian float (`f32le` in ffmpeg).
+    #[default]
+    F32Le,
+    /// 16-bit little-endian signed integer (`s16le` in ffmpeg).
+    S16Le,
+}
+
+impl PcmFormat {
+    /// Bytes per sample.
+    pub fn sample_bytes(&self) -> usize {
+        match self {
+            Self::F32Le => 4,
+            Self::S16Le => 2,
+        }
+    }
+
+    pub(crate) fn encode(&self, samples: &[f32], out: &mut Vec<u8>) {
+        out.reserve(samples.len() * self.sample_bytes());
+        for &value in samples {
+            match self {
+                Self::F32Le => out.extend_from_slice(&value.to_le_bytes()),
+                Self::S16Le => {
+                    let scaled = (value.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16;
+                    out.extend_from_slice(&scaled.to_le_bytes());
+                }
+            }
+        }
+    }
+}
+
+/// Destination of a [`PcmBackend`] stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PcmTarget {
+    /// Standard output of the current process.
+    Stdout,
+    /// File or named pipe, opened for writing on first use.
+    Path(PathBuf),
+}
+
+type SharedWriter = Arc<Mutex<Option<Box<dyn Write + Send>>>>;
+
+/// Output backend that streams raw PCM to stdout or a pipe.
+#[derive(Clone)]
+pub struct PcmBackend {
+    target: PcmTarget,
+    format: PcmFormat,
+    sample_rate: Option<u32>,
+    writer: SharedWriter,
+}
+
+impl PcmBackend {
+    /// Stream `format` samples to `target` at the content's sample rate.
+    pub fn new(target: PcmTarget, format: PcmFormat) -> Self {
+        Self {
+            target,
+            format,
+            sample_rate: None,
+            writer: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Resample the stream to a fixed `sample_rate` regardless of content.
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    /// Sample encoding of the stream.
+    pub fn format(&self) -> PcmFormat {
+        self.format
+    }
+
+    /// Destination of the stream.
+    pub fn target(&self) -> &PcmTarget {
+        &self.target
+    }
+
+    fn open_writer(&self) -> Result<(), OutputBackendError> {
+        let mut writer = lock_writer(&self.writer);
+        if writer.is_some() {
+            return Ok(());
+        }
+        *writer = Some(match &self.target {
+            PcmTarget::Stdout => Box::new(io::stdout()),
+            PcmTarget::Path(path) => Box::new(
+                OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .truncate(true)
+                    .open(path)
+                    .map_err(|err| {
+                        OutputBackendError::Unavailable(format!("{}: {}", path.display(), err))
+                    })?,
+            ),
+        });
+        Ok(())
+    }
+}
+
+impl OutputBackend for PcmBackend {
+    fn open_sink(
+        &self,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Box<dyn OutputSink>, OutputBackendError> {
+        if channels == 0 || sample_rate == 0 {
+            return Err(OutputBackendError::UnsupportedFormat {
+                channels,
+                sample_rate,
+            });
+        }
+        self.open_writer()?;
+        let writer = self.writer.clone();
+        let format = self.format;
+        let mut bytes = Vec::new();
+        open_paced_sink(
+            channels,
+            sample_rate,
+            self.sample_rate.unwrap_or(sample_rate),
+            Box::new(move |samples| {
+                bytes.clear();
+                format.encode(samples, &mut bytes);
+                write_shared(&writer, &bytes)
+            }),
+        )
+    }
+}
+
+/// Consumer of paced, volume-scaled interleaved samples.
+pub(crate) type PcmConsumer = Box<dyn FnMut(&[f32]) -> io::Result<()> + Send>;
+
+/// Open a sink that hands queued audio to `consumer` at real-time pace.
+///
+/// Content at `sample_rate` is converted to `output_rate` on append. The
+/// consumer runs on a dedicated writer thread; an error from it is logged
+/// and ends the sink's stream.
+pub(crate) fn open_paced_sink(
+    channels: u16,
+    sample_rate: u32,
+    output_rate: u32,
+    consumer: PcmConsumer,
+) -> Result<Box<dyn OutputSink>, OutputBackendError> {
+    let resampler = (output_rate != sample_rate).then(|| {
+        Resampler::new(
+            ResampleQuality::SincMedium,
+            sample_rate,
+            output_rate,
+            channels as usize,
+        )
+    });
+    let shared = Arc::new(PcmShared {
+        queue: Mutex::new(PcmQueue {
+            volume: 1.0,
+            ..PcmQueue::default()
+        }),
+        wake: Condvar::new(),
+        stopped: AtomicBool::new(false),
+    });
+    let writer = PcmWriter {
+        shared: shared.clone(),
+        consumer,
+        frame_samples: channels as usize,
+        sample_rate: output_rate,
+    };
+    thread::Builder::new()
+        .name("proteus-pcm-writer".to_string())
+        .spawn(move || writer.run())
+        .map_err(|err| OutputBackendError::Other(err.to_string()))?;
+    Ok(Box::new(PcmSink {
+        shared,
+        resampler: Mutex::new(resampler),
+        sample_rate: output_rate,
+    }))
+}
+
+#[derive(Default)]
+struct PcmQueue {
+    chunks: VecDeque<Vec<f32>>,
+    writing: bool,
+    paused: bool,
+    volume: f32,
+    frames_written: u64,
+}
+
+struct PcmShared {
+    queue: Mutex<PcmQueue>,
+    wake: Condvar,
+    stopped: AtomicBool,
+}
+
+impl PcmShared {
+    /// Recoverable poison policy: queued output is disposable playback state.
+    fn lock_queue(&self) -> MutexGuard<'_, PcmQueue> {
+        lock_recoverable(
+            &self.queue,
+            "pcm output queue",
+            "queued output is disposable playback state",
+        )
+    }
+}
+
+/// Recoverable poison policy: a failed write already ends the stream.
+fn lock_writer(writer: &SharedWriter) -> MutexGuard<'_, Option<Box<dyn Write + Send>>> {
+    lock_recoverable(
+        writer,
+        "pcm output writer",
+        "a failed write already ends the stream",
+    )
+}
+
+fn write_shared(writer: &SharedWriter, bytes: &[u8]) -> io::Result<()> {
+    let mut writer = lock_writer(writer);
+    let Some(writer) = writer.as_mut() else {
+        return Err(io::Error::new(io::ErrorKind::NotConnected, "stream closed"));
+    };
+    writer.write_all(bytes)?;
+    writer.flush()
+}
+
+struct PcmWriter {
+    shared: Arc<PcmShared>,
+    consumer: PcmConsumer,
+    frame_samples: usize,
+    sample_rate: u32,
+}
+
+impl PcmWriter {
+    // Hand queued chunks to the consumer in order, pacing output to real time.
+    fn run(mut self) {
+        let mut clock: Option<(Instant, u64)> = None;
+        while !self.shared.stopped.load(Ordering::Acquire) {
+            let (mut chunk, volume) = {
+                let mut queue = self.shared.lock_queue();
+                queue.writing = false;
+                if queue.paused || queue.chunks.is_empty() {
+                    // Restart pacing after a gap so output does not burst.
+                    clock = None;
+                    let _ = self.shared.wake.wait_timeout(queue, IDLE_WAIT);
+                    continue;
+                }
+                queue.writing = true;
+                (queue.chunks.pop_front().unwrap_or_default(), queue.volume)
+            };
+
+            if volume != 1.0 {
+                chunk.iter_mut().for_each(|sample| *sample *= volume);
+            }
+            if let Err(err) = (self.consumer)(&chunk) {
+                error!("pcm output write failed: {}", err);
+                self.shared.stopped.store(true, Ordering::Release);
+                break;
+            }
+
+            let frames = (chunk.len() / self.frame_samples) as u64;
+            let (start, written) = clock.get_or_insert((Instant::now(), 0));
+            *written += frames;
+            self.shared.lock_queue().frames_written += frames;
+            let due = Duration::from_secs_f64(*written as f64 / self.sample_rate as f64);
+            if let Some(wait) = due.checked_sub(start.elapsed()) {
+                thread::sleep(wait);
+            }
+        }
+        self.shared.lock_queue().writing = false;
+    }
+}
+
+struct PcmSink {
+    shared: Arc<PcmShared>,
+    resampler: Mutex<Option<Resampler>>,
+    sample_rate: u32,
+}
+
+impl OutputSink for PcmSink {
+    fn append(&self, chunk: OutputChunk) {
+        let samples = match lock_recoverable(
+            &self.resampler,
+            "pcm output resampler",
+            "resampler history only affects the next few frames",
+        )
+        .as_mut()
+        {
+            Some(resampler) => {
+                let mut output = Vec::with_capacity(chunk.samples.len());
+                resampler.process(&chunk.samples, &mut output);
+                output
+            }
+            None => chunk.samples,
+        };
+        self.shared.lock_queue().chunks.push_back(samples);
+        self.shared.wake.notify_all();
+    }
+
+    fn play(&self) {
+        self.shared.lock_queue().paused = false;
+        self.shared.wake.notify_all();
+    }
+
+    fn pause(&self) {
+        self.shared.lock_queue().paused = true;
+    }
+
+    fn is_paused(&self) -> bool {
+        self.shared.lock_queue().paused
+    }
+
+    fn volume(&self) -> f32 {
+        self.shared.lock_queue().volume
+    }
+
+    fn set_volume(&self, volume: f32) {
+        self.shared.lock_queue().volume = volume;
+    }
+
+    fn len(&self) -> usize {
+        let queue = self.shared.lock_queue();
+        queue.chunks.len() + usize::from(queue.writing)
+    }
+
+    fn clear(&self) {
+        let mut queue = self.shared.lock_queue();
+        queue.chunks.clear();
+        queue.frames_written = 0;
+    }
+
+    fn stop(&self) {
+        self.clear();
+        self.shared.stopped.store(true, Ordering::Release);
+        self.shared.wake.notify_all();
+    }
+
+    fn position(&self) -> Duration {
+        let frames = self.shared.lock_queue().frames_written;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+}
+
+impl Drop for PcmSink {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        self.shared.wake.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_encode_little_endian_samples() {
+        let mut bytes = Vec::new();
+        PcmFormat::S16Le.encode(&[1.0, -2.0, 0.5], &mut bytes);
+        assert_eq!(bytes, [0xFF, 0x7F, 0x01, 0x80, 0x00, 0x40]);
+
+        bytes.clear();
+        PcmFormat::F32Le.encode(&[0.25], &mut bytes);
+        assert_eq!(bytes, 0.25_f32.to_le_bytes());
+    }
+
+    #[test]
+    fn sink_writes_queued_chunks_to_file() {
+        let path = std::env::temp_dir().join(format!("proteus-pcm-{}.raw", std::process::id()));
+        let backend = PcmBackend::new(PcmTarget::Path(path.clone()), PcmFormat::S16Le);
+        let sink = backend.open_sink(2, 48_000).unwrap();
+        sink.append(OutputChunk::new(vec![0.0; 96], 2, 48_000));
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !sink.is_empty() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        drop(sink);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 96 * 2);
+        let _ = std::fs::remove_file(path);
+    }
+}
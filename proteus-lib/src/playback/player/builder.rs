@@ -1,18 +1,28 @@
 //! Player construction helpers.
 
-use rodio::Sink;
+use rodio::{OutputStream, Sink};
+
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize};
 use std::sync::{Arc, Mutex};
 
 use super::{
-    default_output_stream_handle, Player, PlayerInitError, PlayerInitOptions, PlayerSource,
-    PlayerState, WorkerNotify, OUTPUT_METER_REFRESH_HZ,
+    default_output_stream_handle, OutputMode, Player, PlayerInitError, PlayerInitOptions,
+    PlayerSource, PlayerState, WorkerNotify, OUTPUT_METER_REFRESH_HZ,
 };
 use crate::container::info::Info;
 use crate::container::prot::{PathsTrack, Prot};
-use crate::playback::engine::{DspChainMetrics, PlaybackBufferSettings};
+use crate::diagnostics::log_capture::LogCapture;
+use crate::diagnostics::reporter::new_session_id;
+use crate::diagnostics::session::PlaySession;
+use crate::playback::engine::{
+    DecodePool, DspChainMetrics, PlaybackBufferSettings, ReaderCache, VolumeRamp,
+};
+use crate::playback::gain_staging::GainStagingRecorder;
 use crate::playback::mutex_policy::lock_invariant;
 use crate::playback::output_meter::OutputMeter;
+use crate::playback::output_sink::SharedOutputSink;
+use crate::playback::realization::RealizationRecorder;
 
 impl Player {
     /// Fallible constructor from a typed source and options.
@@ -25,11 +35,36 @@ impl Player {
         source: PlayerSource,
         options: PlayerInitOptions,
     ) -> Result<Self, PlayerInitError> {
-        let (prot, info) = load_player_source(source)?;
+        Self::try_from_source_with_output(source, options, default_output_stream_handle())
+    }
+
+    /// Fallible constructor that outputs through the given stream handle.
+    ///
+    /// Players sharing a handle mix into one device stream; see
+    /// [`crate::playback::server::AudioServer`].
+    pub(crate) fn try_from_source_with_output(
+        source: PlayerSource,
+        options: PlayerInitOptions,
+        output_stream: Arc<Mutex<Option<OutputStream>>>,
+    ) -> Result<Self, PlayerInitError> {
+        let session_id = new_session_id();
+        let log_capture = LogCapture::register(&session_id);
+        let _log_scope = log_capture.enter();
+        let single_file = matches!(source, PlayerSource::SingleFile(_));
+        let (prot, info) = load_player_source(source, &options)?;
         let sink = create_player_sink();
         let channels = info.channels as usize;
         let sample_rate = info.sample_rate;
         let effects = load_initial_effects(&prot);
+        let mut session_stats = PlaySession::new(session_id.clone());
+        let schedule = lock_invariant(
+            &prot,
+            "player prot",
+            "session statistics read the resolved shuffle schedule",
+        )
+        .get_shuffle_schedule();
+        let realization = RealizationRecorder::new(schedule.clone());
+        session_stats.record_schedule(schedule, false);
 
         let mut player = Self {
             info,
@@ -46,14 +81,29 @@ impl Player {
             play_command_ms: Arc::new(AtomicU64::new(0)),
             volume: Arc::new(Mutex::new(0.8)),
             sink,
-            output_stream: default_output_stream_handle(),
+            output_stream,
             reporter: None,
-            buffer_settings: Arc::new(Mutex::new(PlaybackBufferSettings::new(20.0))),
+            buffer_settings: Arc::new(Mutex::new(initial_buffer_settings(
+                options.output_mode,
+                sample_rate,
+            ))),
             effects,
             effect_settings_commands: Arc::new(Mutex::new(Vec::new())),
             inline_effects_update: Arc::new(Mutex::new(None)),
             inline_track_mix_updates: Arc::new(Mutex::new(Vec::new())),
+            inline_group_gains: Arc::new(Mutex::new(None)),
             dsp_metrics: Arc::new(Mutex::new(DspChainMetrics::default())),
+            track_levels: Arc::new(Mutex::new(Vec::new())),
+            effect_timings: Arc::new(Mutex::new(Vec::new())),
+            diagnostics_events: Arc::new(Mutex::new(Vec::new())),
+            session_id,
+            log_capture,
+            session_stats: Arc::new(Mutex::new(session_stats)),
+            realization: Arc::new(Mutex::new(realization)),
+            channel_map: Arc::new(Mutex::new(Vec::new())),
+            clock_sync: Arc::new(Mutex::new(None)),
+            #[cfg(feature = "link")]
+            link: Arc::new(Mutex::new(None)),
             effects_reset: Arc::new(AtomicU64::new(0)),
             output_meter: Arc::new(Mutex::new(OutputMeter::new(
                 channels,
@@ -65,11 +115,23 @@ impl Player {
             last_time_update_ms: Arc::new(AtomicU64::new(0)),
             next_resume_fade_ms: Arc::new(Mutex::new(None)),
             end_of_stream_action: Arc::new(Mutex::new(options.end_of_stream_action)),
+            output_mode: options.output_mode,
+            single_file,
+            output_backend: Arc::new(Mutex::new(None)),
             handle_count: Arc::new(AtomicUsize::new(1)),
             shutdown_once: Arc::new(AtomicBool::new(false)),
             impulse_response_override: None,
             impulse_response_tail_override: None,
             worker_notify: Arc::new(WorkerNotify::new()),
+            section_queue: Arc::new(Mutex::new(VecDeque::new())),
+            scheduled_shuffle: Arc::new(Mutex::new(None)),
+            one_shots: Arc::new(Mutex::new(Vec::new())),
+            live_input: Arc::new(Mutex::new(None)),
+            volume_ramp: Arc::new(Mutex::new(VolumeRamp::default())),
+            gain_staging: Arc::new(Mutex::new(GainStagingRecorder::default())),
+            live_capture: Arc::new(Mutex::new(None)),
+            decode_pool: DecodePool::new(options.decode_threads),
+            reader_cache: ReaderCache::default(),
         };
 
         player.initialize_thread(None);
@@ -81,7 +143,7 @@ impl Player {
     ///
     /// # Arguments
     ///
-    /// * `file_path` - Path to a `.prot`/`.mka` container file.
+    /// * `file_path` - Path to a `.prot`/`.mka` container or a single audio file.
     ///
     /// # Panics
     ///
@@ -95,7 +157,7 @@ impl Player {
     ///
     /// # Arguments
     ///
-    /// * `file_path` - Path to a `.prot`/`.mka` container file.
+    /// * `file_path` - Path to a `.prot`/`.mka` container or a single audio file.
     /// * `options` - Player initialization options.
     ///
     /// # Panics
@@ -250,11 +312,15 @@ impl Player {
     }
 }
 
-fn load_player_source(source: PlayerSource) -> Result<(Arc<Mutex<Prot>>, Info), PlayerInitError> {
+fn load_player_source(
+    source: PlayerSource,
+    options: &PlayerInitOptions,
+) -> Result<(Arc<Mutex<Prot>>, Info), PlayerInitError> {
     match source {
         PlayerSource::ContainerPath(path) => {
             let prot = Arc::new(Mutex::new(
-                Prot::try_new(&path).map_err(PlayerInitError::ProtInitialization)?,
+                Prot::try_new_with_limits(&path, options.play_settings_mode, &options.parse_limits)
+                    .map_err(PlayerInitError::ProtInitialization)?,
             ));
             let info = lock_invariant(
                 &prot,
@@ -265,6 +331,19 @@ fn load_player_source(source: PlayerSource) -> Result<(Arc<Mutex<Prot>>, Info),
             .clone();
             Ok((prot, info))
         }
+        PlayerSource::Progressive(file) => {
+            let prot =
+                Prot::try_new_progressive(&file, options.play_settings_mode, &options.parse_limits)
+                    .map_err(PlayerInitError::ProtInitialization)?;
+            let info = prot.info.clone();
+            Ok((Arc::new(Mutex::new(prot)), info))
+        }
+        PlayerSource::SingleFile(path) => {
+            let prot =
+                Prot::try_new_single_file(&path).map_err(PlayerInitError::ProtInitialization)?;
+            let info = prot.info.clone();
+            Ok((Arc::new(Mutex::new(prot)), info))
+        }
         PlayerSource::FilePaths(paths) => {
             let prot = Arc::new(Mutex::new(Prot::new_from_file_paths(paths)));
             let info = Info::new_from_file_paths(
@@ -280,9 +359,20 @@ fn load_player_source(source: PlayerSource) -> Result<(Arc<Mutex<Prot>>, Info),
     }
 }
 
-fn create_player_sink() -> Arc<Mutex<Sink>> {
+// Low-latency output modes start from the live-authoring profile sized to
+// the requested device buffer; the negotiated size refines it once opened.
+fn initial_buffer_settings(mode: OutputMode, sample_rate: u32) -> PlaybackBufferSettings {
+    let Some(buffer_frames) = mode.buffer_frames() else {
+        return PlaybackBufferSettings::new(20.0);
+    };
+    let mut settings = PlaybackBufferSettings::live_authoring();
+    settings.fit_output_latency(buffer_frames as f32 * 1000.0 / sample_rate.max(1) as f32);
+    settings
+}
+
+fn create_player_sink() -> SharedOutputSink {
     let (sink, _queue) = Sink::new();
-    Arc::new(Mutex::new(sink))
+    Arc::new(Mutex::new(Box::new(sink)))
 }
 
 fn load_initial_effects(
@@ -300,7 +390,8 @@ fn load_initial_effects(
 
 #[cfg(test)]
 mod tests {
-    use crate::container::prot::PathsTrack;
+    use crate::container::limits::ParseLimits;
+    use crate::container::prot::{PathsTrack, ProtError};
 
     use super::super::{Player, PlayerInitError, PlayerInitOptions};
 
@@ -337,4 +428,26 @@ mod tests {
         );
         assert!(matches!(result, Err(PlayerInitError::AmbiguousSource)));
     }
+
+    #[test]
+    fn parse_limits_from_options_bound_the_container() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test_audio/demo_shuffle_points.prot"
+        );
+        let options = PlayerInitOptions {
+            parse_limits: ParseLimits {
+                max_tracks: 1,
+                ..ParseLimits::default()
+            },
+            ..Default::default()
+        };
+        let result = Player::try_new_from_path_or_paths_with_options(Some(path), None, options);
+        assert!(matches!(
+            result,
+            Err(PlayerInitError::ProtInitialization(
+                ProtError::LimitExceeded(_)
+            ))
+        ));
+    }
 }
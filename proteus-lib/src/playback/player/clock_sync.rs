@@ -0,0 +1,82 @@
+//! Slaving playback to an external clock.
+
+use super::Player;
+use crate::playback::clock_sync::{
+    ClockSource, ClockSync, ClockSyncStatus, RELOCATE_THRESHOLD_SECONDS,
+};
+
+impl Player {
+    /// Slave playback to `clock`, replacing any clock already set.
+    ///
+    /// When the clock is running and more than
+    /// [`RELOCATE_THRESHOLD_SECONDS`] away, the player first seeks to it.
+    /// From then on every appended chunk is played slightly faster or slower
+    /// until the player's position matches the clock's. Varispeed needs the
+    /// device output; with an output backend installed the offset is tracked
+    /// but not corrected.
+    pub fn sync_to_clock(&mut self, clock: ClockSource) {
+        let target = clock.seconds();
+        *self.lock_clock_sync_recoverable() = Some(ClockSync::new(clock));
+        if let Some(target) = target {
+            self.relocate_if_beyond_threshold(target);
+        }
+    }
+
+    /// Stop following the external clock; playback returns to nominal speed.
+    pub fn release_clock(&self) {
+        *self.lock_clock_sync_recoverable() = None;
+    }
+
+    /// State of the active clock sync, or `None` when no clock is set.
+    pub fn clock_sync_status(&self) -> Option<ClockSyncStatus> {
+        self.lock_clock_sync_recoverable()
+            .as_ref()
+            .map(ClockSync::status)
+    }
+
+    /// Seek to the clock when it has moved too far to chase by varispeed.
+    ///
+    /// A clock that jumps (a relocate on the timecode source, or a host
+    /// seeking its sample clock) cannot be caught up with by small rate
+    /// changes. Call this from the host's control loop to follow such jumps.
+    /// Returns `true` when the player relocated.
+    pub fn chase_clock(&mut self) -> bool {
+        let target = self
+            .lock_clock_sync_recoverable()
+            .as_ref()
+            .and_then(|sync| sync.source().seconds());
+        target.is_some_and(|target| self.relocate_if_beyond_threshold(target))
+    }
+
+    fn relocate_if_beyond_threshold(&mut self, target: f64) -> bool {
+        if (target - self.get_time()).abs() <= RELOCATE_THRESHOLD_SECONDS {
+            return false;
+        }
+        self.seek(target.clamp(0.0, self.get_duration().max(0.0)));
+        if let Some(sync) = self.lock_clock_sync_recoverable().as_mut() {
+            sync.reset();
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+    use crate::playback::clock_sync::SampleClock;
+
+    #[test]
+    fn clock_sync_installs_and_releases() {
+        let mut player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        assert!(player.clock_sync_status().is_none());
+        player.sync_to_clock(ClockSource::Samples(SampleClock::new(48_000)));
+        let status = player.clock_sync_status().expect("clock is set");
+        assert_eq!(status.rate, 1.0);
+        assert!(!player.chase_clock());
+        player.release_clock();
+        assert!(player.clock_sync_status().is_none());
+    }
+}
@@ -7,19 +7,27 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
-use log::{debug, info};
+use log::{debug, info, warn};
 
 use super::lifecycle::current_ms;
-use super::{EndOfStreamAction, Player, PlayerState};
+use super::{EndOfStreamAction, Player, PlayerState, ShuffleBoundary};
+use crate::container::prot::MissingTrackPolicy;
 use crate::diagnostics::reporter::{Report, Reporter};
 
 impl Player {
     /// Start playback from a specific timestamp (seconds).
     ///
+    /// Inputs that cannot seek (see [`Self::is_seekable`]) only start from
+    /// the beginning; any other `ts` is ignored with a warning.
+    ///
     /// # Arguments
     ///
     /// * `ts` - Target start position in seconds.
     pub fn play_at(&mut self, ts: f64) {
+        if ts > 0.0 && !self.is_seekable() {
+            warn!("play_at({:.3}) ignored: input is not seekable", ts);
+            return;
+        }
         let trace_ms = current_ms();
         self.play_command_ms
             .store(trace_ms, std::sync::atomic::Ordering::Relaxed);
@@ -84,8 +92,11 @@ impl Player {
     }
 
     /// Stop playback and reset timing state.
+    ///
+    /// Pending one-shots are dropped.
     pub fn stop(&self) {
         self.stop_and_join_playback_thread();
+        self.stop_one_shots();
         self.lock_ts_recoverable().clone_from(&0.0);
     }
 
@@ -106,12 +117,18 @@ impl Player {
     /// Seek to the given timestamp (seconds).
     ///
     /// Seeking rebuilds the playback runtime at `ts` and applies configured
-    /// seek fade-out/fade-in behavior when currently playing.
+    /// seek fade-out/fade-in behavior when currently playing. Inputs that
+    /// cannot seek (see [`Self::is_seekable`]) ignore the call with a warning
+    /// and keep playing.
     ///
     /// # Arguments
     ///
     /// * `ts` - New playback position in seconds.
     pub fn seek(&mut self, ts: f64) {
+        if !self.is_seekable() {
+            warn!("seek to {:.3}s ignored: input is not seekable", ts);
+            return;
+        }
         let mut timestamp = self.lock_ts_recoverable();
         *timestamp = ts;
         drop(timestamp);
@@ -166,38 +183,67 @@ impl Player {
 
     /// Refresh active track selections from the underlying container.
     ///
-    /// Existing reverb overrides are re-applied and active playback is
-    /// restarted at the current timestamp.
+    /// Existing reverb overrides are re-applied. Active playback keeps its
+    /// sink and output stream and crossfades into the new selection over
+    /// `PlaybackBufferSettings::shuffle_crossfade_ms`, as
+    /// [`Player::shuffle_at_next`] does with [`ShuffleBoundary::Chunk`].
     pub fn refresh_tracks(&mut self) {
+        self.shuffle_at_next(ShuffleBoundary::Chunk);
+    }
+
+    /// Choose how missing or zero-length selections are handled.
+    ///
+    /// Selections are redrawn under the new policy and active playback
+    /// crossfades into them.
+    pub fn set_missing_track_policy(&mut self, policy: MissingTrackPolicy) {
+        self.lock_prot_invariant().set_missing_track_policy(policy);
+        self.refresh_tracks();
+    }
+
+    /// Shuffle track selections, crossfading active playback into them.
+    ///
+    /// Use [`Player::shuffle_at_next`] to wait for a beat or shuffle point.
+    pub fn shuffle(&mut self) {
+        self.refresh_tracks();
+    }
+
+    /// Set a runtime variable that steers upcoming shuffle points.
+    ///
+    /// Play-settings conditions (`shuffle_when`, `candidate_when`) read these
+    /// variables. When `name` is used by a condition, every shuffle boundary
+    /// after the current position is redrawn and active playback restarts at
+    /// the current timestamp; the selection playing right now is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Variable name referenced by conditions.
+    /// * `value` - New variable value.
+    pub fn set_variable(&mut self, name: &str, value: f64) {
+        let ts = self.get_time();
         let mut prot = self.lock_prot_invariant();
-        prot.refresh_tracks();
-        if let Some(spec) = self.impulse_response_override.clone() {
-            prot.set_impulse_response_spec(spec);
-        }
-        if let Some(tail_db) = self.impulse_response_tail_override {
-            prot.set_impulse_response_tail_db(tail_db);
+        if !prot.set_variable(name, value) {
+            return;
         }
+        prot.reschedule_after(ts);
+        let schedule = prot.get_shuffle_schedule();
         drop(prot);
+        self.lock_realization_recoverable()
+            .set_schedule(schedule.clone(), true);
+        self.lock_session_stats_recoverable()
+            .record_schedule(schedule, true);
 
-        self.request_effects_reset();
-        self.clear_inline_effects_update();
         if self.thread_finished() {
             return;
         }
-
-        let ts = self.get_time();
         self.seek(ts);
-
         if self.is_playing() {
             self.resume();
         }
-
-        self.wait_for_audio_heard(Duration::from_secs(5));
     }
 
-    /// Shuffle track selections and restart playback.
-    pub fn shuffle(&mut self) {
-        self.refresh_tracks();
+    /// Get the current value of a runtime variable.
+    pub fn get_variable(&self, name: &str) -> Option<f64> {
+        self.lock_prot_invariant().get_variable(name)
     }
 
     /// Set the playback volume (linear gain).
@@ -237,14 +283,17 @@ impl Player {
             Self::lock_reporter_invariant(reporter).stop();
         }
 
-        let reporter = Arc::new(Mutex::new(Reporter::new(
+        let reporter = Reporter::new(
             self.ts.clone(),
             self.volume.clone(),
             self.duration.clone(),
             self.state.clone(),
             reporting,
             reporting_interval,
-        )));
+        );
+        #[cfg(feature = "link")]
+        let reporter = reporter.with_link(self.link.clone());
+        let reporter = Arc::new(Mutex::new(reporter));
 
         Self::lock_reporter_invariant(&reporter).start();
 
@@ -297,6 +346,14 @@ mod tests {
         assert!(player.abort.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn refresh_tracks_without_a_running_thread_schedules_no_handoff() {
+        let mut player = lifecycle_test_player();
+        player.refresh_tracks();
+        assert_eq!(player.scheduled_shuffle(), None);
+        assert_eq!(*player.state.lock().unwrap(), PlayerState::Stopped);
+    }
+
     #[test]
     fn end_of_stream_action_round_trip() {
         let player = lifecycle_test_player();
@@ -0,0 +1,171 @@
+//! Structured diagnostics snapshots and export for the player.
+
+use std::time::Duration;
+
+use crate::diagnostics::log_capture::LogEvent;
+use crate::diagnostics::reporter::{
+    unix_time_ms, DiagnosticsExporter, DiagnosticsRecord, ExportError, Report,
+};
+use crate::diagnostics::session::PlaySession;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
+use crate::playback::gain_staging::GainStagingReport;
+use crate::playback::realization::Realization;
+
+use super::Player;
+
+impl Player {
+    /// Identifier shared by all diagnostics records from this player.
+    ///
+    /// Clones of the player share the same session ID.
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Recent `info`, `warn`, and `error` log events from this player, oldest
+    /// first.
+    ///
+    /// Covers events raised while loading the container and on the
+    /// player's playback threads, plus events from outside any player. Stays
+    /// empty unless the host installed
+    /// [`crate::diagnostics::log_capture::CaptureLogger`] or forwards records
+    /// to [`crate::diagnostics::log_capture::capture`].
+    pub fn get_recent_events(&self) -> Vec<LogEvent> {
+        self.log_capture.recent()
+    }
+
+    /// Snapshot the play statistics collected so far in this session.
+    ///
+    /// Counts cover output time, reshuffles, shuffle points crossed, how
+    /// often and how long each candidate played, and output underruns.
+    /// Serialize with [`PlaySession::to_json`].
+    pub fn get_session_stats(&self) -> PlaySession {
+        self.lock_session_stats_recoverable().clone()
+    }
+
+    /// Clear the session statistics, keeping the session ID.
+    pub fn reset_session_stats(&self) {
+        self.lock_session_stats_recoverable().reset();
+    }
+
+    /// Log which sources were sent to the output and when, so far.
+    ///
+    /// The log covers every chunk handed to the output this session,
+    /// including shuffle points, seeks, section jumps, and reshuffles with
+    /// their fades. Audio still queued in the output counts as played.
+    /// Serialize with [`Realization::to_json`] or [`Realization::to_edl`].
+    pub fn export_realization(&self) -> Realization {
+        Realization {
+            container: self.lock_prot_invariant().get_container_path(),
+            sample_rate: self.info.sample_rate,
+            channels: self.info.channels,
+            spans: self.lock_realization_recoverable().spans(),
+        }
+    }
+
+    /// Record per-stage levels over a rolling `window`, or stop with `None`.
+    ///
+    /// While enabled, the mix thread measures peak and RMS levels for every
+    /// track after gain/pan, the summed mix, each effect's output, and the
+    /// master output. Changing the window discards recorded history. Effect
+    /// stages are not recorded while an inline effect-chain swap crossfades.
+    pub fn set_gain_staging_window(&self, window: Option<Duration>) {
+        self.lock_gain_staging_recoverable().set_window(window);
+    }
+
+    /// Summarize stage levels recorded over the current window.
+    ///
+    /// Returns an empty report while gain staging is disabled; see
+    /// [`Player::set_gain_staging_window`].
+    pub fn get_gain_staging_report(&self) -> GainStagingReport {
+        self.lock_gain_staging_recoverable().report()
+    }
+
+    /// Drain diagnostics events raised by the playback watchdog since the last call.
+    ///
+    /// Events flag sustained silence, frozen buffers, stalled input, or late
+    /// sink appends while playing, along with the stage suspected of causing
+    /// them. See [`Player::set_silence_watchdog_ms`] for the threshold.
+    pub fn take_diagnostics_events(&self) -> Vec<DiagnosticsEvent> {
+        std::mem::take(&mut *self.lock_diagnostics_events_recoverable())
+    }
+
+    /// Capture a structured diagnostics snapshot without draining pending events.
+    pub fn diagnostics_record(&self) -> DiagnosticsRecord {
+        let events = self.lock_diagnostics_events_recoverable().clone();
+        self.build_diagnostics_record(events)
+    }
+
+    /// Capture a snapshot, drain pending watchdog events into it, and export it.
+    ///
+    /// Events are drained even if the export fails, so a broken exporter does
+    /// not grow the pending queue.
+    pub fn export_diagnostics(
+        &self,
+        exporter: &mut dyn DiagnosticsExporter,
+    ) -> Result<(), ExportError> {
+        let record = self.build_diagnostics_record(self.take_diagnostics_events());
+        exporter.export(&record)
+    }
+
+    fn build_diagnostics_record(&self, events: Vec<DiagnosticsEvent>) -> DiagnosticsRecord {
+        let container_id = {
+            let prot = self.lock_prot_invariant();
+            prot.get_container_path().or_else(|| {
+                let paths = prot.get_file_paths_dictionary();
+                (!paths.is_empty()).then(|| paths.join(";"))
+            })
+        };
+        DiagnosticsRecord {
+            session_id: self.session_id.clone(),
+            container_id,
+            timestamp_ms: unix_time_ms(),
+            report: Report {
+                time: self.get_time(),
+                volume: self.get_volume(),
+                duration: self.get_duration(),
+                playing: self.is_playing(),
+                #[cfg(feature = "link")]
+                link: self.get_link_report(),
+            },
+            metrics: self.get_metrics(),
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+    use crate::diagnostics::log_capture::capture;
+
+    fn missing_file_player() -> Player {
+        Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])])
+    }
+
+    #[test]
+    fn recent_events_follow_the_player_session() {
+        let player = missing_file_player();
+        let other = missing_file_player();
+        {
+            let _scope = player.log_capture.enter();
+            capture(
+                &log::Record::builder()
+                    .level(log::Level::Warn)
+                    .args(format_args!("impulse response missing"))
+                    .build(),
+            );
+        }
+        let has_event = |player: &Player| {
+            player
+                .get_recent_events()
+                .iter()
+                .any(|event| event.message == "impulse response missing")
+        };
+        assert!(has_event(&player));
+        assert!(has_event(&player.clone()));
+        assert!(!has_event(&other));
+    }
+}
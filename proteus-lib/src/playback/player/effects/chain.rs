@@ -0,0 +1,329 @@
+//! Per-effect edits on the active chain: parameters, enable state, order.
+//!
+//! Each edit is queued for the mix thread as an [`EffectSettingsCommand`]
+//! and mirrored on the shared chain so control-path reads see it at once.
+
+use crate::{
+    dsp::effects::AudioEffect,
+    playback::engine::{EffectParameter, EffectSettingsCommand},
+};
+
+use super::Player;
+
+impl Player {
+    /// Move the effect at `from_index` so it sits at `to_index`, shifting the
+    /// effects in between.
+    ///
+    /// The reordered chain is shown to control-path reads immediately and
+    /// handed to the mixing thread through [`Self::set_effects_inline`], so
+    /// playback crossfades from the old order to the new one.
+    ///
+    /// # Returns
+    ///
+    /// `false` if either index is out of range, `true` otherwise.
+    pub fn move_effect(&self, from_index: usize, to_index: usize) -> bool {
+        let mut effects = self.lock_effects_recoverable();
+        if from_index >= effects.len() || to_index >= effects.len() {
+            return false;
+        }
+        if from_index == to_index {
+            return true;
+        }
+        let effect = effects.remove(from_index);
+        effects.insert(to_index, effect);
+        let reordered = effects.clone();
+        drop(effects);
+        self.set_effects_inline(reordered);
+        true
+    }
+
+    /// Enqueue an incremental effect settings command for the mix thread.
+    pub(super) fn push_effect_settings_command(&self, command: EffectSettingsCommand) {
+        self.lock_effect_settings_commands_recoverable()
+            .push(command);
+    }
+
+    /// Update a single parameter on the effect at `index` in the chain.
+    ///
+    /// The update is queued for the mix thread and also applied to the shared
+    /// chain so that control-path reads reflect the new value immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based index into the effect chain.
+    /// * `param` - The specific parameter value to update.
+    ///
+    /// # Returns
+    ///
+    /// `false` if `index` is out of range, `true` otherwise.
+    pub fn set_effect_parameter(&self, index: usize, param: EffectParameter) -> bool {
+        let effects = self.lock_effects_recoverable();
+        if index >= effects.len() {
+            return false;
+        }
+        drop(effects);
+        self.push_effect_settings_command(EffectSettingsCommand::SetEffectParameter {
+            effect_index: index,
+            parameter: param.clone(),
+        });
+        // Mirror the update on the shared chain for UI reads.
+        let mut effects = self.lock_effects_recoverable();
+        apply_effect_parameter_shared(&mut effects[index], param);
+        true
+    }
+
+    /// Toggle enabled/disabled for the effect at `index` in the chain.
+    ///
+    /// The update is queued for the mix thread and also applied to the shared
+    /// chain so that control-path reads reflect the new value immediately.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Zero-based index into the effect chain.
+    /// * `enabled` - New enabled state.
+    ///
+    /// # Returns
+    ///
+    /// `false` if `index` is out of range, `true` otherwise.
+    pub fn set_effect_enabled(&self, index: usize, enabled: bool) -> bool {
+        let effects = self.lock_effects_recoverable();
+        if index >= effects.len() {
+            return false;
+        }
+        drop(effects);
+        self.push_effect_settings_command(EffectSettingsCommand::SetEffectEnabled {
+            effect_index: index,
+            enabled,
+        });
+        let mut effects = self.lock_effects_recoverable();
+        effects[index].set_enabled(enabled);
+        true
+    }
+
+    /// Flip the enabled state of the effect at `index` in the chain.
+    ///
+    /// Queued for the mix thread like [`Self::set_effect_enabled`], so the
+    /// effect fades in or out instead of clicking.
+    ///
+    /// # Returns
+    ///
+    /// The new enabled state, or `None` if `index` is out of range.
+    pub fn toggle_effect(&self, index: usize) -> Option<bool> {
+        let mut effects = self.lock_effects_recoverable();
+        let effect = effects.get_mut(index)?;
+        let enabled = !effect.is_enabled();
+        effect.set_enabled(enabled);
+        drop(effects);
+        self.push_effect_settings_command(EffectSettingsCommand::SetEffectEnabled {
+            effect_index: index,
+            enabled,
+        });
+        Some(enabled)
+    }
+}
+
+fn apply_effect_parameter_shared(effect: &mut AudioEffect, param: EffectParameter) {
+    match param {
+        EffectParameter::Gain(v) => {
+            if let AudioEffect::Gain(e) = effect {
+                e.settings.gain = v;
+            }
+        }
+        EffectParameter::Pan(v) => {
+            if let AudioEffect::Pan(e) = effect {
+                e.settings.pan = v;
+            }
+        }
+        EffectParameter::ReverbMix(v) => {
+            let clamped = v.clamp(0.0, 1.0);
+            match effect {
+                AudioEffect::ConvolutionReverb(e) => e.dry_wet = clamped,
+                AudioEffect::DelayReverb(e) => e.mix = clamped,
+                AudioEffect::DiffusionReverb(e) => e.mix = clamped,
+                AudioEffect::ShimmerReverb(e) => e.mix = clamped,
+                _ => {}
+            }
+        }
+        EffectParameter::DistortionGain(v) => {
+            if let AudioEffect::Distortion(e) = effect {
+                e.settings.gain = v;
+            }
+        }
+        EffectParameter::DistortionThreshold(v) => {
+            if let AudioEffect::Distortion(e) = effect {
+                e.settings.threshold = v;
+            }
+        }
+        EffectParameter::LowPassFreqHz(v) => {
+            if let AudioEffect::LowPassFilter(e) = effect {
+                e.settings.freq_hz = v;
+            }
+        }
+        EffectParameter::LowPassQ(v) => {
+            if let AudioEffect::LowPassFilter(e) = effect {
+                e.settings.q = v;
+            }
+        }
+        EffectParameter::HighPassFreqHz(v) => {
+            if let AudioEffect::HighPassFilter(e) = effect {
+                e.settings.freq_hz = v;
+            }
+        }
+        EffectParameter::HighPassQ(v) => {
+            if let AudioEffect::HighPassFilter(e) = effect {
+                e.settings.q = v;
+            }
+        }
+        EffectParameter::CompressorThresholdDb(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.threshold_db = v;
+            }
+        }
+        EffectParameter::CompressorRatio(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.ratio = v;
+            }
+        }
+        EffectParameter::CompressorAttackMs(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.attack_ms = v;
+            }
+        }
+        EffectParameter::CompressorReleaseMs(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.release_ms = v;
+            }
+        }
+        EffectParameter::CompressorMakeupDb(v) => {
+            if let AudioEffect::Compressor(e) = effect {
+                e.settings.makeup_gain_db = v;
+            }
+        }
+        EffectParameter::LimiterThresholdDb(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.threshold_db = v;
+            }
+        }
+        EffectParameter::LimiterKneeWidthDb(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.knee_width_db = v;
+            }
+        }
+        EffectParameter::LimiterAttackMs(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.attack_ms = v;
+            }
+        }
+        EffectParameter::LimiterReleaseMs(v) => {
+            if let AudioEffect::Limiter(e) = effect {
+                e.settings.release_ms = v;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::test_player;
+    use crate::dsp::effects::{AudioEffect, DelayReverbEffect, GainEffect, PanEffect};
+    use crate::playback::engine::{EffectParameter, EffectSettingsCommand};
+
+    #[test]
+    fn set_effect_parameter_updates_gain_pan_and_reverb_mix() {
+        let player = test_player(vec![
+            AudioEffect::Gain(GainEffect::default()),
+            AudioEffect::Pan(PanEffect::default()),
+            AudioEffect::DelayReverb(DelayReverbEffect::default()),
+        ]);
+
+        assert!(player.set_effect_parameter(0, EffectParameter::Gain(1.5)));
+        assert!(player.set_effect_parameter(1, EffectParameter::Pan(0.75)));
+        assert!(player.set_effect_parameter(2, EffectParameter::ReverbMix(0.6)));
+
+        let effects = player.lock_effects_recoverable();
+        match &effects[0] {
+            AudioEffect::Gain(effect) => assert!((effect.settings.gain - 1.5).abs() < 1e-6),
+            _ => panic!("expected gain effect"),
+        }
+        match &effects[1] {
+            AudioEffect::Pan(effect) => assert!((effect.settings.pan - 0.75).abs() < 1e-6),
+            _ => panic!("expected pan effect"),
+        }
+        match &effects[2] {
+            AudioEffect::DelayReverb(effect) => assert!((effect.mix - 0.6).abs() < 1e-6),
+            _ => panic!("expected delay reverb effect"),
+        }
+        drop(effects);
+
+        let commands = player.lock_effect_settings_commands_recoverable();
+        assert!(matches!(
+            commands[0],
+            EffectSettingsCommand::SetEffectParameter {
+                effect_index: 0,
+                parameter: EffectParameter::Gain(_)
+            }
+        ));
+        assert!(matches!(
+            commands[1],
+            EffectSettingsCommand::SetEffectParameter {
+                effect_index: 1,
+                parameter: EffectParameter::Pan(_)
+            }
+        ));
+        assert!(matches!(
+            commands[2],
+            EffectSettingsCommand::SetEffectParameter {
+                effect_index: 2,
+                parameter: EffectParameter::ReverbMix(_)
+            }
+        ));
+    }
+
+    #[test]
+    fn set_effect_parameter_returns_false_for_out_of_range_index() {
+        let player = test_player(vec![AudioEffect::Gain(GainEffect::default())]);
+        assert!(!player.set_effect_parameter(3, EffectParameter::Gain(2.0)));
+    }
+
+    #[test]
+    fn toggle_effect_flips_enabled_and_queues_the_fade() {
+        let player = test_player(vec![
+            AudioEffect::Gain(GainEffect::default()),
+            AudioEffect::Pan(PanEffect::default()),
+        ]);
+        let was_enabled = player.get_effect_descriptors()[1].enabled;
+
+        assert_eq!(player.toggle_effect(1), Some(!was_enabled));
+        assert_eq!(player.get_effect_descriptors()[1].enabled, !was_enabled);
+        assert_eq!(player.toggle_effect(1), Some(was_enabled));
+        assert_eq!(player.toggle_effect(2), None);
+
+        let commands = player.lock_effect_settings_commands_recoverable();
+        assert_eq!(commands.len(), 2);
+        assert!(matches!(
+            commands[0],
+            EffectSettingsCommand::SetEffectEnabled {
+                effect_index: 1,
+                enabled,
+            } if enabled != was_enabled
+        ));
+    }
+
+    #[test]
+    fn move_effect_reorders_the_chain() {
+        let player = test_player(vec![
+            AudioEffect::Gain(GainEffect::default()),
+            AudioEffect::Pan(PanEffect::default()),
+            AudioEffect::DelayReverb(DelayReverbEffect::default()),
+        ]);
+
+        assert!(player.move_effect(0, 2));
+        assert_eq!(player.get_effect_names(), ["Pan", "DelayReverb", "Gain"]);
+        assert!(player.move_effect(2, 0));
+        assert_eq!(player.get_effect_names(), ["Gain", "Pan", "DelayReverb"]);
+        assert!(player.move_effect(1, 1));
+        assert!(!player.move_effect(0, 3));
+        assert!(!player.move_effect(3, 0));
+        assert_eq!(player.get_effect_names(), ["Gain", "Pan", "DelayReverb"]);
+    }
+}
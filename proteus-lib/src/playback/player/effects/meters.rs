@@ -0,0 +1,102 @@
+//! Output metering and DSP metrics accessors for `Player`, suitable for UI
+//! polling.
+
+use crate::{
+    diagnostics::metrics::MetricsRegistry,
+    playback::engine::{DspChainMetrics, EffectTiming},
+    playback::output_meter::CorrelationReading,
+    playback::track_meter::TrackLevels,
+};
+
+use super::Player;
+
+impl Player {
+    /// Retrieve the latest DSP chain performance metrics.
+    ///
+    /// # Returns
+    ///
+    /// A copy of the most recent metrics updated by the playback thread.
+    #[deprecated(note = "Use get_metrics instead.")]
+    pub fn get_dsp_metrics(&self) -> DspChainMetrics {
+        *self.lock_dsp_metrics_recoverable()
+    }
+
+    /// Snapshot the engine's metrics under `mix.*`, `output.*`, and
+    /// `effects.convolution.*` names.
+    ///
+    /// See [`crate::diagnostics::metrics`] for the namespaces.
+    pub fn get_metrics(&self) -> MetricsRegistry {
+        MetricsRegistry::from(&*self.lock_dsp_metrics_recoverable())
+    }
+
+    /// Retrieve processing time for each effect in the chain, in chain order.
+    ///
+    /// Timings cover the steady-state chain and restart when its effects
+    /// change; the list is empty until the mix thread processes a chunk.
+    pub fn get_dsp_metrics_per_effect(&self) -> Vec<EffectTiming> {
+        self.lock_effect_timings_recoverable().clone()
+    }
+
+    /// Retrieve the most recent per-channel peak levels.
+    pub fn get_levels(&self) -> Vec<f32> {
+        self.lock_output_meter_recoverable().levels()
+    }
+
+    /// Retrieve the most recent per-channel peak levels in dBFS.
+    pub fn get_levels_db(&self) -> Vec<f32> {
+        self.lock_output_meter_recoverable()
+            .levels()
+            .into_iter()
+            .map(linear_to_dbfs)
+            .collect()
+    }
+
+    /// Retrieve the most recent per-channel average levels.
+    pub fn get_levels_avg(&self) -> Vec<f32> {
+        self.lock_output_meter_recoverable().averages()
+    }
+
+    /// Retrieve the most recent stereo correlation and per-channel phase inversion flags.
+    ///
+    /// Requires the `output-meter` feature; otherwise the reading is always zero.
+    pub fn get_correlation(&self) -> CorrelationReading {
+        self.lock_output_meter_recoverable().correlation()
+    }
+
+    /// Retrieve the most recent per-track peak and RMS levels, indexed by slot.
+    ///
+    /// Levels are measured after track level/pan and before the tracks are
+    /// summed. The list is empty until the mix thread produces its first chunk.
+    pub fn get_track_levels(&self) -> Vec<TrackLevels> {
+        self.lock_track_levels_recoverable().clone()
+    }
+
+    /// Set the output meter refresh rate (frames per second).
+    pub fn set_output_meter_refresh_hz(&self, hz: f32) {
+        self.lock_output_meter_recoverable().set_refresh_hz(hz);
+    }
+}
+
+fn linear_to_dbfs(value: f32) -> f32 {
+    if value <= 0.0 {
+        f32::NEG_INFINITY
+    } else {
+        20.0 * value.log10()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::linear_to_dbfs;
+
+    #[test]
+    fn zero_or_negative_linear_maps_to_negative_infinity() {
+        assert!(linear_to_dbfs(0.0).is_infinite() && linear_to_dbfs(0.0).is_sign_negative());
+        assert!(linear_to_dbfs(-1.0).is_infinite() && linear_to_dbfs(-1.0).is_sign_negative());
+    }
+
+    #[test]
+    fn unity_linear_maps_to_zero_dbfs() {
+        assert_eq!(linear_to_dbfs(1.0), 0.0);
+    }
+}
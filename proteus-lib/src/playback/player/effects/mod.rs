@@ -0,0 +1,401 @@
+//! DSP-chain control accessors for `Player`.
+//!
+//! Methods in this module replace the effect chain, apply container presets,
+//! and adjust reverb and impulse-response settings. Per-effect edits live in
+//! [`chain`] and meter/metrics snapshots in [`meters`].
+
+mod chain;
+mod meters;
+
+use crate::dsp::effects::convolution_reverb::{parse_impulse_response_string, ImpulseResponseSpec};
+use crate::{
+    container::attachments::AttachmentError,
+    container::presets::read_effect_presets_with_limits,
+    dsp::effects::{normalize_legacy_effect_aliases, AudioEffect, EffectContext, EffectDescriptor},
+    playback::engine::{EffectSettingsCommand, InlineEffectsUpdate},
+};
+use std::sync::atomic::Ordering;
+
+use super::{Player, ReverbSettingsSnapshot};
+
+impl Player {
+    /// Override the impulse response used for convolution reverb.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - Parsed IR selection/configuration to persist on the player.
+    pub fn set_impulse_response_spec(&mut self, spec: ImpulseResponseSpec) {
+        self.impulse_response_override = Some(spec.clone());
+        let mut prot = self.lock_prot_invariant();
+        prot.set_impulse_response_spec(spec);
+        self.request_effects_reset();
+    }
+
+    /// Parse and apply an impulse response spec string.
+    ///
+    /// Invalid input is ignored and leaves the current override unchanged.
+    pub fn set_impulse_response_from_string(&mut self, value: &str) {
+        if let Some(spec) = parse_impulse_response_string(value) {
+            self.set_impulse_response_spec(spec);
+        }
+    }
+
+    /// Override the impulse response tail trim (dB).
+    ///
+    /// # Arguments
+    ///
+    /// * `tail_db` - Trim threshold in decibels applied to IR tails.
+    pub fn set_impulse_response_tail_db(&mut self, tail_db: f32) {
+        self.impulse_response_tail_override = Some(tail_db);
+        let mut prot = self.lock_prot_invariant();
+        prot.set_impulse_response_tail_db(tail_db);
+        self.request_effects_reset();
+    }
+
+    /// Enable or disable supported reverb effects in the active chain.
+    ///
+    /// The toggle is applied to convolution and delay-reverb instances when
+    /// present. The update is queued for the mix thread and also applied to the
+    /// shared chain so that control-path reads reflect the new value.
+    pub fn set_reverb_enabled(&self, enabled: bool) {
+        self.push_effect_settings_command(EffectSettingsCommand::SetReverbEnabled(enabled));
+        let mut effects = self.lock_effects_recoverable();
+        if let Some(effect) = effects
+            .iter_mut()
+            .find_map(|effect| effect.as_convolution_reverb_mut())
+        {
+            effect.enabled = enabled;
+        }
+        if let Some(effect) = effects
+            .iter_mut()
+            .find_map(|effect| effect.as_delay_reverb_mut())
+        {
+            effect.enabled = enabled;
+        }
+    }
+
+    /// Set the reverb wet/dry mix (clamped to `[0.0, 1.0]`).
+    ///
+    /// The value is mapped across convolution, delay, and diffusion reverb
+    /// variants when those effects are part of the chain. The update is queued
+    /// for the mix thread and also applied to the shared chain for reads.
+    pub fn set_reverb_mix(&self, dry_wet: f32) {
+        self.push_effect_settings_command(EffectSettingsCommand::SetReverbMix(dry_wet));
+        let mut effects = self.lock_effects_recoverable();
+        if let Some(effect) = effects
+            .iter_mut()
+            .find_map(|effect| effect.as_convolution_reverb_mut())
+        {
+            effect.dry_wet = dry_wet.clamp(0.0, 1.0);
+        }
+        if let Some(effect) = effects
+            .iter_mut()
+            .find_map(|effect| effect.as_delay_reverb_mut())
+        {
+            effect.mix = dry_wet.clamp(0.0, 1.0);
+        }
+        if let Some(effect) = effects
+            .iter_mut()
+            .find_map(|effect| effect.as_diffusion_reverb_mut())
+        {
+            effect.mix = dry_wet.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Retrieve the current reverb settings snapshot.
+    ///
+    /// Returns a disabled/zeroed snapshot when no known reverb effect exists.
+    pub fn get_reverb_settings(&self) -> ReverbSettingsSnapshot {
+        let effects = self.lock_effects_recoverable();
+        if let Some(effect) = effects
+            .iter()
+            .find_map(|effect| effect.as_convolution_reverb())
+        {
+            return ReverbSettingsSnapshot {
+                enabled: effect.enabled,
+                dry_wet: effect.dry_wet,
+            };
+        }
+        if let Some(effect) = effects
+            .iter()
+            .find_map(|effect| effect.as_diffusion_reverb())
+        {
+            return ReverbSettingsSnapshot {
+                enabled: effect.enabled,
+                dry_wet: effect.mix,
+            };
+        }
+        if let Some(effect) = effects.iter().find_map(|effect| effect.as_delay_reverb()) {
+            return ReverbSettingsSnapshot {
+                enabled: effect.enabled,
+                dry_wet: effect.mix,
+            };
+        }
+        ReverbSettingsSnapshot {
+            enabled: false,
+            dry_wet: 0.0,
+        }
+    }
+
+    /// Snapshot the active effect chain names.
+    ///
+    /// This is primarily intended for diagnostics and UI display.
+    pub fn get_effect_names(&self) -> Vec<String> {
+        let effects = self.lock_effects_recoverable();
+        effects
+            .iter()
+            .map(|effect| effect.display_name().to_string())
+            .collect()
+    }
+
+    /// Describe each effect in the active chain: its type, settings, latency,
+    /// and whether it is enabled but skipping itself.
+    ///
+    /// A convolution reverb with no impulse response, or one that cannot be
+    /// loaded, passes audio through untouched; its descriptor carries an
+    /// [`crate::dsp::effects::EffectBypass`] saying so.
+    pub fn get_effect_descriptors(&self) -> Vec<EffectDescriptor> {
+        let context = {
+            let prot = self.lock_prot_invariant();
+            // Descriptors do not depend on the stream format, so a player
+            // whose audio info is not known yet still gets a usable context.
+            EffectContext::new(
+                prot.info.sample_rate.max(1),
+                (prot.info.channels as usize).max(1),
+                prot.get_container_path(),
+                prot.get_impulse_response_spec(),
+                prot.get_impulse_response_tail_db().unwrap_or(-60.0),
+            )
+            .expect("sample rate and channel count were clamped to at least one")
+        };
+        let effects = self.lock_effects_recoverable();
+        effects
+            .iter()
+            .map(|effect| effect.descriptor(&context))
+            .collect()
+    }
+
+    /// Replace the active DSP effects chain.
+    ///
+    /// During playback the new chain is handed to the mixing thread as an
+    /// inline update: it crossfades in over
+    /// `PlaybackBufferSettings::inline_effects_transition_ms` while the sink
+    /// and decode pipeline keep running. Control-path reads see the new chain
+    /// immediately. When playback is stopped the chain is replaced and effect
+    /// state is reset for the next run.
+    ///
+    /// # Arguments
+    ///
+    /// * `effects` - New ordered list of effects to apply.
+    pub fn set_effects(&mut self, effects: Vec<AudioEffect>) {
+        self.set_effects_with_transition(effects, None);
+    }
+
+    /// Apply `effects` only when the loaded container provides no effects
+    /// chain of its own.
+    ///
+    /// Hosts call this after loading so containers without an `effects` list
+    /// still get a rack; [`DefaultEffectChain`](crate::dsp::effects::DefaultEffectChain)
+    /// builds the chain every frontend shares. A container's own chain,
+    /// even an empty one, always wins.
+    ///
+    /// # Returns
+    ///
+    /// `true` when `effects` was applied.
+    pub fn set_default_effects(&mut self, effects: Vec<AudioEffect>) -> bool {
+        if self.lock_prot_invariant().get_effects().is_some() {
+            return false;
+        }
+        self.set_effects(effects);
+        true
+    }
+
+    /// Replace the effects chain with a preset bundled in the container.
+    ///
+    /// Presets are `presets/<name>.json` attachments listing effects; see
+    /// [`Prot::get_effect_presets`](crate::container::prot::Prot::get_effect_presets).
+    /// The chain is applied as by [`Self::set_effects`], crossfading over
+    /// `transition_ms` instead of the configured inline transition.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(false)` when no container is loaded or it has no preset named
+    /// `name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AttachmentError`] when the container cannot be read.
+    pub fn apply_container_preset(
+        &mut self,
+        name: &str,
+        transition_ms: f32,
+    ) -> Result<bool, AttachmentError> {
+        let (container_path, limits) = {
+            let prot = self.lock_prot_invariant();
+            (prot.get_container_path(), prot.parse_limits())
+        };
+        let Some(container_path) = container_path else {
+            return Ok(false);
+        };
+        let Some(preset) = read_effect_presets_with_limits(container_path, &limits)?
+            .into_iter()
+            .find(|preset| preset.name == name)
+        else {
+            return Ok(false);
+        };
+        log::info!("applying container effect preset {:?}", preset.name);
+        self.set_effects_with_transition(preset.effects, Some(transition_ms));
+        Ok(true)
+    }
+
+    /// [`Self::set_effects`] with an optional crossfade length overriding
+    /// the configured inline transition.
+    fn set_effects_with_transition(&self, effects: Vec<AudioEffect>, transition_ms: Option<f32>) {
+        if self.thread_finished() {
+            self.clear_inline_effects_update();
+            self.replace_effects_chain(effects);
+            self.request_effects_reset();
+            return;
+        }
+
+        self.replace_effects_chain(effects);
+        let normalized = self.lock_effects_recoverable().clone();
+        match transition_ms {
+            Some(transition_ms) => self.queue_inline_effects(normalized, transition_ms),
+            None => self.set_effects_inline(normalized),
+        }
+    }
+
+    /// Replace the active DSP effects chain inline during playback.
+    ///
+    /// Unlike [`Self::set_effects`], this does not reset effect internals,
+    /// clear effect tails, or rebuild the sink. The updated chain settings are
+    /// used for future chunks processed by the mixing thread, with a short
+    /// internal crossfade to reduce boundary clicks.
+    ///
+    /// # Arguments
+    ///
+    /// * `effects` - New ordered list of effects to apply.
+    pub fn set_effects_inline(&self, effects: Vec<AudioEffect>) {
+        if self.thread_finished() {
+            self.replace_effects_chain(effects);
+            return;
+        }
+
+        let transition_ms = {
+            let settings = self.lock_buffer_settings_recoverable();
+            settings.inline_effects_transition_ms.max(0.0)
+        };
+        self.queue_inline_effects(effects, transition_ms);
+    }
+
+    /// Hand `effects` to the mixing thread, crossfading over `transition_ms`.
+    fn queue_inline_effects(&self, effects: Vec<AudioEffect>, transition_ms: f32) {
+        let mut pending = self.lock_inline_effects_update_recoverable();
+        *pending = Some(InlineEffectsUpdate::new(effects, transition_ms));
+    }
+
+    /// Bump the effects reset generation consumed by the runtime engine.
+    pub(super) fn request_effects_reset(&self) {
+        self.effects_reset.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Drop any pending inline effects transition update.
+    pub(super) fn clear_inline_effects_update(&self) {
+        let mut pending = self.lock_inline_effects_update_recoverable();
+        pending.take();
+    }
+
+    /// Replace the currently active effect vector atomically.
+    pub(super) fn replace_effects_chain(&self, effects: Vec<AudioEffect>) {
+        let mut guard = self.lock_effects_recoverable();
+        let normalized = normalize_legacy_effect_aliases(effects);
+        log::info!("updated effects chain: {} effect(s)", normalized.len());
+        *guard = normalized;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::container::prot::PathsTrack;
+    use crate::dsp::effects::{AudioEffect, DefaultEffectChain, GainEffect, PanEffect};
+    use crate::playback::player::{Player, PlayerState};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn set_effects_during_playback_queues_an_inline_update_without_a_rebuild() {
+        let mut player = test_player(vec![AudioEffect::Gain(GainEffect::default())]);
+        player.playback_thread_exists.store(true, Ordering::SeqCst);
+        let reset_before = player.effects_reset.load(Ordering::SeqCst);
+
+        player.set_effects(vec![
+            AudioEffect::Pan(PanEffect::default()),
+            AudioEffect::Gain(GainEffect::default()),
+        ]);
+
+        assert_eq!(player.get_effect_names(), ["Pan", "Gain"]);
+        assert_eq!(player.effects_reset.load(Ordering::SeqCst), reset_before);
+        let pending = player.lock_inline_effects_update_recoverable().take();
+        let update = pending.expect("set_effects should queue an inline update");
+        assert_eq!(update.effects.len(), 2);
+        assert!(update.transition_ms > 0.0);
+        player.playback_thread_exists.store(false, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn preset_transitions_override_the_inline_crossfade() {
+        let mut player = test_player(vec![AudioEffect::Gain(GainEffect::default())]);
+        assert!(!player.apply_container_preset("Warm", 250.0).unwrap());
+
+        player.playback_thread_exists.store(true, Ordering::SeqCst);
+        player
+            .set_effects_with_transition(vec![AudioEffect::Pan(PanEffect::default())], Some(250.0));
+        assert_eq!(player.get_effect_names(), ["Pan"]);
+        let update = player
+            .lock_inline_effects_update_recoverable()
+            .take()
+            .expect("the preset should queue an inline update");
+        assert_eq!(update.transition_ms, 250.0);
+        player.playback_thread_exists.store(false, Ordering::SeqCst);
+    }
+
+    #[test]
+    fn set_effects_when_stopped_replaces_the_chain_for_the_next_run() {
+        let mut player = test_player(vec![AudioEffect::Gain(GainEffect::default())]);
+        let reset_before = player.effects_reset.load(Ordering::SeqCst);
+
+        player.set_effects(vec![AudioEffect::Pan(PanEffect::default())]);
+
+        assert_eq!(player.get_effect_names(), ["Pan"]);
+        assert!(player.lock_inline_effects_update_recoverable().is_none());
+        assert_eq!(
+            player.effects_reset.load(Ordering::SeqCst),
+            reset_before + 1
+        );
+    }
+
+    #[test]
+    fn set_default_effects_applies_when_the_source_has_no_chain() {
+        let mut player = test_player(Vec::new());
+        let defaults = DefaultEffectChain::new().enabled(false).build();
+        assert!(player.set_default_effects(defaults.clone()));
+        assert_eq!(player.lock_effects_recoverable().len(), defaults.len());
+
+        player.lock_prot_invariant().effects = Some(Vec::new());
+        assert!(!player.set_default_effects(defaults));
+        assert_eq!(player.lock_effects_recoverable().len(), 15);
+    }
+
+    pub(super) fn test_player(effects: Vec<AudioEffect>) -> Player {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        player.playback_thread_exists.store(false, Ordering::SeqCst);
+        player.abort.store(true, Ordering::SeqCst);
+        *player.lock_playback_thread_handle_invariant() = None;
+        *player.lock_state_invariant() = PlayerState::Stopped;
+        *player.lock_effects_recoverable() = effects;
+        player.lock_effect_settings_commands_recoverable().clear();
+        player
+    }
+}
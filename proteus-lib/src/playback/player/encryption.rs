@@ -0,0 +1,105 @@
+//! Key provider for containers with encrypted tracks (feature `encryption`).
+
+use super::Player;
+use crate::container::encryption::{read_encryption_manifest_with_limits, EncryptionError};
+
+impl Player {
+    /// Resolve the keys of the loaded container's encrypted tracks.
+    ///
+    /// `provider` maps each key id in the container's encryption manifest to
+    /// a 32-byte key; see [`crate::container::encryption`]. It is called
+    /// once per encrypted track. The resulting keys are kept with the
+    /// player's container and handed to its decode workers and offline
+    /// renders, which decrypt packets as they are demuxed. If playback is
+    /// running it restarts at the current position to pick up the keys.
+    ///
+    /// # Returns
+    ///
+    /// `Ok(false)` when no container is loaded or none of its tracks is
+    /// encrypted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`EncryptionError`] when the manifest cannot be read or a
+    /// key cannot be resolved; the keys are left unchanged then.
+    pub fn set_key_provider(
+        &mut self,
+        provider: impl Fn(&str) -> Option<[u8; 32]>,
+    ) -> Result<bool, EncryptionError> {
+        let (container_path, limits) = {
+            let prot = self.lock_prot_invariant();
+            (prot.get_container_path(), prot.parse_limits())
+        };
+        let Some(container_path) = container_path else {
+            return Ok(false);
+        };
+        let Some(manifest) = read_encryption_manifest_with_limits(&container_path, &limits)? else {
+            return Ok(false);
+        };
+        let keys = manifest.resolve_keys(provider)?;
+        log::info!(
+            "resolved keys for {} encrypted track(s) of {}",
+            keys.len(),
+            container_path
+        );
+        self.lock_prot_invariant().set_track_keys(keys);
+        self.reader_cache.evict(&container_path);
+        if !self.thread_finished() {
+            let ts = self.get_time();
+            self.seek(ts);
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::container::encryption::tests::{fixture_frames, temp_path, write_flac_container};
+    use crate::container::encryption::{
+        encrypt_packet, wrap_key, EncryptedTrack, EncryptionManifest,
+    };
+    use crate::playback::player::Player;
+    use crate::playback::render::render_selection_to_pcm;
+
+    fn render(player: &Player) -> Vec<f32> {
+        render_selection_to_pcm(&player.lock_prot_invariant(), 1, 1.0).samples
+    }
+
+    #[test]
+    fn key_provider_decrypts_encrypted_tracks_during_decode() {
+        let flac = fixture_frames();
+        let plain_path = temp_path("plain");
+        write_flac_container(&plain_path, &flac, |_, frame| frame.to_vec(), &[]);
+        let mut plain = Player::new(&plain_path.display().to_string());
+        assert!(!plain.set_key_provider(|_| None).unwrap());
+        let expected = render(&plain);
+        assert!(expected.iter().any(|sample| sample.abs() > 1.0e-3));
+
+        let (label_key, content_key) = ([3_u8; 32], [4_u8; 32]);
+        let manifest = EncryptionManifest::new(vec![EncryptedTrack {
+            track_id: 1,
+            key_id: "label".to_string(),
+            wrapped_key: Some(wrap_key(&label_key, &content_key).unwrap()),
+        }]);
+        let path = temp_path("player");
+        write_flac_container(
+            &path,
+            &flac,
+            |ts, frame| encrypt_packet(&content_key, 1, ts, frame).unwrap(),
+            &[manifest.to_attachment()],
+        );
+        let mut player = Player::new(&path.display().to_string());
+        assert_ne!(render(&player), expected);
+        assert!(player.set_key_provider(|_| None).is_err());
+        assert!(player
+            .set_key_provider(|key_id| (key_id == "label").then_some(label_key))
+            .unwrap());
+        assert_eq!(render(&player), expected);
+        // Keys belong to the player that resolved them, not to the file.
+        let other = Player::new(&path.display().to_string());
+        assert_ne!(render(&other), expected);
+
+        std::fs::remove_file(plain_path).unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
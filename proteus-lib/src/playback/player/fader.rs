@@ -0,0 +1,116 @@
+//! Thread-safe volume and transport handle for automated fades.
+//!
+//! `Player` holds its output stream, which is not `Send` on every platform,
+//! so it cannot move to a helper thread. [`PlayerFader`] carries only the
+//! shared state a fade needs (volume, sink, transport state, worker wakeup)
+//! and can drive a player's level from any thread.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use super::notify::WorkerNotify;
+use super::{Player, PlayerState};
+use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
+use crate::playback::output_sink::{OutputSink, SharedOutputSink};
+
+/// Send-safe handle that ramps one player's volume.
+#[derive(Clone)]
+pub(crate) struct PlayerFader {
+    volume: Arc<Mutex<f32>>,
+    sink: SharedOutputSink,
+    state: Arc<Mutex<PlayerState>>,
+    worker_notify: Arc<WorkerNotify>,
+    audio_heard: Arc<AtomicBool>,
+    playback_thread_exists: Arc<AtomicBool>,
+}
+
+impl Player {
+    /// Create a fade handle sharing this player's volume and transport state.
+    pub(crate) fn fader(&self) -> PlayerFader {
+        PlayerFader {
+            volume: self.volume.clone(),
+            sink: self.sink.clone(),
+            state: self.state.clone(),
+            worker_notify: self.worker_notify.clone(),
+            audio_heard: self.audio_heard.clone(),
+            playback_thread_exists: self.playback_thread_exists.clone(),
+        }
+    }
+}
+
+impl PlayerFader {
+    /// Return `true` when both handles control the same player.
+    pub(crate) fn same_player(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.volume, &other.volume)
+    }
+
+    /// Current target volume of the player.
+    pub(crate) fn volume(&self) -> f32 {
+        *self.lock_volume()
+    }
+
+    /// Set both the target volume and the live sink volume.
+    pub(crate) fn set_volume(&self, volume: f32) {
+        *self.lock_volume() = volume;
+        self.lock_sink().set_volume(volume);
+    }
+
+    /// Set the volume restored on the next resume without touching the sink.
+    pub(crate) fn set_resume_volume(&self, volume: f32) {
+        *self.lock_volume() = volume;
+    }
+
+    /// Return `true` while a playback worker thread is alive.
+    pub(crate) fn has_playback_thread(&self) -> bool {
+        self.playback_thread_exists.load(Ordering::Acquire)
+    }
+
+    /// Return `true` once the player has appended audible output.
+    pub(crate) fn audio_heard(&self) -> bool {
+        self.audio_heard.load(Ordering::Acquire)
+    }
+
+    /// Return `true` when the player is playing or about to.
+    pub(crate) fn is_active(&self) -> bool {
+        matches!(
+            *self.lock_state(),
+            PlayerState::Playing | PlayerState::Resuming
+        )
+    }
+
+    /// Request a resume, as [`Player::resume`] does.
+    pub(crate) fn resume(&self) {
+        self.lock_state().clone_from(&PlayerState::Resuming);
+        self.worker_notify.notify();
+    }
+
+    /// Request a pause, as [`Player::pause`] does.
+    pub(crate) fn pause(&self) {
+        self.lock_state().clone_from(&PlayerState::Pausing);
+        self.worker_notify.notify();
+    }
+
+    fn lock_volume(&self) -> MutexGuard<'_, f32> {
+        lock_recoverable(
+            &self.volume,
+            "player fader volume",
+            "volume is a scalar control value that can continue from the inner value",
+        )
+    }
+
+    fn lock_sink(&self) -> MutexGuard<'_, Box<dyn OutputSink>> {
+        lock_recoverable(
+            &self.sink,
+            "player fader sink",
+            "the output sink is replaceable runtime I/O state",
+        )
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, PlayerState> {
+        lock_invariant(
+            &self.state,
+            "player fader state",
+            "transport transitions rely on a coherent state machine",
+        )
+    }
+}
@@ -13,6 +13,8 @@ impl Player {
     /// Stop the current playback thread and wait for it to exit.
     ///
     /// Internal state is moved through `Stopping` and finalized as `Stopped`.
+    /// A deferred shuffle not yet handed over is dropped; its selection
+    /// already applies to the next run.
     pub fn stop_and_join_playback_thread(&self) {
         self.lock_state_invariant()
             .clone_from(&PlayerState::Stopping);
@@ -27,6 +29,7 @@ impl Player {
             thread::sleep(Duration::from_millis(10));
         }
         self.join_playback_thread();
+        self.lock_scheduled_shuffle_recoverable().take();
 
         self.lock_state_invariant()
             .clone_from(&PlayerState::Stopped);
@@ -143,6 +146,11 @@ pub(super) fn drop_cleanup(player: &mut Player) {
         inline_track_mix_updates.shrink_to_fit();
     }
 
+    {
+        let mut inline_group_gains = player.lock_inline_group_gains_recoverable();
+        *inline_group_gains = None;
+    }
+
     {
         let mut dsp_metrics = player.lock_dsp_metrics_recoverable();
         *dsp_metrics = DspChainMetrics::default();
@@ -153,6 +161,10 @@ pub(super) fn drop_cleanup(player: &mut Player) {
         output_meter.reset();
     }
 
+    player.lock_track_levels_recoverable().clear();
+    player.lock_effect_timings_recoverable().clear();
+    player.lock_diagnostics_events_recoverable().clear();
+
     debug!("player dropped");
 
     *player.lock_duration_recoverable() = 0.0;
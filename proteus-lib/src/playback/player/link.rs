@@ -0,0 +1,61 @@
+//! Ableton Link tempo and phase sync (feature `link`).
+
+use super::Player;
+use crate::playback::link::{LinkReport, SharedLink};
+
+impl Player {
+    /// Follow a Link session, or stop following with `None`.
+    ///
+    /// While set, shuffle points quantize to the Link beat grid and queued
+    /// section changes wait for the next Link bar line. The grid is taken
+    /// when an engine starts, so the shuffle points of a running playback
+    /// follow from the next play, seek, or section jump.
+    pub fn set_link(&self, link: Option<SharedLink>) {
+        if link.is_none() {
+            self.lock_prot_invariant().set_beat_grid(None);
+        }
+        *self.lock_link_recoverable() = link;
+    }
+
+    /// Current tempo and phase of the Link session, if one is set.
+    pub fn get_link_report(&self) -> Option<LinkReport> {
+        self.lock_link_recoverable()
+            .as_deref()
+            .map(LinkReport::capture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::container::prot::PathsTrack;
+    use crate::playback::link::LinkTimeline;
+
+    struct SteadyLink;
+
+    impl LinkTimeline for SteadyLink {
+        fn tempo(&self) -> f64 {
+            128.0
+        }
+
+        fn beat(&self) -> f64 {
+            6.0
+        }
+    }
+
+    #[test]
+    fn link_report_follows_the_installed_session() {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        assert!(player.get_link_report().is_none());
+        player.set_link(Some(Arc::new(SteadyLink)));
+        let report = player.get_link_report().expect("link is set");
+        assert_eq!(report.tempo, 128.0);
+        assert_eq!(report.phase, 2.0);
+        player.set_link(None);
+        assert!(player.get_link_report().is_none());
+    }
+}
@@ -0,0 +1,393 @@
+//! Live capture input (microphone, instrument) mixed as an extra track.
+//!
+//! A capture thread owns the cpal input stream, converts each callback's
+//! frames to the container channel layout and sample rate, and pushes them
+//! into the shared [`LiveInputBus`]. The mix thread sums the bus into the
+//! track mix with the same level/pan law as container stems, so the live
+//! signal also passes through the main effect chain.
+//!
+//! Monitoring latency follows the output buffering; use
+//! [`Player::configure_for_live_authoring`] for a shallow sink queue.
+
+use std::fmt;
+use std::sync::mpsc;
+use std::thread::JoinHandle;
+
+use log::{error, warn};
+use rodio::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use rodio::cpal::{self, FromSample, SampleFormat, SizedSample, StreamConfig};
+
+use super::Player;
+use crate::dsp::effects::AudioEffect;
+use crate::playback::engine::{LiveInputBus, SharedLiveInput};
+use crate::playback::mutex_policy::lock_recoverable;
+
+/// Options for [`Player::start_live_input`].
+#[derive(Debug, Clone)]
+pub struct LiveInputConfig {
+    /// Input device name; `None` uses the host default input device.
+    pub device_name: Option<String>,
+    /// Linear gain applied to the live track.
+    pub level: f32,
+    /// Stereo position in `[-1.0, 1.0]`.
+    pub pan: f32,
+    /// Effects applied to the live track before it joins the mix.
+    pub effects: Vec<AudioEffect>,
+    /// Most captured audio (ms) held before the oldest frames are dropped.
+    pub max_latency_ms: f32,
+    /// Key main-mix ducking while the processed input peaks at or above this level (dBFS).
+    pub duck_threshold_db: Option<f32>,
+}
+
+impl Default for LiveInputConfig {
+    fn default() -> Self {
+        Self {
+            device_name: None,
+            level: 1.0,
+            pan: 0.0,
+            effects: Vec::new(),
+            max_latency_ms: 250.0,
+            duck_threshold_db: None,
+        }
+    }
+}
+
+/// Errors that can occur while starting live input capture.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LiveInputError {
+    /// The host has no default input device.
+    NoInputDevice,
+    /// No input device with the given name was found.
+    DeviceNotFound(String),
+    /// The device reported no usable input configuration.
+    Config(String),
+    /// The device uses a sample format that is not supported.
+    UnsupportedSampleFormat(String),
+    /// The input stream could not be built or started.
+    Stream(String),
+}
+
+impl fmt::Display for LiveInputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoInputDevice => write!(f, "no default input device"),
+            Self::DeviceNotFound(name) => write!(f, "input device not found: {}", name),
+            Self::Config(msg) => write!(f, "input device config unavailable: {}", msg),
+            Self::UnsupportedSampleFormat(format) => {
+                write!(f, "unsupported input sample format: {}", format)
+            }
+            Self::Stream(msg) => write!(f, "failed to start input stream: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for LiveInputError {}
+
+/// Running capture thread; dropping it stops the input stream.
+#[derive(Debug)]
+pub(super) struct LiveCapture {
+    stop: mpsc::Sender<()>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for LiveCapture {
+    fn drop(&mut self) {
+        let _ = self.stop.send(());
+        if let Some(handle) = self.handle.take() {
+            if handle.join().is_err() {
+                warn!("live input capture thread panicked during join");
+            }
+        }
+    }
+}
+
+impl Player {
+    /// Start capturing an input device and mix it as a live track.
+    ///
+    /// Any running capture is stopped first. The live track plays whenever
+    /// playback runs; captured audio is dropped while playback is paused.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`LiveInputError`] when no matching device exists or the
+    /// input stream cannot be opened.
+    pub fn start_live_input(&self, config: LiveInputConfig) -> Result<(), LiveInputError> {
+        self.stop_live_input();
+        let (channels, sample_rate, pan_law) = {
+            let prot = self.lock_prot_invariant();
+            (
+                prot.info.channels.max(1) as usize,
+                prot.info.sample_rate,
+                self.effective_pan_law_for(&prot),
+            )
+        };
+        let capacity_frames =
+            (config.max_latency_ms.max(1.0) / 1000.0 * sample_rate as f32) as usize;
+        let mut bus =
+            LiveInputBus::new(channels, capacity_frames, config.level, config.pan, pan_law);
+        bus.set_effects(config.effects.clone());
+        bus.set_duck_threshold(config.duck_threshold_db.map(db_to_linear));
+        *self.lock_live_input_recoverable() = Some(bus);
+
+        match spawn_capture(
+            self.live_input.clone(),
+            config.device_name,
+            channels,
+            sample_rate,
+        ) {
+            Ok(capture) => {
+                *self.lock_live_capture_recoverable() = Some(capture);
+                Ok(())
+            }
+            Err(err) => {
+                *self.lock_live_input_recoverable() = None;
+                Err(err)
+            }
+        }
+    }
+
+    /// Stop live input capture and remove the live track from the mix.
+    pub fn stop_live_input(&self) {
+        let capture = self.lock_live_capture_recoverable().take();
+        drop(capture);
+        *self.lock_live_input_recoverable() = None;
+    }
+
+    /// Return `true` while live input capture is running.
+    pub fn is_live_input_active(&self) -> bool {
+        self.lock_live_capture_recoverable().is_some()
+    }
+
+    /// Number of captured samples dropped because the mix was not pulling them.
+    ///
+    /// Captured audio piles up and is dropped while playback is paused or
+    /// stalled. Returns `0` when capture is not running.
+    pub fn live_input_dropped_samples(&self) -> u64 {
+        self.lock_live_input_recoverable()
+            .as_ref()
+            .map_or(0, |bus| bus.dropped_samples())
+    }
+
+    /// Update the live track level and pan.
+    pub fn set_live_input_mix(&self, level: f32, pan: f32) {
+        if let Some(bus) = self.lock_live_input_recoverable().as_mut() {
+            bus.set_mix(level, pan);
+        }
+    }
+
+    /// Replace the live track effect chain.
+    pub fn set_live_input_effects(&self, effects: Vec<AudioEffect>) {
+        if let Some(bus) = self.lock_live_input_recoverable().as_mut() {
+            bus.set_effects(effects);
+        }
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+/// Spawn the thread owning the input stream and wait until it is running.
+///
+/// cpal streams are not `Send` on every platform, so the stream is created,
+/// played, and dropped on the same dedicated thread.
+fn spawn_capture(
+    bus: SharedLiveInput,
+    device_name: Option<String>,
+    channels: usize,
+    sample_rate: u32,
+) -> Result<LiveCapture, LiveInputError> {
+    let (stop_tx, stop_rx) = mpsc::channel::<()>();
+    let (ready_tx, ready_rx) = mpsc::channel::<Result<(), LiveInputError>>();
+    let handle = std::thread::spawn(move || {
+        let stream = match open_input_stream(bus, device_name.as_deref(), channels, sample_rate) {
+            Ok(stream) => stream,
+            Err(err) => {
+                let _ = ready_tx.send(Err(err));
+                return;
+            }
+        };
+        let _ = ready_tx.send(Ok(()));
+        let _ = stop_rx.recv();
+        drop(stream);
+    });
+    let capture = LiveCapture {
+        stop: stop_tx,
+        handle: Some(handle),
+    };
+    match ready_rx.recv() {
+        Ok(Ok(())) => Ok(capture),
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err(LiveInputError::Stream(
+            "capture thread exited during startup".to_string(),
+        )),
+    }
+}
+
+fn open_input_stream(
+    bus: SharedLiveInput,
+    device_name: Option<&str>,
+    channels: usize,
+    sample_rate: u32,
+) -> Result<cpal::Stream, LiveInputError> {
+    let host = cpal::default_host();
+    let device = match device_name {
+        Some(name) => host
+            .input_devices()
+            .map_err(|err| LiveInputError::Config(err.to_string()))?
+            .find(|device| device.name().is_ok_and(|device_name| device_name == name))
+            .ok_or_else(|| LiveInputError::DeviceNotFound(name.to_string()))?,
+        None => host
+            .default_input_device()
+            .ok_or(LiveInputError::NoInputDevice)?,
+    };
+    let supported = device
+        .default_input_config()
+        .map_err(|err| LiveInputError::Config(err.to_string()))?;
+    let sample_format = supported.sample_format();
+    let config: StreamConfig = supported.into();
+    let converter = CaptureConverter::new(
+        usize::from(config.channels),
+        config.sample_rate.0,
+        channels,
+        sample_rate,
+    );
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, bus, converter),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, bus, converter),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, bus, converter),
+        SampleFormat::I32 => build_stream::<i32>(&device, &config, bus, converter),
+        other => return Err(LiveInputError::UnsupportedSampleFormat(other.to_string())),
+    }?;
+    stream
+        .play()
+        .map_err(|err| LiveInputError::Stream(err.to_string()))?;
+    Ok(stream)
+}
+
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    bus: SharedLiveInput,
+    mut converter: CaptureConverter,
+) -> Result<cpal::Stream, LiveInputError>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let mut converted = Vec::new();
+    device
+        .build_input_stream::<T, _, _>(
+            config,
+            move |data: &[T], _| {
+                converted.clear();
+                converter.process(data.iter().map(|sample| sample.to_sample()), &mut converted);
+                let mut bus = lock_recoverable(
+                    &bus,
+                    "live input capture bus",
+                    "captured live input is disposable streaming audio",
+                );
+                if let Some(bus) = bus.as_mut() {
+                    bus.push_captured(&converted);
+                }
+            },
+            |err| error!("live input stream error: {}", err),
+            None,
+        )
+        .map_err(|err| LiveInputError::Stream(err.to_string()))
+}
+
+/// Streaming channel-map and linear sample-rate conversion for capture frames.
+struct CaptureConverter {
+    input_channels: usize,
+    output_channels: usize,
+    step: f64,
+    position: f64,
+    previous: Vec<f32>,
+    current: Vec<f32>,
+    frame: Vec<f32>,
+}
+
+impl CaptureConverter {
+    fn new(
+        input_channels: usize,
+        input_rate: u32,
+        output_channels: usize,
+        output_rate: u32,
+    ) -> Self {
+        let output_channels = output_channels.max(1);
+        Self {
+            input_channels: input_channels.max(1),
+            output_channels,
+            step: f64::from(input_rate.max(1)) / f64::from(output_rate.max(1)),
+            position: 0.0,
+            previous: vec![0.0; output_channels],
+            current: vec![0.0; output_channels],
+            frame: Vec::new(),
+        }
+    }
+
+    /// Convert interleaved input samples and append them to `output`.
+    fn process(&mut self, input: impl Iterator<Item = f32>, output: &mut Vec<f32>) {
+        for sample in input {
+            self.frame.push(sample);
+            if self.frame.len() < self.input_channels {
+                continue;
+            }
+            self.map_frame();
+            self.frame.clear();
+            while self.position < 1.0 {
+                let fraction = self.position as f32;
+                output.extend(
+                    self.previous
+                        .iter()
+                        .zip(&self.current)
+                        .map(|(a, b)| a + (b - a) * fraction),
+                );
+                self.position += self.step;
+            }
+            self.position -= 1.0;
+            std::mem::swap(&mut self.previous, &mut self.current);
+        }
+    }
+
+    /// Map the buffered input frame onto `current` in the output layout.
+    ///
+    /// Mono input feeds every output channel; otherwise channels map by index
+    /// and a mono output averages all inputs.
+    fn map_frame(&mut self) {
+        if self.output_channels == 1 {
+            self.current[0] = self.frame.iter().sum::<f32>() / self.input_channels as f32;
+            return;
+        }
+        for (channel, out) in self.current.iter_mut().enumerate() {
+            *out = if self.input_channels == 1 {
+                self.frame[0]
+            } else {
+                self.frame.get(channel).copied().unwrap_or(0.0)
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CaptureConverter;
+
+    #[test]
+    fn converter_duplicates_mono_input_at_equal_rates() {
+        let mut converter = CaptureConverter::new(1, 48_000, 2, 48_000);
+        let mut output = Vec::new();
+        converter.process([0.5, 1.0].into_iter(), &mut output);
+        // One frame of latency from the interpolation history.
+        assert_eq!(output, vec![0.0, 0.0, 0.5, 0.5]);
+    }
+
+    #[test]
+    fn converter_upsamples_by_rate_ratio() {
+        let mut converter = CaptureConverter::new(2, 24_000, 2, 48_000);
+        let mut output = Vec::new();
+        converter.process(std::iter::repeat_n(0.25, 2 * 100), &mut output);
+        assert_eq!(output.len(), 2 * 200);
+    }
+}
@@ -1,21 +1,174 @@
 //! Centralized poison-policy accessors for critical `Player` mutexes.
 
-use std::sync::MutexGuard;
+use std::collections::VecDeque;
+use std::sync::{Arc, MutexGuard};
 
-use rodio::{OutputStream, Sink};
+use rodio::OutputStream;
 
-use super::{EndOfStreamAction, Player, PlayerState};
+use super::live_input::LiveCapture;
+use super::{EndOfStreamAction, Player, PlayerState, ShuffleBoundary};
 use crate::container::prot::Prot;
 use crate::diagnostics::reporter::Reporter;
+use crate::diagnostics::session::PlaySession;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
 use crate::dsp::effects::AudioEffect;
+use crate::playback::clock_sync::ClockSync;
 use crate::playback::engine::{
-    DspChainMetrics, EffectSettingsCommand, InlineEffectsUpdate, InlineTrackMixUpdate,
-    PlaybackBufferSettings,
+    DspChainMetrics, EffectSettingsCommand, EffectTiming, InlineEffectsUpdate,
+    InlineTrackMixUpdate, LiveInputBus, OneShotVoice, PlaybackBufferSettings, VolumeRamp,
 };
+use crate::playback::gain_staging::GainStagingRecorder;
 use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
 use crate::playback::output_meter::OutputMeter;
+use crate::playback::output_sink::{OutputBackend, OutputSink};
+use crate::playback::realization::RealizationRecorder;
+use crate::playback::track_meter::TrackLevels;
 
 impl Player {
+    /// Recoverable poison policy: queued section names are a plain request list.
+    pub(in crate::playback::player) fn lock_section_queue_recoverable(
+        &self,
+    ) -> MutexGuard<'_, VecDeque<String>> {
+        lock_recoverable(
+            &self.section_queue,
+            "player section queue",
+            "queued section names are a plain request list",
+        )
+    }
+
+    /// Recoverable poison policy: a scheduled shuffle is a plain request slot.
+    pub(in crate::playback::player) fn lock_scheduled_shuffle_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<ShuffleBoundary>> {
+        lock_recoverable(
+            &self.scheduled_shuffle,
+            "player scheduled shuffle",
+            "a scheduled shuffle is a plain request slot",
+        )
+    }
+
+    /// Recoverable poison policy: queued one-shots are disposable transient voices.
+    pub(in crate::playback::player) fn lock_one_shots_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<OneShotVoice>> {
+        lock_recoverable(
+            &self.one_shots,
+            "player one-shots",
+            "queued one-shots are disposable transient voices",
+        )
+    }
+
+    /// Recoverable poison policy: session statistics are additive telemetry.
+    pub(in crate::playback::player) fn lock_session_stats_recoverable(
+        &self,
+    ) -> MutexGuard<'_, PlaySession> {
+        lock_recoverable(
+            &self.session_stats,
+            "player session stats",
+            "session statistics are additive telemetry",
+        )
+    }
+
+    /// Recoverable poison policy: the realization log is append-only telemetry.
+    pub(in crate::playback::player) fn lock_realization_recoverable(
+        &self,
+    ) -> MutexGuard<'_, RealizationRecorder> {
+        lock_recoverable(
+            &self.realization,
+            "player realization",
+            "the realization log is append-only telemetry",
+        )
+    }
+
+    /// Recoverable poison policy: the backend handle is replaced wholesale.
+    pub(in crate::playback::player) fn lock_output_backend_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<Arc<dyn OutputBackend>>> {
+        lock_recoverable(
+            &self.output_backend,
+            "player output backend",
+            "the backend handle is replaced wholesale",
+        )
+    }
+
+    /// Recoverable poison policy: the channel map is replaced wholesale on update.
+    pub(in crate::playback::player) fn lock_channel_map_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<usize>> {
+        lock_recoverable(
+            &self.channel_map,
+            "player channel map",
+            "the channel map is replaced wholesale on update",
+        )
+    }
+
+    /// Recoverable poison policy: the clock controller only smooths readings.
+    pub(in crate::playback::player) fn lock_clock_sync_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<ClockSync>> {
+        lock_recoverable(
+            &self.clock_sync,
+            "player clock sync",
+            "the clock controller only smooths readings",
+        )
+    }
+
+    /// Recoverable poison policy: the Link handle is replaced wholesale.
+    #[cfg(feature = "link")]
+    pub(in crate::playback::player) fn lock_link_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<crate::playback::link::SharedLink>> {
+        lock_recoverable(
+            &self.link,
+            "player link",
+            "the Link handle is replaced wholesale",
+        )
+    }
+
+    /// Recoverable poison policy: gain staging history is disposable diagnostics.
+    pub(in crate::playback::player) fn lock_gain_staging_recoverable(
+        &self,
+    ) -> MutexGuard<'_, GainStagingRecorder> {
+        lock_recoverable(
+            &self.gain_staging,
+            "player gain staging",
+            "gain staging history is disposable diagnostics",
+        )
+    }
+
+    /// Recoverable poison policy: volume automation is a scalar control ramp.
+    pub(in crate::playback::player) fn lock_volume_ramp_recoverable(
+        &self,
+    ) -> MutexGuard<'_, VolumeRamp> {
+        lock_recoverable(
+            &self.volume_ramp,
+            "player volume ramp",
+            "volume automation is a scalar control ramp",
+        )
+    }
+
+    /// Recoverable poison policy: captured live input is disposable streaming audio.
+    pub(in crate::playback::player) fn lock_live_input_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<LiveInputBus>> {
+        lock_recoverable(
+            &self.live_input,
+            "player live input",
+            "captured live input is disposable streaming audio",
+        )
+    }
+
+    /// Recoverable poison policy: the capture handle only owns a stop channel and thread.
+    pub(in crate::playback::player) fn lock_live_capture_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<LiveCapture>> {
+        lock_recoverable(
+            &self.live_capture,
+            "player live capture",
+            "the capture handle only owns a stop channel and thread",
+        )
+    }
+
     /// Recoverable poison policy: playback position is telemetry and can resume from the inner value.
     pub(in crate::playback::player) fn lock_ts_recoverable(&self) -> MutexGuard<'_, f64> {
         lock_recoverable(
@@ -73,7 +226,9 @@ impl Player {
     }
 
     /// Recoverable poison policy: the sink is disposable output state and should not cascade failures.
-    pub(in crate::playback::player) fn lock_sink_recoverable(&self) -> MutexGuard<'_, Sink> {
+    pub(in crate::playback::player) fn lock_sink_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Box<dyn OutputSink>> {
         lock_recoverable(
             &self.sink,
             "player sink",
@@ -158,6 +313,17 @@ impl Player {
         )
     }
 
+    /// Recoverable poison policy: pending group gains are a disposable snapshot.
+    pub(in crate::playback::player) fn lock_inline_group_gains_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Option<Vec<f32>>> {
+        lock_recoverable(
+            &self.inline_group_gains,
+            "player inline group gains",
+            "pending group gains are a disposable snapshot",
+        )
+    }
+
     /// Recoverable poison policy: DSP metrics are derived telemetry.
     pub(in crate::playback::player) fn lock_dsp_metrics_recoverable(
         &self,
@@ -169,6 +335,39 @@ impl Player {
         )
     }
 
+    /// Recoverable poison policy: track levels are derived telemetry.
+    pub(in crate::playback::player) fn lock_track_levels_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<TrackLevels>> {
+        lock_recoverable(
+            &self.track_levels,
+            "player track levels",
+            "track levels are derived telemetry that can be rebuilt",
+        )
+    }
+
+    /// Recoverable poison policy: effect timings are derived telemetry.
+    pub(in crate::playback::player) fn lock_effect_timings_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<EffectTiming>> {
+        lock_recoverable(
+            &self.effect_timings,
+            "player effect timings",
+            "effect timings are derived telemetry that can be rebuilt",
+        )
+    }
+
+    /// Recoverable poison policy: diagnostics events are a disposable queue.
+    pub(in crate::playback::player) fn lock_diagnostics_events_recoverable(
+        &self,
+    ) -> MutexGuard<'_, Vec<DiagnosticsEvent>> {
+        lock_recoverable(
+            &self.diagnostics_events,
+            "player diagnostics events",
+            "diagnostics events are a disposable queue",
+        )
+    }
+
     /// Recoverable poison policy: the output meter is derived telemetry.
     pub(in crate::playback::player) fn lock_output_meter_recoverable(
         &self,
@@ -0,0 +1,51 @@
+//! Loudness-tag input trim controls.
+
+use super::Player;
+use crate::container::loudness::LoudnessTag;
+
+impl Player {
+    /// Return the loaded container's loudness tag, if it has one.
+    pub fn get_loudness_tag(&self) -> Option<LoudnessTag> {
+        self.lock_prot_invariant().get_loudness_tag()
+    }
+
+    /// Enable or disable the automatic loudness trim (enabled by default).
+    ///
+    /// While enabled, the container's loudness tag scales the track mix
+    /// before live input and effects, so differently mastered containers
+    /// play at a consistent level. Changes apply from the next mixed chunk.
+    pub fn set_loudness_trim_enabled(&self, enabled: bool) {
+        self.update_buffer_settings(|settings| settings.loudness_trim = enabled);
+    }
+
+    /// Return `true` when the loudness trim is enabled.
+    pub fn is_loudness_trim_enabled(&self) -> bool {
+        self.lock_buffer_settings_recoverable().loudness_trim
+    }
+
+    /// Trim currently applied to the mix, in dB (0.0 when off or untagged).
+    pub fn get_loudness_trim_db(&self) -> f32 {
+        if !self.is_loudness_trim_enabled() {
+            return 0.0;
+        }
+        self.get_loudness_tag().map_or(0.0, |tag| tag.trim_db())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+
+    #[test]
+    fn loudness_trim_toggles_and_reports_zero_without_tag() {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        assert!(player.is_loudness_trim_enabled());
+        assert!(player.get_loudness_tag().is_none());
+        assert_eq!(player.get_loudness_trim_db(), 0.0);
+        player.set_loudness_trim_enabled(false);
+        assert!(!player.is_loudness_trim_enabled());
+    }
+}
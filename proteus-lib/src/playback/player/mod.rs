@@ -3,39 +3,92 @@
 //! `Player` is the primary integration point for consumers that need to load a
 //! container or file list, control transport state, and inspect DSP/runtime
 //! telemetry. Implementation details are split into focused submodules:
+//! - `clock_sync`: slaving playback to an external clock.
 //! - `controls`: transport operations and lifecycle orchestration.
 //! - `effects`: DSP-chain and metering controls.
+//! - `encryption`: key provider for encrypted tracks (feature `encryption`).
+//! - `fader`: send-safe volume handle used by automated transitions.
+//! - `link`: Ableton Link tempo and phase sync (feature `link`).
+//! - `live_input`: live capture input mixed as an extra track.
+//! - `loudness`: automatic loudness-tag input trim.
+//! - `one_shot`: transient one-shot sounds mixed over the output.
+//! - `output`: output device format and sample-rate conversion.
+//! - `progressive`: playback of containers that are still downloading.
+//! - `sections`: runtime section queueing for horizontal re-sequencing.
 //! - `settings`: runtime tuning and debug surface.
+//! - `shuffle`: deferred shuffles handed over at a chosen boundary.
+//! - `single_file`: express path for auditioning one audio file.
 //! - `runtime`: internal playback thread bootstrap and worker loop.
+//! - `saved_state`: saving and restoring the full player state.
+//! - `volume_fade`: volume automation with configurable curves.
 
 mod builder;
+mod clip;
+mod clock_sync;
 mod controls;
+mod diagnostics;
 mod effects;
+#[cfg(feature = "encryption")]
+mod encryption;
+mod fader;
 mod lifecycle;
+#[cfg(feature = "link")]
+mod link;
+mod live_input;
 mod locks;
+mod loudness;
 mod notify;
+mod one_shot;
+mod output;
+mod progressive;
 mod runtime;
+mod saved_state;
+mod sections;
 mod settings;
+mod shuffle;
+mod single_file;
 mod state;
+mod volume_fade;
 
-use rodio::{OutputStream, Sink};
+pub use live_input::{LiveInputConfig, LiveInputError};
+pub use output::{ChannelMapError, OutputInfo, OutputMode, RateConversion};
+pub use saved_state::{PlayerStateSnapshot, RestoreStateError};
+pub use sections::SectionError;
+pub use shuffle::ShuffleBoundary;
+
+use rodio::OutputStream;
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::JoinHandle;
 
+use crate::container::limits::ParseLimits;
+use crate::container::play_settings::ParseMode;
 use crate::container::prot::{PathsTrack, Prot, ProtError};
+use crate::diagnostics::log_capture::LogCapture;
 use crate::diagnostics::reporter::Reporter;
+use crate::diagnostics::session::PlaySession;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
 use crate::dsp::effects::convolution_reverb::ImpulseResponseSpec;
+use crate::playback::clock_sync::ClockSync;
+use crate::playback::gain_staging::SharedGainStaging;
 use crate::playback::output_meter::OutputMeter;
+use crate::playback::output_sink::{OutputBackend, SharedOutputSink};
+use crate::playback::realization::RealizationRecorder;
+use crate::playback::track_meter::TrackLevels;
+use crate::tools::progressive::{BufferingState, ProgressiveFile};
 use crate::{
     container::info::Info,
     dsp::effects::AudioEffect,
     playback::engine::{
-        DspChainMetrics, EffectSettingsCommand, InlineEffectsUpdate, InlineTrackMixUpdate,
-        PlaybackBufferSettings,
+        DecodePool, DspChainMetrics, EffectSettingsCommand, EffectTiming, InlineEffectsUpdate,
+        InlineTrackMixUpdate, OneShotVoice, PlaybackBufferSettings, ReaderCache, SharedLiveInput,
+        SharedVolumeRamp,
     },
 };
 
+pub(crate) use self::fader::PlayerFader;
+use self::live_input::LiveCapture;
 use self::notify::WorkerNotify;
 
 /// High-level playback state for the player.
@@ -76,12 +129,31 @@ pub enum EndOfStreamAction {
 pub struct PlayerInitOptions {
     /// End-of-stream transport action.
     pub end_of_stream_action: EndOfStreamAction,
+    /// How the output device is opened.
+    pub output_mode: OutputMode,
+    /// How a container's `play_settings.json` is decoded.
+    pub play_settings_mode: ParseMode,
+    /// Decode threads kept alive across seeks and shuffles.
+    ///
+    /// Defaults to [`DecodePool::default_threads`]. Use a small value on
+    /// low-core devices; `0` starts fresh threads for every run.
+    pub decode_threads: usize,
+    /// Bounds applied while loading a container and to every attachment it
+    /// later reads (presets, artwork, one-shots, impulse responses).
+    ///
+    /// Defaults to [`ParseLimits::unlimited`]; pass
+    /// [`ParseLimits::default`] for user-supplied files.
+    pub parse_limits: ParseLimits,
 }
 
 impl Default for PlayerInitOptions {
     fn default() -> Self {
         Self {
             end_of_stream_action: EndOfStreamAction::Stop,
+            output_mode: OutputMode::Shared,
+            play_settings_mode: ParseMode::Lenient,
+            decode_threads: DecodePool::default_threads(),
+            parse_limits: ParseLimits::unlimited(),
         }
     }
 }
@@ -95,6 +167,9 @@ pub enum PlayerInitError {
     AmbiguousSource,
     /// Failed to initialize the underlying `.prot` container.
     ProtInitialization(ProtError),
+    /// A progressive download failed or was still buffering; see
+    /// [`Player::play_progressive`].
+    DownloadNotReady(BufferingState),
 }
 
 impl std::fmt::Display for PlayerInitError {
@@ -108,6 +183,9 @@ impl std::fmt::Display for PlayerInitError {
                 )
             }
             Self::ProtInitialization(err) => write!(f, "player source init failed: {}", err),
+            Self::DownloadNotReady(state) => {
+                write!(f, "progressive download not ready to play: {:?}", state)
+            }
         }
     }
 }
@@ -117,10 +195,19 @@ impl std::error::Error for PlayerInitError {}
 /// Source input used to initialize a [`Player`].
 #[derive(Debug, Clone)]
 pub enum PlayerSource {
-    /// Playback from a `.prot`/`.mka` container path.
+    /// Playback from a `.prot`/`.mka` container path, or from any single
+    /// audio file, which plays as a one-track container.
     ContainerPath(String),
     /// Playback from standalone grouped track paths.
     FilePaths(Vec<PathsTrack>),
+    /// Playback of one audio file with no play settings or shuffling.
+    ///
+    /// See [`Player::new_single_file`].
+    SingleFile(String),
+    /// Playback of a container that is still downloading.
+    ///
+    /// See [`Player::play_progressive`].
+    Progressive(ProgressiveFile),
 }
 
 /// Snapshot of active reverb settings for UI consumers.
@@ -173,8 +260,7 @@ pub struct Player {
     audio_heard: Arc<AtomicBool>,
     play_command_ms: Arc<AtomicU64>,
     volume: Arc<Mutex<f32>>,
-    sink: Arc<Mutex<Sink>>,
-    #[allow(clippy::arc_with_non_send_sync)]
+    sink: SharedOutputSink,
     output_stream: Arc<Mutex<Option<OutputStream>>>,
     reporter: Option<Arc<Mutex<Reporter>>>,
     buffer_settings: Arc<Mutex<PlaybackBufferSettings>>,
@@ -182,9 +268,27 @@ pub struct Player {
     effect_settings_commands: Arc<Mutex<Vec<EffectSettingsCommand>>>,
     inline_effects_update: Arc<Mutex<Option<InlineEffectsUpdate>>>,
     inline_track_mix_updates: Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
+    session_id: String,
+    /// Recent log events attributed to this player.
+    log_capture: LogCapture,
+    /// Play statistics accumulated over this player's session.
+    session_stats: Arc<Mutex<PlaySession>>,
+    /// Log of which sources were sent to the output and when.
+    realization: Arc<Mutex<RealizationRecorder>>,
     effects_reset: Arc<AtomicU64>,
     output_meter: Arc<Mutex<OutputMeter>>,
+    /// Device channel for each engine channel; empty routes channels in order.
+    channel_map: Arc<Mutex<Vec<usize>>>,
+    /// External clock the output rate is chasing, if any.
+    clock_sync: Arc<Mutex<Option<ClockSync>>>,
+    /// Link session shuffle points and section changes quantize to, if any.
+    #[cfg(feature = "link")]
+    link: Arc<Mutex<Option<crate::playback::link::SharedLink>>>,
     /// Producer-buffering-complete publication flag.
     ///
     /// **Ordering contract (acquire/release):**
@@ -199,11 +303,34 @@ pub struct Player {
     last_time_update_ms: Arc<AtomicU64>,
     next_resume_fade_ms: Arc<Mutex<Option<f32>>>,
     end_of_stream_action: Arc<Mutex<EndOfStreamAction>>,
+    output_mode: OutputMode,
+    /// Whether the player was built from [`PlayerSource::SingleFile`].
+    single_file: bool,
+    /// Custom output replacing the rodio device stream, when installed.
+    output_backend: Arc<Mutex<Option<Arc<dyn OutputBackend>>>>,
     handle_count: Arc<AtomicUsize>,
     shutdown_once: Arc<AtomicBool>,
     impulse_response_override: Option<ImpulseResponseSpec>,
     impulse_response_tail_override: Option<f32>,
     worker_notify: Arc<WorkerNotify>,
+    /// Section names waiting to be played at the next section boundary.
+    section_queue: Arc<Mutex<VecDeque<String>>>,
+    /// Shuffle waiting to be handed over at the next boundary of this kind.
+    scheduled_shuffle: Arc<Mutex<Option<ShuffleBoundary>>>,
+    /// One-shot voices waiting to be mixed over the output.
+    one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    /// Captured live input mixed as an extra track.
+    live_input: SharedLiveInput,
+    /// Volume automation evaluated per frame on the mix thread.
+    volume_ramp: SharedVolumeRamp,
+    /// Per-stage level history for gain staging diagnostics.
+    gain_staging: SharedGainStaging,
+    /// Capture thread feeding `live_input`, while running.
+    live_capture: Arc<Mutex<Option<LiveCapture>>>,
+    /// Decode threads shared by every engine this player starts.
+    decode_pool: DecodePool,
+    /// Container readers kept open across this player's seeks.
+    reader_cache: ReaderCache,
 }
 
 impl Clone for Player {
@@ -231,7 +358,19 @@ impl Clone for Player {
             effect_settings_commands: self.effect_settings_commands.clone(),
             inline_effects_update: self.inline_effects_update.clone(),
             inline_track_mix_updates: self.inline_track_mix_updates.clone(),
+            inline_group_gains: self.inline_group_gains.clone(),
             dsp_metrics: self.dsp_metrics.clone(),
+            track_levels: self.track_levels.clone(),
+            effect_timings: self.effect_timings.clone(),
+            diagnostics_events: self.diagnostics_events.clone(),
+            session_id: self.session_id.clone(),
+            log_capture: self.log_capture.clone(),
+            session_stats: self.session_stats.clone(),
+            realization: self.realization.clone(),
+            channel_map: self.channel_map.clone(),
+            clock_sync: self.clock_sync.clone(),
+            #[cfg(feature = "link")]
+            link: self.link.clone(),
             effects_reset: self.effects_reset.clone(),
             output_meter: self.output_meter.clone(),
             buffering_done: self.buffering_done.clone(),
@@ -239,16 +378,35 @@ impl Clone for Player {
             last_time_update_ms: self.last_time_update_ms.clone(),
             next_resume_fade_ms: self.next_resume_fade_ms.clone(),
             end_of_stream_action: self.end_of_stream_action.clone(),
+            output_mode: self.output_mode,
+            single_file: self.single_file,
+            output_backend: self.output_backend.clone(),
             handle_count: self.handle_count.clone(),
             shutdown_once: self.shutdown_once.clone(),
             impulse_response_override: self.impulse_response_override.clone(),
             impulse_response_tail_override: self.impulse_response_tail_override,
             worker_notify: self.worker_notify.clone(),
+            section_queue: self.section_queue.clone(),
+            scheduled_shuffle: self.scheduled_shuffle.clone(),
+            one_shots: self.one_shots.clone(),
+            live_input: self.live_input.clone(),
+            volume_ramp: self.volume_ramp.clone(),
+            gain_staging: self.gain_staging.clone(),
+            live_capture: self.live_capture.clone(),
+            decode_pool: self.decode_pool.clone(),
+            reader_cache: self.reader_cache.clone(),
         }
     }
 }
 
-#[allow(clippy::arc_with_non_send_sync)]
+// Handles move onto playback, watcher, and async blocking threads.
+const _: () = {
+    fn _assert_send<T: Send>() {}
+    fn _assert_player_send() {
+        _assert_send::<Player>();
+    }
+};
+
 pub(in crate::playback::player) fn default_output_stream_handle() -> Arc<Mutex<Option<OutputStream>>>
 {
     Arc::new(Mutex::new(None))
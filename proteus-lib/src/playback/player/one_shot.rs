@@ -0,0 +1,66 @@
+//! One-shot overlay sounds (UI sounds, stingers) mixed over playback.
+
+use super::Player;
+use crate::playback::engine::{OneShotError, OneShotLayout, OneShotSource, OneShotVoice};
+
+impl Player {
+    /// Mix a short auxiliary sound on top of the current output.
+    ///
+    /// The sound is decoded on the calling thread, converted to the
+    /// container's channel layout and sample rate, and played once through a
+    /// transient engine slot after the effect chain. Main track buffers,
+    /// shuffle state, and the timeline are not affected. Several one-shots
+    /// may overlap. While any one-shot plays, the main mix is ducked as
+    /// configured by [`Player::set_ducking`].
+    ///
+    /// # Arguments
+    ///
+    /// * `source` - File path or container attachment to play.
+    /// * `gain` - Linear gain applied to the sound.
+    /// * `pan` - Stereo position in `[-1.0, 1.0]`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OneShotError::NotPlaying`] when no playback thread is
+    /// running, or an error describing why the sound could not be loaded.
+    pub fn play_one_shot(
+        &self,
+        source: OneShotSource,
+        gain: f32,
+        pan: f32,
+    ) -> Result<(), OneShotError> {
+        if self.thread_finished() {
+            return Err(OneShotError::NotPlaying);
+        }
+        let (container_path, download, max_attachment_bytes, layout) = {
+            let prot = self.lock_prot_invariant();
+            let layout = OneShotLayout {
+                channels: prot.info.channels.max(1) as usize,
+                sample_rate: prot.info.sample_rate,
+                pan_law: self.effective_pan_law_for(&prot),
+            };
+            (
+                prot.get_container_path(),
+                prot.download(),
+                prot.parse_limits().max_attachment_bytes,
+                layout,
+            )
+        };
+        let voice = OneShotVoice::load(
+            &source,
+            container_path.as_deref(),
+            download.as_ref(),
+            max_attachment_bytes,
+            gain,
+            pan,
+            layout,
+        )?;
+        self.lock_one_shots_recoverable().push(voice);
+        Ok(())
+    }
+
+    /// Drop every one-shot that is still playing or waiting to play.
+    pub fn stop_one_shots(&self) {
+        self.lock_one_shots_recoverable().clear();
+    }
+}
@@ -0,0 +1,337 @@
+//! Output device format, channel routing, and sample-rate conversion controls.
+
+use std::fmt;
+use std::sync::Arc;
+
+use rodio::cpal::BufferSize;
+use rodio::stream::OutputStreamConfig;
+
+use super::Player;
+use crate::dsp::resample::ResampleQuality;
+use crate::playback::output_sink::OutputBackend;
+
+/// How the output device stream is opened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Default device configuration and buffer size.
+    #[default]
+    Shared,
+    /// Request a fixed device buffer of `buffer_frames` frames.
+    ///
+    /// Buffering settings are tightened to match the negotiated buffer. If
+    /// the device rejects the size, the stream falls back to a supported
+    /// configuration.
+    LowLatency {
+        /// Requested device buffer size in frames.
+        buffer_frames: u32,
+    },
+    /// Request exclusive device access (WASAPI exclusive / CoreAudio hog mode).
+    ///
+    /// The audio backend does not currently expose exclusive access on any
+    /// host, so this opens a [`OutputMode::LowLatency`] stream with the same
+    /// buffer size and logs a warning.
+    Exclusive {
+        /// Requested device buffer size in frames.
+        buffer_frames: u32,
+    },
+}
+
+impl OutputMode {
+    /// Requested device buffer size, or `None` for the device default.
+    pub fn buffer_frames(&self) -> Option<u32> {
+        match self {
+            Self::Shared => None,
+            Self::LowLatency { buffer_frames } | Self::Exclusive { buffer_frames } => {
+                Some(*buffer_frames)
+            }
+        }
+    }
+}
+
+/// Sample-rate conversion applied between the mix and the output device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateConversion {
+    /// Container sample rate produced by the mix.
+    pub from_rate: u32,
+    /// Device sample rate.
+    pub to_rate: u32,
+    /// Converter in use; [`ResampleQuality::Linear`] means rodio converts.
+    pub quality: ResampleQuality,
+}
+
+/// Format of the open output device relative to the playing content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OutputInfo {
+    /// Sample rate the device was opened at.
+    pub device_sample_rate: u32,
+    /// Channel count the device was opened with.
+    pub device_channels: u16,
+    /// Sample rate of the mixed content.
+    pub source_sample_rate: u32,
+    /// Channel count of the mixed content.
+    pub source_channels: u32,
+    /// Active rate conversion, or `None` when the rates match.
+    pub conversion: Option<RateConversion>,
+    /// Mode requested when the device was opened.
+    pub mode: OutputMode,
+    /// Negotiated device buffer in frames, or `None` when the device chose it.
+    pub buffer_frames: Option<u32>,
+    /// Device buffer latency in milliseconds, when the buffer size is known.
+    pub buffer_latency_ms: Option<f64>,
+}
+
+/// Errors returned by [`Player::set_channel_map`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelMapError {
+    /// The map must have one entry per engine channel.
+    LengthMismatch {
+        /// Engine channel count.
+        expected: usize,
+        /// Number of entries supplied.
+        actual: usize,
+    },
+    /// A target channel does not exist on the opened device.
+    OutOfRange {
+        /// Requested device channel.
+        channel: usize,
+        /// Channel count of the opened device.
+        device_channels: u16,
+    },
+    /// Two engine channels were routed to the same device channel.
+    DuplicateTarget(usize),
+}
+
+impl fmt::Display for ChannelMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LengthMismatch { expected, actual } => write!(
+                f,
+                "channel map has {} entries but the engine has {} channels",
+                actual, expected
+            ),
+            Self::OutOfRange {
+                channel,
+                device_channels,
+            } => write!(
+                f,
+                "device channel {} is out of range for a {}-channel device",
+                channel, device_channels
+            ),
+            Self::DuplicateTarget(channel) => {
+                write!(f, "device channel {} is targeted more than once", channel)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ChannelMapError {}
+
+impl Player {
+    /// Send output to a custom backend instead of the default rodio device.
+    ///
+    /// The backend opens one sink per playback run in the content's channel
+    /// count and sample rate; pass `None` to return to the default device.
+    /// The change takes effect the next time playback starts or seeks.
+    /// Device-only stages (the channel map and sinc resampling) are skipped
+    /// while a backend is installed.
+    pub fn set_output_backend(&self, backend: Option<Arc<dyn OutputBackend>>) {
+        *self.lock_output_backend_recoverable() = backend;
+    }
+
+    /// Return `true` while a custom output backend is installed.
+    pub fn has_output_backend(&self) -> bool {
+        self.lock_output_backend_recoverable().is_some()
+    }
+
+    /// Route engine channels to specific device channels.
+    ///
+    /// Entry `i` names the zero-based device channel that receives engine
+    /// channel `i`; unmapped device channels are silent. For example,
+    /// `vec![2, 3]` sends a stereo mix to outputs 3/4 of an interface. Pass
+    /// an empty map to restore in-order routing. Targets are checked against
+    /// the opened device; a map set before the stream opens that does not fit
+    /// the device is ignored during playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ChannelMapError`] when the map length differs from the
+    /// engine channel count, a target repeats, or a target exceeds the
+    /// opened device's channel count.
+    pub fn set_channel_map(&self, map: Vec<usize>) -> Result<(), ChannelMapError> {
+        if !map.is_empty() {
+            let expected = self.info.channels as usize;
+            if map.len() != expected {
+                return Err(ChannelMapError::LengthMismatch {
+                    expected,
+                    actual: map.len(),
+                });
+            }
+            if let Some(duplicate) = map
+                .iter()
+                .enumerate()
+                .find(|(index, channel)| map[..*index].contains(channel))
+            {
+                return Err(ChannelMapError::DuplicateTarget(*duplicate.1));
+            }
+            let device_channels = self
+                .lock_output_stream_recoverable()
+                .as_ref()
+                .map(|stream| stream.config().channel_count());
+            if let Some(device_channels) = device_channels {
+                if let Some(channel) = map.iter().find(|c| **c >= device_channels as usize) {
+                    return Err(ChannelMapError::OutOfRange {
+                        channel: *channel,
+                        device_channels,
+                    });
+                }
+            }
+        }
+        *self.lock_channel_map_recoverable() = map;
+        Ok(())
+    }
+
+    /// Get the current channel map (empty when channels are routed in order).
+    pub fn get_channel_map(&self) -> Vec<usize> {
+        self.lock_channel_map_recoverable().clone()
+    }
+
+    /// Select the converter used when the device rate differs from the container rate.
+    ///
+    /// [`ResampleQuality::Linear`] (the default) leaves conversion to rodio.
+    /// The sinc qualities convert in-crate before chunks reach the sink.
+    /// Changes apply from the next appended chunk.
+    pub fn set_output_resampler(&self, quality: ResampleQuality) {
+        self.update_buffer_settings(|settings| {
+            settings.output_resampler = quality;
+        });
+    }
+
+    /// Get the configured output resampler quality.
+    pub fn get_output_resampler(&self) -> ResampleQuality {
+        self.lock_buffer_settings_recoverable().output_resampler
+    }
+
+    /// Describe the open output device and any active rate conversion.
+    ///
+    /// Returns `None` until playback has opened the output stream, and while
+    /// an output backend is installed.
+    pub fn get_output_info(&self) -> Option<OutputInfo> {
+        if self.has_output_backend() {
+            return None;
+        }
+        let config = *self.lock_output_stream_recoverable().as_ref()?.config();
+        let device_sample_rate = config.sample_rate();
+        let buffer_frames = fixed_buffer_frames(&config);
+        let source_sample_rate = self.info.sample_rate;
+        let conversion = (source_sample_rate != device_sample_rate).then(|| RateConversion {
+            from_rate: source_sample_rate,
+            to_rate: device_sample_rate,
+            quality: self.get_output_resampler(),
+        });
+        Some(OutputInfo {
+            device_sample_rate,
+            device_channels: config.channel_count(),
+            source_sample_rate,
+            source_channels: self.info.channels,
+            conversion,
+            mode: self.output_mode,
+            buffer_frames,
+            buffer_latency_ms: buffer_frames
+                .map(|frames| frames as f64 * 1000.0 / device_sample_rate.max(1) as f64),
+        })
+    }
+
+    /// Output mode this player opens the device with.
+    pub fn get_output_mode(&self) -> OutputMode {
+        self.output_mode
+    }
+
+    /// Size buffering to the device buffer negotiated for a low-latency stream.
+    pub(in crate::playback::player) fn fit_buffering_to_output(&self, config: &OutputStreamConfig) {
+        if self.output_mode.buffer_frames().is_none() {
+            return;
+        }
+        let Some(frames) = fixed_buffer_frames(config) else {
+            return;
+        };
+        let latency_ms = frames as f32 * 1000.0 / config.sample_rate().max(1) as f32;
+        self.update_buffer_settings(|settings| settings.fit_output_latency(latency_ms));
+    }
+}
+
+fn fixed_buffer_frames(config: &OutputStreamConfig) -> Option<u32> {
+    match config.buffer_size() {
+        BufferSize::Fixed(frames) => Some(*frames),
+        BufferSize::Default => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+
+    #[test]
+    fn output_resampler_round_trips_and_info_needs_stream() {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        assert_eq!(player.get_output_resampler(), ResampleQuality::Linear);
+        player.set_output_resampler(ResampleQuality::SincBest);
+        assert_eq!(player.get_output_resampler(), ResampleQuality::SincBest);
+        assert!(player.get_output_info().is_none());
+    }
+
+    #[test]
+    fn channel_map_validates_length_and_duplicates() {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        let channels = player.info.channels as usize;
+        assert_eq!(
+            player.set_channel_map(vec![0; channels + 1]),
+            Err(ChannelMapError::LengthMismatch {
+                expected: channels,
+                actual: channels + 1,
+            })
+        );
+        if channels >= 2 {
+            let mut map: Vec<usize> = (0..channels).collect();
+            map[1] = 0;
+            assert_eq!(
+                player.set_channel_map(map),
+                Err(ChannelMapError::DuplicateTarget(0))
+            );
+        }
+        let routed: Vec<usize> = (2..channels + 2).collect();
+        player.set_channel_map(routed.clone()).unwrap();
+        assert_eq!(player.get_channel_map(), routed);
+        player.set_channel_map(Vec::new()).unwrap();
+        assert!(player.get_channel_map().is_empty());
+    }
+
+    #[test]
+    fn low_latency_mode_sizes_buffering_to_requested_buffer() {
+        let options = crate::playback::player::PlayerInitOptions {
+            output_mode: OutputMode::LowLatency { buffer_frames: 128 },
+            ..Default::default()
+        };
+        let player = Player::new_from_file_paths_with_options(
+            vec![PathsTrack::new_from_file_paths(vec![
+                "/tmp/nonexistent.wav".to_string(),
+            ])],
+            options,
+        );
+        assert_eq!(player.get_output_mode().buffer_frames(), Some(128));
+        let settings = *player.lock_buffer_settings_recoverable();
+        let period_ms = 128.0 * 1000.0 / player.info.sample_rate.max(1) as f32;
+        assert_eq!(
+            settings.output_slice_ms,
+            Some((period_ms * 2.0).clamp(5.0, 30.0))
+        );
+        assert_eq!(
+            settings.max_sink_latency_ms,
+            Some((period_ms * 4.0).max(20.0))
+        );
+    }
+}
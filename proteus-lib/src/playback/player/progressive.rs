@@ -0,0 +1,121 @@
+//! Playback of containers that are still downloading.
+
+use std::time::Duration;
+
+use super::{Player, PlayerInitError, PlayerInitOptions, PlayerSource};
+use crate::tools::progressive::{BufferingState, ProgressiveFile};
+
+impl Player {
+    /// Start playing a container as soon as its download can play.
+    ///
+    /// `file` is the handle of a running
+    /// [`ProgressiveDownload`](crate::tools::progressive::ProgressiveDownload).
+    /// This waits up to `timeout` for the Matroska header, the attachments
+    /// carrying `play_settings.json`, and the initial clusters to arrive,
+    /// then loads the container from those bytes and starts playback.
+    /// Decode workers keep reading through `file` as later bytes arrive;
+    /// subscribe to the download for [`BufferingState`] changes such as
+    /// stalls. A download that already completed loads and plays like
+    /// [`Player::new`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlayerInitError::DownloadNotReady`] when the download
+    /// failed or was still buffering at the timeout, and
+    /// [`PlayerInitError::ProtInitialization`] when the container cannot be
+    /// parsed.
+    pub fn play_progressive(
+        file: &ProgressiveFile,
+        options: PlayerInitOptions,
+        timeout: Duration,
+    ) -> Result<Self, PlayerInitError> {
+        let state = file.wait_until_ready(timeout);
+        if matches!(state, BufferingState::Buffering | BufferingState::Failed) {
+            return Err(PlayerInitError::DownloadNotReady(state));
+        }
+        let mut player =
+            Self::try_from_source_with_options(PlayerSource::Progressive(file.clone()), options)?;
+        player.play();
+        Ok(player)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::container::limits::ParseLimits;
+    use crate::container::play_settings::ParseMode;
+    use crate::container::prot::Prot;
+    use crate::playback::player::{Player, PlayerInitError, PlayerInitOptions};
+    use crate::playback::render::render_selection_to_pcm;
+    use crate::test_fixtures::{demo_container_bytes, temp_container_path, DEMO_CONTAINER};
+    use crate::tools::progressive::{BufferingState, ProgressiveDownload};
+
+    #[test]
+    fn containers_load_and_render_while_downloading() {
+        let bytes = demo_container_bytes();
+        let expected = render_selection_to_pcm(&Prot::try_new(DEMO_CONTAINER).unwrap(), 7, 6.0);
+
+        let path = temp_container_path("player_progressive", "render");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        let mut chunks = bytes.chunks(32 * 1024);
+        while download.state() == BufferingState::Buffering {
+            download.append(chunks.next().unwrap()).unwrap();
+        }
+        let prot = Prot::try_new_progressive(
+            &download.file(),
+            ParseMode::Lenient,
+            &ParseLimits::unlimited(),
+        )
+        .unwrap();
+        assert!(prot.get_shuffle_schedule().len() > 1);
+
+        // Decoding catches up with the download and waits for the rest.
+        let rest: Vec<Vec<u8>> = chunks.map(<[u8]>::to_vec).collect();
+        let feeder = thread::spawn(move || {
+            for chunk in rest {
+                thread::sleep(Duration::from_millis(1));
+                download.append(&chunk).unwrap();
+            }
+            download.state()
+        });
+        let rendered = render_selection_to_pcm(&prot, 7, 6.0);
+        assert_eq!(feeder.join().unwrap(), BufferingState::Complete);
+        assert_eq!(rendered.samples, expected.samples);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn playback_waits_for_the_download_to_buffer() {
+        let bytes = demo_container_bytes();
+        let path = temp_container_path("player_progressive", "player");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        let file = download.file();
+        download.append(&bytes[..1024]).unwrap();
+        assert_eq!(
+            Player::play_progressive(
+                &file,
+                PlayerInitOptions::default(),
+                Duration::from_millis(20)
+            )
+            .err(),
+            Some(PlayerInitError::DownloadNotReady(BufferingState::Buffering))
+        );
+
+        let feeder = thread::spawn(move || {
+            for chunk in bytes[1024..].chunks(64 * 1024) {
+                thread::sleep(Duration::from_millis(1));
+                download.append(chunk).unwrap();
+            }
+        });
+        let player =
+            Player::play_progressive(&file, PlayerInitOptions::default(), Duration::from_secs(30))
+                .unwrap();
+        assert!(player.lock_prot_invariant().get_shuffle_schedule().len() > 1);
+        player.stop();
+        feeder.join().unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -7,13 +7,13 @@ use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 
-use log::debug;
-
-use rodio::mixer::Mixer;
+use log::{debug, error};
 
 use super::super::Player;
 use super::now_ms;
-use super::worker::{open_output_stream_with_retry, run_playback_thread, ThreadContext};
+use super::worker::{
+    open_output_stream_with_retry, run_playback_thread, OutputTarget, ThreadContext,
+};
 
 fn trace_elapsed(trace_ms: u64, now: u64) -> Option<u64> {
     if trace_ms > 0 {
@@ -62,22 +62,69 @@ impl Player {
         self.audio_heard.store(false, Ordering::Relaxed);
         self.lock_output_meter_recoverable().reset();
 
-        let (output_mixer, opened_now) = {
+        let Some(output) = self.open_output_target(trace_ms) else {
+            // Release: publish false to any Acquire load (early-exit path,
+            // no thread was ever spawned for this run).
+            self.playback_thread_exists.store(false, Ordering::Release);
+            return;
+        };
+
+        let context = self.build_thread_context(output);
+        let log_capture = self.log_capture.clone();
+        let handle = thread::spawn(move || {
+            let _scope = log_capture.enter();
+            run_playback_thread(context, playback_id, ts)
+        });
+        *self.lock_playback_thread_handle_invariant() = Some(handle);
+        if let Some(elapsed_ms) = trace_elapsed(trace_ms, now_ms()) {
+            debug!(
+                "play trace: initialize_thread spawned playback_id={} +{}ms",
+                playback_id, elapsed_ms
+            );
+        } else {
+            debug!(
+                "play trace: initialize_thread spawned playback_id={}",
+                playback_id
+            );
+        }
+    }
+
+    // Resolve where this run's audio goes: a sink from the installed output
+    // backend, or the shared rodio device stream (opened on first use).
+    fn open_output_target(&self, trace_ms: u64) -> Option<OutputTarget> {
+        let backend = self.lock_output_backend_recoverable().clone();
+        if let Some(backend) = backend {
+            let channels = self.info.channels as u16;
+            return match backend.open_sink(channels, self.info.sample_rate) {
+                Ok(sink) => {
+                    *self.lock_sink_recoverable() = sink;
+                    Some(OutputTarget::Backend)
+                }
+                Err(err) => {
+                    error!("failed to open output backend sink: {}", err);
+                    None
+                }
+            };
+        }
+
+        let (target, opened_now) = {
             let mut output_stream = self.lock_output_stream_recoverable();
             let opened_now = if output_stream.is_none() {
-                *output_stream = open_output_stream_with_retry();
+                *output_stream = open_output_stream_with_retry(self.output_mode);
                 true
             } else {
                 false
             };
-            let Some(stream) = output_stream.as_ref() else {
-                // Release: publish false to any Acquire load (early-exit path,
-                // no thread was ever spawned for this run).
-                self.playback_thread_exists.store(false, Ordering::Release);
-                return;
+            let stream = output_stream.as_ref()?;
+            let target = OutputTarget::Device {
+                mixer: stream.mixer().clone(),
+                config: *stream.config(),
             };
-            (stream.mixer().clone(), opened_now)
+            (target, opened_now)
         };
+        if let (true, Some(config)) = (opened_now, target.device_config()) {
+            self.fit_buffering_to_output(config);
+        }
         if let Some(elapsed_ms) = trace_elapsed(trace_ms, now_ms()) {
             if opened_now {
                 debug!("play trace: output stream opened +{}ms", elapsed_ms);
@@ -85,24 +132,10 @@ impl Player {
                 debug!("play trace: output stream reused +{}ms", elapsed_ms);
             }
         }
-
-        let context = self.build_thread_context(output_mixer);
-        let handle = thread::spawn(move || run_playback_thread(context, playback_id, ts));
-        *self.lock_playback_thread_handle_invariant() = Some(handle);
-        if let Some(elapsed_ms) = trace_elapsed(trace_ms, now_ms()) {
-            debug!(
-                "play trace: initialize_thread spawned playback_id={} +{}ms",
-                playback_id, elapsed_ms
-            );
-        } else {
-            debug!(
-                "play trace: initialize_thread spawned playback_id={}",
-                playback_id
-            );
-        }
+        Some(target)
     }
 
-    fn build_thread_context(&self, output_mixer: Mixer) -> ThreadContext {
+    fn build_thread_context(&self, output: OutputTarget) -> ThreadContext {
         ThreadContext {
             play_state: self.state.clone(),
             abort: self.abort.clone(),
@@ -116,7 +149,11 @@ impl Player {
             effect_settings_commands: self.effect_settings_commands.clone(),
             inline_effects_update: self.inline_effects_update.clone(),
             inline_track_mix_updates: self.inline_track_mix_updates.clone(),
+            inline_group_gains: self.inline_group_gains.clone(),
             dsp_metrics: self.dsp_metrics.clone(),
+            track_levels: self.track_levels.clone(),
+            effect_timings: self.effect_timings.clone(),
+            diagnostics_events: self.diagnostics_events.clone(),
             effects_reset: self.effects_reset.clone(),
             output_meter: self.output_meter.clone(),
             audio_info: self.info.clone(),
@@ -126,11 +163,25 @@ impl Player {
             play_command_ms: self.play_command_ms.clone(),
             volume: self.volume.clone(),
             sink_mutex: self.sink.clone(),
-            output_mixer,
+            output,
             buffer_done_thread_flag: self.buffering_done.clone(),
             last_chunk_ms: self.last_chunk_ms.clone(),
             last_time_update_ms: self.last_time_update_ms.clone(),
             worker_notify: self.worker_notify.clone(),
+            section_queue: self.section_queue.clone(),
+            scheduled_shuffle: self.scheduled_shuffle.clone(),
+            one_shots: self.one_shots.clone(),
+            live_input: self.live_input.clone(),
+            volume_ramp: self.volume_ramp.clone(),
+            gain_staging: self.gain_staging.clone(),
+            session_stats: self.session_stats.clone(),
+            realization: self.realization.clone(),
+            channel_map: self.channel_map.clone(),
+            clock_sync: self.clock_sync.clone(),
+            #[cfg(feature = "link")]
+            link: self.link.clone(),
+            decode_pool: self.decode_pool.clone(),
+            reader_cache: self.reader_cache.clone(),
         }
     }
 }
@@ -0,0 +1,25 @@
+//! Varispeed for players synced to an external clock.
+
+use super::context::ThreadContext;
+use crate::playback::output_sink::OutputChunk;
+
+// Play one chunk at the rate the clock controller asks for.
+//
+// The chunk is relabelled with a slightly shifted sample rate, so the device
+// stream's rate conversion speeds it up or slows it down like varispeed.
+// Chunks pass through untouched when no clock is set, while the sink is
+// paused, or when a backend sink is in use.
+pub(super) fn apply_clock_sync(ctx: &ThreadContext, chunk: OutputChunk) -> OutputChunk {
+    if ctx.output.device_config().is_none() || ctx.lock_sink_recoverable().is_paused() {
+        return chunk;
+    }
+    let position = *ctx.lock_time_passed_recoverable();
+    let mut clock_sync = ctx.lock_clock_sync_recoverable();
+    let Some(sync) = clock_sync.as_mut() else {
+        return chunk;
+    };
+    let rate = sync.update(position);
+    drop(clock_sync);
+    let sample_rate = (f64::from(chunk.sample_rate) * rate).round() as u32;
+    OutputChunk::new(chunk.samples, chunk.channels, sample_rate.max(1))
+}
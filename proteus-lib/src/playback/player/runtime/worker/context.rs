@@ -1,21 +1,54 @@
 //! Shared runtime context captured at thread spawn time.
 
-use rodio::{mixer::Mixer, Sink};
+use rodio::mixer::Mixer;
+use rodio::stream::OutputStreamConfig;
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, AtomicU64};
 use std::sync::{Arc, Mutex, MutexGuard};
 
 use crate::container::info::Info;
 use crate::container::prot::Prot;
+use crate::diagnostics::session::PlaySession;
+use crate::diagnostics::watchdog::DiagnosticsEvent;
 use crate::dsp::effects::AudioEffect;
+use crate::playback::clock_sync::ClockSync;
 use crate::playback::engine::{
-    DspChainMetrics, EffectSettingsCommand, InlineEffectsUpdate, InlineTrackMixUpdate,
-    PlaybackBufferSettings,
+    DecodePool, DspChainMetrics, EffectSettingsCommand, EffectTiming, InlineEffectsUpdate,
+    InlineTrackMixUpdate, OneShotVoice, PlaybackBufferSettings, ReaderCache, SharedLiveInput,
+    SharedVolumeRamp,
 };
+use crate::playback::gain_staging::SharedGainStaging;
 use crate::playback::mutex_policy::{lock_invariant, lock_recoverable};
 use crate::playback::output_meter::OutputMeter;
+use crate::playback::output_sink::{OutputSink, SharedOutputSink};
 use crate::playback::player::notify::WorkerNotify;
+use crate::playback::realization::RealizationRecorder;
+use crate::playback::track_meter::TrackLevels;
 
-use super::super::super::{EndOfStreamAction, PlayerState};
+use super::super::super::{EndOfStreamAction, PlayerState, ShuffleBoundary};
+
+// Destination of one playback run's audio.
+pub(in crate::playback::player::runtime) enum OutputTarget {
+    // Shared rodio device stream; the worker connects a fresh sink to its mixer.
+    Device {
+        mixer: Mixer,
+        config: OutputStreamConfig,
+    },
+    // Sink opened from the player's output backend before the worker started.
+    Backend,
+}
+
+impl OutputTarget {
+    // Device stream configuration, or `None` when a backend sink is in use.
+    pub(in crate::playback::player::runtime) fn device_config(
+        &self,
+    ) -> Option<&OutputStreamConfig> {
+        match self {
+            Self::Device { config, .. } => Some(config),
+            Self::Backend => None,
+        }
+    }
+}
 
 /// Captured shared state passed from `Player::initialize_thread` into the
 /// detached worker thread.
@@ -35,7 +68,11 @@ pub(in crate::playback::player::runtime) struct ThreadContext {
         Arc<Mutex<Option<InlineEffectsUpdate>>>,
     pub(in crate::playback::player::runtime) inline_track_mix_updates:
         Arc<Mutex<Vec<InlineTrackMixUpdate>>>,
+    pub(in crate::playback::player::runtime) inline_group_gains: Arc<Mutex<Option<Vec<f32>>>>,
     pub(in crate::playback::player::runtime) dsp_metrics: Arc<Mutex<DspChainMetrics>>,
+    pub(in crate::playback::player::runtime) track_levels: Arc<Mutex<Vec<TrackLevels>>>,
+    pub(in crate::playback::player::runtime) effect_timings: Arc<Mutex<Vec<EffectTiming>>>,
+    pub(in crate::playback::player::runtime) diagnostics_events: Arc<Mutex<Vec<DiagnosticsEvent>>>,
     pub(in crate::playback::player::runtime) effects_reset: Arc<AtomicU64>,
     pub(in crate::playback::player::runtime) output_meter: Arc<Mutex<OutputMeter>>,
     pub(in crate::playback::player::runtime) audio_info: Info,
@@ -44,12 +81,27 @@ pub(in crate::playback::player::runtime) struct ThreadContext {
     pub(in crate::playback::player::runtime) audio_heard: Arc<AtomicBool>,
     pub(in crate::playback::player::runtime) play_command_ms: Arc<AtomicU64>,
     pub(in crate::playback::player::runtime) volume: Arc<Mutex<f32>>,
-    pub(in crate::playback::player::runtime) sink_mutex: Arc<Mutex<Sink>>,
-    pub(in crate::playback::player::runtime) output_mixer: Mixer,
+    pub(in crate::playback::player::runtime) sink_mutex: SharedOutputSink,
+    pub(in crate::playback::player::runtime) output: OutputTarget,
     pub(in crate::playback::player::runtime) buffer_done_thread_flag: Arc<AtomicBool>,
     pub(in crate::playback::player::runtime) last_chunk_ms: Arc<AtomicU64>,
     pub(in crate::playback::player::runtime) last_time_update_ms: Arc<AtomicU64>,
     pub(in crate::playback::player::runtime) worker_notify: Arc<WorkerNotify>,
+    pub(in crate::playback::player::runtime) section_queue: Arc<Mutex<VecDeque<String>>>,
+    pub(in crate::playback::player::runtime) scheduled_shuffle: Arc<Mutex<Option<ShuffleBoundary>>>,
+    pub(in crate::playback::player::runtime) one_shots: Arc<Mutex<Vec<OneShotVoice>>>,
+    pub(in crate::playback::player::runtime) live_input: SharedLiveInput,
+    pub(in crate::playback::player::runtime) volume_ramp: SharedVolumeRamp,
+    pub(in crate::playback::player::runtime) gain_staging: SharedGainStaging,
+    pub(in crate::playback::player::runtime) session_stats: Arc<Mutex<PlaySession>>,
+    pub(in crate::playback::player::runtime) realization: Arc<Mutex<RealizationRecorder>>,
+    pub(in crate::playback::player::runtime) channel_map: Arc<Mutex<Vec<usize>>>,
+    pub(in crate::playback::player::runtime) clock_sync: Arc<Mutex<Option<ClockSync>>>,
+    #[cfg(feature = "link")]
+    pub(in crate::playback::player::runtime) link:
+        Arc<Mutex<Option<crate::playback::link::SharedLink>>>,
+    pub(in crate::playback::player::runtime) decode_pool: DecodePool,
+    pub(in crate::playback::player::runtime) reader_cache: ReaderCache,
 }
 
 impl ThreadContext {
@@ -62,6 +114,53 @@ impl ThreadContext {
         )
     }
 
+    /// Recoverable poison policy: session statistics are additive telemetry.
+    pub(super) fn lock_session_stats_recoverable(&self) -> MutexGuard<'_, PlaySession> {
+        lock_recoverable(
+            &self.session_stats,
+            "playback worker session stats",
+            "session statistics are additive telemetry",
+        )
+    }
+
+    /// Recoverable poison policy: the realization log is append-only telemetry.
+    pub(super) fn lock_realization_recoverable(&self) -> MutexGuard<'_, RealizationRecorder> {
+        lock_recoverable(
+            &self.realization,
+            "playback worker realization",
+            "the realization log is append-only telemetry",
+        )
+    }
+
+    /// Recoverable poison policy: the channel map is replaced wholesale on update.
+    pub(super) fn lock_channel_map_recoverable(&self) -> MutexGuard<'_, Vec<usize>> {
+        lock_recoverable(
+            &self.channel_map,
+            "playback worker channel map",
+            "the channel map is replaced wholesale on update",
+        )
+    }
+
+    /// Recoverable poison policy: the clock controller only smooths readings.
+    pub(super) fn lock_clock_sync_recoverable(&self) -> MutexGuard<'_, Option<ClockSync>> {
+        lock_recoverable(
+            &self.clock_sync,
+            "playback worker clock sync",
+            "the clock controller only smooths readings",
+        )
+    }
+
+    /// Recoverable poison policy: the Link handle is replaced wholesale.
+    #[cfg(feature = "link")]
+    pub(super) fn link(&self) -> Option<crate::playback::link::SharedLink> {
+        lock_recoverable(
+            &self.link,
+            "playback worker link",
+            "the Link handle is replaced wholesale",
+        )
+        .clone()
+    }
+
     /// Recoverable poison policy: playback time is scalar telemetry.
     pub(super) fn lock_time_passed_recoverable(&self) -> MutexGuard<'_, f64> {
         lock_recoverable(
@@ -139,7 +238,7 @@ impl ThreadContext {
     }
 
     /// Recoverable poison policy: the sink is disposable output state.
-    pub(super) fn lock_sink_recoverable(&self) -> MutexGuard<'_, Sink> {
+    pub(super) fn lock_sink_recoverable(&self) -> MutexGuard<'_, Box<dyn OutputSink>> {
         lock_recoverable(
             &self.sink_mutex,
             "playback worker sink",
@@ -0,0 +1,23 @@
+//! Output fold-down preview for appended chunks.
+
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+
+use super::context::ThreadContext;
+use crate::dsp::downmix::{downmix_in_place, DownmixMode};
+
+// Apply the configured downmix to one mixed chunk.
+//
+// Runs before metering so the output meters and correlation read the
+// folded-down signal that is actually heard.
+pub(super) fn apply_downmix(ctx: &ThreadContext, chunk: SamplesBuffer) -> SamplesBuffer {
+    let mode = ctx.lock_buffer_settings_recoverable().downmix;
+    if mode == DownmixMode::Off {
+        return chunk;
+    }
+    let channels = chunk.channels();
+    let sample_rate = chunk.sample_rate();
+    let mut samples: Vec<f32> = chunk.collect();
+    downmix_in_place(&mut samples, channels as usize, mode);
+    SamplesBuffer::new(channels, sample_rate, samples)
+}
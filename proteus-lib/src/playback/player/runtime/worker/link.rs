@@ -0,0 +1,37 @@
+//! Link beat-grid alignment for engines and section jumps.
+
+use super::context::ThreadContext;
+use crate::container::prot::BeatGrid;
+use crate::playback::link::link_beat_grid;
+use crate::playback::mutex_policy::lock_invariant;
+
+// Quantize the shuffle points of the engine about to start at `start_time`.
+//
+// `on_bar` marks a start known to be heard on a Link bar line, as a section
+// jump is; otherwise the session's current phase is laid under `start_time`.
+pub(super) fn align_engine_to_link(ctx: &ThreadContext, start_time: f64, on_bar: bool) {
+    let Some(link) = ctx.link() else {
+        return;
+    };
+    let grid = if on_bar {
+        BeatGrid::new(start_time, link.tempo(), link.quantum())
+    } else {
+        link_beat_grid(link.as_ref(), start_time)
+    };
+    lock_invariant(
+        &ctx.prot,
+        "playback worker prot",
+        "container selection and effect metadata must stay internally consistent",
+    )
+    .set_beat_grid(Some(grid));
+}
+
+// Move a section boundary to the first Link bar line at or after it.
+//
+// `heard_position` is the source position currently reaching the listener.
+pub(super) fn quantize_boundary(ctx: &ThreadContext, boundary: f64, heard_position: f64) -> f64 {
+    match ctx.link() {
+        Some(link) => link_beat_grid(link.as_ref(), heard_position).next_bar(boundary),
+        None => boundary,
+    }
+}
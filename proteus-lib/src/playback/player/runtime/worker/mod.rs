@@ -1,21 +1,33 @@
 //! Playback worker internals.
 //!
 //! This module is split so responsibilities are explicit:
+//! - [`clock`] applies varispeed while chasing an external clock.
 //! - [`context`] defines captured shared thread state.
 //! - [`guard`] tracks playback-thread liveness.
+//! - [`link`] aligns engines and section jumps to a Link session.
 //! - [`runner`] executes the long-running receive loop entry points.
+//! - [`routing`] maps engine channels onto device channels.
+//! - [`sections`] sequences engines across queued section jumps.
+//! - [`resample`] converts appended chunks to the device sample rate.
 //! - [`sink`] manages output stream and sink appends.
 //! - [`transitions`] applies transport-state changes.
 //! - [`timing`] maintains playback time and drain completion.
 
+mod clock;
 mod context;
+mod downmix;
 mod guard;
+#[cfg(feature = "link")]
+mod link;
+mod resample;
+mod routing;
 mod runner;
+mod sections;
 mod sink;
 mod timing;
 mod transitions;
 
-pub(in crate::playback::player::runtime) use context::ThreadContext;
+pub(in crate::playback::player::runtime) use context::{OutputTarget, ThreadContext};
 pub(in crate::playback::player::runtime) use runner::run_playback_thread;
 pub(in crate::playback::player::runtime) use sink::open_output_stream_with_retry;
 
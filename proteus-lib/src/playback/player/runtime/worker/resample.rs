@@ -0,0 +1,54 @@
+//! Device sample-rate conversion for appended output chunks.
+
+use super::context::ThreadContext;
+use super::runner::LoopState;
+use crate::dsp::resample::Resampler;
+use crate::playback::output_sink::OutputChunk;
+
+// Convert one mixed chunk to the device rate when a sinc resampler is selected.
+//
+// Chunks pass through untouched when the rates match, linear conversion is
+// selected (leaving any conversion to rodio), or a backend sink is in use. The resampler keeps its filter
+// history across chunks and is rebuilt when the quality or rates change.
+pub(super) fn convert_for_output(
+    ctx: &ThreadContext,
+    loop_state: &mut LoopState,
+    chunk: OutputChunk,
+) -> OutputChunk {
+    let quality = ctx.lock_buffer_settings_recoverable().output_resampler;
+    let input_rate = chunk.sample_rate;
+    let output_rate = ctx
+        .output
+        .device_config()
+        .map_or(input_rate, |config| config.sample_rate());
+    if !quality.is_sinc() || input_rate == output_rate || output_rate == 0 {
+        loop_state.output_resampler = None;
+        return chunk;
+    }
+
+    let channels = chunk.channels;
+    let stale = loop_state
+        .output_resampler
+        .as_ref()
+        .is_none_or(|resampler| {
+            resampler.quality() != quality || resampler.rates() != (input_rate, output_rate)
+        });
+    if stale {
+        loop_state.output_resampler = Some(Resampler::new(
+            quality,
+            input_rate,
+            output_rate,
+            channels as usize,
+        ));
+    }
+    let Some(resampler) = loop_state.output_resampler.as_mut() else {
+        return chunk;
+    };
+
+    let mut output = Vec::with_capacity(
+        (chunk.samples.len() as u64 * output_rate as u64 / input_rate as u64) as usize
+            + channels as usize,
+    );
+    resampler.process(&chunk.samples, &mut output);
+    OutputChunk::new(output, channels, output_rate)
+}
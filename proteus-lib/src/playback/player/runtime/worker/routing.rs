@@ -0,0 +1,52 @@
+//! Engine-to-device channel routing for appended output chunks.
+
+use super::context::ThreadContext;
+use crate::playback::output_sink::OutputChunk;
+
+// Route a chunk's channels onto the device channels selected by the channel map.
+//
+// Chunks pass through untouched when no map is set or a backend sink is in use. A map that no longer fits
+// the chunk or the opened device (validated again here because the stream may
+// have been opened after the map was set) is ignored.
+pub(super) fn route_channels(ctx: &ThreadContext, chunk: OutputChunk) -> OutputChunk {
+    let map = ctx.lock_channel_map_recoverable().clone();
+    if map.is_empty() {
+        return chunk;
+    }
+    let channels = chunk.channels as usize;
+    let Some(config) = ctx.output.device_config() else {
+        return chunk;
+    };
+    let device_channels = config.channel_count() as usize;
+    if map.len() != channels || map.iter().any(|target| *target >= device_channels) {
+        return chunk;
+    }
+    let output = map_channels(&chunk.samples, &map, device_channels);
+    OutputChunk::new(output, device_channels as u16, chunk.sample_rate)
+}
+
+// Spread interleaved `input` (one channel per map entry) across `device_channels`.
+fn map_channels(input: &[f32], map: &[usize], device_channels: usize) -> Vec<f32> {
+    let frames = input.len() / map.len();
+    let mut output = vec![0.0; frames * device_channels];
+    for (frame, out) in input
+        .chunks_exact(map.len())
+        .zip(output.chunks_exact_mut(device_channels))
+    {
+        for (sample, target) in frame.iter().zip(map) {
+            out[*target] = *sample;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::map_channels;
+
+    #[test]
+    fn stereo_is_routed_to_outputs_three_and_four() {
+        let output = map_channels(&[0.1, 0.2, 0.3, 0.4], &[2, 3], 4);
+        assert_eq!(output, vec![0.0, 0.0, 0.1, 0.2, 0.0, 0.0, 0.3, 0.4]);
+    }
+}
@@ -1,6 +1,5 @@
 //! Playback worker loop implementation.
 
-use rodio::buffer::SamplesBuffer;
 use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::MutexGuard;
@@ -9,12 +8,14 @@ use std::time::Instant;
 
 use log::debug;
 
+use crate::dsp::resample::Resampler;
 use crate::playback::engine::{PlayerEngine, PlayerEngineConfig};
 use crate::playback::mutex_policy::lock_recoverable;
 use crate::tools::timer;
 
 use super::context::ThreadContext;
 use super::guard::PlaybackThreadGuard;
+use super::sections::SectionSequencer;
 use super::sink::{append_startup_silence, initialize_sink, update_sink};
 #[cfg(feature = "debug")]
 use super::timing::log_drain_loop_start;
@@ -33,6 +34,7 @@ pub(super) struct LoopState {
     pub(super) last_meter_time: f64,
     pub(super) append_timing: Arc<Mutex<(Instant, f64, u64, f64)>>,
     pub(super) resuming_gate_started_at: Option<Instant>,
+    pub(super) output_resampler: Option<Resampler>,
 }
 
 impl LoopState {
@@ -57,6 +59,7 @@ impl LoopState {
             last_meter_time: 0.0,
             append_timing: Arc::new(Mutex::new((Instant::now(), 0.0, 0, 0.0))),
             resuming_gate_started_at: None,
+            output_resampler: None,
         }
     }
 
@@ -129,36 +132,23 @@ pub(in crate::playback::player::runtime) fn run_playback_thread(
         );
     }
 
-    let mut engine = PlayerEngine::new(
-        ctx.prot.clone(),
-        PlayerEngineConfig {
-            abort_option: Some(ctx.abort.clone()),
-            start_time,
-            buffer_settings: ctx.buffer_settings.clone(),
-            effects: ctx.effects.clone(),
-            dsp_metrics: ctx.dsp_metrics.clone(),
-            effect_settings_commands: ctx.effect_settings_commands.clone(),
-            effects_reset: ctx.effects_reset.clone(),
-            inline_effects_update: ctx.inline_effects_update.clone(),
-            inline_track_mix_updates: ctx.inline_track_mix_updates.clone(),
-        },
-    );
-
-    initialize_sink(&ctx, &ctx.output_mixer);
+    initialize_sink(&ctx);
     if let Some(elapsed_ms) = play_trace_elapsed_ms(&ctx) {
         debug!("play trace: sink initialized +{}ms", elapsed_ms);
     }
-    set_duration_from_engine(&ctx, &engine);
+
+    let mut sequencer = SectionSequencer::start(&ctx, start_time);
+    set_duration_from_engine(&ctx, sequencer.engine());
     set_start_time(&ctx, start_time);
+    record_run_start(&ctx, start_time);
     append_startup_silence(&ctx);
 
     let mut loop_state = LoopState::new(start_time);
 
-    let receiver = engine.start_receiver();
     if let Some(elapsed_ms) = play_trace_elapsed_ms(&ctx) {
         debug!("play trace: engine receiver started +{}ms", elapsed_ms);
     }
-    run_engine_receive_loop(&ctx, &mut loop_state, playback_id, receiver);
+    run_engine_receive_loop(&ctx, &mut loop_state, playback_id, &mut sequencer);
     #[cfg(feature = "debug")]
     log::info!("engine reception loop finished");
 
@@ -167,7 +157,7 @@ pub(in crate::playback::player::runtime) fn run_playback_thread(
     #[cfg(feature = "debug")]
     log_drain_loop_start(&ctx, &loop_state);
 
-    let drain_completed = run_drain_loop(&ctx, &mut loop_state, &engine);
+    let drain_completed = run_drain_loop(&ctx, &mut loop_state, sequencer.engine());
 
     #[cfg(feature = "debug")]
     log::info!("finished drain loop");
@@ -181,15 +171,15 @@ fn run_engine_receive_loop(
     ctx: &ThreadContext,
     loop_state: &mut LoopState,
     playback_id: u64,
-    receiver: std::sync::mpsc::Receiver<(SamplesBuffer, f64)>,
+    sequencer: &mut SectionSequencer,
 ) {
     let mut logged_first_engine_chunk = false;
     loop {
         if ctx.abort.load(Ordering::SeqCst) {
             break;
         }
-        match receiver.recv() {
-            Ok(chunk) => {
+        match sequencer.recv(ctx) {
+            Some(chunk) => {
                 if !logged_first_engine_chunk {
                     logged_first_engine_chunk = true;
                     if let Some(elapsed_ms) = play_trace_elapsed_ms(ctx) {
@@ -201,11 +191,49 @@ fn run_engine_receive_loop(
                     break;
                 }
             }
-            Err(_) => break,
+            None => break,
         }
     }
 }
 
+// Build an engine for this run starting at `start_time`.
+//
+// # Arguments
+//
+// * `ctx` - Shared worker context providing engine inputs.
+// * `start_time` - Source position in seconds.
+// * `abort` - Abort flag for the engine; `None` creates a private one.
+pub(super) fn new_engine(
+    ctx: &ThreadContext,
+    start_time: f64,
+    abort: Option<Arc<AtomicBool>>,
+) -> PlayerEngine {
+    PlayerEngine::new(
+        ctx.prot.clone(),
+        PlayerEngineConfig {
+            abort_option: abort,
+            start_time,
+            buffer_settings: ctx.buffer_settings.clone(),
+            effects: ctx.effects.clone(),
+            dsp_metrics: ctx.dsp_metrics.clone(),
+            track_levels: ctx.track_levels.clone(),
+            effect_timings: ctx.effect_timings.clone(),
+            diagnostics_events: ctx.diagnostics_events.clone(),
+            effect_settings_commands: ctx.effect_settings_commands.clone(),
+            effects_reset: ctx.effects_reset.clone(),
+            inline_effects_update: ctx.inline_effects_update.clone(),
+            inline_track_mix_updates: ctx.inline_track_mix_updates.clone(),
+            inline_group_gains: ctx.inline_group_gains.clone(),
+            one_shots: ctx.one_shots.clone(),
+            live_input: ctx.live_input.clone(),
+            volume_ramp: ctx.volume_ramp.clone(),
+            gain_staging: ctx.gain_staging.clone(),
+            decode_pool: ctx.decode_pool.clone(),
+            reader_cache: ctx.reader_cache.clone(),
+        },
+    )
+}
+
 // Snapshot the total engine duration into shared player state.
 //
 // # Arguments
@@ -228,6 +256,24 @@ fn set_start_time(ctx: &ThreadContext, start_time: f64) {
     *time_passed = start_time;
 }
 
+// Open a realization span for a run starting at `start_time`.
+//
+// The first run of a session fades in over the startup fade; later runs are
+// restarts that fade in over the seek fade.
+fn record_run_start(ctx: &ThreadContext, start_time: f64) {
+    let (startup_fade_ms, seek_fade_in_ms) = {
+        let settings = ctx.lock_buffer_settings_recoverable();
+        (settings.startup_fade_ms, settings.seek_fade_in_ms)
+    };
+    let mut realization = ctx.lock_realization_recoverable();
+    let fade_ms = if realization.is_empty() {
+        startup_fade_ms
+    } else {
+        seek_fade_in_ms
+    };
+    realization.begin_run(start_time, fade_ms);
+}
+
 #[cfg(test)]
 mod tests {
     use super::LoopState;
@@ -0,0 +1,546 @@
+//! Section sequencing and deferred shuffles across playback engines.
+//!
+//! Without declared sections or a scheduled shuffle the sequencer forwards
+//! engine chunks unchanged. Otherwise it tracks the source position of every
+//! chunk. Once a section is queued and the current section's end comes within
+//! pre-roll range, a second engine is started at the queued section's start.
+//! A scheduled shuffle starts the second engine at the handoff position
+//! instead, on the freshly drawn selection. The two engines are crossfaded
+//! across the boundary and playback continues from the new engine.
+
+use std::collections::VecDeque;
+use std::f32::consts::FRAC_PI_2;
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use log::{debug, warn};
+use rodio::buffer::SamplesBuffer;
+use rodio::Source;
+
+use crate::container::prot::{BeatGrid, TimelineSection};
+use crate::playback::engine::{OutputReceiver, PlayerEngine};
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::player::ShuffleBoundary;
+
+use super::context::ThreadContext;
+use super::runner::new_engine;
+
+/// Seconds before a crossfade starts at which the next engine is started.
+const SECTION_PREROLL_SECONDS: f64 = 1.0;
+
+type Chunk = (SamplesBuffer, f64);
+
+/// Chunk ready for the worker, with the section start it jumps to, if any.
+type ReadyChunk = (Chunk, Option<f64>);
+
+/// One running engine and the source position of its next chunk.
+struct EngineRun {
+    // Declared before `engine` so the channel closes before the engine
+    // joins its mix thread on drop.
+    receiver: OutputReceiver,
+    engine: PlayerEngine,
+    position: f64,
+}
+
+impl EngineRun {
+    fn start(ctx: &ThreadContext, start_time: f64, abort: Option<Arc<AtomicBool>>) -> Self {
+        let mut engine = new_engine(ctx, start_time, abort);
+        let receiver = engine.start_receiver();
+        Self {
+            receiver,
+            engine,
+            position: start_time,
+        }
+    }
+}
+
+/// Engine pre-rolled for a queued section or a scheduled shuffle.
+struct PendingJump {
+    run: EngineRun,
+    /// Source position in the current run where the crossfade starts.
+    fade_start: f64,
+    /// Source position in the current run where it is cut.
+    boundary: f64,
+    /// Interleaved samples of the current run between `fade_start` and `boundary`.
+    tail: Vec<f32>,
+    /// `true` for a shuffle, which stays on the same timeline: it is not
+    /// reported as a jump and fades linearly, since most tracks play the
+    /// same audio in both engines.
+    shuffle: bool,
+}
+
+/// Scheduled shuffle resolved to source positions in the current run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ShuffleHandoff {
+    boundary: ShuffleBoundary,
+    fade_start: f64,
+    cut: f64,
+}
+
+/// Chunk source for the worker receive loop.
+pub(super) struct SectionSequencer {
+    sections: Vec<TimelineSection>,
+    queue: Arc<Mutex<VecDeque<String>>>,
+    shuffle: Arc<Mutex<Option<ShuffleBoundary>>>,
+    /// Handoff resolved for the shuffle in `shuffle`, while it waits.
+    handoff: Option<ShuffleHandoff>,
+    crossfade_seconds: f64,
+    current: EngineRun,
+    pending: Option<PendingJump>,
+    ready: VecDeque<ReadyChunk>,
+    /// Timeline position after the last emitted chunk, used to estimate
+    /// how far ahead of the listener the sequencer runs.
+    #[cfg(feature = "link")]
+    emitted_until: f64,
+}
+
+impl SectionSequencer {
+    /// Start the first engine at `start_time`.
+    ///
+    /// Every engine gets its own abort flag, since any run may be handed
+    /// over to another and a retired engine must stop without ending
+    /// playback.
+    pub(super) fn start(ctx: &ThreadContext, start_time: f64) -> Self {
+        let sections = lock_recoverable(
+            &ctx.prot,
+            "section sequencer prot",
+            "section metadata is read-only during playback",
+        )
+        .get_sections();
+        let abort = Some(Arc::new(AtomicBool::new(false)));
+        let crossfade_ms = ctx.lock_buffer_settings_recoverable().section_crossfade_ms;
+        #[cfg(feature = "link")]
+        super::link::align_engine_to_link(ctx, start_time, false);
+        Self {
+            sections,
+            queue: ctx.section_queue.clone(),
+            shuffle: ctx.scheduled_shuffle.clone(),
+            handoff: None,
+            crossfade_seconds: f64::from(crossfade_ms.max(0.0)) / 1000.0,
+            current: EngineRun::start(ctx, start_time, abort),
+            pending: None,
+            ready: VecDeque::new(),
+            #[cfg(feature = "link")]
+            emitted_until: start_time,
+        }
+    }
+
+    /// Engine currently producing audio.
+    pub(super) fn engine(&self) -> &PlayerEngine {
+        &self.current.engine
+    }
+
+    /// Receive the next output chunk, or `None` once playback has ended.
+    pub(super) fn recv(&mut self, ctx: &ThreadContext) -> Option<Chunk> {
+        loop {
+            if let Some((chunk, jump_to)) = self.ready.pop_front() {
+                if let Some(target_s) = jump_to {
+                    let fade_ms = (self.crossfade_seconds * 1000.0) as f32;
+                    ctx.lock_realization_recoverable().jump(target_s, fade_ms);
+                }
+                return Some(chunk);
+            }
+            self.prepare_jump(ctx);
+            self.prepare_shuffle(ctx);
+            match self.current.receiver.recv() {
+                Ok(chunk) if self.sections.is_empty() && self.pending.is_none() => {
+                    self.current.position += chunk.1;
+                    #[cfg(feature = "link")]
+                    {
+                        self.emitted_until += chunk.1;
+                    }
+                    return Some(chunk);
+                }
+                Ok((buffer, _)) => self.route(buffer),
+                Err(_) => {
+                    if self.pending.is_none() {
+                        let position = self.current.position;
+                        self.start_jump(ctx, position, position);
+                    }
+                    let pending = self.pending.take()?;
+                    self.complete_jump(pending);
+                }
+            }
+        }
+    }
+
+    fn lock_queue(&self) -> MutexGuard<'_, VecDeque<String>> {
+        lock_recoverable(
+            &self.queue,
+            "section queue",
+            "queued section names are a plain request list",
+        )
+    }
+
+    fn lock_shuffle(&self) -> MutexGuard<'_, Option<ShuffleBoundary>> {
+        lock_recoverable(
+            &self.shuffle,
+            "scheduled shuffle",
+            "a scheduled shuffle is a plain request slot",
+        )
+    }
+
+    /// Start an engine on the redrawn selection once the scheduled shuffle's
+    /// handoff is within pre-roll range.
+    ///
+    /// The request stays visible to the player until its engine starts, so
+    /// a later request with another boundary is resolved again.
+    fn prepare_shuffle(&mut self, ctx: &ThreadContext) {
+        if self.pending.is_some() {
+            return;
+        }
+        let Some(boundary) = *self.lock_shuffle() else {
+            self.handoff = None;
+            return;
+        };
+        let handoff = match self.handoff {
+            Some(handoff) if handoff.boundary == boundary => handoff,
+            _ => match self.resolve_handoff(ctx, boundary) {
+                Some(handoff) => *self.handoff.insert(handoff),
+                None => {
+                    debug!(
+                        "no {:?} boundary ahead; dropping scheduled shuffle",
+                        boundary
+                    );
+                    self.lock_shuffle().take();
+                    return;
+                }
+            },
+        };
+        if self.current.position < handoff.fade_start - SECTION_PREROLL_SECONDS {
+            return;
+        }
+        self.lock_shuffle().take();
+        self.handoff = None;
+        debug!(
+            "shuffle handoff at {:?} ({:.3}s to {:.3}s)",
+            boundary, handoff.fade_start, handoff.cut
+        );
+        let abort = Some(Arc::new(AtomicBool::new(false)));
+        self.pending = Some(PendingJump {
+            run: EngineRun::start(ctx, handoff.fade_start, abort),
+            fade_start: handoff.fade_start,
+            boundary: handoff.cut,
+            tail: Vec::new(),
+            shuffle: true,
+        });
+    }
+
+    fn resolve_handoff(
+        &self,
+        ctx: &ThreadContext,
+        boundary: ShuffleBoundary,
+    ) -> Option<ShuffleHandoff> {
+        let crossfade_ms = ctx.lock_buffer_settings_recoverable().shuffle_crossfade_ms;
+        let (grid, shuffle_points) = {
+            let prot = lock_recoverable(
+                &ctx.prot,
+                "section sequencer prot",
+                "section metadata is read-only during playback",
+            );
+            let grid = prot.beat_grid().or_else(|| {
+                prot.get_tempo_bpm()
+                    .map(|bpm| BeatGrid::new(0.0, f64::from(bpm), 4.0))
+            });
+            let points: Vec<f64> = prot
+                .get_shuffle_schedule()
+                .iter()
+                .map(|(at_seconds, _)| *at_seconds)
+                .collect();
+            (grid, points)
+        };
+        shuffle_handoff(
+            boundary,
+            self.current.position,
+            f64::from(crossfade_ms.max(0.0)) / 1000.0,
+            grid,
+            &shuffle_points,
+        )
+    }
+
+    /// Start the next engine once the upcoming boundary is within pre-roll range.
+    fn prepare_jump(&mut self, ctx: &ThreadContext) {
+        if self.pending.is_some() || self.lock_queue().is_empty() {
+            return;
+        }
+        let Some(boundary) = self.next_boundary() else {
+            return;
+        };
+        #[cfg(feature = "link")]
+        let boundary = {
+            let ahead = (self.emitted_until - *ctx.lock_time_passed_recoverable()).max(0.0);
+            super::link::quantize_boundary(ctx, boundary, self.current.position - ahead)
+        };
+        let fade_start = (boundary - self.crossfade_seconds).max(self.current.position);
+        if self.current.position >= fade_start - SECTION_PREROLL_SECONDS {
+            self.start_jump(ctx, fade_start, boundary);
+        }
+    }
+
+    fn start_jump(&mut self, ctx: &ThreadContext, fade_start: f64, boundary: f64) {
+        let Some(target) = self.pop_queued() else {
+            return;
+        };
+        debug!(
+            "section jump to {} ({:.3}s) at {:.3}s",
+            target.name, target.start_seconds, boundary
+        );
+        let abort = Some(Arc::new(AtomicBool::new(false)));
+        #[cfg(feature = "link")]
+        super::link::align_engine_to_link(ctx, target.start_seconds, true);
+        self.pending = Some(PendingJump {
+            run: EngineRun::start(ctx, target.start_seconds, abort),
+            fade_start,
+            boundary,
+            tail: Vec::new(),
+            shuffle: false,
+        });
+    }
+
+    fn pop_queued(&mut self) -> Option<TimelineSection> {
+        loop {
+            let name = self.lock_queue().pop_front()?;
+            match self.sections.iter().find(|section| section.name == name) {
+                Some(section) => return Some(section.clone()),
+                None => warn!("skipping unknown queued section: {}", name),
+            }
+        }
+    }
+
+    /// End of the section playing now, or the start of the next one.
+    fn next_boundary(&self) -> Option<f64> {
+        let position = self.current.position;
+        if let Some(section) = self
+            .sections
+            .iter()
+            .find(|section| section.contains(position))
+        {
+            return Some(section.end_seconds);
+        }
+        self.sections
+            .iter()
+            .map(|section| section.start_seconds)
+            .filter(|start| *start > position)
+            .min_by(f64::total_cmp)
+    }
+
+    /// Split a chunk of the current run around a pending crossfade.
+    fn route(&mut self, buffer: SamplesBuffer) {
+        let channels = usize::from(buffer.channels().max(1));
+        let sample_rate = buffer.sample_rate();
+        let samples: Vec<f32> = buffer.collect();
+        let frames = samples.len() / channels;
+        let chunk_start = self.current.position;
+        self.current.position += frames as f64 / f64::from(sample_rate);
+
+        let Some(pending) = self.pending.as_mut() else {
+            self.emit(channels, sample_rate, samples, None);
+            return;
+        };
+        let frame_at = |seconds: f64| {
+            let frame = ((seconds - chunk_start) * f64::from(sample_rate)).round();
+            (frame.max(0.0) as usize).min(frames) * channels
+        };
+        let fade_at = frame_at(pending.fade_start);
+        let cut_at = frame_at(pending.boundary).max(fade_at);
+        pending.tail.extend_from_slice(&samples[fade_at..cut_at]);
+        let reached_boundary = self.current.position >= pending.boundary;
+
+        self.emit(channels, sample_rate, samples[..fade_at].to_vec(), None);
+        if reached_boundary {
+            if let Some(pending) = self.pending.take() {
+                self.complete_jump(pending);
+            }
+        }
+    }
+
+    /// Crossfade the collected tail into the new run and make it current.
+    fn complete_jump(&mut self, pending: PendingJump) {
+        let PendingJump {
+            mut run,
+            tail,
+            shuffle,
+            ..
+        } = pending;
+        let mut head = Vec::with_capacity(tail.len());
+        let mut format = None;
+        while head.len() < tail.len().max(1) {
+            let Ok((buffer, _)) = run.receiver.recv() else {
+                break;
+            };
+            format = Some((usize::from(buffer.channels().max(1)), buffer.sample_rate()));
+            head.extend(buffer);
+        }
+
+        if let Some((channels, sample_rate)) = format {
+            let target_s = run.position;
+            run.position += (head.len() / channels) as f64 / f64::from(sample_rate);
+            if shuffle {
+                crossfade_linear_into(&tail, &mut head, channels);
+                self.emit(channels, sample_rate, head, None);
+            } else {
+                crossfade_into(&tail, &mut head, channels);
+                self.emit(channels, sample_rate, head, Some(target_s));
+            }
+        }
+        let retired = std::mem::replace(&mut self.current, run);
+        // Joining the old mix thread can take a moment; keep it off the
+        // worker thread so appends are not delayed.
+        std::thread::spawn(move || drop(retired));
+    }
+
+    fn emit(&mut self, channels: usize, sample_rate: u32, samples: Vec<f32>, jump_to: Option<f64>) {
+        if samples.is_empty() {
+            return;
+        }
+        let seconds = (samples.len() / channels) as f64 / f64::from(sample_rate);
+        #[cfg(feature = "link")]
+        {
+            self.emitted_until += seconds;
+        }
+        self.ready.push_back((
+            (
+                SamplesBuffer::new(channels as u16, sample_rate, samples),
+                seconds,
+            ),
+            jump_to,
+        ));
+    }
+}
+
+/// Resolve where a shuffle at the next `boundary` fades in and cuts over,
+/// as source positions of a run now at `position`.
+///
+/// Chunk and beat handoffs crossfade over `crossfade_s` ending on the
+/// boundary; a shuffle-point handoff cuts right at the point, where the
+/// selections already switch. Returns `None` when no shuffle point lies ahead.
+fn shuffle_handoff(
+    boundary: ShuffleBoundary,
+    position: f64,
+    crossfade_s: f64,
+    grid: Option<BeatGrid>,
+    shuffle_points: &[f64],
+) -> Option<ShuffleHandoff> {
+    let (fade_start, cut) = match (boundary, grid) {
+        (ShuffleBoundary::Beat, Some(grid)) => {
+            let beat = grid.next_beat(position + crossfade_s);
+            (beat - crossfade_s, beat)
+        }
+        (ShuffleBoundary::Chunk | ShuffleBoundary::Beat, _) => (position, position + crossfade_s),
+        (ShuffleBoundary::ShufflePoint, _) => {
+            let point = shuffle_points
+                .iter()
+                .copied()
+                .find(|at_seconds| *at_seconds > position)?;
+            (point, point)
+        }
+    };
+    Some(ShuffleHandoff {
+        boundary,
+        fade_start,
+        cut,
+    })
+}
+
+/// Linear crossfade from `tail` into the start of `head`, in place.
+///
+/// Used where both runs largely play the same audio, which an equal-power
+/// fade would boost mid-way. Samples of `tail` past the end of `head` are
+/// faded against silence.
+fn crossfade_linear_into(tail: &[f32], head: &mut Vec<f32>, channels: usize) {
+    if head.len() < tail.len() {
+        head.resize(tail.len(), 0.0);
+    }
+    let frames = tail.len() / channels;
+    for (frame, (tail_frame, head_frame)) in tail
+        .chunks(channels)
+        .zip(head.chunks_mut(channels))
+        .enumerate()
+    {
+        let fade_in = (frame as f32 + 0.5) / frames as f32;
+        for (out, old) in head_frame.iter_mut().zip(tail_frame) {
+            *out = *out * fade_in + *old * (1.0 - fade_in);
+        }
+    }
+}
+
+/// Equal-power crossfade from `tail` into the start of `head`, in place.
+///
+/// Samples of `tail` past the end of `head` are faded against silence.
+fn crossfade_into(tail: &[f32], head: &mut Vec<f32>, channels: usize) {
+    if head.len() < tail.len() {
+        head.resize(tail.len(), 0.0);
+    }
+    let frames = tail.len() / channels;
+    for (frame, (tail_frame, head_frame)) in tail
+        .chunks(channels)
+        .zip(head.chunks_mut(channels))
+        .enumerate()
+    {
+        let t = (frame as f32 + 0.5) / frames as f32 * FRAC_PI_2;
+        let (fade_in, fade_out) = t.sin_cos();
+        for (out, old) in head_frame.iter_mut().zip(tail_frame) {
+            *out = *out * fade_in + *old * fade_out;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crossfade_moves_from_tail_to_head() {
+        let tail = vec![1.0_f32; 8];
+        let mut head = vec![0.0_f32; 12];
+        crossfade_into(&tail, &mut head, 2);
+        assert!(head[0] > 0.9);
+        assert!(head[7] < 0.4);
+        assert!(head[8..].iter().all(|sample| *sample == 0.0));
+        assert_eq!(head[0], head[1]);
+    }
+
+    #[test]
+    fn crossfade_pads_short_head_with_silence() {
+        let tail = vec![0.5_f32; 4];
+        let mut head = vec![1.0_f32; 2];
+        crossfade_into(&tail, &mut head, 1);
+        assert_eq!(head.len(), 4);
+        assert!(head[3] < 0.5);
+    }
+
+    #[test]
+    fn linear_crossfade_keeps_identical_audio_at_unity() {
+        let tail = vec![0.5_f32; 8];
+        let mut head = vec![0.5_f32; 8];
+        crossfade_linear_into(&tail, &mut head, 2);
+        assert!(head.iter().all(|sample| (*sample - 0.5).abs() < 1e-6));
+    }
+
+    #[test]
+    fn chunk_handoff_fades_in_from_the_current_position() {
+        let handoff = shuffle_handoff(ShuffleBoundary::Chunk, 10.0, 0.25, None, &[]).unwrap();
+        assert_eq!((handoff.fade_start, handoff.cut), (10.0, 10.25));
+    }
+
+    #[test]
+    fn beat_handoff_crossfades_into_the_next_beat_or_falls_back_to_chunk() {
+        let grid = BeatGrid::new(0.0, 120.0, 4.0);
+        let handoff = shuffle_handoff(ShuffleBoundary::Beat, 10.1, 0.25, Some(grid), &[]).unwrap();
+        assert!((handoff.cut - 10.5).abs() < 1e-9);
+        assert!((handoff.fade_start - 10.25).abs() < 1e-9);
+        let fallback = shuffle_handoff(ShuffleBoundary::Beat, 10.1, 0.25, None, &[]).unwrap();
+        assert_eq!(fallback.fade_start, 10.1);
+    }
+
+    #[test]
+    fn shuffle_point_handoff_cuts_at_the_next_point() {
+        let points = [0.0, 8.0, 16.0];
+        let handoff =
+            shuffle_handoff(ShuffleBoundary::ShufflePoint, 9.0, 0.25, None, &points).unwrap();
+        assert_eq!((handoff.fade_start, handoff.cut), (16.0, 16.0));
+        assert!(
+            shuffle_handoff(ShuffleBoundary::ShufflePoint, 16.0, 0.25, None, &points).is_none()
+        );
+    }
+}
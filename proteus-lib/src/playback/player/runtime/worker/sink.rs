@@ -1,35 +1,58 @@
 //! Sink and output-stream management helpers for the playback worker.
 
 use rodio::buffer::SamplesBuffer;
+use rodio::cpal::BufferSize;
 use rodio::{OutputStream, OutputStreamBuilder, Sink};
+
 use std::sync::atomic::Ordering;
 use std::thread;
 use std::time::Duration;
 
 use log::{debug, error, warn};
 
-use super::context::ThreadContext;
+use super::clock::apply_clock_sync;
+use super::context::{OutputTarget, ThreadContext};
+use super::downmix::apply_downmix;
+use super::resample::convert_for_output;
+use super::routing::route_channels;
 use super::runner::LoopState;
 use super::timing::{update_append_timing, update_chunk_lengths};
 use super::transitions::check_runtime_state;
+use crate::playback::output_sink::{OutputChunk, OutputSink};
 use crate::playback::player::runtime::now_ms;
-use crate::playback::player::{OUTPUT_STREAM_OPEN_RETRIES, OUTPUT_STREAM_OPEN_RETRY_MS};
+use crate::playback::player::{
+    OutputMode, OUTPUT_STREAM_OPEN_RETRIES, OUTPUT_STREAM_OPEN_RETRY_MS,
+};
 
 // Open the default output stream with bounded retry behavior.
 //
 // # Returns
 //
 // `Some(OutputStream)` on success, otherwise `None` after all retries fail.
-pub(in crate::playback::player::runtime) fn open_output_stream_with_retry() -> Option<OutputStream>
-{
+pub(in crate::playback::player::runtime) fn open_output_stream_with_retry(
+    mode: OutputMode,
+) -> Option<OutputStream> {
     open_output_stream_with_retry_hooks(
         OUTPUT_STREAM_OPEN_RETRIES,
         OUTPUT_STREAM_OPEN_RETRY_MS,
-        OutputStreamBuilder::open_default_stream,
+        || open_output_stream(mode),
         thread::sleep,
     )
 }
 
+// Open the default device, requesting a fixed buffer size in low-latency modes.
+fn open_output_stream(mode: OutputMode) -> Result<OutputStream, rodio::StreamError> {
+    let Some(buffer_frames) = mode.buffer_frames() else {
+        return OutputStreamBuilder::open_default_stream();
+    };
+    if matches!(mode, OutputMode::Exclusive { .. }) {
+        warn!("exclusive output is not available from the audio backend; opening a shared low-latency stream");
+    }
+    OutputStreamBuilder::from_default_device()?
+        .with_buffer_size(BufferSize::Fixed(buffer_frames))
+        .open_stream_or_fallback()
+}
+
 fn open_output_stream_with_retry_hooks<Open, Sleep>(
     retries: usize,
     retry_ms: u64,
@@ -69,10 +92,12 @@ where
     sleep_fn(Duration::from_millis(retry_ms));
 }
 
-// Recreate and initialize the sink connected to the active mixer.
-pub(super) fn initialize_sink(ctx: &ThreadContext, mixer: &rodio::mixer::Mixer) {
+// Prepare the run's sink, reconnecting to the device mixer when one is used.
+pub(super) fn initialize_sink(ctx: &ThreadContext) {
     let mut sink = ctx.lock_sink_recoverable();
-    *sink = Sink::connect_new(mixer);
+    if let OutputTarget::Device { mixer, .. } = &ctx.output {
+        *sink = Box::new(Sink::connect_new(mixer));
+    }
     sink.pause();
     sink.set_volume(0.0);
 }
@@ -88,7 +113,7 @@ pub(super) fn append_startup_silence(ctx: &ThreadContext) {
     let channels = ctx.audio_info.channels as u16;
     let samples =
         ((startup_silence_ms / 1000.0) * sample_rate as f32).ceil() as usize * channels as usize;
-    let silence_buffer = SamplesBuffer::new(channels, sample_rate, vec![0.0_f32; samples.max(1)]);
+    let silence_buffer = OutputChunk::new(vec![0.0_f32; samples.max(1)], channels, sample_rate);
     ctx.lock_sink_recoverable().append(silence_buffer);
 }
 
@@ -96,7 +121,7 @@ pub(super) fn append_startup_silence(ctx: &ThreadContext) {
 pub(super) fn pause_sink(
     ctx: &ThreadContext,
     loop_state: &LoopState,
-    sink: &Sink,
+    sink: &dyn OutputSink,
     fade_seconds: f32,
 ) {
     let timestamp = *ctx.lock_time_passed_recoverable();
@@ -110,7 +135,7 @@ pub(super) fn pause_sink(
 }
 
 // Start or resume sink playback with optional fade-in.
-pub(super) fn resume_sink(ctx: &ThreadContext, sink: &Sink, fade_seconds: f32) {
+pub(super) fn resume_sink(ctx: &ThreadContext, sink: &dyn OutputSink, fade_seconds: f32) {
     let target_volume = *ctx.lock_volume_recoverable();
     if let Some(elapsed_ms) = super::timing::play_trace_elapsed_ms(ctx) {
         debug!(
@@ -232,10 +257,13 @@ pub(super) fn update_sink(
         );
     }
     // Release: publish first-chunk event to any Acquire load of audio_heard.
-    ctx.audio_heard.store(true, Ordering::Release);
+    let was_heard = ctx.audio_heard.swap(true, Ordering::AcqRel);
     ctx.last_chunk_ms.store(now, Ordering::Relaxed);
 
+    let mixer = apply_downmix(ctx, mixer);
     ctx.lock_output_meter_recoverable().push_samples(&mixer);
+    let output = route_channels(ctx, convert_for_output(ctx, loop_state, mixer.into()));
+    let output = apply_clock_sync(ctx, output);
 
     {
         let mut metrics = ctx.lock_dsp_metrics_recoverable();
@@ -257,8 +285,10 @@ pub(super) fn update_sink(
         );
     }
 
-    sink.append(mixer);
+    let starved = was_heard && sink.is_empty() && !sink.is_paused();
+    sink.append(output);
     drop(sink);
+    record_session_output(ctx, length_in_seconds, starved);
     loop_state
         .lock_chunk_lengths_recoverable()
         .push_back(length_in_seconds);
@@ -275,6 +305,19 @@ pub(super) fn update_sink(
     }
 }
 
+// Credit one appended chunk to the session statistics.
+fn record_session_output(ctx: &ThreadContext, length_in_seconds: f64, starved: bool) {
+    let position = *ctx.lock_time_passed_recoverable();
+    let mut session = ctx.lock_session_stats_recoverable();
+    session.record_output(position, length_in_seconds);
+    if starved {
+        session.record_underrun();
+    }
+    drop(session);
+    ctx.lock_realization_recoverable()
+        .record_output(length_in_seconds);
+}
+
 #[cfg(test)]
 mod tests {
     use super::open_output_stream_with_retry_hooks;
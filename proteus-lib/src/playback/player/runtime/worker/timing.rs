@@ -131,7 +131,7 @@ pub(super) fn is_drain_complete(
         return false;
     }
 
-    if ctx.lock_sink_recoverable().empty() {
+    if ctx.lock_sink_recoverable().is_empty() {
         return true;
     }
 
@@ -147,7 +147,7 @@ pub(super) fn is_drain_complete(
 pub(super) fn log_drain_loop_start(ctx: &ThreadContext, loop_state: &LoopState) {
     let sink = ctx.lock_sink_recoverable();
     let paused = sink.is_paused();
-    let empty = sink.empty();
+    let empty = sink.is_empty();
     let sink_len = sink.len();
     drop(sink);
 
@@ -1,6 +1,6 @@
 //! Playback-state transition helpers for the worker loop.
 
-use rodio::Sink;
+use crate::playback::output_sink::OutputSink;
 use std::sync::atomic::Ordering;
 use std::time::Instant;
 
@@ -22,13 +22,13 @@ pub(super) fn check_runtime_state(ctx: &ThreadContext, loop_state: &mut LoopStat
     let start_sink_chunks = ctx.lock_buffer_settings_recoverable().start_sink_chunks;
     let sink = ctx.lock_sink_recoverable();
 
-    if handle_resuming_gate(state, start_sink_chunks, &sink, loop_state) {
+    if handle_resuming_gate(state, start_sink_chunks, &**sink, loop_state) {
         return true;
     }
-    if handle_pausing(ctx, loop_state, state, &sink) {
+    if handle_pausing(ctx, loop_state, state, &**sink) {
         return true;
     }
-    if handle_resuming_commit(ctx, loop_state, state, start_sink_chunks, &sink) {
+    if handle_resuming_commit(ctx, loop_state, state, start_sink_chunks, &**sink) {
         return true;
     }
 
@@ -41,7 +41,7 @@ fn handle_abort(ctx: &ThreadContext, loop_state: &mut LoopState) -> bool {
         return false;
     }
     let sink = ctx.lock_sink_recoverable();
-    pause_sink(ctx, loop_state, &sink, 0.1);
+    pause_sink(ctx, loop_state, &**sink, 0.1);
     sink.clear();
     true
 }
@@ -49,7 +49,7 @@ fn handle_abort(ctx: &ThreadContext, loop_state: &mut LoopState) -> bool {
 fn handle_resuming_gate(
     state: PlayerState,
     start_sink_chunks: usize,
-    sink: &Sink,
+    sink: &dyn OutputSink,
     loop_state: &mut LoopState,
 ) -> bool {
     if state != PlayerState::Resuming || start_sink_chunks == 0 || sink.len() >= start_sink_chunks {
@@ -66,7 +66,7 @@ fn handle_pausing(
     ctx: &ThreadContext,
     loop_state: &mut LoopState,
     state: PlayerState,
-    sink: &Sink,
+    sink: &dyn OutputSink,
 ) -> bool {
     if state != PlayerState::Pausing {
         return false;
@@ -82,7 +82,7 @@ fn handle_resuming_commit(
     loop_state: &mut LoopState,
     state: PlayerState,
     start_sink_chunks: usize,
-    sink: &Sink,
+    sink: &dyn OutputSink,
 ) -> bool {
     if state != PlayerState::Resuming {
         return false;
@@ -0,0 +1,175 @@
+//! Saving and restoring the full player state across restarts.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::Player;
+use crate::container::play_settings::RuntimeVariables;
+use crate::dsp::effects::AudioEffect;
+
+/// Serializable snapshot of everything needed to resume a session.
+///
+/// The shuffle schedule is stored as the concrete selections drawn, not as a
+/// seed, so a restored player plays exactly what was playing, including
+/// selections after boundaries that variables redrew.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerStateSnapshot {
+    /// Playback position in seconds.
+    pub position: f64,
+    /// `true` when the player was playing.
+    pub playing: bool,
+    /// Output volume (linear gain).
+    pub volume: f32,
+    /// Shuffle schedule as `(time_seconds, sources)`, one track ID or file
+    /// path per slot.
+    pub schedule: Vec<(f64, Vec<String>)>,
+    /// Runtime variables steering shuffle conditions.
+    #[serde(default)]
+    pub variables: RuntimeVariables,
+    /// Effect chain with its parameters, in processing order.
+    #[serde(default)]
+    pub effects: Vec<AudioEffect>,
+    /// `(level, pan)` of each slot, in slot order.
+    #[serde(default)]
+    pub track_mix: Vec<(f32, f32)>,
+    /// Gain of each track group, by name.
+    #[serde(default)]
+    pub group_levels: BTreeMap<String, f32>,
+}
+
+/// Error returned when a snapshot cannot be restored.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RestoreStateError {
+    /// A scheduled source names no track of the loaded container.
+    UnknownSource(String),
+}
+
+impl fmt::Display for RestoreStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSource(source) => {
+                write!(f, "snapshot source not in container: {}", source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RestoreStateError {}
+
+impl Player {
+    /// Capture the current session for [`Player::restore_state`].
+    pub fn save_state(&self) -> PlayerStateSnapshot {
+        let prot = self.lock_prot_invariant();
+        let schedule = prot
+            .get_shuffle_schedule()
+            .into_iter()
+            .map(|(at_seconds, groups)| (at_seconds, groups.concat()))
+            .collect();
+        let mut track_mix: Vec<(u16, (f32, f32))> =
+            prot.get_track_mix_settings().into_iter().collect();
+        track_mix.sort_by_key(|(slot_index, _)| *slot_index);
+        let group_levels = prot
+            .group_names()
+            .into_iter()
+            .filter_map(|name| Some((name.clone(), prot.group_level(&name)?)))
+            .collect();
+        let variables = prot.variables().clone();
+        drop(prot);
+
+        PlayerStateSnapshot {
+            position: self.get_time(),
+            playing: self.is_playing(),
+            volume: self.get_volume(),
+            schedule,
+            variables,
+            effects: self.lock_effects_recoverable().clone(),
+            track_mix: track_mix.into_iter().map(|(_, mix)| mix).collect(),
+            group_levels,
+        }
+    }
+
+    /// Resume a session captured by [`Player::save_state`].
+    ///
+    /// The snapshot must come from the same container or file set. Playback
+    /// restarts at the saved position and resumes if it was playing.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RestoreStateError::UnknownSource`] when the schedule names a
+    /// source the loaded container lacks; nothing is changed in that case.
+    pub fn restore_state(&mut self, state: &PlayerStateSnapshot) -> Result<(), RestoreStateError> {
+        let mut prot = self.lock_prot_invariant();
+        prot.set_fixed_schedule(&state.schedule)
+            .map_err(RestoreStateError::UnknownSource)?;
+        for (name, value) in &state.variables {
+            prot.set_variable(name, *value);
+        }
+        for (slot_index, (level, pan)) in state.track_mix.iter().enumerate() {
+            prot.set_slot_mix_settings(slot_index, *level, *pan);
+        }
+        for (name, level) in &state.group_levels {
+            prot.set_group_level(name, *level);
+        }
+        let schedule = prot.get_shuffle_schedule();
+        drop(prot);
+        self.lock_realization_recoverable()
+            .set_schedule(schedule.clone(), true);
+        self.lock_session_stats_recoverable()
+            .record_schedule(schedule, true);
+
+        self.clear_inline_effects_update();
+        self.replace_effects_chain(state.effects.clone());
+        self.request_effects_reset();
+        self.set_volume(state.volume);
+
+        self.seek(state.position);
+        if state.playing && !self.is_playing() {
+            self.play();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+
+    fn fixture_player() -> Player {
+        let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../test_audio/test-16bit.wav");
+        Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(
+            vec![path.to_string()],
+        )])
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let player = fixture_player();
+        let state = player.save_state();
+        assert_eq!(state.schedule.len(), 1);
+        assert_eq!(state.track_mix.len(), 1);
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: PlayerStateSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.schedule, state.schedule);
+        assert_eq!(restored.track_mix, state.track_mix);
+        assert_eq!(restored.volume, state.volume);
+    }
+
+    #[test]
+    fn restore_rejects_unknown_sources_without_changes() {
+        let mut player = fixture_player();
+        let before = player.get_shuffle_schedule();
+        let mut state = player.save_state();
+        state.schedule[0].1 = vec!["/tmp/missing.wav".to_string()];
+        assert_eq!(
+            player.restore_state(&state),
+            Err(RestoreStateError::UnknownSource(
+                "/tmp/missing.wav".to_string()
+            ))
+        );
+        assert_eq!(player.get_shuffle_schedule(), before);
+    }
+}
@@ -0,0 +1,71 @@
+//! Horizontal re-sequencing: queue named timeline sections at runtime.
+//!
+//! Sections come from play_settings `sections`. Queued sections play in
+//! order, each starting at the end of the section currently playing; the
+//! playback worker crossfades across every jump.
+
+use std::fmt;
+
+use super::Player;
+use crate::container::prot::TimelineSection;
+
+/// Error returned when queueing a section fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SectionError {
+    /// No section with this name is declared in play_settings.
+    UnknownSection(String),
+}
+
+impl fmt::Display for SectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownSection(name) => write!(f, "unknown section: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for SectionError {}
+
+impl Player {
+    /// Return the named timeline sections declared by the loaded container.
+    pub fn get_sections(&self) -> Vec<TimelineSection> {
+        self.lock_prot_invariant().get_sections()
+    }
+
+    /// Queue a section to play after the current one.
+    ///
+    /// When the section playing now ends, playback jumps to the start of the
+    /// first queued section, crossfading over
+    /// `PlaybackBufferSettings::section_crossfade_ms`. After the queue drains
+    /// playback continues linearly from wherever the last section ends.
+    /// Queue the same name repeatedly to loop a section.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SectionError::UnknownSection`] when `name` is not declared.
+    pub fn queue_section(&self, name: &str) -> Result<(), SectionError> {
+        if !self
+            .get_sections()
+            .iter()
+            .any(|section| section.name == name)
+        {
+            return Err(SectionError::UnknownSection(name.to_string()));
+        }
+        self.lock_section_queue_recoverable()
+            .push_back(name.to_string());
+        Ok(())
+    }
+
+    /// Return the section names still waiting to play.
+    pub fn queued_sections(&self) -> Vec<String> {
+        self.lock_section_queue_recoverable()
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Drop every queued section; playback continues linearly.
+    pub fn clear_section_queue(&self) {
+        self.lock_section_queue_recoverable().clear();
+    }
+}
@@ -0,0 +1,255 @@
+//! Mix-stage controls for `Player`: chain dry/wet, ducking, overload
+//! handling, dither, pan law, fold-down, and per-track and per-group levels.
+//!
+//! Each applies to the running mix thread without restarting playback.
+
+use crate::container::prot::Prot;
+use crate::dsp::dither::DitherSettings;
+use crate::dsp::downmix::DownmixMode;
+use crate::dsp::pan::PanLaw;
+use crate::playback::engine::{DuckingSettings, InlineTrackMixUpdate, OverloadPolicy};
+
+use super::Player;
+
+impl Player {
+    /// Blend the unprocessed mix with the effect-chain output, for parallel
+    /// compression or reverb.
+    ///
+    /// `mix` is the processed share, clamped to `[0.0, 1.0]`; `1.0` (the
+    /// default) plays the chain output alone. The dry path is delayed by the
+    /// chain's latency, and changes ramp over the parameter ramp time.
+    pub fn set_chain_mix(&self, mix: f32) {
+        let mix = if mix.is_finite() {
+            mix.clamp(0.0, 1.0)
+        } else {
+            1.0
+        };
+        self.update_buffer_settings(|settings| {
+            settings.chain_mix = mix;
+        });
+    }
+
+    /// Get the current chain mix.
+    pub fn get_chain_mix(&self) -> f32 {
+        self.lock_buffer_settings_recoverable().chain_mix
+    }
+
+    /// Configure automatic ducking of the main mix while one-shots play.
+    ///
+    /// Takes effect on the next output chunk; pass
+    /// `DuckingSettings::default()` to disable.
+    pub fn set_ducking(&self, ducking: DuckingSettings) {
+        self.update_buffer_settings(|settings| {
+            settings.ducking = ducking;
+        });
+    }
+
+    /// Get the current ducking configuration.
+    pub fn get_ducking(&self) -> DuckingSettings {
+        self.lock_buffer_settings_recoverable().ducking
+    }
+
+    /// Let playback degrade quality instead of under-running when the effect
+    /// chain cannot keep up.
+    ///
+    /// Steps are applied and undone one at a time with the policy's
+    /// hysteresis, each raising a `QualityDegraded` or `QualityRestored`
+    /// diagnostics event (see [`Player::take_diagnostics_events`]). Only the
+    /// running chain is affected: effect settings read back unchanged, and a
+    /// replaced chain starts at full quality. Pass `OverloadPolicy::default()`
+    /// to disable; any applied steps are then undone.
+    pub fn set_overload_policy(&self, policy: OverloadPolicy) {
+        self.update_buffer_settings(|settings| {
+            settings.overload = policy;
+        });
+    }
+
+    /// Get the current overload policy.
+    pub fn get_overload_policy(&self) -> OverloadPolicy {
+        self.lock_buffer_settings_recoverable().overload
+    }
+
+    /// Dither the output to a reduced bit depth before it reaches the sink.
+    ///
+    /// Use this when the device runs at 16 bits so quantization noise stays
+    /// uncorrelated with the signal. Pass `None` to disable (the default).
+    pub fn set_output_dither(&self, dither: Option<DitherSettings>) {
+        self.update_buffer_settings(|settings| {
+            settings.output_dither = dither;
+        });
+    }
+
+    /// Get the current output dither configuration.
+    pub fn get_output_dither(&self) -> Option<DitherSettings> {
+        self.lock_buffer_settings_recoverable().output_dither
+    }
+
+    /// Override the pan law declared in play_settings.
+    ///
+    /// Applies to track slots, live input, and one-shots started afterwards,
+    /// without restarting playback. Pass `None` to restore the container's
+    /// law (the default).
+    pub fn set_pan_law(&self, pan_law: Option<PanLaw>) {
+        self.update_buffer_settings(|settings| {
+            settings.pan_law = pan_law;
+        });
+        let effective = self.effective_pan_law();
+        if let Some(bus) = self.lock_live_input_recoverable().as_mut() {
+            bus.set_pan_law(effective);
+        }
+    }
+
+    /// Get the runtime pan law override, if any.
+    pub fn get_pan_law(&self) -> Option<PanLaw> {
+        self.lock_buffer_settings_recoverable().pan_law
+    }
+
+    /// Pan law currently applied: the override, or the container's law.
+    pub fn effective_pan_law(&self) -> PanLaw {
+        let prot = self.lock_prot_invariant();
+        self.effective_pan_law_for(&prot)
+    }
+
+    pub(in crate::playback::player) fn effective_pan_law_for(&self, prot: &Prot) -> PanLaw {
+        self.lock_buffer_settings_recoverable()
+            .pan_law
+            .unwrap_or_else(|| prot.get_pan_law())
+    }
+
+    /// Fold the output down to preview it on a smaller playback system.
+    ///
+    /// [`DownmixMode::Mono`] sums the mix to mono to check the mono
+    /// compatibility of shuffled selections; [`DownmixMode::Stereo`] folds
+    /// surround output into the front pair. The fold-down applies to the
+    /// next appended chunk and the output meters read the folded signal.
+    pub fn set_downmix(&self, mode: DownmixMode) {
+        self.update_buffer_settings(|settings| {
+            settings.downmix = mode;
+        });
+    }
+
+    /// Get the current output fold-down.
+    pub fn get_downmix(&self) -> DownmixMode {
+        self.lock_buffer_settings_recoverable().downmix
+    }
+
+    /// Update per-slot track level/pan without restarting playback.
+    ///
+    /// This mutates the underlying track model and queues an inline update for
+    /// the active mix thread. Returns `false` if `slot_index` is out of range.
+    pub fn set_track_mix_inline(&self, slot_index: usize, level: f32, pan: f32) -> bool {
+        let linked_slots = {
+            let mut prot = self.lock_prot_invariant();
+            if !prot.set_slot_mix_settings(slot_index, level, pan) {
+                return false;
+            }
+            prot.linked_slot_indices(slot_index)
+        };
+        let Some(linked_slots) = linked_slots else {
+            return false;
+        };
+
+        let mut pending = self.lock_inline_track_mix_updates_recoverable();
+        for slot_index in linked_slots {
+            pending.push(InlineTrackMixUpdate {
+                slot_index,
+                level,
+                pan,
+            });
+        }
+        true
+    }
+
+    /// Set the gain of a track group without restarting playback.
+    ///
+    /// The gain multiplies the level of every track in the group, on top of
+    /// each track's own level. Returns `false` if no track group is named
+    /// `name`.
+    pub fn set_group_level(&self, name: &str, gain: f32) -> bool {
+        let gains = {
+            let mut prot = self.lock_prot_invariant();
+            if !prot.set_group_level(name, gain) {
+                return false;
+            }
+            prot.track_group_gains()
+        };
+        *self.lock_inline_group_gains_recoverable() = Some(gains);
+        true
+    }
+
+    /// Current gain of a track group, or `None` if no group is named `name`.
+    pub fn group_level(&self, name: &str) -> Option<f32> {
+        self.lock_prot_invariant().group_level(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::test_player;
+    use crate::dsp::dither::{DitherSettings, NoiseShaping};
+    use crate::dsp::downmix::DownmixMode;
+    use crate::dsp::pan::PanLaw;
+    use crate::playback::engine::{DuckingSettings, OverloadPolicy};
+
+    #[test]
+    fn set_output_dither_round_trips_through_buffer_settings() {
+        let player = test_player();
+        assert_eq!(player.get_output_dither(), None);
+        let dither = DitherSettings::new(16).with_shaping(NoiseShaping::FirstOrder);
+        player.set_output_dither(Some(dither));
+        assert_eq!(player.get_output_dither(), Some(dither));
+    }
+
+    #[test]
+    fn set_pan_law_overrides_the_container_law() {
+        let player = test_player();
+        assert_eq!(player.get_pan_law(), None);
+        assert_eq!(player.effective_pan_law(), PanLaw::Balance);
+        player.set_pan_law(Some(PanLaw::Minus3Db));
+        assert_eq!(player.effective_pan_law(), PanLaw::Minus3Db);
+        player.set_pan_law(None);
+        assert_eq!(player.effective_pan_law(), PanLaw::Balance);
+    }
+
+    #[test]
+    fn set_downmix_round_trips_through_buffer_settings() {
+        let player = test_player();
+        assert_eq!(player.get_downmix(), DownmixMode::Off);
+        player.set_downmix(DownmixMode::Mono);
+        assert_eq!(player.get_downmix(), DownmixMode::Mono);
+    }
+
+    #[test]
+    fn set_chain_mix_clamps_into_range() {
+        let player = test_player();
+        assert_eq!(player.get_chain_mix(), 1.0);
+        player.set_chain_mix(0.4);
+        assert_eq!(player.get_chain_mix(), 0.4);
+        player.set_chain_mix(-2.0);
+        assert_eq!(player.get_chain_mix(), 0.0);
+        player.set_chain_mix(f32::NAN);
+        assert_eq!(player.get_chain_mix(), 1.0);
+    }
+
+    #[test]
+    fn set_ducking_round_trips_through_buffer_settings() {
+        let player = test_player();
+        assert!(!player.get_ducking().enabled);
+        let ducking = DuckingSettings::new(-12.0, 5.0, 100.0, 250.0);
+        player.set_ducking(ducking);
+        assert_eq!(player.get_ducking(), ducking);
+    }
+
+    #[test]
+    fn set_overload_policy_round_trips_through_buffer_settings() {
+        let player = test_player();
+        assert!(!player.get_overload_policy().enabled);
+        let policy = OverloadPolicy {
+            enabled: true,
+            degrade_load: 1.0,
+            ..OverloadPolicy::default()
+        };
+        player.set_overload_policy(policy);
+        assert_eq!(player.get_overload_policy(), policy);
+    }
+}
@@ -1,11 +1,15 @@
 //! Runtime tuning and debug accessors for `Player`.
 //!
 //! These methods expose buffering/fade/jitter controls used by the runtime
-//! worker thread, plus lightweight debug snapshots for diagnostics.
+//! worker thread, plus lightweight debug snapshots for diagnostics. Mix-stage
+//! controls (chain mix, ducking, pan law, fold-down, track and group levels)
+//! live in [`mixing`].
+
+mod mixing;
 
 use std::sync::atomic::Ordering;
 
-use crate::playback::engine::{InlineTrackMixUpdate, PlaybackBufferSettings};
+use crate::playback::engine::PlaybackBufferSettings;
 
 use super::{Player, PlayerState};
 
@@ -112,6 +116,33 @@ impl Player {
         });
     }
 
+    /// Configure how much audio (ms) before a seek target is pre-rolled
+    /// through the effect chain so reverbs start with a warm tail.
+    ///
+    /// The pre-roll is processed but never heard; it delays the first audible
+    /// chunk by the time the chain needs to process it. Applies from the next
+    /// seek or start; `0` (the default) disables it.
+    pub fn set_seek_preroll_ms(&self, ms: f32) {
+        self.update_buffer_settings(|settings| {
+            settings.seek_preroll_ms = clamp_non_negative(ms);
+        });
+    }
+
+    /// Configure the crossfade length (ms) used when jumping to a queued section.
+    pub fn set_section_crossfade_ms(&self, ms: f32) {
+        self.update_buffer_settings(|settings| {
+            settings.section_crossfade_ms = clamp_non_negative(ms);
+        });
+    }
+
+    /// Configure the crossfade length (ms) used when a deferred shuffle hands
+    /// playback to the new selection at a chunk or beat boundary.
+    pub fn set_shuffle_crossfade_ms(&self, ms: f32) {
+        self.update_buffer_settings(|settings| {
+            settings.shuffle_crossfade_ms = clamp_non_negative(ms);
+        });
+    }
+
     /// Configure the append jitter logging threshold (ms). 0 disables logging.
     pub fn set_append_jitter_log_ms(&self, ms: f32) {
         self.update_buffer_settings(|settings| {
@@ -119,6 +150,16 @@ impl Player {
         });
     }
 
+    /// Configure the playback watchdog threshold (ms). 0 disables the watchdog.
+    ///
+    /// Events raised by the watchdog are retrieved with
+    /// [`Player::take_diagnostics_events`].
+    pub fn set_silence_watchdog_ms(&self, ms: f32) {
+        self.update_buffer_settings(|settings| {
+            settings.silence_watchdog_ms = clamp_non_negative(ms);
+        });
+    }
+
     /// Configure inline effects transition duration (ms) for `set_effects_inline`.
     pub fn set_inline_effects_transition_ms(&self, ms: f32) {
         self.update_buffer_settings(|settings| {
@@ -177,31 +218,26 @@ impl Player {
         });
     }
 
-    /// Update per-slot track level/pan without restarting playback.
+    /// Enable or disable adaptive output chunking.
+    ///
+    /// When enabled (the default), output is sent in fine slices shortly
+    /// after a start or seek, approaching a shuffle boundary, and at the end
+    /// of the mix, so transport actions are heard sooner without shrinking
+    /// chunks mid-stream.
+    pub fn set_adaptive_chunking(&self, enabled: bool) {
+        self.update_buffer_settings(|settings| {
+            settings.adaptive_chunking = enabled;
+        });
+    }
+
+    /// Configure how much mixed audio (ms) may queue ahead of the playback thread.
     ///
-    /// This mutates the underlying track model and queues an inline update for
-    /// the active mix thread. Returns `false` if `slot_index` is out of range.
-    pub fn set_track_mix_inline(&self, slot_index: usize, level: f32, pan: f32) -> bool {
-        let linked_slots = {
-            let mut prot = self.lock_prot_invariant();
-            if !prot.set_slot_mix_settings(slot_index, level, pan) {
-                return false;
-            }
-            prot.linked_slot_indices(slot_index)
-        };
-        let Some(linked_slots) = linked_slots else {
-            return false;
-        };
-
-        let mut pending = self.lock_inline_track_mix_updates_recoverable();
-        for slot_index in linked_slots {
-            pending.push(InlineTrackMixUpdate {
-                slot_index,
-                level,
-                pan,
-            });
-        }
-        true
+    /// Larger values trade control latency for underrun margin. The new size
+    /// applies from the next play or seek.
+    pub fn set_output_queue_ms(&self, ms: f32) {
+        self.update_buffer_settings(|settings| {
+            settings.output_queue_ms = clamp_non_negative(ms);
+        });
     }
 
     /// Debug helper returning thread alive, state, and audio heard flags.
@@ -237,7 +273,7 @@ impl Player {
     pub fn debug_sink_state(&self) -> (bool, bool, usize) {
         let sink = self.lock_sink_recoverable();
         let paused = sink.is_paused();
-        let empty = sink.empty();
+        let empty = sink.is_empty();
         let len = sink.len();
         (paused, empty, len)
     }
@@ -270,6 +306,25 @@ mod tests {
         );
     }
 
+    #[test]
+    fn set_seek_preroll_ms_clamps_negative_values() {
+        let player = test_player();
+        assert_eq!(
+            player.lock_buffer_settings_recoverable().seek_preroll_ms,
+            0.0
+        );
+        player.set_seek_preroll_ms(750.0);
+        assert_eq!(
+            player.lock_buffer_settings_recoverable().seek_preroll_ms,
+            750.0
+        );
+        player.set_seek_preroll_ms(-1.0);
+        assert_eq!(
+            player.lock_buffer_settings_recoverable().seek_preroll_ms,
+            0.0
+        );
+    }
+
     #[test]
     fn configure_for_live_authoring_applies_opt_in_profile() {
         let player = test_player();
@@ -343,7 +398,24 @@ mod tests {
             .is_none());
     }
 
-    fn test_player() -> Player {
+    #[test]
+    fn set_adaptive_chunking_updates_buffer_settings() {
+        let player = test_player();
+        player.set_adaptive_chunking(false);
+        assert!(!player.lock_buffer_settings_recoverable().adaptive_chunking);
+    }
+
+    #[test]
+    fn set_output_queue_ms_clamps_negative() {
+        let player = test_player();
+        player.set_output_queue_ms(-5.0);
+        assert_eq!(
+            player.lock_buffer_settings_recoverable().output_queue_ms,
+            0.0
+        );
+    }
+
+    pub(super) fn test_player() -> Player {
         let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
             "/tmp/nonexistent.wav".to_string(),
         ])]);
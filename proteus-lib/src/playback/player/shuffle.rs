@@ -0,0 +1,78 @@
+//! Deferred shuffles handed over at a chosen boundary.
+//!
+//! [`Player::shuffle_at_next`] redraws selections but leaves the running
+//! playback alone: the playback worker starts a second engine on the new
+//! selection and hands over to it at the requested boundary, keeping the
+//! sink and output stream alive. [`Player::refresh_tracks`] and
+//! [`Player::shuffle`] hand over at the next chunk.
+
+use super::Player;
+use crate::container::prot::Prot;
+
+/// Where a deferred shuffle takes over from the running selection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShuffleBoundary {
+    /// As soon as possible, crossfading over
+    /// `PlaybackBufferSettings::shuffle_crossfade_ms`.
+    Chunk,
+    /// On the next beat of the player's beat grid, or of the container
+    /// tempo when no grid is set, crossfading into the beat. Without either
+    /// this behaves like [`ShuffleBoundary::Chunk`].
+    Beat,
+    /// At the next shuffle point. The selection playing now is kept until
+    /// then, and the switch is as tight as a regular shuffle point.
+    ShufflePoint,
+}
+
+impl Player {
+    /// Shuffle track selections without restarting playback.
+    ///
+    /// The new selection is drawn now and takes over at the next `boundary`.
+    /// The sink and output stream keep running and the transport position
+    /// stays continuous. A later call replaces a
+    /// shuffle that has not been handed over yet. When nothing is playing,
+    /// the new selection simply applies to the next run. Inputs that cannot
+    /// seek have nothing to hand over to and ignore the call.
+    pub fn shuffle_at_next(&mut self, boundary: ShuffleBoundary) {
+        if !self.is_seekable() {
+            return;
+        }
+        let ts = self.get_time();
+        self.redraw_selections(|prot| {
+            // Keep the current selection up to the boundary when the
+            // container can redraw only the shuffle points ahead.
+            if boundary != ShuffleBoundary::ShufflePoint || !prot.reschedule_after(ts) {
+                prot.refresh_tracks();
+            }
+        });
+        if self.thread_finished() {
+            return;
+        }
+        *self.lock_scheduled_shuffle_recoverable() = Some(boundary);
+    }
+
+    /// Return the boundary of a deferred shuffle still waiting to be handed
+    /// over, if any.
+    pub fn scheduled_shuffle(&self) -> Option<ShuffleBoundary> {
+        *self.lock_scheduled_shuffle_recoverable()
+    }
+
+    /// Redraw selections with `redraw`, re-apply reverb overrides, and record
+    /// the resulting schedule.
+    fn redraw_selections(&self, redraw: impl FnOnce(&mut Prot)) {
+        let mut prot = self.lock_prot_invariant();
+        redraw(&mut prot);
+        if let Some(spec) = self.impulse_response_override.clone() {
+            prot.set_impulse_response_spec(spec);
+        }
+        if let Some(tail_db) = self.impulse_response_tail_override {
+            prot.set_impulse_response_tail_db(tail_db);
+        }
+        let schedule = prot.get_shuffle_schedule();
+        drop(prot);
+        self.lock_realization_recoverable()
+            .set_schedule(schedule.clone(), true);
+        self.lock_session_stats_recoverable()
+            .record_schedule(schedule, true);
+    }
+}
@@ -0,0 +1,97 @@
+//! Express path for auditioning one audio file.
+//!
+//! A single-file player skips play settings and shuffle logic entirely: the
+//! file plays as a one-track container with an empty effect chain. The DSP
+//! chain, output and track metering, and peak extraction work as usual,
+//! which suits auditioning stems and impulse responses in authoring apps.
+
+use super::{Player, PlayerInitError, PlayerInitOptions, PlayerSource};
+use crate::peaks::{extract_peaks_from_audio, PeaksData, PeaksError};
+
+impl Player {
+    /// Create a player for one audio file, skipping play settings and shuffling.
+    ///
+    /// Any format Symphonia can probe is accepted, including `.prot`/`.mka`
+    /// files, whose embedded `play_settings.json` is ignored in favour of
+    /// their first audio track. The effect chain starts empty; use
+    /// [`Self::set_effects`] to audition effects on the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlayerInitError::ProtInitialization`] when the file cannot
+    /// be opened or is not a supported audio file.
+    pub fn new_single_file(file_path: &str) -> Result<Self, PlayerInitError> {
+        Self::try_from_source_with_options(
+            PlayerSource::SingleFile(file_path.to_string()),
+            PlayerInitOptions::default(),
+        )
+    }
+
+    /// Return `true` when the player was built for a single file.
+    pub fn is_single_file(&self) -> bool {
+        self.single_file
+    }
+
+    /// Extract waveform peaks from the single file being played.
+    ///
+    /// Returns `Ok(None)` when the player was not built for a single file.
+    /// With `limited`, only channel 0 is processed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`PeaksError`] when the file cannot be decoded.
+    pub fn get_single_file_peaks(&self, limited: bool) -> Result<Option<PeaksData>, PeaksError> {
+        if !self.single_file {
+            return Ok(None);
+        }
+        let Some(file_path) = self.lock_prot_invariant().get_container_path() else {
+            return Ok(None);
+        };
+        extract_peaks_from_audio(&file_path, limited).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Player;
+    use crate::container::prot::PathsTrack;
+
+    fn test_audio(name: &str) -> String {
+        format!("{}/../test_audio/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    #[test]
+    fn single_file_player_plays_one_track_without_play_settings() {
+        let player = Player::new_single_file(&test_audio("demo_shuffle_points_effects.prot"))
+            .expect("single file player should initialize");
+        player.stop();
+
+        assert!(player.is_single_file());
+        assert_eq!(player.get_shuffle_schedule().len(), 1);
+        assert!(player.get_effect_names().is_empty());
+        assert!(*player.lock_prot_invariant().get_duration() > 0.0);
+    }
+
+    #[test]
+    fn single_file_peaks_are_extracted_only_in_single_file_mode() {
+        let player = Player::new_single_file(&test_audio("test-16bit.wav"))
+            .expect("single file player should initialize");
+        player.stop();
+        let peaks = player.get_single_file_peaks(true).unwrap().unwrap();
+        assert!(!peaks.channels.is_empty());
+
+        let player =
+            Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![test_audio(
+                "test-16bit.wav",
+            )])]);
+        player.stop();
+        assert!(!player.is_single_file());
+        assert!(player.get_single_file_peaks(true).unwrap().is_none());
+    }
+
+    #[test]
+    fn non_audio_files_are_rejected() {
+        let path = format!("{}/Cargo.toml", env!("CARGO_MANIFEST_DIR"));
+        assert!(Player::new_single_file(&path).is_err());
+    }
+}
@@ -1,9 +1,15 @@
 //! Read-only player state helpers.
 
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
+use rodio::OutputStream;
+
 use super::{Player, PlayerState};
+use crate::container::attachments::{
+    read_attachments_with_download, AttachmentError, ContainerAttachment,
+};
 
 impl Player {
     /// Get read-only metadata describing the active container or file list.
@@ -16,6 +22,11 @@ impl Player {
         *self.lock_state_invariant() == PlayerState::Playing
     }
 
+    /// Return `true` when this player outputs through `stream`.
+    pub(crate) fn shares_output_stream(&self, stream: &Arc<Mutex<Option<OutputStream>>>) -> bool {
+        Arc::ptr_eq(&self.output_stream, stream)
+    }
+
     /// Return true if playback is currently paused.
     pub fn is_paused(&self) -> bool {
         *self.lock_state_invariant() == PlayerState::Paused
@@ -63,10 +74,25 @@ impl Player {
     }
 
     /// Get the total duration (seconds) of the active selection.
+    ///
+    /// Reports `0.0` while the duration is unknown, as for standard input
+    /// whose header gives no length.
     pub fn get_duration(&self) -> f64 {
         *self.lock_duration_recoverable()
     }
 
+    /// Return `false` when the input cannot seek, as for standard input.
+    ///
+    /// [`Self::seek`] is ignored for such inputs.
+    pub fn is_seekable(&self) -> bool {
+        self.lock_prot_invariant().is_seekable()
+    }
+
+    /// Get the current transport state.
+    pub fn get_state(&self) -> PlayerState {
+        *self.lock_state_invariant()
+    }
+
     /// Get the track identifiers used for display.
     pub fn get_ids(&self) -> Vec<String> {
         self.lock_prot_invariant().get_ids()
@@ -80,6 +106,49 @@ impl Player {
     pub fn get_shuffle_schedule(&self) -> Vec<(f64, Vec<Vec<String>>)> {
         self.lock_prot_invariant().get_shuffle_schedule()
     }
+
+    /// Get name, mix state, and current selection for each logical track.
+    ///
+    /// Selections reflect the shuffle schedule entry active at the current
+    /// playback time, for building mixer UIs.
+    pub fn get_track_info(&self) -> Vec<crate::container::prot::LogicalTrackInfo> {
+        let at_seconds = self.get_time();
+        self.lock_prot_invariant().get_track_info(at_seconds)
+    }
+
+    /// Read a candidate artwork attachment from the loaded container.
+    ///
+    /// `name` is a [`CandidateInfo::artwork`](crate::container::prot::CandidateInfo)
+    /// reference. Returns `Ok(None)` when no container is loaded or it has no
+    /// attachment with that name.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`AttachmentError`] when the container cannot be read.
+    pub fn read_candidate_artwork(
+        &self,
+        name: &str,
+    ) -> Result<Option<ContainerAttachment>, AttachmentError> {
+        let (container_path, download, limits) = {
+            let prot = self.lock_prot_invariant();
+            (
+                prot.get_container_path(),
+                prot.download(),
+                prot.parse_limits(),
+            )
+        };
+        let Some(container_path) = container_path else {
+            return Ok(None);
+        };
+        let attachments = read_attachments_with_download(
+            container_path,
+            download.as_ref(),
+            limits.max_attachment_bytes,
+        )?;
+        Ok(attachments
+            .into_iter()
+            .find(|attachment| attachment.name.trim_matches('"') == name))
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,43 @@
+//! Click-free volume automation evaluated on the mix thread.
+
+use std::time::Duration;
+
+use super::Player;
+use crate::playback::engine::VolumeCurve;
+
+impl Player {
+    /// Ramp the playback gain to `target` over `duration`.
+    ///
+    /// The fade starts from the current automation gain and is evaluated per
+    /// frame on the mix thread, so it stays smooth across chunk boundaries.
+    /// The automation gain multiplies the level set with
+    /// [`Player::set_volume`] and persists across seeks; start a new fade or
+    /// call [`Player::cancel_volume_fade`] to change it. Because it is applied
+    /// before the output queue, the change becomes audible after the queued
+    /// output has played.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Final linear gain (`1.0` is unity).
+    /// * `duration` - Fade length; zero jumps at the next chunk.
+    /// * `curve` - Shape of the fade.
+    pub fn fade_volume_to(&self, target: f32, duration: Duration, curve: VolumeCurve) {
+        self.lock_volume_ramp_recoverable()
+            .fade_to(target, duration, curve);
+    }
+
+    /// Stop the running volume fade and hold the gain it has reached.
+    pub fn cancel_volume_fade(&self) {
+        self.lock_volume_ramp_recoverable().cancel();
+    }
+
+    /// Current volume automation gain.
+    pub fn get_fade_gain(&self) -> f32 {
+        self.lock_volume_ramp_recoverable().gain()
+    }
+
+    /// Return `true` while a volume fade is queued or running.
+    pub fn is_volume_fading(&self) -> bool {
+        self.lock_volume_ramp_recoverable().is_fading()
+    }
+}
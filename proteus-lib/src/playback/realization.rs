@@ -0,0 +1,342 @@
+//! Realized playback logs for reproducing a random mix.
+//!
+//! A [`Realization`] records what a player actually sent to the output: one
+//! [`RealizedSpan`] per stretch of contiguous timeline audio with an
+//! unchanged selection. A new span starts at every shuffle point that changes
+//! a source, and at every seek, section jump, and reshuffle. Serialize it with
+//! [`Realization::to_json`] to archive a realized mix so it can be reproduced
+//! or re-rendered, or export an edit decision list with
+//! [`Realization::to_edl`].
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::diagnostics::session::ScheduleSnapshot;
+use crate::playback::engine::SHUFFLE_CROSSFADE_MS;
+
+/// Tolerance when matching a position to a schedule boundary, in seconds.
+const POSITION_EPSILON_S: f64 = 1.0e-6;
+/// Frame rate of EDL timecodes.
+const EDL_FPS: f64 = 30.0;
+
+/// How playback entered a [`RealizedSpan`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanTransition {
+    /// First audio of the session.
+    Start,
+    /// The schedule crossed a shuffle point and changed a source.
+    ShufflePoint,
+    /// Playback restarted at another position (seek, or stop and play).
+    Seek,
+    /// A queued section was crossfaded in.
+    Section,
+    /// Selections were redrawn by a shuffle or a variable change.
+    Reshuffle,
+}
+
+/// Contiguous stretch of output with one selection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RealizedSpan {
+    /// Output time where the span starts, in seconds since the first audio.
+    pub output_s: f64,
+    /// Timeline position where the span starts, in seconds.
+    pub position_s: f64,
+    /// Span length in seconds.
+    pub duration_s: f64,
+    /// How playback entered the span.
+    pub transition: SpanTransition,
+    /// Fade or crossfade into the span, in milliseconds.
+    pub fade_ms: f32,
+    /// Selected track IDs or file paths, grouped per logical track.
+    pub tracks: Vec<Vec<String>>,
+}
+
+impl RealizedSpan {
+    /// Timeline position where the span ends, in seconds.
+    pub fn end_position_s(&self) -> f64 {
+        self.position_s + self.duration_s
+    }
+}
+
+/// Log of exactly which sources played when during a session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Realization {
+    /// Container path, or `None` for file-list playback.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// Sample rate of the realized audio in Hz.
+    pub sample_rate: u32,
+    /// Channel count of the realized audio.
+    pub channels: u32,
+    /// Spans in output order.
+    pub spans: Vec<RealizedSpan>,
+}
+
+impl Realization {
+    /// Total output time covered by the spans, in seconds.
+    pub fn duration_s(&self) -> f64 {
+        self.spans.iter().map(|span| span.duration_s).sum()
+    }
+
+    /// Serialize as pretty-printed JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Parse a realization previously written by [`Realization::to_json`].
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Render a CMX 3600 style edit decision list at 30 fps non-drop.
+    ///
+    /// Each span becomes one audio event whose source timecodes are timeline
+    /// positions and whose record timecodes are output times. Spans entered
+    /// with a fade of at least one frame are written as dissolves. Selected
+    /// sources are listed in `FROM CLIP NAME` comments, with logical tracks
+    /// separated by `|`.
+    pub fn to_edl(&self) -> String {
+        let title = self
+            .container
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+            .map_or_else(
+                || "proteus realization".to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+        let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n", title);
+        for (index, span) in self.spans.iter().enumerate() {
+            let fade_frames = (f64::from(span.fade_ms) * EDL_FPS / 1000.0).round() as u32;
+            let transition = if fade_frames == 0 {
+                "C       ".to_string()
+            } else {
+                format!("D    {:03}", fade_frames)
+            };
+            let clips: Vec<String> = span.tracks.iter().map(|group| group.join("+")).collect();
+            edl.push_str(&format!(
+                "\n{:03}  AX       AA     {} {} {} {} {}\n* FROM CLIP NAME: {}\n* TRANSITION: {}\n",
+                index + 1,
+                transition,
+                timecode(span.position_s),
+                timecode(span.end_position_s()),
+                timecode(span.output_s),
+                timecode(span.output_s + span.duration_s),
+                clips.join(" | "),
+                transition_name(span.transition),
+            ));
+        }
+        edl
+    }
+}
+
+fn transition_name(transition: SpanTransition) -> &'static str {
+    match transition {
+        SpanTransition::Start => "start",
+        SpanTransition::ShufflePoint => "shuffle_point",
+        SpanTransition::Seek => "seek",
+        SpanTransition::Section => "section",
+        SpanTransition::Reshuffle => "reshuffle",
+    }
+}
+
+fn timecode(seconds: f64) -> String {
+    let frames = (seconds.max(0.0) * EDL_FPS).round() as u64;
+    let fps = EDL_FPS as u64;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        frames / (fps * 3600),
+        frames / (fps * 60) % 60,
+        frames / fps % 60,
+        frames % fps
+    )
+}
+
+/// Builds a [`Realization`] from the chunks a player appends to its output.
+///
+/// The player reports schedule changes and run starts, the section sequencer
+/// reports jumps, and the worker reports the length of every appended chunk.
+/// The recorder tracks the timeline position itself and splits chunks that
+/// cross a shuffle point.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RealizationRecorder {
+    schedule: ScheduleSnapshot,
+    spans: Vec<RealizedSpan>,
+    position_s: f64,
+    output_s: f64,
+    pending: Option<(SpanTransition, f32)>,
+}
+
+impl RealizationRecorder {
+    /// Create a recorder for the initially resolved schedule.
+    pub(crate) fn new(schedule: ScheduleSnapshot) -> Self {
+        Self {
+            schedule,
+            ..Self::default()
+        }
+    }
+
+    /// Return `true` until the first chunk is recorded.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Replace the schedule; `reshuffle` starts a new span at the next chunk.
+    pub(crate) fn set_schedule(&mut self, schedule: ScheduleSnapshot, reshuffle: bool) {
+        self.schedule = schedule;
+        if reshuffle {
+            self.mark(SpanTransition::Reshuffle, 0.0);
+        }
+    }
+
+    /// Note that a playback run starts at `start_s`, fading in over `fade_ms`.
+    pub(crate) fn begin_run(&mut self, start_s: f64, fade_ms: f32) {
+        self.position_s = start_s;
+        let transition = if self.is_empty() {
+            SpanTransition::Start
+        } else {
+            SpanTransition::Seek
+        };
+        self.mark(transition, fade_ms);
+    }
+
+    /// Note a section jump to `target_s`, crossfaded over `fade_ms`.
+    pub(crate) fn jump(&mut self, target_s: f64, fade_ms: f32) {
+        self.position_s = target_s;
+        self.mark(SpanTransition::Section, fade_ms);
+    }
+
+    // A reshuffle restarts playback, so an already pending transition keeps
+    // its kind and only takes the fade of the restart.
+    fn mark(&mut self, transition: SpanTransition, fade_ms: f32) {
+        self.pending.get_or_insert((transition, fade_ms)).1 = fade_ms;
+    }
+
+    /// Record `seconds` of output at the current timeline position.
+    pub(crate) fn record_output(&mut self, seconds: f64) {
+        let mut remaining = seconds.max(0.0);
+        while remaining > POSITION_EPSILON_S {
+            let segment = self
+                .schedule
+                .iter()
+                .rposition(|(start_s, _)| *start_s <= self.position_s + POSITION_EPSILON_S);
+            let step = self
+                .schedule
+                .get(segment.map_or(0, |index| index + 1))
+                .map_or(remaining, |(boundary_s, _)| {
+                    (boundary_s - self.position_s).min(remaining)
+                });
+            let tracks = segment.map_or_else(Vec::new, |index| self.schedule[index].1.clone());
+            self.append(step, tracks);
+            self.position_s += step;
+            self.output_s += step;
+            remaining -= step;
+        }
+    }
+
+    fn append(&mut self, seconds: f64, tracks: Vec<Vec<String>>) {
+        let pending = self.pending.take();
+        if let (None, Some(last)) = (pending, self.spans.last_mut()) {
+            if last.tracks == tracks {
+                last.duration_s += seconds;
+                return;
+            }
+        }
+        let (transition, fade_ms) = pending.unwrap_or(if self.spans.is_empty() {
+            (SpanTransition::Start, 0.0)
+        } else {
+            (SpanTransition::ShufflePoint, SHUFFLE_CROSSFADE_MS as f32)
+        });
+        self.spans.push(RealizedSpan {
+            output_s: self.output_s,
+            position_s: self.position_s,
+            duration_s: seconds,
+            transition,
+            fade_ms,
+            tracks,
+        });
+    }
+
+    /// Spans recorded so far.
+    pub(crate) fn spans(&self) -> Vec<RealizedSpan> {
+        self.spans.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(second: &str) -> ScheduleSnapshot {
+        vec![
+            (0.0, vec![vec!["1".to_string()], vec!["3".to_string()]]),
+            (10.0, vec![vec![second.to_string()], vec!["3".to_string()]]),
+        ]
+    }
+
+    #[test]
+    fn recorder_splits_spans_at_shuffle_points_seeks_and_reshuffles() {
+        let mut recorder = RealizationRecorder::new(schedule("2"));
+        recorder.begin_run(8.0, 150.0);
+        recorder.record_output(1.5);
+        recorder.record_output(1.5);
+        recorder.begin_run(4.0, 30.0);
+        recorder.record_output(1.0);
+        recorder.set_schedule(schedule("5"), true);
+        recorder.begin_run(5.0, 30.0);
+        recorder.record_output(6.0);
+
+        let spans = recorder.spans();
+        let summary: Vec<(SpanTransition, f64, f64, &str)> = spans
+            .iter()
+            .map(|span| {
+                (
+                    span.transition,
+                    span.position_s,
+                    span.duration_s,
+                    span.tracks[0][0].as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (SpanTransition::Start, 8.0, 2.0, "1"),
+                (SpanTransition::ShufflePoint, 10.0, 1.0, "2"),
+                (SpanTransition::Seek, 4.0, 1.0, "1"),
+                (SpanTransition::Reshuffle, 5.0, 5.0, "1"),
+                (SpanTransition::ShufflePoint, 10.0, 1.0, "5"),
+            ]
+        );
+        assert_eq!(spans[0].fade_ms, 150.0);
+        assert_eq!(spans[3].fade_ms, 30.0);
+        assert_eq!(spans[3].output_s, 4.0);
+    }
+
+    #[test]
+    fn realization_round_trips_json_and_writes_edl() {
+        let mut recorder = RealizationRecorder::new(schedule("2"));
+        recorder.begin_run(0.0, 0.0);
+        recorder.record_output(2.0);
+        recorder.jump(30.0, 250.0);
+        recorder.record_output(1.0);
+        let realization = Realization {
+            container: Some("/music/song.prot".to_string()),
+            sample_rate: 48_000,
+            channels: 2,
+            spans: recorder.spans(),
+        };
+        assert_eq!(realization.duration_s(), 3.0);
+
+        let parsed = Realization::from_json(&realization.to_json().unwrap()).unwrap();
+        assert_eq!(parsed, realization);
+
+        let edl = realization.to_edl();
+        assert!(edl.starts_with("TITLE: song.prot\nFCM: NON-DROP FRAME\n"));
+        assert!(edl.contains(
+            "002  AX       AA     D    008 00:00:30:00 00:00:31:00 00:00:02:00 00:00:03:00\n\
+             * FROM CLIP NAME: 2 | 3\n* TRANSITION: section\n"
+        ));
+    }
+}
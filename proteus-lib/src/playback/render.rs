@@ -0,0 +1,384 @@
+//! Deterministic offline rendering for golden-file regression tests.
+//!
+//! [`render_selection_to_pcm`] resolves a container's track selection from a
+//! fixed seed and runs the playback mix and effect pipeline offline. With the
+//! default single-threaded decode the output has no wall-clock or scheduling
+//! dependence, so the same container, seed, and length always produce the
+//! same samples bit for bit and can be compared against a stored golden WAV.
+//!
+//! [`render_realization_to_pcm`] replays a recorded
+//! [`Realization`](crate::playback::realization::Realization) with no
+//! randomness, and renders can be written out with [`RenderedPcm::write_wav`].
+//! Long exports can report progress and be cancelled through
+//! [`render_selection_to_pcm_with_progress`]. [`render_combination_to_pcm`]
+//! renders one fixed combination from
+//! [`Prot::combinations`](crate::container::prot::Prot::combinations), and
+//! [`export_batch`] writes many seeds or combinations to WAV files in
+//! parallel. [`RenderOptions::post`] trims, normalizes, and fades exports
+//! without a separate DAW pass. [`RenderedPcm::checksum`] and
+//! [`checksum_audio_file`] give platform-independent hashes for checking
+//! reproducibility and deduplicating exports.
+
+mod batch;
+mod checksum;
+mod post;
+mod realization;
+mod wav;
+
+pub use batch::{
+    export_batch, BatchEvent, BatchExportError, BatchExportOptions, BatchJob, BatchVariant,
+};
+pub use checksum::{checksum_audio_file, AudioChecksum, ChecksumError};
+pub use post::PostProcessing;
+pub use realization::{
+    render_realization_to_file, render_realization_to_pcm, RealizationRenderError,
+};
+
+use crate::container::loudness::LoudnessTag;
+use crate::container::prot::{Combination, Prot, ProtError};
+use crate::dsp::dither::{DitherSettings, Ditherer};
+use crate::dsp::effects::AudioEffect;
+use crate::dsp::loudness::LoudnessMeter;
+use crate::playback::engine::{
+    render_offline, DecodeThreading, OfflineRun, PlaybackBufferSettings,
+};
+use crate::tools::progress::{Cancelled, Progress};
+
+/// Interleaved PCM produced by an offline render.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedPcm {
+    /// Interleaved `f32` samples.
+    pub samples: Vec<f32>,
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+    /// Interleaved channel count.
+    pub channels: u32,
+}
+
+impl RenderedPcm {
+    /// Rendered length in seconds.
+    pub fn duration_seconds(&self) -> f64 {
+        let frames = self.samples.len() / self.channels.max(1) as usize;
+        frames as f64 / f64::from(self.sample_rate.max(1))
+    }
+}
+
+/// Options for [`render_selection_to_pcm_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    /// Decode on the calling thread before mixing (default `true`).
+    ///
+    /// When `false`, the regular decode worker threads are used. That matches
+    /// live playback more closely, but chunk boundaries then depend on thread
+    /// scheduling and the output is not guaranteed to be bit-identical.
+    pub single_threaded: bool,
+    /// Dither applied to the finished render (`None` = leave full `f32` precision).
+    ///
+    /// Set this when the render will be written at a reduced bit depth. The
+    /// dither noise uses a fixed seed, so output stays deterministic.
+    pub dither: Option<DitherSettings>,
+    /// Apply the container's loudness tag as an input trim (default `true`).
+    pub loudness_trim: bool,
+    /// Trim, normalization, and fades applied to the finished render, before
+    /// dither (default: none).
+    pub post: PostProcessing,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            single_threaded: true,
+            dither: None,
+            loudness_trim: true,
+            post: PostProcessing::default(),
+        }
+    }
+}
+
+/// Render the first `seconds` of `prot` with its selection resolved from `seed`.
+///
+/// The container's own effect chain is applied. Output is bit-deterministic
+/// for a given container, seed, and length.
+pub fn render_selection_to_pcm(prot: &Prot, seed: u64, seconds: f64) -> RenderedPcm {
+    render_selection_to_pcm_with_options(prot, seed, seconds, RenderOptions::default())
+}
+
+/// Like [`render_selection_to_pcm`], with explicit [`RenderOptions`].
+pub fn render_selection_to_pcm_with_options(
+    prot: &Prot,
+    seed: u64,
+    seconds: f64,
+    options: RenderOptions,
+) -> RenderedPcm {
+    match render_selection_to_pcm_with_progress(prot, seed, seconds, options, &mut Progress::new())
+    {
+        Ok(rendered) => rendered,
+        Err(Cancelled) => unreachable!("a fresh progress token is never cancelled"),
+    }
+}
+
+/// Like [`render_selection_to_pcm_with_options`], reporting the rendered
+/// fraction of `seconds` to `progress`.
+///
+/// # Errors
+///
+/// Returns [`Cancelled`] when `progress` is cancelled before the render
+/// finishes; the partial output is discarded.
+pub fn render_selection_to_pcm_with_progress(
+    prot: &Prot,
+    seed: u64,
+    seconds: f64,
+    options: RenderOptions,
+    progress: &mut Progress<'_>,
+) -> Result<RenderedPcm, Cancelled> {
+    progress.check()?;
+    let mut prot = prot.clone();
+    prot.refresh_tracks_with_seed(seed);
+    render_resolved(prot, seconds, options, progress)
+}
+
+/// Render the first `seconds` of `prot` playing one fixed `combination`.
+///
+/// Pair with [`Prot::combinations`] to batch-render every variant of a
+/// container, or a few unique ones through
+/// [`Combinations::sample`](crate::container::prot::Combinations::sample).
+/// The container's own effect chain is applied.
+///
+/// # Errors
+///
+/// Returns [`ProtError::UnknownSource`] when `combination` names a source
+/// that is not part of `prot`.
+pub fn render_combination_to_pcm(
+    prot: &Prot,
+    combination: &Combination,
+    seconds: f64,
+    options: RenderOptions,
+) -> Result<RenderedPcm, ProtError> {
+    let mut prot = prot.clone();
+    prot.apply_combination(combination)?;
+    match render_resolved(prot, seconds, options, &mut Progress::new()) {
+        Ok(rendered) => Ok(rendered),
+        Err(Cancelled) => unreachable!("a fresh progress token is never cancelled"),
+    }
+}
+
+// Render `prot` with its current selection.
+fn render_resolved(
+    prot: Prot,
+    seconds: f64,
+    options: RenderOptions,
+    progress: &mut Progress<'_>,
+) -> Result<RenderedPcm, Cancelled> {
+    let effects = prot.get_effects().unwrap_or_default();
+    render_window(prot, effects, 0.0, seconds, options, progress)
+}
+
+// Render `seconds` of `prot`'s current selection from `start` through `effects`.
+pub(crate) fn render_window(
+    prot: Prot,
+    effects: Vec<AudioEffect>,
+    start: f64,
+    seconds: f64,
+    options: RenderOptions,
+    progress: &mut Progress<'_>,
+) -> Result<RenderedPcm, Cancelled> {
+    let threading = if options.single_threaded {
+        DecodeThreading::Inline
+    } else {
+        DecodeThreading::Workers
+    };
+    let mut rendered = RenderedPcm {
+        samples: Vec::new(),
+        sample_rate: prot.info.sample_rate,
+        channels: prot.info.channels,
+    };
+    if rendered.sample_rate == 0 || rendered.channels == 0 {
+        progress.report(1.0);
+        return Ok(rendered);
+    }
+
+    let mut settings = PlaybackBufferSettings::new(0.0);
+    settings.loudness_trim = options.loudness_trim;
+    let token = progress.cancellation().clone();
+    let run = OfflineRun {
+        threading,
+        cancel: Some(token.flag()),
+    };
+    let total_samples =
+        (seconds.max(0.0) * f64::from(rendered.sample_rate)) as u64 * u64::from(rendered.channels);
+    let stats = render_offline(prot, effects, settings, start, seconds, run, |chunk| {
+        rendered.samples.extend_from_slice(chunk);
+        progress.report_ratio(rendered.samples.len() as u64, total_samples);
+    });
+    if stats.cancelled {
+        return Err(Cancelled);
+    }
+    rendered.apply_post_processing(&options.post);
+    if let Some(dither) = options.dither {
+        Ditherer::new(dither, rendered.channels as usize).process(&mut rendered.samples);
+    }
+    progress.report(1.0);
+    Ok(rendered)
+}
+
+/// Measure the untrimmed loudness of `prot`'s selection for `seed`.
+///
+/// Renders the full container with its effect chain and returns a tag for
+/// [`LoudnessTag::write_to_play_settings`], or `None` when the render is
+/// silent.
+pub fn measure_loudness(prot: &Prot, seed: u64) -> Option<LoudnessTag> {
+    let mut prot = prot.clone();
+    prot.refresh_tracks_with_seed(seed);
+    let seconds = *prot.get_duration();
+    let rendered = render_selection_to_pcm_with_options(
+        &prot,
+        seed,
+        seconds,
+        RenderOptions {
+            loudness_trim: false,
+            ..RenderOptions::default()
+        },
+    );
+    let mut meter = LoudnessMeter::new(rendered.sample_rate, rendered.channels as usize);
+    meter.process(&rendered.samples);
+    let integrated_lufs = meter.integrated_lufs()?;
+    Some(LoudnessTag::from_measurement(
+        integrated_lufs,
+        meter.sample_peak(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn fixture() -> Prot {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio/demo_shuffle_points_effects.prot");
+        Prot::try_new(&path.display().to_string()).unwrap()
+    }
+
+    #[test]
+    fn single_threaded_render_is_bit_identical_across_runs() {
+        let prot = fixture();
+        let first = render_selection_to_pcm(&prot, 42, 1.0);
+        let second = render_selection_to_pcm(&prot, 42, 1.0);
+        assert!((first.duration_seconds() - 1.0).abs() < 1e-3);
+        assert!(first.samples.iter().any(|sample| sample.abs() > 1.0e-4));
+        let first_bits: Vec<u32> = first.samples.iter().map(|s| s.to_bits()).collect();
+        let second_bits: Vec<u32> = second.samples.iter().map(|s| s.to_bits()).collect();
+        assert_eq!(first_bits, second_bits);
+    }
+
+    #[test]
+    fn each_combination_renders_its_own_selection() {
+        let prot = fixture();
+        let mut combinations = prot.combinations().unwrap();
+        let first = combinations.next().unwrap();
+        let second = combinations.next().unwrap();
+        let render = |combination| {
+            render_combination_to_pcm(&prot, combination, 0.5, RenderOptions::default()).unwrap()
+        };
+        let rendered = render(&first);
+        assert!(rendered.samples.iter().any(|sample| sample.abs() > 1.0e-4));
+        assert_eq!(rendered, render(&first));
+        assert_ne!(rendered, render(&second));
+    }
+
+    #[test]
+    fn loudness_tag_trims_render_unless_disabled() {
+        let mut prot = fixture();
+        prot.replaygain = Some(LoudnessTag {
+            gain_db: -6.0,
+            peak: None,
+            integrated_lufs: None,
+        });
+        let trimmed = render_selection_to_pcm(&prot, 42, 0.5);
+        let untrimmed = render_selection_to_pcm_with_options(
+            &prot,
+            42,
+            0.5,
+            RenderOptions {
+                loudness_trim: false,
+                ..RenderOptions::default()
+            },
+        );
+        let peak = |samples: &[f32]| samples.iter().fold(0.0_f32, |max, s| max.max(s.abs()));
+        let ratio = peak(&trimmed.samples) / peak(&untrimmed.samples);
+        assert!(
+            (ratio - 10_f32.powf(-6.0 / 20.0)).abs() < 0.02,
+            "ratio {ratio}"
+        );
+    }
+
+    #[test]
+    fn render_reports_progress_and_honours_cancellation() {
+        let prot = fixture();
+        let mut fractions = Vec::new();
+        let rendered = {
+            let mut progress = Progress::new().with_callback(|fraction| fractions.push(fraction));
+            render_selection_to_pcm_with_progress(
+                &prot,
+                42,
+                0.5,
+                RenderOptions::default(),
+                &mut progress,
+            )
+            .unwrap()
+        };
+        assert!((rendered.duration_seconds() - 0.5).abs() < 1e-3);
+        assert_eq!(fractions.last(), Some(&1.0));
+
+        let token = crate::tools::progress::CancellationToken::new();
+        token.cancel();
+        let mut progress = Progress::new().with_cancellation(token);
+        let result = render_selection_to_pcm_with_progress(
+            &prot,
+            42,
+            0.5,
+            RenderOptions::default(),
+            &mut progress,
+        );
+        assert_eq!(result, Err(Cancelled));
+    }
+
+    #[test]
+    fn post_processing_runs_on_the_finished_render() {
+        let rendered = render_selection_to_pcm_with_options(
+            &fixture(),
+            42,
+            0.5,
+            RenderOptions {
+                post: PostProcessing {
+                    peak_dbfs: Some(-6.0),
+                    ..PostProcessing::default()
+                },
+                ..RenderOptions::default()
+            },
+        );
+        let peak = rendered
+            .samples
+            .iter()
+            .fold(0.0_f32, |max, s| max.max(s.abs()));
+        assert!(
+            (peak - 10_f32.powf(-6.0 / 20.0)).abs() < 1e-4,
+            "peak {peak}"
+        );
+    }
+
+    #[test]
+    fn threaded_render_produces_requested_length() {
+        let rendered = render_selection_to_pcm_with_options(
+            &fixture(),
+            42,
+            0.5,
+            RenderOptions {
+                single_threaded: false,
+                ..RenderOptions::default()
+            },
+        );
+        assert!((rendered.duration_seconds() - 0.5).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,308 @@
+//! Batch export of container variants to WAV files on worker threads.
+//!
+//! [`export_batch`] renders each [`BatchJob`] — a seeded selection or a
+//! fixed [`Combination`] — with its own clone of the container, writes it
+//! to the job's output path, and reports the render's [`AudioChecksum`]. Up to [`BatchExportOptions::jobs`] renders run
+//! at once. Per-job [`BatchEvent`]s are delivered to the caller's callback on
+//! the calling thread, so the callback does not need to be `Send`.
+
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+use crate::container::prot::{Combination, Prot, ProtError};
+use crate::playback::pcm_output::PcmFormat;
+use crate::tools::progress::{CancellationToken, Cancelled, Progress};
+
+use super::{render_resolved, AudioChecksum, RenderOptions};
+
+/// Which selection a batch job renders.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchVariant {
+    /// The selection resolved from a seed, as in
+    /// [`render_selection_to_pcm`](super::render_selection_to_pcm).
+    Seed(u64),
+    /// A fixed combination from [`Prot::combinations`].
+    Combination(Combination),
+}
+
+/// One render written to one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchJob {
+    /// Selection to render.
+    pub variant: BatchVariant,
+    /// WAV file to write.
+    pub output: PathBuf,
+}
+
+/// Options shared by every job of a batch.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BatchExportOptions {
+    /// Seconds to render per job, or `None` for the full length of each
+    /// job's selection.
+    pub seconds: Option<f64>,
+    /// Render options applied to every job.
+    pub render: RenderOptions,
+    /// Sample format of the written WAV files.
+    pub format: PcmFormat,
+    /// Maximum number of renders running at once (at least one).
+    pub jobs: usize,
+}
+
+impl Default for BatchExportOptions {
+    fn default() -> Self {
+        Self {
+            seconds: None,
+            render: RenderOptions::default(),
+            format: PcmFormat::F32Le,
+            jobs: thread::available_parallelism().map_or(1, |jobs| jobs.get()),
+        }
+    }
+}
+
+/// Error returned for a batch job that did not produce its file.
+#[derive(Debug)]
+pub enum BatchExportError {
+    /// The job's combination names a source missing from the container.
+    Selection(ProtError),
+    /// Writing the output file failed.
+    Io(io::Error),
+    /// The batch was cancelled before this job finished.
+    Cancelled,
+}
+
+impl fmt::Display for BatchExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Selection(err) => write!(f, "invalid selection: {}", err),
+            Self::Io(err) => write!(f, "failed to write batch render: {}", err),
+            Self::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for BatchExportError {}
+
+impl From<Cancelled> for BatchExportError {
+    fn from(_: Cancelled) -> Self {
+        Self::Cancelled
+    }
+}
+
+/// Progress of one job, reported by [`export_batch`].
+#[derive(Debug)]
+pub enum BatchEvent<'a> {
+    /// The job at `job` started rendering.
+    Started {
+        /// Index into the job list.
+        job: usize,
+    },
+    /// The job at `job` rendered `fraction` of its length.
+    Progress {
+        /// Index into the job list.
+        job: usize,
+        /// Completed fraction in `[0.0, 1.0]`.
+        fraction: f64,
+    },
+    /// The job at `job` finished, successfully or not.
+    Finished {
+        /// Index into the job list.
+        job: usize,
+        /// Checksum of the written render, or why the job failed.
+        result: &'a Result<AudioChecksum, BatchExportError>,
+    },
+}
+
+/// Message from a worker thread to the calling thread.
+enum WorkerMessage {
+    Started(usize),
+    Progress(usize, f64),
+    Finished(usize, Result<AudioChecksum, BatchExportError>),
+}
+
+/// Render every job in `jobs` from `prot` and write it to its output file.
+///
+/// Jobs run on up to `options.jobs` worker threads and start in list order.
+/// Cancelling `cancel` stops running renders early and skips the rest; each
+/// job that did not complete is reported as [`BatchExportError::Cancelled`].
+/// Returns one result per job, in list order; a successful job yields the
+/// [`RenderedPcm::checksum`](super::RenderedPcm::checksum) of its render.
+pub fn export_batch(
+    prot: &Prot,
+    jobs: &[BatchJob],
+    options: BatchExportOptions,
+    cancel: &CancellationToken,
+    mut on_event: impl FnMut(BatchEvent<'_>),
+) -> Vec<Result<AudioChecksum, BatchExportError>> {
+    let mut results: Vec<Option<Result<AudioChecksum, BatchExportError>>> =
+        jobs.iter().map(|_| None).collect();
+    let next_job = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.clamp(1, jobs.len().max(1)) {
+            let sender = sender.clone();
+            let next_job = &next_job;
+            scope.spawn(move || loop {
+                let job = next_job.fetch_add(1, Ordering::Relaxed);
+                let Some(batch_job) = jobs.get(job) else {
+                    break;
+                };
+                let result = if cancel.is_cancelled() {
+                    Err(BatchExportError::Cancelled)
+                } else {
+                    let _ = sender.send(WorkerMessage::Started(job));
+                    let progress_sender = sender.clone();
+                    let mut progress = Progress::new()
+                        .with_cancellation(cancel.clone())
+                        .with_callback(move |fraction| {
+                            let _ = progress_sender.send(WorkerMessage::Progress(job, fraction));
+                        });
+                    run_job(prot, batch_job, options, &mut progress)
+                };
+                let _ = sender.send(WorkerMessage::Finished(job, result));
+            });
+        }
+        drop(sender);
+
+        for message in receiver {
+            match message {
+                WorkerMessage::Started(job) => on_event(BatchEvent::Started { job }),
+                WorkerMessage::Progress(job, fraction) => {
+                    on_event(BatchEvent::Progress { job, fraction })
+                }
+                WorkerMessage::Finished(job, result) => {
+                    let result = results[job].insert(result);
+                    on_event(BatchEvent::Finished { job, result });
+                }
+            }
+        }
+    });
+
+    results
+        .into_iter()
+        .map(|result| result.unwrap_or(Err(BatchExportError::Cancelled)))
+        .collect()
+}
+
+fn run_job(
+    prot: &Prot,
+    job: &BatchJob,
+    options: BatchExportOptions,
+    progress: &mut Progress<'_>,
+) -> Result<AudioChecksum, BatchExportError> {
+    progress.check()?;
+    let mut prot = prot.clone();
+    match &job.variant {
+        BatchVariant::Seed(seed) => prot.refresh_tracks_with_seed(*seed),
+        BatchVariant::Combination(combination) => prot
+            .apply_combination(combination)
+            .map_err(BatchExportError::Selection)?,
+    }
+    let seconds = options.seconds.unwrap_or(*prot.get_duration());
+    let rendered = render_resolved(prot, seconds, options.render, progress)?;
+    rendered
+        .write_wav(&job.output, options.format)
+        .map_err(BatchExportError::Io)?;
+    Ok(rendered.checksum())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn fixture() -> Prot {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio/demo_shuffle_points_effects.prot");
+        Prot::try_new(&path.display().to_string()).unwrap()
+    }
+
+    fn batch_dir(label: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("proteus-batch-{}-{}", label, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn options() -> BatchExportOptions {
+        BatchExportOptions {
+            seconds: Some(0.25),
+            jobs: 2,
+            ..BatchExportOptions::default()
+        }
+    }
+
+    #[test]
+    fn batch_writes_every_job_and_reports_each_once() {
+        let prot = fixture();
+        let dir = batch_dir("export");
+        let combination = prot.combinations().unwrap().get(1).unwrap();
+        let jobs = vec![
+            BatchJob {
+                variant: BatchVariant::Seed(1),
+                output: dir.join("seed-1.wav"),
+            },
+            BatchJob {
+                variant: BatchVariant::Seed(2),
+                output: dir.join("seed-2.wav"),
+            },
+            BatchJob {
+                variant: BatchVariant::Combination(combination),
+                output: dir.join("variant-1.wav"),
+            },
+        ];
+
+        let mut started = vec![0; jobs.len()];
+        let mut finished = vec![0; jobs.len()];
+        let results = export_batch(
+            &prot,
+            &jobs,
+            options(),
+            &CancellationToken::new(),
+            |event| match event {
+                BatchEvent::Started { job } => started[job] += 1,
+                BatchEvent::Progress { fraction, .. } => assert!((0.0..=1.0).contains(&fraction)),
+                BatchEvent::Finished { job, result } => {
+                    assert!(result.is_ok());
+                    finished[job] += 1;
+                }
+            },
+        );
+
+        assert!(results.iter().all(Result::is_ok));
+        assert_ne!(results[0].as_ref().unwrap(), results[1].as_ref().unwrap());
+        assert_eq!(started, vec![1, 1, 1]);
+        assert_eq!(finished, vec![1, 1, 1]);
+        for job in &jobs {
+            assert!(std::fs::metadata(&job.output).unwrap().len() > 44);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn cancelled_batch_skips_every_job() {
+        let dir = batch_dir("cancel");
+        let jobs: Vec<BatchJob> = (0..3)
+            .map(|seed| BatchJob {
+                variant: BatchVariant::Seed(seed),
+                output: dir.join(format!("seed-{}.wav", seed)),
+            })
+            .collect();
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let results = export_batch(&fixture(), &jobs, options(), &cancel, |_| {});
+
+        assert!(results
+            .iter()
+            .all(|result| matches!(result, Err(BatchExportError::Cancelled))));
+        assert!(jobs.iter().all(|job| !job.output.exists()));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,220 @@
+//! Stable checksums of rendered and exported audio.
+//!
+//! [`AudioChecksum`] is a 64-bit FNV-1a hash over the sample rate, channel
+//! count, and samples, so it is identical on every platform and Rust
+//! version. [`RenderedPcm::checksum`] hashes the exact `f32` bit patterns and
+//! verifies bit-for-bit reproducibility; [`RenderedPcm::checksum_at_bit_depth`]
+//! hashes samples rounded to an integer grid, which tolerates the sub-LSB
+//! differences floating-point math can show across machines.
+//! [`checksum_audio_file`] decodes a file and hashes it the same way as
+//! [`RenderedPcm::checksum`], so a 32-bit float WAV export matches the render
+//! it was written from.
+
+use std::fmt;
+
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::audio::decode::for_each_channel_sample;
+use crate::tools::decode::{find_audio_track, open_file, DecoderOpenError};
+
+use super::RenderedPcm;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Platform-independent 64-bit checksum of audio content.
+///
+/// Displays as 16 lowercase hex digits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioChecksum(pub u64);
+
+impl fmt::Display for AudioChecksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+/// Incremental FNV-1a hasher.
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new(sample_rate: u32, channels: u32) -> Self {
+        let mut hasher = Self(FNV_OFFSET_BASIS);
+        hasher.write(&sample_rate.to_le_bytes());
+        hasher.write(&channels.to_le_bytes());
+        hasher
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn finish(&self) -> AudioChecksum {
+        AudioChecksum(self.0)
+    }
+}
+
+impl RenderedPcm {
+    /// Checksum of the exact samples, sample rate, and channel count.
+    pub fn checksum(&self) -> AudioChecksum {
+        let mut hasher = Fnv1a::new(self.sample_rate, self.channels);
+        for sample in &self.samples {
+            hasher.write(&sample.to_bits().to_le_bytes());
+        }
+        hasher.finish()
+    }
+
+    /// Checksum of the samples rounded to signed `bit_depth`-bit integers.
+    ///
+    /// `bit_depth` is clamped to `2..=24`. Renders that differ by less than
+    /// half an LSB at that depth hash the same, except where a sample sits
+    /// right on a rounding boundary.
+    pub fn checksum_at_bit_depth(&self, bit_depth: u32) -> AudioChecksum {
+        let scale = (1_i64 << (bit_depth.clamp(2, 24) - 1)) as f64;
+        let mut hasher = Fnv1a::new(self.sample_rate, self.channels);
+        hasher.write(&bit_depth.clamp(2, 24).to_le_bytes());
+        for sample in &self.samples {
+            let level = f64::from(*sample).clamp(-1.0, 1.0) * scale;
+            let quantized = level.round().clamp(-scale, scale - 1.0) as i32;
+            hasher.write(&quantized.to_le_bytes());
+        }
+        hasher.finish()
+    }
+}
+
+/// Error returned when an audio file cannot be checksummed.
+#[derive(Debug)]
+pub enum ChecksumError {
+    /// The file could not be opened or has no decodable audio track.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the file.
+    Decode(SymphoniaError),
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for ChecksumError {}
+
+/// Decode the first audio track of `file_path` and checksum its samples.
+///
+/// Hashes like [`RenderedPcm::checksum`], with samples converted to `f32`
+/// the same way playback converts them.
+///
+/// # Errors
+///
+/// Returns [`ChecksumError`] when the file cannot be opened or decoded.
+pub fn checksum_audio_file(file_path: &str) -> Result<AudioChecksum, ChecksumError> {
+    let (mut decoder, mut format) = open_file(file_path).map_err(ChecksumError::Open)?;
+    let track = find_audio_track(format.tracks()).map_err(ChecksumError::Open)?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let channels = track
+        .codec_params
+        .channels
+        .map_or(0, |channels| channels.count());
+
+    let mut hasher = Fnv1a::new(sample_rate, channels as u32);
+    let mut planar: Vec<Vec<f32>> = Vec::new();
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(err) => return Err(ChecksumError::Decode(err)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+        let decoded = decoder.decode(&packet).map_err(ChecksumError::Decode)?;
+        let decoded_channels = decoded.spec().channels.count();
+        planar.resize_with(decoded_channels, Vec::new);
+        for (channel, samples) in planar.iter_mut().enumerate() {
+            samples.clear();
+            for_each_channel_sample(&decoded, channel, |sample| samples.push(sample));
+        }
+        let frames = planar.first().map_or(0, Vec::len);
+        for frame in 0..frames {
+            for samples in &planar {
+                hasher.write(&samples[frame].to_bits().to_le_bytes());
+            }
+        }
+    }
+    Ok(hasher.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::pcm_output::PcmFormat;
+
+    fn rendered(samples: Vec<f32>) -> RenderedPcm {
+        RenderedPcm {
+            samples,
+            sample_rate: 48_000,
+            channels: 2,
+        }
+    }
+
+    #[test]
+    fn checksum_is_a_fixed_function_of_the_content() {
+        let pcm = rendered(vec![0.0, 0.5, -0.5, 1.0]);
+        assert_eq!(pcm.checksum(), pcm.clone().checksum());
+        assert_eq!(rendered(Vec::new()).checksum().to_string().len(), 16);
+
+        let mut louder = pcm.clone();
+        louder.samples[1] = 0.5000001;
+        assert_ne!(pcm.checksum(), louder.checksum());
+        let mut mono = pcm.clone();
+        mono.channels = 1;
+        assert_ne!(pcm.checksum(), mono.checksum());
+    }
+
+    #[test]
+    fn quantized_checksum_ignores_sub_lsb_differences() {
+        let pcm = rendered(vec![0.0, 0.25, -0.25, 0.75]);
+        let mut nudged = pcm.clone();
+        nudged.samples[1] += 1.0e-7;
+        assert_ne!(pcm.checksum(), nudged.checksum());
+        assert_eq!(
+            pcm.checksum_at_bit_depth(16),
+            nudged.checksum_at_bit_depth(16)
+        );
+        assert_ne!(pcm.checksum_at_bit_depth(16), pcm.checksum_at_bit_depth(24));
+    }
+
+    #[test]
+    fn float_wav_export_matches_the_render_checksum() {
+        let samples = (0..4_800)
+            .map(|i| (i as f32 * 0.01).sin() * 0.5)
+            .collect::<Vec<_>>();
+        let pcm = rendered(samples);
+        let path =
+            std::env::temp_dir().join(format!("proteus-checksum-{}.wav", std::process::id()));
+        pcm.write_wav(&path, PcmFormat::F32Le).unwrap();
+
+        let file_checksum = checksum_audio_file(&path.to_string_lossy()).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(file_checksum, pcm.checksum());
+    }
+
+    #[test]
+    fn missing_files_fail_to_checksum() {
+        assert!(matches!(
+            checksum_audio_file("/definitely/missing.wav"),
+            Err(ChecksumError::Open(_))
+        ));
+    }
+}
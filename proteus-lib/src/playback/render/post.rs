@@ -0,0 +1,210 @@
+//! Post-processing of finished offline renders.
+//!
+//! Exports often need the same mastering touches a DAW pass would add:
+//! silence trimmed from the ends, a level target, and short fades.
+//! [`PostProcessing`] applies them to a [`RenderedPcm`] in a fixed order —
+//! trim, loudness normalization, peak normalization, then fades — so fades
+//! land on the trimmed edges and never affect the measured level.
+
+use crate::dsp::loudness::LoudnessMeter;
+
+use super::RenderedPcm;
+
+/// Post-processing applied to a render before dither.
+///
+/// The default does nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PostProcessing {
+    /// Trim leading and trailing frames whose every channel stays below this
+    /// level in dBFS.
+    pub trim_silence_dbfs: Option<f32>,
+    /// Scale the render to this integrated loudness in LUFS.
+    ///
+    /// Renders too short or too quiet to measure are left unchanged.
+    pub loudness_lufs: Option<f32>,
+    /// Scale the render so its sample peak sits at this level in dBFS.
+    ///
+    /// With [`Self::loudness_lufs`] set this is a ceiling instead: gain is
+    /// only lowered when the loudness-normalized peak would exceed it.
+    pub peak_dbfs: Option<f32>,
+    /// Linear fade-in length in milliseconds (`0.0` = none).
+    pub fade_in_ms: f32,
+    /// Linear fade-out length in milliseconds (`0.0` = none).
+    pub fade_out_ms: f32,
+}
+
+impl RenderedPcm {
+    /// Apply `post` to the render in place.
+    pub fn apply_post_processing(&mut self, post: &PostProcessing) {
+        let channels = self.channels.max(1) as usize;
+        if let Some(threshold_dbfs) = post.trim_silence_dbfs {
+            trim_silence(&mut self.samples, channels, db_to_gain(threshold_dbfs));
+        }
+
+        let mut gain = 1.0_f32;
+        if let Some(target_lufs) = post.loudness_lufs {
+            let mut meter = LoudnessMeter::new(self.sample_rate, channels);
+            meter.process(&self.samples);
+            if let Some(measured_lufs) = meter.integrated_lufs() {
+                gain = db_to_gain(target_lufs - measured_lufs);
+            }
+        }
+        if let Some(target_dbfs) = post.peak_dbfs {
+            let peak = self
+                .samples
+                .iter()
+                .fold(0.0_f32, |max, sample| max.max(sample.abs()))
+                * gain;
+            let target = db_to_gain(target_dbfs);
+            if peak > 0.0 && (post.loudness_lufs.is_none() || peak > target) {
+                gain *= target / peak;
+            }
+        }
+        if gain.is_finite() && gain != 1.0 {
+            self.samples.iter_mut().for_each(|sample| *sample *= gain);
+        }
+
+        let rate = f64::from(self.sample_rate);
+        let frames = |ms: f32| (f64::from(ms.max(0.0)) / 1000.0 * rate).round() as usize;
+        fade_in(&mut self.samples, channels, frames(post.fade_in_ms));
+        fade_out(&mut self.samples, channels, frames(post.fade_out_ms));
+    }
+}
+
+fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+fn trim_silence(samples: &mut Vec<f32>, channels: usize, threshold: f32) {
+    let audible = |frame: &[f32]| frame.iter().any(|sample| sample.abs() >= threshold);
+    let start = samples.chunks(channels).position(audible);
+    let end = samples.chunks(channels).rposition(audible);
+    match (start, end) {
+        (Some(start), Some(end)) => {
+            samples.truncate((end + 1) * channels);
+            samples.drain(..start * channels);
+        }
+        _ => samples.clear(),
+    }
+}
+
+fn fade_in(samples: &mut [f32], channels: usize, frames: usize) {
+    let total = samples.len() / channels;
+    let frames = frames.min(total);
+    for (frame, values) in samples.chunks_mut(channels).take(frames).enumerate() {
+        let gain = frame as f32 / frames as f32;
+        values.iter_mut().for_each(|value| *value *= gain);
+    }
+}
+
+fn fade_out(samples: &mut [f32], channels: usize, frames: usize) {
+    let total = samples.len() / channels;
+    let frames = frames.min(total);
+    let start = total - frames;
+    for (frame, values) in samples.chunks_mut(channels).skip(start).enumerate() {
+        let gain = (frames - frame - 1) as f32 / frames as f32;
+        values.iter_mut().for_each(|value| *value *= gain);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo(samples: Vec<f32>) -> RenderedPcm {
+        RenderedPcm {
+            samples,
+            sample_rate: 1000,
+            channels: 2,
+        }
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0_f32, |max, s| max.max(s.abs()))
+    }
+
+    #[test]
+    fn default_post_processing_leaves_the_render_alone() {
+        let mut rendered = stereo(vec![0.0, 0.0, 0.5, -0.25, 0.0, 0.0]);
+        let original = rendered.clone();
+        rendered.apply_post_processing(&PostProcessing::default());
+        assert_eq!(rendered, original);
+    }
+
+    #[test]
+    fn silence_is_trimmed_from_both_ends_by_whole_frames() {
+        let mut rendered = stereo(vec![0.0, 0.0001, 0.0, 0.5, 0.2, 0.0, 0.0, 0.0]);
+        rendered.apply_post_processing(&PostProcessing {
+            trim_silence_dbfs: Some(-60.0),
+            ..PostProcessing::default()
+        });
+        assert_eq!(rendered.samples, vec![0.0, 0.5, 0.2, 0.0]);
+
+        let mut silent = stereo(vec![0.0; 8]);
+        silent.apply_post_processing(&PostProcessing {
+            trim_silence_dbfs: Some(-60.0),
+            ..PostProcessing::default()
+        });
+        assert!(silent.samples.is_empty());
+    }
+
+    #[test]
+    fn peak_normalization_hits_the_target() {
+        let mut rendered = stereo(vec![0.1, -0.2, 0.05, 0.0]);
+        rendered.apply_post_processing(&PostProcessing {
+            peak_dbfs: Some(-1.0),
+            ..PostProcessing::default()
+        });
+        assert!((peak(&rendered.samples) - db_to_gain(-1.0)).abs() < 1e-6);
+        assert!((rendered.samples[0] / rendered.samples[1] + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn loudness_normalization_reaches_the_target_under_a_peak_ceiling() {
+        let tone: Vec<f32> = (0..48_000 * 2)
+            .map(|i| 0.05 * ((i / 2) as f32 * 440.0 * std::f32::consts::TAU / 48_000.0).sin())
+            .collect();
+        let measure = |rendered: &RenderedPcm| {
+            let mut meter = LoudnessMeter::new(rendered.sample_rate, 2);
+            meter.process(&rendered.samples);
+            meter.integrated_lufs().unwrap()
+        };
+        let mut rendered = RenderedPcm {
+            samples: tone.clone(),
+            sample_rate: 48_000,
+            channels: 2,
+        };
+        rendered.apply_post_processing(&PostProcessing {
+            loudness_lufs: Some(-16.0),
+            ..PostProcessing::default()
+        });
+        assert!((measure(&rendered) + 16.0).abs() < 0.1);
+
+        let mut ceiling = RenderedPcm {
+            samples: tone,
+            sample_rate: 48_000,
+            channels: 2,
+        };
+        ceiling.apply_post_processing(&PostProcessing {
+            loudness_lufs: Some(-6.0),
+            peak_dbfs: Some(-10.0),
+            ..PostProcessing::default()
+        });
+        assert!((peak(&ceiling.samples) - db_to_gain(-10.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn fades_ramp_the_ends_to_silence() {
+        let mut rendered = stereo(vec![1.0; 20]);
+        rendered.apply_post_processing(&PostProcessing {
+            fade_in_ms: 4.0,
+            fade_out_ms: 2.0,
+            ..PostProcessing::default()
+        });
+        let left: Vec<f32> = rendered.samples.iter().step_by(2).copied().collect();
+        assert_eq!(
+            left,
+            vec![0.0, 0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0, 0.5, 0.0]
+        );
+    }
+}
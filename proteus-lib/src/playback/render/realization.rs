@@ -0,0 +1,290 @@
+//! Offline re-rendering of a recorded [`Realization`].
+//!
+//! Spans joined by shuffle points cover one contiguous stretch of the
+//! timeline. Each such run is rendered in one pass with its recorded
+//! selections as a fixed schedule, so decoding and effect state carry across
+//! shuffle points exactly as they did live. Every other transition restarted
+//! the live engine, so each run starts from a fresh render: it fades in over
+//! the recorded fade, or, after a section jump, is crossfaded from the
+//! continuation of the previous run. Player volume and output-side fades are
+//! not part of the realization and are not applied.
+
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use crate::container::prot::Prot;
+use crate::playback::engine::{render_offline, DecodeThreading, PlaybackBufferSettings};
+use crate::playback::pcm_output::PcmFormat;
+use crate::playback::realization::{Realization, RealizedSpan, SpanTransition};
+
+use super::RenderedPcm;
+
+/// Error returned when a realization cannot be re-rendered.
+#[derive(Debug)]
+pub enum RealizationRenderError {
+    /// The realization was recorded at a different sample rate or channel count.
+    FormatMismatch {
+        /// Sample rate recorded in the realization.
+        sample_rate: u32,
+        /// Channel count recorded in the realization.
+        channels: u32,
+    },
+    /// A recorded source names no track of the container.
+    UnknownSource(String),
+    /// Writing the output file failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for RealizationRenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FormatMismatch {
+                sample_rate,
+                channels,
+            } => write!(
+                f,
+                "realization was recorded at {} Hz with {} channels",
+                sample_rate, channels
+            ),
+            Self::UnknownSource(source) => {
+                write!(f, "realization source not in container: {}", source)
+            }
+            Self::Io(err) => write!(f, "failed to write realization render: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for RealizationRenderError {}
+
+/// Re-render the audio recorded in `realization` from `prot`.
+///
+/// No randomness is involved: every span plays its recorded selection from
+/// its recorded timeline position, with the container's effect chain.
+///
+/// # Errors
+///
+/// Returns [`RealizationRenderError::FormatMismatch`] when `prot` does not
+/// match the recorded format and [`RealizationRenderError::UnknownSource`]
+/// when a recorded source is missing from `prot`.
+pub fn render_realization_to_pcm(
+    prot: &Prot,
+    realization: &Realization,
+) -> Result<RenderedPcm, RealizationRenderError> {
+    let mut rendered = RenderedPcm {
+        samples: Vec::new(),
+        sample_rate: prot.info.sample_rate,
+        channels: prot.info.channels,
+    };
+    if realization.sample_rate != rendered.sample_rate || realization.channels != rendered.channels
+    {
+        return Err(RealizationRenderError::FormatMismatch {
+            sample_rate: realization.sample_rate,
+            channels: realization.channels,
+        });
+    }
+    let channels = rendered.channels.max(1) as usize;
+    let rate = f64::from(rendered.sample_rate);
+    let effects = prot.get_effects().unwrap_or_default();
+    let runs = split_runs(&realization.spans);
+
+    let mut tail: Vec<f32> = Vec::new();
+    for (index, run) in runs.iter().enumerate() {
+        let first = &run[0];
+        let run_seconds: f64 = run.iter().map(|span| span.duration_s).sum();
+        let tail_seconds = runs
+            .get(index + 1)
+            .filter(|next| next[0].transition == SpanTransition::Section)
+            .map_or(0.0, |next| f64::from(next[0].fade_ms.max(0.0)) / 1000.0);
+
+        let mut run_prot = prot.clone();
+        run_prot
+            .set_fixed_schedule(&run_schedule(run))
+            .map_err(RealizationRenderError::UnknownSource)?;
+        let mut samples = Vec::new();
+        render_offline(
+            run_prot,
+            effects.clone(),
+            PlaybackBufferSettings::new(0.0),
+            first.position_s,
+            run_seconds + tail_seconds,
+            DecodeThreading::Inline.into(),
+            |chunk| samples.extend_from_slice(chunk),
+        );
+
+        // Size each run from cumulative output time so rounding never drifts.
+        let end_frame = ((first.output_s + run_seconds) * rate).round() as usize;
+        let body_len = end_frame.saturating_sub(rendered.samples.len() / channels) * channels;
+        let tail_len = (tail_seconds * rate).round() as usize * channels;
+        samples.resize(body_len + tail_len, 0.0);
+        let next_tail = samples.split_off(body_len);
+
+        if first.transition == SpanTransition::Section {
+            crossfade_from(&tail, &mut samples, channels);
+        } else {
+            let fade_frames = (f64::from(first.fade_ms.max(0.0)) / 1000.0 * rate).round();
+            fade_in(&mut samples, channels, fade_frames as usize);
+        }
+        rendered.samples.extend_from_slice(&samples);
+        tail = next_tail;
+    }
+    Ok(rendered)
+}
+
+/// Re-render `realization` from `prot` and write it to `out` as a 32-bit float WAV.
+///
+/// # Errors
+///
+/// Returns the errors of [`render_realization_to_pcm`], and
+/// [`RealizationRenderError::Io`] when the file cannot be written.
+pub fn render_realization_to_file(
+    prot: &Prot,
+    realization: &Realization,
+    out: impl AsRef<Path>,
+) -> Result<(), RealizationRenderError> {
+    render_realization_to_pcm(prot, realization)?
+        .write_wav(out, PcmFormat::F32Le)
+        .map_err(RealizationRenderError::Io)
+}
+
+// Group spans into runs that each played from one engine.
+fn split_runs(spans: &[RealizedSpan]) -> Vec<&[RealizedSpan]> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    for index in 1..=spans.len() {
+        let continues = spans
+            .get(index)
+            .is_some_and(|span| span.transition == SpanTransition::ShufflePoint);
+        if !continues {
+            runs.push(&spans[start..index]);
+            start = index;
+        }
+    }
+    runs
+}
+
+// Fixed schedule for one run; the first selection also covers everything
+// before the run's start position.
+fn run_schedule(run: &[RealizedSpan]) -> Vec<(f64, Vec<String>)> {
+    run.iter()
+        .enumerate()
+        .map(|(index, span)| {
+            let at_seconds = if index == 0 { 0.0 } else { span.position_s };
+            (at_seconds, span.tracks.concat())
+        })
+        .collect()
+}
+
+fn fade_in(samples: &mut [f32], channels: usize, frames: usize) {
+    if frames == 0 {
+        return;
+    }
+    for (frame, values) in samples.chunks_mut(channels).take(frames).enumerate() {
+        let gain = frame as f32 / frames as f32;
+        values.iter_mut().for_each(|value| *value *= gain);
+    }
+}
+
+// Equal-power crossfade from `tail` into the start of `head`, in place.
+fn crossfade_from(tail: &[f32], head: &mut [f32], channels: usize) {
+    let frames = tail.len() / channels;
+    for (frame, (old, new)) in tail
+        .chunks(channels)
+        .zip(head.chunks_mut(channels))
+        .enumerate()
+    {
+        let t = (frame as f32 + 0.5) / frames as f32 * FRAC_PI_2;
+        let (fade_in, fade_out) = t.sin_cos();
+        for (out, old) in new.iter_mut().zip(old) {
+            *out = *out * fade_in + *old * fade_out;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::playback::realization::RealizationRecorder;
+    use crate::playback::render::render_selection_to_pcm;
+
+    fn fixture() -> Prot {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio/demo_shuffle_points_effects.prot");
+        Prot::try_new(&path.display().to_string()).unwrap()
+    }
+
+    fn realization_of(prot: &Prot, recorder: RealizationRecorder) -> Realization {
+        Realization {
+            container: prot.get_container_path(),
+            sample_rate: prot.info.sample_rate,
+            channels: prot.info.channels,
+            spans: recorder.spans(),
+        }
+    }
+
+    #[test]
+    fn realization_render_matches_seeded_render() {
+        let mut prot = fixture();
+        prot.refresh_tracks_with_seed(42);
+        let mut recorder = RealizationRecorder::new(prot.get_shuffle_schedule());
+        recorder.begin_run(0.0, 0.0);
+        recorder.record_output(1.0);
+        let realization = realization_of(&prot, recorder);
+
+        let replayed = render_realization_to_pcm(&fixture(), &realization).unwrap();
+        let expected = render_selection_to_pcm(&prot, 42, 1.0);
+        assert_eq!(replayed.samples.len(), expected.samples.len());
+        let max_error = replayed
+            .samples
+            .iter()
+            .zip(&expected.samples)
+            .fold(0.0_f32, |max, (a, b)| max.max((a - b).abs()));
+        assert!(max_error < 1.0e-4, "max error {max_error}");
+    }
+
+    #[test]
+    fn realization_render_rejects_unknown_sources_and_formats() {
+        let prot = fixture();
+        let mut recorder = RealizationRecorder::new(vec![(0.0, vec![vec!["999".to_string()]])]);
+        recorder.begin_run(0.0, 0.0);
+        recorder.record_output(0.5);
+        let mut realization = realization_of(&prot, recorder);
+        assert!(matches!(
+            render_realization_to_pcm(&prot, &realization),
+            Err(RealizationRenderError::UnknownSource(source)) if source == "999"
+        ));
+
+        realization.channels += 1;
+        assert!(matches!(
+            render_realization_to_pcm(&prot, &realization),
+            Err(RealizationRenderError::FormatMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn runs_split_at_restarts_and_fade_in_ramps() {
+        let span = |transition| RealizedSpan {
+            output_s: 0.0,
+            position_s: 0.0,
+            duration_s: 1.0,
+            transition,
+            fade_ms: 0.0,
+            tracks: Vec::new(),
+        };
+        let spans = [
+            span(SpanTransition::Start),
+            span(SpanTransition::ShufflePoint),
+            span(SpanTransition::Section),
+            span(SpanTransition::Seek),
+        ];
+        let lengths: Vec<usize> = split_runs(&spans).iter().map(|run| run.len()).collect();
+        assert_eq!(lengths, vec![2, 1, 1]);
+
+        let mut samples = vec![1.0_f32; 8];
+        fade_in(&mut samples, 2, 2);
+        assert_eq!(samples, vec![0.0, 0.0, 0.5, 0.5, 1.0, 1.0, 1.0, 1.0]);
+    }
+}
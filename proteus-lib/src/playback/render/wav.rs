@@ -0,0 +1,103 @@
+//! WAV file output for offline renders.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::playback::pcm_output::PcmFormat;
+
+use super::RenderedPcm;
+
+/// `WAVE_FORMAT_PCM` format tag.
+const FORMAT_PCM: u16 = 1;
+/// `WAVE_FORMAT_IEEE_FLOAT` format tag.
+const FORMAT_IEEE_FLOAT: u16 = 3;
+
+impl RenderedPcm {
+    /// Write the render to `path` as a WAV file in `format`.
+    ///
+    /// # Errors
+    ///
+    /// Returns any I/O error from creating or writing the file, or
+    /// [`io::ErrorKind::InvalidInput`] when the render is too long for a
+    /// WAV header.
+    pub fn write_wav(&self, path: impl AsRef<Path>, format: PcmFormat) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.encode_wav(&mut writer, format)?;
+        writer.flush()
+    }
+
+    /// Write the render as WAV bytes to `out`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`RenderedPcm::write_wav`].
+    pub fn encode_wav(&self, out: &mut impl Write, format: PcmFormat) -> io::Result<()> {
+        let sample_bytes = format.sample_bytes();
+        let data_len = u32::try_from(self.samples.len() * sample_bytes)
+            .ok()
+            .filter(|len| *len <= u32::MAX - 36)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "render too long for wav")
+            })?;
+        let channels = self.channels.max(1) as u16;
+        let block_align = channels * sample_bytes as u16;
+        let format_tag = match format {
+            PcmFormat::F32Le => FORMAT_IEEE_FLOAT,
+            PcmFormat::S16Le => FORMAT_PCM,
+        };
+
+        let mut header = Vec::with_capacity(44);
+        header.extend_from_slice(b"RIFF");
+        header.extend_from_slice(&(36 + data_len).to_le_bytes());
+        header.extend_from_slice(b"WAVEfmt ");
+        header.extend_from_slice(&16_u32.to_le_bytes());
+        header.extend_from_slice(&format_tag.to_le_bytes());
+        header.extend_from_slice(&channels.to_le_bytes());
+        header.extend_from_slice(&self.sample_rate.to_le_bytes());
+        header.extend_from_slice(&(self.sample_rate * u32::from(block_align)).to_le_bytes());
+        header.extend_from_slice(&block_align.to_le_bytes());
+        header.extend_from_slice(&(sample_bytes as u16 * 8).to_le_bytes());
+        header.extend_from_slice(b"data");
+        header.extend_from_slice(&data_len.to_le_bytes());
+        out.write_all(&header)?;
+
+        let mut data = Vec::new();
+        format.encode(&self.samples, &mut data);
+        out.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wav_header_describes_samples() {
+        let pcm = RenderedPcm {
+            samples: vec![0.5, -0.5, 1.0, 0.0],
+            sample_rate: 48_000,
+            channels: 2,
+        };
+        let mut bytes = Vec::new();
+        pcm.encode_wav(&mut bytes, PcmFormat::S16Le).unwrap();
+        assert_eq!(bytes.len(), 44 + 8);
+        assert_eq!(&bytes[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(bytes[4..8].try_into().unwrap()), 44);
+        assert_eq!(u16::from_le_bytes([bytes[20], bytes[21]]), FORMAT_PCM);
+        assert_eq!(u16::from_le_bytes([bytes[22], bytes[23]]), 2);
+        assert_eq!(
+            u32::from_le_bytes(bytes[28..32].try_into().unwrap()),
+            192_000
+        );
+        assert_eq!(i16::from_le_bytes([bytes[44], bytes[45]]), 16_384);
+
+        let mut float = Vec::new();
+        pcm.encode_wav(&mut float, PcmFormat::F32Le).unwrap();
+        assert_eq!(
+            u16::from_le_bytes([float[20], float[21]]),
+            FORMAT_IEEE_FLOAT
+        );
+        assert_eq!(float.len(), 44 + 16);
+    }
+}
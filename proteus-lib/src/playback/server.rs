@@ -0,0 +1,124 @@
+//! Shared output stream for running several players on one device.
+//!
+//! Every [`Player`] normally opens its own device stream. Players created
+//! through the same [`AudioServer`] instead connect sinks to one shared
+//! stream, whose mixer sums them. This keeps a single device handle open
+//! when several containers play at once (scene crossfades, layered apps)
+//! and works with drivers that only allow one stream per device.
+
+use std::sync::{Arc, Mutex};
+
+use rodio::{OutputStream, OutputStreamBuilder, StreamError};
+
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::player::{Player, PlayerInitError, PlayerInitOptions, PlayerSource};
+
+/// Shared device output stream that multiple players mix into.
+///
+/// Cloning an `AudioServer` yields another handle to the same stream. The
+/// stream closes once the server and every player created from it are
+/// dropped.
+#[derive(Clone)]
+pub struct AudioServer {
+    stream: Arc<Mutex<Option<OutputStream>>>,
+}
+
+impl AudioServer {
+    /// Create a server that opens the default output device on first playback.
+    pub fn new() -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Create a server and open the default output device immediately.
+    ///
+    /// # Errors
+    ///
+    /// Returns the rodio error when the default device cannot be opened.
+    pub fn open_default() -> Result<Self, StreamError> {
+        Ok(Self::from_stream(
+            OutputStreamBuilder::open_default_stream()?
+        ))
+    }
+
+    /// Create a server around an already opened output stream.
+    ///
+    /// Use this to target a specific device or stream configuration.
+    pub fn from_stream(stream: OutputStream) -> Self {
+        Self {
+            stream: Arc::new(Mutex::new(Some(stream))),
+        }
+    }
+
+    /// Return `true` once the shared device stream is open.
+    pub fn is_open(&self) -> bool {
+        lock_recoverable(
+            &self.stream,
+            "audio server output stream",
+            "the output stream handle is disposable runtime I/O state",
+        )
+        .is_some()
+    }
+
+    /// Create a player that outputs through this server's stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PlayerInitError`] under the same conditions as
+    /// [`Player::try_from_source_with_options`].
+    pub fn create_player(
+        &self,
+        source: PlayerSource,
+        options: PlayerInitOptions,
+    ) -> Result<Player, PlayerInitError> {
+        Player::try_from_source_with_output(source, options, self.stream.clone())
+    }
+
+    /// Return `true` when `player` outputs through this server's stream.
+    pub fn serves(&self, player: &Player) -> bool {
+        player.shares_output_stream(&self.stream)
+    }
+}
+
+impl Default for AudioServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for AudioServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AudioServer")
+            .field("open", &self.is_open())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AudioServer;
+    use crate::container::prot::PathsTrack;
+    use crate::playback::player::{PlayerInitOptions, PlayerSource};
+
+    fn source() -> PlayerSource {
+        PlayerSource::FilePaths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])])
+    }
+
+    #[test]
+    fn players_created_by_a_server_share_its_stream() {
+        let server = AudioServer::new();
+        let first = server
+            .create_player(source(), PlayerInitOptions::default())
+            .unwrap();
+        let second = server
+            .clone()
+            .create_player(source(), PlayerInitOptions::default())
+            .unwrap();
+        assert!(server.serves(&first));
+        assert!(server.serves(&second));
+        assert!(!AudioServer::new().serves(&first));
+    }
+}
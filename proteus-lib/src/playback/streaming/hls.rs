@@ -0,0 +1,203 @@
+//! Rolling HLS playlist writer.
+//!
+//! Each segment is a complete encoded stream written to its own file, and the
+//! media playlist keeps a sliding window of the newest segments. Files are
+//! written under a temporary name and renamed into place so an HTTP server
+//! never serves a partial segment or playlist. Segments that leave the window
+//! are deleted.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+
+use super::StreamEncoder;
+
+/// Directory layout and timing of an HLS stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HlsConfig {
+    dir: PathBuf,
+    playlist_name: String,
+    segment_ms: u32,
+    window: usize,
+}
+
+impl HlsConfig {
+    /// Write `stream.m3u8` and 6 s segments to `dir`, keeping the newest 5.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            playlist_name: "stream.m3u8".to_string(),
+            segment_ms: 6_000,
+            window: 5,
+        }
+    }
+
+    /// File name of the media playlist inside the directory.
+    pub fn with_playlist_name(mut self, name: impl Into<String>) -> Self {
+        self.playlist_name = name.into();
+        self
+    }
+
+    /// Target segment duration in milliseconds (minimum 500).
+    pub fn with_segment_ms(mut self, segment_ms: u32) -> Self {
+        self.segment_ms = segment_ms.max(500);
+        self
+    }
+
+    /// Number of segments listed in the playlist (minimum 2).
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(2);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment {
+    sequence: u64,
+    duration_s: f64,
+}
+
+pub(super) struct HlsWriter {
+    config: HlsConfig,
+    extension: &'static str,
+    frame_samples: usize,
+    sample_rate: u32,
+    segments: VecDeque<Segment>,
+    next_sequence: u64,
+    current: Vec<u8>,
+    current_frames: u64,
+}
+
+impl HlsWriter {
+    pub(super) fn create(
+        config: &HlsConfig,
+        encoder: &dyn StreamEncoder,
+        channels: u16,
+    ) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+        Ok(Self {
+            config: config.clone(),
+            extension: encoder.segment_extension(),
+            frame_samples: channels.max(1) as usize,
+            sample_rate: encoder.sample_rate().max(1),
+            segments: VecDeque::new(),
+            next_sequence: 0,
+            current: Vec::new(),
+            current_frames: 0,
+        })
+    }
+
+    pub(super) fn push(
+        &mut self,
+        encoder: &mut dyn StreamEncoder,
+        samples: &[f32],
+    ) -> io::Result<()> {
+        if self.current.is_empty() {
+            encoder.begin_stream(&mut self.current)?;
+        }
+        encoder.encode(samples, &mut self.current)?;
+        self.current_frames += (samples.len() / self.frame_samples) as u64;
+        let segment_frames = self.config.segment_ms as u64 * self.sample_rate as u64 / 1000;
+        if self.current_frames >= segment_frames {
+            self.close_segment(encoder, false)?;
+        }
+        Ok(())
+    }
+
+    // Flush the partial segment and mark the playlist as ended.
+    pub(super) fn finish(&mut self, encoder: &mut dyn StreamEncoder) {
+        if let Err(err) = self.close_segment(encoder, true) {
+            warn!("failed to finalize hls playlist: {}", err);
+        }
+    }
+
+    fn close_segment(&mut self, encoder: &mut dyn StreamEncoder, ended: bool) -> io::Result<()> {
+        if self.current_frames > 0 {
+            encoder.end_stream(&mut self.current)?;
+            let segment = Segment {
+                sequence: self.next_sequence,
+                duration_s: self.current_frames as f64 / self.sample_rate as f64,
+            };
+            write_atomic(&self.segment_path(segment.sequence), &self.current)?;
+            self.next_sequence += 1;
+            self.segments.push_back(segment);
+            while self.segments.len() > self.config.window {
+                if let Some(expired) = self.segments.pop_front() {
+                    let _ = fs::remove_file(self.segment_path(expired.sequence));
+                }
+            }
+        }
+        self.current.clear();
+        self.current_frames = 0;
+        let playlist = render_playlist(&self.segments, self.extension, ended);
+        write_atomic(
+            &self.config.dir.join(&self.config.playlist_name),
+            playlist.as_bytes(),
+        )
+    }
+
+    fn segment_path(&self, sequence: u64) -> PathBuf {
+        self.config.dir.join(segment_name(sequence, self.extension))
+    }
+}
+
+fn segment_name(sequence: u64, extension: &str) -> String {
+    format!("segment_{:06}.{}", sequence, extension)
+}
+
+fn render_playlist(segments: &VecDeque<Segment>, extension: &str, ended: bool) -> String {
+    let target = segments
+        .iter()
+        .map(|segment| segment.duration_s.ceil() as u64)
+        .max()
+        .unwrap_or(1);
+    let first = segments.front().map_or(0, |segment| segment.sequence);
+    let mut playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        target, first
+    );
+    for segment in segments {
+        playlist.push_str(&format!(
+            "#EXTINF:{:.3},\n{}\n",
+            segment.duration_s,
+            segment_name(segment.sequence, extension)
+        ));
+    }
+    if ended {
+        playlist.push_str("#EXT-X-ENDLIST\n");
+    }
+    playlist
+}
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, bytes)?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn playlist_lists_window_with_target_duration() {
+        let segments: VecDeque<Segment> = [(4, 6.0), (5, 6.02)]
+            .into_iter()
+            .map(|(sequence, duration_s)| Segment {
+                sequence,
+                duration_s,
+            })
+            .collect();
+        let playlist = render_playlist(&segments, "opus", true);
+        assert_eq!(
+            playlist,
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:7\n#EXT-X-MEDIA-SEQUENCE:4\n\
+             #EXTINF:6.000,\nsegment_000004.opus\n#EXTINF:6.020,\nsegment_000005.opus\n\
+             #EXT-X-ENDLIST\n"
+        );
+    }
+}
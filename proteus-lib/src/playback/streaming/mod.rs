@@ -0,0 +1,193 @@
+//! Network streaming output (feature `streaming`).
+//!
+//! [`StreamBackend`] encodes the master output and publishes it either as a
+//! live source on an Icecast mount or as a rolling HLS playlist of segment
+//! files in a directory, so a player can run unattended as a generative-radio
+//! backend. Install it with `Player::set_output_backend`.
+//!
+//! Encoding goes through the [`StreamEncoder`] trait. [`OpusEncoder`] (Ogg
+//! Opus) is built in; other codecs such as AAC plug in through
+//! [`StreamBackend::new`]. The connection or segment sequence lives on the
+//! backend, so seeks and section jumps continue the same stream.
+
+mod hls;
+mod icecast;
+mod opus;
+
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+pub use hls::HlsConfig;
+pub use icecast::IcecastConfig;
+pub use opus::OpusEncoder;
+
+use hls::HlsWriter;
+use icecast::IcecastConnection;
+
+use crate::playback::mutex_policy::lock_recoverable;
+use crate::playback::output_sink::{OutputBackend, OutputBackendError, OutputSink};
+use crate::playback::pcm_output::open_paced_sink;
+
+/// Encoder that turns interleaved samples into a streamable byte format.
+pub trait StreamEncoder: Send {
+    /// MIME type of the encoded stream, e.g. `audio/ogg`.
+    fn content_type(&self) -> &'static str;
+    /// File extension used for HLS segments, without the dot.
+    fn segment_extension(&self) -> &'static str;
+    /// Sample rate the encoder expects its input at.
+    fn sample_rate(&self) -> u32;
+    /// Start a new independently decodable stream, appending any headers.
+    fn begin_stream(&mut self, out: &mut Vec<u8>) -> io::Result<()>;
+    /// Encode interleaved samples, appending whatever output is ready.
+    fn encode(&mut self, samples: &[f32], out: &mut Vec<u8>) -> io::Result<()>;
+    /// Close the current stream, appending its trailer.
+    ///
+    /// Input buffered short of a full codec frame carries over into the next
+    /// stream, so consecutive HLS segments stay gapless.
+    fn end_stream(&mut self, out: &mut Vec<u8>) -> io::Result<()>;
+}
+
+/// Builds an encoder for the given channel count.
+pub type EncoderFactory =
+    Arc<dyn Fn(u16) -> Result<Box<dyn StreamEncoder>, OutputBackendError> + Send + Sync>;
+
+/// Where a [`StreamBackend`] publishes its stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamTarget {
+    /// Live source on an Icecast mount.
+    Icecast(IcecastConfig),
+    /// Rolling HLS playlist written to a directory.
+    Hls(HlsConfig),
+}
+
+/// Output backend that encodes and publishes the master output.
+#[derive(Clone)]
+pub struct StreamBackend {
+    target: StreamTarget,
+    encoder: EncoderFactory,
+    publisher: Arc<Mutex<Option<Publisher>>>,
+}
+
+impl StreamBackend {
+    /// Publish to `target` using encoders built by `encoder`.
+    pub fn new(target: StreamTarget, encoder: EncoderFactory) -> Self {
+        Self {
+            target,
+            encoder,
+            publisher: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Publish Ogg Opus at `bitrate` bits per second to `target`.
+    pub fn opus(target: StreamTarget, bitrate: u32) -> Self {
+        Self::new(
+            target,
+            Arc::new(move |channels| {
+                OpusEncoder::new(channels, bitrate)
+                    .map(|encoder| Box::new(encoder) as Box<dyn StreamEncoder>)
+            }),
+        )
+    }
+
+    /// Destination of the stream.
+    pub fn target(&self) -> &StreamTarget {
+        &self.target
+    }
+}
+
+impl OutputBackend for StreamBackend {
+    fn open_sink(
+        &self,
+        channels: u16,
+        sample_rate: u32,
+    ) -> Result<Box<dyn OutputSink>, OutputBackendError> {
+        if channels == 0 || sample_rate == 0 {
+            return Err(OutputBackendError::UnsupportedFormat {
+                channels,
+                sample_rate,
+            });
+        }
+        let encoder_rate = {
+            let mut publisher = lock_publisher(&self.publisher);
+            if publisher.as_ref().map(|p| p.channels) != Some(channels) {
+                let encoder = (self.encoder)(channels)?;
+                *publisher = Some(Publisher::open(&self.target, encoder, channels)?);
+            }
+            publisher.as_ref().map_or(sample_rate, |p| p.sample_rate())
+        };
+        let publisher = self.publisher.clone();
+        open_paced_sink(
+            channels,
+            sample_rate,
+            encoder_rate,
+            Box::new(move |samples| match lock_publisher(&publisher).as_mut() {
+                Some(publisher) => publisher.push(samples),
+                None => Err(io::Error::new(io::ErrorKind::NotConnected, "stream closed")),
+            }),
+        )
+    }
+}
+
+/// Recoverable poison policy: a failed publish already ends the stream.
+fn lock_publisher(publisher: &Mutex<Option<Publisher>>) -> MutexGuard<'_, Option<Publisher>> {
+    lock_recoverable(
+        publisher,
+        "stream publisher",
+        "a failed publish already ends the stream",
+    )
+}
+
+enum PublishTarget {
+    Icecast(IcecastConnection),
+    Hls(HlsWriter),
+}
+
+// Encoder plus destination, kept across sinks so the stream is continuous.
+struct Publisher {
+    encoder: Box<dyn StreamEncoder>,
+    channels: u16,
+    target: PublishTarget,
+}
+
+impl Publisher {
+    fn open(
+        target: &StreamTarget,
+        mut encoder: Box<dyn StreamEncoder>,
+        channels: u16,
+    ) -> Result<Self, OutputBackendError> {
+        let target = match target {
+            StreamTarget::Icecast(config) => PublishTarget::Icecast(
+                IcecastConnection::connect(config, encoder.as_mut())
+                    .map_err(|err| OutputBackendError::Unavailable(err.to_string()))?,
+            ),
+            StreamTarget::Hls(config) => PublishTarget::Hls(
+                HlsWriter::create(config, encoder.as_ref(), channels)
+                    .map_err(|err| OutputBackendError::Unavailable(err.to_string()))?,
+            ),
+        };
+        Ok(Self {
+            encoder,
+            channels,
+            target,
+        })
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.encoder.sample_rate()
+    }
+
+    fn push(&mut self, samples: &[f32]) -> io::Result<()> {
+        match &mut self.target {
+            PublishTarget::Icecast(connection) => connection.send(self.encoder.as_mut(), samples),
+            PublishTarget::Hls(writer) => writer.push(self.encoder.as_mut(), samples),
+        }
+    }
+}
+
+impl Drop for Publisher {
+    fn drop(&mut self) {
+        if let PublishTarget::Hls(writer) = &mut self.target {
+            writer.finish(self.encoder.as_mut());
+        }
+    }
+}
@@ -0,0 +1,179 @@
+//! Ogg Opus stream encoder.
+//!
+//! Encodes 20 ms Opus frames at 48 kHz and wraps them in Ogg pages
+//! (RFC 7845). The newest packet is held back one frame so that closing a
+//! stream can flag it as end-of-stream without padding extra audio.
+
+use std::io;
+
+use audiopus::coder::Encoder;
+use audiopus::{Application, Bitrate, Channels, SampleRate};
+use ogg::writing::{PacketWriteEndInfo, PacketWriter};
+
+use super::StreamEncoder;
+use crate::playback::output_sink::OutputBackendError;
+
+/// Opus always runs at 48 kHz in this encoder.
+const OPUS_RATE: u32 = 48_000;
+/// Frames per Opus packet (20 ms).
+const FRAME_SIZE: usize = 960;
+/// Upper bound on one encoded packet, per the Opus recommendation.
+const MAX_PACKET_BYTES: usize = 4_000;
+
+/// Ogg Opus encoder for mono or stereo streams.
+pub struct OpusEncoder {
+    encoder: Encoder,
+    channels: u16,
+    pre_skip: u16,
+    streams_started: u32,
+    serial: u32,
+    granule: u64,
+    pending: Vec<f32>,
+    packet: Vec<u8>,
+    held: Option<(Vec<u8>, u64)>,
+    ogg: PacketWriter<Vec<u8>>,
+}
+
+impl OpusEncoder {
+    /// Create an encoder for `channels` (1 or 2) at `bitrate` bits per second.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`OutputBackendError::UnsupportedFormat`] for other channel
+    /// counts and [`OutputBackendError::Other`] when libopus rejects the
+    /// configuration.
+    pub fn new(channels: u16, bitrate: u32) -> Result<Self, OutputBackendError> {
+        let opus_channels = match channels {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => {
+                return Err(OutputBackendError::UnsupportedFormat {
+                    channels,
+                    sample_rate: OPUS_RATE,
+                })
+            }
+        };
+        let opus_err = |err: audiopus::Error| OutputBackendError::Other(err.to_string());
+        let mut encoder = Encoder::new(SampleRate::Hz48000, opus_channels, Application::Audio)
+            .map_err(opus_err)?;
+        encoder
+            .set_bitrate(Bitrate::BitsPerSecond(bitrate.min(i32::MAX as u32) as i32))
+            .map_err(opus_err)?;
+        let pre_skip = encoder.lookahead().map_err(opus_err)? as u16;
+        Ok(Self {
+            encoder,
+            channels,
+            pre_skip,
+            streams_started: 0,
+            serial: 0,
+            granule: 0,
+            pending: Vec::new(),
+            packet: vec![0; MAX_PACKET_BYTES],
+            held: None,
+            ogg: PacketWriter::new(Vec::new()),
+        })
+    }
+
+    fn write_packet(
+        &mut self,
+        data: Vec<u8>,
+        info: PacketWriteEndInfo,
+        granule: u64,
+    ) -> io::Result<()> {
+        self.ogg
+            .write_packet(data.into_boxed_slice(), self.serial, info, granule)
+    }
+
+    // Write the held packet, then hold `next` in its place.
+    fn rotate_held(
+        &mut self,
+        next: Option<(Vec<u8>, u64)>,
+        info: PacketWriteEndInfo,
+    ) -> io::Result<()> {
+        if let Some((data, granule)) = std::mem::replace(&mut self.held, next) {
+            self.write_packet(data, info, granule)?;
+        }
+        Ok(())
+    }
+
+    fn drain_pages(&mut self, out: &mut Vec<u8>) {
+        out.append(self.ogg.inner_mut());
+    }
+}
+
+impl StreamEncoder for OpusEncoder {
+    fn content_type(&self) -> &'static str {
+        "audio/ogg"
+    }
+
+    fn segment_extension(&self) -> &'static str {
+        "opus"
+    }
+
+    fn sample_rate(&self) -> u32 {
+        OPUS_RATE
+    }
+
+    fn begin_stream(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        self.serial = rand::random();
+        self.granule = 0;
+        // Only the first stream starts from a cold encoder; later streams
+        // continue its output and have no priming samples to skip.
+        let pre_skip = if self.streams_started == 0 {
+            self.pre_skip
+        } else {
+            0
+        };
+        self.streams_started += 1;
+
+        let mut head = b"OpusHead".to_vec();
+        head.push(1);
+        head.push(self.channels as u8);
+        head.extend_from_slice(&pre_skip.to_le_bytes());
+        head.extend_from_slice(&OPUS_RATE.to_le_bytes());
+        head.extend_from_slice(&0_i16.to_le_bytes());
+        head.push(0);
+        self.write_packet(head, PacketWriteEndInfo::EndPage, 0)?;
+
+        let vendor = concat!("proteus ", env!("CARGO_PKG_VERSION"));
+        let mut tags = b"OpusTags".to_vec();
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0_u32.to_le_bytes());
+        self.write_packet(tags, PacketWriteEndInfo::EndPage, 0)?;
+
+        self.drain_pages(out);
+        Ok(())
+    }
+
+    fn encode(&mut self, samples: &[f32], out: &mut Vec<u8>) -> io::Result<()> {
+        self.pending.extend_from_slice(samples);
+        let frame_samples = FRAME_SIZE * self.channels as usize;
+        let mut consumed = 0;
+        while self.pending.len() - consumed >= frame_samples {
+            let frame = &self.pending[consumed..consumed + frame_samples];
+            let len = self
+                .encoder
+                .encode_float(frame, &mut self.packet)
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            consumed += frame_samples;
+            self.granule += FRAME_SIZE as u64;
+            let next = (self.packet[..len].to_vec(), self.granule);
+            let info = if self.pending.len() - consumed < frame_samples {
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            self.rotate_held(Some(next), info)?;
+        }
+        self.pending.drain(..consumed);
+        self.drain_pages(out);
+        Ok(())
+    }
+
+    fn end_stream(&mut self, out: &mut Vec<u8>) -> io::Result<()> {
+        self.rotate_held(None, PacketWriteEndInfo::EndStream)?;
+        self.drain_pages(out);
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+//! Per-track level metering for mixer-style UIs.
+//!
+//! Levels are measured in the mix thread on each logical track's buffer after
+//! track gain/pan and before the tracks are summed, then published per slot.
+//! Because the mix thread runs ahead of the output device, readings lead the
+//! audible signal by roughly the playback buffer length.
+
+/// Peak and RMS levels for one track, per output channel.
+#[derive(Debug, Default, PartialEq)]
+pub struct TrackLevels {
+    /// Absolute peak level per channel (linear, 1.0 = full scale).
+    pub peak: Vec<f32>,
+    /// RMS level per channel (linear, 1.0 = full scale).
+    pub rms: Vec<f32>,
+}
+
+impl TrackLevels {
+    /// Zero levels for `channels` channels.
+    pub fn silent(channels: usize) -> Self {
+        Self {
+            peak: vec![0.0; channels],
+            rms: vec![0.0; channels],
+        }
+    }
+
+    /// Measure peak and RMS levels of an interleaved buffer.
+    #[cfg(test)]
+    pub(crate) fn measure(samples: &[f32], channels: usize) -> Self {
+        let mut levels = Self::default();
+        levels.measure_into(samples, channels);
+        levels
+    }
+
+    /// Measure an interleaved buffer into these levels, reusing their
+    /// buffers so the mix loop does not allocate.
+    pub(crate) fn measure_into(&mut self, samples: &[f32], channels: usize) {
+        let channels = channels.max(1);
+        self.peak.resize(channels, 0.0);
+        self.rms.resize(channels, 0.0);
+        let frames = samples.len().div_ceil(channels).max(1) as f64;
+        for (ch, (peak, rms)) in self.peak.iter_mut().zip(&mut self.rms).enumerate() {
+            let mut max = 0.0_f32;
+            let mut sum_squares = 0.0_f64;
+            for &sample in samples.iter().skip(ch).step_by(channels) {
+                let value = if sample.is_finite() {
+                    sample.abs()
+                } else {
+                    0.0
+                };
+                max = max.max(value);
+                sum_squares += f64::from(value) * f64::from(value);
+            }
+            *peak = max;
+            *rms = (sum_squares / frames).sqrt() as f32;
+        }
+    }
+}
+
+impl Clone for TrackLevels {
+    fn clone(&self) -> Self {
+        Self {
+            peak: self.peak.clone(),
+            rms: self.rms.clone(),
+        }
+    }
+
+    fn clone_from(&mut self, source: &Self) {
+        self.peak.clone_from(&source.peak);
+        self.rms.clone_from(&source.rms);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TrackLevels;
+
+    #[test]
+    fn measure_reports_peak_and_rms_per_channel() {
+        let samples = [0.5_f32, -1.0, -0.5, 0.0, 0.5, 0.0, -0.5, 0.0];
+        let levels = TrackLevels::measure(&samples, 2);
+        assert_eq!(levels.peak, vec![0.5, 1.0]);
+        assert!((levels.rms[0] - 0.5).abs() < 1e-6);
+        assert!((levels.rms[1] - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn measure_ignores_non_finite_samples() {
+        let levels = TrackLevels::measure(&[f32::NAN, f32::INFINITY], 1);
+        assert_eq!(levels.peak, vec![0.0]);
+        assert_eq!(levels.rms, vec![0.0]);
+    }
+
+    #[test]
+    fn measure_into_reuses_the_level_buffers() {
+        let mut levels = TrackLevels::silent(2);
+        let peak = levels.peak.as_ptr();
+        levels.measure_into(&[0.25, -0.5, 0.25, -0.5], 2);
+        assert_eq!(levels.peak, vec![0.25, 0.5]);
+        assert_eq!(levels.peak.as_ptr(), peak);
+
+        let mut copy = TrackLevels::silent(2);
+        let rms = copy.rms.as_ptr();
+        copy.clone_from(&levels);
+        assert_eq!(copy, levels);
+        assert_eq!(copy.rms.as_ptr(), rms);
+    }
+}
@@ -0,0 +1,274 @@
+//! Automated transitions between players.
+//!
+//! [`crossfade`] fades one player out while another fades in, on a helper
+//! thread, so scene changes do not need hand-written volume ramps. Gains are
+//! applied to each player's output sink, which rodio evaluates after
+//! resampling to the device rate, so containers with different sample rates
+//! stay aligned on the device clock. The fade starts once the incoming player
+//! has produced audio, so the outgoing one never fades into startup silence.
+
+use std::f32::consts::FRAC_PI_2;
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use log::{debug, warn};
+
+use crate::playback::player::{Player, PlayerFader};
+
+/// Interval between gain updates.
+const STEP: Duration = Duration::from_millis(5);
+/// Longest wait for the incoming player to produce audio before fading anyway.
+const START_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Gain curve used for a crossfade.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CrossfadeCurve {
+    /// Straight-line gains; the summed level dips at the midpoint.
+    Linear,
+    /// Sine/cosine gains that keep the summed power constant.
+    #[default]
+    EqualPower,
+}
+
+impl CrossfadeCurve {
+    /// Return `(outgoing, incoming)` gain factors at progress `t` in `[0, 1]`.
+    pub fn gains(&self, t: f32) -> (f32, f32) {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => (1.0 - t, t),
+            Self::EqualPower => {
+                let (sin, cos) = (t * FRAC_PI_2).sin_cos();
+                (cos, sin)
+            }
+        }
+    }
+}
+
+/// Options for [`crossfade_with`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CrossfadeOptions {
+    /// Gain curve applied to both players.
+    pub curve: CrossfadeCurve,
+    /// Pause the outgoing player once it is silent.
+    ///
+    /// Its volume is restored, so a later resume plays at the previous level.
+    pub pause_from: bool,
+    /// Volume the incoming player reaches; `None` keeps its current volume.
+    pub target_volume: Option<f32>,
+}
+
+impl Default for CrossfadeOptions {
+    fn default() -> Self {
+        Self {
+            curve: CrossfadeCurve::EqualPower,
+            pause_from: true,
+            target_volume: None,
+        }
+    }
+}
+
+/// How a crossfade ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CrossfadeOutcome {
+    /// Both ramps reached their end.
+    Completed,
+    /// [`CrossfadeHandle::cancel`] stopped the fade early; volumes stay where they were.
+    Cancelled,
+}
+
+/// Error returned when a crossfade cannot start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransitionError {
+    /// `from` and `to` are handles to the same player.
+    SamePlayer,
+    /// The incoming player has no playback thread; call `play` or `seek` on it first.
+    TargetNotReady,
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SamePlayer => write!(f, "cannot crossfade a player into itself"),
+            Self::TargetNotReady => write!(f, "incoming player has no playback thread"),
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+/// Handle to a running crossfade.
+#[derive(Debug)]
+pub struct CrossfadeHandle {
+    cancel: Arc<AtomicBool>,
+    handle: Option<JoinHandle<CrossfadeOutcome>>,
+}
+
+impl CrossfadeHandle {
+    /// Stop the fade at its current gains.
+    pub fn cancel(&self) {
+        self.cancel.store(true, Ordering::SeqCst);
+    }
+
+    /// Return `true` once the fade thread has finished.
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Block until the fade ends and return how it ended.
+    pub fn wait(mut self) -> CrossfadeOutcome {
+        match self.handle.take().map(JoinHandle::join) {
+            Some(Ok(outcome)) => outcome,
+            _ => CrossfadeOutcome::Cancelled,
+        }
+    }
+}
+
+/// Crossfade from `from` to `to` over `duration` with default options.
+///
+/// # Errors
+///
+/// See [`crossfade_with`].
+pub fn crossfade(
+    from: &Player,
+    to: &Player,
+    duration: Duration,
+) -> Result<CrossfadeHandle, TransitionError> {
+    crossfade_with(from, to, duration, CrossfadeOptions::default(), |_| {})
+}
+
+/// Crossfade from `from` to `to` over `duration`.
+///
+/// `to` is resumed at zero volume if it is not already playing. `on_complete`
+/// runs on the fade thread once the fade completes or is cancelled.
+///
+/// # Errors
+///
+/// Returns [`TransitionError::SamePlayer`] when both arguments are the same
+/// player and [`TransitionError::TargetNotReady`] when `to` has no playback
+/// thread to resume.
+pub fn crossfade_with<F>(
+    from: &Player,
+    to: &Player,
+    duration: Duration,
+    options: CrossfadeOptions,
+    on_complete: F,
+) -> Result<CrossfadeHandle, TransitionError>
+where
+    F: FnOnce(CrossfadeOutcome) + Send + 'static,
+{
+    let (outgoing, incoming) = (from.fader(), to.fader());
+    if outgoing.same_player(&incoming) {
+        return Err(TransitionError::SamePlayer);
+    }
+    if !incoming.has_playback_thread() {
+        return Err(TransitionError::TargetNotReady);
+    }
+    let (from_rate, to_rate) = (from.audio_info().sample_rate, to.audio_info().sample_rate);
+    if from_rate != to_rate {
+        debug!(
+            "crossfade between {}Hz and {}Hz sources; both are resampled by the output mixer",
+            from_rate, to_rate
+        );
+    }
+
+    let from_volume = outgoing.volume();
+    let to_volume = options.target_volume.unwrap_or(incoming.volume()).max(0.0);
+    if !incoming.is_active() {
+        incoming.set_volume(0.0);
+        incoming.resume();
+    }
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let thread_cancel = cancel.clone();
+    let handle = std::thread::spawn(move || {
+        let outcome = run_crossfade(
+            &outgoing,
+            &incoming,
+            (from_volume, to_volume),
+            duration,
+            options,
+            &thread_cancel,
+        );
+        on_complete(outcome);
+        outcome
+    });
+    Ok(CrossfadeHandle {
+        cancel,
+        handle: Some(handle),
+    })
+}
+
+fn run_crossfade(
+    outgoing: &PlayerFader,
+    incoming: &PlayerFader,
+    (from_volume, to_volume): (f32, f32),
+    duration: Duration,
+    options: CrossfadeOptions,
+    cancel: &AtomicBool,
+) -> CrossfadeOutcome {
+    let waiting = Instant::now();
+    while !incoming.audio_heard() && incoming.has_playback_thread() {
+        if cancel.load(Ordering::SeqCst) {
+            return CrossfadeOutcome::Cancelled;
+        }
+        if waiting.elapsed() >= START_TIMEOUT {
+            warn!("crossfade started before the incoming player produced audio");
+            break;
+        }
+        std::thread::sleep(STEP);
+    }
+
+    let start = Instant::now();
+    loop {
+        if cancel.load(Ordering::SeqCst) {
+            return CrossfadeOutcome::Cancelled;
+        }
+        let t = if duration.is_zero() {
+            1.0
+        } else {
+            start.elapsed().as_secs_f32() / duration.as_secs_f32()
+        };
+        let (out_gain, in_gain) = options.curve.gains(t);
+        outgoing.set_volume(from_volume * out_gain);
+        incoming.set_volume(to_volume * in_gain);
+        if t >= 1.0 {
+            break;
+        }
+        std::thread::sleep(STEP);
+    }
+
+    if options.pause_from {
+        outgoing.pause();
+        outgoing.set_resume_volume(from_volume);
+    }
+    CrossfadeOutcome::Completed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::container::prot::PathsTrack;
+
+    #[test]
+    fn equal_power_curve_keeps_power_constant() {
+        for step in 0..=10 {
+            let (out_gain, in_gain) = CrossfadeCurve::EqualPower.gains(step as f32 / 10.0);
+            assert!((out_gain * out_gain + in_gain * in_gain - 1.0).abs() < 1e-5);
+        }
+        assert_eq!(CrossfadeCurve::Linear.gains(0.25), (0.75, 0.25));
+        assert_eq!(CrossfadeCurve::Linear.gains(2.0), (0.0, 1.0));
+    }
+
+    #[test]
+    fn crossfade_rejects_same_player() {
+        let player = Player::new_from_file_paths(vec![PathsTrack::new_from_file_paths(vec![
+            "/tmp/nonexistent.wav".to_string(),
+        ])]);
+        let other = player.clone();
+        let err = crossfade(&player, &other, Duration::from_millis(10)).unwrap_err();
+        assert_eq!(err, TransitionError::SamePlayer);
+    }
+}
@@ -0,0 +1,57 @@
+//! Fixture helpers shared by unit tests.
+
+/// Demo container with shuffle points, used across container and playback tests.
+pub(crate) const DEMO_CONTAINER: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../test_audio/demo_shuffle_points.prot"
+);
+
+/// Per-process temp path for a test container, unique per `scope` and `name`.
+pub(crate) fn temp_container_path(scope: &str, name: &str) -> String {
+    std::env::temp_dir()
+        .join(format!(
+            "proteus_{}_{}_{}.prot",
+            scope,
+            name,
+            std::process::id()
+        ))
+        .display()
+        .to_string()
+}
+
+/// Copy [`DEMO_CONTAINER`] to [`temp_container_path`] and return the copy.
+pub(crate) fn demo_container_copy(scope: &str, name: &str) -> String {
+    let path = temp_container_path(scope, name);
+    std::fs::copy(DEMO_CONTAINER, &path).unwrap();
+    path
+}
+
+/// Bytes of [`DEMO_CONTAINER`].
+pub(crate) fn demo_container_bytes() -> Vec<u8> {
+    std::fs::read(DEMO_CONTAINER).unwrap()
+}
+
+/// [`demo_container_copy`] with a short mono impulse response appended as
+/// the attachment `hall.wav`.
+pub(crate) fn demo_container_with_impulse_response(scope: &str, name: &str) -> String {
+    use crate::container::attachments::{append_attachment, ContainerAttachment};
+    use crate::playback::pcm_output::PcmFormat;
+    use crate::playback::render::RenderedPcm;
+
+    let path = demo_container_copy(scope, name);
+    let mut wav = Vec::new();
+    RenderedPcm {
+        samples: vec![1.0, 0.5, 0.25, 0.125],
+        sample_rate: 48_000,
+        channels: 1,
+    }
+    .encode_wav(&mut wav, PcmFormat::S16Le)
+    .unwrap();
+    let attachment = ContainerAttachment {
+        name: "hall.wav".to_string(),
+        mime_type: "audio/wav".to_string(),
+        data: wav,
+    };
+    append_attachment(&path, &attachment).unwrap();
+    path
+}
@@ -3,6 +3,7 @@ use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 
+use crate::container::play_settings::ParseMode;
 use crate::container::prot::{PathsTrack, Prot};
 use crate::dsp::effects::AudioEffect;
 use crate::playback::player::{Player, PlayerInitOptions, PlayerSource};
@@ -99,6 +100,18 @@ fn smoke_loads_and_initializes_demo_shuffle_points_container() {
     player.stop();
 }
 
+#[test]
+fn demo_containers_load_in_strict_play_settings_mode() {
+    for name in [
+        "demo_shuffle_points.prot",
+        "demo_shuffle_points_effects.prot",
+    ] {
+        let fixture = test_audio_path(name);
+        Prot::try_new_with_mode(&fixture.display().to_string(), ParseMode::Strict)
+            .unwrap_or_else(|err| panic!("{} should decode strictly: {}", name, err));
+    }
+}
+
 #[test]
 fn smoke_loads_and_initializes_directory_backed_24bit_wav_session() {
     let fixture_root = test_audio_path("24bit_wav");
@@ -153,6 +166,11 @@ fn smoke_loads_and_initializes_directory_backed_24bit_wav_session() {
         !player.get_effect_names().is_empty(),
         "player should accept the fixture effect chain"
     );
+    assert_eq!(
+        player.get_effect_descriptors().len(),
+        player.get_effect_names().len(),
+        "every effect in the chain should be described"
+    );
 
     player.stop();
 }
@@ -188,6 +206,11 @@ fn load_paths_tracks_from_shuffle_schedule(root: &Path) -> Vec<PathsTrack> {
             pan: track.pan,
             selections_count: track.selections_count.max(1),
             shuffle_points: track.shuffle_points,
+            selection_mode: Default::default(),
+            transitions: Vec::new(),
+            start_offset_ms: 0,
+            looping: None,
+            group: None,
         })
         .collect()
 }
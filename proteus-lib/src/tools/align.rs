@@ -0,0 +1,499 @@
+//! Time-offset detection between the candidate stems of a track.
+//!
+//! Candidates of one track are meant to be interchangeable; if one was
+//! exported a few milliseconds early or late, every shuffle onto it smears
+//! transients and combs against the other tracks. [`analyze_alignment`]
+//! cross-correlates each candidate with the track's first candidate and
+//! reports the constant offset between them, and [`apply_alignment`] folds
+//! confident offsets into the `candidate_trims` of `play_settings.json`.
+//!
+//! Correlation runs over the first [`AlignOptions::window_seconds`] of each
+//! candidate after its current trim start, downmixed to mono, so an already
+//! trimmed container is measured as it plays.
+
+use std::collections::HashMap;
+use std::fmt;
+
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::audio::decode::for_each_channel_sample;
+use crate::container::play_settings::{settings_tracks, settings_tracks_mut};
+use crate::dsp::fft::{plan_real, Complex};
+use crate::dsp::precision::{narrow, widen, DspFloat};
+use crate::tools::decode::{get_reader, DecoderOpenError};
+
+/// Analysis window and acceptance limits for [`analyze_alignment`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AlignOptions {
+    /// Seconds of each candidate compared, from its trim start.
+    pub window_seconds: f64,
+    /// Largest offset searched for, in milliseconds, either way.
+    pub max_offset_ms: f64,
+    /// Normalized correlation (`0.0..=1.0`) an offset needs before
+    /// [`apply_alignment`] acts on it.
+    pub min_correlation: f32,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self {
+            window_seconds: 15.0,
+            max_offset_ms: 250.0,
+            min_correlation: 0.5,
+        }
+    }
+}
+
+/// Measured offset of one candidate against its track's reference candidate.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateOffset {
+    /// Index of the track in `play_settings` `tracks`.
+    pub track_index: usize,
+    /// Candidate id that was measured.
+    pub id: u32,
+    /// First candidate id of the track, which offsets are measured against.
+    pub reference_id: u32,
+    /// How much later the candidate's audio arrives, in milliseconds;
+    /// negative when it arrives early.
+    pub offset_ms: f64,
+    /// Normalized correlation at the offset; near `1.0` for the same
+    /// material, near `0.0` for unrelated takes.
+    pub correlation: f32,
+}
+
+/// Error returned when a container cannot be analyzed.
+#[derive(Debug)]
+pub enum AlignError {
+    /// The container could not be opened or a codec is unsupported.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the container.
+    Decode(SymphoniaError),
+    /// The play settings list no track with more than one candidate.
+    NoCandidates,
+}
+
+impl fmt::Display for AlignError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+            Self::NoCandidates => write!(f, "no track has more than one candidate"),
+        }
+    }
+}
+
+impl std::error::Error for AlignError {}
+
+/// Mono window of one container track.
+struct Excerpt {
+    decoder: Box<dyn Decoder>,
+    sample_rate: u32,
+    skip_frames: usize,
+    window_frames: usize,
+    samples: Vec<f32>,
+}
+
+impl Excerpt {
+    fn is_full(&self) -> bool {
+        self.samples.len() >= self.window_frames
+    }
+}
+
+/// Measure every candidate of every multi-candidate track in `play_settings`
+/// against the track's first candidate, decoding audio from the container at
+/// `file_path`.
+///
+/// Candidates that are silent in the window, or recorded at a different
+/// sample rate than the reference, are left out of the report.
+///
+/// # Errors
+///
+/// Returns [`AlignError`] when the container cannot be decoded or no track
+/// has candidates to compare.
+pub fn analyze_alignment(
+    file_path: &str,
+    play_settings: &serde_json::Value,
+    options: AlignOptions,
+) -> Result<Vec<CandidateOffset>, AlignError> {
+    let tracks: Vec<(Vec<u32>, HashMap<u32, u64>)> = settings_tracks(play_settings)
+        .map(|tracks| tracks.iter().map(track_candidates).collect())
+        .unwrap_or_default();
+    if tracks.iter().all(|(ids, _)| ids.len() < 2) {
+        return Err(AlignError::NoCandidates);
+    }
+    let starts: HashMap<u32, u64> = tracks
+        .iter()
+        .filter(|(ids, _)| ids.len() > 1)
+        .flat_map(|(ids, trims)| {
+            ids.iter()
+                .map(|id| (*id, trims.get(id).copied().unwrap_or(0)))
+        })
+        .collect();
+    let excerpts = read_excerpts(file_path, &starts, options.window_seconds)?;
+
+    let mut offsets = Vec::new();
+    for (track_index, (ids, _)) in tracks.iter().enumerate() {
+        let Some((&reference_id, candidates)) = ids.split_first() else {
+            continue;
+        };
+        let Some(reference) = excerpts.get(&reference_id) else {
+            continue;
+        };
+        let rate = reference.0;
+        let max_lag = (options.max_offset_ms.max(0.0) / 1000.0 * f64::from(rate)) as usize;
+        for id in candidates {
+            let Some((candidate_rate, samples)) = excerpts.get(id) else {
+                continue;
+            };
+            if *candidate_rate != rate {
+                continue;
+            }
+            if let Some((lag, correlation)) = correlate(&reference.1, samples, max_lag) {
+                offsets.push(CandidateOffset {
+                    track_index,
+                    id: *id,
+                    reference_id,
+                    offset_ms: lag as f64 * 1000.0 / f64::from(rate),
+                    correlation,
+                });
+            }
+        }
+    }
+    Ok(offsets)
+}
+
+/// Fold `offsets` into the `candidate_trims` of a parsed
+/// `play_settings.json` document and return how many candidates changed.
+///
+/// Offsets below [`AlignOptions::min_correlation`] are ignored. A late
+/// candidate has its trim start moved later by its offset. When a candidate
+/// is early, the trim can not delay it, so the reference and the other
+/// corrected candidates of the track are trimmed to meet it instead. Trims
+/// are whole milliseconds.
+pub fn apply_alignment(
+    play_settings: &mut serde_json::Value,
+    offsets: &[CandidateOffset],
+    options: AlignOptions,
+) -> usize {
+    let Some(tracks) = settings_tracks_mut(play_settings) else {
+        return 0;
+    };
+    let mut changed = 0;
+    for (track_index, track) in tracks.iter_mut().enumerate() {
+        let reliable: Vec<&CandidateOffset> = offsets
+            .iter()
+            .filter(|offset| {
+                offset.track_index == track_index && offset.correlation >= options.min_correlation
+            })
+            .collect();
+        let Some(reference_id) = reliable.first().map(|offset| offset.reference_id) else {
+            continue;
+        };
+        let earliest = reliable
+            .iter()
+            .map(|offset| offset.offset_ms)
+            .fold(0.0_f64, f64::min);
+        let shifts = std::iter::once((reference_id, -earliest)).chain(
+            reliable
+                .iter()
+                .map(|offset| (offset.id, offset.offset_ms - earliest)),
+        );
+        for (id, shift_ms) in shifts {
+            let shift_ms = shift_ms.round() as u64;
+            if shift_ms > 0 && shift_trim_start(track, id, shift_ms) {
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+/// Candidate ids of a track object and their current trim starts.
+fn track_candidates(track: &serde_json::Value) -> (Vec<u32>, HashMap<u32, u64>) {
+    let ids = track
+        .get("ids")
+        .and_then(serde_json::Value::as_array)
+        .map(|ids| {
+            ids.iter()
+                .filter_map(|id| u32::try_from(id.as_u64()?).ok())
+                .collect()
+        })
+        .unwrap_or_default();
+    let trims = track
+        .get("candidate_trims")
+        .and_then(serde_json::Value::as_array)
+        .map(|trims| {
+            trims
+                .iter()
+                .filter_map(|trim| {
+                    let id = u32::try_from(trim.get("id")?.as_u64()?).ok()?;
+                    Some((
+                        id,
+                        trim.get("start_ms").and_then(serde_json::Value::as_u64)?,
+                    ))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    (ids, trims)
+}
+
+/// Add `shift_ms` to the trim start of `id`, creating the trim if needed.
+fn shift_trim_start(track: &mut serde_json::Value, id: u32, shift_ms: u64) -> bool {
+    let Some(track) = track.as_object_mut() else {
+        return false;
+    };
+    let trims = track
+        .entry("candidate_trims")
+        .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+    let Some(trims) = trims.as_array_mut() else {
+        return false;
+    };
+    let existing = trims
+        .iter_mut()
+        .filter_map(serde_json::Value::as_object_mut)
+        .find(|trim| trim.get("id").and_then(serde_json::Value::as_u64) == Some(u64::from(id)));
+    match existing {
+        Some(trim) => {
+            let start_ms = trim
+                .get("start_ms")
+                .and_then(serde_json::Value::as_u64)
+                .unwrap_or(0);
+            trim.insert("start_ms".to_string(), (start_ms + shift_ms).into());
+        }
+        None => trims.push(serde_json::json!({"id": id, "start_ms": shift_ms})),
+    }
+    true
+}
+
+/// Decode mono windows of the tracks in `starts`, each beginning at its
+/// start in milliseconds, keyed by track id with their sample rates.
+fn read_excerpts(
+    file_path: &str,
+    starts: &HashMap<u32, u64>,
+    window_seconds: f64,
+) -> Result<HashMap<u32, (u32, Vec<f32>)>, AlignError> {
+    let mut format = get_reader(file_path).map_err(AlignError::Open)?;
+    let mut excerpts: HashMap<u32, Excerpt> = HashMap::new();
+    for track in format.tracks() {
+        let Some(start_ms) = starts.get(&track.id) else {
+            continue;
+        };
+        if track.codec_params.codec == CODEC_TYPE_NULL {
+            continue;
+        }
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| AlignError::Open(DecoderOpenError::UnsupportedCodec(err)))?;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(48_000);
+        let rate = f64::from(sample_rate);
+        excerpts.insert(
+            track.id,
+            Excerpt {
+                decoder,
+                sample_rate,
+                skip_frames: (*start_ms as f64 / 1000.0 * rate) as usize,
+                window_frames: (window_seconds.max(0.0) * rate) as usize,
+                samples: Vec::new(),
+            },
+        );
+    }
+
+    let mut mono: Vec<f32> = Vec::new();
+    while !excerpts.values().all(Excerpt::is_full) {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(err) => return Err(AlignError::Decode(err)),
+        };
+        let Some(excerpt) = excerpts.get_mut(&packet.track_id()) else {
+            continue;
+        };
+        if excerpt.is_full() {
+            continue;
+        }
+        let decoded = excerpt
+            .decoder
+            .decode(&packet)
+            .map_err(AlignError::Decode)?;
+        let channels = decoded.spec().channels.count();
+        mono.clear();
+        mono.resize(decoded.frames(), 0.0);
+        for channel in 0..channels {
+            let mut frame = 0;
+            for_each_channel_sample(&decoded, channel, |sample| {
+                if let Some(sum) = mono.get_mut(frame) {
+                    *sum += sample / channels as f32;
+                }
+                frame += 1;
+            });
+        }
+        let skipped = excerpt.skip_frames.min(mono.len());
+        excerpt.skip_frames -= skipped;
+        let room = excerpt.window_frames - excerpt.samples.len();
+        excerpt
+            .samples
+            .extend(mono[skipped..].iter().take(room).copied());
+    }
+    Ok(excerpts
+        .into_iter()
+        .map(|(id, excerpt)| (id, (excerpt.sample_rate, excerpt.samples)))
+        .collect())
+}
+
+/// Lag, in samples, at which `candidate` best matches `reference`, searched
+/// over `-max_lag..=max_lag`, with its normalized correlation.
+///
+/// A positive lag means the candidate's material arrives later. Returns
+/// `None` when either signal is silent.
+fn correlate(reference: &[f32], candidate: &[f32], max_lag: usize) -> Option<(isize, f32)> {
+    let energy = |samples: &[f32]| samples.iter().map(|s| f64::from(*s).powi(2)).sum::<f64>();
+    let norm = (energy(reference) * energy(candidate)).sqrt();
+    if norm <= f64::EPSILON {
+        return None;
+    }
+
+    let len = (reference.len() + candidate.len()).next_power_of_two();
+    let fft = plan_real(len);
+    let spectrum = |samples: &[f32]| {
+        let mut input: Vec<DspFloat> = samples.iter().map(|s| widen(*s)).collect();
+        input.resize(len, 0.0);
+        let mut bins = vec![Complex::new(0.0, 0.0); fft.spectrum_len()];
+        fft.forward(&mut input, &mut bins).ok()?;
+        Some(bins)
+    };
+    let reference_bins = spectrum(reference)?;
+    let mut product = spectrum(candidate)?;
+    for (bin, reference_bin) in product.iter_mut().zip(&reference_bins) {
+        *bin *= reference_bin.conj();
+    }
+    let mut correlation = vec![0.0; len];
+    fft.inverse(&mut product, &mut correlation).ok()?;
+
+    let max_lag = max_lag.min(len / 2 - 1) as isize;
+    let (lag, peak) = (-max_lag..=max_lag)
+        .map(|lag| {
+            let index = lag.rem_euclid(len as isize) as usize;
+            (lag, narrow(correlation[index]) / len as f32)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    Some((lag, (f64::from(peak) / norm) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noise(len: usize, seed: u32) -> Vec<f32> {
+        let mut state = seed.wrapping_mul(2_654_435_761).max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect()
+    }
+
+    fn delayed(signal: &[f32], lag: isize) -> Vec<f32> {
+        (0..signal.len() as isize)
+            .map(|index| {
+                usize::try_from(index - lag)
+                    .ok()
+                    .and_then(|source| signal.get(source).copied())
+                    .unwrap_or(0.0)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn correlation_finds_late_and_early_copies() {
+        let reference = noise(4_800, 7);
+        let (lag, correlation) = correlate(&reference, &delayed(&reference, 37), 480).unwrap();
+        assert_eq!(lag, 37);
+        assert!(correlation > 0.9);
+        let (lag, _) = correlate(&reference, &delayed(&reference, -120), 480).unwrap();
+        assert_eq!(lag, -120);
+    }
+
+    #[test]
+    fn unrelated_or_silent_material_is_not_confident() {
+        let (_, correlation) = correlate(&noise(4_800, 1), &noise(4_800, 2), 480).unwrap();
+        assert!(correlation < 0.2);
+        assert!(correlate(&noise(4_800, 1), &[0.0; 4_800], 480).is_none());
+    }
+
+    #[test]
+    fn confident_offsets_become_trim_starts() {
+        let mut settings = serde_json::json!({
+            "play_settings": {"tracks": [
+                {"ids": [1, 2, 3, 4], "candidate_trims": [{"id": 2, "start_ms": 100, "end_ms": 900}]},
+                {"ids": [5, 6]}
+            ]}
+        });
+        let offset = |track_index, id, reference_id, offset_ms, correlation| CandidateOffset {
+            track_index,
+            id,
+            reference_id,
+            offset_ms,
+            correlation,
+        };
+        let offsets = [
+            offset(0, 2, 1, 12.4, 0.9),
+            offset(0, 3, 1, 30.0, 0.1),
+            offset(0, 4, 1, 0.2, 0.9),
+            offset(1, 6, 5, -8.0, 0.8),
+        ];
+        assert_eq!(
+            apply_alignment(&mut settings, &offsets, AlignOptions::default()),
+            2
+        );
+        let tracks = &settings["play_settings"]["tracks"];
+        assert_eq!(
+            tracks[0]["candidate_trims"],
+            serde_json::json!([{"id": 2, "start_ms": 112, "end_ms": 900}])
+        );
+        assert_eq!(
+            tracks[1]["candidate_trims"],
+            serde_json::json!([{"id": 5, "start_ms": 8}])
+        );
+    }
+
+    #[test]
+    fn container_candidates_are_measured_against_the_first_id() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test_audio/demo_shuffle_points.prot"
+        );
+        let settings = crate::container::attachments::read_attachments_from_path_with_limit(
+            path,
+            crate::container::limits::ParseLimits::default().max_attachment_bytes,
+        )
+        .unwrap()
+        .into_iter()
+        .find(|attachment| attachment.name.trim_matches('"') == "play_settings.json")
+        .unwrap();
+        let settings: serde_json::Value = serde_json::from_slice(&settings.data).unwrap();
+        let options = AlignOptions {
+            window_seconds: 1.0,
+            ..AlignOptions::default()
+        };
+
+        let offsets = analyze_alignment(path, &settings, options).unwrap();
+        assert!(!offsets.is_empty());
+        for offset in &offsets {
+            assert_ne!(offset.id, offset.reference_id);
+            assert!(offset.offset_ms.abs() <= options.max_offset_ms);
+        }
+        assert!(matches!(
+            analyze_alignment(path, &serde_json::json!({}), options),
+            Err(AlignError::NoCandidates)
+        ));
+    }
+}
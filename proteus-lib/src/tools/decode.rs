@@ -1,12 +1,19 @@
 //! Symphonia helpers for opening and decoding audio files.
 
+use std::sync::Arc;
+
 use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
 use symphonia::core::errors::Error as SymphoniaError;
 use symphonia::core::formats::{FormatOptions, FormatReader, Track};
-use symphonia::core::io::MediaSourceStream;
+use symphonia::core::io::{MediaSource, MediaSourceStream};
 use symphonia::core::meta::MetadataOptions;
 use symphonia::core::probe::Hint;
 
+use crate::container::prot::TrackKeys;
+
+use super::progressive::{open_media, ProgressiveFile};
+use super::stdin::{is_stdin_path, StdinSource};
+
 /// Errors produced while opening or preparing decoder state for media input.
 #[derive(Debug)]
 pub enum DecoderOpenError {
@@ -63,13 +70,26 @@ pub fn open_file(file_path: &str) -> Result<OpenedDecoder, DecoderOpenError> {
 
 /// Build a Symphonia `FormatReader` for the given file path.
 ///
-/// `.prot` files are treated as `.mka` for probe hinting.
+/// `.prot` files are treated as `.mka` for probe hinting, and `-` reads a
+/// non-seekable stream from standard input.
 pub fn get_reader(file_path: &str) -> Result<Box<dyn FormatReader>, DecoderOpenError> {
+    get_reader_with_download(file_path, None)
+}
+
+// With a `download`, a file still arriving is read as its bytes arrive.
+fn get_reader_with_download(
+    file_path: &str,
+    download: Option<&ProgressiveFile>,
+) -> Result<Box<dyn FormatReader>, DecoderOpenError> {
     // Open the media source.
-    let src = std::fs::File::open(file_path)?;
+    let src: Box<dyn MediaSource> = if is_stdin_path(file_path) {
+        Box::new(StdinSource::open())
+    } else {
+        open_media(file_path, download)?
+    };
 
     // Create the media source stream.
-    let mss = MediaSourceStream::new(Box::new(src), Default::default());
+    let mss = MediaSourceStream::new(src, Default::default());
 
     // Create a probe hint using the file's extension. [Optional]
     let mut hint = Hint::new();
@@ -102,11 +122,31 @@ pub fn get_reader(file_path: &str) -> Result<Box<dyn FormatReader>, DecoderOpenE
     Ok(format)
 }
 
+/// [`get_reader`] for a container whose tracks may be encrypted, and that
+/// may still be downloading.
+///
+/// With the `encryption` feature, packets of tracks that have a key in
+/// `keys` are decrypted as they are read; see
+/// [`crate::container::encryption`]. Without it `keys` is ignored. While
+/// `download` runs, reads wait for the bytes to arrive.
+pub(crate) fn get_container_reader(
+    file_path: &str,
+    keys: &Arc<TrackKeys>,
+    download: Option<&ProgressiveFile>,
+) -> Result<Box<dyn FormatReader>, DecoderOpenError> {
+    let format = get_reader_with_download(file_path, download)?;
+    #[cfg(feature = "encryption")]
+    let format = crate::container::encryption::decrypting_reader(format, keys);
+    #[cfg(not(feature = "encryption"))]
+    let _ = keys;
+    Ok(format)
+}
+
 /// Find the first track with a non-null (decodable) codec.
 ///
 /// Returns the track reference, or [`DecoderOpenError::NoSupportedAudioTrack`]
 /// if every track has `CODEC_TYPE_NULL`.
-fn find_audio_track(tracks: &[Track]) -> Result<&Track, DecoderOpenError> {
+pub(crate) fn find_audio_track(tracks: &[Track]) -> Result<&Track, DecoderOpenError> {
     tracks
         .iter()
         .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
@@ -135,14 +175,18 @@ mod tests {
     use super::{find_audio_track, get_reader, DecoderOpenError};
 
     fn null_track(id: u32) -> Track {
-        let mut params = CodecParameters::default();
-        params.codec = CODEC_TYPE_NULL;
+        let params = CodecParameters {
+            codec: CODEC_TYPE_NULL,
+            ..Default::default()
+        };
         Track::new(id, params)
     }
 
     fn audio_track(id: u32) -> Track {
-        let mut params = CodecParameters::default();
-        params.codec = CODEC_TYPE_VORBIS;
+        let params = CodecParameters {
+            codec: CODEC_TYPE_VORBIS,
+            ..Default::default()
+        };
         Track::new(id, params)
     }
 
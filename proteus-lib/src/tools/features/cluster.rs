@@ -0,0 +1,91 @@
+//! Deterministic k-means over standardized feature vectors.
+
+/// k-means refinement passes.
+const KMEANS_ITERATIONS: usize = 50;
+
+/// Scale every dimension of `points` to zero mean and unit variance;
+/// constant dimensions become zero.
+pub(super) fn standardize(points: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let dimensions = points.first().map_or(0, Vec::len);
+    let count = points.len().max(1) as f32;
+    let mut scaled = points.to_vec();
+    for dimension in 0..dimensions {
+        let mean = points.iter().map(|point| point[dimension]).sum::<f32>() / count;
+        let deviation = (points
+            .iter()
+            .map(|point| (point[dimension] - mean).powi(2))
+            .sum::<f32>()
+            / count)
+            .sqrt();
+        for point in &mut scaled {
+            point[dimension] = if deviation > f32::EPSILON {
+                (point[dimension] - mean) / deviation
+            } else {
+                0.0
+            };
+        }
+    }
+    scaled
+}
+
+fn distance(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum()
+}
+
+/// k-means labels for `points`, seeded with the first point and then the
+/// points farthest from every chosen center.
+pub(super) fn kmeans(points: &[Vec<f32>], clusters: usize) -> Vec<usize> {
+    let Some(first) = points.first() else {
+        return Vec::new();
+    };
+    let mut centers: Vec<Vec<f32>> = vec![first.clone()];
+    while centers.len() < clusters.clamp(1, points.len()) {
+        let farthest = points
+            .iter()
+            .map(|point| {
+                centers
+                    .iter()
+                    .map(|center| distance(point, center))
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .filter(|(_, gap)| *gap > f32::EPSILON);
+        match farthest {
+            Some((index, _)) => centers.push(points[index].clone()),
+            None => break,
+        }
+    }
+
+    let nearest = |point: &[f32], centers: &[Vec<f32>]| {
+        centers
+            .iter()
+            .enumerate()
+            .min_by(|a, b| distance(point, a.1).total_cmp(&distance(point, b.1)))
+            .map_or(0, |(index, _)| index)
+    };
+    let mut labels: Vec<usize> = points.iter().map(|p| nearest(p, &centers)).collect();
+    for _ in 0..KMEANS_ITERATIONS {
+        for (index, center) in centers.iter_mut().enumerate() {
+            let members: Vec<&Vec<f32>> = points
+                .iter()
+                .zip(&labels)
+                .filter(|(_, label)| **label == index)
+                .map(|(point, _)| point)
+                .collect();
+            if members.is_empty() {
+                continue;
+            }
+            for (dimension, value) in center.iter_mut().enumerate() {
+                *value = members.iter().map(|point| point[dimension]).sum::<f32>()
+                    / members.len() as f32;
+            }
+        }
+        let next: Vec<usize> = points.iter().map(|p| nearest(p, &centers)).collect();
+        if next == labels {
+            break;
+        }
+        labels = next;
+    }
+    labels
+}
@@ -0,0 +1,364 @@
+//! Spectral features and intensity tagging for candidate stems.
+//!
+//! [`extract_features`] summarizes every track of a container with a few
+//! spectral statistics and averaged MFCCs. [`cluster_features`] groups
+//! candidates that sound alike, and [`tag_by_intensity`] sorts them into
+//! ordered intensity tiers ("calm" to "intense") from loudness, brightness,
+//! and onset density. [`intensity_conditions`] turns the tiers into
+//! `candidate_when` ranges on a runtime variable, which
+//! [`write_candidate_conditions`] stores in `play_settings.json`, so a host
+//! raising `intensity` shuffles towards busier takes without hand-tagging.
+
+mod cluster;
+mod spectral;
+
+use std::fmt;
+
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::container::play_settings::{
+    write_candidate_entries, CandidateCondition, VariableCondition,
+};
+use crate::tools::decode::DecoderOpenError;
+use crate::tools::stem_signal::{read_stem_signals, StemReadError, ANALYSIS_RATE};
+use cluster::{kmeans, standardize};
+use spectral::spectral_features;
+
+/// Number of cepstral coefficients kept.
+pub const MFCC_COUNT: usize = 13;
+
+/// Analysis length for [`extract_features`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeatureOptions {
+    /// Seconds analyzed from the start of each track.
+    pub max_seconds: f64,
+}
+
+impl Default for FeatureOptions {
+    fn default() -> Self {
+        Self { max_seconds: 60.0 }
+    }
+}
+
+/// Error returned when a file cannot be analyzed.
+#[derive(Debug)]
+pub enum FeatureError {
+    /// The file could not be opened or has no decodable audio track.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the file.
+    Decode(SymphoniaError),
+}
+
+impl fmt::Display for FeatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for FeatureError {}
+
+impl From<StemReadError> for FeatureError {
+    fn from(err: StemReadError) -> Self {
+        match err {
+            StemReadError::Open(err) => Self::Open(err),
+            StemReadError::Decode(err) => Self::Decode(err),
+        }
+    }
+}
+
+/// Spectral summary of one stem, averaged over its non-silent frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpectralFeatures {
+    /// RMS level of the analyzed audio in dBFS.
+    pub rms_dbfs: f32,
+    /// Magnitude-weighted mean frequency, in hertz.
+    pub centroid_hz: f32,
+    /// Frequency below which 85% of the spectral energy lies, in hertz.
+    pub rolloff_hz: f32,
+    /// Geometric over arithmetic mean of the power spectrum (`0.0` tonal,
+    /// `1.0` noise-like).
+    pub flatness: f32,
+    /// Mean positive change of the normalized spectrum between frames; higher
+    /// for busy, percussive material.
+    pub flux: f32,
+    /// Mel-frequency cepstral coefficients, `c0` first.
+    pub mfcc: [f32; MFCC_COUNT],
+}
+
+impl SpectralFeatures {
+    /// All features as one vector, in declaration order, for clustering.
+    pub fn to_vector(&self) -> Vec<f32> {
+        let mut vector = vec![
+            self.rms_dbfs,
+            self.centroid_hz,
+            self.rolloff_hz,
+            self.flatness,
+            self.flux,
+        ];
+        vector.extend_from_slice(&self.mfcc);
+        vector
+    }
+}
+
+/// Features of one candidate id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateFeatures {
+    /// Container track id.
+    pub id: u32,
+    /// Extracted features.
+    pub features: SpectralFeatures,
+}
+
+/// Intensity tier of one candidate from [`tag_by_intensity`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CandidateTag {
+    /// Candidate id.
+    pub id: u32,
+    /// Tier index; `0` is the calmest.
+    pub tier: usize,
+    /// Relative intensity among the tagged candidates (`0.0..=1.0`).
+    pub intensity: f32,
+}
+
+/// Extract [`SpectralFeatures`] for every audio track of `file_path`.
+///
+/// # Errors
+///
+/// Returns [`FeatureError`] when the file cannot be opened or decoded.
+pub fn extract_features(
+    file_path: &str,
+    options: FeatureOptions,
+) -> Result<Vec<CandidateFeatures>, FeatureError> {
+    Ok(
+        read_stem_signals(file_path, None, options.max_seconds, ANALYSIS_RATE)?
+            .into_iter()
+            .map(|signal| CandidateFeatures {
+                id: signal.id,
+                features: spectral_features(&signal.samples, signal.rate),
+            })
+            .collect(),
+    )
+}
+
+/// Group candidates into at most `clusters` sets of similar-sounding takes.
+///
+/// Runs k-means on the standardized [`SpectralFeatures::to_vector`] values,
+/// seeded deterministically by farthest-point selection. Returns a cluster
+/// index per entry of `features`, numbered in order of first appearance.
+pub fn cluster_features(features: &[CandidateFeatures], clusters: usize) -> Vec<usize> {
+    let points = standardize(
+        &features
+            .iter()
+            .map(|candidate| candidate.features.to_vector())
+            .collect::<Vec<_>>(),
+    );
+    let labels = kmeans(&points, clusters);
+    let mut order: Vec<usize> = Vec::new();
+    labels
+        .iter()
+        .map(|label| match order.iter().position(|seen| seen == label) {
+            Some(index) => index,
+            None => {
+                order.push(*label);
+                order.len() - 1
+            }
+        })
+        .collect()
+}
+
+/// Sort candidates into at most `tiers` intensity tiers, calmest first.
+///
+/// Intensity is the mean standardized loudness, spectral centroid, and flux,
+/// rescaled to `0.0..=1.0` across the candidates; tiers come from 1-D
+/// k-means on it. Identical candidates all score `0.5`.
+pub fn tag_by_intensity(features: &[CandidateFeatures], tiers: usize) -> Vec<CandidateTag> {
+    let drivers: Vec<Vec<f32>> = features
+        .iter()
+        .map(|candidate| {
+            let features = candidate.features;
+            vec![features.rms_dbfs, features.centroid_hz, features.flux]
+        })
+        .collect();
+    let scores: Vec<f32> = standardize(&drivers)
+        .iter()
+        .map(|point| point.iter().sum::<f32>() / point.len() as f32)
+        .collect();
+    let low = scores.iter().copied().fold(f32::INFINITY, f32::min);
+    let high = scores.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let intensities: Vec<f32> = scores
+        .iter()
+        .map(|score| {
+            if high - low > f32::EPSILON {
+                (score - low) / (high - low)
+            } else {
+                0.5
+            }
+        })
+        .collect();
+
+    let labels = kmeans(
+        &intensities
+            .iter()
+            .map(|score| vec![*score])
+            .collect::<Vec<_>>(),
+        tiers,
+    );
+    let mut centers: Vec<(usize, f32)> = Vec::new();
+    for label in 0..=labels.iter().copied().max().unwrap_or(0) {
+        let members: Vec<f32> = labels
+            .iter()
+            .zip(&intensities)
+            .filter(|(member, _)| **member == label)
+            .map(|(_, intensity)| *intensity)
+            .collect();
+        if !members.is_empty() {
+            centers.push((label, members.iter().sum::<f32>() / members.len() as f32));
+        }
+    }
+    centers.sort_by(|a, b| a.1.total_cmp(&b.1));
+    features
+        .iter()
+        .zip(labels.iter().zip(&intensities))
+        .map(|(candidate, (label, intensity))| CandidateTag {
+            id: candidate.id,
+            tier: centers
+                .iter()
+                .position(|(center, _)| center == label)
+                .unwrap_or(0),
+            intensity: *intensity,
+        })
+        .collect()
+}
+
+/// `candidate_when` conditions selecting each tier over an equal share of
+/// `variable`'s `0.0..=1.0` range.
+///
+/// With `tiers` tiers, tier `i` is eligible while `variable` is within
+/// `i / tiers..=(i + 1) / tiers`; the calmest tier has no lower bound and
+/// the most intense no upper bound.
+pub fn intensity_conditions(
+    tags: &[CandidateTag],
+    tiers: usize,
+    variable: &str,
+) -> Vec<CandidateCondition> {
+    let tiers = tiers.max(1);
+    tags.iter()
+        .map(|tag| {
+            let tier = tag.tier.min(tiers - 1);
+            CandidateCondition {
+                id: tag.id,
+                when: vec![VariableCondition {
+                    variable: variable.to_string(),
+                    min: (tier > 0).then(|| tier as f64 / tiers as f64),
+                    max: (tier + 1 < tiers).then(|| (tier + 1) as f64 / tiers as f64),
+                }],
+            }
+        })
+        .collect()
+}
+
+/// Store `conditions` as `candidate_when` on the tracks of a parsed
+/// `play_settings.json` document.
+///
+/// Each track receives the conditions for the ids it lists, replacing any it
+/// had; tracks with none lose the key. Returns `false` when the document has
+/// no `tracks` array.
+pub fn write_candidate_conditions(
+    play_settings: &mut serde_json::Value,
+    conditions: &[CandidateCondition],
+) -> bool {
+    write_candidate_entries(play_settings, "candidate_when", conditions, |condition| {
+        condition.id
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(super) const RATE: f64 = 12_000.0;
+
+    pub(super) fn noise(len: usize, level: f32, seed: u32) -> Vec<f32> {
+        let mut state = seed.max(1);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                (state as f32 / u32::MAX as f32 - 0.5) * level
+            })
+            .collect()
+    }
+
+    pub(super) fn sine(len: usize, hz: f32, level: f32) -> Vec<f32> {
+        (0..len)
+            .map(|i| level * (std::f32::consts::TAU * hz * i as f32 / RATE as f32).sin())
+            .collect()
+    }
+
+    fn candidate(id: u32, samples: &[f32]) -> CandidateFeatures {
+        CandidateFeatures {
+            id,
+            features: spectral_features(samples, RATE),
+        }
+    }
+
+    #[test]
+    fn calm_and_intense_takes_fall_into_ordered_tiers() {
+        let features = [
+            candidate(1, &noise(24_000, 0.9, 1)),
+            candidate(2, &sine(24_000, 220.0, 0.1)),
+            candidate(3, &noise(24_000, 1.0, 2)),
+            candidate(4, &sine(24_000, 247.0, 0.12)),
+        ];
+        let tags = tag_by_intensity(&features, 2);
+        let tiers: Vec<usize> = tags.iter().map(|tag| tag.tier).collect();
+        assert_eq!(tiers, vec![1, 0, 1, 0]);
+        assert!(tags.iter().all(|tag| (0.0..=1.0).contains(&tag.intensity)));
+
+        assert_eq!(cluster_features(&features, 2), vec![0, 1, 0, 1]);
+        assert_eq!(cluster_features(&features[..1], 3), vec![0]);
+    }
+
+    #[test]
+    fn tiers_become_candidate_conditions_in_play_settings() {
+        let tags = [
+            CandidateTag {
+                id: 1,
+                tier: 0,
+                intensity: 0.0,
+            },
+            CandidateTag {
+                id: 2,
+                tier: 2,
+                intensity: 1.0,
+            },
+        ];
+        let conditions = intensity_conditions(&tags, 3, "intensity");
+        assert_eq!(conditions[0].when[0].min, None);
+        assert_eq!(conditions[0].when[0].max, Some(1.0 / 3.0));
+        assert_eq!(conditions[1].when[0].min, Some(2.0 / 3.0));
+        assert_eq!(conditions[1].when[0].max, None);
+
+        let mut settings = serde_json::json!({"play_settings": {"tracks": [{"ids": [1, 2]}]}});
+        assert!(write_candidate_conditions(&mut settings, &conditions));
+        assert_eq!(
+            settings["play_settings"]["tracks"][0]["candidate_when"][1],
+            serde_json::json!({"id": 2, "when": [{"variable": "intensity", "min": 2.0 / 3.0}]})
+        );
+    }
+
+    #[test]
+    fn container_tracks_are_all_extracted() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test_audio/demo_shuffle_points.prot"
+        );
+        let features = extract_features(path, FeatureOptions { max_seconds: 2.0 }).unwrap();
+        let ids: Vec<u32> = features.iter().map(|candidate| candidate.id).collect();
+        assert_eq!(ids, (1..=8).collect::<Vec<_>>());
+    }
+}
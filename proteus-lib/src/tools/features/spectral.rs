@@ -0,0 +1,165 @@
+//! Per-frame spectral statistics and MFCCs of a mono signal, averaged into
+//! [`SpectralFeatures`].
+
+use crate::tools::stem_signal::for_each_spectrum;
+
+use super::{SpectralFeatures, MFCC_COUNT};
+
+/// Frame and hop, in analysis samples, of the spectral analysis.
+const FRAME: usize = 2048;
+const HOP: usize = 1024;
+/// Mel filters folded into the cepstrum.
+const MEL_BANDS: usize = 26;
+/// Share of spectral energy below the rolloff frequency.
+const ROLLOFF_SHARE: f32 = 0.85;
+/// Floor, in dBFS, reported for silence.
+pub(super) const SILENCE_DBFS: f32 = -120.0;
+
+/// Features of a mono signal at `rate`.
+pub(super) fn spectral_features(samples: &[f32], rate: f64) -> SpectralFeatures {
+    let energy = samples.iter().map(|s| f64::from(*s).powi(2)).sum::<f64>();
+    let rms = (energy / samples.len().max(1) as f64).sqrt();
+    let rms_dbfs = if rms > 0.0 {
+        ((20.0 * rms.log10()) as f32).max(SILENCE_DBFS)
+    } else {
+        SILENCE_DBFS
+    };
+
+    let bin_hz = (rate / FRAME as f64) as f32;
+    let filters = mel_filters(FRAME / 2 + 1, rate);
+    let mut sums = [0.0_f32; 4];
+    let mut mfcc = [0.0_f32; MFCC_COUNT];
+    let mut frames = 0_usize;
+    let mut previous: Vec<f32> = Vec::new();
+    for_each_spectrum(samples, FRAME, HOP, |magnitudes| {
+        let total: f32 = magnitudes.iter().sum();
+        let power: Vec<f32> = magnitudes.iter().map(|m| m * m).collect();
+        let total_power: f32 = power.iter().sum();
+        if total <= f32::EPSILON || total_power <= f32::EPSILON {
+            previous.clear();
+            return;
+        }
+
+        let centroid = magnitudes
+            .iter()
+            .enumerate()
+            .map(|(bin, m)| bin as f32 * bin_hz * m)
+            .sum::<f32>()
+            / total;
+        let mut cumulative = 0.0;
+        let rolloff_bin = power
+            .iter()
+            .position(|p| {
+                cumulative += p;
+                cumulative >= ROLLOFF_SHARE * total_power
+            })
+            .unwrap_or(power.len() - 1);
+        let log_mean = power.iter().map(|p| (p + 1.0e-12).ln()).sum::<f32>() / power.len() as f32;
+        let flatness = log_mean.exp() / (total_power / power.len() as f32);
+        let normalized: Vec<f32> = magnitudes.iter().map(|m| m / total).collect();
+        let flux = if previous.is_empty() {
+            0.0
+        } else {
+            normalized
+                .iter()
+                .zip(&previous)
+                .map(|(now, before)| (now - before).max(0.0))
+                .sum()
+        };
+        previous = normalized;
+
+        let bands: Vec<f32> = filters
+            .iter()
+            .map(|filter| {
+                let band: f32 = filter
+                    .iter()
+                    .map(|(bin, weight)| power[*bin] * weight)
+                    .sum();
+                (band + 1.0e-10).ln()
+            })
+            .collect();
+        for (index, coefficient) in mfcc.iter_mut().enumerate() {
+            *coefficient += bands
+                .iter()
+                .enumerate()
+                .map(|(band, value)| {
+                    value
+                        * (std::f32::consts::PI * index as f32 * (band as f32 + 0.5)
+                            / MEL_BANDS as f32)
+                            .cos()
+                })
+                .sum::<f32>();
+        }
+        for (sum, value) in sums.iter_mut().zip([
+            centroid,
+            rolloff_bin as f32 * bin_hz,
+            flatness.clamp(0.0, 1.0),
+            flux,
+        ]) {
+            *sum += value;
+        }
+        frames += 1;
+    });
+
+    let frames_f = frames.max(1) as f32;
+    mfcc.iter_mut().for_each(|value| *value /= frames_f);
+    SpectralFeatures {
+        rms_dbfs,
+        centroid_hz: sums[0] / frames_f,
+        rolloff_hz: sums[1] / frames_f,
+        flatness: sums[2] / frames_f,
+        flux: sums[3] / frames_f,
+        mfcc,
+    }
+}
+
+/// Triangular mel filters as `(bin, weight)` lists over `bins` spectrum bins.
+fn mel_filters(bins: usize, rate: f64) -> Vec<Vec<(usize, f32)>> {
+    let to_mel = |hz: f64| 2595.0 * (1.0 + hz / 700.0).log10();
+    let to_hz = |mel: f64| 700.0 * (10_f64.powf(mel / 2595.0) - 1.0);
+    let (low, high) = (to_mel(20.0), to_mel(rate / 2.0));
+    let edges: Vec<f64> = (0..MEL_BANDS + 2)
+        .map(|index| to_hz(low + (high - low) * index as f64 / (MEL_BANDS + 1) as f64))
+        .collect();
+    let bin_hz = rate / ((bins - 1) * 2) as f64;
+    edges
+        .windows(3)
+        .map(|edge| {
+            (0..bins)
+                .filter_map(|bin| {
+                    let hz = bin as f64 * bin_hz;
+                    let weight = if hz <= edge[0] || hz >= edge[2] {
+                        0.0
+                    } else if hz <= edge[1] {
+                        (hz - edge[0]) / (edge[1] - edge[0])
+                    } else {
+                        (edge[2] - hz) / (edge[2] - edge[1])
+                    };
+                    (weight > 0.0).then_some((bin, weight as f32))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::{noise, sine, RATE};
+    use super::{spectral_features, SILENCE_DBFS};
+
+    #[test]
+    fn noise_is_brighter_and_flatter_than_a_tone() {
+        let tone = spectral_features(&sine(24_000, 220.0, 0.2), RATE);
+        let hiss = spectral_features(&noise(24_000, 0.8, 3), RATE);
+        assert!(hiss.rms_dbfs > tone.rms_dbfs);
+        assert!(hiss.centroid_hz > tone.centroid_hz * 4.0);
+        assert!(hiss.rolloff_hz > tone.rolloff_hz);
+        assert!(hiss.flatness > tone.flatness);
+        assert!(hiss.flux > tone.flux);
+        assert!((tone.rms_dbfs - (-17.0)).abs() < 0.5, "{}", tone.rms_dbfs);
+        assert_eq!(
+            spectral_features(&[0.0; 4_096], RATE).rms_dbfs,
+            SILENCE_DBFS
+        );
+    }
+}
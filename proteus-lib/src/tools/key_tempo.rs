@@ -0,0 +1,383 @@
+//! Tempo and key estimation for candidate stems.
+//!
+//! [`analyze_key_tempo`] decodes every track of a container, estimates its
+//! tempo from the autocorrelation of a spectral-flux onset envelope and its
+//! key by correlating an averaged chroma vector with the Krumhansl-Kessler
+//! key profiles. [`write_candidate_analysis`] stores the result as
+//! `candidate_analysis` in `play_settings.json`, where
+//! `selection_rules.avoid_key_clashes` can read it.
+//!
+//! Audio is downmixed to mono and decimated to roughly 12 kHz before
+//! analysis, which still resolves pitch up to the top of the chroma range.
+
+use std::fmt;
+
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::container::play_settings::{
+    write_candidate_entries, CandidateAnalysis, KeyMode, MusicalKey, PitchClass,
+};
+use crate::tools::decode::DecoderOpenError;
+use crate::tools::stem_signal::{
+    for_each_spectrum, read_stem_signals, StemReadError, ANALYSIS_RATE,
+};
+
+/// Frame and hop, in analysis samples, of the onset envelope.
+const ONSET_FRAME: usize = 1024;
+const ONSET_HOP: usize = 128;
+/// Frame and hop, in analysis samples, of the chroma analysis.
+const CHROMA_FRAME: usize = 8192;
+const CHROMA_HOP: usize = 4096;
+/// Frequency range, in hertz, folded into the chroma vector.
+const CHROMA_MIN_HZ: f64 = 55.0;
+const CHROMA_MAX_HZ: f64 = 4_000.0;
+/// Profile correlation below which a stem is reported as unpitched.
+const MIN_KEY_CORRELATION: f32 = 0.5;
+/// Normalized autocorrelation below which a stem has no clear pulse.
+const MIN_PULSE_CORRELATION: f32 = 0.1;
+
+/// Krumhansl-Kessler major key profile, starting at the tonic.
+const MAJOR_PROFILE: [f32; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+/// Krumhansl-Kessler minor key profile, starting at the tonic.
+const MINOR_PROFILE: [f32; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Analysis length and tempo range for [`analyze_key_tempo`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyTempoOptions {
+    /// Seconds analyzed from the start of each track.
+    pub max_seconds: f64,
+    /// Slowest tempo reported, in beats per minute.
+    pub min_bpm: f32,
+    /// Fastest tempo reported, in beats per minute.
+    pub max_bpm: f32,
+}
+
+impl Default for KeyTempoOptions {
+    fn default() -> Self {
+        Self {
+            max_seconds: 60.0,
+            min_bpm: 60.0,
+            max_bpm: 180.0,
+        }
+    }
+}
+
+/// Error returned when a file cannot be analyzed.
+#[derive(Debug)]
+pub enum KeyTempoError {
+    /// The file could not be opened or has no decodable audio track.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the file.
+    Decode(SymphoniaError),
+}
+
+impl fmt::Display for KeyTempoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for KeyTempoError {}
+
+impl From<StemReadError> for KeyTempoError {
+    fn from(err: StemReadError) -> Self {
+        match err {
+            StemReadError::Open(err) => Self::Open(err),
+            StemReadError::Decode(err) => Self::Decode(err),
+        }
+    }
+}
+
+/// Estimate tempo and key for every audio track of `file_path`.
+///
+/// Entry ids are container track ids. Tracks with neither a clear pulse nor
+/// a clear key get no entry.
+///
+/// # Errors
+///
+/// Returns [`KeyTempoError`] when the file cannot be opened or decoded.
+pub fn analyze_key_tempo(
+    file_path: &str,
+    options: KeyTempoOptions,
+) -> Result<Vec<CandidateAnalysis>, KeyTempoError> {
+    let analysis = read_stem_signals(file_path, None, options.max_seconds, ANALYSIS_RATE)?
+        .into_iter()
+        .filter_map(|signal| {
+            let tempo_bpm = estimate_tempo(&signal.samples, signal.rate, options);
+            let key = estimate_key(&signal.samples, signal.rate);
+            (tempo_bpm.is_some() || key.is_some()).then_some(CandidateAnalysis {
+                id: signal.id,
+                tempo_bpm,
+                key,
+            })
+        })
+        .collect();
+    Ok(analysis)
+}
+
+/// Store `analysis` as `candidate_analysis` on the tracks of a parsed
+/// `play_settings.json` document.
+///
+/// Each track receives the entries for the ids it lists, replacing any it
+/// had; tracks with none lose the key. Returns `false` when the document has
+/// no `tracks` array.
+pub fn write_candidate_analysis(
+    play_settings: &mut serde_json::Value,
+    analysis: &[CandidateAnalysis],
+) -> bool {
+    write_candidate_entries(play_settings, "candidate_analysis", analysis, |entry| {
+        entry.id
+    })
+}
+
+/// Tempo in beats per minute from the autocorrelation of the spectral-flux
+/// onset envelope, weighted towards 120 BPM to settle octave ambiguity.
+fn estimate_tempo(samples: &[f32], rate: f64, options: KeyTempoOptions) -> Option<f32> {
+    let mut envelope = Vec::new();
+    let mut previous: Vec<f32> = Vec::new();
+    for_each_spectrum(samples, ONSET_FRAME, ONSET_HOP, |magnitudes| {
+        let compressed: Vec<f32> = magnitudes
+            .iter()
+            .map(|magnitude| (1.0 + 1000.0 * magnitude).ln())
+            .collect();
+        let flux = compressed
+            .iter()
+            .zip(&previous)
+            .map(|(now, before)| (now - before).max(0.0))
+            .sum::<f32>();
+        if !previous.is_empty() {
+            envelope.push(flux);
+        }
+        previous = compressed;
+    });
+    let mean = envelope.iter().sum::<f32>() / envelope.len().max(1) as f32;
+    envelope.iter_mut().for_each(|value| *value -= mean);
+
+    let envelope_rate = rate / ONSET_HOP as f64;
+    let min_lag = (envelope_rate * 60.0 / f64::from(options.max_bpm)).floor() as usize;
+    let max_lag = (envelope_rate * 60.0 / f64::from(options.min_bpm)).ceil() as usize;
+    if min_lag < 2 || envelope.len() < max_lag * 4 {
+        return None;
+    }
+    let autocorrelation = |lag: usize| {
+        envelope
+            .iter()
+            .zip(&envelope[lag..])
+            .map(|(a, b)| a * b)
+            .sum::<f32>()
+            / (envelope.len() - lag) as f32
+    };
+    let energy = autocorrelation(0);
+    if energy <= f32::EPSILON {
+        return None;
+    }
+    let correlations: Vec<f32> = (min_lag - 1..=max_lag + 1).map(autocorrelation).collect();
+    let bpm_at = |lag: f64| (envelope_rate * 60.0 / lag) as f32;
+    let (index, _) = correlations
+        .iter()
+        .enumerate()
+        .skip(1)
+        .take(correlations.len() - 2)
+        .map(|(index, correlation)| {
+            let bpm = bpm_at((min_lag + index - 1) as f64);
+            let prior = (-0.5 * (bpm / 120.0).log2().powi(2)).exp();
+            (index, correlation * prior)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    if correlations[index] / energy < MIN_PULSE_CORRELATION {
+        return None;
+    }
+
+    let (before, peak, after) = (
+        correlations[index - 1],
+        correlations[index],
+        correlations[index + 1],
+    );
+    let curvature = before - 2.0 * peak + after;
+    let refinement = if curvature < 0.0 {
+        (0.5 * (before - after) / curvature).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    let lag = (min_lag + index - 1) as f64 + f64::from(refinement);
+    let bpm = bpm_at(lag).clamp(options.min_bpm, options.max_bpm);
+    Some((bpm * 10.0).round() / 10.0)
+}
+
+/// Key whose profile best correlates with the averaged chroma vector.
+fn estimate_key(samples: &[f32], rate: f64) -> Option<MusicalKey> {
+    let mut chroma = [0.0_f32; 12];
+    let bin_hz = rate / CHROMA_FRAME as f64;
+    let pitch_classes: Vec<Option<usize>> = (0..=CHROMA_FRAME / 2)
+        .map(|bin| {
+            let hz = bin as f64 * bin_hz;
+            (CHROMA_MIN_HZ..=CHROMA_MAX_HZ).contains(&hz).then(|| {
+                let midi = 69.0 + 12.0 * (hz / 440.0).log2();
+                (midi.round() as i64).rem_euclid(12) as usize
+            })
+        })
+        .collect();
+    for_each_spectrum(samples, CHROMA_FRAME, CHROMA_HOP, |magnitudes| {
+        for (magnitude, pitch_class) in magnitudes.iter().zip(&pitch_classes) {
+            if let Some(pitch_class) = pitch_class {
+                chroma[*pitch_class] += magnitude * magnitude;
+            }
+        }
+    });
+    if chroma.iter().sum::<f32>() <= f32::EPSILON {
+        return None;
+    }
+
+    let (key, correlation) = (0..12)
+        .flat_map(|tonic| {
+            [
+                (KeyMode::Major, &MAJOR_PROFILE),
+                (KeyMode::Minor, &MINOR_PROFILE),
+            ]
+            .map(|(mode, profile)| {
+                let rotated: Vec<f32> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+                let key = MusicalKey {
+                    tonic: PitchClass::from_semitone(tonic),
+                    mode,
+                };
+                (key, pearson(&chroma, &rotated))
+            })
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))?;
+    (correlation >= MIN_KEY_CORRELATION).then_some(key)
+}
+
+/// Pearson correlation of two equally long vectors.
+fn pearson(a: &[f32], b: &[f32]) -> f32 {
+    let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+    let norm = (variance_a * variance_b).sqrt();
+    if norm <= f32::EPSILON {
+        0.0
+    } else {
+        covariance / norm
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::playback::pcm_output::PcmFormat;
+    use crate::playback::render::RenderedPcm;
+
+    const RATE: u32 = 48_000;
+
+    fn write_stem(name: &str, samples: Vec<f32>) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "proteus-key-tempo-{}-{}.wav",
+            name,
+            std::process::id()
+        ));
+        RenderedPcm {
+            samples,
+            sample_rate: RATE,
+            channels: 1,
+        }
+        .write_wav(&path, PcmFormat::F32Le)
+        .unwrap();
+        path
+    }
+
+    fn analyze(path: &std::path::Path) -> Vec<CandidateAnalysis> {
+        let analysis = analyze_key_tempo(&path.to_string_lossy(), KeyTempoOptions::default());
+        let _ = std::fs::remove_file(path);
+        analysis.unwrap()
+    }
+
+    fn tone(frequencies: &[(f32, f32)], seconds: f32) -> Vec<f32> {
+        (0..(seconds * RATE as f32) as usize)
+            .map(|frame| {
+                let t = frame as f32 / RATE as f32;
+                frequencies
+                    .iter()
+                    .map(|(hz, level)| level * (std::f32::consts::TAU * hz * t).sin())
+                    .sum()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn clicks_at_a_steady_pulse_give_its_tempo() {
+        let beat = (RATE as f32 * 60.0 / 120.0) as usize;
+        let mut state = 1_u32;
+        let samples: Vec<f32> = (0..RATE as usize * 10)
+            .map(|frame| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                let noise = state as f32 / u32::MAX as f32 - 0.5;
+                let since_beat = (frame % beat) as f32 / RATE as f32;
+                noise * (-since_beat * 60.0).exp()
+            })
+            .collect();
+        let analysis = analyze(&write_stem("clicks", samples));
+        let tempo = analysis[0].tempo_bpm.unwrap();
+        assert!((tempo - 120.0).abs() < 2.0, "{tempo}");
+    }
+
+    #[test]
+    fn triads_give_their_key() {
+        let c_major = tone(&[(261.63, 0.3), (329.63, 0.2), (392.0, 0.2)], 4.0);
+        let analysis = analyze(&write_stem("c-major", c_major));
+        assert_eq!(
+            analysis[0].key,
+            Some(MusicalKey {
+                tonic: PitchClass::C,
+                mode: KeyMode::Major
+            })
+        );
+
+        let a_minor = tone(&[(220.0, 0.3), (261.63, 0.2), (329.63, 0.2)], 4.0);
+        let analysis = analyze(&write_stem("a-minor", a_minor));
+        assert_eq!(
+            analysis[0].key,
+            Some(MusicalKey {
+                tonic: PitchClass::A,
+                mode: KeyMode::Minor
+            })
+        );
+    }
+
+    #[test]
+    fn silence_gets_no_entry() {
+        assert!(analyze(&write_stem("silent", vec![0.0; RATE as usize * 2])).is_empty());
+    }
+
+    #[test]
+    fn analysis_is_written_to_the_tracks_listing_their_ids() {
+        let mut settings = serde_json::json!({"tracks": [{"ids": [1, 2]}, {"ids": [3]}]});
+        let analysis = [CandidateAnalysis {
+            id: 2,
+            tempo_bpm: Some(98.0),
+            key: None,
+        }];
+        assert!(write_candidate_analysis(&mut settings, &analysis));
+        assert_eq!(
+            settings["tracks"][0]["candidate_analysis"],
+            serde_json::json!([{"id": 2, "tempo_bpm": 98.0}])
+        );
+        assert!(settings["tracks"][1].get("candidate_analysis").is_none());
+        assert!(!write_candidate_analysis(
+            &mut serde_json::json!([]),
+            &analysis
+        ));
+    }
+}
@@ -0,0 +1,364 @@
+//! Library index of the containers found under a directory tree.
+//!
+//! [`LibraryIndex::rescan`] walks a directory for `.prot` and `.mka` files
+//! and records lightweight metadata for each: title, duration, logical track
+//! count, and the number of possible selections. Files whose size and
+//! modification time are unchanged since the last scan keep their entry, so
+//! rescanning a large library only opens what changed. Indexes round-trip
+//! through JSON with [`LibraryIndex::load`] and [`LibraryIndex::save`].
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+use symphonia::core::meta::StandardTagKey;
+
+use crate::container::info::get_probe_result_from_string;
+use crate::container::prot::Prot;
+
+/// File extensions picked up by a library scan (compared case-insensitively).
+pub const LIBRARY_EXTENSIONS: [&str; 2] = ["prot", "mka"];
+
+/// Errors returned while scanning or persisting a library index.
+#[derive(Debug)]
+pub enum LibraryError {
+    /// The directory tree or index file could not be read or written.
+    Io(io::Error),
+    /// The index file is not valid library JSON.
+    InvalidIndex(String),
+}
+
+impl Display for LibraryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "io error: {}", err),
+            Self::InvalidIndex(err) => write!(f, "invalid library index: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+impl From<io::Error> for LibraryError {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+/// Metadata recorded for one container in the library.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    /// Path of the container file.
+    pub path: String,
+    /// `TITLE` tag of the container, or the file name without extension.
+    pub title: String,
+    /// Longest track duration in seconds.
+    pub duration: f64,
+    /// Number of logical tracks mixed together during playback.
+    pub track_count: usize,
+    /// Number of possible unique selections, when it can be counted.
+    pub combination_count: Option<u128>,
+    /// File size in bytes when the entry was read.
+    pub size: u64,
+    /// File modification time in milliseconds since the Unix epoch when the
+    /// entry was read, or `0` when the platform does not report one.
+    pub modified_ms: u64,
+}
+
+impl LibraryEntry {
+    /// Read the library metadata of the container at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message describing why the file could not be read or loaded.
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let (size, modified_ms) = file_stamp(path).map_err(|err| err.to_string())?;
+        let file_path = path.to_string_lossy().into_owned();
+        let prot = Prot::try_new(&file_path).map_err(|err| err.to_string())?;
+        let duration = prot
+            .info
+            .duration_map
+            .values()
+            .copied()
+            .fold(0.0_f64, f64::max);
+        let title = read_title(&file_path).unwrap_or_else(|| {
+            path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file_path.clone())
+        });
+        Ok(Self {
+            title,
+            duration,
+            track_count: prot.get_length(),
+            combination_count: prot.count_possible_combinations(),
+            size,
+            modified_ms,
+            path: file_path,
+        })
+    }
+}
+
+/// What changed in a library during one rescan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LibraryScanSummary {
+    /// Files indexed for the first time.
+    pub added: Vec<String>,
+    /// Files re-read because their size or modification time changed.
+    pub updated: Vec<String>,
+    /// Entries dropped because their file no longer exists under the root.
+    pub removed: Vec<String>,
+    /// Number of entries kept without reopening their file.
+    pub unchanged: usize,
+    /// Files that matched the extension filter but could not be loaded,
+    /// with the reason. They are left out of the index.
+    pub failed: Vec<(String, String)>,
+}
+
+/// Index of the containers under one directory tree, keyed by path.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LibraryIndex {
+    /// Root directory the index was scanned from.
+    pub root: String,
+    /// Indexed containers keyed by path.
+    pub entries: BTreeMap<String, LibraryEntry>,
+}
+
+impl LibraryIndex {
+    /// Create an empty index for `root`.
+    pub fn new(root: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Scan `root` into a new index.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError::Io`] when the directory tree cannot be walked.
+    pub fn scan(root: impl Into<String>) -> Result<(Self, LibraryScanSummary), LibraryError> {
+        let mut index = Self::new(root);
+        let summary = index.rescan()?;
+        Ok((index, summary))
+    }
+
+    /// Bring the index up to date with the files under its root.
+    ///
+    /// Only new files and files whose size or modification time changed are
+    /// opened; entries for files that disappeared are removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError::Io`] when the directory tree cannot be walked.
+    /// Individual files that fail to load are reported in
+    /// [`LibraryScanSummary::failed`] instead.
+    pub fn rescan(&mut self) -> Result<LibraryScanSummary, LibraryError> {
+        let mut found = Vec::new();
+        collect_library_files(Path::new(&self.root), &mut found)?;
+        found.sort();
+
+        let mut summary = LibraryScanSummary::default();
+        let mut entries = BTreeMap::new();
+        for path in found {
+            let key = path.to_string_lossy().into_owned();
+            let previous = self.entries.remove(&key);
+            if let Some(previous) = &previous {
+                if file_stamp(&path).ok() == Some((previous.size, previous.modified_ms)) {
+                    entries.insert(key, previous.clone());
+                    summary.unchanged += 1;
+                    continue;
+                }
+            }
+            match LibraryEntry::read(&path) {
+                Ok(entry) => {
+                    entries.insert(key.clone(), entry);
+                    if previous.is_some() {
+                        summary.updated.push(key);
+                    } else {
+                        summary.added.push(key);
+                    }
+                }
+                Err(err) => summary.failed.push((key, err)),
+            }
+        }
+        summary.removed = self.entries.keys().cloned().collect();
+        self.entries = entries;
+        Ok(summary)
+    }
+
+    /// Serialize the index to pretty-printed JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError::InvalidIndex`] when serialization fails.
+    pub fn to_json(&self) -> Result<String, LibraryError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|err| LibraryError::InvalidIndex(err.to_string()))
+    }
+
+    /// Parse an index from JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError::InvalidIndex`] when `json` is not a library index.
+    pub fn from_json(json: &str) -> Result<Self, LibraryError> {
+        serde_json::from_str(json).map_err(|err| LibraryError::InvalidIndex(err.to_string()))
+    }
+
+    /// Load an index previously written with [`Self::save`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError`] when the file cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, LibraryError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    /// Write the index to `path` as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`LibraryError`] when serialization or the write fails.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LibraryError> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+/// Return `true` when `path` has one of [`LIBRARY_EXTENSIONS`].
+pub fn is_library_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            LIBRARY_EXTENSIONS
+                .iter()
+                .any(|candidate| ext.eq_ignore_ascii_case(candidate))
+        })
+}
+
+fn collect_library_files(dir: &Path, found: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_library_files(&path, found)?;
+        } else if is_library_file(&path) {
+            found.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn file_stamp(path: &Path) -> io::Result<(u64, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified_ms = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |elapsed| elapsed.as_millis() as u64);
+    Ok((metadata.len(), modified_ms))
+}
+
+fn read_title(file_path: &str) -> Option<String> {
+    let mut probed = get_probe_result_from_string(file_path).ok()?;
+    let revision = probed.format.metadata().current()?.clone();
+    revision
+        .tags()
+        .iter()
+        .find(|tag| {
+            tag.std_key == Some(StandardTagKey::TrackTitle) || tag.key.eq_ignore_ascii_case("TITLE")
+        })
+        .map(|tag| tag.value.to_string())
+        .filter(|title| !title.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_audio(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("../test_audio")
+            .join(name)
+    }
+
+    fn library_dir(label: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("proteus-library-{}-{}", label, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn library_files_are_matched_by_extension() {
+        assert!(is_library_file(Path::new("song.prot")));
+        assert!(is_library_file(Path::new("song.MKA")));
+        assert!(!is_library_file(Path::new("song.wav")));
+        assert!(!is_library_file(Path::new("prot")));
+    }
+
+    #[test]
+    fn scan_indexes_containers_and_rescans_incrementally() {
+        let dir = library_dir("rescan");
+        let first = dir.join("first.prot");
+        let second = dir.join("nested").join("second.mka");
+        fs::copy(test_audio("demo_shuffle_points.prot"), &first).unwrap();
+        fs::copy(test_audio("demo_shuffle_points.prot"), &second).unwrap();
+        fs::copy(test_audio("test-32bit.mp3"), dir.join("skipped.mp3")).unwrap();
+        fs::write(dir.join("broken.prot"), b"not a container").unwrap();
+
+        let (mut index, summary) = LibraryIndex::scan(dir.to_string_lossy()).unwrap();
+        assert_eq!(summary.added.len(), 2);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(index.entries.len(), 2);
+        let entry = &index.entries[&first.to_string_lossy().into_owned()];
+        assert_eq!(entry.title, "first");
+        assert!(entry.duration > 0.0);
+        assert!(entry.track_count > 0);
+        assert!(entry.combination_count.unwrap_or(0) > 0);
+
+        fs::remove_file(&second).unwrap();
+        let summary = index.rescan().unwrap();
+        assert_eq!(summary.unchanged, 1);
+        assert_eq!(summary.removed, vec![second.to_string_lossy().into_owned()]);
+        assert!(summary.added.is_empty() && summary.updated.is_empty());
+
+        let key = first.to_string_lossy().into_owned();
+        index.entries.get_mut(&key).unwrap().size += 1;
+        let summary = index.rescan().unwrap();
+        assert_eq!(summary.updated, vec![key]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn index_round_trips_through_json() {
+        let mut index = LibraryIndex::new("/music");
+        index.entries.insert(
+            "/music/song.prot".to_string(),
+            LibraryEntry {
+                path: "/music/song.prot".to_string(),
+                title: "Song".to_string(),
+                duration: 182.5,
+                track_count: 4,
+                combination_count: Some(u128::from(u64::MAX) * 3),
+                size: 1024,
+                modified_ms: 1_700_000_000_000,
+            },
+        );
+        let dir = library_dir("json");
+        let path = dir.join("library.json");
+        index.save(&path).unwrap();
+        assert_eq!(LibraryIndex::load(&path).unwrap(), index);
+        assert!(matches!(
+            LibraryIndex::from_json("[]"),
+            Err(LibraryError::InvalidIndex(_))
+        ));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,353 @@
+//! Inter-track frequency masking analysis for a selection of stems.
+//!
+//! Two stems mask each other when they put similar energy into the same
+//! band at the same time. [`analyze_masking`] measures band levels of every
+//! selected track every 50 ms and, for each pair of tracks and each band,
+//! reports how often both are active there ([`BandCollision::coactive`]) and
+//! how evenly matched they are while active ([`BandCollision::overlap`]).
+//! The worst collisions point at stems that should never shuffle together;
+//! [`MaskingReport::never_together`] lists them in the shape
+//! `selection_rules.never_together` expects.
+
+use std::fmt;
+
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::tools::decode::DecoderOpenError;
+use crate::tools::stem_signal::{for_each_spectrum, read_stem_signals, StemReadError};
+
+/// Rate signals are decimated towards; keeps the brilliance band.
+const MASKING_RATE: u32 = 24_000;
+/// Spacing of band-level measurements, in seconds.
+const STEP_SECONDS: f64 = 0.05;
+
+/// Frequency band used for masking analysis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MaskingBand {
+    /// 20–60 Hz.
+    Sub,
+    /// 60–250 Hz.
+    Bass,
+    /// 250–500 Hz.
+    LowMid,
+    /// 500 Hz–2 kHz.
+    Mid,
+    /// 2–4 kHz.
+    UpperMid,
+    /// 4–6 kHz.
+    Presence,
+    /// 6–12 kHz.
+    Brilliance,
+}
+
+impl MaskingBand {
+    /// All bands, lowest first.
+    pub const ALL: [MaskingBand; 7] = [
+        Self::Sub,
+        Self::Bass,
+        Self::LowMid,
+        Self::Mid,
+        Self::UpperMid,
+        Self::Presence,
+        Self::Brilliance,
+    ];
+
+    /// Lower and upper edge of the band, in hertz.
+    pub fn range_hz(self) -> (f64, f64) {
+        match self {
+            Self::Sub => (20.0, 60.0),
+            Self::Bass => (60.0, 250.0),
+            Self::LowMid => (250.0, 500.0),
+            Self::Mid => (500.0, 2_000.0),
+            Self::UpperMid => (2_000.0, 4_000.0),
+            Self::Presence => (4_000.0, 6_000.0),
+            Self::Brilliance => (6_000.0, 12_000.0),
+        }
+    }
+}
+
+impl fmt::Display for MaskingBand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Sub => "sub",
+            Self::Bass => "bass",
+            Self::LowMid => "low-mid",
+            Self::Mid => "mid",
+            Self::UpperMid => "upper-mid",
+            Self::Presence => "presence",
+            Self::Brilliance => "brilliance",
+        };
+        let (low, high) = self.range_hz();
+        write!(f, "{} ({}-{} Hz)", name, low, high)
+    }
+}
+
+/// Analysis length and activity threshold for [`analyze_masking`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaskingOptions {
+    /// Seconds analyzed from the start of each track.
+    pub max_seconds: f64,
+    /// Band level, in dBFS, at or above which a track counts as active in a
+    /// band.
+    pub activity_dbfs: f32,
+}
+
+impl Default for MaskingOptions {
+    fn default() -> Self {
+        Self {
+            max_seconds: 60.0,
+            activity_dbfs: -50.0,
+        }
+    }
+}
+
+/// Error returned when a selection cannot be analyzed.
+#[derive(Debug)]
+pub enum MaskingError {
+    /// The file could not be opened or has no decodable audio track.
+    Open(DecoderOpenError),
+    /// Decoding failed partway through the file.
+    Decode(SymphoniaError),
+}
+
+impl fmt::Display for MaskingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Open(err) => write!(f, "{}", err),
+            Self::Decode(err) => write!(f, "decode error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for MaskingError {}
+
+impl From<StemReadError> for MaskingError {
+    fn from(err: StemReadError) -> Self {
+        match err {
+            StemReadError::Open(err) => Self::Open(err),
+            StemReadError::Decode(err) => Self::Decode(err),
+        }
+    }
+}
+
+/// Masking between two tracks in one band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BandCollision {
+    /// Track ids of the pair, smaller first.
+    pub ids: [u32; 2],
+    /// Band the tracks collide in.
+    pub band: MaskingBand,
+    /// Share of the analyzed time both tracks are active in the band
+    /// (`0.0..=1.0`).
+    pub coactive: f32,
+    /// Energy of the quieter track over the louder one while both are active
+    /// (`0.0..=1.0`); `1.0` means evenly matched.
+    pub overlap: f32,
+    /// `coactive * overlap`; collisions are ranked by it.
+    pub severity: f32,
+}
+
+/// Collisions found by [`analyze_masking`], worst first.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MaskingReport {
+    /// Every pair and band where both tracks were ever active together.
+    pub collisions: Vec<BandCollision>,
+}
+
+impl MaskingReport {
+    /// The `limit` worst collisions.
+    pub fn worst(&self, limit: usize) -> &[BandCollision] {
+        &self.collisions[..limit.min(self.collisions.len())]
+    }
+
+    /// Pairs whose worst band reaches `min_severity`, worst first.
+    pub fn never_together(&self, min_severity: f32) -> Vec<[u32; 2]> {
+        let mut pairs: Vec<[u32; 2]> = Vec::new();
+        for collision in &self.collisions {
+            if collision.severity >= min_severity && !pairs.contains(&collision.ids) {
+                pairs.push(collision.ids);
+            }
+        }
+        pairs
+    }
+}
+
+/// Level of each [`MaskingBand`] of one track every [`STEP_SECONDS`].
+struct BandLevels {
+    id: u32,
+    /// Mean-square level per step, one array per step.
+    steps: Vec<[f32; MaskingBand::ALL.len()]>,
+}
+
+/// Measure masking between every pair of tracks in `selection`, decoding
+/// audio from the container at `file_path`.
+///
+/// # Errors
+///
+/// Returns [`MaskingError`] when the container cannot be opened or decoded,
+/// or none of the selected ids is an audio track.
+pub fn analyze_masking(
+    file_path: &str,
+    selection: &[u32],
+    options: MaskingOptions,
+) -> Result<MaskingReport, MaskingError> {
+    let levels: Vec<BandLevels> = read_stem_signals(
+        file_path,
+        Some(selection),
+        options.max_seconds,
+        MASKING_RATE,
+    )?
+    .into_iter()
+    .map(|signal| band_levels(signal.id, &signal.samples, signal.rate))
+    .collect();
+    Ok(MaskingReport {
+        collisions: collide(&levels, options),
+    })
+}
+
+/// Band levels of a mono signal at `rate`.
+fn band_levels(id: u32, samples: &[f32], rate: f64) -> BandLevels {
+    let hop = (rate * STEP_SECONDS).round().max(1.0) as usize;
+    let frame = (hop * 2).next_power_of_two();
+    let bin_hz = rate / frame as f64;
+    let band_of_bin: Vec<Option<usize>> = (0..=frame / 2)
+        .map(|bin| {
+            let hz = bin as f64 * bin_hz;
+            MaskingBand::ALL.iter().position(|band| {
+                let (low, high) = band.range_hz();
+                hz >= low && hz < high
+            })
+        })
+        .collect();
+    let mut steps = Vec::new();
+    for_each_spectrum(samples, frame, hop, |magnitudes| {
+        let mut bands = [0.0_f32; MaskingBand::ALL.len()];
+        for (magnitude, band) in magnitudes.iter().zip(&band_of_bin) {
+            if let Some(band) = band {
+                bands[*band] += magnitude * magnitude;
+            }
+        }
+        // One-sided Hann-windowed power to mean square of the band signal.
+        bands.iter_mut().for_each(|power| *power *= 16.0 / 3.0);
+        steps.push(bands);
+    });
+    BandLevels { id, steps }
+}
+
+/// Collisions between every pair of `levels`, worst first.
+fn collide(levels: &[BandLevels], options: MaskingOptions) -> Vec<BandCollision> {
+    // Full-scale sine has a mean square of 0.5, which is 0 dBFS.
+    let threshold = 0.5 * 10.0_f32.powf(options.activity_dbfs / 10.0);
+    let mut collisions = Vec::new();
+    for (position, a) in levels.iter().enumerate() {
+        for b in &levels[position + 1..] {
+            let steps = a.steps.len().min(b.steps.len());
+            if steps == 0 {
+                continue;
+            }
+            for (band_index, band) in MaskingBand::ALL.iter().enumerate() {
+                let (mut coactive, mut quieter, mut louder) = (0_usize, 0.0_f64, 0.0_f64);
+                for (step_a, step_b) in a.steps.iter().zip(&b.steps) {
+                    let (level_a, level_b) = (step_a[band_index], step_b[band_index]);
+                    if level_a >= threshold && level_b >= threshold {
+                        coactive += 1;
+                        quieter += f64::from(level_a.min(level_b));
+                        louder += f64::from(level_a.max(level_b));
+                    }
+                }
+                if coactive == 0 {
+                    continue;
+                }
+                let coactive = coactive as f32 / steps as f32;
+                let overlap = (quieter / louder) as f32;
+                collisions.push(BandCollision {
+                    ids: [a.id.min(b.id), a.id.max(b.id)],
+                    band: *band,
+                    coactive,
+                    overlap,
+                    severity: coactive * overlap,
+                });
+            }
+        }
+    }
+    collisions.sort_by(|a, b| b.severity.total_cmp(&a.severity));
+    collisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RATE: f64 = 24_000.0;
+
+    fn sine(hz: f64, level: f32) -> Vec<f32> {
+        (0..RATE as usize)
+            .map(|i| level * (std::f64::consts::TAU * hz * i as f64 / RATE).sin() as f32)
+            .collect()
+    }
+
+    #[test]
+    fn band_levels_read_in_dbfs() {
+        let levels = band_levels(1, &sine(1_000.0, 1.0), RATE);
+        let mid = MaskingBand::ALL
+            .iter()
+            .position(|band| *band == MaskingBand::Mid)
+            .unwrap();
+        let dbfs = 10.0 * (levels.steps[3][mid] / 0.5).log10();
+        assert!(dbfs.abs() < 0.5, "{dbfs}");
+    }
+
+    #[test]
+    fn matched_stems_in_one_band_collide_hardest() {
+        let levels = [
+            band_levels(1, &sine(100.0, 0.5), RATE),
+            band_levels(2, &sine(120.0, 0.5), RATE),
+            band_levels(3, &sine(150.0, 0.05), RATE),
+            band_levels(4, &sine(3_000.0, 0.5), RATE),
+        ];
+        let report = MaskingReport {
+            collisions: collide(&levels, MaskingOptions::default()),
+        };
+        let worst = report.worst(1)[0];
+        assert_eq!(worst.ids, [1, 2]);
+        assert_eq!(worst.band, MaskingBand::Bass);
+        assert!(worst.overlap > 0.9 && worst.coactive > 0.9);
+        let quiet = report
+            .collisions
+            .iter()
+            .find(|collision| collision.ids == [1, 3])
+            .unwrap();
+        assert!(quiet.overlap < 0.05);
+        assert!(report
+            .collisions
+            .iter()
+            .all(|collision| collision.ids[1] != 4 || collision.band != MaskingBand::UpperMid));
+        assert_eq!(report.never_together(0.5), vec![[1, 2]]);
+        assert!(report.worst(100).len() == report.collisions.len());
+    }
+
+    #[test]
+    fn selected_container_tracks_are_compared() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../test_audio/demo_shuffle_points.prot"
+        );
+        let options = MaskingOptions {
+            max_seconds: 2.0,
+            ..MaskingOptions::default()
+        };
+        let report = analyze_masking(path, &[1, 5], options).unwrap();
+        assert!(report
+            .collisions
+            .iter()
+            .all(|collision| collision.ids == [1, 5]));
+        assert!(report
+            .collisions
+            .windows(2)
+            .all(|pair| pair[0].severity >= pair[1].severity));
+        assert!(matches!(
+            analyze_masking(path, &[99], options),
+            Err(MaskingError::Open(DecoderOpenError::NoSupportedAudioTrack))
+        ));
+    }
+}
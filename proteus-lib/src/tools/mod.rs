@@ -1,4 +1,13 @@
 //! Small utilities used throughout the library.
 
+pub mod align;
 pub mod decode;
+pub mod features;
+pub mod key_tempo;
+pub mod library;
+pub mod masking;
+pub mod progress;
+pub mod progressive;
+pub mod stdin;
+mod stem_signal;
 pub mod timer;
@@ -0,0 +1,173 @@
+//! Cancellation and progress reporting for long-running operations.
+//!
+//! Duration scans, peak extraction, and offline renders can take minutes on
+//! long containers. Their `*_with_progress` variants take a [`Progress`]
+//! that reports the completed fraction to a callback and stops the work
+//! early once its [`CancellationToken`] is cancelled from another thread.
+
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// Smallest fraction change forwarded to a progress callback.
+const REPORT_STEP: f64 = 0.001;
+
+/// Shared flag a host sets to abort a running operation.
+///
+/// Clones share the same flag. Cancellation is cooperative: operations
+/// check the flag between packets or chunks.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Create a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request cancellation of every operation holding this token.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Return `true` once [`Self::cancel`] has been called.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    /// The underlying flag, for APIs that take an abort `AtomicBool`.
+    pub(crate) fn flag(&self) -> &AtomicBool {
+        &self.cancelled
+    }
+}
+
+/// Error returned when an operation stops because it was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+/// Cancellation token plus an optional completion callback.
+///
+/// The callback receives the completed fraction in `[0.0, 1.0]`. Reports
+/// are monotonic and throttled to steps of 0.1%; `1.0` is always reported
+/// when an operation completes.
+#[derive(Default)]
+pub struct Progress<'a> {
+    cancel: CancellationToken,
+    on_progress: Option<Box<dyn FnMut(f64) + 'a>>,
+    reported: Option<f64>,
+}
+
+impl<'a> Progress<'a> {
+    /// Create a progress handle with no callback and a fresh token.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `token` for cancellation.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Call `callback` with the completed fraction as work advances.
+    pub fn with_callback(mut self, callback: impl FnMut(f64) + 'a) -> Self {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// The token checked by this operation.
+    pub fn cancellation(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Return `true` once the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    /// Return [`Cancelled`] once the token has been cancelled.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`Cancelled`] when cancellation was requested.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report `fraction` complete; non-finite values are ignored.
+    pub fn report(&mut self, fraction: f64) {
+        if !fraction.is_finite() {
+            return;
+        }
+        let fraction = fraction.clamp(0.0, 1.0);
+        let due = match self.reported {
+            None => true,
+            Some(last) => (fraction >= 1.0 && last < 1.0) || fraction - last >= REPORT_STEP,
+        };
+        if !due {
+            return;
+        }
+        self.reported = Some(fraction);
+        if let Some(callback) = self.on_progress.as_mut() {
+            callback(fraction);
+        }
+    }
+
+    /// Report `done` of `total` complete; ignored when `total` is zero.
+    pub fn report_ratio(&mut self, done: u64, total: u64) {
+        if total > 0 {
+            self.report(done as f64 / total as f64);
+        }
+    }
+}
+
+impl fmt::Debug for Progress<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress")
+            .field("cancel", &self.cancel)
+            .field("reported", &self.reported)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_are_monotonic_throttled_and_end_at_one() {
+        let mut seen = Vec::new();
+        {
+            let mut progress = Progress::new().with_callback(|fraction| seen.push(fraction));
+            for step in [0.0, 0.0004, 0.2, 0.1, 0.2005, 0.9995, 1.0, 1.0, f64::NAN] {
+                progress.report(step);
+            }
+        }
+        assert_eq!(seen, vec![0.0, 0.2, 0.9995, 1.0]);
+    }
+
+    #[test]
+    fn cancelling_a_clone_cancels_the_progress() {
+        let token = CancellationToken::new();
+        let progress = Progress::new().with_cancellation(token.clone());
+        assert_eq!(progress.check(), Ok(()));
+        token.cancel();
+        assert!(progress.is_cancelled());
+        assert_eq!(progress.check(), Err(Cancelled));
+        assert!(progress.cancellation().flag().load(Ordering::Acquire));
+    }
+}
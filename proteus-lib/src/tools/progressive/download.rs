@@ -0,0 +1,263 @@
+//! Writer side of a progressive download.
+
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+
+use super::scan::ClusterScan;
+use super::{BufferingState, Download, DownloadProgress, ProgressiveFile, DEFAULT_STALL_TIMEOUT};
+
+/// Writer for a container that plays while it downloads.
+///
+/// Feed bytes in file order with [`append`](Self::append); the download is
+/// complete once `total_len` bytes arrived. Dropping the writer earlier
+/// fails the download, which unblocks every reader. Readers go through the
+/// [`ProgressiveFile`] returned by [`file`](Self::file).
+///
+/// # Example
+/// ```no_run
+/// use std::time::Duration;
+/// use proteus_lib::playback::player::{Player, PlayerInitOptions};
+/// use proteus_lib::tools::progressive::ProgressiveDownload;
+///
+/// # fn fetch(_: u64) -> Vec<u8> { Vec::new() }
+/// let mut download = ProgressiveDownload::create("cache/album.prot", 48_000_000, 5.0)?;
+/// let file = download.file();
+/// let states = download.subscribe();
+/// std::thread::spawn(move || {
+///     while download.available() < download.total_len() {
+///         let chunk = fetch(download.available());
+///         download.append(&chunk).unwrap();
+///     }
+/// });
+/// let _player = Player::play_progressive(
+///     &file,
+///     PlayerInitOptions::default(),
+///     Duration::from_secs(30),
+/// )?;
+/// for state in states {
+///     println!("{state:?}");
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct ProgressiveDownload {
+    download: Arc<Download>,
+    file: File,
+    scan: ClusterScan,
+}
+
+impl ProgressiveDownload {
+    /// Create (or truncate) the file at `path` for a download of
+    /// `total_len` bytes.
+    ///
+    /// `prebuffer_seconds` of audio must arrive before the download is
+    /// [`BufferingState::Ready`], and again after every stall.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error when the file cannot be created.
+    pub fn create(path: &str, total_len: u64, prebuffer_seconds: f64) -> io::Result<Self> {
+        let file = File::create(path)?;
+        let scan = ClusterScan::new(path)?;
+        let download = Arc::new(Download {
+            path: path.to_string(),
+            total_len,
+            prebuffer_seconds: prebuffer_seconds.max(0.0),
+            progress: Mutex::new(DownloadProgress {
+                available: 0,
+                buffered_seconds: 0.0,
+                state: BufferingState::Buffering,
+                stalled_at: 0.0,
+                subscribers: Vec::new(),
+            }),
+            arrived: Condvar::new(),
+        });
+        let mut this = Self {
+            download,
+            file,
+            scan,
+        };
+        if total_len == 0 {
+            this.finish();
+        }
+        Ok(this)
+    }
+
+    /// Handle for reading the file while it downloads.
+    pub fn file(&self) -> ProgressiveFile {
+        ProgressiveFile {
+            download: self.download.clone(),
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
+        }
+    }
+
+    /// Write the next `bytes` of the file and wake waiting readers.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`io::ErrorKind::InvalidInput`] when `bytes` would run past
+    /// `total_len` or the download already ended, and any error writing the
+    /// file, which fails the download.
+    pub fn append(&mut self, bytes: &[u8]) -> io::Result<()> {
+        let available = self.available();
+        if self.state().is_final() || available + bytes.len() as u64 > self.total_len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "bytes run past the end of the download",
+            ));
+        }
+        if let Err(err) = self.file.write_all(bytes).and_then(|()| self.file.flush()) {
+            self.download.end(BufferingState::Failed);
+            return Err(err);
+        }
+        let available = available + bytes.len() as u64;
+        let complete = available == self.total_len();
+        self.scan.advance(available, self.total_len());
+        {
+            let mut progress = self.download.lock_progress();
+            progress.available = available;
+            progress.buffered_seconds = self.scan.buffered_seconds;
+            let prebuffered = self.scan.reached_clusters
+                && match progress.state {
+                    BufferingState::Buffering => {
+                        self.scan.buffered_seconds >= self.download.prebuffer_seconds
+                    }
+                    BufferingState::Stalled => {
+                        self.scan.buffered_seconds - progress.stalled_at
+                            >= self.download.prebuffer_seconds
+                    }
+                    _ => false,
+                };
+            if prebuffered {
+                progress.set_state(BufferingState::Ready);
+            }
+        }
+        self.download.arrived.notify_all();
+        if complete {
+            self.finish();
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        let mut progress = self.download.lock_progress();
+        progress.buffered_seconds = progress.buffered_seconds.max(self.scan.buffered_seconds);
+        if progress.state == BufferingState::Buffering {
+            // Short files may never buffer `prebuffer_seconds`.
+            progress.set_state(BufferingState::Ready);
+        }
+        drop(progress);
+        self.download.end(BufferingState::Complete);
+    }
+
+    /// Path the download writes to.
+    pub fn path(&self) -> &str {
+        &self.download.path
+    }
+
+    /// Length of the whole file, in bytes.
+    pub fn total_len(&self) -> u64 {
+        self.download.total_len
+    }
+
+    /// Bytes written so far.
+    pub fn available(&self) -> u64 {
+        self.download.lock_progress().available
+    }
+
+    /// Seconds of audio whose clusters have fully arrived.
+    pub fn buffered_seconds(&self) -> f64 {
+        self.download.lock_progress().buffered_seconds
+    }
+
+    /// Current buffering state.
+    pub fn state(&self) -> BufferingState {
+        self.download.lock_progress().state
+    }
+
+    /// Receive every buffering state change, starting with the current
+    /// state.
+    pub fn subscribe(&self) -> mpsc::Receiver<BufferingState> {
+        let (sender, receiver) = mpsc::channel();
+        let mut progress = self.download.lock_progress();
+        if sender.send(progress.state).is_ok() {
+            progress.subscribers.push(sender);
+        }
+        receiver
+    }
+}
+
+impl Drop for ProgressiveDownload {
+    fn drop(&mut self) {
+        if !self.state().is_final() {
+            self.download.end(BufferingState::Failed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::test_fixtures::{demo_container_bytes, temp_container_path};
+
+    #[test]
+    fn downloads_become_ready_after_the_header_and_prebuffered_clusters() {
+        let bytes = demo_container_bytes();
+        let path = temp_container_path("progressive", "ready");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 2.0).unwrap();
+        let file = download.file();
+        let states = download.subscribe();
+
+        let mut ready_at = None;
+        for (index, chunk) in bytes.chunks(16 * 1024).enumerate() {
+            download.append(chunk).unwrap();
+            if ready_at.is_none() && download.state() == BufferingState::Ready {
+                ready_at = Some(index);
+                assert!(download.buffered_seconds() >= 2.0);
+                assert!(download.available() < bytes.len() as u64);
+                assert!(file.is_downloading());
+            }
+        }
+        assert!(ready_at.is_some());
+        assert!(!file.is_downloading());
+        assert_eq!(
+            states.try_iter().collect::<Vec<_>>(),
+            [
+                BufferingState::Buffering,
+                BufferingState::Ready,
+                BufferingState::Complete
+            ]
+        );
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn dropped_downloads_fail_and_end_readers_early() {
+        let bytes = demo_container_bytes();
+        let path = temp_container_path("progressive", "dropped");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        let file = download.file();
+        download.append(&bytes[..1000]).unwrap();
+        assert_eq!(
+            file.wait_until_ready(Duration::from_millis(10)),
+            BufferingState::Buffering
+        );
+        let states = download.subscribe();
+        let mut source = file.open().unwrap();
+        drop(download);
+        assert_eq!(
+            states.try_iter().collect::<Vec<_>>(),
+            [BufferingState::Buffering, BufferingState::Failed]
+        );
+
+        let mut all = Vec::new();
+        source.read_to_end(&mut all).unwrap();
+        assert_eq!(all, bytes[..1000]);
+        assert!(!file.is_downloading());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,234 @@
+//! Containers that play while they download.
+//!
+//! A [`ProgressiveDownload`] writes a container to disk as its bytes arrive.
+//! Its [`ProgressiveFile`] handle is what the library reads the container
+//! through: pass it to
+//! [`Player::play_progressive`](crate::playback::player::Player::play_progressive)
+//! or [`Prot::try_new_progressive`](crate::container::prot::Prot::try_new_progressive),
+//! and every reader opened for that container while the download runs
+//! (probing, attachments, decode workers) is a [`ProgressiveSource`]. Its
+//! reads block until the bytes they need have arrived instead of hitting the
+//! end of a partial file, and it reports itself as non-seekable, so the
+//! Matroska demuxer reads forward from the header instead of jumping to cues
+//! at the end of the file. Once the download ends the file is read as an
+//! ordinary file.
+//!
+//! The download scans the EBML structure as it grows. It is
+//! [`BufferingState::Ready`] once every element ahead of the first cluster
+//! has arrived (the header, tracks, and the attachments carrying
+//! `play_settings.json`) along with the clusters of the first
+//! `prebuffer_seconds` of audio. Clusters interleave every track, so the
+//! opening of each selected track is buffered whichever tracks the shuffle
+//! picks. Muxers that append attachments after the clusters leave them
+//! unread until the download completes.
+
+mod download;
+mod scan;
+mod source;
+
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::sync::{mpsc, Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use symphonia::core::io::MediaSource;
+
+use crate::playback::mutex_policy::{lock_recoverable, wait_timeout_recoverable};
+
+pub use download::ProgressiveDownload;
+pub use source::ProgressiveSource;
+
+/// How long a reader waits for the next bytes before giving up; see
+/// [`ProgressiveFile::with_stall_timeout`].
+pub const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Buffering state of a [`ProgressiveDownload`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferingState {
+    /// The header, attachments, or initial clusters are still arriving.
+    Buffering,
+    /// Enough has arrived to play; later bytes keep arriving in the
+    /// background.
+    Ready,
+    /// A reader caught up with the download and waits for bytes. The
+    /// download is `Ready` again once another `prebuffer_seconds` arrived.
+    Stalled,
+    /// Every byte has arrived; the file is now an ordinary file.
+    Complete,
+    /// The download ended early; readers see the end of the file where the
+    /// bytes stop.
+    Failed,
+}
+
+impl BufferingState {
+    fn is_final(self) -> bool {
+        matches!(self, Self::Complete | Self::Failed)
+    }
+}
+
+/// State shared by a download and the handles reading it.
+struct Download {
+    path: String,
+    total_len: u64,
+    prebuffer_seconds: f64,
+    progress: Mutex<DownloadProgress>,
+    arrived: Condvar,
+}
+
+struct DownloadProgress {
+    available: u64,
+    buffered_seconds: f64,
+    state: BufferingState,
+    /// Buffered seconds when the download stalled.
+    stalled_at: f64,
+    subscribers: Vec<mpsc::Sender<BufferingState>>,
+}
+
+impl DownloadProgress {
+    fn set_state(&mut self, state: BufferingState) {
+        if self.state == state {
+            return;
+        }
+        self.state = state;
+        self.subscribers
+            .retain(|subscriber| subscriber.send(state).is_ok());
+    }
+}
+
+impl Download {
+    // Recoverable: progress only moves forward, and every field is written
+    // in one step.
+    fn lock_progress(&self) -> MutexGuard<'_, DownloadProgress> {
+        lock_recoverable(
+            &self.progress,
+            "progressive download progress",
+            "progress fields are each written in a single step",
+        )
+    }
+
+    fn wait_for_progress<'a>(
+        &self,
+        progress: MutexGuard<'a, DownloadProgress>,
+        timeout: Duration,
+    ) -> MutexGuard<'a, DownloadProgress> {
+        wait_timeout_recoverable(
+            &self.arrived,
+            progress,
+            timeout,
+            "progressive download progress",
+            "progress fields are each written in a single step",
+        )
+        .0
+    }
+
+    /// Move to a final state and wake every reader.
+    fn end(&self, state: BufferingState) {
+        self.lock_progress().set_state(state);
+        self.arrived.notify_all();
+    }
+}
+
+/// Read side of a [`ProgressiveDownload`].
+///
+/// Handles are cheap to clone and stay valid after the download ends, when
+/// the readers they open read the file like any other.
+#[derive(Clone)]
+pub struct ProgressiveFile {
+    download: Arc<Download>,
+    stall_timeout: Duration,
+}
+
+impl ProgressiveFile {
+    /// Path the download writes to.
+    pub fn path(&self) -> &str {
+        &self.download.path
+    }
+
+    /// Current buffering state.
+    pub fn state(&self) -> BufferingState {
+        self.download.lock_progress().state
+    }
+
+    /// Let readers opened through this handle wait at most `timeout` for
+    /// the next bytes, instead of [`DEFAULT_STALL_TIMEOUT`].
+    ///
+    /// A reader that times out fails with [`io::ErrorKind::TimedOut`]; the
+    /// download itself keeps running.
+    pub fn with_stall_timeout(mut self, timeout: Duration) -> Self {
+        self.stall_timeout = timeout;
+        self
+    }
+
+    /// Wait until the download can play, at most `timeout`.
+    ///
+    /// Returns the state reached: `Ready` or `Stalled` once playable,
+    /// `Complete` or `Failed` when the download ended, or `Buffering` on
+    /// timeout.
+    pub fn wait_until_ready(&self, timeout: Duration) -> BufferingState {
+        let deadline = Instant::now() + timeout;
+        let mut progress = self.download.lock_progress();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if progress.state != BufferingState::Buffering || remaining.is_zero() {
+                return progress.state;
+            }
+            progress = self.download.wait_for_progress(progress, remaining);
+        }
+    }
+
+    /// Open a reader that waits for bytes still to arrive.
+    ///
+    /// # Errors
+    ///
+    /// Returns an I/O error when the file cannot be opened.
+    pub fn open(&self) -> io::Result<ProgressiveSource> {
+        Ok(ProgressiveSource::new(
+            self.download.clone(),
+            File::open(self.path())?,
+            self.stall_timeout,
+        ))
+    }
+
+    /// Return `true` while bytes are still arriving.
+    pub(crate) fn is_downloading(&self) -> bool {
+        !self.state().is_final()
+    }
+
+    /// Open the file for a media reader: through a [`ProgressiveSource`]
+    /// while the download runs, as a plain (seekable) file once it ended.
+    pub(crate) fn open_media(&self) -> io::Result<Box<dyn MediaSource>> {
+        if self.is_downloading() {
+            Ok(Box::new(self.open()?))
+        } else {
+            Ok(Box::new(File::open(self.path())?))
+        }
+    }
+}
+
+impl PartialEq for ProgressiveFile {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.download, &other.download) && self.stall_timeout == other.stall_timeout
+    }
+}
+
+impl fmt::Debug for ProgressiveFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProgressiveFile")
+            .field("path", &self.path())
+            .field("state", &self.state())
+            .field("stall_timeout", &self.stall_timeout)
+            .finish()
+    }
+}
+
+/// Open `path` for a media reader, through `download` when one is given.
+pub(crate) fn open_media(
+    path: &str,
+    download: Option<&ProgressiveFile>,
+) -> io::Result<Box<dyn MediaSource>> {
+    match download {
+        Some(download) => download.open_media(),
+        None => Ok(Box::new(File::open(path)?)),
+    }
+}
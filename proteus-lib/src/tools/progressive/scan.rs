@@ -0,0 +1,161 @@
+//! Incremental EBML walk that measures how much audio has arrived.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+use crate::container::attachments::{
+    read_element_header, AttachmentError, ID_CLUSTER, ID_EBML_HEADER, ID_SEGMENT,
+};
+
+const ID_INFO: u32 = 0x1549_A966;
+const ID_TIMECODE_SCALE: u32 = 0x2A_D7B1;
+const ID_CLUSTER_TIMECODE: u32 = 0xE7;
+const ID_CRC32: u32 = 0xBF;
+const ID_VOID: u32 = 0xEC;
+/// Matroska's default timecode scale: one millisecond, in nanoseconds.
+const DEFAULT_TIMECODE_SCALE: u64 = 1_000_000;
+
+/// Incremental walk over the top-level elements that have arrived.
+pub(super) struct ClusterScan {
+    file: BufReader<File>,
+    /// Next element to inspect.
+    next: u64,
+    /// End of the segment, once its header arrived.
+    segment_end: Option<u64>,
+    timecode_scale: u64,
+    /// Every element ahead of the first cluster has arrived.
+    pub(super) reached_clusters: bool,
+    /// Start time of the newest cluster whose timecode arrived; every
+    /// earlier cluster is complete.
+    pub(super) buffered_seconds: f64,
+    /// The structure cannot be followed further (malformed, or a cluster of
+    /// unknown size); only completion makes the download ready.
+    stopped: bool,
+}
+
+impl ClusterScan {
+    pub(super) fn new(path: &str) -> io::Result<Self> {
+        Ok(Self {
+            file: BufReader::new(File::open(path)?),
+            next: 0,
+            segment_end: None,
+            timecode_scale: DEFAULT_TIMECODE_SCALE,
+            reached_clusters: false,
+            buffered_seconds: 0.0,
+            stopped: false,
+        })
+    }
+
+    /// Follow every element whose header has arrived.
+    pub(super) fn advance(&mut self, available: u64, total_len: u64) {
+        while !self.stopped {
+            match self.step(available, total_len) {
+                Ok(true) => {}
+                Ok(false) => return,
+                // Truncation only means the next bytes have not arrived.
+                Err(AttachmentError::Io(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    return
+                }
+                Err(err) => {
+                    log::warn!("progressive download is not followable: {}", err);
+                    self.stopped = true;
+                }
+            }
+        }
+    }
+
+    /// Inspect the element at `next`; `Ok(false)` when it has not fully
+    /// arrived.
+    fn step(&mut self, available: u64, total_len: u64) -> Result<bool, AttachmentError> {
+        let parent_end = self.segment_end.unwrap_or(total_len);
+        if self.next >= parent_end {
+            self.stopped = true;
+            return Ok(false);
+        }
+        self.file.seek(SeekFrom::Start(self.next))?;
+        let element = read_element_header(&mut self.file, self.next, parent_end)?;
+        if self.segment_end.is_none() {
+            match element.id {
+                ID_SEGMENT => {
+                    self.segment_end = Some(element.end.unwrap_or(total_len));
+                    self.next = element.data_start;
+                    return Ok(true);
+                }
+                ID_EBML_HEADER => {}
+                _ => {
+                    return Err(AttachmentError::Malformed(
+                        "expected a Matroska segment".to_string(),
+                    ))
+                }
+            }
+        }
+        if element.id == ID_CLUSTER {
+            self.reached_clusters = true;
+            let timecode = self.read_cluster_timecode(element.data_start, element.end)?;
+            self.buffered_seconds = self
+                .buffered_seconds
+                .max(timecode as f64 * self.timecode_scale as f64 / 1e9);
+        }
+        let Some(end) = element.end else {
+            self.stopped = true;
+            return Ok(false);
+        };
+        if end > available {
+            return Ok(false);
+        }
+        if element.id == ID_INFO {
+            self.read_timecode_scale(element.data_start, end)?;
+        }
+        self.next = end;
+        Ok(true)
+    }
+
+    /// Read the timecode of the cluster whose data starts at `start`; it
+    /// precedes the blocks, after at most a CRC or void element.
+    fn read_cluster_timecode(
+        &mut self,
+        start: u64,
+        end: Option<u64>,
+    ) -> Result<u64, AttachmentError> {
+        let end = end.unwrap_or(u64::MAX);
+        let mut position = start;
+        while position < end {
+            self.file.seek(SeekFrom::Start(position))?;
+            let child = read_element_header(&mut self.file, position, end)?;
+            match (child.id, child.end) {
+                (ID_CLUSTER_TIMECODE, _) => return self.read_uint(child.data_start, child.end),
+                (ID_CRC32 | ID_VOID, Some(child_end)) => position = child_end,
+                _ => break,
+            }
+        }
+        Err(AttachmentError::Malformed(
+            "cluster does not start with its timecode".to_string(),
+        ))
+    }
+
+    fn read_timecode_scale(&mut self, start: u64, end: u64) -> Result<(), AttachmentError> {
+        let mut position = start;
+        while position < end {
+            self.file.seek(SeekFrom::Start(position))?;
+            let child = read_element_header(&mut self.file, position, end)?;
+            if child.id == ID_TIMECODE_SCALE {
+                self.timecode_scale = self.read_uint(child.data_start, child.end)?.max(1);
+            }
+            position = child.end.unwrap_or(end);
+        }
+        Ok(())
+    }
+
+    fn read_uint(&mut self, start: u64, end: Option<u64>) -> Result<u64, AttachmentError> {
+        let len = end.map_or(0, |end| end - start);
+        if len > 8 {
+            return Err(AttachmentError::Malformed(
+                "unsigned integer longer than 8 bytes".to_string(),
+            ));
+        }
+        let mut bytes = [0_u8; 8];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut bytes[8 - len as usize..])?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+}
@@ -0,0 +1,177 @@
+//! Blocking reader over a file that is still downloading.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use symphonia::core::io::MediaSource;
+
+use super::{BufferingState, Download};
+
+/// Reader over a file that is still downloading.
+///
+/// Reads block until the requested bytes arrive, marking the download
+/// [`BufferingState::Stalled`] while they wait, and see the end of the file
+/// where a failed download stops. A read that sees no new bytes for the
+/// handle's stall timeout fails with [`io::ErrorKind::TimedOut`]. Seeks only
+/// move the read position, so seeking ahead blocks the next read instead.
+pub struct ProgressiveSource {
+    download: Arc<Download>,
+    file: File,
+    position: u64,
+    stall_timeout: Duration,
+}
+
+impl ProgressiveSource {
+    pub(super) fn new(download: Arc<Download>, file: File, stall_timeout: Duration) -> Self {
+        Self {
+            download,
+            file,
+            position: 0,
+            stall_timeout,
+        }
+    }
+}
+
+impl Read for ProgressiveSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let download = &self.download;
+        let mut progress = download.lock_progress();
+        let mut arrived = progress.available;
+        let mut deadline = Instant::now() + self.stall_timeout;
+        while progress.available <= self.position
+            && progress.available < download.total_len
+            && !progress.state.is_final()
+        {
+            if progress.state == BufferingState::Ready {
+                progress.stalled_at = progress.buffered_seconds;
+                progress.set_state(BufferingState::Stalled);
+            }
+            // Bytes still arriving short of the position is not a stall.
+            if progress.available != arrived {
+                arrived = progress.available;
+                deadline = Instant::now() + self.stall_timeout;
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!(
+                        "no bytes of {} arrived for {:?}",
+                        download.path, self.stall_timeout
+                    ),
+                ));
+            }
+            progress = download.wait_for_progress(progress, remaining);
+        }
+        let readable = progress.available.saturating_sub(self.position);
+        drop(progress);
+        if readable == 0 {
+            return Ok(0);
+        }
+        let len = buf
+            .len()
+            .min(usize::try_from(readable).unwrap_or(usize::MAX));
+        self.file.seek(SeekFrom::Start(self.position))?;
+        let read = self.file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Seek for ProgressiveSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(delta) => self.download.total_len.checked_add_signed(delta),
+            SeekFrom::Current(delta) => self.position.checked_add_signed(delta),
+        };
+        self.position = target.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "seek before start of file")
+        })?;
+        Ok(self.position)
+    }
+}
+
+impl MediaSource for ProgressiveSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        Some(self.download.total_len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+    use crate::test_fixtures::{demo_container_bytes, temp_container_path};
+    use crate::tools::progressive::ProgressiveDownload;
+
+    #[test]
+    fn readers_wait_for_bytes_and_stall_until_refilled() {
+        let bytes = demo_container_bytes();
+        let path = temp_container_path("progressive", "reader");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        let states = download.subscribe();
+        let split = bytes.len() / 2;
+        download.append(&bytes[..split]).unwrap();
+        assert_eq!(download.state(), BufferingState::Ready);
+
+        let mut source = download.file().open().unwrap();
+        assert!(!source.is_seekable());
+        assert_eq!(source.byte_len(), Some(bytes.len() as u64));
+        let reader = thread::spawn(move || {
+            let mut all = Vec::new();
+            source.read_to_end(&mut all).unwrap();
+            all
+        });
+        while download.state() != BufferingState::Stalled {
+            thread::sleep(Duration::from_millis(5));
+        }
+        download.append(&bytes[split..]).unwrap();
+        assert_eq!(reader.join().unwrap(), bytes);
+        assert_eq!(
+            states.try_iter().collect::<Vec<_>>(),
+            [
+                BufferingState::Buffering,
+                BufferingState::Ready,
+                BufferingState::Stalled,
+                BufferingState::Ready,
+                BufferingState::Complete
+            ]
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn reads_time_out_when_the_download_stalls() {
+        let bytes = demo_container_bytes();
+        let path = temp_container_path("progressive", "stall");
+        let mut download = ProgressiveDownload::create(&path, bytes.len() as u64, 1.0).unwrap();
+        download.append(&bytes[..1000]).unwrap();
+        let mut source = download
+            .file()
+            .with_stall_timeout(Duration::from_millis(20))
+            .open()
+            .unwrap();
+
+        let mut head = [0_u8; 1000];
+        source.read_exact(&mut head).unwrap();
+        let err = source.read(&mut [0_u8; 16]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(download.state(), BufferingState::Buffering);
+
+        download.append(&bytes[1000..2000]).unwrap();
+        assert_eq!(source.read(&mut [0_u8; 16]).unwrap(), 16);
+        drop(download);
+        std::fs::remove_file(path).unwrap();
+    }
+}
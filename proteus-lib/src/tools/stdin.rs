@@ -0,0 +1,272 @@
+//! Standard input as a non-seekable media source.
+//!
+//! A container path of [`STDIN_PATH`] reads audio from standard input.
+//! Probing, metadata, and decoding each open the input from its start, so the
+//! bytes read so far are kept in a process-wide buffer and every
+//! [`StdinSource`] replays them before reading further. Probing and metadata
+//! only read the head of the input before decoding starts, so once the input
+//! runs past [`REPLAY_WINDOW_BYTES`] the oldest bytes are dropped and a
+//! reader that falls behind them gets an error. The input is never seekable
+//! and its length is unknown.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+
+use symphonia::core::io::MediaSource;
+
+use crate::playback::mutex_policy::{lock_recoverable, wait_recoverable};
+
+/// Container path that selects standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Bytes of standard input kept for readers that start later or lag behind.
+///
+/// The buffer holds up to twice this much before trimming back to it, so the
+/// trim runs once per window instead of once per read.
+pub const REPLAY_WINDOW_BYTES: usize = 16 * 1024 * 1024;
+
+/// Bytes requested from the underlying reader per refill.
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Return `true` when `file_path` selects standard input.
+pub fn is_stdin_path(file_path: &str) -> bool {
+    file_path == STDIN_PATH
+}
+
+/// Recent bytes of an input stream, shared by every reader of it.
+struct ReplayBuffer {
+    state: Mutex<ReplayState>,
+    /// Signalled when a refill finishes, successfully or not.
+    refilled: Condvar,
+    window: usize,
+}
+
+struct ReplayState {
+    /// The underlying reader; `None` while a reader refills outside the lock.
+    source: Option<Box<dyn Read + Send>>,
+    /// Input offset of `bytes[0]`.
+    start: usize,
+    bytes: Vec<u8>,
+    eof: bool,
+}
+
+impl ReplayBuffer {
+    fn new(source: Box<dyn Read + Send>, window: usize) -> Self {
+        Self {
+            state: Mutex::new(ReplayState {
+                source: Some(source),
+                start: 0,
+                bytes: Vec::new(),
+                eof: false,
+            }),
+            refilled: Condvar::new(),
+            window,
+        }
+    }
+
+    /// Copy bytes from input offset `position` into `buf`, reading more from
+    /// the source when `position` is past the buffered bytes.
+    ///
+    /// Only one reader refills at a time, and it reads without holding the
+    /// lock; the others wait for it and then copy from the buffer.
+    fn read_at(&self, position: usize, buf: &mut [u8]) -> io::Result<usize> {
+        let mut state = self.lock_state();
+        loop {
+            if position < state.start {
+                return Err(io::Error::other(format!(
+                    "standard input before byte {} is no longer buffered",
+                    state.start
+                )));
+            }
+            let offset = position - state.start;
+            if let Some(available) = state.bytes.get(offset..).filter(|b| !b.is_empty()) {
+                let read = available.len().min(buf.len());
+                buf[..read].copy_from_slice(&available[..read]);
+                return Ok(read);
+            }
+            if state.eof {
+                return Ok(0);
+            }
+            let Some(mut source) = state.source.take() else {
+                state = wait_recoverable(
+                    &self.refilled,
+                    state,
+                    "stdin replay buffer",
+                    "bytes are appended only after a whole read",
+                );
+                continue;
+            };
+            drop(state);
+            let refill = read_chunk(source.as_mut());
+            state = self.lock_state();
+            state.source = Some(source);
+            self.refilled.notify_all();
+            match refill {
+                Ok(chunk) if chunk.is_empty() => state.eof = true,
+                Ok(chunk) => state.append(&chunk, self.window),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Recoverable poison policy: bytes are appended only after a whole read
+    /// and trimmed in one step, so the buffer is never half-updated.
+    fn lock_state(&self) -> MutexGuard<'_, ReplayState> {
+        lock_recoverable(
+            &self.state,
+            "stdin replay buffer",
+            "bytes are appended only after a whole read",
+        )
+    }
+}
+
+impl ReplayState {
+    fn append(&mut self, chunk: &[u8], window: usize) {
+        self.bytes.extend_from_slice(chunk);
+        if self.bytes.len() > window.saturating_mul(2) {
+            let dropped = self.bytes.len() - window;
+            self.bytes.drain(..dropped);
+            self.start += dropped;
+        }
+    }
+}
+
+/// Read one chunk from `source`, retrying interrupted reads; an empty chunk
+/// means the input ended.
+fn read_chunk(source: &mut dyn Read) -> io::Result<Vec<u8>> {
+    let mut chunk = vec![0_u8; READ_CHUNK_BYTES];
+    loop {
+        match source.read(&mut chunk) {
+            Ok(read) => {
+                chunk.truncate(read);
+                return Ok(chunk);
+            }
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Reader over standard input that starts from the first byte.
+///
+/// Reads block until standard input supplies data or closes. Seeking fails
+/// with [`io::ErrorKind::Unsupported`], and reading fails once the reader is
+/// more than [`REPLAY_WINDOW_BYTES`] behind the furthest read.
+pub struct StdinSource {
+    buffer: Arc<ReplayBuffer>,
+    position: usize,
+}
+
+impl StdinSource {
+    /// Open standard input from its first byte.
+    pub fn open() -> Self {
+        static STDIN: OnceLock<Arc<ReplayBuffer>> = OnceLock::new();
+        let buffer = STDIN.get_or_init(|| {
+            Arc::new(ReplayBuffer::new(
+                Box::new(io::stdin()),
+                REPLAY_WINDOW_BYTES,
+            ))
+        });
+        Self {
+            buffer: buffer.clone(),
+            position: 0,
+        }
+    }
+}
+
+impl Read for StdinSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let read = self.buffer.read_at(self.position, buf)?;
+        self.position += read;
+        Ok(read)
+    }
+}
+
+impl Seek for StdinSource {
+    fn seek(&mut self, _pos: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "standard input is not seekable",
+        ))
+    }
+}
+
+impl MediaSource for StdinSource {
+    fn is_seekable(&self) -> bool {
+        false
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_over(bytes: Vec<u8>) -> (StdinSource, StdinSource) {
+        sources_with_window(bytes, REPLAY_WINDOW_BYTES)
+    }
+
+    fn sources_with_window(bytes: Vec<u8>, window: usize) -> (StdinSource, StdinSource) {
+        let buffer = Arc::new(ReplayBuffer::new(Box::new(io::Cursor::new(bytes)), window));
+        let open = || StdinSource {
+            buffer: buffer.clone(),
+            position: 0,
+        };
+        (open(), open())
+    }
+
+    #[test]
+    fn every_reader_replays_the_input_from_the_start() {
+        let input: Vec<u8> = (0..200_000_u32).map(|i| i as u8).collect();
+        let (mut first, mut second) = source_over(input.clone());
+
+        let mut head = [0_u8; 16];
+        first.read_exact(&mut head).unwrap();
+        assert_eq!(head[..], input[..16]);
+
+        let mut all = Vec::new();
+        second.read_to_end(&mut all).unwrap();
+        assert_eq!(all, input);
+
+        let mut rest = Vec::new();
+        first.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest[..], input[16..]);
+    }
+
+    #[test]
+    fn bytes_past_the_replay_window_are_dropped() {
+        let input: Vec<u8> = (0..300_000_u32).map(|i| i as u8).collect();
+        let (mut leader, mut straggler) = sources_with_window(input.clone(), 64 * 1024);
+
+        let mut all = Vec::new();
+        leader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, input);
+        assert!(leader.buffer.lock_state().bytes.len() <= 128 * 1024);
+
+        let mut head = [0_u8; 16];
+        let err = straggler.read(&mut head).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn stdin_source_is_not_seekable() {
+        let (mut source, _) = source_over(b"RIFF".to_vec());
+        assert!(!source.is_seekable());
+        assert_eq!(source.byte_len(), None);
+        let err = source.seek(SeekFrom::Start(0)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    }
+
+    #[test]
+    fn stdin_path_is_a_single_dash() {
+        assert!(is_stdin_path("-"));
+        assert!(!is_stdin_path("./-"));
+        assert!(!is_stdin_path("song.wav"));
+    }
+}
@@ -0,0 +1,192 @@
+//! Decimated mono signals of container tracks for offline stem analysis.
+
+use std::collections::HashMap;
+
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+
+use crate::audio::decode::for_each_channel_sample;
+use crate::dsp::fft::{plan_real, Complex};
+use crate::dsp::precision::{narrow, widen, DspFloat};
+use crate::tools::decode::{get_reader, DecoderOpenError};
+
+/// Rate signals are usually decimated towards; enough for pitch and
+/// rhythm analysis.
+pub(crate) const ANALYSIS_RATE: u32 = 12_000;
+
+/// Failure while reading stem signals; analysis errors wrap it.
+#[derive(Debug)]
+pub(crate) enum StemReadError {
+    Open(DecoderOpenError),
+    Decode(SymphoniaError),
+}
+
+/// Mono signal of one container track at roughly the requested rate.
+pub(crate) struct StemSignal {
+    /// Container track id.
+    pub id: u32,
+    /// Rate of `samples`, in hertz.
+    pub rate: f64,
+    /// Downmixed, decimated samples.
+    pub samples: Vec<f32>,
+}
+
+/// Decoder and accumulated signal of one track.
+struct StemReader {
+    decoder: Box<dyn Decoder>,
+    decimation: usize,
+    rate: f64,
+    limit: usize,
+    accumulator: f32,
+    accumulated: usize,
+    samples: Vec<f32>,
+}
+
+impl StemReader {
+    fn is_full(&self) -> bool {
+        self.samples.len() >= self.limit
+    }
+
+    fn push(&mut self, mono: &[f32]) {
+        for sample in mono {
+            if self.is_full() {
+                return;
+            }
+            self.accumulator += sample;
+            self.accumulated += 1;
+            if self.accumulated == self.decimation {
+                self.samples.push(self.accumulator / self.decimation as f32);
+                self.accumulator = 0.0;
+                self.accumulated = 0;
+            }
+        }
+    }
+}
+
+/// Decode the first `max_seconds` of the audio tracks of `file_path` in a
+/// single pass, sorted by track id.
+///
+/// Only tracks listed in `only` are read when it is set. Samples are averaged
+/// across channels and over runs of the integer factor that brings the
+/// track's rate closest to (but not below) `target_rate`, which keeps memory
+/// bounded while staying accurate well below the new Nyquist frequency.
+pub(crate) fn read_stem_signals(
+    file_path: &str,
+    only: Option<&[u32]>,
+    max_seconds: f64,
+    target_rate: u32,
+) -> Result<Vec<StemSignal>, StemReadError> {
+    let mut format = get_reader(file_path).map_err(StemReadError::Open)?;
+    let mut readers: HashMap<u32, StemReader> = HashMap::new();
+    for track in format.tracks() {
+        if track.codec_params.codec == CODEC_TYPE_NULL
+            || only.is_some_and(|ids| !ids.contains(&track.id))
+        {
+            continue;
+        }
+        let decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|err| StemReadError::Open(DecoderOpenError::UnsupportedCodec(err)))?;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(48_000);
+        let decimation = (sample_rate / target_rate.max(1)).max(1) as usize;
+        let rate = f64::from(sample_rate) / decimation as f64;
+        readers.insert(
+            track.id,
+            StemReader {
+                decoder,
+                decimation,
+                rate,
+                limit: (max_seconds.max(0.0) * rate) as usize,
+                accumulator: 0.0,
+                accumulated: 0,
+                samples: Vec::new(),
+            },
+        );
+    }
+    if readers.is_empty() {
+        return Err(StemReadError::Open(DecoderOpenError::NoSupportedAudioTrack));
+    }
+
+    let mut mono: Vec<f32> = Vec::new();
+    while !readers.values().all(StemReader::is_full) {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(err))
+                if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(err) => return Err(StemReadError::Decode(err)),
+        };
+        let Some(reader) = readers.get_mut(&packet.track_id()) else {
+            continue;
+        };
+        if reader.is_full() {
+            continue;
+        }
+        let decoded = reader
+            .decoder
+            .decode(&packet)
+            .map_err(StemReadError::Decode)?;
+        let channels = decoded.spec().channels.count();
+        mono.clear();
+        mono.resize(decoded.frames(), 0.0);
+        for channel in 0..channels {
+            let mut frame = 0;
+            for_each_channel_sample(&decoded, channel, |sample| {
+                if let Some(sum) = mono.get_mut(frame) {
+                    *sum += sample / channels as f32;
+                }
+                frame += 1;
+            });
+        }
+        reader.push(&mono);
+    }
+
+    let mut signals: Vec<StemSignal> = readers
+        .into_iter()
+        .map(|(id, reader)| StemSignal {
+            id,
+            rate: reader.rate,
+            samples: reader.samples,
+        })
+        .collect();
+    signals.sort_by_key(|signal| signal.id);
+    Ok(signals)
+}
+
+/// Hann-windowed magnitude spectra of `frame`-sample frames every `hop`
+/// samples, passed to `visit` in order.
+pub(crate) fn for_each_spectrum(
+    samples: &[f32],
+    frame: usize,
+    hop: usize,
+    mut visit: impl FnMut(&[f32]),
+) {
+    if samples.len() < frame {
+        return;
+    }
+    let fft = plan_real(frame);
+    let window: Vec<f32> = (0..frame)
+        .map(|i| 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / frame as f32).cos())
+        .collect();
+    let mut input: Vec<DspFloat> = vec![0.0; frame];
+    let mut bins = vec![Complex::new(0.0, 0.0); fft.spectrum_len()];
+    let mut magnitudes = vec![0.0_f32; fft.spectrum_len()];
+    for start in (0..=samples.len() - frame).step_by(hop) {
+        for ((slot, sample), weight) in input
+            .iter_mut()
+            .zip(&samples[start..start + frame])
+            .zip(&window)
+        {
+            *slot = widen(sample * weight);
+        }
+        if fft.forward(&mut input, &mut bins).is_err() {
+            return;
+        }
+        for (magnitude, bin) in magnitudes.iter_mut().zip(&bins) {
+            *magnitude = narrow(bin.norm()) / frame as f32;
+        }
+        visit(&magnitudes);
+    }
+}
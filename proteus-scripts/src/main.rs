@@ -1,9 +1,12 @@
 use std::env;
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use hound::{SampleFormat, WavSpec, WavWriter};
 use proteus_lib::dsp::effects::convolution_reverb::impulse_response::{
+    ImpulseResponse, downmix_impulse_response, load_impulse_response_from_file_raw,
     load_impulse_response_from_file_with_tail, normalize_impulse_response_channels,
+    resample_impulse_response, trim_impulse_response, validate_impulse_response,
 };
 
 fn main() {
@@ -15,6 +18,9 @@ fn main() {
 
     match cmd.as_str() {
         "normalize" => normalize_cmd(args.collect()),
+        "ir-info" => ir_info_cmd(args.collect()),
+        "ir-convert" => ir_convert_cmd(args.collect()),
+        "ir-trim" => ir_trim_cmd(args.collect()),
         "-h" | "--help" => print_help(),
         _ => {
             eprintln!("Unknown command: {}", cmd);
@@ -109,7 +115,7 @@ fn normalize_cmd(args: Vec<String>) {
     let mut channels = impulse_response.channels;
     normalize_impulse_response_channels(&mut channels, tail_db, true);
 
-    if let Err(err) = write_wav(&out_path, impulse_response.sample_rate, &channels) {
+    if let Err(err) = write_wav(&out_path, impulse_response.sample_rate, &channels, 32) {
         eprintln!("Failed to write {}: {}", out_path.display(), err);
         return;
     }
@@ -117,9 +123,204 @@ fn normalize_cmd(args: Vec<String>) {
     println!("Wrote {}", out_path.display());
 }
 
+fn ir_info_cmd(args: Vec<String>) {
+    let Some(in_path) = args.iter().find(|arg| !arg.starts_with('-')) else {
+        print_ir_info_help();
+        return;
+    };
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print_ir_info_help();
+        return;
+    }
+    let Some(ir) = load_raw(&PathBuf::from(in_path)) else {
+        return;
+    };
+
+    let info = ir.inspect();
+    let peak = ir
+        .channels
+        .iter()
+        .flatten()
+        .fold(0.0_f32, |acc, sample| acc.max(sample.abs()));
+    println!("{}", in_path);
+    println!("  sample rate:  {} Hz", info.sample_rate);
+    println!("  channels:     {}", info.channels);
+    println!("  length:       {:.3} s", info.length_seconds);
+    println!(
+        "  peak:         {:.2} dBFS",
+        20.0 * peak.max(1.0e-9).log10()
+    );
+    match info.rt60_seconds {
+        Some(rt60) => println!("  RT60:         {:.2} s", rt60),
+        None => println!("  RT60:         n/a"),
+    }
+    match info.direct_to_reverberant_db {
+        Some(drr) => println!("  DRR:          {:.1} dB", drr),
+        None => println!("  DRR:          n/a"),
+    }
+
+    let issues = validate_impulse_response(&ir);
+    if issues.is_empty() {
+        println!("  no issues found");
+    }
+    for issue in issues {
+        println!("  warning: {}", issue);
+    }
+}
+
+fn ir_convert_cmd(args: Vec<String>) {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut sample_rate: Option<u32> = None;
+    let mut channels: Option<usize> = None;
+    let mut bits: u16 = 32;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let parsed = match arg.as_str() {
+            "--rate" => parse_value(&mut iter, &arg).map(|value| sample_rate = Some(value)),
+            "--channels" => parse_value(&mut iter, &arg).map(|value| channels = Some(value)),
+            "--bits" => parse_value(&mut iter, &arg).map(|value| bits = value),
+            "-h" | "--help" => {
+                print_ir_convert_help();
+                return;
+            }
+            value if !value.starts_with("--") => {
+                paths.push(PathBuf::from(value));
+                Ok(())
+            }
+            _ => Err(format!("Unknown ir-convert arg: {}", arg)),
+        };
+        if let Err(err) = parsed {
+            eprintln!("{}", err);
+            print_ir_convert_help();
+            return;
+        }
+    }
+    if !matches!(bits, 16 | 24 | 32) {
+        eprintln!("--bits must be 16, 24 or 32");
+        return;
+    }
+    let [in_path, out_path] = paths.as_slice() else {
+        eprintln!("Expected an input and an output path");
+        print_ir_convert_help();
+        return;
+    };
+    let Some(mut ir) = load_raw(in_path) else {
+        return;
+    };
+
+    if let Some(channels) = channels {
+        ir = downmix_impulse_response(&ir, channels);
+    }
+    if let Some(sample_rate) = sample_rate {
+        ir = resample_impulse_response(&ir, sample_rate);
+    }
+    write_ir(out_path, &ir, bits);
+}
+
+fn ir_trim_cmd(args: Vec<String>) {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut start_ms: f32 = 0.0;
+    let mut length_ms: Option<f32> = None;
+    let mut tail_db: Option<f32> = None;
+    let mut bits: u16 = 32;
+
+    let mut iter = args.into_iter();
+    while let Some(arg) = iter.next() {
+        let parsed = match arg.as_str() {
+            "--start-ms" => parse_value(&mut iter, &arg).map(|value| start_ms = value),
+            "--length-ms" => parse_value(&mut iter, &arg).map(|value| length_ms = Some(value)),
+            "--tail-db" => parse_value(&mut iter, &arg).map(|value| tail_db = Some(value)),
+            "--bits" => parse_value(&mut iter, &arg).map(|value| bits = value),
+            "-h" | "--help" => {
+                print_ir_trim_help();
+                return;
+            }
+            value if !value.starts_with("--") => {
+                paths.push(PathBuf::from(value));
+                Ok(())
+            }
+            _ => Err(format!("Unknown ir-trim arg: {}", arg)),
+        };
+        if let Err(err) = parsed {
+            eprintln!("{}", err);
+            print_ir_trim_help();
+            return;
+        }
+    }
+    if !matches!(bits, 16 | 24 | 32) {
+        eprintln!("--bits must be 16, 24 or 32");
+        return;
+    }
+    let [in_path, out_path] = paths.as_slice() else {
+        eprintln!("Expected an input and an output path");
+        print_ir_trim_help();
+        return;
+    };
+    let Some(ir) = load_raw(in_path) else {
+        return;
+    };
+
+    let trimmed = trim_impulse_response(
+        &ir,
+        start_ms / 1000.0,
+        length_ms.map(|ms| ms / 1000.0),
+        tail_db,
+    );
+    write_ir(out_path, &trimmed, bits);
+}
+
+fn parse_value<T: FromStr>(
+    iter: &mut impl Iterator<Item = String>,
+    flag: &str,
+) -> Result<T, String> {
+    let value = iter
+        .next()
+        .ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("Invalid {} value: {}", flag, value))
+}
+
+fn load_raw(path: &PathBuf) -> Option<ImpulseResponse> {
+    match load_impulse_response_from_file_raw(path) {
+        Ok(ir) => Some(ir),
+        Err(err) => {
+            eprintln!("Failed to load impulse response: {}", err);
+            None
+        }
+    }
+}
+
+fn write_ir(path: &PathBuf, ir: &ImpulseResponse, bits: u16) {
+    if let Err(err) = write_wav(path, ir.sample_rate, &ir.channels, bits) {
+        eprintln!("Failed to write {}: {}", path.display(), err);
+        return;
+    }
+    println!("Wrote {}", path.display());
+}
+
 fn print_help() {
     println!(
-        "proteus-scripts\n\nCommands:\n  normalize    Normalize an impulse response audio file\n\nRun 'proteus-scripts normalize --help' for options."
+        "proteus-scripts\n\nCommands:\n  normalize    Normalize an impulse response audio file\n  ir-info      Show impulse response characteristics and problems\n  ir-convert   Resample, downmix or change the bit depth of an impulse response\n  ir-trim      Trim the start, length or tail of an impulse response\n\nRun 'proteus-scripts <command> --help' for options."
+    );
+}
+
+fn print_ir_info_help() {
+    println!(
+        "Usage: proteus-scripts ir-info <input>\n\nPrints sample rate, channels, length, peak, RT60 and direct-to-reverberant\nratio, and warns about clipping, DC offset, empty channels, leading silence\nand very long responses."
+    );
+}
+
+fn print_ir_convert_help() {
+    println!(
+        "Usage: proteus-scripts ir-convert <input> <output> [options]\n\nOptions:\n  --rate <hz>        Resample to this sample rate\n  --channels <n>     Downmix (or repeat) to this many channels\n  --bits <16|24|32>  Output bit depth; 32 writes float (default 32)\n  -h, --help         Show this help"
+    );
+}
+
+fn print_ir_trim_help() {
+    println!(
+        "Usage: proteus-scripts ir-trim <input> <output> [options]\n\nOptions:\n  --start-ms <ms>    Remove this much from the start\n  --length-ms <ms>   Keep at most this much after the start\n  --tail-db <db>     Cut the tail below this level relative to the peak\n  --bits <16|24|32>  Output bit depth; 32 writes float (default 32)\n  -h, --help         Show this help"
     );
 }
 
@@ -129,15 +330,28 @@ fn print_normalize_help() {
     );
 }
 
-fn write_wav(path: &PathBuf, sample_rate: u32, channels: &[Vec<f32>]) -> Result<(), String> {
+/// Write `channels` as a wav file; `bits` of 32 writes float samples, 16 or
+/// 24 writes integer samples.
+fn write_wav(
+    path: &PathBuf,
+    sample_rate: u32,
+    channels: &[Vec<f32>],
+    bits: u16,
+) -> Result<(), String> {
     let channel_count = channels.len().max(1) as u16;
     let max_len = channels.iter().map(|ch| ch.len()).max().unwrap_or(0);
+    let sample_format = if bits == 32 {
+        SampleFormat::Float
+    } else {
+        SampleFormat::Int
+    };
     let spec = WavSpec {
         channels: channel_count,
         sample_rate,
-        bits_per_sample: 32,
-        sample_format: SampleFormat::Float,
+        bits_per_sample: bits,
+        sample_format,
     };
+    let int_scale = ((1_i64 << (bits - 1)) - 1) as f32;
 
     let mut writer = WavWriter::create(path, spec)
         .map_err(|err| format!("failed to create wav writer: {}", err))?;
@@ -149,9 +363,12 @@ fn write_wav(path: &PathBuf, sample_rate: u32, channels: &[Vec<f32>]) -> Result<
                 .and_then(|data| data.get(frame))
                 .copied()
                 .unwrap_or(0.0);
-            writer
-                .write_sample(sample)
-                .map_err(|err| format!("failed to write sample: {}", err))?;
+            let written = if sample_format == SampleFormat::Float {
+                writer.write_sample(sample)
+            } else {
+                writer.write_sample((sample.clamp(-1.0, 1.0) * int_scale).round() as i32)
+            };
+            written.map_err(|err| format!("failed to write sample: {}", err))?;
         }
     }
 
@@ -179,10 +396,32 @@ mod tests {
         let path: PathBuf = std::env::temp_dir().join(unique);
         let channels = vec![vec![0.1_f32, -0.1, 0.2], vec![0.0_f32, 0.0, 0.0]];
 
-        write_wav(&path, 44_100, &channels).expect("write_wav should succeed");
+        write_wav(&path, 44_100, &channels, 32).expect("write_wav should succeed");
         let metadata = std::fs::metadata(&path).expect("output file should exist");
         assert!(metadata.len() > 0);
 
         let _ = std::fs::remove_file(path);
     }
+
+    #[test]
+    fn write_wav_writes_integer_samples_at_lower_bit_depths() {
+        let unique = format!(
+            "proteus-scripts-test-int-{}.wav",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        let path: PathBuf = std::env::temp_dir().join(unique);
+        let channels = vec![vec![0.5_f32, -1.5]];
+
+        write_wav(&path, 48_000, &channels, 16).expect("write_wav should succeed");
+        let mut reader = hound::WavReader::open(&path).expect("output should be a wav file");
+        assert_eq!(reader.spec().bits_per_sample, 16);
+        assert_eq!(reader.spec().sample_format, hound::SampleFormat::Int);
+        let samples: Vec<i32> = reader.samples::<i32>().map(Result::unwrap).collect();
+        assert_eq!(samples, vec![16_384, -32_767]);
+
+        let _ = std::fs::remove_file(path);
+    }
 }